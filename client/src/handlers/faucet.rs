@@ -0,0 +1,53 @@
+use romer_common::types::address::Address;
+use serde_json::{json, Value};
+
+use crate::handlers::keymanager::read_line;
+use crate::handlers::Handler;
+use crate::rpc::RpcClient;
+
+// Requests test RØMER from the sequencer's faucet. Only development and
+// testnet sequencers run one; others refuse the request.
+pub struct FaucetHandler {
+    rpc: RpcClient,
+}
+
+impl FaucetHandler {
+    pub fn new() -> Self {
+        Self {
+            rpc: RpcClient::from_env(),
+        }
+    }
+}
+
+impl Default for FaucetHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Handler for FaucetHandler {
+    fn handle(&mut self) -> Result<(), String> {
+        let address: Address = read_line("\nAddress to fund:")
+            .map_err(|e| format!("Failed to read address: {}", e))?
+            .parse()
+            .map_err(|e| format!("Invalid address: {}", e))?;
+        let amount = read_line("Amount in base units (blank for the faucet's drip):")
+            .map_err(|e| format!("Failed to read amount: {}", e))?;
+        let params = if amount.is_empty() {
+            json!({ "address": address })
+        } else {
+            let amount: u64 = amount.parse().map_err(|_| format!("Invalid amount: {}", amount))?;
+            json!({ "address": address, "amount": amount })
+        };
+
+        let runtime = tokio::runtime::Runtime::new().map_err(|e| format!("Failed to create runtime: {}", e))?;
+        let outcome: Value = runtime
+            .block_on(self.rpc.call("request_faucet", params))
+            .map_err(|e| format!("Faucet request failed: {}", e))?;
+        println!(
+            "\nReceived {} test RØMER, balance now {}",
+            outcome["amount"], outcome["balance"]
+        );
+        Ok(())
+    }
+}
@@ -1,22 +1,66 @@
-use commonware_cryptography::{Bls12381, Ed25519, PrivateKey, Scheme};
-use commonware_utils::hex;
+use commonware_utils::{from_hex, hex};
 use romer_common::keystore::keymanager::KeyManager;
+use romer_common::keystore::unlock::KeyCache;
+use romer_common::types::address::Address;
+use romer_common::types::admin::{AdminToken, ADMIN_TOKEN_NAMESPACE};
 use romer_common::types::keymanager::{SessionKeyData, SignatureScheme};
 use romer_common::error::{RomerResult, ClientError, RomerError};
 use std::fs;
 use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use crate::handlers::Handler;
+use crate::signer::detached::{self, SignatureEncoding, MESSAGE_NAMESPACE};
+use crate::signer::{LedgerSigner, LocalSigner, Signer, SignerBackend, SignerSelection};
+
+/// Prompts for the keystore passphrase unless `keys` is still unlocked
+pub fn ensure_unlocked(key_manager: &KeyManager, keys: &KeyCache) -> Result<(), String> {
+    if keys.is_unlocked() {
+        return Ok(());
+    }
+    let passphrase = rpassword::prompt_password("Keystore passphrase: ")
+        .map_err(|e| format!("Failed to read passphrase: {}", e))?;
+    keys.unlock(key_manager, &passphrase)
+        .map_err(|e| format!("Failed to unlock keys: {}", e))?;
+    println!("Keys unlocked for {} seconds", keys.timeout().as_secs());
+    Ok(())
+}
+
+/// Reads the passphrase for a new key. Existing keys must decrypt under it
+/// so the keystore keeps a single passphrase; a first key has its
+/// passphrase entered twice.
+pub fn read_new_key_passphrase(key_manager: &KeyManager, keys: &KeyCache) -> RomerResult<String> {
+    let passphrase = rpassword::prompt_password("Keystore passphrase: ")
+        .map_err(ClientError::Io)?;
+
+    let has_keys = [SignatureScheme::Ed25519, SignatureScheme::Bls12381]
+        .into_iter()
+        .any(|scheme| key_manager.has_permanent_key(scheme));
+    if has_keys {
+        keys.unlock(key_manager, &passphrase)?;
+    } else {
+        if passphrase.is_empty() {
+            return Err(ClientError::Config("Passphrase cannot be empty".into()).into());
+        }
+        let confirmation = rpassword::prompt_password("Confirm passphrase: ")
+            .map_err(ClientError::Io)?;
+        if confirmation != passphrase {
+            return Err(ClientError::Config("Passphrases do not match".into()).into());
+        }
+    }
+    Ok(passphrase)
+}
 
 // Generator for new keypairs
 pub struct GenerateKeypairHandler {
     key_manager: KeyManager,
+    keys: KeyCache,
 }
 
 impl GenerateKeypairHandler {
-    pub fn new() -> RomerResult<Self> {
+    pub fn new(keys: KeyCache) -> RomerResult<Self> {
         let key_manager = KeyManager::new()
             .map_err(|e| ClientError::Config(e.to_string()))?;
-        Ok(Self { key_manager })
+        Ok(Self { key_manager, keys })
     }
 
     fn get_key_type(&self) -> RomerResult<SignatureScheme> {
@@ -48,11 +92,18 @@ impl Handler for GenerateKeypairHandler {
         let scheme = self.get_key_type()
             .map_err(|e| format!("Failed to get key type: {}", e))?;
 
+        let passphrase = read_new_key_passphrase(&self.key_manager, &self.keys)
+            .map_err(|e| format!("Failed to get passphrase: {}", e))?;
+
         // Handle the initialization result by converting directly to String
-        match self.key_manager.initialize(scheme) {
+        match self.key_manager.initialize(scheme, &passphrase) {
             Ok(public_key) => {
+                // Pick up the new key in the unlocked set
+                self.keys.unlock(&self.key_manager, &passphrase)
+                    .map_err(|e| format!("Failed to unlock keys: {}", e))?;
                 println!("Key generated successfully!");
                 println!("Public key: {}", hex(&public_key));
+                println!("Address: {}", Address::from_public_key(scheme, &public_key));
                 Ok(())
             }
             Err(e) => Err(format!("Failed to initialize key: {}", e))
@@ -63,26 +114,32 @@ impl Handler for GenerateKeypairHandler {
 // Checker for existing keys
 pub struct CheckKeysHandler {
     key_manager: KeyManager,
+    keys: KeyCache,
 }
 
 impl CheckKeysHandler {
-    pub fn new() -> Result<Self, io::Error> {
+    pub fn new(keys: KeyCache) -> Result<Self, io::Error> {
         let key_manager = KeyManager::new()
             .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
-        Ok(Self { key_manager })
+        Ok(Self { key_manager, keys })
     }
 
     fn check_permanent_keys(&self) -> io::Result<()> {
         println!("\nChecking permanent keys...");
 
-        match self.key_manager.load_permanent_key(SignatureScheme::Ed25519) {
-            Ok(_) => println!("✓ Ed25519 key found"),
-            Err(_) => println!("✗ No Ed25519 key found"),
+        match self.key_manager.has_permanent_key(SignatureScheme::Ed25519) {
+            true => println!("✓ Ed25519 key found"),
+            false => println!("✗ No Ed25519 key found"),
+        }
+
+        match self.key_manager.has_permanent_key(SignatureScheme::Bls12381) {
+            true => println!("✓ BLS12381 key found"),
+            false => println!("✗ No BLS12381 key found"),
         }
 
-        match self.key_manager.load_permanent_key(SignatureScheme::Bls12381) {
-            Ok(_) => println!("✓ BLS12381 key found"),
-            Err(_) => println!("✗ No BLS12381 key found"),
+        match self.keys.remaining() {
+            Some(remaining) => println!("Keys unlocked, locking in {} seconds", remaining.as_secs()),
+            None => println!("Keys locked"),
         }
 
         Ok(())
@@ -150,13 +207,15 @@ impl Handler for CheckKeysHandler {
 // Handler for signing messages
 pub struct SignMessageHandler {
     key_manager: KeyManager,
+    keys: KeyCache,
+    selection: SignerSelection,
 }
 
 impl SignMessageHandler {
-    pub fn new() -> Result<Self, io::Error> {
+    pub fn new(keys: KeyCache, selection: SignerSelection) -> Result<Self, io::Error> {
         let key_manager = KeyManager::new()
             .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
-        Ok(Self { key_manager })
+        Ok(Self { key_manager, keys, selection })
     }
 
     fn get_key_type(&self) -> io::Result<SignatureScheme> {
@@ -179,44 +238,48 @@ impl SignMessageHandler {
         }
     }
 
-    fn get_message(&self) -> io::Result<String> {
-        println!("\nEnter the message to sign:");
-        print!("> ");
-        io::stdout().flush()?;
-
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-        Ok(input.trim().to_string())
+    fn get_encoding(&self) -> io::Result<SignatureEncoding> {
+        match read_line("\nSelect signature encoding:\n1. Hex\n2. Base64")?.as_str() {
+            "2" => Ok(SignatureEncoding::Base64),
+            _ => Ok(SignatureEncoding::Hex),
+        }
     }
 
-    fn select_key(&self, scheme: SignatureScheme) -> io::Result<Vec<u8>> {
-        match self.key_manager.load_permanent_key(scheme) {
-            Ok(bytes) => Ok(bytes),
-            Err(_) => {
-                println!("No {:?} keys found. Please generate one first.", scheme);
-                Err(io::Error::new(io::ErrorKind::NotFound, "No keys available"))
-            }
+    /// Where to write the detached signature. Defaults to `<file>.sig` for
+    /// file input; blank skips writing for typed messages.
+    fn get_output_path(&self, input: Option<&Path>) -> io::Result<Option<PathBuf>> {
+        let default = input.map(|path| {
+            let mut name = path.as_os_str().to_owned();
+            name.push(".sig");
+            PathBuf::from(name)
+        });
+        let prompt = match &default {
+            Some(path) => format!("\nSave detached signature to (default {}):", path.display()),
+            None => "\nSave detached signature to (blank to skip):".to_string(),
+        };
+        match read_line(&prompt)?.as_str() {
+            "" => Ok(default),
+            path => Ok(Some(PathBuf::from(path))),
         }
     }
 
-    fn sign_message(
-        &self,
-        scheme: SignatureScheme,
-        key_bytes: Vec<u8>,
-        message: &str,
-    ) -> io::Result<Vec<u8>> {
-        match scheme {
-            SignatureScheme::Ed25519 => {
-                let private_key = PrivateKey::from(key_bytes);
-                let mut signer = <Ed25519 as Scheme>::from(private_key)
-                    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Invalid Ed25519 key"))?;
-                Ok(signer.sign(Some(&[]), message.as_bytes()).to_vec())
+    /// Signer for `scheme` under the selected backend. Ledger devices only
+    /// hold Ed25519 keys, so BLS always signs locally.
+    fn select_signer(&self, scheme: SignatureScheme) -> Result<Box<dyn Signer>, String> {
+        match (self.selection.backend(), scheme) {
+            (SignerBackend::Ledger { account }, SignatureScheme::Ed25519) => {
+                let signer = LedgerSigner::connect(account)
+                    .map_err(|e| format!("Failed to connect to Ledger: {}", e))?;
+                Ok(Box::new(signer))
             }
-            SignatureScheme::Bls12381 => {
-                let private_key = PrivateKey::from(key_bytes);
-                let mut signer = <Bls12381 as Scheme>::from(private_key)
-                    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Invalid BLS key"))?;
-                Ok(signer.sign(Some(&[]), message.as_bytes()).to_vec())
+            _ => {
+                if !self.key_manager.has_permanent_key(scheme) {
+                    println!("No {:?} keys found. Please generate one first.", scheme);
+                    return Err("No keys available".into());
+                }
+                ensure_unlocked(&self.key_manager, &self.keys)?;
+                let key_manager = KeyManager::new().map_err(|e| e.to_string())?;
+                Ok(Box::new(LocalSigner::new(key_manager, self.keys.clone(), scheme)))
             }
         }
     }
@@ -227,22 +290,40 @@ impl Handler for SignMessageHandler {
         // Convert io::Error to String for each operation with meaningful context
         let scheme = self.get_key_type()
             .map_err(|e| format!("Failed to get key type: {}", e))?;
-            
-        let key_bytes = self.select_key(scheme)
+
+        let mut signer = self.select_signer(scheme)
             .map_err(|e| format!("Failed to select key: {}", e))?;
             
-        let message = self.get_message()
+        let (message, input_path) = read_message("sign")
             .map_err(|e| format!("Failed to get message: {}", e))?;
 
-        // Handle the sign_message result with descriptive error message
-        match self.sign_message(scheme, key_bytes, &message) {
+        let encoding = self.get_encoding()
+            .map_err(|e| format!("Failed to get encoding: {}", e))?;
+
+        let output_path = self.get_output_path(input_path.as_deref())
+            .map_err(|e| format!("Failed to get output path: {}", e))?;
+
+        if matches!(self.selection.backend(), SignerBackend::Ledger { .. }) && scheme == SignatureScheme::Ed25519 {
+            println!("Review and approve the signature on your Ledger...");
+        }
+
+        // Handle the sign result with descriptive error message
+        match signer.sign(Some(MESSAGE_NAMESPACE), &message) {
             Ok(signature) => {
-                println!("\nMessage signed successfully!");
-                println!("Signature (hex): {}", hex(&signature));
+                let encoded = detached::encode(&signature, encoding);
+                println!("\nMessage signed successfully with {}!", signer.describe());
+                println!("Signature ({:?}): {}", encoding, encoded);
+                if let Ok(public_key) = signer.public_key() {
+                    println!("Public key: {}", hex(&public_key));
+                }
+                if let Some(path) = output_path {
+                    fs::write(&path, format!("{}\n", encoded))
+                        .map_err(|e| format!("Failed to write signature to {}: {}", path.display(), e))?;
+                    println!("Detached signature written to {}", path.display());
+                }
                 Ok(())
             }
             Err(e) => {
-                // Convert the IO error directly to a String with context
                 let error_msg = format!("Error signing message: {}", e);
                 println!("{}", error_msg);
                 Err(error_msg)
@@ -251,26 +332,250 @@ impl Handler for SignMessageHandler {
     }
 }
 
+// Handler for creating tokens that authenticate to a sequencer's admin API.
+// The sequencer maps the Ed25519 public key to a role.
+pub struct AdminTokenHandler {
+    key_manager: KeyManager,
+    keys: KeyCache,
+    selection: SignerSelection,
+}
+
+impl AdminTokenHandler {
+    pub fn new(keys: KeyCache, selection: SignerSelection) -> Result<Self, io::Error> {
+        let key_manager = KeyManager::new()
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+        Ok(Self { key_manager, keys, selection })
+    }
+
+    fn get_lifetime(&self) -> io::Result<u64> {
+        let input = read_line("
+Token lifetime in minutes (default 60):")?;
+        if input.is_empty() {
+            return Ok(60);
+        }
+        input.parse().map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Invalid lifetime"))
+    }
+
+    fn select_signer(&self) -> Result<Box<dyn Signer>, String> {
+        match self.selection.backend() {
+            SignerBackend::Ledger { account } => {
+                let signer = LedgerSigner::connect(account)
+                    .map_err(|e| format!("Failed to connect to Ledger: {}", e))?;
+                Ok(Box::new(signer))
+            }
+            _ => {
+                if !self.key_manager.has_permanent_key(SignatureScheme::Ed25519) {
+                    return Err("No Ed25519 key found. Please generate one first.".into());
+                }
+                ensure_unlocked(&self.key_manager, &self.keys)?;
+                let key_manager = KeyManager::new().map_err(|e| e.to_string())?;
+                Ok(Box::new(LocalSigner::new(key_manager, self.keys.clone(), SignatureScheme::Ed25519)))
+            }
+        }
+    }
+}
+
+impl Handler for AdminTokenHandler {
+    fn handle(&mut self) -> Result<(), String> {
+        let minutes = self.get_lifetime()
+            .map_err(|e| format!("Failed to get lifetime: {}", e))?;
+        let mut signer = self.select_signer()?;
+
+        let expires_at = chrono::Utc::now().timestamp().max(0) as u64 + minutes * 60;
+        let public_key = signer.public_key().map_err(|e| format!("Failed to read public key: {}", e))?;
+        let signature = signer
+            .sign(Some(ADMIN_TOKEN_NAMESPACE), &AdminToken::signing_message(expires_at))
+            .map_err(|e| format!("Failed to sign token: {}", e))?;
+        let token = AdminToken {
+            public_key,
+            expires_at,
+            signature,
+        };
+
+        println!("
+Admin token for {} (expires in {} minutes):", hex(&token.public_key), minutes);
+        println!("{}", token);
+        println!("
+Send it in the X-Romer-Admin-Token header, or export it as ROMER_ADMIN_TOKEN");
+        Ok(())
+    }
+}
+
+// Handler for checking detached signatures
+#[derive(Default)]
+pub struct VerifySignatureHandler;
+
+impl VerifySignatureHandler {
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn get_key_type(&self) -> io::Result<SignatureScheme> {
+        match read_line("\nSelect signature scheme:\n1. Ed25519\n2. BLS12381")?.as_str() {
+            "1" => Ok(SignatureScheme::Ed25519),
+            "2" => Ok(SignatureScheme::Bls12381),
+            _ => {
+                println!("Invalid selection, please try again");
+                self.get_key_type()
+            }
+        }
+    }
+
+    /// Reads the signature as hex or base64, either typed or from a file
+    fn get_signature(&self) -> Result<Vec<u8>, String> {
+        let input = read_line("\nEnter the signature (hex or base64) or the path of a signature file:")
+            .map_err(|e| e.to_string())?;
+        let text = match Path::new(&input).is_file() {
+            true => fs::read_to_string(&input).map_err(|e| format!("Failed to read {}: {}", input, e))?,
+            false => input,
+        };
+        detached::decode(&text).map_err(|e| e.to_string())
+    }
+
+    fn get_public_key(&self) -> Result<Vec<u8>, String> {
+        let input = read_line("\nEnter the signer's public key (hex):").map_err(|e| e.to_string())?;
+        from_hex(input.trim_start_matches("0x")).ok_or_else(|| "Public key is not valid hex".to_string())
+    }
+}
+
+impl Handler for VerifySignatureHandler {
+    fn handle(&mut self) -> Result<(), String> {
+        let scheme = self.get_key_type()
+            .map_err(|e| format!("Failed to get key type: {}", e))?;
+
+        let (message, _) = read_message("verify")
+            .map_err(|e| format!("Failed to get message: {}", e))?;
+
+        let signature = self.get_signature()
+            .map_err(|e| format!("Failed to get signature: {}", e))?;
+
+        let public_key = self.get_public_key()
+            .map_err(|e| format!("Failed to get public key: {}", e))?;
+
+        if detached::verify(scheme, &message, &public_key, &signature) {
+            println!("\n✓ Signature is valid");
+            println!("Signer address: {}", Address::from_public_key(scheme, &public_key));
+        } else {
+            println!("\n✗ Signature is NOT valid for this message and public key");
+        }
+        Ok(())
+    }
+}
+
+/// Prints `prompt` and reads one trimmed line
+pub(crate) fn read_line(prompt: &str) -> io::Result<String> {
+    println!("{}", prompt);
+    print!("> ");
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(input.trim().to_string())
+}
+
+/// Reads a message typed on the terminal or the raw bytes of a file,
+/// returning the file path for the latter
+fn read_message(action: &str) -> io::Result<(Vec<u8>, Option<PathBuf>)> {
+    match read_line(&format!("\nMessage to {}:\n1. Enter text\n2. Read from file", action))?.as_str() {
+        "2" => {
+            let path = PathBuf::from(read_line("\nEnter the file path:")?);
+            Ok((fs::read(&path)?, Some(path)))
+        }
+        _ => Ok((read_line(&format!("\nEnter the message to {}:", action))?.into_bytes(), None)),
+    }
+}
+
+// Handler for choosing where Ed25519 signatures come from
+pub struct SelectSignerHandler {
+    selection: SignerSelection,
+}
+
+impl SelectSignerHandler {
+    pub fn new(selection: SignerSelection) -> Self {
+        Self { selection }
+    }
+
+    fn get_backend(&self) -> io::Result<Option<SignerBackend>> {
+        println!("\nCurrent signer: {:?}", self.selection.backend());
+        println!("\nSelect signer for Ed25519 keys:");
+        println!("1. Local keystore");
+        println!("2. Ledger hardware wallet");
+        print!("> ");
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+
+        match input.trim() {
+            "1" => Ok(Some(SignerBackend::Local)),
+            "2" => {
+                println!("\nEnter Ledger account index (default 0):");
+                print!("> ");
+                io::stdout().flush()?;
+
+                let mut input = String::new();
+                io::stdin().read_line(&mut input)?;
+                let account = match input.trim() {
+                    "" => 0,
+                    raw => raw.parse().map_err(|_| {
+                        io::Error::new(io::ErrorKind::InvalidInput, "Invalid account index")
+                    })?,
+                };
+                Ok(Some(SignerBackend::Ledger { account }))
+            }
+            _ => {
+                println!("Invalid selection, signer unchanged");
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl Handler for SelectSignerHandler {
+    fn handle(&mut self) -> Result<(), String> {
+        let Some(backend) = self.get_backend()
+            .map_err(|e| format!("Failed to get signer: {}", e))?
+        else {
+            return Ok(());
+        };
+
+        if let SignerBackend::Ledger { account } = backend {
+            // Make sure the device is reachable and let the user check the
+            // address on its screen before anything is signed with it
+            let mut signer = LedgerSigner::connect(account)
+                .map_err(|e| format!("Failed to connect to Ledger: {}", e))?;
+            println!("Confirm the address on your Ledger...");
+            let public_key = signer.confirm_public_key()
+                .map_err(|e| format!("Failed to get Ledger public key: {}", e))?;
+            println!("Public key: {}", hex(&public_key));
+            println!("Address: {}", Address::from_public_key(SignatureScheme::Ed25519, &public_key));
+        }
+
+        self.selection.select(backend);
+        println!("Signer set to {:?}", backend);
+        Ok(())
+    }
+}
+
 // Handler for creating session keys
 pub struct CreateSessionKeyHandler {
     key_manager: KeyManager,
+    keys: KeyCache,
 }
 
 impl CreateSessionKeyHandler {
-    pub fn new() -> Result<Self, io::Error> {
+    pub fn new(keys: KeyCache) -> Result<Self, io::Error> {
         let key_manager = KeyManager::new()
             .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
-        Ok(Self { key_manager })
+        Ok(Self { key_manager, keys })
     }
 
-    fn load_parent_key(&self) -> io::Result<Vec<u8>> {
-        match self.key_manager.load_permanent_key(SignatureScheme::Bls12381) {
-            Ok(key_bytes) => Ok(key_bytes),
-            Err(_) => {
-                println!("No BLS key found. Please generate one first using the Generate Keypair option.");
-                Err(io::Error::new(io::ErrorKind::NotFound, "BLS parent key not found"))
-            }
+    fn check_parent_key(&self) -> io::Result<()> {
+        if !self.key_manager.has_permanent_key(SignatureScheme::Bls12381) {
+            println!("No BLS key found. Please generate one first using the Generate Keypair option.");
+            return Err(io::Error::new(io::ErrorKind::NotFound, "BLS parent key not found"));
         }
+        Ok(())
     }
 
     fn get_namespace(&self) -> io::Result<String> {
@@ -360,7 +665,7 @@ impl CreateSessionKeyHandler {
 impl Handler for CreateSessionKeyHandler {
     fn handle(&mut self) -> Result<(), String> {
         // Convert each IO operation's error to a String with descriptive context
-        let parent_key_bytes = self.load_parent_key()
+        self.check_parent_key()
             .map_err(|e| format!("Failed to load parent key: {}", e))?;
             
         let namespace = self.get_namespace()
@@ -381,7 +686,14 @@ impl Handler for CreateSessionKeyHandler {
         }
 
         // Handle the session key creation result with detailed error information
-        match self.key_manager.create_session_key(&parent_key_bytes, &namespace, duration, &purpose) {
+        // The unlock may have timed out while the parameters were entered
+        ensure_unlocked(&self.key_manager, &self.keys)?;
+        let created = self.keys
+            .with_key(SignatureScheme::Bls12381, |parent_key_bytes| {
+                self.key_manager.create_session_key(parent_key_bytes, &namespace, duration, &purpose)
+            })
+            .and_then(|created| created);
+        match created {
             Ok(session_data) => {
                 self.display_session_key(&session_data);
                 Ok(())
@@ -7,17 +7,25 @@ pub trait Handler {
 }
 
 // Declare the submodules
+pub mod faucet;
 pub mod keymanager;
+pub mod onboarding;
+pub mod organization;
 pub mod sequencer;
+pub mod session;
+pub mod settlement;
 pub mod state;
 
 
 // Re-export the handlers from submodules for easier access
 pub use keymanager::{
+    AdminTokenHandler,
     CheckKeysHandler,
     CreateSessionKeyHandler, 
     GenerateKeypairHandler,
-    SignMessageHandler
+    SelectSignerHandler,
+    SignMessageHandler,
+    VerifySignatureHandler
 };
 
 // FIX-related handler exports will go here as they are implemented
@@ -27,6 +35,20 @@ pub use sequencer::{
     HeartbeatHandler,
 };
 
+pub use session::PersistentSessionHandler;
+
+pub use onboarding::OnboardingHandler;
+
+pub use settlement::SettleHandler;
+
+pub use faucet::FaucetHandler;
+
+pub use organization::{
+    CreateOrganizationHandler,
+    UpdateOrganizationHandler,
+    ViewOrganizationHandler,
+};
+
 pub use state::{
     RegisterSenderCompIdHandler,
 };
\ No newline at end of file
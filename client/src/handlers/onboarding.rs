@@ -0,0 +1,291 @@
+use chrono::Utc;
+use commonware_utils::hex;
+use romer_common::fix::mock::FixMockGenerator;
+use romer_common::keystore::keymanager::KeyManager;
+use romer_common::keystore::unlock::KeyCache;
+use romer_common::types::fix::{utils, FixConfig, ValidatedMessage};
+use romer_common::types::keymanager::{SessionKeyData, SignatureScheme};
+use romer_common::types::org::OrganizationType;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use uuid::Uuid;
+
+use crate::handlers::keymanager::{ensure_unlocked, read_line, read_new_key_passphrase};
+use crate::handlers::organization::{read_org_type, register_organization};
+use crate::handlers::Handler;
+use crate::rpc::RpcClient;
+
+/// FIX endpoint of the local sequencer
+const FIX_ADDRESS: &str = "127.0.0.1:9878";
+/// Lifetime of the session key issued for certification
+const SESSION_KEY_HOURS: i64 = 24;
+
+/// Outcome of one onboarding step
+enum StepResult {
+    Passed(String),
+    Failed(String),
+    /// The sequencer answered but the behaviour under test was not observed
+    NotVerified(String),
+}
+
+impl StepResult {
+    fn label(&self) -> &'static str {
+        match self {
+            StepResult::Passed(_) => "PASS",
+            StepResult::Failed(_) => "FAIL",
+            StepResult::NotVerified(_) => "NOT VERIFIED",
+        }
+    }
+
+    fn detail(&self) -> &str {
+        match self {
+            StepResult::Passed(detail)
+            | StepResult::Failed(detail)
+            | StepResult::NotVerified(detail) => detail,
+        }
+    }
+}
+
+// Walks a new market maker from keys to a certified FIX connection
+pub struct OnboardingHandler {
+    key_manager: KeyManager,
+    keys: KeyCache,
+    rpc: RpcClient,
+    steps: Vec<(String, StepResult)>,
+}
+
+impl OnboardingHandler {
+    pub fn new(keys: KeyCache) -> io::Result<Self> {
+        let key_manager = KeyManager::new()
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+        Ok(Self {
+            key_manager,
+            keys,
+            rpc: RpcClient::from_env(),
+            steps: Vec::new(),
+        })
+    }
+
+    fn record(&mut self, step: &str, result: StepResult) {
+        println!("[{}] {}: {}", result.label(), step, result.detail());
+        self.steps.push((step.to_string(), result));
+    }
+
+    /// Step 1: the organization's BLS key, generated if missing
+    fn ensure_organization_key(&mut self) -> Result<Vec<u8>, String> {
+        println!("\nStep 1/5: Organization key");
+        if self.key_manager.has_permanent_key(SignatureScheme::Bls12381) {
+            ensure_unlocked(&self.key_manager, &self.keys)?;
+        } else {
+            println!("No BLS12381 key found, generating one");
+            let passphrase = read_new_key_passphrase(&self.key_manager, &self.keys)
+                .map_err(|e| format!("Failed to get passphrase: {}", e))?;
+            self.key_manager
+                .initialize(SignatureScheme::Bls12381, &passphrase)
+                .map_err(|e| format!("Failed to generate key: {}", e))?;
+            self.keys
+                .unlock(&self.key_manager, &passphrase)
+                .map_err(|e| format!("Failed to unlock keys: {}", e))?;
+        }
+        let public_key = self
+            .key_manager
+            .permanent_public_key(SignatureScheme::Bls12381)
+            .map_err(|e| format!("Failed to read public key: {}", e))?;
+        self.record("Organization key", StepResult::Passed(hex(&public_key)));
+        Ok(public_key)
+    }
+
+    fn get_org_type(&self) -> io::Result<OrganizationType> {
+        Ok(read_org_type("\nOrganization type:")?.unwrap_or_else(|| {
+            println!("Invalid selection, defaulting to MarketMaker");
+            OrganizationType::MarketMaker
+        }))
+    }
+
+    /// Step 2: registers the organization and CompID with the sequencer,
+    /// proving possession of the key
+    fn register(&mut self, runtime: &tokio::runtime::Runtime, public_key: Vec<u8>) -> Result<String, String> {
+        println!("\nStep 2/5: Registration with {}", self.rpc.address());
+        let name = read_line("\nOrganization name:").map_err(|e| e.to_string())?;
+        let org_type = self.get_org_type().map_err(|e| e.to_string())?;
+        let sender_comp_id = read_line("\nSenderCompID:").map_err(|e| e.to_string())?;
+
+        ensure_unlocked(&self.key_manager, &self.keys)?;
+        match register_organization(
+            runtime,
+            &self.rpc,
+            &self.keys,
+            name,
+            org_type,
+            sender_comp_id.clone(),
+            public_key,
+        ) {
+            Ok(organization) => {
+                self.record(
+                    "Registration",
+                    StepResult::Passed(format!("{} registered as {}", sender_comp_id, organization.id)),
+                );
+                Ok(sender_comp_id)
+            }
+            Err(e) => {
+                self.record("Registration", StepResult::Failed(e.clone()));
+                Err(e)
+            }
+        }
+    }
+
+    /// Step 3: a session key for the CompID to log on with
+    fn issue_session_key(&mut self, sender_comp_id: &str) -> Result<SessionKeyData, String> {
+        println!("\nStep 3/5: Session key");
+        ensure_unlocked(&self.key_manager, &self.keys)?;
+        let session = self
+            .keys
+            .with_key(SignatureScheme::Bls12381, |parent_key_bytes| {
+                self.key_manager
+                    .create_session_key(parent_key_bytes, sender_comp_id, SESSION_KEY_HOURS, "FIX")
+            })
+            .and_then(|created| created)
+            .map_err(|e| format!("Failed to create session key: {}", e))?;
+        self.record(
+            "Session key",
+            StepResult::Passed(format!("expires {}", session.expires_at)),
+        );
+        Ok(session)
+    }
+
+    /// Step 4: a heartbeat and a session key logon over FIX
+    fn connectivity_test(&mut self, runtime: &tokio::runtime::Runtime, generator: &FixMockGenerator, session: &SessionKeyData) {
+        println!("\nStep 4/5: Connectivity test against {}", FIX_ADDRESS);
+        let heartbeat = match runtime.block_on(send_message(&generator.mock_heartbeat())) {
+            Ok(response) if response.starts_with("Heartbeat received") => StepResult::Passed("heartbeat acknowledged".into()),
+            Ok(response) => StepResult::Failed(format!("unexpected response: {}", response.trim())),
+            Err(e) => StepResult::Failed(format!("connection failed: {}", e)),
+        };
+        self.record("Heartbeat", heartbeat);
+
+        let logon = match generator.mock_session_key_logon(session) {
+            Ok(logon) => match runtime.block_on(send_message(&logon)) {
+                Ok(response) if response.starts_with("Logon accepted") => StepResult::Passed("session key logon authenticated".into()),
+                Ok(response) => StepResult::Failed(response.trim().to_string()),
+                Err(e) => StepResult::Failed(format!("connection failed: {}", e)),
+            },
+            Err(e) => StepResult::Failed(format!("failed to sign logon: {}", e)),
+        };
+        self.record("Logon", logon);
+    }
+
+    /// Step 5: the certification order script. A new order followed by one
+    /// reusing its ClOrdID, which must be rejected as a duplicate.
+    fn certification_script(&mut self, runtime: &tokio::runtime::Runtime, generator: &FixMockGenerator) {
+        println!("\nStep 5/5: Certification order script");
+        let cl_ord_id = format!("CERT{}", Uuid::new_v4().simple());
+        let order = generator.mock_limit_order(&cl_ord_id, "AAPL", '1', 100, 10.0);
+
+        let first = match runtime.block_on(send_message(&order)) {
+            Ok(response) => StepResult::Passed(format!("sequencer responded: {}", response.trim())),
+            Err(e) => StepResult::Failed(format!("connection failed: {}", e)),
+        };
+        self.record("New order", first);
+
+        let duplicate = generator.mock_limit_order(&cl_ord_id, "AAPL", '1', 100, 10.0);
+        let rejected = match runtime.block_on(send_message(&duplicate)) {
+            // ExecutionReport with OrdRejReason 6 (duplicate order)
+            Ok(response) if is_duplicate_reject(&response) => {
+                StepResult::Passed("duplicate ClOrdID rejected".into())
+            }
+            Ok(response) => StepResult::NotVerified(format!(
+                "no duplicate order reject, the order may lack trading permissions: {}",
+                response.trim()
+            )),
+            Err(e) => StepResult::Failed(format!("connection failed: {}", e)),
+        };
+        self.record("Duplicate ClOrdID", rejected);
+    }
+
+    fn report(&self, sender_comp_id: &str) -> String {
+        let mut report = String::new();
+        let _ = writeln!(report, "RØMER onboarding report");
+        let _ = writeln!(report, "SenderCompID: {}", sender_comp_id);
+        let _ = writeln!(report, "Sequencer RPC: {}", self.rpc.address());
+        let _ = writeln!(report, "Sequencer FIX: {}", FIX_ADDRESS);
+        let _ = writeln!(report, "Completed: {}", Utc::now().to_rfc3339());
+        let _ = writeln!(report);
+        for (step, result) in &self.steps {
+            let _ = writeln!(report, "[{}] {}: {}", result.label(), step, result.detail());
+        }
+        let failed = self
+            .steps
+            .iter()
+            .any(|(_, result)| matches!(result, StepResult::Failed(_)));
+        let _ = writeln!(report);
+        let _ = writeln!(
+            report,
+            "Result: {}",
+            if failed { "FAILED" } else { "COMPLETE" }
+        );
+        report
+    }
+
+    /// Writes the report to `<keystore>/onboarding/<compid>-<timestamp>.txt`
+    fn save_report(&self, sender_comp_id: &str, report: &str) -> io::Result<PathBuf> {
+        let dir = self.key_manager.base_dir.join("onboarding");
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(format!(
+            "{}-{}.txt",
+            sender_comp_id,
+            Utc::now().format("%Y%m%dT%H%M%S")
+        ));
+        fs::write(&path, report)?;
+        Ok(path)
+    }
+}
+
+impl Handler for OnboardingHandler {
+    fn handle(&mut self) -> Result<(), String> {
+        println!("\nRØMER Onboarding Wizard");
+        println!("This registers your organization with the sequencer and certifies your FIX connection.");
+        self.steps.clear();
+
+        let runtime = tokio::runtime::Runtime::new()
+            .map_err(|e| format!("Failed to create runtime: {}", e))?;
+
+        let public_key = self.ensure_organization_key()?;
+        let sender_comp_id = self.register(&runtime, public_key)?;
+        let session = self.issue_session_key(&sender_comp_id)?;
+
+        let generator = FixMockGenerator::new(FixConfig {
+            sender_comp_id: sender_comp_id.clone(),
+            ..FixConfig::default()
+        });
+        self.connectivity_test(&runtime, &generator, &session);
+        self.certification_script(&runtime, &generator);
+
+        let report = self.report(&sender_comp_id);
+        println!("\n{}", report);
+        match self.save_report(&sender_comp_id, &report) {
+            Ok(path) => println!("Report saved to {}", path.display()),
+            Err(e) => println!("Failed to save report: {}", e),
+        }
+        Ok(())
+    }
+}
+
+/// Sends one message to the sequencer's FIX port and reads the response
+async fn send_message(message: &ValidatedMessage) -> io::Result<String> {
+    let mut stream = TcpStream::connect(FIX_ADDRESS).await?;
+    stream.write_all(&message.raw_data).await?;
+
+    let mut buffer = [0u8; 1024];
+    let n = stream.read(&mut buffer).await?;
+    Ok(String::from_utf8_lossy(&buffer[..n]).to_string())
+}
+
+/// Whether `response` is an ExecutionReport with OrdRejReason 6
+fn is_duplicate_reject(response: &str) -> bool {
+    let fields = utils::parse_message_fields(response.as_bytes());
+    fields.get(&35).map(String::as_str) == Some("8") && fields.get(&103).map(String::as_str) == Some("6")
+}
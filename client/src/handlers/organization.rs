@@ -0,0 +1,343 @@
+use chrono::{TimeZone, Utc};
+use commonware_cryptography::{Bls12381, PrivateKey, Scheme};
+use commonware_utils::hex;
+use romer_common::keystore::keymanager::KeyManager;
+use romer_common::keystore::unlock::KeyCache;
+use romer_common::types::keymanager::SignatureScheme;
+use romer_common::types::org::{
+    Organization, OrganizationRegistration, OrganizationType, OrganizationUpdate,
+    REGISTRATION_NAMESPACE, UPDATE_NAMESPACE,
+};
+use serde_json::json;
+use std::io;
+
+use crate::handlers::keymanager::{ensure_unlocked, read_line};
+use crate::handlers::Handler;
+use crate::rpc::RpcClient;
+
+const ORGANIZATION_TYPES: [OrganizationType; 8] = [
+    OrganizationType::MarketMaker,
+    OrganizationType::BrokerDealer,
+    OrganizationType::Bank,
+    OrganizationType::AssetManager,
+    OrganizationType::InfraProvider,
+    OrganizationType::ServiceProvider,
+    OrganizationType::PrimeBroker,
+    OrganizationType::Custodian,
+];
+
+/// Prompts for an organization type. Returns `None` for a blank or invalid
+/// selection.
+pub(crate) fn read_org_type(prompt: &str) -> io::Result<Option<OrganizationType>> {
+    let mut menu = prompt.to_string();
+    for (index, org_type) in ORGANIZATION_TYPES.iter().enumerate() {
+        menu.push_str(&format!("\n{}. {:?}", index + 1, org_type));
+    }
+    Ok(read_line(&menu)?
+        .parse::<usize>()
+        .ok()
+        .and_then(|index| ORGANIZATION_TYPES.get(index.checked_sub(1)?).cloned()))
+}
+
+/// Signs a registration with the unlocked BLS key and submits it to the
+/// sequencer's registry
+pub(crate) fn register_organization(
+    runtime: &tokio::runtime::Runtime,
+    rpc: &RpcClient,
+    keys: &KeyCache,
+    name: String,
+    org_type: OrganizationType,
+    sender_comp_id: String,
+    public_key: Vec<u8>,
+) -> Result<Organization, String> {
+    let message = OrganizationRegistration::signing_message(&name, &org_type, &sender_comp_id);
+    let signature = keys
+        .sign(SignatureScheme::Bls12381, REGISTRATION_NAMESPACE, &message)
+        .map_err(|e| format!("Failed to sign registration: {}", e))?;
+
+    let registration = OrganizationRegistration {
+        name,
+        org_type,
+        sender_comp_id,
+        public_key,
+        signature,
+    };
+    runtime
+        .block_on(rpc.call("register_organization", json!(registration)))
+        .map_err(|e| format!("Registration failed: {}", e))
+}
+
+/// Looks up the organization registered under `sender_comp_id`
+fn lookup(runtime: &tokio::runtime::Runtime, rpc: &RpcClient, sender_comp_id: &str) -> Result<Organization, String> {
+    runtime
+        .block_on(rpc.call("get_organization", json!({ "sender_comp_id": sender_comp_id })))
+        .map_err(|e| format!("Failed to look up {}: {}", sender_comp_id, e))
+}
+
+fn display_organization(organization: &Organization) {
+    println!("\nOrganization:");
+    println!("  ID: {}", organization.id);
+    println!("  Name: {}", organization.name);
+    println!("  Type: {:?}", organization.org_type);
+    println!("  SenderCompID: {}", organization.sender_comp_id);
+    println!("  Public key: {}", hex(&organization.public_key));
+    if let Some(registered_at) = Utc.timestamp_opt(organization.registered_at as i64, 0).single() {
+        println!("  Registered: {}", registered_at);
+    }
+    if organization.symbol_permissions.is_empty() {
+        println!("  Symbol permissions: none");
+    } else {
+        println!("  Symbol permissions:");
+        for (symbol, permission) in &organization.symbol_permissions {
+            println!("    {}: {:?}", symbol, permission);
+        }
+    }
+}
+
+fn runtime() -> Result<tokio::runtime::Runtime, String> {
+    tokio::runtime::Runtime::new().map_err(|e| format!("Failed to create runtime: {}", e))
+}
+
+// Registers a new organization with the sequencer under the local BLS key
+pub struct CreateOrganizationHandler {
+    key_manager: KeyManager,
+    keys: KeyCache,
+    rpc: RpcClient,
+}
+
+impl CreateOrganizationHandler {
+    pub fn new(keys: KeyCache) -> io::Result<Self> {
+        let key_manager = KeyManager::new()
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+        Ok(Self {
+            key_manager,
+            keys,
+            rpc: RpcClient::from_env(),
+        })
+    }
+
+    fn get_org_type(&self) -> io::Result<OrganizationType> {
+        match read_org_type("\nSelect organization type:")? {
+            Some(org_type) => Ok(org_type),
+            None => {
+                println!("Invalid selection, please try again");
+                self.get_org_type()
+            }
+        }
+    }
+}
+
+impl Handler for CreateOrganizationHandler {
+    fn handle(&mut self) -> Result<(), String> {
+        let public_key = self
+            .key_manager
+            .permanent_public_key(SignatureScheme::Bls12381)
+            .map_err(|_| "No BLS12381 key found. Please generate one first.".to_string())?;
+
+        let name = read_line("\nOrganization name:")
+            .map_err(|e| format!("Failed to get organization name: {}", e))?;
+        let org_type = self
+            .get_org_type()
+            .map_err(|e| format!("Failed to get organization type: {}", e))?;
+        let sender_comp_id = read_line("\nSenderCompID:")
+            .map_err(|e| format!("Failed to get SenderCompID: {}", e))?;
+
+        ensure_unlocked(&self.key_manager, &self.keys)?;
+        let runtime = runtime()?;
+        let organization = register_organization(
+            &runtime,
+            &self.rpc,
+            &self.keys,
+            name,
+            org_type,
+            sender_comp_id,
+            public_key,
+        )?;
+
+        println!("\nOrganization registered with {}", self.rpc.address());
+        display_organization(&organization);
+        Ok(())
+    }
+}
+
+// Shows an organization from the sequencer's registry
+pub struct ViewOrganizationHandler {
+    rpc: RpcClient,
+}
+
+impl ViewOrganizationHandler {
+    pub fn new() -> Self {
+        Self {
+            rpc: RpcClient::from_env(),
+        }
+    }
+}
+
+impl Default for ViewOrganizationHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Handler for ViewOrganizationHandler {
+    fn handle(&mut self) -> Result<(), String> {
+        let sender_comp_id = read_line("\nSenderCompID:")
+            .map_err(|e| format!("Failed to get SenderCompID: {}", e))?;
+        let organization = lookup(&runtime()?, &self.rpc, &sender_comp_id)?;
+        display_organization(&organization);
+        Ok(())
+    }
+}
+
+// Changes an organization's name, type, CompID or key, signed by its current key
+pub struct UpdateOrganizationHandler {
+    key_manager: KeyManager,
+    keys: KeyCache,
+    rpc: RpcClient,
+}
+
+impl UpdateOrganizationHandler {
+    pub fn new(keys: KeyCache) -> io::Result<Self> {
+        let key_manager = KeyManager::new()
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+        Ok(Self {
+            key_manager,
+            keys,
+            rpc: RpcClient::from_env(),
+        })
+    }
+
+    /// Reads a replacement value, `None` when left blank
+    fn read_change(&self, prompt: &str, current: &str) -> io::Result<Option<String>> {
+        let value = read_line(&format!("\n{} [{}] (blank to keep):", prompt, current))?;
+        Ok(Some(value).filter(|value| !value.is_empty() && value != current))
+    }
+
+    /// Generates the replacement key and its proof of possession over the
+    /// update. The key is only committed once the sequencer accepts it.
+    fn rotate_key(&self, update: &mut OrganizationUpdate) -> Result<String, String> {
+        let passphrase = rpassword::prompt_password("Keystore passphrase: ")
+            .map_err(|e| format!("Failed to read passphrase: {}", e))?;
+        // The new key is sealed under the same passphrase as the current one
+        self.keys
+            .unlock(&self.key_manager, &passphrase)
+            .map_err(|e| format!("Failed to unlock keys: {}", e))?;
+        let (public_key, private_key) = self
+            .key_manager
+            .generate_pending_key(SignatureScheme::Bls12381, &passphrase)
+            .map_err(|e| format!("Failed to generate key: {}", e))?;
+        update.public_key = Some(public_key);
+
+        let mut signer = <Bls12381 as Scheme>::from(PrivateKey::from(private_key.to_vec()))
+            .ok_or_else(|| "Invalid generated key".to_string())?;
+        update.new_key_signature = Some(
+            signer
+                .sign(Some(REGISTRATION_NAMESPACE), &update.signing_message())
+                .to_vec(),
+        );
+        Ok(passphrase)
+    }
+
+    fn confirm(&self, update: &OrganizationUpdate) -> io::Result<bool> {
+        println!("\nPlease confirm the update:");
+        if let Some(name) = &update.name {
+            println!("Name: {}", name);
+        }
+        if let Some(org_type) = &update.org_type {
+            println!("Type: {:?}", org_type);
+        }
+        if let Some(sender_comp_id) = &update.sender_comp_id {
+            println!("SenderCompID: {}", sender_comp_id);
+        }
+        if update.public_key.is_some() {
+            println!("Public key: rotate to a newly generated key");
+        }
+        Ok(read_line("\nProceed with update? (y/n)")?.to_lowercase() == "y")
+    }
+}
+
+impl Handler for UpdateOrganizationHandler {
+    fn handle(&mut self) -> Result<(), String> {
+        let runtime = runtime()?;
+        let sender_comp_id = read_line("\nSenderCompID of the organization:")
+            .map_err(|e| format!("Failed to get SenderCompID: {}", e))?;
+        let current = lookup(&runtime, &self.rpc, &sender_comp_id)?;
+        display_organization(&current);
+
+        let local_key = self
+            .key_manager
+            .permanent_public_key(SignatureScheme::Bls12381)
+            .map_err(|e| format!("Failed to read BLS key: {}", e))?;
+        if local_key != current.public_key {
+            return Err("This keystore does not hold the organization's key".into());
+        }
+
+        let name = self
+            .read_change("Name", &current.name)
+            .map_err(|e| format!("Failed to get name: {}", e))?;
+        let org_type = read_org_type(&format!("\nType [{:?}] (blank to keep):", current.org_type))
+            .map_err(|e| format!("Failed to get type: {}", e))?
+            .filter(|org_type| *org_type != current.org_type);
+        let new_sender_comp_id = self
+            .read_change("SenderCompID", &current.sender_comp_id)
+            .map_err(|e| format!("Failed to get SenderCompID: {}", e))?;
+        let rotate = read_line("\nRotate the organization key? (y/n)")
+            .map_err(|e| format!("Failed to read input: {}", e))?
+            .to_lowercase()
+            == "y";
+
+        if name.is_none() && org_type.is_none() && new_sender_comp_id.is_none() && !rotate {
+            println!("Nothing to update.");
+            return Ok(());
+        }
+
+        let mut update = OrganizationUpdate {
+            org_id: current.id.clone(),
+            name,
+            org_type,
+            sender_comp_id: new_sender_comp_id,
+            public_key: None,
+            issued_at: (Utc::now().timestamp() as u64).max(current.updated_at + 1),
+            signature: Vec::new(),
+            new_key_signature: None,
+        };
+        if !self
+            .confirm(&update)
+            .map_err(|e| format!("Confirmation failed: {}", e))?
+        {
+            println!("Update cancelled.");
+            return Ok(());
+        }
+
+        let passphrase = match rotate {
+            true => Some(self.rotate_key(&mut update)?),
+            false => {
+                ensure_unlocked(&self.key_manager, &self.keys)?;
+                None
+            }
+        };
+        update.signature = self
+            .keys
+            .sign(SignatureScheme::Bls12381, UPDATE_NAMESPACE, &update.signing_message())
+            .map_err(|e| format!("Failed to sign update: {}", e))?;
+
+        let organization: Organization = runtime
+            .block_on(self.rpc.call("update_organization", json!(update)))
+            .map_err(|e| format!("Update failed: {}", e))?;
+
+        if let Some(passphrase) = passphrase {
+            let backup = self
+                .key_manager
+                .commit_pending_key(SignatureScheme::Bls12381)
+                .map_err(|e| format!("Sequencer accepted the new key but it could not be saved: {}", e))?;
+            self.keys
+                .unlock(&self.key_manager, &passphrase)
+                .map_err(|e| format!("Failed to unlock the new key: {}", e))?;
+            println!("Key rotated, previous key backed up to {}", backup.display());
+        }
+
+        println!("\nOrganization updated");
+        display_organization(&organization);
+        Ok(())
+    }
+}
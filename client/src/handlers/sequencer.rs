@@ -1,15 +1,14 @@
 use crate::handlers::Handler;
 use rand::Rng;
 use romer_common::{error::RomerResult, fix::mock::FixMockGenerator, types::fix::{utils, FixConfig, MessageType, ValidatedMessage}};
+use romer_common::fix::session_logon;
+use romer_common::keystore::keymanager::KeyManager;
+use romer_common::types::keymanager::SessionKeyData;
 use std::{
     io::{self, Write}
 };
 use tokio::{io::{AsyncReadExt, AsyncWriteExt}, net::TcpStream};
 use uuid::Uuid;
-use romer_common::{
-    types::org::{Organization, OrganizationType},
-    storage::journal::RomerJournal,
-};
 
 
 // Handles FIX session logon operations
@@ -74,6 +73,51 @@ impl LogonHandler {
         })
     }
 
+    // Offers the session keys issued for the SenderCompID to sign the logon with
+    fn select_session_key(&self, sender_comp_id: &str) -> Result<Option<SessionKeyData>, String> {
+        let key_manager = KeyManager::new().map_err(|e| e.to_string())?;
+        let mut sessions = key_manager.active_session_keys(sender_comp_id).map_err(|e| e.to_string())?;
+        if sessions.is_empty() {
+            println!("\nNo active session keys for {}, sending an unauthenticated logon", sender_comp_id);
+            return Ok(None);
+        }
+
+        println!("\nSign the logon with a session key:");
+        println!("0. None (unauthenticated)");
+        for (index, (session_id, session)) in sessions.iter().enumerate() {
+            println!(
+                "{}. {}... ({}, expires {})",
+                index + 1,
+                &session_id[..16.min(session_id.len())],
+                session.purpose,
+                session.expires_at
+            );
+        }
+        print!("> ");
+        io::stdout().flush().map_err(|e| e.to_string())?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input).map_err(|e| e.to_string())?;
+        match input.trim().parse::<usize>() {
+            Ok(index) if index >= 1 && index <= sessions.len() => Ok(Some(sessions.swap_remove(index - 1).1)),
+            _ => Ok(None),
+        }
+    }
+
+    // Username (553) and Password (554) for firms with a logon credential
+    fn get_credentials(&self) -> io::Result<Option<(String, String)>> {
+        print!("\nUsername (553) [none]: ");
+        io::stdout().flush()?;
+        let mut username = String::new();
+        io::stdin().read_line(&mut username)?;
+        let username = username.trim();
+        if username.is_empty() {
+            return Ok(None);
+        }
+        let password = rpassword::prompt_password("Password (554): ")?;
+        Ok(Some((username.to_string(), password)))
+    }
+
     // Displays a formatted FIX message
     fn display_message(&self, message: &ValidatedMessage) -> io::Result<()> {
         println!("\nGenerated FIX Logon Message Details:");
@@ -147,6 +191,19 @@ impl LogonHandler {
             );
         }
 
+        if let Some(username) = fields.get(&session_logon::TAG_USERNAME) {
+            println!("  Username ({}): {} - Logon credential, sent with the password in {}", session_logon::TAG_USERNAME, username, session_logon::TAG_PASSWORD);
+        }
+
+        if let Some(namespace) = fields.get(&session_logon::TAG_SESSION_NAMESPACE) {
+            println!("\nSession Key Authentication:");
+            println!("  Namespace ({}): {}", session_logon::TAG_SESSION_NAMESPACE, namespace);
+            if let Some(expiry) = fields.get(&session_logon::TAG_SESSION_EXPIRY) {
+                println!("  Expiry ({}): {} - Unix time the session key expires", session_logon::TAG_SESSION_EXPIRY, expiry);
+            }
+            println!("  Certificate and logon signature in tags {}-{}", session_logon::TAG_PARENT_PUBLIC_KEY, session_logon::TAG_LOGON_SIGNATURE);
+        }
+
         println!("\nTrailer Fields:");
         if let Some(checksum) = fields.get(&10) {
             println!(
@@ -156,7 +213,11 @@ impl LogonHandler {
         }
 
         println!("\nRaw Message (for reference):");
-        println!("{}", String::from_utf8_lossy(&message.raw_data));
+        let mut raw = utils::display(&message.raw_data);
+        if let Some(password) = fields.get(&session_logon::TAG_PASSWORD) {
+            raw = raw.replace(&format!("|{}={}|", session_logon::TAG_PASSWORD, password), &format!("|{}=****|", session_logon::TAG_PASSWORD));
+        }
+        println!("{}", raw);
 
         Ok(())
     }
@@ -167,9 +228,23 @@ impl Handler for LogonHandler {
         // Convert io::Error to String using map_err
         let config = self.get_session_config()
             .map_err(|e| format!("Failed to get session config: {}", e))?;
+
+        let session = self.select_session_key(&config.sender_comp_id)
+            .map_err(|e| format!("Failed to select session key: {}", e))?;
             
-        let generator = FixMockGenerator::new(config);
-        let logon = generator.mock_logon();
+        let credentials = self.get_credentials()
+            .map_err(|e| format!("Failed to read credentials: {}", e))?;
+
+        let generator = match credentials {
+            Some((username, password)) => FixMockGenerator::new(config).with_credentials(username, password),
+            None => FixMockGenerator::new(config),
+        };
+        let logon = match &session {
+            Some(session) => generator
+                .mock_session_key_logon(session)
+                .map_err(|e| format!("Failed to sign logon: {}", e))?,
+            None => generator.mock_logon(),
+        };
 
         // Convert io::Error to String for display_message
         self.display_message(&logon)
@@ -183,7 +258,7 @@ impl Handler for LogonHandler {
         match runtime.block_on(self.send_message(&logon)) {
             Ok(response) => {
                 println!("\nReceived response from sequencer:");
-                println!("{}", response);
+                println!("{}", utils::display(response.as_bytes()));
             }
             Err(e) => println!("Error communicating with sequencer: {}", e),
         }
@@ -271,7 +346,7 @@ impl LogoutHandler {
         }
 
         println!("\nRaw Message (for reference):");
-        println!("{}", String::from_utf8_lossy(&message.raw_data));
+        println!("{}", utils::display(&message.raw_data));
 
         Ok(())
     }
@@ -375,7 +450,7 @@ impl HeartbeatHandler {
 
         // Show the raw message for reference purposes
         println!("\nRaw Message (for reference):");
-        println!("{}", String::from_utf8_lossy(&message.raw_data));
+        println!("{}", utils::display(&message.raw_data));
 
         Ok(())
     }
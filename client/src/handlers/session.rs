@@ -0,0 +1,229 @@
+use chrono::Utc;
+use romer_common::fix::admin::AdminMessage;
+use romer_common::fix::reconnect::{Backoff, ClientSequences, ReconnectPolicy};
+use romer_common::types::fix::{utils, MessageType};
+use std::io;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use uuid::Uuid;
+
+use crate::handlers::keymanager::read_line;
+use crate::handlers::Handler;
+
+const BEGIN_STRING: &str = "FIX.4.2";
+const HEART_BT_INT: u32 = 30;
+
+/// How a connection ended
+enum Closed {
+    /// Ctrl-C; we logged out and stop
+    Interrupted,
+    /// The sequencer logged us out, refusing the Logon if `during_logon`
+    LoggedOut { text: String, during_logon: bool },
+}
+
+// Keeps a FIX session to the sequencer open until Ctrl-C, reconnecting with
+// exponential backoff whenever the connection drops. Sequence numbers are
+// resumed across connections, falling back to a reset if the sequencer
+// refuses the resumed Logon, and market data subscriptions are sent again
+// after every Logon.
+pub struct PersistentSessionHandler {
+    address: String,
+    policy: ReconnectPolicy,
+    sender_comp_id: String,
+    target_comp_id: String,
+    /// MDReqID (262) kept across reconnects, and the symbols subscribed
+    subscription: Option<(String, Vec<String>)>,
+    sequences: ClientSequences,
+}
+
+impl PersistentSessionHandler {
+    pub fn new() -> Self {
+        Self {
+            address: std::env::var("ROMER_SEQUENCER_FIX").unwrap_or_else(|_| "127.0.0.1:9878".to_string()),
+            policy: ReconnectPolicy::default(),
+            sender_comp_id: "ROMER".to_string(),
+            target_comp_id: "MARKET".to_string(),
+            subscription: None,
+            sequences: ClientSequences::new(),
+        }
+    }
+
+    fn encode(&mut self, message: &AdminMessage) -> Vec<u8> {
+        let seq = self.sequences.take_outgoing();
+        message.encode(BEGIN_STRING, &self.sender_comp_id, &self.target_comp_id, seq, Utc::now())
+    }
+
+    /// Market Data Request (35=V) for top of book updates on the subscribed
+    /// symbols
+    fn market_data_request(&mut self, md_req_id: &str, symbols: &[String]) -> Vec<u8> {
+        let mut fields = vec![
+            (35, MessageType::MarketDataRequest.to_fix().to_string()),
+            (49, self.sender_comp_id.clone()),
+            (56, self.target_comp_id.clone()),
+            (34, self.sequences.take_outgoing().to_string()),
+            (52, utils::generate_timestamp()),
+            (262, md_req_id.to_string()),
+            (263, "1".to_string()),
+            (264, "1".to_string()),
+            (267, "2".to_string()),
+            (269, "0".to_string()),
+            (269, "1".to_string()),
+            (146, symbols.len().to_string()),
+        ];
+        fields.extend(symbols.iter().map(|symbol| (55, symbol.clone())));
+        utils::encode_message(BEGIN_STRING, &fields)
+    }
+
+    /// Runs connections until Ctrl-C or the reconnect attempts are used up
+    async fn run(&mut self) -> Result<(), String> {
+        let mut backoff = Backoff::new(self.policy);
+        loop {
+            match self.connect_once(&mut backoff).await {
+                Ok(Closed::Interrupted) => return Ok(()),
+                Ok(Closed::LoggedOut { text, during_logon }) => {
+                    println!("\nLogged out by the sequencer: {}", text);
+                    if during_logon && !self.sequences.resets_on_logon() {
+                        println!("Resumed Logon refused, the next Logon resets sequence numbers");
+                        self.sequences.request_reset();
+                    }
+                }
+                Err(e) => println!("\nConnection lost: {}", e),
+            }
+
+            let Some(delay) = backoff.next_delay() else {
+                return Err(format!("gave up after {} attempts", backoff.attempts()));
+            };
+            println!("Reconnecting in {:?} (attempt {})...", delay, backoff.attempts());
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = tokio::signal::ctrl_c() => return Ok(()),
+            }
+        }
+    }
+
+    async fn connect_once(&mut self, backoff: &mut Backoff) -> io::Result<Closed> {
+        let mut stream = TcpStream::connect(&self.address).await?;
+        let (seq, logon) = self.sequences.logon(HEART_BT_INT);
+        let logon = logon.encode(BEGIN_STRING, &self.sender_comp_id, &self.target_comp_id, seq, Utc::now());
+        stream.write_all(&logon).await?;
+
+        let mut buffer = Vec::new();
+        let mut logged_on = false;
+        let mut heartbeat = tokio::time::interval(Duration::from_secs(HEART_BT_INT.into()));
+        heartbeat.tick().await;
+
+        loop {
+            while let Some(raw) = take_message(&mut buffer) {
+                let fields = utils::parse_message_fields(&raw);
+                let msg_type = fields.get(&35).map(String::as_str).unwrap_or_default();
+                match msg_type {
+                    "A" if !logged_on => {
+                        logged_on = true;
+                        backoff.reset();
+                        let actions = self.sequences.logon_accepted(&fields);
+                        println!(
+                            "\nLogged on as {} (next outgoing {}, next incoming {})",
+                            self.sender_comp_id,
+                            self.sequences.next_outgoing(),
+                            self.sequences.next_incoming()
+                        );
+                        if let Some(from) = actions.gap_fill_from {
+                            let gap_fill = AdminMessage::SequenceReset {
+                                new_seq_no: self.sequences.next_outgoing(),
+                                gap_fill: true,
+                            }
+                            .encode(BEGIN_STRING, &self.sender_comp_id, &self.target_comp_id, from, Utc::now());
+                            stream.write_all(&gap_fill).await?;
+                        }
+                        if let Some((begin, end)) = actions.resend {
+                            let request = self.encode(&AdminMessage::ResendRequest { begin, end });
+                            stream.write_all(&request).await?;
+                        }
+                        if let Some((md_req_id, symbols)) = self.subscription.clone() {
+                            let request = self.market_data_request(&md_req_id, &symbols);
+                            stream.write_all(&request).await?;
+                            println!("Subscribed to market data for {}", symbols.join(", "));
+                        }
+                    }
+                    "5" => {
+                        return Ok(Closed::LoggedOut {
+                            text: fields.get(&58).cloned().unwrap_or_default(),
+                            during_logon: !logged_on,
+                        });
+                    }
+                    _ => {
+                        if let Some(seq) = fields.get(&34).and_then(|seq| seq.parse().ok()) {
+                            self.sequences.received(seq);
+                        }
+                        if msg_type == "1" {
+                            let test_req_id = fields.get(&112).cloned();
+                            stream.write_all(&self.encode(&AdminMessage::Heartbeat { test_req_id })).await?;
+                        } else if msg_type != "0" {
+                            println!("\n{}", utils::display(&raw));
+                        }
+                    }
+                }
+            }
+
+            let mut chunk = [0u8; 4096];
+            tokio::select! {
+                read = stream.read(&mut chunk) => match read? {
+                    0 => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "sequencer closed the connection")),
+                    n => buffer.extend_from_slice(&chunk[..n]),
+                },
+                _ = heartbeat.tick(), if logged_on => {
+                    stream.write_all(&self.encode(&AdminMessage::Heartbeat { test_req_id: None })).await?;
+                }
+                _ = tokio::signal::ctrl_c() => {
+                    let logout = self.encode(&AdminMessage::Logout { text: Some("Client closed".to_string()) });
+                    stream.write_all(&logout).await?;
+                    return Ok(Closed::Interrupted);
+                }
+            }
+        }
+    }
+}
+
+impl Default for PersistentSessionHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Removes the first complete message, ending with its CheckSum (10), from
+/// the front of `buffer`
+fn take_message(buffer: &mut Vec<u8>) -> Option<Vec<u8>> {
+    let checksum = buffer.windows(4).position(|window| window == b"\x0110=")? + 1;
+    let end = checksum + buffer[checksum..].iter().position(|byte| *byte == 0x01)?;
+    Some(buffer.drain(..=end).collect())
+}
+
+impl Handler for PersistentSessionHandler {
+    fn handle(&mut self) -> Result<(), String> {
+        let read = |prompt: &str| read_line(prompt).map_err(|e| format!("Failed to read input: {}", e));
+        let sender = read("\nSenderCompID [ROMER]:")?;
+        if !sender.is_empty() {
+            self.sender_comp_id = sender;
+        }
+        let target = read("TargetCompID [MARKET]:")?;
+        if !target.is_empty() {
+            self.target_comp_id = target;
+        }
+        let symbols: Vec<String> = read("Symbols to subscribe to, comma separated (blank for none):")?
+            .split(',')
+            .map(|symbol| symbol.trim().to_string())
+            .filter(|symbol| !symbol.is_empty())
+            .collect();
+        if !symbols.is_empty() {
+            self.subscription = Some((format!("MD{}", Uuid::new_v4().simple()), symbols));
+        }
+
+        println!("\nConnecting to {}, press Ctrl-C to log out", self.address);
+        let runtime = tokio::runtime::Runtime::new().map_err(|e| format!("Failed to create runtime: {}", e))?;
+        runtime.block_on(self.run())?;
+        println!("\nSession closed");
+        Ok(())
+    }
+}
+
@@ -0,0 +1,64 @@
+use serde_json::{json, Value};
+
+use crate::handlers::keymanager::read_line;
+use crate::handlers::Handler;
+use crate::rpc::RpcClient;
+
+// Asks the sequencer to push a day's reconciled net amounts to its
+// configured settlement adapter and shows what was submitted
+pub struct SettleHandler {
+    rpc: RpcClient,
+}
+
+impl SettleHandler {
+    pub fn new() -> Self {
+        Self {
+            rpc: RpcClient::from_env(),
+        }
+    }
+}
+
+impl Default for SettleHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Handler for SettleHandler {
+    fn handle(&mut self) -> Result<(), String> {
+        let day = read_line("\nTrading day to settle, YYYY-MM-DD (blank for the latest reconciled):")
+            .map_err(|e| format!("Failed to read day: {}", e))?;
+        let params = if day.is_empty() { json!({}) } else { json!({ "day": day }) };
+
+        let runtime = tokio::runtime::Runtime::new().map_err(|e| format!("Failed to create runtime: {}", e))?;
+        let outcome: Value = runtime
+            .block_on(self.rpc.call("admin_settle", params))
+            .map_err(|e| format!("Settlement failed: {}", e))?;
+
+        let empty = Vec::new();
+        let submitted = outcome["submitted"].as_array().unwrap_or(&empty);
+        println!("\nSubmitted {} instruction(s):", submitted.len());
+        for receipt in submitted {
+            let instruction = &receipt["instruction"];
+            println!(
+                "  {:<24} {:>20} {:<6} via {} ({})",
+                instruction["id"].as_str().unwrap_or_default(),
+                instruction["amount"],
+                instruction["asset"].as_str().unwrap_or_default(),
+                receipt["adapter"].as_str().unwrap_or_default(),
+                receipt["reference"].as_str().unwrap_or_default()
+            );
+        }
+        for id in outcome["already_settled"].as_array().unwrap_or(&empty) {
+            println!("  {} already settled", id.as_str().unwrap_or_default());
+        }
+        for failure in outcome["failed"].as_array().unwrap_or(&empty) {
+            println!(
+                "  {} failed: {}",
+                failure["firm"].as_str().unwrap_or_default(),
+                failure["reason"].as_str().unwrap_or_default()
+            );
+        }
+        Ok(())
+    }
+}
@@ -1,4 +1,6 @@
 mod handlers;
+mod rpc;
+mod signer;
 
 use crossterm::{
     event::{self, Event, KeyCode, KeyEvent},
@@ -6,8 +8,10 @@ use crossterm::{
     ExecutableCommand,
 };
 use handlers::{
-    CheckKeysHandler, CreateSessionKeyHandler, GenerateKeypairHandler, Handler, HeartbeatHandler, LogonHandler, LogoutHandler, RegisterSenderCompIdHandler, SignMessageHandler
+    AdminTokenHandler, CheckKeysHandler, CreateOrganizationHandler, CreateSessionKeyHandler, FaucetHandler, GenerateKeypairHandler, Handler, HeartbeatHandler, LogonHandler, LogoutHandler, OnboardingHandler, PersistentSessionHandler, RegisterSenderCompIdHandler, SelectSignerHandler, SettleHandler, SignMessageHandler, UpdateOrganizationHandler, VerifySignatureHandler, ViewOrganizationHandler
 };
+use romer_common::keystore::unlock::KeyCache;
+use signer::SignerSelection;
 use std::io::{self, stdout, Write};
 
 // Represents which menu we're currently displaying
@@ -71,6 +75,10 @@ fn get_user_input() -> io::Result<Option<String>> {
 
 fn main() -> io::Result<()> {
     let mut current_menu = CurrentMenu::Main;
+    // Decrypted keys shared by every handler until the unlock times out
+    let keys = KeyCache::from_env();
+    // Local keystore or Ledger, for Ed25519 signatures
+    let signers = SignerSelection::default();
 
     // Clear screen at startup
     clear_screen()?;
@@ -83,7 +91,8 @@ fn main() -> io::Result<()> {
                 println!("2. Sequencer");
                 println!("3. Move VM");
                 println!("4. State");
-                println!("5. Exit");
+                println!("5. Onboarding Wizard");
+                println!("6. Exit");
                 println!("\nPress ESC at any time to return to the previous menu");
 
                 match get_user_input()? {
@@ -104,7 +113,18 @@ fn main() -> io::Result<()> {
                             current_menu = CurrentMenu::StateMenu;
                             clear_screen()?;
                         }
-                        "5" => break,
+                        "5" => match OnboardingHandler::new(keys.clone()) {
+                            Ok(mut handler) => {
+                                if let Err(e) = handler.handle() {
+                                    println!("Onboarding stopped: {}", e);
+                                }
+                                println!("\nPress Enter to continue...");
+                                get_user_input()?;
+                                clear_screen()?;
+                            }
+                            Err(e) => println!("Error creating key manager: {}", e),
+                        },
+                        "6" => break,
                         _ => println!("Invalid option, please try again"),
                     },
                     None => continue, // ESC pressed, stay in current menu
@@ -117,12 +137,16 @@ fn main() -> io::Result<()> {
                 println!("2. Generate KeyPair");
                 println!("3. Sign a Message");
                 println!("4. Create a Session Key");
-                println!("5. Back to Main Menu");
+                println!("5. Lock Keys");
+                println!("6. Select Signer");
+                println!("7. Verify a Signature");
+                println!("8. Create an Admin Token");
+                println!("9. Back to Main Menu");
                 println!("\nPress ESC at any time to return to the previous menu");
 
                 match get_user_input()? {
                     Some(input) => match input.as_str() {
-                        "1" => match CheckKeysHandler::new() {
+                        "1" => match CheckKeysHandler::new(keys.clone()) {
                             Ok(mut handler) => {
                                 if let Err(e) = handler.handle() {
                                     println!("Error checking keys: {}", e);
@@ -133,7 +157,7 @@ fn main() -> io::Result<()> {
                             }
                             Err(e) => println!("Error creating key manager: {}", e),
                         },
-                        "2" => match GenerateKeypairHandler::new() {
+                        "2" => match GenerateKeypairHandler::new(keys.clone()) {
                             Ok(mut handler) => {
                                 if let Err(e) = handler.handle() {
                                     println!("Error generating keypair: {}", e);
@@ -144,7 +168,7 @@ fn main() -> io::Result<()> {
                             }
                             Err(e) => println!("Error creating key manager: {}", e),
                         },
-                        "3" => match SignMessageHandler::new() {
+                        "3" => match SignMessageHandler::new(keys.clone(), signers.clone()) {
                             Ok(mut handler) => {
                                 if let Err(e) = handler.handle() {
                                     println!("Error signing message: {}", e);
@@ -155,7 +179,7 @@ fn main() -> io::Result<()> {
                             }
                             Err(e) => println!("Error creating key manager: {}", e),
                         },
-                        "4" => match CreateSessionKeyHandler::new() {
+                        "4" => match CreateSessionKeyHandler::new(keys.clone()) {
                             Ok(mut handler) => {
                                 if let Err(e) = handler.handle() {
                                     println!("Error creating session key: {}", e);
@@ -167,6 +191,39 @@ fn main() -> io::Result<()> {
                             Err(e) => println!("Error creating key manager: {}", e),
                         },
                         "5" => {
+                            keys.lock();
+                            println!("Keys locked");
+                        }
+                        "6" => {
+                            let mut handler = SelectSignerHandler::new(signers.clone());
+                            if let Err(e) = handler.handle() {
+                                println!("Error selecting signer: {}", e);
+                            }
+                            println!("\nPress Enter to continue...");
+                            get_user_input()?;
+                            clear_screen()?;
+                        }
+                        "7" => {
+                            let mut handler = VerifySignatureHandler::new();
+                            if let Err(e) = handler.handle() {
+                                println!("Error verifying signature: {}", e);
+                            }
+                            println!("\nPress Enter to continue...");
+                            get_user_input()?;
+                            clear_screen()?;
+                        }
+                        "8" => match AdminTokenHandler::new(keys.clone(), signers.clone()) {
+                            Ok(mut handler) => {
+                                if let Err(e) = handler.handle() {
+                                    println!("Error creating admin token: {}", e);
+                                }
+                                println!("\nPress Enter to continue...");
+                                get_user_input()?;
+                                clear_screen()?;
+                            }
+                            Err(e) => println!("Error creating key manager: {}", e),
+                        },
+                        "9" => {
                             current_menu = CurrentMenu::Main;
                             clear_screen()?;
                         }
@@ -220,7 +277,8 @@ fn main() -> io::Result<()> {
                 println!("2. Logon");
                 println!("3. Logout");
                 println!("4. Heartbeat");
-                println!("5. Back to FIX Menu");
+                println!("5. Persistent Session");
+                println!("6. Back to FIX Menu");
                 println!("\nPress ESC at any time to return to the previous menu");
 
                 match get_user_input()? {
@@ -271,6 +329,15 @@ fn main() -> io::Result<()> {
                             clear_screen()?;
                         }
                         "5" => {
+                            let mut handler = PersistentSessionHandler::new();
+                            if let Err(e) = handler.handle() {
+                                println!("Persistent session stopped: {}", e);
+                            }
+                            println!("\nPress Enter to continue...");
+                            get_user_input()?;
+                            clear_screen()?;
+                        }
+                        "6" => {
                             current_menu = CurrentMenu::Sequencer;
                             clear_screen()?;
                         }
@@ -319,7 +386,10 @@ fn main() -> io::Result<()> {
                 match get_user_input()? {
                     Some(input) => match input.as_str() {
                         "1" => {
-                            println!("Settle selected - functionality coming soon!");
+                            let mut handler = SettleHandler::new();
+                            if let Err(e) = handler.handle() {
+                                println!("Error settling: {}", e);
+                            }
                             println!("\nPress Enter to continue...");
                             get_user_input()?;
                             clear_screen()?;
@@ -368,7 +438,11 @@ fn main() -> io::Result<()> {
                 println!("\nState Menu:");
                 println!("1. List Organizations");
                 println!("2. List Tokens");
-                println!("3. Back to Last Menu");
+                println!("3. Create Organization");
+                println!("4. View Organization");
+                println!("5. Update Organization");
+                println!("6. Request Test RØMER");
+                println!("7. Back to Last Menu");
 
                 match get_user_input()? {
                     Some(input) => match input.as_str() {
@@ -384,7 +458,47 @@ fn main() -> io::Result<()> {
                             get_user_input()?;
                             clear_screen()?;
                         }
-                        "3" => {
+                        "3" => match CreateOrganizationHandler::new(keys.clone()) {
+                            Ok(mut handler) => {
+                                if let Err(e) = handler.handle() {
+                                    println!("Error creating organization: {}", e);
+                                }
+                                println!("\nPress Enter to continue...");
+                                get_user_input()?;
+                                clear_screen()?;
+                            }
+                            Err(e) => println!("Error creating key manager: {}", e),
+                        },
+                        "4" => {
+                            let mut handler = ViewOrganizationHandler::new();
+                            if let Err(e) = handler.handle() {
+                                println!("Error viewing organization: {}", e);
+                            }
+                            println!("\nPress Enter to continue...");
+                            get_user_input()?;
+                            clear_screen()?;
+                        }
+                        "5" => match UpdateOrganizationHandler::new(keys.clone()) {
+                            Ok(mut handler) => {
+                                if let Err(e) = handler.handle() {
+                                    println!("Error updating organization: {}", e);
+                                }
+                                println!("\nPress Enter to continue...");
+                                get_user_input()?;
+                                clear_screen()?;
+                            }
+                            Err(e) => println!("Error creating key manager: {}", e),
+                        },
+                        "6" => {
+                            let mut handler = FaucetHandler::new();
+                            if let Err(e) = handler.handle() {
+                                println!("Error requesting test RØMER: {}", e);
+                            }
+                            println!("\nPress Enter to continue...");
+                            get_user_input()?;
+                            clear_screen()?;
+                        }
+                        "7" => {
                             current_menu = CurrentMenu::Main;
                             clear_screen()?;
                         }
@@ -400,6 +514,7 @@ fn main() -> io::Result<()> {
     }
 
     // Clear screen before exiting
+    keys.lock();
     clear_screen()?;
     println!("Goodbye!");
     Ok(())
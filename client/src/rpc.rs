@@ -0,0 +1,89 @@
+// Minimal JSON-RPC 2.0 client for the sequencer's HTTP endpoint
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::io;
+use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+#[derive(Error, Debug)]
+pub enum RpcClientError {
+    #[error("Connection error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Malformed response: {0}")]
+    Malformed(String),
+
+    #[error("RPC error {code}: {message}")]
+    Rpc { code: i64, message: String },
+}
+
+/// Sends requests to the sequencer's JSON-RPC endpoint, one connection per
+/// call
+pub struct RpcClient {
+    address: String,
+}
+
+impl RpcClient {
+    pub fn new(address: impl Into<String>) -> Self {
+        Self {
+            address: address.into(),
+        }
+    }
+
+    /// Endpoint from `ROMER_SEQUENCER_RPC`, the local sequencer by default
+    pub fn from_env() -> Self {
+        Self::new(std::env::var("ROMER_SEQUENCER_RPC").unwrap_or_else(|_| "127.0.0.1:9879".to_string()))
+    }
+
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Calls `method` and decodes its result
+    pub async fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T, RpcClientError> {
+        let body = json!({ "jsonrpc": "2.0", "method": method, "params": params, "id": 1 }).to_string();
+        let request = format!(
+            "POST / HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            self.address,
+            body.len(),
+            body
+        );
+
+        let stream = TcpStream::connect(&self.address).await?;
+        let (reader, mut writer) = stream.into_split();
+        writer.write_all(request.as_bytes()).await?;
+
+        // The sequencer keeps connections alive, so read exactly one response
+        let mut reader = BufReader::new(reader);
+        let mut content_length = 0usize;
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header).await? == 0 {
+                return Err(RpcClientError::Malformed("connection closed in headers".into()));
+            }
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap_or(0);
+                }
+            }
+        }
+        let mut body = vec![0u8; content_length];
+        reader.read_exact(&mut body).await?;
+
+        let mut response: Value =
+            serde_json::from_slice(&body).map_err(|e| RpcClientError::Malformed(e.to_string()))?;
+
+        if let Some(error) = response.get("error") {
+            return Err(RpcClientError::Rpc {
+                code: error["code"].as_i64().unwrap_or_default(),
+                message: error["message"].as_str().unwrap_or_default().to_string(),
+            });
+        }
+        serde_json::from_value(response["result"].take()).map_err(|e| RpcClientError::Malformed(e.to_string()))
+    }
+}
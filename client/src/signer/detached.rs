@@ -0,0 +1,47 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use commonware_cryptography::{Bls12381, Ed25519, PublicKey, Scheme, Signature};
+use commonware_utils::{from_hex, hex};
+use romer_common::types::keymanager::SignatureScheme;
+
+use super::SignerError;
+
+/// Namespace of free-form message signatures, distinct from the namespaces
+/// used for transactions and session keys
+pub const MESSAGE_NAMESPACE: &[u8] = &[];
+
+/// Text encoding of a detached signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureEncoding {
+    Hex,
+    Base64,
+}
+
+pub fn encode(signature: &[u8], encoding: SignatureEncoding) -> String {
+    match encoding {
+        SignatureEncoding::Hex => hex(signature),
+        SignatureEncoding::Base64 => STANDARD.encode(signature),
+    }
+}
+
+/// Decodes a hex or base64 signature. Text that is valid hex is read as hex.
+pub fn decode(text: &str) -> Result<Vec<u8>, SignerError> {
+    let text = text.trim();
+    let text = text.strip_prefix("0x").unwrap_or(text);
+    if let Some(bytes) = from_hex(text) {
+        return Ok(bytes);
+    }
+    STANDARD
+        .decode(text)
+        .map_err(|_| SignerError::Encoding("Signature is neither hex nor base64".into()))
+}
+
+/// Checks a detached signature over `message` made with `MESSAGE_NAMESPACE`
+pub fn verify(scheme: SignatureScheme, message: &[u8], public_key: &[u8], signature: &[u8]) -> bool {
+    let public_key = PublicKey::from(public_key.to_vec());
+    let signature = Signature::from(signature.to_vec());
+    match scheme {
+        SignatureScheme::Ed25519 => Ed25519::verify(Some(MESSAGE_NAMESPACE), message, &public_key, &signature),
+        SignatureScheme::Bls12381 => Bls12381::verify(Some(MESSAGE_NAMESPACE), message, &public_key, &signature),
+    }
+}
@@ -0,0 +1,133 @@
+use commonware_utils::union_unique;
+use ledger_transport_hid::hidapi::HidApi;
+use ledger_transport_hid::{LedgerHIDError, TransportNativeHID};
+use ledger_apdu::APDUCommand;
+use romer_common::types::keymanager::SignatureScheme;
+
+use super::{Signer, SignerError};
+
+/// APDU class of the Rømer Ledger app
+const CLA: u8 = 0xE0;
+const INS_GET_PUBLIC_KEY: u8 = 0x02;
+const INS_SIGN: u8 = 0x04;
+
+/// P1 of `INS_GET_PUBLIC_KEY`: show the address on the device for approval
+const P1_CONFIRM: u8 = 0x01;
+const P1_SILENT: u8 = 0x00;
+
+/// P1 of `INS_SIGN` chunks
+const P1_FIRST: u8 = 0x00;
+const P1_MORE: u8 = 0x80;
+/// P2 of `INS_SIGN` chunks
+const P2_LAST: u8 = 0x00;
+const P2_MORE: u8 = 0x80;
+
+/// Largest APDU payload
+const CHUNK_SIZE: usize = 255;
+
+const SW_OK: u16 = 0x9000;
+const SW_DENIED: u16 = 0x6985;
+
+/// SLIP-44 coin type used in Rømer derivation paths
+const COIN_TYPE: u32 = 1001;
+const HARDENED: u32 = 0x8000_0000;
+
+/// Ed25519 signer backed by a Ledger device running the Rømer app. Keys are
+/// derived on the device at `m/44'/1001'/<account>'/0'/0'` and never leave
+/// it; every signature is approved on the device screen.
+pub struct LedgerSigner {
+    transport: TransportNativeHID,
+    account: u32,
+    public_key: Option<Vec<u8>>,
+}
+
+impl LedgerSigner {
+    /// Connects to the first Ledger device found
+    pub fn connect(account: u32) -> Result<Self, SignerError> {
+        let api = HidApi::new().map_err(|e| SignerError::Ledger(e.to_string()))?;
+        let transport = TransportNativeHID::new(&api).map_err(|e| SignerError::Ledger(e.to_string()))?;
+        Ok(Self {
+            transport,
+            account,
+            public_key: None,
+        })
+    }
+
+    /// Asks the device to display the public key for the user to compare
+    /// against what the host shows
+    pub fn confirm_public_key(&mut self) -> Result<Vec<u8>, SignerError> {
+        let public_key = self.exchange(INS_GET_PUBLIC_KEY, P1_CONFIRM, 0, self.path())?;
+        self.public_key = Some(public_key.clone());
+        Ok(public_key)
+    }
+
+    /// BIP32 path, serialized as a component count followed by big-endian
+    /// components. Ed25519 derivation only supports hardened components.
+    fn path(&self) -> Vec<u8> {
+        let components = [44, COIN_TYPE, self.account, 0, 0];
+        let mut path = vec![components.len() as u8];
+        for component in components {
+            path.extend_from_slice(&(component | HARDENED).to_be_bytes());
+        }
+        path
+    }
+
+    fn exchange(&self, ins: u8, p1: u8, p2: u8, data: Vec<u8>) -> Result<Vec<u8>, SignerError> {
+        let command = APDUCommand {
+            cla: CLA,
+            ins,
+            p1,
+            p2,
+            data,
+        };
+        let answer = self
+            .transport
+            .exchange(&command)
+            .map_err(|e: LedgerHIDError| SignerError::Ledger(e.to_string()))?;
+        match answer.retcode() {
+            SW_OK => Ok(answer.data().to_vec()),
+            SW_DENIED => Err(SignerError::Rejected),
+            code => Err(SignerError::Ledger(format!("Device returned status {:04x}", code))),
+        }
+    }
+}
+
+impl Signer for LedgerSigner {
+    fn describe(&self) -> String {
+        format!("Ledger account {}", self.account)
+    }
+
+    fn scheme(&self) -> SignatureScheme {
+        SignatureScheme::Ed25519
+    }
+
+    fn public_key(&mut self) -> Result<Vec<u8>, SignerError> {
+        if let Some(public_key) = &self.public_key {
+            return Ok(public_key.clone());
+        }
+        let public_key = self.exchange(INS_GET_PUBLIC_KEY, P1_SILENT, 0, self.path())?;
+        self.public_key = Some(public_key.clone());
+        Ok(public_key)
+    }
+
+    fn sign(&mut self, namespace: Option<&[u8]>, message: &[u8]) -> Result<Vec<u8>, SignerError> {
+        // The device signs exactly what commonware's Ed25519 would, so the
+        // signature verifies like one made with a local key
+        let payload = match namespace {
+            Some(namespace) => union_unique(namespace, message),
+            None => message.to_vec(),
+        };
+
+        // The first chunk carries the derivation path, the rest the payload
+        let mut data = self.path();
+        data.extend_from_slice(&payload);
+        let chunks: Vec<&[u8]> = data.chunks(CHUNK_SIZE).collect();
+        let mut signature = Vec::new();
+        for (index, chunk) in chunks.iter().enumerate() {
+            let p1 = if index == 0 { P1_FIRST } else { P1_MORE };
+            let p2 = if index + 1 == chunks.len() { P2_LAST } else { P2_MORE };
+            signature = self.exchange(INS_SIGN, p1, p2, chunk.to_vec())?;
+        }
+        Ok(signature)
+    }
+}
@@ -0,0 +1,51 @@
+use commonware_cryptography::{Bls12381, Ed25519, PrivateKey, Scheme};
+use romer_common::keystore::keymanager::KeyManager;
+use romer_common::keystore::unlock::KeyCache;
+use romer_common::types::keymanager::{KeyManagerError, SignatureScheme};
+
+use super::{Signer, SignerError};
+
+/// Signs with a permanent key from the local keystore. The keystore must
+/// already be unlocked.
+pub struct LocalSigner {
+    key_manager: KeyManager,
+    keys: KeyCache,
+    scheme: SignatureScheme,
+}
+
+impl LocalSigner {
+    pub fn new(key_manager: KeyManager, keys: KeyCache, scheme: SignatureScheme) -> Self {
+        Self {
+            key_manager,
+            keys,
+            scheme,
+        }
+    }
+}
+
+impl Signer for LocalSigner {
+    fn describe(&self) -> String {
+        format!("Local {:?} key", self.scheme)
+    }
+
+    fn scheme(&self) -> SignatureScheme {
+        self.scheme
+    }
+
+    fn public_key(&mut self) -> Result<Vec<u8>, SignerError> {
+        Ok(self.key_manager.permanent_public_key(self.scheme)?)
+    }
+
+    fn sign(&mut self, namespace: Option<&[u8]>, message: &[u8]) -> Result<Vec<u8>, SignerError> {
+        let signature = self.keys.with_key(self.scheme, |key_bytes| {
+            let private_key = PrivateKey::from(key_bytes.to_vec());
+            match self.scheme {
+                SignatureScheme::Ed25519 => <Ed25519 as Scheme>::from(private_key)
+                    .map(|mut signer| signer.sign(namespace, message).to_vec()),
+                SignatureScheme::Bls12381 => <Bls12381 as Scheme>::from(private_key)
+                    .map(|mut signer| signer.sign(namespace, message).to_vec()),
+            }
+        })?;
+        signature.ok_or_else(|| KeyManagerError::InvalidKeyFormat(format!("Invalid {:?} key", self.scheme)).into())
+    }
+}
@@ -0,0 +1,95 @@
+// Signing backends the client can sign with
+use romer_common::types::envelope::{SignedTransaction, UnsignedTransaction, TRANSACTION_NAMESPACE};
+use romer_common::types::keymanager::{KeyManagerError, SignatureScheme};
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+
+pub mod detached;
+pub mod ledger;
+pub mod local;
+
+pub use ledger::LedgerSigner;
+pub use local::LocalSigner;
+
+#[derive(Error, Debug)]
+pub enum SignerError {
+    #[error("Key error: {0}")]
+    Key(#[from] KeyManagerError),
+
+    #[error("Ledger error: {0}")]
+    Ledger(String),
+
+    #[error("Request rejected on device")]
+    Rejected,
+
+    #[error("{0:?} is not supported by this signer")]
+    UnsupportedScheme(SignatureScheme),
+
+    #[error("Failed to encode transaction: {0}")]
+    Encoding(String),
+}
+
+/// Something that holds a private key and signs with it, whether the key
+/// lives on the host or on a hardware wallet
+pub trait Signer {
+    /// Short description shown in menus
+    fn describe(&self) -> String;
+
+    fn scheme(&self) -> SignatureScheme;
+
+    fn public_key(&mut self) -> Result<Vec<u8>, SignerError>;
+
+    /// Signs `message` under `namespace`, producing a signature that
+    /// verifies with the commonware scheme of `self.scheme()`
+    fn sign(&mut self, namespace: Option<&[u8]>, message: &[u8]) -> Result<Vec<u8>, SignerError>;
+
+    /// Signs a transaction envelope
+    fn sign_transaction(
+        &mut self,
+        transaction: UnsignedTransaction,
+    ) -> Result<SignedTransaction, SignerError> {
+        let message = transaction
+            .signing_bytes()
+            .map_err(|e| SignerError::Encoding(e.to_string()))?;
+        let signature = self.sign(Some(TRANSACTION_NAMESPACE), &message)?;
+        Ok(SignedTransaction {
+            transaction,
+            scheme: self.scheme(),
+            public_key: self.public_key()?,
+            signature,
+        })
+    }
+}
+
+/// Where Ed25519 signatures come from, chosen in the KeyManager menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignerBackend {
+    /// Keys in the local encrypted keystore
+    Local,
+    /// A Ledger device running the Rømer app
+    Ledger { account: u32 },
+}
+
+/// Backend selection shared by the handlers
+#[derive(Clone)]
+pub struct SignerSelection {
+    backend: Arc<Mutex<SignerBackend>>,
+}
+
+impl Default for SignerSelection {
+    fn default() -> Self {
+        Self {
+            backend: Arc::new(Mutex::new(SignerBackend::Local)),
+        }
+    }
+}
+
+impl SignerSelection {
+    pub fn backend(&self) -> SignerBackend {
+        *self.backend.lock().unwrap()
+    }
+
+    pub fn select(&self, backend: SignerBackend) {
+        *self.backend.lock().unwrap() = backend;
+    }
+}
@@ -0,0 +1,134 @@
+use chrono::{DateTime, Utc};
+
+use crate::types::fix::utils;
+
+/// Session level (administrative) messages the sequencer originates
+#[derive(Debug, Clone, PartialEq)]
+pub enum AdminMessage {
+    /// Heartbeat (35=0), echoing the TestReqID when answering a TestRequest
+    Heartbeat { test_req_id: Option<String> },
+    /// TestRequest (35=1) probing a silent counterparty
+    TestRequest { test_req_id: String },
+    /// Logout (35=5), starting or confirming the logout handshake
+    Logout { text: Option<String> },
+    /// Logon (35=A) accepting a counterparty's Logon, with the sequence
+    /// number we expect from it next (789)
+    Logon {
+        heart_bt_int: u32,
+        reset_seq_num: bool,
+        next_expected_msg_seq_num: u64,
+    },
+    /// ResendRequest (35=2) for the counterparty's messages `begin..=end`
+    ResendRequest { begin: u64, end: u64 },
+    /// SequenceReset (35=4), with GapFillFlag (123) when skipping session
+    /// messages that are not resent
+    SequenceReset { new_seq_no: u64, gap_fill: bool },
+}
+
+impl AdminMessage {
+    pub fn msg_type(&self) -> &'static str {
+        match self {
+            Self::Heartbeat { .. } => "0",
+            Self::TestRequest { .. } => "1",
+            Self::Logout { .. } => "5",
+            Self::Logon { .. } => "A",
+            Self::ResendRequest { .. } => "2",
+            Self::SequenceReset { .. } => "4",
+        }
+    }
+
+    /// Encodes the message from `sender_comp_id` to `target_comp_id`
+    pub fn encode(
+        &self,
+        begin_string: &str,
+        sender_comp_id: &str,
+        target_comp_id: &str,
+        msg_seq_num: u64,
+        sending_time: DateTime<Utc>,
+    ) -> Vec<u8> {
+        let mut fields = vec![
+            (35, self.msg_type().to_string()),
+            (49, sender_comp_id.to_string()),
+            (56, target_comp_id.to_string()),
+            (34, msg_seq_num.to_string()),
+            (52, utils::format_timestamp(sending_time)),
+        ];
+        match self {
+            Self::Heartbeat { test_req_id: Some(id) } | Self::TestRequest { test_req_id: id } => {
+                fields.push((112, id.clone()));
+            }
+            Self::Logout { text: Some(text) } => fields.push((58, text.clone())),
+            Self::Logon {
+                heart_bt_int,
+                reset_seq_num,
+                next_expected_msg_seq_num,
+            } => {
+                fields.push((98, "0".to_string()));
+                fields.push((108, heart_bt_int.to_string()));
+                if *reset_seq_num {
+                    fields.push((141, "Y".to_string()));
+                }
+                fields.push((789, next_expected_msg_seq_num.to_string()));
+            }
+            Self::ResendRequest { begin, end } => {
+                fields.push((7, begin.to_string()));
+                fields.push((16, end.to_string()));
+            }
+            Self::SequenceReset { new_seq_no, gap_fill } => {
+                // Gap fills stand in for messages already sent
+                if *gap_fill {
+                    fields.push((43, "Y".to_string()));
+                    fields.push((123, "Y".to_string()));
+                }
+                fields.push((36, new_seq_no.to_string()));
+            }
+            Self::Heartbeat { test_req_id: None } | Self::Logout { text: None } => {}
+        }
+        utils::encode_message(begin_string, &fields)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heartbeat_echoes_test_req_id() {
+        let heartbeat = AdminMessage::Heartbeat {
+            test_req_id: Some("T1".into()),
+        }
+        .encode("FIX.4.2", "ROMER", "MM1", 3, Utc::now());
+        utils::validate_message(&heartbeat).unwrap();
+        let fields = utils::parse_message_fields(&heartbeat);
+        assert_eq!(fields.get(&35).map(String::as_str), Some("0"));
+        assert_eq!(fields.get(&112).map(String::as_str), Some("T1"));
+        assert_eq!(fields.get(&56).map(String::as_str), Some("MM1"));
+    }
+
+    #[test]
+    fn test_logout_carries_text() {
+        let logout = AdminMessage::Logout {
+            text: Some("end of day".into()),
+        }
+        .encode("FIX.4.2", "ROMER", "MM1", 7, Utc::now());
+        utils::validate_message(&logout).unwrap();
+        let fields = utils::parse_message_fields(&logout);
+        assert_eq!(fields.get(&35).map(String::as_str), Some("5"));
+        assert_eq!(fields.get(&58).map(String::as_str), Some("end of day"));
+    }
+
+    #[test]
+    fn test_logon_carries_next_expected() {
+        let logon = AdminMessage::Logon {
+            heart_bt_int: 30,
+            reset_seq_num: false,
+            next_expected_msg_seq_num: 12,
+        }
+        .encode("FIX.4.4", "ROMER", "MM1", 20, Utc::now());
+        utils::validate_message(&logon).unwrap();
+        let fields = utils::parse_message_fields(&logon);
+        assert_eq!(fields.get(&35).map(String::as_str), Some("A"));
+        assert_eq!(fields.get(&789).map(String::as_str), Some("12"));
+        assert!(!fields.contains_key(&141));
+    }
+}
@@ -0,0 +1,352 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::types::fix::utils;
+use crate::types::oracle::{format_price, parse_price};
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AllocationFixError {
+    #[error("Missing field {0}")]
+    MissingField(u32),
+
+    #[error("Invalid field {tag}: {reason}")]
+    InvalidField { tag: u32, reason: String },
+
+    #[error("Invalid allocation: {0}")]
+    Invalid(String),
+}
+
+/// Side (54) of the executions being allocated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AllocSide {
+    Buy,
+    Sell,
+}
+
+impl AllocSide {
+    pub fn from_fix(value: &str) -> Option<Self> {
+        match value {
+            "1" => Some(Self::Buy),
+            "2" => Some(Self::Sell),
+            _ => None,
+        }
+    }
+
+    pub fn to_fix(&self) -> &'static str {
+        match self {
+            Self::Buy => "1",
+            Self::Sell => "2",
+        }
+    }
+}
+
+/// One entry of the NoAllocs (78) group: shares given up to an account at
+/// a clearing firm
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AllocationEntry {
+    /// AllocAccount (79), the account at the clearing firm
+    pub account: String,
+    /// ClearingFirm (439), the SenderCompID of the clearing firm's session
+    pub clearing_firm: String,
+    /// AllocShares (80)
+    pub quantity: u64,
+}
+
+/// AllocationInstruction (35=J) from the executing firm, giving up its
+/// executions in a symbol to one or more clearing firms
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AllocationInstruction {
+    /// AllocID (70)
+    pub alloc_id: String,
+    pub side: AllocSide,
+    pub symbol: String,
+    /// Shares (53), the total allocated
+    pub quantity: u64,
+    /// AvgPx (6), in units of 10^-9
+    pub avg_price: u64,
+    /// TradeDate (75), YYYYMMDD
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trade_date: Option<String>,
+    pub allocations: Vec<AllocationEntry>,
+}
+
+impl AllocationInstruction {
+    /// Parses a new (AllocTransType 71=0) instruction. Each allocation
+    /// starts with AllocAccount (79).
+    pub fn parse(raw_data: &[u8]) -> Result<Self, AllocationFixError> {
+        let fields = ordered_fields(raw_data);
+        let field = |tag| {
+            fields
+                .iter()
+                .find(|(field, _)| *field == tag)
+                .map(|(_, value)| value.as_str())
+                .ok_or(AllocationFixError::MissingField(tag))
+        };
+        let invalid = |tag, reason: &str| AllocationFixError::InvalidField {
+            tag,
+            reason: reason.to_string(),
+        };
+        if let Ok(trans_type) = field(71) {
+            if trans_type != "0" {
+                return Err(invalid(71, "only new allocations are supported"));
+            }
+        }
+
+        let mut allocations: Vec<AllocationEntry> = Vec::new();
+        for (tag, value) in &fields {
+            match (*tag, allocations.last_mut()) {
+                (79, _) => allocations.push(AllocationEntry {
+                    account: value.clone(),
+                    clearing_firm: String::new(),
+                    quantity: 0,
+                }),
+                (439, Some(entry)) => entry.clearing_firm = value.clone(),
+                (80, Some(entry)) => {
+                    entry.quantity = value.parse().map_err(|_| invalid(80, "not a quantity"))?;
+                }
+                _ => {}
+            }
+        }
+        if let Some(entry) = allocations.iter().find(|entry| entry.clearing_firm.is_empty()) {
+            return Err(AllocationFixError::Invalid(format!(
+                "allocation to {} names no clearing firm",
+                entry.account
+            )));
+        }
+        let declared: usize = field(78)?.parse().map_err(|_| invalid(78, "not a count"))?;
+        if declared != allocations.len() {
+            return Err(invalid(
+                78,
+                &format!("declares {} allocations, {} sent", declared, allocations.len()),
+            ));
+        }
+
+        let instruction = Self {
+            alloc_id: field(70)?.to_string(),
+            side: AllocSide::from_fix(field(54)?).ok_or_else(|| invalid(54, "not buy or sell"))?,
+            symbol: field(55)?.to_string(),
+            quantity: field(53)?.parse().map_err(|_| invalid(53, "not a quantity"))?,
+            avg_price: parse_price(field(6)?).map_err(|e| invalid(6, &e.to_string()))?,
+            trade_date: field(75).ok().map(str::to_string),
+            allocations,
+        };
+        instruction.validate()?;
+        Ok(instruction)
+    }
+
+    /// Checks the allocations are nonzero and add up to the total
+    pub fn validate(&self) -> Result<(), AllocationFixError> {
+        let invalid = |reason: String| Err(AllocationFixError::Invalid(reason));
+        if self.alloc_id.is_empty() {
+            return invalid("empty AllocID".into());
+        }
+        if self.allocations.is_empty() {
+            return invalid("no allocations".into());
+        }
+        if self.allocations.iter().any(|entry| entry.quantity == 0) {
+            return invalid("allocation of 0 shares".into());
+        }
+        let allocated: u64 = self.allocations.iter().map(|entry| entry.quantity).sum();
+        if allocated != self.quantity {
+            return invalid(format!("allocations total {} of {} shares", allocated, self.quantity));
+        }
+        Ok(())
+    }
+
+    /// Encodes the instruction, forwarded from `sender_comp_id` to a
+    /// clearing firm
+    pub fn encode(
+        &self,
+        sender_comp_id: &str,
+        target_comp_id: &str,
+        msg_seq_num: u64,
+        sending_time: DateTime<Utc>,
+    ) -> Vec<u8> {
+        let mut fields = vec![
+            (35, "J".to_string()),
+            (49, sender_comp_id.to_string()),
+            (56, target_comp_id.to_string()),
+            (34, msg_seq_num.to_string()),
+            (52, utils::format_timestamp(sending_time)),
+            (70, self.alloc_id.clone()),
+            (71, "0".to_string()),
+            (54, self.side.to_fix().to_string()),
+            (55, self.symbol.clone()),
+            (53, self.quantity.to_string()),
+            (6, format_price(self.avg_price)),
+        ];
+        if let Some(trade_date) = &self.trade_date {
+            fields.push((75, trade_date.clone()));
+        }
+        fields.push((78, self.allocations.len().to_string()));
+        for entry in &self.allocations {
+            fields.push((79, entry.account.clone()));
+            fields.push((439, entry.clearing_firm.clone()));
+            fields.push((80, entry.quantity.to_string()));
+        }
+        utils::encode_message("FIX.4.2", &fields)
+    }
+}
+
+/// AllocStatus (87) of an AllocationACK
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AllocStatus {
+    Accepted,
+    BlockRejected,
+    AccountRejected,
+    Received,
+}
+
+impl AllocStatus {
+    pub fn from_fix(value: &str) -> Option<Self> {
+        match value {
+            "0" => Some(Self::Accepted),
+            "1" => Some(Self::BlockRejected),
+            "2" => Some(Self::AccountRejected),
+            "3" => Some(Self::Received),
+            _ => None,
+        }
+    }
+
+    pub fn to_fix(&self) -> &'static str {
+        match self {
+            Self::Accepted => "0",
+            Self::BlockRejected => "1",
+            Self::AccountRejected => "2",
+            Self::Received => "3",
+        }
+    }
+}
+
+/// AllocationACK (35=P): the sequencer acknowledging receipt to the
+/// executing firm, or a clearing firm accepting or rejecting a give-up
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AllocationAck {
+    pub alloc_id: String,
+    pub status: AllocStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+impl AllocationAck {
+    pub fn parse(raw_data: &[u8]) -> Result<Self, AllocationFixError> {
+        let fields = utils::parse_message_fields(raw_data);
+        let field = |tag| fields.get(&tag).ok_or(AllocationFixError::MissingField(tag));
+        Ok(Self {
+            alloc_id: field(70)?.clone(),
+            status: AllocStatus::from_fix(field(87)?).ok_or_else(|| AllocationFixError::InvalidField {
+                tag: 87,
+                reason: "unknown AllocStatus".into(),
+            })?,
+            text: fields.get(&58).cloned(),
+        })
+    }
+
+    pub fn encode(
+        &self,
+        sender_comp_id: &str,
+        target_comp_id: &str,
+        msg_seq_num: u64,
+        sending_time: DateTime<Utc>,
+    ) -> Vec<u8> {
+        let mut fields = vec![
+            (35, "P".to_string()),
+            (49, sender_comp_id.to_string()),
+            (56, target_comp_id.to_string()),
+            (34, msg_seq_num.to_string()),
+            (52, utils::format_timestamp(sending_time)),
+            (70, self.alloc_id.clone()),
+            (75, sending_time.format("%Y%m%d").to_string()),
+            (87, self.status.to_fix().to_string()),
+        ];
+        if let Some(text) = &self.text {
+            fields.push((58, text.clone()));
+        }
+        utils::encode_message("FIX.4.2", &fields)
+    }
+}
+
+/// Fields of `raw_data` in the order they appear, which repeating groups
+/// depend on
+fn ordered_fields(raw_data: &[u8]) -> Vec<(u32, String)> {
+    raw_data
+        .split(|&b| b == utils::delimiter(raw_data))
+        .filter_map(|field| {
+            let field = String::from_utf8_lossy(field);
+            let (tag, value) = field.split_once('=')?;
+            Some((tag.parse().ok()?, value.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instruction() -> AllocationInstruction {
+        AllocationInstruction {
+            alloc_id: "A1".into(),
+            side: AllocSide::Buy,
+            symbol: "AAPL".into(),
+            quantity: 300,
+            avg_price: parse_price("150.25").unwrap(),
+            trade_date: Some("20261015".into()),
+            allocations: vec![
+                AllocationEntry {
+                    account: "FUND-1".into(),
+                    clearing_firm: "CLR1".into(),
+                    quantity: 200,
+                },
+                AllocationEntry {
+                    account: "FUND-2".into(),
+                    clearing_firm: "CLR2".into(),
+                    quantity: 100,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_instruction_round_trip() {
+        let encoded = instruction().encode("EXEC1", "ROMER", 4, Utc::now());
+        utils::validate_message(&encoded).unwrap();
+        assert_eq!(AllocationInstruction::parse(&encoded).unwrap(), instruction());
+
+        let piped = "8=FIX.4.2|35=J|49=EXEC1|70=A2|71=0|54=2|55=MSFT|53=10|6=410|78=1|79=F|439=CLR1|80=10|";
+        let parsed = AllocationInstruction::parse(piped.as_bytes()).unwrap();
+        assert_eq!((parsed.side, parsed.allocations[0].quantity), (AllocSide::Sell, 10));
+    }
+
+    #[test]
+    fn test_instruction_checks() {
+        let short = "8=FIX.4.2|35=J|70=A2|54=1|55=MSFT|53=10|6=410|78=1|79=F|439=CLR1|80=9|";
+        assert!(matches!(
+            AllocationInstruction::parse(short.as_bytes()),
+            Err(AllocationFixError::Invalid(_))
+        ));
+        let miscounted = "8=FIX.4.2|35=J|70=A2|54=1|55=MSFT|53=10|6=410|78=2|79=F|439=CLR1|80=10|";
+        assert!(matches!(
+            AllocationInstruction::parse(miscounted.as_bytes()),
+            Err(AllocationFixError::InvalidField { tag: 78, .. })
+        ));
+        let no_firm = "8=FIX.4.2|35=J|70=A2|54=1|55=MSFT|53=10|6=410|78=1|79=F|80=10|";
+        assert!(AllocationInstruction::parse(no_firm.as_bytes()).is_err());
+        let cancel = "8=FIX.4.2|35=J|70=A2|71=2|54=1|55=MSFT|53=10|6=410|78=1|79=F|439=CLR1|80=10|";
+        assert!(AllocationInstruction::parse(cancel.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_ack_round_trip() {
+        let ack = AllocationAck {
+            alloc_id: "A1".into(),
+            status: AllocStatus::AccountRejected,
+            text: Some("unknown account FUND-2".into()),
+        };
+        let encoded = ack.encode("CLR2", "ROMER", 9, Utc::now());
+        utils::validate_message(&encoded).unwrap();
+        assert_eq!(AllocationAck::parse(&encoded).unwrap(), ack);
+    }
+}
@@ -1,5 +1,6 @@
+use crate::fix::session_logon::{self, LogonAuthError};
 use crate::types::fix::{utils, FixConfig, MessageType, ValidatedMessage};
-use chrono::Utc;
+use crate::types::keymanager::SessionKeyData;
 use rand::Rng;
 use std::collections::HashMap;
 use uuid::Uuid;
@@ -9,6 +10,8 @@ use uuid::Uuid;
 /// proper checksums, and realistic data to simulate production scenarios.
 pub struct FixMockGenerator {
     config: FixConfig,
+    /// Username (553) and Password (554) sent at Logon
+    credentials: Option<(String, String)>,
 }
 
 impl FixMockGenerator {
@@ -16,49 +19,80 @@ impl FixMockGenerator {
     /// This allows for consistent message generation with the same configuration
     /// without having to pass the config parameter to each mock method.
     pub fn new(config: FixConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            credentials: None,
+        }
+    }
+
+    /// Logons carry `username` and `password` in Username (553) and
+    /// Password (554)
+    pub fn with_credentials(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.credentials = Some((username.into(), password.into()));
+        self
+    }
+
+    /// Logon body fields: HeartBtInt, EncryptMethod and any credentials
+    fn logon_fields(&self) -> Vec<(u32, String)> {
+        let mut fields = vec![(108, "30".to_string()), (98, "0".to_string())];
+        if let Some((username, password)) = &self.credentials {
+            fields.push((session_logon::TAG_USERNAME, username.clone()));
+            fields.push((session_logon::TAG_PASSWORD, password.clone()));
+        }
+        fields
+    }
+    /// Standard header fields after BodyLength: MsgType, SenderCompID,
+    /// TargetCompID, MsgSeqNum and SendingTime
+    fn header(&self, msg_type: MessageType, msg_seq_num: u32) -> Vec<(u32, String)> {
+        vec![
+            (35, msg_type.to_fix().to_string()),
+            (49, self.config.sender_comp_id.clone()),
+            (56, self.config.target_comp_id.clone()),
+            (34, msg_seq_num.to_string()),
+            (52, utils::generate_timestamp()),
+        ]
+    }
+
+    /// Encodes `fields` (header and body) with BodyLength and CheckSum
+    fn message(&self, msg_type: MessageType, msg_seq_num: u32, fields: &[(u32, String)]) -> ValidatedMessage {
+        ValidatedMessage {
+            msg_type,
+            sender_comp_id: self.config.sender_comp_id.clone(),
+            target_comp_id: self.config.target_comp_id.clone(),
+            msg_seq_num,
+            raw_data: utils::encode_message(&format!("FIX.{}", self.config.fix_version), fields),
+        }
     }
+
     /// Creates a mock Logon message (35=A) used to initiate a FIX session.
     /// The Logon message includes essential session parameters like heartbeat
     /// interval and encryption method, along with the standard header fields.
-    ///
-    /// # Arguments
-    /// * `config` - The FIX configuration containing sender/target information
     pub fn mock_logon(&self) -> ValidatedMessage {
         let mut rng = rand::thread_rng();
         let msg_seq_num = rng.gen_range(1..100_000);
-        let timestamp = utils::generate_timestamp();
-
-        // Construct the message body with all required Logon fields:
-        // 8=FIX Version        - Begin string
-        // 9=Length            - Body length (calculated later)
-        // 35=A               - Message type (Logon)
-        // 49=SenderCompID    - Sender ID
-        // 56=TargetCompID    - Target ID
-        // 34=SeqNum          - Message sequence number
-        // 52=Time            - Sending time
-        // 108=30            - Heartbeat interval (30 seconds)
-        // 98=0              - Encryption method (none)
-        let msg = format!(
-            "8=FIX.{}|9=0|35=A|49={}|56={}|34={}|52={}|108=30|98=0|",
-            self.config.fix_version,
-            self.config.sender_comp_id,
-            self.config.target_comp_id,
-            msg_seq_num,
-            timestamp
-        );
 
-        // Calculate and append the message checksum (tag 10)
-        let raw_data =
-            format!("{}10={}|", msg, utils::calculate_checksum(msg.as_bytes())).into_bytes();
+        // 108=30 - Heartbeat interval (30 seconds)
+        // 98=0   - Encryption method (none)
+        let mut fields = self.header(MessageType::Logon, msg_seq_num);
+        fields.extend(self.logon_fields());
+        self.message(MessageType::Logon, msg_seq_num, &fields)
+    }
 
-        ValidatedMessage {
-            msg_type: MessageType::Logon,
-            sender_comp_id: self.config.sender_comp_id.clone(),
-            target_comp_id: self.config.target_comp_id.clone(),
-            msg_seq_num,
-            raw_data,
-        }
+    /// Creates a Logon (35=A) authenticated with a session key: the session
+    /// certificate fields and the session key's signature over the Logon are
+    /// appended to the standard Logon fields.
+    pub fn mock_session_key_logon(&self, session: &SessionKeyData) -> Result<ValidatedMessage, LogonAuthError> {
+        let mut rng = rand::thread_rng();
+        let msg_seq_num = rng.gen_range(1..100_000);
+
+        let mut fields = self.header(MessageType::Logon, msg_seq_num);
+        fields.extend(self.logon_fields());
+        fields.extend(session_logon::certificate_fields(session)?);
+        let signed: HashMap<u32, String> = fields.iter().cloned().collect();
+        let signature = session_logon::sign_logon(session, &signed)?;
+        fields.push((session_logon::TAG_LOGON_SIGNATURE, signature));
+
+        Ok(self.message(MessageType::Logon, msg_seq_num, &fields))
     }
 
     /// Creates a mock Logout message (35=5) used to terminate a FIX session.
@@ -66,61 +100,46 @@ impl FixMockGenerator {
     pub fn mock_logout(&self) -> ValidatedMessage {
         let mut rng = rand::thread_rng();
         let msg_seq_num = rng.gen_range(1..100_000);
-        let timestamp = utils::generate_timestamp();
-
-        let msg = format!(
-            "8=FIX.{}|9=0|35=5|49={}|56={}|34={}|52={}|58=Normal Logout|",
-            self.config.fix_version,
-            self.config.sender_comp_id,
-            self.config.target_comp_id,
-            msg_seq_num,
-            timestamp
-        );
 
-        let raw_data =
-            format!("{}10={}|", msg, utils::calculate_checksum(msg.as_bytes())).into_bytes();
-
-        ValidatedMessage {
-            msg_type: MessageType::Logout,
-            sender_comp_id: self.config.sender_comp_id.clone(),
-            target_comp_id: self.config.target_comp_id.clone(),
-            msg_seq_num,
-            raw_data,
-        }
+        let mut fields = self.header(MessageType::Logout, msg_seq_num);
+        fields.push((58, "Normal Logout".to_string()));
+        self.message(MessageType::Logout, msg_seq_num, &fields)
     }
 
     /// Creates a mock New Order Single message (35=D) representing a new trade order.
     /// Generates realistic order details including symbol, price, and quantity.
     pub fn mock_new_order_single(&self) -> ValidatedMessage {
         let mut rng = rand::thread_rng();
-        let msg_seq_num = rng.gen_range(1..100_000);
-        let timestamp = utils::generate_timestamp();
         let client_order_id = format!("ORDER{}", Uuid::new_v4().simple());
         let price: f64 = (rng.gen_range(10.0..100.0) * 100.0) / 100.0;
         let quantity = rng.gen_range(100..10_000);
+        self.mock_limit_order(&client_order_id, "AAPL", '1', quantity, price)
+    }
 
-        let msg = format!(
-            "8=FIX.{}|9=0|35=D|49={}|56={}|34={}|52={}|11={}|55=AAPL|54=1|38={}|40=2|44={}|59=0|",
-            self.config.fix_version,
-            self.config.sender_comp_id,
-            self.config.target_comp_id,
-            msg_seq_num,
-            timestamp,
-            client_order_id,
-            quantity,
-            price
-        );
-
-        let raw_data =
-            format!("{}10={}|", msg, utils::calculate_checksum(msg.as_bytes())).into_bytes();
+    /// Creates a day limit New Order Single (35=D) with the given ClOrdID,
+    /// symbol, side (54) and terms, for scripted order flows
+    pub fn mock_limit_order(
+        &self,
+        client_order_id: &str,
+        symbol: &str,
+        side: char,
+        quantity: u64,
+        price: f64,
+    ) -> ValidatedMessage {
+        let mut rng = rand::thread_rng();
+        let msg_seq_num = rng.gen_range(1..100_000);
 
-        ValidatedMessage {
-            msg_type: MessageType::NewOrderSingle,
-            sender_comp_id: self.config.sender_comp_id.clone(),
-            target_comp_id: self.config.target_comp_id.clone(),
-            msg_seq_num,
-            raw_data,
-        }
+        let mut fields = self.header(MessageType::NewOrderSingle, msg_seq_num);
+        fields.extend([
+            (11, client_order_id.to_string()),
+            (55, symbol.to_string()),
+            (54, side.to_string()),
+            (38, quantity.to_string()),
+            (40, "2".to_string()),
+            (44, price.to_string()),
+            (59, "0".to_string()),
+        ]);
+        self.message(MessageType::NewOrderSingle, msg_seq_num, &fields)
     }
 
     /// Creates a mock Market Data Request message (35=V) used to subscribe
@@ -128,29 +147,21 @@ impl FixMockGenerator {
     pub fn mock_market_data_request(&self) -> ValidatedMessage {
         let mut rng = rand::thread_rng();
         let msg_seq_num = rng.gen_range(1..100_000);
-        let timestamp = utils::generate_timestamp();
         let request_id = format!("REQ{}", Uuid::new_v4().simple());
 
-        let msg = format!(
-            "8=FIX.{}|9=0|35=V|49={}|56={}|34={}|52={}|262={}|263=1|264=0|267=2|269=0|269=1|146=2|55=AAPL|55=GOOGL|",
-            self.config.fix_version,
-            self.config.sender_comp_id,
-            self.config.target_comp_id,
-            msg_seq_num,
-            timestamp,
-            request_id
-        );
-
-        let raw_data =
-            format!("{}10={}|", msg, utils::calculate_checksum(msg.as_bytes())).into_bytes();
-
-        ValidatedMessage {
-            msg_type: MessageType::MarketDataRequest,
-            sender_comp_id: self.config.sender_comp_id.clone(),
-            target_comp_id: self.config.target_comp_id.clone(),
-            msg_seq_num,
-            raw_data,
-        }
+        let mut fields = self.header(MessageType::MarketDataRequest, msg_seq_num);
+        fields.extend([
+            (262, request_id),
+            (263, "1".to_string()),
+            (264, "0".to_string()),
+            (267, "2".to_string()),
+            (269, "0".to_string()),
+            (269, "1".to_string()),
+            (146, "2".to_string()),
+            (55, "AAPL".to_string()),
+            (55, "GOOGL".to_string()),
+        ]);
+        self.message(MessageType::MarketDataRequest, msg_seq_num, &fields)
     }
 
     /// Creates a mock Heartbeat message (35=0) used to maintain session activity
@@ -158,26 +169,29 @@ impl FixMockGenerator {
     pub fn mock_heartbeat(&self) -> ValidatedMessage {
         let mut rng = rand::thread_rng();
         let msg_seq_num = rng.gen_range(1..100_000);
-        let timestamp = utils::generate_timestamp();
 
-        let msg = format!(
-            "8=FIX.{}|9=0|35=0|49={}|56={}|34={}|52={}|",
-            self.config.fix_version,
-            self.config.sender_comp_id,
-            self.config.target_comp_id,
-            msg_seq_num,
-            timestamp
-        );
-
-        let raw_data =
-            format!("{}10={}|", msg, utils::calculate_checksum(msg.as_bytes())).into_bytes();
+        let fields = self.header(MessageType::Heartbeat, msg_seq_num);
+        self.message(MessageType::Heartbeat, msg_seq_num, &fields)
+    }
+}
 
-        ValidatedMessage {
-            msg_type: MessageType::Heartbeat,
-            sender_comp_id: self.config.sender_comp_id.clone(),
-            target_comp_id: self.config.target_comp_id.clone(),
-            msg_seq_num,
-            raw_data,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_messages_are_well_formed() {
+        let generator = FixMockGenerator::new(FixConfig::default());
+        for message in [
+            generator.mock_logon(),
+            generator.mock_logout(),
+            generator.mock_new_order_single(),
+            generator.mock_market_data_request(),
+            generator.mock_heartbeat(),
+        ] {
+            utils::validate_message(&message.raw_data).unwrap();
+            let fields = utils::parse_message_fields(&message.raw_data);
+            assert_eq!(fields.get(&35).map(String::as_str), Some(message.msg_type.to_fix()));
         }
     }
 }
@@ -1 +1,6 @@
-pub mod mock;
\ No newline at end of file
+pub mod admin;
+pub mod allocation;
+pub mod mock;
+pub mod oracle;
+pub mod reconnect;
+pub mod session_logon;
@@ -0,0 +1,51 @@
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::types::oracle::{parse_price, PriceSubmission, SignedPriceSubmission};
+
+/// User-defined tags carrying a feeder's signed price in a Market Data
+/// Snapshot (35=W), alongside Symbol (55) and MDEntryPx (270)
+pub const TAG_ORACLE_PUBLIC_KEY: u32 = 20110;
+pub const TAG_ORACLE_SIGNATURE: u32 = 20111;
+/// When the feeder observed the price, unix milliseconds
+pub const TAG_ORACLE_OBSERVED_AT: u32 = 20112;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum OracleFixError {
+    #[error("Missing field {0}")]
+    MissingField(u32),
+
+    #[error("Invalid field {tag}: {reason}")]
+    InvalidField { tag: u32, reason: String },
+}
+
+/// Whether a snapshot carries a signed oracle price rather than market data
+pub fn is_price_submission(fields: &HashMap<u32, String>) -> bool {
+    fields.contains_key(&TAG_ORACLE_SIGNATURE)
+}
+
+/// The signed price a snapshot carries. The signature is checked by the
+/// oracle, not here.
+pub fn price_submission(fields: &HashMap<u32, String>) -> Result<SignedPriceSubmission, OracleFixError> {
+    let field = |tag| fields.get(&tag).ok_or(OracleFixError::MissingField(tag));
+    let price = parse_price(field(270)?).map_err(|e| OracleFixError::InvalidField {
+        tag: 270,
+        reason: e.to_string(),
+    })?;
+    let observed_at_ms = field(TAG_ORACLE_OBSERVED_AT)?
+        .parse()
+        .map_err(|_| OracleFixError::InvalidField {
+            tag: TAG_ORACLE_OBSERVED_AT,
+            reason: "not unix milliseconds".into(),
+        })?;
+    Ok(SignedPriceSubmission {
+        submission: PriceSubmission {
+            symbol: field(55)?.clone(),
+            price,
+            observed_at_ms,
+        },
+        public_key: field(TAG_ORACLE_PUBLIC_KEY)?.clone(),
+        signature: field(TAG_ORACLE_SIGNATURE)?.clone(),
+    })
+}
@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::fix::admin::AdminMessage;
+
+/// How long to wait between attempts to reach the sequencer again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// Delay before the first retry
+    pub initial: Duration,
+    /// Ceiling the delay doubles up to
+    pub max: Duration,
+    /// Attempts in a row before giving up; retries forever when `None`
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(500),
+            max: Duration::from_secs(30),
+            max_attempts: None,
+        }
+    }
+}
+
+/// Exponential backoff over a `ReconnectPolicy`, reset once a Logon is
+/// accepted
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    policy: ReconnectPolicy,
+    attempt: u32,
+}
+
+impl Backoff {
+    pub fn new(policy: ReconnectPolicy) -> Self {
+        Self { policy, attempt: 0 }
+    }
+
+    /// Delay before the next attempt, or `None` once the attempts are used up
+    pub fn next_delay(&mut self) -> Option<Duration> {
+        if self.policy.max_attempts.is_some_and(|max| self.attempt >= max) {
+            return None;
+        }
+        let delay = self
+            .policy
+            .initial
+            .saturating_mul(2u32.saturating_pow(self.attempt))
+            .min(self.policy.max);
+        self.attempt += 1;
+        Some(delay)
+    }
+
+    pub fn attempts(&self) -> u32 {
+        self.attempt
+    }
+
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+}
+
+/// Sequence numbers of a client session, kept across connections so a
+/// reconnect resumes where the last one stopped. Should the sequencer
+/// refuse the resumed Logon, the next one asks to reset both sides to 1
+/// with ResetSeqNumFlag (141).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientSequences {
+    next_outgoing: u64,
+    next_incoming: u64,
+    reset_on_logon: bool,
+}
+
+impl Default for ClientSequences {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What has to be sent after the sequencer accepted our Logon
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResumeActions {
+    /// The sequencer missed our messages from this sequence number on. The
+    /// client keeps no outbound store, so they are gap filled.
+    pub gap_fill_from: Option<u64>,
+    /// We missed the sequencer's messages in this range and request them
+    pub resend: Option<(u64, u64)>,
+}
+
+impl ClientSequences {
+    pub fn new() -> Self {
+        Self {
+            next_outgoing: 1,
+            next_incoming: 1,
+            reset_on_logon: false,
+        }
+    }
+
+    pub fn next_outgoing(&self) -> u64 {
+        self.next_outgoing
+    }
+
+    pub fn next_incoming(&self) -> u64 {
+        self.next_incoming
+    }
+
+    /// Whether the next Logon asks for a reset
+    pub fn resets_on_logon(&self) -> bool {
+        self.reset_on_logon
+    }
+
+    /// Asks for a reset with the next Logon instead of resuming
+    pub fn request_reset(&mut self) {
+        self.reset_on_logon = true;
+    }
+
+    /// Takes the sequence number of the next message we send
+    pub fn take_outgoing(&mut self) -> u64 {
+        let seq = self.next_outgoing;
+        self.next_outgoing += 1;
+        seq
+    }
+
+    /// The Logon opening a connection, with the sequence number to send it
+    /// under. A reset Logon restarts both sides at 1.
+    pub fn logon(&mut self, heart_bt_int: u32) -> (u64, AdminMessage) {
+        if self.reset_on_logon {
+            self.next_outgoing = 1;
+            self.next_incoming = 1;
+        }
+        let logon = AdminMessage::Logon {
+            heart_bt_int,
+            reset_seq_num: self.reset_on_logon,
+            next_expected_msg_seq_num: self.next_incoming,
+        };
+        (self.take_outgoing(), logon)
+    }
+
+    /// Applies the sequencer's Logon response, returning what is owed to
+    /// recover the gap in either direction
+    pub fn logon_accepted(&mut self, fields: &HashMap<u32, String>) -> ResumeActions {
+        self.reset_on_logon = false;
+        let mut actions = ResumeActions::default();
+        if fields.get(&141).is_some_and(|flag| flag == "Y") {
+            self.next_incoming = 1;
+        }
+        if let Some(next_expected) = fields.get(&789).and_then(|seq| seq.parse::<u64>().ok()) {
+            // The Logon itself counts as sent
+            if next_expected < self.next_outgoing - 1 {
+                actions.gap_fill_from = Some(next_expected);
+            }
+        }
+        if let Some(seq) = fields.get(&34).and_then(|seq| seq.parse::<u64>().ok()) {
+            if seq > self.next_incoming {
+                actions.resend = Some((self.next_incoming, seq - 1));
+            }
+            self.next_incoming = seq + 1;
+        }
+        actions
+    }
+
+    /// Records a message received from the sequencer. Resent messages below
+    /// the expected number leave it alone.
+    pub fn received(&mut self, msg_seq_num: u64) {
+        self.next_incoming = self.next_incoming.max(msg_seq_num + 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_to_ceiling() {
+        let mut backoff = Backoff::new(ReconnectPolicy {
+            initial: Duration::from_secs(1),
+            max: Duration::from_secs(5),
+            max_attempts: Some(5),
+        });
+        let delays: Vec<_> = std::iter::from_fn(|| backoff.next_delay()).collect();
+        assert_eq!(delays, [1, 2, 4, 5, 5].map(Duration::from_secs));
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), Some(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_resume_and_reset() {
+        let mut sequences = ClientSequences::new();
+        let (seq, _) = sequences.logon(30);
+        assert_eq!(seq, 1);
+        let ack: HashMap<u32, String> = [(34, "1".to_string()), (789, "2".to_string())].into();
+        assert_eq!(sequences.logon_accepted(&ack), ResumeActions::default());
+        sequences.take_outgoing();
+        sequences.take_outgoing();
+        sequences.received(4);
+
+        // Reconnecting resumes; the sequencer lost our 3 and sent 5 and 6
+        let (seq, logon) = sequences.logon(30);
+        assert_eq!(seq, 4);
+        assert!(matches!(logon, AdminMessage::Logon { reset_seq_num: false, next_expected_msg_seq_num: 5, .. }));
+        let ack: HashMap<u32, String> = [(34, "7".to_string()), (789, "3".to_string())].into();
+        assert_eq!(
+            sequences.logon_accepted(&ack),
+            ResumeActions { gap_fill_from: Some(3), resend: Some((5, 6)) }
+        );
+
+        sequences.request_reset();
+        let (seq, logon) = sequences.logon(30);
+        assert_eq!(seq, 1);
+        assert!(matches!(logon, AdminMessage::Logon { reset_seq_num: true, next_expected_msg_seq_num: 1, .. }));
+    }
+}
@@ -0,0 +1,359 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use commonware_cryptography::{Bls12381, PrivateKey, PublicKey, Scheme, Signature};
+use commonware_utils::{from_hex, hex};
+use thiserror::Error;
+
+use crate::types::keymanager::{KeyManagerError, SessionCertificate, SessionKeyData};
+use crate::types::org::Organization;
+
+/// Namespace of the session key's signature over a Logon
+pub const LOGON_NAMESPACE: &[u8] = b"_ROMER_FIX_LOGON";
+
+/// User-defined Logon tags carrying the session certificate and signature
+pub const TAG_PARENT_PUBLIC_KEY: u32 = 20100;
+pub const TAG_SESSION_PUBLIC_KEY: u32 = 20101;
+pub const TAG_SESSION_NAMESPACE: u32 = 20102;
+pub const TAG_SESSION_PURPOSE: u32 = 20103;
+pub const TAG_SESSION_EXPIRY: u32 = 20104;
+pub const TAG_PARENT_SIGNATURE: u32 = 20105;
+pub const TAG_LOGON_SIGNATURE: u32 = 20106;
+
+/// Standard Logon credential tags, checked against the organization's
+/// `LogonCredential`
+pub const TAG_USERNAME: u32 = 553;
+pub const TAG_PASSWORD: u32 = 554;
+
+/// Header and Logon fields covered by the session key signature, in order.
+/// The certificate fields are covered too so they cannot be swapped.
+const SIGNED_TAGS: [u32; 10] = [
+    49,
+    56,
+    34,
+    52,
+    108,
+    TAG_PARENT_PUBLIC_KEY,
+    TAG_SESSION_PUBLIC_KEY,
+    TAG_SESSION_NAMESPACE,
+    TAG_SESSION_PURPOSE,
+    TAG_SESSION_EXPIRY,
+];
+
+#[derive(Error, Debug)]
+pub enum LogonAuthError {
+    #[error("Missing field {0}")]
+    MissingField(u32),
+
+    #[error("Invalid field {tag}: {reason}")]
+    InvalidField { tag: u32, reason: String },
+
+    #[error("Session namespace {namespace} does not match SenderCompID {sender_comp_id}")]
+    NamespaceMismatch {
+        namespace: String,
+        sender_comp_id: String,
+    },
+
+    #[error("Session key was not issued by the key registered for {0}")]
+    UnregisteredParent(String),
+
+    #[error("Invalid session certificate: {0}")]
+    Certificate(#[from] KeyManagerError),
+
+    #[error("Invalid Logon signature")]
+    InvalidSignature,
+
+    #[error("Logon carries neither a session key signature nor a password")]
+    Unauthenticated,
+
+    #[error("Invalid username or password")]
+    InvalidCredentials,
+
+    #[error("{0} must sign its Logon with a session key as well as send a password")]
+    SignatureRequired(String),
+}
+
+/// Session key authentication carried in a Logon (35=A): the certificate
+/// chaining the session key to the firm's registered key, and the session
+/// key's signature over the Logon
+#[derive(Debug, Clone)]
+pub struct SessionKeyLogon {
+    pub certificate: SessionCertificate,
+    pub signature: Vec<u8>,
+}
+
+impl SessionKeyLogon {
+    /// Whether the Logon carries session key authentication at all
+    pub fn is_present(fields: &HashMap<u32, String>) -> bool {
+        fields.contains_key(&TAG_LOGON_SIGNATURE)
+    }
+
+    pub fn from_fields(fields: &HashMap<u32, String>) -> Result<Self, LogonAuthError> {
+        let field = |tag| fields.get(&tag).ok_or(LogonAuthError::MissingField(tag));
+        let hex_field = |tag| {
+            from_hex(field(tag)?).ok_or_else(|| LogonAuthError::InvalidField {
+                tag,
+                reason: "not hex".into(),
+            })
+        };
+        let expires_at = field(TAG_SESSION_EXPIRY)?
+            .parse()
+            .map_err(|_| LogonAuthError::InvalidField {
+                tag: TAG_SESSION_EXPIRY,
+                reason: "not a unix timestamp".into(),
+            })?;
+
+        Ok(Self {
+            certificate: SessionCertificate {
+                session_public_key: hex_field(TAG_SESSION_PUBLIC_KEY)?,
+                parent_public_key: hex_field(TAG_PARENT_PUBLIC_KEY)?,
+                namespace: field(TAG_SESSION_NAMESPACE)?.clone(),
+                purpose: field(TAG_SESSION_PURPOSE)?.clone(),
+                expires_at,
+                parent_signature: hex_field(TAG_PARENT_SIGNATURE)?,
+            },
+            signature: hex_field(TAG_LOGON_SIGNATURE)?,
+        })
+    }
+
+    /// Validates the full chain: the session namespace is the SenderCompID,
+    /// the parent is the key registered for it, the parent endorsed the
+    /// session key and it has not expired, and the session key signed this
+    /// Logon
+    pub fn verify(
+        &self,
+        fields: &HashMap<u32, String>,
+        registered_public_key: &[u8],
+        now: DateTime<Utc>,
+    ) -> Result<(), LogonAuthError> {
+        let sender_comp_id = fields.get(&49).ok_or(LogonAuthError::MissingField(49))?;
+        if self.certificate.namespace != *sender_comp_id {
+            return Err(LogonAuthError::NamespaceMismatch {
+                namespace: self.certificate.namespace.clone(),
+                sender_comp_id: sender_comp_id.clone(),
+            });
+        }
+        if self.certificate.parent_public_key != registered_public_key {
+            return Err(LogonAuthError::UnregisteredParent(sender_comp_id.clone()));
+        }
+
+        self.certificate.verify(now)?;
+
+        let payload = signing_payload(fields)?;
+        if !Bls12381::verify(
+            Some(LOGON_NAMESPACE),
+            &payload,
+            &PublicKey::from(self.certificate.session_public_key.clone()),
+            &Signature::from(self.signature.clone()),
+        ) {
+            return Err(LogonAuthError::InvalidSignature);
+        }
+        Ok(())
+    }
+}
+
+/// How a Logon authenticated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogonFactors {
+    pub session_key: bool,
+    pub password: bool,
+}
+
+/// Authenticates a Logon from `organization`'s SenderCompID. A session key
+/// signature is verified when present. Once the organization has a logon
+/// credential, Username (553) and Password (554) must match it, and stand
+/// in for the signature only when the credential allows that.
+pub fn authenticate_logon(
+    fields: &HashMap<u32, String>,
+    organization: &Organization,
+    now: DateTime<Utc>,
+) -> Result<LogonFactors, LogonAuthError> {
+    let session_key = SessionKeyLogon::is_present(fields);
+    if session_key {
+        SessionKeyLogon::from_fields(fields)?.verify(fields, &organization.public_key, now)?;
+    }
+    let password = match &organization.logon_credential {
+        Some(credential) => {
+            let username = fields.get(&TAG_USERNAME).ok_or(LogonAuthError::MissingField(TAG_USERNAME))?;
+            let password = fields.get(&TAG_PASSWORD).ok_or(LogonAuthError::MissingField(TAG_PASSWORD))?;
+            if !credential.matches(username, password) {
+                return Err(LogonAuthError::InvalidCredentials);
+            }
+            if !session_key && !credential.allow_unsigned {
+                return Err(LogonAuthError::SignatureRequired(organization.sender_comp_id.clone()));
+            }
+            true
+        }
+        None if !session_key => return Err(LogonAuthError::Unauthenticated),
+        None => false,
+    };
+    Ok(LogonFactors { session_key, password })
+}
+
+/// The bytes the session key signs: the signed tags as `tag=value|`
+pub fn signing_payload(fields: &HashMap<u32, String>) -> Result<Vec<u8>, LogonAuthError> {
+    let mut payload = String::new();
+    for tag in SIGNED_TAGS {
+        let value = fields.get(&tag).ok_or(LogonAuthError::MissingField(tag))?;
+        payload.push_str(&format!("{}={}|", tag, value));
+    }
+    Ok(payload.into_bytes())
+}
+
+/// Certificate fields to append to a Logon body before signing it
+pub fn certificate_fields(session: &SessionKeyData) -> Result<Vec<(u32, String)>, LogonAuthError> {
+    let certificate = session.certificate()?;
+    Ok(vec![
+        (TAG_PARENT_PUBLIC_KEY, hex(&certificate.parent_public_key)),
+        (TAG_SESSION_PUBLIC_KEY, hex(&certificate.session_public_key)),
+        (TAG_SESSION_NAMESPACE, certificate.namespace),
+        (TAG_SESSION_PURPOSE, certificate.purpose),
+        (TAG_SESSION_EXPIRY, certificate.expires_at.to_string()),
+        (TAG_PARENT_SIGNATURE, hex(&certificate.parent_signature)),
+    ])
+}
+
+/// Signs a Logon whose `fields` already include the certificate fields,
+/// returning the value of `TAG_LOGON_SIGNATURE`
+pub fn sign_logon(session: &SessionKeyData, fields: &HashMap<u32, String>) -> Result<String, LogonAuthError> {
+    let mut session_key = <Bls12381 as Scheme>::from(PrivateKey::from(session.key_bytes.clone()))
+        .ok_or_else(|| KeyManagerError::InvalidKeyFormat("Invalid session key".into()))?;
+    let payload = signing_payload(fields)?;
+    Ok(hex(&session_key.sign(Some(LOGON_NAMESPACE), &payload)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::mock::FixMockGenerator;
+    use crate::types::fix::{utils, FixConfig};
+    use crate::types::org::{LogonCredential, OrganizationType};
+    use chrono::Duration;
+    use rand::rngs::OsRng;
+
+    fn session_key(parent: &mut Bls12381, namespace: &str, expires_at: DateTime<Utc>) -> SessionKeyData {
+        let session = Bls12381::new(&mut OsRng);
+        let mut data = SessionKeyData {
+            key_bytes: session.private_key().to_vec(),
+            created_at: Utc::now(),
+            expires_at,
+            parent_public_key: parent.public_key().to_vec(),
+            parent_signature: Vec::new(),
+            purpose: "FIX".into(),
+            namespace: namespace.into(),
+        };
+        let message = data.certificate().unwrap().message();
+        data.parent_signature = parent.sign(Some(namespace.as_bytes()), message.as_bytes()).to_vec();
+        data
+    }
+
+    fn organization(parent: &Bls12381, credential: Option<LogonCredential>) -> Organization {
+        let mut organization = Organization::new(
+            "mm1".into(),
+            "Market Maker One".into(),
+            OrganizationType::MarketMaker,
+            "MM1".into(),
+            parent.public_key().to_vec(),
+        );
+        organization.logon_credential = credential;
+        organization
+    }
+
+    fn generator(sender: &str) -> FixMockGenerator {
+        FixMockGenerator::new(FixConfig {
+            sender_comp_id: sender.into(),
+            ..FixConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_session_key_logon_chain() {
+        let mut parent = Bls12381::new(&mut OsRng);
+        let session = session_key(&mut parent, "MM1", Utc::now() + Duration::hours(1));
+
+        let logon = generator("MM1").mock_session_key_logon(&session).unwrap();
+        let fields = utils::parse_message_fields(&logon.raw_data);
+        let auth = SessionKeyLogon::from_fields(&fields).unwrap();
+        auth.verify(&fields, &parent.public_key(), Utc::now()).unwrap();
+
+        // Another firm's registered key
+        let other = Bls12381::new(&mut OsRng);
+        assert!(matches!(
+            auth.verify(&fields, &other.public_key(), Utc::now()),
+            Err(LogonAuthError::UnregisteredParent(_))
+        ));
+
+        // Tampering with a signed field
+        let mut tampered = fields.clone();
+        tampered.insert(108, "5".into());
+        assert!(matches!(
+            auth.verify(&tampered, &parent.public_key(), Utc::now()),
+            Err(LogonAuthError::InvalidSignature)
+        ));
+
+        // After expiry
+        assert!(matches!(
+            auth.verify(&fields, &parent.public_key(), Utc::now() + Duration::hours(2)),
+            Err(LogonAuthError::Certificate(KeyManagerError::SessionExpired))
+        ));
+    }
+
+    #[test]
+    fn test_namespace_must_match_sender() {
+        let mut parent = Bls12381::new(&mut OsRng);
+        let session = session_key(&mut parent, "MM2", Utc::now() + Duration::hours(1));
+
+        let logon = generator("MM1").mock_session_key_logon(&session).unwrap();
+        let fields = utils::parse_message_fields(&logon.raw_data);
+        let auth = SessionKeyLogon::from_fields(&fields).unwrap();
+        assert!(matches!(
+            auth.verify(&fields, &parent.public_key(), Utc::now()),
+            Err(LogonAuthError::NamespaceMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_password_logon() {
+        let mut parent = Bls12381::new(&mut OsRng);
+        let session = session_key(&mut parent, "MM1", Utc::now() + Duration::hours(1));
+        let fields = |generator: FixMockGenerator, signed: bool| {
+            let logon = match signed {
+                true => generator.mock_session_key_logon(&session).unwrap(),
+                false => generator.mock_logon(),
+            };
+            utils::parse_message_fields(&logon.raw_data)
+        };
+        let with_password = |password: &str| generator("MM1").with_credentials("mm1-engine", password);
+
+        // Without a credential, only the signature counts
+        let plain = organization(&parent, None);
+        assert!(matches!(
+            authenticate_logon(&fields(generator("MM1"), false), &plain, Utc::now()),
+            Err(LogonAuthError::Unauthenticated)
+        ));
+        let factors = authenticate_logon(&fields(generator("MM1"), true), &plain, Utc::now()).unwrap();
+        assert!(factors.session_key && !factors.password);
+
+        // As a second factor
+        let second_factor = organization(&parent, Some(LogonCredential::new("mm1-engine", "s3cret", false)));
+        assert!(matches!(
+            authenticate_logon(&fields(generator("MM1"), true), &second_factor, Utc::now()),
+            Err(LogonAuthError::MissingField(TAG_USERNAME))
+        ));
+        assert!(matches!(
+            authenticate_logon(&fields(with_password("wrong"), true), &second_factor, Utc::now()),
+            Err(LogonAuthError::InvalidCredentials)
+        ));
+        assert!(matches!(
+            authenticate_logon(&fields(with_password("s3cret"), false), &second_factor, Utc::now()),
+            Err(LogonAuthError::SignatureRequired(_))
+        ));
+        let factors = authenticate_logon(&fields(with_password("s3cret"), true), &second_factor, Utc::now()).unwrap();
+        assert!(factors.session_key && factors.password);
+
+        // Alone, for engines that cannot sign
+        let password_only = organization(&parent, Some(LogonCredential::new("mm1-engine", "s3cret", true)));
+        let factors = authenticate_logon(&fields(with_password("s3cret"), false), &password_only, Utc::now()).unwrap();
+        assert!(!factors.session_key && factors.password);
+    }
+}
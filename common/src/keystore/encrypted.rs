@@ -0,0 +1,124 @@
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+use crate::keystore::unlock::SecretBytes;
+use crate::types::keymanager::{KeyManagerError, KeyManagerResult, SignatureScheme};
+
+/// Version 1: Argon2id with default parameters, XChaCha20-Poly1305
+const VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+/// A permanent key encrypted under a passphrase, as stored on disk. The
+/// public key is kept in the clear so it can be read while locked.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptedKey {
+    pub version: u8,
+    pub scheme: SignatureScheme,
+    pub public_key: Vec<u8>,
+    salt: Vec<u8>,
+    nonce: Vec<u8>,
+    ciphertext: Vec<u8>,
+}
+
+impl EncryptedKey {
+    /// Encrypts `private_key` under `passphrase`
+    pub fn seal(
+        scheme: SignatureScheme,
+        public_key: Vec<u8>,
+        private_key: &[u8],
+        passphrase: &str,
+    ) -> KeyManagerResult<Self> {
+        let mut salt = vec![0u8; SALT_LEN];
+        let mut nonce = vec![0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+
+        let cipher = cipher(passphrase, &salt)?;
+        let ciphertext = cipher
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: private_key,
+                    aad: &aad(scheme, &public_key),
+                },
+            )
+            .map_err(|e| KeyManagerError::EncryptionError(e.to_string()))?;
+
+        Ok(Self {
+            version: VERSION,
+            scheme,
+            public_key,
+            salt,
+            nonce,
+            ciphertext,
+        })
+    }
+
+    /// Decrypts the private key. A wrong passphrase and a tampered file are
+    /// indistinguishable and both report `InvalidPassphrase`.
+    pub fn open(&self, passphrase: &str) -> KeyManagerResult<SecretBytes> {
+        if self.version != VERSION {
+            return Err(KeyManagerError::InvalidKeyFormat(format!(
+                "Unsupported keystore version {}",
+                self.version
+            )));
+        }
+        if self.nonce.len() != NONCE_LEN {
+            return Err(KeyManagerError::InvalidKeyFormat("Invalid nonce length".into()));
+        }
+
+        let cipher = cipher(passphrase, &self.salt)?;
+        cipher
+            .decrypt(
+                XNonce::from_slice(&self.nonce),
+                Payload {
+                    msg: &self.ciphertext,
+                    aad: &aad(self.scheme, &self.public_key),
+                },
+            )
+            .map(SecretBytes::new)
+            .map_err(|_| KeyManagerError::InvalidPassphrase)
+    }
+}
+
+fn cipher(passphrase: &str, salt: &[u8]) -> KeyManagerResult<XChaCha20Poly1305> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| KeyManagerError::EncryptionError(e.to_string()))?;
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(&key));
+    key.zeroize();
+    Ok(cipher)
+}
+
+/// Binds the ciphertext to the scheme and public key stored beside it
+fn aad(scheme: SignatureScheme, public_key: &[u8]) -> Vec<u8> {
+    let mut aad = format!("{:?}:", scheme).into_bytes();
+    aad.extend_from_slice(public_key);
+    aad
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_open() {
+        let sealed =
+            EncryptedKey::seal(SignatureScheme::Ed25519, vec![1, 2, 3], b"secret key", "hunter2").unwrap();
+        assert_ne!(sealed.ciphertext, b"secret key".to_vec());
+        assert_eq!(&*sealed.open("hunter2").unwrap(), b"secret key");
+        assert!(matches!(sealed.open("wrong"), Err(KeyManagerError::InvalidPassphrase)));
+
+        // Swapping the public key breaks authentication
+        let mut tampered = sealed.clone();
+        tampered.public_key = vec![4, 5, 6];
+        assert!(matches!(tampered.open("hunter2"), Err(KeyManagerError::InvalidPassphrase)));
+    }
+}
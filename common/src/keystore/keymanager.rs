@@ -5,11 +5,13 @@ use serde_json;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use crate::keystore::encrypted::EncryptedKey;
+use crate::keystore::unlock::SecretBytes;
 use crate::types::keymanager::{
     KeyManagerError, KeyManagerResult, SessionKeyData, SignatureScheme,
 };
 use crate::utils::hardware_validator::{HardwareDetector, OperatingSystem};
-use commonware_cryptography::{Bls12381, Ed25519, PrivateKey, PublicKey, Scheme, Signature};
+use commonware_cryptography::{Bls12381, Ed25519, PrivateKey, Scheme};
 use commonware_utils::hex;
 
 /// Manages cryptographic keys for the system, supporting both permanent and session keys.
@@ -32,6 +34,15 @@ impl KeyManager {
     pub fn new() -> KeyManagerResult<Self> {
         let os = HardwareDetector::detect_os();
         let base_dir = Self::determine_base_dir(&os)?;
+        Self::create(base_dir, os)
+    }
+
+    /// Creates a KeyManager rooted at `base_dir` instead of the per-user default
+    pub fn with_base_dir(base_dir: PathBuf) -> KeyManagerResult<Self> {
+        Self::create(base_dir, HardwareDetector::detect_os())
+    }
+
+    fn create(base_dir: PathBuf, os: OperatingSystem) -> KeyManagerResult<Self> {
         let permanent_dir = base_dir.join("permanent");
         let session_dir = base_dir.join("sessions");
 
@@ -49,21 +60,13 @@ impl KeyManager {
         })
     }
 
-    /// Initializes a new key for the specified signature scheme.
-    /// Returns the public key bytes of the generated key.
-    pub fn initialize(&self, scheme: SignatureScheme) -> KeyManagerResult<Vec<u8>> {
-        match scheme {
-            SignatureScheme::Ed25519 => {
-                let signer = Ed25519::new(&mut OsRng);
-                self.save_permanent_key(scheme, &signer.private_key().to_vec())?;
-                Ok(signer.public_key().to_vec())
-            }
-            SignatureScheme::Bls12381 => {
-                let signer = Bls12381::new(&mut OsRng);
-                self.save_permanent_key(scheme, &signer.private_key().to_vec())?;
-                Ok(signer.public_key().to_vec())
-            }
-        }
+    /// Initializes a new key for the specified signature scheme, encrypted
+    /// under `passphrase`. Returns the public key bytes of the generated key.
+    pub fn initialize(&self, scheme: SignatureScheme, passphrase: &str) -> KeyManagerResult<Vec<u8>> {
+        let (public_key, private_key) = Self::generate(scheme);
+        let sealed = EncryptedKey::seal(scheme, public_key.clone(), &private_key, passphrase)?;
+        self.save_permanent_key(&sealed)?;
+        Ok(public_key)
     }
 
     /// Creates a new session key signed by the specified permanent BLS key.
@@ -118,54 +121,92 @@ impl KeyManager {
 
     /// Verifies a session key's validity
     pub fn verify_session_key(&self, session_data: &SessionKeyData) -> KeyManagerResult<bool> {
-        // Check expiration first
-        if Utc::now() > session_data.expires_at {
-            return Err(KeyManagerError::SessionExpired);
-        }
-
-        // Convert the raw bytes into a PrivateKey type first
-        let session_private_key = PrivateKey::from(session_data.key_bytes.clone());
+        session_data.certificate()?.verify(Utc::now())?;
+        Ok(true)
+    }
 
-        // Create a key instance from the session key bytes using the Scheme trait
-        let session_key = <Bls12381 as Scheme>::from(session_private_key)
-            .ok_or_else(|| KeyManagerError::InvalidKeyFormat("Invalid session key".into()))?;
+    /// Whether an encrypted permanent key of the specified scheme exists
+    pub fn has_permanent_key(&self, scheme: SignatureScheme) -> bool {
+        self.get_permanent_key_path(scheme).exists()
+    }
 
-        // Create the verification message
-        let message = format!(
-            "{}:{}:{}",
-            hex(session_key.public_key().as_ref()),
-            session_data.expires_at.timestamp(),
-            session_data.purpose
-        );
+    /// Decrypts the permanent key of the specified scheme. Prefer unlocking
+    /// a `KeyCache` once over calling this for every operation.
+    pub fn load_permanent_key(
+        &self,
+        scheme: SignatureScheme,
+        passphrase: &str,
+    ) -> KeyManagerResult<SecretBytes> {
+        self.load_encrypted_key(scheme)?.open(passphrase)
+    }
 
-        // For verification, we don't need to construct a full signer - we can use the static verify method
-        let namespace_bytes = session_data.namespace.as_bytes();
-
-        // Use the static verify method from the Scheme trait
-        if !Bls12381::verify(
-            Some(namespace_bytes),
-            message.as_bytes(),
-            &PublicKey::from(session_data.parent_public_key.clone()),
-            &Signature::from(session_data.parent_signature.clone()),
-        ) {
-            return Err(KeyManagerError::InvalidSessionSignature);
-        }
+    /// Public key of the permanent key of the specified scheme, readable
+    /// without the passphrase
+    pub fn permanent_public_key(&self, scheme: SignatureScheme) -> KeyManagerResult<Vec<u8>> {
+        Ok(self.load_encrypted_key(scheme)?.public_key)
+    }
 
-        Ok(true)
+    /// Generates a replacement for the permanent key of `scheme` and stores
+    /// it beside the current key until `commit_pending_key`, so a rotation
+    /// that is refused elsewhere leaves the current key in place
+    pub fn generate_pending_key(
+        &self,
+        scheme: SignatureScheme,
+        passphrase: &str,
+    ) -> KeyManagerResult<(Vec<u8>, SecretBytes)> {
+        let (public_key, private_key) = Self::generate(scheme);
+        let sealed = EncryptedKey::seal(scheme, public_key.clone(), &private_key, passphrase)?;
+        let content = serde_json::to_string(&sealed)
+            .map_err(|e| KeyManagerError::SerializationError(e.to_string()))?;
+        fs::write(self.get_pending_key_path(scheme), content).map_err(KeyManagerError::IoError)?;
+        Ok((public_key, private_key))
     }
 
-    /// Loads a permanent key of the specified scheme.
-    /// Returns the key bytes which can be used to reconstruct the cryptographic type.
-    pub fn load_permanent_key(&self, scheme: SignatureScheme) -> KeyManagerResult<Vec<u8>> {
-        let path = self.get_permanent_key_path(scheme);
-        if !path.exists() {
+    /// Makes the pending key of `scheme` the permanent key. The previous key
+    /// is kept as a backup, whose path is returned.
+    pub fn commit_pending_key(&self, scheme: SignatureScheme) -> KeyManagerResult<PathBuf> {
+        let pending = self.get_pending_key_path(scheme);
+        if !pending.exists() {
             return Err(KeyManagerError::KeyNotFound(format!(
-                "No key found for scheme {:?}",
+                "No pending key for scheme {:?}",
                 scheme
             )));
         }
+        let current = self.get_permanent_key_path(scheme);
+        let backup = self
+            .permanent_dir
+            .join(format!("{:?}.key.enc.{}.bak", scheme, Utc::now().timestamp()));
+        if current.exists() {
+            fs::rename(&current, &backup).map_err(KeyManagerError::IoError)?;
+        }
+        fs::rename(&pending, &current).map_err(KeyManagerError::IoError)?;
+        Ok(backup)
+    }
 
-        fs::read(&path).map_err(|e| KeyManagerError::IoError(e))
+    /// Encrypts plaintext key files written by earlier versions under
+    /// `passphrase` and removes them. Returns the migrated schemes.
+    pub fn migrate_plaintext_keys(&self, passphrase: &str) -> KeyManagerResult<Vec<SignatureScheme>> {
+        let mut migrated = Vec::new();
+        for scheme in [SignatureScheme::Ed25519, SignatureScheme::Bls12381] {
+            let legacy = self.permanent_dir.join(format!("{:?}.key", scheme));
+            if !legacy.exists() {
+                continue;
+            }
+            if self.has_permanent_key(scheme) {
+                return Err(KeyManagerError::StorageError(format!(
+                    "Both plaintext and encrypted {:?} keys exist",
+                    scheme
+                )));
+            }
+
+            let private_key = SecretBytes::new(fs::read(&legacy).map_err(KeyManagerError::IoError)?);
+            let public_key = Self::public_key_of(scheme, &private_key)?;
+            let sealed = EncryptedKey::seal(scheme, public_key, &private_key, passphrase)?;
+            self.save_permanent_key(&sealed)?;
+            fs::remove_file(&legacy).map_err(KeyManagerError::IoError)?;
+            migrated.push(scheme);
+        }
+        Ok(migrated)
     }
 
     /// Loads a session key by its identifier.
@@ -184,21 +225,28 @@ impl KeyManager {
             .map_err(|e| KeyManagerError::SerializationError(e.to_string()))
     }
 
+    /// Unexpired session keys issued for `namespace`, by session id
+    pub fn active_session_keys(&self, namespace: &str) -> KeyManagerResult<Vec<(String, SessionKeyData)>> {
+        let mut sessions = Vec::new();
+        for entry in fs::read_dir(&self.session_dir).map_err(KeyManagerError::IoError)? {
+            let file_name = entry.map_err(KeyManagerError::IoError)?.file_name();
+            let Some(session_id) = file_name.to_string_lossy().strip_suffix(".json").map(str::to_string) else {
+                continue;
+            };
+            let session = self.load_session_key(&session_id)?;
+            if session.namespace == namespace && session.expires_at > Utc::now() {
+                sessions.push((session_id, session));
+            }
+        }
+        sessions.sort_by_key(|(_, session)| std::cmp::Reverse(session.expires_at));
+        Ok(sessions)
+    }
+
     /// Gets the BLS public key bytes if one exists. This is typically used during
     /// organization registration to establish the organization's blockchain identity.
     pub fn get_bls_public_key(&self) -> KeyManagerResult<Vec<u8>> {
-        // First load the raw private key bytes
-        let private_key_bytes = self.load_permanent_key(SignatureScheme::Bls12381)?;
-
-        // Convert the bytes into a PrivateKey type that the BLS scheme can use
-        let private_key = PrivateKey::from(private_key_bytes);
-
-        // Create a BLS signer instance from the private key
-        let signer = <Bls12381 as Scheme>::from(private_key)
-            .ok_or_else(|| KeyManagerError::InvalidKeyFormat("Invalid BLS key format".into()))?;
-
-        // Extract and return the public key bytes
-        Ok(signer.public_key().to_vec())
+        // The public key is stored in the clear beside the encrypted private key
+        self.permanent_public_key(SignatureScheme::Bls12381)
     }
 
     // Private helper methods
@@ -228,13 +276,56 @@ impl KeyManager {
 
     /// Gets the path where a permanent key of the specified scheme should be stored
     fn get_permanent_key_path(&self, scheme: SignatureScheme) -> PathBuf {
-        self.permanent_dir.join(format!("{:?}.key", scheme))
+        self.permanent_dir.join(format!("{:?}.key.enc", scheme))
+    }
+
+    fn get_pending_key_path(&self, scheme: SignatureScheme) -> PathBuf {
+        self.permanent_dir.join(format!("{:?}.key.enc.pending", scheme))
     }
 
-    /// Saves a permanent key to disk
-    fn save_permanent_key(&self, scheme: SignatureScheme, key: &[u8]) -> KeyManagerResult<()> {
+    fn load_encrypted_key(&self, scheme: SignatureScheme) -> KeyManagerResult<EncryptedKey> {
         let path = self.get_permanent_key_path(scheme);
-        fs::write(&path, key).map_err(|e| KeyManagerError::IoError(e))
+        if !path.exists() {
+            return Err(KeyManagerError::KeyNotFound(format!(
+                "No key found for scheme {:?}",
+                scheme
+            )));
+        }
+
+        let content = fs::read_to_string(&path).map_err(KeyManagerError::IoError)?;
+        serde_json::from_str(&content).map_err(|e| KeyManagerError::SerializationError(e.to_string()))
+    }
+
+    /// Saves an encrypted permanent key to disk
+    fn save_permanent_key(&self, key: &EncryptedKey) -> KeyManagerResult<()> {
+        let content = serde_json::to_string(key)
+            .map_err(|e| KeyManagerError::SerializationError(e.to_string()))?;
+        fs::write(self.get_permanent_key_path(key.scheme), content).map_err(KeyManagerError::IoError)
+    }
+
+    fn generate(scheme: SignatureScheme) -> (Vec<u8>, SecretBytes) {
+        match scheme {
+            SignatureScheme::Ed25519 => {
+                let signer = Ed25519::new(&mut OsRng);
+                (signer.public_key().to_vec(), SecretBytes::new(signer.private_key().to_vec()))
+            }
+            SignatureScheme::Bls12381 => {
+                let signer = Bls12381::new(&mut OsRng);
+                (signer.public_key().to_vec(), SecretBytes::new(signer.private_key().to_vec()))
+            }
+        }
+    }
+
+    fn public_key_of(scheme: SignatureScheme, private_key: &[u8]) -> KeyManagerResult<Vec<u8>> {
+        let private_key = PrivateKey::from(private_key.to_vec());
+        match scheme {
+            SignatureScheme::Ed25519 => <Ed25519 as Scheme>::from(private_key)
+                .map(|signer| signer.public_key().to_vec())
+                .ok_or_else(|| KeyManagerError::InvalidKeyFormat("Invalid Ed25519 key".into())),
+            SignatureScheme::Bls12381 => <Bls12381 as Scheme>::from(private_key)
+                .map(|signer| signer.public_key().to_vec())
+                .ok_or_else(|| KeyManagerError::InvalidKeyFormat("Invalid BLS key".into())),
+        }
     }
 
     /// Saves session key data to disk
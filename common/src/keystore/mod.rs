@@ -1 +1,3 @@
-pub mod keymanager;
\ No newline at end of file
+pub mod encrypted;
+pub mod keymanager;
+pub mod unlock;
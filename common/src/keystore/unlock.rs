@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use commonware_cryptography::{Bls12381, Ed25519, PrivateKey, Scheme};
+use tracing::{info, warn};
+use zeroize::Zeroize;
+
+use crate::keystore::keymanager::KeyManager;
+use crate::types::envelope::{SignedTransaction, UnsignedTransaction};
+use crate::types::keymanager::{KeyManagerError, KeyManagerResult, SignatureScheme};
+
+/// How long keys stay unlocked when `ROMER_KEY_UNLOCK_TIMEOUT_SECS` is unset
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Decrypted key material. The buffer is locked into memory so it is never
+/// swapped to disk, and zeroed when dropped.
+pub struct SecretBytes {
+    bytes: Vec<u8>,
+}
+
+impl SecretBytes {
+    pub fn new(bytes: Vec<u8>) -> Self {
+        lock_memory(&bytes);
+        Self { bytes }
+    }
+}
+
+impl Deref for SecretBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl fmt::Debug for SecretBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretBytes([REDACTED])")
+    }
+}
+
+impl Drop for SecretBytes {
+    fn drop(&mut self) {
+        let (ptr, len) = (self.bytes.as_ptr(), self.bytes.len());
+        self.bytes.zeroize();
+        unlock_memory(ptr, len);
+    }
+}
+
+#[cfg(unix)]
+fn lock_memory(bytes: &[u8]) {
+    if bytes.is_empty() {
+        return;
+    }
+    // SAFETY: the range is a live allocation owned by the caller
+    if unsafe { libc::mlock(bytes.as_ptr().cast(), bytes.len()) } != 0 {
+        warn!("Failed to lock key material in memory, it may be swapped to disk");
+    }
+}
+
+#[cfg(unix)]
+fn unlock_memory(ptr: *const u8, len: usize) {
+    if len > 0 {
+        // SAFETY: the range was locked by `lock_memory` and is still allocated
+        unsafe { libc::munlock(ptr.cast(), len) };
+    }
+}
+
+#[cfg(not(unix))]
+fn lock_memory(_bytes: &[u8]) {}
+
+#[cfg(not(unix))]
+fn unlock_memory(_ptr: *const u8, _len: usize) {}
+
+struct Unlocked {
+    keys: HashMap<SignatureScheme, SecretBytes>,
+    expires_at: Instant,
+}
+
+/// Permanent keys decrypted once per unlock and held for a fixed timeout,
+/// after which the passphrase has to be entered again. Clones share the
+/// same unlock state.
+#[derive(Clone)]
+pub struct KeyCache {
+    state: Arc<Mutex<Option<Unlocked>>>,
+    timeout: Duration,
+}
+
+impl KeyCache {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(None)),
+            timeout,
+        }
+    }
+
+    /// Cache with the timeout from `ROMER_KEY_UNLOCK_TIMEOUT_SECS`, five
+    /// minutes by default
+    pub fn from_env() -> Self {
+        let timeout = std::env::var("ROMER_KEY_UNLOCK_TIMEOUT_SECS")
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_TIMEOUT);
+        Self::new(timeout)
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Decrypts every permanent key in `manager` with `passphrase`. Legacy
+    /// plaintext key files are encrypted under the passphrase first.
+    pub fn unlock(&self, manager: &KeyManager, passphrase: &str) -> KeyManagerResult<()> {
+        for scheme in manager.migrate_plaintext_keys(passphrase)? {
+            info!(?scheme, "Encrypted plaintext key file");
+        }
+
+        let mut keys = HashMap::new();
+        for scheme in [SignatureScheme::Ed25519, SignatureScheme::Bls12381] {
+            if manager.has_permanent_key(scheme) {
+                keys.insert(scheme, manager.load_permanent_key(scheme, passphrase)?);
+            }
+        }
+        if keys.is_empty() {
+            return Err(KeyManagerError::KeyNotFound("No permanent keys to unlock".into()));
+        }
+
+        *self.state.lock().unwrap() = Some(Unlocked {
+            keys,
+            expires_at: Instant::now() + self.timeout,
+        });
+        Ok(())
+    }
+
+    /// Drops and zeroes the decrypted keys
+    pub fn lock(&self) {
+        self.state.lock().unwrap().take();
+    }
+
+    /// Time left before the keys lock, `None` if locked
+    pub fn remaining(&self) -> Option<Duration> {
+        let mut state = self.state.lock().unwrap();
+        Self::expire(&mut state);
+        state
+            .as_ref()
+            .map(|unlocked| unlocked.expires_at.saturating_duration_since(Instant::now()))
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        self.remaining().is_some()
+    }
+
+    /// Runs `f` with the decrypted key of `scheme`
+    pub fn with_key<T>(
+        &self,
+        scheme: SignatureScheme,
+        f: impl FnOnce(&[u8]) -> T,
+    ) -> KeyManagerResult<T> {
+        let mut state = self.state.lock().unwrap();
+        Self::expire(&mut state);
+        let unlocked = state.as_ref().ok_or(KeyManagerError::Locked)?;
+        let key = unlocked
+            .keys
+            .get(&scheme)
+            .ok_or_else(|| KeyManagerError::KeyNotFound(format!("No key found for scheme {:?}", scheme)))?;
+        Ok(f(key))
+    }
+
+    /// Signs `message` under `namespace` with the unlocked key of `scheme`
+    pub fn sign(
+        &self,
+        scheme: SignatureScheme,
+        namespace: &[u8],
+        message: &[u8],
+    ) -> KeyManagerResult<Vec<u8>> {
+        self.with_key(scheme, |key| {
+            let private_key = PrivateKey::from(key.to_vec());
+            match scheme {
+                SignatureScheme::Ed25519 => <Ed25519 as Scheme>::from(private_key)
+                    .map(|mut signer| signer.sign(Some(namespace), message).to_vec())
+                    .ok_or_else(|| KeyManagerError::InvalidKeyFormat("Invalid Ed25519 key".into())),
+                SignatureScheme::Bls12381 => <Bls12381 as Scheme>::from(private_key)
+                    .map(|mut signer| signer.sign(Some(namespace), message).to_vec())
+                    .ok_or_else(|| KeyManagerError::InvalidKeyFormat("Invalid BLS key".into())),
+            }
+        })?
+    }
+
+    /// Signs a transaction envelope with the unlocked key of the given scheme
+    pub fn sign_transaction(
+        &self,
+        scheme: SignatureScheme,
+        transaction: UnsignedTransaction,
+    ) -> KeyManagerResult<SignedTransaction> {
+        let signed = self.with_key(scheme, |key| {
+            let private_key = PrivateKey::from(key.to_vec());
+            match scheme {
+                SignatureScheme::Ed25519 => {
+                    let mut signer = <Ed25519 as Scheme>::from(private_key)
+                        .ok_or_else(|| KeyManagerError::InvalidKeyFormat("Invalid Ed25519 key".into()))?;
+                    Ok(transaction.sign(scheme, &mut signer))
+                }
+                SignatureScheme::Bls12381 => {
+                    let mut signer = <Bls12381 as Scheme>::from(private_key)
+                        .ok_or_else(|| KeyManagerError::InvalidKeyFormat("Invalid BLS key".into()))?;
+                    Ok(transaction.sign(scheme, &mut signer))
+                }
+            }
+        })??;
+
+        signed.map_err(|e| KeyManagerError::SerializationError(e.to_string()))
+    }
+
+    fn expire(state: &mut Option<Unlocked>) {
+        if state.as_ref().is_some_and(|unlocked| Instant::now() >= unlocked.expires_at) {
+            info!("Key unlock timed out, locking keystore");
+            state.take();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unlock_expires() {
+        let dir = std::env::temp_dir().join(format!("romer-keycache-{}", std::process::id()));
+        let manager = KeyManager::with_base_dir(dir.clone()).unwrap();
+        manager.initialize(SignatureScheme::Ed25519, "passphrase").unwrap();
+
+        let cache = KeyCache::new(Duration::from_millis(50));
+        assert!(matches!(
+            cache.with_key(SignatureScheme::Ed25519, |_| ()),
+            Err(KeyManagerError::Locked)
+        ));
+        assert!(matches!(
+            cache.unlock(&manager, "wrong"),
+            Err(KeyManagerError::InvalidPassphrase)
+        ));
+
+        cache.unlock(&manager, "passphrase").unwrap();
+        assert_eq!(cache.with_key(SignatureScheme::Ed25519, |key| key.len()).unwrap(), 32);
+        assert!(matches!(
+            cache.with_key(SignatureScheme::Bls12381, |_| ()),
+            Err(KeyManagerError::KeyNotFound(_))
+        ));
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(!cache.is_unlocked());
+        assert!(matches!(
+            cache.with_key(SignatureScheme::Ed25519, |_| ()),
+            Err(KeyManagerError::Locked)
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
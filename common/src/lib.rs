@@ -1,5 +1,6 @@
 // Expose our type system
 pub mod keystore;
+pub mod light;
 pub mod utils;
 pub mod types;
 pub mod error;
@@ -7,5 +8,6 @@ pub mod fix;
 pub mod storage;
 
 // Re-export commonly used types
-pub use types::org::{Organization, OrganizationType};
-pub use types::token::Token;
\ No newline at end of file
+pub use types::org::{Organization, OrganizationType, SymbolPermission};
+pub use types::token::Token;
+pub use types::address::Address;
\ No newline at end of file
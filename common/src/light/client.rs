@@ -0,0 +1,255 @@
+use std::collections::BTreeMap;
+
+use super::header::{verify_chain, BlockHeader};
+use super::state::StateProof;
+use super::validators::{Commit, ValidatorSet, ValidatorSetChange};
+use super::LightError;
+use crate::types::receipt::ReceiptWithProof;
+
+/// Finalized headers kept for checking proofs against
+pub const RETAINED_HEADERS: usize = 10_000;
+
+/// Follows finalized headers from a trusted checkpoint
+pub struct LightClient {
+    validators: ValidatorSet,
+    head: BlockHeader,
+    /// Verified headers by height, the most recent `RETAINED_HEADERS`
+    headers: BTreeMap<u64, BlockHeader>,
+}
+
+impl LightClient {
+    /// Trusts `checkpoint` and `validators`, which must come from a source
+    /// trusted out of band such as the genesis file or an operator
+    pub fn new(checkpoint: BlockHeader, validators: ValidatorSet) -> Result<Self, LightError> {
+        validators.validate()?;
+        let mut headers = BTreeMap::new();
+        headers.insert(checkpoint.block_id, checkpoint.clone());
+        Ok(Self {
+            validators,
+            head: checkpoint,
+            headers,
+        })
+    }
+
+    /// Latest finalized header
+    pub fn head(&self) -> &BlockHeader {
+        &self.head
+    }
+
+    /// Validator set finalizing blocks after the head
+    pub fn validators(&self) -> &ValidatorSet {
+        &self.validators
+    }
+
+    /// Verified header at `height`, if retained
+    pub fn header(&self, height: u64) -> Option<&BlockHeader> {
+        self.headers.get(&height)
+    }
+
+    /// Advances the head to the last of `headers`, which must extend the
+    /// head one block at a time and be finalized by `commit`. Every header
+    /// before a finalized one is final too.
+    pub fn update(&mut self, headers: Vec<BlockHeader>, commit: &Commit) -> Result<&BlockHeader, LightError> {
+        let Some(last) = headers.last() else {
+            return Ok(&self.head);
+        };
+        verify_chain(&self.head, &headers)?;
+        let finalized = commit.verify(&self.validators)?;
+        let hash = last.hash();
+        if finalized != hash {
+            return Err(LightError::CommitMismatch {
+                commit: finalized,
+                header: hash,
+            });
+        }
+
+        for header in headers {
+            self.head = header.clone();
+            self.headers.insert(header.block_id, header);
+        }
+        while self.headers.len() > RETAINED_HEADERS {
+            self.headers.pop_first();
+        }
+        Ok(&self.head)
+    }
+
+    /// Hands over to the next validator set once the head has reached the
+    /// last block the current set finalizes, which commits to the next set
+    pub fn rotate(&mut self, change: &ValidatorSetChange) -> Result<(), LightError> {
+        if change.height != self.head.block_id {
+            return Err(LightError::ChangeHeight {
+                change: change.height,
+                head: self.head.block_id,
+            });
+        }
+        change.verify(&self.head)?;
+        self.validators = change.next.clone();
+        Ok(())
+    }
+
+    /// Checks the receipt's transaction is in the finalized block at the
+    /// receipt's height
+    pub fn verify_receipt(&self, receipt: &ReceiptWithProof) -> Result<(), LightError> {
+        let height = receipt.receipt.block_height;
+        let header = self.header(height).ok_or(LightError::UnknownHeight(height))?;
+        if !receipt.verify(&header.transactions_root_bytes()?) {
+            return Err(LightError::InvalidProof("transactions"));
+        }
+        Ok(())
+    }
+
+    /// Checks the proven key held its value after the finalized block at
+    /// `height`
+    pub fn verify_state(&self, height: u64, proof: &StateProof) -> Result<(), LightError> {
+        let header = self.header(height).ok_or(LightError::UnknownHeight(height))?;
+        if !proof.verify(&header.state_root_bytes()?) {
+            return Err(LightError::InvalidProof("state"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::light::header::GENESIS_PREVIOUS_HASH;
+    use crate::light::state::state_root;
+    use crate::light::validators::finalization;
+    use crate::types::address::Address;
+    use crate::types::envelope::{SignedTransaction, TransactionPayload, UnsignedTransaction, DEFAULT_CHAIN_ID};
+    use crate::types::keymanager::SignatureScheme;
+    use crate::types::protocol::GENESIS_PROTOCOL_VERSION;
+    use crate::types::receipt::Receipt;
+    use crate::utils::merkle::{merkle_root, MerkleProof};
+    use chrono::DateTime;
+    use commonware_cryptography::{Ed25519, PublicKey, Scheme};
+    use commonware_utils::{from_hex, hex};
+
+    fn signers(seeds: std::ops::Range<u64>) -> Vec<Ed25519> {
+        seeds.map(Ed25519::from_seed).collect()
+    }
+
+    fn set(signers: &[Ed25519]) -> ValidatorSet {
+        let keys: Vec<PublicKey> = signers.iter().map(Scheme::public_key).collect();
+        ValidatorSet::new(SignatureScheme::Ed25519, &keys)
+    }
+
+    fn header(previous: Option<&BlockHeader>, transactions_root: [u8; 32], state_root: String) -> BlockHeader {
+        let block_id = previous.map_or(0, |previous| previous.block_id + 1);
+        BlockHeader {
+            block_id,
+            previous_hash: previous.map_or(GENESIS_PREVIOUS_HASH.to_string(), BlockHeader::hash),
+            timestamp: DateTime::from_timestamp(1_700_000_000 + block_id as i64, 0).unwrap(),
+            message_count: 0,
+            messages_root: String::new(),
+            transaction_count: 0,
+            transactions_root: hex(&transactions_root),
+            batch_sequence: block_id,
+            protocol_version: GENESIS_PROTOCOL_VERSION,
+            state_root,
+            oracle_root: String::new(),
+            validators_digest: String::new(),
+        }
+    }
+
+    fn commit(header: &BlockHeader, signers: &mut [Ed25519]) -> Commit {
+        Commit::new(&finalization(&from_hex(&header.hash()).unwrap(), signers))
+    }
+
+    fn transfer(nonce: u64) -> SignedTransaction {
+        let mut signer = Ed25519::from_seed(1);
+        UnsignedTransaction {
+            chain_id: DEFAULT_CHAIN_ID.to_string(),
+            payload: TransactionPayload::Transfer {
+                to: Address::new([1u8; 32]),
+                amount: 10,
+            },
+            sender: Address::from_public_key(SignatureScheme::Ed25519, &signer.public_key()),
+            nonce,
+            fee: 0,
+            expiry: u64::MAX,
+        }
+        .sign(SignatureScheme::Ed25519, &mut signer)
+        .unwrap()
+    }
+
+    #[test]
+    fn test_follows_finalized_headers() {
+        let mut validators = signers(0..4);
+        let genesis = header(None, [0u8; 32], String::new());
+        let mut client = LightClient::new(genesis.clone(), set(&validators)).unwrap();
+
+        let transactions: Vec<_> = (0..3).map(transfer).collect();
+        let leaves: Vec<_> = transactions.iter().map(|tx| tx.digest().unwrap()).collect();
+        let state: BTreeMap<Vec<u8>, Vec<u8>> = (0u8..3).map(|i| (vec![i], vec![i * 2])).collect();
+        let first = header(Some(&genesis), [0u8; 32], String::new());
+        let second = header(Some(&first), merkle_root(&leaves), hex(&state_root(&state)));
+
+        // Too few signatures, or a commit for another block, do not finalize
+        let weak = commit(&second, &mut validators[..2]);
+        assert!(matches!(
+            client.update(vec![first.clone(), second.clone()], &weak),
+            Err(LightError::QuorumNotReached { .. })
+        ));
+        let stale = commit(&first, &mut validators[..3]);
+        assert!(matches!(
+            client.update(vec![first.clone(), second.clone()], &stale),
+            Err(LightError::CommitMismatch { .. })
+        ));
+        assert_eq!(client.head(), &genesis);
+
+        let finalized = commit(&second, &mut validators[1..]);
+        assert_eq!(client.update(vec![first.clone(), second.clone()], &finalized).unwrap(), &second);
+        assert_eq!(client.header(1), Some(&first));
+
+        let receipt = ReceiptWithProof {
+            receipt: Receipt::success(&transactions[2], 2, 2, 0).unwrap(),
+            transactions_root: merkle_root(&leaves),
+            proof: MerkleProof::generate(&leaves, 2).unwrap(),
+        };
+        client.verify_receipt(&receipt).unwrap();
+        let mut elsewhere = receipt.clone();
+        elsewhere.receipt.block_height = 1;
+        assert_eq!(client.verify_receipt(&elsewhere), Err(LightError::InvalidProof("transactions")));
+        elsewhere.receipt.block_height = 3;
+        assert_eq!(client.verify_receipt(&elsewhere), Err(LightError::UnknownHeight(3)));
+
+        let proof = StateProof::generate(&state, &[1]).unwrap();
+        client.verify_state(2, &proof).unwrap();
+        assert_eq!(client.verify_state(1, &proof), Err(LightError::NoStateRoot(1)));
+    }
+
+    #[test]
+    fn test_rotates_validators() {
+        let mut current = signers(0..4);
+        let mut next = signers(4..8);
+        let genesis = header(None, [0u8; 32], String::new());
+        let mut client = LightClient::new(genesis.clone(), set(&current)).unwrap();
+
+        // The hand-over takes effect once a block committing to it is final
+        let change = ValidatorSetChange {
+            height: 1,
+            next: set(&next),
+        };
+        let first = header(Some(&genesis), [0u8; 32], String::new());
+        client.update(vec![first.clone()], &commit(&first, &mut current)).unwrap();
+        assert_eq!(client.rotate(&change), Err(LightError::UncommittedValidatorSet(1)));
+
+        let mut client = LightClient::new(genesis.clone(), set(&current)).unwrap();
+        let mut first = first;
+        first.validators_digest = hex(&set(&next).digest());
+        client.update(vec![first.clone()], &commit(&first, &mut current)).unwrap();
+        client.rotate(&change).unwrap();
+        assert_eq!(client.validators(), &set(&next));
+
+        // Only the new set finalizes blocks after the hand-over
+        let second = header(Some(&first), [0u8; 32], String::new());
+        assert!(client.update(vec![second.clone()], &commit(&second, &mut current)).is_err());
+        client.update(vec![second.clone()], &commit(&second, &mut next)).unwrap();
+
+        assert_eq!(
+            client.rotate(&change),
+            Err(LightError::ChangeHeight { change: 1, head: 2 })
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use chrono::{DateTime, Utc};
+use commonware_cryptography::{Hasher, Sha256};
+use commonware_utils::{from_hex, hex};
+use serde::{Deserialize, Serialize};
+
+use super::LightError;
+use crate::types::protocol::GENESIS_PROTOCOL_VERSION;
+
+/// Previous hash of the first block
+pub const GENESIS_PREVIOUS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Contains metadata about the block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockHeader {
+    /// Unique identifier for this block
+    pub block_id: u64,
+    /// Hash of the previous block
+    pub previous_hash: String,
+    /// When this block was created
+    pub timestamp: DateTime<Utc>,
+    /// Number of messages in the block
+    pub message_count: usize,
+    /// Merkle root of the messages
+    pub messages_root: String,
+    /// Number of direct transactions in the block
+    #[serde(default)]
+    pub transaction_count: usize,
+    /// Merkle root of the direct transactions' digests, hex encoded
+    #[serde(default)]
+    pub transactions_root: String,
+    /// Sequence number from the batch
+    pub batch_sequence: u64,
+    /// Protocol version the block was built under
+    #[serde(default = "genesis_protocol_version")]
+    pub protocol_version: u32,
+    /// Root of the state after the block, hex encoded, empty if the block
+    /// commits to no state
+    #[serde(default)]
+    pub state_root: String,
+    /// SHA-256 of the oracle prices written in the block, hex encoded,
+    /// empty if none were
+    #[serde(default)]
+    pub oracle_root: String,
+    /// Digest of the validator set finalizing the blocks after this one,
+    /// hex encoded, empty if the set does not change
+    #[serde(default)]
+    pub validators_digest: String,
+}
+
+fn genesis_protocol_version() -> u32 {
+    GENESIS_PROTOCOL_VERSION
+}
+
+fn decode_root(root: &str) -> Option<[u8; 32]> {
+    from_hex(root)?.try_into().ok()
+}
+
+impl BlockHeader {
+    /// Hash of the header, hex encoded, which is the block's hash
+    pub fn hash(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(&self.block_id.to_le_bytes());
+        hasher.update(self.previous_hash.as_bytes());
+        hasher.update(&self.timestamp.timestamp().to_le_bytes());
+        hasher.update(&(self.message_count as u64).to_le_bytes());
+        hasher.update(self.messages_root.as_bytes());
+        hasher.update(&(self.transaction_count as u64).to_le_bytes());
+        hasher.update(self.transactions_root.as_bytes());
+        hasher.update(&self.batch_sequence.to_le_bytes());
+        hasher.update(&self.protocol_version.to_le_bytes());
+        // Empty for headers without state, oracle prices or a validator set
+        // change, keeping their hashes unchanged
+        hasher.update(self.state_root.as_bytes());
+        hasher.update(self.oracle_root.as_bytes());
+        hasher.update(self.validators_digest.as_bytes());
+        hex(&hasher.finalize())
+    }
+
+    /// Checks the header is the block right after `previous`
+    pub fn follows(&self, previous: &BlockHeader) -> Result<(), LightError> {
+        if self.block_id != previous.block_id + 1 || self.previous_hash != previous.hash() {
+            return Err(LightError::BrokenChain {
+                height: self.block_id,
+                previous: previous.block_id,
+            });
+        }
+        Ok(())
+    }
+
+    /// Decoded root that receipts' inclusion proofs are checked against
+    pub fn transactions_root_bytes(&self) -> Result<[u8; 32], LightError> {
+        decode_root(&self.transactions_root).ok_or(LightError::InvalidRoot {
+            field: "transactions",
+            height: self.block_id,
+        })
+    }
+
+    /// Decoded root that state proofs are checked against
+    pub fn state_root_bytes(&self) -> Result<[u8; 32], LightError> {
+        if self.state_root.is_empty() {
+            return Err(LightError::NoStateRoot(self.block_id));
+        }
+        decode_root(&self.state_root).ok_or(LightError::InvalidRoot {
+            field: "state",
+            height: self.block_id,
+        })
+    }
+}
+
+/// Checks `headers` extend `trusted` one block at a time
+pub fn verify_chain(trusted: &BlockHeader, headers: &[BlockHeader]) -> Result<(), LightError> {
+    let mut previous = trusted;
+    for header in headers {
+        header.follows(previous)?;
+        previous = header;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chain(length: u64) -> Vec<BlockHeader> {
+        let mut headers: Vec<BlockHeader> = Vec::new();
+        for block_id in 0..length {
+            let previous_hash = headers
+                .last()
+                .map_or(GENESIS_PREVIOUS_HASH.to_string(), BlockHeader::hash);
+            headers.push(BlockHeader {
+                block_id,
+                previous_hash,
+                timestamp: DateTime::from_timestamp(1_700_000_000 + block_id as i64, 0).unwrap(),
+                message_count: 0,
+                messages_root: String::new(),
+                transaction_count: 0,
+                transactions_root: hex(&[0u8; 32]),
+                batch_sequence: block_id,
+                protocol_version: GENESIS_PROTOCOL_VERSION,
+                state_root: String::new(),
+                oracle_root: String::new(),
+                validators_digest: String::new(),
+            });
+        }
+        headers
+    }
+
+    #[test]
+    fn test_verify_chain() {
+        let headers = chain(4);
+        verify_chain(&headers[0], &headers[1..]).unwrap();
+        assert_eq!(headers[1].transactions_root_bytes(), Ok([0u8; 32]));
+        assert_eq!(headers[1].state_root_bytes(), Err(LightError::NoStateRoot(1)));
+
+        // Skipping a block or altering one breaks the chain
+        assert_eq!(
+            verify_chain(&headers[0], &headers[2..]),
+            Err(LightError::BrokenChain { height: 2, previous: 0 })
+        );
+        let mut tampered = headers.clone();
+        tampered[2].message_count = 1;
+        assert_eq!(
+            verify_chain(&tampered[0], &tampered[1..]),
+            Err(LightError::BrokenChain { height: 3, previous: 2 })
+        );
+    }
+
+    #[test]
+    fn test_hash_ignores_missing_state_root() {
+        let header = chain(1).remove(0);
+        let mut json = serde_json::to_value(&header).unwrap();
+        json.as_object_mut().unwrap().remove("state_root");
+        let decoded: BlockHeader = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded.hash(), header.hash());
+    }
+}
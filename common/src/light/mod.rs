@@ -0,0 +1,61 @@
+//! Light-client verification (`romer-light`).
+//!
+//! Lets exchanges and custodians check settlement finality without running
+//! a full node. Starting from a header and validator set obtained out of
+//! band, header chains are checked link by link, finality by the simplex
+//! finalization certificate consensus emits for the block hash, and
+//! receipts and state against the roots committed in a finalized header.
+
+pub mod client;
+pub mod header;
+pub mod state;
+pub mod validators;
+
+pub use client::LightClient;
+pub use header::{verify_chain, BlockHeader, GENESIS_PREVIOUS_HASH};
+pub use state::{state_leaf, state_root, StateProof};
+pub use validators::{Commit, ValidatorSet, ValidatorSetChange, CONSENSUS_NAMESPACE};
+
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum LightError {
+    #[error("Header {0} does not match its hash")]
+    HashMismatch(u64),
+
+    #[error("Header {height} does not follow header {previous}")]
+    BrokenChain { height: u64, previous: u64 },
+
+    #[error("Invalid {field} root in header {height}")]
+    InvalidRoot { field: &'static str, height: u64 },
+
+    #[error("Header {0} commits to no state")]
+    NoStateRoot(u64),
+
+    #[error("No verified header at height {0}")]
+    UnknownHeight(u64),
+
+    #[error("Invalid validator set: {0}")]
+    InvalidValidatorSet(String),
+
+    #[error("Signature from {0}, which is not in the validator set")]
+    UnknownSigner(String),
+
+    #[error("Invalid finalization certificate")]
+    InvalidCertificate,
+
+    #[error("Quorum not reached: {signatures} of {quorum} signatures")]
+    QuorumNotReached { signatures: usize, quorum: usize },
+
+    #[error("Commit is for block {commit}, not {header}")]
+    CommitMismatch { commit: String, header: String },
+
+    #[error("Header {0} does not commit to the next validator set")]
+    UncommittedValidatorSet(u64),
+
+    #[error("Validator set change at height {change} does not apply at head {head}")]
+    ChangeHeight { change: u64, head: u64 },
+
+    #[error("Proof does not match the {0} root")]
+    InvalidProof(&'static str),
+}
@@ -0,0 +1,76 @@
+use commonware_cryptography::{Hasher, Sha256};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::utils::merkle::{merkle_root, MerkleProof};
+
+/// Leaf committing to `key` holding `value`. The key is length-prefixed so
+/// no key and value pair can pass for another.
+pub fn state_leaf(key: &[u8], value: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(&(key.len() as u64).to_be_bytes());
+    hasher.update(key);
+    hasher.update(value);
+    let mut leaf = [0u8; 32];
+    leaf.copy_from_slice(&hasher.finalize());
+    leaf
+}
+
+fn leaves(state: &BTreeMap<Vec<u8>, Vec<u8>>) -> Vec<[u8; 32]> {
+    state.iter().map(|(key, value)| state_leaf(key, value)).collect()
+}
+
+/// Root committing to every entry of `state`, in key order
+pub fn state_root(state: &BTreeMap<Vec<u8>, Vec<u8>>) -> [u8; 32] {
+    merkle_root(&leaves(state))
+}
+
+/// Proof that a key held a value in a committed state. It proves presence
+/// only, not that a key is absent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateProof {
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+    pub proof: MerkleProof,
+}
+
+impl StateProof {
+    /// Proves the value `key` holds in `state`, `None` if it holds none
+    pub fn generate(state: &BTreeMap<Vec<u8>, Vec<u8>>, key: &[u8]) -> Option<Self> {
+        let index = state.keys().position(|candidate| candidate.as_slice() == key)?;
+        Some(Self {
+            key: key.to_vec(),
+            value: state[key].clone(),
+            proof: MerkleProof::generate(&leaves(state), index)?,
+        })
+    }
+
+    /// Checks the key held the value under `state_root`
+    pub fn verify(&self, state_root: &[u8; 32]) -> bool {
+        self.proof.verify(state_root, &state_leaf(&self.key, &self.value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_proof() {
+        let state: BTreeMap<Vec<u8>, Vec<u8>> = (0u8..5)
+            .map(|i| (vec![i; 4], (i as u64 * 100).to_be_bytes().to_vec()))
+            .collect();
+        let root = state_root(&state);
+
+        let proof = StateProof::generate(&state, &[3u8; 4]).unwrap();
+        assert!(proof.verify(&root));
+        assert!(StateProof::generate(&state, &[9u8; 4]).is_none());
+
+        let mut forged = proof.clone();
+        forged.value = 1_000u64.to_be_bytes().to_vec();
+        assert!(!forged.verify(&root));
+
+        // Moving bytes between key and value changes the leaf
+        assert_ne!(state_leaf(b"ab", b"c"), state_leaf(b"a", b"bc"));
+    }
+}
@@ -0,0 +1,243 @@
+use bytes::Bytes;
+use commonware_consensus::simplex::Prover;
+use commonware_cryptography::{Bls12381, Ed25519, Hasher, PublicKey, Sha256};
+use commonware_utils::{from_hex, hex};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use super::header::BlockHeader;
+use super::LightError;
+use crate::types::keymanager::SignatureScheme;
+
+/// Namespace validators sign consensus votes under, which simplex extends
+/// with the kind of each vote
+pub const CONSENSUS_NAMESPACE: &[u8] = b"ROMER_CONSENSUS";
+
+/// The validators whose signatures finalize blocks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidatorSet {
+    pub scheme: SignatureScheme,
+    /// Hex-encoded public keys
+    pub validators: Vec<String>,
+}
+
+impl ValidatorSet {
+    pub fn new(scheme: SignatureScheme, validators: &[PublicKey]) -> Self {
+        Self {
+            scheme,
+            validators: validators.iter().map(|key| hex(key)).collect(),
+        }
+    }
+
+    /// Ensures the set is non-empty and its keys are valid hex and unique
+    pub fn validate(&self) -> Result<(), LightError> {
+        if self.validators.is_empty() {
+            return Err(LightError::InvalidValidatorSet("no validators".to_string()));
+        }
+        let mut seen = HashSet::new();
+        for validator in &self.validators {
+            if from_hex(validator).is_none() {
+                return Err(LightError::InvalidValidatorSet(format!("{} is not hex", validator)));
+            }
+            if !seen.insert(validator) {
+                return Err(LightError::InvalidValidatorSet(format!("{} is listed twice", validator)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Signatures needed to finalize a block: all but the up to a third of
+    /// validators that may be faulty, so any two quorums share an honest one
+    pub fn quorum(&self) -> usize {
+        let count = self.validators.len();
+        count - count.saturating_sub(1) / 3
+    }
+
+    /// Digest identifying the set, independent of the order of its keys
+    pub fn digest(&self) -> Vec<u8> {
+        let mut keys: Vec<Vec<u8>> = self.validators.iter().filter_map(|key| from_hex(key)).collect();
+        keys.sort();
+        let mut hasher = Sha256::new();
+        hasher.update(&[self.scheme as u8]);
+        for key in keys {
+            hasher.update(&key);
+        }
+        hasher.finalize().to_vec()
+    }
+
+    /// Checks `proof` is a simplex finalization certificate signed by
+    /// distinct members reaching quorum, returning the finalized payload
+    pub fn verify_finalization(&self, proof: &[u8]) -> Result<Vec<u8>, LightError> {
+        let proof = Bytes::copy_from_slice(proof);
+        let max = self.validators.len() as u32;
+        let finalization = match self.scheme {
+            SignatureScheme::Ed25519 => {
+                Prover::<Ed25519, Sha256>::new(CONSENSUS_NAMESPACE).deserialize_finalization(proof, max, true)
+            }
+            SignatureScheme::Bls12381 => {
+                Prover::<Bls12381, Sha256>::new(CONSENSUS_NAMESPACE).deserialize_finalization(proof, max, true)
+            }
+        };
+        let (_, _, payload, signers) = finalization.ok_or(LightError::InvalidCertificate)?;
+
+        let mut members = HashSet::new();
+        for signer in signers {
+            let signer = hex(&signer);
+            if !self.validators.contains(&signer) {
+                return Err(LightError::UnknownSigner(signer));
+            }
+            members.insert(signer);
+        }
+        let quorum = self.quorum();
+        if members.len() < quorum {
+            return Err(LightError::QuorumNotReached {
+                signatures: members.len(),
+                quorum,
+            });
+        }
+        Ok(payload.to_vec())
+    }
+}
+
+/// Simplex finalization certificate for a block, as consensus emits it
+/// when the validators finalize the block's hash
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Commit {
+    /// The certificate, hex encoded
+    pub proof: String,
+}
+
+impl Commit {
+    pub fn new(proof: &[u8]) -> Self {
+        Self { proof: hex(proof) }
+    }
+
+    /// Checks a quorum of `validators` finalized a block, returning the
+    /// block's hash, hex encoded
+    pub fn verify(&self, validators: &ValidatorSet) -> Result<String, LightError> {
+        let proof = from_hex(&self.proof).ok_or(LightError::InvalidCertificate)?;
+        Ok(hex(&validators.verify_finalization(&proof)?))
+    }
+}
+
+/// Hand-over to a new validator set, committed to by the last block the
+/// outgoing set finalizes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidatorSetChange {
+    /// Last block the outgoing set finalizes
+    pub height: u64,
+    pub next: ValidatorSet,
+}
+
+impl ValidatorSetChange {
+    /// Checks the next set is valid and is the one `header`, finalized by
+    /// the outgoing set, hands over to
+    pub fn verify(&self, header: &BlockHeader) -> Result<(), LightError> {
+        self.next.validate()?;
+        if header.block_id != self.height || header.validators_digest != hex(&self.next.digest()) {
+            return Err(LightError::UncommittedValidatorSet(self.height));
+        }
+        Ok(())
+    }
+}
+
+/// Builds the certificate consensus emits when `signers` finalize
+/// `payload`: the proposal's view, parent view and payload, then each
+/// signer's key and its signature over them
+#[cfg(test)]
+pub(crate) fn finalization(payload: &[u8], signers: &mut [Ed25519]) -> Vec<u8> {
+    use commonware_cryptography::Scheme;
+    use commonware_utils::union;
+
+    let mut message = 5u64.to_be_bytes().to_vec();
+    message.extend_from_slice(&4u64.to_be_bytes());
+    message.extend_from_slice(payload);
+    let namespace = union(CONSENSUS_NAMESPACE, b"_FINALIZE");
+    let mut proof = message.clone();
+    proof.extend_from_slice(&(signers.len() as u32).to_be_bytes());
+    for signer in signers {
+        proof.extend_from_slice(&signer.public_key());
+        proof.extend_from_slice(&signer.sign(Some(&namespace), &message));
+    }
+    proof
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::protocol::GENESIS_PROTOCOL_VERSION;
+    use commonware_cryptography::Scheme;
+
+    fn signers(count: u64) -> Vec<Ed25519> {
+        (0..count).map(Ed25519::from_seed).collect()
+    }
+
+    fn set(signers: &[Ed25519]) -> ValidatorSet {
+        let keys: Vec<PublicKey> = signers.iter().map(Scheme::public_key).collect();
+        ValidatorSet::new(SignatureScheme::Ed25519, &keys)
+    }
+
+    #[test]
+    fn test_quorum() {
+        let quorums: Vec<usize> = [1, 2, 3, 4, 5, 7, 10]
+            .iter()
+            .map(|&count| set(&signers(count)).quorum())
+            .collect();
+        assert_eq!(quorums, vec![1, 2, 3, 3, 4, 5, 7]);
+    }
+
+    #[test]
+    fn test_commit() {
+        let mut signers = signers(4);
+        let validators = set(&signers);
+        validators.validate().unwrap();
+        let block_hash = [7u8; 32];
+        let commit = Commit::new(&finalization(&block_hash, &mut signers[..2]));
+        assert_eq!(
+            commit.verify(&validators),
+            Err(LightError::QuorumNotReached { signatures: 2, quorum: 3 })
+        );
+        let commit = Commit::new(&finalization(&block_hash, &mut signers[..3]));
+        assert_eq!(commit.verify(&validators), Ok(hex(&block_hash)));
+
+        let mut outsiders = signers[..3].to_vec();
+        outsiders.push(Ed25519::from_seed(9));
+        let commit = Commit::new(&finalization(&block_hash, &mut outsiders));
+        assert!(matches!(commit.verify(&validators), Err(LightError::UnknownSigner(_))));
+
+        // The signatures are over the block's hash, so another block's fails
+        let mut proof = finalization(&block_hash, &mut signers[..3]);
+        proof[16] ^= 1;
+        assert_eq!(Commit::new(&proof).verify(&validators), Err(LightError::InvalidCertificate));
+        assert_eq!(
+            Commit { proof: "zz".to_string() }.verify(&validators),
+            Err(LightError::InvalidCertificate)
+        );
+    }
+
+    #[test]
+    fn test_validator_set_change() {
+        let next = set(&signers(7)[3..]);
+        let mut header = BlockHeader {
+            block_id: 10,
+            previous_hash: String::new(),
+            timestamp: Default::default(),
+            message_count: 0,
+            messages_root: String::new(),
+            transaction_count: 0,
+            transactions_root: String::new(),
+            batch_sequence: 10,
+            protocol_version: GENESIS_PROTOCOL_VERSION,
+            state_root: String::new(),
+            oracle_root: String::new(),
+            validators_digest: String::new(),
+        };
+        let change = ValidatorSetChange { height: 10, next: next.clone() };
+        assert_eq!(change.verify(&header), Err(LightError::UncommittedValidatorSet(10)));
+
+        header.validators_digest = hex(&next.digest());
+        change.verify(&header).unwrap();
+        let moved = ValidatorSetChange { height: 11, ..change };
+        assert!(moved.verify(&header).is_err());
+    }
+}
@@ -0,0 +1,371 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use bytes::Bytes;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use object_store::aws::AmazonS3Builder;
+use object_store::path::Path as ObjectPath;
+use object_store::{ObjectStore, PutPayload};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::storage::compression::CompressionError;
+use crate::storage::journal::StorageConfig;
+use crate::storage::mmap::{MmapError, MmapSection};
+
+const MANIFEST: &str = "manifest.jsonl";
+
+#[derive(Error, Debug)]
+pub enum ArchiveError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Object store error: {0}")]
+    ObjectStore(#[from] object_store::Error),
+
+    #[error("Read error: {0}")]
+    Read(#[from] MmapError),
+
+    #[error("Decompression error: {0}")]
+    Compression(#[from] CompressionError),
+
+    #[error("Section {section} of {partition} is not archived")]
+    NotArchived { partition: String, section: u64 },
+
+    #[error("Invalid manifest entry: {0}")]
+    Manifest(String),
+}
+
+/// S3-compatible bucket archives are uploaded to. Credentials come from the
+/// usual `AWS_ACCESS_KEY_ID` / `AWS_SECRET_ACCESS_KEY` variables.
+#[derive(Debug, Clone)]
+pub struct ObjectStoreConfig {
+    pub bucket: String,
+    /// Custom endpoint for non-AWS stores such as MinIO
+    pub endpoint: Option<String>,
+    pub region: String,
+    /// Key prefix archives are stored under
+    pub prefix: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ArchiveConfig {
+    /// Where compressed archive files and the manifest live
+    pub archive_directory: PathBuf,
+    /// Newest sections of each partition kept in the live journal
+    pub retention_sections: u64,
+    pub object_store: Option<ObjectStoreConfig>,
+}
+
+/// One archived journal section, as recorded in the manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchivedSection {
+    pub partition: String,
+    pub section: u64,
+    /// Archive file name, relative to the archive directory
+    pub file: String,
+    pub original_bytes: u64,
+    pub compressed_bytes: u64,
+    /// Object key, once uploaded
+    pub object_key: Option<String>,
+}
+
+/// Moves journal sections older than the retention window into gzip
+/// compressed archive files, optionally uploads them, and rehydrates them on
+/// demand.
+///
+/// Archiving copies a section before removing it from the live partition.
+/// Only archive partitions no journal has open, or prune the owning journal
+/// to the returned floor instead of letting the archiver delete blobs.
+pub struct Archiver {
+    storage: StorageConfig,
+    config: ArchiveConfig,
+    store: Option<Arc<dyn ObjectStore>>,
+}
+
+impl Archiver {
+    pub fn new(storage: StorageConfig, config: ArchiveConfig) -> Result<Self, ArchiveError> {
+        let store = match &config.object_store {
+            Some(object_store) => {
+                let mut builder = AmazonS3Builder::from_env()
+                    .with_bucket_name(&object_store.bucket)
+                    .with_region(&object_store.region);
+                if let Some(endpoint) = &object_store.endpoint {
+                    builder = builder.with_endpoint(endpoint).with_allow_http(true);
+                }
+                Some(Arc::new(builder.build()?) as Arc<dyn ObjectStore>)
+            }
+            None => None,
+        };
+        fs::create_dir_all(&config.archive_directory)?;
+        Ok(Self { storage, config, store })
+    }
+
+    /// Sections currently in the live partition, oldest first
+    pub fn live_sections(&self, partition: &str) -> Result<Vec<u64>, ArchiveError> {
+        let dir = self.storage.storage_directory.join(partition);
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut sections: Vec<u64> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| parse_section(&entry.file_name().to_string_lossy()))
+            .collect();
+        sections.sort_unstable();
+        Ok(sections)
+    }
+
+    /// Archives every section of `partition` outside the retention window.
+    /// With `remove` the archived blobs are deleted from the live partition.
+    /// Returns the newly archived sections.
+    pub async fn archive_partition(
+        &self,
+        partition: &str,
+        remove: bool,
+    ) -> Result<Vec<ArchivedSection>, ArchiveError> {
+        let sections = self.live_sections(partition)?;
+        let keep = self.config.retention_sections as usize;
+        let expired = &sections[..sections.len().saturating_sub(keep)];
+        let manifest = self.manifest()?;
+
+        let mut archived = Vec::new();
+        for &section in expired {
+            let blob = self.blob_path(partition, section);
+            if !manifest.contains_key(&(partition.to_string(), section)) {
+                let entry = self.archive_section(partition, section, &blob).await?;
+                self.append_manifest(&entry)?;
+                archived.push(entry);
+            }
+            if remove {
+                fs::remove_file(&blob)?;
+            }
+        }
+
+        if !archived.is_empty() {
+            info!(partition, sections = archived.len(), "Archived journal sections");
+        }
+        Ok(archived)
+    }
+
+    async fn archive_section(
+        &self,
+        partition: &str,
+        section: u64,
+        blob: &Path,
+    ) -> Result<ArchivedSection, ArchiveError> {
+        let raw = fs::read(blob)?;
+        let file = format!("{}-{:020}.blob.gz", partition, section);
+        let path = self.config.archive_directory.join(&file);
+
+        // Write under a temporary name so a crash never leaves a partial archive
+        let tmp = path.with_extension("gz.tmp");
+        let mut encoder = GzEncoder::new(File::create(&tmp)?, Compression::default());
+        encoder.write_all(&raw)?;
+        encoder.finish()?.sync_all()?;
+        fs::rename(&tmp, &path)?;
+        let compressed = fs::read(&path)?;
+
+        let object_key = match (&self.store, &self.config.object_store) {
+            (Some(store), Some(object_store)) => {
+                let key = format!("{}/{}", object_store.prefix, file);
+                store
+                    .put(&ObjectPath::from(key.as_str()), PutPayload::from(compressed.clone()))
+                    .await?;
+                Some(key)
+            }
+            _ => None,
+        };
+
+        Ok(ArchivedSection {
+            partition: partition.to_string(),
+            section,
+            file,
+            original_bytes: raw.len() as u64,
+            compressed_bytes: compressed.len() as u64,
+            object_key,
+        })
+    }
+
+    /// Restores the archived sections of `partition` within `range` into
+    /// `dest`, laid out like a storage directory, fetching archives missing
+    /// locally from the object store. Returns the restored blob paths.
+    pub async fn rehydrate(
+        &self,
+        partition: &str,
+        range: RangeInclusive<u64>,
+        dest: &Path,
+    ) -> Result<Vec<PathBuf>, ArchiveError> {
+        let manifest = self.manifest()?;
+        let out_dir = dest.join(partition);
+        fs::create_dir_all(&out_dir)?;
+
+        let mut restored = Vec::new();
+        for section in range {
+            let Some(entry) = manifest.get(&(partition.to_string(), section)) else {
+                continue;
+            };
+            let compressed = self.fetch(entry).await?;
+            let mut raw = Vec::with_capacity(entry.original_bytes as usize);
+            GzDecoder::new(compressed.as_ref()).read_to_end(&mut raw)?;
+
+            let path = out_dir.join(section_name(section));
+            fs::write(&path, raw)?;
+            restored.push(path);
+        }
+        info!(partition, sections = restored.len(), "Rehydrated archived sections");
+        Ok(restored)
+    }
+
+    /// Entries of one archived section, decompressed, rehydrating it into
+    /// `scratch` first
+    pub async fn read_section(
+        &self,
+        partition: &str,
+        section: u64,
+        scratch: &Path,
+    ) -> Result<Vec<Bytes>, ArchiveError> {
+        let restored = self.rehydrate(partition, section..=section, scratch).await?;
+        let path = restored.first().ok_or_else(|| ArchiveError::NotArchived {
+            partition: partition.to_string(),
+            section,
+        })?;
+        let compressor = self.storage.compressor()?;
+        let mapped = MmapSection::open(path)?;
+        let mut entries = Vec::new();
+        for item in mapped.items() {
+            let (_, payload) = item?;
+            entries.push(Bytes::from(compressor.decompress(payload)?));
+        }
+        Ok(entries)
+    }
+
+    async fn fetch(&self, entry: &ArchivedSection) -> Result<Bytes, ArchiveError> {
+        let local = self.config.archive_directory.join(&entry.file);
+        if local.exists() {
+            return Ok(fs::read(local)?.into());
+        }
+        match (&self.store, &entry.object_key) {
+            (Some(store), Some(key)) => {
+                warn!(file = %entry.file, "Archive missing locally, downloading");
+                Ok(store.get(&ObjectPath::from(key.as_str())).await?.bytes().await?)
+            }
+            _ => Err(ArchiveError::NotArchived {
+                partition: entry.partition.clone(),
+                section: entry.section,
+            }),
+        }
+    }
+
+    /// Archived sections keyed by partition and section
+    pub fn manifest(&self) -> Result<BTreeMap<(String, u64), ArchivedSection>, ArchiveError> {
+        let path = self.config.archive_directory.join(MANIFEST);
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut manifest = BTreeMap::new();
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: ArchivedSection =
+                serde_json::from_str(&line).map_err(|e| ArchiveError::Manifest(e.to_string()))?;
+            manifest.insert((entry.partition.clone(), entry.section), entry);
+        }
+        Ok(manifest)
+    }
+
+    fn append_manifest(&self, entry: &ArchivedSection) -> Result<(), ArchiveError> {
+        let mut line = serde_json::to_string(entry).map_err(|e| ArchiveError::Manifest(e.to_string()))?;
+        line.push('\n');
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.config.archive_directory.join(MANIFEST))?;
+        file.write_all(line.as_bytes())?;
+        file.sync_all()?;
+        Ok(())
+    }
+
+    fn blob_path(&self, partition: &str, section: u64) -> PathBuf {
+        self.storage
+            .storage_directory
+            .join(partition)
+            .join(section_name(section))
+    }
+}
+
+/// Blob file name of a section, hex of its big-endian bytes
+fn section_name(section: u64) -> String {
+    section.to_be_bytes().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn parse_section(name: &str) -> Option<u64> {
+    if name.len() != 16 {
+        return None;
+    }
+    u64::from_str_radix(name, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(payload: &[u8]) -> Vec<u8> {
+        let mut bytes = (payload.len() as u32).to_be_bytes().to_vec();
+        bytes.extend_from_slice(payload);
+        bytes.extend_from_slice(&crc32fast::hash(payload).to_be_bytes());
+        bytes
+    }
+
+    #[tokio::test]
+    async fn test_archive_and_rehydrate() {
+        let root = std::env::temp_dir().join(format!("romer-archive-{}", std::process::id()));
+        let storage = StorageConfig {
+            storage_directory: root.join("storage"),
+            ..StorageConfig::default()
+        };
+        let partition_dir = storage.storage_directory.join("log");
+        fs::create_dir_all(&partition_dir).unwrap();
+        for section in 1..=4u64 {
+            fs::write(partition_dir.join(section_name(section)), frame(&section.to_be_bytes())).unwrap();
+        }
+
+        let archiver = Archiver::new(
+            storage,
+            ArchiveConfig {
+                archive_directory: root.join("archive"),
+                retention_sections: 2,
+                object_store: None,
+            },
+        )
+        .unwrap();
+
+        let archived = archiver.archive_partition("log", true).await.unwrap();
+        assert_eq!(archived.iter().map(|a| a.section).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(archiver.live_sections("log").unwrap(), vec![3, 4]);
+        // Already archived sections are not archived again
+        assert!(archiver.archive_partition("log", true).await.unwrap().is_empty());
+
+        let entries = archiver.read_section("log", 2, &root.join("scratch")).await.unwrap();
+        assert_eq!(entries, vec![Bytes::copy_from_slice(&2u64.to_be_bytes())]);
+        assert!(matches!(
+            archiver.read_section("log", 3, &root.join("scratch")).await,
+            Err(ArchiveError::NotArchived { section: 3, .. })
+        ));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,418 @@
+//! Write-ahead commit protocol for block execution.
+//!
+//! A block touches several stores: fills and other journal appends on the
+//! trading side, and Move state in the VM. To keep a crash between match and
+//! settlement from leaving phantom fills behind, every store takes part in a
+//! two-phase commit driven by `CommitCoordinator`:
+//!
+//! 1. `Begin` is logged and each participant durably writes its staged data,
+//!    tagged with the commit's transaction id (prepare).
+//! 2. If every participant prepared, `Commit` is logged and synced. This
+//!    record is the commit point. Otherwise `Abort` is logged and every
+//!    participant rolls back.
+//!
+//! On restart, participants replay their own journals and drop any entry
+//! whose transaction id the `Recovery` does not report as committed.
+
+use std::collections::BTreeSet;
+
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::storage::journal::RomerJournal;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum CommitError {
+    #[error("Commit log error: {0}")]
+    Log(String),
+
+    #[error("Participant {participant} failed to prepare: {reason}")]
+    Prepare { participant: String, reason: String },
+
+    #[error("Participant {participant} failed to apply committed txn {txn}: {reason}")]
+    Apply { participant: String, txn: u64, reason: String },
+}
+
+/// Record in the commit write-ahead log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CommitRecord {
+    Begin { txn: u64, height: u64 },
+    Commit { txn: u64 },
+    Abort { txn: u64 },
+}
+
+/// Outcome of replaying the commit log
+#[derive(Debug, Clone, Default)]
+pub struct Recovery {
+    committed: BTreeSet<u64>,
+    /// Transactions that began but never reached a decision. They are
+    /// treated as aborted.
+    in_doubt: Vec<u64>,
+    next_txn: u64,
+    last_committed_height: Option<u64>,
+}
+
+impl Recovery {
+    /// Rebuilds the commit state from log records in append order
+    pub fn from_records(records: impl IntoIterator<Item = CommitRecord>) -> Self {
+        let mut recovery = Self::default();
+        let mut open: Vec<(u64, u64)> = Vec::new();
+
+        for record in records {
+            match record {
+                CommitRecord::Begin { txn, height } => {
+                    open.push((txn, height));
+                    recovery.next_txn = recovery.next_txn.max(txn + 1);
+                }
+                CommitRecord::Commit { txn } => {
+                    if let Some(pos) = open.iter().position(|(t, _)| *t == txn) {
+                        let (_, height) = open.remove(pos);
+                        recovery.last_committed_height = Some(height);
+                    }
+                    recovery.committed.insert(txn);
+                }
+                CommitRecord::Abort { txn } => {
+                    open.retain(|(t, _)| *t != txn);
+                }
+            }
+        }
+
+        recovery.in_doubt = open.into_iter().map(|(txn, _)| txn).collect();
+        recovery
+    }
+
+    /// Whether entries tagged with `txn` are part of committed state
+    pub fn is_committed(&self, txn: u64) -> bool {
+        self.committed.contains(&txn)
+    }
+
+    pub fn in_doubt(&self) -> &[u64] {
+        &self.in_doubt
+    }
+
+    /// Height of the last block that was committed
+    pub fn last_committed_height(&self) -> Option<u64> {
+        self.last_committed_height
+    }
+}
+
+/// A store taking part in block commits
+pub trait Participant: Send {
+    fn name(&self) -> &str;
+
+    /// Durably writes staged changes tagged with `txn`. Must not make them
+    /// visible as committed state.
+    fn prepare(&mut self, txn: u64) -> BoxFuture<'_, Result<(), String>>;
+
+    /// Makes the changes prepared under `txn` the current state. Called only
+    /// after the commit point, so it should not fail for prepared data.
+    fn commit(&mut self, txn: u64) -> BoxFuture<'_, Result<(), String>>;
+
+    /// Discards changes staged or prepared under `txn`
+    fn rollback(&mut self, txn: u64) -> BoxFuture<'_, ()>;
+}
+
+/// Drives the two-phase commit of each block across participants
+pub struct CommitCoordinator {
+    journal: Option<RomerJournal>,
+    next_txn: u64,
+}
+
+impl CommitCoordinator {
+    /// Coordinator without a durable log, for tests and tooling
+    pub fn in_memory() -> Self {
+        Self {
+            journal: None,
+            next_txn: 0,
+        }
+    }
+
+    /// Opens the coordinator over its write-ahead log, resolving in-doubt
+    /// transactions as aborted. The returned `Recovery` is handed to each
+    /// participant when it replays its own journal.
+    pub async fn open(mut journal: RomerJournal) -> Result<(Self, Recovery), CommitError> {
+        let mut records = Vec::new();
+        for bytes in journal.replay_all().await.map_err(CommitError::Log)? {
+            match serde_json::from_slice(&bytes) {
+                Ok(record) => records.push(record),
+                Err(e) => warn!(error = %e, "Skipping undecodable commit record"),
+            }
+        }
+        let recovery = Recovery::from_records(records);
+
+        let mut coordinator = Self {
+            journal: Some(journal),
+            next_txn: recovery.next_txn,
+        };
+        for txn in recovery.in_doubt() {
+            warn!(txn, "Rolling back in-doubt block commit");
+            coordinator.log(CommitRecord::Abort { txn: *txn }).await?;
+        }
+        info!(
+            next_txn = recovery.next_txn,
+            last_height = ?recovery.last_committed_height(),
+            "Recovered commit log"
+        );
+
+        Ok((coordinator, recovery))
+    }
+
+    async fn log(&mut self, record: CommitRecord) -> Result<(), CommitError> {
+        let Some(journal) = self.journal.as_mut() else {
+            return Ok(());
+        };
+        let bytes = serde_json::to_vec(&record).map_err(|e| CommitError::Log(e.to_string()))?;
+        journal.append(bytes).await.map_err(CommitError::Log)?;
+        journal.sync().await.map_err(CommitError::Log)
+    }
+
+    /// Atomically commits the staged changes of every participant for the
+    /// block at `height`. Returns the transaction id on success; on failure
+    /// every participant has been rolled back.
+    pub async fn commit_block(
+        &mut self,
+        height: u64,
+        participants: &mut [&mut dyn Participant],
+    ) -> Result<u64, CommitError> {
+        let txn = self.next_txn;
+        self.next_txn += 1;
+
+        if let Err(e) = self.log(CommitRecord::Begin { txn, height }).await {
+            for participant in participants.iter_mut() {
+                participant.rollback(txn).await;
+            }
+            return Err(e);
+        }
+
+        // Phase one: every participant makes its changes durable
+        let mut failure = None;
+        for participant in participants.iter_mut() {
+            if let Err(reason) = participant.prepare(txn).await {
+                failure = Some(CommitError::Prepare {
+                    participant: participant.name().to_string(),
+                    reason,
+                });
+                break;
+            }
+        }
+
+        // Commit point
+        let decision = match failure {
+            None => self.log(CommitRecord::Commit { txn }).await.err(),
+            Some(e) => Some(e),
+        };
+        if let Some(error) = decision {
+            warn!(txn, height, error = %error, "Aborting block commit");
+            if let Err(e) = self.log(CommitRecord::Abort { txn }).await {
+                warn!(txn, error = %e, "Failed to log abort, recovery will roll back");
+            }
+            for participant in participants.iter_mut() {
+                participant.rollback(txn).await;
+            }
+            return Err(error);
+        }
+
+        // Phase two: publish. The block is committed even if this fails,
+        // and replay will restore it.
+        for participant in participants.iter_mut() {
+            participant
+                .commit(txn)
+                .await
+                .map_err(|reason| CommitError::Apply {
+                    participant: participant.name().to_string(),
+                    txn,
+                    reason,
+                })?;
+        }
+
+        Ok(txn)
+    }
+}
+
+/// Entry of a `StagedJournal`
+#[derive(Serialize, Deserialize)]
+struct StagedEntry {
+    txn: u64,
+    bytes: Vec<u8>,
+}
+
+/// Journal whose appends only become part of history when the block they
+/// belong to commits. Used for fills and other trading records.
+pub struct StagedJournal {
+    name: String,
+    journal: Option<RomerJournal>,
+    staged: Vec<Vec<u8>>,
+    prepared: Option<(u64, Vec<Vec<u8>>)>,
+    committed: Vec<Vec<u8>>,
+}
+
+impl StagedJournal {
+    pub fn in_memory(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            journal: None,
+            staged: Vec::new(),
+            prepared: None,
+            committed: Vec::new(),
+        }
+    }
+
+    /// Opens the journal and replays entries of committed transactions
+    pub async fn open(
+        name: impl Into<String>,
+        mut journal: RomerJournal,
+        recovery: &Recovery,
+    ) -> Result<Self, CommitError> {
+        let mut committed = Vec::new();
+        for bytes in journal.replay_all().await.map_err(CommitError::Log)? {
+            match serde_json::from_slice::<StagedEntry>(&bytes) {
+                Ok(entry) if recovery.is_committed(entry.txn) => committed.push(entry.bytes),
+                Ok(_) => {}
+                Err(e) => warn!(error = %e, "Skipping undecodable journal entry"),
+            }
+        }
+
+        Ok(Self {
+            journal: Some(journal),
+            ..Self::in_memory(name)
+        }
+        .with_committed(committed))
+    }
+
+    fn with_committed(mut self, committed: Vec<Vec<u8>>) -> Self {
+        self.committed = committed;
+        self
+    }
+
+    /// Stages an entry for the next commit
+    pub fn stage(&mut self, entry: Vec<u8>) {
+        self.staged.push(entry);
+    }
+
+    /// Entries of committed transactions, in order
+    pub fn committed(&self) -> &[Vec<u8>] {
+        &self.committed
+    }
+
+    pub fn staged(&self) -> &[Vec<u8>] {
+        &self.staged
+    }
+}
+
+impl Participant for StagedJournal {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn prepare(&mut self, txn: u64) -> BoxFuture<'_, Result<(), String>> {
+        Box::pin(async move {
+            let entries = std::mem::take(&mut self.staged);
+            if let Some(journal) = self.journal.as_mut() {
+                let records = entries
+                    .iter()
+                    .map(|bytes| {
+                        serde_json::to_vec(&StagedEntry {
+                            txn,
+                            bytes: bytes.clone(),
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| e.to_string())?;
+                journal.append_batch(records).await?;
+                journal.sync().await?;
+            }
+            self.prepared = Some((txn, entries));
+            Ok(())
+        })
+    }
+
+    fn commit(&mut self, txn: u64) -> BoxFuture<'_, Result<(), String>> {
+        Box::pin(async move {
+            match self.prepared.take() {
+                Some((prepared, entries)) if prepared == txn => {
+                    self.committed.extend(entries);
+                    Ok(())
+                }
+                _ => Err(format!("txn {} was not prepared", txn)),
+            }
+        })
+    }
+
+    fn rollback(&mut self, _txn: u64) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            self.staged.clear();
+            self.prepared = None;
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Failing;
+
+    impl Participant for Failing {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        fn prepare(&mut self, _txn: u64) -> BoxFuture<'_, Result<(), String>> {
+            Box::pin(async { Err("disk full".to_string()) })
+        }
+
+        fn commit(&mut self, _txn: u64) -> BoxFuture<'_, Result<(), String>> {
+            Box::pin(async { Ok(()) })
+        }
+
+        fn rollback(&mut self, _txn: u64) -> BoxFuture<'_, ()> {
+            Box::pin(async {})
+        }
+    }
+
+    #[test]
+    fn test_recovery_from_records() {
+        let recovery = Recovery::from_records(vec![
+            CommitRecord::Begin { txn: 0, height: 10 },
+            CommitRecord::Commit { txn: 0 },
+            CommitRecord::Begin { txn: 1, height: 11 },
+            CommitRecord::Abort { txn: 1 },
+            CommitRecord::Begin { txn: 2, height: 11 },
+        ]);
+
+        assert!(recovery.is_committed(0));
+        assert!(!recovery.is_committed(1));
+        assert!(!recovery.is_committed(2));
+        assert_eq!(recovery.in_doubt(), &[2]);
+        assert_eq!(recovery.last_committed_height(), Some(10));
+        assert_eq!(recovery.next_txn, 3);
+    }
+
+    #[tokio::test]
+    async fn test_commit_publishes_staged_entries() {
+        let mut coordinator = CommitCoordinator::in_memory();
+        let mut fills = StagedJournal::in_memory("fills");
+        fills.stage(b"fill-1".to_vec());
+
+        let txn = coordinator.commit_block(1, &mut [&mut fills]).await.unwrap();
+        assert_eq!(txn, 0);
+        assert_eq!(fills.committed(), &[b"fill-1".to_vec()]);
+        assert!(fills.staged().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_prepare_rolls_back_everyone() {
+        let mut coordinator = CommitCoordinator::in_memory();
+        let mut fills = StagedJournal::in_memory("fills");
+        fills.stage(b"fill-1".to_vec());
+
+        let result = coordinator
+            .commit_block(1, &mut [&mut fills, &mut Failing])
+            .await;
+        assert!(matches!(result, Err(CommitError::Prepare { .. })));
+        assert!(fills.committed().is_empty());
+        assert!(fills.staged().is_empty());
+    }
+}
@@ -0,0 +1,265 @@
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use thiserror::Error;
+
+/// Format byte of a payload stored as it is
+pub const FORMAT_RAW: u8 = 0;
+
+/// Format byte of a payload compressed to a zstd frame
+pub const FORMAT_ZSTD: u8 = 1;
+
+/// Format byte of a payload compressed to a zstd frame with the dictionary
+pub const FORMAT_ZSTD_DICTIONARY: u8 = 2;
+
+/// Highest zstd compression level
+pub const MAX_LEVEL: i32 = 22;
+
+/// Default size of a trained dictionary
+pub const DEFAULT_DICTIONARY_SIZE: usize = 64 * 1024;
+
+#[derive(Error, Debug)]
+pub enum CompressionError {
+    #[error("Compression level {0} is outside 0..=22")]
+    InvalidLevel(i32),
+
+    #[error("Failed to read dictionary {path}: {source}")]
+    Dictionary { path: String, source: io::Error },
+
+    #[error("Not enough samples to train a dictionary: {0}")]
+    Training(String),
+
+    #[error("Unknown payload format {0}")]
+    UnknownFormat(u8),
+
+    #[error("Payload has no format byte")]
+    Empty,
+
+    #[error("Payload was compressed with a dictionary, and none is configured")]
+    MissingDictionary,
+
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// zstd compression of journal entries and gossip payloads, optionally
+/// with a dictionary trained on past entries. Short payloads such as
+/// single FIX messages share most of their bytes with each other but little
+/// within themselves, so they only compress well with a dictionary.
+///
+/// Every payload is written behind a format byte saying how it was stored:
+/// `FORMAT_RAW`, `FORMAT_ZSTD` or `FORMAT_ZSTD_DICTIONARY`. Payloads are
+/// read back by their format whatever the level, so compression can be
+/// turned on or off over existing data.
+#[derive(Clone, Default)]
+pub struct Compressor {
+    /// Zero leaves payloads uncompressed
+    level: i32,
+    dictionary: Option<Arc<Vec<u8>>>,
+}
+
+impl Compressor {
+    pub fn new(level: i32) -> Result<Self, CompressionError> {
+        if !(0..=MAX_LEVEL).contains(&level) {
+            return Err(CompressionError::InvalidLevel(level));
+        }
+        Ok(Self { level, dictionary: None })
+    }
+
+    /// Compresses and decompresses with `dictionary`. Payloads compressed
+    /// with a dictionary can only be read back with the same one.
+    pub fn with_dictionary(mut self, dictionary: Vec<u8>) -> Self {
+        self.dictionary = Some(Arc::new(dictionary));
+        self
+    }
+
+    /// Reads the dictionary written by `train_dictionary` from `path`
+    pub fn with_dictionary_file(self, path: &Path) -> Result<Self, CompressionError> {
+        let dictionary = std::fs::read(path).map_err(|source| CompressionError::Dictionary {
+            path: path.display().to_string(),
+            source,
+        })?;
+        Ok(self.with_dictionary(dictionary))
+    }
+
+    pub fn level(&self) -> i32 {
+        self.level
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.level > 0
+    }
+
+    pub fn has_dictionary(&self) -> bool {
+        self.dictionary.is_some()
+    }
+
+    /// `payload` behind its format byte, as a zstd frame unless
+    /// compression is off
+    pub fn compress(&self, payload: &[u8]) -> Result<Vec<u8>, CompressionError> {
+        if !self.is_enabled() {
+            let mut stored = Vec::with_capacity(payload.len() + 1);
+            stored.push(FORMAT_RAW);
+            stored.extend_from_slice(payload);
+            return Ok(stored);
+        }
+        let mut encoder = match &self.dictionary {
+            Some(dictionary) => {
+                zstd::stream::Encoder::with_dictionary(vec![FORMAT_ZSTD_DICTIONARY], self.level, dictionary)?
+            }
+            None => zstd::stream::Encoder::new(vec![FORMAT_ZSTD], self.level)?,
+        };
+        encoder.include_contentsize(true)?;
+        encoder.set_pledged_src_size(Some(payload.len() as u64))?;
+        encoder.write_all(payload)?;
+        Ok(encoder.finish()?)
+    }
+
+    /// The original of a payload written by `compress`, read by its format
+    /// byte whatever the level
+    pub fn decompress(&self, payload: &[u8]) -> Result<Vec<u8>, CompressionError> {
+        let (&format, body) = payload.split_first().ok_or(CompressionError::Empty)?;
+        let mut decompressed = Vec::new();
+        match format {
+            FORMAT_RAW => return Ok(body.to_vec()),
+            FORMAT_ZSTD => zstd::stream::Decoder::new(body)?.read_to_end(&mut decompressed)?,
+            FORMAT_ZSTD_DICTIONARY => {
+                let dictionary = self.dictionary.as_ref().ok_or(CompressionError::MissingDictionary)?;
+                zstd::stream::Decoder::with_dictionary(body, dictionary)?.read_to_end(&mut decompressed)?
+            }
+            other => return Err(CompressionError::UnknownFormat(other)),
+        };
+        Ok(decompressed)
+    }
+}
+
+/// Whether `payload`, as written by `compress`, is a zstd frame
+pub fn is_compressed(payload: &[u8]) -> bool {
+    matches!(payload.first().copied(), Some(FORMAT_ZSTD | FORMAT_ZSTD_DICTIONARY))
+}
+
+/// Trains a dictionary of up to `max_size` bytes on `samples`, which
+/// should be representative payloads such as journal entries
+pub fn train_dictionary(samples: &[Vec<u8>], max_size: usize) -> Result<Vec<u8>, CompressionError> {
+    zstd::dict::from_samples(samples, max_size).map_err(|e| CompressionError::Training(e.to_string()))
+}
+
+/// Size and CPU cost of compressing a set of samples one by one
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompressionTrial {
+    pub level: i32,
+    pub dictionary: bool,
+    pub input_bytes: usize,
+    pub output_bytes: usize,
+    pub compress_time: Duration,
+    pub decompress_time: Duration,
+}
+
+impl CompressionTrial {
+    /// Input bytes per output byte
+    pub fn ratio(&self) -> f64 {
+        self.input_bytes as f64 / self.output_bytes.max(1) as f64
+    }
+
+    /// Input megabytes compressed per second of CPU
+    pub fn compress_mb_per_sec(&self) -> f64 {
+        self.input_bytes as f64 / 1_000_000.0 / self.compress_time.as_secs_f64().max(f64::EPSILON)
+    }
+
+    pub fn decompress_mb_per_sec(&self) -> f64 {
+        self.input_bytes as f64 / 1_000_000.0 / self.decompress_time.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+/// Compresses and decompresses every sample with `compressor`, as a
+/// journal or gossip would, checking each round trip
+pub fn measure(compressor: &Compressor, samples: &[Vec<u8>]) -> Result<CompressionTrial, CompressionError> {
+    let started = Instant::now();
+    let compressed = samples
+        .iter()
+        .map(|sample| compressor.compress(sample))
+        .collect::<Result<Vec<_>, _>>()?;
+    let compress_time = started.elapsed();
+
+    let started = Instant::now();
+    for (sample, frame) in samples.iter().zip(&compressed) {
+        if compressor.decompress(frame)? != *sample {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "round trip changed a sample").into());
+        }
+    }
+    Ok(CompressionTrial {
+        level: compressor.level(),
+        dictionary: compressor.has_dictionary(),
+        input_bytes: samples.iter().map(Vec::len).sum(),
+        output_bytes: compressed.iter().map(Vec::len).sum(),
+        compress_time,
+        decompress_time: started.elapsed(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fix_messages() -> Vec<Vec<u8>> {
+        (0..2_000)
+            .map(|i| {
+                format!(
+                    "8=FIX.4.2\x019=150\x0135=D\x0149=MM{}\x0156=ROMER\x0134={}\x0152=20260115-10:{:02}:{:02}.{:03}\x01\
+                     11=order-{}\x0155=ROMER-USD\x0154={}\x0138={}\x0140=2\x0144={}.{:02}\x0159=0\x0110={:03}\x01",
+                    i % 7,
+                    i,
+                    i / 60 % 60,
+                    i % 60,
+                    i * 37 % 1000,
+                    i,
+                    i % 2 + 1,
+                    (i * 13) % 500 + 1,
+                    100 + i % 20,
+                    i % 100,
+                    i % 256
+                )
+                .into_bytes()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_dictionary_round_trip() {
+        let samples = fix_messages();
+        let plain = Compressor::new(3).unwrap();
+        let dictionary = train_dictionary(&samples[..1_000], 4 * 1024).unwrap();
+        let trained = Compressor::new(3).unwrap().with_dictionary(dictionary);
+
+        let without = measure(&plain, &samples[1_000..]).unwrap();
+        let with = measure(&trained, &samples[1_000..]).unwrap();
+        assert!(with.ratio() > without.ratio() * 2.0, "{} vs {}", with.ratio(), without.ratio());
+        assert!(with.ratio() > 2.0);
+
+        // Entries written before compression was turned on still read back
+        let stored = Compressor::default().compress(&samples[0]).unwrap();
+        assert!(!is_compressed(&stored));
+        assert_eq!(trained.decompress(&stored).unwrap(), samples[0]);
+        assert!(is_compressed(&trained.compress(&samples[0]).unwrap()));
+        assert!(matches!(Compressor::new(23), Err(CompressionError::InvalidLevel(23))));
+    }
+
+    #[test]
+    fn test_format_byte_decides() {
+        let plain = Compressor::new(3).unwrap();
+        let trained = Compressor::new(3).unwrap().with_dictionary(train_dictionary(&fix_messages(), 4 * 1024).unwrap());
+
+        // A raw payload that happens to start like a zstd frame is not
+        // mistaken for one
+        let payload = [0x28, 0xB5, 0x2F, 0xFD, 1, 2, 3];
+        assert_eq!(plain.decompress(&Compressor::default().compress(&payload).unwrap()).unwrap(), payload);
+
+        let compressed = trained.compress(b"35=D").unwrap();
+        assert_eq!(compressed[0], FORMAT_ZSTD_DICTIONARY);
+        assert!(matches!(plain.decompress(&compressed), Err(CompressionError::MissingDictionary)));
+        assert!(matches!(plain.decompress(&[9, 1]), Err(CompressionError::UnknownFormat(9))));
+        assert!(matches!(plain.decompress(&[]), Err(CompressionError::Empty)));
+    }
+}
@@ -0,0 +1,135 @@
+use std::time::Duration;
+
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, error};
+
+use crate::storage::journal::{FsyncPolicy, RomerJournal};
+
+enum Request {
+    Append(Vec<u8>, oneshot::Sender<Result<(), String>>),
+    EndBlock(oneshot::Sender<Result<(), String>>),
+}
+
+/// Funnels appends from many tasks into one `RomerJournal`, so concurrent
+/// writers share batched appends and a single fsync per group instead of
+/// paying for one each.
+///
+/// `append` resolves once the entry is durable under the journal's fsync
+/// policy: immediately after the group's sync for `PerWrite`, at the next
+/// interval sync for `Interval`, and once appended for `PerBlock`, where
+/// durability comes with `end_block`.
+#[derive(Clone)]
+pub struct GroupCommitter {
+    requests: mpsc::Sender<Request>,
+}
+
+impl GroupCommitter {
+    /// Takes ownership of `journal` and starts the commit task
+    pub fn spawn(journal: RomerJournal) -> Self {
+        let (requests, rx) = mpsc::channel(journal.config().max_batch.max(1) * 4);
+        tokio::spawn(run(journal, rx));
+        Self { requests }
+    }
+
+    /// Appends `entry` as part of the next group
+    pub async fn append(&self, entry: Vec<u8>) -> Result<(), String> {
+        let (done, rx) = oneshot::channel();
+        self.requests
+            .send(Request::Append(entry, done))
+            .await
+            .map_err(|_| "group commit task stopped".to_string())?;
+        rx.await.map_err(|_| "group commit task stopped".to_string())?
+    }
+
+    /// Syncs everything appended so far, marking the end of a block
+    pub async fn end_block(&self) -> Result<(), String> {
+        let (done, rx) = oneshot::channel();
+        self.requests
+            .send(Request::EndBlock(done))
+            .await
+            .map_err(|_| "group commit task stopped".to_string())?;
+        rx.await.map_err(|_| "group commit task stopped".to_string())?
+    }
+}
+
+async fn run(mut journal: RomerJournal, mut requests: mpsc::Receiver<Request>) {
+    let max_batch = journal.config().max_batch.max(1);
+    let tick = match journal.config().fsync {
+        FsyncPolicy::Interval(interval) => interval,
+        // Only used to drive `sync_if_due`, which never syncs for these
+        FsyncPolicy::PerWrite | FsyncPolicy::PerBlock => Duration::from_secs(3600),
+    };
+    let mut timer = tokio::time::interval(tick);
+    // Appends waiting for the next sync
+    let mut waiting: Vec<oneshot::Sender<Result<(), String>>> = Vec::new();
+
+    loop {
+        let first = tokio::select! {
+            request = requests.recv() => match request {
+                Some(request) => request,
+                None => break,
+            },
+            _ = timer.tick() => {
+                let result = journal.sync_if_due().await;
+                if matches!(result, Ok(true) | Err(_)) {
+                    complete(&mut waiting, result.map(|_| ()));
+                }
+                continue;
+            }
+        };
+
+        // Drain whatever else is already queued into the same group
+        let mut group = vec![first];
+        while group.len() < max_batch {
+            match requests.try_recv() {
+                Ok(request) => group.push(request),
+                Err(_) => break,
+            }
+        }
+
+        let mut entries = Vec::with_capacity(group.len());
+        let mut block_ends = Vec::new();
+        for request in group {
+            match request {
+                Request::Append(entry, done) => {
+                    entries.push(entry);
+                    waiting.push(done);
+                }
+                Request::EndBlock(done) => block_ends.push(done),
+            }
+        }
+
+        let count = entries.len();
+        let result = match journal.append_batch(entries).await {
+            Ok(_) if !block_ends.is_empty() => journal.end_block().await.map(|_| true),
+            Ok(_) => Ok(journal.unsynced() == 0),
+            Err(e) => Err(e),
+        };
+        debug!(entries = count, unsynced = journal.unsynced(), "Group commit");
+
+        match result {
+            // Synced, or durable by appending alone
+            Ok(synced) if synced || journal.config().fsync == FsyncPolicy::PerBlock => {
+                complete(&mut waiting, Ok(()));
+            }
+            Ok(_) => {}
+            Err(e) => {
+                error!(error = %e, "Group commit failed");
+                complete(&mut waiting, Err(e.clone()));
+                complete(&mut block_ends, Err(e));
+                continue;
+            }
+        }
+        complete(&mut block_ends, Ok(()));
+    }
+
+    if let Err(e) = journal.sync().await {
+        error!(error = %e, "Final group commit sync failed");
+    }
+}
+
+fn complete(waiting: &mut Vec<oneshot::Sender<Result<(), String>>>, result: Result<(), String>) {
+    for done in waiting.drain(..) {
+        let _ = done.send(result.clone());
+    }
+}
@@ -1,12 +1,21 @@
 use commonware_runtime::tokio::{self, Blob, Context};
+use bytes::Bytes;
 use commonware_storage::journal::{self, Journal};
+use futures::{pin_mut, StreamExt};
 use prometheus_client::registry::Registry;
 use serde::{Deserialize, Serialize};
 use std::io::{self, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
+use crate::storage::compression::{CompressionError, Compressor};
+use crate::storage::metrics::StorageMetrics;
+use crate::storage::mmap::{blob_path, MmapError, MmapSection};
 use crate::types::org::{Organization, OrganizationType};
+use tracing::debug;
 
 #[derive(Serialize, Deserialize)]
 pub enum JournalEntry {
@@ -18,11 +27,132 @@ pub enum JournalEntry {
 pub enum Partition {
     SYSTEM,
     TRADING,
+    VM,
+    COMMIT,
+    SESSION,
+    MARKET_DATA,
+}
+
+impl Partition {
+    /// Storage partition backing the journal
+    pub fn name(&self) -> &'static str {
+        match self {
+            Partition::SYSTEM => "system",
+            Partition::TRADING => "trading",
+            Partition::VM => "vm",
+            Partition::COMMIT => "commit",
+            Partition::SESSION => "session",
+            Partition::MARKET_DATA => "market_data",
+        }
+    }
 }
 
 pub enum Section {
-    ORGANIZATION
+    ORGANIZATION,
+    STATE,
+    FILLS,
+    WAL,
+    DEDUP,
+    CANDLES,
+    ORDERS,
+    NEWS,
+    API_KEYS,
+}
+
+impl Section {
+    /// Journal section entries are appended to
+    pub fn id(&self) -> u64 {
+        match self {
+            Section::ORGANIZATION => 1,
+            Section::STATE => 1,
+            Section::FILLS => 1,
+            Section::WAL => 1,
+            Section::DEDUP => 1,
+            Section::CANDLES => 1,
+            Section::ORDERS => 1,
+            Section::NEWS => 1,
+            Section::API_KEYS => 1,
+        }
+    }
+}
+/// When appended entries are made durable
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsyncPolicy {
+    /// Sync after every append or batch of appends
+    PerWrite,
+    /// Sync only when the block is sealed with `end_block`
+    PerBlock,
+    /// Sync once the oldest unsynced entry is this old, or a full batch is pending
+    Interval(Duration),
+}
+
+impl FromStr for FsyncPolicy {
+    type Err = String;
+
+    /// Parses `write`, `block` or `interval:<millis>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "write" => Ok(FsyncPolicy::PerWrite),
+            "block" => Ok(FsyncPolicy::PerBlock),
+            other => other
+                .strip_prefix("interval:")
+                .and_then(|ms| ms.parse().ok())
+                .map(|ms| FsyncPolicy::Interval(Duration::from_millis(ms)))
+                .ok_or_else(|| format!("invalid fsync policy: {}", other)),
+        }
+    }
+}
+
+/// Storage settings shared by every `RomerJournal`
+#[derive(Debug, Clone)]
+pub struct StorageConfig {
+    /// Directory holding the journal partitions
+    pub storage_directory: PathBuf,
+    pub fsync: FsyncPolicy,
+    /// Unsynced entries that force a sync under `FsyncPolicy::Interval`,
+    /// and the most entries a group commit appends at once
+    pub max_batch: usize,
+    /// zstd level entries are compressed at, 1 to 22, or 0 to store them
+    /// as they are. Entries are read back whatever the level they were
+    /// written at.
+    pub compression_level: i32,
+    /// Dictionary trained on past entries, which short entries compress
+    /// far better with. Entries written with it can't be read without it.
+    pub compression_dictionary: Option<PathBuf>,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            storage_directory: "devnet-storage".into(),
+            fsync: FsyncPolicy::PerWrite,
+            max_batch: 1024,
+            compression_level: 0,
+            compression_dictionary: None,
+        }
+    }
+}
+
+impl StorageConfig {
+    /// The same settings over `markets/<market_id>` of the storage
+    /// directory, so each market's partitions are kept apart
+    pub fn for_market(&self, market_id: &str) -> Self {
+        Self {
+            storage_directory: self.storage_directory.join("markets").join(market_id),
+            ..self.clone()
+        }
+    }
+
+    /// Compressor of the configured level and dictionary
+    pub fn compressor(&self) -> Result<Compressor, CompressionError> {
+        let compressor = Compressor::new(self.compression_level)?;
+        match &self.compression_dictionary {
+            Some(path) => compressor.with_dictionary_file(path),
+            None => Ok(compressor),
+        }
+    }
 }
+
 pub struct RomerJournal {
     /// The core journal instance for storage and retrieval
     pub journal: Journal<tokio::Blob, tokio::Context>,
@@ -32,6 +162,18 @@ pub struct RomerJournal {
 
     /// The section or subsystem within the partition
     pub section: Section,
+
+    config: StorageConfig,
+
+    /// Entries appended since the last sync
+    unsynced: usize,
+
+    /// When the oldest unsynced entry was appended
+    unsynced_since: Option<Instant>,
+
+    metrics: Option<StorageMetrics>,
+
+    compressor: Compressor,
 }
 
 impl RomerJournal {
@@ -39,8 +181,17 @@ impl RomerJournal {
         partition: Partition,
         section: Section
     ) -> Result<Self, String> {
+        Self::with_config(partition, section, StorageConfig::default()).await
+    }
+
+    pub async fn with_config(
+        partition: Partition,
+        section: Section,
+        config: StorageConfig,
+    ) -> Result<Self, String> {
+        let compressor = config.compressor().map_err(|e| e.to_string())?;
         let runtime_cfg = tokio::Config {
-            storage_directory: "devnet-storage".into(),
+            storage_directory: config.storage_directory.clone(),
             ..Default::default()
         };
 
@@ -50,7 +201,7 @@ impl RomerJournal {
             runtime,
             journal::Config {
                 registry: Arc::new(Mutex::new(Registry::default())),
-                partition: String::from("system"),
+                partition: String::from(partition.name()),
             },
         )
         .await
@@ -60,7 +211,166 @@ impl RomerJournal {
             journal,
             partition,
             section,
+            config,
+            unsynced: 0,
+            unsynced_since: None,
+            metrics: None,
+            compressor,
          })
     }
 
+    /// Record write throughput and sync latency in `metrics`
+    pub fn with_metrics(mut self, metrics: StorageMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    pub fn config(&self) -> &StorageConfig {
+        &self.config
+    }
+
+    /// Reads back every entry in the journal, in append order and
+    /// decompressed. Reads the section through a memory map when possible,
+    /// falling back to streaming replay.
+    pub async fn replay_all(&mut self) -> Result<Vec<Bytes>, String> {
+        let entries = match self.replay_mapped() {
+            Ok(entries) => entries,
+            Err(e) => {
+                debug!(error = %e, "Mapped replay unavailable, streaming instead");
+                self.replay_streamed().await?
+            }
+        };
+        entries
+            .iter()
+            .map(|entry| self.compressor.decompress(entry).map(Bytes::from).map_err(|e| e.to_string()))
+            .collect()
+    }
+
+    /// Reads the section's blob through a memory map, as stored
+    pub fn replay_mapped(&self) -> Result<Vec<Bytes>, MmapError> {
+        let path = blob_path(&self.config, &self.partition, self.section.id());
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        MmapSection::open(path)?
+            .items()
+            .map(|item| item.map(|(_, payload)| Bytes::copy_from_slice(payload)))
+            .collect()
+    }
+
+    async fn replay_streamed(&mut self) -> Result<Vec<Bytes>, String> {
+        let stream = self.journal.replay(1).await.map_err(|e| e.to_string())?;
+        pin_mut!(stream);
+
+        let mut entries = Vec::new();
+        while let Some(item) = stream.next().await {
+            let (_, _, _, bytes) = item.map_err(|e| e.to_string())?;
+            entries.push(bytes);
+        }
+        Ok(entries)
+    }
+
+    /// Appends `entry` to this journal's section, syncing as the fsync
+    /// policy requires
+    pub async fn append(&mut self, entry: Vec<u8>) -> Result<(), String> {
+        self.append_unsynced(entry).await?;
+        self.sync_if_due().await.map(|_| ())
+    }
+
+    /// Appends every entry, then syncs at most once as the fsync policy
+    /// requires. Returns the number of entries appended.
+    pub async fn append_batch(
+        &mut self,
+        entries: impl IntoIterator<Item = Vec<u8>>,
+    ) -> Result<usize, String> {
+        let mut appended = 0;
+        for entry in entries {
+            self.append_unsynced(entry).await?;
+            appended += 1;
+        }
+        self.sync_if_due().await?;
+        Ok(appended)
+    }
+
+    async fn append_unsynced(&mut self, entry: Vec<u8>) -> Result<(), String> {
+        let started = Instant::now();
+        let stored = self.compressor.compress(&entry).map_err(|e| e.to_string())?;
+        if let Some(metrics) = &self.metrics {
+            metrics.record_write(&self.partition, entry.len());
+            if self.compressor.is_enabled() {
+                metrics.record_compression(&self.partition, stored.len(), started.elapsed());
+            }
+        }
+        self.journal
+            .append(self.section.id(), stored.into())
+            .await
+            .map_err(|e| e.to_string())?;
+        self.unsynced += 1;
+        self.unsynced_since.get_or_insert_with(Instant::now);
+        Ok(())
+    }
+
+    /// Syncs if the fsync policy calls for it now. Under an interval policy
+    /// this should also be called from a timer so idle journals still sync.
+    /// Returns whether a sync happened.
+    pub async fn sync_if_due(&mut self) -> Result<bool, String> {
+        let due = match self.config.fsync {
+            FsyncPolicy::PerWrite => true,
+            FsyncPolicy::PerBlock => false,
+            FsyncPolicy::Interval(interval) => {
+                self.unsynced >= self.config.max_batch
+                    || self.unsynced_since.is_some_and(|since| since.elapsed() >= interval)
+            }
+        };
+        if due && self.unsynced > 0 {
+            self.sync().await?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Marks the end of a block, syncing everything appended during it
+    pub async fn end_block(&mut self) -> Result<(), String> {
+        self.sync().await
+    }
+
+    /// Makes every append so far durable, whatever the fsync policy
+    pub async fn sync(&mut self) -> Result<(), String> {
+        if self.unsynced == 0 {
+            return Ok(());
+        }
+        let started = Instant::now();
+        self.journal
+            .sync(self.section.id())
+            .await
+            .map_err(|e| e.to_string())?;
+        if let Some(metrics) = &self.metrics {
+            metrics.record_sync(&self.partition, started.elapsed());
+        }
+        self.unsynced = 0;
+        self.unsynced_since = None;
+        Ok(())
+    }
+
+    /// Entries appended but not yet synced
+    pub fn unsynced(&self) -> usize {
+        self.unsynced
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fsync_policy() {
+        assert_eq!("write".parse(), Ok(FsyncPolicy::PerWrite));
+        assert_eq!("block".parse(), Ok(FsyncPolicy::PerBlock));
+        assert_eq!(
+            "interval:5".parse(),
+            Ok(FsyncPolicy::Interval(Duration::from_millis(5)))
+        );
+        assert!("interval:soon".parse::<FsyncPolicy>().is_err());
+        assert!("never".parse::<FsyncPolicy>().is_err());
+    }
 }
@@ -0,0 +1,336 @@
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::family::Family;
+use prometheus_client::metrics::gauge::Gauge;
+use prometheus_client::metrics::histogram::Histogram;
+use prometheus_client::registry::Registry;
+use tracing::{error, info, warn};
+
+use crate::storage::journal::{Partition, StorageConfig};
+
+/// Buckets (in seconds) used for the journal sync latency histogram
+const SYNC_BUCKETS: [f64; 8] = [0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0];
+
+/// Buckets (in seconds) used for the entry compression time histogram
+const COMPRESSION_BUCKETS: [f64; 7] = [0.000_005, 0.00001, 0.00005, 0.0001, 0.0005, 0.001, 0.01];
+
+/// Every partition a `RomerJournal` can write to
+const PARTITIONS: [Partition; 6] = [
+    Partition::SYSTEM,
+    Partition::TRADING,
+    Partition::VM,
+    Partition::COMMIT,
+    Partition::SESSION,
+    Partition::MARKET_DATA,
+];
+
+type PartitionLabels = Vec<(&'static str, &'static str)>;
+
+fn labels(partition: &Partition) -> PartitionLabels {
+    vec![("partition", partition.name())]
+}
+
+/// Journal storage metrics, labelled by partition
+#[derive(Clone)]
+pub struct StorageMetrics {
+    /// Bytes on disk per partition
+    pub partition_size_bytes: Family<PartitionLabels, Gauge>,
+    /// Payload bytes appended per partition
+    pub bytes_written: Family<PartitionLabels, Counter>,
+    /// Entries appended per partition
+    pub entries_written: Family<PartitionLabels, Counter>,
+    /// Time spent in journal syncs per partition
+    pub sync_seconds: Family<PartitionLabels, Histogram>,
+    /// Bytes appended per partition after compression
+    pub bytes_compressed: Family<PartitionLabels, Counter>,
+    /// Time spent compressing each entry per partition
+    pub compression_seconds: Family<PartitionLabels, Histogram>,
+    /// Free space on the storage volume
+    pub disk_available_bytes: Gauge,
+}
+
+impl StorageMetrics {
+    /// Creates the metrics and registers them under the `romer_storage` prefix
+    pub fn new(registry: &Arc<Mutex<Registry>>) -> Self {
+        let metrics = Self {
+            partition_size_bytes: Family::default(),
+            bytes_written: Family::default(),
+            entries_written: Family::default(),
+            sync_seconds: Family::new_with_constructor(|| Histogram::new(SYNC_BUCKETS.into_iter())),
+            bytes_compressed: Family::default(),
+            compression_seconds: Family::new_with_constructor(|| Histogram::new(COMPRESSION_BUCKETS.into_iter())),
+            disk_available_bytes: Gauge::default(),
+        };
+
+        let mut registry = registry.lock().unwrap();
+        let registry = registry.sub_registry_with_prefix("romer_storage");
+        registry.register(
+            "partition_size_bytes",
+            "Bytes on disk per journal partition",
+            metrics.partition_size_bytes.clone(),
+        );
+        registry.register(
+            "bytes_written",
+            "Payload bytes appended per journal partition",
+            metrics.bytes_written.clone(),
+        );
+        registry.register(
+            "entries_written",
+            "Entries appended per journal partition",
+            metrics.entries_written.clone(),
+        );
+        registry.register(
+            "sync_seconds",
+            "Time spent syncing each journal partition",
+            metrics.sync_seconds.clone(),
+        );
+        registry.register(
+            "bytes_compressed",
+            "Bytes appended per journal partition after compression",
+            metrics.bytes_compressed.clone(),
+        );
+        registry.register(
+            "compression_seconds",
+            "Time spent compressing each entry per journal partition",
+            metrics.compression_seconds.clone(),
+        );
+        registry.register(
+            "disk_available_bytes",
+            "Free space on the storage volume",
+            metrics.disk_available_bytes.clone(),
+        );
+
+        metrics
+    }
+
+    pub fn record_write(&self, partition: &Partition, bytes: usize) {
+        let labels = labels(partition);
+        self.bytes_written.get_or_create(&labels).inc_by(bytes as u64);
+        self.entries_written.get_or_create(&labels).inc();
+    }
+
+    /// Records an entry compressed to `bytes` in `elapsed`
+    pub fn record_compression(&self, partition: &Partition, bytes: usize, elapsed: Duration) {
+        let labels = labels(partition);
+        self.bytes_compressed.get_or_create(&labels).inc_by(bytes as u64);
+        self.compression_seconds.get_or_create(&labels).observe(elapsed.as_secs_f64());
+    }
+
+    pub fn record_sync(&self, partition: &Partition, elapsed: Duration) {
+        self.sync_seconds
+            .get_or_create(&labels(partition))
+            .observe(elapsed.as_secs_f64());
+    }
+}
+
+/// Free space levels that trigger a warning and, below the hard floor, a
+/// pause of new order acceptance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CapacityThresholds {
+    pub warn_below_bytes: u64,
+    pub pause_below_bytes: u64,
+}
+
+impl Default for CapacityThresholds {
+    fn default() -> Self {
+        Self {
+            warn_below_bytes: 10 * 1024 * 1024 * 1024,
+            pause_below_bytes: 1024 * 1024 * 1024,
+        }
+    }
+}
+
+impl CapacityThresholds {
+    pub fn level(&self, available: u64) -> CapacityLevel {
+        if available < self.pause_below_bytes {
+            CapacityLevel::Critical
+        } else if available < self.warn_below_bytes {
+            CapacityLevel::Low
+        } else {
+            CapacityLevel::Ok
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CapacityLevel {
+    Ok,
+    /// Below the warning threshold
+    Low,
+    /// Below the hard floor; new orders are not accepted
+    Critical,
+}
+
+/// Shared view of the latest capacity level, checked before accepting orders
+#[derive(Clone)]
+pub struct CapacityGate {
+    level: Arc<AtomicU8>,
+}
+
+impl Default for CapacityGate {
+    fn default() -> Self {
+        Self {
+            level: Arc::new(AtomicU8::new(CapacityLevel::Ok as u8)),
+        }
+    }
+}
+
+impl CapacityGate {
+    pub fn level(&self) -> CapacityLevel {
+        match self.level.load(Ordering::Relaxed) {
+            0 => CapacityLevel::Ok,
+            1 => CapacityLevel::Low,
+            _ => CapacityLevel::Critical,
+        }
+    }
+
+    /// Whether new orders must be refused
+    pub fn is_paused(&self) -> bool {
+        self.level() == CapacityLevel::Critical
+    }
+
+    fn set(&self, level: CapacityLevel) -> CapacityLevel {
+        let previous = self.level();
+        self.level.store(level as u8, Ordering::Relaxed);
+        previous
+    }
+}
+
+/// Periodically measures partition sizes and free disk space, updates the
+/// storage metrics, and moves the `CapacityGate` between levels
+pub struct CapacityMonitor {
+    config: StorageConfig,
+    thresholds: CapacityThresholds,
+    metrics: StorageMetrics,
+    gate: CapacityGate,
+}
+
+impl CapacityMonitor {
+    pub fn new(config: StorageConfig, thresholds: CapacityThresholds, metrics: StorageMetrics) -> Self {
+        Self {
+            config,
+            thresholds,
+            metrics,
+            gate: CapacityGate::default(),
+        }
+    }
+
+    pub fn gate(&self) -> CapacityGate {
+        self.gate.clone()
+    }
+
+    /// Takes one measurement, returning the resulting level
+    pub fn check(&self) -> io::Result<CapacityLevel> {
+        for partition in &PARTITIONS {
+            let size = dir_size(&self.config.storage_directory.join(partition.name()))?;
+            self.metrics
+                .partition_size_bytes
+                .get_or_create(&labels(partition))
+                .set(size as i64);
+        }
+
+        std::fs::create_dir_all(&self.config.storage_directory)?;
+        let available = fs2::available_space(&self.config.storage_directory)?;
+        self.metrics.disk_available_bytes.set(available as i64);
+
+        let level = self.thresholds.level(available);
+        let previous = self.gate.set(level);
+        if level != previous {
+            match level {
+                CapacityLevel::Ok => info!(available, "Storage capacity recovered"),
+                CapacityLevel::Low => warn!(
+                    available,
+                    threshold = self.thresholds.warn_below_bytes,
+                    "Storage capacity low"
+                ),
+                CapacityLevel::Critical => error!(
+                    available,
+                    floor = self.thresholds.pause_below_bytes,
+                    "Storage capacity below hard floor, pausing order acceptance"
+                ),
+            }
+        }
+        Ok(level)
+    }
+
+    /// Checks every `interval` until the task is dropped
+    pub async fn run(self, interval: Duration) {
+        let mut timer = tokio::time::interval(interval);
+        loop {
+            timer.tick().await;
+            if let Err(e) = self.check() {
+                warn!(error = %e, "Failed to measure storage capacity");
+            }
+        }
+    }
+}
+
+/// Total size of the files under `path`, zero if it does not exist
+fn dir_size(path: &Path) -> io::Result<u64> {
+    let entries = match std::fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    let mut total = 0;
+    for entry in entries {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        total += if metadata.is_dir() {
+            dir_size(&entry.path())?
+        } else {
+            metadata.len()
+        };
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prometheus_client::encoding::text::encode;
+
+    #[test]
+    fn test_threshold_levels() {
+        let thresholds = CapacityThresholds {
+            warn_below_bytes: 100,
+            pause_below_bytes: 10,
+        };
+        assert_eq!(thresholds.level(500), CapacityLevel::Ok);
+        assert_eq!(thresholds.level(50), CapacityLevel::Low);
+        assert_eq!(thresholds.level(5), CapacityLevel::Critical);
+    }
+
+    #[test]
+    fn test_check_pauses_below_floor() {
+        let registry = Arc::new(Mutex::new(Registry::default()));
+        let metrics = StorageMetrics::new(&registry);
+        let config = StorageConfig {
+            storage_directory: std::env::temp_dir().join(format!("romer-capacity-{}", std::process::id())),
+            ..StorageConfig::default()
+        };
+        let monitor = CapacityMonitor::new(
+            config.clone(),
+            CapacityThresholds {
+                warn_below_bytes: u64::MAX,
+                pause_below_bytes: u64::MAX,
+            },
+            metrics.clone(),
+        );
+
+        assert_eq!(monitor.check().unwrap(), CapacityLevel::Critical);
+        assert!(monitor.gate().is_paused());
+
+        metrics.record_write(&Partition::VM, 42);
+        let mut body = String::new();
+        encode(&mut body, &registry.lock().unwrap()).unwrap();
+        assert!(body.contains("romer_storage_bytes_written_total{partition=\"vm\"} 42"));
+
+        std::fs::remove_dir_all(&config.storage_directory).unwrap();
+    }
+}
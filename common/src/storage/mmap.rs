@@ -0,0 +1,262 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use memmap2::Mmap;
+use thiserror::Error;
+
+use crate::storage::journal::{Partition, StorageConfig};
+
+/// Journal items are framed as a big-endian u32 length, the payload, and a
+/// big-endian CRC32 of the payload
+const LEN_SIZE: usize = 4;
+const CRC_SIZE: usize = 4;
+
+#[derive(Error, Debug)]
+pub enum MmapError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Corrupt item at offset {offset}: {reason}")]
+    Corrupt { offset: usize, reason: String },
+
+    #[error("Blob shrank from {mapped} to {current} bytes while mapped")]
+    Truncated { mapped: u64, current: u64 },
+}
+
+/// Path of the blob holding `section` of `partition`, as laid out by the
+/// tokio runtime: `<storage dir>/<partition>/<hex section>`
+pub fn blob_path(config: &StorageConfig, partition: &Partition, section: u64) -> PathBuf {
+    let name: String = section.to_be_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+    config.storage_directory.join(partition.name()).join(name)
+}
+
+/// Read-only memory map of one journal section.
+///
+/// Journals only ever append, so bytes below the mapped length never change
+/// while an appender is running. The map covers the blob as it was when
+/// mapped; an item an appender is still writing shows up as an incomplete
+/// tail and is not returned. `refresh` picks up later appends. Journals are
+/// only truncated while recovering on open, so a blob that shrank under a
+/// map is reported instead of read.
+pub struct MmapSection {
+    path: PathBuf,
+    map: Option<Mmap>,
+}
+
+impl MmapSection {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, MmapError> {
+        let mut section = Self {
+            path: path.as_ref().to_path_buf(),
+            map: None,
+        };
+        section.refresh()?;
+        Ok(section)
+    }
+
+    /// Bytes currently mapped
+    pub fn mapped_len(&self) -> u64 {
+        self.map.as_ref().map_or(0, |map| map.len() as u64)
+    }
+
+    /// Remaps the blob if it grew. Returns whether the map changed.
+    pub fn refresh(&mut self) -> Result<bool, MmapError> {
+        let file = File::open(&self.path)?;
+        let current = file.metadata()?.len();
+        let mapped = self.mapped_len();
+        if current < mapped {
+            return Err(MmapError::Truncated { mapped, current });
+        }
+        if current == mapped && self.map.is_some() {
+            return Ok(false);
+        }
+        // Empty files cannot be mapped on every platform
+        self.map = if current == 0 {
+            None
+        } else {
+            // SAFETY: the blob is append-only while mapped, see the type docs
+            Some(unsafe { Mmap::map(&file)? })
+        };
+        Ok(true)
+    }
+
+    fn bytes(&self) -> &[u8] {
+        self.map.as_deref().unwrap_or(&[])
+    }
+
+    /// Payload of the item at `offset`. `None` if the item is not (yet)
+    /// completely written.
+    pub fn read_at(&self, offset: usize) -> Result<Option<&[u8]>, MmapError> {
+        let bytes = self.bytes();
+        let Some(header) = bytes.get(offset..offset + LEN_SIZE) else {
+            return Ok(None);
+        };
+        let len = u32::from_be_bytes(header.try_into().unwrap()) as usize;
+        let start = offset + LEN_SIZE;
+        let Some(frame) = bytes.get(start..start + len + CRC_SIZE) else {
+            return Ok(None);
+        };
+
+        let (payload, crc) = frame.split_at(len);
+        let expected = u32::from_be_bytes(crc.try_into().unwrap());
+        let actual = crc32fast::hash(payload);
+        if expected != actual {
+            return Err(MmapError::Corrupt {
+                offset,
+                reason: format!("checksum {:08x} != {:08x}", actual, expected),
+            });
+        }
+        Ok(Some(payload))
+    }
+
+    /// Iterates over complete items from the start of the section, yielding
+    /// each item's offset and payload
+    pub fn items(&self) -> Items<'_> {
+        Items {
+            section: self,
+            offset: 0,
+            done: false,
+        }
+    }
+}
+
+pub struct Items<'a> {
+    section: &'a MmapSection,
+    offset: usize,
+    done: bool,
+}
+
+impl<'a> Iterator for Items<'a> {
+    type Item = Result<(usize, &'a [u8]), MmapError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.section.read_at(self.offset) {
+            Ok(Some(payload)) => {
+                let offset = self.offset;
+                self.offset += LEN_SIZE + payload.len() + CRC_SIZE;
+                Some(Ok((offset, payload)))
+            }
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Small cache of mapped sections for repeated reads of recent history,
+/// such as explorer queries. Least recently opened sections are unmapped
+/// first.
+pub struct HotSections {
+    config: StorageConfig,
+    capacity: usize,
+    sections: VecDeque<((&'static str, u64), MmapSection)>,
+}
+
+impl HotSections {
+    pub fn new(config: StorageConfig, capacity: usize) -> Self {
+        Self {
+            config,
+            capacity: capacity.max(1),
+            sections: VecDeque::new(),
+        }
+    }
+
+    /// Mapped `section` of `partition`, refreshed to include recent appends
+    pub fn get(&mut self, partition: &Partition, section: u64) -> Result<&MmapSection, MmapError> {
+        let key = (partition.name(), section);
+        match self.sections.iter().position(|(k, _)| *k == key) {
+            Some(index) => {
+                let entry = self.sections.remove(index).unwrap();
+                self.sections.push_back(entry);
+            }
+            None => {
+                let mapped = MmapSection::open(blob_path(&self.config, partition, section))?;
+                if self.sections.len() == self.capacity {
+                    self.sections.pop_front();
+                }
+                self.sections.push_back((key, mapped));
+            }
+        }
+
+        let (_, mapped) = self.sections.back_mut().unwrap();
+        mapped.refresh()?;
+        Ok(mapped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn frame(payload: &[u8]) -> Vec<u8> {
+        let mut bytes = (payload.len() as u32).to_be_bytes().to_vec();
+        bytes.extend_from_slice(payload);
+        bytes.extend_from_slice(&crc32fast::hash(payload).to_be_bytes());
+        bytes
+    }
+
+    fn temp_blob(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("romer-mmap-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_reads_complete_items_and_skips_torn_tail() {
+        let path = temp_blob("torn");
+        let mut file = File::create(&path).unwrap();
+        file.write_all(&frame(b"first")).unwrap();
+        file.write_all(&frame(b"second")).unwrap();
+        // An append still in flight
+        file.write_all(&frame(b"third")[..6]).unwrap();
+        file.flush().unwrap();
+
+        let mut section = MmapSection::open(&path).unwrap();
+        let items: Vec<_> = section.items().map(|item| item.unwrap().1.to_vec()).collect();
+        assert_eq!(items, vec![b"first".to_vec(), b"second".to_vec()]);
+
+        file.write_all(&frame(b"third")[6..]).unwrap();
+        file.flush().unwrap();
+        assert!(section.refresh().unwrap());
+        assert_eq!(section.items().count(), 3);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_detects_corruption() {
+        let path = temp_blob("corrupt");
+        let mut bytes = frame(b"payload");
+        bytes[5] ^= 0xff;
+        std::fs::write(&path, &bytes).unwrap();
+
+        let section = MmapSection::open(&path).unwrap();
+        assert!(matches!(section.read_at(0), Err(MmapError::Corrupt { offset: 0, .. })));
+        assert!(section.items().next().unwrap().is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_detects_truncation() {
+        let path = temp_blob("truncated");
+        std::fs::write(&path, frame(b"payload")).unwrap();
+        let mut section = MmapSection::open(&path).unwrap();
+
+        // Only the length is checked, the shrunken map is never read
+        File::create(&path).unwrap();
+        assert!(matches!(section.refresh(), Err(MmapError::Truncated { .. })));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
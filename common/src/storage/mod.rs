@@ -1,4 +1,10 @@
+pub mod archive;
+pub mod commit;
+pub mod compression;
+pub mod group_commit;
 pub mod journal;
+pub mod metrics;
+pub mod mmap;
 
 // Partitions enum with explicit discriminant values
 pub enum Partitions {
@@ -0,0 +1,302 @@
+use commonware_cryptography::{Hasher, Sha256};
+use commonware_utils::{from_hex, hex};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+use crate::types::keymanager::SignatureScheme;
+
+/// Human-readable prefix of bech32 encoded addresses
+pub const ADDRESS_HRP: &str = "romer";
+
+/// Length of an address in bytes
+pub const ADDRESS_LENGTH: usize = 32;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum AddressError {
+    #[error("Invalid address length: {0}")]
+    InvalidLength(usize),
+
+    #[error("Invalid address encoding: {0}")]
+    InvalidEncoding(String),
+
+    #[error("Address checksum mismatch")]
+    InvalidChecksum,
+
+    #[error("Unexpected address prefix: {0}")]
+    InvalidPrefix(String),
+}
+
+/// Canonical Romer account address.
+///
+/// Derived as `sha256(scheme_tag || public_key)` so the same key material
+/// under different schemes never collides. Displayed as bech32 with the
+/// `romer` prefix, which carries a checksum; the raw `0x` hex form is also
+/// accepted when parsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Address([u8; ADDRESS_LENGTH]);
+
+impl Address {
+    /// The all-zero address. Used as the burn address and as the sender of mints.
+    pub const ZERO: Address = Address([0u8; ADDRESS_LENGTH]);
+
+    pub const fn new(bytes: [u8; ADDRESS_LENGTH]) -> Self {
+        Self(bytes)
+    }
+
+    /// Derives the address controlled by `public_key` under `scheme`
+    pub fn from_public_key(scheme: SignatureScheme, public_key: &[u8]) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(&[scheme_tag(scheme)]);
+        hasher.update(public_key);
+        let mut digest = [0u8; ADDRESS_LENGTH];
+        digest.copy_from_slice(&hasher.finalize());
+        Self::from_digest(&digest)
+    }
+
+    /// Builds an address from a 32 byte digest
+    pub fn from_digest(digest: &[u8; ADDRESS_LENGTH]) -> Self {
+        Self(*digest)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; ADDRESS_LENGTH] {
+        &self.0
+    }
+
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.0.to_vec()
+    }
+
+    /// Raw `0x` prefixed hex form, as used by Move tooling
+    pub fn to_hex(&self) -> String {
+        format!("0x{}", hex(&self.0))
+    }
+
+    /// Checksummed bech32 form
+    pub fn to_bech32(&self) -> String {
+        bech32::encode(ADDRESS_HRP, &self.0)
+    }
+}
+
+fn scheme_tag(scheme: SignatureScheme) -> u8 {
+    match scheme {
+        SignatureScheme::Ed25519 => 0x00,
+        SignatureScheme::Bls12381 => 0x01,
+    }
+}
+
+impl From<[u8; ADDRESS_LENGTH]> for Address {
+    fn from(bytes: [u8; ADDRESS_LENGTH]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<Address> for [u8; ADDRESS_LENGTH] {
+    fn from(address: Address) -> Self {
+        address.0
+    }
+}
+
+impl TryFrom<&[u8]> for Address {
+    type Error = AddressError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        let bytes: [u8; ADDRESS_LENGTH] = bytes
+            .try_into()
+            .map_err(|_| AddressError::InvalidLength(bytes.len()))?;
+        Ok(Self(bytes))
+    }
+}
+
+impl AsRef<[u8]> for Address {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_bech32())
+    }
+}
+
+impl FromStr for Address {
+    type Err = AddressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(raw) = s.strip_prefix("0x") {
+            let bytes = from_hex(raw)
+                .ok_or_else(|| AddressError::InvalidEncoding(s.to_string()))?;
+            return Address::try_from(bytes.as_slice());
+        }
+
+        let (hrp, bytes) = bech32::decode(s)?;
+        if hrp != ADDRESS_HRP {
+            return Err(AddressError::InvalidPrefix(hrp));
+        }
+        Address::try_from(bytes.as_slice())
+    }
+}
+
+impl Serialize for Address {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_bech32())
+    }
+}
+
+impl<'de> Deserialize<'de> for Address {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Minimal BIP-173 bech32 encoding
+mod bech32 {
+    use super::AddressError;
+
+    const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+    const GENERATORS: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+
+    fn polymod(values: &[u8]) -> u32 {
+        let mut chk: u32 = 1;
+        for v in values {
+            let top = chk >> 25;
+            chk = (chk & 0x1ffffff) << 5 ^ (*v as u32);
+            for (i, g) in GENERATORS.iter().enumerate() {
+                if (top >> i) & 1 == 1 {
+                    chk ^= g;
+                }
+            }
+        }
+        chk
+    }
+
+    fn hrp_expand(hrp: &str) -> Vec<u8> {
+        let mut out: Vec<u8> = hrp.bytes().map(|b| b >> 5).collect();
+        out.push(0);
+        out.extend(hrp.bytes().map(|b| b & 31));
+        out
+    }
+
+    fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Option<Vec<u8>> {
+        let mut acc: u32 = 0;
+        let mut bits: u32 = 0;
+        let max = (1u32 << to) - 1;
+        let mut out = Vec::new();
+        for value in data {
+            let v = *value as u32;
+            if v >> from != 0 {
+                return None;
+            }
+            acc = (acc << from) | v;
+            bits += from;
+            while bits >= to {
+                bits -= to;
+                out.push(((acc >> bits) & max) as u8);
+            }
+        }
+        if pad {
+            if bits > 0 {
+                out.push(((acc << (to - bits)) & max) as u8);
+            }
+        } else if bits >= from || ((acc << (to - bits)) & max) != 0 {
+            return None;
+        }
+        Some(out)
+    }
+
+    pub fn encode(hrp: &str, data: &[u8]) -> String {
+        let data = convert_bits(data, 8, 5, true).expect("8-bit input is always valid");
+
+        let mut values = hrp_expand(hrp);
+        values.extend_from_slice(&data);
+        values.extend_from_slice(&[0u8; 6]);
+        let checksum = polymod(&values) ^ 1;
+
+        let mut out = String::with_capacity(hrp.len() + 1 + data.len() + 6);
+        out.push_str(hrp);
+        out.push('1');
+        for d in &data {
+            out.push(CHARSET[*d as usize] as char);
+        }
+        for i in 0..6 {
+            out.push(CHARSET[((checksum >> (5 * (5 - i))) & 31) as usize] as char);
+        }
+        out
+    }
+
+    pub fn decode(s: &str) -> Result<(String, Vec<u8>), AddressError> {
+        let invalid = || AddressError::InvalidEncoding(s.to_string());
+        if s.to_lowercase() != s && s.to_uppercase() != s {
+            return Err(invalid());
+        }
+        let s = s.to_lowercase();
+        let split = s.rfind('1').ok_or_else(invalid)?;
+        let (hrp, rest) = (&s[..split], &s[split + 1..]);
+        if hrp.is_empty() || rest.len() < 6 {
+            return Err(invalid());
+        }
+
+        let data = rest
+            .bytes()
+            .map(|c| CHARSET.iter().position(|x| *x == c).map(|p| p as u8))
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(invalid)?;
+
+        let mut values = hrp_expand(hrp);
+        values.extend_from_slice(&data);
+        if polymod(&values) != 1 {
+            return Err(AddressError::InvalidChecksum);
+        }
+
+        let bytes = convert_bits(&data[..data.len() - 6], 5, 8, false).ok_or_else(invalid)?;
+        Ok((hrp.to_string(), bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bech32_round_trip() {
+        let address = Address::from_public_key(SignatureScheme::Ed25519, &[7u8; 32]);
+        let encoded = address.to_string();
+
+        assert!(encoded.starts_with("romer1"));
+        assert_eq!(encoded.parse::<Address>().unwrap(), address);
+        assert_eq!(address.to_hex().parse::<Address>().unwrap(), address);
+    }
+
+    #[test]
+    fn test_checksum_detects_typos() {
+        let encoded = Address::new([1u8; 32]).to_string();
+        let mut corrupted = encoded.clone().into_bytes();
+        let last = corrupted.len() - 1;
+        corrupted[last] = if corrupted[last] == b'q' { b'p' } else { b'q' };
+
+        assert_eq!(
+            String::from_utf8(corrupted).unwrap().parse::<Address>(),
+            Err(AddressError::InvalidChecksum)
+        );
+    }
+
+    #[test]
+    fn test_scheme_separates_addresses() {
+        let key = [9u8; 48];
+        assert_ne!(
+            Address::from_public_key(SignatureScheme::Ed25519, &key),
+            Address::from_public_key(SignatureScheme::Bls12381, &key)
+        );
+    }
+
+    #[test]
+    fn test_serde_uses_bech32() {
+        let address = Address::new([2u8; 32]);
+        let json = serde_json::to_string(&address).unwrap();
+        assert_eq!(json, format!("\"{}\"", address.to_bech32()));
+        assert_eq!(serde_json::from_str::<Address>(&json).unwrap(), address);
+    }
+}
@@ -0,0 +1,182 @@
+use commonware_cryptography::{Ed25519, PublicKey, Scheme, Signature};
+use commonware_utils::{from_hex, hex};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Namespace of admin token signatures, so a token can never be replayed
+/// as a transaction or any other signed message
+pub const ADMIN_TOKEN_NAMESPACE: &[u8] = b"_ROMER_ADMIN";
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AdminAuthError {
+    #[error("Admin token is malformed")]
+    MalformedToken,
+
+    #[error("Admin token expired at {0}")]
+    Expired(u64),
+
+    #[error("Admin token expires at {expires_at}, more than {max_lifetime_secs}s ahead")]
+    TooLongLived { expires_at: u64, max_lifetime_secs: u64 },
+
+    #[error("Invalid admin token signature")]
+    InvalidSignature,
+
+    #[error("Unknown role {0}")]
+    UnknownRole(String),
+}
+
+/// What an admin may do. Viewers read; operators run the market and risk
+/// officers contain it, each on top of viewing; superusers do everything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AdminRole {
+    Viewer,
+    Operator,
+    Risk,
+    Superuser,
+}
+
+impl AdminRole {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Viewer => "viewer",
+            Self::Operator => "operator",
+            Self::Risk => "risk",
+            Self::Superuser => "superuser",
+        }
+    }
+
+    /// Whether holders of this role may do what `required` may
+    pub fn allows(&self, required: AdminRole) -> bool {
+        *self == required || matches!((self, required), (Self::Superuser, _) | (_, Self::Viewer))
+    }
+}
+
+impl fmt::Display for AdminRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for AdminRole {
+    type Err = AdminAuthError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "viewer" => Ok(Self::Viewer),
+            "operator" => Ok(Self::Operator),
+            "risk" => Ok(Self::Risk),
+            "superuser" => Ok(Self::Superuser),
+            other => Err(AdminAuthError::UnknownRole(other.to_string())),
+        }
+    }
+}
+
+/// Bearer credential for the admin API, signed by a KeyManager Ed25519 key
+/// and valid until `expires_at`. Sent as `<public key>.<expires_at>.<signature>`
+/// with the public key and signature in hex.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdminToken {
+    pub public_key: Vec<u8>,
+    /// Unix timestamp (seconds)
+    pub expires_at: u64,
+    pub signature: Vec<u8>,
+}
+
+impl AdminToken {
+    /// The message signed under `ADMIN_TOKEN_NAMESPACE`
+    pub fn signing_message(expires_at: u64) -> Vec<u8> {
+        format!("romer-admin:{}", expires_at).into_bytes()
+    }
+
+    pub fn sign(expires_at: u64, signer: &mut Ed25519) -> Self {
+        Self {
+            public_key: signer.public_key().to_vec(),
+            expires_at,
+            signature: signer
+                .sign(Some(ADMIN_TOKEN_NAMESPACE), &Self::signing_message(expires_at))
+                .to_vec(),
+        }
+    }
+
+    /// Checks the signature and that the token is live at `now`, expiring
+    /// no more than `max_lifetime_secs` ahead
+    pub fn verify(&self, now: u64, max_lifetime_secs: u64) -> Result<(), AdminAuthError> {
+        if now > self.expires_at {
+            return Err(AdminAuthError::Expired(self.expires_at));
+        }
+        if self.expires_at - now > max_lifetime_secs {
+            return Err(AdminAuthError::TooLongLived {
+                expires_at: self.expires_at,
+                max_lifetime_secs,
+            });
+        }
+        if !Ed25519::verify(
+            Some(ADMIN_TOKEN_NAMESPACE),
+            &Self::signing_message(self.expires_at),
+            &PublicKey::from(self.public_key.clone()),
+            &Signature::from(self.signature.clone()),
+        ) {
+            return Err(AdminAuthError::InvalidSignature);
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for AdminToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", hex(&self.public_key), self.expires_at, hex(&self.signature))
+    }
+}
+
+impl FromStr for AdminToken {
+    type Err = AdminAuthError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().split('.');
+        let (Some(public_key), Some(expires_at), Some(signature), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(AdminAuthError::MalformedToken);
+        };
+        Ok(Self {
+            public_key: from_hex(public_key).ok_or(AdminAuthError::MalformedToken)?,
+            expires_at: expires_at.parse().map_err(|_| AdminAuthError::MalformedToken)?,
+            signature: from_hex(signature).ok_or(AdminAuthError::MalformedToken)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roles() {
+        assert!(AdminRole::Superuser.allows(AdminRole::Risk));
+        assert!(AdminRole::Operator.allows(AdminRole::Viewer));
+        assert!(!AdminRole::Operator.allows(AdminRole::Risk));
+        assert!(!AdminRole::Risk.allows(AdminRole::Operator));
+        assert!(!AdminRole::Viewer.allows(AdminRole::Operator));
+        assert_eq!("risk".parse::<AdminRole>(), Ok(AdminRole::Risk));
+    }
+
+    #[test]
+    fn test_token_round_trip() {
+        let mut signer = Ed25519::from_seed(4);
+        let token = AdminToken::sign(1_000, &mut signer);
+        let parsed: AdminToken = token.to_string().parse().unwrap();
+        assert_eq!(parsed, token);
+        parsed.verify(900, 3_600).unwrap();
+        assert_eq!(parsed.verify(1_001, 3_600), Err(AdminAuthError::Expired(1_000)));
+        assert!(matches!(parsed.verify(0, 60), Err(AdminAuthError::TooLongLived { .. })));
+
+        let forged = AdminToken {
+            expires_at: 2_000,
+            ..parsed
+        };
+        assert_eq!(forged.verify(900, 3_600), Err(AdminAuthError::InvalidSignature));
+    }
+}
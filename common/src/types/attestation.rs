@@ -0,0 +1,200 @@
+use commonware_cryptography::{Bls12381, Ed25519, PublicKey, Scheme, Signature};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::types::address::Address;
+use crate::types::keymanager::SignatureScheme;
+
+/// Namespace applied to every attestation signature so that attestations
+/// can never be replayed as transactions or consensus messages
+pub const ATTESTATION_NAMESPACE: &[u8] = b"_ROMER_ATTESTATION";
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum AttestationError {
+    #[error("Validator {validator} does not match signing key address {derived}")]
+    ValidatorMismatch { validator: Address, derived: Address },
+
+    #[error("Attestation expired at {0}")]
+    Expired(u64),
+
+    #[error("Attestation issued at {issued_at} does not expire after it ({expires_at})")]
+    InvalidLifetime { issued_at: u64, expires_at: u64 },
+
+    #[error("Invalid attestation signature")]
+    InvalidSignature,
+
+    #[error("Failed to encode attestation: {0}")]
+    Encoding(String),
+}
+
+/// Where a validator claims to be, and how well latency to the reference
+/// points supported the claim
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LocationAttestation {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Confidence of the location check, between 0 and 1
+    pub confidence: f64,
+    pub passed: bool,
+}
+
+/// What a validator runs on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HardwareAttestation {
+    /// Virtualization platform detected, `None` on physical hardware
+    pub virtualization: Option<String>,
+    /// Resources detected, `None` where they could not be
+    pub cpu_cores: Option<usize>,
+    pub memory_bytes: Option<u64>,
+    /// Whether every hardware requirement was met
+    pub requirements_met: bool,
+}
+
+/// The signed portion of an attestation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attestation {
+    pub validator: Address,
+    pub location: LocationAttestation,
+    pub hardware: HardwareAttestation,
+    /// Unix timestamp (seconds) the checks were made at
+    pub issued_at: u64,
+    /// Unix timestamp (seconds) after which the attestation is stale
+    pub expires_at: u64,
+}
+
+impl Attestation {
+    /// Canonical bytes covered by the signature
+    pub fn signing_bytes(&self) -> Result<Vec<u8>, AttestationError> {
+        serde_json::to_vec(self).map_err(|e| AttestationError::Encoding(e.to_string()))
+    }
+
+    /// Signs the attestation with `signer`
+    pub fn sign<C: Scheme>(
+        self,
+        scheme: SignatureScheme,
+        signer: &mut C,
+    ) -> Result<SignedAttestation, AttestationError> {
+        let message = self.signing_bytes()?;
+        let signature = signer.sign(Some(ATTESTATION_NAMESPACE), &message);
+        Ok(SignedAttestation {
+            attestation: self,
+            scheme,
+            public_key: signer.public_key().to_vec(),
+            signature: signature.to_vec(),
+        })
+    }
+}
+
+/// A validator's location and hardware attestation, signed with the key its
+/// address is derived from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignedAttestation {
+    pub attestation: Attestation,
+    pub scheme: SignatureScheme,
+    pub public_key: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+impl SignedAttestation {
+    /// Checks the validator, lifetime and signature. `now` is unix seconds.
+    pub fn verify(&self, now: u64) -> Result<(), AttestationError> {
+        let attestation = &self.attestation;
+        let derived = Address::from_public_key(self.scheme, &self.public_key);
+        if derived != attestation.validator {
+            return Err(AttestationError::ValidatorMismatch {
+                validator: attestation.validator,
+                derived,
+            });
+        }
+
+        if attestation.expires_at <= attestation.issued_at {
+            return Err(AttestationError::InvalidLifetime {
+                issued_at: attestation.issued_at,
+                expires_at: attestation.expires_at,
+            });
+        }
+        if now > attestation.expires_at {
+            return Err(AttestationError::Expired(attestation.expires_at));
+        }
+
+        let message = attestation.signing_bytes()?;
+        let public_key = PublicKey::from(self.public_key.clone());
+        let signature = Signature::from(self.signature.clone());
+        let valid = match self.scheme {
+            SignatureScheme::Ed25519 => {
+                Ed25519::verify(Some(ATTESTATION_NAMESPACE), &message, &public_key, &signature)
+            }
+            SignatureScheme::Bls12381 => {
+                Bls12381::verify(Some(ATTESTATION_NAMESPACE), &message, &public_key, &signature)
+            }
+        };
+        if !valid {
+            return Err(AttestationError::InvalidSignature);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attestation(signer: &Ed25519) -> Attestation {
+        Attestation {
+            validator: Address::from_public_key(SignatureScheme::Ed25519, &signer.public_key()),
+            location: LocationAttestation {
+                latitude: 50.1109,
+                longitude: 8.6821,
+                confidence: 0.9,
+                passed: true,
+            },
+            hardware: HardwareAttestation {
+                virtualization: None,
+                cpu_cores: Some(16),
+                memory_bytes: Some(64 << 30),
+                requirements_met: true,
+            },
+            issued_at: 1_000,
+            expires_at: 2_000,
+        }
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let mut signer = Ed25519::from_seed(1);
+        let signed = attestation(&signer).sign(SignatureScheme::Ed25519, &mut signer).unwrap();
+
+        assert!(signed.verify(1_500).is_ok());
+        assert_eq!(signed.verify(2_001), Err(AttestationError::Expired(2_000)));
+
+        let json = serde_json::to_string(&signed).unwrap();
+        let decoded: SignedAttestation = serde_json::from_str(&json).unwrap();
+        assert!(decoded.verify(1_500).is_ok());
+    }
+
+    #[test]
+    fn test_rejects_tampering_and_wrong_validator() {
+        let mut signer = Ed25519::from_seed(1);
+        let mut signed = attestation(&signer).sign(SignatureScheme::Ed25519, &mut signer).unwrap();
+        signed.attestation.location.confidence = 1.0;
+        assert_eq!(signed.verify(1_500), Err(AttestationError::InvalidSignature));
+
+        let other = Ed25519::from_seed(2);
+        let signed = attestation(&other).sign(SignatureScheme::Ed25519, &mut signer).unwrap();
+        assert!(matches!(
+            signed.verify(1_500),
+            Err(AttestationError::ValidatorMismatch { .. })
+        ));
+
+        let backwards = Attestation {
+            expires_at: 1_000,
+            ..attestation(&signer)
+        };
+        let signed = backwards.sign(SignatureScheme::Ed25519, &mut signer).unwrap();
+        assert!(matches!(
+            signed.verify(1_000),
+            Err(AttestationError::InvalidLifetime { .. })
+        ));
+    }
+}
@@ -0,0 +1,278 @@
+use commonware_cryptography::{Ed25519, PublicKey, Scheme, Signature};
+use commonware_utils::{from_hex, hex};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+use crate::types::address::Address;
+
+/// Prefix of the message validators sign to authorize a mint. Must match
+/// `MINT_DOMAIN` in the `romer::bridge` Move module.
+pub const MINT_DOMAIN: &[u8] = b"ROMER_BRIDGE_MINT";
+
+/// Prefix of the message validators sign to authorize a release on the
+/// source chain
+pub const RELEASE_DOMAIN: &[u8] = b"ROMER_BRIDGE_RELEASE";
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum BridgeError {
+    #[error("Invalid bridge committee: {0}")]
+    InvalidCommittee(String),
+
+    #[error("{0} is not a bridge validator")]
+    UnknownSigner(String),
+
+    #[error("Invalid signature from {0}")]
+    InvalidSignature(String),
+
+    #[error("{signatures} distinct signatures, {threshold} required")]
+    ThresholdNotReached { signatures: usize, threshold: usize },
+}
+
+/// Assets locked on a source chain, to be minted as their wrapped
+/// representation on Romer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deposit {
+    pub source_chain: String,
+    /// Unique per source chain, e.g. the lock transaction hash and log index
+    pub deposit_id: String,
+    /// Move type the asset is wrapped as, as `std::type_name` prints it:
+    /// the full 64 digit address without `0x`, module and struct
+    pub asset: String,
+    pub recipient: Address,
+    pub amount: u64,
+}
+
+impl Deposit {
+    /// The message authorizing the mint, as the Move module rebuilds it:
+    /// the domain, then the BCS encoding of the source chain, asset,
+    /// deposit id, recipient and amount
+    pub fn mint_message(&self) -> Vec<u8> {
+        let mut message = MINT_DOMAIN.to_vec();
+        bcs_bytes(&mut message, self.source_chain.as_bytes());
+        bcs_bytes(&mut message, self.asset.as_bytes());
+        bcs_bytes(&mut message, self.deposit_id.as_bytes());
+        message.extend_from_slice(self.recipient.as_bytes());
+        message.extend_from_slice(&self.amount.to_le_bytes());
+        message
+    }
+}
+
+/// Wrapped assets burned on Romer, to be released on their source chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Withdrawal {
+    pub source_chain: String,
+    /// Sequence number assigned by the Move module at burn time
+    pub nonce: u64,
+    /// Wrapped type burned, as in [`Deposit::asset`]
+    pub asset: String,
+    /// Recipient on the source chain, in its own address format
+    pub destination: String,
+    pub amount: u64,
+}
+
+impl Withdrawal {
+    /// The message authorizing the release, checked by the source chain's
+    /// lock contract
+    pub fn release_message(&self) -> Vec<u8> {
+        let mut message = RELEASE_DOMAIN.to_vec();
+        bcs_bytes(&mut message, self.source_chain.as_bytes());
+        bcs_bytes(&mut message, self.asset.as_bytes());
+        message.extend_from_slice(&self.nonce.to_le_bytes());
+        bcs_bytes(&mut message, self.destination.as_bytes());
+        message.extend_from_slice(&self.amount.to_le_bytes());
+        message
+    }
+}
+
+/// A bridge validator's Ed25519 signature, with the key it was made with,
+/// both hex encoded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BridgeSignature {
+    pub public_key: String,
+    pub signature: String,
+}
+
+impl BridgeSignature {
+    /// Signs the raw `message`, without a namespace, so Move's
+    /// `ed25519_verify` and source chain contracts can check it
+    pub fn sign(signer: &mut Ed25519, message: &[u8]) -> Self {
+        Self {
+            public_key: hex(&signer.public_key()),
+            signature: hex(&signer.sign(None, message)),
+        }
+    }
+}
+
+/// The validators attesting to deposits and withdrawals, and how many of
+/// them must agree
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BridgeCommittee {
+    /// Hex-encoded Ed25519 public keys, in the order the Move module holds
+    /// them
+    pub validators: Vec<String>,
+    pub threshold: usize,
+}
+
+impl BridgeCommittee {
+    pub fn validate(&self) -> Result<(), BridgeError> {
+        if self.threshold == 0 || self.threshold > self.validators.len() {
+            return Err(BridgeError::InvalidCommittee(format!(
+                "threshold {} of {} validators",
+                self.threshold,
+                self.validators.len()
+            )));
+        }
+        for (index, validator) in self.validators.iter().enumerate() {
+            if from_hex(validator).is_none() {
+                return Err(BridgeError::InvalidCommittee(format!("{} is not hex", validator)));
+            }
+            if self.validators[..index].contains(validator) {
+                return Err(BridgeError::InvalidCommittee(format!("{} is listed twice", validator)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Position of `public_key` in the committee
+    pub fn index(&self, public_key: &str) -> Option<usize> {
+        self.validators.iter().position(|validator| validator == public_key)
+    }
+
+    /// Checks one signature over `message`, returning the signer's index
+    pub fn verify_one(&self, message: &[u8], signature: &BridgeSignature) -> Result<usize, BridgeError> {
+        let index = self
+            .index(&signature.public_key)
+            .ok_or_else(|| BridgeError::UnknownSigner(signature.public_key.clone()))?;
+        let invalid = || BridgeError::InvalidSignature(signature.public_key.clone());
+        let public_key = PublicKey::from(from_hex(&signature.public_key).ok_or_else(invalid)?);
+        let raw = Signature::from(from_hex(&signature.signature).ok_or_else(invalid)?);
+        if !Ed25519::verify(None, message, &public_key, &raw) {
+            return Err(invalid());
+        }
+        Ok(index)
+    }
+
+    /// Checks `signatures` over `message`, returning the valid ones by
+    /// signer index once at least `threshold` distinct validators signed
+    pub fn verify(
+        &self,
+        message: &[u8],
+        signatures: &[BridgeSignature],
+    ) -> Result<BTreeMap<usize, BridgeSignature>, BridgeError> {
+        let mut valid = BTreeMap::new();
+        for signature in signatures {
+            let index = self.verify_one(message, signature)?;
+            valid.insert(index, signature.clone());
+        }
+        if valid.len() < self.threshold {
+            return Err(BridgeError::ThresholdNotReached {
+                signatures: valid.len(),
+                threshold: self.threshold,
+            });
+        }
+        Ok(valid)
+    }
+}
+
+/// Appends `bytes` as a BCS `vector<u8>`: ULEB128 length, then the bytes
+pub(crate) fn bcs_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    bcs_length(out, bytes.len());
+    out.extend_from_slice(bytes);
+}
+
+/// Appends the ULEB128 length prefix of a BCS vector
+pub(crate) fn bcs_length(out: &mut Vec<u8>, mut length: usize) {
+    loop {
+        let byte = (length & 0x7f) as u8;
+        length >>= 7;
+        if length == 0 {
+            out.push(byte);
+            break;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deposit() -> Deposit {
+        Deposit {
+            source_chain: "ethereum".to_string(),
+            deposit_id: "0xabc-1".to_string(),
+            asset: format!("{:0>64}::bridge_tests::USDC", "10"),
+            recipient: Address::new([7u8; 32]),
+            amount: 1_000,
+        }
+    }
+
+    fn committee(signers: &[Ed25519], threshold: usize) -> BridgeCommittee {
+        BridgeCommittee {
+            validators: signers.iter().map(|signer| hex(&signer.public_key())).collect(),
+            threshold,
+        }
+    }
+
+    #[test]
+    fn test_mint_message_layout() {
+        let message = deposit().mint_message();
+        let mut expected = b"ROMER_BRIDGE_MINT".to_vec();
+        expected.push(8);
+        expected.extend_from_slice(b"ethereum");
+        expected.push(84);
+        expected.extend_from_slice(format!("{:0>64}::bridge_tests::USDC", "10").as_bytes());
+        expected.push(7);
+        expected.extend_from_slice(b"0xabc-1");
+        expected.extend_from_slice(&[7u8; 32]);
+        expected.extend_from_slice(&1_000u64.to_le_bytes());
+        assert_eq!(message, expected);
+
+        let mut long = Vec::new();
+        bcs_bytes(&mut long, &[0u8; 200]);
+        assert_eq!(&long[..2], &[0xc8, 0x01]);
+    }
+
+    #[test]
+    fn test_threshold() {
+        let mut signers: Vec<Ed25519> = (0..3).map(Ed25519::from_seed).collect();
+        let committee = committee(&signers, 2);
+        committee.validate().unwrap();
+        let message = deposit().mint_message();
+
+        let first = BridgeSignature::sign(&mut signers[0], &message);
+        assert_eq!(
+            committee.verify(&message, &[first.clone(), first.clone()]),
+            Err(BridgeError::ThresholdNotReached {
+                signatures: 1,
+                threshold: 2
+            })
+        );
+        let third = BridgeSignature::sign(&mut signers[2], &message);
+        let valid = committee.verify(&message, &[third, first]).unwrap();
+        assert_eq!(valid.keys().copied().collect::<Vec<_>>(), vec![0, 2]);
+
+        // Signed over something else
+        let forged = BridgeSignature::sign(&mut signers[1], b"other");
+        assert!(matches!(
+            committee.verify_one(&message, &forged),
+            Err(BridgeError::InvalidSignature(_))
+        ));
+        let mut outsider = Ed25519::from_seed(9);
+        assert!(matches!(
+            committee.verify_one(&message, &BridgeSignature::sign(&mut outsider, &message)),
+            Err(BridgeError::UnknownSigner(_))
+        ));
+    }
+
+    #[test]
+    fn test_committee_validation() {
+        let signers: Vec<Ed25519> = (0..2).map(Ed25519::from_seed).collect();
+        assert!(committee(&signers, 3).validate().is_err());
+        assert!(committee(&signers, 0).validate().is_err());
+        let mut twice = committee(&signers, 1);
+        twice.validators.push(twice.validators[0].clone());
+        assert!(twice.validate().is_err());
+    }
+}
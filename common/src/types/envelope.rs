@@ -0,0 +1,349 @@
+use commonware_cryptography::{Bls12381, Ed25519, Hasher, PublicKey, Scheme, Sha256, Signature};
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Deserialize, Serialize, Serializer};
+use thiserror::Error;
+
+use crate::types::address::Address;
+use crate::types::keymanager::SignatureScheme;
+
+/// Namespace applied to every transaction signature so that envelope
+/// signatures can never be replayed as FIX or treasury signatures
+pub const TRANSACTION_NAMESPACE: &[u8] = b"_ROMER_TX";
+
+/// Chain id of a network that does not configure one
+pub const DEFAULT_CHAIN_ID: &str = "romer-devnet";
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum EnvelopeError {
+    #[error("Sender {sender} does not match signing key address {derived}")]
+    SenderMismatch { sender: Address, derived: Address },
+
+    #[error("Transaction is for chain {actual}, not {expected}")]
+    WrongChain { expected: String, actual: String },
+
+    #[error("Transaction expired at {0}")]
+    Expired(u64),
+
+    #[error("Invalid transaction signature")]
+    InvalidSignature,
+
+    #[error("Failed to encode transaction: {0}")]
+    Encoding(String),
+}
+
+/// What a transaction asks the chain to do
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TransactionPayload {
+    /// Native token transfer
+    Transfer { to: Address, amount: u64 },
+    /// Call an entry function of a published Move package
+    MoveCall {
+        package: Address,
+        module: String,
+        function: String,
+        type_arguments: Vec<String>,
+        /// BCS encoded arguments
+        arguments: Vec<Vec<u8>>,
+    },
+    /// Privileged operator action, authorized by the sender's role
+    Admin { action: String, params: serde_json::Value },
+}
+
+/// The signed portion of a transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnsignedTransaction {
+    /// Network the transaction is meant for. It is signed, so the
+    /// transaction can't be replayed on another network.
+    pub chain_id: String,
+    pub payload: TransactionPayload,
+    pub sender: Address,
+    pub nonce: u64,
+    /// Fee offered for inclusion; higher fees are included first
+    #[serde(default)]
+    pub fee: u64,
+    /// Unix timestamp (seconds) after which the transaction is invalid
+    pub expiry: u64,
+}
+
+impl UnsignedTransaction {
+    /// Canonical bytes covered by the signature, integers big-endian:
+    /// `chain_id | sender (32) | nonce (8) | fee (8) | expiry (8) | tag (1) | payload`
+    ///
+    /// The payload is `to (32) | amount (8)` for a transfer (tag 0),
+    /// `package (32) | module | function | type_arguments | arguments` for a
+    /// Move call (tag 1) and `action | params` for an admin action (tag 2).
+    /// Strings and byte strings are prefixed with their length (4), lists
+    /// with their count (4), and `params` is JSON with every object's keys
+    /// sorted.
+    pub fn signing_bytes(&self) -> Result<Vec<u8>, EnvelopeError> {
+        let mut bytes = Vec::with_capacity(128);
+        put(&mut bytes, self.chain_id.as_bytes())?;
+        bytes.extend_from_slice(self.sender.as_ref());
+        for value in [self.nonce, self.fee, self.expiry] {
+            bytes.extend_from_slice(&value.to_be_bytes());
+        }
+        match &self.payload {
+            TransactionPayload::Transfer { to, amount } => {
+                bytes.push(0);
+                bytes.extend_from_slice(to.as_ref());
+                bytes.extend_from_slice(&amount.to_be_bytes());
+            }
+            TransactionPayload::MoveCall {
+                package,
+                module,
+                function,
+                type_arguments,
+                arguments,
+            } => {
+                bytes.push(1);
+                bytes.extend_from_slice(package.as_ref());
+                put(&mut bytes, module.as_bytes())?;
+                put(&mut bytes, function.as_bytes())?;
+                put_count(&mut bytes, type_arguments.len())?;
+                for argument in type_arguments {
+                    put(&mut bytes, argument.as_bytes())?;
+                }
+                put_count(&mut bytes, arguments.len())?;
+                for argument in arguments {
+                    put(&mut bytes, argument)?;
+                }
+            }
+            TransactionPayload::Admin { action, params } => {
+                bytes.push(2);
+                put(&mut bytes, action.as_bytes())?;
+                let params =
+                    serde_json::to_vec(&SortedJson(params)).map_err(|e| EnvelopeError::Encoding(e.to_string()))?;
+                put(&mut bytes, &params)?;
+            }
+        }
+        Ok(bytes)
+    }
+
+    /// Signs the transaction with `signer`, producing an envelope
+    pub fn sign<C: Scheme>(
+        self,
+        scheme: SignatureScheme,
+        signer: &mut C,
+    ) -> Result<SignedTransaction, EnvelopeError> {
+        let message = self.signing_bytes()?;
+        let signature = signer.sign(Some(TRANSACTION_NAMESPACE), &message);
+        Ok(SignedTransaction {
+            transaction: self,
+            scheme,
+            public_key: signer.public_key().to_vec(),
+            signature: signature.to_vec(),
+        })
+    }
+}
+
+fn put_count(bytes: &mut Vec<u8>, count: usize) -> Result<(), EnvelopeError> {
+    let count = u32::try_from(count).map_err(|_| EnvelopeError::Encoding(format!("{} is too long", count)))?;
+    bytes.extend_from_slice(&count.to_be_bytes());
+    Ok(())
+}
+
+fn put(bytes: &mut Vec<u8>, field: &[u8]) -> Result<(), EnvelopeError> {
+    put_count(bytes, field.len())?;
+    bytes.extend_from_slice(field);
+    Ok(())
+}
+
+/// A JSON value serialized with the keys of every object sorted, whatever
+/// order they were parsed in
+struct SortedJson<'a>(&'a serde_json::Value);
+
+impl Serialize for SortedJson<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            serde_json::Value::Array(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(&SortedJson(item))?;
+                }
+                seq.end()
+            }
+            serde_json::Value::Object(object) => {
+                let mut entries: Vec<_> = object.iter().collect();
+                entries.sort_by(|a, b| a.0.cmp(b.0));
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries {
+                    map.serialize_entry(key, &SortedJson(value))?;
+                }
+                map.end()
+            }
+            value => value.serialize(serializer),
+        }
+    }
+}
+
+/// Transaction envelope for submissions outside of FIX, such as JSON-RPC.
+/// Signed with the same schemes the KeyManager issues.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedTransaction {
+    pub transaction: UnsignedTransaction,
+    pub scheme: SignatureScheme,
+    pub public_key: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+impl SignedTransaction {
+    /// Checks the chain, sender, expiry and signature of a transaction
+    /// received on `chain_id`. `now` is unix seconds.
+    pub fn verify(&self, chain_id: &str, now: u64) -> Result<(), EnvelopeError> {
+        if self.transaction.chain_id != chain_id {
+            return Err(EnvelopeError::WrongChain {
+                expected: chain_id.to_string(),
+                actual: self.transaction.chain_id.clone(),
+            });
+        }
+
+        let derived = Address::from_public_key(self.scheme, &self.public_key);
+        if derived != self.transaction.sender {
+            return Err(EnvelopeError::SenderMismatch {
+                sender: self.transaction.sender,
+                derived,
+            });
+        }
+
+        if now > self.transaction.expiry {
+            return Err(EnvelopeError::Expired(self.transaction.expiry));
+        }
+
+        let message = self.transaction.signing_bytes()?;
+        let public_key = PublicKey::from(self.public_key.clone());
+        let signature = Signature::from(self.signature.clone());
+        let valid = match self.scheme {
+            SignatureScheme::Ed25519 => {
+                Ed25519::verify(Some(TRANSACTION_NAMESPACE), &message, &public_key, &signature)
+            }
+            SignatureScheme::Bls12381 => {
+                Bls12381::verify(Some(TRANSACTION_NAMESPACE), &message, &public_key, &signature)
+            }
+        };
+        if !valid {
+            return Err(EnvelopeError::InvalidSignature);
+        }
+
+        Ok(())
+    }
+
+    /// Unique identifier of the signed transaction: SHA-256 of its signing
+    /// bytes and signature
+    pub fn digest(&self) -> Result<[u8; 32], EnvelopeError> {
+        let mut hasher = Sha256::new();
+        hasher.update(&self.transaction.signing_bytes()?);
+        hasher.update(&self.signature);
+        let mut digest = [0u8; 32];
+        digest.copy_from_slice(&hasher.finalize());
+        Ok(digest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(signer: &Ed25519, nonce: u64) -> UnsignedTransaction {
+        UnsignedTransaction {
+            chain_id: DEFAULT_CHAIN_ID.to_string(),
+            payload: TransactionPayload::Transfer {
+                to: Address::new([5u8; 32]),
+                amount: 100,
+            },
+            sender: Address::from_public_key(SignatureScheme::Ed25519, &signer.public_key()),
+            nonce,
+            fee: 0,
+            expiry: 1_000,
+        }
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let mut signer = Ed25519::from_seed(1);
+        let signed = transfer(&signer, 0)
+            .sign(SignatureScheme::Ed25519, &mut signer)
+            .unwrap();
+
+        assert!(signed.verify(DEFAULT_CHAIN_ID, 999).is_ok());
+        assert_eq!(signed.verify(DEFAULT_CHAIN_ID, 1_001), Err(EnvelopeError::Expired(1_000)));
+    }
+
+    #[test]
+    fn test_bls_envelope() {
+        let mut signer = Bls12381::from_seed(1);
+        let tx = UnsignedTransaction {
+            sender: Address::from_public_key(SignatureScheme::Bls12381, &signer.public_key()),
+            ..transfer(&Ed25519::from_seed(1), 0)
+        };
+        let signed = tx.sign(SignatureScheme::Bls12381, &mut signer).unwrap();
+        assert!(signed.verify(DEFAULT_CHAIN_ID, 0).is_ok());
+    }
+
+    #[test]
+    fn test_rejects_tampering_and_wrong_sender() {
+        let mut signer = Ed25519::from_seed(1);
+        let mut signed = transfer(&signer, 0)
+            .sign(SignatureScheme::Ed25519, &mut signer)
+            .unwrap();
+
+        signed.transaction.nonce = 1;
+        assert_eq!(signed.verify(DEFAULT_CHAIN_ID, 0), Err(EnvelopeError::InvalidSignature));
+
+        let other = Ed25519::from_seed(2);
+        let signed = transfer(&other, 0)
+            .sign(SignatureScheme::Ed25519, &mut signer)
+            .unwrap();
+        assert!(matches!(signed.verify(DEFAULT_CHAIN_ID, 0), Err(EnvelopeError::SenderMismatch { .. })));
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let mut signer = Ed25519::from_seed(1);
+        let signed = transfer(&signer, 3)
+            .sign(SignatureScheme::Ed25519, &mut signer)
+            .unwrap();
+
+        let json = serde_json::to_string(&signed).unwrap();
+        let decoded: SignedTransaction = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, signed);
+        assert_eq!(decoded.digest().unwrap(), signed.digest().unwrap());
+    }
+
+    #[test]
+    fn test_signature_binds_chain() {
+        let mut signer = Ed25519::from_seed(1);
+        let signed = transfer(&signer, 0)
+            .sign(SignatureScheme::Ed25519, &mut signer)
+            .unwrap();
+        assert!(matches!(
+            signed.verify("romer-mainnet", 0),
+            Err(EnvelopeError::WrongChain { .. })
+        ));
+
+        // Relabelling the chain breaks the signature
+        let mut relabelled = signed.clone();
+        relabelled.transaction.chain_id = "romer-mainnet".into();
+        assert_eq!(relabelled.verify("romer-mainnet", 0), Err(EnvelopeError::InvalidSignature));
+        assert_ne!(relabelled.digest().unwrap(), signed.digest().unwrap());
+    }
+
+    #[test]
+    fn test_admin_params_order_independent() {
+        let admin = |params: &str| UnsignedTransaction {
+            payload: TransactionPayload::Admin {
+                action: "halt".into(),
+                params: serde_json::from_str(params).unwrap(),
+            },
+            ..transfer(&Ed25519::from_seed(1), 0)
+        };
+        assert_eq!(
+            admin(r#"{"market": "ROMER", "limits": {"b": 1, "a": [2, {"d": 3, "c": 4}]}}"#).signing_bytes(),
+            admin(r#"{"limits": {"a": [2, {"c": 4, "d": 3}], "b": 1}, "market": "ROMER"}"#).signing_bytes()
+        );
+        assert_ne!(
+            admin(r#"{"market": "ROMER"}"#).signing_bytes(),
+            admin(r#"{"market": "ROMER-FX"}"#).signing_bytes()
+        );
+    }
+}
@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+/// Network a node runs for. Test conveniences such as the faucet exist on
+/// development and testnet networks only, never where RØMER has value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExecutionEnvironment {
+    #[default]
+    Development,
+    Testnet,
+    Mainnet,
+}
+
+impl ExecutionEnvironment {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Development => "development",
+            Self::Testnet => "testnet",
+            Self::Mainnet => "mainnet",
+        }
+    }
+
+    /// Whether test RØMER may be minted on request
+    pub fn allows_faucet(&self) -> bool {
+        !matches!(self, Self::Mainnet)
+    }
+}
+
+impl std::fmt::Display for ExecutionEnvironment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl std::str::FromStr for ExecutionEnvironment {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "development" => Ok(Self::Development),
+            "testnet" => Ok(Self::Testnet),
+            "mainnet" => Ok(Self::Mainnet),
+            other => Err(format!("unknown environment {}", other)),
+        }
+    }
+}
@@ -50,12 +50,22 @@ pub enum MessageType {
     Logout,
     /// Heartbeat message (35=0) - Keeps session alive
     Heartbeat,
+    /// Test Request message (35=1) - Forces a Heartbeat from the counterparty
+    TestRequest,
     /// New Order Single message (35=D) - Submits a new order
     NewOrderSingle,
     /// Market Data Request message (35=V) - Requests market data
     MarketDataRequest,
     /// Market Data Snapshot message (35=W) - Provides market data
     MarketDataSnapshot,
+    /// Order Mass Cancel Request message (35=q) - Cancels many orders at once
+    OrderMassCancelRequest,
+    /// Allocation Instruction message (35=J) - Gives executions up to clearing firms
+    AllocationInstruction,
+    /// Allocation ACK message (35=P) - Acknowledges an allocation instruction
+    AllocationAck,
+    /// Order Status Request message (35=H) - Asks for the current state of an order
+    OrderStatusRequest,
 }
 
 impl MessageType {
@@ -65,9 +75,14 @@ impl MessageType {
             "A" => Some(Self::Logon),
             "5" => Some(Self::Logout),
             "0" => Some(Self::Heartbeat),
+            "1" => Some(Self::TestRequest),
             "D" => Some(Self::NewOrderSingle),
             "V" => Some(Self::MarketDataRequest),
             "W" => Some(Self::MarketDataSnapshot),
+            "q" => Some(Self::OrderMassCancelRequest),
+            "J" => Some(Self::AllocationInstruction),
+            "P" => Some(Self::AllocationAck),
+            "H" => Some(Self::OrderStatusRequest),
             _ => None,
         }
     }
@@ -78,9 +93,14 @@ impl MessageType {
             Self::Logon => "A",
             Self::Logout => "5",
             Self::Heartbeat => "0",
+            Self::TestRequest => "1",
             Self::NewOrderSingle => "D",
             Self::MarketDataRequest => "V",
             Self::MarketDataSnapshot => "W",
+            Self::OrderMassCancelRequest => "q",
+            Self::AllocationInstruction => "J",
+            Self::AllocationAck => "P",
+            Self::OrderStatusRequest => "H",
         }
     }
 }
@@ -110,37 +130,111 @@ pub struct ValidatedMessage {
 pub mod utils {
     use super::*;
 
-    /// Generates a timestamp string in FIX protocol format (YYYYMMDD-HH:MM:SS).
+    /// Field delimiter on the wire
+    pub const SOH: u8 = 0x01;
+
+    /// Formats a time as a FIX UTCTimestamp (YYYYMMDD-HH:MM:SS.sss)
+    pub fn format_timestamp(time: DateTime<Utc>) -> String {
+        time.format("%Y%m%d-%H:%M:%S%.3f").to_string()
+    }
+
+    /// Generates a SendingTime (52) for the current instant.
     /// All timestamps in the system are in UTC to ensure consistency across regions.
     pub fn generate_timestamp() -> String {
-        let now: DateTime<Utc> = Utc::now();
-        now.format("%Y%m%d-%H:%M:%S").to_string()
+        format_timestamp(Utc::now())
     }
 
-    /// Calculates the FIX message checksum according to protocol specifications.
-    /// The checksum is simply the sum of all bytes modulo 256, formatted as a
-    /// three-digit string with leading zeros.
+    /// Calculates the FIX message checksum according to protocol specifications:
+    /// the sum of every byte up to and including the SOH before the CheckSum
+    /// (10) field, modulo 256, formatted as a three-digit string with leading zeros.
     pub fn calculate_checksum(msg: &[u8]) -> String {
         let sum: u32 = msg.iter().map(|&b| b as u32).sum();
         format!("{:03}", sum % 256)
     }
 
+    /// Encodes a complete message. `body` holds every field after BodyLength
+    /// (9), starting with MsgType (35); BeginString, BodyLength and CheckSum
+    /// are added here.
+    pub fn encode_message(begin_string: &str, body: &[(u32, String)]) -> Vec<u8> {
+        let mut encoded_body = Vec::new();
+        for (tag, value) in body {
+            encoded_body.extend_from_slice(format!("{}={}", tag, value).as_bytes());
+            encoded_body.push(SOH);
+        }
+
+        let mut msg = format!("8={}\x019={}\x01", begin_string, encoded_body.len()).into_bytes();
+        msg.extend_from_slice(&encoded_body);
+        let checksum = calculate_checksum(&msg);
+        msg.extend_from_slice(format!("10={}", checksum).as_bytes());
+        msg.push(SOH);
+        msg
+    }
+
+    /// Field delimiter of `raw_data`: SOH, or `|` for messages written by
+    /// hand or copied from logs
+    pub fn delimiter(raw_data: &[u8]) -> u8 {
+        if raw_data.contains(&SOH) {
+            SOH
+        } else {
+            b'|'
+        }
+    }
+
+    /// Checks the framing of an SOH delimited message: BeginString (8) and
+    /// BodyLength (9) lead, BodyLength counts the bytes from MsgType (35) up to
+    /// CheckSum (10), and CheckSum ends the message and matches its bytes.
+    pub fn validate_message(raw_data: &[u8]) -> Result<(), FixError> {
+        let fields: Vec<&[u8]> = raw_data
+            .strip_suffix(&[SOH])
+            .ok_or_else(|| FixError::Malformed("message does not end with SOH".into()))?
+            .split(|&b| b == SOH)
+            .collect();
+        if fields.len() < 4 || !fields[0].starts_with(b"8=") {
+            return Err(FixError::MissingField(8));
+        }
+        let declared = fields[1]
+            .strip_prefix(b"9=")
+            .ok_or(FixError::MissingField(9))?;
+        let declared = String::from_utf8_lossy(declared).to_string();
+        let trailer = fields[fields.len() - 1]
+            .strip_prefix(b"10=")
+            .ok_or(FixError::MissingField(10))?;
+
+        let body_start = fields[0].len() + fields[1].len() + 2;
+        let checksum_start = raw_data.len() - trailer.len() - 4;
+        let actual = (checksum_start - body_start).to_string();
+        if declared != actual {
+            return Err(FixError::BodyLengthMismatch { declared, actual });
+        }
+
+        let expected = calculate_checksum(&raw_data[..checksum_start]);
+        let actual = String::from_utf8_lossy(trailer).to_string();
+        if expected != actual {
+            return Err(FixError::ChecksumMismatch { expected, actual });
+        }
+        Ok(())
+    }
+
     /// Parses a raw FIX message into a map of field tags to values.
-    /// This is useful for debugging and logging purposes.
+    /// Repeated tags keep their last value.
     pub fn parse_message_fields(raw_data: &[u8]) -> HashMap<u32, String> {
         let mut fields = HashMap::new();
-        let data = String::from_utf8_lossy(raw_data);
-        
-        for field in data.split('|') {
+        for field in raw_data.split(|&b| b == delimiter(raw_data)) {
+            let field = String::from_utf8_lossy(field);
             if let Some((tag, value)) = field.split_once('=') {
                 if let Ok(tag_num) = tag.parse::<u32>() {
                     fields.insert(tag_num, value.to_string());
                 }
             }
         }
-        
+
         fields
     }
+
+    /// Renders a message for logs and terminals, with `|` in place of SOH
+    pub fn display(raw_data: &[u8]) -> String {
+        String::from_utf8_lossy(raw_data).replace('\x01', "|")
+    }
 }
 
 /// Error types that can occur during FIX message processing
@@ -158,6 +252,15 @@ pub enum FixError {
         value: String,
     },
     
+    #[error("BodyLength mismatch: declared {declared}, actual {actual}")]
+    BodyLengthMismatch {
+        declared: String,
+        actual: String,
+    },
+
+    #[error("Malformed message: {0}")]
+    Malformed(String),
+
     #[error("Checksum mismatch: expected {expected}, got {actual}")]
     ChecksumMismatch {
         expected: String,
@@ -177,8 +280,66 @@ mod tests {
 
     #[test]
     fn test_checksum_calculation() {
-        let msg = b"8=FIX.4.2|9=0|35=A|";
+        let msg = b"8=FIX.4.2\x019=0\x0135=A\x01";
         let checksum = utils::calculate_checksum(msg);
         assert_eq!(checksum.len(), 3);
     }
-}
\ No newline at end of file
+
+    fn soh(message: &str) -> Vec<u8> {
+        message.replace('|', "\x01").into_bytes()
+    }
+
+    // Widely published sample messages with the BodyLength and CheckSum
+    // QuickFIX computes for them
+    const SAMPLES: [&str; 2] = [
+        "8=FIX.4.2|9=178|35=8|49=PHLX|56=PERS|52=20071123-05:30:00.000|11=ATOMNOCCC9990900|20=3|150=E|39=E|55=MSFT|167=CS|54=1|38=15|40=2|44=15|58=PHLX EQUITY TESTING|59=0|47=C|32=0|31=0|151=15|14=0|6=0|10=128|",
+        "8=FIX.4.4|9=122|35=D|34=215|49=CLIENT12|52=20100225-19:41:57.316|56=B|1=Marcel|11=13346|21=1|40=2|44=5|54=1|59=0|60=20100225-19:39:52.020|10=072|",
+    ];
+
+    #[test]
+    fn test_conformance_samples() {
+        for sample in SAMPLES {
+            let raw = soh(sample);
+            utils::validate_message(&raw).unwrap();
+
+            // Re-encoding the body reproduces the sample byte for byte
+            let fields: Vec<(u32, String)> = sample
+                .trim_end_matches('|')
+                .split('|')
+                .map(|field| {
+                    let (tag, value) = field.split_once('=').unwrap();
+                    (tag.parse().unwrap(), value.to_string())
+                })
+                .filter(|(tag, _)| ![8, 9, 10].contains(tag))
+                .collect();
+            let begin_string = &sample[2..sample.find('|').unwrap()];
+            assert_eq!(utils::encode_message(begin_string, &fields), raw);
+        }
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_framing() {
+        let mut raw = soh(SAMPLES[0]);
+        assert!(matches!(
+            utils::validate_message(&soh(&SAMPLES[0].replace("9=178", "9=0"))),
+            Err(FixError::BodyLengthMismatch { .. })
+        ));
+        assert!(matches!(
+            utils::validate_message(&soh(&SAMPLES[0].replace("10=128", "10=127"))),
+            Err(FixError::ChecksumMismatch { .. })
+        ));
+        raw.pop();
+        assert!(matches!(utils::validate_message(&raw), Err(FixError::Malformed(_))));
+        assert!(utils::validate_message(SAMPLES[0].as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_parse_fields() {
+        let fields = utils::parse_message_fields(&soh(SAMPLES[1]));
+        assert_eq!(fields.get(&35).map(String::as_str), Some("D"));
+        assert_eq!(fields.get(&1).map(String::as_str), Some("Marcel"));
+        // Pipe delimited input is still accepted
+        assert_eq!(utils::parse_message_fields(SAMPLES[1].as_bytes()), fields);
+        assert_eq!(utils::display(&soh(SAMPLES[1])), SAMPLES[1]);
+    }
+}
@@ -0,0 +1,176 @@
+use commonware_cryptography::{Hasher, Sha256};
+use commonware_utils::{from_hex, hex};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use thiserror::Error;
+
+use crate::types::address::Address;
+use crate::types::environment::ExecutionEnvironment;
+use crate::types::org::Organization;
+
+/// Format of the bundles written by this version
+pub const GENESIS_BUNDLE_VERSION: u32 = 1;
+
+#[derive(Error, Debug)]
+pub enum GenesisError {
+    #[error("Unsupported genesis bundle version {0}")]
+    UnsupportedVersion(u32),
+
+    #[error("Genesis bundle digest {actual} does not match its contents ({expected})")]
+    DigestMismatch { expected: String, actual: String },
+
+    #[error("Genesis object {0} is not valid hex")]
+    InvalidObject(String),
+
+    #[error("Failed to encode genesis bundle: {0}")]
+    Encoding(String),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// A piece of VM state, as opaque key and value bytes in hex. The key is
+/// the VM's encoding of where the object lives.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct GenesisObject {
+    pub key: String,
+    pub value: String,
+}
+
+impl GenesisObject {
+    pub fn new(key: &[u8], value: &[u8]) -> Self {
+        Self {
+            key: hex(key),
+            value: hex(value),
+        }
+    }
+
+    /// Key and value bytes
+    pub fn decode(&self) -> Result<(Vec<u8>, Vec<u8>), GenesisError> {
+        let key = from_hex(&self.key).ok_or_else(|| GenesisError::InvalidObject(self.key.clone()))?;
+        let value = from_hex(&self.value).ok_or_else(|| GenesisError::InvalidObject(self.key.clone()))?;
+        Ok((key, value))
+    }
+}
+
+/// Chain state at a height, portable enough to boot another network from:
+/// a staging network can start out as a copy of production. Everything is
+/// kept in a canonical order so exporting the same state twice gives the
+/// same bytes, and the digest lets the importing side check the bundle
+/// arrived intact.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GenesisBundle {
+    pub version: u32,
+    /// Network the state was exported from
+    pub environment: ExecutionEnvironment,
+    /// The bundle holds the state before the block at this height
+    pub height: u64,
+    pub balances: BTreeMap<Address, u64>,
+    /// Next nonce expected to execute, per account
+    pub nonces: BTreeMap<Address, u64>,
+    /// Registered organizations, by id
+    pub organizations: Vec<Organization>,
+    /// VM objects, by key
+    pub objects: Vec<GenesisObject>,
+    /// Hex SHA-256 of the bundle with this field empty, set by `seal`
+    #[serde(default)]
+    pub digest: String,
+}
+
+impl GenesisBundle {
+    pub fn new(environment: ExecutionEnvironment, height: u64) -> Self {
+        Self {
+            version: GENESIS_BUNDLE_VERSION,
+            environment,
+            height,
+            balances: BTreeMap::new(),
+            nonces: BTreeMap::new(),
+            organizations: Vec::new(),
+            objects: Vec::new(),
+            digest: String::new(),
+        }
+    }
+
+    /// Puts organizations and objects in canonical order and sets the digest
+    pub fn seal(mut self) -> Result<Self, GenesisError> {
+        self.organizations.sort_by(|a, b| a.id.cmp(&b.id));
+        self.objects.sort();
+        self.digest = self.compute_digest()?;
+        Ok(self)
+    }
+
+    /// Checks the version and that the contents match the digest
+    pub fn verify(&self) -> Result<(), GenesisError> {
+        if self.version != GENESIS_BUNDLE_VERSION {
+            return Err(GenesisError::UnsupportedVersion(self.version));
+        }
+        let expected = self.compute_digest()?;
+        if expected != self.digest {
+            return Err(GenesisError::DigestMismatch {
+                expected,
+                actual: self.digest.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    fn compute_digest(&self) -> Result<String, GenesisError> {
+        let unsealed = Self {
+            digest: String::new(),
+            ..self.clone()
+        };
+        let bytes = serde_json::to_vec(&unsealed).map_err(|e| GenesisError::Encoding(e.to_string()))?;
+        let mut hasher = Sha256::new();
+        hasher.update(&bytes);
+        Ok(hex(&hasher.finalize()))
+    }
+
+    /// Reads and verifies a bundle written by `write`
+    pub fn read(path: &Path) -> Result<Self, GenesisError> {
+        let bytes = std::fs::read(path)?;
+        let bundle: Self = serde_json::from_slice(&bytes).map_err(|e| GenesisError::Encoding(e.to_string()))?;
+        bundle.verify()?;
+        Ok(bundle)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), GenesisError> {
+        let bytes = serde_json::to_vec_pretty(self).map_err(|e| GenesisError::Encoding(e.to_string()))?;
+        std::fs::write(path, bytes)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundle(objects: Vec<GenesisObject>) -> GenesisBundle {
+        let mut bundle = GenesisBundle::new(ExecutionEnvironment::Mainnet, 42);
+        bundle.balances.insert(Address::new([1u8; 32]), 500);
+        bundle.balances.insert(Address::new([2u8; 32]), 7);
+        bundle.nonces.insert(Address::new([1u8; 32]), 3);
+        bundle.objects = objects;
+        bundle.seal().unwrap()
+    }
+
+    #[test]
+    fn test_digest_is_deterministic() {
+        let a = GenesisObject::new(b"a", &[1, 2]);
+        let b = GenesisObject::new(b"b", &[3]);
+        let first = bundle(vec![a.clone(), b.clone()]);
+        let second = bundle(vec![b, a]);
+        assert_eq!(first, second);
+        assert_eq!(first.objects[1].decode().unwrap(), (b"b".to_vec(), vec![3]));
+        first.verify().unwrap();
+
+        let path = std::env::temp_dir().join(format!("romer-genesis-{}.json", std::process::id()));
+        first.write(&path).unwrap();
+        assert_eq!(GenesisBundle::read(&path).unwrap(), first);
+        std::fs::remove_file(&path).unwrap();
+
+        let mut tampered = first.clone();
+        tampered.balances.insert(Address::new([2u8; 32]), 7_000);
+        assert!(matches!(tampered.verify(), Err(GenesisError::DigestMismatch { .. })));
+    }
+}
@@ -0,0 +1,217 @@
+use commonware_cryptography::{Ed25519, PublicKey, Scheme, Signature};
+use commonware_utils::{from_hex, hex};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+use crate::types::instrument::InstrumentOverride;
+
+/// Namespace of validators' signatures over proposals and votes
+pub const GOVERNANCE_NAMESPACE: &[u8] = b"_ROMER_GOVERNANCE";
+
+const MAX_BPS: u32 = 10_000;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum GovernanceError {
+    #[error("Invalid parameter change: {0}")]
+    InvalidChange(String),
+
+    #[error("Invalid governance signature")]
+    InvalidSignature,
+
+    #[error("Failed to encode governance message: {0}")]
+    Encoding(String),
+}
+
+/// A runtime parameter and the value it takes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "parameter", rename_all = "snake_case")]
+pub enum ParameterChange {
+    /// Share of trading fees burned, in basis points
+    FeeBurnBps { value: u16 },
+    /// Band around the reference price orders must lie in, `None` to lift it
+    PriceCollarBps { value: Option<u32> },
+    /// Length of the block window
+    BlockWindowMs { value: u64 },
+    /// Gas charged for one VM operation
+    GasCost { operation: String, cost: u64 },
+    /// Matching parameters of one instrument
+    Instrument {
+        symbol: String,
+        overrides: InstrumentOverride,
+    },
+}
+
+impl ParameterChange {
+    pub fn validate(&self) -> Result<(), GovernanceError> {
+        let invalid = |reason: &str| Err(GovernanceError::InvalidChange(reason.to_string()));
+        match self {
+            Self::FeeBurnBps { value } if *value as u32 > MAX_BPS => invalid("fee burn share above 10000bps"),
+            Self::PriceCollarBps { value: Some(0) } => invalid("price collar of 0bps"),
+            Self::BlockWindowMs { value: 0 } => invalid("block window of 0ms"),
+            Self::GasCost { operation, .. } if operation.is_empty() => invalid("gas cost without an operation"),
+            Self::Instrument { symbol, .. } if symbol.is_empty() => invalid("instrument override without a symbol"),
+            Self::Instrument { overrides, .. } => overrides
+                .validate()
+                .map_err(|e| GovernanceError::InvalidChange(e.to_string())),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The runtime parameters in force
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Parameters {
+    pub fee_burn_bps: u16,
+    pub price_collar_bps: Option<u32>,
+    pub block_window_ms: u64,
+    /// Overrides of the VM's default gas costs, by operation
+    pub gas_costs: BTreeMap<String, u64>,
+    /// Instrument overrides approved so far, merged by symbol
+    #[serde(default)]
+    pub instruments: BTreeMap<String, InstrumentOverride>,
+}
+
+impl Parameters {
+    pub fn apply(&mut self, change: &ParameterChange) {
+        match change {
+            ParameterChange::FeeBurnBps { value } => self.fee_burn_bps = *value,
+            ParameterChange::PriceCollarBps { value } => self.price_collar_bps = *value,
+            ParameterChange::BlockWindowMs { value } => self.block_window_ms = *value,
+            ParameterChange::GasCost { operation, cost } => {
+                self.gas_costs.insert(operation.clone(), *cost);
+            }
+            ParameterChange::Instrument { symbol, overrides } => {
+                let merged = self.instruments.entry(symbol.clone()).or_default();
+                *merged = InstrumentOverride {
+                    tick_size: overrides.tick_size.or(merged.tick_size),
+                    lot_size: overrides.lot_size.or(merged.lot_size),
+                    matching: overrides.matching.or(merged.matching),
+                    band_bps: overrides.band_bps.or(merged.band_bps),
+                };
+            }
+        }
+    }
+}
+
+/// Parameter changes a validator proposes to activate at a height
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Proposal {
+    pub changes: Vec<ParameterChange>,
+    /// Votes close and, if approved, the changes apply at this height
+    pub activation_height: u64,
+    #[serde(default)]
+    pub description: String,
+}
+
+/// A validator's vote on a proposal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Vote {
+    pub proposal_id: u64,
+    pub approve: bool,
+}
+
+/// A proposal or vote with the validator's Ed25519 key and signature over
+/// its JSON encoding, hex encoded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Signed<T> {
+    pub body: T,
+    pub public_key: String,
+    pub signature: String,
+}
+
+impl<T: Serialize> Signed<T> {
+    pub fn sign(body: T, signer: &mut Ed25519) -> Result<Self, GovernanceError> {
+        let message = signing_bytes(&body)?;
+        Ok(Self {
+            public_key: hex(&signer.public_key()),
+            signature: hex(&signer.sign(Some(GOVERNANCE_NAMESPACE), &message)),
+            body,
+        })
+    }
+
+    pub fn verify(&self) -> Result<(), GovernanceError> {
+        let message = signing_bytes(&self.body)?;
+        let public_key = from_hex(&self.public_key).ok_or(GovernanceError::InvalidSignature)?;
+        let signature = from_hex(&self.signature).ok_or(GovernanceError::InvalidSignature)?;
+        if !Ed25519::verify(
+            Some(GOVERNANCE_NAMESPACE),
+            &message,
+            &PublicKey::from(public_key),
+            &Signature::from(signature),
+        ) {
+            return Err(GovernanceError::InvalidSignature);
+        }
+        Ok(())
+    }
+}
+
+fn signing_bytes<T: Serialize>(body: &T) -> Result<Vec<u8>, GovernanceError> {
+    serde_json::to_vec(body).map_err(|e| GovernanceError::Encoding(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signed_vote() {
+        let mut validator = Ed25519::from_seed(1);
+        let mut vote = Signed::sign(
+            Vote {
+                proposal_id: 3,
+                approve: true,
+            },
+            &mut validator,
+        )
+        .unwrap();
+        vote.verify().unwrap();
+        vote.body.approve = false;
+        assert_eq!(vote.verify(), Err(GovernanceError::InvalidSignature));
+    }
+
+    #[test]
+    fn test_apply_changes() {
+        let mut parameters = Parameters::default();
+        for change in [
+            ParameterChange::BlockWindowMs { value: 250 },
+            ParameterChange::GasCost {
+                operation: "move_call".into(),
+                cost: 40,
+            },
+            ParameterChange::PriceCollarBps { value: Some(500) },
+        ] {
+            change.validate().unwrap();
+            parameters.apply(&change);
+        }
+        assert_eq!(parameters.block_window_ms, 250);
+        assert_eq!(parameters.gas_costs["move_call"], 40);
+        assert_eq!(parameters.price_collar_bps, Some(500));
+
+        assert!(ParameterChange::FeeBurnBps { value: 10_001 }.validate().is_err());
+        assert!(ParameterChange::BlockWindowMs { value: 0 }.validate().is_err());
+
+        for overrides in [
+            InstrumentOverride {
+                tick_size: Some(10_000_000),
+                ..InstrumentOverride::default()
+            },
+            InstrumentOverride {
+                lot_size: Some(100),
+                ..InstrumentOverride::default()
+            },
+        ] {
+            parameters.apply(&ParameterChange::Instrument {
+                symbol: "AAPL".into(),
+                overrides,
+            });
+        }
+        assert_eq!(parameters.instruments["AAPL"].tick_size, Some(10_000_000));
+        assert_eq!(parameters.instruments["AAPL"].lot_size, Some(100));
+        let empty = ParameterChange::Instrument {
+            symbol: "AAPL".into(),
+            overrides: InstrumentOverride::default(),
+        };
+        assert!(empty.validate().is_err());
+    }
+}
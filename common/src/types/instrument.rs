@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::types::oracle::parse_price;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum InstrumentError {
+    #[error("Invalid instrument override: {0}")]
+    InvalidOverride(String),
+
+    #[error("Invalid {field} {value}")]
+    InvalidField { field: &'static str, value: String },
+
+    #[error("Price {price} is not a multiple of the tick size {tick_size}")]
+    OffTick { price: String, tick_size: u64 },
+
+    #[error("Quantity {quantity} is not a multiple of the lot size {lot_size}")]
+    OddLot { quantity: u64, lot_size: u64 },
+}
+
+/// How an instrument's orders are matched
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchingMode {
+    /// Orders match as they arrive
+    #[default]
+    Continuous,
+    /// Orders collected over a block match at a single price
+    Batch,
+}
+
+/// Matching parameters of one instrument
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstrumentParameters {
+    /// Smallest price increment, in units of 10^-9
+    pub tick_size: u64,
+    /// Smallest quantity increment
+    pub lot_size: u64,
+    pub matching: MatchingMode,
+    /// Band around the reference price orders must lie in, the sequencer's
+    /// price collar without
+    pub band_bps: Option<u32>,
+}
+
+impl Default for InstrumentParameters {
+    fn default() -> Self {
+        Self {
+            tick_size: 1,
+            lot_size: 1,
+            matching: MatchingMode::default(),
+            band_bps: None,
+        }
+    }
+}
+
+impl InstrumentParameters {
+    /// Checks an order's FIX Price (44), if any, and OrderQty (38)
+    pub fn check_order(&self, price: Option<&str>, quantity: &str) -> Result<(), InstrumentError> {
+        if let Some(price) = price {
+            let scaled = parse_price(price).map_err(|_| InstrumentError::InvalidField {
+                field: "price",
+                value: price.to_string(),
+            })?;
+            if scaled % self.tick_size != 0 {
+                return Err(InstrumentError::OffTick {
+                    price: price.to_string(),
+                    tick_size: self.tick_size,
+                });
+            }
+        }
+        let quantity: u64 = quantity.parse().map_err(|_| InstrumentError::InvalidField {
+            field: "quantity",
+            value: quantity.to_string(),
+        })?;
+        if quantity % self.lot_size != 0 {
+            return Err(InstrumentError::OddLot {
+                quantity,
+                lot_size: self.lot_size,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Changes to an instrument's parameters; unset fields keep their value
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InstrumentOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tick_size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lot_size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matching: Option<MatchingMode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub band_bps: Option<u32>,
+}
+
+impl InstrumentOverride {
+    pub fn validate(&self) -> Result<(), InstrumentError> {
+        let invalid = |reason: &str| Err(InstrumentError::InvalidOverride(reason.to_string()));
+        if *self == Self::default() {
+            return invalid("no parameter changed");
+        }
+        if self.tick_size == Some(0) || self.lot_size == Some(0) {
+            return invalid("tick and lot sizes must be nonzero");
+        }
+        if self.band_bps == Some(0) {
+            return invalid("band of 0bps");
+        }
+        Ok(())
+    }
+
+    pub fn apply(&self, parameters: &mut InstrumentParameters) {
+        if let Some(tick_size) = self.tick_size {
+            parameters.tick_size = tick_size;
+        }
+        if let Some(lot_size) = self.lot_size {
+            parameters.lot_size = lot_size;
+        }
+        if let Some(matching) = self.matching {
+            parameters.matching = matching;
+        }
+        if let Some(band_bps) = self.band_bps {
+            parameters.band_bps = Some(band_bps);
+        }
+    }
+}
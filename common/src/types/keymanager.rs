@@ -1,9 +1,11 @@
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, TimeZone, Utc};
+use commonware_cryptography::{Bls12381, PrivateKey, PublicKey, Scheme, Signature};
+use commonware_utils::hex;
 
 /// Represents the supported signature schemes in the system
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum SignatureScheme {
     Ed25519,
     Bls12381,
@@ -30,6 +32,65 @@ pub struct SessionKeyData {
     pub namespace: String,
 }
 
+impl SessionKeyData {
+    /// The public half of the session key and the parent's endorsement of
+    /// it, which is everything a verifier needs
+    pub fn certificate(&self) -> KeyManagerResult<SessionCertificate> {
+        let session_key = <Bls12381 as Scheme>::from(PrivateKey::from(self.key_bytes.clone()))
+            .ok_or_else(|| KeyManagerError::InvalidKeyFormat("Invalid session key".into()))?;
+        Ok(SessionCertificate {
+            session_public_key: session_key.public_key().to_vec(),
+            parent_public_key: self.parent_public_key.clone(),
+            namespace: self.namespace.clone(),
+            purpose: self.purpose.clone(),
+            expires_at: self.expires_at.timestamp(),
+            parent_signature: self.parent_signature.clone(),
+        })
+    }
+}
+
+/// A session public key endorsed by a permanent BLS key. The parent signs
+/// `<hex session key>:<expiry>:<purpose>` under the session namespace.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionCertificate {
+    pub session_public_key: Vec<u8>,
+    pub parent_public_key: Vec<u8>,
+    pub namespace: String,
+    pub purpose: String,
+    /// Unix timestamp (seconds) the session key expires at
+    pub expires_at: i64,
+    pub parent_signature: Vec<u8>,
+}
+
+impl SessionCertificate {
+    /// The message the parent key signs
+    pub fn message(&self) -> String {
+        format!("{}:{}:{}", hex(&self.session_public_key), self.expires_at, self.purpose)
+    }
+
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        Utc.timestamp_opt(self.expires_at, 0).single()
+    }
+
+    /// Checks the expiry and the parent's signature
+    pub fn verify(&self, now: DateTime<Utc>) -> KeyManagerResult<()> {
+        if now.timestamp() > self.expires_at {
+            return Err(KeyManagerError::SessionExpired);
+        }
+
+        if !Bls12381::verify(
+            Some(self.namespace.as_bytes()),
+            self.message().as_bytes(),
+            &PublicKey::from(self.parent_public_key.clone()),
+            &Signature::from(self.parent_signature.clone()),
+        ) {
+            return Err(KeyManagerError::InvalidSessionSignature);
+        }
+
+        Ok(())
+    }
+}
+
 /// Custom error types for key management operations
 #[derive(Error, Debug)]
 pub enum KeyManagerError {
@@ -56,6 +117,15 @@ pub enum KeyManagerError {
 
     #[error("Storage directory error: {0}")]
     StorageError(String),
+
+    #[error("Keystore is locked")]
+    Locked,
+
+    #[error("Invalid passphrase")]
+    InvalidPassphrase,
+
+    #[error("Encryption error: {0}")]
+    EncryptionError(String),
 }
 
 /// Result type alias for key management operations
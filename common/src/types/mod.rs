@@ -1,6 +1,23 @@
+pub mod address;
+pub mod admin;
+pub mod bridge;
+pub mod attestation;
+pub mod envelope;
+pub mod environment;
+pub mod genesis;
 pub mod org;
 pub mod token;
 pub mod keymanager;
+pub mod nonce;
+pub mod oracle;
+pub mod protocol;
+pub mod receipt;
+pub mod rejection;
+pub mod snapshot;
 pub mod fix;
+pub mod governance;
+pub mod instrument;
+pub mod tokenomics;
+pub mod treasury;
 
 use serde::{Deserialize, Serialize};
\ No newline at end of file
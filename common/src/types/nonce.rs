@@ -0,0 +1,39 @@
+use thiserror::Error;
+
+/// Errors raised when a transaction's nonce is not acceptable
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum NonceError {
+    #[error("Nonce {got} already used, next expected is {expected}")]
+    Stale { expected: u64, got: u64 },
+
+    #[error("Nonce {got} is too far ahead of expected nonce {expected}")]
+    TooFarAhead { expected: u64, got: u64 },
+
+    #[error("A transaction with nonce {0} is already pending")]
+    AlreadyPending(u64),
+}
+
+/// Checks `got` against the account's next expected nonce. `max_gap` allows
+/// nonces ahead of the expected one, which the mempool needs to queue
+/// transactions; execution uses a gap of zero.
+pub fn check_nonce(expected: u64, got: u64, max_gap: u64) -> Result<(), NonceError> {
+    if got < expected {
+        return Err(NonceError::Stale { expected, got });
+    }
+    if got - expected > max_gap {
+        return Err(NonceError::TooFarAhead { expected, got });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gap_allowance() {
+        assert!(check_nonce(5, 8, 3).is_ok());
+        assert!(check_nonce(5, 9, 3).is_err());
+        assert!(check_nonce(5, 4, 3).is_err());
+    }
+}
@@ -0,0 +1,149 @@
+use commonware_cryptography::{Ed25519, PublicKey, Scheme, Signature};
+use commonware_utils::{from_hex, hex};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Namespace of feeders' signatures over price submissions
+pub const ORACLE_NAMESPACE: &[u8] = b"_ROMER_ORACLE";
+
+/// Prices are fixed point with this many units per 1.0, as in the Move
+/// framework
+pub const PRICE_SCALE: u64 = 1_000_000_000;
+
+const PRICE_DECIMALS: usize = 9;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum OracleError {
+    #[error("Invalid price {0}")]
+    InvalidPrice(String),
+
+    #[error("Invalid oracle signature")]
+    InvalidSignature,
+}
+
+/// One feeder's price of a symbol
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PriceSubmission {
+    pub symbol: String,
+    /// Scaled by [`PRICE_SCALE`]
+    pub price: u64,
+    /// When the feeder observed the price, unix milliseconds
+    pub observed_at_ms: u64,
+}
+
+impl PriceSubmission {
+    /// The bytes a feeder signs, `symbol|price|observed_at_ms`
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        format!("{}|{}|{}", self.symbol, self.price, self.observed_at_ms).into_bytes()
+    }
+}
+
+/// A price submission with the feeder's Ed25519 key and signature, hex
+/// encoded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedPriceSubmission {
+    pub submission: PriceSubmission,
+    pub public_key: String,
+    pub signature: String,
+}
+
+impl SignedPriceSubmission {
+    pub fn sign(submission: PriceSubmission, signer: &mut Ed25519) -> Self {
+        let signature = signer.sign(Some(ORACLE_NAMESPACE), &submission.signing_bytes());
+        Self {
+            submission,
+            public_key: hex(&signer.public_key()),
+            signature: hex(&signature),
+        }
+    }
+
+    pub fn verify(&self) -> Result<(), OracleError> {
+        let public_key = from_hex(&self.public_key).ok_or(OracleError::InvalidSignature)?;
+        let signature = from_hex(&self.signature).ok_or(OracleError::InvalidSignature)?;
+        if !Ed25519::verify(
+            Some(ORACLE_NAMESPACE),
+            &self.submission.signing_bytes(),
+            &PublicKey::from(public_key),
+            &Signature::from(signature),
+        ) {
+            return Err(OracleError::InvalidSignature);
+        }
+        Ok(())
+    }
+}
+
+/// The aggregated price of a symbol written to the `romer::oracle` module
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OraclePrice {
+    pub symbol: String,
+    /// Scaled by [`PRICE_SCALE`]
+    pub price: u64,
+    /// Feeders whose prices the median was taken over
+    pub feeders: u32,
+    /// Block time the price was aggregated at, unix milliseconds
+    pub timestamp_ms: u64,
+}
+
+/// Parses a decimal price such as `101.25` into [`PRICE_SCALE`] units,
+/// without going through floating point
+pub fn parse_price(value: &str) -> Result<u64, OracleError> {
+    let invalid = || OracleError::InvalidPrice(value.to_string());
+    let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+    let all_digits = |part: &str| part.bytes().all(|byte| byte.is_ascii_digit());
+    if whole.is_empty() || !all_digits(whole) || !all_digits(fraction) || fraction.len() > PRICE_DECIMALS {
+        return Err(invalid());
+    }
+    let whole: u64 = whole.parse().map_err(|_| invalid())?;
+    let fraction: u64 = format!("{:0<width$}", fraction, width = PRICE_DECIMALS)
+        .parse()
+        .map_err(|_| invalid())?;
+    whole
+        .checked_mul(PRICE_SCALE)
+        .and_then(|scaled| scaled.checked_add(fraction))
+        .filter(|price| *price > 0)
+        .ok_or_else(invalid)
+}
+
+/// Formats a [`PRICE_SCALE`] price as a decimal, the inverse of
+/// [`parse_price`]
+pub fn format_price(price: u64) -> String {
+    let (whole, fraction) = (price / PRICE_SCALE, price % PRICE_SCALE);
+    if fraction == 0 {
+        return whole.to_string();
+    }
+    let fraction = format!("{:0>width$}", fraction, width = PRICE_DECIMALS);
+    format!("{}.{}", whole, fraction.trim_end_matches('0'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_price() {
+        assert_eq!(parse_price("101.25"), Ok(101_250_000_000));
+        assert_eq!(parse_price("7"), Ok(7 * PRICE_SCALE));
+        assert_eq!(parse_price("0.000000001"), Ok(1));
+        for invalid in ["", "0", "-1", "1.0000000001", "1e3", ".5", "18446744074"] {
+            assert!(parse_price(invalid).is_err(), "{}", invalid);
+        }
+        for price in ["101.25", "7", "0.000000001"] {
+            assert_eq!(format_price(parse_price(price).unwrap()), price);
+        }
+    }
+
+    #[test]
+    fn test_signed_submission() {
+        let mut feeder = Ed25519::from_seed(1);
+        let submission = PriceSubmission {
+            symbol: "BTC/USD".into(),
+            price: parse_price("64000.5").unwrap(),
+            observed_at_ms: 1_700_000_000_000,
+        };
+        let mut signed = SignedPriceSubmission::sign(submission, &mut feeder);
+        signed.verify().unwrap();
+
+        signed.submission.price += 1;
+        assert_eq!(signed.verify(), Err(OracleError::InvalidSignature));
+    }
+}
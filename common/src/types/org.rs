@@ -1,5 +1,10 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+use commonware_cryptography::{Bls12381, PublicKey, Scheme, Signature};
 use commonware_storage::journal;
+use commonware_utils::hex;
+use rand::RngCore;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use thiserror::Error;
 
 use crate::storage::journal::Partition;
@@ -68,12 +73,279 @@ pub enum OrganizationType {
     Custodian,
 }
 
+/// What an organization may do with an instrument. Levels are cumulative:
+/// quoting implies viewing and trading implies quoting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolPermission {
+    /// Subscribe to market data
+    ViewOnly,
+    /// Additionally post quotes
+    Quote,
+    /// Additionally submit orders
+    Trade,
+}
+
+impl SymbolPermission {
+    /// Whether holding `self` grants `required`
+    pub fn allows(&self, required: SymbolPermission) -> bool {
+        *self >= required
+    }
+}
+
+/// Risk limits of a sub-account. Unset limits are not enforced.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SubAccountLimits {
+    /// Largest OrderQty (38) of a single order
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_order_quantity: Option<u64>,
+    /// Largest net position, long or short, in any one symbol
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_position: Option<u64>,
+}
+
+/// A trading desk under an organization, e.g. one of a prime broker's
+/// clients sharing its CompID
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubAccount {
+    pub name: String,
+    #[serde(default)]
+    pub limits: SubAccountLimits,
+}
+
+/// Username (553) and Password (554) an organization's FIX engine may log on
+/// with, for counterparties whose engines cannot sign RawData. Only an
+/// Argon2id hash of the password is kept, with the cost it was computed at.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogonCredential {
+    pub username: String,
+    /// Hex random salt
+    pub salt: String,
+    /// Hex Argon2id hash of the password under the salt
+    pub password_hash: String,
+    /// Cost of the hash. Credentials hashed before it was recorded no longer
+    /// match and must be set again.
+    #[serde(default)]
+    pub params: PasswordParams,
+    /// Whether a Logon may authenticate with the password alone. Otherwise
+    /// the password is a second factor on top of a session key signature.
+    #[serde(default)]
+    pub allow_unsigned: bool,
+}
+
+/// Argon2id cost of a logon password hash
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PasswordParams {
+    /// Memory in KiB
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for PasswordParams {
+    fn default() -> Self {
+        Self {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl LogonCredential {
+    pub fn new(username: impl Into<String>, password: &str, allow_unsigned: bool) -> Self {
+        let mut salt = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut salt);
+        let params = PasswordParams::default();
+        Self {
+            username: username.into(),
+            salt: hex(&salt),
+            password_hash: password_hash(&params, &salt, password).expect("default Argon2 parameters are valid"),
+            params,
+            allow_unsigned,
+        }
+    }
+
+    /// Whether `username` and `password` are this credential's. Compares in
+    /// constant time.
+    pub fn matches(&self, username: &str, password: &str) -> bool {
+        let Some(hash) = commonware_utils::from_hex(&self.salt)
+            .and_then(|salt| password_hash(&self.params, &salt, password))
+        else {
+            return false;
+        };
+        let equal = hash.len() == self.password_hash.len()
+            && hash
+                .bytes()
+                .zip(self.password_hash.bytes())
+                .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+                == 0;
+        equal && username == self.username
+    }
+
+    /// The credential without its salt and hash, for display
+    pub fn redacted(&self) -> Self {
+        Self {
+            salt: String::new(),
+            password_hash: String::new(),
+            ..self.clone()
+        }
+    }
+}
+
+/// Hex Argon2id hash of `password` under `salt`, or `None` if `params` are
+/// out of Argon2's bounds
+fn password_hash(params: &PasswordParams, salt: &[u8], password: &str) -> Option<String> {
+    let params = Params::new(params.memory_kib, params.iterations, params.parallelism, Some(32)).ok()?;
+    let mut hash = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password.as_bytes(), salt, &mut hash)
+        .ok()?;
+    Some(hex(&hash))
+}
+
+/// Namespace of the proof of possession in an `OrganizationRegistration`
+pub const REGISTRATION_NAMESPACE: &[u8] = b"_ROMER_REGISTER";
+
+/// Request to register an organization with a sequencer. The signature by
+/// the organization's BLS key proves the registrant holds that key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrganizationRegistration {
+    pub name: String,
+    pub org_type: OrganizationType,
+    pub sender_comp_id: String,
+    pub public_key: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+impl OrganizationRegistration {
+    /// The message signed by the organization's key
+    pub fn signing_message(name: &str, org_type: &OrganizationType, sender_comp_id: &str) -> Vec<u8> {
+        format!("{}:{:?}:{}", name, org_type, sender_comp_id).into_bytes()
+    }
+
+    /// Checks the proof of possession
+    pub fn verify(&self) -> OrganizationResult<()> {
+        let message = Self::signing_message(&self.name, &self.org_type, &self.sender_comp_id);
+        if !Bls12381::verify(
+            Some(REGISTRATION_NAMESPACE),
+            &message,
+            &PublicKey::from(self.public_key.clone()),
+            &Signature::from(self.signature.clone()),
+        ) {
+            return Err(OrganizationError::InvalidPublicKey(
+                "Registration is not signed by the organization key".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// The organization this registration creates, under `id`
+    pub fn into_organization(self, id: String) -> Organization {
+        Organization::new(id, self.name, self.org_type, self.sender_comp_id, self.public_key)
+    }
+}
+
+/// Namespace of the current key's signature over an `OrganizationUpdate`
+pub const UPDATE_NAMESPACE: &[u8] = b"_ROMER_ORG_UPDATE";
+
+/// Signed change to a registered organization. Fields left as `None` keep
+/// their current value. The update is signed by the organization's current
+/// key; a new key must also sign it under `REGISTRATION_NAMESPACE` to prove
+/// possession.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrganizationUpdate {
+    pub org_id: String,
+    pub name: Option<String>,
+    pub org_type: Option<OrganizationType>,
+    pub sender_comp_id: Option<String>,
+    pub public_key: Option<Vec<u8>>,
+    /// Unix seconds; must be later than the organization's last update so
+    /// a signed update cannot be replayed
+    pub issued_at: u64,
+    pub signature: Vec<u8>,
+    pub new_key_signature: Option<Vec<u8>>,
+}
+
+impl OrganizationUpdate {
+    /// The message signed by the current key, and by the new key if any
+    pub fn signing_message(&self) -> Vec<u8> {
+        format!(
+            "{}:{:?}:{:?}:{:?}:{}:{}",
+            self.org_id,
+            self.name,
+            self.org_type,
+            self.sender_comp_id,
+            self.public_key.as_deref().map(hex).unwrap_or_default(),
+            self.issued_at
+        )
+        .into_bytes()
+    }
+
+    /// Checks the signatures against `current` and returns the updated
+    /// organization
+    pub fn apply(&self, current: &Organization) -> OrganizationResult<Organization> {
+        if self.org_id != current.id {
+            return Err(OrganizationError::InvalidIdentifier(format!(
+                "Update is for {}, not {}",
+                self.org_id, current.id
+            )));
+        }
+        if self.issued_at <= current.updated_at {
+            return Err(OrganizationError::InvalidIdentifier(
+                "Update is older than the organization's last update".into(),
+            ));
+        }
+
+        let message = self.signing_message();
+        if !Bls12381::verify(
+            Some(UPDATE_NAMESPACE),
+            &message,
+            &PublicKey::from(current.public_key.clone()),
+            &Signature::from(self.signature.clone()),
+        ) {
+            return Err(OrganizationError::InvalidPublicKey(
+                "Update is not signed by the organization key".into(),
+            ));
+        }
+
+        let mut updated = current.clone();
+        if let Some(public_key) = &self.public_key {
+            let proof = self.new_key_signature.clone().unwrap_or_default();
+            if !Bls12381::verify(
+                Some(REGISTRATION_NAMESPACE),
+                &message,
+                &PublicKey::from(public_key.clone()),
+                &Signature::from(proof),
+            ) {
+                return Err(OrganizationError::InvalidPublicKey(
+                    "Update is not signed by the new key".into(),
+                ));
+            }
+            updated.public_key = public_key.clone();
+        }
+        if let Some(name) = &self.name {
+            updated.name = name.clone();
+        }
+        if let Some(org_type) = &self.org_type {
+            updated.org_type = org_type.clone();
+        }
+        if let Some(sender_comp_id) = &self.sender_comp_id {
+            updated.sender_comp_id = sender_comp_id.clone();
+        }
+        updated.updated_at = self.issued_at;
+        updated.validate()?;
+        Ok(updated)
+    }
+}
+
 pub struct OrganizationManager {
     organization: Organization,
     journal: RomerJournal,
 }
 /// Represents an organization participating in the RØMER network
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Organization {
     /// Unique identifier for the organization
     pub id: String,
@@ -92,6 +364,25 @@ pub struct Organization {
 
     /// Timestamp of registration (Unix timestamp in seconds)
     pub registered_at: u64,
+
+    /// Timestamp of the last applied `OrganizationUpdate` (Unix seconds)
+    #[serde(default)]
+    pub updated_at: u64,
+
+    /// Pre-registered instrument permissions, keyed by symbol. Symbols not
+    /// listed are not accessible.
+    #[serde(default)]
+    pub symbol_permissions: BTreeMap<String, SymbolPermission>,
+
+    /// Trading desks under the organization, keyed by the Account (1) their
+    /// orders carry. Once any is registered, every order must name one.
+    #[serde(default)]
+    pub sub_accounts: BTreeMap<String, SubAccount>,
+
+    /// Username and password the organization's SenderCompID may log on
+    /// with. Once set, every Logon must carry them.
+    #[serde(default)]
+    pub logon_credential: Option<LogonCredential>,
 }
 
 impl Organization {
@@ -116,9 +407,43 @@ impl Organization {
             sender_comp_id,
             public_key,
             registered_at: now,
+            updated_at: now,
+            symbol_permissions: BTreeMap::new(),
+            sub_accounts: BTreeMap::new(),
+            logon_credential: None,
         }
     }
 
+    /// Permission held on `symbol`, if any
+    pub fn permission_for(&self, symbol: &str) -> Option<SymbolPermission> {
+        self.symbol_permissions.get(symbol).copied()
+    }
+
+    /// Grants `permission` on `symbol`, replacing any previous grant
+    pub fn set_symbol_permission(&mut self, symbol: impl Into<String>, permission: SymbolPermission) {
+        self.symbol_permissions.insert(symbol.into(), permission);
+    }
+
+    /// Removes all access to `symbol`. Returns the permission that was held.
+    pub fn revoke_symbol_permission(&mut self, symbol: &str) -> Option<SymbolPermission> {
+        self.symbol_permissions.remove(symbol)
+    }
+
+    /// Sub-account registered under `account`, if any
+    pub fn sub_account(&self, account: &str) -> Option<&SubAccount> {
+        self.sub_accounts.get(account)
+    }
+
+    /// Adds a sub-account under `account`, replacing any previous one
+    pub fn set_sub_account(&mut self, account: impl Into<String>, sub_account: SubAccount) {
+        self.sub_accounts.insert(account.into(), sub_account);
+    }
+
+    /// Removes the sub-account under `account`. Returns the one removed.
+    pub fn remove_sub_account(&mut self, account: &str) -> Option<SubAccount> {
+        self.sub_accounts.remove(account)
+    }
+
     /// Validates the organization's data
     /// Validates the organization's data, now returning OrganizationResult
     pub fn validate(&self) -> OrganizationResult<()> {
@@ -204,6 +529,45 @@ impl Organization {
         Ok(())
     }
 
+    /// Records changes to an already registered organization, such as its
+    /// symbol permissions
+    pub async fn write_update_to_journal(&self) -> RegistrationResult<()> {
+        let mut journal = RomerJournal::new(Partition::SYSTEM, Section::ORGANIZATION)
+            .await
+            .map_err(RegistrationError::Storage)?;
+
+        let entry = JournalEntry::OrganizationUpdated(self.clone());
+        let bytes = serde_json::to_vec(&entry).map_err(|e| RegistrationError::Storage(e.to_string()))?;
+        journal.append(bytes).await.map_err(RegistrationError::Storage)?;
+        journal.sync().await.map_err(RegistrationError::Storage)?;
+
+        Ok(())
+    }
+
+    /// Current state of every registered organization, folded from the
+    /// registration journal. Deactivated organizations are left out.
+    pub async fn load_all() -> RegistrationResult<Vec<Organization>> {
+        let mut journal = RomerJournal::new(Partition::SYSTEM, Section::ORGANIZATION)
+            .await
+            .map_err(RegistrationError::Storage)?;
+
+        let mut organizations = BTreeMap::new();
+        for bytes in journal.replay_all().await.map_err(RegistrationError::Storage)? {
+            match serde_json::from_slice::<JournalEntry>(&bytes) {
+                Ok(JournalEntry::OrganizationRegistered(org))
+                | Ok(JournalEntry::OrganizationUpdated(org)) => {
+                    organizations.insert(org.id.clone(), org);
+                }
+                Ok(JournalEntry::OrganizationDeactivated(id)) => {
+                    organizations.remove(&id);
+                }
+                Err(_) => continue,
+            }
+        }
+
+        Ok(organizations.into_values().collect())
+    }
+
     pub async fn get_all_organizations(&self) -> Result<Vec<Organization>, String> {
         let mut organizations = Vec::new();
 
@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Highest protocol version this binary implements
+pub const SUPPORTED_PROTOCOL_VERSION: u32 = 1;
+
+/// Version in force from genesis until the first activation
+pub const GENESIS_PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ProtocolError {
+    #[error("Invalid protocol schedule: {0}")]
+    Invalid(String),
+
+    #[error("Protocol version {version} is active at height {height}, this binary supports up to {supported}")]
+    Unsupported { version: u32, height: u64, supported: u32 },
+}
+
+/// A protocol version and the block height it takes effect at
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Activation {
+    pub version: u32,
+    pub height: u64,
+    /// What changes, for operators reading the schedule
+    #[serde(default)]
+    pub description: String,
+}
+
+/// Heights behavior changes such as new message types or a new gas schedule
+/// take effect at. Every validator must run with the same schedule, so a
+/// change activates on all of them at the same block.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProtocolSchedule {
+    /// Upgrades in order of height
+    pub activations: Vec<Activation>,
+}
+
+/// Where the chain stands against the schedule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProtocolStatus {
+    pub height: u64,
+    pub active_version: u32,
+    pub supported_version: u32,
+    /// Next activation above `height`, if any
+    pub next: Option<Activation>,
+    /// Blocks until `next` activates
+    pub blocks_remaining: Option<u64>,
+    /// Whether this binary supports the next activation
+    pub next_supported: bool,
+}
+
+impl ProtocolSchedule {
+    /// Checks versions and heights both strictly increase past genesis
+    pub fn validate(&self) -> Result<(), ProtocolError> {
+        let mut previous: Option<&Activation> = None;
+        for activation in &self.activations {
+            let previous_version = previous.map_or(GENESIS_PROTOCOL_VERSION, |previous| previous.version);
+            if activation.version <= previous_version {
+                return Err(ProtocolError::Invalid(format!(
+                    "version {} does not follow version {}",
+                    activation.version, previous_version
+                )));
+            }
+            if let Some(previous) = previous.filter(|previous| activation.height <= previous.height) {
+                return Err(ProtocolError::Invalid(format!(
+                    "version {} activates at height {}, not after version {} at {}",
+                    activation.version, activation.height, previous.version, previous.height
+                )));
+            }
+            previous = Some(activation);
+        }
+        Ok(())
+    }
+
+    /// Version in force for the block at `height`
+    pub fn version_at(&self, height: u64) -> u32 {
+        self.activations
+            .iter()
+            .take_while(|activation| activation.height <= height)
+            .last()
+            .map_or(GENESIS_PROTOCOL_VERSION, |activation| activation.version)
+    }
+
+    /// Whether behavior introduced in `version` applies to the block at
+    /// `height`
+    pub fn is_active(&self, version: u32, height: u64) -> bool {
+        self.version_at(height) >= version
+    }
+
+    /// First activation after `height`
+    pub fn next_activation(&self, height: u64) -> Option<&Activation> {
+        self.activations.iter().find(|activation| activation.height > height)
+    }
+
+    /// Version in force at `height`, or an error if a binary supporting
+    /// versions up to `supported` cannot process that block
+    pub fn check(&self, height: u64, supported: u32) -> Result<u32, ProtocolError> {
+        let version = self.version_at(height);
+        if version > supported {
+            return Err(ProtocolError::Unsupported {
+                version,
+                height,
+                supported,
+            });
+        }
+        Ok(version)
+    }
+
+    /// The first activation within `within` blocks after `height` that a
+    /// binary supporting versions up to `supported` cannot run, for warning
+    /// operators before it takes effect
+    pub fn pending_unsupported(&self, height: u64, supported: u32, within: u64) -> Option<&Activation> {
+        self.activations.iter().find(|activation| {
+            activation.version > supported
+                && activation.height > height
+                && activation.height - height <= within
+        })
+    }
+
+    pub fn status(&self, height: u64, supported: u32) -> ProtocolStatus {
+        let next = self.next_activation(height).cloned();
+        ProtocolStatus {
+            height,
+            active_version: self.version_at(height),
+            supported_version: supported,
+            blocks_remaining: next.as_ref().map(|activation| activation.height - height),
+            next_supported: next.as_ref().map_or(true, |activation| activation.version <= supported),
+            next,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn activation(version: u32, height: u64) -> Activation {
+        Activation {
+            version,
+            height,
+            description: String::new(),
+        }
+    }
+
+    fn schedule() -> ProtocolSchedule {
+        ProtocolSchedule {
+            activations: vec![activation(2, 100), activation(3, 500)],
+        }
+    }
+
+    #[test]
+    fn test_version_at() {
+        let schedule = schedule();
+        schedule.validate().unwrap();
+        assert_eq!(schedule.version_at(0), 1);
+        assert_eq!(schedule.version_at(99), 1);
+        assert_eq!(schedule.version_at(100), 2);
+        assert_eq!(schedule.version_at(1_000), 3);
+        assert!(schedule.is_active(2, 499));
+        assert!(!schedule.is_active(3, 499));
+        assert_eq!(ProtocolSchedule::default().version_at(u64::MAX), GENESIS_PROTOCOL_VERSION);
+    }
+
+    #[test]
+    fn test_validate() {
+        let backwards = ProtocolSchedule {
+            activations: vec![activation(3, 100), activation(2, 500)],
+        };
+        assert!(backwards.validate().is_err());
+        let same_height = ProtocolSchedule {
+            activations: vec![activation(2, 100), activation(3, 100)],
+        };
+        assert!(same_height.validate().is_err());
+        let genesis = ProtocolSchedule {
+            activations: vec![activation(1, 0)],
+        };
+        assert!(genesis.validate().is_err());
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let schedule = schedule();
+        assert_eq!(schedule.check(499, 2), Ok(2));
+        assert_eq!(
+            schedule.check(500, 2),
+            Err(ProtocolError::Unsupported {
+                version: 3,
+                height: 500,
+                supported: 2
+            })
+        );
+        assert_eq!(schedule.pending_unsupported(399, 2, 100), None);
+        assert_eq!(schedule.pending_unsupported(400, 2, 100), Some(&activation(3, 500)));
+        assert_eq!(schedule.pending_unsupported(400, 3, 100), None);
+
+        let status = schedule.status(450, 2);
+        assert_eq!(status.active_version, 2);
+        assert_eq!(status.blocks_remaining, Some(50));
+        assert!(!status.next_supported);
+    }
+}
@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+
+use crate::types::address::Address;
+use crate::types::envelope::{EnvelopeError, SignedTransaction, TransactionPayload};
+use crate::utils::merkle::MerkleProof;
+
+/// Outcome of a transaction included in a block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiptStatus {
+    Success,
+    Failed(String),
+}
+
+/// What a transaction did, for external systems to reconcile against
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum ReceiptEvent {
+    Transfer { from: Address, to: Address, amount: u64 },
+    MoveCall { package: Address, module: String, function: String },
+    Admin { action: String },
+    FeePaid { payer: Address, amount: u64 },
+}
+
+/// Record of a transaction's inclusion in a block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Receipt {
+    /// Digest of the signed transaction, the leaf proven against the
+    /// block's transactions root
+    pub transaction_hash: [u8; 32],
+    pub block_height: u64,
+    /// Position of the transaction in the block
+    pub index: usize,
+    pub status: ReceiptStatus,
+    /// Gas metered while executing the transaction
+    pub gas_used: u64,
+    pub events: Vec<ReceiptEvent>,
+}
+
+impl Receipt {
+    /// Receipt of `transaction`, included at `index` of the block at
+    /// `block_height` and executed successfully
+    pub fn success(
+        transaction: &SignedTransaction,
+        block_height: u64,
+        index: usize,
+        gas_used: u64,
+    ) -> Result<Self, EnvelopeError> {
+        let unsigned = &transaction.transaction;
+        let mut events = vec![match &unsigned.payload {
+            TransactionPayload::Transfer { to, amount } => ReceiptEvent::Transfer {
+                from: unsigned.sender,
+                to: *to,
+                amount: *amount,
+            },
+            TransactionPayload::MoveCall {
+                package,
+                module,
+                function,
+                ..
+            } => ReceiptEvent::MoveCall {
+                package: *package,
+                module: module.clone(),
+                function: function.clone(),
+            },
+            TransactionPayload::Admin { action, .. } => ReceiptEvent::Admin { action: action.clone() },
+        }];
+        if unsigned.fee > 0 {
+            events.push(ReceiptEvent::FeePaid {
+                payer: unsigned.sender,
+                amount: unsigned.fee,
+            });
+        }
+        Ok(Self {
+            transaction_hash: transaction.digest()?,
+            block_height,
+            index,
+            status: ReceiptStatus::Success,
+            gas_used,
+            events,
+        })
+    }
+}
+
+/// A receipt with the proof its transaction is in the block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReceiptWithProof {
+    pub receipt: Receipt,
+    /// Transactions root of the block header the proof is against
+    pub transactions_root: [u8; 32],
+    pub proof: MerkleProof,
+}
+
+impl ReceiptWithProof {
+    /// Checks the transaction sits at the receipt's index under
+    /// `transactions_root`, which the verifier takes from a block header it
+    /// trusts
+    pub fn verify(&self, transactions_root: &[u8; 32]) -> bool {
+        self.transactions_root == *transactions_root
+            && self.proof.index == self.receipt.index
+            && self.proof.verify(transactions_root, &self.receipt.transaction_hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::envelope::{UnsignedTransaction, DEFAULT_CHAIN_ID};
+    use crate::types::keymanager::SignatureScheme;
+    use crate::utils::merkle::merkle_root;
+    use commonware_cryptography::{Ed25519, Scheme};
+
+    fn transaction(nonce: u64) -> SignedTransaction {
+        let mut signer = Ed25519::from_seed(1);
+        UnsignedTransaction {
+            chain_id: DEFAULT_CHAIN_ID.to_string(),
+            payload: TransactionPayload::Transfer {
+                to: Address::new([1u8; 32]),
+                amount: 10,
+            },
+            sender: Address::from_public_key(SignatureScheme::Ed25519, &signer.public_key()),
+            nonce,
+            fee: 2,
+            expiry: u64::MAX,
+        }
+        .sign(SignatureScheme::Ed25519, &mut signer)
+        .unwrap()
+    }
+
+    #[test]
+    fn test_receipt_proof() {
+        let transactions: Vec<_> = (0..3).map(transaction).collect();
+        let leaves: Vec<_> = transactions.iter().map(|tx| tx.digest().unwrap()).collect();
+        let root = merkle_root(&leaves);
+
+        let receipt = Receipt::success(&transactions[1], 7, 1, 0).unwrap();
+        assert_eq!(receipt.events.len(), 2);
+        let proven = ReceiptWithProof {
+            receipt,
+            transactions_root: root,
+            proof: MerkleProof::generate(&leaves, 1).unwrap(),
+        };
+        assert!(proven.verify(&root));
+        assert!(!proven.verify(&merkle_root(&leaves[..2])));
+
+        let json = serde_json::to_value(&proven).unwrap();
+        assert_eq!(json["receipt"]["status"], "success");
+        let decoded: ReceiptWithProof = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded, proven);
+
+        let mut moved = proven;
+        moved.receipt.index = 2;
+        assert!(!moved.verify(&root));
+    }
+}
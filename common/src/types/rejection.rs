@@ -0,0 +1,237 @@
+use serde::{Deserialize, Serialize};
+
+use crate::types::envelope::EnvelopeError;
+use crate::types::nonce::NonceError;
+
+/// Why an order, message or transaction was refused. The one catalogue
+/// behind FIX ExecutionReport text, JSON-RPC errors and Move abort mapping,
+/// so a client sees the same numeric code whichever way a rejection reaches
+/// it. Codes are stable: new reasons get new codes, retired ones are not
+/// reused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectReason {
+    // 1xxx: order entry
+    UnknownSymbol,
+    MarketClosed,
+    ExceedsLimit,
+    DuplicateOrder,
+    PermissionDenied,
+    FirmBlocked,
+    OutsidePriceCollar,
+    InvalidOrder,
+    PluginRejected,
+    CapacityPaused,
+    NoSession,
+    UnknownOrder,
+    Throttled,
+    // 2xxx: transactions
+    InvalidSignature,
+    Expired,
+    InvalidNonce,
+    DuplicateTransaction,
+    InsufficientBalance,
+    // 3xxx: execution
+    NoReferencePrice,
+    Aborted,
+    OutOfGas,
+    Other,
+}
+
+impl RejectReason {
+    pub const ALL: [RejectReason; 22] = [
+        Self::UnknownSymbol,
+        Self::MarketClosed,
+        Self::ExceedsLimit,
+        Self::DuplicateOrder,
+        Self::PermissionDenied,
+        Self::FirmBlocked,
+        Self::OutsidePriceCollar,
+        Self::InvalidOrder,
+        Self::PluginRejected,
+        Self::CapacityPaused,
+        Self::NoSession,
+        Self::UnknownOrder,
+        Self::Throttled,
+        Self::InvalidSignature,
+        Self::Expired,
+        Self::InvalidNonce,
+        Self::DuplicateTransaction,
+        Self::InsufficientBalance,
+        Self::NoReferencePrice,
+        Self::Aborted,
+        Self::OutOfGas,
+        Self::Other,
+    ];
+
+    pub fn code(&self) -> u16 {
+        match self {
+            Self::UnknownSymbol => 1001,
+            Self::MarketClosed => 1002,
+            Self::ExceedsLimit => 1003,
+            Self::DuplicateOrder => 1004,
+            Self::PermissionDenied => 1005,
+            Self::FirmBlocked => 1006,
+            Self::OutsidePriceCollar => 1007,
+            Self::InvalidOrder => 1008,
+            Self::PluginRejected => 1009,
+            Self::CapacityPaused => 1010,
+            Self::NoSession => 1011,
+            Self::UnknownOrder => 1012,
+            Self::Throttled => 1013,
+            Self::InvalidSignature => 2001,
+            Self::Expired => 2002,
+            Self::InvalidNonce => 2003,
+            Self::DuplicateTransaction => 2004,
+            Self::InsufficientBalance => 2005,
+            Self::NoReferencePrice => 3001,
+            Self::Aborted => 3002,
+            Self::OutOfGas => 3003,
+            Self::Other => 9999,
+        }
+    }
+
+    pub fn from_code(code: u16) -> Option<Self> {
+        Self::ALL.into_iter().find(|reason| reason.code() == code)
+    }
+
+    pub fn text(&self) -> &'static str {
+        match self {
+            Self::UnknownSymbol => "Unknown symbol",
+            Self::MarketClosed => "Market closed",
+            Self::ExceedsLimit => "Order exceeds limit",
+            Self::DuplicateOrder => "Duplicate order",
+            Self::PermissionDenied => "Permission denied",
+            Self::FirmBlocked => "Firm blocked",
+            Self::OutsidePriceCollar => "Price outside collar",
+            Self::InvalidOrder => "Invalid order",
+            Self::PluginRejected => "Rejected by pre-trade check",
+            Self::CapacityPaused => "Order acceptance paused",
+            Self::NoSession => "No active session",
+            Self::UnknownOrder => "Unknown order",
+            Self::Throttled => "Throttled, sequencer overloaded",
+            Self::InvalidSignature => "Invalid signature",
+            Self::Expired => "Expired",
+            Self::InvalidNonce => "Invalid nonce",
+            Self::DuplicateTransaction => "Duplicate transaction",
+            Self::InsufficientBalance => "Insufficient balance",
+            Self::NoReferencePrice => "No reference price",
+            Self::Aborted => "Execution aborted",
+            Self::OutOfGas => "Out of gas",
+            Self::Other => "Rejected",
+        }
+    }
+
+    /// OrdRejReason (103) closest to the reason, for ExecutionReports
+    pub fn ord_rej_reason(&self) -> u32 {
+        match self {
+            Self::UnknownSymbol | Self::PermissionDenied => 1,
+            Self::UnknownOrder => 5,
+            Self::MarketClosed | Self::CapacityPaused => 2,
+            Self::ExceedsLimit | Self::OutsidePriceCollar => 3,
+            Self::DuplicateOrder => 6,
+            Self::FirmBlocked | Self::NoSession => 0,
+            _ => 99,
+        }
+    }
+
+    /// Text sent to clients, `[code] text: detail`, which `parse` reads back
+    pub fn describe(&self, detail: &str) -> String {
+        if detail.is_empty() {
+            format!("[{}] {}", self.code(), self.text())
+        } else {
+            format!("[{}] {}: {}", self.code(), self.text(), detail)
+        }
+    }
+
+    /// The reason and detail of text produced by `describe`
+    pub fn parse(text: &str) -> Option<(Self, &str)> {
+        let (code, rest) = text.strip_prefix('[')?.split_once(']')?;
+        let reason = Self::from_code(code.parse().ok()?)?;
+        let rest = rest.strip_prefix(' ')?.strip_prefix(reason.text())?;
+        Some((reason, rest.strip_prefix(": ").unwrap_or(rest)))
+    }
+
+    /// Reason for a Move abort with `code` in `module` of the Romer
+    /// framework. Aborts of other packages, whose codes mean nothing to us,
+    /// are reported as `Aborted`.
+    pub fn from_abort(module: &str, code: u64) -> Self {
+        match (module, code) {
+            ("orders", 1..=3) => Self::InvalidOrder,
+            ("orderbook", 2 | 3) => Self::InvalidOrder,
+            ("settlement", 5 | 6) => Self::InsufficientBalance,
+            ("settlement", 4) => Self::ExceedsLimit,
+            ("settlement", _) => Self::InvalidOrder,
+            ("oracle", 1 | 2) => Self::NoReferencePrice,
+            ("bridge", 3 | 5) => Self::InvalidSignature,
+            ("bridge", 2) => Self::DuplicateTransaction,
+            ("faucet", 2 | 3) => Self::ExceedsLimit,
+            _ => Self::Aborted,
+        }
+    }
+}
+
+impl std::fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {}", self.code(), self.text())
+    }
+}
+
+impl From<&EnvelopeError> for RejectReason {
+    fn from(error: &EnvelopeError) -> Self {
+        match error {
+            EnvelopeError::SenderMismatch { .. }
+            | EnvelopeError::WrongChain { .. }
+            | EnvelopeError::InvalidSignature => Self::InvalidSignature,
+            EnvelopeError::Expired(_) => Self::Expired,
+            EnvelopeError::Encoding(_) => Self::Other,
+        }
+    }
+}
+
+impl From<&NonceError> for RejectReason {
+    fn from(_: &NonceError) -> Self {
+        Self::InvalidNonce
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_unique_and_round_trip() {
+        let mut codes: Vec<u16> = RejectReason::ALL.iter().map(RejectReason::code).collect();
+        codes.sort_unstable();
+        codes.dedup();
+        assert_eq!(codes.len(), RejectReason::ALL.len());
+        for reason in RejectReason::ALL {
+            assert_eq!(RejectReason::from_code(reason.code()), Some(reason));
+        }
+        assert_eq!(RejectReason::from_code(42), None);
+    }
+
+    #[test]
+    fn test_describe_parses_back() {
+        let text = RejectReason::MarketClosed.describe("XNYS closed until 09:30");
+        assert_eq!(text, "[1002] Market closed: XNYS closed until 09:30");
+        assert_eq!(
+            RejectReason::parse(&text),
+            Some((RejectReason::MarketClosed, "XNYS closed until 09:30"))
+        );
+        assert_eq!(
+            RejectReason::parse(&RejectReason::DuplicateOrder.describe("")),
+            Some((RejectReason::DuplicateOrder, ""))
+        );
+        assert_eq!(RejectReason::parse("Duplicate ClOrdID"), None);
+    }
+
+    #[test]
+    fn test_abort_mapping() {
+        assert_eq!(RejectReason::from_abort("settlement", 6), RejectReason::InsufficientBalance);
+        assert_eq!(RejectReason::from_abort("orders", 2), RejectReason::InvalidOrder);
+        assert_eq!(RejectReason::from_abort("orderbook", 3), RejectReason::InvalidOrder);
+        assert_eq!(RejectReason::from_abort("pool", 2), RejectReason::Aborted);
+        assert_eq!(RejectReason::from(&EnvelopeError::Expired(5)), RejectReason::Expired);
+    }
+}
@@ -0,0 +1,261 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::types::address::Address;
+use crate::types::genesis::{GenesisBundle, GenesisError};
+use crate::types::org::Organization;
+
+/// Price levels of one order book, best first, as `(price, size)`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BookLevels {
+    pub bids: Vec<(u64, u64)>,
+    pub asks: Vec<(u64, u64)>,
+}
+
+/// State once the block at `height` was sealed, kept for debugging rather
+/// than booting: the contents of a genesis bundle plus the order books.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    pub height: u64,
+    pub state: GenesisBundle,
+    /// By symbol
+    pub books: BTreeMap<String, BookLevels>,
+}
+
+impl StateSnapshot {
+    /// File of the snapshot at `height` in `directory`, zero padded so
+    /// snapshots list in height order
+    pub fn path(directory: &Path, height: u64) -> PathBuf {
+        directory.join(format!("{:020}.json", height))
+    }
+
+    /// Reads the snapshot at `height` from `directory`, checking the digest
+    /// of its state
+    pub fn read(directory: &Path, height: u64) -> Result<Self, GenesisError> {
+        let bytes = std::fs::read(Self::path(directory, height))?;
+        let snapshot: Self = serde_json::from_slice(&bytes).map_err(|e| GenesisError::Encoding(e.to_string()))?;
+        snapshot.state.verify()?;
+        Ok(snapshot)
+    }
+
+    /// Writes the snapshot into `directory`, returning its path
+    pub fn write(&self, directory: &Path) -> Result<PathBuf, GenesisError> {
+        std::fs::create_dir_all(directory)?;
+        let path = Self::path(directory, self.height);
+        let bytes = serde_json::to_vec_pretty(self).map_err(|e| GenesisError::Encoding(e.to_string()))?;
+        std::fs::write(&path, bytes)?;
+        Ok(path)
+    }
+
+    /// Heights of the snapshots in `directory`, lowest first
+    pub fn heights(directory: &Path) -> std::io::Result<Vec<u64>> {
+        let mut heights = Vec::new();
+        for entry in std::fs::read_dir(directory)? {
+            let name = entry?.file_name();
+            if let Some(height) = name.to_str().and_then(|name| name.strip_suffix(".json")).and_then(|h| h.parse().ok()) {
+                heights.push(height);
+            }
+        }
+        heights.sort_unstable();
+        Ok(heights)
+    }
+}
+
+/// A value present on either side of a diff, or both with different values
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Change<K, V> {
+    pub key: K,
+    pub before: Option<V>,
+    pub after: Option<V>,
+}
+
+/// A price level whose size differs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LevelChange {
+    pub symbol: String,
+    pub side: &'static str,
+    pub price: u64,
+    pub before: Option<u64>,
+    pub after: Option<u64>,
+}
+
+/// Everything that differs between two state snapshots
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct StateDiff {
+    pub from: u64,
+    pub to: u64,
+    pub balances: Vec<Change<Address, u64>>,
+    pub nonces: Vec<Change<Address, u64>>,
+    pub organizations: Vec<Change<String, Organization>>,
+    /// VM objects by hex key, with hex values
+    pub objects: Vec<Change<String, String>>,
+    pub books: Vec<LevelChange>,
+}
+
+/// Entries of `before` and `after` whose values differ, in key order
+fn changes<K: Ord + Clone, V: PartialEq + Clone>(before: &BTreeMap<K, V>, after: &BTreeMap<K, V>) -> Vec<Change<K, V>> {
+    let keys: BTreeSet<&K> = before.keys().chain(after.keys()).collect();
+    keys.into_iter()
+        .filter_map(|key| {
+            let (a, b) = (before.get(key), after.get(key));
+            (a != b).then(|| Change {
+                key: key.clone(),
+                before: a.cloned(),
+                after: b.cloned(),
+            })
+        })
+        .collect()
+}
+
+impl StateDiff {
+    pub fn between(from: &StateSnapshot, to: &StateSnapshot) -> Self {
+        let organizations = |snapshot: &StateSnapshot| -> BTreeMap<String, Organization> {
+            snapshot.state.organizations.iter().map(|org| (org.id.clone(), org.clone())).collect()
+        };
+        let objects = |snapshot: &StateSnapshot| -> BTreeMap<String, String> {
+            snapshot.state.objects.iter().map(|object| (object.key.clone(), object.value.clone())).collect()
+        };
+
+        let mut books = Vec::new();
+        let symbols: BTreeSet<&String> = from.books.keys().chain(to.books.keys()).collect();
+        for symbol in symbols {
+            let empty = BookLevels::default();
+            let (a, b) = (from.books.get(symbol).unwrap_or(&empty), to.books.get(symbol).unwrap_or(&empty));
+            for (side, before, after) in [("bid", &a.bids, &b.bids), ("ask", &a.asks, &b.asks)] {
+                let before: BTreeMap<u64, u64> = before.iter().copied().collect();
+                let after: BTreeMap<u64, u64> = after.iter().copied().collect();
+                books.extend(changes(&before, &after).into_iter().map(|change| LevelChange {
+                    symbol: symbol.clone(),
+                    side,
+                    price: change.key,
+                    before: change.before,
+                    after: change.after,
+                }));
+            }
+        }
+
+        Self {
+            from: from.height,
+            to: to.height,
+            balances: changes(&from.state.balances, &to.state.balances),
+            nonces: changes(&from.state.nonces, &to.state.nonces),
+            organizations: changes(&organizations(from), &organizations(to)),
+            objects: changes(&objects(from), &objects(to)),
+            books,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.balances.is_empty()
+            && self.nonces.is_empty()
+            && self.organizations.is_empty()
+            && self.objects.is_empty()
+            && self.books.is_empty()
+    }
+}
+
+fn show<V: fmt::Display>(value: &Option<V>) -> String {
+    value.as_ref().map_or_else(|| "-".to_string(), |value| value.to_string())
+}
+
+/// One line per change under a heading per kind: `+` added, `-` removed,
+/// `~` changed
+fn write_changes<K: fmt::Display, V>(
+    f: &mut fmt::Formatter<'_>,
+    heading: &str,
+    changes: &[Change<K, V>],
+    value: impl Fn(&Option<V>) -> String,
+) -> fmt::Result {
+    if changes.is_empty() {
+        return Ok(());
+    }
+    writeln!(f, "{} ({})", heading, changes.len())?;
+    for change in changes {
+        let marker = match (&change.before, &change.after) {
+            (None, _) => '+',
+            (_, None) => '-',
+            _ => '~',
+        };
+        writeln!(f, "  {} {}: {} -> {}", marker, change.key, value(&change.before), value(&change.after))?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for StateDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No differences between {} and {}", self.from, self.to);
+        }
+        writeln!(f, "Differences from {} to {}", self.from, self.to)?;
+        write_changes(f, "balances", &self.balances, show)?;
+        write_changes(f, "nonces", &self.nonces, show)?;
+        write_changes(f, "organizations", &self.organizations, |org| {
+            org.as_ref().map_or_else(|| "-".to_string(), |org| org.name.clone())
+        })?;
+        write_changes(f, "objects", &self.objects, |value| {
+            value.as_ref().map_or_else(|| "-".to_string(), |value| format!("{} bytes", value.len() / 2))
+        })?;
+        if !self.books.is_empty() {
+            writeln!(f, "books ({})", self.books.len())?;
+            for level in &self.books {
+                writeln!(
+                    f,
+                    "  {} {} {}: {} -> {}",
+                    level.symbol,
+                    level.side,
+                    level.price,
+                    show(&level.before),
+                    show(&level.after)
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::environment::ExecutionEnvironment;
+    use crate::types::genesis::GenesisObject;
+
+    fn snapshot(height: u64, balance: u64, bids: Vec<(u64, u64)>) -> StateSnapshot {
+        let mut state = GenesisBundle::new(ExecutionEnvironment::Testnet, height + 1);
+        state.balances.insert(Address::new([1u8; 32]), balance);
+        state.balances.insert(Address::new([2u8; 32]), 7);
+        state.objects = vec![GenesisObject::new(b"pool", &[height as u8])];
+        StateSnapshot {
+            height,
+            state: state.seal().unwrap(),
+            books: [("ROMER-USD".to_string(), BookLevels { bids, asks: vec![(101, 5)] })].into(),
+        }
+    }
+
+    #[test]
+    fn test_diff_between_heights() {
+        let a = snapshot(10, 500, vec![(100, 3), (99, 1)]);
+        let b = snapshot(20, 450, vec![(100, 4)]);
+        let directory = std::env::temp_dir().join(format!("romer-snapshots-{}", std::process::id()));
+        a.write(&directory).unwrap();
+        b.write(&directory).unwrap();
+        assert_eq!(StateSnapshot::heights(&directory).unwrap(), vec![10, 20]);
+        assert_eq!(StateSnapshot::read(&directory, 10).unwrap(), a);
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        let diff = StateDiff::between(&a, &b);
+        assert_eq!(diff.balances.len(), 1);
+        assert_eq!((diff.balances[0].before, diff.balances[0].after), (Some(500), Some(450)));
+        assert_eq!(diff.objects.len(), 1);
+        assert_eq!(
+            diff.books,
+            vec![
+                LevelChange { symbol: "ROMER-USD".into(), side: "bid", price: 99, before: Some(1), after: None },
+                LevelChange { symbol: "ROMER-USD".into(), side: "bid", price: 100, before: Some(3), after: Some(4) },
+            ]
+        );
+        assert!(diff.to_string().contains("ROMER-USD bid 99: 1 -> -"));
+        assert!(StateDiff::between(&a, &a).is_empty());
+    }
+}
@@ -0,0 +1,236 @@
+use commonware_cryptography::{Hasher, Sha256};
+use commonware_utils::from_hex;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::types::address::Address;
+use crate::types::keymanager::SignatureScheme;
+
+/// Account that burned tokens are sent to. No key controls it.
+pub const BURN_ADDRESS: Address = Address::ZERO;
+
+/// Denominator for basis point values
+pub const MAX_BPS: u16 = 10_000;
+
+/// Errors raised while validating the tokenomics configuration
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum TokenomicsError {
+    #[error("Treasury requires at least one signer")]
+    NoSigners,
+
+    #[error("Duplicate treasury signer: {0}")]
+    DuplicateSigner(String),
+
+    #[error("Invalid treasury threshold {threshold} for {signers} signers")]
+    InvalidThreshold { threshold: u32, signers: usize },
+
+    #[error("Invalid signer key: {0}")]
+    InvalidSignerKey(String),
+
+    #[error("Treasury signers must use Ed25519, not {0:?}")]
+    UnsupportedTreasuryScheme(SignatureScheme),
+
+    #[error("Burn share {0} exceeds {MAX_BPS} basis points")]
+    InvalidBurnShare(u16),
+
+    #[error("Initial supply {initial} exceeds max supply {max}")]
+    InitialExceedsMax { initial: u64, max: u64 },
+}
+
+/// Network-wide token economics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenomicsConfig {
+    pub supply: SupplyConfig,
+    pub addresses: AddressConfig,
+    pub treasury: TreasuryConfig,
+    #[serde(default)]
+    pub fees: FeeConfig,
+}
+
+impl TokenomicsConfig {
+    pub fn validate(&self) -> Result<(), TokenomicsError> {
+        self.supply.validate()?;
+        self.treasury.validate()?;
+        self.fees.validate()
+    }
+}
+
+/// How collected trading fees are split between burning and the treasury
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct FeeConfig {
+    /// Share of fees burned, in basis points
+    pub burn_bps: u16,
+}
+
+impl FeeConfig {
+    pub fn validate(&self) -> Result<(), TokenomicsError> {
+        if self.burn_bps > MAX_BPS {
+            return Err(TokenomicsError::InvalidBurnShare(self.burn_bps));
+        }
+        Ok(())
+    }
+
+    /// Splits `fees` into `(burned, to_treasury)`. Rounding favours the treasury.
+    pub fn split(&self, fees: u64) -> (u64, u64) {
+        let burned = (fees as u128 * self.burn_bps.min(MAX_BPS) as u128 / MAX_BPS as u128) as u64;
+        (burned, fees - burned)
+    }
+}
+
+/// Token supply parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupplyConfig {
+    /// Amount minted to the treasury in the genesis block
+    pub initial_supply: u64,
+    /// Hard cap on total supply, if any
+    #[serde(default)]
+    pub max_supply: Option<u64>,
+}
+
+impl SupplyConfig {
+    pub fn validate(&self) -> Result<(), TokenomicsError> {
+        match self.max_supply {
+            Some(max) if self.initial_supply > max => Err(TokenomicsError::InitialExceedsMax {
+                initial: self.initial_supply,
+                max,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Well-known addresses. The treasury is deliberately absent: it is
+/// controlled by the signer set in [`TreasuryConfig`] rather than a single key.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AddressConfig {
+    /// Address receiving protocol fees before distribution
+    pub fee_collector: Option<Address>,
+}
+
+/// Governance of treasury spends. A spend must be signed by `threshold` of
+/// `signers` and then wait `timelock_secs` before executing. Genesis shares
+/// the `romer::treasury` object governed by these parameters.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreasuryConfig {
+    /// Signature scheme used by every signer. The treasury module verifies
+    /// Ed25519 signatures, so no other scheme is accepted.
+    pub scheme: SignatureScheme,
+    /// Hex-encoded public keys of the signers
+    pub signers: Vec<String>,
+    /// Number of approvals required to execute a spend
+    pub threshold: u32,
+    /// Delay between reaching quorum and execution
+    pub timelock_secs: u64,
+}
+
+impl TreasuryConfig {
+    /// Ensures the signer set is Ed25519, non-empty, unique and can reach
+    /// quorum
+    pub fn validate(&self) -> Result<(), TokenomicsError> {
+        if self.scheme != SignatureScheme::Ed25519 {
+            return Err(TokenomicsError::UnsupportedTreasuryScheme(self.scheme));
+        }
+        if self.signers.is_empty() {
+            return Err(TokenomicsError::NoSigners);
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        for signer in &self.signers {
+            if from_hex(signer).is_none() {
+                return Err(TokenomicsError::InvalidSignerKey(signer.clone()));
+            }
+            if !seen.insert(signer.to_lowercase()) {
+                return Err(TokenomicsError::DuplicateSigner(signer.clone()));
+            }
+        }
+
+        if self.threshold == 0 || self.threshold as usize > self.signers.len() {
+            return Err(TokenomicsError::InvalidThreshold {
+                threshold: self.threshold,
+                signers: self.signers.len(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Decoded signer public keys, skipping any that are not valid hex
+    pub fn signer_keys(&self) -> Vec<Vec<u8>> {
+        self.signers
+            .iter()
+            .filter_map(|s| from_hex(s))
+            .collect()
+    }
+
+    /// Account that holds treasury funds, derived from the governance
+    /// parameters so that changing the signer set yields a new account
+    pub fn account(&self) -> Address {
+        let mut hasher = Sha256::new();
+        hasher.update(b"treasury");
+        hasher.update(&self.threshold.to_be_bytes());
+        hasher.update(&self.timelock_secs.to_be_bytes());
+        for key in self.signer_keys() {
+            hasher.update(&key);
+        }
+
+        let mut digest = [0u8; 32];
+        digest.copy_from_slice(&hasher.finalize());
+        Address::from_digest(&digest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn treasury(signers: &[&str], threshold: u32) -> TreasuryConfig {
+        TreasuryConfig {
+            scheme: SignatureScheme::Ed25519,
+            signers: signers.iter().map(|s| s.to_string()).collect(),
+            threshold,
+            timelock_secs: 86_400,
+        }
+    }
+
+    #[test]
+    fn test_valid_treasury() {
+        assert!(treasury(&["aa", "bb", "cc"], 2).validate().is_ok());
+    }
+
+    #[test]
+    fn test_invalid_treasury() {
+        assert_eq!(treasury(&[], 1).validate(), Err(TokenomicsError::NoSigners));
+        assert_eq!(
+            treasury(&["aa", "AA"], 1).validate(),
+            Err(TokenomicsError::DuplicateSigner("AA".into()))
+        );
+        assert!(matches!(
+            treasury(&["aa", "bb"], 3).validate(),
+            Err(TokenomicsError::InvalidThreshold { .. })
+        ));
+        assert!(matches!(
+            treasury(&["zz"], 1).validate(),
+            Err(TokenomicsError::InvalidSignerKey(_))
+        ));
+        let bls = TreasuryConfig {
+            scheme: SignatureScheme::Bls12381,
+            ..treasury(&["aa"], 1)
+        };
+        assert_eq!(
+            bls.validate(),
+            Err(TokenomicsError::UnsupportedTreasuryScheme(SignatureScheme::Bls12381))
+        );
+    }
+
+    #[test]
+    fn test_fee_split() {
+        let fees = FeeConfig { burn_bps: 2_500 };
+        assert_eq!(fees.split(1_000), (250, 750));
+        assert_eq!(fees.split(3), (0, 3));
+        assert_eq!(FeeConfig { burn_bps: MAX_BPS }.split(7), (7, 0));
+        assert_eq!(
+            FeeConfig { burn_bps: 10_001 }.validate(),
+            Err(TokenomicsError::InvalidBurnShare(10_001))
+        );
+    }
+}
@@ -0,0 +1,342 @@
+use commonware_cryptography::{Ed25519, PublicKey, Scheme, Signature};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+use crate::types::address::Address;
+use crate::types::bridge::{bcs_bytes, bcs_length};
+use crate::types::envelope::TransactionPayload;
+use crate::types::keymanager::SignatureScheme;
+use crate::types::tokenomics::TreasuryConfig;
+
+/// Prefix of the message signers sign to authorize a spend. Must match
+/// `SPEND_DOMAIN` in the `romer::treasury` Move module.
+pub const SPEND_DOMAIN: &[u8] = b"ROMER_TREASURY_SPEND";
+
+/// Prefix of the message signers sign to cancel a spend. Must match
+/// `CANCEL_DOMAIN` in the `romer::treasury` Move module.
+pub const CANCEL_DOMAIN: &[u8] = b"ROMER_TREASURY_CANCEL";
+
+/// Package the `romer::treasury` module is published in
+pub const TREASURY_PACKAGE: Address = {
+    let mut address = [0u8; 32];
+    address[31] = 0x10;
+    Address::new(address)
+};
+
+/// Coin the network treasury holds
+pub const TREASURY_COIN: &str = "0x10::coins::COINS";
+
+/// The shared clock object spends are timelocked against
+const CLOCK_OBJECT: Address = {
+    let mut address = [0u8; 32];
+    address[31] = 0x6;
+    Address::new(address)
+};
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum TreasuryError {
+    #[error("Quorum not reached: {approvals} of {threshold} approvals")]
+    QuorumNotReached { approvals: usize, threshold: u32 },
+
+    #[error("Treasury signatures must be Ed25519")]
+    UnsupportedScheme,
+
+    #[error("Approval from unknown signer")]
+    UnknownSigner,
+
+    #[error("Invalid approval signature")]
+    InvalidSignature,
+}
+
+/// A request to move funds out of the treasury, signed for the proposal id
+/// the treasury will assign it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpendProposal {
+    pub id: u64,
+    pub recipient: Address,
+    pub amount: u64,
+    pub memo: String,
+}
+
+impl SpendProposal {
+    /// The message each signer signs to approve this proposal, as the Move
+    /// module rebuilds it: the domain, then the BCS encoding of the id,
+    /// recipient, amount and memo
+    pub fn message(&self) -> Vec<u8> {
+        let mut message = SPEND_DOMAIN.to_vec();
+        message.extend_from_slice(&self.id.to_le_bytes());
+        message.extend_from_slice(self.recipient.as_bytes());
+        message.extend_from_slice(&self.amount.to_le_bytes());
+        bcs_bytes(&mut message, self.memo.as_bytes());
+        message
+    }
+
+    /// The `propose_spend` call recording this proposal on `treasury`,
+    /// once `approvals` reach the quorum of `config`
+    pub fn propose_call(
+        &self,
+        config: &TreasuryConfig,
+        treasury: Address,
+        approvals: &[Approval],
+    ) -> Result<TransactionPayload, TreasuryError> {
+        let approved = authorize_spend(config, self, approvals)?;
+        let mut memo = Vec::new();
+        bcs_bytes(&mut memo, self.memo.as_bytes());
+        let (signers, signatures) = signer_arguments(&approved);
+        Ok(call(
+            "propose_spend",
+            vec![
+                treasury.to_vec(),
+                self.recipient.to_vec(),
+                self.amount.to_le_bytes().to_vec(),
+                memo,
+                signers,
+                signatures,
+                CLOCK_OBJECT.to_vec(),
+            ],
+        ))
+    }
+}
+
+/// The message each signer signs to cancel proposal `id`
+pub fn cancel_message(id: u64) -> Vec<u8> {
+    let mut message = CANCEL_DOMAIN.to_vec();
+    message.extend_from_slice(&id.to_le_bytes());
+    message
+}
+
+/// A single signer's approval of a proposal
+#[derive(Debug, Clone)]
+pub struct Approval {
+    pub signer: PublicKey,
+    pub signature: Signature,
+}
+
+impl Approval {
+    /// Signs the raw `message`, without a namespace, so the Move module's
+    /// `ed25519_verify` can check it
+    pub fn sign(signer: &mut Ed25519, message: &[u8]) -> Self {
+        Self {
+            signer: signer.public_key(),
+            signature: signer.sign(None, message),
+        }
+    }
+}
+
+/// Checks that `approvals` form a valid quorum for `proposal`, returning the
+/// valid ones by signer index, in the ascending order the Move module takes
+/// them. The timelock is left to the module, which knows when the proposal
+/// was recorded.
+pub fn authorize_spend(
+    config: &TreasuryConfig,
+    proposal: &SpendProposal,
+    approvals: &[Approval],
+) -> Result<BTreeMap<u64, Approval>, TreasuryError> {
+    authorize(config, &proposal.message(), approvals)
+}
+
+/// The `create` call sharing the treasury governed by `config`, sent by
+/// the genesis sender after the framework is published
+pub fn create_call(config: &TreasuryConfig) -> TransactionPayload {
+    let keys = config.signer_keys();
+    let mut signers = Vec::new();
+    bcs_length(&mut signers, keys.len());
+    for key in &keys {
+        bcs_bytes(&mut signers, key);
+    }
+    call(
+        "create",
+        vec![
+            signers,
+            u64::from(config.threshold).to_le_bytes().to_vec(),
+            config.timelock_secs.saturating_mul(1_000).to_le_bytes().to_vec(),
+        ],
+    )
+}
+
+/// The `execute` call paying out proposal `id` once its timelock passed
+pub fn execute_call(treasury: Address, id: u64) -> TransactionPayload {
+    call(
+        "execute",
+        vec![treasury.to_vec(), id.to_le_bytes().to_vec(), CLOCK_OBJECT.to_vec()],
+    )
+}
+
+/// The `cancel` call for proposal `id`, once `approvals` of its
+/// cancellation reach the quorum of `config`
+pub fn cancel_call(
+    config: &TreasuryConfig,
+    treasury: Address,
+    id: u64,
+    approvals: &[Approval],
+) -> Result<TransactionPayload, TreasuryError> {
+    let approved = authorize(config, &cancel_message(id), approvals)?;
+    let (signers, signatures) = signer_arguments(&approved);
+    Ok(call(
+        "cancel",
+        vec![treasury.to_vec(), id.to_le_bytes().to_vec(), signers, signatures],
+    ))
+}
+
+fn authorize(
+    config: &TreasuryConfig,
+    message: &[u8],
+    approvals: &[Approval],
+) -> Result<BTreeMap<u64, Approval>, TreasuryError> {
+    if config.scheme != SignatureScheme::Ed25519 {
+        return Err(TreasuryError::UnsupportedScheme);
+    }
+    let signers = config.signer_keys();
+
+    let mut approved = BTreeMap::new();
+    for approval in approvals {
+        let index = signers
+            .iter()
+            .position(|signer| signer.as_slice() == approval.signer.as_ref())
+            .ok_or(TreasuryError::UnknownSigner)?;
+        if !Ed25519::verify(None, message, &approval.signer, &approval.signature) {
+            return Err(TreasuryError::InvalidSignature);
+        }
+        approved.insert(index as u64, approval.clone());
+    }
+
+    if approved.len() < config.threshold as usize {
+        return Err(TreasuryError::QuorumNotReached {
+            approvals: approved.len(),
+            threshold: config.threshold,
+        });
+    }
+    Ok(approved)
+}
+
+/// BCS `vector<u64>` of signer indices and `vector<vector<u8>>` of their
+/// signatures
+fn signer_arguments(approved: &BTreeMap<u64, Approval>) -> (Vec<u8>, Vec<u8>) {
+    let mut signers = Vec::new();
+    let mut signatures = Vec::new();
+    bcs_length(&mut signers, approved.len());
+    bcs_length(&mut signatures, approved.len());
+    for (index, approval) in approved {
+        signers.extend_from_slice(&index.to_le_bytes());
+        bcs_bytes(&mut signatures, &approval.signature);
+    }
+    (signers, signatures)
+}
+
+fn call(function: &str, arguments: Vec<Vec<u8>>) -> TransactionPayload {
+    TransactionPayload::MoveCall {
+        package: TREASURY_PACKAGE,
+        module: "treasury".to_string(),
+        function: function.to_string(),
+        type_arguments: vec![TREASURY_COIN.to_string()],
+        arguments,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use commonware_utils::hex;
+
+    fn setup(threshold: u32) -> (Vec<Ed25519>, TreasuryConfig, SpendProposal) {
+        let signers: Vec<Ed25519> = (0..3).map(Ed25519::from_seed).collect();
+        let config = TreasuryConfig {
+            scheme: SignatureScheme::Ed25519,
+            signers: signers.iter().map(|s| hex(&s.public_key())).collect(),
+            threshold,
+            timelock_secs: 100,
+        };
+        let mut recipient = [0u8; 32];
+        recipient[31] = 0xd;
+        let proposal = SpendProposal {
+            id: 0,
+            recipient: Address::new(recipient),
+            amount: 400,
+            memo: "grant".into(),
+        };
+        (signers, config, proposal)
+    }
+
+    fn approve(signer: &mut Ed25519, proposal: &SpendProposal) -> Approval {
+        Approval::sign(signer, &proposal.message())
+    }
+
+    #[test]
+    fn test_messages_match_move() {
+        // The messages `romer::treasury_tests` checks its signatures over
+        let (_, _, proposal) = setup(2);
+        assert_eq!(
+            hex(&proposal.message()),
+            "524f4d45525f54524541535552595f5350454e4400000000000000000000000000000000000000000000000000\
+             00000000000000000000000000000d9001000000000000056772616e74"
+        );
+        assert_eq!(hex(&cancel_message(0)), "524f4d45525f54524541535552595f43414e43454c0000000000000000");
+    }
+
+    #[test]
+    fn test_propose_call_orders_signers() {
+        let (mut signers, config, proposal) = setup(2);
+        let approvals = vec![approve(&mut signers[2], &proposal), approve(&mut signers[0], &proposal)];
+        let approved = authorize_spend(&config, &proposal, &approvals).unwrap();
+        assert_eq!(approved.keys().copied().collect::<Vec<_>>(), vec![0, 2]);
+
+        let TransactionPayload::MoveCall { function, arguments, .. } =
+            proposal.propose_call(&config, Address::new([5u8; 32]), &approvals).unwrap()
+        else {
+            panic!("not a Move call");
+        };
+        assert_eq!(function, "propose_spend");
+        let mut indices = vec![2];
+        indices.extend_from_slice(&0u64.to_le_bytes());
+        indices.extend_from_slice(&2u64.to_le_bytes());
+        assert_eq!(arguments[4], indices);
+        assert_eq!(arguments[5].len(), 1 + 2 * (1 + 64));
+    }
+
+    #[test]
+    fn test_create_call_arguments() {
+        let (_, config, _) = setup(2);
+        let TransactionPayload::MoveCall { function, type_arguments, arguments, .. } = create_call(&config) else {
+            panic!("not a Move call");
+        };
+        assert_eq!(function, "create");
+        assert_eq!(type_arguments, vec![TREASURY_COIN.to_string()]);
+        assert_eq!(arguments[0].len(), 1 + 3 * (1 + 32));
+        assert_eq!(arguments[1], 2u64.to_le_bytes());
+        assert_eq!(arguments[2], 100_000u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_duplicate_approvals_do_not_count() {
+        let (mut signers, config, proposal) = setup(2);
+        let approval = approve(&mut signers[0], &proposal);
+
+        assert_eq!(
+            authorize_spend(&config, &proposal, &[approval.clone(), approval]).unwrap_err(),
+            TreasuryError::QuorumNotReached { approvals: 1, threshold: 2 }
+        );
+    }
+
+    #[test]
+    fn test_rejects_outsiders_and_tampering() {
+        let (mut signers, config, proposal) = setup(1);
+        let mut outsider = Ed25519::from_seed(42);
+        assert_eq!(
+            authorize_spend(&config, &proposal, &[approve(&mut outsider, &proposal)]).unwrap_err(),
+            TreasuryError::UnknownSigner
+        );
+
+        let approval = approve(&mut signers[0], &proposal);
+        let tampered = SpendProposal { amount: 401, ..proposal };
+        assert_eq!(
+            authorize_spend(&config, &tampered, &[approval.clone()]).unwrap_err(),
+            TreasuryError::InvalidSignature
+        );
+
+        // A spend approval does not cancel
+        assert_eq!(
+            cancel_call(&config, Address::new([5u8; 32]), 0, &[approval]).unwrap_err(),
+            TreasuryError::InvalidSignature
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use chrono::{DateTime, Utc};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Source of time for sessions, batching, block timestamps and the VM.
+/// Components take a `SharedClock` instead of reading the system clock so
+/// that simulations and tests can control time.
+pub trait Clock: Send + Sync {
+    /// Wall-clock time
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Monotonic time, for measuring intervals
+    fn instant(&self) -> Instant;
+
+    /// Wall-clock time as unix seconds
+    fn unix_secs(&self) -> u64 {
+        self.now().timestamp().max(0) as u64
+    }
+
+    /// Wall-clock time as unix milliseconds
+    fn unix_millis(&self) -> u64 {
+        self.now().timestamp_millis().max(0) as u64
+    }
+}
+
+pub type SharedClock = Arc<dyn Clock>;
+
+/// The operating system clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    fn instant(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Shared handle to the system clock
+pub fn system_clock() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+/// Clock that only moves when told to, for deterministic simulation and tests
+#[derive(Debug)]
+pub struct ManualClock {
+    state: Mutex<(DateTime<Utc>, Instant)>,
+}
+
+impl ManualClock {
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            state: Mutex::new((start, Instant::now())),
+        }
+    }
+
+    /// Moves both wall-clock and monotonic time forward
+    pub fn advance(&self, by: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.0 += chrono::Duration::from_std(by).unwrap_or(chrono::Duration::zero());
+        state.1 += by;
+    }
+
+    /// Sets wall-clock time, leaving monotonic time untouched
+    pub fn set(&self, now: DateTime<Utc>) {
+        self.state.lock().unwrap().0 = now;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.state.lock().unwrap().0
+    }
+
+    fn instant(&self) -> Instant {
+        self.state.lock().unwrap().1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_manual_clock() {
+        let start = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        let clock = ManualClock::new(start);
+        let before = clock.instant();
+
+        clock.advance(Duration::from_millis(1500));
+        assert_eq!(clock.unix_millis(), 1_700_000_001_500);
+        assert_eq!(clock.unix_secs(), 1_700_000_001);
+        assert_eq!(clock.instant() - before, Duration::from_millis(1500));
+
+        clock.set(start);
+        assert_eq!(clock.now(), start);
+        assert_eq!(clock.instant() - before, Duration::from_millis(1500));
+    }
+}
@@ -1,4 +1,5 @@
 use std::env;
+use std::path::Path;
 use std::process::Command;
 use anyhow::{Context, Result};
 use tracing::info;
@@ -164,6 +165,216 @@ impl HardwareDetector {
     }
 }
 
+/// Resources of the machine a validator runs on, as far as they could be
+/// detected. Undetected values are `None`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HardwareProfile {
+    pub cpu_model: Option<String>,
+    pub cpu_cores: Option<usize>,
+    pub memory_bytes: Option<u64>,
+    /// Free space on the filesystem holding the storage directory
+    pub disk_available_bytes: Option<u64>,
+    pub nics: Vec<NetworkInterface>,
+}
+
+/// A physical network interface and its negotiated link speed
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkInterface {
+    pub name: String,
+    pub speed_mbps: Option<u64>,
+}
+
+/// Minimum resources a validator must have
+#[derive(Debug, Clone, PartialEq)]
+pub struct HardwareRequirements {
+    pub physical: bool,
+    pub min_cpu_cores: usize,
+    pub min_memory_bytes: u64,
+    pub min_disk_available_bytes: u64,
+    pub min_nic_speed_mbps: u64,
+}
+
+impl Default for HardwareRequirements {
+    fn default() -> Self {
+        const GIB: u64 = 1024 * 1024 * 1024;
+        Self {
+            physical: true,
+            min_cpu_cores: 8,
+            min_memory_bytes: 32 * GIB,
+            min_disk_available_bytes: 1024 * GIB,
+            min_nic_speed_mbps: 1000,
+        }
+    }
+}
+
+/// One row of a requirement comparison
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequirementCheck {
+    pub requirement: &'static str,
+    pub required: String,
+    pub detected: String,
+    pub passed: bool,
+}
+
+impl HardwareRequirements {
+    /// Compares a machine against every requirement. Anything that could
+    /// not be detected fails.
+    pub fn check(&self, virtualization: &VirtualizationType, profile: &HardwareProfile) -> Vec<RequirementCheck> {
+        const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+        let unknown = || "unknown".to_string();
+        let fastest_nic = profile.nics.iter().filter_map(|nic| nic.speed_mbps).max();
+        vec![
+            RequirementCheck {
+                requirement: "Physical hardware",
+                required: if self.physical { "yes" } else { "no" }.to_string(),
+                detected: match virtualization {
+                    VirtualizationType::Physical => "physical".to_string(),
+                    VirtualizationType::Virtual(platform) => format!("virtual ({})", platform),
+                },
+                passed: !self.physical || *virtualization == VirtualizationType::Physical,
+            },
+            RequirementCheck {
+                requirement: "CPU cores",
+                required: format!(">= {}", self.min_cpu_cores),
+                detected: profile.cpu_cores.map_or_else(unknown, |cores| cores.to_string()),
+                passed: profile.cpu_cores.is_some_and(|cores| cores >= self.min_cpu_cores),
+            },
+            RequirementCheck {
+                requirement: "Memory",
+                required: format!(">= {:.0} GiB", self.min_memory_bytes as f64 / GIB),
+                detected: profile
+                    .memory_bytes
+                    .map_or_else(unknown, |bytes| format!("{:.1} GiB", bytes as f64 / GIB)),
+                passed: profile.memory_bytes.is_some_and(|bytes| bytes >= self.min_memory_bytes),
+            },
+            RequirementCheck {
+                requirement: "Free disk",
+                required: format!(">= {:.0} GiB", self.min_disk_available_bytes as f64 / GIB),
+                detected: profile
+                    .disk_available_bytes
+                    .map_or_else(unknown, |bytes| format!("{:.1} GiB", bytes as f64 / GIB)),
+                passed: profile
+                    .disk_available_bytes
+                    .is_some_and(|bytes| bytes >= self.min_disk_available_bytes),
+            },
+            RequirementCheck {
+                requirement: "Network link",
+                required: format!(">= {} Mbps", self.min_nic_speed_mbps),
+                detected: fastest_nic.map_or_else(unknown, |speed| format!("{} Mbps", speed)),
+                passed: fastest_nic.is_some_and(|speed| speed >= self.min_nic_speed_mbps),
+            },
+        ]
+    }
+}
+
+impl HardwareDetector {
+    /// Every sign of virtualization found, whether or not
+    /// [`HardwareDetector::detect_virtualization`] acted on it
+    pub fn virtualization_evidence() -> Vec<String> {
+        let mut evidence = Vec::new();
+        if let Ok(output) = Command::new("systemd-detect-virt").output() {
+            let detected = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            if !detected.is_empty() && detected != "none" {
+                evidence.push(format!("systemd-detect-virt reports {}", detected));
+            }
+        }
+        if let Ok(cpuinfo) = std::fs::read_to_string("/proc/cpuinfo") {
+            if cpuinfo.lines().any(|line| line.starts_with("flags") && line.split_whitespace().any(|f| f == "hypervisor")) {
+                evidence.push("CPU advertises the hypervisor flag".to_string());
+            }
+        }
+        for path in ["/sys/class/dmi/id/product_name", "/sys/class/dmi/id/sys_vendor"] {
+            if let Ok(value) = std::fs::read_to_string(path) {
+                let value = value.trim();
+                let lower = value.to_lowercase();
+                if ["vmware", "virtual", "kvm", "qemu", "xen", "amazon ec2", "google compute"]
+                    .iter()
+                    .any(|marker| lower.contains(marker))
+                {
+                    evidence.push(format!("{} is {}", path, value));
+                }
+            }
+        }
+        if Path::new("/.dockerenv").exists() {
+            evidence.push("/.dockerenv exists".to_string());
+        }
+        for var in ["CONTAINER", "KUBERNETES_SERVICE_HOST", "VIRTUAL_ENV"] {
+            if env::var(var).is_ok() {
+                evidence.push(format!("{} is set", var));
+            }
+        }
+        evidence
+    }
+
+    /// Detects CPU, memory, free disk under `storage_dir` and network
+    /// interfaces. Detection reads `/proc` and `/sys`, so only Linux is
+    /// fully supported.
+    pub fn profile(storage_dir: &Path) -> HardwareProfile {
+        let cpuinfo = std::fs::read_to_string("/proc/cpuinfo").unwrap_or_default();
+        let cpu_model = cpuinfo
+            .lines()
+            .find(|line| line.starts_with("model name"))
+            .and_then(|line| line.split_once(':'))
+            .map(|(_, model)| model.trim().to_string());
+        let cpu_cores = std::thread::available_parallelism().ok().map(|cores| cores.get());
+
+        let memory_bytes = std::fs::read_to_string("/proc/meminfo").ok().and_then(|meminfo| {
+            meminfo
+                .lines()
+                .find(|line| line.starts_with("MemTotal:"))
+                .and_then(|line| line.split_whitespace().nth(1))
+                .and_then(|kib| kib.parse::<u64>().ok())
+                .map(|kib| kib * 1024)
+        });
+
+        let mut nics = Vec::new();
+        if let Ok(entries) = std::fs::read_dir("/sys/class/net") {
+            for entry in entries.flatten() {
+                // Virtual interfaces (loopback, bridges, tunnels) have no device
+                if !entry.path().join("device").exists() {
+                    continue;
+                }
+                let speed_mbps = std::fs::read_to_string(entry.path().join("speed"))
+                    .ok()
+                    .and_then(|speed| speed.trim().parse::<i64>().ok())
+                    .filter(|speed| *speed > 0)
+                    .map(|speed| speed as u64);
+                nics.push(NetworkInterface {
+                    name: entry.file_name().to_string_lossy().to_string(),
+                    speed_mbps,
+                });
+            }
+        }
+        nics.sort_by(|a, b| a.name.cmp(&b.name));
+
+        HardwareProfile {
+            cpu_model,
+            cpu_cores,
+            memory_bytes,
+            disk_available_bytes: available_space(storage_dir),
+            nics,
+        }
+    }
+}
+
+/// Free space on the filesystem holding `path`, or its nearest existing
+/// ancestor
+#[cfg(unix)]
+fn available_space(path: &Path) -> Option<u64> {
+    let existing = path.ancestors().find(|ancestor| ancestor.exists())?;
+    let c_path = std::ffi::CString::new(existing.as_os_str().to_string_lossy().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+fn available_space(_path: &Path) -> Option<u64> {
+    None
+}
+
 /// Unit tests for hardware detection
 #[cfg(test)]
 mod tests {
@@ -184,6 +395,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_requirements_check() {
+        let requirements = HardwareRequirements::default();
+        let profile = HardwareProfile {
+            cpu_model: Some("Test CPU".to_string()),
+            cpu_cores: Some(16),
+            memory_bytes: Some(64 * 1024 * 1024 * 1024),
+            disk_available_bytes: Some(2 * 1024 * 1024 * 1024 * 1024),
+            nics: vec![NetworkInterface {
+                name: "eth0".to_string(),
+                speed_mbps: Some(10_000),
+            }],
+        };
+        let checks = requirements.check(&VirtualizationType::Physical, &profile);
+        assert!(checks.iter().all(|check| check.passed));
+
+        let checks = requirements.check(&VirtualizationType::Virtual("kvm".to_string()), &HardwareProfile::default());
+        assert!(checks.iter().all(|check| !check.passed));
+        assert_eq!(checks[1].detected, "unknown");
+    }
+
+    #[test]
+    fn test_profile_detects_disk() {
+        let profile = HardwareDetector::profile(&std::env::temp_dir().join("romer-missing-dir"));
+        assert!(profile.disk_available_bytes.is_some());
+        assert!(profile.cpu_cores.is_some());
+    }
+
     #[test]
     fn test_virtualization_detection() {
         // We expect this to complete without panicking
@@ -0,0 +1,364 @@
+//! Logging initializer shared by the validator and the sequencer.
+//!
+//! Applies a [`LoggingConfig`]: the level directives, the output format and,
+//! optionally, a log file rotated by size. The returned [`LogHandle`] changes
+//! the level directives of the running process, as a whole or for one
+//! module at a time.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use thiserror::Error;
+use tracing::Subscriber;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, EnvFilter, Layer, Registry};
+
+#[derive(Error, Debug)]
+pub enum LoggingError {
+    #[error("Invalid log level {level}: {reason}")]
+    Level { level: String, reason: String },
+
+    #[error("Failed to open log file {path}: {source}")]
+    File { path: PathBuf, source: io::Error },
+
+    #[error("Invalid logging configuration: {0}")]
+    Invalid(String),
+
+    #[error("Failed to install logger: {0}")]
+    Init(String),
+}
+
+/// How log lines are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human readable, one event per line with its spans
+    #[default]
+    Plain,
+    /// Human readable without span context
+    Compact,
+    /// One JSON object per line, for log shippers
+    Json,
+}
+
+/// When the log file is rotated and how many rotated files are kept
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogRotation {
+    /// Size at which the log file is rotated
+    pub max_bytes: u64,
+    /// Rotated files kept as `<file>.1` (newest) to `<file>.<keep>`
+    pub keep: usize,
+}
+
+impl Default for LogRotation {
+    fn default() -> Self {
+        Self {
+            max_bytes: 100 * 1024 * 1024,
+            keep: 5,
+        }
+    }
+}
+
+/// Logging settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    /// Level directives, e.g. `info` or `info,commonware_p2p=warn`
+    pub level: String,
+    pub format: LogFormat,
+    /// File logs are also written to, none unless set
+    pub file: Option<PathBuf>,
+    pub rotation: LogRotation,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            format: LogFormat::default(),
+            file: None,
+            rotation: LogRotation::default(),
+        }
+    }
+}
+
+impl LoggingConfig {
+    pub fn validate(&self) -> Result<(), LoggingError> {
+        parse_level(&self.level)?;
+        if self.rotation.max_bytes == 0 {
+            return Err(LoggingError::Invalid("rotation.max_bytes must be nonzero".into()));
+        }
+        Ok(())
+    }
+}
+
+fn parse_level(level: &str) -> Result<EnvFilter, LoggingError> {
+    EnvFilter::try_new(level).map_err(|e| LoggingError::Level {
+        level: level.to_string(),
+        reason: e.to_string(),
+    })
+}
+
+/// Short names of the workspace crates accepted as the first segment of a
+/// directive's target, so `sequencer::fix=debug` means `romer_sequencer::fix`
+const CRATE_ALIASES: &[(&str, &str)] = &[
+    ("sequencer", "romer_sequencer"),
+    ("validator", "romer_validator"),
+    ("common", "romer_common"),
+    ("client", "romer_client"),
+    ("vm", "romer_vm"),
+];
+
+/// Expands a crate alias at the start of a `target=level` directive
+pub fn expand_directive(directive: &str) -> String {
+    let directive = directive.trim();
+    for (alias, name) in CRATE_ALIASES {
+        if let Some(rest) = directive.strip_prefix(alias) {
+            if rest.starts_with("::") || rest.starts_with('=') || rest.starts_with('[') {
+                return format!("{}{}", name, rest);
+            }
+        }
+    }
+    directive.to_string()
+}
+
+/// Target a directive applies to, `None` for a bare default level
+fn directive_target(directive: &str) -> Option<&str> {
+    match directive.split_once('=') {
+        Some((target, _)) => Some(target),
+        None if directive.parse::<tracing::level_filters::LevelFilter>().is_ok() => None,
+        None => Some(directive),
+    }
+}
+
+/// Changes the level directives of the installed logger
+pub struct LogHandle {
+    reload: reload::Handle<EnvFilter, Registry>,
+    level: Mutex<String>,
+    /// Directives the logger was installed with, restored by `reset`
+    initial: String,
+}
+
+impl LogHandle {
+    /// Current level directives
+    pub fn level(&self) -> String {
+        self.level.lock().unwrap().clone()
+    }
+
+    /// Replaces the level directives, leaving them unchanged if invalid
+    pub fn set_level(&self, level: &str) -> Result<(), LoggingError> {
+        let level = level.split(',').map(expand_directive).collect::<Vec<_>>().join(",");
+        let filter = parse_level(&level)?;
+        self.reload
+            .reload(filter)
+            .map_err(|e| LoggingError::Init(e.to_string()))?;
+        *self.level.lock().unwrap() = level;
+        Ok(())
+    }
+
+    /// Sets the level of one module, e.g. `sequencer::fix=debug`, keeping
+    /// every other directive. A bare level replaces the default level.
+    /// Returns the resulting directives.
+    pub fn set_directive(&self, directive: &str) -> Result<String, LoggingError> {
+        let level = merge_directive(&self.level(), directive);
+        self.set_level(&level)?;
+        Ok(level)
+    }
+
+    /// Restores the directives the logger was installed with
+    pub fn reset(&self) -> Result<String, LoggingError> {
+        self.set_level(&self.initial)?;
+        Ok(self.initial.clone())
+    }
+}
+
+/// Replaces the directive of `directive`'s target in `level`, or appends it
+fn merge_directive(level: &str, directive: &str) -> String {
+    let directive = expand_directive(directive);
+    let target = directive_target(&directive);
+    let mut directives: Vec<String> = level
+        .split(',')
+        .map(str::trim)
+        .filter(|existing| !existing.is_empty() && directive_target(existing) != target)
+        .map(str::to_string)
+        .collect();
+    // The default level goes first, as it is usually written
+    if target.is_none() {
+        directives.insert(0, directive);
+    } else {
+        directives.push(directive);
+    }
+    directives.join(",")
+}
+
+/// Installs the global logger, writing to stdout in the configured format
+pub fn init(config: &LoggingConfig) -> Result<LogHandle, LoggingError> {
+    init_with_console(config, BoxMakeWriter::new(io::stdout), config.format)
+}
+
+/// Installs the global logger, writing to `console` in `console_format`
+/// and to the configured file, if any, in the configured format
+pub fn init_with_console(
+    config: &LoggingConfig,
+    console: BoxMakeWriter,
+    console_format: LogFormat,
+) -> Result<LogHandle, LoggingError> {
+    let (filter, reload) = reload::Layer::new(parse_level(&config.level)?);
+
+    let mut layers = vec![layer(console_format, console, true)];
+    if let Some(path) = &config.file {
+        let file = RotatingFile::open(path, config.rotation.clone()).map_err(|source| LoggingError::File {
+            path: path.clone(),
+            source,
+        })?;
+        layers.push(layer(config.format, BoxMakeWriter::new(Mutex::new(file)), false));
+    }
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(layers)
+        .try_init()
+        .map_err(|e| LoggingError::Init(e.to_string()))?;
+    Ok(LogHandle {
+        reload,
+        level: Mutex::new(config.level.clone()),
+        initial: config.level.clone(),
+    })
+}
+
+fn layer<S>(format: LogFormat, writer: BoxMakeWriter, ansi: bool) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let layer = fmt::layer().with_writer(writer).with_ansi(ansi);
+    match format {
+        LogFormat::Plain => layer.boxed(),
+        LogFormat::Compact => layer.compact().boxed(),
+        LogFormat::Json => layer.json().boxed(),
+    }
+}
+
+/// Log file that is rotated once it would grow past `max_bytes`
+pub struct RotatingFile {
+    path: PathBuf,
+    rotation: LogRotation,
+    file: File,
+    written: u64,
+}
+
+impl RotatingFile {
+    /// Opens `path` for appending, creating it and its directory if needed
+    pub fn open(path: &Path, rotation: LogRotation) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            path: path.to_path_buf(),
+            rotation,
+            file,
+            written,
+        })
+    }
+
+    fn rotated(&self, index: usize) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    /// Shifts `<file>.<n>` to `<file>.<n + 1>`, dropping the oldest, and
+    /// starts a new file
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.rotation.keep == 0 {
+            std::fs::remove_file(&self.path)?;
+        } else {
+            let _ = std::fs::remove_file(self.rotated(self.rotation.keep));
+            for index in (1..self.rotation.keep).rev() {
+                let from = self.rotated(index);
+                if from.exists() {
+                    std::fs::rename(from, self.rotated(index + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, self.rotated(1))?;
+        }
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written > 0 && self.written + buf.len() as u64 > self.rotation.max_bytes {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation() {
+        let dir = std::env::temp_dir().join(format!("romer-logging-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("node.log");
+        let mut file = RotatingFile::open(&path, LogRotation { max_bytes: 10, keep: 2 }).unwrap();
+
+        for line in ["first line\n", "second line\n", "third line\n", "fourth line\n"] {
+            file.write_all(line.as_bytes()).unwrap();
+        }
+        file.flush().unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "fourth line\n");
+        assert_eq!(std::fs::read_to_string(dir.join("node.log.1")).unwrap(), "third line\n");
+        assert_eq!(std::fs::read_to_string(dir.join("node.log.2")).unwrap(), "second line\n");
+        assert!(!dir.join("node.log.3").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_merge_directive() {
+        assert_eq!(expand_directive("sequencer::fix=debug"), "romer_sequencer::fix=debug");
+        assert_eq!(expand_directive("sequencers=debug"), "sequencers=debug");
+        assert_eq!(merge_directive("info", "sequencer::fix=debug"), "info,romer_sequencer::fix=debug");
+        assert_eq!(
+            merge_directive("info,romer_sequencer::fix=debug,commonware_p2p=warn", "sequencer::fix=trace"),
+            "info,commonware_p2p=warn,romer_sequencer::fix=trace"
+        );
+        assert_eq!(merge_directive("info,commonware_p2p=warn", "debug"), "debug,commonware_p2p=warn");
+    }
+
+    #[test]
+    fn test_config() {
+        let config: LoggingConfig =
+            serde_json::from_value(serde_json::json!({ "level": "info,commonware_p2p=warn", "format": "json" }))
+                .unwrap();
+        assert_eq!(config.format, LogFormat::Json);
+        assert_eq!(config.rotation, LogRotation::default());
+        config.validate().unwrap();
+
+        let config = LoggingConfig {
+            level: "info,commonware_p2p=loud".into(),
+            ..LoggingConfig::default()
+        };
+        assert!(matches!(config.validate(), Err(LoggingError::Level { .. })));
+    }
+}
@@ -0,0 +1,162 @@
+//! Binary Merkle tree over 32-byte leaves, with inclusion proofs.
+//!
+//! Leaves and inner nodes are hashed under different prefixes so a node can
+//! never be passed off as a leaf. A node without a sibling is carried up to
+//! the next level unchanged rather than paired with itself, so no two leaf
+//! lists share a root.
+
+use commonware_cryptography::{Hasher, Sha256};
+use serde::{Deserialize, Serialize};
+
+const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+
+/// Root of a tree without leaves
+pub const EMPTY_ROOT: [u8; 32] = [0u8; 32];
+
+fn hash(prefix: u8, parts: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(&[prefix]);
+    for part in parts {
+        hasher.update(part);
+    }
+    let mut digest = [0u8; 32];
+    digest.copy_from_slice(&hasher.finalize());
+    digest
+}
+
+fn hash_leaf(leaf: &[u8; 32]) -> [u8; 32] {
+    hash(LEAF_PREFIX, &[leaf])
+}
+
+fn hash_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    hash(NODE_PREFIX, &[left, right])
+}
+
+/// Hashes of each level, from the hashed leaves up to the root
+fn levels(leaves: &[[u8; 32]]) -> Vec<Vec<[u8; 32]>> {
+    let mut levels = vec![leaves.iter().map(hash_leaf).collect::<Vec<_>>()];
+    while levels.last().unwrap().len() > 1 {
+        let next = levels
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => hash_node(left, right),
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
+
+/// Root committing to `leaves` in order
+pub fn merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    if leaves.is_empty() {
+        return EMPTY_ROOT;
+    }
+    levels(leaves).last().unwrap()[0]
+}
+
+/// Proof that a leaf sits at `index` of a tree of `leaf_count` leaves
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    pub index: usize,
+    pub leaf_count: usize,
+    /// Siblings from the leaf level up, skipping levels where the node had
+    /// none
+    pub siblings: Vec<[u8; 32]>,
+}
+
+impl MerkleProof {
+    /// Proves the leaf at `index`, `None` if out of range
+    pub fn generate(leaves: &[[u8; 32]], index: usize) -> Option<Self> {
+        if index >= leaves.len() {
+            return None;
+        }
+        let mut siblings = Vec::new();
+        let mut position = index;
+        for level in levels(leaves).iter().take_while(|level| level.len() > 1) {
+            if let Some(sibling) = level.get(position ^ 1) {
+                siblings.push(*sibling);
+            }
+            position /= 2;
+        }
+        Some(Self {
+            index,
+            leaf_count: leaves.len(),
+            siblings,
+        })
+    }
+
+    /// Checks `leaf` sits at the proven index under `root`
+    pub fn verify(&self, root: &[u8; 32], leaf: &[u8; 32]) -> bool {
+        if self.index >= self.leaf_count {
+            return false;
+        }
+        let mut siblings = self.siblings.iter();
+        let mut node = hash_leaf(leaf);
+        let (mut position, mut width) = (self.index, self.leaf_count);
+        while width > 1 {
+            let sibling = position ^ 1;
+            if sibling < width {
+                let Some(hash) = siblings.next() else {
+                    return false;
+                };
+                node = if position % 2 == 0 {
+                    hash_node(&node, hash)
+                } else {
+                    hash_node(hash, &node)
+                };
+            }
+            position /= 2;
+            width = width.div_ceil(2);
+        }
+        siblings.next().is_none() && node == *root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(count: u8) -> Vec<[u8; 32]> {
+        (0..count).map(|i| [i; 32]).collect()
+    }
+
+    #[test]
+    fn test_every_leaf_proves() {
+        for count in 1..=9 {
+            let leaves = leaves(count);
+            let root = merkle_root(&leaves);
+            for (index, leaf) in leaves.iter().enumerate() {
+                let proof = MerkleProof::generate(&leaves, index).unwrap();
+                assert!(proof.verify(&root, leaf), "leaf {} of {}", index, count);
+                assert!(!proof.verify(&root, &[99u8; 32]));
+            }
+            assert!(MerkleProof::generate(&leaves, leaves.len()).is_none());
+        }
+        assert_eq!(merkle_root(&[]), EMPTY_ROOT);
+    }
+
+    #[test]
+    fn test_rejects_tampered_proofs() {
+        let leaves = leaves(5);
+        let root = merkle_root(&leaves);
+        let proof = MerkleProof::generate(&leaves, 2).unwrap();
+
+        let moved = MerkleProof { index: 3, ..proof.clone() };
+        assert!(!moved.verify(&root, &leaves[2]));
+        let mut short = proof.clone();
+        short.siblings.pop();
+        assert!(!short.verify(&root, &leaves[2]));
+        let mut long = proof;
+        long.siblings.push([0u8; 32]);
+        assert!(!long.verify(&root, &leaves[2]));
+
+        // An unpaired last leaf is not duplicated, so the roots differ
+        assert_ne!(merkle_root(&leaves(3)), merkle_root(&[[0; 32], [1; 32], [2; 32], [2; 32]]));
+    }
+}
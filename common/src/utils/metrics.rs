@@ -0,0 +1,51 @@
+//! Prometheus scrape endpoint shared by the validator and the sequencer.
+
+use prometheus_client::encoding::text::encode;
+use prometheus_client::registry::Registry;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tracing::{info, warn};
+
+/// Serves the registry in the Prometheus text format on `addr`.
+///
+/// This is intentionally minimal: every request, regardless of path,
+/// receives the current metrics snapshot.
+pub async fn serve(addr: SocketAddr, registry: Arc<Mutex<Registry>>) {
+    let listener = match TcpListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            warn!(%addr, error = %e, "Failed to bind metrics endpoint");
+            return;
+        }
+    };
+    info!(%addr, "Serving metrics");
+
+    loop {
+        let (mut socket, _) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                warn!(error = %e, "Failed to accept metrics connection");
+                continue;
+            }
+        };
+
+        // Drain the request; we don't care what was asked for
+        let mut request = [0u8; 1024];
+        let _ = socket.read(&mut request).await;
+
+        let mut body = String::new();
+        if let Err(e) = encode(&mut body, &registry.lock().unwrap()) {
+            warn!(error = %e, "Failed to encode metrics");
+            continue;
+        }
+
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        let _ = socket.write_all(response.as_bytes()).await;
+    }
+}
@@ -1 +1,6 @@
-pub mod hardware_validator;
\ No newline at end of file
+pub mod clock;
+pub mod hardware_validator;
+pub mod logging;
+pub mod merkle;
+pub mod metrics;
+pub mod rpc;
@@ -0,0 +1,73 @@
+use serde_json::{json, Value};
+use std::io;
+use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+#[derive(Error, Debug)]
+pub enum RpcCallError {
+    #[error("Connection error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Malformed response: {0}")]
+    Malformed(String),
+
+    #[error("RPC error {code}: {message}")]
+    Rpc { code: i64, message: String },
+}
+
+/// Calls `method` on the JSON-RPC endpoint at `address` over a fresh
+/// connection and returns its result. The API key in `ROMER_API_KEY` and
+/// the admin token in `ROMER_ADMIN_TOKEN`, if set, are sent along for
+/// endpoints that rate limit reads or control admin access.
+pub async fn call(address: &str, method: &str, params: Value) -> Result<Value, RpcCallError> {
+    let body = json!({ "jsonrpc": "2.0", "method": method, "params": params, "id": 1 }).to_string();
+    let api_key = std::env::var("ROMER_API_KEY")
+        .map(|key| format!("X-API-Key: {}\r\n", key))
+        .unwrap_or_default();
+    let admin_token = std::env::var("ROMER_ADMIN_TOKEN")
+        .map(|token| format!("X-Romer-Admin-Token: {}\r\n", token))
+        .unwrap_or_default();
+    let request = format!(
+        "POST / HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\n{}{}Content-Length: {}\r\n\r\n{}",
+        address,
+        api_key,
+        admin_token,
+        body.len(),
+        body
+    );
+
+    let stream = TcpStream::connect(address).await?;
+    let (reader, mut writer) = stream.into_split();
+    writer.write_all(request.as_bytes()).await?;
+
+    // Servers may keep the connection alive, so read exactly one response
+    let mut reader = BufReader::new(reader);
+    let mut content_length = 0usize;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).await? == 0 {
+            return Err(RpcCallError::Malformed("connection closed in headers".into()));
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+    let mut body = vec![0u8; content_length];
+    reader.read_exact(&mut body).await?;
+
+    let mut response: Value = serde_json::from_slice(&body).map_err(|e| RpcCallError::Malformed(e.to_string()))?;
+    if let Some(error) = response.get("error") {
+        return Err(RpcCallError::Rpc {
+            code: error["code"].as_i64().unwrap_or_default(),
+            message: error["message"].as_str().unwrap_or_default().to_string(),
+        });
+    }
+    Ok(response["result"].take())
+}
@@ -0,0 +1,60 @@
+// build.rs
+//! Compiles the Romer Move framework and generates `framework.rs`, which
+//! embeds every module (including dependencies) with `include_bytes!`.
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use move_package::BuildConfig;
+
+const PACKAGE: &str = "packages/romer-framework";
+
+fn main() -> anyhow::Result<()> {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR")?);
+    let out_dir = PathBuf::from(env::var("OUT_DIR")?);
+    let package = manifest_dir.join(PACKAGE);
+
+    println!("cargo:rerun-if-changed={}", package.join("Move.toml").display());
+    println!("cargo:rerun-if-changed={}", package.join("sources").display());
+
+    let config = BuildConfig {
+        install_dir: Some(out_dir.join("build")),
+        ..Default::default()
+    };
+    let compiled = config.compile_package(&package, &mut std::io::stderr())?;
+
+    let modules_dir = out_dir.join("modules");
+    fs::create_dir_all(&modules_dir)?;
+
+    let mut generated = String::from("pub static MODULES: &[FrameworkModule] = &[\n");
+    for unit in compiled.all_modules() {
+        let module = &unit.unit.module;
+        let id = module.self_id();
+        let file = format!("{}_{}.mv", id.address().short_str_lossless(), id.name());
+
+        let mut bytes = Vec::new();
+        module.serialize_with_version(module.version, &mut bytes)?;
+        write_if_changed(&modules_dir.join(&file), &bytes)?;
+
+        writeln!(
+            generated,
+            "    FrameworkModule {{ address: \"0x{}\", name: \"{}\", bytes: include_bytes!(concat!(env!(\"OUT_DIR\"), \"/modules/{}\")) }},",
+            id.address().short_str_lossless(),
+            id.name(),
+            file
+        )?;
+    }
+    generated.push_str("];\n");
+    write_if_changed(&out_dir.join("framework.rs"), generated.as_bytes())?;
+
+    Ok(())
+}
+
+/// Avoids touching outputs that did not change so dependents are not rebuilt
+fn write_if_changed(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    if fs::read(path).map(|existing| existing == contents).unwrap_or(false) {
+        return Ok(());
+    }
+    fs::write(path, contents)
+}
@@ -0,0 +1,200 @@
+// SPDX-License-Identifier: Apache-2.0
+
+/// Wrapped representations of assets locked on other chains. Bridge
+/// validators watch each source chain's lock contract and sign every
+/// deposit; once `threshold` of them have, anyone may submit the
+/// signatures to mint the wrapped asset to its recipient. Burning a
+/// wrapped asset records a withdrawal, which the validators sign a release
+/// of for the source chain.
+module romer::bridge;
+
+use std::string::{Self, String};
+use std::type_name;
+use sui::balance::{Self, Supply};
+use sui::bcs;
+use sui::coin::{Self, Coin};
+use sui::ed25519;
+use sui::table::{Self, Table};
+use romer::events;
+
+// === Errors ===
+const EInvalidThreshold: u64 = 1;
+const EAlreadyMinted: u64 = 2;
+const EUnknownSigner: u64 = 3;
+const ESignersNotAscending: u64 = 4;
+const EInvalidSignature: u64 = 5;
+const EThresholdNotReached: u64 = 6;
+const ESignatureCountMismatch: u64 = 7;
+const EZeroAmount: u64 = 8;
+
+// === Constants ===
+/// Prefix of the signed mint message, `MINT_DOMAIN` in `romer_common`
+const MINT_DOMAIN: vector<u8> = b"ROMER_BRIDGE_MINT";
+
+// === Structs ===
+/// Type marker of the wrapped representation of `T`.
+public struct Wrapped<phantom T> has drop {}
+
+/// The validator committee and the deposits already minted.
+public struct Bridge has key {
+    id: UID,
+    /// Ed25519 public keys
+    validators: vector<vector<u8>>,
+    threshold: u64,
+    /// Keyed by source chain and deposit id
+    minted: Table<vector<u8>, bool>,
+    next_withdrawal: u64,
+}
+
+/// Supply of the wrapped representation of `T`, locked on `source_chain`.
+public struct WrappedAsset<phantom T> has key {
+    id: UID,
+    source_chain: String,
+    supply: Supply<Wrapped<T>>,
+}
+
+/// Registers assets and rotates the committee.
+public struct BridgeAdminCap has key, store {
+    id: UID,
+}
+
+// === Public-Mutative Functions ===
+/// Shares a bridge run by `validators`, returning its admin capability.
+public fun create(validators: vector<vector<u8>>, threshold: u64, ctx: &mut TxContext): BridgeAdminCap {
+    transfer::share_object(new(validators, threshold, ctx));
+    BridgeAdminCap { id: object::new(ctx) }
+}
+
+/// Shares the supply of wrapped `T`. Register each asset once.
+public fun register_asset<T>(_: &BridgeAdminCap, source_chain: String, ctx: &mut TxContext) {
+    transfer::share_object(new_asset<T>(source_chain, ctx));
+}
+
+public fun rotate_validators(
+    _: &BridgeAdminCap,
+    bridge: &mut Bridge,
+    validators: vector<vector<u8>>,
+    threshold: u64,
+) {
+    assert!(threshold > 0 && threshold <= validators.length(), EInvalidThreshold);
+    bridge.validators = validators;
+    bridge.threshold = threshold;
+}
+
+/// Mints `amount` of wrapped `T` to `recipient` for a deposit signed by at
+/// least `threshold` validators, given by ascending committee index.
+public fun mint<T>(
+    bridge: &mut Bridge,
+    asset: &mut WrappedAsset<T>,
+    deposit_id: String,
+    recipient: address,
+    amount: u64,
+    signers: vector<u64>,
+    signatures: vector<vector<u8>>,
+    ctx: &mut TxContext,
+) {
+    assert!(amount > 0, EZeroAmount);
+    let mut key = bcs::to_bytes(&asset.source_chain);
+    key.append(bcs::to_bytes(&deposit_id));
+    assert!(!bridge.minted.contains(key), EAlreadyMinted);
+
+    let message = mint_message<T>(&asset.source_chain, &deposit_id, recipient, amount);
+    verify(bridge, &message, &signers, &signatures);
+    bridge.minted.add(key, true);
+
+    let minted = coin::from_balance(asset.supply.increase_supply(amount), ctx);
+    transfer::public_transfer(minted, recipient);
+    events::emit_bridge_minted(asset.source_chain, asset_name<T>(), deposit_id, recipient, amount);
+}
+
+/// Burns `wrapped` for release to `destination` on the source chain.
+/// Returns the withdrawal's nonce.
+public fun burn<T>(
+    bridge: &mut Bridge,
+    asset: &mut WrappedAsset<T>,
+    wrapped: Coin<Wrapped<T>>,
+    destination: String,
+    ctx: &TxContext,
+): u64 {
+    let amount = asset.supply.decrease_supply(wrapped.into_balance());
+    assert!(amount > 0, EZeroAmount);
+    let nonce = bridge.next_withdrawal;
+    bridge.next_withdrawal = nonce + 1;
+    events::emit_withdrawal_requested(asset.source_chain, asset_name<T>(), nonce, ctx.sender(), destination, amount);
+    nonce
+}
+
+// === Public-View Functions ===
+/// The message validators sign to authorize a mint.
+public fun mint_message<T>(source_chain: &String, deposit_id: &String, recipient: address, amount: u64): vector<u8> {
+    let mut message = MINT_DOMAIN;
+    message.append(bcs::to_bytes(source_chain));
+    message.append(bcs::to_bytes(&asset_name<T>()));
+    message.append(bcs::to_bytes(deposit_id));
+    message.append(bcs::to_bytes(&recipient));
+    message.append(bcs::to_bytes(&amount));
+    message
+}
+
+public fun is_minted(bridge: &Bridge, source_chain: String, deposit_id: String): bool {
+    let mut key = bcs::to_bytes(&source_chain);
+    key.append(bcs::to_bytes(&deposit_id));
+    bridge.minted.contains(key)
+}
+
+public fun threshold(bridge: &Bridge): u64 {
+    bridge.threshold
+}
+
+public fun total_supply<T>(asset: &WrappedAsset<T>): u64 {
+    asset.supply.supply_value()
+}
+
+// === Private Functions ===
+fun new(validators: vector<vector<u8>>, threshold: u64, ctx: &mut TxContext): Bridge {
+    assert!(threshold > 0 && threshold <= validators.length(), EInvalidThreshold);
+    Bridge {
+        id: object::new(ctx),
+        validators,
+        threshold,
+        minted: table::new(ctx),
+        next_withdrawal: 0,
+    }
+}
+
+fun new_asset<T>(source_chain: String, ctx: &mut TxContext): WrappedAsset<T> {
+    WrappedAsset {
+        id: object::new(ctx),
+        source_chain,
+        supply: balance::create_supply(Wrapped<T> {}),
+    }
+}
+
+fun asset_name<T>(): String {
+    string::from_ascii(type_name::get<T>().into_string())
+}
+
+fun verify(bridge: &Bridge, message: &vector<u8>, signers: &vector<u64>, signatures: &vector<vector<u8>>) {
+    assert!(signers.length() == signatures.length(), ESignatureCountMismatch);
+    assert!(signers.length() >= bridge.threshold, EThresholdNotReached);
+    let mut i = 0;
+    while (i < signers.length()) {
+        let index = signers[i];
+        assert!(index < bridge.validators.length(), EUnknownSigner);
+        // Ascending indices rule out counting one validator twice
+        assert!(i == 0 || index > signers[i - 1], ESignersNotAscending);
+        assert!(ed25519::ed25519_verify(&signatures[i], &bridge.validators[index], message), EInvalidSignature);
+        i = i + 1;
+    };
+}
+
+// === Test Functions ===
+#[test_only]
+public fun new_for_testing(validators: vector<vector<u8>>, threshold: u64, ctx: &mut TxContext): Bridge {
+    new(validators, threshold, ctx)
+}
+
+#[test_only]
+public fun new_asset_for_testing<T>(source_chain: String, ctx: &mut TxContext): WrappedAsset<T> {
+    new_asset<T>(source_chain, ctx)
+}
@@ -0,0 +1,46 @@
+// SPDX-License-Identifier: Apache-2.0
+
+/// The native ROMER coin. Created once at genesis; the treasury cap is
+/// handed to the genesis sender, which mints the initial supply into the
+/// network treasury.
+module romer::coins;
+
+use sui::coin::{Self, TreasuryCap};
+
+// === Constants ===
+const DECIMALS: u8 = 9;
+
+// === Structs ===
+/// One-time witness and type marker of the ROMER coin.
+public struct COINS has drop {}
+
+// === Init ===
+fun init(witness: COINS, ctx: &mut TxContext) {
+    let (treasury_cap, metadata) = coin::create_currency(
+        witness,
+        DECIMALS,
+        b"ROMER",
+        b"Romer",
+        b"Native coin of the Romer network",
+        option::none(),
+        ctx,
+    );
+    transfer::public_freeze_object(metadata);
+    transfer::public_transfer(treasury_cap, ctx.sender());
+}
+
+// === Public-View Functions ===
+public fun decimals(): u8 {
+    DECIMALS
+}
+
+/// Total ROMER minted so far.
+public fun total_supply(cap: &TreasuryCap<COINS>): u64 {
+    coin::total_supply(cap)
+}
+
+// === Test Functions ===
+#[test_only]
+public fun init_for_testing(ctx: &mut TxContext) {
+    init(COINS {}, ctx);
+}
@@ -0,0 +1,45 @@
+// SPDX-License-Identifier: Apache-2.0
+
+/// Block context supplied by the sequencer to every trading transaction.
+module romer::context;
+
+// === Errors ===
+const ETimeWentBackwards: u64 = 1;
+
+// === Structs ===
+/// Height, sequencer timestamp and producer of the block being executed.
+public struct Context has copy, drop, store {
+    height: u64,
+    timestamp_ms: u64,
+    sequencer: address,
+}
+
+// === Public-Package Functions ===
+public(package) fun new(height: u64, timestamp_ms: u64, sequencer: address): Context {
+    Context { height, timestamp_ms, sequencer }
+}
+
+/// Context of the next block. Timestamps never decrease.
+public(package) fun advance(self: &Context, timestamp_ms: u64, sequencer: address): Context {
+    assert!(timestamp_ms >= self.timestamp_ms, ETimeWentBackwards);
+    Context { height: self.height + 1, timestamp_ms, sequencer }
+}
+
+// === Public-View Functions ===
+public fun height(self: &Context): u64 {
+    self.height
+}
+
+public fun timestamp_ms(self: &Context): u64 {
+    self.timestamp_ms
+}
+
+public fun sequencer(self: &Context): address {
+    self.sequencer
+}
+
+// === Test Functions ===
+#[test_only]
+public fun new_for_testing(height: u64, timestamp_ms: u64): Context {
+    new(height, timestamp_ms, @0x0)
+}
@@ -0,0 +1,144 @@
+// SPDX-License-Identifier: Apache-2.0
+
+/// Events emitted by the framework, consumed by indexers and the explorer.
+module romer::events;
+
+use std::string::String;
+use sui::event;
+
+// === Structs ===
+public struct OrderAccepted has copy, drop {
+    order_id: u64,
+    owner: address,
+    symbol: String,
+    side: u8,
+    price: u64,
+    quantity: u64,
+    height: u64,
+}
+
+public struct TradeSettled has copy, drop {
+    buyer: address,
+    seller: address,
+    symbol: String,
+    price: u64,
+    quantity: u64,
+    height: u64,
+}
+
+public struct BridgeMinted has copy, drop {
+    source_chain: String,
+    asset: String,
+    deposit_id: String,
+    recipient: address,
+    amount: u64,
+}
+
+/// Wrapped assets burned, for the bridge validators to release on the
+/// source chain.
+public struct WithdrawalRequested has copy, drop {
+    source_chain: String,
+    asset: String,
+    nonce: u64,
+    sender: address,
+    destination: String,
+    amount: u64,
+}
+
+/// Test ROMER minted by the faucet of a development or testnet network.
+public struct FaucetDripped has copy, drop {
+    recipient: address,
+    amount: u64,
+    total: u64,
+}
+
+/// A treasury spend signed by `approvals` signers, payable once its
+/// timelock has passed.
+public struct SpendProposed has copy, drop {
+    proposal_id: u64,
+    recipient: address,
+    amount: u64,
+    approvals: u64,
+}
+
+public struct SpendExecuted has copy, drop {
+    proposal_id: u64,
+    recipient: address,
+    amount: u64,
+}
+
+public struct SpendCancelled has copy, drop {
+    proposal_id: u64,
+}
+
+/// Fees routed by `romer::fee_burn`, `burned` of them removed from supply.
+public struct FeesBurned has copy, drop {
+    burned: u64,
+    to_treasury: u64,
+    total_burned: u64,
+}
+
+// === Public-Package Functions ===
+public(package) fun emit_order_accepted(
+    order_id: u64,
+    owner: address,
+    symbol: String,
+    side: u8,
+    price: u64,
+    quantity: u64,
+    height: u64,
+) {
+    event::emit(OrderAccepted { order_id, owner, symbol, side, price, quantity, height });
+}
+
+public(package) fun emit_trade_settled(
+    buyer: address,
+    seller: address,
+    symbol: String,
+    price: u64,
+    quantity: u64,
+    height: u64,
+) {
+    event::emit(TradeSettled { buyer, seller, symbol, price, quantity, height });
+}
+
+public(package) fun emit_bridge_minted(
+    source_chain: String,
+    asset: String,
+    deposit_id: String,
+    recipient: address,
+    amount: u64,
+) {
+    event::emit(BridgeMinted { source_chain, asset, deposit_id, recipient, amount });
+}
+
+public(package) fun emit_withdrawal_requested(
+    source_chain: String,
+    asset: String,
+    nonce: u64,
+    sender: address,
+    destination: String,
+    amount: u64,
+) {
+    event::emit(WithdrawalRequested { source_chain, asset, nonce, sender, destination, amount });
+}
+
+public(package) fun emit_faucet_dripped(recipient: address, amount: u64, total: u64) {
+    event::emit(FaucetDripped { recipient, amount, total });
+}
+
+public(package) fun emit_spend_proposed(proposal_id: u64, recipient: address, amount: u64, approvals: u64) {
+    event::emit(SpendProposed { proposal_id, recipient, amount, approvals });
+}
+
+public(package) fun emit_spend_executed(proposal_id: u64, recipient: address, amount: u64) {
+    event::emit(SpendExecuted { proposal_id, recipient, amount });
+}
+
+public(package) fun emit_spend_cancelled(proposal_id: u64) {
+    event::emit(SpendCancelled { proposal_id });
+}
+
+public(package) fun emit_fees_burned(burned: u64, to_treasury: u64, total_burned: u64) {
+    event::emit(FeesBurned { burned, to_treasury, total_burned });
+}
@@ -0,0 +1,93 @@
+// SPDX-License-Identifier: Apache-2.0
+
+/// Test ROMER for development and testnet networks. Genesis of such a
+/// network hands the ROMER treasury cap to a shared `Faucet`, which mints
+/// up to `drip` per request and `max_per_address` to any one address.
+/// Mainnet genesis never creates one, so no faucet exists where ROMER has
+/// value.
+module romer::faucet;
+
+use sui::coin::{Self, TreasuryCap};
+use sui::table::{Self, Table};
+use romer::coins::COINS;
+use romer::events;
+
+// === Errors ===
+const EZeroAmount: u64 = 1;
+const EExceedsDrip: u64 = 2;
+const ELimitReached: u64 = 3;
+const EInvalidLimits: u64 = 4;
+
+// === Structs ===
+public struct Faucet has key {
+    id: UID,
+    treasury_cap: TreasuryCap<COINS>,
+    drip: u64,
+    max_per_address: u64,
+    /// Total minted to each address
+    dispensed: Table<address, u64>,
+}
+
+// === Public-Mutative Functions ===
+/// Shares a faucet minting with `treasury_cap`.
+public fun create(treasury_cap: TreasuryCap<COINS>, drip: u64, max_per_address: u64, ctx: &mut TxContext) {
+    transfer::share_object(new(treasury_cap, drip, max_per_address, ctx));
+}
+
+/// Mints `amount` of test ROMER to the sender.
+public fun request(faucet: &mut Faucet, amount: u64, ctx: &mut TxContext) {
+    request_for(faucet, ctx.sender(), amount, ctx);
+}
+
+/// Mints `amount` of test ROMER to `recipient`, as the sequencer does for
+/// `request_faucet` calls.
+public fun request_for(faucet: &mut Faucet, recipient: address, amount: u64, ctx: &mut TxContext) {
+    assert!(amount > 0, EZeroAmount);
+    assert!(amount <= faucet.drip, EExceedsDrip);
+    let total = if (faucet.dispensed.contains(recipient)) {
+        *faucet.dispensed.borrow(recipient)
+    } else {
+        0
+    };
+    assert!(total + amount <= faucet.max_per_address, ELimitReached);
+    if (faucet.dispensed.contains(recipient)) {
+        *faucet.dispensed.borrow_mut(recipient) = total + amount;
+    } else {
+        faucet.dispensed.add(recipient, amount);
+    };
+
+    let minted = coin::mint(&mut faucet.treasury_cap, amount, ctx);
+    transfer::public_transfer(minted, recipient);
+    events::emit_faucet_dripped(recipient, amount, total + amount);
+}
+
+// === Public-View Functions ===
+public fun drip(faucet: &Faucet): u64 {
+    faucet.drip
+}
+
+public fun dispensed(faucet: &Faucet, recipient: address): u64 {
+    if (faucet.dispensed.contains(recipient)) {
+        *faucet.dispensed.borrow(recipient)
+    } else {
+        0
+    }
+}
+
+// === Private Functions ===
+fun new(treasury_cap: TreasuryCap<COINS>, drip: u64, max_per_address: u64, ctx: &mut TxContext): Faucet {
+    assert!(drip > 0 && drip <= max_per_address, EInvalidLimits);
+    Faucet {
+        id: object::new(ctx),
+        treasury_cap,
+        drip,
+        max_per_address,
+        dispensed: table::new(ctx),
+    }
+}
+
+// === Test Functions ===
+#[test_only]
+public fun new_for_testing(treasury_cap: TreasuryCap<COINS>, drip: u64, max_per_address: u64, ctx: &mut TxContext): Faucet {
+    new(treasury_cap, drip, max_per_address, ctx)
+}
@@ -0,0 +1,65 @@
+// SPDX-License-Identifier: Apache-2.0
+
+/// Routes collected trading fees: a configurable share is burned, reducing
+/// the coin's total supply, and the remainder is deposited into the treasury.
+module romer::fee_burn;
+
+use sui::coin::{Self, Coin, TreasuryCap};
+use romer::events;
+use romer::treasury::Treasury;
+
+// === Errors ===
+const EInvalidBurnShare: u64 = 1;
+
+// === Constants ===
+const MAX_BPS: u64 = 10_000;
+
+// === Structs ===
+public struct FeeRouter<phantom T> has key {
+    id: UID,
+    /// Share of fees burned, in basis points
+    burn_bps: u64,
+    /// Running total of fees burned through this router
+    total_burned: u64,
+}
+
+// === Public Functions ===
+public fun create<T>(burn_bps: u64, ctx: &mut TxContext) {
+    assert!(burn_bps <= MAX_BPS, EInvalidBurnShare);
+    transfer::share_object(FeeRouter<T> {
+        id: object::new(ctx),
+        burn_bps,
+        total_burned: 0,
+    });
+}
+
+/// Burn the router's share of `fees` and deposit the rest into the
+/// treasury. Burning takes the coin's `TreasuryCap`, so only its holder
+/// routes fees.
+public fun route<T>(
+    router: &mut FeeRouter<T>,
+    treasury: &mut Treasury<T>,
+    cap: &mut TreasuryCap<T>,
+    mut fees: Coin<T>,
+    ctx: &mut TxContext,
+) {
+    let total = fees.value();
+    let burned = (((total as u128) * (router.burn_bps as u128) / (MAX_BPS as u128)) as u64);
+
+    if (burned > 0) {
+        coin::burn(cap, fees.split(burned, ctx));
+    };
+    treasury.deposit(fees);
+
+    router.total_burned = router.total_burned + burned;
+    events::emit_fees_burned(burned, total - burned, router.total_burned);
+}
+
+// === Public-View Functions ===
+public fun burn_bps<T>(router: &FeeRouter<T>): u64 {
+    router.burn_bps
+}
+
+public fun total_burned<T>(router: &FeeRouter<T>): u64 {
+    router.total_burned
+}
@@ -0,0 +1,85 @@
+// SPDX-License-Identifier: Apache-2.0
+
+/// Prices aggregated by the sequencer from whitelisted feeders: the median
+/// of their fresh prices with outliers dropped, written before each block's
+/// transactions execute so risk logic reads the price in force at the block.
+module romer::oracle;
+
+use std::string::String;
+use sui::table::{Self, Table};
+use romer::context::Context;
+
+// === Errors ===
+const ENoPrice: u64 = 1;
+const EStalePrice: u64 = 2;
+const ETimeWentBackwards: u64 = 3;
+
+// === Structs ===
+/// Latest price of each symbol. Shared at genesis.
+public struct PriceOracle has key {
+    id: UID,
+    prices: Table<String, Price>,
+}
+
+/// Prices are quoted in units of `1 / 1_000_000_000`, as in settlement.
+public struct Price has copy, drop, store {
+    price: u64,
+    /// Feeders the median was taken over
+    feeders: u64,
+    timestamp_ms: u64,
+}
+
+// === Init ===
+fun init(ctx: &mut TxContext) {
+    transfer::share_object(PriceOracle { id: object::new(ctx), prices: table::new(ctx) });
+}
+
+// === Public-Package Functions ===
+/// Writes the aggregated price of `symbol`, as the sequencer does for
+/// every price in a block.
+public(package) fun update(oracle: &mut PriceOracle, symbol: String, price: u64, feeders: u64, timestamp_ms: u64) {
+    let next = Price { price, feeders, timestamp_ms };
+    if (oracle.prices.contains(symbol)) {
+        let current = &mut oracle.prices[symbol];
+        assert!(timestamp_ms >= current.timestamp_ms, ETimeWentBackwards);
+        *current = next;
+    } else {
+        oracle.prices.add(symbol, next);
+    }
+}
+
+// === Public-View Functions ===
+/// Price of `symbol`, aborting unless written within `max_age_ms` of the
+/// block being executed.
+public fun price(oracle: &PriceOracle, symbol: String, ctx: &Context, max_age_ms: u64): u64 {
+    let current = latest(oracle, symbol);
+    assert!(ctx.timestamp_ms() <= current.timestamp_ms + max_age_ms, EStalePrice);
+    current.price
+}
+
+public fun has_price(oracle: &PriceOracle, symbol: String): bool {
+    oracle.prices.contains(symbol)
+}
+
+public fun latest(oracle: &PriceOracle, symbol: String): Price {
+    assert!(oracle.prices.contains(symbol), ENoPrice);
+    oracle.prices[symbol]
+}
+
+public fun value(self: &Price): u64 {
+    self.price
+}
+
+public fun feeders(self: &Price): u64 {
+    self.feeders
+}
+
+public fun timestamp_ms(self: &Price): u64 {
+    self.timestamp_ms
+}
+
+// === Test Functions ===
+#[test_only]
+public fun new_for_testing(ctx: &mut TxContext): PriceOracle {
+    PriceOracle { id: object::new(ctx), prices: table::new(ctx) }
+}
@@ -0,0 +1,53 @@
+// SPDX-License-Identifier: Apache-2.0
+
+/// Native order book primitives for the hot path of trading packages.
+///
+/// One side of a book is a pair of vectors, `prices` and the `quantities`
+/// resting at each price, best first: bids in descending and asks in
+/// ascending price order, with no empty levels. The natives keep that shape
+/// and behave identically on every validator. Every native aborts with
+/// `ELengthMismatch` if the vectors differ in length, and those taking a
+/// side by value with `EUnsorted` if it is out of order or has an empty
+/// level.
+module romer::orderbook;
+
+// === Errors ===
+#[allow(unused_const)]
+const ELengthMismatch: u64 = 1;
+#[allow(unused_const)]
+const EZeroPrice: u64 = 2;
+#[allow(unused_const)]
+const EZeroQuantity: u64 = 3;
+#[allow(unused_const)]
+const EUnsorted: u64 = 4;
+#[allow(unused_const)]
+const EQuantityOverflow: u64 = 5;
+
+// === Public Functions ===
+/// Adds `quantity` at `price`, to the existing level or as a new one in
+/// price order. Aborts with `EZeroPrice` or `EZeroQuantity` on zeros and
+/// `EQuantityOverflow` if the level would hold more than a u64.
+public native fun insert_level(
+    prices: vector<u64>,
+    quantities: vector<u64>,
+    price: u64,
+    quantity: u64,
+    is_bid: bool,
+): (vector<u64>, vector<u64>);
+
+/// Whether the side has a level, and the price and quantity of its best:
+/// the best bid of a bid side, the best ask of an ask side.
+public native fun best_level(prices: &vector<u64>, quantities: &vector<u64>): (bool, u64, u64);
+
+/// Takes up to `quantity` from the side, best level first, at prices no
+/// worse than `limit_price` for the taker: at or above it from bids, at or
+/// below it from asks. Emptied levels are removed. Returns the side left,
+/// the quantity filled and its cost, the sum of price times quantity of
+/// every fill.
+public native fun match_quantity(
+    prices: vector<u64>,
+    quantities: vector<u64>,
+    limit_price: u64,
+    quantity: u64,
+    is_bid: bool,
+): (vector<u64>, vector<u64>, u64, u128);
@@ -0,0 +1,88 @@
+// SPDX-License-Identifier: Apache-2.0
+
+/// Order representation shared by the sequencer and on-chain settlement.
+module romer::orders;
+
+use std::string::String;
+use romer::context::Context;
+
+// === Errors ===
+const EInvalidSide: u64 = 1;
+const EZeroPrice: u64 = 2;
+const EZeroQuantity: u64 = 3;
+
+// === Constants ===
+const SIDE_BUY: u8 = 0;
+const SIDE_SELL: u8 = 1;
+
+// === Structs ===
+/// A limit order as accepted by the sequencer.
+public struct Order has copy, drop, store {
+    /// Sequencer assigned order id
+    id: u64,
+    owner: address,
+    symbol: String,
+    side: u8,
+    price: u64,
+    quantity: u64,
+    /// Sequencer timestamp when the order was accepted
+    timestamp_ms: u64,
+}
+
+// === Public-Package Functions ===
+public(package) fun new(
+    id: u64,
+    owner: address,
+    symbol: String,
+    side: u8,
+    price: u64,
+    quantity: u64,
+    ctx: &Context,
+): Order {
+    assert!(side == SIDE_BUY || side == SIDE_SELL, EInvalidSide);
+    assert!(price > 0, EZeroPrice);
+    assert!(quantity > 0, EZeroQuantity);
+
+    Order { id, owner, symbol, side, price, quantity, timestamp_ms: ctx.timestamp_ms() }
+}
+
+// === Public-View Functions ===
+public fun side_buy(): u8 {
+    SIDE_BUY
+}
+
+public fun side_sell(): u8 {
+    SIDE_SELL
+}
+
+public fun id(self: &Order): u64 {
+    self.id
+}
+
+public fun owner(self: &Order): address {
+    self.owner
+}
+
+public fun symbol(self: &Order): &String {
+    &self.symbol
+}
+
+public fun side(self: &Order): u8 {
+    self.side
+}
+
+public fun is_buy(self: &Order): bool {
+    self.side == SIDE_BUY
+}
+
+public fun price(self: &Order): u64 {
+    self.price
+}
+
+public fun quantity(self: &Order): u64 {
+    self.quantity
+}
+
+public fun timestamp_ms(self: &Order): u64 {
+    self.timestamp_ms
+}
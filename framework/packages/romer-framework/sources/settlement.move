@@ -0,0 +1,71 @@
+// SPDX-License-Identifier: Apache-2.0
+
+/// Settles matched trades by exchanging base and quote balances between
+/// buyer and seller.
+module romer::settlement;
+
+use sui::balance::Balance;
+use romer::context::Context;
+use romer::events;
+use romer::orders::Order;
+
+// === Errors ===
+const ESymbolMismatch: u64 = 1;
+const ESameSide: u64 = 2;
+const EPriceNotCrossed: u64 = 3;
+const EQuantityExceeded: u64 = 4;
+const EInsufficientBase: u64 = 5;
+const EInsufficientQuote: u64 = 6;
+const EOverflow: u64 = 7;
+
+// === Constants ===
+/// Prices are quoted in quote units per `PRICE_SCALE` base units
+const PRICE_SCALE: u128 = 1_000_000_000;
+
+// === Public-Package Functions ===
+/// Settles `quantity` base units between `maker` and `taker` at the maker's
+/// price. Returns the base balance owed to the buyer and the quote balance
+/// owed to the seller.
+public(package) fun settle<Base, Quote>(
+    maker: &Order,
+    taker: &Order,
+    quantity: u64,
+    seller_base: &mut Balance<Base>,
+    buyer_quote: &mut Balance<Quote>,
+    ctx: &Context,
+): (Balance<Base>, Balance<Quote>) {
+    assert!(maker.symbol() == taker.symbol(), ESymbolMismatch);
+    assert!(maker.side() != taker.side(), ESameSide);
+    assert!(quantity <= maker.quantity() && quantity <= taker.quantity(), EQuantityExceeded);
+
+    let (buy, sell) = if (maker.is_buy()) (maker, taker) else (taker, maker);
+    assert!(buy.price() >= sell.price(), EPriceNotCrossed);
+
+    let price = maker.price();
+    let cost = quote_amount(price, quantity);
+    assert!(seller_base.value() >= quantity, EInsufficientBase);
+    assert!(buyer_quote.value() >= cost, EInsufficientQuote);
+
+    events::emit_trade_settled(
+        buy.owner(),
+        sell.owner(),
+        *maker.symbol(),
+        price,
+        quantity,
+        ctx.height(),
+    );
+
+    (seller_base.split(quantity), buyer_quote.split(cost))
+}
+
+// === Public-View Functions ===
+/// Quote units paid for `quantity` base units at `price`.
+public fun quote_amount(price: u64, quantity: u64): u64 {
+    let amount = (price as u128) * (quantity as u128) / PRICE_SCALE;
+    assert!(amount <= 18_446_744_073_709_551_615, EOverflow);
+    amount as u64
+}
+
+public fun price_scale(): u64 {
+    PRICE_SCALE as u64
+}
@@ -0,0 +1,226 @@
+// SPDX-License-Identifier: Apache-2.0
+
+/// The network treasury. Funds only leave it through a spend proposal
+/// signed by at least `threshold` of its Ed25519 signers, once the proposal
+/// has waited out the timelock. Signatures are checked here, over the
+/// message `spend_message` builds, so no single key or sender can move
+/// funds. The treasury is shared at genesis from the network's
+/// `TreasuryConfig`.
+module romer::treasury;
+
+use sui::balance::{Self, Balance};
+use sui::bcs;
+use sui::clock::Clock;
+use sui::coin::{Self, Coin};
+use sui::ed25519;
+use sui::table::{Self, Table};
+use romer::events;
+
+// === Errors ===
+const EInvalidThreshold: u64 = 1;
+const EProposalNotFound: u64 = 2;
+const EUnknownSigner: u64 = 3;
+const ESignersNotAscending: u64 = 4;
+const EInvalidSignature: u64 = 5;
+const EThresholdNotReached: u64 = 6;
+const ESignatureCountMismatch: u64 = 7;
+const ETimelockActive: u64 = 8;
+const EInsufficientFunds: u64 = 9;
+const EProposalClosed: u64 = 10;
+const EDuplicateSigner: u64 = 11;
+
+// === Constants ===
+/// Prefix of the signed spend message, `SPEND_DOMAIN` in `romer_common`
+const SPEND_DOMAIN: vector<u8> = b"ROMER_TREASURY_SPEND";
+/// Prefix of the signed cancel message, `CANCEL_DOMAIN` in `romer_common`
+const CANCEL_DOMAIN: vector<u8> = b"ROMER_TREASURY_CANCEL";
+
+// === Structs ===
+/// Shared treasury holding funds of type `T`.
+public struct Treasury<phantom T> has key {
+    id: UID,
+    funds: Balance<T>,
+    /// Ed25519 public keys allowed to sign spends
+    signers: vector<vector<u8>>,
+    /// Number of signatures a spend or cancellation needs
+    threshold: u64,
+    /// Minimum time between a proposal and its execution
+    timelock_ms: u64,
+    proposals: Table<u64, SpendProposal>,
+    next_proposal_id: u64,
+}
+
+public struct SpendProposal has store {
+    recipient: address,
+    amount: u64,
+    memo: vector<u8>,
+    /// Time the signed proposal was recorded
+    proposed_at_ms: u64,
+    executed: bool,
+    cancelled: bool,
+}
+
+// === Public-Mutative Functions ===
+/// Shares a treasury governed by `threshold` of `signers`.
+public fun create<T>(signers: vector<vector<u8>>, threshold: u64, timelock_ms: u64, ctx: &mut TxContext) {
+    transfer::share_object(new<T>(signers, threshold, timelock_ms, ctx));
+}
+
+/// Anyone may deposit into the treasury.
+public fun deposit<T>(treasury: &mut Treasury<T>, coin: Coin<T>) {
+    treasury.funds.join(coin.into_balance());
+}
+
+/// Records a spend signed by at least `threshold` signers, given by
+/// ascending index, over `spend_message` for the next proposal id. Anyone
+/// may submit it. Returns the proposal id.
+public fun propose_spend<T>(
+    treasury: &mut Treasury<T>,
+    recipient: address,
+    amount: u64,
+    memo: vector<u8>,
+    signers: vector<u64>,
+    signatures: vector<vector<u8>>,
+    clock: &Clock,
+): u64 {
+    let proposal_id = treasury.next_proposal_id;
+    let message = spend_message(proposal_id, recipient, amount, &memo);
+    verify(treasury, &message, &signers, &signatures);
+    treasury.next_proposal_id = proposal_id + 1;
+
+    treasury.proposals.add(proposal_id, SpendProposal {
+        recipient,
+        amount,
+        memo,
+        proposed_at_ms: clock.timestamp_ms(),
+        executed: false,
+        cancelled: false,
+    });
+    events::emit_spend_proposed(proposal_id, recipient, amount, signers.length());
+    proposal_id
+}
+
+/// Pays out a proposal that has waited out the timelock. Anyone may
+/// execute it.
+public fun execute<T>(treasury: &mut Treasury<T>, proposal_id: u64, clock: &Clock, ctx: &mut TxContext) {
+    assert!(treasury.proposals.contains(proposal_id), EProposalNotFound);
+    let timelock_ms = treasury.timelock_ms;
+    let available = treasury.funds.value();
+
+    let proposal = &mut treasury.proposals[proposal_id];
+    assert!(!proposal.executed && !proposal.cancelled, EProposalClosed);
+    assert!(clock.timestamp_ms() >= proposal.proposed_at_ms + timelock_ms, ETimelockActive);
+    assert!(available >= proposal.amount, EInsufficientFunds);
+
+    proposal.executed = true;
+    let recipient = proposal.recipient;
+    let amount = proposal.amount;
+
+    let payment = coin::from_balance(treasury.funds.split(amount), ctx);
+    transfer::public_transfer(payment, recipient);
+    events::emit_spend_executed(proposal_id, recipient, amount);
+}
+
+/// Cancels an open proposal, signed by at least `threshold` signers over
+/// `cancel_message`.
+public fun cancel<T>(
+    treasury: &mut Treasury<T>,
+    proposal_id: u64,
+    signers: vector<u64>,
+    signatures: vector<vector<u8>>,
+) {
+    assert!(treasury.proposals.contains(proposal_id), EProposalNotFound);
+    verify(treasury, &cancel_message(proposal_id), &signers, &signatures);
+
+    let proposal = &mut treasury.proposals[proposal_id];
+    assert!(!proposal.executed && !proposal.cancelled, EProposalClosed);
+    proposal.cancelled = true;
+    events::emit_spend_cancelled(proposal_id);
+}
+
+// === Public-View Functions ===
+/// The message signers sign to authorize a spend.
+public fun spend_message(proposal_id: u64, recipient: address, amount: u64, memo: &vector<u8>): vector<u8> {
+    let mut message = SPEND_DOMAIN;
+    message.append(bcs::to_bytes(&proposal_id));
+    message.append(bcs::to_bytes(&recipient));
+    message.append(bcs::to_bytes(&amount));
+    message.append(bcs::to_bytes(memo));
+    message
+}
+
+/// The message signers sign to cancel a proposal.
+public fun cancel_message(proposal_id: u64): vector<u8> {
+    let mut message = CANCEL_DOMAIN;
+    message.append(bcs::to_bytes(&proposal_id));
+    message
+}
+
+public fun balance<T>(treasury: &Treasury<T>): u64 {
+    treasury.funds.value()
+}
+
+public fun threshold<T>(treasury: &Treasury<T>): u64 {
+    treasury.threshold
+}
+
+public fun timelock_ms<T>(treasury: &Treasury<T>): u64 {
+    treasury.timelock_ms
+}
+
+/// Id the next proposal is signed for
+public fun next_proposal_id<T>(treasury: &Treasury<T>): u64 {
+    treasury.next_proposal_id
+}
+
+public fun is_executed<T>(treasury: &Treasury<T>, proposal_id: u64): bool {
+    treasury.proposals[proposal_id].executed
+}
+
+// === Private Functions ===
+fun new<T>(signers: vector<vector<u8>>, threshold: u64, timelock_ms: u64, ctx: &mut TxContext): Treasury<T> {
+    assert!(threshold > 0 && threshold <= signers.length(), EInvalidThreshold);
+    let mut i = 0;
+    while (i < signers.length()) {
+        let mut j = 0;
+        while (j < i) {
+            assert!(signers[i] != signers[j], EDuplicateSigner);
+            j = j + 1;
+        };
+        i = i + 1;
+    };
+    Treasury {
+        id: object::new(ctx),
+        funds: balance::zero(),
+        signers,
+        threshold,
+        timelock_ms,
+        proposals: table::new(ctx),
+        next_proposal_id: 0,
+    }
+}
+
+fun verify<T>(treasury: &Treasury<T>, message: &vector<u8>, signers: &vector<u64>, signatures: &vector<vector<u8>>) {
+    assert!(signers.length() == signatures.length(), ESignatureCountMismatch);
+    assert!(signers.length() >= treasury.threshold, EThresholdNotReached);
+    let mut i = 0;
+    while (i < signers.length()) {
+        let index = signers[i];
+        assert!(index < treasury.signers.length(), EUnknownSigner);
+        // Ascending indices rule out counting one signer twice
+        assert!(i == 0 || index > signers[i - 1], ESignersNotAscending);
+        assert!(ed25519::ed25519_verify(&signatures[i], &treasury.signers[index], message), EInvalidSignature);
+        i = i + 1;
+    };
+}
+
+// === Test Functions ===
+#[test_only]
+public fun new_for_testing<T>(
+    signers: vector<vector<u8>>,
+    threshold: u64,
+    timelock_ms: u64,
+    ctx: &mut TxContext,
+): Treasury<T> {
+    new<T>(signers, threshold, timelock_ms, ctx)
+}
@@ -0,0 +1,109 @@
+// SPDX-License-Identifier: Apache-2.0
+
+#[test_only]
+module romer::bridge_tests;
+
+use std::string;
+use sui::coin::Coin;
+use sui::test_scenario;
+use sui::test_utils;
+use romer::bridge::{Self, Bridge, Wrapped, WrappedAsset};
+
+public struct USDC has drop {}
+
+const RELAYER: address = @0xA;
+const RECIPIENT: address = @0xB0B;
+const AMOUNT: u64 = 1000;
+
+// Keys from the seeds [1; 32], [2; 32] and [3; 32], and their signatures
+// over the mint message of deposit "0xabc-1" from "ethereum", matching
+// `romer_common::types::bridge::Deposit::mint_message`
+const KEY_1: vector<u8> = x"8a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c";
+const KEY_2: vector<u8> = x"8139770ea87d175f56a35466c34c7ecccb8d8a91b4ee37a25df60f5b8fc9b394";
+const KEY_3: vector<u8> = x"ed4928c628d1c2c6eae90338905995612959273a5c63f93636c14614ac8737d1";
+const SIG_1: vector<u8> = x"84dc5921f446d70740d0ed02058287d5c8d87b99784dd067de1a5a4914dde7fc997159c32f83fa2158c9ce5801d0c367ac59100ee5066a52c240f74e86d57a0e";
+const SIG_3: vector<u8> = x"7057d8751ac0a84ef60947c94820a08c35d95393c0c270f34dd8d658c68b7c17da53e5d61d29294e91d44152ce9808a0736c6b05a69f35d86bff5d5508654506";
+const MESSAGE: vector<u8> = x"524f4d45525f4252494447455f4d494e5408657468657265756d54303030303030303030303030303030303030303030303030303030303030303030303030303030303030303030303030303030303030303030303030303031303a3a6272696467655f74657374733a3a555344430730786162632d310000000000000000000000000000000000000000000000000000000000000b0be803000000000000";
+
+fun setup(ctx: &mut TxContext): (Bridge, WrappedAsset<USDC>) {
+    (
+        bridge::new_for_testing(vector[KEY_1, KEY_2, KEY_3], 2, ctx),
+        bridge::new_asset_for_testing<USDC>(string::utf8(b"ethereum"), ctx),
+    )
+}
+
+fun mint(bridge: &mut Bridge, asset: &mut WrappedAsset<USDC>, signers: vector<u64>, signatures: vector<vector<u8>>, ctx: &mut TxContext) {
+    bridge::mint(bridge, asset, string::utf8(b"0xabc-1"), RECIPIENT, AMOUNT, signers, signatures, ctx);
+}
+
+#[test]
+fun test_message_matches_rust() {
+    let message = bridge::mint_message<USDC>(&string::utf8(b"ethereum"), &string::utf8(b"0xabc-1"), RECIPIENT, AMOUNT);
+    assert!(message == MESSAGE);
+}
+
+#[test]
+fun test_mint_and_burn() {
+    let mut scenario = test_scenario::begin(RELAYER);
+    let (mut bridge, mut asset) = setup(scenario.ctx());
+    mint(&mut bridge, &mut asset, vector[0, 2], vector[SIG_1, SIG_3], scenario.ctx());
+    assert!(bridge::is_minted(&bridge, string::utf8(b"ethereum"), string::utf8(b"0xabc-1")));
+    assert!(bridge::total_supply(&asset) == AMOUNT);
+
+    scenario.next_tx(RECIPIENT);
+    let wrapped = scenario.take_from_sender<Coin<Wrapped<USDC>>>();
+    assert!(wrapped.value() == AMOUNT);
+    let nonce = bridge::burn(&mut bridge, &mut asset, wrapped, string::utf8(b"0x1111"), scenario.ctx());
+    assert!(nonce == 0);
+    assert!(bridge::total_supply(&asset) == 0);
+
+    test_utils::destroy(bridge);
+    test_utils::destroy(asset);
+    scenario.end();
+}
+
+#[test, expected_failure(abort_code = bridge::EAlreadyMinted)]
+fun test_mints_once() {
+    let mut scenario = test_scenario::begin(RELAYER);
+    let (mut bridge, mut asset) = setup(scenario.ctx());
+    mint(&mut bridge, &mut asset, vector[0, 2], vector[SIG_1, SIG_3], scenario.ctx());
+    mint(&mut bridge, &mut asset, vector[0, 2], vector[SIG_1, SIG_3], scenario.ctx());
+
+    test_utils::destroy(bridge);
+    test_utils::destroy(asset);
+    scenario.end();
+}
+
+#[test, expected_failure(abort_code = bridge::EThresholdNotReached)]
+fun test_threshold() {
+    let mut scenario = test_scenario::begin(RELAYER);
+    let (mut bridge, mut asset) = setup(scenario.ctx());
+    mint(&mut bridge, &mut asset, vector[0], vector[SIG_1], scenario.ctx());
+
+    test_utils::destroy(bridge);
+    test_utils::destroy(asset);
+    scenario.end();
+}
+
+#[test, expected_failure(abort_code = bridge::ESignersNotAscending)]
+fun test_signer_counted_once() {
+    let mut scenario = test_scenario::begin(RELAYER);
+    let (mut bridge, mut asset) = setup(scenario.ctx());
+    mint(&mut bridge, &mut asset, vector[0, 0], vector[SIG_1, SIG_1], scenario.ctx());
+
+    test_utils::destroy(bridge);
+    test_utils::destroy(asset);
+    scenario.end();
+}
+
+#[test, expected_failure(abort_code = bridge::EInvalidSignature)]
+fun test_signature_of_other_key() {
+    let mut scenario = test_scenario::begin(RELAYER);
+    let (mut bridge, mut asset) = setup(scenario.ctx());
+    // SIG_3 presented as validator 1's
+    mint(&mut bridge, &mut asset, vector[0, 1], vector[SIG_1, SIG_3], scenario.ctx());
+
+    test_utils::destroy(bridge);
+    test_utils::destroy(asset);
+    scenario.end();
+}
@@ -0,0 +1,42 @@
+// SPDX-License-Identifier: Apache-2.0
+
+#[test_only]
+module romer::faucet_tests;
+
+use sui::coin::{Self, Coin};
+use sui::test_scenario;
+use sui::test_utils;
+use romer::coins::COINS;
+use romer::faucet;
+
+const TESTER: address = @0xB0B;
+
+#[test]
+fun test_request_mints_to_sender() {
+    let mut scenario = test_scenario::begin(TESTER);
+    let cap = coin::create_treasury_cap_for_testing<COINS>(scenario.ctx());
+    let mut faucet = faucet::new_for_testing(cap, 100, 250, scenario.ctx());
+    faucet.request(100, scenario.ctx());
+    faucet.request(100, scenario.ctx());
+    assert!(faucet.dispensed(TESTER) == 200);
+
+    scenario.next_tx(TESTER);
+    let minted = scenario.take_from_sender<Coin<COINS>>();
+    assert!(minted.value() == 100);
+
+    test_utils::destroy(minted);
+    test_utils::destroy(faucet);
+    scenario.end();
+}
+
+#[test, expected_failure(abort_code = faucet::ELimitReached)]
+fun test_limit_per_address() {
+    let mut scenario = test_scenario::begin(TESTER);
+    let cap = coin::create_treasury_cap_for_testing<COINS>(scenario.ctx());
+    let mut faucet = faucet::new_for_testing(cap, 100, 150, scenario.ctx());
+    faucet.request(100, scenario.ctx());
+    faucet.request(100, scenario.ctx());
+
+    test_utils::destroy(faucet);
+    scenario.end();
+}
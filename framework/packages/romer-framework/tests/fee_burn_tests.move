@@ -0,0 +1,36 @@
+// SPDX-License-Identifier: Apache-2.0
+
+#[test_only]
+module romer::fee_burn_tests;
+
+use sui::coin;
+use sui::sui::SUI;
+use sui::test_scenario as ts;
+use sui::test_utils;
+use romer::fee_burn::{Self, FeeRouter};
+use romer::treasury;
+
+const ALICE: address = @0xA;
+const KEY: vector<u8> = x"8a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c";
+
+#[test]
+fun route_burns_share_from_supply() {
+    let mut test = ts::begin(ALICE);
+    fee_burn::create<SUI>(2_500, test.ctx());
+    test.next_tx(ALICE);
+
+    let mut treasury = treasury::new_for_testing<SUI>(vector[KEY], 1, 0, test.ctx());
+    let mut cap = coin::create_treasury_cap_for_testing<SUI>(test.ctx());
+    let fees = cap.mint(1000, test.ctx());
+    let mut router = test.take_shared<FeeRouter<SUI>>();
+    router.route(&mut treasury, &mut cap, fees, test.ctx());
+
+    assert!(cap.total_supply() == 750);
+    assert!(treasury.balance() == 750);
+    assert!(router.total_burned() == 250);
+
+    ts::return_shared(router);
+    test_utils::destroy(treasury);
+    transfer::public_transfer(cap, ALICE);
+    test.end();
+}
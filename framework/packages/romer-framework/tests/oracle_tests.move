@@ -0,0 +1,46 @@
+// SPDX-License-Identifier: Apache-2.0
+
+#[test_only]
+module romer::oracle_tests;
+
+use std::string;
+use sui::test_utils;
+use romer::context;
+use romer::oracle;
+
+#[test]
+fun test_update_and_read() {
+    let mut ctx = tx_context::dummy();
+    let mut prices = oracle::new_for_testing(&mut ctx);
+    let symbol = string::utf8(b"BTC/USD");
+    assert!(!prices.has_price(symbol));
+
+    oracle::update(&mut prices, symbol, 64_000_000_000_000, 3, 1000);
+    oracle::update(&mut prices, symbol, 64_100_000_000_000, 2, 2000);
+    let latest = prices.latest(symbol);
+    assert!(latest.value() == 64_100_000_000_000);
+    assert!(latest.feeders() == 2);
+    assert!(prices.price(symbol, &context::new_for_testing(5, 2500), 1000) == 64_100_000_000_000);
+
+    test_utils::destroy(prices);
+}
+
+#[test, expected_failure(abort_code = oracle::EStalePrice)]
+fun test_stale_price_aborts() {
+    let mut ctx = tx_context::dummy();
+    let mut prices = oracle::new_for_testing(&mut ctx);
+    let symbol = string::utf8(b"BTC/USD");
+    oracle::update(&mut prices, symbol, 64_000_000_000_000, 3, 1000);
+    prices.price(symbol, &context::new_for_testing(5, 5000), 1000);
+
+    test_utils::destroy(prices);
+}
+
+#[test, expected_failure(abort_code = oracle::ENoPrice)]
+fun test_missing_price_aborts() {
+    let mut ctx = tx_context::dummy();
+    let prices = oracle::new_for_testing(&mut ctx);
+    prices.latest(string::utf8(b"ETH/USD"));
+
+    test_utils::destroy(prices);
+}
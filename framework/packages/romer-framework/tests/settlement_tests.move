@@ -0,0 +1,69 @@
+// SPDX-License-Identifier: Apache-2.0
+
+#[test_only]
+module romer::settlement_tests;
+
+use std::string;
+use sui::balance;
+use sui::sui::SUI;
+use romer::coins::COINS;
+use romer::context;
+use romer::orders::{Self, Order};
+use romer::settlement;
+
+const BUYER: address = @0xB;
+const SELLER: address = @0x5;
+
+fun order(id: u64, owner: address, side: u8, price: u64, quantity: u64): Order {
+    orders::new(
+        id,
+        owner,
+        string::utf8(b"ROMER/SUI"),
+        side,
+        price,
+        quantity,
+        &context::new_for_testing(1, 1000),
+    )
+}
+
+#[test]
+fun test_settle_at_maker_price() {
+    let ctx = context::new_for_testing(1, 1000);
+    let maker = order(1, SELLER, orders::side_sell(), 2_000_000_000, 10);
+    let taker = order(2, BUYER, orders::side_buy(), 3_000_000_000, 4);
+
+    let mut seller_base = balance::create_for_testing<COINS>(10);
+    let mut buyer_quote = balance::create_for_testing<SUI>(100);
+    let (base, quote) = settlement::settle(&maker, &taker, 4, &mut seller_base, &mut buyer_quote, &ctx);
+
+    assert!(base.value() == 4);
+    assert!(quote.value() == 8);
+    assert!(seller_base.value() == 6);
+    assert!(buyer_quote.value() == 92);
+
+    balance::destroy_for_testing(base);
+    balance::destroy_for_testing(quote);
+    balance::destroy_for_testing(seller_base);
+    balance::destroy_for_testing(buyer_quote);
+}
+
+#[test, expected_failure(abort_code = settlement::EPriceNotCrossed)]
+fun test_uncrossed_prices_abort() {
+    let ctx = context::new_for_testing(1, 1000);
+    let maker = order(1, SELLER, orders::side_sell(), 3_000_000_000, 10);
+    let taker = order(2, BUYER, orders::side_buy(), 2_000_000_000, 10);
+
+    let mut seller_base = balance::create_for_testing<COINS>(10);
+    let mut buyer_quote = balance::create_for_testing<SUI>(100);
+    let (base, quote) = settlement::settle(&maker, &taker, 1, &mut seller_base, &mut buyer_quote, &ctx);
+
+    balance::destroy_for_testing(base);
+    balance::destroy_for_testing(quote);
+    balance::destroy_for_testing(seller_base);
+    balance::destroy_for_testing(buyer_quote);
+}
+
+#[test, expected_failure(abort_code = orders::EZeroQuantity)]
+fun test_zero_quantity_order_aborts() {
+    order(1, BUYER, orders::side_buy(), 1, 0);
+}
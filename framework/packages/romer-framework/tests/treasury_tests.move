@@ -0,0 +1,124 @@
+// SPDX-License-Identifier: Apache-2.0
+
+#[test_only]
+module romer::treasury_tests;
+
+use sui::clock::{Self, Clock};
+use sui::coin::{Self, Coin};
+use sui::sui::SUI;
+use sui::test_scenario::{Self as ts, Scenario};
+use sui::test_utils;
+use romer::treasury::{Self, Treasury};
+
+const SUBMITTER: address = @0xA;
+const RECIPIENT: address = @0xD;
+const AMOUNT: u64 = 400;
+const TIMELOCK_MS: u64 = 1000;
+
+// Keys from the seeds [1; 32], [2; 32] and [3; 32], and their signatures
+// over the spend of 400 to @0xD with memo "grant" as proposal 0, and over
+// its cancellation, matching `romer_common::types::treasury`
+const KEY_1: vector<u8> = x"8a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c";
+const KEY_2: vector<u8> = x"8139770ea87d175f56a35466c34c7ecccb8d8a91b4ee37a25df60f5b8fc9b394";
+const KEY_3: vector<u8> = x"ed4928c628d1c2c6eae90338905995612959273a5c63f93636c14614ac8737d1";
+const SPEND_1: vector<u8> = x"fc4b5dee6dfa9ae842325cce394ed178bc43f9db45bc4cc46e0118c8d88968d7284b2158cabf7dc43a458ce79be8430c21c6d4ebd89410f18d730e3484e2aa08";
+const SPEND_3: vector<u8> = x"a6c15f0848192b9bfaba244d63931199fd8e06da9a508c221a238a8c965ae386b3408190cc690a33fa651053d554619464cf8cd0ca4a8d50d8229dfb33ec240f";
+const CANCEL_1: vector<u8> = x"e6389b8e43bf84e5b1d87b22575ac9da024c22cf9f22d72ca85759c0bc611a683988ec0d051be7ac65749b84d9ff3652ee7db250f6a5417d46f2ac5d991ccc02";
+const CANCEL_2: vector<u8> = x"0b80e2a776e8ce003a2dfbdc6da626a14540ccb2184b366f0a8d18a09d916ecfe2646e5a07ab99fa082372943566068a43f2de7e2b82afd66871a356936bd409";
+const SPEND_MESSAGE: vector<u8> = x"524f4d45525f54524541535552595f5350454e440000000000000000000000000000000000000000000000000000000000000000000000000000000d9001000000000000056772616e74";
+const CANCEL_MESSAGE: vector<u8> = x"524f4d45525f54524541535552595f43414e43454c0000000000000000";
+
+fun setup(scenario: &mut Scenario): (Treasury<SUI>, Clock) {
+    let mut treasury = treasury::new_for_testing<SUI>(vector[KEY_1, KEY_2, KEY_3], 2, TIMELOCK_MS, scenario.ctx());
+    treasury.deposit(coin::mint_for_testing<SUI>(1000, scenario.ctx()));
+    (treasury, clock::create_for_testing(scenario.ctx()))
+}
+
+fun propose(treasury: &mut Treasury<SUI>, signers: vector<u64>, signatures: vector<vector<u8>>, clock: &Clock): u64 {
+    treasury.propose_spend(RECIPIENT, AMOUNT, b"grant", signers, signatures, clock)
+}
+
+fun teardown(scenario: Scenario, treasury: Treasury<SUI>, clock: Clock) {
+    test_utils::destroy(treasury);
+    clock.destroy_for_testing();
+    scenario.end();
+}
+
+#[test]
+fun test_messages_match_rust() {
+    assert!(treasury::spend_message(0, RECIPIENT, AMOUNT, &b"grant") == SPEND_MESSAGE);
+    assert!(treasury::cancel_message(0) == CANCEL_MESSAGE);
+}
+
+#[test]
+fun test_spend_after_timelock() {
+    let mut scenario = ts::begin(SUBMITTER);
+    let (mut treasury, mut clock) = setup(&mut scenario);
+    let id = propose(&mut treasury, vector[0, 2], vector[SPEND_1, SPEND_3], &clock);
+    assert!(treasury.next_proposal_id() == 1);
+
+    clock.increment_for_testing(TIMELOCK_MS);
+    treasury.execute(id, &clock, scenario.ctx());
+    assert!(treasury.is_executed(id));
+    assert!(treasury.balance() == 600);
+
+    scenario.next_tx(RECIPIENT);
+    let payment = scenario.take_from_sender<Coin<SUI>>();
+    assert!(payment.value() == AMOUNT);
+    scenario.return_to_sender(payment);
+    teardown(scenario, treasury, clock);
+}
+
+#[test, expected_failure(abort_code = treasury::ETimelockActive)]
+fun test_execute_before_timelock() {
+    let mut scenario = ts::begin(SUBMITTER);
+    let (mut treasury, clock) = setup(&mut scenario);
+    let id = propose(&mut treasury, vector[0, 2], vector[SPEND_1, SPEND_3], &clock);
+    treasury.execute(id, &clock, scenario.ctx());
+    teardown(scenario, treasury, clock);
+}
+
+#[test, expected_failure(abort_code = treasury::EThresholdNotReached)]
+fun test_threshold() {
+    let mut scenario = ts::begin(SUBMITTER);
+    let (mut treasury, clock) = setup(&mut scenario);
+    propose(&mut treasury, vector[0], vector[SPEND_1], &clock);
+    teardown(scenario, treasury, clock);
+}
+
+#[test, expected_failure(abort_code = treasury::EInvalidSignature)]
+fun test_signature_from_other_signer() {
+    let mut scenario = ts::begin(SUBMITTER);
+    let (mut treasury, clock) = setup(&mut scenario);
+    // Signer 2 did not make the second signature
+    propose(&mut treasury, vector[0, 1], vector[SPEND_1, SPEND_3], &clock);
+    teardown(scenario, treasury, clock);
+}
+
+#[test, expected_failure(abort_code = treasury::ESignersNotAscending)]
+fun test_signer_counted_once() {
+    let mut scenario = ts::begin(SUBMITTER);
+    let (mut treasury, clock) = setup(&mut scenario);
+    propose(&mut treasury, vector[0, 0], vector[SPEND_1, SPEND_1], &clock);
+    teardown(scenario, treasury, clock);
+}
+
+#[test, expected_failure(abort_code = treasury::EProposalClosed)]
+fun test_cancelled_spend_not_executed() {
+    let mut scenario = ts::begin(SUBMITTER);
+    let (mut treasury, mut clock) = setup(&mut scenario);
+    let id = propose(&mut treasury, vector[0, 2], vector[SPEND_1, SPEND_3], &clock);
+    treasury.cancel(id, vector[0, 1], vector[CANCEL_1, CANCEL_2]);
+
+    clock.increment_for_testing(TIMELOCK_MS);
+    treasury.execute(id, &clock, scenario.ctx());
+    teardown(scenario, treasury, clock);
+}
+
+#[test, expected_failure(abort_code = treasury::EDuplicateSigner)]
+fun test_signers_distinct() {
+    let mut scenario = ts::begin(SUBMITTER);
+    let treasury = treasury::new_for_testing<SUI>(vector[KEY_1, KEY_1], 2, TIMELOCK_MS, scenario.ctx());
+    test_utils::destroy(treasury);
+    scenario.end();
+}
@@ -0,0 +1,73 @@
+//! The Romer Move framework.
+//!
+//! Move sources live in `packages/romer-framework` and are compiled by the
+//! build script, so the bytecode the VM loads at genesis always matches the
+//! natives and Rust types built alongside it.
+
+use move_core_types::account_address::AccountAddress;
+
+/// Address the framework package is published at
+pub const ROMER_FRAMEWORK_ADDRESS: AccountAddress = {
+    let mut address = [0u8; AccountAddress::LENGTH];
+    address[AccountAddress::LENGTH - 1] = 0x10;
+    AccountAddress::new(address)
+};
+
+/// Modules making up the framework package
+pub const FRAMEWORK_MODULES: [&str; 11] = [
+    "bridge",
+    "coins",
+    "context",
+    "events",
+    "faucet",
+    "fee_burn",
+    "oracle",
+    "orderbook",
+    "orders",
+    "settlement",
+    "treasury",
+];
+
+/// A compiled module embedded in the binary
+#[derive(Debug, Clone, Copy)]
+pub struct FrameworkModule {
+    /// Short hex address, e.g. "0x10"
+    pub address: &'static str,
+    pub name: &'static str,
+    pub bytes: &'static [u8],
+}
+
+include!(concat!(env!("OUT_DIR"), "/framework.rs"));
+
+/// Every embedded module, the Romer framework and its dependencies
+pub fn modules() -> &'static [FrameworkModule] {
+    MODULES
+}
+
+/// Only the modules of the Romer framework package itself
+pub fn framework_modules() -> impl Iterator<Item = &'static FrameworkModule> {
+    MODULES
+        .iter()
+        .filter(|m| AccountAddress::from_hex_literal(m.address).ok() == Some(ROMER_FRAMEWORK_ADDRESS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_binary_format::CompiledModule;
+
+    #[test]
+    fn test_framework_modules_embedded() {
+        let mut names: Vec<&str> = framework_modules().map(|m| m.name).collect();
+        names.sort();
+        assert_eq!(names, FRAMEWORK_MODULES);
+    }
+
+    #[test]
+    fn test_modules_deserialize() {
+        for module in modules() {
+            let compiled = CompiledModule::deserialize_with_defaults(module.bytes).unwrap();
+            assert_eq!(compiled.self_id().name().as_str(), module.name);
+        }
+    }
+}
@@ -0,0 +1,203 @@
+// src/attestation/registry.rs
+
+use dashmap::DashMap;
+use romer_common::types::address::Address;
+use romer_common::types::attestation::{Attestation, AttestationError, SignedAttestation};
+use romer_common::utils::clock::{system_clock, SharedClock};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use thiserror::Error;
+use tracing::info;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum AttestationRejection {
+    #[error("Validator {0} is not registered")]
+    Unregistered(Address),
+
+    #[error(transparent)]
+    Invalid(#[from] AttestationError),
+
+    #[error("Attestation issued at {issued_at} is not newer than the one held, issued at {latest}")]
+    Outdated { issued_at: u64, latest: u64 },
+}
+
+/// Attestation status of a validator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttestationState {
+    /// Holds an unexpired attestation whose location and hardware checks passed
+    Attested,
+    /// Holds an unexpired attestation in which a check failed
+    Failing,
+    /// The last attestation has expired
+    Expired,
+    /// Has never delivered an attestation
+    Missing,
+}
+
+/// What `get_attestation_status` returns for a validator
+#[derive(Debug, Clone, Serialize)]
+pub struct AttestationStatus {
+    pub validator: Address,
+    pub state: AttestationState,
+    /// The latest attestation delivered, if any
+    pub attestation: Option<Attestation>,
+}
+
+/// Latest signed location and hardware attestation of each registered
+/// validator, delivered over `submit_attestation` and queried by
+/// counterparties over `get_attestation_status`
+pub struct AttestationRegistry {
+    validators: BTreeSet<Address>,
+    attestations: DashMap<Address, SignedAttestation>,
+    clock: SharedClock,
+}
+
+impl AttestationRegistry {
+    /// Registry accepting attestations of `validators` only
+    pub fn new(validators: impl IntoIterator<Item = Address>) -> Self {
+        Self::with_clock(validators, system_clock())
+    }
+
+    pub fn with_clock(validators: impl IntoIterator<Item = Address>, clock: SharedClock) -> Self {
+        Self {
+            validators: validators.into_iter().collect(),
+            attestations: DashMap::new(),
+            clock,
+        }
+    }
+
+    /// Verifies and stores an attestation, replacing an older one of the
+    /// same validator
+    pub fn submit(&self, signed: SignedAttestation) -> Result<AttestationStatus, AttestationRejection> {
+        let validator = signed.attestation.validator;
+        if !self.validators.contains(&validator) {
+            return Err(AttestationRejection::Unregistered(validator));
+        }
+        signed.verify(self.clock.unix_secs())?;
+
+        let issued_at = signed.attestation.issued_at;
+        if let Some(latest) = self.attestations.get(&validator) {
+            let latest = latest.attestation.issued_at;
+            if issued_at <= latest {
+                return Err(AttestationRejection::Outdated { issued_at, latest });
+            }
+        }
+        info!(
+            %validator,
+            confidence = signed.attestation.location.confidence,
+            location_passed = signed.attestation.location.passed,
+            hardware_passed = signed.attestation.hardware.requirements_met,
+            "Received attestation"
+        );
+        self.attestations.insert(validator, signed);
+        Ok(self.status(&validator))
+    }
+
+    /// Current status of `validator`, which may be unregistered
+    pub fn status(&self, validator: &Address) -> AttestationStatus {
+        let attestation = self.attestations.get(validator).map(|signed| signed.attestation.clone());
+        let state = match &attestation {
+            None => AttestationState::Missing,
+            Some(attestation) if self.clock.unix_secs() > attestation.expires_at => AttestationState::Expired,
+            Some(attestation) if attestation.location.passed && attestation.hardware.requirements_met => {
+                AttestationState::Attested
+            }
+            Some(_) => AttestationState::Failing,
+        };
+        AttestationStatus {
+            validator: *validator,
+            state,
+            attestation,
+        }
+    }
+
+    /// Status of every registered validator
+    pub fn statuses(&self) -> Vec<AttestationStatus> {
+        self.validators.iter().map(|validator| self.status(validator)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use commonware_cryptography::{Ed25519, Scheme};
+    use romer_common::types::attestation::{HardwareAttestation, LocationAttestation};
+    use romer_common::types::keymanager::SignatureScheme;
+    use romer_common::utils::clock::ManualClock;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn address(signer: &Ed25519) -> Address {
+        Address::from_public_key(SignatureScheme::Ed25519, &signer.public_key())
+    }
+
+    fn attest(signer: &mut Ed25519, issued_at: u64, passed: bool) -> SignedAttestation {
+        Attestation {
+            validator: address(signer),
+            location: LocationAttestation {
+                latitude: 50.1109,
+                longitude: 8.6821,
+                confidence: if passed { 0.9 } else { 0.2 },
+                passed,
+            },
+            hardware: HardwareAttestation {
+                virtualization: None,
+                cpu_cores: Some(16),
+                memory_bytes: Some(64 << 30),
+                requirements_met: true,
+            },
+            issued_at,
+            expires_at: issued_at + 600,
+        }
+        .sign(SignatureScheme::Ed25519, signer)
+        .unwrap()
+    }
+
+    #[test]
+    fn test_attestation_lifecycle() {
+        let clock = Arc::new(ManualClock::new(Utc.timestamp_opt(1_000, 0).unwrap()));
+        let mut signer = Ed25519::from_seed(1);
+        let validator = address(&signer);
+        let registry = AttestationRegistry::with_clock([validator], clock.clone());
+        assert_eq!(registry.status(&validator).state, AttestationState::Missing);
+
+        let status = registry.submit(attest(&mut signer, 1_000, true)).unwrap();
+        assert_eq!(status.state, AttestationState::Attested);
+
+        // Replays and older attestations are refused
+        assert!(matches!(
+            registry.submit(attest(&mut signer, 1_000, true)),
+            Err(AttestationRejection::Outdated { .. })
+        ));
+
+        clock.advance(Duration::from_secs(10));
+        let status = registry.submit(attest(&mut signer, 1_010, false)).unwrap();
+        assert_eq!(status.state, AttestationState::Failing);
+
+        clock.advance(Duration::from_secs(700));
+        assert_eq!(registry.status(&validator).state, AttestationState::Expired);
+        assert_eq!(registry.statuses().len(), 1);
+    }
+
+    #[test]
+    fn test_rejects_unregistered_and_invalid() {
+        let clock = Arc::new(ManualClock::new(Utc.timestamp_opt(1_000, 0).unwrap()));
+        let mut signer = Ed25519::from_seed(1);
+        let mut stranger = Ed25519::from_seed(2);
+        let registry = AttestationRegistry::with_clock([address(&signer)], clock);
+
+        assert!(matches!(
+            registry.submit(attest(&mut stranger, 1_000, true)),
+            Err(AttestationRejection::Unregistered(_))
+        ));
+
+        let mut tampered = attest(&mut signer, 1_000, false);
+        tampered.attestation.location.passed = true;
+        assert_eq!(
+            registry.submit(tampered),
+            Err(AttestationRejection::Invalid(AttestationError::InvalidSignature))
+        );
+    }
+}
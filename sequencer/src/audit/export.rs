@@ -0,0 +1,328 @@
+// src/audit/export.rs
+
+use crate::events::types::SequencerEvent;
+use chrono::{DateTime, NaiveDate, Utc};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Column layout of the exported audit trail
+pub const CSV_HEADER: &str = "event_time,event_type,firm_id,sender_comp_id,session_id,msg_seq_num,symbol,price,quantity,counterparty_firm_id,block_id,block_hash,reason";
+
+#[derive(Error, Debug)]
+pub enum AuditExportError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Invalid audit log entry at line {line}: {reason}")]
+    InvalidEntry { line: usize, reason: String },
+}
+
+/// Produces per trading day order and trade audit trails from the raw event
+/// log. Output depends only on the log contents and the firm mapping, so
+/// regenerating a day always yields byte-identical files.
+pub struct AuditExporter {
+    /// SenderCompID to regulatory firm identifier (e.g. LEI)
+    firms: BTreeMap<String, String>,
+}
+
+impl AuditExporter {
+    pub fn new(firms: BTreeMap<String, String>) -> Self {
+        Self { firms }
+    }
+
+    /// Reads raw events written by the `AuditLog` subscriber
+    pub fn read_log(path: impl AsRef<Path>) -> Result<Vec<SequencerEvent>, AuditExportError> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut events = Vec::new();
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event = serde_json::from_str(&line).map_err(|e| AuditExportError::InvalidEntry {
+                line: index + 1,
+                reason: e.to_string(),
+            })?;
+            events.push(event);
+        }
+        Ok(events)
+    }
+
+    /// Groups events by UTC trading day, each day ordered by event time.
+    /// The sort is stable so events with equal timestamps keep log order.
+    pub fn by_day(events: Vec<SequencerEvent>) -> BTreeMap<NaiveDate, Vec<SequencerEvent>> {
+        let mut days: BTreeMap<NaiveDate, Vec<SequencerEvent>> = BTreeMap::new();
+        for event in events {
+            days.entry(event.at().date_naive()).or_default().push(event);
+        }
+        for events in days.values_mut() {
+            events.sort_by_key(|event| event.at());
+        }
+        days
+    }
+
+    /// Writes one trading day's audit trail as CSV
+    pub fn write_day<W: Write>(&self, events: &[SequencerEvent], writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "{}", CSV_HEADER)?;
+        for event in events {
+            writeln!(writer, "{}", self.row(event).join(","))?;
+        }
+        Ok(())
+    }
+
+    /// Exports every trading day in the log at `log` into `out_dir` as
+    /// `audit-YYYY-MM-DD.csv`, each with a `.sha256` digest alongside.
+    /// Returns the files written.
+    pub fn export(&self, log: impl AsRef<Path>, out_dir: impl AsRef<Path>) -> Result<Vec<PathBuf>, AuditExportError> {
+        let mut written = Vec::new();
+        for (day, events) in Self::by_day(Self::read_log(log)?) {
+            written.push(self.write_export(day, &events, out_dir.as_ref())?);
+        }
+        Ok(written)
+    }
+
+    /// Exports the single trading day `day` from the log at `log`. Returns
+    /// the file written, `None` if the log has no events that day.
+    pub fn export_day(
+        &self,
+        log: impl AsRef<Path>,
+        day: NaiveDate,
+        out_dir: impl AsRef<Path>,
+    ) -> Result<Option<PathBuf>, AuditExportError> {
+        match Self::by_day(Self::read_log(log)?).remove(&day) {
+            Some(events) => Ok(Some(self.write_export(day, &events, out_dir.as_ref())?)),
+            None => Ok(None),
+        }
+    }
+
+    fn write_export(&self, day: NaiveDate, events: &[SequencerEvent], out_dir: &Path) -> Result<PathBuf, AuditExportError> {
+        fs::create_dir_all(out_dir)?;
+        let mut contents = Vec::new();
+        self.write_day(events, &mut contents)?;
+
+        let path = out_dir.join(format!("audit-{}.csv", day.format("%Y-%m-%d")));
+        fs::write(&path, &contents)?;
+        fs::write(
+            path.with_extension("csv.sha256"),
+            format!("{}  {}\n", hex::encode(Sha256::digest(&contents)), file_name(&path)),
+        )?;
+        Ok(path)
+    }
+
+    fn firm(&self, sender_comp_id: &str) -> String {
+        self.firms
+            .get(sender_comp_id)
+            .cloned()
+            .unwrap_or_else(|| sender_comp_id.to_string())
+    }
+
+    fn row(&self, event: &SequencerEvent) -> Vec<String> {
+        // event_time, event_type, firm_id, sender_comp_id, session_id,
+        // msg_seq_num, symbol, price, quantity, counterparty_firm_id,
+        // block_id, block_hash, reason
+        let mut row = vec![String::new(); 13];
+        row[0] = format_time(event.at());
+        row[1] = event.kind().to_string();
+
+        match event {
+            SequencerEvent::OrderAccepted { sender_comp_id, msg_seq_num, .. } => {
+                row[2] = self.firm(sender_comp_id);
+                row[3] = sender_comp_id.clone();
+                row[5] = msg_seq_num.to_string();
+            }
+            SequencerEvent::OrderRejected { sender_comp_id, msg_seq_num, reason, .. } => {
+                row[2] = self.firm(sender_comp_id);
+                row[3] = sender_comp_id.clone();
+                row[5] = msg_seq_num.to_string();
+                row[12] = reason.clone();
+            }
+            SequencerEvent::Match { symbol, price, quantity, buyer, seller, .. } => {
+                row[2] = self.firm(buyer);
+                row[3] = buyer.clone();
+                row[6] = symbol.clone();
+                row[7] = price.to_string();
+                row[8] = quantity.to_string();
+                row[9] = self.firm(seller);
+            }
+            SequencerEvent::BlockSealed { block_id, block_hash, .. } => {
+                row[10] = block_id.to_string();
+                row[11] = block_hash.clone();
+            }
+            SequencerEvent::SessionOpened { session_id, sender_comp_id, .. } => {
+                row[2] = self.firm(sender_comp_id);
+                row[3] = sender_comp_id.clone();
+                row[4] = session_id.to_string();
+            }
+            SequencerEvent::SessionClosed { session_id, sender_comp_id, reason, .. } => {
+                row[2] = self.firm(sender_comp_id);
+                row[3] = sender_comp_id.clone();
+                row[4] = session_id.to_string();
+                row[12] = reason.clone();
+            }
+            SequencerEvent::KillSwitchEngaged { firm, triggered_by, reason, .. } => {
+                row[2] = firm.clone();
+                row[12] = format!("{} (by {})", reason, triggered_by);
+            }
+            SequencerEvent::KillSwitchReleased { firm, released_by, .. } => {
+                row[2] = firm.clone();
+                row[12] = format!("released by {}", released_by);
+            }
+            SequencerEvent::DrainModeEntered { reason, triggered_by, .. } => {
+                row[12] = format!("{} (by {})", reason, triggered_by);
+            }
+            SequencerEvent::DrainModeExited { released_by, .. } => {
+                row[12] = format!("released by {}", released_by);
+            }
+            SequencerEvent::LoadSheddingEntered { queue_latency_us, batch_latency_us, .. } => {
+                row[12] = format!("queue latency {}us, batch latency {}us", queue_latency_us, batch_latency_us);
+            }
+            SequencerEvent::LoadSheddingExited { shed, .. } => {
+                row[12] = format!("{} orders shed", shed);
+            }
+            SequencerEvent::BalanceChanged { address, balance, .. } => {
+                row[12] = format!("{} balance {}", address, balance);
+            }
+            SequencerEvent::ObligationEpochClosed { epoch, met, missed, .. } => {
+                row[12] = format!("epoch {}: met {}; missed {}", epoch, met.join(" "), missed.join(" "));
+            }
+            SequencerEvent::FeeTierChanged { firm, epoch, previous, tier, volume, .. } => {
+                row[2] = firm.clone();
+                row[8] = volume.to_string();
+                row[12] = format!("epoch {}: tier {} to {}", epoch, previous, tier);
+            }
+            SequencerEvent::AllocationAccepted { alloc_id, executing_firm, symbol, avg_price, allocations, .. } => {
+                row[2] = self.firm(executing_firm);
+                row[3] = executing_firm.clone();
+                row[6] = symbol.clone();
+                row[7] = avg_price.to_string();
+                row[8] = allocations.iter().map(|entry| entry.quantity).sum::<u64>().to_string();
+                row[12] = format!(
+                    "allocation {}: {}",
+                    alloc_id,
+                    allocations
+                        .iter()
+                        .map(|entry| format!("{} to {}/{}", entry.quantity, entry.clearing_firm, entry.account))
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                );
+            }
+            SequencerEvent::NewsPublished { headline, recipients, published_by, .. } => {
+                let to = if recipients.is_empty() { "all".to_string() } else { recipients.join(" ") };
+                row[12] = format!("news \"{}\" to {} (by {})", headline, to, published_by);
+            }
+            SequencerEvent::AdminAction { method, identity, role, outcome, .. } => {
+                let role = role.as_deref().unwrap_or("unidentified");
+                row[12] = format!("{} by {} ({}): {}", method, identity, role, outcome);
+            }
+            SequencerEvent::InstrumentOverrideScheduled { symbol, overrides, activation_height, source, .. } => {
+                row[6] = symbol.clone();
+                row[10] = activation_height.to_string();
+                row[12] = format!(
+                    "{} (by {})",
+                    serde_json::to_string(overrides).unwrap_or_default(),
+                    source
+                );
+            }
+        }
+
+        row.into_iter().map(|field| escape(&field)).collect()
+    }
+}
+
+/// UTC timestamp with microsecond precision
+fn format_time(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%dT%H:%M:%S%.6fZ").to_string()
+}
+
+/// Quotes a CSV field if it contains a delimiter, quote or newline
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(secs: i64, micros: u32) -> DateTime<Utc> {
+        Utc.timestamp_opt(secs, micros * 1_000).unwrap()
+    }
+
+    fn rejected(time: DateTime<Utc>, reason: &str) -> SequencerEvent {
+        SequencerEvent::OrderRejected {
+            sender_comp_id: "MM1".to_string(),
+            msg_seq_num: 7,
+            reason: reason.to_string(),
+            at: time,
+        }
+    }
+
+    #[test]
+    fn test_row_format() {
+        let exporter = AuditExporter::new(BTreeMap::from([(
+            "MM1".to_string(),
+            "5493001KJTIIGC8Y1R12".to_string(),
+        )]));
+        let mut out = Vec::new();
+        exporter
+            .write_day(&[rejected(at(1_700_000_000, 123_456), "bad price, too low")], &mut out)
+            .unwrap();
+
+        let csv = String::from_utf8(out).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some(CSV_HEADER));
+        assert_eq!(
+            lines.next(),
+            Some("2023-11-14T22:13:20.123456Z,order_rejected,5493001KJTIIGC8Y1R12,MM1,,7,,,,,,,\"bad price, too low\"")
+        );
+    }
+
+    #[test]
+    fn test_days_split_and_sorted() {
+        let day_one = 1_700_000_000;
+        let day_two = day_one + 86_400;
+        let days = AuditExporter::by_day(vec![
+            rejected(at(day_two, 0), "c"),
+            rejected(at(day_one, 5), "b"),
+            rejected(at(day_one, 1), "a"),
+        ]);
+
+        assert_eq!(days.len(), 2);
+        let first: Vec<_> = days.values().next().unwrap().iter().map(|e| e.at()).collect();
+        assert_eq!(first, vec![at(day_one, 1), at(day_one, 5)]);
+    }
+
+    #[test]
+    fn test_export_is_deterministic() {
+        let dir = std::env::temp_dir().join(format!("romer-audit-export-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("events.log");
+        let lines: Vec<String> = [rejected(at(1_700_000_000, 0), "a"), rejected(at(1_700_000_001, 0), "b")]
+            .iter()
+            .map(|e| serde_json::to_string(e).unwrap())
+            .collect();
+        fs::write(&log, lines.join("\n")).unwrap();
+
+        let exporter = AuditExporter::new(BTreeMap::new());
+        let first = exporter.export(&log, dir.join("one")).unwrap();
+        let second = exporter.export(&log, dir.join("two")).unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(fs::read(&first[0]).unwrap(), fs::read(&second[0]).unwrap());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
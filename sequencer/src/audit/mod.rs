@@ -0,0 +1,3 @@
+pub mod export;
+pub mod reconciliation;
+pub mod snapshots;
@@ -0,0 +1,616 @@
+// src/audit/reconciliation.rs
+
+use super::export::{AuditExportError, AuditExporter};
+use crate::config::ReconciliationConfig;
+use crate::events::types::SequencerEvent;
+use crate::market::calendar::MarketCalendar;
+use romer_common::fix::allocation::AllocSide;
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use parking_lot::Mutex;
+use romer_common::utils::clock::SharedClock;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::{error, info, warn};
+
+#[derive(Error, Debug)]
+pub enum ReconciliationError {
+    #[error("Failed to read the event log: {0}")]
+    Log(#[from] AuditExportError),
+
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Failed to encode report: {0}")]
+    Encode(#[from] serde_json::Error),
+
+    #[error("Delivery via {target} failed: {reason}")]
+    Delivery { target: String, reason: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FillSide {
+    Buy,
+    Sell,
+}
+
+/// One side of a fill, from the point of view of the reporting firm
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FillRecord {
+    pub at: DateTime<Utc>,
+    pub sender_comp_id: String,
+    pub symbol: String,
+    pub side: FillSide,
+    pub price: u64,
+    pub quantity: u64,
+    pub counterparty: String,
+    pub fee: u128,
+    /// AllocID of the give-up this fill records, which moves an
+    /// execution from the executing firm to a clearing firm
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allocation: Option<String>,
+}
+
+/// A firm's trading in one symbol over the day
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Position {
+    pub bought: u64,
+    pub sold: u64,
+    /// Bought less sold
+    pub net: i128,
+    pub buy_notional: u128,
+    pub sell_notional: u128,
+}
+
+/// A firm's day: its orders, fills, fees, the positions they leave and
+/// what it owes or is owed at settlement
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FirmReport {
+    pub day: NaiveDate,
+    pub firm: String,
+    pub sender_comp_ids: BTreeSet<String>,
+    pub orders_accepted: u64,
+    pub orders_rejected: u64,
+    pub fills: Vec<FillRecord>,
+    pub fees: u128,
+    pub positions: BTreeMap<String, Position>,
+    /// Day the settlement is due, by the settlement calendar if there is one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settlement_date: Option<NaiveDate>,
+    /// Cash due to the firm at settlement, negative when it pays: sales
+    /// less purchases less fees
+    pub settlement: i128,
+}
+
+impl FirmReport {
+    fn new(day: NaiveDate, firm: String) -> Self {
+        Self {
+            day,
+            firm,
+            sender_comp_ids: BTreeSet::new(),
+            orders_accepted: 0,
+            orders_rejected: 0,
+            fills: Vec::new(),
+            fees: 0,
+            positions: BTreeMap::new(),
+            settlement_date: None,
+            settlement: 0,
+        }
+    }
+
+    fn add_fill(&mut self, fill: FillRecord) {
+        let notional = fill.price as u128 * fill.quantity as u128;
+        let position = self.positions.entry(fill.symbol.clone()).or_default();
+        match fill.side {
+            FillSide::Buy => {
+                position.bought += fill.quantity;
+                position.net += fill.quantity as i128;
+                position.buy_notional += notional;
+                self.settlement -= notional as i128;
+            }
+            FillSide::Sell => {
+                position.sold += fill.quantity;
+                position.net -= fill.quantity as i128;
+                position.sell_notional += notional;
+                self.settlement += notional as i128;
+            }
+        }
+        self.fees += fill.fee;
+        self.settlement -= fill.fee as i128;
+        self.sender_comp_ids.insert(fill.sender_comp_id.clone());
+        self.fills.push(fill);
+    }
+}
+
+/// Builds per firm reports from a day's events. Output depends only on the
+/// events, the firm mapping, the fee rate and the settlement calendar, so a
+/// day can be rebuilt from the journal at any time with the same result.
+pub struct Reconciler {
+    /// SenderCompID to firm
+    firms: BTreeMap<String, String>,
+    fee_bps: u32,
+    calendar: Option<MarketCalendar>,
+}
+
+impl Reconciler {
+    pub fn new(firms: BTreeMap<String, String>, fee_bps: u32) -> Self {
+        Self {
+            firms,
+            fee_bps,
+            calendar: None,
+        }
+    }
+
+    /// Dates each report's settlement by `calendar`
+    pub fn with_calendar(mut self, calendar: MarketCalendar) -> Self {
+        self.calendar = Some(calendar);
+        self
+    }
+
+    fn firm(&self, sender_comp_id: &str) -> String {
+        self.firms
+            .get(sender_comp_id)
+            .cloned()
+            .unwrap_or_else(|| sender_comp_id.to_string())
+    }
+
+    fn fee(&self, price: u64, quantity: u64) -> u128 {
+        price as u128 * quantity as u128 * self.fee_bps as u128 / 10_000
+    }
+
+    fn report<'a>(
+        &self,
+        reports: &'a mut BTreeMap<String, FirmReport>,
+        day: NaiveDate,
+        sender_comp_id: &str,
+    ) -> &'a mut FirmReport {
+        let firm = self.firm(sender_comp_id);
+        let report = reports.entry(firm.clone()).or_insert_with(|| FirmReport {
+            settlement_date: self.calendar.as_ref().and_then(|calendar| calendar.settlement_date(day)),
+            ..FirmReport::new(day, firm)
+        });
+        report.sender_comp_ids.insert(sender_comp_id.to_string());
+        report
+    }
+
+    /// Reports of every firm active on `day`, by firm
+    pub fn reports(&self, day: NaiveDate, events: &[SequencerEvent]) -> Vec<FirmReport> {
+        let mut reports = BTreeMap::new();
+        for event in events.iter().filter(|event| event.at().date_naive() == day) {
+            match event {
+                SequencerEvent::OrderAccepted { sender_comp_id, .. } => {
+                    self.report(&mut reports, day, sender_comp_id).orders_accepted += 1;
+                }
+                SequencerEvent::OrderRejected { sender_comp_id, .. } => {
+                    self.report(&mut reports, day, sender_comp_id).orders_rejected += 1;
+                }
+                SequencerEvent::Match {
+                    symbol,
+                    price,
+                    quantity,
+                    buyer,
+                    seller,
+                    at,
+                    ..
+                } => {
+                    for (sender_comp_id, side, counterparty) in
+                        [(buyer, FillSide::Buy, seller), (seller, FillSide::Sell, buyer)]
+                    {
+                        let fill = FillRecord {
+                            at: *at,
+                            sender_comp_id: sender_comp_id.clone(),
+                            symbol: symbol.clone(),
+                            side,
+                            price: *price,
+                            quantity: *quantity,
+                            counterparty: self.firm(counterparty),
+                            fee: self.fee(*price, *quantity),
+                            allocation: None,
+                        };
+                        self.report(&mut reports, day, sender_comp_id).add_fill(fill);
+                    }
+                }
+                SequencerEvent::AllocationAccepted {
+                    alloc_id,
+                    executing_firm,
+                    symbol,
+                    side,
+                    avg_price,
+                    allocations,
+                    at,
+                } => {
+                    // The executing firm's side passes to each clearing
+                    // firm, without fees, along with what it settles
+                    let (given, taken) = match side {
+                        AllocSide::Buy => (FillSide::Sell, FillSide::Buy),
+                        AllocSide::Sell => (FillSide::Buy, FillSide::Sell),
+                    };
+                    for entry in allocations {
+                        for (sender_comp_id, side, counterparty) in [
+                            (executing_firm, given, &entry.clearing_firm),
+                            (&entry.clearing_firm, taken, executing_firm),
+                        ] {
+                            let fill = FillRecord {
+                                at: *at,
+                                sender_comp_id: sender_comp_id.clone(),
+                                symbol: symbol.clone(),
+                                side,
+                                price: *avg_price,
+                                quantity: entry.quantity,
+                                counterparty: self.firm(counterparty),
+                                fee: 0,
+                                allocation: Some(alloc_id.clone()),
+                            };
+                            self.report(&mut reports, day, sender_comp_id).add_fill(fill);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        reports.into_values().collect()
+    }
+}
+
+/// Hands a written report on, e.g. by email or to an SFTP server
+pub trait ReportDelivery: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Delivers `report`, already written to `path`
+    fn deliver(&self, report: &FirmReport, path: &Path) -> Result<(), ReconciliationError>;
+}
+
+/// Runs a program per report, substituting `{path}`, `{firm}` and `{day}`
+/// in its arguments, e.g. `curl -T {path} sftp://reports.example.com/{firm}/`
+pub struct CommandDelivery {
+    program: String,
+    args: Vec<String>,
+}
+
+impl CommandDelivery {
+    /// Program and arguments from `command`, `None` if it is empty
+    pub fn new(command: &[String]) -> Option<Self> {
+        let (program, args) = command.split_first()?;
+        Some(Self {
+            program: program.clone(),
+            args: args.to_vec(),
+        })
+    }
+
+    fn args(&self, report: &FirmReport, path: &Path) -> Vec<String> {
+        self.args
+            .iter()
+            .map(|arg| {
+                arg.replace("{path}", &path.to_string_lossy())
+                    .replace("{firm}", &report.firm)
+                    .replace("{day}", &report.day.to_string())
+            })
+            .collect()
+    }
+}
+
+impl ReportDelivery for CommandDelivery {
+    fn name(&self) -> &str {
+        &self.program
+    }
+
+    fn deliver(&self, report: &FirmReport, path: &Path) -> Result<(), ReconciliationError> {
+        let output = Command::new(&self.program).args(self.args(report, path)).output()?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(ReconciliationError::Delivery {
+                target: self.program.clone(),
+                reason: format!("{}: {}", output.status, String::from_utf8_lossy(&output.stderr).trim()),
+            })
+        }
+    }
+}
+
+/// Builds each day's reports from the event log at session close, writes
+/// them to `out_dir` as `recon-YYYY-MM-DD-<firm>.json` and hands them to
+/// every delivery. The latest reports are kept for the admin API.
+pub struct ReconciliationService {
+    reconciler: Reconciler,
+    log: PathBuf,
+    out_dir: PathBuf,
+    deliveries: Vec<Box<dyn ReportDelivery>>,
+    reports: Mutex<BTreeMap<NaiveDate, Vec<FirmReport>>>,
+}
+
+impl ReconciliationService {
+    pub fn new(reconciler: Reconciler, log: impl Into<PathBuf>, out_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            reconciler,
+            log: log.into(),
+            out_dir: out_dir.into(),
+            deliveries: Vec::new(),
+            reports: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn from_config(config: &ReconciliationConfig, log: &Path, storage_dir: &Path) -> Self {
+        let service = Self::new(
+            Reconciler::new(config.firms.clone(), config.fee_bps),
+            log,
+            config.out_dir(storage_dir),
+        );
+        match CommandDelivery::new(&config.deliver_command) {
+            Some(delivery) => service.with_delivery(Box::new(delivery)),
+            None => service,
+        }
+    }
+
+    pub fn with_calendar(mut self, calendar: MarketCalendar) -> Self {
+        self.reconciler.calendar = Some(calendar);
+        self
+    }
+
+    pub fn with_delivery(mut self, delivery: Box<dyn ReportDelivery>) -> Self {
+        self.deliveries.push(delivery);
+        self
+    }
+
+    /// Builds, writes and delivers the reports of `day`, replacing any
+    /// built before. A failed delivery is logged and does not stop the
+    /// others.
+    pub fn run_day(&self, day: NaiveDate) -> Result<Vec<FirmReport>, ReconciliationError> {
+        let events = AuditExporter::by_day(AuditExporter::read_log(&self.log)?)
+            .remove(&day)
+            .unwrap_or_default();
+        let reports = self.reconciler.reports(day, &events);
+
+        fs::create_dir_all(&self.out_dir)?;
+        for report in &reports {
+            let path = self.out_dir.join(format!("recon-{}-{}.json", day, file_safe(&report.firm)));
+            fs::write(&path, serde_json::to_vec_pretty(report)?)?;
+            for delivery in &self.deliveries {
+                if let Err(e) = delivery.deliver(report, &path) {
+                    warn!(firm = %report.firm, %day, delivery = delivery.name(), error = %e, "Failed to deliver reconciliation report");
+                }
+            }
+        }
+        info!(%day, firms = reports.len(), "Built reconciliation reports");
+        self.reports.lock().insert(day, reports.clone());
+        Ok(reports)
+    }
+
+    /// Reports built for `day`, the latest day built without, optionally
+    /// of one firm
+    pub fn reports(&self, day: Option<NaiveDate>, firm: Option<&str>) -> Option<Vec<FirmReport>> {
+        let reports = self.reports.lock();
+        let reports = match day {
+            Some(day) => reports.get(&day)?,
+            None => reports.values().next_back()?,
+        };
+        Some(
+            reports
+                .iter()
+                .filter(|report| firm.map_or(true, |firm| report.firm == firm))
+                .cloned()
+                .collect(),
+        )
+    }
+
+    /// Builds the day's reports at `session_close` UTC every day, forever
+    pub async fn run(self: Arc<Self>, session_close: NaiveTime, clock: SharedClock) {
+        loop {
+            let now = clock.now();
+            let close = next_close(now, session_close);
+            let wait = (close - now).to_std().unwrap_or_default();
+            tokio::time::sleep(wait).await;
+
+            let service = self.clone();
+            let day = close.date_naive();
+            match tokio::task::spawn_blocking(move || service.run_day(day)).await {
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => error!(%day, error = %e, "Failed to build reconciliation reports"),
+                Err(e) => error!(%day, error = %e, "Reconciliation task failed"),
+            }
+        }
+    }
+}
+
+/// The first `session_close` strictly after `now`
+fn next_close(now: DateTime<Utc>, session_close: NaiveTime) -> DateTime<Utc> {
+    let today = now.date_naive().and_time(session_close).and_utc();
+    if today > now {
+        today
+    } else {
+        today + Duration::days(1)
+    }
+}
+
+/// `firm` with anything but letters, digits, `-` and `_` replaced
+fn file_safe(firm: &str) -> String {
+    firm.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use romer_common::fix::allocation::AllocationEntry;
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 1, hour, 0, 0).unwrap()
+    }
+
+    fn day() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, 1).unwrap()
+    }
+
+    fn fill(buyer: &str, seller: &str, price: u64, quantity: u64, at: DateTime<Utc>) -> SequencerEvent {
+        SequencerEvent::Match {
+            symbol: "AAPL".to_string(),
+            price,
+            quantity,
+            buyer: buyer.to_string(),
+            seller: seller.to_string(),
+            maker: None,
+            buyer_account: None,
+            seller_account: None,
+            at,
+        }
+    }
+
+    fn events() -> Vec<SequencerEvent> {
+        vec![
+            SequencerEvent::OrderAccepted {
+                sender_comp_id: "MM1".to_string(),
+                msg_seq_num: 1,
+                at: at(10),
+            },
+            SequencerEvent::OrderRejected {
+                sender_comp_id: "MM2".to_string(),
+                msg_seq_num: 1,
+                reason: "price collar".to_string(),
+                at: at(10),
+            },
+            fill("MM1", "HF1", 100, 50, at(11)),
+            fill("HF1", "MM2", 110, 20, at(12)),
+            // The next day
+            fill("MM1", "HF1", 100, 1, at(12) + Duration::days(1)),
+        ]
+    }
+
+    #[test]
+    fn test_reports() {
+        let reconciler = Reconciler::new(
+            BTreeMap::from([("MM1".to_string(), "ACME".to_string()), ("MM2".to_string(), "ACME".to_string())]),
+            10,
+        );
+        let reports = reconciler.reports(day(), &events());
+        assert_eq!(reports.len(), 2);
+
+        let acme = &reports[0];
+        assert_eq!(acme.firm, "ACME");
+        assert_eq!(acme.sender_comp_ids, BTreeSet::from(["MM1".to_string(), "MM2".to_string()]));
+        assert_eq!((acme.orders_accepted, acme.orders_rejected), (1, 1));
+        assert_eq!(acme.fills.len(), 2);
+        let position = &acme.positions["AAPL"];
+        assert_eq!((position.bought, position.sold, position.net), (50, 20, 30));
+        // 10 bps of 5000 and of 2200
+        assert_eq!(acme.fees, 5 + 2);
+        assert_eq!(acme.settlement, 2200 - 5000 - 7);
+
+        let hedge_fund = &reports[1];
+        assert_eq!(hedge_fund.firm, "HF1");
+        assert_eq!(hedge_fund.positions["AAPL"].net, -30);
+        assert_eq!(hedge_fund.fills[0].counterparty, "ACME");
+        assert_eq!(hedge_fund.settlement, 5000 - 2200 - 7);
+        assert_eq!(hedge_fund.settlement_date, None);
+    }
+
+    #[test]
+    fn test_settlement_date_follows_calendar() {
+        let calendar = MarketCalendar {
+            open: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            close: NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+            holidays: BTreeSet::new(),
+            half_days: BTreeMap::new(),
+            settlement_holidays: BTreeSet::from([NaiveDate::from_ymd_opt(2024, 3, 4).unwrap()]),
+            settlement_days: 2,
+        };
+        let reports = Reconciler::new(BTreeMap::new(), 10)
+            .with_calendar(calendar)
+            .reports(day(), &events());
+        // Friday's trades skip the weekend and Monday's bank holiday
+        assert!(reports
+            .iter()
+            .all(|report| report.settlement_date == NaiveDate::from_ymd_opt(2024, 3, 6)));
+    }
+
+    #[test]
+    fn test_allocations_move_to_clearing_firms() {
+        let reconciler = Reconciler::new(BTreeMap::new(), 10);
+        let mut events = events();
+        events.push(SequencerEvent::AllocationAccepted {
+            alloc_id: "A1".to_string(),
+            executing_firm: "HF1".to_string(),
+            symbol: "AAPL".to_string(),
+            side: AllocSide::Buy,
+            avg_price: 100,
+            allocations: vec![AllocationEntry {
+                account: "FUND-1".to_string(),
+                clearing_firm: "CLR1".to_string(),
+                quantity: 30,
+            }],
+            at: at(16),
+        });
+        let reports = reconciler.reports(day(), &events);
+
+        let clearing = reports.iter().find(|report| report.firm == "CLR1").unwrap();
+        assert_eq!(clearing.positions["AAPL"].net, 30);
+        assert_eq!(clearing.settlement, -3000);
+        assert_eq!(clearing.fills[0].allocation.as_deref(), Some("A1"));
+        let hedge_fund = reports.iter().find(|report| report.firm == "HF1").unwrap();
+        assert_eq!(hedge_fund.positions["AAPL"].net, -60);
+        assert_eq!(hedge_fund.settlement, 5000 - 2200 - 7 + 3000);
+    }
+
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl ReportDelivery for Recorder {
+        fn name(&self) -> &str {
+            "recorder"
+        }
+
+        fn deliver(&self, report: &FirmReport, path: &Path) -> Result<(), ReconciliationError> {
+            assert!(path.exists());
+            self.0.lock().push(report.firm.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_run_day() {
+        let dir = std::env::temp_dir().join(format!("romer-recon-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("events.jsonl");
+        let lines: Vec<String> = events().iter().map(|event| serde_json::to_string(event).unwrap()).collect();
+        fs::write(&log, lines.join("\n")).unwrap();
+
+        let delivered = Arc::new(Mutex::new(Vec::new()));
+        let service = ReconciliationService::new(Reconciler::new(BTreeMap::new(), 0), &log, dir.join("out"))
+            .with_delivery(Box::new(Recorder(delivered.clone())));
+        assert!(service.reports(None, None).is_none());
+
+        let reports = service.run_day(day()).unwrap();
+        assert_eq!(reports.len(), 3);
+        assert_eq!(*delivered.lock(), vec!["HF1", "MM1", "MM2"]);
+        assert!(dir.join("out/recon-2024-03-01-MM1.json").exists());
+        assert_eq!(service.reports(None, Some("MM2")).unwrap()[0].settlement, 2200);
+        assert!(service.reports(Some(day() + Duration::days(1)), None).is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_next_close() {
+        let close = NaiveTime::from_hms_opt(21, 0, 0).unwrap();
+        assert_eq!(next_close(at(20), close), at(21));
+        assert_eq!(next_close(at(21), close), at(21) + Duration::days(1));
+        assert_eq!(file_safe("ACME Corp/EU"), "ACME_Corp_EU");
+    }
+
+    #[test]
+    fn test_command_arguments() {
+        let command = ["curl", "-T", "{path}", "sftp://host/{firm}/{day}/"].map(String::from);
+        let delivery = CommandDelivery::new(&command).unwrap();
+        let report = FirmReport::new(day(), "ACME".to_string());
+        assert_eq!(
+            delivery.args(&report, Path::new("/tmp/r.json")),
+            vec!["-T", "/tmp/r.json", "sftp://host/ACME/2024-03-01/"]
+        );
+        assert!(CommandDelivery::new(&[]).is_none());
+    }
+}
@@ -0,0 +1,166 @@
+// src/audit/snapshots.rs
+
+use crate::config::StateSnapshotConfig;
+use crate::events::bus::EventSink;
+use crate::events::types::SequencerEvent;
+use crate::market::data::MarketDataPublisher;
+use crate::market::depth::Level;
+use crate::risk::permissions::PermissionRegistry;
+use crate::rpc::handler::RpcState;
+use romer_common::types::environment::ExecutionEnvironment;
+use romer_common::types::genesis::GenesisError;
+use romer_common::types::snapshot::{BookLevels, StateSnapshot};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::{debug, error};
+
+/// Writes a `StateSnapshot` every `interval_blocks` sealed blocks, keeping
+/// the newest `keep`, so `state diff` can show what changed between two
+/// heights when a validator disagrees with the sequencer
+pub struct StateSnapshotter {
+    state: Arc<RpcState>,
+    permissions: Option<Arc<PermissionRegistry>>,
+    market_data: Arc<MarketDataPublisher>,
+    environment: ExecutionEnvironment,
+    directory: PathBuf,
+    interval_blocks: u64,
+    keep: usize,
+}
+
+impl StateSnapshotter {
+    pub fn new(
+        config: &StateSnapshotConfig,
+        directory: PathBuf,
+        state: Arc<RpcState>,
+        market_data: Arc<MarketDataPublisher>,
+        environment: ExecutionEnvironment,
+    ) -> Self {
+        Self {
+            state,
+            permissions: None,
+            market_data,
+            environment,
+            directory,
+            interval_blocks: config.interval_blocks.max(1),
+            keep: config.keep,
+        }
+    }
+
+    /// Includes the organizations `permissions` holds
+    pub fn with_permissions(mut self, permissions: Arc<PermissionRegistry>) -> Self {
+        self.permissions = Some(permissions);
+        self
+    }
+
+    /// Snapshot of the state once the block at `height` was sealed
+    pub fn snapshot(&self, height: u64) -> Result<StateSnapshot, GenesisError> {
+        let mut state = self.state.export_genesis(self.environment);
+        if let Some(permissions) = &self.permissions {
+            state.organizations = permissions.organizations();
+        }
+        let books = self
+            .market_data
+            .depths()
+            .into_iter()
+            .map(|depth| {
+                let levels = |levels: &[Level]| levels.iter().map(|level| (level.price, level.size)).collect();
+                let book = BookLevels {
+                    bids: levels(&depth.bids),
+                    asks: levels(&depth.asks),
+                };
+                (depth.symbol, book)
+            })
+            .collect();
+        Ok(StateSnapshot {
+            height,
+            state: state.seal()?,
+            books,
+        })
+    }
+
+    fn write(&self, height: u64) -> Result<(), GenesisError> {
+        let path = self.snapshot(height)?.write(&self.directory)?;
+        debug!(height, path = %path.display(), "Wrote state snapshot");
+        let heights = StateSnapshot::heights(&self.directory)?;
+        for stale in &heights[..heights.len().saturating_sub(self.keep)] {
+            std::fs::remove_file(StateSnapshot::path(&self.directory, *stale))?;
+        }
+        Ok(())
+    }
+}
+
+impl EventSink for StateSnapshotter {
+    fn name(&self) -> &str {
+        "state_snapshots"
+    }
+
+    fn handle(&mut self, event: &SequencerEvent) {
+        if let SequencerEvent::BlockSealed { block_id, .. } = event {
+            if block_id % self.interval_blocks == 0 {
+                if let Err(e) = self.write(*block_id) {
+                    error!(height = block_id, error = %e, "Failed to write state snapshot");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::data::{BookSide, BookUpdate};
+    use chrono::Utc;
+    use romer_common::types::address::Address;
+    use romer_common::types::snapshot::StateDiff;
+
+    fn sealed(block_id: u64) -> SequencerEvent {
+        SequencerEvent::BlockSealed {
+            block_id,
+            block_hash: String::new(),
+            message_count: 0,
+            transaction_count: 0,
+            at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_snapshots_every_interval() {
+        let directory = std::env::temp_dir().join(format!("romer-state-snapshots-{}", std::process::id()));
+        let state = Arc::new(RpcState::new());
+        let market_data = Arc::new(MarketDataPublisher::default());
+        let config = StateSnapshotConfig {
+            interval_blocks: 10,
+            directory: None,
+            keep: 2,
+        };
+        let mut snapshotter = StateSnapshotter::new(
+            &config,
+            directory.clone(),
+            state.clone(),
+            market_data.clone(),
+            ExecutionEnvironment::Development,
+        );
+
+        state.set_balance(Address::new([1u8; 32]), 100);
+        snapshotter.handle(&sealed(10));
+        snapshotter.handle(&sealed(15));
+        snapshotter.handle(&sealed(20));
+        state.set_balance(Address::new([1u8; 32]), 80);
+        market_data.publish(&BookUpdate {
+            symbol: "ROMER-USD".into(),
+            side: BookSide::Bid,
+            price: 100,
+            size: 5,
+        });
+        snapshotter.handle(&sealed(30));
+        assert_eq!(StateSnapshot::heights(&directory).unwrap(), vec![20, 30]);
+
+        let diff = StateDiff::between(
+            &StateSnapshot::read(&directory, 20).unwrap(),
+            &StateSnapshot::read(&directory, 30).unwrap(),
+        );
+        assert_eq!((diff.balances[0].before, diff.balances[0].after), (Some(100), Some(80)));
+        assert_eq!(diff.books.len(), 1);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -3,6 +3,7 @@ use tokio::time::{self, Duration, Instant};
 use crate::fix::types::ValidatedMessage;
 use std::sync::Arc;
 use parking_lot::Mutex;
+use romer_common::utils::clock::{system_clock, SharedClock};
 
 /// Represents a collection of FIX messages ready to be formed into a block
 #[derive(Debug, Clone)]
@@ -18,6 +19,7 @@ pub struct MessageBatch {
 }
 
 /// Manages the collection of FIX messages into batches
+#[derive(Clone)]
 pub struct BatchManager {
     /// Currently accumulating messages
     current_batch: Arc<Mutex<Vec<ValidatedMessage>>>,
@@ -31,6 +33,8 @@ pub struct BatchManager {
     max_batch_time: Duration,
     /// Current batch sequence number
     sequence: Arc<Mutex<u64>>,
+    /// Time source for batch windows
+    clock: SharedClock,
 }
 
 impl BatchManager {
@@ -39,17 +43,32 @@ impl BatchManager {
         batch_sender: mpsc::Sender<MessageBatch>,
         max_batch_size: usize,
         max_batch_time: Duration,
+    ) -> Self {
+        Self::with_clock(batch_sender, max_batch_size, max_batch_time, system_clock())
+    }
+
+    /// Create a batch manager driven by `clock`
+    pub fn with_clock(
+        batch_sender: mpsc::Sender<MessageBatch>,
+        max_batch_size: usize,
+        max_batch_time: Duration,
+        clock: SharedClock,
     ) -> Self {
         Self {
             current_batch: Arc::new(Mutex::new(Vec::with_capacity(max_batch_size))),
-            batch_start: Arc::new(Mutex::new(Instant::now())),
+            batch_start: Arc::new(Mutex::new(Instant::from_std(clock.instant()))),
             batch_sender,
             max_batch_size,
             max_batch_time,
             sequence: Arc::new(Mutex::new(0)),
+            clock,
         }
     }
 
+    fn now(&self) -> Instant {
+        Instant::from_std(self.clock.instant())
+    }
+
     /// Start the batch management process
     pub async fn run(&self) {
         let mut interval = time::interval(Duration::from_millis(10));
@@ -76,41 +95,54 @@ impl BatchManager {
     /// Check if the current batch should be flushed based on time
     async fn check_batch(&self) {
         let start = *self.batch_start.lock();
-        if start.elapsed() >= self.max_batch_time {
+        if self.now() - start >= self.max_batch_time {
             self.flush_batch().await;
         }
     }
 
     /// Flush the current batch and start a new one
     async fn flush_batch(&self) {
-        let mut batch = self.current_batch.lock();
         // Only create a batch if we have messages
-        if !batch.is_empty() {
-            let messages = std::mem::replace(batch.deref_mut(), Vec::with_capacity(self.max_batch_size));
-            let start_time = *self.batch_start.lock();
-            let end_time = Instant::now();
-            
-            // Get sequence number and increment
-            let sequence = {
-                let mut seq = self.sequence.lock();
-                let current = *seq;
-                *seq += 1;
-                current
-            };
-
-            let message_batch = MessageBatch {
-                messages,
-                start_time,
-                end_time,
-                sequence,
-            };
-
-            // Send the batch, ignoring errors if receiver is closed
-            let _ = self.batch_sender.send(message_batch).await;
+        if self.current_batch.lock().is_empty() {
+            *self.batch_start.lock() = self.now();
+            return;
+        }
+        // Send the batch, ignoring errors if receiver is closed
+        let _ = self.batch_sender.send(self.take_batch()).await;
+    }
+
+    /// Takes the messages collected so far, possibly none, as the next
+    /// batch and starts a new one
+    pub fn take_batch(&self) -> MessageBatch {
+        let messages = std::mem::replace(
+            &mut *self.current_batch.lock(),
+            Vec::with_capacity(self.max_batch_size),
+        );
+        let end_time = self.now();
+        let start_time = std::mem::replace(&mut *self.batch_start.lock(), end_time);
+
+        // Get sequence number and increment
+        let sequence = {
+            let mut seq = self.sequence.lock();
+            let current = *seq;
+            *seq += 1;
+            current
+        };
+
+        MessageBatch {
+            messages,
+            start_time,
+            end_time,
+            sequence,
         }
+    }
 
-        // Reset the batch start time
-        *self.batch_start.lock() = Instant::now();
+    /// Puts the messages of a batch that could not be sealed back in front
+    /// of those collected since
+    pub fn restore(&self, messages: Vec<ValidatedMessage>) {
+        let mut batch = self.current_batch.lock();
+        let newer = std::mem::replace(&mut *batch, messages);
+        batch.extend(newer);
     }
 }
 
@@ -124,10 +156,11 @@ mod tests {
         // Create a simple test message
         ValidatedMessage {
             msg_type: MessageType::NewOrderSingle,
-            message: fefix::tagvalue::Message::new(fefix::Dictionary::fix42()),
             sender_comp_id: "SENDER".to_string(),
             target_comp_id: "TARGET".to_string(),
             msg_seq_num: 1,
+            fields: Default::default(),
+            raw: Vec::new(),
         }
     }
 
@@ -1,8 +1,35 @@
 use crate::fix::types::ValidatedMessage;
 use super::batch::MessageBatch;
+use romer_common::types::envelope::{EnvelopeError, SignedTransaction};
 use sha2::{Sha256, Digest};
 use chrono::{DateTime, Utc};
+use romer_common::types::receipt::{Receipt, ReceiptWithProof};
+use romer_common::utils::merkle::{merkle_root, MerkleProof};
+use romer_common::light::header::GENESIS_PREVIOUS_HASH;
+pub use romer_common::light::header::BlockHeader;
+use romer_common::types::protocol::{ProtocolError, ProtocolSchedule, SUPPORTED_PROTOCOL_VERSION};
+use romer_common::utils::clock::{system_clock, SharedClock};
+use crate::events::bus::EventBus;
+use crate::events::types::SequencerEvent;
+use crate::market::oracle::OracleAggregator;
+use super::clock_quality::{ClockConfidence, ClockMonitor, ClockQualityError};
+use romer_common::types::oracle::OraclePrice;
+use std::sync::Arc;
 use serde::{Serialize, Deserialize};
+use thiserror::Error;
+use tracing::warn;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum BuildError {
+    #[error(transparent)]
+    Protocol(#[from] ProtocolError),
+
+    #[error(transparent)]
+    Clock(#[from] ClockQualityError),
+
+    #[error(transparent)]
+    Transaction(#[from] EnvelopeError),
+}
 
 /// Represents a complete block ready for the builder service
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,25 +38,41 @@ pub struct Block {
     pub header: BlockHeader,
     /// The FIX messages contained in this block
     pub messages: Vec<ValidatedMessage>,
+    /// Direct transactions taken from the mempool
+    #[serde(default)]
+    pub transactions: Vec<SignedTransaction>,
+    /// Receipt of each direct transaction, in the same order
+    #[serde(default)]
+    pub receipts: Vec<Receipt>,
+    /// Aggregated oracle prices, written into the `romer::oracle` module
+    /// before the block's transactions execute
+    #[serde(default)]
+    pub oracle_prices: Vec<OraclePrice>,
+    /// How far the clock stamping the block could be off, when monitored.
+    /// Not part of the header, as each node measures its own clock.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clock_confidence: Option<ClockConfidence>,
     /// Hash of the block's contents
     pub block_hash: String,
 }
 
-/// Contains metadata about the block
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct BlockHeader {
-    /// Unique identifier for this block
-    pub block_id: u64,
-    /// Hash of the previous block
-    pub previous_hash: String,
-    /// When this block was created
-    pub timestamp: DateTime<Utc>,
-    /// Number of messages in the block
-    pub message_count: usize,
-    /// Merkle root of the messages
-    pub messages_root: String,
-    /// Sequence number from the batch
-    pub batch_sequence: u64,
+impl Block {
+    /// Receipt of the transaction at `index`, with the proof it is under
+    /// the header's transactions root
+    pub fn receipt_with_proof(&self, index: usize) -> Option<ReceiptWithProof> {
+        let receipt = self.receipts.get(index)?.clone();
+        let leaves = self
+            .transactions
+            .iter()
+            .map(SignedTransaction::digest)
+            .collect::<Result<Vec<_>, _>>()
+            .ok()?;
+        Some(ReceiptWithProof {
+            receipt,
+            transactions_root: merkle_root(&leaves),
+            proof: MerkleProof::generate(&leaves, index)?,
+        })
+    }
 }
 
 /// Responsible for constructing blocks from message batches
@@ -38,30 +81,145 @@ pub struct BlockBuilder {
     previous_hash: String,
     /// The current block number
     current_block_id: u64,
+    /// Timestamp of the most recent block
+    last_timestamp: Option<DateTime<Utc>>,
+    /// Time source for block timestamps
+    clock: SharedClock,
+    /// Sealed blocks are announced here
+    events: EventBus,
+    /// Heights protocol upgrades activate at
+    protocol: ProtocolSchedule,
+    /// Blocks ahead of an unsupported activation that warnings start
+    protocol_warn_blocks: u64,
+    /// Source of the oracle prices each block writes
+    oracle: Option<Arc<OracleAggregator>>,
+    /// Judges the clock before each block is stamped
+    clock_monitor: Option<Arc<ClockMonitor>>,
 }
 
 impl BlockBuilder {
     pub fn new() -> Self {
+        Self::with_clock(system_clock())
+    }
+
+    /// Create a block builder taking timestamps from `clock`
+    pub fn with_clock(clock: SharedClock) -> Self {
         Self {
             // Initialize with genesis block hash
-            previous_hash: "0000000000000000000000000000000000000000000000000000000000000000".to_string(),
+            previous_hash: GENESIS_PREVIOUS_HASH.to_string(),
             current_block_id: 0,
+            last_timestamp: None,
+            clock,
+            events: EventBus::default(),
+            protocol: ProtocolSchedule::default(),
+            protocol_warn_blocks: 0,
+            oracle: None,
+            clock_monitor: None,
         }
     }
 
+    /// Continue the chain after the block at `height - 1` hashing to
+    /// `previous_hash`, as when starting from a genesis bundle
+    pub fn starting_at(mut self, height: u64, previous_hash: String) -> Self {
+        self.current_block_id = height;
+        self.previous_hash = previous_hash;
+        self
+    }
+
+    /// Publish a `BlockSealed` event on `events` for every block built
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Stamp blocks with the protocol version `schedule` puts in force at
+    /// their height, warning `warn_blocks` ahead of an activation this
+    /// binary does not support
+    pub fn with_protocol(mut self, schedule: ProtocolSchedule, warn_blocks: u64) -> Self {
+        self.protocol = schedule;
+        self.protocol_warn_blocks = warn_blocks;
+        self
+    }
+
+    /// Write the prices `oracle` aggregates into every block
+    pub fn with_oracle(mut self, oracle: Arc<OracleAggregator>) -> Self {
+        self.oracle = Some(oracle);
+        self
+    }
+
+    /// Refuse to build blocks while `monitor` does not trust the clock, and
+    /// annotate the rest with its confidence
+    pub fn with_clock_monitor(mut self, monitor: Arc<ClockMonitor>) -> Self {
+        self.clock_monitor = Some(monitor);
+        self
+    }
+
+    /// Timestamp for the next block. Block timestamps never go backwards,
+    /// even if the clock does.
+    fn next_timestamp(&mut self) -> DateTime<Utc> {
+        let now = self.clock.now();
+        let timestamp = match self.last_timestamp {
+            Some(last) if last > now => last,
+            _ => now,
+        };
+        self.last_timestamp = Some(timestamp);
+        timestamp
+    }
+
     /// Build a new block from a batch of messages
-    pub fn build_block(&mut self, batch: MessageBatch) -> Block {
+    pub fn build_block(&mut self, batch: MessageBatch) -> Result<Block, BuildError> {
+        self.build_block_with_transactions(batch, Vec::new())
+    }
+
+    /// Build a new block from a batch of FIX messages plus direct
+    /// transactions selected from the mempool. Fails once the protocol
+    /// version in force is newer than this binary supports, or while the
+    /// clock has drifted beyond its bound.
+    pub fn build_block_with_transactions(
+        &mut self,
+        batch: MessageBatch,
+        transactions: Vec<SignedTransaction>,
+    ) -> Result<Block, BuildError> {
+        let height = self.current_block_id;
+        let protocol_version = self.protocol.check(height, SUPPORTED_PROTOCOL_VERSION)?;
+        let clock_confidence = self.clock_monitor.as_ref().map(|monitor| monitor.check()).transpose()?;
+        if let Some(activation) =
+            self.protocol
+                .pending_unsupported(height, SUPPORTED_PROTOCOL_VERSION, self.protocol_warn_blocks)
+        {
+            warn!(
+                version = activation.version,
+                activation_height = activation.height,
+                blocks_remaining = activation.height - height,
+                supported = SUPPORTED_PROTOCOL_VERSION,
+                "Upgrade this binary before the pending protocol version activates"
+            );
+        }
+
         // Calculate the merkle root of messages
         let messages_root = self.calculate_messages_root(&batch.messages);
+        let transactions_root = self.calculate_transactions_root(&transactions)?;
+        let timestamp = self.next_timestamp();
+        let oracle_prices = match &self.oracle {
+            Some(oracle) => oracle.aggregate(timestamp.timestamp_millis().max(0) as u64),
+            None => Vec::new(),
+        };
 
         // Create the block header
         let header = BlockHeader {
             block_id: self.current_block_id,
             previous_hash: self.previous_hash.clone(),
-            timestamp: Utc::now(),
+            timestamp,
             message_count: batch.messages.len(),
             messages_root,
+            transaction_count: transactions.len(),
+            transactions_root,
             batch_sequence: batch.sequence,
+            protocol_version,
+            // Blocks are not executed here, so they commit to no state
+            state_root: String::new(),
+            oracle_root: self.calculate_oracle_root(&oracle_prices),
+            validators_digest: String::new(),
         };
 
         // Calculate block hash
@@ -71,12 +229,31 @@ impl BlockBuilder {
         self.previous_hash = block_hash.clone();
         self.current_block_id += 1;
 
+        self.events.publish(SequencerEvent::BlockSealed {
+            block_id: header.block_id,
+            block_hash: block_hash.clone(),
+            message_count: header.message_count,
+            transaction_count: header.transaction_count,
+            at: header.timestamp,
+        });
+
+        // Direct transactions are not executed here, so no gas is metered
+        let receipts = transactions
+            .iter()
+            .enumerate()
+            .map(|(index, tx)| Receipt::success(tx, header.block_id, index, 0))
+            .collect::<Result<_, _>>()?;
+
         // Construct and return the full block
-        Block {
+        Ok(Block {
             header,
             messages: batch.messages,
+            transactions,
+            receipts,
+            oracle_prices,
+            clock_confidence,
             block_hash,
-        }
+        })
     }
 
     /// Calculate the merkle root of the messages
@@ -95,19 +272,29 @@ impl BlockBuilder {
         hex::encode(hasher.finalize())
     }
 
+    /// Calculate the Merkle root of the direct transactions, which their
+    /// receipts' inclusion proofs are checked against
+    fn calculate_transactions_root(&self, transactions: &[SignedTransaction]) -> Result<String, EnvelopeError> {
+        let leaves = transactions
+            .iter()
+            .map(SignedTransaction::digest)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(hex::encode(merkle_root(&leaves)))
+    }
+
+    /// SHA-256 of the oracle prices' JSON encoding, empty without prices so
+    /// blocks without them hash as before
+    fn calculate_oracle_root(&self, prices: &[OraclePrice]) -> String {
+        if prices.is_empty() {
+            return String::new();
+        }
+        let encoded = serde_json::to_vec(prices).expect("oracle prices serialize");
+        hex::encode(Sha256::digest(encoded))
+    }
+
     /// Calculate the hash of the block
     fn calculate_block_hash(&self, header: &BlockHeader) -> String {
-        let mut hasher = Sha256::new();
-        
-        // Hash key header fields
-        hasher.update(header.block_id.to_le_bytes());
-        hasher.update(header.previous_hash.as_bytes());
-        hasher.update(header.timestamp.timestamp().to_le_bytes());
-        hasher.update(header.message_count.to_le_bytes());
-        hasher.update(header.messages_root.as_bytes());
-        hasher.update(header.batch_sequence.to_le_bytes());
-
-        hex::encode(hasher.finalize())
+        header.hash()
     }
 
     /// Verify a block's integrity
@@ -129,6 +316,17 @@ impl BlockBuilder {
             return false;
         }
 
+        if self.calculate_oracle_root(&block.oracle_prices) != block.header.oracle_root {
+            return false;
+        }
+
+        // Verify direct transactions
+        if block.transactions.len() != block.header.transaction_count
+            || self.calculate_transactions_root(&block.transactions).ok().as_ref() != Some(&block.header.transactions_root)
+        {
+            return false;
+        }
+
         true
     }
 }
@@ -159,10 +357,11 @@ mod tests {
     fn create_test_message(seq: u64) -> ValidatedMessage {
         ValidatedMessage {
             msg_type: MessageType::NewOrderSingle,
-            message: fefix::tagvalue::Message::new(fefix::Dictionary::fix42()),
             sender_comp_id: "SENDER".to_string(),
             target_comp_id: "TARGET".to_string(),
             msg_seq_num: seq,
+            fields: Default::default(),
+            raw: Vec::new(),
         }
     }
 
@@ -187,7 +386,7 @@ mod tests {
         let batch = create_test_batch(0, 5);
         
         // Build a block
-        let block = builder.build_block(batch);
+        let block = builder.build_block(batch).unwrap();
         
         // Verify the block
         assert!(builder.verify_block(&block));
@@ -200,11 +399,164 @@ mod tests {
         let mut builder = BlockBuilder::new();
         
         // Create two sequential blocks
-        let block1 = builder.build_block(create_test_batch(0, 3));
-        let block2 = builder.build_block(create_test_batch(1, 3));
+        let block1 = builder.build_block(create_test_batch(0, 3)).unwrap();
+        let block2 = builder.build_block(create_test_batch(1, 3)).unwrap();
         
         // Verify sequential properties
         assert_eq!(block2.header.previous_hash, block1.block_hash);
         assert_eq!(block2.header.block_id, 1);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_light_client_follows_blocks() {
+        use romer_common::light::verify_chain;
+
+        let mut builder = BlockBuilder::new();
+        let blocks: Vec<Block> = (0..3)
+            .map(|sequence| builder.build_block(create_test_batch(sequence, 2)).unwrap())
+            .collect();
+        let headers: Vec<BlockHeader> = blocks.iter().map(|block| block.header.clone()).collect();
+        assert!(blocks.iter().all(|block| block.block_hash == block.header.hash()));
+        assert!(verify_chain(&headers[0], &headers[1..]).is_ok());
+    }
+
+    #[test]
+    fn test_timestamps_from_clock_never_go_backwards() {
+        use romer_common::utils::clock::ManualClock;
+        use std::sync::Arc;
+
+        let start = Utc::now();
+        let clock = Arc::new(ManualClock::new(start));
+        let mut builder = BlockBuilder::with_clock(clock.clone());
+
+        let block1 = builder.build_block(create_test_batch(0, 1)).unwrap();
+        assert_eq!(block1.header.timestamp, start);
+
+        clock.set(start - chrono::Duration::seconds(5));
+        let block2 = builder.build_block(create_test_batch(1, 1)).unwrap();
+        assert_eq!(block2.header.timestamp, start);
+    }
+
+    #[test]
+    fn test_block_with_direct_transactions() {
+        use commonware_cryptography::{Ed25519, Scheme};
+        use romer_common::types::address::Address;
+        use romer_common::types::envelope::{TransactionPayload, UnsignedTransaction, DEFAULT_CHAIN_ID};
+        use romer_common::types::keymanager::SignatureScheme;
+
+        let mut signer = Ed25519::from_seed(1);
+        let tx = UnsignedTransaction {
+            chain_id: DEFAULT_CHAIN_ID.to_string(),
+            payload: TransactionPayload::Transfer { to: Address::new([1u8; 32]), amount: 1 },
+            sender: Address::from_public_key(SignatureScheme::Ed25519, &signer.public_key()),
+            nonce: 0,
+            fee: 1,
+            expiry: u64::MAX,
+        }
+        .sign(SignatureScheme::Ed25519, &mut signer)
+        .unwrap();
+
+        let mut builder = BlockBuilder::new();
+        let mut block = builder.build_block_with_transactions(create_test_batch(0, 2), vec![tx]).unwrap();
+        assert_eq!(block.header.transaction_count, 1);
+        assert!(builder.verify_block(&block));
+
+        let proven = block.receipt_with_proof(0).unwrap();
+        assert_eq!(hex::encode(proven.transactions_root), block.header.transactions_root);
+        assert!(proven.verify(&proven.transactions_root));
+        assert!(block.receipt_with_proof(1).is_none());
+
+        block.transactions.clear();
+        assert!(!builder.verify_block(&block));
+    }
+
+    #[test]
+    fn test_block_writes_oracle_prices() {
+        use crate::market::oracle::OracleAggregator;
+        use commonware_cryptography::{Ed25519, Scheme};
+        use romer_common::types::oracle::{PriceSubmission, SignedPriceSubmission};
+        use romer_common::utils::clock::{Clock, ManualClock};
+        use std::time::Duration;
+
+        let clock = Arc::new(ManualClock::new(Utc::now()));
+        let mut feeder = Ed25519::from_seed(1);
+        let feeders = [(hex::encode(feeder.public_key()), "feeder".to_string())].into_iter().collect();
+        let oracle = Arc::new(OracleAggregator::new(feeders, Duration::from_secs(10), 1, 100, clock.clone()));
+        let mut builder = BlockBuilder::with_clock(clock.clone()).with_oracle(oracle.clone());
+
+        let empty = builder.build_block(create_test_batch(0, 1)).unwrap();
+        assert!(empty.oracle_prices.is_empty());
+        assert!(empty.header.oracle_root.is_empty());
+
+        let submission = PriceSubmission { symbol: "BTC/USD".into(), price: 64_000, observed_at_ms: clock.unix_millis() };
+        oracle.submit(SignedPriceSubmission::sign(submission, &mut feeder)).unwrap();
+        let mut block = builder.build_block(create_test_batch(1, 1)).unwrap();
+        assert_eq!(block.oracle_prices.len(), 1);
+        assert_eq!(block.oracle_prices[0].timestamp_ms, clock.unix_millis());
+        assert!(builder.verify_block(&block));
+
+        block.oracle_prices[0].price += 1;
+        assert!(!builder.verify_block(&block));
+    }
+
+    #[test]
+    fn test_protocol_version_by_height() {
+        use romer_common::types::protocol::{Activation, GENESIS_PROTOCOL_VERSION};
+
+        let schedule = ProtocolSchedule {
+            activations: vec![
+                Activation { version: SUPPORTED_PROTOCOL_VERSION + 1, height: 2, description: String::new() },
+            ],
+        };
+        let mut builder = BlockBuilder::new().with_protocol(schedule, 10);
+        let block = builder.build_block(create_test_batch(0, 1)).unwrap();
+        assert_eq!(block.header.protocol_version, GENESIS_PROTOCOL_VERSION);
+        assert!(builder.verify_block(&block));
+        builder.build_block(create_test_batch(1, 1)).unwrap();
+        assert_eq!(
+            builder.build_block(create_test_batch(2, 1)).unwrap_err(),
+            BuildError::Protocol(ProtocolError::Unsupported {
+                version: SUPPORTED_PROTOCOL_VERSION + 1,
+                height: 2,
+                supported: SUPPORTED_PROTOCOL_VERSION,
+            })
+        );
+    }
+
+    #[test]
+    fn test_drifted_clock_stops_blocks() {
+        use super::super::clock_quality::ClockSample;
+        use romer_common::utils::clock::ManualClock;
+        use std::collections::BTreeMap;
+
+        let clock = Arc::new(ManualClock::new(Utc::now()));
+        let monitor = Arc::new(ClockMonitor::new(
+            BTreeMap::new(),
+            chrono::Duration::milliseconds(50),
+            chrono::Duration::seconds(60),
+            clock.clone(),
+        ));
+        let mut builder = BlockBuilder::with_clock(clock.clone()).with_clock_monitor(monitor.clone());
+        assert!(matches!(
+            builder.build_block(create_test_batch(0, 1)),
+            Err(BuildError::Clock(ClockQualityError::Unmeasured(_)))
+        ));
+
+        let sample = |offset_us| ClockSample {
+            source: "ntp".into(),
+            offset_us,
+            round_trip_us: 400,
+            at: clock.now(),
+        };
+        monitor.record(vec![sample(1_000)]);
+        let block = builder.build_block(create_test_batch(0, 1)).unwrap();
+        assert_eq!(block.clock_confidence.unwrap().offset_us, 1_000);
+        assert_eq!(block.header.block_id, 0);
+
+        monitor.record(vec![sample(80_000)]);
+        assert!(matches!(
+            builder.build_block(create_test_batch(1, 1)),
+            Err(BuildError::Clock(ClockQualityError::Drift { .. }))
+        ));
+    }
+}
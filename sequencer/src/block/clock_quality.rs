@@ -0,0 +1,386 @@
+// src/block/clock_quality.rs
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use parking_lot::RwLock;
+use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::gauge::Gauge;
+use prometheus_client::registry::Registry;
+use romer_common::utils::clock::SharedClock;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+use tokio::net::UdpSocket;
+use tracing::warn;
+
+/// Seconds from the NTP epoch, 1900, to the unix epoch
+const NTP_UNIX_OFFSET: u64 = 2_208_988_800;
+/// How long an NTP server has to answer
+const NTP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ClockQualityError {
+    #[error("Time source {source_name} failed: {reason}")]
+    Source { source_name: String, reason: String },
+
+    #[error("No time source has been measured within the last {0}s")]
+    Unmeasured(i64),
+
+    #[error("Clock is {offset_us}us off its time sources, beyond the bound of {max_us}us")]
+    Drift { offset_us: i64, max_us: i64 },
+}
+
+/// Where the node's clock is compared against
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TimeSource {
+    /// An NTP server, `host:port`, queried over SNTP
+    Ntp { address: String },
+    /// A PTP clock, read through `command`, e.g. a `pmc` wrapper, which
+    /// prints the node's offset from the grandmaster in nanoseconds
+    Ptp { command: Vec<String> },
+}
+
+/// One measurement of the node's clock against a source
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClockSample {
+    pub source: String,
+    /// Source time less node time: positive when the node is behind
+    pub offset_us: i64,
+    /// Network delay of the measurement, zero for PTP
+    pub round_trip_us: u64,
+    pub at: DateTime<Utc>,
+}
+
+/// How far the clock stamping a block could be off, carried with the block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClockConfidence {
+    /// Median offset of the sources
+    pub offset_us: i64,
+    /// Half the widest round trip plus the spread of the sources' offsets
+    pub uncertainty_us: u64,
+    pub sources: usize,
+    pub measured_at: DateTime<Utc>,
+}
+
+/// Clock offset metrics, under the `romer_clock` prefix
+#[derive(Clone, Default)]
+pub struct ClockMetrics {
+    pub offset_microseconds: Gauge,
+    pub uncertainty_microseconds: Gauge,
+    pub sources: Gauge,
+    pub source_failures: Counter,
+}
+
+impl ClockMetrics {
+    pub fn new(registry: &Arc<Mutex<Registry>>) -> Self {
+        let metrics = Self::default();
+        let mut registry = registry.lock().unwrap();
+        let registry = registry.sub_registry_with_prefix("romer_clock");
+        registry.register(
+            "offset_microseconds",
+            "Median offset of the time sources from the node clock",
+            metrics.offset_microseconds.clone(),
+        );
+        registry.register(
+            "uncertainty_microseconds",
+            "Uncertainty of the measured clock offset",
+            metrics.uncertainty_microseconds.clone(),
+        );
+        registry.register(
+            "sources",
+            "Time sources answering the last poll",
+            metrics.sources.clone(),
+        );
+        registry.register(
+            "source_failures",
+            "Failed time source measurements",
+            metrics.source_failures.clone(),
+        );
+        metrics
+    }
+}
+
+/// Measures the node clock against NTP and PTP sources and judges whether
+/// it is good enough to stamp blocks with. The clock is trusted while the
+/// latest measurement is recent and its offset within `max_drift`.
+pub struct ClockMonitor {
+    sources: BTreeMap<String, TimeSource>,
+    max_drift: Duration,
+    max_age: Duration,
+    clock: SharedClock,
+    metrics: ClockMetrics,
+    latest: RwLock<Vec<ClockSample>>,
+}
+
+impl ClockMonitor {
+    pub fn new(
+        sources: BTreeMap<String, TimeSource>,
+        max_drift: Duration,
+        max_age: Duration,
+        clock: SharedClock,
+    ) -> Self {
+        Self {
+            sources,
+            max_drift,
+            max_age,
+            clock,
+            metrics: ClockMetrics::default(),
+            latest: RwLock::new(Vec::new()),
+        }
+    }
+
+    pub fn with_metrics(mut self, metrics: ClockMetrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Replaces the latest measurements with `samples`
+    pub fn record(&self, samples: Vec<ClockSample>) {
+        self.metrics.sources.set(samples.len() as i64);
+        if let Some(confidence) = confidence(&samples) {
+            self.metrics.offset_microseconds.set(confidence.offset_us);
+            self.metrics
+                .uncertainty_microseconds
+                .set(confidence.uncertainty_us as i64);
+        }
+        *self.latest.write() = samples;
+    }
+
+    pub fn samples(&self) -> Vec<ClockSample> {
+        self.latest.read().clone()
+    }
+
+    /// Confidence in the clock now, or why it cannot be trusted
+    pub fn check(&self) -> Result<ClockConfidence, ClockQualityError> {
+        let confidence = confidence(&self.latest.read())
+            .filter(|confidence| self.clock.now() - confidence.measured_at <= self.max_age)
+            .ok_or(ClockQualityError::Unmeasured(self.max_age.num_seconds()))?;
+        let max_us = self.max_drift.num_microseconds().unwrap_or(i64::MAX);
+        if confidence.offset_us.unsigned_abs() > max_us as u64 {
+            return Err(ClockQualityError::Drift {
+                offset_us: confidence.offset_us,
+                max_us,
+            });
+        }
+        Ok(confidence)
+    }
+
+    /// Measures every source once, keeping the answers
+    pub async fn poll(&self) -> Vec<ClockSample> {
+        let mut samples = Vec::new();
+        for (name, source) in &self.sources {
+            match measure(name, source, &self.clock).await {
+                Ok(sample) => samples.push(sample),
+                Err(e) => {
+                    warn!("{}", e);
+                    self.metrics.source_failures.inc();
+                }
+            }
+        }
+        self.record(samples.clone());
+        samples
+    }
+
+    /// Polls the sources every `interval`, forever
+    pub async fn run(self: Arc<Self>, interval: std::time::Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            self.poll().await;
+            if let Err(e) = self.check() {
+                warn!("Blocks will not be produced: {}", e);
+            }
+        }
+    }
+}
+
+/// Combined confidence of `samples`, none without any
+fn confidence(samples: &[ClockSample]) -> Option<ClockConfidence> {
+    let mut offsets: Vec<i64> = samples.iter().map(|sample| sample.offset_us).collect();
+    offsets.sort_unstable();
+    let offset_us = *offsets.get(offsets.len() / 2)?;
+    let spread = (offsets[offsets.len() - 1] - offsets[0]).unsigned_abs();
+    let round_trip = samples
+        .iter()
+        .map(|sample| sample.round_trip_us)
+        .max()
+        .unwrap_or_default();
+    Some(ClockConfidence {
+        offset_us,
+        uncertainty_us: round_trip / 2 + spread,
+        sources: samples.len(),
+        measured_at: samples.iter().map(|sample| sample.at).min()?,
+    })
+}
+
+async fn measure(name: &str, source: &TimeSource, clock: &SharedClock) -> Result<ClockSample, ClockQualityError> {
+    let failed = |reason: String| ClockQualityError::Source {
+        source_name: name.to_string(),
+        reason,
+    };
+    let (offset_us, round_trip_us) = match source {
+        TimeSource::Ntp { address } => query_ntp(address, clock).await.map_err(failed)?,
+        TimeSource::Ptp { command } => (read_ptp(command).await.map_err(failed)?, 0),
+    };
+    Ok(ClockSample {
+        source: name.to_string(),
+        offset_us,
+        round_trip_us,
+        at: clock.now(),
+    })
+}
+
+/// Offset and round trip against an NTP server, by one SNTP exchange
+async fn query_ntp(address: &str, clock: &SharedClock) -> Result<(i64, u64), String> {
+    let socket = UdpSocket::bind("0.0.0.0:0").await.map_err(|e| e.to_string())?;
+    socket.connect(address).await.map_err(|e| e.to_string())?;
+    let sent = clock.now();
+    socket.send(&ntp_request(sent)).await.map_err(|e| e.to_string())?;
+    let mut response = [0u8; 48];
+    let received = tokio::time::timeout(NTP_TIMEOUT, socket.recv(&mut response))
+        .await
+        .map_err(|_| "timed out".to_string())?
+        .map_err(|e| e.to_string())?;
+    if received < response.len() {
+        return Err(format!("short response of {} bytes", received));
+    }
+    ntp_offset(&response, sent, clock.now())
+}
+
+/// SNTP client request, version 4, carrying `sent` as its transmit time
+fn ntp_request(sent: DateTime<Utc>) -> [u8; 48] {
+    let mut request = [0u8; 48];
+    request[0] = 0x23;
+    request[40..48].copy_from_slice(&to_ntp(sent).to_be_bytes());
+    request
+}
+
+/// Offset and round trip of the node clock from a server's `response` to a
+/// request sent at `sent` and answered at `received`, node time
+fn ntp_offset(response: &[u8; 48], sent: DateTime<Utc>, received: DateTime<Utc>) -> Result<(i64, u64), String> {
+    if response[0] & 0x07 != 4 {
+        return Err("not a server response".into());
+    }
+    if response[1] == 0 {
+        return Err("server sent a kiss-o'-death".into());
+    }
+    let timestamp = |at: usize| u64::from_be_bytes(response[at..at + 8].try_into().unwrap_or_default());
+    if timestamp(24) != to_ntp(sent) {
+        return Err("response does not answer our request".into());
+    }
+    let (server_received, server_sent) = (from_ntp(timestamp(32)), from_ntp(timestamp(40)));
+    let micros = |duration: Duration| duration.num_microseconds().unwrap_or_default();
+    let offset = (micros(server_received - sent) + micros(server_sent - received)) / 2;
+    let round_trip = micros(received - sent) - micros(server_sent - server_received);
+    Ok((offset, round_trip.max(0) as u64))
+}
+
+fn to_ntp(at: DateTime<Utc>) -> u64 {
+    let seconds = at.timestamp() as u64 + NTP_UNIX_OFFSET;
+    let fraction = ((at.timestamp_subsec_nanos() as u64) << 32) / 1_000_000_000;
+    (seconds << 32) | fraction
+}
+
+fn from_ntp(timestamp: u64) -> DateTime<Utc> {
+    let seconds = (timestamp >> 32) as i64 - NTP_UNIX_OFFSET as i64;
+    let nanos = ((timestamp & 0xffff_ffff) * 1_000_000_000) >> 32;
+    Utc.timestamp_opt(seconds, nanos as u32).single().unwrap_or_default()
+}
+
+/// Offset from a PTP grandmaster printed by `command`, in nanoseconds, as
+/// source time less node time
+async fn read_ptp(command: &[String]) -> Result<i64, String> {
+    let (program, args) = command.split_first().ok_or("no command")?;
+    let output = tokio::process::Command::new(program)
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!("{} exited with {}", program, output.status));
+    }
+    let printed = String::from_utf8_lossy(&output.stdout);
+    let nanos: i64 = printed
+        .trim()
+        .parse()
+        .map_err(|_| format!("{} printed {:?}, not nanoseconds", program, printed.trim()))?;
+    Ok(nanos / 1_000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use romer_common::utils::clock::{Clock, ManualClock};
+
+    fn sample(source: &str, offset_us: i64, round_trip_us: u64, at: DateTime<Utc>) -> ClockSample {
+        ClockSample {
+            source: source.into(),
+            offset_us,
+            round_trip_us,
+            at,
+        }
+    }
+
+    #[test]
+    fn test_ntp_exchange() {
+        let sent = Utc.with_ymd_and_hms(2026, 10, 15, 12, 0, 0).unwrap();
+        assert_eq!(from_ntp(to_ntp(sent)), sent);
+
+        // The server is 5ms ahead and the network 2ms each way
+        let server_received = sent + Duration::milliseconds(7);
+        let server_sent = server_received + Duration::milliseconds(1);
+        let received = sent + Duration::milliseconds(5);
+        let mut response = ntp_request(sent);
+        response[0] = 0x24;
+        response[1] = 2;
+        response[24..32].copy_from_slice(&to_ntp(sent).to_be_bytes());
+        response[32..40].copy_from_slice(&to_ntp(server_received).to_be_bytes());
+        response[40..48].copy_from_slice(&to_ntp(server_sent).to_be_bytes());
+        let (offset, round_trip) = ntp_offset(&response, sent, received).unwrap();
+        // Within the precision of NTP fractions
+        assert!((offset - 5_000).abs() <= 1, "{}", offset);
+        assert!((round_trip as i64 - 4_000).abs() <= 1, "{}", round_trip);
+
+        response[1] = 0;
+        assert!(ntp_offset(&response, sent, received).is_err());
+        response[1] = 2;
+        assert!(ntp_offset(&response, sent + Duration::seconds(1), received).is_err());
+    }
+
+    #[test]
+    fn test_check_bounds_drift_and_age() {
+        let clock = Arc::new(ManualClock::new(Utc::now()));
+        let monitor = ClockMonitor::new(
+            BTreeMap::new(),
+            Duration::milliseconds(100),
+            Duration::seconds(60),
+            clock.clone(),
+        );
+        assert_eq!(monitor.check(), Err(ClockQualityError::Unmeasured(60)));
+
+        let now = clock.now();
+        monitor.record(vec![
+            sample("ntp-a", 2_000, 800, now),
+            sample("ntp-b", 3_000, 1_200, now),
+            sample("ptp", 2_500, 0, now),
+        ]);
+        let confidence = monitor.check().unwrap();
+        assert_eq!(confidence.offset_us, 2_500);
+        assert_eq!(confidence.uncertainty_us, 600 + 1_000);
+        assert_eq!(confidence.sources, 3);
+
+        monitor.record(vec![sample("ntp-a", -150_000, 800, now)]);
+        assert_eq!(
+            monitor.check(),
+            Err(ClockQualityError::Drift {
+                offset_us: -150_000,
+                max_us: 100_000,
+            })
+        );
+
+        monitor.record(vec![sample("ntp-a", 0, 800, now)]);
+        clock.advance(std::time::Duration::from_secs(61));
+        assert!(matches!(monitor.check(), Err(ClockQualityError::Unmeasured(_))));
+    }
+}
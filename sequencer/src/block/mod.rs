@@ -1,3 +1,5 @@
 pub mod batch;
 pub mod builder;
+pub mod clock_quality;
+pub mod producer;
 pub mod timer;
\ No newline at end of file
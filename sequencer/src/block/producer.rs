@@ -0,0 +1,226 @@
+// src/block/producer.rs
+
+use super::batch::{BatchManager, MessageBatch};
+use super::builder::{BlockBuilder, BuildError};
+use commonware_utils::hex;
+use crate::mempool::pool::Mempool;
+use crate::rpc::handler::RpcState;
+use parking_lot::Mutex;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::time::{self, Duration, MissedTickBehavior};
+use tracing::{debug, error};
+
+/// Most direct transactions sealed into one block by default
+pub const DEFAULT_MAX_BLOCK_TRANSACTIONS: usize = 1000;
+
+/// Seals blocks from the batches of accepted FIX messages together with
+/// the direct transactions waiting in the mempool, and records each one in
+/// the RPC state. The builder announces every block with `BlockSealed`,
+/// which state snapshots and statistics follow.
+pub struct BlockProducer {
+    builder: BlockBuilder,
+    /// Where accepted messages collect between blocks
+    batches: BatchManager,
+    mempool: Arc<Mutex<Mempool>>,
+    state: Arc<RpcState>,
+    /// Blocks are sealed for waiting transactions every window without
+    /// a batch
+    window: Duration,
+    max_transactions: usize,
+}
+
+impl BlockProducer {
+    pub fn new(
+        builder: BlockBuilder,
+        batches: BatchManager,
+        mempool: Arc<Mutex<Mempool>>,
+        state: Arc<RpcState>,
+        window: Duration,
+    ) -> Self {
+        Self {
+            builder,
+            batches,
+            mempool,
+            state,
+            window,
+            max_transactions: DEFAULT_MAX_BLOCK_TRANSACTIONS,
+        }
+    }
+
+    /// Seal at most `max_transactions` direct transactions into a block
+    pub fn with_max_transactions(mut self, max_transactions: usize) -> Self {
+        self.max_transactions = max_transactions;
+        self
+    }
+
+    /// Seals a block for every batch flushed to `flushed`, and one for the
+    /// mempool in each window that flushed none while transactions wait
+    pub async fn run(mut self, mut flushed: mpsc::Receiver<MessageBatch>) {
+        let mut ticker = time::interval(self.window);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut sealed = false;
+        loop {
+            tokio::select! {
+                batch = flushed.recv() => {
+                    let Some(batch) = batch else {
+                        return;
+                    };
+                    sealed |= self.seal(batch).is_ok();
+                }
+                _ = ticker.tick() => {
+                    if !std::mem::take(&mut sealed) && !self.mempool.lock().is_empty() {
+                        let batch = self.batches.take_batch();
+                        self.seal(batch).ok();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Builds a block of `batch` and the highest paying executable
+    /// transactions of the mempool, returning its height. When the block
+    /// cannot be built its messages go back to the batch manager and its
+    /// transactions stay in the mempool, for the next block.
+    pub fn seal(&mut self, batch: MessageBatch) -> Result<u64, BuildError> {
+        let transactions = self
+            .mempool
+            .lock()
+            .select(self.max_transactions, |address| self.state.next_nonce(address));
+        let messages = batch.messages.clone();
+        let block = match self.builder.build_block_with_transactions(batch, transactions) {
+            Ok(block) => block,
+            Err(e) => {
+                error!(error = %e, "Failed to build block");
+                self.batches.restore(messages);
+                return Err(e);
+            }
+        };
+
+        // The transactions root was built from these digests, so each encodes
+        let hashes: Vec<String> = {
+            let mut mempool = self.mempool.lock();
+            block
+                .transactions
+                .iter()
+                .filter_map(|transaction| transaction.digest().ok())
+                .map(|digest| {
+                    mempool.remove(&digest);
+                    hex(&digest)
+                })
+                .collect()
+        };
+        let height = block.header.block_id;
+        debug!(
+            height,
+            messages = block.header.message_count,
+            transactions = block.header.transaction_count,
+            "Sealed block"
+        );
+        self.state.record_block(block, &hashes);
+        Ok(height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::types::{MessageType, ValidatedMessage};
+    use crate::mempool::pool::MempoolConfig;
+    use crate::rpc::handler::RpcHandler;
+    use crate::rpc::types::{RpcRequest, JSONRPC_VERSION};
+    use commonware_cryptography::{Ed25519, Scheme};
+    use romer_common::types::address::Address;
+    use romer_common::types::envelope::{TransactionPayload, UnsignedTransaction, DEFAULT_CHAIN_ID};
+    use romer_common::types::keymanager::SignatureScheme;
+    use romer_common::types::protocol::{Activation, ProtocolSchedule, SUPPORTED_PROTOCOL_VERSION};
+    use serde_json::json;
+
+    fn message() -> ValidatedMessage {
+        ValidatedMessage {
+            msg_type: MessageType::NewOrderSingle,
+            sender_comp_id: "MM1".to_string(),
+            target_comp_id: "ROMER".to_string(),
+            msg_seq_num: 2,
+            fields: Default::default(),
+            raw: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_blocks_take_batches_and_mempool() {
+        let (submissions_tx, mut submissions) = mpsc::channel(8);
+        let state = Arc::new(RpcState::new());
+        let handler = RpcHandler::new(state.clone(), submissions_tx);
+        let mut signer = Ed25519::from_seed(1);
+        let sender = Address::from_public_key(SignatureScheme::Ed25519, &signer.public_key());
+        let transaction = UnsignedTransaction {
+            chain_id: DEFAULT_CHAIN_ID.to_string(),
+            payload: TransactionPayload::Transfer {
+                to: Address::new([9u8; 32]),
+                amount: 10,
+            },
+            sender,
+            nonce: 0,
+            fee: 0,
+            expiry: u64::MAX,
+        }
+        .sign(SignatureScheme::Ed25519, &mut signer)
+        .unwrap();
+        let request = RpcRequest {
+            jsonrpc: JSONRPC_VERSION.into(),
+            method: "submit_transaction".into(),
+            params: json!({ "transaction": transaction }),
+            id: Some(json!(1)),
+        };
+        handler.handle(request).await.unwrap().result.unwrap();
+
+        let mempool = Arc::new(Mutex::new(Mempool::new(MempoolConfig::default())));
+        mempool
+            .lock()
+            .insert(submissions.recv().await.unwrap(), std::time::Instant::now())
+            .unwrap();
+        let (flushed_tx, _flushed) = mpsc::channel(8);
+        let batches = BatchManager::new(flushed_tx, 10, Duration::from_secs(1));
+        let mut producer = BlockProducer::new(
+            BlockBuilder::new(),
+            batches.clone(),
+            mempool.clone(),
+            state.clone(),
+            Duration::from_secs(1),
+        );
+
+        // The transaction is sealed with the accepted messages
+        batches.add_message(message()).await;
+        assert_eq!(producer.seal(batches.take_batch()), Ok(0));
+        assert!(mempool.lock().is_empty());
+        assert_eq!(state.next_height(), 1);
+        assert_eq!(state.next_nonce(&sender), 1);
+        assert!(batches.take_batch().messages.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unbuilt_batch_restored() {
+        let schedule = ProtocolSchedule {
+            activations: vec![Activation {
+                version: SUPPORTED_PROTOCOL_VERSION + 1,
+                height: 0,
+                description: String::new(),
+            }],
+        };
+        let (flushed_tx, _flushed) = mpsc::channel(8);
+        let batches = BatchManager::new(flushed_tx, 10, Duration::from_secs(1));
+        let mut producer = BlockProducer::new(
+            BlockBuilder::new().with_protocol(schedule, 0),
+            batches.clone(),
+            Arc::new(Mutex::new(Mempool::new(MempoolConfig::default()))),
+            Arc::new(RpcState::new()),
+            Duration::from_secs(1),
+        );
+
+        batches.add_message(message()).await;
+        assert!(producer.seal(batches.take_batch()).is_err());
+        // The message waits for the next block
+        assert_eq!(batches.take_batch().messages.len(), 1);
+    }
+}
@@ -2,6 +2,7 @@ use tokio::sync::mpsc;
 use tokio::time::{self, Duration, Instant, MissedTickBehavior};
 use std::sync::Arc;
 use parking_lot::Mutex;
+use romer_common::utils::clock::{system_clock, SharedClock};
 use tracing::{info, warn};
 
 /// Represents the current state of the block timer
@@ -29,21 +30,37 @@ pub struct BlockTimer {
     precise_windows: Arc<Mutex<u64>>,
     /// How many times we've exceeded our target window
     exceeded_windows: Arc<Mutex<u64>>,
+    /// Time source for block windows
+    clock: SharedClock,
 }
 
 impl BlockTimer {
     /// Create a new block timer with the specified window duration
     pub fn new(timer_tx: mpsc::Sender<Instant>, window_duration: Duration) -> Self {
+        Self::with_clock(timer_tx, window_duration, system_clock())
+    }
+
+    /// Create a block timer driven by `clock`
+    pub fn with_clock(
+        timer_tx: mpsc::Sender<Instant>,
+        window_duration: Duration,
+        clock: SharedClock,
+    ) -> Self {
         Self {
             state: Arc::new(Mutex::new(TimerState::Running)),
-            window_start: Arc::new(Mutex::new(Instant::now())),
+            window_start: Arc::new(Mutex::new(Instant::from_std(clock.instant()))),
             window_duration,
             timer_tx,
             precise_windows: Arc::new(Mutex::new(0)),
             exceeded_windows: Arc::new(Mutex::new(0)),
+            clock,
         }
     }
 
+    fn now(&self) -> Instant {
+        Instant::from_std(self.clock.instant())
+    }
+
     /// Start the timer process
     pub async fn run(&self) {
         // Create an interval that ticks slightly more frequently than our window
@@ -72,7 +89,7 @@ impl BlockTimer {
 
     /// Check if the current window has expired and signal if necessary
     async fn check_window(&self) {
-        let now = Instant::now();
+        let now = self.now();
         let window_start = *self.window_start.lock();
         let elapsed = now - window_start;
 
@@ -120,7 +137,7 @@ impl BlockTimer {
         if *state == TimerState::Paused {
             *state = TimerState::Running;
             // Reset the window start when resuming
-            *self.window_start.lock() = Instant::now();
+            *self.window_start.lock() = self.now();
             info!("Block timer resumed");
         }
     }
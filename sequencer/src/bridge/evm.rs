@@ -0,0 +1,251 @@
+// src/bridge/evm.rs
+
+use super::source::{DepositsFuture, ReleaseFuture, SourceChainAdapter, SourceError};
+use parking_lot::Mutex;
+use romer_common::types::address::Address;
+use romer_common::types::bridge::{BridgeSignature, Deposit, Withdrawal};
+use romer_common::utils::rpc;
+use serde_json::{json, Value};
+
+/// `Locked(bytes32,uint256)`, emitted by the lock contract with the Romer
+/// recipient and amount
+pub const LOCKED_TOPIC: &str = "0x83ead0c50d00591163b21bd91a8d2ccc526ba2fca4b334f352b0c10275c05f62";
+/// `release(bytes,bytes)`, taking the release message and the concatenated
+/// public key and signature of each signer
+const RELEASE: [u8; 4] = [0xaa, 0x30, 0xd5, 0x3a];
+
+/// Watches an EVM lock contract for `Locked` events once `confirmations`
+/// blocks deep, and releases through it with `eth_sendTransaction`, signed
+/// by the node or a signing proxy in front of it for `sender`. Each lock
+/// contract holds one asset, wrapped as the Move type `asset`.
+pub struct EvmLockAdapter {
+    chain: String,
+    /// JSON-RPC endpoint, `host:port`
+    endpoint: String,
+    lock_contract: String,
+    sender: String,
+    asset: String,
+    confirmations: u64,
+    /// First block not yet scanned, the confirmed head on the first poll
+    /// without a start block
+    next_block: Mutex<Option<u64>>,
+}
+
+impl EvmLockAdapter {
+    pub fn new(
+        chain: impl Into<String>,
+        endpoint: impl Into<String>,
+        lock_contract: impl Into<String>,
+        sender: impl Into<String>,
+        asset: impl Into<String>,
+    ) -> Self {
+        Self {
+            chain: chain.into(),
+            endpoint: endpoint.into(),
+            lock_contract: lock_contract.into(),
+            sender: sender.into(),
+            asset: asset.into(),
+            confirmations: 0,
+            next_block: Mutex::new(None),
+        }
+    }
+
+    pub fn with_confirmations(mut self, confirmations: u64) -> Self {
+        self.confirmations = confirmations;
+        self
+    }
+
+    /// Scan from `block` rather than the head at startup
+    pub fn with_start_block(self, block: u64) -> Self {
+        *self.next_block.lock() = Some(block);
+        self
+    }
+
+    /// The deposit a `Locked` log records
+    pub fn deposit(&self, log: &Value) -> Result<Deposit, SourceError> {
+        let malformed = |reason: &str| SourceError::Malformed {
+            chain: self.chain.clone(),
+            reason: reason.to_string(),
+        };
+        let transaction = log["transactionHash"]
+            .as_str()
+            .ok_or_else(|| malformed("no transactionHash"))?;
+        let index = log["logIndex"]
+            .as_str()
+            .and_then(quantity)
+            .ok_or_else(|| malformed("no logIndex"))?;
+        let data = log["data"]
+            .as_str()
+            .and_then(|data| hex::decode(data.strip_prefix("0x").unwrap_or(data)).ok())
+            .ok_or_else(|| malformed("data is not hex"))?;
+        if data.len() != 64 {
+            return Err(malformed("data is not two words"));
+        }
+        let recipient = Address::try_from(&data[..32]).map_err(|e| malformed(&e.to_string()))?;
+        if data[32..56].iter().any(|byte| *byte != 0) {
+            return Err(malformed("amount exceeds u64"));
+        }
+        let amount = u64::from_be_bytes(data[56..].try_into().expect("8 bytes"));
+        Ok(Deposit {
+            source_chain: self.chain.clone(),
+            deposit_id: format!("{}-{}", transaction, index),
+            asset: self.asset.clone(),
+            recipient,
+            amount,
+        })
+    }
+
+    /// The `eth_sendTransaction` parameters releasing `withdrawal`
+    pub fn release_transaction(
+        &self,
+        withdrawal: &Withdrawal,
+        signatures: &[BridgeSignature],
+    ) -> Result<Value, SourceError> {
+        let mut signed = Vec::new();
+        for signature in signatures {
+            for field in [&signature.public_key, &signature.signature] {
+                signed.extend(hex::decode(field).map_err(|_| SourceError::Malformed {
+                    chain: self.chain.clone(),
+                    reason: format!("signature field {} is not hex", field),
+                })?);
+            }
+        }
+        Ok(json!({
+            "from": self.sender,
+            "to": self.lock_contract,
+            "data": format!("0x{}", hex::encode(release_calldata(&withdrawal.release_message(), &signed))),
+        }))
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Result<Value, SourceError> {
+        rpc::call(&self.endpoint, method, params)
+            .await
+            .map_err(|e| SourceError::Rpc {
+                chain: self.chain.clone(),
+                reason: e.to_string(),
+            })
+    }
+}
+
+impl SourceChainAdapter for EvmLockAdapter {
+    fn source_chain(&self) -> &str {
+        &self.chain
+    }
+
+    fn poll_deposits(&self) -> DepositsFuture<'_> {
+        Box::pin(async move {
+            let head = self.call("eth_blockNumber", json!([])).await?;
+            let head = head.as_str().and_then(quantity).ok_or_else(|| SourceError::Rpc {
+                chain: self.chain.clone(),
+                reason: format!("unexpected block number {}", head),
+            })?;
+            let confirmed = head.saturating_sub(self.confirmations);
+            let from = self.next_block.lock().unwrap_or(confirmed);
+            if from > confirmed {
+                return Ok(Vec::new());
+            }
+
+            let filter = json!({
+                "address": self.lock_contract,
+                "topics": [LOCKED_TOPIC],
+                "fromBlock": format!("0x{:x}", from),
+                "toBlock": format!("0x{:x}", confirmed),
+            });
+            let logs = self.call("eth_getLogs", json!([filter])).await?;
+            let deposits = logs
+                .as_array()
+                .map(|logs| logs.iter().map(|log| self.deposit(log)).collect::<Result<Vec<_>, _>>())
+                .unwrap_or_else(|| Ok(Vec::new()))?;
+            *self.next_block.lock() = Some(confirmed + 1);
+            Ok(deposits)
+        })
+    }
+
+    fn release<'a>(&'a self, withdrawal: &'a Withdrawal, signatures: &'a [BridgeSignature]) -> ReleaseFuture<'a> {
+        Box::pin(async move {
+            let transaction = self.release_transaction(withdrawal, signatures)?;
+            let result = self.call("eth_sendTransaction", json!([transaction])).await?;
+            result.as_str().map(str::to_string).ok_or_else(|| SourceError::Rpc {
+                chain: self.chain.clone(),
+                reason: format!("unexpected result {}", result),
+            })
+        })
+    }
+}
+
+/// A `0x` hex quantity
+fn quantity(value: &str) -> Option<u64> {
+    u64::from_str_radix(value.strip_prefix("0x")?, 16).ok()
+}
+
+/// ABI encoding of `release(bytes message, bytes signatures)`
+fn release_calldata(message: &[u8], signatures: &[u8]) -> Vec<u8> {
+    let padded = |length: usize| length.div_ceil(32) * 32;
+    let mut data = RELEASE.to_vec();
+    data.extend_from_slice(&word(64));
+    data.extend_from_slice(&word(64 + 32 + padded(message.len()) as u64));
+    for bytes in [message, signatures] {
+        data.extend_from_slice(&word(bytes.len() as u64));
+        data.extend_from_slice(bytes);
+        data.resize(data.len() + padded(bytes.len()) - bytes.len(), 0);
+    }
+    data
+}
+
+fn word(value: u64) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adapter() -> EvmLockAdapter {
+        EvmLockAdapter::new(
+            "ethereum",
+            "127.0.0.1:8545",
+            "0x4444444444444444444444444444444444444444",
+            "0x5555555555555555555555555555555555555555",
+            "usdc",
+        )
+    }
+
+    #[test]
+    fn test_deposit_from_log() {
+        let log = json!({
+            "transactionHash": "0xabc",
+            "logIndex": "0x2",
+            "data": format!("0x{}{:064x}", "07".repeat(32), 1000),
+        });
+        let deposit = adapter().deposit(&log).unwrap();
+        assert_eq!(deposit.deposit_id, "0xabc-2");
+        assert_eq!(deposit.recipient, Address::new([7u8; 32]));
+        assert_eq!(deposit.amount, 1000);
+        assert_eq!(deposit.asset, "usdc");
+
+        let too_large = json!({
+            "transactionHash": "0xabc",
+            "logIndex": "0x2",
+            "data": format!("0x{}{:064x}", "07".repeat(32), u128::MAX),
+        });
+        assert!(matches!(
+            adapter().deposit(&too_large),
+            Err(SourceError::Malformed { .. })
+        ));
+    }
+
+    #[test]
+    fn test_release_calldata() {
+        let data = release_calldata(&[1u8; 33], &[2u8; 96]);
+        // Selector, two offsets, then each length and its padded bytes
+        assert_eq!(data.len(), 4 + 64 + 32 + 64 + 32 + 96);
+        assert_eq!(&data[..4], &RELEASE);
+        assert_eq!(data[4 + 31], 64);
+        assert_eq!(data[4 + 63], 160);
+        assert_eq!(data[4 + 64 + 31], 33);
+        assert_eq!(data[4 + 64 + 32 + 33], 0);
+        assert_eq!(data[4 + 160 + 31], 96);
+    }
+}
@@ -0,0 +1,3 @@
+pub mod evm;
+pub mod relay;
+pub mod source;
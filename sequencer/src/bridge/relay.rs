@@ -0,0 +1,410 @@
+// src/bridge/relay.rs
+
+use super::source::SourceChainAdapter;
+use parking_lot::Mutex;
+use romer_common::types::bridge::{BridgeCommittee, BridgeError, BridgeSignature, Deposit, Withdrawal};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tracing::{info, warn};
+
+/// Package the `romer::bridge` module is published in
+const FRAMEWORK_PACKAGE: &str = "0x10";
+
+#[derive(Error, Debug)]
+pub enum RelayError {
+    #[error(transparent)]
+    Bridge(#[from] BridgeError),
+
+    #[error("No deposit {deposit_id} observed on {source_chain}")]
+    UnknownDeposit { source_chain: String, deposit_id: String },
+
+    #[error("No adapter for source chain {0}")]
+    UnknownChain(String),
+}
+
+/// An observed deposit and the validators who attested to it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DepositStatus {
+    pub deposit: Deposit,
+    /// Committee indices of the attesting validators
+    pub signers: Vec<usize>,
+    /// Enough validators attested for the mint to be submitted
+    pub ready: bool,
+}
+
+/// Where a withdrawal stands on its source chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ReleaseState {
+    Collecting,
+    Releasing,
+    Released {
+        reference: String,
+    },
+    /// Retried on the next attestation
+    Failed {
+        reason: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WithdrawalStatus {
+    pub withdrawal: Withdrawal,
+    pub signers: Vec<usize>,
+    pub release: ReleaseState,
+}
+
+/// Arguments of `romer::bridge::mint`, for anyone to submit with the shared
+/// `Bridge` and the asset's `WrappedAsset` object
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MintCall {
+    pub package: String,
+    pub module: String,
+    pub function: String,
+    pub type_arguments: Vec<String>,
+    pub arguments: Value,
+}
+
+struct Attested<T> {
+    item: T,
+    signatures: BTreeMap<usize, BridgeSignature>,
+}
+
+struct PendingWithdrawal {
+    attested: Attested<Withdrawal>,
+    release: ReleaseState,
+}
+
+/// Collects validator attestations for deposits the source chain adapters
+/// observe, so mints can be submitted once the committee threshold signed,
+/// and for burns on Romer, releasing them on the source chain at threshold.
+/// Pending attestations are held in memory; validators re-attest after a
+/// restart.
+pub struct BridgeRelay {
+    committee: BridgeCommittee,
+    adapters: HashMap<String, Box<dyn SourceChainAdapter>>,
+    /// By source chain, then deposit id
+    deposits: Mutex<BTreeMap<(String, String), Attested<Deposit>>>,
+    /// By source chain, then nonce
+    withdrawals: Mutex<BTreeMap<(String, u64), PendingWithdrawal>>,
+}
+
+impl BridgeRelay {
+    pub fn new(committee: BridgeCommittee) -> Result<Self, BridgeError> {
+        committee.validate()?;
+        Ok(Self {
+            committee,
+            adapters: HashMap::new(),
+            deposits: Mutex::new(BTreeMap::new()),
+            withdrawals: Mutex::new(BTreeMap::new()),
+        })
+    }
+
+    pub fn with_adapter(mut self, adapter: Box<dyn SourceChainAdapter>) -> Self {
+        self.adapters.insert(adapter.source_chain().to_string(), adapter);
+        self
+    }
+
+    /// Records a deposit seen on its source chain. Returns false if it was
+    /// already known.
+    pub fn observe(&self, deposit: Deposit) -> bool {
+        let key = (deposit.source_chain.clone(), deposit.deposit_id.clone());
+        let mut deposits = self.deposits.lock();
+        if deposits.contains_key(&key) {
+            return false;
+        }
+        info!(source_chain = %key.0, deposit_id = %key.1, amount = deposit.amount, "Bridge deposit observed");
+        deposits.insert(
+            key,
+            Attested {
+                item: deposit,
+                signatures: BTreeMap::new(),
+            },
+        );
+        true
+    }
+
+    /// Adds a validator's signature over an observed deposit's mint message
+    pub fn attest_deposit(
+        &self,
+        source_chain: &str,
+        deposit_id: &str,
+        signature: BridgeSignature,
+    ) -> Result<DepositStatus, RelayError> {
+        let mut deposits = self.deposits.lock();
+        let attested = deposits
+            .get_mut(&(source_chain.to_string(), deposit_id.to_string()))
+            .ok_or_else(|| RelayError::UnknownDeposit {
+                source_chain: source_chain.to_string(),
+                deposit_id: deposit_id.to_string(),
+            })?;
+        let index = self.committee.verify_one(&attested.item.mint_message(), &signature)?;
+        attested.signatures.insert(index, signature);
+        Ok(self.deposit_status(attested))
+    }
+
+    pub fn deposits(&self) -> Vec<DepositStatus> {
+        self.deposits
+            .lock()
+            .values()
+            .map(|attested| self.deposit_status(attested))
+            .collect()
+    }
+
+    /// The `mint` call for a deposit attested by the committee threshold
+    pub fn mint_call(&self, source_chain: &str, deposit_id: &str) -> Result<MintCall, RelayError> {
+        let deposits = self.deposits.lock();
+        let attested = deposits
+            .get(&(source_chain.to_string(), deposit_id.to_string()))
+            .ok_or_else(|| RelayError::UnknownDeposit {
+                source_chain: source_chain.to_string(),
+                deposit_id: deposit_id.to_string(),
+            })?;
+        let deposit = &attested.item;
+        let signatures: Vec<_> = attested.signatures.values().cloned().collect();
+        // Ascending by index, as the Move module requires
+        let valid = self.committee.verify(&deposit.mint_message(), &signatures)?;
+        Ok(MintCall {
+            package: FRAMEWORK_PACKAGE.to_string(),
+            module: "bridge".to_string(),
+            function: "mint".to_string(),
+            type_arguments: vec![format!("0x{}", deposit.asset)],
+            arguments: json!({
+                "deposit_id": deposit.deposit_id,
+                "recipient": deposit.recipient.to_hex(),
+                "amount": deposit.amount,
+                "signers": valid.keys().collect::<Vec<_>>(),
+                "signatures": valid.values().map(|signature| &signature.signature).collect::<Vec<_>>(),
+            }),
+        })
+    }
+
+    /// Adds a validator's signature releasing a burn, submitting the
+    /// release to the source chain once the threshold signed
+    pub async fn attest_withdrawal(
+        &self,
+        withdrawal: Withdrawal,
+        signature: BridgeSignature,
+    ) -> Result<WithdrawalStatus, RelayError> {
+        let adapter = self
+            .adapters
+            .get(&withdrawal.source_chain)
+            .ok_or_else(|| RelayError::UnknownChain(withdrawal.source_chain.clone()))?;
+        // The signature must cover exactly this withdrawal, so an entry is
+        // only ever created by a validator
+        let index = self.committee.verify_one(&withdrawal.release_message(), &signature)?;
+        let key = (withdrawal.source_chain.clone(), withdrawal.nonce);
+
+        let signatures = {
+            let mut withdrawals = self.withdrawals.lock();
+            let pending = withdrawals.entry(key.clone()).or_insert_with(|| PendingWithdrawal {
+                attested: Attested {
+                    item: withdrawal.clone(),
+                    signatures: BTreeMap::new(),
+                },
+                release: ReleaseState::Collecting,
+            });
+            if pending.attested.item != withdrawal {
+                // A validator signed a withdrawal conflicting with the one
+                // others attested to under the same nonce
+                warn!(source_chain = %key.0, nonce = key.1, signer = index, "Conflicting bridge withdrawal attestation");
+                return Err(BridgeError::InvalidSignature(signature.public_key).into());
+            }
+            pending.attested.signatures.insert(index, signature);
+            let ready = pending.attested.signatures.len() >= self.committee.threshold
+                && matches!(pending.release, ReleaseState::Collecting | ReleaseState::Failed { .. });
+            if !ready {
+                return Ok(withdrawal_status(pending));
+            }
+            pending.release = ReleaseState::Releasing;
+            pending.attested.signatures.values().cloned().collect::<Vec<_>>()
+        };
+
+        let release = match adapter.release(&withdrawal, &signatures).await {
+            Ok(reference) => {
+                info!(source_chain = %key.0, nonce = key.1, %reference, "Bridge withdrawal released");
+                ReleaseState::Released { reference }
+            }
+            Err(e) => {
+                warn!(source_chain = %key.0, nonce = key.1, "Bridge release failed: {}", e);
+                ReleaseState::Failed { reason: e.to_string() }
+            }
+        };
+        let mut withdrawals = self.withdrawals.lock();
+        let pending = withdrawals.get_mut(&key).expect("withdrawals are never removed");
+        pending.release = release;
+        Ok(withdrawal_status(pending))
+    }
+
+    pub fn withdrawals(&self) -> Vec<WithdrawalStatus> {
+        self.withdrawals.lock().values().map(withdrawal_status).collect()
+    }
+
+    /// Polls every source chain for new deposits each `interval`
+    pub async fn run(self: Arc<Self>, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            for adapter in self.adapters.values() {
+                match adapter.poll_deposits().await {
+                    Ok(deposits) => {
+                        for deposit in deposits {
+                            self.observe(deposit);
+                        }
+                    }
+                    Err(e) => warn!(
+                        source_chain = adapter.source_chain(),
+                        "Bridge deposit poll failed: {}", e
+                    ),
+                }
+            }
+        }
+    }
+
+    fn deposit_status(&self, attested: &Attested<Deposit>) -> DepositStatus {
+        DepositStatus {
+            deposit: attested.item.clone(),
+            signers: attested.signatures.keys().copied().collect(),
+            ready: attested.signatures.len() >= self.committee.threshold,
+        }
+    }
+}
+
+fn withdrawal_status(pending: &PendingWithdrawal) -> WithdrawalStatus {
+    WithdrawalStatus {
+        withdrawal: pending.attested.item.clone(),
+        signers: pending.attested.signatures.keys().copied().collect(),
+        release: pending.release.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bridge::source::{DepositsFuture, ReleaseFuture};
+    use commonware_cryptography::{Ed25519, Scheme};
+    use romer_common::types::address::Address;
+
+    /// Accepts every release
+    struct Recorder;
+
+    impl SourceChainAdapter for Recorder {
+        fn source_chain(&self) -> &str {
+            "ethereum"
+        }
+
+        fn poll_deposits(&self) -> DepositsFuture<'_> {
+            Box::pin(async { Ok(Vec::new()) })
+        }
+
+        fn release<'a>(&'a self, _: &'a Withdrawal, _: &'a [BridgeSignature]) -> ReleaseFuture<'a> {
+            Box::pin(async { Ok("0xfeed".to_string()) })
+        }
+    }
+
+    fn relay(signers: &[Ed25519]) -> BridgeRelay {
+        let committee = BridgeCommittee {
+            validators: signers.iter().map(|signer| hex::encode(signer.public_key())).collect(),
+            threshold: 2,
+        };
+        BridgeRelay::new(committee).unwrap().with_adapter(Box::new(Recorder))
+    }
+
+    fn deposit() -> Deposit {
+        Deposit {
+            source_chain: "ethereum".into(),
+            deposit_id: "0xabc-1".into(),
+            asset: format!("{:0>64}::usdc::USDC", "20"),
+            recipient: Address::new([7u8; 32]),
+            amount: 1_000,
+        }
+    }
+
+    #[test]
+    fn test_mint_after_threshold() {
+        let mut signers: Vec<Ed25519> = (0..3).map(Ed25519::from_seed).collect();
+        let relay = relay(&signers);
+        assert!(relay.observe(deposit()));
+        assert!(!relay.observe(deposit()));
+
+        let message = deposit().mint_message();
+        let status = relay
+            .attest_deposit("ethereum", "0xabc-1", BridgeSignature::sign(&mut signers[2], &message))
+            .unwrap();
+        assert!(!status.ready);
+        assert!(matches!(
+            relay.mint_call("ethereum", "0xabc-1"),
+            Err(RelayError::Bridge(BridgeError::ThresholdNotReached { .. }))
+        ));
+        // A signature over a different amount is refused
+        let mut altered = deposit();
+        altered.amount = 1_000_000;
+        assert!(relay
+            .attest_deposit(
+                "ethereum",
+                "0xabc-1",
+                BridgeSignature::sign(&mut signers[0], &altered.mint_message())
+            )
+            .is_err());
+
+        let status = relay
+            .attest_deposit("ethereum", "0xabc-1", BridgeSignature::sign(&mut signers[0], &message))
+            .unwrap();
+        assert!(status.ready);
+        assert_eq!(status.signers, vec![0, 2]);
+        let call = relay.mint_call("ethereum", "0xabc-1").unwrap();
+        assert_eq!(call.type_arguments, vec![format!("0x{:0>64}::usdc::USDC", "20")]);
+        assert_eq!(call.arguments["signers"], json!([0, 2]));
+        assert!(matches!(
+            relay.attest_deposit("ethereum", "0xdef-1", BridgeSignature::sign(&mut signers[0], &message)),
+            Err(RelayError::UnknownDeposit { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_release_at_threshold() {
+        let mut signers: Vec<Ed25519> = (0..3).map(Ed25519::from_seed).collect();
+        let relay = relay(&signers);
+        let withdrawal = Withdrawal {
+            source_chain: "ethereum".into(),
+            nonce: 4,
+            asset: format!("{:0>64}::usdc::USDC", "20"),
+            destination: "0x1111111111111111111111111111111111111111".into(),
+            amount: 500,
+        };
+        let message = withdrawal.release_message();
+
+        let status = relay
+            .attest_withdrawal(withdrawal.clone(), BridgeSignature::sign(&mut signers[1], &message))
+            .await
+            .unwrap();
+        assert_eq!(status.release, ReleaseState::Collecting);
+
+        let mut conflicting = withdrawal.clone();
+        conflicting.destination = "0x9999999999999999999999999999999999999999".into();
+        let signature = BridgeSignature::sign(&mut signers[0], &conflicting.release_message());
+        assert!(relay.attest_withdrawal(conflicting, signature).await.is_err());
+
+        let status = relay
+            .attest_withdrawal(withdrawal.clone(), BridgeSignature::sign(&mut signers[0], &message))
+            .await
+            .unwrap();
+        assert_eq!(
+            status.release,
+            ReleaseState::Released {
+                reference: "0xfeed".into()
+            }
+        );
+
+        // Late attestations do not release again
+        relay
+            .attest_withdrawal(withdrawal, BridgeSignature::sign(&mut signers[2], &message))
+            .await
+            .unwrap();
+        assert_eq!(relay.withdrawals()[0].signers, vec![0, 1, 2]);
+    }
+}
@@ -0,0 +1,32 @@
+// src/bridge/source.rs
+
+use romer_common::types::bridge::{BridgeSignature, Deposit, Withdrawal};
+use std::future::Future;
+use std::pin::Pin;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum SourceError {
+    #[error("{chain} RPC failed: {reason}")]
+    Rpc { chain: String, reason: String },
+
+    #[error("Malformed {chain} lock event: {reason}")]
+    Malformed { chain: String, reason: String },
+}
+
+pub type DepositsFuture<'a> = Pin<Box<dyn Future<Output = Result<Vec<Deposit>, SourceError>> + Send + 'a>>;
+pub type ReleaseFuture<'a> = Pin<Box<dyn Future<Output = Result<String, SourceError>> + Send + 'a>>;
+
+/// A chain assets are locked on to be bridged to Romer, and released on
+/// when their wrapped representation is burned
+pub trait SourceChainAdapter: Send + Sync {
+    /// Name deposits and withdrawals refer to the chain by
+    fn source_chain(&self) -> &str;
+
+    /// Locks confirmed since the previous poll
+    fn poll_deposits(&self) -> DepositsFuture<'_>;
+
+    /// Submits `withdrawal` with the validator signatures authorizing it,
+    /// resolving to the source chain's transaction reference
+    fn release<'a>(&'a self, withdrawal: &'a Withdrawal, signatures: &'a [BridgeSignature]) -> ReleaseFuture<'a>;
+}
@@ -0,0 +1,116 @@
+// src/cli.rs
+
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand};
+use romer_common::storage::compression::DEFAULT_DICTIONARY_SIZE;
+use std::path::PathBuf;
+
+/// Rømer Chain sequencer
+#[derive(Debug, Parser)]
+#[command(name = "romer-sequencer", version)]
+pub struct Cli {
+    /// TOML configuration file, `SEQUENCER_CONFIG` if not given
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Profile of the configuration file to apply, `SEQUENCER_PROFILE` if
+    /// not given
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the sequencer (the default)
+    Run,
+    /// Validate the configuration and print it as resolved
+    CheckConfig,
+    /// Replay an event journal written by the audit log and summarize it
+    Replay {
+        journal: PathBuf,
+    },
+    /// Regenerate the audit trail CSV of one trading day
+    ExportAudit {
+        /// Trading day, YYYY-MM-DD
+        date: NaiveDate,
+        /// Event log to export from, the configured audit log if not given
+        #[arg(long)]
+        log: Option<PathBuf>,
+        #[arg(long, default_value = "audit")]
+        out_dir: PathBuf,
+    },
+    /// Show the statistics of a running sequencer from its admin API
+    Status {
+        /// JSON-RPC address, the configured RPC port on this host if not given
+        #[arg(long)]
+        rpc: Option<String>,
+    },
+    /// Show or change the log level of a running sequencer from its admin API
+    LogLevel {
+        /// Level of one module, e.g. `sequencer::fix=debug`, or a bare level
+        /// replacing the default. Other modules keep their levels.
+        directive: Option<String>,
+        /// Restore the configured levels
+        #[arg(long, conflicts_with = "directive")]
+        reset: bool,
+        /// JSON-RPC address, the configured RPC port on this host if not given
+        #[arg(long)]
+        rpc: Option<String>,
+    },
+    /// Write the state of a running sequencer to a genesis bundle another
+    /// network can boot from with `storage.genesis`
+    ExportGenesis {
+        #[arg(long, default_value = "genesis.json")]
+        out: PathBuf,
+        /// JSON-RPC address, the configured RPC port on this host if not given
+        #[arg(long)]
+        rpc: Option<String>,
+    },
+    /// Move journal sections outside the retention window to the cold archive
+    Archive {
+        partitions: Vec<String>,
+    },
+    /// Measure how well the entries of journal partitions compress at each
+    /// level, with and without a dictionary trained on them
+    Compression {
+        partitions: Vec<String>,
+        #[arg(long, value_delimiter = ',', default_value = "1,3,9,19")]
+        levels: Vec<i32>,
+        /// Write the trained dictionary here, for `storage.compression_dictionary`
+        #[arg(long)]
+        train: Option<PathBuf>,
+        #[arg(long, default_value_t = DEFAULT_DICTIONARY_SIZE)]
+        dictionary_size: usize,
+    },
+    /// Restore archived sections `first..=last` of a partition
+    Rehydrate {
+        partition: String,
+        first: u64,
+        last: u64,
+        out_dir: PathBuf,
+    },
+    /// Inspect the state snapshots written every
+    /// `state_snapshots.interval_blocks`
+    State {
+        #[command(subcommand)]
+        command: StateCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum StateCommand {
+    /// Show the balances, nonces, organizations, objects and price levels
+    /// that differ between the snapshots at two heights
+    Diff {
+        from: u64,
+        to: u64,
+        /// Snapshot directory, the configured one if not given
+        #[arg(long)]
+        dir: Option<PathBuf>,
+        #[arg(long)]
+        json: bool,
+    },
+}
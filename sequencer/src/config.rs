@@ -0,0 +1,1902 @@
+// src/config.rs
+
+use crate::block::clock_quality::TimeSource;
+use crate::block::producer::DEFAULT_MAX_BLOCK_TRANSACTIONS;
+use crate::fix::heartbeat::HeartbeatBounds;
+use crate::mempool::pool::MempoolConfig;
+use crate::market::calendar::{MarketCalendar, TradingCalendar};
+use crate::market::data::DEFAULT_QUEUE_LIMIT;
+use crate::market::fees::FeeTier;
+use crate::market::registry::MarketConfig;
+use crate::risk::plugins::PluginLimits;
+use crate::session::state::LogoutTimers;
+use romer_common::types::address::Address;
+use romer_common::types::admin::AdminRole;
+use romer_common::types::bridge::BridgeCommittee;
+use romer_common::storage::archive::{ArchiveConfig, ObjectStoreConfig};
+use romer_common::storage::journal::{FsyncPolicy, StorageConfig};
+use romer_common::storage::metrics::CapacityThresholds;
+use romer_common::types::envelope::DEFAULT_CHAIN_ID;
+use romer_common::types::environment::ExecutionEnvironment;
+use romer_common::types::fix::FixConfig;
+use romer_common::types::protocol::{Activation, ProtocolSchedule};
+use chrono::NaiveTime;
+use commonware_cryptography::{Ed25519, PrivateKey, Scheme};
+use romer_common::utils::logging::LoggingConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Failed to read {path}: {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("Failed to parse configuration: {0}")]
+    Parse(String),
+
+    #[error("Unknown profile {0}")]
+    UnknownProfile(String),
+
+    #[error("Invalid {var}: {value}")]
+    Env { var: &'static str, value: String },
+
+    #[error("Invalid configuration: {0}")]
+    Invalid(String),
+}
+
+/// Listener addresses, and the chain they serve
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkConfig {
+    /// Chain id transactions must be signed for
+    pub chain_id: String,
+    /// Address every listener binds to
+    pub host: String,
+    pub fix_port: u16,
+    pub rpc_port: u16,
+    pub metrics_port: u16,
+    /// Native binary order gateway, off unless set
+    pub binary_port: Option<u16>,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            chain_id: DEFAULT_CHAIN_ID.to_string(),
+            host: "127.0.0.1".to_string(),
+            fix_port: 9878,
+            rpc_port: 9879,
+            metrics_port: 9880,
+            binary_port: None,
+        }
+    }
+}
+
+/// Block building and the mempool feeding it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BlockConfig {
+    /// Length of each block window
+    pub window_ms: u64,
+    /// Most messages batched into one block
+    pub max_batch_size: usize,
+    /// Most direct transactions taken from the mempool into one block
+    pub max_transactions: usize,
+    pub mempool_max_size: usize,
+    pub mempool_max_per_account: usize,
+    pub mempool_ttl_secs: u64,
+}
+
+impl Default for BlockConfig {
+    fn default() -> Self {
+        let mempool = MempoolConfig::default();
+        Self {
+            window_ms: 1000,
+            max_batch_size: 1000,
+            max_transactions: DEFAULT_MAX_BLOCK_TRANSACTIONS,
+            mempool_max_size: mempool.max_size,
+            mempool_max_per_account: mempool.max_per_account,
+            mempool_ttl_secs: mempool.ttl.as_secs(),
+        }
+    }
+}
+
+impl BlockConfig {
+    pub fn window(&self) -> Duration {
+        Duration::from_millis(self.window_ms)
+    }
+
+    pub fn mempool(&self) -> MempoolConfig {
+        MempoolConfig {
+            max_size: self.mempool_max_size,
+            max_per_account: self.mempool_max_per_account,
+            ttl: Duration::from_secs(self.mempool_ttl_secs),
+        }
+    }
+}
+
+/// What FIX sessions are held to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SessionPolicy {
+    pub heartbeat_min_secs: u32,
+    pub heartbeat_max_secs: u32,
+    pub heartbeat_default_secs: u32,
+    /// How long a connection stays open after confirming a Logout
+    pub logout_grace_secs: u64,
+    /// How long to wait for the counterparty to confirm our Logout
+    pub logout_confirm_timeout_secs: u64,
+    /// How long after closing a session's sequence numbers may be resumed
+    pub resume_window_secs: u64,
+}
+
+impl Default for SessionPolicy {
+    fn default() -> Self {
+        let bounds = HeartbeatBounds::default();
+        Self {
+            heartbeat_min_secs: bounds.min_secs,
+            heartbeat_max_secs: bounds.max_secs,
+            heartbeat_default_secs: bounds.default_secs,
+            logout_grace_secs: 2,
+            logout_confirm_timeout_secs: 10,
+            resume_window_secs: 300,
+        }
+    }
+}
+
+impl SessionPolicy {
+    pub fn heartbeat_bounds(&self) -> HeartbeatBounds {
+        HeartbeatBounds {
+            min_secs: self.heartbeat_min_secs,
+            max_secs: self.heartbeat_max_secs,
+            default_secs: self.heartbeat_default_secs,
+        }
+    }
+
+    pub fn logout_timers(&self) -> LogoutTimers {
+        LogoutTimers {
+            grace: Duration::from_secs(self.logout_grace_secs),
+            confirm_timeout: Duration::from_secs(self.logout_confirm_timeout_secs),
+        }
+    }
+
+    pub fn resume_window(&self) -> Duration {
+        Duration::from_secs(self.resume_window_secs)
+    }
+}
+
+/// Where the sequencer keeps its data
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StoragePaths {
+    /// Directory holding the journal partitions
+    pub directory: PathBuf,
+    /// Event log the audit trail is exported from, none unless set
+    pub audit_log: Option<PathBuf>,
+    /// Genesis bundle, written by `export-genesis`, whose balances, nonces
+    /// and organizations the sequencer starts from. Not allowed on mainnet.
+    pub genesis: Option<PathBuf>,
+    /// zstd level journal entries are compressed at, 1 to 22, or 0 to
+    /// store them as they are
+    pub compression_level: i32,
+    /// Dictionary written by `compression --train`, which journal entries
+    /// are compressed and read back with
+    pub compression_dictionary: Option<PathBuf>,
+    /// When journal appends are synced: `write`, `block` or
+    /// `interval:<millis>`
+    pub fsync: String,
+    /// Most entries a group commit appends at once
+    pub max_batch: usize,
+    /// Free space under which a warning is logged
+    pub warn_below_bytes: u64,
+    /// Free space under which new orders are no longer accepted
+    pub pause_below_bytes: u64,
+}
+
+impl Default for StoragePaths {
+    fn default() -> Self {
+        let thresholds = CapacityThresholds::default();
+        let journal = StorageConfig::default();
+        Self {
+            directory: journal.storage_directory,
+            audit_log: None,
+            genesis: None,
+            compression_level: journal.compression_level,
+            compression_dictionary: None,
+            fsync: "write".into(),
+            max_batch: journal.max_batch,
+            warn_below_bytes: thresholds.warn_below_bytes,
+            pause_below_bytes: thresholds.pause_below_bytes,
+        }
+    }
+}
+
+impl StoragePaths {
+    pub fn fsync_policy(&self) -> Result<FsyncPolicy, ConfigError> {
+        self.fsync
+            .parse()
+            .map_err(|e: String| ConfigError::Invalid(format!("storage.fsync: {}", e)))
+    }
+
+    /// Settings every journal is opened with
+    pub fn journal(&self) -> Result<StorageConfig, ConfigError> {
+        Ok(StorageConfig {
+            storage_directory: self.directory.clone(),
+            fsync: self.fsync_policy()?,
+            max_batch: self.max_batch,
+            compression_level: self.compression_level,
+            compression_dictionary: self.compression_dictionary.clone(),
+        })
+    }
+
+    pub fn thresholds(&self) -> CapacityThresholds {
+        CapacityThresholds {
+            warn_below_bytes: self.warn_below_bytes,
+            pause_below_bytes: self.pause_below_bytes,
+        }
+    }
+}
+
+/// Validators whose location and hardware attestations are accepted
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AttestationConfig {
+    /// Addresses of the registered validators. Attestations of anyone else
+    /// are refused, so none are accepted until this is set.
+    pub validators: Vec<Address>,
+}
+
+/// Protocol upgrades and how early operators are warned about them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProtocolConfig {
+    /// Protocol versions and the heights they activate at, the same on
+    /// every validator
+    pub activations: Vec<Activation>,
+    /// Blocks ahead of an activation this binary does not support that
+    /// warnings start
+    pub warn_blocks: u64,
+}
+
+impl Default for ProtocolConfig {
+    fn default() -> Self {
+        Self {
+            activations: Vec::new(),
+            warn_blocks: 10_000,
+        }
+    }
+}
+
+impl ProtocolConfig {
+    pub fn schedule(&self) -> ProtocolSchedule {
+        ProtocolSchedule {
+            activations: self.activations.clone(),
+        }
+    }
+}
+
+/// Analytics indexer, off unless a target is set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IndexerConfig {
+    /// Directory records wait in until every target has them, under the
+    /// storage directory unless set
+    pub outbox: Option<PathBuf>,
+    /// Connection string of the Postgres database to write to
+    pub postgres_url: Option<String>,
+    /// Kafka bootstrap brokers to publish to
+    pub kafka_brokers: Vec<String>,
+    /// Records are published to `<prefix>.<table>`
+    pub kafka_topic_prefix: String,
+    /// Most records delivered at once
+    pub batch_size: usize,
+    /// Wait before retrying a failed delivery
+    pub retry_ms: u64,
+}
+
+impl Default for IndexerConfig {
+    fn default() -> Self {
+        Self {
+            outbox: None,
+            postgres_url: None,
+            kafka_brokers: Vec::new(),
+            kafka_topic_prefix: "romer".to_string(),
+            batch_size: 500,
+            retry_ms: 1000,
+        }
+    }
+}
+
+impl IndexerConfig {
+    pub fn enabled(&self) -> bool {
+        self.postgres_url.is_some() || !self.kafka_brokers.is_empty()
+    }
+
+    pub fn outbox_dir(&self, storage_dir: &Path) -> PathBuf {
+        self.outbox.clone().unwrap_or_else(|| storage_dir.join("indexer-outbox"))
+    }
+}
+
+/// End-of-day reconciliation reports, off unless `session_close` is set
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReconciliationConfig {
+    /// UTC time the trading session closes and the day's reports are built
+    pub session_close: Option<NaiveTime>,
+    /// Directory reports are written to, under the storage directory unless
+    /// set
+    pub out_dir: Option<PathBuf>,
+    /// Fee charged to each side of a fill, in basis points of its notional
+    pub fee_bps: u32,
+    /// SenderCompID to the firm it reports under; unmapped sessions report
+    /// under their own SenderCompID
+    pub firms: BTreeMap<String, String>,
+    /// Program and arguments each written report is also handed to, e.g.
+    /// `curl` uploading to an SFTP server or sending through SMTP
+    pub deliver_command: Vec<String>,
+}
+
+impl ReconciliationConfig {
+    pub fn enabled(&self) -> bool {
+        self.session_close.is_some()
+    }
+
+    pub fn out_dir(&self, storage_dir: &Path) -> PathBuf {
+        self.out_dir.clone().unwrap_or_else(|| storage_dir.join("reconciliation"))
+    }
+}
+
+/// External system net settlement is pushed to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SettlementAdapterKind {
+    /// An EVM chain, through `eth_sendTransaction`
+    Evm,
+    /// The bank payments stub, which accepts and logs every instruction
+    Bank,
+}
+
+/// Settlement of reconciled amounts, off unless `adapter` is set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SettlementConfig {
+    pub adapter: Option<SettlementAdapterKind>,
+    /// Firm to its account on the external system
+    pub accounts: BTreeMap<String, String>,
+    /// Asset amounts are denominated in
+    pub asset: String,
+    /// EVM JSON-RPC endpoint, `host:port`
+    pub evm_rpc: Option<String>,
+    /// EVM account paying and collecting, signed for by the endpoint
+    pub evm_treasury: Option<String>,
+    /// ERC-20 contract settled in, native value without
+    pub evm_token: Option<String>,
+}
+
+impl Default for SettlementConfig {
+    fn default() -> Self {
+        Self {
+            adapter: None,
+            accounts: BTreeMap::new(),
+            asset: "USD".to_string(),
+            evm_rpc: None,
+            evm_treasury: None,
+            evm_token: None,
+        }
+    }
+}
+
+/// Oracle prices from whitelisted feeders, off unless `feeders` is set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OracleConfig {
+    /// Feeder name to its hex-encoded Ed25519 key
+    pub feeders: BTreeMap<String, String>,
+    /// Prices older than this are neither accepted nor aggregated
+    pub max_age_secs: u64,
+    /// Agreeing feeders required before a price is written
+    pub min_feeders: usize,
+    /// Prices further than this from the median are dropped as outliers
+    pub max_deviation_bps: u32,
+}
+
+impl Default for OracleConfig {
+    fn default() -> Self {
+        Self {
+            feeders: BTreeMap::new(),
+            max_age_secs: 30,
+            min_feeders: 1,
+            max_deviation_bps: 200,
+        }
+    }
+}
+
+impl OracleConfig {
+    pub fn enabled(&self) -> bool {
+        !self.feeders.is_empty()
+    }
+
+    pub fn max_age(&self) -> Duration {
+        Duration::from_secs(self.max_age_secs)
+    }
+}
+
+/// Validator votes on runtime parameters, off unless `validators` is set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GovernanceConfig {
+    /// Hex-encoded Ed25519 key of each voting validator to its stake
+    pub validators: BTreeMap<String, u64>,
+    /// Share of all stake that must approve a proposal
+    pub quorum_bps: u32,
+    /// Blocks between a proposal and the earliest height it may activate at
+    pub min_delay_blocks: u64,
+}
+
+impl Default for GovernanceConfig {
+    fn default() -> Self {
+        Self {
+            validators: BTreeMap::new(),
+            quorum_bps: 6_667,
+            min_delay_blocks: 100,
+        }
+    }
+}
+
+impl GovernanceConfig {
+    pub fn enabled(&self) -> bool {
+        !self.validators.is_empty()
+    }
+}
+
+/// A WASM pre-trade plugin version and the height it activates at
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginModuleConfig {
+    pub name: String,
+    pub version: u32,
+    pub path: PathBuf,
+    #[serde(default)]
+    pub activation_height: u64,
+}
+
+/// WASM pre-trade plugins, off unless `modules` is set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PluginsConfig {
+    /// Fuel each plugin call may burn, roughly one per instruction
+    pub fuel: u64,
+    /// Linear memory each plugin call may grow to
+    pub memory_bytes: usize,
+    /// Registered in order, so versions of a plugin must ascend
+    pub modules: Vec<PluginModuleConfig>,
+}
+
+impl Default for PluginsConfig {
+    fn default() -> Self {
+        Self {
+            fuel: 1_000_000,
+            memory_bytes: 1 << 20,
+            modules: Vec::new(),
+        }
+    }
+}
+
+impl PluginsConfig {
+    pub fn enabled(&self) -> bool {
+        !self.modules.is_empty()
+    }
+
+    pub fn limits(&self) -> PluginLimits {
+        PluginLimits {
+            fuel: self.fuel,
+            memory_bytes: self.memory_bytes,
+        }
+    }
+}
+
+/// Maker/taker fee tiers assigned by each firm's volume over an epoch, off
+/// unless `tiers` is set. Firms are those of `reconciliation.firms`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeesConfig {
+    pub epoch_secs: u64,
+    /// Ascending by `min_volume`, the first starting at zero
+    pub tiers: Vec<FeeTier>,
+}
+
+impl Default for FeesConfig {
+    fn default() -> Self {
+        Self {
+            epoch_secs: 86_400,
+            tiers: Vec::new(),
+        }
+    }
+}
+
+impl FeesConfig {
+    pub fn enabled(&self) -> bool {
+        !self.tiers.is_empty()
+    }
+
+    pub fn epoch(&self) -> Duration {
+        Duration::from_secs(self.epoch_secs)
+    }
+}
+
+/// Trading and settlement calendars by market TargetCompID, off unless
+/// `markets` is set. Markets without a calendar trade around the clock.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CalendarConfig {
+    /// Market whose calendar settlement dates on reconciliation reports
+    /// follow, needed only when more than one market has a calendar
+    pub settlement_market: Option<String>,
+    pub markets: BTreeMap<String, MarketCalendar>,
+}
+
+impl CalendarConfig {
+    pub fn enabled(&self) -> bool {
+        !self.markets.is_empty()
+    }
+
+    pub fn trading_calendar(&self) -> Result<TradingCalendar, ConfigError> {
+        TradingCalendar::new(self.markets.clone()).map_err(|e| ConfigError::Invalid(format!("calendar: {}", e)))
+    }
+
+    /// Calendar settlement dates follow
+    pub fn settlement_calendar(&self) -> Option<&MarketCalendar> {
+        match &self.settlement_market {
+            Some(market) => self.markets.get(market),
+            None if self.markets.len() == 1 => self.markets.values().next(),
+            None => None,
+        }
+    }
+}
+
+/// Clock offset monitoring against NTP and PTP sources, off unless
+/// `sources` is set. Blocks are not produced while the clock is off by more
+/// than `max_drift_ms` or has not been measured for `max_age_secs`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClockConfig {
+    pub max_drift_ms: u64,
+    pub max_age_secs: u64,
+    pub poll_secs: u64,
+    /// Source name to where it is read from
+    pub sources: BTreeMap<String, TimeSource>,
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        Self {
+            max_drift_ms: 100,
+            max_age_secs: 120,
+            poll_secs: 16,
+            sources: BTreeMap::new(),
+        }
+    }
+}
+
+impl ClockConfig {
+    pub fn enabled(&self) -> bool {
+        !self.sources.is_empty()
+    }
+
+    pub fn max_drift(&self) -> chrono::Duration {
+        chrono::Duration::milliseconds(self.max_drift_ms as i64)
+    }
+
+    pub fn max_age(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.max_age_secs as i64)
+    }
+
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_secs(self.poll_secs)
+    }
+}
+
+/// Ingress delay applied alike to every order message, off unless
+/// `delay_ms` is set
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SpeedBumpConfig {
+    pub delay_ms: u64,
+}
+
+impl SpeedBumpConfig {
+    pub fn enabled(&self) -> bool {
+        self.delay_ms > 0
+    }
+
+    pub fn delay(&self) -> Duration {
+        Duration::from_millis(self.delay_ms)
+    }
+}
+
+/// Latency budget of the order pipeline. When the smoothed queue or batch
+/// latency goes over its budget, new orders are rejected as throttled while
+/// cancels are still accepted, until both are back under `recover_pct` of
+/// their budgets for at least `min_shed_ms`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoadSheddingConfig {
+    pub enabled: bool,
+    /// Budget for the wait between a message arriving and its handling
+    pub max_queue_latency_ms: u64,
+    /// Budget for handling a batch of messages
+    pub max_batch_latency_ms: u64,
+    pub recover_pct: u8,
+    pub min_shed_ms: u64,
+}
+
+impl Default for LoadSheddingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_queue_latency_ms: 50,
+            max_batch_latency_ms: 200,
+            recover_pct: 50,
+            min_shed_ms: 1_000,
+        }
+    }
+}
+
+/// Test RØMER minted on request, off unless `drip` is set. Only development
+/// and testnet environments may enable it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FaucetConfig {
+    /// Most minted per request, in base units
+    pub drip: u64,
+    /// Most one address may receive in total
+    pub max_per_address: u64,
+    /// Wait between requests from one address
+    pub cooldown_secs: u64,
+    /// Hex object id of the shared `romer::faucet::Faucet` minting the
+    /// requests
+    pub object: Option<String>,
+    /// Hex Ed25519 private key signing the mints the sequencer submits
+    pub key: Option<String>,
+}
+
+impl Default for FaucetConfig {
+    fn default() -> Self {
+        Self {
+            drip: 0,
+            max_per_address: 0,
+            cooldown_secs: 60,
+            object: None,
+            key: None,
+        }
+    }
+}
+
+impl FaucetConfig {
+    pub fn enabled(&self) -> bool {
+        self.drip > 0
+    }
+
+    /// The shared faucet object, if `object` is a valid id
+    pub fn object(&self) -> Option<Address> {
+        self.object.as_deref()?.parse().ok()
+    }
+
+    /// Signer of the faucet's mints, if `key` is a valid private key
+    pub fn signer(&self) -> Option<Ed25519> {
+        let key = hex::decode(self.key.as_deref()?).ok()?;
+        <Ed25519 as Scheme>::from(PrivateKey::from(key))
+    }
+}
+
+/// API keys and rate limits of the public read methods (`get_*`) of the
+/// JSON-RPC endpoint. Keys are managed with the `admin_*_api_key` methods.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiKeyConfig {
+    /// Refuse reads without a valid key
+    pub required: bool,
+    /// Requests a second of keys created without their own limit
+    pub rate_per_sec: u32,
+    /// Requests a key may make at once after being idle
+    pub burst: u32,
+    /// Requests a second from one address without a key, unlimited at 0.
+    /// Only applies while keys are not required.
+    pub anonymous_rate_per_sec: u32,
+}
+
+impl Default for ApiKeyConfig {
+    fn default() -> Self {
+        Self {
+            required: false,
+            rate_per_sec: 10,
+            burst: 20,
+            anonymous_rate_per_sec: 0,
+        }
+    }
+}
+
+/// Mutual TLS and role-based access to the `admin_*` methods. They are
+/// refused until a certificate or key is given a role, which outside
+/// development is required.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdminConfig {
+    /// Server certificate chain, PEM. The JSON-RPC endpoint speaks TLS
+    /// when set, together with `tls_key`.
+    pub tls_cert: Option<PathBuf>,
+    pub tls_key: Option<PathBuf>,
+    /// CA bundle client certificates are verified against, PEM. Clients
+    /// without a certificate are still served the public methods.
+    pub client_ca: Option<PathBuf>,
+    /// Roles of client certificates, by hex SHA-256 fingerprint of the DER
+    /// encoded certificate
+    pub certificates: BTreeMap<String, AdminRole>,
+    /// Roles of KeyManager Ed25519 keys, by hex public key, whose signed
+    /// admin tokens are accepted. Bearer tokens are only accepted over TLS
+    /// outside development.
+    pub tokens: BTreeMap<String, AdminRole>,
+    /// Furthest ahead an admin token may expire
+    pub token_max_lifetime_secs: u64,
+}
+
+impl Default for AdminConfig {
+    fn default() -> Self {
+        Self {
+            tls_cert: None,
+            tls_key: None,
+            client_ca: None,
+            certificates: BTreeMap::new(),
+            tokens: BTreeMap::new(),
+            token_max_lifetime_secs: 3600,
+        }
+    }
+}
+
+impl AdminConfig {
+    /// Whether the admin methods are access controlled
+    pub fn enabled(&self) -> bool {
+        !self.certificates.is_empty() || !self.tokens.is_empty()
+    }
+
+    /// Certificate and key the endpoint serves TLS with
+    pub fn tls(&self) -> Option<(&Path, &Path)> {
+        self.tls_cert.as_deref().zip(self.tls_key.as_deref())
+    }
+}
+
+/// A source chain's lock contract, watched for deposits
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BridgeChainConfig {
+    /// EVM JSON-RPC endpoint, `host:port`
+    pub rpc: String,
+    pub lock_contract: String,
+    /// Account releases are sent from, signed for by the endpoint
+    pub sender: String,
+    /// Move type the locked asset is wrapped as, `<address>::<module>::<struct>`
+    pub asset: String,
+    /// Blocks a lock must be buried under before it is relayed
+    pub confirmations: u64,
+    /// Block to scan from, the head at startup without
+    pub start_block: Option<u64>,
+}
+
+/// Asset bridge from source chains, off unless `validators` is set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BridgeConfig {
+    /// Hex-encoded Ed25519 keys of the bridge committee, in the order the
+    /// `romer::bridge` module holds them
+    pub validators: Vec<String>,
+    /// Attestations required to mint or release
+    pub threshold: usize,
+    pub poll_secs: u64,
+    /// Source chain name to its lock contract
+    pub chains: BTreeMap<String, BridgeChainConfig>,
+}
+
+impl Default for BridgeConfig {
+    fn default() -> Self {
+        Self {
+            validators: Vec::new(),
+            threshold: 0,
+            poll_secs: 15,
+            chains: BTreeMap::new(),
+        }
+    }
+}
+
+impl BridgeConfig {
+    pub fn enabled(&self) -> bool {
+        !self.validators.is_empty()
+    }
+
+    pub fn committee(&self) -> BridgeCommittee {
+        BridgeCommittee {
+            validators: self.validators.clone(),
+            threshold: self.threshold,
+        }
+    }
+}
+
+/// Periodic state snapshots `state diff` compares, off unless
+/// `interval_blocks` is set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StateSnapshotConfig {
+    /// Blocks between snapshots
+    pub interval_blocks: u64,
+    /// `state-snapshots` under the storage directory unless set
+    pub directory: Option<PathBuf>,
+    /// Newest snapshots kept
+    pub keep: usize,
+}
+
+impl Default for StateSnapshotConfig {
+    fn default() -> Self {
+        Self {
+            interval_blocks: 0,
+            directory: None,
+            keep: 100,
+        }
+    }
+}
+
+impl StateSnapshotConfig {
+    pub fn enabled(&self) -> bool {
+        self.interval_blocks > 0
+    }
+
+    pub fn dir(&self, storage_dir: &Path) -> PathBuf {
+        self.directory.clone().unwrap_or_else(|| storage_dir.join("state-snapshots"))
+    }
+}
+
+/// Journal sections moved out of the live storage directory by `archive`,
+/// and the bucket they are uploaded to if `bucket` is set. Credentials come
+/// from the usual `AWS_ACCESS_KEY_ID` / `AWS_SECRET_ACCESS_KEY` variables.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColdArchiveConfig {
+    /// Where compressed archive files and the manifest live
+    pub directory: PathBuf,
+    /// Newest sections of each partition kept in the live journal
+    pub retention_sections: u64,
+    pub bucket: Option<String>,
+    /// Custom endpoint for non-AWS stores such as MinIO
+    pub endpoint: Option<String>,
+    pub region: String,
+    /// Key prefix archives are stored under
+    pub prefix: String,
+}
+
+impl Default for ColdArchiveConfig {
+    fn default() -> Self {
+        Self {
+            directory: "devnet-archive".into(),
+            retention_sections: 64,
+            bucket: None,
+            endpoint: None,
+            region: "us-east-1".into(),
+            prefix: "romer".into(),
+        }
+    }
+}
+
+impl ColdArchiveConfig {
+    pub fn archive(&self) -> ArchiveConfig {
+        ArchiveConfig {
+            archive_directory: self.directory.clone(),
+            retention_sections: self.retention_sections,
+            object_store: self.bucket.clone().map(|bucket| ObjectStoreConfig {
+                bucket,
+                endpoint: self.endpoint.clone(),
+                region: self.region.clone(),
+                prefix: self.prefix.clone(),
+            }),
+        }
+    }
+}
+
+/// How long designated market makers' quoting obligations are measured
+/// over before rewards are announced
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ObligationsConfig {
+    pub epoch_secs: u64,
+}
+
+impl Default for ObligationsConfig {
+    fn default() -> Self {
+        Self { epoch_secs: 3600 }
+    }
+}
+
+impl ObligationsConfig {
+    pub fn epoch(&self) -> Duration {
+        Duration::from_secs(self.epoch_secs)
+    }
+}
+
+/// Feeds of the reference prices collars, circuit breakers and margining
+/// use, tried in order: a pushed stream, a polled RPC endpoint, then prices
+/// entered by an administrator
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReferencePriceConfig {
+    /// Feed prices older than this are stale
+    pub max_age_secs: u64,
+    /// Administrator prices older than this are stale
+    pub manual_max_age_secs: u64,
+    /// Address of a price stream, none unless set
+    pub stream: Option<String>,
+    /// Address of a JSON-RPC endpoint polled for prices, none unless set
+    pub rpc: Option<String>,
+    pub rpc_method: String,
+    /// Orders priced further than this from the reference price are
+    /// rejected, unbounded unless set
+    pub collar_bps: Option<u32>,
+}
+
+impl Default for ReferencePriceConfig {
+    fn default() -> Self {
+        Self {
+            max_age_secs: 10,
+            manual_max_age_secs: 86_400,
+            stream: None,
+            rpc: None,
+            rpc_method: "get_price".into(),
+            collar_bps: None,
+        }
+    }
+}
+
+impl ReferencePriceConfig {
+    pub fn max_age(&self) -> Duration {
+        Duration::from_secs(self.max_age_secs)
+    }
+
+    pub fn manual_max_age(&self) -> Duration {
+        Duration::from_secs(self.manual_max_age_secs)
+    }
+
+    /// Twice per staleness window, so one missed poll does not fail over
+    pub fn poll_interval(&self) -> Duration {
+        (self.max_age() / 2).max(Duration::from_millis(100))
+    }
+}
+
+/// Market data fan-out
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MarketDataConfig {
+    /// Updates queued for a consumer before it is sent the latest state of
+    /// each price level instead
+    pub queue_limit: usize,
+}
+
+impl Default for MarketDataConfig {
+    fn default() -> Self {
+        Self {
+            queue_limit: DEFAULT_QUEUE_LIMIT,
+        }
+    }
+}
+
+/// Periodic statistics logging
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StatsConfig {
+    pub log_secs: u64,
+}
+
+impl Default for StatsConfig {
+    fn default() -> Self {
+        Self { log_secs: 60 }
+    }
+}
+
+impl StatsConfig {
+    pub fn log_interval(&self) -> Duration {
+        Duration::from_secs(self.log_secs)
+    }
+}
+
+/// Settings of a sequencer instance. Built from the defaults, then a TOML
+/// file, then the file's `[profiles.<name>]` table for the selected
+/// environment profile, then the environment.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SequencerConfig {
+    /// Network the sequencer serves, which decides whether the faucet may run
+    pub environment: ExecutionEnvironment,
+    pub network: NetworkConfig,
+    pub block: BlockConfig,
+    pub session: SessionPolicy,
+    pub storage: StoragePaths,
+    pub attestation: AttestationConfig,
+    pub logging: LoggingConfig,
+    pub protocol: ProtocolConfig,
+    pub indexer: IndexerConfig,
+    pub reconciliation: ReconciliationConfig,
+    pub settlement: SettlementConfig,
+    pub bridge: BridgeConfig,
+    pub oracle: OracleConfig,
+    pub governance: GovernanceConfig,
+    pub plugins: PluginsConfig,
+    pub fees: FeesConfig,
+    pub calendar: CalendarConfig,
+    pub clock: ClockConfig,
+    pub speed_bump: SpeedBumpConfig,
+    pub load_shedding: LoadSheddingConfig,
+    pub faucet: FaucetConfig,
+    pub api_keys: ApiKeyConfig,
+    pub admin: AdminConfig,
+    pub state_snapshots: StateSnapshotConfig,
+    pub archive: ColdArchiveConfig,
+    /// Markets hosted, by TargetCompID. One market named by the default
+    /// FIX TargetCompID, listing every symbol, unless set.
+    pub markets: Vec<MarketConfig>,
+    pub obligations: ObligationsConfig,
+    pub reference_prices: ReferencePriceConfig,
+    pub market_data: MarketDataConfig,
+    pub stats: StatsConfig,
+}
+
+impl SequencerConfig {
+    /// The configured markets, or the default market
+    pub fn markets(&self) -> Vec<MarketConfig> {
+        if !self.markets.is_empty() {
+            return self.markets.clone();
+        }
+        vec![MarketConfig {
+            target_comp_id: FixConfig::default().target_comp_id,
+            symbols: Default::default(),
+        }]
+    }
+
+    /// Loads `path`, or else the file at `SEQUENCER_CONFIG` if set, under
+    /// `profile`, or else the profile named by `SEQUENCER_PROFILE`, then
+    /// applies environment overrides
+    pub fn resolve(path: Option<PathBuf>, profile: Option<String>) -> Result<Self, ConfigError> {
+        let path = path.or_else(|| std::env::var("SEQUENCER_CONFIG").ok().map(PathBuf::from));
+        let profile = profile.or_else(|| std::env::var("SEQUENCER_PROFILE").ok());
+        Self::load(path.as_deref(), profile.as_deref())
+    }
+
+    /// Loads `path`, or the defaults without one, under `profile`, then
+    /// applies environment overrides and validates the result
+    pub fn load(path: Option<&Path>, profile: Option<&str>) -> Result<Self, ConfigError> {
+        let mut config = match path {
+            Some(path) => {
+                let raw = std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+                    path: path.to_path_buf(),
+                    source,
+                })?;
+                Self::parse(&raw, profile)?
+            }
+            None if profile.is_some() => {
+                return Err(ConfigError::UnknownProfile(profile.unwrap_or_default().to_string()))
+            }
+            None => Self::default(),
+        };
+        config.apply_env()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Parses a TOML document, overlaying the `[profiles.<profile>]` table
+    /// on the top level settings
+    pub fn parse(raw: &str, profile: Option<&str>) -> Result<Self, ConfigError> {
+        let mut document: toml::Table = raw.parse().map_err(|e: toml::de::Error| ConfigError::Parse(e.to_string()))?;
+        let mut profiles = match document.remove("profiles") {
+            Some(toml::Value::Table(profiles)) => profiles,
+            Some(_) => return Err(ConfigError::Parse("`profiles` must be a table".into())),
+            None => toml::Table::new(),
+        };
+        if let Some(name) = profile {
+            match profiles.remove(name) {
+                Some(toml::Value::Table(overlay)) => merge(&mut document, overlay),
+                Some(_) => return Err(ConfigError::Parse(format!("profile {} must be a table", name))),
+                None => return Err(ConfigError::UnknownProfile(name.to_string())),
+            }
+        }
+        toml::Value::Table(document)
+            .try_into()
+            .map_err(|e: toml::de::Error| ConfigError::Parse(e.to_string()))
+    }
+
+    /// Overrides settings from the environment variables read before the
+    /// configuration file existed, which still take precedence over it
+    pub fn apply_env(&mut self) -> Result<(), ConfigError> {
+        fn parse<T: std::str::FromStr>(var: &'static str, value: String) -> Result<T, ConfigError> {
+            value.parse().map_err(|_| ConfigError::Env { var, value })
+        }
+
+        if let Ok(value) = std::env::var("SEQUENCER_ENVIRONMENT") {
+            self.environment = parse("SEQUENCER_ENVIRONMENT", value)?;
+        }
+        if let Ok(chain_id) = std::env::var("SEQUENCER_CHAIN_ID") {
+            self.network.chain_id = chain_id;
+        }
+        if let Ok(host) = std::env::var("SEQUENCER_HOST") {
+            self.network.host = host;
+        }
+        for (var, port) in [
+            ("SEQUENCER_PORT", &mut self.network.fix_port),
+            ("SEQUENCER_RPC_PORT", &mut self.network.rpc_port),
+            ("SEQUENCER_METRICS_PORT", &mut self.network.metrics_port),
+        ] {
+            if let Ok(value) = std::env::var(var) {
+                *port = parse(var, value)?;
+            }
+        }
+        if let Ok(value) = std::env::var("SEQUENCER_BINARY_PORT") {
+            self.network.binary_port = Some(parse("SEQUENCER_BINARY_PORT", value)?);
+        }
+        for (var, secs) in [
+            ("ROMER_HEARTBEAT_MIN_SECS", &mut self.session.heartbeat_min_secs),
+            ("ROMER_HEARTBEAT_MAX_SECS", &mut self.session.heartbeat_max_secs),
+            ("ROMER_HEARTBEAT_DEFAULT_SECS", &mut self.session.heartbeat_default_secs),
+        ] {
+            if let Ok(value) = std::env::var(var) {
+                *secs = parse(var, value)?;
+            }
+        }
+        if let Ok(dir) = std::env::var("ROMER_STORAGE_DIR") {
+            self.storage.directory = dir.into();
+        }
+        if let Ok(path) = std::env::var("SEQUENCER_AUDIT_LOG") {
+            self.storage.audit_log = Some(path.into());
+        }
+        if let Ok(path) = std::env::var("SEQUENCER_GENESIS") {
+            self.storage.genesis = Some(path.into());
+        }
+        if let Ok(value) = std::env::var("ROMER_COMPRESSION_LEVEL") {
+            self.storage.compression_level = parse("ROMER_COMPRESSION_LEVEL", value)?;
+        }
+        if let Ok(path) = std::env::var("ROMER_COMPRESSION_DICTIONARY") {
+            self.storage.compression_dictionary = Some(path.into());
+        }
+        if let Ok(key) = std::env::var("SEQUENCER_FAUCET_KEY") {
+            self.faucet.key = Some(key);
+        }
+        if let Ok(value) = std::env::var("ROMER_FSYNC_POLICY") {
+            self.storage.fsync = value;
+        }
+        if let Ok(dir) = std::env::var("ROMER_ARCHIVE_DIR") {
+            self.archive.directory = dir.into();
+        }
+        for (var, value) in [
+            ("ROMER_STORAGE_WARN_BELOW_BYTES", &mut self.storage.warn_below_bytes),
+            ("ROMER_STORAGE_PAUSE_BELOW_BYTES", &mut self.storage.pause_below_bytes),
+            ("ROMER_ARCHIVE_RETENTION", &mut self.archive.retention_sections),
+            ("SEQUENCER_OBLIGATION_EPOCH_SECS", &mut self.obligations.epoch_secs),
+            ("SEQUENCER_PRICE_MAX_AGE_SECS", &mut self.reference_prices.max_age_secs),
+            ("SEQUENCER_MANUAL_PRICE_MAX_AGE_SECS", &mut self.reference_prices.manual_max_age_secs),
+            ("SEQUENCER_STATS_LOG_SECS", &mut self.stats.log_secs),
+        ] {
+            if let Ok(raw) = std::env::var(var) {
+                *value = parse(var, raw)?;
+            }
+        }
+        for (var, value) in [
+            ("ROMER_STORAGE_MAX_BATCH", &mut self.storage.max_batch),
+            ("SEQUENCER_MD_QUEUE_LIMIT", &mut self.market_data.queue_limit),
+        ] {
+            if let Ok(raw) = std::env::var(var) {
+                *value = parse(var, raw)?;
+            }
+        }
+        for (var, value) in [
+            ("ROMER_ARCHIVE_REGION", &mut self.archive.region),
+            ("ROMER_ARCHIVE_PREFIX", &mut self.archive.prefix),
+            ("SEQUENCER_PRICE_FEED_RPC_METHOD", &mut self.reference_prices.rpc_method),
+        ] {
+            if let Ok(raw) = std::env::var(var) {
+                *value = raw;
+            }
+        }
+        for (var, value) in [
+            ("ROMER_ARCHIVE_BUCKET", &mut self.archive.bucket),
+            ("ROMER_ARCHIVE_ENDPOINT", &mut self.archive.endpoint),
+            ("SEQUENCER_PRICE_FEED_STREAM", &mut self.reference_prices.stream),
+            ("SEQUENCER_PRICE_FEED_RPC", &mut self.reference_prices.rpc),
+        ] {
+            if let Ok(raw) = std::env::var(var) {
+                *value = Some(raw);
+            }
+        }
+        if let Ok(value) = std::env::var("SEQUENCER_PRICE_COLLAR_BPS") {
+            self.reference_prices.collar_bps = Some(parse("SEQUENCER_PRICE_COLLAR_BPS", value)?);
+        }
+        if let Ok(value) = std::env::var("SEQUENCER_MARKETS") {
+            self.markets = MarketConfig::parse_list(&value).map_err(|_| ConfigError::Env {
+                var: "SEQUENCER_MARKETS",
+                value,
+            })?;
+        }
+        if let Ok(url) = std::env::var("SEQUENCER_INDEXER_POSTGRES_URL") {
+            self.indexer.postgres_url = Some(url);
+        }
+        if let Ok(level) = std::env::var("SEQUENCER_LOG") {
+            self.logging.level = level;
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |reason: &str| Err(ConfigError::Invalid(reason.to_string()));
+
+        let network = &self.network;
+        if network.chain_id.is_empty() {
+            return invalid("network.chain_id must be set");
+        }
+        if network.host.parse::<IpAddr>().is_err() {
+            return Err(ConfigError::Invalid(format!("network.host {:?} is not an IP address", network.host)));
+        }
+        let mut ports = vec![network.fix_port, network.rpc_port, network.metrics_port];
+        ports.extend(network.binary_port);
+        if ports.contains(&0) {
+            return invalid("network ports must be nonzero");
+        }
+        ports.sort_unstable();
+        if ports.windows(2).any(|pair| pair[0] == pair[1]) {
+            return invalid("network ports must be distinct");
+        }
+
+        let block = &self.block;
+        if block.window_ms == 0 || block.max_batch_size == 0 {
+            return invalid("block.window_ms and block.max_batch_size must be nonzero");
+        }
+        if block.mempool_max_size == 0 || block.mempool_max_per_account == 0 || block.mempool_ttl_secs == 0 {
+            return invalid("mempool limits must be nonzero");
+        }
+
+        let session = &self.session;
+        if session.heartbeat_min_secs == 0 || session.heartbeat_min_secs > session.heartbeat_max_secs {
+            return invalid("heartbeat bounds must satisfy 0 < min <= max");
+        }
+        if !(session.heartbeat_min_secs..=session.heartbeat_max_secs).contains(&session.heartbeat_default_secs) {
+            return invalid("default heartbeat interval is outside the bounds");
+        }
+        if session.logout_confirm_timeout_secs == 0 {
+            return invalid("session.logout_confirm_timeout_secs must be nonzero");
+        }
+
+        if self.storage.directory.as_os_str().is_empty() {
+            return invalid("storage.directory must be set");
+        }
+        if self.indexer.enabled() && (self.indexer.batch_size == 0 || self.indexer.retry_ms == 0) {
+            return invalid("indexer.batch_size and indexer.retry_ms must be nonzero");
+        }
+        if self.reconciliation.enabled() && self.storage.audit_log.is_none() {
+            return invalid("reconciliation reads the event log, so storage.audit_log must be set");
+        }
+        if self.reconciliation.fee_bps > 10_000 {
+            return invalid("reconciliation.fee_bps must be at most 10000");
+        }
+        if self.settlement.adapter.is_some() && !self.reconciliation.enabled() {
+            return invalid("settlement settles reconciled amounts, so reconciliation must be enabled");
+        }
+        if self.settlement.adapter == Some(SettlementAdapterKind::Evm)
+            && (self.settlement.evm_rpc.is_none() || self.settlement.evm_treasury.is_none())
+        {
+            return invalid("the evm settlement adapter needs settlement.evm_rpc and settlement.evm_treasury");
+        }
+        if self.bridge.enabled() {
+            self.bridge
+                .committee()
+                .validate()
+                .map_err(|e| ConfigError::Invalid(format!("bridge: {}", e)))?;
+            if self.bridge.poll_secs == 0 {
+                return invalid("bridge.poll_secs must be nonzero");
+            }
+            if let Some((name, _)) = self.bridge.chains.iter().find(|(_, chain)| chain.asset.split("::").count() != 3) {
+                return Err(ConfigError::Invalid(format!(
+                    "bridge.chains.{}.asset must be <address>::<module>::<struct>",
+                    name
+                )));
+            }
+        }
+        let oracle = &self.oracle;
+        if oracle.enabled() {
+            if oracle.min_feeders == 0 || oracle.min_feeders > oracle.feeders.len() {
+                return invalid("oracle.min_feeders must be between 1 and the number of feeders");
+            }
+            if oracle.max_age_secs == 0 || oracle.max_deviation_bps > 10_000 {
+                return invalid("oracle.max_age_secs must be nonzero and oracle.max_deviation_bps at most 10000");
+            }
+            let invalid_key = oracle
+                .feeders
+                .iter()
+                .find(|(_, key)| !matches!(hex::decode(key), Ok(key) if key.len() == 32));
+            if let Some((name, _)) = invalid_key {
+                return Err(ConfigError::Invalid(format!("oracle.feeders.{} is not a hex Ed25519 key", name)));
+            }
+        }
+        let governance = &self.governance;
+        if governance.enabled() {
+            if governance.quorum_bps == 0 || governance.quorum_bps > 10_000 {
+                return invalid("governance.quorum_bps must be between 1 and 10000");
+            }
+            if governance.validators.values().any(|stake| *stake == 0) {
+                return invalid("governance.validators must each have a nonzero stake");
+            }
+            let invalid_key = governance
+                .validators
+                .keys()
+                .find(|key| !matches!(hex::decode(key), Ok(key) if key.len() == 32));
+            if let Some(key) = invalid_key {
+                return Err(ConfigError::Invalid(format!("governance.validators.{} is not a hex Ed25519 key", key)));
+            }
+        }
+        if self.fees.enabled() && self.fees.epoch_secs == 0 {
+            return invalid("fees.epoch_secs must be nonzero");
+        }
+        if self.calendar.enabled() {
+            self.calendar.trading_calendar()?;
+            if let Some(market) = &self.calendar.settlement_market {
+                if !self.calendar.markets.contains_key(market) {
+                    return Err(ConfigError::Invalid(format!(
+                        "calendar.settlement_market {} has no calendar",
+                        market
+                    )));
+                }
+            }
+        }
+        if self.speed_bump.delay_ms > 1_000 {
+            return invalid("speed_bump.delay_ms must be at most 1000");
+        }
+        let shedding = &self.load_shedding;
+        if shedding.max_queue_latency_ms == 0 || shedding.max_batch_latency_ms == 0 {
+            return invalid("load_shedding latency budgets must be nonzero");
+        }
+        if shedding.recover_pct == 0 || shedding.recover_pct > 100 {
+            return invalid("load_shedding.recover_pct must be between 1 and 100");
+        }
+        if self.faucet.enabled() {
+            if !self.environment.allows_faucet() {
+                return Err(ConfigError::Invalid(format!("the faucet cannot run in the {} environment", self.environment)));
+            }
+            if self.faucet.max_per_address < self.faucet.drip {
+                return invalid("faucet.max_per_address must be at least faucet.drip");
+            }
+            if self.faucet.object().is_none() || self.faucet.signer().is_none() {
+                return invalid("faucet.object and faucet.key must be a hex object id and Ed25519 private key");
+            }
+        }
+        if self.storage.genesis.is_some() && self.environment == ExecutionEnvironment::Mainnet {
+            return invalid("mainnet cannot boot from a genesis bundle");
+        }
+        if !(0..=22).contains(&self.storage.compression_level) {
+            return invalid("storage.compression_level must be between 0 and 22");
+        }
+        let storage = &self.storage;
+        storage.fsync_policy()?;
+        if storage.max_batch == 0 {
+            return invalid("storage.max_batch must be nonzero");
+        }
+        if storage.pause_below_bytes > storage.warn_below_bytes {
+            return invalid("storage.pause_below_bytes must be at most storage.warn_below_bytes");
+        }
+        if self.archive.retention_sections == 0 {
+            return invalid("archive.retention_sections must be nonzero");
+        }
+        if !self.markets.is_empty() {
+            MarketConfig::validate_list(&self.markets).map_err(|e| ConfigError::Invalid(format!("markets: {}", e)))?;
+        }
+        if self.obligations.epoch_secs == 0 || self.stats.log_secs == 0 {
+            return invalid("obligations.epoch_secs and stats.log_secs must be nonzero");
+        }
+        if self.reference_prices.max_age_secs == 0 || self.reference_prices.manual_max_age_secs == 0 {
+            return invalid("reference_prices.max_age_secs and manual_max_age_secs must be nonzero");
+        }
+        if self.market_data.queue_limit == 0 {
+            return invalid("market_data.queue_limit must be nonzero");
+        }
+        if self.state_snapshots.enabled() && self.state_snapshots.keep == 0 {
+            return invalid("state_snapshots.keep must be nonzero");
+        }
+        if self.api_keys.rate_per_sec == 0 || self.api_keys.burst == 0 {
+            return invalid("api_keys.rate_per_sec and api_keys.burst must be nonzero");
+        }
+        let admin = &self.admin;
+        if admin.tls_cert.is_some() != admin.tls_key.is_some() {
+            return invalid("admin.tls_cert and admin.tls_key must be set together");
+        }
+        if admin.client_ca.is_some() && admin.tls().is_none() {
+            return invalid("admin.client_ca needs admin.tls_cert and admin.tls_key");
+        }
+        if !admin.certificates.is_empty() && admin.client_ca.is_none() {
+            return invalid("admin.certificates are only presented with admin.client_ca set");
+        }
+        if admin.token_max_lifetime_secs == 0 {
+            return invalid("admin.token_max_lifetime_secs must be nonzero");
+        }
+        if self.environment != ExecutionEnvironment::Development {
+            if !admin.enabled() {
+                return Err(ConfigError::Invalid(format!(
+                    "the {} environment needs admin.certificates or admin.tokens",
+                    self.environment
+                )));
+            }
+            // A token sent in the clear could be replayed by anyone on the path
+            if !admin.tokens.is_empty() && admin.tls().is_none() {
+                return Err(ConfigError::Invalid(format!(
+                    "admin.tokens need admin.tls_cert and admin.tls_key in the {} environment",
+                    self.environment
+                )));
+            }
+        }
+        let clock = &self.clock;
+        if clock.enabled() {
+            if clock.poll_secs == 0 || clock.max_age_secs < clock.poll_secs {
+                return invalid("clock.poll_secs must be nonzero and at most clock.max_age_secs");
+            }
+            if clock.sources.values().any(|source| matches!(source, TimeSource::Ptp { command } if command.is_empty())) {
+                return invalid("clock.sources of kind ptp need a command");
+            }
+        }
+        if self.plugins.enabled() && (self.plugins.fuel == 0 || self.plugins.memory_bytes < 65_536) {
+            return invalid("plugins.fuel must be nonzero and plugins.memory_bytes at least one 64KiB page");
+        }
+        self.protocol
+            .schedule()
+            .validate()
+            .map_err(|e| ConfigError::Invalid(format!("protocol: {}", e)))?;
+        self.logging
+            .validate()
+            .map_err(|e| ConfigError::Invalid(format!("logging: {}", e)))
+
+    }
+}
+
+/// Overlays `overlay` on `base`, merging nested tables key by key
+fn merge(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overlay)) => merge(base, overlay),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use romer_common::utils::logging::LogFormat;
+    use chrono::NaiveDate;
+
+    const CONFIG: &str = r#"
+        [network]
+        fix_port = 7000
+
+        [block]
+        window_ms = 500
+
+        [profiles.production]
+        environment = "mainnet"
+
+        [profiles.production.network]
+        host = "0.0.0.0"
+        binary_port = 7100
+
+        [profiles.production.session]
+        resume_window_secs = 60
+
+        [logging]
+        format = "json"
+
+        [profiles.production.logging]
+        level = "warn"
+        file = "/var/log/romer/sequencer.log"
+
+        [attestation]
+        validators = ["0x0505050505050505050505050505050505050505050505050505050505050505"]
+
+        [[protocol.activations]]
+        version = 2
+        height = 1000
+        description = "gas schedule v2"
+
+        [profiles.production.indexer]
+        kafka_brokers = ["kafka-1:9092", "kafka-2:9092"]
+
+        [profiles.production.storage]
+        audit_log = "/var/lib/romer/events.jsonl"
+        compression_level = 3
+        fsync = "interval:5"
+        pause_below_bytes = 4294967296
+
+        [profiles.production.archive]
+        bucket = "romer-archive"
+
+        [[profiles.production.markets]]
+        target_comp_id = "ROMER"
+        symbols = ["AAPL", "MSFT"]
+
+        [[profiles.production.markets]]
+        target_comp_id = "ROMER-FX"
+
+        [profiles.production.reference_prices]
+        rpc = "prices:8545"
+        collar_bps = 500
+
+        [profiles.production.state_snapshots]
+        interval_blocks = 1000
+
+        [profiles.production.reconciliation]
+        session_close = "21:00:00"
+        fee_bps = 2
+        firms = { MM1 = "ACME", MM2 = "ACME" }
+
+        [profiles.production.settlement]
+        adapter = "evm"
+        evm_rpc = "geth:8545"
+        evm_treasury = "0x2222222222222222222222222222222222222222"
+        accounts = { ACME = "0x1111111111111111111111111111111111111111" }
+
+        [profiles.production.oracle]
+        min_feeders = 2
+        feeders = { chainlink = "8a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c", pyth = "8139770ea87d175f56a35466c34c7ecccb8d8a91b4ee37a25df60f5b8fc9b394" }
+
+        [profiles.production.governance]
+        quorum_bps = 5000
+        validators = { 8a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c = 60, 8139770ea87d175f56a35466c34c7ecccb8d8a91b4ee37a25df60f5b8fc9b394 = 40 }
+
+        [[profiles.production.plugins.modules]]
+        name = "jurisdiction"
+        version = 1
+        path = "/etc/romer/plugins/jurisdiction-v1.wasm"
+
+        [[profiles.production.fees.tiers]]
+        name = "base"
+        min_volume = 0
+        maker_bps = 2
+        taker_bps = 5
+
+        [[profiles.production.fees.tiers]]
+        name = "gold"
+        min_volume = 1000000000
+        maker_bps = -1
+        taker_bps = 3
+
+        [profiles.production.calendar.markets.ROMER]
+        open = "14:30:00"
+        close = "21:00:00"
+        holidays = ["2026-11-26", "2026-12-25"]
+        half_days = { 2026-11-27 = "18:00:00" }
+        settlement_holidays = ["2026-11-11"]
+
+        [profiles.production.clock]
+        max_drift_ms = 50
+        sources = { pool = { kind = "ntp", address = "pool.ntp.org:123" }, grandmaster = { kind = "ptp", command = ["/usr/local/bin/ptp-offset"] } }
+
+        [profiles.production.speed_bump]
+        delay_ms = 350
+
+        [profiles.production.load_shedding]
+        enabled = true
+        max_queue_latency_ms = 20
+
+        [profiles.production.api_keys]
+        required = true
+        rate_per_sec = 50
+
+        [profiles.production.admin]
+        tls_cert = "/etc/romer/tls/sequencer.pem"
+        tls_key = "/etc/romer/tls/sequencer.key"
+        client_ca = "/etc/romer/tls/admin-ca.pem"
+        certificates = { 3f9a0c1e = "superuser" }
+        tokens = { 8a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c = "risk" }
+
+        [profiles.production.bridge]
+        validators = [
+            "8a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c",
+            "8139770ea87d175f56a35466c34c7ecccb8d8a91b4ee37a25df60f5b8fc9b394",
+        ]
+        threshold = 2
+
+        [profiles.production.bridge.chains.ethereum]
+        rpc = "geth:8545"
+        lock_contract = "0x4444444444444444444444444444444444444444"
+        sender = "0x5555555555555555555555555555555555555555"
+        asset = "0000000000000000000000000000000000000000000000000000000000000020::usdc::USDC"
+        confirmations = 12
+    "#;
+
+    #[test]
+    fn test_profile_overlays_file() {
+        let config = SequencerConfig::parse(CONFIG, None).unwrap();
+        assert_eq!(config.network.fix_port, 7000);
+        assert_eq!(config.network.host, "127.0.0.1");
+        assert_eq!(config.block.window(), Duration::from_millis(500));
+        assert_eq!(config.session, SessionPolicy::default());
+        assert_eq!(config.attestation.validators, vec![Address::new([5u8; 32])]);
+        assert_eq!(config.protocol.schedule().version_at(1000), 2);
+        assert_eq!(config.protocol.warn_blocks, 10_000);
+
+        let production = SequencerConfig::parse(CONFIG, Some("production")).unwrap();
+        assert_eq!(config.environment, ExecutionEnvironment::Development);
+        assert_eq!(production.environment, ExecutionEnvironment::Mainnet);
+        assert_eq!(production.network.host, "0.0.0.0");
+        assert_eq!(production.network.fix_port, 7000);
+        assert_eq!(production.network.binary_port, Some(7100));
+        assert_eq!(production.session.resume_window_secs, 60);
+        assert_eq!(production.logging.level, "warn");
+        assert_eq!(production.logging.format, LogFormat::Json);
+        assert!(!config.indexer.enabled());
+        assert!(production.indexer.enabled());
+        assert!(!config.api_keys.required);
+        assert!(production.api_keys.required);
+        assert_eq!(production.api_keys.rate_per_sec, 50);
+        assert_eq!(production.api_keys.burst, 20);
+        assert!(!config.admin.enabled());
+        assert!(production.admin.enabled());
+        assert!(production.admin.tls().is_some());
+        assert_eq!(production.admin.certificates["3f9a0c1e"], AdminRole::Superuser);
+        assert_eq!(config.storage.compression_level, 0);
+        assert_eq!(production.storage.compression_level, 3);
+        assert_eq!(config.storage.journal().unwrap().fsync, FsyncPolicy::PerWrite);
+        assert_eq!(
+            production.storage.journal().unwrap().fsync,
+            FsyncPolicy::Interval(Duration::from_millis(5))
+        );
+        assert_eq!(production.storage.thresholds().pause_below_bytes, 4 << 30);
+        assert!(config.archive.archive().object_store.is_none());
+        assert_eq!(production.archive.archive().object_store.unwrap().region, "us-east-1");
+        assert_eq!(config.markets()[0].target_comp_id, FixConfig::default().target_comp_id);
+        assert_eq!(production.markets().len(), 2);
+        assert!(production.markets[1].symbols.is_empty());
+        assert_eq!(config.reference_prices.collar_bps, None);
+        assert_eq!(production.reference_prices.collar_bps, Some(500));
+        assert_eq!(production.reference_prices.rpc_method, "get_price");
+        assert_eq!(production.reference_prices.poll_interval(), Duration::from_secs(5));
+        assert_eq!(config.obligations.epoch(), Duration::from_secs(3600));
+        assert!(!config.state_snapshots.enabled());
+        assert_eq!(
+            production.state_snapshots.dir(&production.storage.directory),
+            production.storage.directory.join("state-snapshots")
+        );
+        assert_eq!(
+            production.indexer.outbox_dir(&production.storage.directory),
+            production.storage.directory.join("indexer-outbox")
+        );
+        assert!(!config.reconciliation.enabled());
+        assert_eq!(production.reconciliation.session_close, NaiveTime::from_hms_opt(21, 0, 0));
+        assert_eq!(production.reconciliation.firms["MM2"], "ACME");
+        assert_eq!(production.settlement.adapter, Some(SettlementAdapterKind::Evm));
+        assert_eq!(production.settlement.asset, "USD");
+        assert!(!config.oracle.enabled());
+        assert_eq!(production.oracle.min_feeders, 2);
+        assert_eq!(production.oracle.max_age(), Duration::from_secs(30));
+        assert!(!config.governance.enabled());
+        assert_eq!(production.governance.quorum_bps, 5_000);
+        assert_eq!(production.governance.min_delay_blocks, 100);
+        assert!(!config.fees.enabled());
+        assert_eq!(production.fees.tiers[1].maker_bps, -1);
+        assert_eq!(production.fees.epoch(), Duration::from_secs(86_400));
+        assert!(!config.calendar.enabled());
+        let romer = production.calendar.settlement_calendar().unwrap();
+        assert_eq!(romer.settlement_days, 2);
+        assert_eq!(romer.half_days.len(), 1);
+        assert!(!romer.is_trading_day(NaiveDate::from_ymd_opt(2026, 12, 25).unwrap()));
+        assert!(!config.clock.enabled());
+        assert_eq!(production.clock.max_drift(), chrono::Duration::milliseconds(50));
+        assert_eq!(
+            production.clock.sources["pool"],
+            TimeSource::Ntp {
+                address: "pool.ntp.org:123".into()
+            }
+        );
+        assert!(!config.speed_bump.enabled());
+        assert_eq!(production.speed_bump.delay(), Duration::from_millis(350));
+        assert!(!config.load_shedding.enabled);
+        assert!(production.load_shedding.enabled);
+        assert_eq!(production.load_shedding.max_queue_latency_ms, 20);
+        assert_eq!(production.load_shedding.max_batch_latency_ms, 200);
+        assert!(!config.plugins.enabled());
+        assert_eq!(production.plugins.modules[0].activation_height, 0);
+        assert_eq!(production.plugins.limits().fuel, 1_000_000);
+        assert!(!config.bridge.enabled());
+        assert_eq!(production.bridge.committee().threshold, 2);
+        assert_eq!(production.bridge.chains["ethereum"].confirmations, 12);
+        production.validate().unwrap();
+
+        assert!(matches!(
+            SequencerConfig::parse(CONFIG, Some("staging")),
+            Err(ConfigError::UnknownProfile(_))
+        ));
+        assert!(matches!(
+            SequencerConfig::parse("[network]\nfix_prot = 1", None),
+            Err(ConfigError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_validation() {
+        SequencerConfig::default().validate().unwrap();
+
+        let mut config = SequencerConfig::default();
+        config.network.rpc_port = config.network.fix_port;
+        assert!(config.validate().is_err());
+
+        let mut config = SequencerConfig::default();
+        config.network.host = "localhost".into();
+        assert!(config.validate().is_err());
+
+        let mut config = SequencerConfig::default();
+        config.network.chain_id.clear();
+        assert!(config.validate().is_err());
+
+        let mut config = SequencerConfig::default();
+        config.session.heartbeat_default_secs = 0;
+        assert!(config.validate().is_err());
+
+        let mut config = SequencerConfig::default();
+        config.logging.level = "info,romer=loud".into();
+        assert!(config.validate().is_err());
+
+        let mut config = SequencerConfig::default();
+        config.reconciliation.session_close = NaiveTime::from_hms_opt(21, 0, 0);
+        assert!(config.validate().is_err());
+        config.storage.audit_log = Some("events.jsonl".into());
+        config.settlement.adapter = Some(SettlementAdapterKind::Evm);
+        assert!(config.validate().is_err());
+        config.settlement.adapter = Some(SettlementAdapterKind::Bank);
+        config.validate().unwrap();
+
+        let mut config = SequencerConfig::default();
+        config.bridge.validators = vec!["0a".into(), "0b".into()];
+        config.bridge.threshold = 3;
+        assert!(config.validate().is_err());
+        config.bridge.threshold = 2;
+        config.validate().unwrap();
+
+        let mut config = SequencerConfig::default();
+        config.oracle.feeders.insert("pyth".into(), "0a".repeat(32));
+        config.oracle.min_feeders = 2;
+        assert!(config.validate().is_err());
+        config.oracle.min_feeders = 1;
+        config.validate().unwrap();
+        config.oracle.feeders.insert("other".into(), "0a".into());
+        assert!(config.validate().is_err());
+
+        let mut config = SequencerConfig::default();
+        config.governance.validators.insert("0a".repeat(32), 10);
+        config.validate().unwrap();
+        config.governance.quorum_bps = 10_001;
+        assert!(config.validate().is_err());
+        config.governance.quorum_bps = 6_667;
+        config.governance.validators.insert("0b".repeat(32), 0);
+        assert!(config.validate().is_err());
+
+        let mut config = SequencerConfig::default();
+        config.plugins.modules.push(PluginModuleConfig {
+            name: "jurisdiction".into(),
+            version: 1,
+            path: "jurisdiction.wasm".into(),
+            activation_height: 0,
+        });
+        config.validate().unwrap();
+        config.plugins.memory_bytes = 1_024;
+        assert!(config.validate().is_err());
+
+        let mut config = SequencerConfig::default();
+        let calendar = SequencerConfig::parse(CONFIG, Some("production")).unwrap().calendar;
+        config.calendar = calendar.clone();
+        config.calendar.settlement_market = Some("ROMER-FX".into());
+        assert!(config.validate().is_err());
+        config.calendar.settlement_market = Some("ROMER".into());
+        config.validate().unwrap();
+        config.calendar.markets.get_mut("ROMER").unwrap().close = NaiveTime::from_hms_opt(14, 0, 0).unwrap();
+        assert!(config.validate().is_err());
+
+        let mut config = SequencerConfig::default();
+        config.clock.sources.insert("grandmaster".into(), TimeSource::Ptp { command: Vec::new() });
+        assert!(config.validate().is_err());
+        config.clock.sources.insert(
+            "grandmaster".into(),
+            TimeSource::Ptp {
+                command: vec!["ptp-offset".into()],
+            },
+        );
+        config.validate().unwrap();
+        config.clock.poll_secs = 0;
+        assert!(config.validate().is_err());
+
+        let mut config = SequencerConfig::default();
+        config.speed_bump.delay_ms = 5_000;
+        assert!(config.validate().is_err());
+
+        let mut config = SequencerConfig::default();
+        config.load_shedding.recover_pct = 150;
+        assert!(config.validate().is_err());
+
+        let mut config = SequencerConfig::default();
+        config.faucet.drip = 1_000;
+        config.faucet.max_per_address = 10_000;
+        assert!(config.validate().is_err());
+        config.faucet.object = Some(Address::new([5u8; 32]).to_hex());
+        config.faucet.key = Some(hex::encode(Ed25519::from_seed(1).private_key()));
+        config.validate().unwrap();
+        config.environment = ExecutionEnvironment::Mainnet;
+        assert!(config.validate().is_err());
+
+        let mut config = SequencerConfig::default();
+        config.api_keys.burst = 0;
+        assert!(config.validate().is_err());
+
+        let mut config = SequencerConfig::default();
+        config.admin.certificates.insert("3f9a0c1e".into(), AdminRole::Viewer);
+        assert!(config.validate().is_err());
+        config.admin.tls_cert = Some("sequencer.pem".into());
+        config.admin.client_ca = Some("admin-ca.pem".into());
+        assert!(config.validate().is_err());
+        config.admin.tls_key = Some("sequencer.key".into());
+        config.validate().unwrap();
+
+        // Outside development admin access must be configured, and tokens
+        // only travel over TLS
+        let mut config = SequencerConfig::default();
+        config.environment = ExecutionEnvironment::Testnet;
+        assert!(config.validate().is_err());
+        config.admin.tokens.insert("8a88e3dd".into(), AdminRole::Operator);
+        assert!(config.validate().is_err());
+        config.admin.tls_cert = Some("sequencer.pem".into());
+        config.admin.tls_key = Some("sequencer.key".into());
+        config.validate().unwrap();
+
+        let mut config = SequencerConfig::default();
+        config.storage.genesis = Some("genesis.json".into());
+        config.validate().unwrap();
+        config.environment = ExecutionEnvironment::Mainnet;
+        assert!(config.validate().is_err());
+
+        let mut config = SequencerConfig::default();
+        config.storage.compression_level = 23;
+        assert!(config.validate().is_err());
+
+        let mut config = SequencerConfig::default();
+        config.storage.fsync = "sometimes".into();
+        assert!(config.validate().is_err());
+
+        let mut config = SequencerConfig::default();
+        config.storage.warn_below_bytes = config.storage.pause_below_bytes - 1;
+        assert!(config.validate().is_err());
+
+        let mut config = SequencerConfig::default();
+        config.markets = MarketConfig::parse_list("ROMER;ROMER-FX").unwrap();
+        config.validate().unwrap();
+        config.markets[1].target_comp_id = "ROMER".into();
+        assert!(config.validate().is_err());
+
+        let mut config = SequencerConfig::default();
+        config.reference_prices.max_age_secs = 0;
+        assert!(config.validate().is_err());
+
+        let mut config = SequencerConfig::default();
+        config.state_snapshots.interval_blocks = 10;
+        config.state_snapshots.keep = 0;
+        assert!(config.validate().is_err());
+
+        let mut config = SequencerConfig::default();
+        config.protocol.activations = vec![Activation {
+            version: 1,
+            height: 10,
+            description: String::new(),
+        }];
+        assert!(config.validate().is_err());
+    }
+}
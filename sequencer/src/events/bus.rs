@@ -0,0 +1,150 @@
+// src/events/bus.rs
+
+use super::types::SequencerEvent;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+/// Events buffered per subscriber before the slowest one starts losing events
+pub const DEFAULT_CAPACITY: usize = 4096;
+
+/// Consumer of sequencer events, run on its own task by `EventBus::attach`
+pub trait EventSink: Send + 'static {
+    fn name(&self) -> &str;
+
+    fn handle(&mut self, event: &SequencerEvent);
+}
+
+/// Typed in-process event bus. Components publish what happened and any
+/// number of subscribers consume it, without the pipeline knowing who
+/// is listening. Publishing never blocks; a subscriber that falls more than
+/// `capacity` events behind skips ahead and is told how many it missed.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<Arc<SequencerEvent>>,
+    /// Events published since the bus was created
+    published: Arc<AtomicU64>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self {
+            sender,
+            published: Arc::default(),
+        }
+    }
+
+    /// Publishes an event to every current subscriber
+    pub fn publish(&self, event: SequencerEvent) {
+        self.published.fetch_add(1, Ordering::Relaxed);
+        // An error only means nobody is subscribed
+        let _ = self.sender.send(Arc::new(event));
+    }
+
+    pub fn subscribe(&self) -> EventSubscription {
+        EventSubscription {
+            receiver: self.sender.subscribe(),
+        }
+    }
+
+    /// Events published so far
+    pub fn published(&self) -> u64 {
+        self.published.load(Ordering::Relaxed)
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+
+    /// Runs `sink` on its own task until the bus is dropped
+    pub fn attach<S: EventSink>(&self, mut sink: S) -> JoinHandle<()> {
+        let mut subscription = self.subscribe();
+        tokio::spawn(async move {
+            while let Some(event) = subscription.recv().await {
+                sink.handle(&event);
+            }
+        })
+    }
+}
+
+/// A subscriber's view of the bus
+pub struct EventSubscription {
+    receiver: broadcast::Receiver<Arc<SequencerEvent>>,
+}
+
+impl EventSubscription {
+    /// Next event, or `None` once every publisher is gone
+    pub async fn recv(&mut self) -> Option<Arc<SequencerEvent>> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) => return Some(event),
+                Err(RecvError::Lagged(missed)) => {
+                    warn!(missed, "Event subscriber lagged, events dropped");
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn sealed(block_id: u64) -> SequencerEvent {
+        SequencerEvent::BlockSealed {
+            block_id,
+            block_hash: String::new(),
+            message_count: 0,
+            transaction_count: 0,
+            at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_every_subscriber_sees_every_event() {
+        let bus = EventBus::new(16);
+        let mut a = bus.subscribe();
+        let mut b = bus.subscribe();
+
+        bus.publish(sealed(1));
+        bus.publish(sealed(2));
+
+        for subscription in [&mut a, &mut b] {
+            for expected in [1, 2] {
+                let event = subscription.recv().await.unwrap();
+                assert!(matches!(*event, SequencerEvent::BlockSealed { block_id, .. } if block_id == expected));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lagging_subscriber_skips_ahead() {
+        let bus = EventBus::new(2);
+        let mut slow = bus.subscribe();
+        for id in 0..5 {
+            bus.publish(sealed(id));
+        }
+
+        let next = slow.recv().await.unwrap();
+        assert!(matches!(*next, SequencerEvent::BlockSealed { block_id: 3, .. }));
+    }
+
+    #[tokio::test]
+    async fn test_closed_bus_ends_subscription() {
+        let bus = EventBus::new(2);
+        let mut subscription = bus.subscribe();
+        drop(bus);
+        assert!(subscription.recv().await.is_none());
+    }
+}
@@ -0,0 +1,4 @@
+pub mod types;
+pub mod bus;
+pub mod stats;
+pub mod subscribers;
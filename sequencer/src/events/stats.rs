@@ -0,0 +1,230 @@
+// src/events/stats.rs
+
+use super::bus::{EventBus, EventSink};
+use super::types::SequencerEvent;
+use crate::mempool::pool::Mempool;
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use romer_common::utils::clock::SharedClock;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Seconds over which `messages_per_sec` is averaged
+const RATE_WINDOW_SECS: u64 = 10;
+
+/// Point in time view of the sequencer, served on the admin API and
+/// logged periodically
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SequencerStats {
+    pub started_at: DateTime<Utc>,
+    pub uptime_secs: u64,
+    /// FIX messages received since start
+    pub messages_received: u64,
+    /// FIX messages per second over the last few seconds
+    pub messages_per_sec: f64,
+    pub blocks_built: u64,
+    pub last_block_id: Option<u64>,
+    pub active_sessions: u64,
+    /// Resting orders in the book, 0 without one attached
+    pub book_depth: usize,
+    /// Direct transactions waiting for a block
+    pub mempool_depth: usize,
+    /// Events published but not yet written to the audit journal, `None`
+    /// without one
+    pub journal_lag: Option<u64>,
+}
+
+/// Something with a queue depth to report
+pub trait DepthSource: Send + Sync {
+    fn depth(&self) -> usize;
+}
+
+impl DepthSource for Mutex<Mempool> {
+    fn depth(&self) -> usize {
+        self.lock().len()
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    messages: AtomicU64,
+    blocks: AtomicU64,
+    last_block_id: Mutex<Option<u64>>,
+    sessions_opened: AtomicU64,
+    sessions_closed: AtomicU64,
+    /// Messages per unix second, oldest first
+    recent: Mutex<VecDeque<(u64, u64)>>,
+}
+
+/// Aggregates `SequencerStats`. Block and session counts follow the event
+/// bus; message rates are recorded by the FIX server and depths are read
+/// from their sources on demand.
+#[derive(Clone)]
+pub struct StatsCollector {
+    counters: Arc<Counters>,
+    started_at: DateTime<Utc>,
+    clock: SharedClock,
+    book: Option<Arc<dyn DepthSource>>,
+    mempool: Option<Arc<dyn DepthSource>>,
+    /// Bus and audit log progress, for the journal lag
+    journal: Option<(EventBus, Arc<AtomicU64>)>,
+}
+
+impl StatsCollector {
+    pub fn new(clock: SharedClock) -> Self {
+        Self {
+            counters: Arc::default(),
+            started_at: clock.now(),
+            clock,
+            book: None,
+            mempool: None,
+            journal: None,
+        }
+    }
+
+    /// Report the depth of `book`
+    pub fn with_book(mut self, book: Arc<dyn DepthSource>) -> Self {
+        self.book = Some(book);
+        self
+    }
+
+    /// Report the depth of `mempool`
+    pub fn with_mempool(mut self, mempool: Arc<dyn DepthSource>) -> Self {
+        self.mempool = Some(mempool);
+        self
+    }
+
+    /// Report how far the audit journal, which has written `written`
+    /// events, trails what was published on `events`
+    pub fn with_journal(mut self, events: EventBus, written: Arc<AtomicU64>) -> Self {
+        self.journal = Some((events, written));
+        self
+    }
+
+    /// Counts one FIX message received
+    pub fn record_message(&self) {
+        self.counters.messages.fetch_add(1, Ordering::Relaxed);
+        let now = self.clock.unix_secs();
+        let mut recent = self.counters.recent.lock();
+        match recent.back_mut() {
+            Some((second, count)) if *second == now => *count += 1,
+            _ => recent.push_back((now, 1)),
+        }
+        while recent.front().is_some_and(|(second, _)| second + RATE_WINDOW_SECS <= now) {
+            recent.pop_front();
+        }
+    }
+
+    pub fn get_stats(&self) -> SequencerStats {
+        let counters = &self.counters;
+        let now = self.clock.now();
+        let unix_now = self.clock.unix_secs();
+        let recent: u64 = counters
+            .recent
+            .lock()
+            .iter()
+            .filter(|(second, _)| second + RATE_WINDOW_SECS > unix_now)
+            .map(|(_, count)| count)
+            .sum();
+
+        SequencerStats {
+            started_at: self.started_at,
+            uptime_secs: (now - self.started_at).num_seconds().max(0) as u64,
+            messages_received: counters.messages.load(Ordering::Relaxed),
+            messages_per_sec: recent as f64 / RATE_WINDOW_SECS as f64,
+            blocks_built: counters.blocks.load(Ordering::Relaxed),
+            last_block_id: *counters.last_block_id.lock(),
+            active_sessions: counters
+                .sessions_opened
+                .load(Ordering::Relaxed)
+                .saturating_sub(counters.sessions_closed.load(Ordering::Relaxed)),
+            book_depth: self.book.as_ref().map_or(0, |book| book.depth()),
+            mempool_depth: self.mempool.as_ref().map_or(0, |mempool| mempool.depth()),
+            journal_lag: self
+                .journal
+                .as_ref()
+                .map(|(events, written)| events.published().saturating_sub(written.load(Ordering::Relaxed))),
+        }
+    }
+}
+
+impl EventSink for StatsCollector {
+    fn name(&self) -> &str {
+        "stats"
+    }
+
+    fn handle(&mut self, event: &SequencerEvent) {
+        let counters = &self.counters;
+        match event {
+            SequencerEvent::BlockSealed { block_id, .. } => {
+                counters.blocks.fetch_add(1, Ordering::Relaxed);
+                *counters.last_block_id.lock() = Some(*block_id);
+            }
+            SequencerEvent::SessionOpened { .. } => {
+                counters.sessions_opened.fetch_add(1, Ordering::Relaxed);
+            }
+            SequencerEvent::SessionClosed { .. } => {
+                counters.sessions_closed.fetch_add(1, Ordering::Relaxed);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use romer_common::utils::clock::ManualClock;
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    struct Book(usize);
+
+    impl DepthSource for Book {
+        fn depth(&self) -> usize {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_stats_aggregate_events_and_messages() {
+        let clock = Arc::new(ManualClock::new(Utc::now()));
+        let mut stats = StatsCollector::new(clock.clone()).with_book(Arc::new(Book(7)));
+
+        for _ in 0..20 {
+            stats.record_message();
+        }
+        let session_id = Uuid::new_v4();
+        stats.handle(&SequencerEvent::SessionOpened {
+            session_id,
+            sender_comp_id: "MM1".into(),
+            at: clock.now(),
+        });
+        stats.handle(&SequencerEvent::BlockSealed {
+            block_id: 4,
+            block_hash: String::new(),
+            message_count: 20,
+            transaction_count: 0,
+            at: clock.now(),
+        });
+
+        clock.advance(Duration::from_secs(3));
+        let snapshot = stats.get_stats();
+        assert_eq!(snapshot.uptime_secs, 3);
+        assert_eq!(snapshot.messages_received, 20);
+        assert_eq!(snapshot.messages_per_sec, 2.0);
+        assert_eq!(snapshot.blocks_built, 1);
+        assert_eq!(snapshot.last_block_id, Some(4));
+        assert_eq!(snapshot.active_sessions, 1);
+        assert_eq!(snapshot.book_depth, 7);
+        assert_eq!(snapshot.journal_lag, None);
+
+        // Messages age out of the rate window but not the total
+        clock.advance(Duration::from_secs(RATE_WINDOW_SECS));
+        let snapshot = stats.get_stats();
+        assert_eq!(snapshot.messages_per_sec, 0.0);
+        assert_eq!(snapshot.messages_received, 20);
+    }
+}
@@ -0,0 +1,125 @@
+// src/events/subscribers.rs
+
+use super::bus::EventSink;
+use super::types::SequencerEvent;
+use dashmap::DashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tracing::error;
+
+/// Appends every event to a file as one JSON object per line
+pub struct AuditLog {
+    writer: BufWriter<File>,
+    /// Events written so far
+    written: Arc<AtomicU64>,
+}
+
+impl AuditLog {
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+            written: Arc::default(),
+        })
+    }
+
+    /// Count of events written, which keeps counting once the log is
+    /// attached to a bus
+    pub fn progress(&self) -> Arc<AtomicU64> {
+        self.written.clone()
+    }
+}
+
+impl EventSink for AuditLog {
+    fn name(&self) -> &str {
+        "audit_log"
+    }
+
+    fn handle(&mut self, event: &SequencerEvent) {
+        let result = serde_json::to_writer(&mut self.writer, event)
+            .map_err(std::io::Error::from)
+            .and_then(|_| self.writer.write_all(b"\n"))
+            .and_then(|_| self.writer.flush());
+        match result {
+            Ok(()) => {
+                self.written.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(e) => error!(error = %e, "Failed to write audit log entry"),
+        }
+    }
+}
+
+/// Counts events by kind, for the metrics exporter
+#[derive(Clone, Default)]
+pub struct EventCounters {
+    counts: Arc<DashMap<&'static str, u64>>,
+}
+
+impl EventCounters {
+    pub fn get(&self, kind: &str) -> u64 {
+        self.counts.get(kind).map(|c| *c).unwrap_or(0)
+    }
+
+    /// Current count of every event kind seen so far
+    pub fn snapshot(&self) -> Vec<(&'static str, u64)> {
+        let mut counts: Vec<_> = self.counts.iter().map(|e| (*e.key(), *e.value())).collect();
+        counts.sort();
+        counts
+    }
+}
+
+impl EventSink for EventCounters {
+    fn name(&self) -> &str {
+        "event_counters"
+    }
+
+    fn handle(&mut self, event: &SequencerEvent) {
+        *self.counts.entry(event.kind()).or_insert(0) += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    fn opened() -> SequencerEvent {
+        SequencerEvent::SessionOpened {
+            session_id: Uuid::new_v4(),
+            sender_comp_id: "MM1".to_string(),
+            at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_counters() {
+        let mut counters = EventCounters::default();
+        counters.handle(&opened());
+        counters.handle(&opened());
+        assert_eq!(counters.get("session_opened"), 2);
+        assert_eq!(counters.get("match"), 0);
+    }
+
+    #[test]
+    fn test_audit_log_writes_json_lines() {
+        let dir = std::env::temp_dir().join(format!("romer-audit-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("audit.log");
+
+        let mut log = AuditLog::open(&path).unwrap();
+        log.handle(&opened());
+        log.handle(&opened());
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let events: Vec<SequencerEvent> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(events.len(), 2);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
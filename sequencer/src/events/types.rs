@@ -0,0 +1,209 @@
+// src/events/types.rs
+
+use chrono::{DateTime, Utc};
+use romer_common::fix::allocation::{AllocSide, AllocationEntry};
+use romer_common::types::address::Address;
+use romer_common::types::instrument::InstrumentOverride;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Something that happened in the sequencer pipeline. Published on the
+/// `EventBus` for metrics, drop-copy, gateways and the audit log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SequencerEvent {
+    OrderAccepted {
+        sender_comp_id: String,
+        msg_seq_num: u64,
+        at: DateTime<Utc>,
+    },
+    OrderRejected {
+        sender_comp_id: String,
+        msg_seq_num: u64,
+        reason: String,
+        at: DateTime<Utc>,
+    },
+    Match {
+        symbol: String,
+        price: u64,
+        quantity: u64,
+        buyer: String,
+        seller: String,
+        /// Whichever of the buyer and seller had the resting order, when known
+        #[serde(default)]
+        maker: Option<String>,
+        /// Account (1) of the buyer's order, naming one of its sub-accounts
+        #[serde(default)]
+        buyer_account: Option<String>,
+        #[serde(default)]
+        seller_account: Option<String>,
+        at: DateTime<Utc>,
+    },
+    BlockSealed {
+        block_id: u64,
+        block_hash: String,
+        message_count: usize,
+        transaction_count: usize,
+        at: DateTime<Utc>,
+    },
+    SessionOpened {
+        session_id: Uuid,
+        sender_comp_id: String,
+        at: DateTime<Utc>,
+    },
+    SessionClosed {
+        session_id: Uuid,
+        sender_comp_id: String,
+        reason: String,
+        at: DateTime<Utc>,
+    },
+    KillSwitchEngaged {
+        firm: String,
+        /// Sessions of the firm that must be logged out
+        sender_comp_ids: Vec<String>,
+        reason: String,
+        triggered_by: String,
+        orders_cancelled: usize,
+        at: DateTime<Utc>,
+    },
+    KillSwitchReleased {
+        firm: String,
+        released_by: String,
+        at: DateTime<Utc>,
+    },
+    DrainModeEntered {
+        reason: String,
+        triggered_by: String,
+        at: DateTime<Utc>,
+    },
+    DrainModeExited {
+        released_by: String,
+        at: DateTime<Utc>,
+    },
+    /// The order pipeline went over its latency budget and started
+    /// rejecting new orders; latencies are smoothed, in microseconds
+    LoadSheddingEntered {
+        queue_latency_us: u64,
+        batch_latency_us: u64,
+        at: DateTime<Utc>,
+    },
+    /// Latency recovered after `shed` orders were rejected
+    LoadSheddingExited {
+        shed: u64,
+        at: DateTime<Utc>,
+    },
+    BalanceChanged {
+        address: Address,
+        balance: u64,
+        at: DateTime<Utc>,
+    },
+    /// Market maker obligations of an epoch, as `SENDER:SYMBOL` pairs
+    ObligationEpochClosed {
+        epoch: u64,
+        met: Vec<String>,
+        missed: Vec<String>,
+        at: DateTime<Utc>,
+    },
+    /// Matching parameters of an instrument changed from a block height
+    InstrumentOverrideScheduled {
+        symbol: String,
+        overrides: InstrumentOverride,
+        activation_height: u64,
+        source: String,
+        at: DateTime<Utc>,
+    },
+    /// A firm's fee tier changed when an epoch closed
+    FeeTierChanged {
+        firm: String,
+        epoch: u64,
+        previous: String,
+        tier: String,
+        volume: u128,
+        at: DateTime<Utc>,
+    },
+    /// Executions given up by `executing_firm`, a SenderCompID, once every
+    /// clearing firm accepted its allocation
+    AllocationAccepted {
+        alloc_id: String,
+        executing_firm: String,
+        symbol: String,
+        side: AllocSide,
+        avg_price: u64,
+        allocations: Vec<AllocationEntry>,
+        at: DateTime<Utc>,
+    },
+    /// An operational notice for `recipients`, SenderCompIDs, or for every
+    /// session when there are none
+    NewsPublished {
+        id: u64,
+        headline: String,
+        text: String,
+        recipients: Vec<String>,
+        published_by: String,
+        at: DateTime<Utc>,
+    },
+    /// An `admin_*` method called by `identity`, whether it was allowed or
+    /// not. `outcome` is `ok`, the error it failed with, or why it was
+    /// denied.
+    AdminAction {
+        method: String,
+        identity: String,
+        /// Role of the caller, when it could be identified
+        #[serde(default)]
+        role: Option<String>,
+        outcome: String,
+        at: DateTime<Utc>,
+    },
+}
+
+impl SequencerEvent {
+    /// Short name of the event kind, used as a metrics label
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::OrderAccepted { .. } => "order_accepted",
+            Self::OrderRejected { .. } => "order_rejected",
+            Self::Match { .. } => "match",
+            Self::BlockSealed { .. } => "block_sealed",
+            Self::SessionOpened { .. } => "session_opened",
+            Self::SessionClosed { .. } => "session_closed",
+            Self::KillSwitchEngaged { .. } => "kill_switch_engaged",
+            Self::KillSwitchReleased { .. } => "kill_switch_released",
+            Self::DrainModeEntered { .. } => "drain_mode_entered",
+            Self::DrainModeExited { .. } => "drain_mode_exited",
+            Self::LoadSheddingEntered { .. } => "load_shedding_entered",
+            Self::LoadSheddingExited { .. } => "load_shedding_exited",
+            Self::BalanceChanged { .. } => "balance_changed",
+            Self::ObligationEpochClosed { .. } => "obligation_epoch_closed",
+            Self::InstrumentOverrideScheduled { .. } => "instrument_override_scheduled",
+            Self::FeeTierChanged { .. } => "fee_tier_changed",
+            Self::AllocationAccepted { .. } => "allocation_accepted",
+            Self::NewsPublished { .. } => "news_published",
+            Self::AdminAction { .. } => "admin_action",
+        }
+    }
+
+    /// When the event happened
+    pub fn at(&self) -> DateTime<Utc> {
+        match self {
+            Self::OrderAccepted { at, .. }
+            | Self::OrderRejected { at, .. }
+            | Self::Match { at, .. }
+            | Self::BlockSealed { at, .. }
+            | Self::SessionOpened { at, .. }
+            | Self::SessionClosed { at, .. }
+            | Self::KillSwitchEngaged { at, .. }
+            | Self::KillSwitchReleased { at, .. }
+            | Self::DrainModeEntered { at, .. }
+            | Self::DrainModeExited { at, .. }
+            | Self::LoadSheddingEntered { at, .. }
+            | Self::LoadSheddingExited { at, .. }
+            | Self::BalanceChanged { at, .. }
+            | Self::ObligationEpochClosed { at, .. }
+            | Self::InstrumentOverrideScheduled { at, .. }
+            | Self::FeeTierChanged { at, .. }
+            | Self::AllocationAccepted { at, .. }
+            | Self::NewsPublished { at, .. }
+            | Self::AdminAction { at, .. } => *at,
+        }
+    }
+}
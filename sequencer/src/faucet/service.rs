@@ -0,0 +1,318 @@
+// src/faucet/service.rs
+
+use crate::config::FaucetConfig;
+use chrono::{DateTime, Utc};
+use commonware_cryptography::{Ed25519, Scheme};
+use parking_lot::Mutex;
+use romer_common::types::address::Address;
+use romer_common::types::envelope::{EnvelopeError, SignedTransaction, TransactionPayload, UnsignedTransaction};
+use romer_common::types::environment::ExecutionEnvironment;
+use romer_common::types::keymanager::SignatureScheme;
+use romer_common::types::rejection::RejectReason;
+use romer_common::utils::clock::SharedClock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::{info, warn};
+
+/// Package the `romer::faucet` module is published in
+const FRAMEWORK_PACKAGE: Address = {
+    let mut address = [0u8; 32];
+    address[31] = 0x10;
+    Address::new(address)
+};
+
+/// How long a signed mint may wait for a block
+const MINT_TTL_SECS: u64 = 300;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum FaucetError {
+    #[error("The faucet is not available in the {0} environment")]
+    Unavailable(ExecutionEnvironment),
+
+    #[error("{address} has received the faucet limit of {limit}")]
+    LimitReached { address: Address, limit: u64 },
+
+    #[error("{address} may request again at {retry_at}")]
+    CoolingDown { address: Address, retry_at: DateTime<Utc> },
+
+    #[error("faucet.object and faucet.key must be a hex object id and Ed25519 private key")]
+    Misconfigured,
+
+    #[error("Failed to journal faucet mint: {0}")]
+    Journal(String),
+}
+
+impl FaucetError {
+    pub fn reject_reason(&self) -> RejectReason {
+        match self {
+            Self::Unavailable(_) | Self::Misconfigured => RejectReason::PermissionDenied,
+            Self::LimitReached { .. } | Self::CoolingDown { .. } => RejectReason::ExceedsLimit,
+            Self::Journal(_) => RejectReason::Other,
+        }
+    }
+}
+
+/// Limits of the faucet, as published by `get_faucet`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FaucetStatus {
+    pub environment: ExecutionEnvironment,
+    pub drip: u64,
+    pub max_per_address: u64,
+    pub cooldown_secs: u64,
+}
+
+/// What one address has received, as journaled
+#[derive(Serialize, Deserialize)]
+struct Dispensed {
+    address: Address,
+    total: u64,
+    last: DateTime<Utc>,
+}
+
+/// Hands out test RØMER to addresses that ask, up to `drip` a request and
+/// `max_per_address` in all, with a cooldown between requests. Each grant
+/// is minted by `romer::faucet::request_for` in a transaction the faucet
+/// signs with its own key. Refuses to exist outside development and
+/// testnet environments.
+pub struct Faucet {
+    config: FaucetConfig,
+    environment: ExecutionEnvironment,
+    /// Shared `romer::faucet::Faucet` object minting the grants
+    object: Address,
+    signer: Mutex<Ed25519>,
+    /// Totals and last grant by address
+    dispensed: Mutex<HashMap<Address, (u64, DateTime<Utc>)>>,
+    /// Grants are appended here, so limits hold across restarts
+    journal: Option<PathBuf>,
+    clock: SharedClock,
+}
+
+impl Faucet {
+    pub fn new(config: FaucetConfig, environment: ExecutionEnvironment, clock: SharedClock) -> Result<Self, FaucetError> {
+        if !environment.allows_faucet() {
+            return Err(FaucetError::Unavailable(environment));
+        }
+        let (Some(object), Some(signer)) = (config.object(), config.signer()) else {
+            return Err(FaucetError::Misconfigured);
+        };
+        Ok(Self {
+            config,
+            environment,
+            object,
+            signer: Mutex::new(signer),
+            dispensed: Mutex::new(HashMap::new()),
+            journal: None,
+            clock,
+        })
+    }
+
+    /// Journal grants to `path`, first replaying those it holds
+    pub fn with_journal(mut self, path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        if path.exists() {
+            let dispensed = self.dispensed.get_mut();
+            for line in BufReader::new(File::open(path)?).lines() {
+                match serde_json::from_str::<Dispensed>(&line?) {
+                    Ok(entry) => {
+                        dispensed.insert(entry.address, (entry.total, entry.last));
+                    }
+                    Err(e) => warn!(error = %e, "Skipping undecodable faucet grant"),
+                }
+            }
+        }
+        self.journal = Some(path.to_path_buf());
+        Ok(self)
+    }
+
+    /// Account the faucet's mints are sent from
+    pub fn sender(&self) -> Address {
+        Address::from_public_key(SignatureScheme::Ed25519, &self.signer.lock().public_key())
+    }
+
+    /// Amount to mint to `address`, `requested` or else the drip, capped by
+    /// what the address has left of its limit
+    pub fn dispense(&self, address: Address, requested: Option<u64>) -> Result<u64, FaucetError> {
+        let now = self.clock.now();
+        let mut dispensed = self.dispensed.lock();
+        let (total, last) = dispensed.get(&address).map_or((0, None), |(total, last)| (*total, Some(*last)));
+        if let Some(last) = last {
+            let retry_at = last + chrono::Duration::seconds(self.config.cooldown_secs as i64);
+            if now < retry_at {
+                return Err(FaucetError::CoolingDown { address, retry_at });
+            }
+        }
+        let amount = requested
+            .unwrap_or(self.config.drip)
+            .min(self.config.drip)
+            .min(self.config.max_per_address.saturating_sub(total));
+        if amount == 0 {
+            return Err(FaucetError::LimitReached {
+                address,
+                limit: self.config.max_per_address,
+            });
+        }
+        self.record(&mut dispensed, address, total + amount, now)?;
+        info!(%address, amount, total = total + amount, "Faucet dispensed");
+        Ok(amount)
+    }
+
+    /// Gives back a grant whose mint never reached the pipeline
+    pub fn refund(&self, address: Address, amount: u64) {
+        let mut dispensed = self.dispensed.lock();
+        if let Some(&(total, last)) = dispensed.get(&address) {
+            if let Err(e) = self.record(&mut dispensed, address, total.saturating_sub(amount), last) {
+                warn!(error = %e, %address, "Failed to refund faucet grant");
+            }
+        }
+    }
+
+    /// The transaction minting `amount` to `recipient` through the shared
+    /// faucet object, signed by the faucet for `chain_id` with `nonce`
+    pub fn mint(
+        &self,
+        chain_id: &str,
+        recipient: Address,
+        amount: u64,
+        nonce: u64,
+    ) -> Result<SignedTransaction, EnvelopeError> {
+        let mut signer = self.signer.lock();
+        UnsignedTransaction {
+            chain_id: chain_id.to_string(),
+            // BCS encodes addresses as their bytes and integers little-endian
+            payload: TransactionPayload::MoveCall {
+                package: FRAMEWORK_PACKAGE,
+                module: "faucet".to_string(),
+                function: "request_for".to_string(),
+                type_arguments: Vec::new(),
+                arguments: vec![self.object.to_vec(), recipient.to_vec(), amount.to_le_bytes().to_vec()],
+            },
+            sender: Address::from_public_key(SignatureScheme::Ed25519, &signer.public_key()),
+            nonce,
+            fee: 0,
+            expiry: self.clock.unix_secs() + MINT_TTL_SECS,
+        }
+        .sign(SignatureScheme::Ed25519, &mut *signer)
+    }
+
+    fn record(
+        &self,
+        dispensed: &mut HashMap<Address, (u64, DateTime<Utc>)>,
+        address: Address,
+        total: u64,
+        last: DateTime<Utc>,
+    ) -> Result<(), FaucetError> {
+        if let Some(path) = &self.journal {
+            let mut line = serde_json::to_vec(&Dispensed { address, total, last })
+                .map_err(|e| FaucetError::Journal(e.to_string()))?;
+            line.push(b'\n');
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| file.write_all(&line))
+                .map_err(|e| FaucetError::Journal(e.to_string()))?;
+        }
+        dispensed.insert(address, (total, last));
+        Ok(())
+    }
+
+    pub fn status(&self) -> FaucetStatus {
+        FaucetStatus {
+            environment: self.environment,
+            drip: self.config.drip,
+            max_per_address: self.config.max_per_address,
+            cooldown_secs: self.config.cooldown_secs,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use romer_common::utils::clock::ManualClock;
+    use romer_common::types::envelope::DEFAULT_CHAIN_ID;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn config(drip: u64, max_per_address: u64, cooldown_secs: u64) -> FaucetConfig {
+        FaucetConfig {
+            drip,
+            max_per_address,
+            cooldown_secs,
+            object: Some(Address::new([5u8; 32]).to_hex()),
+            key: Some(hex::encode(Ed25519::from_seed(3).private_key())),
+        }
+    }
+
+    #[test]
+    fn test_drip_capped_per_address() {
+        let config = config(100, 250, 60);
+        assert!(matches!(
+            Faucet::new(config.clone(), ExecutionEnvironment::Mainnet, Arc::new(ManualClock::new(Utc::now()))),
+            Err(FaucetError::Unavailable(ExecutionEnvironment::Mainnet))
+        ));
+
+        let clock = Arc::new(ManualClock::new(Utc.with_ymd_and_hms(2024, 3, 1, 9, 0, 0).unwrap()));
+        let faucet = Faucet::new(config, ExecutionEnvironment::Testnet, clock.clone()).unwrap();
+        let address = Address::new([7u8; 32]);
+        assert_eq!(faucet.dispense(address, Some(500)), Ok(100));
+        assert!(matches!(faucet.dispense(address, None), Err(FaucetError::CoolingDown { .. })));
+
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(faucet.dispense(address, None), Ok(100));
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(faucet.dispense(address, None), Ok(50));
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(
+            faucet.dispense(address, None),
+            Err(FaucetError::LimitReached { address, limit: 250 })
+        );
+        assert_eq!(faucet.dispense(Address::new([8u8; 32]), Some(10)), Ok(10));
+    }
+
+    #[test]
+    fn test_mint_signed_by_faucet() {
+        let clock = Arc::new(ManualClock::new(Utc::now()));
+        let faucet = Faucet::new(config(100, 250, 60), ExecutionEnvironment::Testnet, clock).unwrap();
+        let recipient = Address::new([7u8; 32]);
+        let mint = faucet.mint(DEFAULT_CHAIN_ID, recipient, 100, 4).unwrap();
+
+        mint.verify(DEFAULT_CHAIN_ID, 0).unwrap();
+        assert_eq!(mint.transaction.sender, faucet.sender());
+        assert_eq!(mint.transaction.nonce, 4);
+        match mint.transaction.payload {
+            TransactionPayload::MoveCall { package, module, function, arguments, .. } => {
+                assert_eq!((package, module.as_str(), function.as_str()), (FRAMEWORK_PACKAGE, "faucet", "request_for"));
+                assert_eq!(arguments[1], recipient.to_vec());
+                assert_eq!(arguments[2], 100u64.to_le_bytes().to_vec());
+            }
+            other => panic!("expected a Move call, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_grants_survive_restart() {
+        let path = std::env::temp_dir().join(format!("faucet-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let clock = Arc::new(ManualClock::new(Utc.with_ymd_and_hms(2024, 3, 1, 9, 0, 0).unwrap()));
+        let open = || {
+            Faucet::new(config(100, 150, 0), ExecutionEnvironment::Testnet, clock.clone())
+                .unwrap()
+                .with_journal(&path)
+                .unwrap()
+        };
+        let address = Address::new([7u8; 32]);
+
+        assert_eq!(open().dispense(address, None), Ok(100));
+        let restarted = open();
+        assert_eq!(restarted.dispense(address, None), Ok(50));
+        restarted.refund(address, 50);
+        assert_eq!(open().dispense(address, None), Ok(50));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
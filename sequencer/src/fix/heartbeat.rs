@@ -0,0 +1,93 @@
+// src/fix/heartbeat.rs
+
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum HeartbeatError {
+    #[error("Invalid HeartBtInt {0}")]
+    Invalid(String),
+
+    #[error("HeartBtInt {requested} outside the accepted range {min}-{max} seconds")]
+    OutOfRange { requested: u32, min: u32, max: u32 },
+}
+
+/// Range of heartbeat intervals (HeartBtInt, 108) accepted from a
+/// counterparty's Logon, in seconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeartbeatBounds {
+    pub min_secs: u32,
+    pub max_secs: u32,
+    /// Used when the Logon carries no HeartBtInt
+    pub default_secs: u32,
+}
+
+impl Default for HeartbeatBounds {
+    fn default() -> Self {
+        Self {
+            min_secs: 1,
+            max_secs: 300,
+            default_secs: 30,
+        }
+    }
+}
+
+impl HeartbeatBounds {
+    /// The interval to run a session at, given the raw HeartBtInt of its
+    /// Logon. A requested interval is honored when it is within bounds.
+    pub fn negotiate(&self, requested: Option<&str>) -> Result<u32, HeartbeatError> {
+        let Some(raw) = requested else {
+            return Ok(self.default_secs);
+        };
+        let requested: u32 = raw
+            .trim()
+            .parse()
+            .map_err(|_| HeartbeatError::Invalid(raw.to_string()))?;
+        if requested < self.min_secs || requested > self.max_secs {
+            return Err(HeartbeatError::OutOfRange {
+                requested,
+                min: self.min_secs,
+                max: self.max_secs,
+            });
+        }
+        Ok(requested)
+    }
+
+    /// How long a connection may stay silent before it is closed: longer
+    /// than a session at the longest interval takes to send its TestRequest
+    /// and give up on the answer, so sessions time out through their
+    /// heartbeats first
+    pub fn idle_timeout(&self) -> Duration {
+        Duration::from_secs(2 * (self.max_secs as u64 + 1) + 2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate() {
+        let bounds = HeartbeatBounds {
+            min_secs: 5,
+            max_secs: 60,
+            default_secs: 30,
+        };
+        assert_eq!(bounds.negotiate(Some("10")), Ok(10));
+        assert_eq!(bounds.negotiate(None), Ok(30));
+        assert_eq!(
+            bounds.negotiate(Some("1")),
+            Err(HeartbeatError::OutOfRange { requested: 1, min: 5, max: 60 })
+        );
+        assert!(matches!(bounds.negotiate(Some("61")), Err(HeartbeatError::OutOfRange { .. })));
+        assert!(matches!(bounds.negotiate(Some("abc")), Err(HeartbeatError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_idle_timeout_outlasts_heartbeats() {
+        let bounds = HeartbeatBounds::default();
+        // A counterparty at the longest interval is silent that long between
+        // heartbeats, and the session waits as long again for its TestRequest
+        assert!(bounds.idle_timeout() > Duration::from_secs(2 * (bounds.max_secs as u64 + 1)));
+    }
+}
@@ -1,3 +1,5 @@
+pub mod heartbeat;
 pub mod parser;
+pub mod reports;
 pub mod types;
 pub mod validator;
\ No newline at end of file
@@ -0,0 +1,257 @@
+// src/fix/reports.rs
+
+use chrono::{DateTime, Utc};
+use romer_common::types::fix::utils::{encode_message, format_timestamp};
+use romer_common::types::rejection::RejectReason;
+
+use crate::market::orders::OrderState;
+
+/// ExecutionReport (35=8) rejecting a NewOrderSingle
+#[derive(Debug, Clone)]
+pub struct OrderReject {
+    /// Our comp ID, sent as SenderCompID
+    pub sender_comp_id: String,
+    /// The counterparty, sent as TargetCompID
+    pub target_comp_id: String,
+    pub cl_ord_id: String,
+    pub symbol: String,
+    pub side: String,
+    pub reason: RejectReason,
+    /// Detail appended to the catalogue text of `reason` in Text (58)
+    pub text: String,
+}
+
+impl OrderReject {
+    /// Encodes the report as a FIX 4.2 message
+    pub fn encode(&self, msg_seq_num: u64, sending_time: DateTime<Utc>) -> String {
+        let fields = [
+            (35, "8".to_string()),
+            (49, self.sender_comp_id.clone()),
+            (56, self.target_comp_id.clone()),
+            (34, msg_seq_num.to_string()),
+            (52, format_timestamp(sending_time)),
+            (37, "NONE".to_string()),
+            (11, self.cl_ord_id.clone()),
+            (17, format!("{}-REJ", self.cl_ord_id)),
+            (20, "0".to_string()),
+            (150, "8".to_string()),
+            (39, "8".to_string()),
+            (55, self.symbol.clone()),
+            (54, self.side.clone()),
+            (38, "0".to_string()),
+            (14, "0".to_string()),
+            (6, "0".to_string()),
+            (151, "0".to_string()),
+            (103, self.reason.ord_rej_reason().to_string()),
+            (58, self.reason.describe(&self.text)),
+        ];
+        String::from_utf8_lossy(&encode_message("FIX.4.2", &fields)).to_string()
+    }
+}
+
+/// ExecutionReport (35=8) with ExecType I carrying the current state of an
+/// order, in answer to an OrderStatusRequest or unsolicited, e.g. while a
+/// counterparty recovers its orders
+#[derive(Debug, Clone)]
+pub struct OrderStatusReport {
+    /// Our comp ID, sent as SenderCompID
+    pub sender_comp_id: String,
+    /// The counterparty, sent as TargetCompID
+    pub target_comp_id: String,
+    /// The order asked about; reported as rejected when unknown
+    pub order: Option<OrderState>,
+    /// ClOrdID, Symbol and Side of the request, echoed for unknown orders
+    pub cl_ord_id: String,
+    pub symbol: String,
+    pub side: String,
+    /// OrdStatusReqID (790) of the request answered, echoed when given
+    pub ord_status_req_id: Option<String>,
+    /// Sent as UnsolicitedIndicator (325) Y when not answering a request
+    pub unsolicited: bool,
+}
+
+impl OrderStatusReport {
+    /// Encodes the report as a FIX 4.2 message
+    pub fn encode(&self, msg_seq_num: u64, sending_time: DateTime<Utc>) -> String {
+        let mut fields = vec![
+            (35, "8".to_string()),
+            (49, self.sender_comp_id.clone()),
+            (56, self.target_comp_id.clone()),
+            (34, msg_seq_num.to_string()),
+            (52, format_timestamp(sending_time)),
+        ];
+        match &self.order {
+            Some(order) => {
+                fields.extend([
+                    (37, order.order_id.clone()),
+                    (11, order.entry.cl_ord_id.clone()),
+                    (17, format!("{}-STATUS-{}", order.order_id, msg_seq_num)),
+                    (20, "3".to_string()),
+                    (150, "I".to_string()),
+                    (39, order.status.fix_code().to_string()),
+                ]);
+                if let Some(account) = &order.entry.account {
+                    fields.push((1, account.clone()));
+                }
+                fields.extend([
+                    (55, order.entry.symbol.clone()),
+                    (54, order.entry.side.clone()),
+                    (38, order.entry.order_qty.to_string()),
+                ]);
+                if let Some(price) = &order.entry.price {
+                    fields.push((44, price.clone()));
+                }
+                fields.extend([
+                    (14, order.cum_qty.to_string()),
+                    (151, order.leaves_qty().to_string()),
+                    (6, order.avg_px.to_string()),
+                ]);
+                if let Some(text) = &order.text {
+                    fields.push((58, text.clone()));
+                }
+            }
+            None => fields.extend([
+                (37, "NONE".to_string()),
+                (11, self.cl_ord_id.clone()),
+                (17, format!("{}-STATUS-{}", self.cl_ord_id, msg_seq_num)),
+                (20, "3".to_string()),
+                (150, "I".to_string()),
+                (39, "8".to_string()),
+                (55, self.symbol.clone()),
+                (54, self.side.clone()),
+                (38, "0".to_string()),
+                (14, "0".to_string()),
+                (151, "0".to_string()),
+                (6, "0".to_string()),
+                (58, RejectReason::UnknownOrder.describe("")),
+            ]),
+        }
+        if let Some(ord_status_req_id) = &self.ord_status_req_id {
+            fields.push((790, ord_status_req_id.clone()));
+        }
+        fields.push((325, if self.unsolicited { "Y" } else { "N" }.to_string()));
+        String::from_utf8_lossy(&encode_message("FIX.4.2", &fields)).to_string()
+    }
+}
+
+/// News (35=B) carrying a notice to one counterparty
+#[derive(Debug, Clone)]
+pub struct News {
+    /// Our comp ID, sent as SenderCompID
+    pub sender_comp_id: String,
+    /// The counterparty, sent as TargetCompID
+    pub target_comp_id: String,
+    pub headline: String,
+    /// Sent as a single LinesOfText (33) entry
+    pub text: String,
+}
+
+impl News {
+    /// Encodes the notice as a FIX 4.2 message
+    pub fn encode(&self, msg_seq_num: u64, sending_time: DateTime<Utc>) -> String {
+        let fields = [
+            (35, "B".to_string()),
+            (49, self.sender_comp_id.clone()),
+            (56, self.target_comp_id.clone()),
+            (34, msg_seq_num.to_string()),
+            (52, format_timestamp(sending_time)),
+            (148, self.headline.clone()),
+            (33, "1".to_string()),
+            (58, self.text.clone()),
+        ];
+        String::from_utf8_lossy(&encode_message("FIX.4.2", &fields)).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use romer_common::types::fix::utils::validate_message;
+
+    #[test]
+    fn test_encode_duplicate_reject() {
+        let reject = OrderReject {
+            sender_comp_id: "ROMER".into(),
+            target_comp_id: "MM1".into(),
+            cl_ord_id: "A1".into(),
+            symbol: "BTC-USD".into(),
+            side: "1".into(),
+            reason: RejectReason::DuplicateOrder,
+            text: "ClOrdID A1 already used".into(),
+        };
+        let encoded = reject.encode(7, Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap());
+
+        validate_message(encoded.as_bytes()).unwrap();
+        assert!(encoded.starts_with("8=FIX.4.2\x019="));
+        assert!(encoded.contains("\x0135=8\x01"));
+        assert!(encoded.contains("\x0134=7\x01"));
+        assert!(encoded.contains("\x0139=8\x01"));
+        assert!(encoded.contains("\x01103=6\x01"));
+        assert!(encoded.contains("\x0158=[1004] Duplicate order: ClOrdID A1 already used\x01"));
+        assert!(encoded.ends_with('\x01'));
+    }
+
+    #[test]
+    fn test_encode_order_status() {
+        use crate::market::orders::{OrderEntry, OrderStatus};
+
+        let at = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        let mut report = OrderStatusReport {
+            sender_comp_id: "ROMER".into(),
+            target_comp_id: "MM1".into(),
+            order: Some(OrderState {
+                order_id: "7".into(),
+                entry: OrderEntry {
+                    sender_comp_id: "MM1".into(),
+                    cl_ord_id: "A1".into(),
+                    symbol: "BTC-USD".into(),
+                    side: "1".into(),
+                    account: None,
+                    order_qty: 10,
+                    price: Some("100".into()),
+                },
+                status: OrderStatus::PartiallyFilled,
+                cum_qty: 4,
+                avg_px: 100.0,
+                text: None,
+                created_at: at,
+                updated_at: at,
+            }),
+            cl_ord_id: "A1".into(),
+            symbol: "BTC-USD".into(),
+            side: "1".into(),
+            ord_status_req_id: Some("S1".into()),
+            unsolicited: false,
+        };
+        let encoded = report.encode(3, at);
+        validate_message(encoded.as_bytes()).unwrap();
+        assert!(encoded.contains("\x0137=7\x0111=A1\x01"));
+        assert!(encoded.contains("\x01150=I\x0139=1\x01"));
+        assert!(encoded.contains("\x0114=4\x01151=6\x01"));
+        assert!(encoded.contains("\x01790=S1\x01325=N\x01"));
+
+        report.order = None;
+        report.ord_status_req_id = None;
+        report.unsolicited = true;
+        let encoded = report.encode(4, at);
+        validate_message(encoded.as_bytes()).unwrap();
+        assert!(encoded.contains("\x0139=8\x01"));
+        assert!(encoded.contains("\x0158=[1012] Unknown order\x01325=Y\x01"));
+    }
+
+    #[test]
+    fn test_encode_news() {
+        let news = News {
+            sender_comp_id: "ROMER".into(),
+            target_comp_id: "MM1".into(),
+            headline: "Fee tier changed".into(),
+            text: "Tier gold from epoch 4".into(),
+        };
+        let encoded = news.encode(1, Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap());
+
+        validate_message(encoded.as_bytes()).unwrap();
+        assert!(encoded.contains("\x0135=B\x01"));
+        assert!(encoded.contains("\x01148=Fee tier changed\x0133=1\x0158=Tier gold from epoch 4\x01"));
+    }
+}
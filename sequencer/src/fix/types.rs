@@ -1,12 +1,13 @@
 // src/fix/types.rs
 
-use fefix::prelude::*;
-use fefix::tagvalue::{Config, Message};
 use fefix::Dictionary;
+use romer_common::types::fix::utils::{parse_message_fields, validate_message};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use thiserror::Error;
 
 /// Represents the core message types we support in FIX 4.2
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MessageType {
     // Session messages
     Logon,              // Type = 'A'
@@ -20,24 +21,48 @@ pub enum MessageType {
     NewOrderSingle,     // Type = 'D'
     OrderCancelRequest, // Type = 'F'
     MarketDataRequest,  // Type = 'V'
+    MarketDataSnapshot, // Type = 'W'
+    OrderMassCancelRequest, // Type = 'q'
+    OrderStatusRequest, // Type = 'H'
+    AllocationInstruction, // Type = 'J'
+    AllocationAck,      // Type = 'P'
 }
 
 impl MessageType {
-    /// Convert a FIX message type char into our enum
-    pub fn from_fix(typ: char) -> Option<Self> {
+    /// Convert a FIX MsgType (35) value into our enum
+    pub fn from_fix(typ: &str) -> Option<Self> {
         match typ {
-            'A' => Some(Self::Logon),
-            '5' => Some(Self::Logout),
-            '0' => Some(Self::Heartbeat),
-            '1' => Some(Self::TestRequest),
-            '2' => Some(Self::ResendRequest),
-            '4' => Some(Self::SequenceReset),
-            'D' => Some(Self::NewOrderSingle),
-            'F' => Some(Self::OrderCancelRequest),
-            'V' => Some(Self::MarketDataRequest),
+            "A" => Some(Self::Logon),
+            "5" => Some(Self::Logout),
+            "0" => Some(Self::Heartbeat),
+            "1" => Some(Self::TestRequest),
+            "2" => Some(Self::ResendRequest),
+            "4" => Some(Self::SequenceReset),
+            "D" => Some(Self::NewOrderSingle),
+            "F" => Some(Self::OrderCancelRequest),
+            "V" => Some(Self::MarketDataRequest),
+            "W" => Some(Self::MarketDataSnapshot),
+            "q" => Some(Self::OrderMassCancelRequest),
+            "H" => Some(Self::OrderStatusRequest),
+            "J" => Some(Self::AllocationInstruction),
+            "P" => Some(Self::AllocationAck),
             _ => None,
         }
     }
+
+    /// Session level messages are handled by the session layer and never
+    /// reach the order pipeline
+    pub fn is_session_level(&self) -> bool {
+        matches!(
+            self,
+            Self::Logon
+                | Self::Logout
+                | Self::Heartbeat
+                | Self::TestRequest
+                | Self::ResendRequest
+                | Self::SequenceReset
+        )
+    }
 }
 
 /// Core configuration for our FIX decoder/encoder
@@ -60,19 +85,58 @@ impl Default for FixConfig {
     }
 }
 
-/// Represents a validated FIX message ready for processing
-#[derive(Debug)]
-pub struct ValidatedMessage<'a, T = Vec<u8>>  {
+/// Represents a validated FIX message ready for processing: its framing
+/// has been checked and the standard header pulled out
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidatedMessage {
     /// The type of message
     pub msg_type: MessageType,
-    /// The raw FIX message for processing
-    pub message: Message<'a, T>,
     /// Sender's comp ID
     pub sender_comp_id: String,
     /// Target comp ID
     pub target_comp_id: String,
     /// Message sequence number
     pub msg_seq_num: u64,
+    /// Every field of the message by tag; repeated tags keep their last value
+    pub fields: BTreeMap<u32, String>,
+    /// The message as received
+    pub raw: Vec<u8>,
+}
+
+impl ValidatedMessage {
+    /// Checks the framing of `raw` and extracts its header. MsgType,
+    /// SenderCompID, TargetCompID and MsgSeqNum are required.
+    pub fn parse(raw: &[u8]) -> FixResult<Self> {
+        if raw.len() > FixConfig::default().max_message_size {
+            return Err(FixError::MessageTooLarge);
+        }
+        validate_message(raw).map_err(|e| FixError::InvalidFormat(e.to_string()))?;
+        let fields: BTreeMap<u32, String> = parse_message_fields(raw).into_iter().collect();
+        let required = |tag: u32, name: &str| {
+            fields
+                .get(&tag)
+                .cloned()
+                .ok_or_else(|| FixError::MissingField(name.to_string()))
+        };
+        let msg_type = required(35, "MsgType")?;
+        let msg_type = MessageType::from_fix(&msg_type).ok_or(FixError::InvalidMessageType(msg_type))?;
+        let msg_seq_num = required(34, "MsgSeqNum")?
+            .parse()
+            .map_err(|_| FixError::InvalidFormat("MsgSeqNum is not a number".to_string()))?;
+        Ok(Self {
+            msg_type,
+            sender_comp_id: required(49, "SenderCompID")?,
+            target_comp_id: required(56, "TargetCompID")?,
+            msg_seq_num,
+            raw: raw.to_vec(),
+            fields,
+        })
+    }
+
+    /// Value of `tag`, if the message has it
+    pub fn field(&self, tag: u32) -> Option<&str> {
+        self.fields.get(&tag).map(String::as_str)
+    }
 }
 
 /// Errors that can occur during FIX message processing
@@ -98,4 +162,34 @@ pub enum FixError {
 }
 
 /// Result type for FIX operations
-pub type FixResult<T> = Result<T, FixError>;
\ No newline at end of file
+pub type FixResult<T> = Result<T, FixError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use romer_common::types::fix::utils::encode_message;
+
+    #[test]
+    fn test_parse_validated_message() {
+        let raw = encode_message(
+            "FIX.4.2",
+            &[
+                (35, "D".to_string()),
+                (49, "MM1".to_string()),
+                (56, "ROMER".to_string()),
+                (34, "7".to_string()),
+                (11, "A1".to_string()),
+            ],
+        );
+        let message = ValidatedMessage::parse(&raw).unwrap();
+        assert_eq!(message.msg_type, MessageType::NewOrderSingle);
+        assert_eq!((message.sender_comp_id.as_str(), message.target_comp_id.as_str()), ("MM1", "ROMER"));
+        assert_eq!(message.msg_seq_num, 7);
+        assert_eq!(message.field(11), Some("A1"));
+        assert_eq!(message.field(44), None);
+
+        let unsequenced = encode_message("FIX.4.2", &[(35, "0".to_string()), (49, "MM1".to_string())]);
+        assert!(matches!(ValidatedMessage::parse(&unsequenced), Err(FixError::MissingField(_))));
+        assert!(matches!(ValidatedMessage::parse(&raw[1..]), Err(FixError::InvalidFormat(_))));
+    }
+}
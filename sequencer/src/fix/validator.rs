@@ -45,6 +45,7 @@ impl FixValidator {
             MessageType::OrderCancelRequest => self.validate_cancel_order(&message.message),
             // Session messages generally don't need extensive validation
             MessageType::Heartbeat | 
+            MessageType::OrderMassCancelRequest |
             MessageType::TestRequest |
             MessageType::ResendRequest |
             MessageType::SequenceReset |
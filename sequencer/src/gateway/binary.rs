@@ -0,0 +1,465 @@
+// src/gateway/binary.rs
+
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use romer_common::types::fix::utils::{encode_message, format_timestamp, parse_message_fields};
+use romer_common::utils::clock::SharedClock;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::sync::Arc;
+use std::time::Instant;
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+/// Largest frame either side may send
+pub const MAX_FRAME_LEN: usize = 64 * 1024;
+
+/// BeginString of the messages the gateway hands to the pipeline
+const BEGIN_STRING: &str = "FIX.4.2";
+
+/// Replies waiting to be written to one connection
+const REPLY_QUEUE: usize = 1024;
+
+#[derive(Error, Debug)]
+pub enum BinaryGatewayError {
+    #[error("Connection error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Frame of {0} bytes exceeds the limit")]
+    FrameTooLarge(usize),
+
+    #[error("Malformed frame: {0}")]
+    Malformed(#[from] bincode::Error),
+
+    #[error("Order pipeline unavailable")]
+    PipelineClosed,
+}
+
+/// Order entry requests of the native protocol. After a `Logon` the
+/// connection is bound to its SenderCompID and TargetCompID, which later
+/// requests do not repeat.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BinaryRequest {
+    Logon {
+        sender_comp_id: String,
+        target_comp_id: String,
+        heartbeat_secs: Option<u32>,
+        /// Further Logon fields, e.g. a session key certificate
+        auth: Vec<(u32, String)>,
+    },
+    Logout {
+        text: Option<String>,
+    },
+    NewOrder {
+        cl_ord_id: String,
+        symbol: String,
+        /// FIX Side (54), `'1'` buy and `'2'` sell
+        side: char,
+        quantity: u64,
+        /// Limit price, `None` for a market order
+        price: Option<f64>,
+    },
+    MassCancel {
+        cl_ord_id: String,
+        /// FIX MassCancelRequestType (530)
+        request_type: char,
+    },
+    Heartbeat,
+}
+
+/// What the pipeline answered
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BinaryResponse {
+    /// A plain acknowledgement or rejection
+    Text(String),
+    /// A FIX message such as an ExecutionReport, as (tag, value) pairs
+    Message(Vec<(u32, String)>),
+}
+
+impl BinaryRequest {
+    /// Renders the request as the FIX message the order pipeline handles,
+    /// so both gateways share session auth, risk checks and sequencing
+    pub fn to_fix(&self, sender_comp_id: &str, target_comp_id: &str, msg_seq_num: u64, now: DateTime<Utc>) -> String {
+        let msg_type = match self {
+            Self::Logon { .. } => "A",
+            Self::Logout { .. } => "5",
+            Self::NewOrder { .. } => "D",
+            Self::MassCancel { .. } => "q",
+            Self::Heartbeat => "0",
+        };
+        let mut fields = vec![
+            (35, msg_type.to_string()),
+            (49, sender_comp_id.to_string()),
+            (56, target_comp_id.to_string()),
+            (34, msg_seq_num.to_string()),
+            (52, format_timestamp(now)),
+        ];
+        match self {
+            Self::Logon { heartbeat_secs, auth, .. } => {
+                fields.push((98, "0".to_string()));
+                // The native protocol has no sequence numbers of its own, so
+                // every connection starts its session's afresh
+                fields.push((141, "Y".to_string()));
+                if let Some(secs) = heartbeat_secs {
+                    fields.push((108, secs.to_string()));
+                }
+                fields.extend(auth.iter().cloned());
+            }
+            Self::Logout { text: Some(text) } => fields.push((58, text.clone())),
+            Self::NewOrder {
+                cl_ord_id,
+                symbol,
+                side,
+                quantity,
+                price,
+            } => {
+                fields.push((11, cl_ord_id.clone()));
+                fields.push((55, symbol.clone()));
+                fields.push((54, side.to_string()));
+                fields.push((38, quantity.to_string()));
+                match price {
+                    Some(price) => {
+                        fields.push((40, "2".to_string()));
+                        fields.push((44, price.to_string()));
+                    }
+                    None => fields.push((40, "1".to_string())),
+                }
+            }
+            Self::MassCancel { cl_ord_id, request_type } => {
+                fields.push((11, cl_ord_id.clone()));
+                fields.push((530, request_type.to_string()));
+            }
+            Self::Logout { text: None } | Self::Heartbeat => {}
+        }
+        String::from_utf8_lossy(&encode_message(BEGIN_STRING, &fields)).into_owned()
+    }
+}
+
+impl BinaryResponse {
+    /// Wraps the pipeline's reply, a FIX message or plain text
+    pub fn from_reply(reply: &str) -> Self {
+        if !reply.starts_with("8=") {
+            return Self::Text(reply.trim_end().to_string());
+        }
+        let mut fields: Vec<(u32, String)> = parse_message_fields(reply.as_bytes()).into_iter().collect();
+        fields.sort_by_key(|(tag, _)| *tag);
+        Self::Message(fields)
+    }
+}
+
+/// Reads one length prefixed frame: a big endian u32 length followed by
+/// the bincode encoded value. Returns `None` at a clean end of stream.
+pub async fn read_frame<R, T>(reader: &mut R) -> Result<Option<T>, BinaryGatewayError>
+where
+    R: AsyncRead + Unpin,
+    T: for<'de> Deserialize<'de>,
+{
+    let len = match reader.read_u32().await {
+        Ok(len) => len as usize,
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    if len > MAX_FRAME_LEN {
+        return Err(BinaryGatewayError::FrameTooLarge(len));
+    }
+    let mut payload = vec![0u8; len];
+    reader.read_exact(&mut payload).await?;
+    Ok(Some(bincode::deserialize(&payload)?))
+}
+
+pub async fn write_frame<W, T>(writer: &mut W, value: &T) -> Result<(), BinaryGatewayError>
+where
+    W: AsyncWrite + Unpin,
+    T: Serialize,
+{
+    let payload = bincode::serialize(value)?;
+    if payload.len() > MAX_FRAME_LEN {
+        return Err(BinaryGatewayError::FrameTooLarge(payload.len()));
+    }
+    writer.write_u32(payload.len() as u32).await?;
+    writer.write_all(&payload).await?;
+    Ok(())
+}
+
+/// A message for the order pipeline and where to send replies to its
+/// connection
+pub struct PipelineRequest {
+    /// Connection the message arrived on, bound to the session its Logon
+    /// opens
+    pub connection_id: Uuid,
+    pub message: String,
+    /// Every message for the connection, answers or not. An empty one
+    /// closes the connection.
+    pub replies: mpsc::Sender<String>,
+    /// When the request arrived, so the pipeline can measure its queueing
+    pub received_at: Instant,
+}
+
+/// Native order entry for latency sensitive internal systems. Connections
+/// stay open for many requests and are handed to the same pipeline as FIX
+/// messages, whose session sends heartbeats, reports and the Logout
+/// handshake back over the connection.
+pub struct BinaryGateway {
+    pipeline: mpsc::Sender<PipelineRequest>,
+    clock: SharedClock,
+}
+
+impl BinaryGateway {
+    pub fn new(pipeline: mpsc::Sender<PipelineRequest>, clock: SharedClock) -> Self {
+        Self { pipeline, clock }
+    }
+
+    pub async fn run(self, listener: TcpListener) {
+        loop {
+            match listener.accept().await {
+                Ok((socket, addr)) => {
+                    info!("Accepted binary gateway connection from: {}", addr);
+                    if let Err(e) = socket.set_nodelay(true) {
+                        error!("Failed to set TCP_NODELAY: {}", e);
+                    }
+                    let (replies, replies_rx) = mpsc::channel(REPLY_QUEUE);
+                    let connection = Connection {
+                        connection_id: Uuid::new_v4(),
+                        pipeline: self.pipeline.clone(),
+                        clock: self.clock.clone(),
+                        binding: Arc::new(Mutex::new(Binding::default())),
+                        replies,
+                        msg_seq_num: 0,
+                    };
+                    tokio::spawn(async move {
+                        if let Err(e) = connection.serve(socket, replies_rx).await {
+                            debug!("Binary gateway connection from {} ended: {}", addr, e);
+                        }
+                    });
+                }
+                Err(e) => error!("Failed to accept binary gateway connection: {}", e),
+            }
+        }
+    }
+}
+
+/// Where a connection is in its session, as seen in both directions
+#[derive(Debug, Default)]
+struct Binding {
+    /// SenderCompID and TargetCompID, bound once the Logon is acknowledged
+    session: Option<(String, String)>,
+    /// Those of a Logon awaiting its acknowledgement
+    pending: Option<(String, String)>,
+    /// The session sent Logout, which the client's Logout confirms
+    logout_received: bool,
+    /// The client sent Logout, which the session's Logout confirms
+    logout_sent: bool,
+}
+
+struct Connection {
+    connection_id: Uuid,
+    pipeline: mpsc::Sender<PipelineRequest>,
+    clock: SharedClock,
+    binding: Arc<Mutex<Binding>>,
+    replies: mpsc::Sender<String>,
+    msg_seq_num: u64,
+}
+
+impl Connection {
+    /// Reads requests while a writer task sends whatever the session
+    /// queues, until either side ends the connection
+    async fn serve(mut self, socket: TcpStream, replies: mpsc::Receiver<String>) -> Result<(), BinaryGatewayError> {
+        let (reader, writer) = socket.into_split();
+        let mut writer = tokio::spawn(write_replies(writer, replies, self.binding.clone()));
+        let result = tokio::select! {
+            result = self.read_requests(reader) => result,
+            written = &mut writer => written.unwrap_or(Ok(())),
+        };
+        // The session notices the connection is gone on its next message
+        writer.abort();
+        result
+    }
+
+    async fn read_requests(&mut self, mut reader: OwnedReadHalf) -> Result<(), BinaryGatewayError> {
+        while let Some(request) = read_frame::<_, BinaryRequest>(&mut reader).await? {
+            let received_at = self.clock.instant();
+            let mut confirms_logout = false;
+            let bound = {
+                let mut binding = self.binding.lock();
+                let binding = &mut *binding;
+                match (&request, &binding.session) {
+                    (BinaryRequest::Logon { .. }, _) if binding.session.is_some() || binding.pending.is_some() => {
+                        Err("Already logged on")
+                    }
+                    (BinaryRequest::Logon { sender_comp_id, target_comp_id, .. }, None) => {
+                        binding.pending = Some((sender_comp_id.clone(), target_comp_id.clone()));
+                        Ok((sender_comp_id.clone(), target_comp_id.clone()))
+                    }
+                    (BinaryRequest::Logout { .. }, Some(session)) => {
+                        confirms_logout = binding.logout_received;
+                        binding.logout_sent = !confirms_logout;
+                        Ok(session.clone())
+                    }
+                    (_, Some(session)) => Ok(session.clone()),
+                    (_, None) => Err("Logon required"),
+                }
+            };
+            let (sender_comp_id, target_comp_id) = match bound {
+                Ok(bound) => bound,
+                Err(text) => {
+                    self.replies
+                        .send(text.to_string())
+                        .await
+                        .map_err(|_| BinaryGatewayError::PipelineClosed)?;
+                    continue;
+                }
+            };
+
+            if let BinaryRequest::Logon { .. } = request {
+                self.msg_seq_num = 0;
+            }
+            self.msg_seq_num += 1;
+            let message = request.to_fix(&sender_comp_id, &target_comp_id, self.msg_seq_num, self.clock.now());
+            self.pipeline
+                .send(PipelineRequest {
+                    connection_id: self.connection_id,
+                    message,
+                    replies: self.replies.clone(),
+                    received_at,
+                })
+                .await
+                .map_err(|_| BinaryGatewayError::PipelineClosed)?;
+            if confirms_logout {
+                return Ok(());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes the session's messages to the connection, binding it when its
+/// Logon is acknowledged and ending it when a Logout completes the
+/// handshake
+async fn write_replies(
+    mut writer: OwnedWriteHalf,
+    mut replies: mpsc::Receiver<String>,
+    binding: Arc<Mutex<Binding>>,
+) -> Result<(), BinaryGatewayError> {
+    while let Some(reply) = replies.recv().await {
+        if reply.is_empty() {
+            break;
+        }
+        let msg_type = reply
+            .starts_with("8=")
+            .then(|| parse_message_fields(reply.as_bytes()).remove(&35))
+            .flatten();
+        let mut last = false;
+        {
+            let mut binding = binding.lock();
+            let binding = &mut *binding;
+            match msg_type.as_deref() {
+                Some("A") => binding.session = binding.pending.take(),
+                // A Logout before the Logon was acknowledged rejects it
+                Some("5") if binding.session.is_none() => binding.pending = None,
+                Some("5") if binding.logout_sent => last = true,
+                Some("5") => binding.logout_received = true,
+                _ => {}
+            }
+        }
+        write_frame(&mut writer, &BinaryResponse::from_reply(&reply)).await?;
+        if last {
+            break;
+        }
+    }
+    let _ = writer.shutdown().await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use romer_common::types::fix::utils::validate_message;
+    use romer_common::utils::clock::system_clock;
+
+    #[test]
+    fn test_new_order_renders_as_fix() {
+        let order = BinaryRequest::NewOrder {
+            cl_ord_id: "A1".into(),
+            symbol: "AAPL".into(),
+            side: '1',
+            quantity: 100,
+            price: Some(187.5),
+        };
+        let message = order.to_fix("MM1", "ROMER", 2, Utc::now());
+        validate_message(message.as_bytes()).unwrap();
+        let fields = parse_message_fields(message.as_bytes());
+        assert_eq!(fields[&35], "D");
+        assert_eq!(fields[&49], "MM1");
+        assert_eq!(fields[&44], "187.5");
+        assert_eq!(fields[&40], "2");
+
+        // Each connection's session starts its sequence numbers afresh
+        let logon = BinaryRequest::Logon {
+            sender_comp_id: "MM1".into(),
+            target_comp_id: "ROMER".into(),
+            heartbeat_secs: None,
+            auth: Vec::new(),
+        };
+        let fields = parse_message_fields(logon.to_fix("MM1", "ROMER", 1, Utc::now()).as_bytes());
+        assert_eq!(fields[&141], "Y");
+    }
+
+    #[tokio::test]
+    async fn test_frames_round_trip() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        write_frame(&mut client, &BinaryRequest::Heartbeat).await.unwrap();
+        drop(client);
+        let request: Option<BinaryRequest> = read_frame(&mut server).await.unwrap();
+        assert_eq!(request, Some(BinaryRequest::Heartbeat));
+        assert!(read_frame::<_, BinaryRequest>(&mut server).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_session_binds_at_logon() {
+        let (pipeline, mut requests) = mpsc::channel::<PipelineRequest>(8);
+        tokio::spawn(async move {
+            while let Some(request) = requests.recv().await {
+                let fields = parse_message_fields(request.message.as_bytes());
+                let reply = match fields[&35].as_str() {
+                    "A" => String::from_utf8(encode_message(
+                        BEGIN_STRING,
+                        &[(35, "A".to_string()), (34, "1".to_string()), (108, "30".to_string())],
+                    ))
+                    .unwrap(),
+                    _ => format!("{} from {}\n", fields[&35], fields[&49]),
+                };
+                let _ = request.replies.send(reply).await;
+            }
+        });
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(BinaryGateway::new(pipeline, system_clock()).run(listener));
+
+        let mut client = TcpStream::connect(address).await.unwrap();
+        write_frame(&mut client, &BinaryRequest::Heartbeat).await.unwrap();
+        let response: BinaryResponse = read_frame(&mut client).await.unwrap().unwrap();
+        assert_eq!(response, BinaryResponse::Text("Logon required".into()));
+
+        let logon = BinaryRequest::Logon {
+            sender_comp_id: "MM1".into(),
+            target_comp_id: "ROMER".into(),
+            heartbeat_secs: Some(30),
+            auth: Vec::new(),
+        };
+        write_frame(&mut client, &logon).await.unwrap();
+        let response: BinaryResponse = read_frame(&mut client).await.unwrap().unwrap();
+        let BinaryResponse::Message(fields) = response else {
+            panic!("expected the Logon acknowledgement, got {:?}", response);
+        };
+        assert!(fields.contains(&(35, "A".to_string())));
+
+        write_frame(&mut client, &BinaryRequest::Heartbeat).await.unwrap();
+        let response: BinaryResponse = read_frame(&mut client).await.unwrap().unwrap();
+        assert_eq!(response, BinaryResponse::Text("0 from MM1".into()));
+    }
+}
@@ -0,0 +1,3 @@
+pub mod binary;
+pub mod news;
+pub mod speed_bump;
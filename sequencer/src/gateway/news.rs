@@ -0,0 +1,198 @@
+// src/gateway/news.rs
+
+use crate::events::bus::EventBus;
+use crate::events::types::SequencerEvent;
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use romer_common::storage::group_commit::GroupCommitter;
+use romer_common::storage::journal::RomerJournal;
+use romer_common::utils::clock::SharedClock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use thiserror::Error;
+use tracing::{info, warn};
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum NewsError {
+    #[error("News needs a headline")]
+    EmptyHeadline,
+
+    #[error("Storage error: {0}")]
+    Storage(String),
+}
+
+/// An operational notice broadcast as FIX News (35=B), as journaled
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewsNotice {
+    pub id: u64,
+    pub headline: String,
+    pub text: String,
+    /// SenderCompIDs the notice is for; every session when empty
+    #[serde(default)]
+    pub recipients: Vec<String>,
+    pub published_by: String,
+    pub at: DateTime<Utc>,
+}
+
+impl NewsNotice {
+    pub fn is_for(&self, sender_comp_id: &str) -> bool {
+        self.recipients.is_empty() || self.recipients.iter().any(|recipient| recipient == sender_comp_id)
+    }
+}
+
+#[derive(Default)]
+struct NewsState {
+    notices: Vec<NewsNotice>,
+    /// Last notice delivered to each SenderCompID
+    delivered: HashMap<String, u64>,
+}
+
+/// Operational notices (maintenance windows, incidents) published by
+/// operators. Each notice is journaled before it is announced on the event
+/// bus, where sessions and gateways pick it up; counterparties without a
+/// live session get the day's notices with their next message.
+pub struct NewsService {
+    state: Mutex<NewsState>,
+    journal: Option<GroupCommitter>,
+    events: EventBus,
+    clock: SharedClock,
+}
+
+impl NewsService {
+    /// Service without persistence, for tests and tooling
+    pub fn in_memory(events: EventBus, clock: SharedClock) -> Self {
+        Self {
+            state: Mutex::new(NewsState::default()),
+            journal: None,
+            events,
+            clock,
+        }
+    }
+
+    /// Opens the service over `journal`, restoring the notices in it
+    pub async fn open(mut journal: RomerJournal, events: EventBus, clock: SharedClock) -> Result<Self, NewsError> {
+        let mut state = NewsState::default();
+        for bytes in journal.replay_all().await.map_err(NewsError::Storage)? {
+            match serde_json::from_slice::<NewsNotice>(&bytes) {
+                Ok(notice) => state.notices.push(notice),
+                Err(e) => warn!(error = %e, "Skipping undecodable news record"),
+            }
+        }
+        info!(notices = state.notices.len(), "Restored news");
+
+        Ok(Self {
+            state: Mutex::new(state),
+            journal: Some(GroupCommitter::spawn(journal)),
+            events,
+            clock,
+        })
+    }
+
+    /// Journals and announces a notice for `recipients`, or for every
+    /// session if there are none
+    pub async fn publish(
+        &self,
+        headline: String,
+        text: String,
+        recipients: Vec<String>,
+        published_by: &str,
+    ) -> Result<NewsNotice, NewsError> {
+        if headline.trim().is_empty() {
+            return Err(NewsError::EmptyHeadline);
+        }
+        let notice = NewsNotice {
+            id: self.state.lock().notices.last().map_or(1, |last| last.id + 1),
+            headline,
+            text,
+            recipients,
+            published_by: published_by.to_string(),
+            at: self.clock.now(),
+        };
+        if let Some(journal) = &self.journal {
+            let bytes = serde_json::to_vec(&notice).map_err(|e| NewsError::Storage(e.to_string()))?;
+            journal.append(bytes).await.map_err(NewsError::Storage)?;
+        }
+        self.state.lock().notices.push(notice.clone());
+
+        info!(id = notice.id, headline = %notice.headline, recipients = notice.recipients.len(), "News published");
+        self.events.publish(SequencerEvent::NewsPublished {
+            id: notice.id,
+            headline: notice.headline.clone(),
+            text: notice.text.clone(),
+            recipients: notice.recipients.clone(),
+            published_by: notice.published_by.clone(),
+            at: notice.at,
+        });
+        Ok(notice)
+    }
+
+    /// Notices of today for `sender_comp_id` not yet delivered to it,
+    /// marking them delivered
+    pub fn take_pending(&self, sender_comp_id: &str) -> Vec<NewsNotice> {
+        let today = self.clock.now().date_naive();
+        let mut state = self.state.lock();
+        let delivered = state.delivered.get(sender_comp_id).copied().unwrap_or(0);
+        let pending: Vec<NewsNotice> = state
+            .notices
+            .iter()
+            .filter(|notice| notice.id > delivered && notice.at.date_naive() == today && notice.is_for(sender_comp_id))
+            .cloned()
+            .collect();
+        if let Some(last) = state.notices.last().map(|notice| notice.id) {
+            state.delivered.insert(sender_comp_id.to_string(), last);
+        }
+        pending
+    }
+
+    /// Marks notices up to `id` delivered to `sender_comp_id`, e.g. once its
+    /// live session was sent them
+    pub fn mark_delivered(&self, sender_comp_id: &str, id: u64) {
+        let mut state = self.state.lock();
+        let delivered = state.delivered.entry(sender_comp_id.to_string()).or_default();
+        *delivered = (*delivered).max(id);
+    }
+
+    /// The latest `limit` notices, oldest first
+    pub fn recent(&self, limit: usize) -> Vec<NewsNotice> {
+        let state = self.state.lock();
+        state.notices[state.notices.len().saturating_sub(limit)..].to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use romer_common::utils::clock::ManualClock;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_notices_delivered_once_to_recipients() {
+        let clock = Arc::new(ManualClock::new(Utc.with_ymd_and_hms(2024, 3, 1, 9, 0, 0).unwrap()));
+        let events = EventBus::default();
+        let mut subscriber = events.subscribe();
+        let news = NewsService::in_memory(events, clock.clone());
+
+        news.publish("Maintenance".into(), "Closed 17:00-18:00".into(), vec![], "ops").await.unwrap();
+        news.publish("Incident".into(), "Order entry delayed".into(), vec!["MM2".into()], "ops").await.unwrap();
+        assert_eq!(
+            news.publish(" ".into(), String::new(), vec![], "ops").await,
+            Err(NewsError::EmptyHeadline)
+        );
+        assert!(matches!(
+            &*subscriber.recv().await.unwrap(),
+            SequencerEvent::NewsPublished { id: 1, recipients, .. } if recipients.is_empty()
+        ));
+
+        assert_eq!(news.take_pending("MM1").len(), 1);
+        assert!(news.take_pending("MM1").is_empty());
+        news.mark_delivered("MM2", 1);
+        assert_eq!(news.take_pending("MM2")[0].headline, "Incident");
+
+        // Yesterday's notices are not delivered
+        clock.advance(Duration::from_secs(24 * 3600));
+        assert!(news.take_pending("MM3").is_empty());
+        assert_eq!(news.recent(1)[0].id, 2);
+    }
+}
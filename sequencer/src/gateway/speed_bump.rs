@@ -0,0 +1,121 @@
+// src/gateway/speed_bump.rs
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+/// The delay and traffic of a speed bump, published to participants
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpeedBumpStatus {
+    pub delay_ms: u64,
+    /// Messages waiting out the delay now
+    pub held: u64,
+    pub released: u64,
+}
+
+/// Counters shared between a speed bump and whoever reports on it
+#[derive(Debug, Default)]
+pub struct SpeedBumpState {
+    delay: Duration,
+    held: AtomicU64,
+    released: AtomicU64,
+}
+
+impl SpeedBumpState {
+    pub fn status(&self) -> SpeedBumpStatus {
+        SpeedBumpStatus {
+            delay_ms: self.delay.as_millis() as u64,
+            held: self.held.load(Ordering::Relaxed),
+            released: self.released.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Ingress delay buffer: every message held is released exactly `delay`
+/// after it arrived, in arrival order, so all participants are slowed
+/// alike and none can act on market data before an earlier order lands.
+/// Without a delay nothing is held.
+pub struct SpeedBump<T> {
+    state: Arc<SpeedBumpState>,
+    inlet: mpsc::UnboundedSender<(Instant, T)>,
+    outlet: mpsc::UnboundedReceiver<T>,
+}
+
+impl<T: Send + 'static> SpeedBump<T> {
+    /// Starts the task releasing held messages
+    pub fn new(delay: Duration) -> Self {
+        let state = Arc::new(SpeedBumpState {
+            delay,
+            ..SpeedBumpState::default()
+        });
+        let (inlet, mut held) = mpsc::unbounded_channel::<(Instant, T)>();
+        let (release, outlet) = mpsc::unbounded_channel();
+        let releasing = state.clone();
+        tokio::spawn(async move {
+            while let Some((arrived, message)) = held.recv().await {
+                tokio::time::sleep_until(arrived + delay).await;
+                releasing.held.fetch_sub(1, Ordering::Relaxed);
+                releasing.released.fetch_add(1, Ordering::Relaxed);
+                if release.send(message).is_err() {
+                    return;
+                }
+            }
+        });
+        Self { state, inlet, outlet }
+    }
+
+    pub fn enabled(&self) -> bool {
+        !self.state.delay.is_zero()
+    }
+
+    pub fn state(&self) -> Arc<SpeedBumpState> {
+        self.state.clone()
+    }
+
+    /// Holds `message` for the delay from now
+    pub fn hold(&self, message: T) {
+        self.state.held.fetch_add(1, Ordering::Relaxed);
+        if self.inlet.send((Instant::now(), message)).is_err() {
+            self.state.held.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+
+    /// The next message whose delay is up
+    pub async fn released(&mut self) -> Option<T> {
+        self.outlet.recv().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_messages_held_for_the_delay_in_order() {
+        let mut bump = SpeedBump::new(Duration::from_millis(50));
+        assert!(bump.enabled());
+        let start = Instant::now();
+
+        bump.hold("first");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        bump.hold("second");
+        assert_eq!(bump.state().status().held, 2);
+
+        assert_eq!(bump.released().await, Some("first"));
+        assert!(Instant::now() - start >= Duration::from_millis(50));
+        assert_eq!(bump.released().await, Some("second"));
+        assert!(Instant::now() - start >= Duration::from_millis(70));
+        assert_eq!(
+            bump.state().status(),
+            SpeedBumpStatus {
+                delay_ms: 50,
+                held: 0,
+                released: 2,
+            }
+        );
+        assert!(!SpeedBump::<()>::new(Duration::ZERO).enabled());
+    }
+}
@@ -0,0 +1,368 @@
+// src/governance/service.rs
+
+use parking_lot::Mutex;
+use romer_common::types::governance::{GovernanceError, Parameters, Proposal, Signed, Vote};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::{info, warn};
+
+#[derive(Error, Debug)]
+pub enum ProposalError {
+    #[error(transparent)]
+    Invalid(#[from] GovernanceError),
+
+    #[error("{0} is not a governance validator")]
+    NotValidator(String),
+
+    #[error("Activation height {activation_height} is less than {min_delay} blocks after {height}")]
+    TooSoon {
+        activation_height: u64,
+        height: u64,
+        min_delay: u64,
+    },
+
+    #[error("No proposal {0}")]
+    UnknownProposal(u64),
+
+    #[error("Voting on proposal {0} has closed")]
+    VotingClosed(u64),
+
+    #[error("Failed to record governance action: {0}")]
+    Io(#[from] io::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalStatus {
+    Voting,
+    /// Approved and applied at its activation height
+    Executed,
+    /// Short of the quorum at its activation height
+    Rejected,
+}
+
+/// A proposal, its votes and the stake behind them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProposalState {
+    pub id: u64,
+    pub proposer: String,
+    pub proposal: Proposal,
+    /// Validator key to whether it approves; a later vote replaces an
+    /// earlier one
+    pub votes: BTreeMap<String, bool>,
+    pub approve_stake: u64,
+    pub reject_stake: u64,
+    pub status: ProposalStatus,
+}
+
+/// Every accepted proposal and vote, appended to the log so the same
+/// state is rebuilt after a restart
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum Action {
+    Propose { signed: Signed<Proposal>, height: u64 },
+    Vote { signed: Signed<Vote>, height: u64 },
+}
+
+struct State {
+    proposals: BTreeMap<u64, ProposalState>,
+    parameters: Parameters,
+}
+
+/// Changes runtime parameters by validator vote. Validators propose
+/// changes to activate at a height at least `min_delay` blocks out and
+/// vote until then, weighted by stake. At the activation height a proposal
+/// approved by `quorum_bps` of all stake is applied, others are rejected.
+pub struct GovernanceService {
+    /// Validator key to stake
+    validators: BTreeMap<String, u64>,
+    quorum_bps: u32,
+    min_delay: u64,
+    state: Mutex<State>,
+    log_path: Option<PathBuf>,
+}
+
+impl GovernanceService {
+    pub fn new(validators: BTreeMap<String, u64>, quorum_bps: u32, min_delay: u64, parameters: Parameters) -> Self {
+        Self {
+            validators,
+            quorum_bps,
+            min_delay,
+            state: Mutex::new(State {
+                proposals: BTreeMap::new(),
+                parameters,
+            }),
+            log_path: None,
+        }
+    }
+
+    /// Appends accepted actions to the JSON lines file at `path`,
+    /// replaying those already there
+    pub fn with_log(mut self, path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        if path.exists() {
+            for line in BufReader::new(File::open(path)?).lines() {
+                let line = line?;
+                let replayed = match serde_json::from_str::<Action>(&line) {
+                    Ok(Action::Propose { signed, height }) => self.accept_proposal(signed, height).map(|_| ()),
+                    Ok(Action::Vote { signed, height }) => self.accept_vote(signed, height).map(|_| ()),
+                    Err(e) => {
+                        warn!(error = %e, "Skipping undecodable governance action");
+                        continue;
+                    }
+                };
+                if let Err(e) = replayed {
+                    warn!(error = %e, "Skipping governance action that no longer applies");
+                }
+            }
+        }
+        self.log_path = Some(path.to_path_buf());
+        Ok(self)
+    }
+
+    /// Opens a proposal submitted at `height`, returning its id
+    pub fn propose(&self, signed: Signed<Proposal>, height: u64) -> Result<u64, ProposalError> {
+        let id = self.accept_proposal(signed.clone(), height)?;
+        self.record(&Action::Propose { signed, height })?;
+        Ok(id)
+    }
+
+    /// Records a validator's vote cast at `height`
+    pub fn vote(&self, signed: Signed<Vote>, height: u64) -> Result<ProposalState, ProposalError> {
+        let state = self.accept_vote(signed.clone(), height)?;
+        self.record(&Action::Vote { signed, height })?;
+        Ok(state)
+    }
+
+    /// Decides every proposal activating at or below `height`, applying
+    /// approved ones in activation order. Returns the proposals decided.
+    pub fn advance(&self, height: u64) -> Vec<ProposalState> {
+        let mut state = self.state.lock();
+        let State { proposals, parameters } = &mut *state;
+        let mut due: Vec<&mut ProposalState> = proposals
+            .values_mut()
+            .filter(|proposal| {
+                proposal.status == ProposalStatus::Voting && proposal.proposal.activation_height <= height
+            })
+            .collect();
+        due.sort_by_key(|proposal| (proposal.proposal.activation_height, proposal.id));
+
+        let total: u64 = self.validators.values().sum();
+        let mut decided = Vec::new();
+        for proposal in due {
+            if proposal.approve_stake as u128 * 10_000 >= self.quorum_bps as u128 * total as u128 {
+                for change in &proposal.proposal.changes {
+                    parameters.apply(change);
+                }
+                proposal.status = ProposalStatus::Executed;
+                info!(
+                    id = proposal.id,
+                    height = proposal.proposal.activation_height,
+                    "Governance proposal executed"
+                );
+            } else {
+                proposal.status = ProposalStatus::Rejected;
+                info!(
+                    id = proposal.id,
+                    approve_stake = proposal.approve_stake,
+                    total,
+                    "Governance proposal rejected"
+                );
+            }
+            decided.push(proposal.clone());
+        }
+        decided
+    }
+
+    pub fn parameters(&self) -> Parameters {
+        self.state.lock().parameters.clone()
+    }
+
+    pub fn proposals(&self) -> Vec<ProposalState> {
+        self.state.lock().proposals.values().cloned().collect()
+    }
+
+    fn stake(&self, public_key: &str) -> Result<u64, ProposalError> {
+        self.validators
+            .get(public_key)
+            .copied()
+            .ok_or_else(|| ProposalError::NotValidator(public_key.to_string()))
+    }
+
+    fn accept_proposal(&self, signed: Signed<Proposal>, height: u64) -> Result<u64, ProposalError> {
+        self.stake(&signed.public_key)?;
+        signed.verify()?;
+        for change in &signed.body.changes {
+            change.validate()?;
+        }
+        let activation_height = signed.body.activation_height;
+        if activation_height < height.saturating_add(self.min_delay) {
+            return Err(ProposalError::TooSoon {
+                activation_height,
+                height,
+                min_delay: self.min_delay,
+            });
+        }
+
+        let mut state = self.state.lock();
+        let id = state.proposals.keys().next_back().map_or(0, |id| id + 1);
+        state.proposals.insert(
+            id,
+            ProposalState {
+                id,
+                proposer: signed.public_key,
+                proposal: signed.body,
+                votes: BTreeMap::new(),
+                approve_stake: 0,
+                reject_stake: 0,
+                status: ProposalStatus::Voting,
+            },
+        );
+        info!(id, activation_height, "Governance proposal opened");
+        Ok(id)
+    }
+
+    fn accept_vote(&self, signed: Signed<Vote>, height: u64) -> Result<ProposalState, ProposalError> {
+        self.stake(&signed.public_key)?;
+        signed.verify()?;
+        let id = signed.body.proposal_id;
+        let mut state = self.state.lock();
+        let proposal = state.proposals.get_mut(&id).ok_or(ProposalError::UnknownProposal(id))?;
+        if proposal.status != ProposalStatus::Voting || height >= proposal.proposal.activation_height {
+            return Err(ProposalError::VotingClosed(id));
+        }
+        proposal.votes.insert(signed.public_key, signed.body.approve);
+        let (approve, reject): (Vec<_>, Vec<_>) = proposal.votes.iter().partition(|(_, approve)| **approve);
+        let weigh =
+            |votes: Vec<(&String, &bool)>| -> u64 { votes.into_iter().map(|(key, _)| self.validators[key]).sum() };
+        proposal.approve_stake = weigh(approve);
+        proposal.reject_stake = weigh(reject);
+        Ok(proposal.clone())
+    }
+
+    fn record(&self, action: &Action) -> Result<(), ProposalError> {
+        let Some(path) = &self.log_path else {
+            return Ok(());
+        };
+        let mut line = serde_json::to_vec(action).map_err(io::Error::other)?;
+        line.push(b'\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?
+            .write_all(&line)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use commonware_cryptography::{Ed25519, Scheme};
+    use romer_common::types::governance::ParameterChange;
+
+    /// Three validators staking 50, 30 and 20
+    fn validators() -> (Vec<Ed25519>, BTreeMap<String, u64>) {
+        let signers: Vec<Ed25519> = (0..3).map(Ed25519::from_seed).collect();
+        let stakes = signers
+            .iter()
+            .zip([50, 30, 20])
+            .map(|(signer, stake)| (hex::encode(signer.public_key()), stake))
+            .collect();
+        (signers, stakes)
+    }
+
+    fn parameters() -> Parameters {
+        Parameters {
+            block_window_ms: 500,
+            ..Parameters::default()
+        }
+    }
+
+    fn proposal(activation_height: u64) -> Proposal {
+        Proposal {
+            changes: vec![ParameterChange::BlockWindowMs { value: 250 }],
+            activation_height,
+            description: "faster blocks".into(),
+        }
+    }
+
+    fn vote(signer: &mut Ed25519, proposal_id: u64, approve: bool) -> Signed<Vote> {
+        Signed::sign(Vote { proposal_id, approve }, signer).unwrap()
+    }
+
+    #[test]
+    fn test_approved_change_applies_at_activation() {
+        let (mut signers, stakes) = validators();
+        let governance = GovernanceService::new(stakes, 6_667, 10, parameters());
+        assert!(matches!(
+            governance.propose(Signed::sign(proposal(15), &mut signers[0]).unwrap(), 10),
+            Err(ProposalError::TooSoon { .. })
+        ));
+        let id = governance
+            .propose(Signed::sign(proposal(20), &mut signers[0]).unwrap(), 10)
+            .unwrap();
+
+        governance.vote(vote(&mut signers[0], id, true), 11).unwrap();
+        governance.vote(vote(&mut signers[2], id, false), 11).unwrap();
+        // 50 of 100 is short of two thirds, until the third validator
+        // changes its vote
+        let state = governance.vote(vote(&mut signers[2], id, true), 12).unwrap();
+        assert_eq!((state.approve_stake, state.reject_stake), (70, 0));
+
+        assert!(governance.advance(19).is_empty());
+        assert_eq!(governance.parameters().block_window_ms, 500);
+        assert_eq!(governance.advance(20)[0].status, ProposalStatus::Executed);
+        assert_eq!(governance.parameters().block_window_ms, 250);
+        assert!(matches!(
+            governance.vote(vote(&mut signers[1], id, true), 20),
+            Err(ProposalError::VotingClosed(_))
+        ));
+    }
+
+    #[test]
+    fn test_rejected_without_quorum() {
+        let (mut signers, stakes) = validators();
+        let governance = GovernanceService::new(stakes, 6_667, 0, parameters());
+        let id = governance
+            .propose(Signed::sign(proposal(5), &mut signers[1]).unwrap(), 0)
+            .unwrap();
+        governance.vote(vote(&mut signers[1], id, true), 1).unwrap();
+        governance.vote(vote(&mut signers[2], id, true), 1).unwrap();
+
+        let mut outsider = Ed25519::from_seed(9);
+        assert!(matches!(
+            governance.vote(vote(&mut outsider, id, true), 1),
+            Err(ProposalError::NotValidator(_))
+        ));
+        assert_eq!(governance.advance(5)[0].status, ProposalStatus::Rejected);
+        assert_eq!(governance.parameters(), parameters());
+    }
+
+    #[test]
+    fn test_log_replay() {
+        let (mut signers, stakes) = validators();
+        let path = std::env::temp_dir().join(format!("governance-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let governance = GovernanceService::new(stakes.clone(), 5_000, 0, parameters())
+            .with_log(&path)
+            .unwrap();
+        let id = governance
+            .propose(Signed::sign(proposal(5), &mut signers[0]).unwrap(), 0)
+            .unwrap();
+        governance.vote(vote(&mut signers[0], id, true), 1).unwrap();
+
+        let restarted = GovernanceService::new(stakes, 5_000, 0, parameters())
+            .with_log(&path)
+            .unwrap();
+        assert_eq!(restarted.proposals(), governance.proposals());
+        restarted.advance(5);
+        assert_eq!(restarted.parameters().block_window_ms, 250);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,128 @@
+// src/indexer/delivery.rs
+
+use super::kafka::KafkaTarget;
+use super::outbox::{Outbox, OutboxEntry};
+use super::postgres::PostgresTarget;
+use crate::config::IndexerConfig;
+use std::path::Path;
+use std::time::Duration;
+use thiserror::Error;
+use tracing::{debug, error, warn};
+
+#[derive(Error, Debug)]
+pub enum IndexerError {
+    #[error("Postgres error: {0}")]
+    Postgres(#[from] tokio_postgres::Error),
+
+    #[error("Kafka error: {0}")]
+    Kafka(#[from] rskafka::client::error::Error),
+
+    #[error("Failed to encode record: {0}")]
+    Encode(#[from] serde_json::Error),
+
+    #[error("Outbox error: {0}")]
+    Outbox(#[from] std::io::Error),
+}
+
+/// Where indexed records are delivered
+pub enum Target {
+    Postgres(PostgresTarget),
+    Kafka(KafkaTarget),
+}
+
+impl Target {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Postgres(_) => "postgres",
+            Self::Kafka(_) => "kafka",
+        }
+    }
+
+    async fn write(&mut self, entries: &[OutboxEntry]) -> Result<(), IndexerError> {
+        match self {
+            Self::Postgres(target) => target.write(entries).await,
+            Self::Kafka(target) => target.write(entries).await,
+        }
+    }
+}
+
+/// Moves records from the outbox to every target. A batch is acknowledged
+/// only once all targets have it; after a failure it is retried against
+/// every target, which tolerate receiving it twice.
+pub struct Indexer {
+    outbox: Outbox,
+    targets: Vec<Target>,
+    batch_size: usize,
+    retry: Duration,
+}
+
+impl Indexer {
+    pub fn new(outbox: Outbox, targets: Vec<Target>, batch_size: usize, retry: Duration) -> Self {
+        Self {
+            outbox,
+            targets,
+            batch_size,
+            retry,
+        }
+    }
+
+    /// Opens the outbox, under `storage_dir` unless configured elsewhere,
+    /// and the targets `config` names
+    pub fn from_config(config: &IndexerConfig, storage_dir: &Path) -> Result<Self, IndexerError> {
+        let mut targets = Vec::new();
+        if let Some(url) = &config.postgres_url {
+            targets.push(Target::Postgres(PostgresTarget::new(url.clone())));
+        }
+        if !config.kafka_brokers.is_empty() {
+            targets.push(Target::Kafka(KafkaTarget::new(
+                config.kafka_brokers.clone(),
+                config.kafka_topic_prefix.clone(),
+            )));
+        }
+        Ok(Self::new(
+            Outbox::open(config.outbox_dir(storage_dir))?,
+            targets,
+            config.batch_size,
+            Duration::from_millis(config.retry_ms),
+        ))
+    }
+
+    pub fn outbox(&self) -> &Outbox {
+        &self.outbox
+    }
+
+    /// Delivers records as they are appended, forever
+    pub async fn run(mut self) {
+        loop {
+            match self.deliver().await {
+                Ok(0) => {
+                    // Also wake periodically in case an append was missed
+                    let _ = tokio::time::timeout(self.retry, self.outbox.appended()).await;
+                }
+                Ok(delivered) => debug!(delivered, backlog = self.outbox.backlog(), "Indexed records"),
+                Err(e) => {
+                    if let IndexerError::Outbox(_) = e {
+                        error!(error = %e, "Indexer outbox failed");
+                    }
+                    tokio::time::sleep(self.retry).await;
+                }
+            }
+        }
+    }
+
+    /// Delivers one batch to every target, returning its size
+    pub async fn deliver(&mut self) -> Result<usize, IndexerError> {
+        let batch = self.outbox.pending(self.batch_size)?;
+        if batch.entries.is_empty() {
+            return Ok(0);
+        }
+        for target in &mut self.targets {
+            if let Err(e) = target.write(&batch.entries).await {
+                warn!(target = target.name(), error = %e, backlog = self.outbox.backlog(), "Indexer delivery failed, retrying");
+                return Err(e);
+            }
+        }
+        self.outbox.acknowledge(&batch)?;
+        Ok(batch.entries.len())
+    }
+}
@@ -0,0 +1,78 @@
+// src/indexer/kafka.rs
+
+use super::delivery::IndexerError;
+use super::outbox::OutboxEntry;
+use chrono::Utc;
+use rskafka::client::partition::{Compression, PartitionClient, UnknownTopicHandling};
+use rskafka::client::{Client, ClientBuilder};
+use rskafka::record::Record;
+use std::collections::{BTreeMap, HashMap};
+
+/// Header carrying the outbox sequence, which consumers deduplicate
+/// redelivered records by
+pub const SEQUENCE_HEADER: &str = "romer-sequence";
+
+/// Publishes records as JSON to one topic per table, `<prefix>.<table>`,
+/// keyed by the entity they describe. Each topic is written to partition 0
+/// so consumers see records in sequence order.
+pub struct KafkaTarget {
+    brokers: Vec<String>,
+    topic_prefix: String,
+    client: Option<Client>,
+    partitions: HashMap<&'static str, PartitionClient>,
+}
+
+impl KafkaTarget {
+    pub fn new(brokers: Vec<String>, topic_prefix: impl Into<String>) -> Self {
+        Self {
+            brokers,
+            topic_prefix: topic_prefix.into(),
+            client: None,
+            partitions: HashMap::new(),
+        }
+    }
+
+    pub fn topic(&self, table: &str) -> String {
+        format!("{}.{}", self.topic_prefix, table)
+    }
+
+    async fn partition(&mut self, table: &'static str) -> Result<&PartitionClient, IndexerError> {
+        if self.client.is_none() {
+            self.client = Some(ClientBuilder::new(self.brokers.clone()).build().await?);
+        }
+        if !self.partitions.contains_key(table) {
+            let topic = self.topic(table);
+            let client = self.client.as_ref().unwrap();
+            let partition = client.partition_client(topic, 0, UnknownTopicHandling::Retry).await?;
+            self.partitions.insert(table, partition);
+        }
+        Ok(&self.partitions[table])
+    }
+
+    /// Publishes `entries`, table by table
+    pub async fn write(&mut self, entries: &[OutboxEntry]) -> Result<(), IndexerError> {
+        let mut by_table: BTreeMap<&'static str, Vec<Record>> = BTreeMap::new();
+        for entry in entries {
+            let record = Record {
+                key: Some(entry.record.key().into_bytes()),
+                value: Some(serde_json::to_vec(&entry.record)?),
+                headers: BTreeMap::from([(SEQUENCE_HEADER.to_string(), entry.sequence.to_be_bytes().to_vec())]),
+                timestamp: Utc::now(),
+            };
+            by_table.entry(entry.record.table()).or_default().push(record);
+        }
+        for (table, records) in by_table {
+            let result = match self.partition(table).await {
+                Ok(partition) => partition.produce(records, Compression::NoCompression).await.map_err(IndexerError::from),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                // Reconnect from scratch on the next write
+                self.client = None;
+                self.partitions.clear();
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+}
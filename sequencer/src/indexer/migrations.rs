@@ -0,0 +1,69 @@
+// src/indexer/migrations.rs
+
+/// A schema change, applied once and recorded in `romer_schema_migrations`
+pub struct Migration {
+    pub version: i32,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+/// Every schema change, in order. Applied migrations must never be edited;
+/// changes go in a new one.
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    version: 1,
+    name: "blocks, orders, trades and balances",
+    sql: r#"
+        CREATE TABLE blocks (
+            block_id BIGINT PRIMARY KEY,
+            block_hash TEXT NOT NULL,
+            message_count BIGINT NOT NULL,
+            transaction_count BIGINT NOT NULL,
+            sealed_at TIMESTAMPTZ NOT NULL,
+            event_sequence BIGINT NOT NULL
+        );
+        CREATE TABLE orders (
+            event_sequence BIGINT PRIMARY KEY,
+            sender_comp_id TEXT NOT NULL,
+            msg_seq_num BIGINT NOT NULL,
+            status TEXT NOT NULL,
+            reason TEXT,
+            at TIMESTAMPTZ NOT NULL
+        );
+        CREATE INDEX orders_by_sender ON orders (sender_comp_id, msg_seq_num);
+        CREATE TABLE trades (
+            event_sequence BIGINT PRIMARY KEY,
+            symbol TEXT NOT NULL,
+            price NUMERIC(20) NOT NULL,
+            quantity NUMERIC(20) NOT NULL,
+            buyer TEXT NOT NULL,
+            seller TEXT NOT NULL,
+            at TIMESTAMPTZ NOT NULL
+        );
+        CREATE INDEX trades_by_symbol ON trades (symbol, at);
+        CREATE TABLE balances (
+            address TEXT PRIMARY KEY,
+            balance NUMERIC(20) NOT NULL,
+            updated_at TIMESTAMPTZ NOT NULL,
+            event_sequence BIGINT NOT NULL
+        );
+    "#,
+}];
+
+/// Migrations not in `applied`, in order
+pub fn pending(applied: &[i32]) -> impl Iterator<Item = &'static Migration> + '_ {
+    MIGRATIONS
+        .iter()
+        .filter(move |migration| !applied.contains(&migration.version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_versions_increase() {
+        assert!(MIGRATIONS.windows(2).all(|pair| pair[0].version < pair[1].version));
+        assert_eq!(pending(&[]).count(), MIGRATIONS.len());
+        assert_eq!(pending(&[1]).count(), MIGRATIONS.len() - 1);
+    }
+}
@@ -0,0 +1,6 @@
+pub mod delivery;
+pub mod kafka;
+pub mod migrations;
+pub mod model;
+pub mod outbox;
+pub mod postgres;
@@ -0,0 +1,175 @@
+// src/indexer/model.rs
+
+use crate::events::types::SequencerEvent;
+use chrono::{DateTime, Utc};
+use romer_common::types::address::Address;
+use serde::{Deserialize, Serialize};
+
+/// Outcome of an order at entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    Accepted,
+    Rejected,
+}
+
+impl OrderStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Accepted => "accepted",
+            Self::Rejected => "rejected",
+        }
+    }
+}
+
+/// A normalized row for analytics, derived from a sequencer event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "table", rename_all = "snake_case")]
+pub enum IndexRecord {
+    Block {
+        block_id: u64,
+        block_hash: String,
+        message_count: usize,
+        transaction_count: usize,
+        sealed_at: DateTime<Utc>,
+    },
+    Order {
+        sender_comp_id: String,
+        msg_seq_num: u64,
+        status: OrderStatus,
+        reason: Option<String>,
+        at: DateTime<Utc>,
+    },
+    Trade {
+        symbol: String,
+        price: u64,
+        quantity: u64,
+        buyer: String,
+        seller: String,
+        at: DateTime<Utc>,
+    },
+    Balance {
+        address: Address,
+        balance: u64,
+        at: DateTime<Utc>,
+    },
+}
+
+impl IndexRecord {
+    /// The record an event is indexed as, `None` for events analytics
+    /// does not track
+    pub fn from_event(event: &SequencerEvent) -> Option<Self> {
+        let record = match event {
+            SequencerEvent::BlockSealed {
+                block_id,
+                block_hash,
+                message_count,
+                transaction_count,
+                at,
+            } => Self::Block {
+                block_id: *block_id,
+                block_hash: block_hash.clone(),
+                message_count: *message_count,
+                transaction_count: *transaction_count,
+                sealed_at: *at,
+            },
+            SequencerEvent::OrderAccepted {
+                sender_comp_id,
+                msg_seq_num,
+                at,
+            } => Self::Order {
+                sender_comp_id: sender_comp_id.clone(),
+                msg_seq_num: *msg_seq_num,
+                status: OrderStatus::Accepted,
+                reason: None,
+                at: *at,
+            },
+            SequencerEvent::OrderRejected {
+                sender_comp_id,
+                msg_seq_num,
+                reason,
+                at,
+            } => Self::Order {
+                sender_comp_id: sender_comp_id.clone(),
+                msg_seq_num: *msg_seq_num,
+                status: OrderStatus::Rejected,
+                reason: Some(reason.clone()),
+                at: *at,
+            },
+            SequencerEvent::Match {
+                symbol,
+                price,
+                quantity,
+                buyer,
+                seller,
+                at,
+                ..
+            } => Self::Trade {
+                symbol: symbol.clone(),
+                price: *price,
+                quantity: *quantity,
+                buyer: buyer.clone(),
+                seller: seller.clone(),
+                at: *at,
+            },
+            SequencerEvent::BalanceChanged { address, balance, at } => Self::Balance {
+                address: *address,
+                balance: *balance,
+                at: *at,
+            },
+            _ => return None,
+        };
+        Some(record)
+    }
+
+    /// Table, and topic suffix, the record is written to
+    pub fn table(&self) -> &'static str {
+        match self {
+            Self::Block { .. } => "blocks",
+            Self::Order { .. } => "orders",
+            Self::Trade { .. } => "trades",
+            Self::Balance { .. } => "balances",
+        }
+    }
+
+    /// Records of the same entity share a key, so consumers partitioning by
+    /// key see them in order
+    pub fn key(&self) -> String {
+        match self {
+            Self::Block { block_id, .. } => block_id.to_string(),
+            Self::Order { sender_comp_id, .. } => sender_comp_id.clone(),
+            Self::Trade { symbol, .. } => symbol.clone(),
+            Self::Balance { address, .. } => address.to_hex(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_event() {
+        let at = Utc::now();
+        let rejected = SequencerEvent::OrderRejected {
+            sender_comp_id: "MM1".to_string(),
+            msg_seq_num: 7,
+            reason: "market closed".to_string(),
+            at,
+        };
+        let record = IndexRecord::from_event(&rejected).unwrap();
+        assert_eq!(record.table(), "orders");
+        assert_eq!(record.key(), "MM1");
+
+        let json = serde_json::to_value(&record).unwrap();
+        assert_eq!(json["table"], "order");
+        assert_eq!(json["status"], "rejected");
+        assert_eq!(serde_json::from_value::<IndexRecord>(json).unwrap(), record);
+
+        let released = SequencerEvent::DrainModeExited {
+            released_by: "ops".to_string(),
+            at,
+        };
+        assert_eq!(IndexRecord::from_event(&released), None);
+    }
+}
@@ -0,0 +1,272 @@
+// src/indexer/outbox.rs
+
+use super::model::IndexRecord;
+use crate::events::bus::EventSink;
+use crate::events::types::SequencerEvent;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Notify;
+use tracing::error;
+
+const RECORDS_FILE: &str = "records.jsonl";
+const CURSOR_FILE: &str = "cursor.json";
+
+/// An indexed record and its position in the outbox. Targets that receive
+/// a record twice tell the copies apart by the sequence.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    pub sequence: u64,
+    pub record: IndexRecord,
+}
+
+/// How far delivery has got
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+struct Cursor {
+    /// Sequence of the next record to deliver
+    next: u64,
+    /// Byte offset of that record in the records file
+    offset: u64,
+}
+
+/// Records read for delivery, acknowledged once every target has them
+#[derive(Debug)]
+pub struct OutboxBatch {
+    pub entries: Vec<OutboxEntry>,
+    /// Offset just past the last entry
+    end: u64,
+}
+
+struct Inner {
+    dir: PathBuf,
+    file: File,
+    /// Sequence the next appended record gets
+    next_sequence: u64,
+    cursor: Cursor,
+}
+
+/// Durable queue between the event bus and the indexer targets. Records
+/// are appended as JSON lines and stay until delivery is acknowledged, so
+/// they survive target outages and restarts: delivery is at least once.
+#[derive(Clone)]
+pub struct Outbox {
+    inner: Arc<Mutex<Inner>>,
+    appended: Arc<Notify>,
+}
+
+impl Outbox {
+    /// Opens the outbox in `dir`, dropping a record left half written by a
+    /// crash
+    pub fn open(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        let mut cursor: Cursor = match fs::read(dir.join(CURSOR_FILE)) {
+            Ok(raw) => serde_json::from_slice(&raw).map_err(io::Error::other)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Cursor::default(),
+            Err(e) => return Err(e),
+        };
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(dir.join(RECORDS_FILE))?;
+        let len = file.metadata()?.len();
+        // Compaction truncated the file but stopped before moving the cursor
+        if cursor.offset > len {
+            cursor.offset = len;
+        }
+
+        let mut next_sequence = cursor.next;
+        let mut valid = cursor.offset;
+        file.seek(SeekFrom::Start(cursor.offset))?;
+        let mut reader = BufReader::new(&file);
+        let mut line = String::new();
+        while reader.read_line(&mut line)? > 0 {
+            let Ok(entry) = serde_json::from_str::<OutboxEntry>(line.trim_end()) else {
+                break;
+            };
+            if !line.ends_with('\n') {
+                break;
+            }
+            next_sequence = entry.sequence + 1;
+            valid += line.len() as u64;
+            line.clear();
+        }
+        if valid < len {
+            file.set_len(valid)?;
+        }
+
+        Ok(Self {
+            inner: Arc::new(Mutex::new(Inner {
+                dir,
+                file,
+                next_sequence,
+                cursor,
+            })),
+            appended: Arc::default(),
+        })
+    }
+
+    /// Appends `record`, returning its sequence
+    pub fn append(&self, record: IndexRecord) -> io::Result<u64> {
+        let mut inner = self.inner.lock();
+        let entry = OutboxEntry {
+            sequence: inner.next_sequence,
+            record,
+        };
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        inner.file.write_all(&line)?;
+        inner.file.flush()?;
+        inner.next_sequence += 1;
+        drop(inner);
+        self.appended.notify_one();
+        Ok(entry.sequence)
+    }
+
+    /// Up to `limit` records not yet acknowledged, oldest first
+    pub fn pending(&self, limit: usize) -> io::Result<OutboxBatch> {
+        let inner = self.inner.lock();
+        let mut file = inner.file.try_clone()?;
+        file.seek(SeekFrom::Start(inner.cursor.offset))?;
+        let mut reader = BufReader::new(file);
+        let mut entries = Vec::new();
+        let mut end = inner.cursor.offset;
+        let mut line = String::new();
+        while entries.len() < limit && reader.read_line(&mut line)? > 0 {
+            entries.push(serde_json::from_str(line.trim_end())?);
+            end += line.len() as u64;
+            line.clear();
+        }
+        Ok(OutboxBatch { entries, end })
+    }
+
+    /// Marks `batch` delivered. Once everything is, the records file is
+    /// emptied.
+    pub fn acknowledge(&self, batch: &OutboxBatch) -> io::Result<()> {
+        let Some(last) = batch.entries.last() else {
+            return Ok(());
+        };
+        let mut inner = self.inner.lock();
+        inner.cursor = Cursor {
+            next: last.sequence + 1,
+            offset: batch.end,
+        };
+        inner.write_cursor()?;
+        if inner.cursor.next == inner.next_sequence {
+            inner.file.set_len(0)?;
+            inner.cursor.offset = 0;
+            inner.write_cursor()?;
+        }
+        Ok(())
+    }
+
+    /// Records appended but not yet acknowledged
+    pub fn backlog(&self) -> u64 {
+        let inner = self.inner.lock();
+        inner.next_sequence - inner.cursor.next
+    }
+
+    /// Waits for the next append
+    pub async fn appended(&self) {
+        self.appended.notified().await
+    }
+}
+
+impl Inner {
+    /// Replaces the cursor file in one step, so a crash leaves the old
+    /// cursor or the new one
+    fn write_cursor(&self) -> io::Result<()> {
+        let path = self.dir.join(CURSOR_FILE);
+        let temp = path.with_extension("json.tmp");
+        let mut file = File::create(&temp)?;
+        file.write_all(&serde_json::to_vec(&self.cursor)?)?;
+        file.sync_all()?;
+        fs::rename(temp, path)
+    }
+}
+
+impl EventSink for Outbox {
+    fn name(&self) -> &str {
+        "indexer_outbox"
+    }
+
+    fn handle(&mut self, event: &SequencerEvent) {
+        if let Some(record) = IndexRecord::from_event(event) {
+            if let Err(e) = self.append(record) {
+                error!(error = %e, "Failed to append to the indexer outbox");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    fn record(block_id: u64) -> IndexRecord {
+        IndexRecord::Block {
+            block_id,
+            block_hash: format!("{:064x}", block_id),
+            message_count: 1,
+            transaction_count: 0,
+            sealed_at: Utc::now(),
+        }
+    }
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("romer-outbox-{}", Uuid::new_v4()))
+    }
+
+    #[test]
+    fn test_redelivers_until_acknowledged() {
+        let dir = temp_dir();
+        let outbox = Outbox::open(&dir).unwrap();
+        for block_id in 0..5 {
+            assert_eq!(outbox.append(record(block_id)).unwrap(), block_id);
+        }
+
+        let batch = outbox.pending(3).unwrap();
+        assert_eq!(batch.entries.len(), 3);
+        // Not acknowledged, so a restart delivers the same records again
+        drop(outbox);
+        let outbox = Outbox::open(&dir).unwrap();
+        assert_eq!(outbox.pending(3).unwrap().entries, batch.entries);
+
+        outbox.acknowledge(&batch).unwrap();
+        assert_eq!(outbox.backlog(), 2);
+        let rest = outbox.pending(10).unwrap();
+        assert_eq!(rest.entries[0].sequence, 3);
+        outbox.acknowledge(&rest).unwrap();
+        assert_eq!(outbox.backlog(), 0);
+        assert_eq!(fs::metadata(dir.join(RECORDS_FILE)).unwrap().len(), 0);
+
+        // Sequences carry on after compaction and restarts
+        drop(outbox);
+        let outbox = Outbox::open(&dir).unwrap();
+        assert_eq!(outbox.append(record(5)).unwrap(), 5);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_drops_torn_record() {
+        let dir = temp_dir();
+        let outbox = Outbox::open(&dir).unwrap();
+        outbox.append(record(0)).unwrap();
+        drop(outbox);
+        let mut file = OpenOptions::new().append(true).open(dir.join(RECORDS_FILE)).unwrap();
+        file.write_all(b"{\"sequence\":1,\"rec").unwrap();
+
+        let outbox = Outbox::open(&dir).unwrap();
+        assert_eq!(outbox.pending(10).unwrap().entries.len(), 1);
+        assert_eq!(outbox.append(record(1)).unwrap(), 1);
+        assert_eq!(outbox.pending(10).unwrap().entries.len(), 2);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
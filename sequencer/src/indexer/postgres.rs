@@ -0,0 +1,161 @@
+// src/indexer/postgres.rs
+
+use super::delivery::IndexerError;
+use super::migrations;
+use super::model::IndexRecord;
+use super::outbox::OutboxEntry;
+use tokio_postgres::{Client, NoTls};
+use tracing::{error, info};
+
+/// Writes records to Postgres, applying schema migrations on connect.
+/// Every write is idempotent, so redelivered records change nothing.
+pub struct PostgresTarget {
+    url: String,
+    /// Open connection, reopened on the next write after a failure
+    client: Option<Client>,
+}
+
+impl PostgresTarget {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            client: None,
+        }
+    }
+
+    async fn connect(&self) -> Result<Client, IndexerError> {
+        let (mut client, connection) = tokio_postgres::connect(&self.url, NoTls).await?;
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                error!(error = %e, "Indexer Postgres connection failed");
+            }
+        });
+        migrate(&mut client).await?;
+        Ok(client)
+    }
+
+    /// Writes `entries` in one transaction
+    pub async fn write(&mut self, entries: &[OutboxEntry]) -> Result<(), IndexerError> {
+        if self.client.is_none() {
+            self.client = Some(self.connect().await?);
+        }
+        let result = write(self.client.as_mut().unwrap(), entries).await;
+        if result.is_err() {
+            self.client = None;
+        }
+        result
+    }
+}
+
+/// Applies the migrations the database has not recorded yet
+async fn migrate(client: &mut Client) -> Result<(), IndexerError> {
+    client
+        .batch_execute(
+            "CREATE TABLE IF NOT EXISTS romer_schema_migrations (
+                version INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                applied_at TIMESTAMPTZ NOT NULL DEFAULT now()
+            )",
+        )
+        .await?;
+    let applied: Vec<i32> = client
+        .query("SELECT version FROM romer_schema_migrations", &[])
+        .await?
+        .iter()
+        .map(|row| row.get(0))
+        .collect();
+    for migration in migrations::pending(&applied) {
+        let transaction = client.transaction().await?;
+        transaction.batch_execute(migration.sql).await?;
+        transaction
+            .execute(
+                "INSERT INTO romer_schema_migrations (version, name) VALUES ($1, $2)",
+                &[&migration.version, &migration.name],
+            )
+            .await?;
+        transaction.commit().await?;
+        info!(version = migration.version, name = migration.name, "Applied indexer migration");
+    }
+    Ok(())
+}
+
+async fn write(client: &mut Client, entries: &[OutboxEntry]) -> Result<(), IndexerError> {
+    let transaction = client.transaction().await?;
+    for entry in entries {
+        let sequence = entry.sequence as i64;
+        match &entry.record {
+            IndexRecord::Block {
+                block_id,
+                block_hash,
+                message_count,
+                transaction_count,
+                sealed_at,
+            } => {
+                transaction
+                    .execute(
+                        "INSERT INTO blocks (block_id, block_hash, message_count, transaction_count, sealed_at, event_sequence)
+                         VALUES ($1, $2, $3, $4, $5, $6) ON CONFLICT (block_id) DO NOTHING",
+                        &[
+                            &(*block_id as i64),
+                            block_hash,
+                            &(*message_count as i64),
+                            &(*transaction_count as i64),
+                            sealed_at,
+                            &sequence,
+                        ],
+                    )
+                    .await?;
+            }
+            IndexRecord::Order {
+                sender_comp_id,
+                msg_seq_num,
+                status,
+                reason,
+                at,
+            } => {
+                transaction
+                    .execute(
+                        "INSERT INTO orders (event_sequence, sender_comp_id, msg_seq_num, status, reason, at)
+                         VALUES ($1, $2, $3, $4, $5, $6) ON CONFLICT (event_sequence) DO NOTHING",
+                        &[&sequence, sender_comp_id, &(*msg_seq_num as i64), &status.as_str(), reason, at],
+                    )
+                    .await?;
+            }
+            IndexRecord::Trade {
+                symbol,
+                price,
+                quantity,
+                buyer,
+                seller,
+                at,
+            } => {
+                // Unsigned 64 bit values are passed as text to fit NUMERIC
+                transaction
+                    .execute(
+                        "INSERT INTO trades (event_sequence, symbol, price, quantity, buyer, seller, at)
+                         VALUES ($1, $2, $3::TEXT::NUMERIC, $4::TEXT::NUMERIC, $5, $6, $7)
+                         ON CONFLICT (event_sequence) DO NOTHING",
+                        &[&sequence, symbol, &price.to_string(), &quantity.to_string(), buyer, seller, at],
+                    )
+                    .await?;
+            }
+            IndexRecord::Balance { address, balance, at } => {
+                // A redelivered older balance never overwrites a newer one
+                transaction
+                    .execute(
+                        "INSERT INTO balances (address, balance, updated_at, event_sequence)
+                         VALUES ($1, $2::TEXT::NUMERIC, $3, $4)
+                         ON CONFLICT (address) DO UPDATE SET
+                            balance = EXCLUDED.balance,
+                            updated_at = EXCLUDED.updated_at,
+                            event_sequence = EXCLUDED.event_sequence
+                         WHERE balances.event_sequence < EXCLUDED.event_sequence",
+                        &[&address.to_hex(), &balance.to_string(), at, &sequence],
+                    )
+                    .await?;
+            }
+        }
+    }
+    transaction.commit().await?;
+    Ok(())
+}
@@ -1,89 +1,1438 @@
+mod attestation;
+mod audit;
+mod block;
+mod bridge;
+mod cli;
+mod config;
+mod events;
+mod faucet;
+mod fix;
+mod gateway;
+mod governance;
+mod indexer;
+mod market;
+mod mempool;
+mod network;
+mod risk;
+mod rpc;
+mod session;
+mod settlement;
+
 use tokio::net::TcpListener;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tracing::{error, info};
-use romer_common::types::fix::MessageType;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+use romer_common::fix::admin::AdminMessage;
+use romer_common::fix::allocation::{AllocStatus, AllocationAck, AllocationInstruction};
+use romer_common::fix::oracle::{is_price_submission, price_submission};
+use romer_common::fix::session_logon::{authenticate_logon, LogonAuthError, LogonFactors};
+use std::collections::HashMap;
+use std::sync::Arc;
+use mempool::pool::Mempool;
+use parking_lot::Mutex;
+use attestation::registry::AttestationRegistry;
+use audit::export::AuditExporter;
+use audit::reconciliation::ReconciliationService;
+use audit::snapshots::StateSnapshotter;
+use block::batch::BatchManager;
+use block::builder::BlockBuilder;
+use block::clock_quality::{ClockMetrics, ClockMonitor};
+use block::producer::BlockProducer;
+use bridge::evm::EvmLockAdapter;
+use bridge::relay::BridgeRelay;
+use clap::Parser;
+use cli::{Cli, Command, StateCommand};
+use config::{SequencerConfig, SettlementAdapterKind};
+use events::bus::{EventBus, EventSink};
+use events::stats::StatsCollector;
+use events::subscribers::{AuditLog, EventCounters};
+use events::types::SequencerEvent;
+use faucet::service::Faucet;
+use gateway::binary::BinaryGateway;
+use gateway::speed_bump::SpeedBump;
+use governance::service::{GovernanceService, ProposalStatus};
+use indexer::delivery::Indexer;
+use fix::reports::News;
+use fix::types::{MessageType, ValidatedMessage};
+use market::candles::{CandleAggregator, CandleStore};
+use market::orders::OrderStore;
+use network::manager::NetworkManager;
+use network::types::{NetworkConfig, NetworkEvent};
+use gateway::news::NewsService;
+use market::data::MarketDataPublisher;
+use market::fees::FeeEngine;
+use market::instruments::InstrumentRegistry;
+use market::feeds::{RpcPriceFeed, StreamPriceFeed};
+use market::obligations::ObligationMonitor;
+use market::oracle::OracleAggregator;
+use market::reference_price::{ManualFeed, ReferencePriceService};
+use market::registry::MarketRegistry;
+use risk::drain::DrainMode;
+use risk::load_shed::LoadShedder;
+use risk::kill_switch::KillSwitch;
+use risk::order_checks::OrderChecks;
+use risk::permissions::PermissionRegistry;
+use risk::sub_accounts::SubAccountTracker;
+use risk::plugins::PluginHost;
+use prometheus_client::registry::Registry;
+use romer_common::storage::archive::Archiver;
+use romer_common::storage::compression::{measure, train_dictionary, Compressor};
+use romer_common::storage::mmap::MmapSection;
+use romer_common::storage::journal::{Partition, RomerJournal, Section, StorageConfig};
+use romer_common::storage::metrics::{CapacityMonitor, StorageMetrics};
+use romer_common::utils::metrics::serve as serve_metrics;
+use romer_common::types::genesis::GenesisBundle;
+use romer_common::types::snapshot::{StateDiff, StateSnapshot};
+use romer_common::types::governance::{ParameterChange, Parameters};
+use romer_common::types::instrument::InstrumentParameters;
+use romer_common::types::org::Organization;
+use romer_common::types::protocol::SUPPORTED_PROTOCOL_VERSION;
+use rpc::admin::AdminAccess;
+use rpc::api_keys::ApiKeyRegistry;
+use rpc::handler::{RpcHandler, RpcState};
+use settlement::adapter::SettlementAdapter;
+use settlement::allocations::AllocationService;
+use settlement::bank::BankStub;
+use settlement::evm::EvmAdapter;
+use settlement::service::SettlementService;
+use session::manager::{OutboundMessage, SessionManager};
+use session::state::{SequenceNegotiation, SessionError};
+use commonware_utils::hex;
+use serde_json::Value;
+use std::path::Path;
+use std::time::Duration;
+use romer_common::utils::clock::system_clock;
+use romer_common::utils::logging::{self, LogHandle};
+use rpc::server::{load_tls, RpcConfig, RpcServer};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Your existing setup code remains the same
-    tracing_subscriber::fmt()
-        .with_target(false)
-        .with_thread_ids(true)
-        .with_level(true)
-        .init();
-
-    let host = std::env::var("SEQUENCER_HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
-    let port = std::env::var("SEQUENCER_PORT")
-        .ok()
-        .and_then(|p| p.parse().ok())
-        .unwrap_or(9878);
-
-    let addr = format!("{}:{}", host, port);
-    let listener = TcpListener::bind(&addr).await?;
-    info!("Server listening on {}", addr);
+    let cli = Cli::parse();
+    // Settings come from the defaults, the configuration file under its
+    // selected profile, then the environment
+    let config = SequencerConfig::resolve(cli.config, cli.profile)?;
+    // The level can be changed at runtime through the admin API
+    let logging = Arc::new(logging::init(&config.logging)?);
 
-    loop {
-        match listener.accept().await {
-            Ok((mut socket, addr)) => {
-                info!("Accepted connection from: {}", addr);
-                
-                // Enable TCP_NODELAY for better latency
-                if let Err(e) = socket.set_nodelay(true) {
-                    error!("Failed to set TCP_NODELAY: {}", e);
-                }
-
-                // Create a buffer for reading the incoming message
-                let mut buffer = [0u8; 4096];
-
-                // Read from the socket into our buffer
-                match socket.read(&mut buffer).await {
-                    Ok(n) if n > 0 => {
-                        // Convert the received bytes to a string
-                        if let Ok(message) = String::from_utf8(buffer[..n].to_vec()) {
-                            // Look for the message type tag (35=X)
-                            if let Some(msg_type) = extract_message_type(&message) {
-                                // Generate appropriate response based on message type
-                                let response = match MessageType::from_fix(msg_type) {
-                                    Some(MessageType::Logon) | Some(MessageType::Logout) => {
-                                        "Session Functionality coming soon\n"
-                                    }
-                                    Some(MessageType::NewOrderSingle) |
-                                    Some(MessageType::MarketDataRequest) |
-                                    Some(MessageType::MarketDataSnapshot) => {
-                                        "Once we have sessions up and running we'll implement this\n"
-                                    }
-                                    Some(MessageType::Heartbeat) => {
-                                        "Heartbeat received\n"
-                                    }
-                                    None => "Unsupported message type\n"
-                                };
-
-                                // Send the response back to the client
-                                if let Err(e) = socket.write_all(response.as_bytes()).await {
-                                    error!("Failed to send response: {}", e);
+    match cli.command.unwrap_or(Command::Run) {
+        Command::Run => run(config, logging).await,
+        Command::CheckConfig => {
+            print!("{}", toml::to_string_pretty(&config)?);
+            Ok(())
+        }
+        Command::Replay { journal } => replay(&journal),
+        Command::ExportAudit { date, log, out_dir } => {
+            let Some(log) = log.or(config.storage.audit_log) else {
+                return Err("no event log given and none configured".into());
+            };
+            match AuditExporter::new(Default::default()).export_day(&log, date, &out_dir)? {
+                Some(path) => info!("Wrote {}", path.display()),
+                None => warn!("No events on {} in {}", date, log.display()),
+            }
+            Ok(())
+        }
+        Command::Status { rpc } => {
+            let address = rpc.unwrap_or_else(|| format!("{}:{}", config.network.host, config.network.rpc_port));
+            let stats = romer_common::utils::rpc::call(&address, "admin_stats", Value::Null).await?;
+            println!("{}", serde_json::to_string_pretty(&stats)?);
+            Ok(())
+        }
+        Command::LogLevel { directive, reset, rpc } => {
+            let address = rpc.unwrap_or_else(|| format!("{}:{}", config.network.host, config.network.rpc_port));
+            let (method, params) = match directive {
+                Some(directive) => ("admin_set_log_directive", serde_json::json!({ "directive": directive })),
+                None if reset => ("admin_reset_log_level", Value::Null),
+                None => ("admin_log_level", Value::Null),
+            };
+            let result = romer_common::utils::rpc::call(&address, method, params).await?;
+            println!("{}", result["level"].as_str().unwrap_or_default());
+            Ok(())
+        }
+        Command::ExportGenesis { out, rpc } => {
+            let address = rpc.unwrap_or_else(|| format!("{}:{}", config.network.host, config.network.rpc_port));
+            let result = romer_common::utils::rpc::call(&address, "admin_export_genesis", Value::Null).await?;
+            let bundle: GenesisBundle = serde_json::from_value(result)?;
+            bundle.verify()?;
+            bundle.write(&out)?;
+            info!("Wrote genesis at height {} ({}) to {}", bundle.height, bundle.digest, out.display());
+            Ok(())
+        }
+        Command::Archive { partitions } => {
+            let archiver = Archiver::new(config.storage.journal()?, config.archive.archive())?;
+            for partition in &partitions {
+                let archived = archiver.archive_partition(partition, true).await?;
+                info!("Archived {} sections of {}", archived.len(), partition);
+            }
+            Ok(())
+        }
+        Command::Compression { partitions, levels, train, dictionary_size } => {
+            compression_report(&config.storage.journal()?, &partitions, &levels, train.as_deref(), dictionary_size)
+        }
+        Command::Rehydrate { partition, first, last, out_dir } => {
+            let archiver = Archiver::new(config.storage.journal()?, config.archive.archive())?;
+            for path in archiver.rehydrate(&partition, first..=last, &out_dir).await? {
+                info!("Restored {}", path.display());
+            }
+            Ok(())
+        }
+        Command::State {
+            command: StateCommand::Diff { from, to, dir, json },
+        } => {
+            let dir = dir.unwrap_or_else(|| config.state_snapshots.dir(&config.storage.directory));
+            let diff = StateDiff::between(&StateSnapshot::read(&dir, from)?, &StateSnapshot::read(&dir, to)?);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&diff)?);
+            } else {
+                print!("{}", diff);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Reads the entries of `partitions`, optionally trains a dictionary on
+/// them, and prints the size and CPU cost of compressing them one by one at
+/// each of `levels`, with and without it
+fn compression_report(
+    storage: &StorageConfig,
+    partitions: &[String],
+    levels: &[i32],
+    train: Option<&Path>,
+    dictionary_size: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let current = storage.compressor()?;
+    let mut samples = Vec::new();
+    for partition in partitions {
+        let dir = storage.storage_directory.join(partition);
+        let mut blobs: Vec<_> = std::fs::read_dir(&dir)?.collect::<Result<Vec<_>, _>>()?;
+        blobs.sort_by_key(|blob| blob.file_name());
+        for blob in blobs {
+            let section = MmapSection::open(blob.path())?;
+            for item in section.items() {
+                let (_, payload) = item?;
+                samples.push(current.decompress(payload)?);
+            }
+        }
+    }
+    if samples.is_empty() {
+        return Err("no journal entries to measure".into());
+    }
+    let bytes: usize = samples.iter().map(Vec::len).sum();
+    println!("{} entries, {} bytes, {} bytes on average", samples.len(), bytes, bytes / samples.len());
+
+    // Train on every other entry and measure on the rest, so the
+    // dictionary is not judged on the entries it was built from
+    let (training, measured): (Vec<_>, Vec<_>) = samples.into_iter().enumerate().partition(|(i, _)| i % 2 == 0);
+    let training: Vec<_> = training.into_iter().map(|(_, sample)| sample).collect();
+    let measured: Vec<_> = measured.into_iter().map(|(_, sample)| sample).collect();
+    let dictionary = match train_dictionary(&training, dictionary_size) {
+        Ok(dictionary) => Some(dictionary),
+        Err(e) => {
+            warn!("{}", e);
+            None
+        }
+    };
+
+    println!("{:>5} {:>10} {:>8} {:>12} {:>14}", "level", "dictionary", "ratio", "compress MB/s", "decompress MB/s");
+    for &level in levels {
+        let plain = Compressor::new(level)?;
+        let mut compressors = vec![plain.clone()];
+        if let Some(dictionary) = &dictionary {
+            compressors.push(plain.with_dictionary(dictionary.clone()));
+        }
+        for compressor in compressors {
+            let trial = measure(&compressor, &measured)?;
+            println!(
+                "{:>5} {:>10} {:>8.2} {:>12.1} {:>14.1}",
+                trial.level,
+                if trial.dictionary { "yes" } else { "no" },
+                trial.ratio(),
+                trial.compress_mb_per_sec(),
+                trial.decompress_mb_per_sec()
+            );
+        }
+    }
+
+    if let (Some(path), Some(dictionary)) = (train, &dictionary) {
+        std::fs::write(path, dictionary)?;
+        info!("Wrote a {} byte dictionary to {}", dictionary.len(), path.display());
+    }
+    Ok(())
+}
+
+/// Reads an event journal written by the audit log back through the event
+/// counters and statistics, and prints what it contained
+fn replay(journal: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let events = AuditExporter::read_log(journal)?;
+    let mut counters = EventCounters::default();
+    let mut stats = StatsCollector::new(system_clock());
+    for event in &events {
+        counters.handle(event);
+        stats.handle(event);
+    }
+    let stats = stats.get_stats();
+    println!("{} events", events.len());
+    if let (Some(first), Some(last)) = (events.first(), events.last()) {
+        println!("from {} to {}", first.at(), last.at());
+    }
+    for (kind, count) in counters.snapshot() {
+        println!("  {:<28} {}", kind, count);
+    }
+    println!(
+        "blocks sealed {}, last block {:?}, sessions left open {}",
+        stats.blocks_built, stats.last_block_id, stats.active_sessions
+    );
+    Ok(())
+}
+
+/// Runs the sequencer until it is stopped
+async fn run(config: SequencerConfig, logging: Arc<LogHandle>) -> Result<(), Box<dyn std::error::Error>> {
+    info!(
+        host = %config.network.host,
+        fix_port = config.network.fix_port,
+        block_window = ?config.block.window(),
+        storage = %config.storage.directory.display(),
+        "Loaded sequencer configuration"
+    );
+    let host = config.network.host.clone();
+    let port = config.network.fix_port;
+
+    // JSON-RPC endpoint for clients that don't speak FIX
+    let rpc_port = config.network.rpc_port;
+    let rpc_config = RpcConfig {
+        bind_address: format!("{}:{}", host, rpc_port).parse()?,
+        ..RpcConfig::default()
+    };
+    let (submission_tx, mut submission_rx) = mpsc::channel(1024);
+    let clock = system_clock();
+
+    // Pipeline events fan out to every subscriber through the bus
+    let events = EventBus::default();
+    // Committed nonces survive restarts, so executed transactions can't be
+    // submitted again
+    std::fs::create_dir_all(&config.storage.directory)?;
+    let rpc_state = Arc::new(
+        RpcState::new()
+            .with_events(events.clone())
+            .with_nonce_journal(config.storage.directory.join("nonces.jsonl"))?,
+    );
+    // Staging networks can start out as a copy of another network's state
+    let genesis = match &config.storage.genesis {
+        Some(path) => {
+            let bundle = GenesisBundle::read(path)?;
+            rpc_state.apply_genesis(&bundle);
+            info!(
+                path = %path.display(),
+                height = bundle.height,
+                from = %bundle.environment,
+                digest = %bundle.digest,
+                "Booting from genesis bundle"
+            );
+            Some(bundle)
+        }
+        None => None,
+    };
+    let event_counters = EventCounters::default();
+    events.attach(event_counters.clone());
+    let mut audit_progress = None;
+    if let Some(path) = &config.storage.audit_log {
+        match AuditLog::open(path) {
+            Ok(log) => {
+                audit_progress = Some(log.progress());
+                events.attach(log);
+            }
+            Err(e) => error!("Failed to open audit log {}: {}", path.display(), e),
+        }
+    }
+
+    // Blocks, orders, trades and balances are indexed for analytics. Records
+    // wait in an outbox on disk until every target has them, so a target
+    // outage or a restart delays them instead of losing them.
+    if config.indexer.enabled() {
+        match Indexer::from_config(&config.indexer, &config.storage.directory) {
+            Ok(indexer) => {
+                events.attach(indexer.outbox().clone());
+                tokio::spawn(indexer.run());
+            }
+            Err(e) => error!("Failed to start the indexer: {}", e),
+        }
+    }
+
+    // Accepted direct transactions wait in the mempool until the block
+    // builder picks them up alongside FIX messages
+    let mempool = Arc::new(Mutex::new(Mempool::new(config.block.mempool())));
+
+    // Throughput, sessions, depths and journal lag, served on the admin
+    // RPC and logged periodically
+    let mut stats = StatsCollector::new(clock.clone()).with_mempool(mempool.clone());
+    if let Some(written) = audit_progress {
+        stats = stats.with_journal(events.clone(), written);
+    }
+    events.attach(stats.clone());
+    {
+        let stats = stats.clone();
+        let period = config.stats.log_interval();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(period);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let s = stats.get_stats();
+                info!(
+                    messages_per_sec = s.messages_per_sec,
+                    messages_received = s.messages_received,
+                    blocks_built = s.blocks_built,
+                    active_sessions = s.active_sessions,
+                    book_depth = s.book_depth,
+                    mempool_depth = s.mempool_depth,
+                    journal_lag = ?s.journal_lag,
+                    "Sequencer stats"
+                );
+            }
+        });
+    }
+    // Firms are blocked through the admin RPC until released
+    let kill_switch = Arc::new(KillSwitch::with_clock(events.clone(), clock.clone()));
+    // Maintenance drains order entry through the admin RPC while sessions,
+    // cancels and block production carry on
+    let drain = Arc::new(DrainMode::with_clock(events.clone(), clock.clone()));
+    // Past its latency budget the pipeline sheds new orders, still taking
+    // cancels, until it has caught up
+    let load_shedding = Arc::new(LoadShedder::new(config.load_shedding.clone(), events.clone(), clock.clone()));
+
+    // Symbol permissions come from the organization registry, and changes
+    // made over the admin RPC are journaled back to it
+    let organizations = Organization::load_all().await.unwrap_or_else(|e| {
+        error!("Failed to load organizations: {}", e);
+        Vec::new()
+    });
+    let (org_update_tx, mut org_update_rx) = mpsc::unbounded_channel::<Organization>();
+    tokio::spawn(async move {
+        while let Some(organization) = org_update_rx.recv().await {
+            if let Err(e) = organization.write_update_to_journal().await {
+                error!("Failed to journal organization {}: {}", organization.id, e);
+            }
+        }
+    });
+    // Organizations registered here replace those of the genesis bundle
+    let organizations = genesis
+        .map(|bundle| bundle.organizations)
+        .unwrap_or_default()
+        .into_iter()
+        .chain(organizations);
+    let permissions = Arc::new(PermissionRegistry::from_organizations(organizations).with_updates(org_update_tx));
+    // Organizations with sub-accounts have each order's Account (1) checked
+    // against that desk's limits and the positions its fills built up
+    let sub_accounts = Arc::new(SubAccountTracker::new());
+    events.attach(sub_accounts.clone());
+
+    // ClOrdIDs used today survive restarts so reused IDs stay rejected
+    let storage_config = config.storage.journal()?;
+
+    // Storage metrics are scraped alongside everything else in the registry,
+    // and order acceptance pauses when the disk is nearly full
+    let registry = Arc::new(std::sync::Mutex::new(Registry::default()));
+    let storage_metrics = StorageMetrics::new(&registry);
+    let capacity_monitor = CapacityMonitor::new(
+        storage_config.clone(),
+        config.storage.thresholds(),
+        storage_metrics.clone(),
+    );
+    let capacity = capacity_monitor.gate();
+    tokio::spawn(capacity_monitor.run(Duration::from_secs(10)));
+    let metrics_port = config.network.metrics_port;
+    tokio::spawn(serve_metrics(format!("{}:{}", host, metrics_port).parse()?, registry.clone()));
+
+    // The clock stamping blocks is measured against NTP and PTP sources;
+    // blocks are refused while it drifts beyond the bound
+    let clock_monitor = config.clock.enabled().then(|| {
+        let monitor = Arc::new(
+            ClockMonitor::new(
+                config.clock.sources.clone(),
+                config.clock.max_drift(),
+                config.clock.max_age(),
+                clock.clone(),
+            )
+            .with_metrics(ClockMetrics::new(&registry)),
+        );
+        tokio::spawn(monitor.clone().run(config.clock.poll_interval()));
+        monitor
+    });
+
+    // Each market, addressed by TargetCompID, keeps its own instruments,
+    // sessions and journal partitions
+    let market_configs = config.markets();
+    let listed_symbols: Vec<String> = market_configs
+        .iter()
+        .flat_map(|market| market.symbols.iter().cloned())
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let markets = Arc::new(MarketRegistry::open(market_configs, &storage_config, &storage_metrics, clock.clone()).await?);
+    info!("Hosting markets {:?}", markets.ids());
+
+    // Designated market makers' quoting is measured per epoch; the makers
+    // meeting their obligations are announced for rewards and rebates
+    let obligations = Arc::new(ObligationMonitor::with_clock(events.clone(), clock.clone()));
+    {
+        let obligations = obligations.clone();
+        let epoch = config.obligations.epoch();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(epoch);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                obligations.close_epoch();
+            }
+        });
+    }
+
+    // Fills are charged at each firm's fee tier, and tiers are reassigned by
+    // volume each epoch; firms hear of a change as News on their next message
+    let fees = if config.fees.enabled() {
+        let fees = Arc::new(FeeEngine::new(
+            config.fees.tiers.clone(),
+            config.reconciliation.firms.clone(),
+            events.clone(),
+            clock.clone(),
+        )?);
+        events.attach(fees.clone());
+        let closing = fees.clone();
+        let epoch = config.fees.epoch();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(epoch);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                closing.close_epoch();
+            }
+        });
+        Some(fees)
+    } else {
+        None
+    };
+
+    // Reference prices for collars, circuit breakers and margining come from
+    // the first feed with a fresh price: a pushed stream, a polled RPC
+    // endpoint, then prices entered by an administrator
+    let prices = &config.reference_prices;
+    let mut reference_prices = ReferencePriceService::with_clock(prices.max_age(), clock.clone());
+    if let Some(address) = &prices.stream {
+        let feed = Arc::new(StreamPriceFeed::new("stream", address, clock.clone()));
+        feed.spawn();
+        reference_prices = reference_prices.with_feed(feed);
+    }
+    if let Some(address) = &prices.rpc {
+        let feed = Arc::new(RpcPriceFeed::new("rpc", address, &prices.rpc_method, listed_symbols, clock.clone()));
+        feed.spawn(prices.poll_interval());
+        reference_prices = reference_prices.with_feed(feed);
+    }
+    let manual_prices = Arc::new(ManualFeed::new(prices.manual_max_age()));
+    let reference_prices = Arc::new(reference_prices.with_feed(manual_prices.clone()));
+    // Orders priced further than this from the reference price are rejected
+    let price_collar_bps = prices.collar_bps;
+
+    // Market data consumers that fall behind get the latest state of each
+    // price level instead of every update
+    let market_data = Arc::new(MarketDataPublisher::new(config.market_data.queue_limit));
+
+    // Fills are aggregated into OHLCV candles and daily statistics for the
+    // explorer. They are journaled so the history survives restarts.
+    let candles = Arc::new(CandleStore::default());
+    let aggregator = match RomerJournal::with_config(Partition::MARKET_DATA, Section::CANDLES, storage_config.clone()).await {
+        Ok(journal) => CandleAggregator::open(candles.clone(), journal.with_metrics(storage_metrics.clone())).await,
+        Err(e) => Err(e),
+    };
+    match aggregator {
+        Ok(aggregator) => {
+            events.attach(aggregator);
+        }
+        Err(e) => {
+            error!("Failed to open the candle journal, candles will not survive restarts: {}", e);
+            events.attach(CandleAggregator::in_memory(candles.clone()));
+        }
+    }
+
+    // Periodic snapshots of the state, for `state diff`
+    if config.state_snapshots.enabled() {
+        let directory = config.state_snapshots.dir(&config.storage.directory);
+        info!("Writing state snapshots every {} blocks to {}", config.state_snapshots.interval_blocks, directory.display());
+        events.attach(
+            StateSnapshotter::new(
+                &config.state_snapshots,
+                directory,
+                rpc_state.clone(),
+                market_data.clone(),
+                config.environment,
+            )
+            .with_permissions(permissions.clone()),
+        );
+    }
+
+    // Every order's lifecycle state, for OrderStatusRequests and the
+    // explorer. Journaled so statuses survive restarts.
+    let orders = match RomerJournal::with_config(Partition::TRADING, Section::ORDERS, storage_config.clone()).await {
+        Ok(journal) => OrderStore::open(journal.with_metrics(storage_metrics.clone()), clock.clone())
+            .await
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e),
+    };
+    let orders = Arc::new(orders.unwrap_or_else(|e| {
+        error!("Failed to open the order journal, order states will not survive restarts: {}", e);
+        OrderStore::in_memory(clock.clone())
+    }));
+
+    // Operators broadcast maintenance and incident notices, delivered as
+    // News and mirrored on the event bus for gateways
+    let news = match RomerJournal::with_config(Partition::SESSION, Section::NEWS, storage_config.clone()).await {
+        Ok(journal) => NewsService::open(journal.with_metrics(storage_metrics.clone()), events.clone(), clock.clone())
+            .await
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e),
+    };
+    let news = Arc::new(news.unwrap_or_else(|e| {
+        error!("Failed to open the news journal, notices will not survive restarts: {}", e);
+        NewsService::in_memory(events.clone(), clock.clone())
+    }));
+
+    // Public reads are rate limited per API key; keys are created and
+    // revoked over the admin RPC
+    let api_keys = match RomerJournal::with_config(Partition::SYSTEM, Section::API_KEYS, storage_config.clone()).await {
+        Ok(journal) => ApiKeyRegistry::open(journal.with_metrics(storage_metrics.clone()), config.api_keys.clone(), clock.clone())
+            .await
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e),
+    };
+    let api_keys = Arc::new(api_keys.unwrap_or_else(|e| {
+        error!("Failed to open the API key journal, keys will not survive restarts: {}", e);
+        ApiKeyRegistry::in_memory(config.api_keys.clone(), clock.clone())
+    }));
+
+    // Validators deliver signed location and hardware attestations for
+    // counterparties to query
+    let attestations = Arc::new(AttestationRegistry::with_clock(
+        config.attestation.validators.iter().copied(),
+        clock.clone(),
+    ));
+
+    // Each firm's orders, fills, fees, positions and settlement obligations
+    // are reported from the event log at session close
+    let reconciliation = match (config.reconciliation.session_close, &config.storage.audit_log) {
+        (Some(session_close), Some(log)) => {
+            let service = ReconciliationService::from_config(&config.reconciliation, log, &config.storage.directory);
+            let service = Arc::new(match config.calendar.settlement_calendar() {
+                Some(calendar) => service.with_calendar(calendar.clone()),
+                None => service,
+            });
+            tokio::spawn(service.clone().run(session_close, clock.clone()));
+            Some(service)
+        }
+        _ => None,
+    };
+
+    // Reconciled amounts are pushed to an external system on `admin_settle`
+    let settlement = match config.settlement.adapter {
+        Some(kind) => {
+            let settings = &config.settlement;
+            let adapter: Box<dyn SettlementAdapter> = match kind {
+                SettlementAdapterKind::Evm => Box::new(EvmAdapter::new(
+                    settings.evm_rpc.clone().unwrap_or_default(),
+                    settings.evm_treasury.clone().unwrap_or_default(),
+                    settings.evm_token.clone(),
+                )),
+                SettlementAdapterKind::Bank => Box::new(BankStub::default()),
+            };
+            let service = SettlementService::new(adapter, settings.accounts.clone(), &settings.asset, clock.clone())
+                .with_receipts(config.storage.directory.join("settlement-receipts.jsonl"))?;
+            Some(Arc::new(service))
+        }
+        None => None,
+    };
+
+    // Whitelisted feeders submit signed prices over FIX and JSON-RPC; each
+    // block writes their aggregate into the `romer::oracle` module
+    let oracle = config.oracle.enabled().then(|| {
+        let feeders = config
+            .oracle
+            .feeders
+            .iter()
+            .map(|(name, key)| (key.clone(), name.clone()))
+            .collect();
+        Arc::new(OracleAggregator::new(
+            feeders,
+            config.oracle.max_age(),
+            config.oracle.min_feeders,
+            config.oracle.max_deviation_bps,
+            clock.clone(),
+        ))
+    });
+
+    // Locks on source chains are relayed for bridge validators to attest,
+    // and burns released on the source chain once they have
+    let bridge = if config.bridge.enabled() {
+        let mut relay = BridgeRelay::new(config.bridge.committee())?;
+        for (name, chain) in &config.bridge.chains {
+            let mut adapter = EvmLockAdapter::new(name, &chain.rpc, &chain.lock_contract, &chain.sender, &chain.asset)
+                .with_confirmations(chain.confirmations);
+            if let Some(block) = chain.start_block {
+                adapter = adapter.with_start_block(block);
+            }
+            relay = relay.with_adapter(Box::new(adapter));
+        }
+        let relay = Arc::new(relay);
+        tokio::spawn(relay.clone().run(Duration::from_secs(config.bridge.poll_secs)));
+        Some(relay)
+    } else {
+        None
+    };
+
+    // Tick and lot sizes, matching mode and price bands per instrument,
+    // changed from a block height by admins or governance
+    let instruments = Arc::new(
+        InstrumentRegistry::new(InstrumentParameters::default(), events.clone(), clock.clone())
+            .with_journal(config.storage.directory.join("instrument-overrides.jsonl"))?,
+    );
+
+    // Sessions, holidays and half days of each market with a calendar;
+    // orders outside a session or past their GTD expiry are rejected
+    let calendar = config.calendar.trading_calendar()?;
+
+    // Give-ups from executing firms are routed to their clearing firms,
+    // whose acceptance moves the executions to them for settlement
+    let allocations = Arc::new(
+        AllocationService::new(events.clone(), clock.clone())
+            .with_journal(config.storage.directory.join("allocations.jsonl"))?,
+    );
+
+    // Validators vote on runtime parameters; approved changes apply once the
+    // chain reaches their activation height
+    let governance = if config.governance.enabled() {
+        let parameters = Parameters {
+            block_window_ms: config.block.window_ms,
+            price_collar_bps,
+            ..Parameters::default()
+        };
+        let governance = Arc::new(
+            GovernanceService::new(
+                config.governance.validators.clone(),
+                config.governance.quorum_bps,
+                config.governance.min_delay_blocks,
+                parameters,
+            )
+            .with_log(config.storage.directory.join("governance.jsonl"))?,
+        );
+        let advancing = governance.clone();
+        let instruments = instruments.clone();
+        let rpc_state = rpc_state.clone();
+        let window = config.block.window();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(window);
+            loop {
+                ticker.tick().await;
+                let height = rpc_state.next_height();
+                for proposal in advancing.advance(height) {
+                    if proposal.status != ProposalStatus::Executed {
+                        continue;
+                    }
+                    for change in &proposal.proposal.changes {
+                        let ParameterChange::Instrument { symbol, overrides } = change else {
+                            continue;
+                        };
+                        // Applied from the activation height, or the next block
+                        // if the chain has moved past it since the last tick
+                        let activation_height = proposal.proposal.activation_height.max(height);
+                        let source = format!("governance proposal {}", proposal.id);
+                        if let Err(e) = instruments.schedule(symbol, overrides.clone(), activation_height, &source, height) {
+                            error!(symbol = %symbol, "Failed to apply instrument override of {}: {}", source, e);
+                        }
+                    }
+                }
+            }
+        });
+        Some(governance)
+    } else {
+        None
+    };
+
+    // Exchange supplied WASM plugins run extra pre-trade checks, each
+    // version from its activation height
+    let plugins = if config.plugins.enabled() {
+        let host = PluginHost::new(config.plugins.limits());
+        for module in &config.plugins.modules {
+            host.register(&module.name, module.version, module.activation_height, &std::fs::read(&module.path)?)?;
+        }
+        Some(Arc::new(host))
+    } else {
+        None
+    };
+
+    // Protocol upgrades activate at the configured heights. Operators are
+    // warned ahead of one this binary does not support.
+    let protocol = Arc::new(config.protocol.schedule());
+    {
+        let protocol = protocol.clone();
+        let rpc_state = rpc_state.clone();
+        let warn_blocks = config.protocol.warn_blocks;
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(Duration::from_secs(60));
+            loop {
+                ticker.tick().await;
+                let height = rpc_state.next_height();
+                if let Some(activation) = protocol.pending_unsupported(height, SUPPORTED_PROTOCOL_VERSION, warn_blocks) {
+                    warn!(
+                        version = activation.version,
+                        activation_height = activation.height,
+                        blocks_remaining = activation.height - height,
+                        supported = SUPPORTED_PROTOCOL_VERSION,
+                        "Upgrade this binary before the pending protocol version activates"
+                    );
+                }
+                if let Err(e) = protocol.check(height, SUPPORTED_PROTOCOL_VERSION) {
+                    error!("{}", e);
+                }
+            }
+        });
+    }
+
+    // Markets blunting latency races hold order messages for a fixed delay
+    // before handling them
+    let mut speed_bump = SpeedBump::new(config.speed_bump.delay());
+
+    let rpc_handler = RpcHandler::with_clock(rpc_state.clone(), submission_tx, clock.clone())
+        .with_environment(config.environment)
+        .with_chain_id(config.network.chain_id.clone())
+        .with_kill_switch(kill_switch.clone())
+        .with_permissions(permissions.clone())
+        .with_sub_accounts(sub_accounts.clone())
+        .with_allocations(allocations.clone())
+        .with_drain_mode(drain.clone())
+        .with_load_shedding(load_shedding.clone())
+        .with_stats(stats.clone())
+        .with_obligations(obligations.clone())
+        .with_reference_prices(reference_prices.clone(), manual_prices)
+        .with_market_data(market_data.clone())
+        .with_candles(candles)
+        .with_orders(orders.clone())
+        .with_news(news.clone())
+        .with_api_keys(api_keys)
+        .with_attestations(attestations)
+        .with_logging(logging)
+        .with_protocol(protocol);
+    let rpc_handler = match reconciliation {
+        Some(reconciliation) => rpc_handler.with_reconciliation(reconciliation),
+        None => rpc_handler,
+    };
+    let rpc_handler = match settlement {
+        Some(settlement) => rpc_handler.with_settlement(settlement),
+        None => rpc_handler,
+    };
+    let rpc_handler = match &oracle {
+        Some(oracle) => rpc_handler.with_oracle(oracle.clone()),
+        None => rpc_handler,
+    };
+    let rpc_handler = match &governance {
+        Some(governance) => rpc_handler.with_governance(governance.clone()),
+        None => rpc_handler,
+    };
+    let rpc_handler = rpc_handler.with_instruments(instruments.clone());
+    let rpc_handler = if speed_bump.enabled() {
+        rpc_handler.with_speed_bump(speed_bump.state())
+    } else {
+        rpc_handler
+    };
+    let rpc_handler = match &clock_monitor {
+        Some(monitor) => rpc_handler.with_clock_monitor(monitor.clone()),
+        None => rpc_handler,
+    };
+    let rpc_handler = match &fees {
+        Some(fees) => rpc_handler.with_fees(fees.clone()),
+        None => rpc_handler,
+    };
+    let rpc_handler = match &plugins {
+        Some(plugins) => rpc_handler.with_plugins(plugins.clone()),
+        None => rpc_handler,
+    };
+    let rpc_handler = match bridge {
+        Some(bridge) => rpc_handler.with_bridge(bridge),
+        None => rpc_handler,
+    };
+    // Development and testnet networks hand out test RØMER on request,
+    // minted through the shared faucet object; configuration validation
+    // keeps the faucet off mainnet. What each address received is journaled
+    // so its limit holds across restarts.
+    let rpc_handler = if config.faucet.enabled() {
+        match Faucet::new(config.faucet.clone(), config.environment, clock.clone()) {
+            Ok(faucet) => rpc_handler.with_faucet(Arc::new(faucet.with_journal(config.storage.directory.join("faucet.jsonl"))?)),
+            Err(e) => {
+                error!("{}", e);
+                rpc_handler
+            }
+        }
+    } else {
+        rpc_handler
+    };
+    // Admin methods are limited to the roles of client certificates and
+    // KeyManager keys, and refused while none are configured
+    let rpc_handler = if config.admin.enabled() {
+        rpc_handler.with_admin_access(Arc::new(AdminAccess::new(&config.admin, clock.clone())))
+    } else {
+        warn!("No admin roles configured, admin RPC methods are disabled");
+        rpc_handler
+    };
+    let rpc_server = RpcServer::new(rpc_config, rpc_handler);
+    let rpc_server = match config.admin.tls() {
+        Some((cert, key)) => rpc_server.with_tls(load_tls(cert, key, config.admin.client_ca.as_deref())?),
+        None => rpc_server,
+    };
+    tokio::spawn(async move {
+        if let Err(e) = rpc_server.run().await {
+            error!("JSON-RPC server failed: {}", e);
+        }
+    });
+
+    {
+        let mempool = mempool.clone();
+        let rpc_state = rpc_state.clone();
+        let clock = clock.clone();
+        tokio::spawn(async move {
+            let mut eviction = tokio::time::interval(Duration::from_secs(1));
+            loop {
+                tokio::select! {
+                    Some(transaction) = submission_rx.recv() => {
+                        // Submissions were hashed when recorded, and pooled
+                        // transactions when inserted
+                        let Ok(digest) = transaction.digest() else {
+                            continue;
+                        };
+                        let hash = hex(&digest);
+                        match mempool.lock().insert(transaction, clock.instant()) {
+                            Ok(Some(evicted)) => {
+                                if let Ok(digest) = evicted.digest() {
+                                    rpc_state.drop_transaction(&hex(&digest));
                                 }
                             }
+                            Ok(None) => {}
+                            Err(e) => {
+                                info!("Mempool rejected {}: {}", hash, e);
+                                rpc_state.drop_transaction(&hash);
+                            }
                         }
                     }
-                    Ok(_) => {
-                        info!("Connection closed by client: {}", addr);
+                    _ = eviction.tick() => {
+                        let evicted = mempool.lock().evict_expired(clock.instant(), clock.unix_secs());
+                        for digest in evicted.iter().filter_map(|evicted| evicted.digest().ok()) {
+                            rpc_state.drop_transaction(&hex(&digest));
+                        }
                     }
-                    Err(e) => {
-                        error!("Failed to read from socket: {}", e);
+                }
+            }
+        });
+    }
+
+    // Sessions are sequenced, heartbeated and checked by the session
+    // manager once their Logon authenticates. What it sends goes to the
+    // connection bound to the session, and the messages it accepts on to
+    // block production.
+    let (accepted_tx, mut accepted) = mpsc::channel::<ValidatedMessage>(1024);
+    let (outbound_tx, outbound_rx) = mpsc::channel(4096);
+    let order_checks = OrderChecks::new(load_shedding.clone(), calendar, instruments.clone(), rpc_state.clone(), clock.clone())
+        .with_sub_accounts(permissions.clone(), sub_accounts.clone())
+        .with_reference_prices(reference_prices.clone())
+        .with_price_collar(price_collar_bps);
+    let order_checks = match &plugins {
+        Some(plugins) => order_checks.with_plugins(plugins.clone()),
+        None => order_checks,
+    };
+    let order_checks = match &governance {
+        Some(governance) => order_checks.with_governance(governance.clone()),
+        None => order_checks,
+    };
+    let sessions = Arc::new(
+        SessionManager::with_clock(accepted_tx, clock.clone())
+            .with_events(events.clone())
+            .with_kill_switch(kill_switch.clone())
+            .with_permissions(permissions.clone())
+            .with_orders(orders.clone())
+            .with_markets(markets.clone())
+            .with_capacity_gate(capacity)
+            .with_drain_mode(drain.clone())
+            .with_order_checks(Arc::new(order_checks))
+            .with_heartbeat_bounds(config.session.heartbeat_bounds())
+            .with_resume_window(config.session.resume_window())
+            .with_logout_timers(config.session.logout_timers())
+            .with_outbound(outbound_tx),
+    );
+    {
+        let sessions = sessions.clone();
+        tokio::spawn(async move { sessions.run().await });
+    }
+    let (routes, routes_rx) = mpsc::unbounded_channel();
+    tokio::spawn(forward_outbound(outbound_rx, routes_rx, sessions.clone()));
+
+    // Accepted messages are batched into blocks along with the direct
+    // transactions of the mempool. Each sealed block is recorded for RPC
+    // and announced on the bus, where snapshots and statistics follow it.
+    let (flushed_tx, flushed) = mpsc::channel(16);
+    let batches = BatchManager::with_clock(flushed_tx, config.block.max_batch_size, config.block.window(), clock.clone());
+    let mut builder = BlockBuilder::with_clock(clock.clone())
+        .with_events(events.clone())
+        .with_protocol((*protocol).clone(), config.protocol.warn_blocks);
+    if let Some(bundle) = &genesis {
+        builder = builder.starting_at(bundle.height, bundle.digest.clone());
+    }
+    if let Some(oracle) = &oracle {
+        builder = builder.with_oracle(oracle.clone());
+    }
+    if let Some(monitor) = &clock_monitor {
+        builder = builder.with_clock_monitor(monitor.clone());
+    }
+    let producer = BlockProducer::new(builder, batches.clone(), mempool.clone(), rpc_state.clone(), config.block.window())
+        .with_max_transactions(config.block.max_transactions);
+    tokio::spawn(producer.run(flushed));
+    {
+        let batches = batches.clone();
+        tokio::spawn(async move { batches.run().await });
+    }
+    tokio::spawn(async move {
+        while let Some(message) = accepted.recv().await {
+            debug!(sender_comp_id = %message.sender_comp_id, msg_seq_num = message.msg_seq_num, "Message accepted");
+            batches.add_message(message).await;
+        }
+    });
+
+    // Internal systems may enter orders over the native binary protocol;
+    // its requests run through the same handling as FIX messages
+    let (binary_tx, mut binary_requests) = mpsc::channel(1024);
+    if let Some(binary_port) = config.network.binary_port {
+        let binary_addr = format!("{}:{}", host, binary_port);
+        let binary_listener = TcpListener::bind(&binary_addr).await?;
+        info!("Binary gateway listening on {}", binary_addr);
+        tokio::spawn(BinaryGateway::new(binary_tx, clock.clone()).run(binary_listener));
+    }
+
+    // Each FIX connection is read by its own task, which frames messages
+    // and stamps when they arrived, so time spent waiting for this loop
+    // counts as queueing
+    let network = NetworkManager::new(
+        NetworkConfig {
+            bind_address: format!("{}:{}", host, port),
+            idle_timeout: config.session.heartbeat_bounds().idle_timeout(),
+            ..NetworkConfig::default()
+        },
+        clock.clone(),
+    )
+    .with_drain_mode(drain.clone());
+    let (addr, mut connections) = network.start().await?;
+    info!("Server listening on {}", addr);
+
+    // The session each connection's Logon opened, with its SenderCompID
+    // (49), kept for the market data it subscribed to once the session is
+    // dropped
+    let mut bound: HashMap<uuid::Uuid, (uuid::Uuid, String)> = HashMap::new();
+    // Messages of each connection held by the speed bump. Later messages
+    // of the connection wait behind them to keep its sequence.
+    let mut held: HashMap<uuid::Uuid, usize> = HashMap::new();
+
+    loop {
+        // FIX messages arrive from their connection's reader, binary gateway
+        // requests with the channel their connection is written from
+        let (inbound, released) = tokio::select! {
+            Some(event) = connections.recv() => match event {
+                NetworkEvent::Message(incoming) => {
+                    let inbound = Inbound {
+                        connection_id: incoming.connection_id,
+                        responder: Responder::Fix(network.clone(), incoming.connection_id),
+                        raw: incoming.data,
+                        received_at: incoming.received_at,
+                    };
+                    (inbound, false)
+                }
+                NetworkEvent::Closed(connection_id) => {
+                    info!(connection_id = %connection_id, "Connection closed");
+                    network.close(connection_id);
+                    if let Some((session_id, sender_comp_id)) = bound.remove(&connection_id) {
+                        let _ = routes.send(Route::Unbind(session_id));
+                        market_data.unsubscribe(&sender_comp_id);
+                        match sessions.terminate_session(session_id).await {
+                            // Terminated before its connection closed
+                            Ok(()) | Err(SessionError::NotFound(_)) => {}
+                            Err(e) => warn!(session_id = %session_id, "Failed to terminate session: {}", e),
+                        }
                     }
+                    continue;
                 }
+            },
+            Some(request) = binary_requests.recv() => {
+                let inbound = Inbound {
+                    connection_id: request.connection_id,
+                    responder: Responder::Binary(request.replies),
+                    raw: request.message.into_bytes(),
+                    received_at: request.received_at,
+                };
+                (inbound, false)
             }
+            Some(inbound) = speed_bump.released() => (inbound, true),
+        };
+        let Inbound {
+            connection_id,
+            responder,
+            raw,
+            received_at,
+        } = inbound;
+
+        let message = match ValidatedMessage::parse(&raw) {
+            Ok(message) => message,
             Err(e) => {
-                error!("Failed to accept connection: {}", e);
+                debug!(connection_id = %connection_id, "Ignoring message: {}", e);
+                if let Responder::Binary(_) = responder {
+                    responder.send(b"Unsupported message type\n");
+                }
+                continue;
+            }
+        };
+        // Order messages wait out the speed bump before they are handled
+        let order = matches!(
+            message.msg_type,
+            MessageType::NewOrderSingle | MessageType::OrderMassCancelRequest
+        );
+        if released {
+            if let Some(count) = held.get_mut(&connection_id) {
+                *count -= 1;
+                if *count == 0 {
+                    held.remove(&connection_id);
+                }
+            }
+        } else if speed_bump.enabled() && (order || held.contains_key(&connection_id)) {
+            *held.entry(connection_id).or_default() += 1;
+            speed_bump.hold(Inbound {
+                connection_id,
+                responder,
+                raw,
+                received_at,
+            });
+            continue;
+        }
+        stats.record_message();
+        // Time held by the speed bump is deliberate, not queueing
+        if !released {
+            load_shedding.record_queue(clock.instant().saturating_duration_since(received_at));
+        }
+        let handling_started = clock.instant();
+
+        // A binding outlives its session once the session terminates
+        let session_id = bound
+            .get(&connection_id)
+            .map(|(session_id, _)| *session_id)
+            .filter(|session_id| sessions.get_session(*session_id).is_ok());
+        let session_id = match (session_id, message.msg_type) {
+            (None, MessageType::Logon) => {
+                match logon(&sessions, &permissions, &message, clock.now()).await {
+                    Ok((session_id, factors)) => {
+                        info!(
+                            sender_comp_id = %message.sender_comp_id,
+                            market = %message.target_comp_id,
+                            session_key = factors.session_key,
+                            password = factors.password,
+                            "Logon authenticated"
+                        );
+                        bound.insert(connection_id, (session_id, message.sender_comp_id.clone()));
+                        let _ = routes.send(Route::Bind(session_id, responder));
+                    }
+                    Err(e) => {
+                        warn!(sender_comp_id = %message.sender_comp_id, error = %e, "Logon rejected");
+                        let logout = AdminMessage::Logout {
+                            text: Some(format!("Logon rejected: {}", e)),
+                        }
+                        .encode(
+                            message.field(8).unwrap_or("FIX.4.2"),
+                            &message.target_comp_id,
+                            &message.sender_comp_id,
+                            1,
+                            clock.now(),
+                        );
+                        responder.send(&logout);
+                        // Binary clients may try again on the same connection
+                        if let Responder::Fix(..) = responder {
+                            responder.close();
+                        }
+                    }
+                }
+                continue;
+            }
+            (None, _) => {
+                bound.remove(&connection_id);
+                match responder {
+                    Responder::Fix(..) => {
+                        warn!(connection_id = %connection_id, msg_type = ?message.msg_type, "Message before Logon, closing connection");
+                        responder.close();
+                    }
+                    Responder::Binary(_) => {
+                        responder.send(b"Logon required\n");
+                    }
+                }
+                continue;
             }
+            (Some(_), MessageType::Logon) => {
+                warn!(sender_comp_id = %message.sender_comp_id, "Ignoring Logon of a logged on session");
+                continue;
+            }
+            (Some(session_id), _) => session_id,
+        };
+
+        let sender_comp_id = message.sender_comp_id.clone();
+        let target_comp_id = message.target_comp_id.clone();
+        // Refused orders are answered by the session with an ExecutionReport
+        if let Err(e) = sessions.handle_message(session_id, message.clone()).await {
+            debug!(sender_comp_id = %sender_comp_id, msg_type = ?message.msg_type, "Message refused: {}", e);
+        } else {
+            match message.msg_type {
+                MessageType::MarketDataRequest => {
+                    // Entitlements were checked by the session
+                    market_data.subscribe(&sender_comp_id, message.field(55).unwrap_or_default());
+                }
+                MessageType::MarketDataSnapshot => {
+                    // Oracle feeders publish their signed prices as snapshots
+                    let fields = message.fields.clone().into_iter().collect();
+                    match (&oracle, is_price_submission(&fields)) {
+                        (Some(oracle), true) => {
+                            let submitted = price_submission(&fields)
+                                .map_err(|e| e.to_string())
+                                .and_then(|signed| oracle.submit(signed).map_err(|e| e.to_string()));
+                            if let Err(e) = submitted {
+                                warn!(sender_comp_id = %sender_comp_id, "Oracle price rejected: {}", e);
+                            }
+                        }
+                        (None, true) => warn!(sender_comp_id = %sender_comp_id, "Oracle price rejected: no oracle configured"),
+                        (_, false) => {}
+                    }
+                }
+                MessageType::AllocationInstruction => {
+                    // Acknowledge receipt; the outcome follows once the
+                    // clearing firms have answered
+                    let received = AllocationInstruction::parse(&message.raw)
+                        .map_err(|e| (message.field(70).unwrap_or_default().to_string(), e.to_string()))
+                        .and_then(|instruction| {
+                            let alloc_id = instruction.alloc_id.clone();
+                            allocations
+                                .submit(&sender_comp_id, instruction)
+                                .map(|_| alloc_id.clone())
+                                .map_err(|e| (alloc_id, e.to_string()))
+                        });
+                    let ack = match received {
+                        Ok(alloc_id) => AllocationAck {
+                            alloc_id,
+                            status: AllocStatus::Received,
+                            text: None,
+                        },
+                        Err((alloc_id, reason)) => {
+                            warn!(sender_comp_id = %sender_comp_id, "Allocation rejected: {}", reason);
+                            AllocationAck {
+                                alloc_id,
+                                status: AllocStatus::BlockRejected,
+                                text: Some(reason),
+                            }
+                        }
+                    };
+                    send(&sessions, session_id, |seq, now| ack.encode(&target_comp_id, &sender_comp_id, seq, now)).await;
+                }
+                MessageType::AllocationAck => {
+                    // A clearing firm accepting or rejecting a give-up
+                    let acknowledged = AllocationAck::parse(&message.raw)
+                        .map_err(|e| e.to_string())
+                        .and_then(|ack| allocations.acknowledge(&sender_comp_id, ack).map_err(|e| e.to_string()));
+                    if let Err(e) = acknowledged {
+                        warn!(sender_comp_id = %sender_comp_id, "Allocation ack rejected: {}", e);
+                    }
+                }
+                MessageType::OrderMassCancelRequest if !sessions.pulls_kill_switch(&message) => {
+                    // Cancelling all orders pulls the kill switch in the
+                    // session; nothing narrower is supported
+                    warn!(
+                        sender_comp_id = %sender_comp_id,
+                        mass_cancel_request_type = message.field(530).unwrap_or_default(),
+                        "Unsupported mass cancel request"
+                    );
+                }
+                _ => {}
+            }
+        }
+
+        // Fee tier changes go out as News after the firm's next message
+        if let Some(fees) = &fees {
+            for change in fees.take_notices(&sender_comp_id) {
+                let (headline, text) = change.notice();
+                let news = News {
+                    sender_comp_id: target_comp_id.clone(),
+                    target_comp_id: sender_comp_id.clone(),
+                    headline,
+                    text,
+                };
+                send(&sessions, session_id, |seq, now| news.encode(seq, now).into_bytes()).await;
+            }
+        }
+        // So do operator notices the counterparty has not seen yet
+        for notice in news.take_pending(&sender_comp_id) {
+            let news = News {
+                sender_comp_id: target_comp_id.clone(),
+                target_comp_id: sender_comp_id.clone(),
+                headline: notice.headline,
+                text: notice.text,
+            };
+            send(&sessions, session_id, |seq, now| news.encode(seq, now).into_bytes()).await;
+        }
+        // Give-ups and their outcomes likewise reach a firm after its next
+        // message
+        for outbound in allocations.take_outbound(&sender_comp_id) {
+            send(&sessions, session_id, |seq, now| outbound.encode(&target_comp_id, &sender_comp_id, seq, now)).await;
+        }
+        // Messages are handled one at a time, so each is its own batch
+        load_shedding.record_batch(clock.instant().saturating_duration_since(handling_started));
+    }
+}
+
+/// A message read from a connection
+struct Inbound {
+    connection_id: uuid::Uuid,
+    responder: Responder,
+    raw: Vec<u8>,
+    received_at: std::time::Instant,
+}
+
+/// Authenticates a Logon and opens its session. Session key logons carry a
+/// certificate chaining the session key to the firm's registered key;
+/// firms with a logon credential also send Username and Password.
+async fn logon(
+    sessions: &SessionManager,
+    permissions: &PermissionRegistry,
+    message: &ValidatedMessage,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Result<(uuid::Uuid, LogonFactors), String> {
+    let fields = message.fields.clone().into_iter().collect();
+    let organization = permissions
+        .organization_for_sender(&message.sender_comp_id)
+        .ok_or_else(|| LogonAuthError::UnregisteredParent(message.sender_comp_id.clone()).to_string())?;
+    let factors = authenticate_logon(&fields, &organization, now).map_err(|e| e.to_string())?;
+    let sequences = SequenceNegotiation {
+        reset: message.field(141) == Some("Y"),
+        logon_seq: message.msg_seq_num,
+        next_expected: message.field(789).and_then(|seq| seq.parse().ok()),
+    };
+    let session_id = sessions
+        .logon(
+            message.sender_comp_id.clone(),
+            message.target_comp_id.clone(),
+            message.field(108),
+            organization.public_key,
+            sequences,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok((session_id, factors))
+}
+
+/// Sends an application message on a session, logging if it has ended
+async fn send(
+    sessions: &SessionManager,
+    session_id: uuid::Uuid,
+    encode: impl FnOnce(u64, chrono::DateTime<chrono::Utc>) -> Vec<u8>,
+) {
+    if let Err(e) = sessions.send(session_id, encode).await {
+        warn!(session_id = %session_id, "Failed to send to session: {}", e);
+    }
+}
+
+/// The connection a session's messages are written to
+enum Responder {
+    /// A FIX connection
+    Fix(NetworkManager, uuid::Uuid),
+    /// A binary gateway connection
+    Binary(mpsc::Sender<String>),
+}
+
+impl Responder {
+    /// Queues `raw` for the connection, returning whether it still takes
+    /// messages
+    fn send(&self, raw: &[u8]) -> bool {
+        match self {
+            Self::Fix(network, connection_id) => network.send(*connection_id, raw.to_vec()).is_ok(),
+            Self::Binary(replies) => replies.try_send(String::from_utf8_lossy(raw).into_owned()).is_ok(),
         }
     }
+
+    /// Closes the connection once what is queued for it has been written
+    fn close(&self) {
+        match self {
+            Self::Fix(network, connection_id) => network.close(*connection_id),
+            Self::Binary(replies) => {
+                let _ = replies.try_send(String::new());
+            }
+        }
+    }
+}
+
+/// Changes to where sessions' messages are written
+enum Route {
+    /// The session's Logon was accepted on this connection
+    Bind(uuid::Uuid, Responder),
+    /// The session's connection went away
+    Unbind(uuid::Uuid),
 }
 
-// Helper function to extract the message type from a FIX message
-fn extract_message_type(message: &str) -> Option<&str> {
-    // Look for the message type tag (35=X)
-    message.split('|')
-        .find(|field| field.starts_with("35="))
-        .map(|field| &field[3..])
-}
\ No newline at end of file
+/// Writes the messages sessions send to their connections. A session's
+/// Logon is acknowledged before its connection is bound, so messages of
+/// sessions not bound yet wait for the binding.
+async fn forward_outbound(
+    mut outbound: mpsc::Receiver<OutboundMessage>,
+    mut routes: mpsc::UnboundedReceiver<Route>,
+    sessions: Arc<SessionManager>,
+) {
+    let mut bound: HashMap<uuid::Uuid, Responder> = HashMap::new();
+    let mut waiting: HashMap<uuid::Uuid, Vec<OutboundMessage>> = HashMap::new();
+    loop {
+        tokio::select! {
+            Some(route) = routes.recv() => match route {
+                Route::Bind(session_id, responder) => {
+                    bound.insert(session_id, responder);
+                    for message in waiting.remove(&session_id).unwrap_or_default() {
+                        forward(&mut bound, message, &sessions);
+                    }
+                }
+                Route::Unbind(session_id) => {
+                    bound.remove(&session_id);
+                    waiting.remove(&session_id);
+                }
+            },
+            Some(message) = outbound.recv() => {
+                if bound.contains_key(&message.session_id) {
+                    forward(&mut bound, message, &sessions);
+                } else if message.close {
+                    // Its Logon failed, so it is never bound
+                    waiting.remove(&message.session_id);
+                } else {
+                    waiting.entry(message.session_id).or_default().push(message);
+                }
+            }
+            else => break,
+        }
+    }
+}
+
+/// Writes one message to its session's connection. Sessions whose
+/// connection no longer takes messages are terminated.
+fn forward(bound: &mut HashMap<uuid::Uuid, Responder>, message: OutboundMessage, sessions: &Arc<SessionManager>) {
+    let Some(responder) = bound.get(&message.session_id) else {
+        return;
+    };
+    let delivered = message.raw.is_empty() || responder.send(&message.raw);
+    if message.close {
+        responder.close();
+        bound.remove(&message.session_id);
+    } else if !delivered {
+        warn!(session_id = %message.session_id, "Connection not taking messages, terminating session");
+        bound.remove(&message.session_id);
+        let sessions = sessions.clone();
+        let session_id = message.session_id;
+        tokio::spawn(async move {
+            let _ = sessions.terminate_session(session_id).await;
+        });
+    }
+}
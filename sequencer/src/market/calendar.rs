@@ -0,0 +1,270 @@
+// src/market/calendar.rs
+
+use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveTime, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use thiserror::Error;
+
+/// How far calendar searches look before giving up, so a calendar of
+/// nothing but holidays cannot loop forever
+const SEARCH_DAYS: u64 = 366;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum CalendarError {
+    #[error("Invalid calendar of {market}: {reason}")]
+    Invalid { market: String, reason: String },
+
+    #[error("Market {market} is closed at {at}")]
+    Closed { market: String, at: DateTime<Utc> },
+
+    #[error("Invalid ExpireDate (432) {0}")]
+    InvalidExpireDate(String),
+
+    #[error("GTD order expired at the close of {0}")]
+    Expired(NaiveDate),
+}
+
+/// One market's trading days and hours, and the days its trades settle on.
+/// Saturdays and Sundays are neither trading nor settlement days. Times are
+/// UTC.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MarketCalendar {
+    pub open: NaiveTime,
+    pub close: NaiveTime,
+    /// Days the market does not trade
+    #[serde(default)]
+    pub holidays: BTreeSet<NaiveDate>,
+    /// Days the market closes early, with the early close
+    #[serde(default)]
+    pub half_days: BTreeMap<NaiveDate, NaiveTime>,
+    /// Days trades do not settle on, such as bank holidays
+    #[serde(default)]
+    pub settlement_holidays: BTreeSet<NaiveDate>,
+    /// Settlement cycle: trades settle this many settlement days after the
+    /// trade date
+    #[serde(default = "default_settlement_days")]
+    pub settlement_days: u32,
+}
+
+fn default_settlement_days() -> u32 {
+    2
+}
+
+impl MarketCalendar {
+    pub fn validate(&self, market: &str) -> Result<(), CalendarError> {
+        let invalid = |reason: String| {
+            Err(CalendarError::Invalid {
+                market: market.to_string(),
+                reason,
+            })
+        };
+        if self.open >= self.close {
+            return invalid(format!("opens at {} but closes at {}", self.open, self.close));
+        }
+        for (date, close) in &self.half_days {
+            if self.holidays.contains(date) {
+                return invalid(format!("{} is both a holiday and a half day", date));
+            }
+            if *close <= self.open || *close >= self.close {
+                return invalid(format!("half day {} closes at {}, outside the session", date, close));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_trading_day(&self, date: NaiveDate) -> bool {
+        !is_weekend(date) && !self.holidays.contains(&date)
+    }
+
+    pub fn is_settlement_day(&self, date: NaiveDate) -> bool {
+        !is_weekend(date) && !self.settlement_holidays.contains(&date)
+    }
+
+    /// Open and close of the session on `date`, `None` on days without one
+    pub fn session(&self, date: NaiveDate) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        if !self.is_trading_day(date) {
+            return None;
+        }
+        let close = self.half_days.get(&date).copied().unwrap_or(self.close);
+        Some((date.and_time(self.open).and_utc(), date.and_time(close).and_utc()))
+    }
+
+    pub fn is_open(&self, at: DateTime<Utc>) -> bool {
+        self.session(at.date_naive())
+            .is_some_and(|(open, close)| open <= at && at < close)
+    }
+
+    /// When a good-till-date order expiring on `expire_date` expires: the
+    /// close of that day's session, or of the last session before it when
+    /// the market does not trade that day
+    pub fn gtd_expiry(&self, expire_date: NaiveDate) -> Option<DateTime<Utc>> {
+        (0..SEARCH_DAYS)
+            .filter_map(|back| expire_date.checked_sub_days(Days::new(back)))
+            .find_map(|date| self.session(date))
+            .map(|(_, close)| close)
+    }
+
+    /// Settlement date of a trade on `trade_date`: the settlement cycle's
+    /// worth of settlement days later
+    pub fn settlement_date(&self, trade_date: NaiveDate) -> Option<NaiveDate> {
+        let mut date = trade_date;
+        let mut remaining = self.settlement_days;
+        let mut searched = 0;
+        while remaining > 0 {
+            date = date.succ_opt()?;
+            searched += 1;
+            if searched > SEARCH_DAYS {
+                return None;
+            }
+            if self.is_settlement_day(date) {
+                remaining -= 1;
+            }
+        }
+        Some(date)
+    }
+}
+
+fn is_weekend(date: NaiveDate) -> bool {
+    matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+}
+
+/// Calendars of the markets that have one, by TargetCompID. Markets without
+/// a calendar trade around the clock.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TradingCalendar {
+    markets: BTreeMap<String, MarketCalendar>,
+}
+
+impl TradingCalendar {
+    pub fn new(markets: BTreeMap<String, MarketCalendar>) -> Result<Self, CalendarError> {
+        for (market, calendar) in &markets {
+            calendar.validate(market)?;
+        }
+        Ok(Self { markets })
+    }
+
+    pub fn market(&self, target_comp_id: &str) -> Option<&MarketCalendar> {
+        self.markets.get(target_comp_id)
+    }
+
+    /// Checks an order reaching `target_comp_id` at `at` arrives during a
+    /// session and, if good till date (TimeInForce 59=6), that its
+    /// ExpireDate (432, YYYYMMDD) has not passed
+    pub fn check_order(
+        &self,
+        target_comp_id: &str,
+        at: DateTime<Utc>,
+        time_in_force: Option<&str>,
+        expire_date: Option<&str>,
+    ) -> Result<(), CalendarError> {
+        let Some(calendar) = self.market(target_comp_id) else {
+            return Ok(());
+        };
+        if !calendar.is_open(at) {
+            return Err(CalendarError::Closed {
+                market: target_comp_id.to_string(),
+                at,
+            });
+        }
+        if time_in_force != Some("6") {
+            return Ok(());
+        }
+        let raw = expire_date.unwrap_or_default();
+        let expire_date =
+            NaiveDate::parse_from_str(raw, "%Y%m%d").map_err(|_| CalendarError::InvalidExpireDate(raw.to_string()))?;
+        match calendar.gtd_expiry(expire_date) {
+            Some(expiry) if expiry > at => Ok(()),
+            _ => Err(CalendarError::Expired(expire_date)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        // November 2026: the 2nd is a Monday
+        NaiveDate::from_ymd_opt(2026, 11, day).unwrap()
+    }
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn calendar() -> MarketCalendar {
+        MarketCalendar {
+            open: time(14, 30),
+            close: time(21, 0),
+            holidays: BTreeSet::from([date(26)]),
+            half_days: BTreeMap::from([(date(27), time(18, 0))]),
+            settlement_holidays: BTreeSet::from([date(11)]),
+            settlement_days: 2,
+        }
+    }
+
+    #[test]
+    fn test_sessions() {
+        let calendar = calendar();
+        calendar.validate("ROMER").unwrap();
+        assert!(calendar.is_open(date(25).and_time(time(15, 0)).and_utc()));
+        assert!(!calendar.is_open(date(25).and_time(time(21, 0)).and_utc()));
+        assert!(!calendar.is_open(date(26).and_time(time(15, 0)).and_utc()));
+        // Half day
+        assert!(!calendar.is_open(date(27).and_time(time(19, 0)).and_utc()));
+        assert!(calendar.session(date(28)).is_none());
+
+        let mut overlapping = calendar.clone();
+        overlapping.half_days.insert(date(26), time(18, 0));
+        assert!(overlapping.validate("ROMER").is_err());
+    }
+
+    #[test]
+    fn test_gtd_expiry_and_settlement_date() {
+        let calendar = calendar();
+        assert_eq!(
+            calendar.gtd_expiry(date(25)),
+            Some(date(25).and_time(time(21, 0)).and_utc())
+        );
+        // Thanksgiving rolls back to Wednesday, the weekend to the half day
+        assert_eq!(
+            calendar.gtd_expiry(date(26)),
+            Some(date(25).and_time(time(21, 0)).and_utc())
+        );
+        assert_eq!(
+            calendar.gtd_expiry(date(29)),
+            Some(date(27).and_time(time(18, 0)).and_utc())
+        );
+
+        // The bank holiday on Wednesday the 11th is skipped
+        assert_eq!(calendar.settlement_date(date(9)), Some(date(12)));
+        assert_eq!(calendar.settlement_date(date(10)), Some(date(13)));
+        // Friday settles Tuesday
+        assert_eq!(calendar.settlement_date(date(13)), Some(date(17)));
+    }
+
+    #[test]
+    fn test_check_order() {
+        let calendar = TradingCalendar::new(BTreeMap::from([("ROMER".to_string(), calendar())])).unwrap();
+        let at = date(25).and_time(time(15, 0)).and_utc();
+
+        calendar.check_order("ROMER", at, None, None).unwrap();
+        calendar.check_order("ROMER", at, Some("6"), Some("20261130")).unwrap();
+        calendar
+            .check_order("ROMER-FX", date(26).and_time(time(3, 0)).and_utc(), None, None)
+            .unwrap();
+        assert!(matches!(
+            calendar.check_order("ROMER", date(26).and_time(time(15, 0)).and_utc(), None, None),
+            Err(CalendarError::Closed { .. })
+        ));
+        assert_eq!(
+            calendar.check_order("ROMER", at, Some("6"), Some("20261124")),
+            Err(CalendarError::Expired(date(24)))
+        );
+        assert!(matches!(
+            calendar.check_order("ROMER", at, Some("6"), None),
+            Err(CalendarError::InvalidExpireDate(_))
+        ));
+    }
+}
@@ -0,0 +1,398 @@
+// src/market/candles.rs
+
+use crate::events::bus::EventSink;
+use crate::events::types::SequencerEvent;
+use chrono::{DateTime, NaiveDate, Utc};
+use parking_lot::Mutex;
+use romer_common::storage::group_commit::GroupCommitter;
+use romer_common::storage::journal::RomerJournal;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+
+/// Candles kept per symbol and interval
+pub const DEFAULT_RETENTION: usize = 1440;
+
+/// Width of a candle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Interval {
+    #[serde(rename = "1m")]
+    OneMinute,
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "15m")]
+    FifteenMinutes,
+    #[serde(rename = "1h")]
+    OneHour,
+    #[serde(rename = "1d")]
+    OneDay,
+}
+
+impl Interval {
+    /// Every interval aggregated
+    pub const ALL: [Interval; 5] = [
+        Interval::OneMinute,
+        Interval::FiveMinutes,
+        Interval::FifteenMinutes,
+        Interval::OneHour,
+        Interval::OneDay,
+    ];
+
+    pub fn secs(&self) -> i64 {
+        match self {
+            Self::OneMinute => 60,
+            Self::FiveMinutes => 300,
+            Self::FifteenMinutes => 900,
+            Self::OneHour => 3_600,
+            Self::OneDay => 86_400,
+        }
+    }
+
+    /// Start of the candle containing `at`. Daily candles start at
+    /// midnight UTC.
+    pub fn open_time(&self, at: DateTime<Utc>) -> DateTime<Utc> {
+        let secs = at.timestamp();
+        DateTime::from_timestamp(secs - secs.rem_euclid(self.secs()), 0).unwrap_or(at)
+    }
+}
+
+/// A trade, as aggregated and journaled
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fill {
+    pub symbol: String,
+    pub price: u64,
+    pub quantity: u64,
+    pub at: DateTime<Utc>,
+}
+
+/// Open, high, low and close price and traded volume of a symbol over one
+/// interval
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Candle {
+    pub open_time: DateTime<Utc>,
+    pub open: u64,
+    pub high: u64,
+    pub low: u64,
+    pub close: u64,
+    /// Quantity traded
+    pub volume: u64,
+    /// Sum of price times quantity
+    pub notional: u128,
+    pub trades: u64,
+    /// Time of the last trade, which set the close
+    #[serde(skip)]
+    last_at: Option<DateTime<Utc>>,
+}
+
+impl Candle {
+    fn new(open_time: DateTime<Utc>, fill: &Fill) -> Self {
+        let mut candle = Self {
+            open_time,
+            open: fill.price,
+            high: fill.price,
+            low: fill.price,
+            close: fill.price,
+            volume: 0,
+            notional: 0,
+            trades: 0,
+            last_at: None,
+        };
+        candle.add(fill);
+        candle
+    }
+
+    fn add(&mut self, fill: &Fill) {
+        self.high = self.high.max(fill.price);
+        self.low = self.low.min(fill.price);
+        // A fill arriving late does not move the close back in time
+        if self.last_at.map_or(true, |last| fill.at >= last) {
+            self.close = fill.price;
+            self.last_at = Some(fill.at);
+        }
+        self.volume = self.volume.saturating_add(fill.quantity);
+        self.notional += fill.price as u128 * fill.quantity as u128;
+        self.trades += 1;
+    }
+}
+
+/// Trading statistics of a symbol over one UTC day
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DailyStats {
+    pub day: NaiveDate,
+    pub open: u64,
+    pub high: u64,
+    pub low: u64,
+    pub close: u64,
+    pub volume: u64,
+    pub notional: u128,
+    pub trades: u64,
+    /// Volume-weighted average price
+    pub vwap: u64,
+}
+
+impl From<&Candle> for DailyStats {
+    fn from(candle: &Candle) -> Self {
+        Self {
+            day: candle.open_time.date_naive(),
+            open: candle.open,
+            high: candle.high,
+            low: candle.low,
+            close: candle.close,
+            volume: candle.volume,
+            notional: candle.notional,
+            trades: candle.trades,
+            vwap: (candle.notional / candle.volume.max(1) as u128) as u64,
+        }
+    }
+}
+
+/// Candles of every symbol and interval, the most recent `retention` of
+/// each
+pub struct CandleStore {
+    retention: usize,
+    series: Mutex<HashMap<(String, Interval), VecDeque<Candle>>>,
+}
+
+impl Default for CandleStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_RETENTION)
+    }
+}
+
+impl CandleStore {
+    pub fn new(retention: usize) -> Self {
+        Self {
+            retention: retention.max(1),
+            series: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Adds `fill` to the candle of each interval it falls in
+    pub fn record(&self, fill: &Fill) {
+        let mut series = self.series.lock();
+        for interval in Interval::ALL {
+            let open_time = interval.open_time(fill.at);
+            let candles = series.entry((fill.symbol.clone(), interval)).or_default();
+            match candles.binary_search_by_key(&open_time, |candle| candle.open_time) {
+                Ok(index) => candles[index].add(fill),
+                // Older than every candle kept
+                Err(0) if candles.len() >= self.retention => {}
+                Err(index) => {
+                    candles.insert(index, Candle::new(open_time, fill));
+                    if candles.len() > self.retention {
+                        candles.pop_front();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Up to `limit` of the latest candles opening at or after `since`,
+    /// oldest first
+    pub fn candles(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        since: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> Vec<Candle> {
+        let series = self.series.lock();
+        let Some(candles) = series.get(&(symbol.to_string(), interval)) else {
+            return Vec::new();
+        };
+        let matching: Vec<&Candle> = candles
+            .iter()
+            .filter(|candle| since.map_or(true, |since| candle.open_time >= since))
+            .collect();
+        matching[matching.len().saturating_sub(limit)..]
+            .iter()
+            .map(|candle| (*candle).clone())
+            .collect()
+    }
+
+    /// Statistics of the latest `days` trading days of `symbol`, oldest
+    /// first
+    pub fn daily(&self, symbol: &str, days: usize) -> Vec<DailyStats> {
+        self.candles(symbol, Interval::OneDay, None, days)
+            .iter()
+            .map(DailyStats::from)
+            .collect()
+    }
+
+    /// Symbols traded so far
+    pub fn symbols(&self) -> Vec<String> {
+        let mut symbols: Vec<String> = self
+            .series
+            .lock()
+            .keys()
+            .filter(|(_, interval)| *interval == Interval::OneDay)
+            .map(|(symbol, _)| symbol.clone())
+            .collect();
+        symbols.sort();
+        symbols
+    }
+}
+
+/// Aggregates the fills on the event bus into the candle store, journaling
+/// each fill to the market data partition so candles survive restarts
+pub struct CandleAggregator {
+    store: Arc<CandleStore>,
+    journal: Option<mpsc::UnboundedSender<Vec<u8>>>,
+}
+
+impl CandleAggregator {
+    /// Aggregator without persistence, for tests and tooling
+    pub fn in_memory(store: Arc<CandleStore>) -> Self {
+        Self { store, journal: None }
+    }
+
+    /// Rebuilds the store from the fills in `journal`, then journals new
+    /// fills to it
+    pub async fn open(store: Arc<CandleStore>, mut journal: RomerJournal) -> Result<Self, String> {
+        let mut restored = 0;
+        for bytes in journal.replay_all().await? {
+            match serde_json::from_slice::<Fill>(&bytes) {
+                Ok(fill) => {
+                    store.record(&fill);
+                    restored += 1;
+                }
+                Err(e) => warn!(error = %e, "Skipping undecodable fill"),
+            }
+        }
+        info!(fills = restored, "Restored candles");
+
+        let committer = GroupCommitter::spawn(journal);
+        let (sender, mut receiver) = mpsc::unbounded_channel::<Vec<u8>>();
+        tokio::spawn(async move {
+            while let Some(bytes) = receiver.recv().await {
+                if let Err(e) = committer.append(bytes).await {
+                    error!(error = %e, "Failed to journal fill");
+                }
+            }
+        });
+        Ok(Self {
+            store,
+            journal: Some(sender),
+        })
+    }
+}
+
+impl EventSink for CandleAggregator {
+    fn name(&self) -> &str {
+        "candles"
+    }
+
+    fn handle(&mut self, event: &SequencerEvent) {
+        let SequencerEvent::Match {
+            symbol,
+            price,
+            quantity,
+            at,
+            ..
+        } = event
+        else {
+            return;
+        };
+        let fill = Fill {
+            symbol: symbol.clone(),
+            price: *price,
+            quantity: *quantity,
+            at: *at,
+        };
+        self.store.record(&fill);
+        if let Some(journal) = &self.journal {
+            match serde_json::to_vec(&fill) {
+                Ok(bytes) => {
+                    let _ = journal.send(bytes);
+                }
+                Err(e) => error!(error = %e, "Failed to encode fill"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn fill(price: u64, quantity: u64, secs: i64) -> Fill {
+        Fill {
+            symbol: "AAPL".to_string(),
+            price,
+            quantity,
+            at: Utc.with_ymd_and_hms(2024, 3, 1, 9, 30, 0).unwrap() + chrono::Duration::seconds(secs),
+        }
+    }
+
+    #[test]
+    fn test_candles() {
+        let store = CandleStore::default();
+        for fill in [fill(100, 10, 0), fill(105, 5, 20), fill(98, 5, 40), fill(101, 10, 70)] {
+            store.record(&fill);
+        }
+
+        let minutes = store.candles("AAPL", Interval::OneMinute, None, 10);
+        assert_eq!(minutes.len(), 2);
+        let first = &minutes[0];
+        assert_eq!((first.open, first.high, first.low, first.close), (100, 105, 98, 98));
+        assert_eq!((first.volume, first.trades), (20, 3));
+        assert_eq!(minutes[1].open_time, first.open_time + chrono::Duration::minutes(1));
+
+        let five = store.candles("AAPL", Interval::FiveMinutes, None, 10);
+        assert_eq!(five.len(), 1);
+        assert_eq!(five[0].close, 101);
+
+        // A late fill counts towards volume without changing the close
+        store.record(&fill(90, 1, 10));
+        let minute = &store.candles("AAPL", Interval::OneMinute, None, 1)[0];
+        assert_eq!(minute.close, 101);
+        let first = &store.candles("AAPL", Interval::OneMinute, None, 2)[0];
+        assert_eq!((first.low, first.close, first.trades), (90, 98, 4));
+
+        let since = Some(first.open_time + chrono::Duration::minutes(1));
+        assert_eq!(store.candles("AAPL", Interval::OneMinute, since, 10).len(), 1);
+        assert!(store.candles("MSFT", Interval::OneMinute, None, 10).is_empty());
+    }
+
+    #[test]
+    fn test_daily_stats_and_retention() {
+        let store = CandleStore::new(2);
+        for day in 0..3 {
+            store.record(&fill(100 + day as u64, 10, day * 86_400));
+            store.record(&fill(110 + day as u64, 30, day * 86_400 + 60));
+        }
+
+        let daily = store.daily("AAPL", 10);
+        assert_eq!(daily.len(), 2);
+        let last = &daily[1];
+        assert_eq!(last.day, NaiveDate::from_ymd_opt(2024, 3, 3).unwrap());
+        assert_eq!((last.open, last.close, last.volume), (102, 112, 40));
+        assert_eq!(last.vwap, (102 * 10 + 112 * 30) / 40);
+        assert_eq!(store.symbols(), vec!["AAPL".to_string()]);
+
+        // Too old for the candles kept
+        store.record(&fill(1, 1, 0));
+        assert_eq!(store.daily("AAPL", 10), daily);
+    }
+
+    #[test]
+    fn test_aggregates_matches() {
+        let store = Arc::new(CandleStore::default());
+        let mut aggregator = CandleAggregator::in_memory(store.clone());
+        aggregator.handle(&SequencerEvent::Match {
+            symbol: "AAPL".to_string(),
+            price: 100,
+            quantity: 3,
+            buyer: "MM1".to_string(),
+            seller: "MM2".to_string(),
+            maker: None,
+            buyer_account: None,
+            seller_account: None,
+            at: Utc::now(),
+        });
+        assert_eq!(store.daily("AAPL", 1)[0].volume, 3);
+    }
+}
@@ -0,0 +1,304 @@
+// src/market/data.rs
+
+use super::depth::{DepthBook, DepthSnapshot};
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::sync::Arc;
+use tokio::sync::Notify;
+use tracing::{debug, info};
+
+/// Updates queued per subscriber before its updates are conflated
+pub const DEFAULT_QUEUE_LIMIT: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum BookSide {
+    Bid,
+    Ask,
+}
+
+/// A change to one price level of a book
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BookUpdate {
+    pub symbol: String,
+    pub side: BookSide,
+    pub price: u64,
+    /// Size now resting at the level, 0 once the level is gone
+    pub size: u64,
+}
+
+impl BookUpdate {
+    fn level(&self) -> (String, BookSide, u64) {
+        (self.symbol.clone(), self.side, self.price)
+    }
+}
+
+/// Conflation counters of one subscriber
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConflationStats {
+    pub session: String,
+    pub symbols: Vec<String>,
+    /// Updates published to the subscriber
+    pub published: u64,
+    /// Updates handed to the subscriber
+    pub delivered: u64,
+    /// Updates replaced by a later update of the same level before delivery
+    pub conflated: u64,
+    /// Times the subscriber fell behind and conflation started
+    pub episodes: u64,
+    /// Whether the subscriber is behind right now
+    pub conflating: bool,
+    /// Updates or levels waiting for delivery
+    pub queued: usize,
+}
+
+#[derive(Default)]
+struct Queue {
+    pending: VecDeque<BookUpdate>,
+    /// Latest state per level while conflating
+    levels: BTreeMap<(String, BookSide, u64), BookUpdate>,
+    conflating: bool,
+    stats: ConflationStats,
+}
+
+impl Queue {
+    fn coalesce(&mut self, update: BookUpdate) {
+        if self.levels.insert(update.level(), update).is_some() {
+            self.stats.conflated += 1;
+        }
+    }
+}
+
+/// One session's market data feed. Updates are delivered in order while the
+/// consumer keeps up; once `queue_limit` updates are waiting, the queue
+/// collapses to the latest state of each price level until it is drained,
+/// so a slow consumer sees a current book instead of being disconnected or
+/// buffering without bound.
+pub struct Subscriber {
+    session: String,
+    symbols: Mutex<BTreeSet<String>>,
+    queue: Mutex<Queue>,
+    queue_limit: usize,
+    ready: Notify,
+}
+
+impl Subscriber {
+    fn new(session: &str, queue_limit: usize) -> Self {
+        Self {
+            session: session.to_string(),
+            symbols: Mutex::new(BTreeSet::new()),
+            queue: Mutex::new(Queue::default()),
+            queue_limit: queue_limit.max(1),
+            ready: Notify::new(),
+        }
+    }
+
+    fn push(&self, update: BookUpdate) {
+        let mut queue = self.queue.lock();
+        queue.stats.published += 1;
+        if queue.conflating {
+            queue.coalesce(update);
+        } else if queue.pending.len() < self.queue_limit {
+            queue.pending.push_back(update);
+        } else {
+            debug!(session = %self.session, "Market data subscriber behind, conflating");
+            queue.conflating = true;
+            queue.stats.episodes += 1;
+            let pending = std::mem::take(&mut queue.pending);
+            for queued in pending {
+                queue.coalesce(queued);
+            }
+            queue.coalesce(update);
+        }
+        drop(queue);
+        self.ready.notify_one();
+    }
+
+    /// Takes every waiting update. After conflation that is one update per
+    /// changed level, ordered by symbol, side and price.
+    pub fn drain(&self) -> Vec<BookUpdate> {
+        let mut queue = self.queue.lock();
+        let batch: Vec<BookUpdate> = if queue.conflating {
+            queue.conflating = false;
+            std::mem::take(&mut queue.levels).into_values().collect()
+        } else {
+            queue.pending.drain(..).collect()
+        };
+        queue.stats.delivered += batch.len() as u64;
+        batch
+    }
+
+    /// Waits for updates and takes them
+    pub async fn recv(&self) -> Vec<BookUpdate> {
+        loop {
+            let batch = self.drain();
+            if !batch.is_empty() {
+                return batch;
+            }
+            self.ready.notified().await;
+        }
+    }
+
+    pub fn stats(&self) -> ConflationStats {
+        let queue = self.queue.lock();
+        ConflationStats {
+            session: self.session.clone(),
+            symbols: self.symbols.lock().iter().cloned().collect(),
+            conflating: queue.conflating,
+            queued: if queue.conflating { queue.levels.len() } else { queue.pending.len() },
+            ..queue.stats.clone()
+        }
+    }
+}
+
+/// Fans book updates out to the sessions subscribed to their symbol, and
+/// keeps the resulting book of each symbol for depth snapshots
+pub struct MarketDataPublisher {
+    subscribers: DashMap<String, Arc<Subscriber>>,
+    books: DashMap<String, DepthBook>,
+    queue_limit: usize,
+}
+
+impl Default for MarketDataPublisher {
+    fn default() -> Self {
+        Self::new(DEFAULT_QUEUE_LIMIT)
+    }
+}
+
+impl MarketDataPublisher {
+    pub fn new(queue_limit: usize) -> Self {
+        Self {
+            subscribers: DashMap::new(),
+            books: DashMap::new(),
+            queue_limit,
+        }
+    }
+
+    /// Subscribes `session` to `symbol`, returning its feed
+    pub fn subscribe(&self, session: &str, symbol: &str) -> Arc<Subscriber> {
+        let subscriber = self
+            .subscribers
+            .entry(session.to_string())
+            .or_insert_with(|| Arc::new(Subscriber::new(session, self.queue_limit)))
+            .clone();
+        if subscriber.symbols.lock().insert(symbol.to_string()) {
+            info!(session, symbol, "Market data subscription added");
+        }
+        subscriber
+    }
+
+    /// Drops every subscription of `session`. Returns false if it had none.
+    pub fn unsubscribe(&self, session: &str) -> bool {
+        self.subscribers.remove(session).is_some()
+    }
+
+    pub fn publish(&self, update: &BookUpdate) {
+        // Held until the update is queued, so a snapshot's sequence matches
+        // the updates subscribers have been sent
+        let mut book = self.books.entry(update.symbol.clone()).or_default();
+        book.apply(update);
+        for subscriber in self.subscribers.iter() {
+            if subscriber.symbols.lock().contains(&update.symbol) {
+                subscriber.push(update.clone());
+            }
+        }
+    }
+
+    /// The top `depth` levels of the book of `symbol`, every level without
+    pub fn depth(&self, symbol: &str, depth: Option<usize>) -> Option<DepthSnapshot> {
+        self.books.get(symbol).map(|book| book.snapshot(symbol, depth))
+    }
+
+    /// Every level of every book, by symbol
+    pub fn depths(&self) -> Vec<DepthSnapshot> {
+        let mut depths: Vec<_> = self.books.iter().map(|book| book.snapshot(book.key(), None)).collect();
+        depths.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        depths
+    }
+
+    /// Conflation counters of every subscriber, by session
+    pub fn stats(&self) -> Vec<ConflationStats> {
+        let mut stats: Vec<ConflationStats> = self.subscribers.iter().map(|s| s.stats()).collect();
+        stats.sort_by(|a, b| a.session.cmp(&b.session));
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(price: u64, size: u64) -> BookUpdate {
+        BookUpdate {
+            symbol: "AAPL".into(),
+            side: BookSide::Bid,
+            price,
+            size,
+        }
+    }
+
+    #[test]
+    fn test_in_order_while_keeping_up() {
+        let publisher = MarketDataPublisher::new(4);
+        let feed = publisher.subscribe("MM1", "AAPL");
+        publisher.publish(&update(100, 1));
+        publisher.publish(&update(100, 2));
+        // Other symbols are not delivered
+        publisher.publish(&BookUpdate { symbol: "MSFT".into(), ..update(1, 1) });
+
+        assert_eq!(feed.drain(), vec![update(100, 1), update(100, 2)]);
+        let stats = feed.stats();
+        assert_eq!((stats.published, stats.delivered, stats.conflated), (2, 2, 0));
+    }
+
+    #[test]
+    fn test_conflates_when_behind() {
+        let publisher = MarketDataPublisher::new(4);
+        let slow = publisher.subscribe("SLOW", "AAPL");
+        let fast = publisher.subscribe("FAST", "AAPL");
+
+        for size in 1..=10 {
+            publisher.publish(&update(100 + size % 2, size));
+            fast.drain();
+        }
+        let stats = slow.stats();
+        assert!(stats.conflating);
+        assert_eq!(stats.episodes, 1);
+        assert_eq!(stats.queued, 2);
+        assert_eq!(stats.conflated, 8);
+
+        // Latest state of each level
+        assert_eq!(slow.drain(), vec![update(100, 10), update(101, 9)]);
+        assert!(!slow.stats().conflating);
+        assert_eq!(fast.stats().delivered, 10);
+        assert_eq!(fast.stats().episodes, 0);
+
+        // Back to in-order delivery once caught up
+        publisher.publish(&update(100, 11));
+        assert_eq!(slow.drain(), vec![update(100, 11)]);
+    }
+
+    #[test]
+    fn test_depth() {
+        let publisher = MarketDataPublisher::default();
+        assert!(publisher.depth("AAPL", None).is_none());
+        publisher.publish(&update(100, 1));
+        publisher.publish(&update(101, 2));
+
+        let snapshot = publisher.depth("AAPL", Some(1)).unwrap();
+        assert_eq!(snapshot.sequence, 2);
+        assert_eq!(snapshot.bids.len(), 1);
+        assert_eq!(snapshot.bids[0].price, 101);
+    }
+
+    #[tokio::test]
+    async fn test_recv_waits_for_updates() {
+        let publisher = Arc::new(MarketDataPublisher::default());
+        let feed = publisher.subscribe("MM1", "AAPL");
+        let waiter = tokio::spawn(async move { feed.recv().await });
+        tokio::task::yield_now().await;
+        publisher.publish(&update(100, 1));
+        assert_eq!(waiter.await.unwrap(), vec![update(100, 1)]);
+    }
+}
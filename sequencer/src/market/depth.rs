@@ -0,0 +1,161 @@
+// src/market/depth.rs
+
+use super::data::{BookSide, BookUpdate};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+/// Levels per side covered by the book checksum
+pub const CHECKSUM_LEVELS: usize = 25;
+
+/// Size resting at one price
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Level {
+    pub price: u64,
+    pub size: u64,
+}
+
+/// The book of one symbol at a point in its update sequence
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DepthSnapshot {
+    pub symbol: String,
+    /// Book updates applied so far; a consumer resumes the feed after this one
+    pub sequence: u64,
+    /// Best (highest) price first
+    pub bids: Vec<Level>,
+    /// Best (lowest) price first
+    pub asks: Vec<Level>,
+    /// CRC32 of the top levels of the full book, see [`checksum`]
+    pub checksum: u32,
+}
+
+/// CRC32 of the top [`CHECKSUM_LEVELS`] of each side, taken over the
+/// string `bid1price:bid1size:ask1price:ask1size:bid2price:...` with the
+/// levels interleaved best first and a side skipped once it runs out.
+/// Consumers maintaining a book from the feed compute the same value to
+/// detect a missed or misapplied update.
+pub fn checksum(bids: &[Level], asks: &[Level]) -> u32 {
+    let mut fields = Vec::new();
+    for i in 0..CHECKSUM_LEVELS {
+        for level in [bids.get(i), asks.get(i)].into_iter().flatten() {
+            fields.push(level.price.to_string());
+            fields.push(level.size.to_string());
+        }
+    }
+    crc32fast::hash(fields.join(":").as_bytes())
+}
+
+/// Price levels of one symbol, rebuilt from its book updates
+#[derive(Debug, Default)]
+pub struct DepthBook {
+    bids: BTreeMap<Reverse<u64>, u64>,
+    asks: BTreeMap<u64, u64>,
+    sequence: u64,
+}
+
+impl DepthBook {
+    /// Sets the level `update` describes, removing it at size 0
+    pub fn apply(&mut self, update: &BookUpdate) {
+        match (update.side, update.size) {
+            (BookSide::Bid, 0) => {
+                self.bids.remove(&Reverse(update.price));
+            }
+            (BookSide::Bid, size) => {
+                self.bids.insert(Reverse(update.price), size);
+            }
+            (BookSide::Ask, 0) => {
+                self.asks.remove(&update.price);
+            }
+            (BookSide::Ask, size) => {
+                self.asks.insert(update.price, size);
+            }
+        }
+        self.sequence += 1;
+    }
+
+    fn bids(&self, depth: usize) -> Vec<Level> {
+        self.bids
+            .iter()
+            .take(depth)
+            .map(|(Reverse(price), size)| Level { price: *price, size: *size })
+            .collect()
+    }
+
+    fn asks(&self, depth: usize) -> Vec<Level> {
+        self.asks
+            .iter()
+            .take(depth)
+            .map(|(price, size)| Level { price: *price, size: *size })
+            .collect()
+    }
+
+    /// The top `depth` levels of each side, every level without. The
+    /// checksum always covers the full book.
+    pub fn snapshot(&self, symbol: &str, depth: Option<usize>) -> DepthSnapshot {
+        let depth = depth.unwrap_or(usize::MAX);
+        DepthSnapshot {
+            symbol: symbol.to_string(),
+            sequence: self.sequence,
+            bids: self.bids(depth),
+            asks: self.asks(depth),
+            checksum: checksum(&self.bids(CHECKSUM_LEVELS), &self.asks(CHECKSUM_LEVELS)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(side: BookSide, price: u64, size: u64) -> BookUpdate {
+        BookUpdate {
+            symbol: "AAPL".into(),
+            side,
+            price,
+            size,
+        }
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let mut book = DepthBook::default();
+        for (side, price, size) in [
+            (BookSide::Bid, 99, 10),
+            (BookSide::Bid, 100, 5),
+            (BookSide::Bid, 98, 1),
+            (BookSide::Ask, 102, 7),
+            (BookSide::Ask, 101, 3),
+            (BookSide::Bid, 98, 0),
+        ] {
+            book.apply(&update(side, price, size));
+        }
+
+        let full = book.snapshot("AAPL", None);
+        assert_eq!(full.sequence, 6);
+        assert_eq!(full.bids, vec![Level { price: 100, size: 5 }, Level { price: 99, size: 10 }]);
+        assert_eq!(full.asks, vec![Level { price: 101, size: 3 }, Level { price: 102, size: 7 }]);
+        assert_eq!(full.checksum, crc32fast::hash(b"100:5:101:3:99:10:102:7"));
+
+        let top = book.snapshot("AAPL", Some(1));
+        assert_eq!((top.bids.len(), top.asks.len()), (1, 1));
+        assert_eq!(top.checksum, full.checksum);
+    }
+
+    #[test]
+    fn test_checksum_detects_divergence() {
+        let mut book = DepthBook::default();
+        let mut replica = DepthBook::default();
+        for price in 1..=40 {
+            book.apply(&update(BookSide::Ask, 100 + price, price));
+            replica.apply(&update(BookSide::Ask, 100 + price, price));
+        }
+        assert_eq!(book.snapshot("AAPL", None).checksum, replica.snapshot("AAPL", None).checksum);
+
+        // Levels beyond the checksummed depth do not count
+        replica.apply(&update(BookSide::Ask, 200, 1));
+        assert_eq!(book.snapshot("AAPL", None).checksum, replica.snapshot("AAPL", None).checksum);
+
+        replica.apply(&update(BookSide::Ask, 101, 2));
+        assert_ne!(book.snapshot("AAPL", None).checksum, replica.snapshot("AAPL", None).checksum);
+    }
+}
@@ -0,0 +1,193 @@
+// src/market/feeds.rs
+
+use super::reference_price::{PriceFeed, PriceObservation};
+use romer_common::utils::rpc::{self, RpcCallError};
+use dashmap::DashMap;
+use romer_common::utils::clock::SharedClock;
+use serde::Deserialize;
+use serde_json::json;
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::net::TcpStream;
+use tracing::{debug, warn};
+
+/// Longest wait between reconnects of a streaming feed
+const MAX_RECONNECT_BACKOFF: Duration = Duration::from_secs(30);
+
+/// One price update as sent by external feeds
+#[derive(Debug, Deserialize)]
+struct PriceUpdate {
+    symbol: String,
+    price: f64,
+}
+
+/// Latest prices of an external feed, stamped when they arrived
+struct FeedPrices {
+    name: String,
+    prices: DashMap<String, PriceObservation>,
+    clock: SharedClock,
+}
+
+impl FeedPrices {
+    fn new(name: &str, clock: SharedClock) -> Self {
+        Self {
+            name: name.to_string(),
+            prices: DashMap::new(),
+            clock,
+        }
+    }
+
+    fn update(&self, update: PriceUpdate) {
+        if !update.price.is_finite() || update.price <= 0.0 {
+            warn!(feed = %self.name, symbol = %update.symbol, price = update.price, "Ignoring invalid price");
+            return;
+        }
+        self.prices.insert(
+            update.symbol,
+            PriceObservation {
+                price: update.price,
+                observed_at: self.clock.now(),
+            },
+        );
+    }
+}
+
+/// Polls a JSON-RPC endpoint for prices. Each symbol is requested with
+/// `{"symbol": ..}` and the result is expected as `{"symbol": .., "price": ..}`.
+pub struct RpcPriceFeed {
+    prices: FeedPrices,
+    address: String,
+    method: String,
+    symbols: Vec<String>,
+}
+
+impl RpcPriceFeed {
+    pub fn new(name: &str, address: &str, method: &str, symbols: Vec<String>, clock: SharedClock) -> Self {
+        Self {
+            prices: FeedPrices::new(name, clock),
+            address: address.to_string(),
+            method: method.to_string(),
+            symbols,
+        }
+    }
+
+    /// Polls every symbol each `interval` until the process exits
+    pub fn spawn(self: &Arc<Self>, interval: Duration) {
+        let feed = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                for symbol in &feed.symbols {
+                    match feed.fetch(symbol).await {
+                        Ok(update) => feed.prices.update(update),
+                        Err(e) => debug!(feed = %feed.prices.name, symbol, "Price poll failed: {}", e),
+                    }
+                }
+            }
+        });
+    }
+
+    async fn fetch(&self, symbol: &str) -> Result<PriceUpdate, RpcCallError> {
+        let result = rpc::call(&self.address, &self.method, json!({ "symbol": symbol })).await?;
+        serde_json::from_value(result).map_err(|e| RpcCallError::Malformed(e.to_string()))
+    }
+}
+
+impl PriceFeed for RpcPriceFeed {
+    fn name(&self) -> &str {
+        &self.prices.name
+    }
+
+    fn latest(&self, symbol: &str) -> Option<PriceObservation> {
+        self.prices.prices.get(symbol).map(|observation| *observation)
+    }
+}
+
+/// Subscribes to a pushed feed of newline delimited JSON updates,
+/// `{"symbol": .., "price": ..}`, reconnecting with backoff when the
+/// connection drops
+pub struct StreamPriceFeed {
+    prices: FeedPrices,
+    address: String,
+}
+
+impl StreamPriceFeed {
+    pub fn new(name: &str, address: &str, clock: SharedClock) -> Self {
+        Self {
+            prices: FeedPrices::new(name, clock),
+            address: address.to_string(),
+        }
+    }
+
+    /// Keeps the subscription up until the process exits
+    pub fn spawn(self: &Arc<Self>) {
+        let feed = self.clone();
+        tokio::spawn(async move {
+            let mut backoff = Duration::from_secs(1);
+            loop {
+                match feed.subscribe().await {
+                    Ok(()) => warn!(feed = %feed.prices.name, "Price stream closed"),
+                    Err(e) => warn!(feed = %feed.prices.name, "Price stream failed: {}", e),
+                }
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_RECONNECT_BACKOFF);
+            }
+        });
+    }
+
+    async fn subscribe(&self) -> io::Result<()> {
+        let stream = TcpStream::connect(&self.address).await?;
+        let mut lines = BufReader::new(stream).lines();
+        while let Some(line) = lines.next_line().await? {
+            match serde_json::from_str::<PriceUpdate>(&line) {
+                Ok(update) => self.prices.update(update),
+                Err(e) => debug!(feed = %self.prices.name, "Ignoring malformed update: {}", e),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PriceFeed for StreamPriceFeed {
+    fn name(&self) -> &str {
+        &self.prices.name
+    }
+
+    fn latest(&self, symbol: &str) -> Option<PriceObservation> {
+        self.prices.prices.get(symbol).map(|observation| *observation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use romer_common::utils::clock::system_clock;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_stream_feed() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let feed = Arc::new(StreamPriceFeed::new("stream", &address, system_clock()));
+        feed.spawn();
+
+        let (mut socket, _) = listener.accept().await.unwrap();
+        socket
+            .write_all(b"{\"symbol\":\"AAPL\",\"price\":101.5}\nnot json\n{\"symbol\":\"MSFT\",\"price\":-1}\n")
+            .await
+            .unwrap();
+
+        for _ in 0..50 {
+            if feed.latest("AAPL").is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(feed.latest("AAPL").unwrap().price, 101.5);
+        assert!(feed.latest("MSFT").is_none());
+    }
+}
@@ -0,0 +1,385 @@
+// src/market/fees.rs
+
+use crate::events::bus::{EventBus, EventSink};
+use crate::events::types::SequencerEvent;
+use parking_lot::Mutex;
+use romer_common::utils::clock::SharedClock;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::info;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum FeeError {
+    #[error("Invalid fee schedule: {0}")]
+    InvalidSchedule(String),
+}
+
+/// Fees of firms that traded at least `min_volume` notional in the
+/// previous epoch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FeeTier {
+    pub name: String,
+    pub min_volume: u128,
+    /// Charged on fills against resting orders; negative pays a rebate
+    pub maker_bps: i32,
+    /// Charged on fills taking liquidity
+    pub taker_bps: u32,
+}
+
+/// A firm moving to another tier when an epoch closes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TierChange {
+    pub firm: String,
+    /// The epoch that closed; the new tier applies from the next one
+    pub epoch: u64,
+    pub previous: String,
+    pub tier: String,
+    /// Notional the firm traded over the epoch
+    pub volume: u128,
+}
+
+impl TierChange {
+    /// Headline and text of the FIX News announcing the change
+    pub fn notice(&self) -> (String, String) {
+        (
+            "Fee tier changed".to_string(),
+            format!(
+                "{} moves from tier {} to {} from epoch {} on volume {}",
+                self.firm,
+                self.previous,
+                self.tier,
+                self.epoch + 1,
+                self.volume
+            ),
+        )
+    }
+}
+
+/// A firm's tier and its trading in the current epoch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FirmFees {
+    pub firm: String,
+    pub tier: String,
+    pub volume: u128,
+    pub maker_volume: u128,
+    /// Fees charged less rebates paid
+    pub net_fees: i128,
+}
+
+#[derive(Default)]
+struct Epoch {
+    volume: u128,
+    maker_volume: u128,
+    net_fees: i128,
+}
+
+struct State {
+    epoch: u64,
+    /// Index into the tiers, by firm; firms not listed are in the first
+    tiers: HashMap<String, usize>,
+    trading: HashMap<String, Epoch>,
+    /// SenderCompIDs seen trading for each firm, told of tier changes
+    senders: HashMap<String, BTreeSet<String>>,
+    /// Tier changes not yet delivered, by SenderCompID
+    notices: HashMap<String, Vec<TierChange>>,
+}
+
+/// Charges maker and taker fees on fills at each firm's tier, tracks
+/// firms' traded notional over an epoch, and reassigns tiers by that
+/// volume when the epoch closes. Changes are published as
+/// `FeeTierChanged` events for drop copy and held for delivery to the
+/// firm's sessions as FIX News.
+pub struct FeeEngine {
+    /// Ascending by `min_volume`, the first starting at zero
+    tiers: Vec<FeeTier>,
+    /// SenderCompID to firm; unmapped senders are their own firm
+    firms: BTreeMap<String, String>,
+    state: Mutex<State>,
+    events: EventBus,
+    clock: SharedClock,
+}
+
+impl FeeEngine {
+    pub fn new(
+        tiers: Vec<FeeTier>,
+        firms: BTreeMap<String, String>,
+        events: EventBus,
+        clock: SharedClock,
+    ) -> Result<Self, FeeError> {
+        validate(&tiers)?;
+        Ok(Self {
+            tiers,
+            firms,
+            state: Mutex::new(State {
+                epoch: 0,
+                tiers: HashMap::new(),
+                trading: HashMap::new(),
+                senders: HashMap::new(),
+                notices: HashMap::new(),
+            }),
+            events,
+            clock,
+        })
+    }
+
+    fn firm(&self, sender_comp_id: &str) -> String {
+        self.firms
+            .get(sender_comp_id)
+            .cloned()
+            .unwrap_or_else(|| sender_comp_id.to_string())
+    }
+
+    /// Charges both sides of a fill, the `maker` at its tier's maker rate
+    /// and the other side at the taker rate. Without a known maker both
+    /// sides pay the taker rate. Returns the buyer's and seller's fees.
+    pub fn record_fill(
+        &self,
+        price: u64,
+        quantity: u64,
+        buyer: &str,
+        seller: &str,
+        maker: Option<&str>,
+    ) -> (i128, i128) {
+        let notional = price as u128 * quantity as u128;
+        let mut state = self.state.lock();
+        let mut charge = |sender_comp_id: &str| {
+            let firm = self.firm(sender_comp_id);
+            let tier = &self.tiers[state.tiers.get(&firm).copied().unwrap_or(0)];
+            let is_maker = maker == Some(sender_comp_id);
+            let bps = if is_maker {
+                tier.maker_bps as i128
+            } else {
+                tier.taker_bps as i128
+            };
+            let fee = notional as i128 * bps / 10_000;
+
+            let epoch = state.trading.entry(firm.clone()).or_default();
+            epoch.volume += notional;
+            if is_maker {
+                epoch.maker_volume += notional;
+            }
+            epoch.net_fees += fee;
+            state
+                .senders
+                .entry(firm)
+                .or_default()
+                .insert(sender_comp_id.to_string());
+            fee
+        };
+        (charge(buyer), charge(seller))
+    }
+
+    /// Closes the epoch, moving every firm to the highest tier its volume
+    /// qualifies for, and starts the next one from zero
+    pub fn close_epoch(&self) -> Vec<TierChange> {
+        let mut state = self.state.lock();
+        let epoch = state.epoch;
+        let trading = std::mem::take(&mut state.trading);
+        let firms: BTreeSet<String> = trading.keys().chain(state.tiers.keys()).cloned().collect();
+
+        let mut changes = Vec::new();
+        for firm in firms {
+            let volume = trading.get(&firm).map_or(0, |epoch| epoch.volume);
+            let tier = self
+                .tiers
+                .iter()
+                .rposition(|tier| tier.min_volume <= volume)
+                .unwrap_or(0);
+            let previous = state.tiers.get(&firm).copied().unwrap_or(0);
+            if tier == 0 {
+                state.tiers.remove(&firm);
+            } else {
+                state.tiers.insert(firm.clone(), tier);
+            }
+            if tier == previous {
+                continue;
+            }
+            let change = TierChange {
+                firm: firm.clone(),
+                epoch,
+                previous: self.tiers[previous].name.clone(),
+                tier: self.tiers[tier].name.clone(),
+                volume,
+            };
+            for sender_comp_id in state.senders.get(&firm).cloned().unwrap_or_default() {
+                state.notices.entry(sender_comp_id).or_default().push(change.clone());
+            }
+            changes.push(change);
+        }
+        state.epoch += 1;
+        drop(state);
+
+        info!(epoch, changes = changes.len(), "Closed fee tier epoch");
+        for change in &changes {
+            self.events.publish(SequencerEvent::FeeTierChanged {
+                firm: change.firm.clone(),
+                epoch: change.epoch,
+                previous: change.previous.clone(),
+                tier: change.tier.clone(),
+                volume: change.volume,
+                at: self.clock.now(),
+            });
+        }
+        changes
+    }
+
+    /// Tier changes not yet delivered to `sender_comp_id`, removing them
+    pub fn take_notices(&self, sender_comp_id: &str) -> Vec<TierChange> {
+        self.state.lock().notices.remove(sender_comp_id).unwrap_or_default()
+    }
+
+    pub fn tiers(&self) -> &[FeeTier] {
+        &self.tiers
+    }
+
+    /// Every firm with a tier above the first or trading this epoch
+    pub fn firms(&self) -> Vec<FirmFees> {
+        let state = self.state.lock();
+        let firms: BTreeSet<&String> = state.trading.keys().chain(state.tiers.keys()).collect();
+        firms
+            .into_iter()
+            .map(|firm| {
+                let trading = state.trading.get(firm);
+                FirmFees {
+                    firm: firm.clone(),
+                    tier: self.tiers[state.tiers.get(firm).copied().unwrap_or(0)].name.clone(),
+                    volume: trading.map_or(0, |epoch| epoch.volume),
+                    maker_volume: trading.map_or(0, |epoch| epoch.maker_volume),
+                    net_fees: trading.map_or(0, |epoch| epoch.net_fees),
+                }
+            })
+            .collect()
+    }
+}
+
+impl EventSink for Arc<FeeEngine> {
+    fn name(&self) -> &str {
+        "fees"
+    }
+
+    fn handle(&mut self, event: &SequencerEvent) {
+        if let SequencerEvent::Match {
+            price,
+            quantity,
+            buyer,
+            seller,
+            maker,
+            ..
+        } = event
+        {
+            self.record_fill(*price, *quantity, buyer, seller, maker.as_deref());
+        }
+    }
+}
+
+fn validate(tiers: &[FeeTier]) -> Result<(), FeeError> {
+    let invalid = |reason: String| Err(FeeError::InvalidSchedule(reason));
+    match tiers.first() {
+        None => return invalid("no tiers".into()),
+        Some(first) if first.min_volume != 0 => return invalid(format!("first tier {} must start at 0", first.name)),
+        Some(_) => {}
+    }
+    for pair in tiers.windows(2) {
+        if pair[1].min_volume <= pair[0].min_volume {
+            return invalid(format!(
+                "tier {} must require more volume than {}",
+                pair[1].name, pair[0].name
+            ));
+        }
+    }
+    for tier in tiers {
+        // A rebate larger than the taker fee would pay out more than it takes in
+        if tier.maker_bps < 0 && tier.maker_bps.unsigned_abs() > tier.taker_bps {
+            return invalid(format!("tier {} rebates more than it charges takers", tier.name));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use romer_common::utils::clock::system_clock;
+
+    fn tiers() -> Vec<FeeTier> {
+        vec![
+            FeeTier {
+                name: "base".into(),
+                min_volume: 0,
+                maker_bps: 2,
+                taker_bps: 5,
+            },
+            FeeTier {
+                name: "gold".into(),
+                min_volume: 1_000_000,
+                maker_bps: -1,
+                taker_bps: 3,
+            },
+        ]
+    }
+
+    fn engine(events: EventBus) -> FeeEngine {
+        let firms = [
+            ("MM1".to_string(), "ACME".to_string()),
+            ("MM2".to_string(), "ACME".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        FeeEngine::new(tiers(), firms, events, system_clock()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_tiers_follow_epoch_volume() {
+        let events = EventBus::default();
+        let mut drop_copy = events.subscribe();
+        let fees = engine(events);
+
+        // 600_000 notional each, ACME making both times
+        assert_eq!(fees.record_fill(100, 6_000, "MM1", "HF1", Some("MM1")), (120, 300));
+        fees.record_fill(100, 6_000, "HF2", "MM2", Some("MM2"));
+        let acme = &fees.firms()[0];
+        assert_eq!(
+            (acme.volume, acme.maker_volume, acme.net_fees),
+            (1_200_000, 1_200_000, 240)
+        );
+
+        let changes = fees.close_epoch();
+        assert_eq!(changes.len(), 1);
+        assert_eq!((changes[0].firm.as_str(), changes[0].tier.as_str()), ("ACME", "gold"));
+        match &*drop_copy.recv().await.unwrap() {
+            SequencerEvent::FeeTierChanged { firm, tier, .. } => {
+                assert_eq!((firm.as_str(), tier.as_str()), ("ACME", "gold"))
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+
+        // Both of the firm's senders are told once
+        assert_eq!(fees.take_notices("MM2").len(), 1);
+        assert!(fees.take_notices("MM2").is_empty());
+        assert_eq!(fees.take_notices("MM1")[0].notice().0, "Fee tier changed");
+        assert!(fees.take_notices("HF1").is_empty());
+
+        // Gold makers earn a rebate; without a known maker both sides take
+        assert_eq!(fees.record_fill(100, 1_000, "MM1", "HF1", Some("MM1")), (-10, 50));
+        assert_eq!(fees.record_fill(100, 1_000, "MM1", "HF1", None), (30, 50));
+
+        // A quiet epoch drops the firm back
+        let changes = fees.close_epoch();
+        assert_eq!(changes[0].tier, "base");
+        assert!(fees.close_epoch().is_empty());
+    }
+
+    #[test]
+    fn test_schedule_validation() {
+        let mut unordered = tiers();
+        unordered[1].min_volume = 0;
+        assert!(FeeEngine::new(unordered, BTreeMap::new(), EventBus::default(), system_clock()).is_err());
+        let mut generous = tiers();
+        generous[1].maker_bps = -4;
+        assert!(FeeEngine::new(generous, BTreeMap::new(), EventBus::default(), system_clock()).is_err());
+        assert!(FeeEngine::new(Vec::new(), BTreeMap::new(), EventBus::default(), system_clock()).is_err());
+    }
+}
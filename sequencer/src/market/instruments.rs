@@ -0,0 +1,256 @@
+// src/market/instruments.rs
+
+use crate::events::bus::EventBus;
+use crate::events::types::SequencerEvent;
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use romer_common::types::instrument::{InstrumentError, InstrumentOverride, InstrumentParameters};
+use romer_common::utils::clock::SharedClock;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::{info, warn};
+
+#[derive(Error, Debug)]
+pub enum OverrideError {
+    #[error(transparent)]
+    Invalid(#[from] InstrumentError),
+
+    #[error("Activation height {activation_height} has passed, the next block is {height}")]
+    Retroactive { activation_height: u64, height: u64 },
+
+    #[error("Failed to journal instrument override: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// A change to an instrument's parameters and the block it applies from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduledOverride {
+    pub symbol: String,
+    pub overrides: InstrumentOverride,
+    pub activation_height: u64,
+    /// Who made the change: `admin` or the governance proposal
+    pub source: String,
+    pub scheduled_at: DateTime<Utc>,
+}
+
+/// Per-instrument matching parameters: the defaults, changed by overrides
+/// scheduled through the admin API or governance. Overrides apply from
+/// their activation height in the order they activate, are appended to a
+/// journal replayed at startup, and are published for the audit log.
+pub struct InstrumentRegistry {
+    defaults: InstrumentParameters,
+    /// Ordered by activation height, then scheduling order
+    overrides: RwLock<Vec<ScheduledOverride>>,
+    journal: Option<PathBuf>,
+    events: EventBus,
+    clock: SharedClock,
+}
+
+impl InstrumentRegistry {
+    pub fn new(defaults: InstrumentParameters, events: EventBus, clock: SharedClock) -> Self {
+        Self {
+            defaults,
+            overrides: RwLock::new(Vec::new()),
+            journal: None,
+            events,
+            clock,
+        }
+    }
+
+    /// Appends scheduled overrides to the JSON lines file at `path`,
+    /// replaying those already there
+    pub fn with_journal(mut self, path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        if path.exists() {
+            let mut overrides = self.overrides.write();
+            for line in BufReader::new(File::open(path)?).lines() {
+                match serde_json::from_str::<ScheduledOverride>(&line?) {
+                    Ok(scheduled) => insert(&mut overrides, scheduled),
+                    Err(e) => warn!(error = %e, "Skipping undecodable instrument override"),
+                }
+            }
+        }
+        self.journal = Some(path.to_path_buf());
+        Ok(self)
+    }
+
+    /// Schedules `overrides` of `symbol` from `activation_height`, which
+    /// may not precede `height`, the next block
+    pub fn schedule(
+        &self,
+        symbol: &str,
+        overrides: InstrumentOverride,
+        activation_height: u64,
+        source: &str,
+        height: u64,
+    ) -> Result<ScheduledOverride, OverrideError> {
+        overrides.validate()?;
+        if activation_height < height {
+            return Err(OverrideError::Retroactive {
+                activation_height,
+                height,
+            });
+        }
+        let scheduled = ScheduledOverride {
+            symbol: symbol.to_string(),
+            overrides,
+            activation_height,
+            source: source.to_string(),
+            scheduled_at: self.clock.now(),
+        };
+
+        if let Some(path) = &self.journal {
+            let mut line = serde_json::to_vec(&scheduled).map_err(io::Error::other)?;
+            line.push(b'\n');
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)?
+                .write_all(&line)?;
+        }
+        insert(&mut self.overrides.write(), scheduled.clone());
+        info!(symbol, activation_height, source, "Instrument override scheduled");
+        self.events.publish(SequencerEvent::InstrumentOverrideScheduled {
+            symbol: scheduled.symbol.clone(),
+            overrides: scheduled.overrides.clone(),
+            activation_height,
+            source: scheduled.source.clone(),
+            at: scheduled.scheduled_at,
+        });
+        Ok(scheduled)
+    }
+
+    /// The parameters `symbol` trades under at `height`
+    pub fn parameters(&self, symbol: &str, height: u64) -> InstrumentParameters {
+        let mut parameters = self.defaults.clone();
+        for scheduled in self
+            .overrides
+            .read()
+            .iter()
+            .take_while(|scheduled| scheduled.activation_height <= height)
+            .filter(|scheduled| scheduled.symbol == symbol)
+        {
+            scheduled.overrides.apply(&mut parameters);
+        }
+        parameters
+    }
+
+    /// Every scheduled override, of `symbol` only if given
+    pub fn overrides(&self, symbol: Option<&str>) -> Vec<ScheduledOverride> {
+        self.overrides
+            .read()
+            .iter()
+            .filter(|scheduled| symbol.is_none() || symbol == Some(scheduled.symbol.as_str()))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Inserts after every override activating at or before `scheduled`
+fn insert(overrides: &mut Vec<ScheduledOverride>, scheduled: ScheduledOverride) {
+    let at = overrides.partition_point(|existing| existing.activation_height <= scheduled.activation_height);
+    overrides.insert(at, scheduled);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use romer_common::types::instrument::MatchingMode;
+    use romer_common::utils::clock::system_clock;
+
+    fn tick(tick_size: u64) -> InstrumentOverride {
+        InstrumentOverride {
+            tick_size: Some(tick_size),
+            ..InstrumentOverride::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_overrides_apply_from_activation() {
+        let events = EventBus::new(16);
+        let mut audit = events.subscribe();
+        let registry = InstrumentRegistry::new(InstrumentParameters::default(), events, system_clock());
+
+        registry.schedule("AAPL", tick(10_000_000), 20, "admin", 5).unwrap();
+        let batch = InstrumentOverride {
+            matching: Some(MatchingMode::Batch),
+            lot_size: Some(100),
+            ..InstrumentOverride::default()
+        };
+        registry
+            .schedule("AAPL", batch, 10, "governance proposal 3", 5)
+            .unwrap();
+        assert!(matches!(
+            registry.schedule("AAPL", tick(1), 4, "admin", 5),
+            Err(OverrideError::Retroactive { .. })
+        ));
+        assert!(matches!(
+            registry.schedule("AAPL", InstrumentOverride::default(), 9, "admin", 5),
+            Err(OverrideError::Invalid(_))
+        ));
+
+        assert_eq!(registry.parameters("AAPL", 9), InstrumentParameters::default());
+        let at_ten = registry.parameters("AAPL", 10);
+        assert_eq!(
+            (at_ten.matching, at_ten.lot_size, at_ten.tick_size),
+            (MatchingMode::Batch, 100, 1)
+        );
+        assert_eq!(registry.parameters("AAPL", 20).tick_size, 10_000_000);
+        assert_eq!(registry.parameters("MSFT", 20), InstrumentParameters::default());
+        assert_eq!(registry.overrides(Some("AAPL"))[0].activation_height, 10);
+
+        match &*audit.recv().await.unwrap() {
+            SequencerEvent::InstrumentOverrideScheduled {
+                symbol,
+                activation_height,
+                ..
+            } => {
+                assert_eq!((symbol.as_str(), *activation_height), ("AAPL", 20));
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_tick_and_lot_checks() {
+        let registry = InstrumentRegistry::new(InstrumentParameters::default(), EventBus::new(16), system_clock());
+        let overrides = InstrumentOverride {
+            lot_size: Some(10),
+            ..tick(50_000_000)
+        };
+        registry.schedule("AAPL", overrides, 0, "admin", 0).unwrap();
+        let parameters = registry.parameters("AAPL", 0);
+
+        parameters.check_order(Some("150.05"), "20").unwrap();
+        parameters.check_order(None, "10").unwrap();
+        assert!(matches!(
+            parameters.check_order(Some("150.01"), "20"),
+            Err(InstrumentError::OffTick { .. })
+        ));
+        assert!(matches!(
+            parameters.check_order(Some("150.05"), "25"),
+            Err(InstrumentError::OddLot { .. })
+        ));
+        assert!(parameters.check_order(Some("150.05"), "ten").is_err());
+    }
+
+    #[test]
+    fn test_journal_replay() {
+        let path = std::env::temp_dir().join(format!("instrument-overrides-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let open = || {
+            InstrumentRegistry::new(InstrumentParameters::default(), EventBus::new(16), system_clock())
+                .with_journal(&path)
+                .unwrap()
+        };
+
+        open().schedule("AAPL", tick(5), 7, "admin", 0).unwrap();
+        let restarted = open();
+        assert_eq!(restarted.parameters("AAPL", 7).tick_size, 5);
+        assert_eq!(restarted.overrides(None).len(), 1);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
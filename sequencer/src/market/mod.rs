@@ -0,0 +1,12 @@
+pub mod calendar;
+pub mod candles;
+pub mod data;
+pub mod depth;
+pub mod fees;
+pub mod feeds;
+pub mod instruments;
+pub mod obligations;
+pub mod oracle;
+pub mod orders;
+pub mod reference_price;
+pub mod registry;
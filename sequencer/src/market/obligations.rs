@@ -0,0 +1,336 @@
+// src/market/obligations.rs
+
+use crate::events::bus::EventBus;
+use crate::events::types::SequencerEvent;
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use romer_common::utils::clock::{system_clock, SharedClock};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::info;
+
+/// Quoting a designated market maker committed to on an instrument
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Obligation {
+    /// Widest acceptable spread, in basis points of the mid price
+    pub max_spread_bps: u32,
+    /// Smallest acceptable size on each side
+    pub min_size: u64,
+    /// Share of the epoch the quote must meet the spread and size, 0-100
+    pub min_uptime_pct: f64,
+}
+
+/// A market maker's resting two sided quote
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Quote {
+    pub bid_price: u64,
+    pub bid_size: u64,
+    pub ask_price: u64,
+    pub ask_size: u64,
+}
+
+impl Quote {
+    /// Spread in basis points of the mid, `None` for a crossed or zero quote
+    pub fn spread_bps(&self) -> Option<f64> {
+        if self.bid_price == 0 || self.ask_price < self.bid_price {
+            return None;
+        }
+        let mid = (self.bid_price + self.ask_price) as f64 / 2.0;
+        Some((self.ask_price - self.bid_price) as f64 * 10_000.0 / mid)
+    }
+
+    fn meets(&self, obligation: &Obligation) -> bool {
+        self.bid_size >= obligation.min_size
+            && self.ask_size >= obligation.min_size
+            && self
+                .spread_bps()
+                .is_some_and(|spread| spread <= obligation.max_spread_bps as f64)
+    }
+}
+
+/// Quote presence of one maker on one instrument within the epoch
+#[derive(Debug, Clone, Default)]
+struct Presence {
+    quote: Option<Quote>,
+    since: Option<DateTime<Utc>>,
+    quoted_ms: u64,
+    compliant_ms: u64,
+    /// Spread weighted by the milliseconds it was quoted
+    spread_bps_ms: f64,
+}
+
+impl Presence {
+    /// Credits the time since the last change to the current quote
+    fn accrue(&mut self, obligation: &Obligation, now: DateTime<Utc>) {
+        let elapsed = self
+            .since
+            .map(|since| (now - since).num_milliseconds().max(0) as u64)
+            .unwrap_or(0);
+        if let Some(quote) = &self.quote {
+            if let Some(spread) = quote.spread_bps() {
+                self.quoted_ms += elapsed;
+                self.spread_bps_ms += spread * elapsed as f64;
+            }
+            if quote.meets(obligation) {
+                self.compliant_ms += elapsed;
+            }
+        }
+        self.since = Some(now);
+    }
+}
+
+/// How a maker did against one obligation over an epoch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ObligationReport {
+    pub sender_comp_id: String,
+    pub symbol: String,
+    pub obligation: Obligation,
+    /// Share of the epoch quoted two sided, 0-100
+    pub presence_pct: f64,
+    /// Share of the epoch within the spread and size obligation, 0-100
+    pub uptime_pct: f64,
+    /// Time weighted spread while quoting
+    pub avg_spread_bps: Option<f64>,
+    /// Whether the obligation was met, which qualifies the maker for the
+    /// epoch's rewards and fee rebates on the instrument
+    pub met: bool,
+}
+
+/// Obligation reports of a closed epoch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EpochReport {
+    pub epoch: u64,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub reports: Vec<ObligationReport>,
+}
+
+impl EpochReport {
+    /// (SenderCompID, symbol) pairs eligible for rewards and rebates
+    pub fn rebate_eligible(&self) -> Vec<(String, String)> {
+        self.reports
+            .iter()
+            .filter(|report| report.met)
+            .map(|report| (report.sender_comp_id.clone(), report.symbol.clone()))
+            .collect()
+    }
+}
+
+struct State {
+    epoch: u64,
+    epoch_started: DateTime<Utc>,
+    obligations: HashMap<(String, String), Obligation>,
+    presence: HashMap<(String, String), Presence>,
+    last_report: Option<EpochReport>,
+}
+
+/// Tracks designated market makers' quotes against their obligations per
+/// instrument, and reports on each epoch when it is closed. The makers that
+/// met their obligations are announced in an `ObligationEpochClosed` event
+/// for reward and rebate calculation.
+pub struct ObligationMonitor {
+    state: Mutex<State>,
+    events: EventBus,
+    clock: SharedClock,
+}
+
+impl ObligationMonitor {
+    pub fn new(events: EventBus) -> Self {
+        Self::with_clock(events, system_clock())
+    }
+
+    pub fn with_clock(events: EventBus, clock: SharedClock) -> Self {
+        Self {
+            state: Mutex::new(State {
+                epoch: 0,
+                epoch_started: clock.now(),
+                obligations: HashMap::new(),
+                presence: HashMap::new(),
+                last_report: None,
+            }),
+            events,
+            clock,
+        }
+    }
+
+    /// Designates `sender_comp_id` as a market maker on `symbol`, or changes
+    /// its obligation. Time already accrued this epoch is kept.
+    pub fn set_obligation(&self, sender_comp_id: &str, symbol: &str, obligation: Obligation) {
+        let now = self.clock.now();
+        let key = (sender_comp_id.to_string(), symbol.to_string());
+        let mut state = self.state.lock();
+        if let Some(previous) = state.obligations.insert(key.clone(), obligation) {
+            if let Some(presence) = state.presence.get_mut(&key) {
+                presence.accrue(&previous, now);
+            }
+        }
+    }
+
+    /// Removes the designation. Returns false if there was none.
+    pub fn remove_obligation(&self, sender_comp_id: &str, symbol: &str) -> bool {
+        let key = (sender_comp_id.to_string(), symbol.to_string());
+        let mut state = self.state.lock();
+        state.presence.remove(&key);
+        state.obligations.remove(&key).is_some()
+    }
+
+    /// Records the maker's current quote on `symbol`, `None` once pulled.
+    /// Quotes of undesignated makers are ignored.
+    pub fn record_quote(&self, sender_comp_id: &str, symbol: &str, quote: Option<Quote>) {
+        let now = self.clock.now();
+        let key = (sender_comp_id.to_string(), symbol.to_string());
+        let mut state = self.state.lock();
+        let Some(obligation) = state.obligations.get(&key).copied() else {
+            return;
+        };
+        let presence = state.presence.entry(key).or_default();
+        presence.accrue(&obligation, now);
+        presence.quote = quote;
+    }
+
+    /// Closes the current epoch, reporting every obligation, and starts the
+    /// next one. Resting quotes carry over.
+    pub fn close_epoch(&self) -> EpochReport {
+        let now = self.clock.now();
+        let mut state = self.state.lock();
+        let epoch_ms = (now - state.epoch_started).num_milliseconds().max(1) as f64;
+
+        let mut reports = Vec::with_capacity(state.obligations.len());
+        let obligations: Vec<_> = state.obligations.iter().map(|(k, o)| (k.clone(), *o)).collect();
+        for ((sender_comp_id, symbol), obligation) in obligations {
+            let presence = state
+                .presence
+                .entry((sender_comp_id.clone(), symbol.clone()))
+                .or_default();
+            presence.accrue(&obligation, now);
+            let uptime_pct = presence.compliant_ms as f64 * 100.0 / epoch_ms;
+            reports.push(ObligationReport {
+                presence_pct: presence.quoted_ms as f64 * 100.0 / epoch_ms,
+                uptime_pct,
+                avg_spread_bps: (presence.quoted_ms > 0)
+                    .then(|| presence.spread_bps_ms / presence.quoted_ms as f64),
+                met: uptime_pct >= obligation.min_uptime_pct,
+                sender_comp_id,
+                symbol,
+                obligation,
+            });
+            // The next epoch starts from zero with the same resting quote
+            presence.quoted_ms = 0;
+            presence.compliant_ms = 0;
+            presence.spread_bps_ms = 0.0;
+        }
+        reports.sort_by(|a, b| (&a.sender_comp_id, &a.symbol).cmp(&(&b.sender_comp_id, &b.symbol)));
+
+        let report = EpochReport {
+            epoch: state.epoch,
+            started_at: state.epoch_started,
+            ended_at: now,
+            reports,
+        };
+        info!(
+            epoch = report.epoch,
+            obligations = report.reports.len(),
+            met = report.rebate_eligible().len(),
+            "Closed market maker obligation epoch"
+        );
+        state.epoch += 1;
+        state.epoch_started = now;
+        state.last_report = Some(report.clone());
+        drop(state);
+
+        let (met, missed): (Vec<_>, Vec<_>) = report.reports.iter().partition(|r| r.met);
+        let pairs = |reports: Vec<&ObligationReport>| {
+            reports
+                .into_iter()
+                .map(|r| format!("{}:{}", r.sender_comp_id, r.symbol))
+                .collect()
+        };
+        self.events.publish(SequencerEvent::ObligationEpochClosed {
+            epoch: report.epoch,
+            met: pairs(met),
+            missed: pairs(missed),
+            at: now,
+        });
+        report
+    }
+
+    /// Report of the last closed epoch
+    pub fn last_report(&self) -> Option<EpochReport> {
+        self.state.lock().last_report.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use romer_common::utils::clock::ManualClock;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    const OBLIGATION: Obligation = Obligation {
+        max_spread_bps: 50,
+        min_size: 100,
+        min_uptime_pct: 70.0,
+    };
+
+    fn quote(bid: u64, ask: u64, size: u64) -> Option<Quote> {
+        Some(Quote {
+            bid_price: bid,
+            bid_size: size,
+            ask_price: ask,
+            ask_size: size,
+        })
+    }
+
+    #[test]
+    fn test_spread_bps() {
+        assert_eq!(quote(9_990, 10_010, 1).unwrap().spread_bps(), Some(20.0));
+        assert_eq!(quote(10_010, 9_990, 1).unwrap().spread_bps(), None);
+    }
+
+    #[tokio::test]
+    async fn test_epoch_uptime() {
+        let clock = Arc::new(ManualClock::new(Utc::now()));
+        let events = EventBus::default();
+        let mut rx = events.subscribe();
+        let monitor = ObligationMonitor::with_clock(events, clock.clone());
+        monitor.set_obligation("MM1", "AAPL", OBLIGATION);
+        monitor.set_obligation("MM2", "AAPL", OBLIGATION);
+
+        // MM1 quotes tight for 80s, then too small for 20s
+        monitor.record_quote("MM1", "AAPL", quote(9_990, 10_010, 100));
+        // MM2 quotes too wide the whole epoch
+        monitor.record_quote("MM2", "AAPL", quote(9_900, 10_100, 500));
+        // Undesignated makers are not tracked
+        monitor.record_quote("MM3", "AAPL", quote(9_990, 10_010, 100));
+        clock.advance(Duration::from_secs(80));
+        monitor.record_quote("MM1", "AAPL", quote(9_990, 10_010, 10));
+        clock.advance(Duration::from_secs(20));
+
+        let report = monitor.close_epoch();
+        assert_eq!(report.reports.len(), 2);
+        let mm1 = &report.reports[0];
+        assert_eq!(mm1.uptime_pct, 80.0);
+        assert_eq!(mm1.presence_pct, 100.0);
+        assert_eq!(mm1.avg_spread_bps, Some(20.0));
+        assert!(mm1.met);
+        let mm2 = &report.reports[1];
+        assert_eq!(mm2.uptime_pct, 0.0);
+        assert!(!mm2.met);
+        assert_eq!(report.rebate_eligible(), vec![("MM1".to_string(), "AAPL".to_string())]);
+        match &*rx.recv().await.unwrap() {
+            SequencerEvent::ObligationEpochClosed { met, missed, .. } => {
+                assert_eq!(met, &vec!["MM1:AAPL".to_string()]);
+                assert_eq!(missed, &vec!["MM2:AAPL".to_string()]);
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+
+        // The resting quote carries into the next epoch
+        clock.advance(Duration::from_secs(10));
+        let next = monitor.close_epoch();
+        assert_eq!(next.epoch, 1);
+        assert_eq!(next.reports[0].presence_pct, 100.0);
+        assert_eq!(next.reports[0].uptime_pct, 0.0);
+    }
+}
@@ -0,0 +1,224 @@
+// src/market/oracle.rs
+
+use parking_lot::Mutex;
+use romer_common::types::oracle::{OracleError, OraclePrice, PriceSubmission, SignedPriceSubmission};
+use romer_common::utils::clock::SharedClock;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+use thiserror::Error;
+use tracing::warn;
+
+/// How far ahead of the sequencer's clock a feeder's timestamp may be
+const MAX_CLOCK_SKEW_MS: u64 = 5_000;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum OracleSubmitError {
+    #[error("{0} is not a whitelisted oracle feeder")]
+    UnknownFeeder(String),
+
+    #[error(transparent)]
+    Invalid(#[from] OracleError),
+
+    #[error("Price observed at {observed_at_ms} is outside the accepted window")]
+    OutsideWindow { observed_at_ms: u64 },
+}
+
+/// Aggregates signed prices from whitelisted feeders. Each block takes the
+/// median of every symbol's fresh prices, drops those deviating from it
+/// by more than `max_deviation_bps`, and writes the median of the rest.
+/// A symbol is left unpriced while fewer than `min_feeders` agree.
+pub struct OracleAggregator {
+    /// Hex-encoded Ed25519 key to feeder name
+    feeders: HashMap<String, String>,
+    max_age: Duration,
+    min_feeders: usize,
+    max_deviation_bps: u32,
+    /// Latest submission of each feeder, by symbol then feeder key
+    submissions: Mutex<BTreeMap<String, BTreeMap<String, PriceSubmission>>>,
+    /// Prices written in the last block
+    latest: Mutex<Vec<OraclePrice>>,
+    clock: SharedClock,
+}
+
+impl OracleAggregator {
+    pub fn new(
+        feeders: HashMap<String, String>,
+        max_age: Duration,
+        min_feeders: usize,
+        max_deviation_bps: u32,
+        clock: SharedClock,
+    ) -> Self {
+        Self {
+            feeders,
+            max_age,
+            min_feeders,
+            max_deviation_bps,
+            submissions: Mutex::new(BTreeMap::new()),
+            latest: Mutex::new(Vec::new()),
+            clock,
+        }
+    }
+
+    /// Accepts a feeder's signed price, replacing its previous price of
+    /// the symbol unless that one is newer
+    pub fn submit(&self, signed: SignedPriceSubmission) -> Result<(), OracleSubmitError> {
+        let feeder = self
+            .feeders
+            .get(&signed.public_key)
+            .ok_or_else(|| OracleSubmitError::UnknownFeeder(signed.public_key.clone()))?;
+        signed.verify()?;
+        let submission = signed.submission;
+        let now = self.clock.unix_millis();
+        if submission.observed_at_ms > now + MAX_CLOCK_SKEW_MS
+            || now.saturating_sub(submission.observed_at_ms) > self.max_age.as_millis() as u64
+        {
+            warn!(feeder = %feeder, symbol = %submission.symbol, "Oracle price outside the accepted window");
+            return Err(OracleSubmitError::OutsideWindow {
+                observed_at_ms: submission.observed_at_ms,
+            });
+        }
+
+        let mut submissions = self.submissions.lock();
+        let symbol = submissions.entry(submission.symbol.clone()).or_default();
+        match symbol.get(&signed.public_key) {
+            Some(previous) if previous.observed_at_ms >= submission.observed_at_ms => {}
+            _ => {
+                symbol.insert(signed.public_key, submission);
+            }
+        }
+        Ok(())
+    }
+
+    /// The prices to write into a block stamped `timestamp_ms`
+    pub fn aggregate(&self, timestamp_ms: u64) -> Vec<OraclePrice> {
+        let max_age = self.max_age.as_millis() as u64;
+        let mut prices = Vec::new();
+        for (symbol, submissions) in self.submissions.lock().iter() {
+            let mut fresh: Vec<u64> = submissions
+                .values()
+                .filter(|submission| timestamp_ms.saturating_sub(submission.observed_at_ms) <= max_age)
+                .map(|submission| submission.price)
+                .collect();
+            if fresh.len() < self.min_feeders.max(1) {
+                continue;
+            }
+            fresh.sort_unstable();
+            let median = median_of(&fresh);
+            let band = (median as u128 * self.max_deviation_bps as u128 / 10_000) as u64;
+            let kept: Vec<u64> = fresh
+                .into_iter()
+                .filter(|price| price.abs_diff(median) <= band)
+                .collect();
+            if kept.len() < self.min_feeders.max(1) {
+                warn!(symbol = %symbol, agreeing = kept.len(), "Oracle feeders disagree, price not written");
+                continue;
+            }
+            prices.push(OraclePrice {
+                symbol: symbol.clone(),
+                price: median_of(&kept),
+                feeders: kept.len() as u32,
+                timestamp_ms,
+            });
+        }
+        *self.latest.lock() = prices.clone();
+        prices
+    }
+
+    /// Prices written in the last block
+    pub fn latest(&self) -> Vec<OraclePrice> {
+        self.latest.lock().clone()
+    }
+}
+
+/// Median of sorted `prices`, the midpoint of the middle two for an even
+/// count
+fn median_of(prices: &[u64]) -> u64 {
+    let middle = prices.len() / 2;
+    if prices.len() % 2 == 1 {
+        prices[middle]
+    } else {
+        let (low, high) = (prices[middle - 1], prices[middle]);
+        low + (high - low) / 2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use commonware_cryptography::{Ed25519, Scheme};
+    use romer_common::utils::clock::{Clock, ManualClock};
+    use std::sync::Arc;
+
+    fn oracle(feeders: &[Ed25519], clock: Arc<ManualClock>) -> OracleAggregator {
+        let feeders = feeders
+            .iter()
+            .enumerate()
+            .map(|(i, feeder)| (hex::encode(feeder.public_key()), format!("feeder-{}", i)))
+            .collect();
+        OracleAggregator::new(feeders, Duration::from_secs(10), 3, 100, clock)
+    }
+
+    fn submit(oracle: &OracleAggregator, feeder: &mut Ed25519, symbol: &str, price: u64, observed_at_ms: u64) {
+        let submission = PriceSubmission {
+            symbol: symbol.into(),
+            price,
+            observed_at_ms,
+        };
+        oracle.submit(SignedPriceSubmission::sign(submission, feeder)).unwrap();
+    }
+
+    #[test]
+    fn test_median_with_outlier_rejection() {
+        let clock = Arc::new(ManualClock::new(Utc::now()));
+        let now = clock.unix_millis();
+        let mut feeders: Vec<Ed25519> = (0..4).map(Ed25519::from_seed).collect();
+        let oracle = oracle(&feeders, clock);
+
+        for (feeder, price) in feeders.iter_mut().zip([1000, 1004, 1006, 5000]) {
+            submit(&oracle, feeder, "BTC/USD", price, now);
+        }
+        // Two feeders are not enough for ETH
+        submit(&oracle, &mut feeders[0], "ETH/USD", 300, now);
+        submit(&oracle, &mut feeders[1], "ETH/USD", 301, now);
+
+        let prices = oracle.aggregate(now);
+        assert_eq!(prices.len(), 1);
+        // 5000 is outside 1% of the median 1005 and dropped
+        assert_eq!((prices[0].price, prices[0].feeders), (1004, 3));
+        assert_eq!(oracle.latest(), prices);
+
+        // Stale prices drop out
+        assert!(oracle.aggregate(now + 11_000).is_empty());
+    }
+
+    #[test]
+    fn test_submission_checks() {
+        let clock = Arc::new(ManualClock::new(Utc::now()));
+        let now = clock.unix_millis();
+        let mut feeders: Vec<Ed25519> = (0..3).map(Ed25519::from_seed).collect();
+        let oracle = oracle(&feeders, clock);
+        let submission = |observed_at_ms| PriceSubmission {
+            symbol: "BTC/USD".into(),
+            price: 1000,
+            observed_at_ms,
+        };
+
+        let mut outsider = Ed25519::from_seed(9);
+        assert!(matches!(
+            oracle.submit(SignedPriceSubmission::sign(submission(now), &mut outsider)),
+            Err(OracleSubmitError::UnknownFeeder(_))
+        ));
+        let mut forged = SignedPriceSubmission::sign(submission(now), &mut feeders[0]);
+        forged.submission.price = 2000;
+        assert_eq!(oracle.submit(forged), Err(OracleError::InvalidSignature.into()));
+        assert!(matches!(
+            oracle.submit(SignedPriceSubmission::sign(submission(now - 60_000), &mut feeders[0])),
+            Err(OracleSubmitError::OutsideWindow { .. })
+        ));
+        assert!(matches!(
+            oracle.submit(SignedPriceSubmission::sign(submission(now + 60_000), &mut feeders[0])),
+            Err(OracleSubmitError::OutsideWindow { .. })
+        ));
+    }
+}
@@ -0,0 +1,375 @@
+// src/market/orders.rs
+
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use romer_common::storage::group_commit::GroupCommitter;
+use romer_common::storage::journal::RomerJournal;
+use romer_common::utils::clock::SharedClock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use thiserror::Error;
+use tracing::{info, warn};
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum OrderStoreError {
+    #[error("Unknown order {0}")]
+    UnknownOrder(String),
+
+    #[error("Order {order_id} is {status:?} and can no longer change")]
+    Closed { order_id: String, status: OrderStatus },
+
+    #[error("Fill of {quantity} exceeds the {leaves} left of order {order_id}")]
+    Overfill {
+        order_id: String,
+        quantity: u64,
+        leaves: u64,
+    },
+
+    #[error("Storage error: {0}")]
+    Storage(String),
+}
+
+/// Where an order is in its lifecycle. Orders start New, or Rejected at
+/// entry, and fill until Filled unless they are Canceled or Expired first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    New,
+    PartiallyFilled,
+    Filled,
+    Canceled,
+    Rejected,
+    Expired,
+}
+
+impl OrderStatus {
+    /// OrdStatus (39) value
+    pub fn fix_code(&self) -> &'static str {
+        match self {
+            Self::New => "0",
+            Self::PartiallyFilled => "1",
+            Self::Filled => "2",
+            Self::Canceled => "4",
+            Self::Rejected => "8",
+            Self::Expired => "C",
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        !matches!(self, Self::New | Self::PartiallyFilled)
+    }
+}
+
+/// A NewOrderSingle as entered
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderEntry {
+    pub sender_comp_id: String,
+    pub cl_ord_id: String,
+    pub symbol: String,
+    /// Side (54) as received
+    pub side: String,
+    #[serde(default)]
+    pub account: Option<String>,
+    pub order_qty: u64,
+    /// Price (44) as received; market orders have none
+    #[serde(default)]
+    pub price: Option<String>,
+}
+
+/// Current state of an order, as journaled on every change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderState {
+    pub order_id: String,
+    #[serde(flatten)]
+    pub entry: OrderEntry,
+    pub status: OrderStatus,
+    pub cum_qty: u64,
+    /// Average price of the fills so far
+    pub avg_px: f64,
+    /// Why the order was rejected
+    #[serde(default)]
+    pub text: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl OrderState {
+    /// Quantity still working; none once the order is closed
+    pub fn leaves_qty(&self) -> u64 {
+        if self.status.is_closed() {
+            0
+        } else {
+            self.entry.order_qty.saturating_sub(self.cum_qty)
+        }
+    }
+}
+
+#[derive(Default)]
+struct Orders {
+    by_order_id: HashMap<String, OrderState>,
+    /// Latest order of each (SenderCompID, ClOrdID)
+    by_cl_ord_id: HashMap<(String, String), String>,
+    next_id: u64,
+}
+
+impl Orders {
+    fn insert(&mut self, state: OrderState) {
+        let key = (state.entry.sender_comp_id.clone(), state.entry.cl_ord_id.clone());
+        // An order rejected as a duplicate must not hide the order whose
+        // ClOrdID it reused that day
+        let displaces = match self.by_cl_ord_id.get(&key).and_then(|id| self.by_order_id.get(id)) {
+            Some(indexed) if indexed.order_id != state.order_id => {
+                state.status != OrderStatus::Rejected
+                    || indexed.created_at.date_naive() != state.created_at.date_naive()
+            }
+            _ => true,
+        };
+        if displaces {
+            self.by_cl_ord_id.insert(key, state.order_id.clone());
+        }
+        self.by_order_id.insert(state.order_id.clone(), state);
+    }
+}
+
+/// Lifecycle state of every order, indexed by OrderID and by ClOrdID. Each
+/// change is journaled through a group commit before it is applied, so
+/// order status queries are answered the same after a restart.
+pub struct OrderStore {
+    orders: Mutex<Orders>,
+    journal: Option<GroupCommitter>,
+    clock: SharedClock,
+}
+
+impl OrderStore {
+    /// Store without persistence, for tests and tooling
+    pub fn in_memory(clock: SharedClock) -> Self {
+        Self {
+            orders: Mutex::new(Orders::default()),
+            journal: None,
+            clock,
+        }
+    }
+
+    /// Opens the store over `journal`, restoring the latest state of every
+    /// order in it
+    pub async fn open(mut journal: RomerJournal, clock: SharedClock) -> Result<Self, OrderStoreError> {
+        let mut orders = Orders::default();
+        for bytes in journal.replay_all().await.map_err(OrderStoreError::Storage)? {
+            match serde_json::from_slice::<OrderState>(&bytes) {
+                Ok(state) => {
+                    if let Ok(id) = state.order_id.parse::<u64>() {
+                        orders.next_id = orders.next_id.max(id + 1);
+                    }
+                    orders.insert(state);
+                }
+                Err(e) => warn!(error = %e, "Skipping undecodable order record"),
+            }
+        }
+        info!(orders = orders.by_order_id.len(), "Restored order states");
+
+        Ok(Self {
+            orders: Mutex::new(orders),
+            journal: Some(GroupCommitter::spawn(journal)),
+            clock,
+        })
+    }
+
+    /// Records an accepted order as New, assigning its OrderID
+    pub async fn accept(&self, entry: OrderEntry) -> Result<OrderState, OrderStoreError> {
+        self.enter(entry, OrderStatus::New, None).await
+    }
+
+    /// Records an order rejected at entry with `text`
+    pub async fn reject(&self, entry: OrderEntry, text: String) -> Result<OrderState, OrderStoreError> {
+        self.enter(entry, OrderStatus::Rejected, Some(text)).await
+    }
+
+    /// Adds a fill of `quantity` at `price` to an open order
+    pub async fn fill(&self, order_id: &str, quantity: u64, price: u64) -> Result<OrderState, OrderStoreError> {
+        self.update(order_id, |state| {
+            let leaves = state.leaves_qty();
+            if quantity > leaves {
+                return Err(OrderStoreError::Overfill {
+                    order_id: state.order_id.clone(),
+                    quantity,
+                    leaves,
+                });
+            }
+            let cum_qty = state.cum_qty + quantity;
+            state.avg_px = (state.avg_px * state.cum_qty as f64 + price as f64 * quantity as f64) / cum_qty as f64;
+            state.cum_qty = cum_qty;
+            state.status = if cum_qty == state.entry.order_qty {
+                OrderStatus::Filled
+            } else {
+                OrderStatus::PartiallyFilled
+            };
+            Ok(())
+        })
+        .await
+    }
+
+    /// Cancels what is left of an open order
+    pub async fn cancel(&self, order_id: &str) -> Result<OrderState, OrderStoreError> {
+        self.close(order_id, OrderStatus::Canceled).await
+    }
+
+    /// Expires what is left of an open order, e.g. a GTD order at its date
+    pub async fn expire(&self, order_id: &str) -> Result<OrderState, OrderStoreError> {
+        self.close(order_id, OrderStatus::Expired).await
+    }
+
+    pub fn by_order_id(&self, order_id: &str) -> Option<OrderState> {
+        self.orders.lock().by_order_id.get(order_id).cloned()
+    }
+
+    /// The latest order `sender_comp_id` entered with `cl_ord_id`
+    pub fn by_cl_ord_id(&self, sender_comp_id: &str, cl_ord_id: &str) -> Option<OrderState> {
+        let orders = self.orders.lock();
+        orders
+            .by_cl_ord_id
+            .get(&(sender_comp_id.to_string(), cl_ord_id.to_string()))
+            .and_then(|order_id| orders.by_order_id.get(order_id))
+            .cloned()
+    }
+
+    /// Orders of `sender_comp_id` still working, oldest first
+    pub fn open_orders(&self, sender_comp_id: &str) -> Vec<OrderState> {
+        let mut open: Vec<OrderState> = self
+            .orders
+            .lock()
+            .by_order_id
+            .values()
+            .filter(|state| state.entry.sender_comp_id == sender_comp_id && !state.status.is_closed())
+            .cloned()
+            .collect();
+        open.sort_by_key(|state| state.created_at);
+        open
+    }
+
+    async fn enter(
+        &self,
+        entry: OrderEntry,
+        status: OrderStatus,
+        text: Option<String>,
+    ) -> Result<OrderState, OrderStoreError> {
+        let now = self.clock.now();
+        let state = {
+            let mut orders = self.orders.lock();
+            let order_id = orders.next_id.to_string();
+            orders.next_id += 1;
+            OrderState {
+                order_id,
+                entry,
+                status,
+                cum_qty: 0,
+                avg_px: 0.0,
+                text,
+                created_at: now,
+                updated_at: now,
+            }
+        };
+        self.persist(&state).await?;
+        self.orders.lock().insert(state.clone());
+        Ok(state)
+    }
+
+    async fn close(&self, order_id: &str, status: OrderStatus) -> Result<OrderState, OrderStoreError> {
+        self.update(order_id, |state| {
+            state.status = status;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Applies `change` to an open order once the new state is journaled
+    async fn update(
+        &self,
+        order_id: &str,
+        change: impl FnOnce(&mut OrderState) -> Result<(), OrderStoreError>,
+    ) -> Result<OrderState, OrderStoreError> {
+        let mut state = self
+            .by_order_id(order_id)
+            .ok_or_else(|| OrderStoreError::UnknownOrder(order_id.to_string()))?;
+        if state.status.is_closed() {
+            return Err(OrderStoreError::Closed {
+                order_id: state.order_id,
+                status: state.status,
+            });
+        }
+        change(&mut state)?;
+        state.updated_at = self.clock.now();
+        self.persist(&state).await?;
+        self.orders.lock().insert(state.clone());
+        Ok(state)
+    }
+
+    async fn persist(&self, state: &OrderState) -> Result<(), OrderStoreError> {
+        let Some(journal) = &self.journal else {
+            return Ok(());
+        };
+        let bytes = serde_json::to_vec(state).map_err(|e| OrderStoreError::Storage(e.to_string()))?;
+        journal.append(bytes).await.map_err(OrderStoreError::Storage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use romer_common::utils::clock::ManualClock;
+    use std::sync::Arc;
+
+    fn entry(cl_ord_id: &str, order_qty: u64) -> OrderEntry {
+        OrderEntry {
+            sender_comp_id: "MM1".into(),
+            cl_ord_id: cl_ord_id.into(),
+            symbol: "BTC-USD".into(),
+            side: "1".into(),
+            account: None,
+            order_qty,
+            price: Some("100".into()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lifecycle_by_cl_ord_id() {
+        let clock = Arc::new(ManualClock::new(Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap()));
+        let store = OrderStore::in_memory(clock);
+
+        let order = store.accept(entry("A1", 10)).await.unwrap();
+        assert_eq!(order.status, OrderStatus::New);
+
+        let partial = store.fill(&order.order_id, 4, 100).await.unwrap();
+        assert_eq!((partial.status, partial.leaves_qty()), (OrderStatus::PartiallyFilled, 6));
+        let filled = store.fill(&order.order_id, 6, 105).await.unwrap();
+        assert_eq!(filled.status, OrderStatus::Filled);
+        assert_eq!(filled.avg_px, 103.0);
+        assert_eq!(store.by_cl_ord_id("MM1", "A1"), Some(filled));
+        assert!(matches!(
+            store.cancel(&order.order_id).await,
+            Err(OrderStoreError::Closed { status: OrderStatus::Filled, .. })
+        ));
+
+        let other = store.accept(entry("A2", 5)).await.unwrap();
+        assert!(matches!(store.fill(&other.order_id, 6, 100).await, Err(OrderStoreError::Overfill { .. })));
+        assert_eq!(store.open_orders("MM1"), vec![other.clone()]);
+        assert_eq!(store.expire(&other.order_id).await.unwrap().status, OrderStatus::Expired);
+        assert!(store.open_orders("MM1").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_reject_keeps_original() {
+        let clock = Arc::new(ManualClock::new(Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap()));
+        let store = OrderStore::in_memory(clock.clone());
+
+        let original = store.accept(entry("A1", 10)).await.unwrap();
+        let duplicate = store.reject(entry("A1", 10), "Duplicate ClOrdID".into()).await.unwrap();
+        assert_eq!(store.by_cl_ord_id("MM1", "A1").unwrap().order_id, original.order_id);
+        assert_eq!(store.by_order_id(&duplicate.order_id).unwrap().status, OrderStatus::Rejected);
+
+        // The ClOrdID is free again on the next day
+        clock.advance(std::time::Duration::from_secs(24 * 3600));
+        let rejected = store.reject(entry("A1", 10), "Market closed".into()).await.unwrap();
+        assert_eq!(store.by_cl_ord_id("MM1", "A1"), Some(rejected));
+    }
+}
@@ -0,0 +1,237 @@
+// src/market/reference_price.rs
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use romer_common::utils::clock::{system_clock, SharedClock};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tracing::warn;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ReferencePriceError {
+    #[error("No reference price for {0}")]
+    NoPrice(String),
+
+    #[error("Reference price for {symbol} is stale ({age_secs}s old)")]
+    Stale { symbol: String, age_secs: i64 },
+
+    #[error("Price {price} of {symbol} is outside the {band_bps}bps collar around {reference}")]
+    OutsideCollar {
+        symbol: String,
+        price: f64,
+        reference: f64,
+        band_bps: u32,
+    },
+}
+
+/// A price as last seen on one feed
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PriceObservation {
+    pub price: f64,
+    pub observed_at: DateTime<Utc>,
+}
+
+/// The price collars, circuit breakers and margining work from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReferencePrice {
+    pub symbol: String,
+    pub price: f64,
+    /// Feed the price came from
+    pub source: String,
+    pub observed_at: DateTime<Utc>,
+}
+
+/// A source of reference prices. Adapters keep the latest price of each
+/// symbol they receive, however they receive it.
+pub trait PriceFeed: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn latest(&self, symbol: &str) -> Option<PriceObservation>;
+
+    /// How old a price of this feed may be, the service's limit if `None`
+    fn max_age(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Prices entered by an administrator
+pub struct ManualFeed {
+    prices: DashMap<String, PriceObservation>,
+    max_age: Duration,
+}
+
+impl ManualFeed {
+    /// Manual prices stay valid for `max_age` after they are entered
+    pub fn new(max_age: Duration) -> Self {
+        Self {
+            prices: DashMap::new(),
+            max_age,
+        }
+    }
+
+    pub fn set(&self, symbol: &str, price: f64, at: DateTime<Utc>) {
+        self.prices.insert(
+            symbol.to_string(),
+            PriceObservation {
+                price,
+                observed_at: at,
+            },
+        );
+    }
+}
+
+impl PriceFeed for ManualFeed {
+    fn name(&self) -> &str {
+        "manual"
+    }
+
+    fn latest(&self, symbol: &str) -> Option<PriceObservation> {
+        self.prices.get(symbol).map(|observation| *observation)
+    }
+
+    fn max_age(&self) -> Option<Duration> {
+        Some(self.max_age)
+    }
+}
+
+/// Supplies the reference price of each symbol from the first feed, in
+/// priority order, with a fresh price. Prices older than their feed's
+/// limit are skipped so a stalled feed fails over to the next one.
+pub struct ReferencePriceService {
+    feeds: Vec<Arc<dyn PriceFeed>>,
+    max_age: Duration,
+    /// Feed each symbol was last priced from, to log failovers
+    sources: DashMap<String, String>,
+    clock: SharedClock,
+}
+
+impl ReferencePriceService {
+    pub fn new(max_age: Duration) -> Self {
+        Self::with_clock(max_age, system_clock())
+    }
+
+    pub fn with_clock(max_age: Duration, clock: SharedClock) -> Self {
+        Self {
+            feeds: Vec::new(),
+            max_age,
+            sources: DashMap::new(),
+            clock,
+        }
+    }
+
+    /// Adds `feed` below the feeds added before it
+    pub fn with_feed(mut self, feed: Arc<dyn PriceFeed>) -> Self {
+        self.feeds.push(feed);
+        self
+    }
+
+    pub fn price(&self, symbol: &str) -> Result<ReferencePrice, ReferencePriceError> {
+        let now = self.clock.now();
+        let mut freshest_stale: Option<i64> = None;
+
+        for feed in &self.feeds {
+            let Some(observation) = feed.latest(symbol) else {
+                continue;
+            };
+            let age = now - observation.observed_at;
+            let max_age = feed.max_age().unwrap_or(self.max_age);
+            if age.to_std().unwrap_or_default() > max_age {
+                let age_secs = age.num_seconds();
+                freshest_stale = Some(freshest_stale.map_or(age_secs, |s| s.min(age_secs)));
+                continue;
+            }
+
+            let previous = self.sources.insert(symbol.to_string(), feed.name().to_string());
+            if let Some(previous) = previous.filter(|previous| previous != feed.name()) {
+                warn!(symbol, from = %previous, to = feed.name(), "Reference price failed over");
+            }
+            return Ok(ReferencePrice {
+                symbol: symbol.to_string(),
+                price: observation.price,
+                source: feed.name().to_string(),
+                observed_at: observation.observed_at,
+            });
+        }
+
+        match freshest_stale {
+            Some(age_secs) => Err(ReferencePriceError::Stale {
+                symbol: symbol.to_string(),
+                age_secs,
+            }),
+            None => Err(ReferencePriceError::NoPrice(symbol.to_string())),
+        }
+    }
+
+    /// Checks `price` lies within `band_bps` of the reference price
+    pub fn check_collar(&self, symbol: &str, price: f64, band_bps: u32) -> Result<(), ReferencePriceError> {
+        let reference = self.price(symbol)?.price;
+        let band = reference * band_bps as f64 / 10_000.0;
+        if (price - reference).abs() > band {
+            return Err(ReferencePriceError::OutsideCollar {
+                symbol: symbol.to_string(),
+                price,
+                reference,
+                band_bps,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use romer_common::utils::clock::ManualClock;
+
+    struct Feed(&'static str, DashMap<String, PriceObservation>);
+
+    impl PriceFeed for Feed {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn latest(&self, symbol: &str) -> Option<PriceObservation> {
+            self.1.get(symbol).map(|o| *o)
+        }
+    }
+
+    #[test]
+    fn test_failover_and_staleness() {
+        let clock = Arc::new(ManualClock::new(Utc::now()));
+        let primary = Arc::new(Feed("primary", DashMap::new()));
+        let manual = Arc::new(ManualFeed::new(Duration::from_secs(3600)));
+        let service = ReferencePriceService::with_clock(Duration::from_secs(5), clock.clone())
+            .with_feed(primary.clone())
+            .with_feed(manual.clone());
+        assert_eq!(service.price("AAPL"), Err(ReferencePriceError::NoPrice("AAPL".into())));
+
+        let observed_at = clock.now();
+        primary.1.insert("AAPL".into(), PriceObservation { price: 100.0, observed_at });
+        manual.set("AAPL", 99.0, observed_at);
+        assert_eq!(service.price("AAPL").unwrap().source, "primary");
+
+        // The primary goes quiet and the manual price takes over
+        clock.advance(Duration::from_secs(10));
+        let price = service.price("AAPL").unwrap();
+        assert_eq!((price.source.as_str(), price.price), ("manual", 99.0));
+
+        // Every feed stale
+        clock.advance(Duration::from_secs(3600));
+        assert!(matches!(service.price("AAPL"), Err(ReferencePriceError::Stale { .. })));
+    }
+
+    #[test]
+    fn test_collar() {
+        let manual = Arc::new(ManualFeed::new(Duration::from_secs(60)));
+        manual.set("AAPL", 100.0, Utc::now());
+        let service = ReferencePriceService::new(Duration::from_secs(5)).with_feed(manual);
+
+        assert!(service.check_collar("AAPL", 104.0, 500).is_ok());
+        assert!(matches!(
+            service.check_collar("AAPL", 106.0, 500),
+            Err(ReferencePriceError::OutsideCollar { .. })
+        ));
+    }
+}
@@ -0,0 +1,262 @@
+// src/market/registry.rs
+
+use crate::risk::cl_ord_ids::{ClOrdIdError, ClOrdIdRegistry};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use romer_common::storage::journal::{Partition, RomerJournal, Section, StorageConfig};
+use romer_common::storage::metrics::StorageMetrics;
+use romer_common::utils::clock::SharedClock;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::{error, info};
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum MarketError {
+    #[error("Invalid market configuration: {0}")]
+    Config(String),
+
+    #[error("Unknown market {0}")]
+    UnknownMarket(String),
+
+    #[error("Symbol {symbol} is not listed on market {market}")]
+    UnknownInstrument { market: String, symbol: String },
+
+    #[error("Sender {0} already has an active session on this market")]
+    SessionActive(String),
+
+    #[error(transparent)]
+    ClOrdId(#[from] ClOrdIdError),
+}
+
+/// A logical market hosted by the sequencer, addressed by the TargetCompID
+/// (56) counterparties send to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MarketConfig {
+    pub target_comp_id: String,
+    /// Listed instruments. Empty lists every symbol.
+    #[serde(default)]
+    pub symbols: BTreeSet<String>,
+}
+
+impl MarketConfig {
+    /// Parses `SEQUENCER_MARKETS`, e.g. `ROMER:AAPL,MSFT;ROMER-FX:EURUSD`.
+    /// A market given without symbols lists every symbol.
+    pub fn parse_list(raw: &str) -> Result<Vec<Self>, MarketError> {
+        let markets: Vec<Self> = raw
+            .split(';')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (target, symbols) = entry.split_once(':').unwrap_or((entry, ""));
+                Self {
+                    target_comp_id: target.trim().to_string(),
+                    symbols: symbols
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|symbol| !symbol.is_empty())
+                        .map(str::to_string)
+                        .collect(),
+                }
+            })
+            .collect();
+        Self::validate_list(&markets)?;
+        Ok(markets)
+    }
+
+    /// Checks `markets` is nonempty and each is named by a distinct
+    /// TargetCompID fit for a directory name
+    pub fn validate_list(markets: &[Self]) -> Result<(), MarketError> {
+        for (i, market) in markets.iter().enumerate() {
+            let target = market.target_comp_id.as_str();
+            // The id names the market's storage directory
+            let valid = target.chars().all(|c| c.is_alphanumeric() || "-_.".contains(c));
+            if target.is_empty() || target.starts_with('.') || !valid {
+                return Err(MarketError::Config(format!("invalid TargetCompID {:?}", target)));
+            }
+            if markets[..i].iter().any(|other| other.target_comp_id == target) {
+                return Err(MarketError::Config(format!("market {} listed twice", target)));
+            }
+        }
+        if markets.is_empty() {
+            return Err(MarketError::Config("no markets configured".into()));
+        }
+        Ok(())
+    }
+
+    pub fn lists(&self, symbol: &str) -> bool {
+        self.symbols.is_empty() || self.symbols.contains(symbol)
+    }
+}
+
+/// One market's isolated state: its instruments, sessions and the ClOrdIDs
+/// journaled in its own partitions
+pub struct Market {
+    pub config: MarketConfig,
+    pub cl_ord_ids: ClOrdIdRegistry,
+    /// Active sessions by SenderCompID, with when they logged on
+    sessions: DashMap<String, DateTime<Utc>>,
+}
+
+impl Market {
+    pub fn new(config: MarketConfig, cl_ord_ids: ClOrdIdRegistry) -> Self {
+        Self {
+            config,
+            cl_ord_ids,
+            sessions: DashMap::new(),
+        }
+    }
+
+    /// Opens the market over its own partitions under `storage`, falling
+    /// back to memory if its journal is unavailable
+    pub async fn open(
+        config: MarketConfig,
+        storage: &StorageConfig,
+        metrics: &StorageMetrics,
+        clock: SharedClock,
+    ) -> Result<Self, MarketError> {
+        let storage = storage.for_market(&config.target_comp_id);
+        let cl_ord_ids = match RomerJournal::with_config(Partition::SESSION, Section::DEDUP, storage).await {
+            Ok(journal) => ClOrdIdRegistry::open(journal.with_metrics(metrics.clone()), clock).await?,
+            Err(e) => {
+                error!(market = %config.target_comp_id, "Failed to open ClOrdID journal, duplicates will not survive restarts: {}", e);
+                ClOrdIdRegistry::in_memory(clock)
+            }
+        };
+        Ok(Self::new(config, cl_ord_ids))
+    }
+
+    pub fn id(&self) -> &str {
+        &self.config.target_comp_id
+    }
+
+    pub fn check_instrument(&self, symbol: &str) -> Result<(), MarketError> {
+        if self.config.lists(symbol) {
+            return Ok(());
+        }
+        Err(MarketError::UnknownInstrument {
+            market: self.id().to_string(),
+            symbol: symbol.to_string(),
+        })
+    }
+
+    /// Records a logon of `sender_comp_id`. A sender may hold one session
+    /// per market.
+    pub fn open_session(&self, sender_comp_id: &str, now: DateTime<Utc>) -> Result<(), MarketError> {
+        match self.sessions.entry(sender_comp_id.to_string()) {
+            dashmap::mapref::entry::Entry::Occupied(_) => {
+                Err(MarketError::SessionActive(sender_comp_id.to_string()))
+            }
+            dashmap::mapref::entry::Entry::Vacant(entry) => {
+                entry.insert(now);
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns whether `sender_comp_id` had a session on the market
+    pub fn close_session(&self, sender_comp_id: &str) -> bool {
+        self.sessions.remove(sender_comp_id).is_some()
+    }
+}
+
+/// The markets of a sequencer instance. Inbound FIX messages are routed to
+/// a market by their TargetCompID.
+pub struct MarketRegistry {
+    markets: HashMap<String, Arc<Market>>,
+}
+
+impl MarketRegistry {
+    pub fn new(markets: impl IntoIterator<Item = Market>) -> Self {
+        Self {
+            markets: markets
+                .into_iter()
+                .map(|market| (market.id().to_string(), Arc::new(market)))
+                .collect(),
+        }
+    }
+
+    /// Opens every market in `configs` over its own storage partitions
+    pub async fn open(
+        configs: Vec<MarketConfig>,
+        storage: &StorageConfig,
+        metrics: &StorageMetrics,
+        clock: SharedClock,
+    ) -> Result<Self, MarketError> {
+        let mut markets = Vec::with_capacity(configs.len());
+        for config in configs {
+            info!(market = %config.target_comp_id, symbols = config.symbols.len(), "Opening market");
+            markets.push(Market::open(config, storage, metrics, clock.clone()).await?);
+        }
+        Ok(Self::new(markets))
+    }
+
+    /// The market addressed by `target_comp_id`
+    pub fn route(&self, target_comp_id: &str) -> Result<Arc<Market>, MarketError> {
+        self.markets
+            .get(target_comp_id)
+            .cloned()
+            .ok_or_else(|| MarketError::UnknownMarket(target_comp_id.to_string()))
+    }
+
+    pub fn ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.markets.keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use romer_common::utils::clock::system_clock;
+
+    fn registry() -> MarketRegistry {
+        let markets = MarketConfig::parse_list("ROMER:AAPL,MSFT; ROMER-FX:EURUSD").unwrap();
+        MarketRegistry::new(
+            markets
+                .into_iter()
+                .map(|config| Market::new(config, ClOrdIdRegistry::in_memory(system_clock()))),
+        )
+    }
+
+    #[test]
+    fn test_parse_list() {
+        let markets = MarketConfig::parse_list("ROMER:AAPL,MSFT;ROMER-FX").unwrap();
+        assert_eq!(markets.len(), 2);
+        assert!(markets[0].lists("AAPL"));
+        assert!(!markets[0].lists("EURUSD"));
+        assert!(markets[1].lists("anything"));
+
+        assert!(MarketConfig::parse_list("").is_err());
+        assert!(MarketConfig::parse_list("A:X;A:Y").is_err());
+        assert!(MarketConfig::parse_list("../etc:X").is_err());
+        assert!(MarketConfig::parse_list("..:X").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_markets_are_isolated() {
+        let registry = registry();
+        let equities = registry.route("ROMER").unwrap();
+        let fx = registry.route("ROMER-FX").unwrap();
+        assert!(matches!(registry.route("OTHER"), Err(MarketError::UnknownMarket(_))));
+
+        // Instruments
+        assert!(equities.check_instrument("AAPL").is_ok());
+        assert!(fx.check_instrument("AAPL").is_err());
+
+        // Sessions: one per sender per market
+        let now = Utc::now();
+        equities.open_session("MM1", now).unwrap();
+        fx.open_session("MM1", now).unwrap();
+        assert!(equities.open_session("MM1", now).is_err());
+        assert!(equities.close_session("MM1"));
+        assert!(fx.open_session("MM1", now).is_err());
+
+        // ClOrdIDs are only unique within a market
+        equities.cl_ord_ids.record("MM1", "A1").await.unwrap();
+        fx.cl_ord_ids.record("MM1", "A1").await.unwrap();
+        assert!(equities.cl_ord_ids.record("MM1", "A1").await.is_err());
+    }
+}
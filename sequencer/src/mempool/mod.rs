@@ -0,0 +1,2 @@
+pub mod nonce;
+pub mod pool;
@@ -0,0 +1,201 @@
+// src/mempool/nonce.rs
+
+use dashmap::DashMap;
+use romer_common::types::address::Address;
+use romer_common::types::nonce::{check_nonce, NonceError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use tracing::{error, warn};
+
+/// How far ahead of the committed nonce a sender may queue transactions
+pub const DEFAULT_MAX_NONCE_GAP: u64 = 64;
+
+/// A committed nonce as journaled
+#[derive(Serialize, Deserialize)]
+struct CommittedNonce {
+    address: Address,
+    next: u64,
+}
+
+/// Tracks committed and pending nonces per account so that replayed or
+/// duplicate envelopes are rejected before they reach a block
+pub struct NonceRegistry {
+    /// Next nonce expected to execute, per account
+    committed: DashMap<Address, u64>,
+    /// Nonces accepted but not yet included in a block
+    pending: DashMap<Address, BTreeSet<u64>>,
+    max_gap: u64,
+    /// Committed nonces are appended here, so a restart can't readmit
+    /// transactions that already executed
+    journal: Option<PathBuf>,
+}
+
+impl NonceRegistry {
+    pub fn new(max_gap: u64) -> Self {
+        Self {
+            committed: DashMap::new(),
+            pending: DashMap::new(),
+            max_gap,
+            journal: None,
+        }
+    }
+
+    /// Journal committed nonces to `path`, first replaying those it holds
+    pub fn with_journal(mut self, path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        if path.exists() {
+            for line in BufReader::new(File::open(path)?).lines() {
+                match serde_json::from_str::<CommittedNonce>(&line?) {
+                    Ok(nonce) => self.advance(nonce.address, nonce.next),
+                    Err(e) => warn!(error = %e, "Skipping undecodable committed nonce"),
+                }
+            }
+        }
+        self.journal = Some(path.to_path_buf());
+        Ok(self)
+    }
+
+    /// Next nonce expected to execute for `address`
+    pub fn next(&self, address: &Address) -> u64 {
+        self.committed.get(address).map(|n| *n).unwrap_or(0)
+    }
+
+    /// Admits a transaction's nonce, reserving it until committed or released
+    pub fn admit(&self, address: Address, nonce: u64) -> Result<(), NonceError> {
+        check_nonce(self.next(&address), nonce, self.max_gap)?;
+
+        let mut pending = self.pending.entry(address).or_default();
+        if !pending.insert(nonce) {
+            return Err(NonceError::AlreadyPending(nonce));
+        }
+        Ok(())
+    }
+
+    /// Admits the lowest nonce of `address` neither committed nor pending,
+    /// for transactions the sequencer signs itself
+    pub fn reserve(&self, address: Address) -> Result<u64, NonceError> {
+        let next = self.next(&address);
+        let mut pending = self.pending.entry(address).or_default();
+        let nonce = (next..).find(|nonce| !pending.contains(nonce)).unwrap_or(next);
+        check_nonce(next, nonce, self.max_gap)?;
+        pending.insert(nonce);
+        Ok(nonce)
+    }
+
+    /// Records that `nonce` was included in a block
+    pub fn commit(&self, address: Address, nonce: u64) {
+        if self.advance(address, nonce + 1) {
+            if let Err(e) = self.append(address, nonce + 1) {
+                error!(error = %e, address = %address, nonce, "Failed to journal committed nonce");
+            }
+        }
+
+        if let Some(mut pending) = self.pending.get_mut(&address) {
+            pending.retain(|n| *n > nonce);
+        }
+    }
+
+    /// Raises the next nonce of `address` to `next`, returning whether it rose
+    fn advance(&self, address: Address, next: u64) -> bool {
+        let mut committed = self.committed.entry(address).or_insert(0);
+        if next <= *committed {
+            return false;
+        }
+        *committed = next;
+        true
+    }
+
+    fn append(&self, address: Address, next: u64) -> io::Result<()> {
+        let Some(path) = &self.journal else {
+            return Ok(());
+        };
+        let mut line = serde_json::to_vec(&CommittedNonce { address, next }).map_err(io::Error::other)?;
+        line.push(b'\n');
+        OpenOptions::new().create(true).append(true).open(path)?.write_all(&line)
+    }
+
+    /// Next nonce expected to execute for every account that has committed one
+    pub fn committed(&self) -> Vec<(Address, u64)> {
+        self.committed.iter().map(|entry| (*entry.key(), *entry.value())).collect()
+    }
+
+    /// Frees a reserved nonce, e.g. when its transaction is evicted
+    pub fn release(&self, address: &Address, nonce: u64) {
+        if let Some(mut pending) = self.pending.get_mut(address) {
+            pending.remove(&nonce);
+        }
+    }
+}
+
+impl Default for NonceRegistry {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_NONCE_GAP)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_admit_and_commit() {
+        let registry = NonceRegistry::new(2);
+        let alice = Address::new([1u8; 32]);
+
+        assert!(registry.admit(alice, 0).is_ok());
+        assert!(registry.admit(alice, 1).is_ok());
+        assert_eq!(registry.admit(alice, 1), Err(NonceError::AlreadyPending(1)));
+        assert!(matches!(registry.admit(alice, 3), Err(NonceError::TooFarAhead { .. })));
+
+        registry.commit(alice, 0);
+        assert_eq!(registry.next(&alice), 1);
+        assert!(matches!(registry.admit(alice, 0), Err(NonceError::Stale { .. })));
+        assert!(registry.admit(alice, 3).is_ok());
+    }
+
+    #[test]
+    fn test_release_frees_nonce() {
+        let registry = NonceRegistry::default();
+        let alice = Address::new([1u8; 32]);
+
+        registry.admit(alice, 0).unwrap();
+        registry.release(&alice, 0);
+        assert!(registry.admit(alice, 0).is_ok());
+    }
+
+    #[test]
+    fn test_reserve_skips_pending() {
+        let registry = NonceRegistry::new(1);
+        let faucet = Address::new([2u8; 32]);
+
+        registry.admit(faucet, 0).unwrap();
+        assert_eq!(registry.reserve(faucet), Ok(1));
+        assert!(matches!(registry.reserve(faucet), Err(NonceError::TooFarAhead { .. })));
+        registry.commit(faucet, 1);
+        assert_eq!(registry.reserve(faucet), Ok(2));
+    }
+
+    #[test]
+    fn test_journal_replay() {
+        let path = std::env::temp_dir().join(format!("nonces-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let open = || NonceRegistry::default().with_journal(&path).unwrap();
+        let alice = Address::new([1u8; 32]);
+
+        let registry = open();
+        registry.admit(alice, 0).unwrap();
+        registry.admit(alice, 1).unwrap();
+        registry.commit(alice, 1);
+        registry.commit(alice, 0);
+
+        // Executed transactions stay stale across a restart
+        let restarted = open();
+        assert_eq!(restarted.next(&alice), 2);
+        assert!(matches!(restarted.admit(alice, 1), Err(NonceError::Stale { .. })));
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 1);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
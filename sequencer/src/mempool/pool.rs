@@ -0,0 +1,290 @@
+// src/mempool/pool.rs
+
+use romer_common::types::address::Address;
+use romer_common::types::envelope::{EnvelopeError, SignedTransaction};
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashMap};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// Errors returned when a transaction cannot enter the mempool
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum MempoolError {
+    #[error("Transaction already in mempool")]
+    Duplicate,
+
+    #[error("Account has {0} pending transactions, the per-account limit")]
+    AccountLimit(usize),
+
+    #[error("Mempool full and fee {0} does not exceed the lowest pending fee")]
+    FeeTooLow(u64),
+
+    #[error(transparent)]
+    Transaction(#[from] EnvelopeError),
+}
+
+/// Configuration for the mempool
+#[derive(Debug, Clone)]
+pub struct MempoolConfig {
+    /// Maximum number of pending transactions
+    pub max_size: usize,
+    /// Maximum pending transactions per sender
+    pub max_per_account: usize,
+    /// How long a transaction may wait before it is evicted
+    pub ttl: Duration,
+}
+
+impl Default for MempoolConfig {
+    fn default() -> Self {
+        Self {
+            max_size: 10_000,
+            max_per_account: 64,
+            ttl: Duration::from_secs(300),
+        }
+    }
+}
+
+type Digest = [u8; 32];
+
+struct Entry {
+    transaction: SignedTransaction,
+    inserted_at: Instant,
+    /// Arrival order, used to break ties between equal fees
+    arrival: u64,
+}
+
+/// Bounded pool of direct (non-FIX) transactions waiting for inclusion.
+/// Transactions are ordered by fee, highest first, and evicted once they
+/// exceed the TTL or their own expiry.
+pub struct Mempool {
+    config: MempoolConfig,
+    entries: HashMap<Digest, Entry>,
+    /// Priority index: highest fee first, then earliest arrival
+    priority: BTreeSet<(Reverse<u64>, u64, Digest)>,
+    per_account: HashMap<Address, usize>,
+    next_arrival: u64,
+}
+
+impl Mempool {
+    pub fn new(config: MempoolConfig) -> Self {
+        Self {
+            config,
+            entries: HashMap::new(),
+            priority: BTreeSet::new(),
+            per_account: HashMap::new(),
+            next_arrival: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Adds a verified transaction. When the pool is full the lowest-fee
+    /// transaction is evicted to make room, and returned.
+    pub fn insert(
+        &mut self,
+        transaction: SignedTransaction,
+        now: Instant,
+    ) -> Result<Option<SignedTransaction>, MempoolError> {
+        let digest = transaction.digest()?;
+        if self.entries.contains_key(&digest) {
+            return Err(MempoolError::Duplicate);
+        }
+
+        let sender = transaction.transaction.sender;
+        let pending = self.per_account.get(&sender).copied().unwrap_or(0);
+        if pending >= self.config.max_per_account {
+            return Err(MempoolError::AccountLimit(pending));
+        }
+
+        let fee = transaction.transaction.fee;
+        let mut evicted = None;
+        if self.entries.len() >= self.config.max_size {
+            let lowest = match self.priority.iter().next_back() {
+                Some((Reverse(lowest_fee), _, digest)) if *lowest_fee < fee => *digest,
+                _ => return Err(MempoolError::FeeTooLow(fee)),
+            };
+            evicted = self.remove(&lowest);
+        }
+
+        let arrival = self.next_arrival;
+        self.next_arrival += 1;
+        self.priority.insert((Reverse(fee), arrival, digest));
+        *self.per_account.entry(sender).or_insert(0) += 1;
+        self.entries.insert(
+            digest,
+            Entry {
+                transaction,
+                inserted_at: now,
+                arrival,
+            },
+        );
+
+        Ok(evicted)
+    }
+
+    /// Removes a transaction, e.g. once it has been included in a block
+    pub fn remove(&mut self, digest: &Digest) -> Option<SignedTransaction> {
+        let entry = self.entries.remove(digest)?;
+        let fee = entry.transaction.transaction.fee;
+        self.priority.remove(&(Reverse(fee), entry.arrival, *digest));
+
+        let sender = entry.transaction.transaction.sender;
+        if let Some(count) = self.per_account.get_mut(&sender) {
+            *count -= 1;
+            if *count == 0 {
+                self.per_account.remove(&sender);
+            }
+        }
+        Some(entry.transaction)
+    }
+
+    /// Evicts transactions older than the TTL or past their expiry.
+    /// `unix_now` is in seconds.
+    pub fn evict_expired(&mut self, now: Instant, unix_now: u64) -> Vec<SignedTransaction> {
+        let expired: Vec<Digest> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| {
+                now.duration_since(entry.inserted_at) > self.config.ttl
+                    || unix_now > entry.transaction.transaction.expiry
+            })
+            .map(|(digest, _)| *digest)
+            .collect();
+
+        expired.iter().filter_map(|digest| self.remove(digest)).collect()
+    }
+
+    /// Picks up to `limit` transactions for the next block, highest fee
+    /// first, while keeping each sender's transactions in nonce order.
+    /// `next_nonce` returns the next executable nonce for an account.
+    pub fn select(
+        &self,
+        limit: usize,
+        next_nonce: impl Fn(&Address) -> u64,
+    ) -> Vec<SignedTransaction> {
+        let mut expected: HashMap<Address, u64> = HashMap::new();
+        let mut selected = Vec::new();
+        let mut taken: BTreeSet<Digest> = BTreeSet::new();
+
+        // A higher-fee transaction may be waiting on a lower-fee one from the
+        // same sender, so sweep until a pass makes no progress.
+        loop {
+            let mut progressed = false;
+            for (_, _, digest) in &self.priority {
+                if selected.len() >= limit {
+                    return selected;
+                }
+                if taken.contains(digest) {
+                    continue;
+                }
+
+                let transaction = &self.entries[digest].transaction;
+                let sender = transaction.transaction.sender;
+                let next = expected.entry(sender).or_insert_with(|| next_nonce(&sender));
+                if transaction.transaction.nonce != *next {
+                    continue;
+                }
+
+                *next += 1;
+                taken.insert(*digest);
+                selected.push(transaction.clone());
+                progressed = true;
+                // Restart so higher-fee transactions unblocked by this one go first
+                break;
+            }
+            if !progressed {
+                return selected;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use commonware_cryptography::{Ed25519, Scheme};
+    use romer_common::types::envelope::{TransactionPayload, UnsignedTransaction, DEFAULT_CHAIN_ID};
+    use romer_common::types::keymanager::SignatureScheme;
+
+    fn tx(seed: u64, nonce: u64, fee: u64) -> SignedTransaction {
+        let mut signer = Ed25519::from_seed(seed);
+        UnsignedTransaction {
+            chain_id: DEFAULT_CHAIN_ID.to_string(),
+            payload: TransactionPayload::Transfer {
+                to: Address::new([0xAA; 32]),
+                amount: 1,
+            },
+            sender: Address::from_public_key(SignatureScheme::Ed25519, &signer.public_key()),
+            nonce,
+            fee,
+            expiry: 1_000,
+        }
+        .sign(SignatureScheme::Ed25519, &mut signer)
+        .unwrap()
+    }
+
+    fn config(max_size: usize, max_per_account: usize) -> MempoolConfig {
+        MempoolConfig {
+            max_size,
+            max_per_account,
+            ttl: Duration::from_secs(10),
+        }
+    }
+
+    #[test]
+    fn test_fee_ordering_respects_nonces() {
+        let mut pool = Mempool::new(config(10, 10));
+        let now = Instant::now();
+        pool.insert(tx(1, 0, 5), now).unwrap();
+        pool.insert(tx(1, 1, 50), now).unwrap();
+        pool.insert(tx(2, 0, 20), now).unwrap();
+
+        let fees: Vec<u64> = pool
+            .select(10, |_| 0)
+            .iter()
+            .map(|t| t.transaction.fee)
+            .collect();
+        // Sender 1's nonce 1 pays most but must wait for nonce 0
+        assert_eq!(fees, vec![20, 5, 50]);
+        assert_eq!(pool.select(1, |_| 0).len(), 1);
+    }
+
+    #[test]
+    fn test_limits_and_eviction() {
+        let mut pool = Mempool::new(config(2, 1));
+        let now = Instant::now();
+
+        pool.insert(tx(1, 0, 5), now).unwrap();
+        assert_eq!(pool.insert(tx(1, 0, 5), now), Err(MempoolError::Duplicate));
+        assert_eq!(pool.insert(tx(1, 1, 5), now), Err(MempoolError::AccountLimit(1)));
+
+        pool.insert(tx(2, 0, 10), now).unwrap();
+        assert_eq!(pool.insert(tx(3, 0, 5), now), Err(MempoolError::FeeTooLow(5)));
+
+        let evicted = pool.insert(tx(3, 0, 7), now).unwrap().unwrap();
+        assert_eq!(evicted.transaction.fee, 5);
+        assert_eq!(pool.len(), 2);
+    }
+
+    #[test]
+    fn test_ttl_eviction() {
+        let mut pool = Mempool::new(config(10, 10));
+        let start = Instant::now();
+        pool.insert(tx(1, 0, 5), start).unwrap();
+        pool.insert(tx(2, 0, 5), start + Duration::from_secs(8)).unwrap();
+
+        let evicted = pool.evict_expired(start + Duration::from_secs(11), 0);
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(pool.len(), 1);
+
+        // Envelope expiry applies regardless of TTL
+        assert_eq!(pool.evict_expired(start + Duration::from_secs(11), 1_001).len(), 1);
+        assert!(pool.is_empty());
+    }
+}
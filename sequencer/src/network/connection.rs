@@ -1,36 +1,45 @@
 // src/network/connection.rs
 
-use crate::network::types::{Connection, IncomingMessage, OutgoingMessage, NetworkError, NetworkResult};
+use crate::network::types::{Connection, IncomingMessage, NetworkError, NetworkEvent, NetworkResult};
 use crate::network::codec::FixCodec;
-use tokio::io::{BufReader, BufWriter};
-use tokio::net::TcpStream;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
 use tokio::sync::mpsc;
-use bytes::{BytesMut, BufMut};
+use tokio::task::JoinHandle;
+use bytes::BytesMut;
 use std::sync::Arc;
+use std::time::Duration;
 use parking_lot::Mutex;
-use tracing::{info, warn, error, debug};
+use romer_common::utils::clock::SharedClock;
+use tracing::{debug, warn};
+use uuid::Uuid;
 
 /// Size of the TCP read buffer
 const READ_BUFFER_SIZE: usize = 8192;
 
-/// Manages an individual TCP connection
+/// Messages waiting to be written to one connection
+const OUTBOUND_QUEUE: usize = 1024;
+
+/// Manages an individual TCP connection: a reader task frames messages off
+/// the socket into the network manager's channel, stamped with when they
+/// were read, and a writer task drains the connection's outbound queue
 pub struct ConnectionHandler {
     /// The connection being handled
     connection: Connection,
-    /// Buffer for incoming data
-    read_buffer: BytesMut,
-    /// Buffer for outgoing data
-    write_buffer: BytesMut,
     /// FIX message codec
     codec: FixCodec,
     /// Channel for forwarding processed messages
-    message_tx: mpsc::Sender<IncomingMessage>,
+    message_tx: mpsc::Sender<NetworkEvent>,
+    /// Connections silent for this long are closed
+    idle_timeout: Duration,
+    /// Time source for stamping received messages
+    clock: SharedClock,
     /// Statistics for this connection
     stats: Arc<Mutex<ConnectionStats>>,
 }
 
 /// Statistics for a single connection
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct ConnectionStats {
     /// Number of messages received
     pub messages_received: u64,
@@ -42,223 +51,209 @@ pub struct ConnectionStats {
     pub bytes_sent: u64,
     /// Number of framing errors detected
     pub framing_errors: u64,
-    /// Number of parse errors
-    pub parse_errors: u64,
 }
 
 impl ConnectionHandler {
     /// Create a new connection handler
     pub fn new(
         connection: Connection,
-        message_tx: mpsc::Sender<IncomingMessage>,
+        message_tx: mpsc::Sender<NetworkEvent>,
+        idle_timeout: Duration,
+        clock: SharedClock,
     ) -> Self {
         Self {
             connection,
-            read_buffer: BytesMut::with_capacity(READ_BUFFER_SIZE),
-            write_buffer: BytesMut::with_capacity(READ_BUFFER_SIZE),
             codec: FixCodec::new(),
             message_tx,
+            idle_timeout,
+            clock,
             stats: Arc::new(Mutex::new(ConnectionStats::default())),
         }
     }
 
-    /// Start processing the connection
-    pub async fn run(&mut self) -> NetworkResult<()> {
-        // Split the TCP stream
-        let (read_half, write_half) = self.connection.stream.split();
-        let mut reader = BufReader::new(read_half);
-        let mut writer = BufWriter::new(write_half);
+    /// Frame messages with `codec`
+    pub fn with_codec(mut self, codec: FixCodec) -> Self {
+        self.codec = codec;
+        self
+    }
 
-        // Create channel for coordinating read and write tasks
-        let (write_tx, mut write_rx) = mpsc::channel(100);
+    /// Statistics of this connection, updated while it runs
+    pub fn stats(&self) -> Arc<Mutex<ConnectionStats>> {
+        self.stats.clone()
+    }
 
-        // Spawn read task
+    /// Starts the reader and writer tasks. Messages sent on the returned
+    /// channel are written in order; dropping it closes the connection once
+    /// they are written. `Closed` is reported when the reader stops, which
+    /// is when the returned handle completes.
+    pub fn spawn(self) -> (mpsc::Sender<Vec<u8>>, JoinHandle<()>) {
         let connection_id = self.connection.connection_id;
-        let message_tx = self.message_tx.clone();
-        let stats = self.stats.clone();
-        let mut read_buffer = BytesMut::with_capacity(READ_BUFFER_SIZE);
-        let read_task = tokio::spawn(async move {
-            let mut tmp_buf = [0u8; READ_BUFFER_SIZE];
-            
-            loop {
-                // Read from TCP stream
-                match reader.read(&mut tmp_buf).await {
-                    Ok(0) => {
-                        // EOF - connection closed
-                        break;
-                    }
-                    Ok(n) => {
-                        // Update statistics
-                        stats.lock().bytes_received += n as u64;
+        let (read_half, write_half) = self.connection.stream.into_split();
+        let (outbound_tx, outbound_rx) = mpsc::channel(OUTBOUND_QUEUE);
+        let (closed_tx, closed_rx) = tokio::sync::oneshot::channel();
+
+        tokio::spawn(write_loop(connection_id, write_half, outbound_rx, self.stats.clone(), closed_tx));
+        let reader = Reader {
+            connection_id,
+            codec: self.codec,
+            message_tx: self.message_tx,
+            idle_timeout: self.idle_timeout,
+            clock: self.clock,
+            stats: self.stats,
+        };
+        let reader = tokio::spawn(async move {
+            let message_tx = reader.message_tx.clone();
+            // The writer finishing means the connection was closed by us
+            let result = tokio::select! {
+                result = reader.run(read_half) => result,
+                _ = closed_rx => Ok(()),
+            };
+            if let Err(e) = result {
+                debug!(connection_id = %connection_id, error = %e, "Connection ended");
+            }
+            let _ = message_tx.send(NetworkEvent::Closed(connection_id)).await;
+        });
+        (outbound_tx, reader)
+    }
+}
 
-                        // Append to buffer
-                        read_buffer.put_slice(&tmp_buf[..n]);
+struct Reader {
+    connection_id: Uuid,
+    codec: FixCodec,
+    message_tx: mpsc::Sender<NetworkEvent>,
+    idle_timeout: Duration,
+    clock: SharedClock,
+    stats: Arc<Mutex<ConnectionStats>>,
+}
 
-                        // Process complete messages
-                        while let Some(msg) = FixCodec::try_parse(&mut read_buffer)? {
-                            stats.lock().messages_received += 1;
-                            
-                            // Forward message
-                            let incoming = IncomingMessage {
-                                connection_id,
-                                data: msg.to_vec(),
-                                received_at: std::time::Instant::now(),
-                            };
-                            
-                            if let Err(e) = message_tx.send(incoming).await {
-                                error!(
-                                    connection_id = %connection_id,
-                                    error = %e,
-                                    "Failed to forward message"
-                                );
-                                return Err(NetworkError::SendError(e.to_string()));
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        return Err(NetworkError::ConnectionError(e));
-                    }
+impl Reader {
+    async fn run(&self, mut reader: OwnedReadHalf) -> NetworkResult<()> {
+        let mut read_buffer = BytesMut::with_capacity(READ_BUFFER_SIZE);
+        let mut tmp_buf = [0u8; READ_BUFFER_SIZE];
+        loop {
+            let n = match tokio::time::timeout(self.idle_timeout, reader.read(&mut tmp_buf)).await {
+                Ok(read) => read?,
+                Err(_) => {
+                    warn!(connection_id = %self.connection_id, "Connection idle timeout");
+                    return Ok(());
                 }
+            };
+            if n == 0 {
+                // EOF - connection closed
+                return Ok(());
             }
+            let received_at = self.clock.instant();
+            self.stats.lock().bytes_received += n as u64;
+            read_buffer.extend_from_slice(&tmp_buf[..n]);
 
-            Ok(())
-        });
-
-        // Spawn write task
-        let stats = self.stats.clone();
-        let write_task = tokio::spawn(async move {
-            let mut write_buffer = BytesMut::with_capacity(READ_BUFFER_SIZE);
-            
-            while let Some(msg) = write_rx.recv().await {
-                // Add message to buffer
-                write_buffer.put_slice(&msg.data);
-                
-                // Write to TCP stream
-                match writer.write_all(&write_buffer).await {
-                    Ok(_) => {
-                        stats.lock().bytes_sent += write_buffer.len() as u64;
-                        stats.lock().messages_sent += 1;
-                        
-                        // Clear buffer after successful write
-                        write_buffer.clear();
-                    }
+            // Process complete messages
+            loop {
+                let message = match self.codec.try_parse(&mut read_buffer) {
+                    Ok(Some(message)) => message,
+                    Ok(None) => break,
                     Err(e) => {
-                        return Err(NetworkError::ConnectionError(e));
+                        self.stats.lock().framing_errors += 1;
+                        return Err(e);
                     }
-                }
-                
-                // Ensure data is sent
-                if let Err(e) = writer.flush().await {
-                    return Err(NetworkError::ConnectionError(e));
-                }
-            }
-
-            Ok(())
-        });
-
-        // Handle incoming messages from connection manager
-        while let Some(message) = self.connection.message_rx.recv().await {
-            if let Err(e) = write_tx.send(message).await {
-                error!(
-                    connection_id = %self.connection.connection_id,
-                    error = %e,
-                    "Failed to forward outgoing message"
-                );
-                break;
+                };
+                self.stats.lock().messages_received += 1;
+                let incoming = IncomingMessage {
+                    connection_id: self.connection_id,
+                    data: message.to_vec(),
+                    received_at,
+                };
+                self.message_tx
+                    .send(NetworkEvent::Message(incoming))
+                    .await
+                    .map_err(|e| NetworkError::SendError(e.to_string()))?;
             }
         }
-
-        // Wait for tasks to complete
-        let (read_result, write_result) = tokio::join!(read_task, write_task);
-
-        // Check for errors
-        if let Err(e) = read_result {
-            error!(
-                connection_id = %self.connection.connection_id,
-                error = %e,
-                "Read task panicked"
-            );
-        }
-
-        if let Err(e) = write_result {
-            error!(
-                connection_id = %self.connection.connection_id,
-                error = %e,
-                "Write task panicked"
-            );
-        }
-
-        Ok(())
     }
+}
 
-    /// Get statistics for this connection
-    pub fn get_stats(&self) -> ConnectionStats {
-        self.stats.lock().clone()
+async fn write_loop(
+    connection_id: Uuid,
+    mut writer: OwnedWriteHalf,
+    mut outbound: mpsc::Receiver<Vec<u8>>,
+    stats: Arc<Mutex<ConnectionStats>>,
+    closed: tokio::sync::oneshot::Sender<()>,
+) {
+    while let Some(data) = outbound.recv().await {
+        if let Err(e) = writer.write_all(&data).await {
+            debug!(connection_id = %connection_id, error = %e, "Failed to write to connection");
+            break;
+        }
+        let mut stats = stats.lock();
+        stats.bytes_sent += data.len() as u64;
+        stats.messages_sent += 1;
     }
+    let _ = writer.shutdown().await;
+    let _ = closed.send(());
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tokio::net::TcpListener;
-    use std::net::SocketAddr;
+    use romer_common::types::fix::utils::encode_message;
+    use romer_common::utils::clock::system_clock;
+    use tokio::net::{TcpListener, TcpStream};
 
-    async fn create_test_connection() -> (ConnectionHandler, TcpStream) {
-        // Create test server
+    async fn create_test_connection(
+        idle_timeout: Duration,
+    ) -> (ConnectionHandler, TcpStream, mpsc::Receiver<NetworkEvent>) {
         let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
-        let addr = listener.local_addr().unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let (server, remote_addr) = listener.accept().await.unwrap();
 
-        // Create client connection
-        let client = TcpStream::connect(addr).await.unwrap();
-        let (server, _) = listener.accept().await.unwrap();
-
-        // Create connection handler
-        let (tx, _) = mpsc::channel(10);
-        let connection = Connection::new(server, addr);
-        let handler = ConnectionHandler::new(connection, tx);
-
-        (handler, client)
+        let (tx, rx) = mpsc::channel(10);
+        let handler = ConnectionHandler::new(Connection::new(server, remote_addr), tx, idle_timeout, system_clock());
+        (handler, client, rx)
     }
 
     #[tokio::test]
     async fn test_connection_lifecycle() {
-        let (mut handler, client) = create_test_connection().await;
-
-        // Start handler in background
-        let handle = tokio::spawn(async move {
-            handler.run().await.unwrap();
-        });
+        let (handler, client, mut events) = create_test_connection(Duration::from_secs(30)).await;
+        let (_outbound, _reader) = handler.spawn();
 
-        // Close client connection
+        // Closing the client ends the reader
         drop(client);
-
-        // Wait for handler to finish
-        handle.await.unwrap();
+        assert!(matches!(events.recv().await, Some(NetworkEvent::Closed(_))));
     }
 
     #[tokio::test]
     async fn test_message_processing() {
-        let (mut handler, mut client) = create_test_connection().await;
-
-        // Start handler in background
-        let handle = tokio::spawn(async move {
-            handler.run().await.unwrap();
-        });
-
-        // Send test message
-        let test_msg = b"8=FIX.4.2\x019=0\x0135=0\x0110=0\x01";
-        client.write_all(test_msg).await.unwrap();
-
-        // Wait a bit for processing
-        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-
-        // Check statistics
-        let stats = handler.get_stats();
-        assert_eq!(stats.messages_received, 1);
-        assert_eq!(stats.bytes_received, test_msg.len() as u64);
+        let (handler, mut client, mut events) = create_test_connection(Duration::from_secs(30)).await;
+        let stats = handler.stats();
+        let (outbound, _reader) = handler.spawn();
+
+        // Two messages in one write are framed separately
+        let test_msg = encode_message("FIX.4.2", &[(35, "0".to_string())]);
+        client.write_all(&[test_msg.clone(), test_msg.clone()].concat()).await.unwrap();
+        for _ in 0..2 {
+            match events.recv().await {
+                Some(NetworkEvent::Message(message)) => assert_eq!(message.data, test_msg),
+                other => panic!("expected a message, got {:?}", other),
+            }
+        }
+        assert_eq!(stats.lock().messages_received, 2);
+
+        // Outbound messages reach the client
+        outbound.send(test_msg.clone()).await.unwrap();
+        let mut reply = vec![0u8; test_msg.len()];
+        client.read_exact(&mut reply).await.unwrap();
+        assert_eq!(reply, test_msg);
+
+        // Dropping the outbound queue closes the connection
+        drop(outbound);
+        assert!(matches!(events.recv().await, Some(NetworkEvent::Closed(_))));
+        assert_eq!(client.read(&mut reply).await.unwrap(), 0);
+    }
 
-        // Clean up
-        drop(client);
-        handle.await.unwrap();
+    #[tokio::test]
+    async fn test_idle_connection_closed() {
+        let (handler, _client, mut events) = create_test_connection(Duration::from_millis(100)).await;
+        let (_outbound, _reader) = handler.spawn();
+        let closed = tokio::time::timeout(Duration::from_secs(2), events.recv()).await.unwrap();
+        assert!(matches!(closed, Some(NetworkEvent::Closed(_))));
     }
-}
\ No newline at end of file
+}
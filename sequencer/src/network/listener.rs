@@ -1,6 +1,7 @@
 // src/network/listener.rs
 
 use crate::network::types::{Connection, NetworkConfig, NetworkResult, NetworkError, NetworkStats};
+use std::net::SocketAddr;
 use tokio::net::TcpListener;
 use tokio::sync::mpsc;
 use tokio::sync::broadcast;
@@ -19,80 +20,77 @@ pub enum ListenerControl {
     Shutdown,
 }
 
+/// What woke the accept loop
+enum Event {
+    Accepted(std::io::Result<(tokio::net::TcpStream, SocketAddr)>),
+    Control(Result<ListenerControl, broadcast::error::RecvError>),
+}
+
 /// Manages TCP connection acceptance
 pub struct ConnectionListener {
+    /// Bound socket, released while paused so connections are refused
+    listener: Option<TcpListener>,
+    /// Address the listener is bound to, kept to rebind on resume
+    local_addr: SocketAddr,
     /// Server configuration
     config: NetworkConfig,
-    /// Current server statistics
+    /// Statistics shared with the network manager, which counts the
+    /// connections it is handling
     stats: Arc<RwLock<NetworkStats>>,
     /// Channel for new connection notifications
     connection_tx: mpsc::Sender<Connection>,
     /// Channel for control messages
     control_rx: broadcast::Receiver<ListenerControl>,
-    /// Whether we're currently accepting connections
-    accepting: Arc<RwLock<bool>>,
 }
 
 impl ConnectionListener {
-    /// Create a new connection listener
-    pub fn new(
+    /// Bind the configured address
+    pub async fn bind(
         config: NetworkConfig,
+        stats: Arc<RwLock<NetworkStats>>,
         connection_tx: mpsc::Sender<Connection>,
         control_rx: broadcast::Receiver<ListenerControl>,
-    ) -> Self {
-        Self {
+    ) -> NetworkResult<Self> {
+        let listener = TcpListener::bind(&config.bind_address).await?;
+        let local_addr = listener.local_addr()?;
+        Ok(Self {
+            listener: Some(listener),
+            local_addr,
             config,
-            stats: Arc::new(RwLock::new(NetworkStats::default())),
+            stats,
             connection_tx,
             control_rx,
-            accepting: Arc::new(RwLock::new(true)),
-        }
+        })
     }
 
-    /// Start accepting connections
-    pub async fn run(&mut self) -> NetworkResult<()> {
-        // Bind to the configured address
-        let listener = TcpListener::bind(&self.config.bind_address).await
-            .map_err(NetworkError::ConnectionError)?;
+    /// Address connections are accepted on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
 
-        info!(
-            address = %self.config.bind_address,
-            "Connection listener started"
-        );
+    /// Start accepting connections
+    pub async fn run(mut self) -> NetworkResult<()> {
+        info!(address = %self.local_addr, "Connection listener started");
 
         loop {
-            // Check for control messages
-            if let Ok(control) = self.control_rx.try_recv() {
-                match control {
-                    ListenerControl::Pause => {
-                        *self.accepting.write() = false;
-                        info!("Connection acceptance paused");
-                        continue;
-                    }
-                    ListenerControl::Resume => {
-                        *self.accepting.write() = true;
-                        info!("Connection acceptance resumed");
-                    }
-                    ListenerControl::Shutdown => {
-                        info!("Connection listener shutting down");
+            let event = match &self.listener {
+                Some(listener) => tokio::select! {
+                    accepted = listener.accept() => Event::Accepted(accepted),
+                    control = self.control_rx.recv() => Event::Control(control),
+                },
+                None => Event::Control(self.control_rx.recv().await),
+            };
+            let accepted = match event {
+                Event::Accepted(accepted) => accepted,
+                Event::Control(control) => {
+                    if !self.control(control).await? {
                         break;
                     }
+                    continue;
                 }
-            }
-
-            // Only accept if we're in accepting state
-            if !*self.accepting.read() {
-                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-                continue;
-            }
-
-            // Accept new connection
-            let accept_result = tokio::select! {
-                result = listener.accept() => result,
-                _ = self.control_rx.recv() => continue,
             };
 
-            match accept_result {
+            match accepted {
                 Ok((stream, addr)) => {
                     // Check connection limit
                     let current_connections = self.stats.read().active_connections;
@@ -107,159 +105,124 @@ impl ConnectionListener {
                         continue;
                     }
 
-                    // Configure the TCP stream
-                    if let Err(e) = self.configure_stream(&stream) {
-                        error!(
-                            remote = %addr,
-                            error = %e,
-                            "Failed to configure connection"
-                        );
+                    // Set TCP_NODELAY to reduce latency
+                    if let Err(e) = stream.set_nodelay(true) {
+                        error!(remote = %addr, error = %e, "Failed to configure connection");
                         self.stats.write().failed_connections += 1;
                         continue;
                     }
 
-                    // Create new connection
-                    let (connection, _) = Connection::new(stream, addr);
-                    let connection_id = connection.connection_id;
-
-                    // Send to connection manager
-                    if let Err(e) = self.connection_tx.send(connection).await {
-                        error!(
-                            connection_id = %connection_id,
-                            error = %e,
-                            "Failed to send connection to manager"
-                        );
-                        self.stats.write().failed_connections += 1;
-                        continue;
+                    let connection = Connection::new(stream, addr);
+                    info!(connection_id = %connection.connection_id, remote = %addr, "New connection accepted");
+                    if self.connection_tx.send(connection).await.is_err() {
+                        // The network manager is gone
+                        break;
                     }
-
-                    // Update statistics
-                    let mut stats = self.stats.write();
-                    stats.active_connections += 1;
-
-                    info!(
-                        connection_id = %connection_id,
-                        remote = %addr,
-                        active = stats.active_connections,
-                        "New connection accepted"
-                    );
                 }
                 Err(e) => {
-                    error!(
-                        error = %e,
-                        "Failed to accept connection"
-                    );
+                    error!(error = %e, "Failed to accept connection");
                     self.stats.write().failed_connections += 1;
                 }
             }
         }
 
+        info!("Connection listener shutting down");
         Ok(())
     }
 
-    /// Configure TCP stream options
-    fn configure_stream(&self, stream: &tokio::net::TcpStream) -> NetworkResult<()> {
-        // Set TCP_NODELAY to reduce latency
-        stream.set_nodelay(true)
-            .map_err(NetworkError::ConnectionError)?;
-
-        // Set keep-alive to detect dead connections
-        stream.set_keepalive(Some(std::time::Duration::from_secs(60)))
-            .map_err(NetworkError::ConnectionError)?;
-
-        Ok(())
+    /// Applies a control message, returning whether to keep running
+    async fn control(
+        &mut self,
+        control: Result<ListenerControl, broadcast::error::RecvError>,
+    ) -> NetworkResult<bool> {
+        match control {
+            Ok(ListenerControl::Pause) => {
+                if self.listener.take().is_some() {
+                    info!("Connection acceptance paused");
+                }
+            }
+            Ok(ListenerControl::Resume) => {
+                if self.listener.is_none() {
+                    self.listener = Some(TcpListener::bind(self.local_addr).await?);
+                    info!("Connection acceptance resumed");
+                }
+            }
+            Ok(ListenerControl::Shutdown) | Err(broadcast::error::RecvError::Closed) => return Ok(false),
+            Err(broadcast::error::RecvError::Lagged(_)) => {}
+        }
+        Ok(true)
     }
+}
 
-    /// Get current listener statistics
-    pub fn get_stats(&self) -> NetworkStats {
-        self.stats.read().clone()
+impl From<broadcast::error::SendError<ListenerControl>> for NetworkError {
+    fn from(e: broadcast::error::SendError<ListenerControl>) -> Self {
+        NetworkError::SendError(e.to_string())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tokio::net::TcpSocket;
-
-    async fn create_test_listener() -> (ConnectionListener, broadcast::Sender<ListenerControl>) {
-        // Create channels
-        let (connection_tx, _) = mpsc::channel(10);
+    use tokio::net::TcpStream;
+
+    async fn create_test_listener() -> (
+        ConnectionListener,
+        mpsc::Receiver<Connection>,
+        broadcast::Sender<ListenerControl>,
+    ) {
+        let (connection_tx, connection_rx) = mpsc::channel(10);
         let (control_tx, control_rx) = broadcast::channel(10);
 
         // Create config with random available port
-        let mut config = NetworkConfig::default();
-        config.bind_address = "127.0.0.1:0".to_string();
-
-        let listener = ConnectionListener::new(
-            config,
-            connection_tx,
-            control_rx,
-        );
-
-        (listener, control_tx)
+        let config = NetworkConfig {
+            bind_address: "127.0.0.1:0".to_string(),
+            ..NetworkConfig::default()
+        };
+        let stats = Arc::new(RwLock::new(NetworkStats::default()));
+        let listener = ConnectionListener::bind(config, stats, connection_tx, control_rx).await.unwrap();
+
+        (listener, connection_rx, control_tx)
     }
 
     #[tokio::test]
     async fn test_listener_lifecycle() {
-        let (mut listener, control_tx) = create_test_listener().await;
+        let (listener, _connections, control_tx) = create_test_listener().await;
+        let handle = tokio::spawn(listener.run());
 
-        // Start listener in background
-        let handle = tokio::spawn(async move {
-            listener.run().await.unwrap();
-        });
-
-        // Send shutdown signal
         control_tx.send(ListenerControl::Shutdown).unwrap();
-
-        // Wait for shutdown
-        handle.await.unwrap();
+        handle.await.unwrap().unwrap();
     }
 
     #[tokio::test]
     async fn test_connection_acceptance() {
-        let (mut listener, _) = create_test_listener().await;
-
-        // Start listener in background
-        let handle = tokio::spawn(async move {
-            listener.run().await.unwrap();
-        });
+        let (listener, mut connections, _control_tx) = create_test_listener().await;
+        let addr = listener.local_addr();
+        let handle = tokio::spawn(listener.run());
 
-        // Create test connection
-        let socket = TcpSocket::new_v4().unwrap();
-        let addr = listener.config.bind_address.parse().unwrap();
-        let _stream = socket.connect(addr).await.unwrap();
-
-        // Check stats
-        assert_eq!(listener.get_stats().active_connections, 1);
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let connection = connections.recv().await.unwrap();
+        assert_eq!(connection.remote_addr, stream.local_addr().unwrap());
 
         handle.abort();
     }
 
     #[tokio::test]
     async fn test_pause_resume() {
-        let (mut listener, control_tx) = create_test_listener().await;
-
-        // Start listener in background
-        let handle = tokio::spawn(async move {
-            listener.run().await.unwrap();
-        });
+        let (listener, mut connections, control_tx) = create_test_listener().await;
+        let addr = listener.local_addr();
+        let handle = tokio::spawn(listener.run());
 
-        // Pause acceptance
+        // Paused, connections are refused
         control_tx.send(ListenerControl::Pause).unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(TcpStream::connect(addr).await.is_err());
 
-        // Try connection - should fail
-        let socket = TcpSocket::new_v4().unwrap();
-        let addr = listener.config.bind_address.parse().unwrap();
-        let result = socket.connect(addr).await;
-        assert!(result.is_err());
-
-        // Resume acceptance
+        // Resumed, they are accepted again on the same address
         control_tx.send(ListenerControl::Resume).unwrap();
-
-        // Try connection again - should succeed
-        let socket = TcpSocket::new_v4().unwrap();
-        let _stream = socket.connect(addr).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        let _stream = TcpStream::connect(addr).await.unwrap();
+        assert!(connections.recv().await.is_some());
 
         handle.abort();
     }
-}
\ No newline at end of file
+}
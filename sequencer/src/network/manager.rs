@@ -1,217 +1,186 @@
 // src/network/manager.rs
 
-use crate::network::types::{Connection, NetworkConfig, NetworkStats, NetworkError, NetworkResult};
+use crate::network::types::{NetworkConfig, NetworkEvent, NetworkStats, NetworkError, NetworkResult};
 use crate::network::listener::{ConnectionListener, ListenerControl};
 use crate::network::connection::ConnectionHandler;
+use crate::network::codec::FixCodec;
+use crate::risk::drain::DrainMode;
 use tokio::sync::{mpsc, broadcast};
+use tokio::sync::mpsc::error::TrySendError;
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 use parking_lot::RwLock;
+use romer_common::utils::clock::SharedClock;
 use uuid::Uuid;
 use tracing::{info, warn, error, debug};
 
-/// Manages all network operations and connections
+/// Manages all network operations and connections. Every connection gets
+/// its own reader and writer task; what they read arrives on the event
+/// channel returned by `start`, and replies are queued with `send`.
+#[derive(Clone)]
 pub struct NetworkManager {
     /// Configuration settings
     config: NetworkConfig,
-    /// Active connections by ID
-    connections: Arc<RwLock<HashMap<Uuid, Connection>>>,
+    /// Outbound queue of each open connection
+    connections: Arc<RwLock<HashMap<Uuid, mpsc::Sender<Vec<u8>>>>>,
     /// Network statistics
     stats: Arc<RwLock<NetworkStats>>,
-    /// Channel for new connections from listener
-    connection_rx: mpsc::Receiver<Connection>,
     /// Channel for sending listener control messages
     listener_tx: broadcast::Sender<ListenerControl>,
-    /// Channel for processed messages
-    message_tx: mpsc::Sender<IncomingMessage>,
-    /// Health check interval in seconds
-    health_check_interval: u64,
+    /// When set, pausing drains order flow instead of refusing connections
+    drain: Option<Arc<DrainMode>>,
+    /// Stamps the time messages are read
+    clock: SharedClock,
 }
 
 impl NetworkManager {
     /// Create a new network manager
-    pub fn new(
-        config: NetworkConfig,
-        message_tx: mpsc::Sender<IncomingMessage>,
-    ) -> NetworkResult<Self> {
-        // Create channels
-        let (connection_tx, connection_rx) = mpsc::channel(100);
+    pub fn new(config: NetworkConfig, clock: SharedClock) -> Self {
         let (listener_tx, _) = broadcast::channel(10);
+        Self {
+            config,
+            connections: Arc::new(RwLock::new(HashMap::new())),
+            stats: Arc::new(RwLock::new(NetworkStats::default())),
+            listener_tx,
+            drain: None,
+            clock,
+        }
+    }
 
-        // Create listener
-        let mut listener = ConnectionListener::new(
-            config.clone(),
-            connection_tx,
-            listener_tx.subscribe(),
-        );
+    /// Pause by entering `drain` rather than refusing TCP connections
+    pub fn with_drain_mode(mut self, drain: Arc<DrainMode>) -> Self {
+        self.drain = Some(drain);
+        self
+    }
 
-        // Start listener in background
+    /// Binds the configured address and starts accepting connections.
+    /// Returns the bound address and the channel every connection's
+    /// messages and closure arrive on.
+    pub async fn start(&self) -> NetworkResult<(SocketAddr, mpsc::Receiver<NetworkEvent>)> {
+        let (connection_tx, mut connection_rx) = mpsc::channel(100);
+        let (event_tx, event_rx) = mpsc::channel(self.config.message_buffer_size);
+        let listener = ConnectionListener::bind(
+            self.config.clone(),
+            self.stats.clone(),
+            connection_tx,
+            self.listener_tx.subscribe(),
+        )
+        .await?;
+        let local_addr = listener.local_addr();
         tokio::spawn(async move {
             if let Err(e) = listener.run().await {
                 error!(error = %e, "Listener error");
             }
         });
 
-        Ok(Self {
-            config,
-            connections: Arc::new(RwLock::new(HashMap::new())),
-            stats: Arc::new(RwLock::new(NetworkStats::default())),
-            connection_rx,
-            listener_tx,
-            message_tx,
-            health_check_interval: 30,
-        })
-    }
-
-    /// Start the network manager
-    pub async fn run(&mut self) -> NetworkResult<()> {
-        info!("Starting network manager");
-
-        // Start health check timer
-        let health_check_interval = tokio::time::Duration::from_secs(self.health_check_interval);
-        let mut health_check = tokio::time::interval(health_check_interval);
+        let manager = self.clone();
+        tokio::spawn(async move {
+            while let Some(connection) = connection_rx.recv().await {
+                let connection_id = connection.connection_id;
+                let handler = ConnectionHandler::new(
+                    connection,
+                    event_tx.clone(),
+                    manager.config.idle_timeout,
+                    manager.clock.clone(),
+                )
+                .with_codec(FixCodec::new().with_max_message_size(manager.config.max_message_size));
+                let (outbound, reader) = handler.spawn();
+                manager.connections.write().insert(connection_id, outbound);
+                manager.stats.write().active_connections += 1;
+
+                // Forget the connection once its reader stops
+                let cleanup = manager.clone();
+                tokio::spawn(async move {
+                    let _ = reader.await;
+                    cleanup.remove(connection_id);
+                    debug!(connection_id = %connection_id, "Connection handler stopped");
+                });
+            }
+        });
 
-        loop {
-            tokio::select! {
-                // Handle new connections
-                Some(connection) = self.connection_rx.recv() => {
-                    self.handle_new_connection(connection).await?;
-                }
+        info!(address = %local_addr, "Network manager started");
+        Ok((local_addr, event_rx))
+    }
 
-                // Periodic health check
-                _ = health_check.tick() => {
-                    self.check_connection_health().await;
-                }
+    /// Queues `data` to be written to a connection. A connection whose
+    /// queue is full is not keeping up and is closed.
+    pub fn send(&self, connection_id: Uuid, data: Vec<u8>) -> NetworkResult<()> {
+        let outbound = self
+            .connections
+            .read()
+            .get(&connection_id)
+            .cloned()
+            .ok_or(NetworkError::ConnectionNotFound(connection_id))?;
+        match outbound.try_send(data) {
+            Ok(()) => {
+                self.stats.write().messages_sent += 1;
+                Ok(())
+            }
+            Err(TrySendError::Full(_)) => {
+                warn!(connection_id = %connection_id, "Connection not keeping up, closing");
+                self.close(connection_id);
+                Err(NetworkError::SendError("outbound queue full".into()))
             }
+            Err(TrySendError::Closed(_)) => Err(NetworkError::ConnectionNotFound(connection_id)),
         }
     }
 
-    /// Handle a new incoming connection
-    async fn handle_new_connection(&mut self, connection: Connection) -> NetworkResult<()> {
-        let connection_id = connection.connection_id;
-        let remote_addr = connection.remote_addr;
-
-        // Store connection
-        self.connections.write().insert(connection_id, connection.clone());
-
-        // Create message channels
-        let (message_tx, message_rx) = mpsc::channel(self.config.message_buffer_size);
-
-        // Create connection handler
-        let mut handler = ConnectionHandler::new(
-            connection,
-            message_tx,
-        );
+    /// Closes a connection once what is queued for it has been written
+    pub fn close(&self, connection_id: Uuid) {
+        self.remove(connection_id);
+    }
 
-        // Start handler in background
-        let connections = self.connections.clone();
-        let stats = self.stats.clone();
+    /// Holds a connection open for `grace` before closing it
+    pub fn close_after(&self, connection_id: Uuid, grace: Duration) {
+        let manager = self.clone();
         tokio::spawn(async move {
-            debug!(
-                connection_id = %connection_id,
-                remote = %remote_addr,
-                "Starting connection handler"
-            );
-
-            // Run the handler
-            if let Err(e) = handler.run().await {
-                error!(
-                    connection_id = %connection_id,
-                    error = %e,
-                    "Connection handler error"
-                );
-            }
-
-            // Clean up connection
-            connections.write().remove(&connection_id);
-            stats.write().active_connections -= 1;
-
-            debug!(
-                connection_id = %connection_id,
-                "Connection handler stopped"
-            );
+            tokio::time::sleep(grace).await;
+            manager.close(connection_id);
         });
-
-        // Update statistics
-        self.stats.write().active_connections += 1;
-
-        info!(
-            connection_id = %connection_id,
-            remote = %remote_addr,
-            "New connection initialized"
-        );
-
-        Ok(())
     }
 
-    /// Check health of all connections
-    async fn check_connection_health(&self) {
-        let mut to_remove = Vec::new();
-
-        // Check each connection
-        for (id, conn) in self.connections.read().iter() {
-            // Check if connection is idle
-            if conn.is_idle(self.config.idle_timeout) {
-                warn!(
-                    connection_id = %id,
-                    remote = %conn.remote_addr,
-                    "Connection idle timeout"
-                );
-                to_remove.push(*id);
-                continue;
-            }
-
-            // Add other health checks as needed
-        }
-
-        // Remove dead connections
-        if !to_remove.is_empty() {
-            let mut connections = self.connections.write();
-            let mut stats = self.stats.write();
-            
-            for id in to_remove {
-                connections.remove(&id);
-                stats.active_connections -= 1;
-            }
+    /// Drops the connection's outbound queue, which ends its writer
+    fn remove(&self, connection_id: Uuid) {
+        if self.connections.write().remove(&connection_id).is_some() {
+            self.stats.write().active_connections -= 1;
         }
     }
 
-    /// Pause accepting new connections
+    /// Pause order flow. With drain mode, connections keep being accepted
+    /// and only new orders are rejected; otherwise stop accepting
+    /// connections.
     pub fn pause(&self) -> NetworkResult<()> {
-        self.listener_tx.send(ListenerControl::Pause)
-            .map_err(|e| NetworkError::SendError(e.to_string()))?;
+        if let Some(drain) = &self.drain {
+            drain.enter("listener paused", "network");
+            info!("Network manager draining");
+            return Ok(());
+        }
+        self.listener_tx.send(ListenerControl::Pause)?;
         info!("Network manager paused");
         Ok(())
     }
 
     /// Resume accepting connections
     pub fn resume(&self) -> NetworkResult<()> {
-        self.listener_tx.send(ListenerControl::Resume)
-            .map_err(|e| NetworkError::SendError(e.to_string()))?;
+        if let Some(drain) = &self.drain {
+            drain.exit("network");
+        }
+        self.listener_tx.send(ListenerControl::Resume)?;
         info!("Network manager resumed");
         Ok(())
     }
 
-    /// Gracefully shutdown the network manager
-    pub async fn shutdown(&self) -> NetworkResult<()> {
+    /// Stop accepting connections and close the open ones
+    pub fn shutdown(&self) -> NetworkResult<()> {
         info!("Starting network manager shutdown");
-
-        // Stop accepting new connections
-        self.listener_tx.send(ListenerControl::Shutdown)
-            .map_err(|e| NetworkError::SendError(e.to_string()))?;
-
-        // Close all active connections
-        let connections = self.connections.read();
-        for (id, _) in connections.iter() {
-            if let Some(conn) = connections.get(id) {
-                debug!(
-                    connection_id = %id,
-                    remote = %conn.remote_addr,
-                    "Closing connection"
-                );
-            }
+        self.listener_tx.send(ListenerControl::Shutdown)?;
+        let open: Vec<Uuid> = self.connections.read().keys().copied().collect();
+        for connection_id in open {
+            debug!(connection_id = %connection_id, "Closing connection");
+            self.close(connection_id);
         }
-
         info!("Network manager shutdown complete");
         Ok(())
     }
@@ -220,89 +189,75 @@ impl NetworkManager {
     pub fn get_stats(&self) -> NetworkStats {
         self.stats.read().clone()
     }
-
-    /// Get information about a specific connection
-    pub fn get_connection(&self, id: Uuid) -> Option<Connection> {
-        self.connections.read().get(&id).cloned()
-    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::net::SocketAddr;
-    use tokio::net::TcpSocket;
-
-    async fn create_test_manager() -> NetworkManager {
-        let mut config = NetworkConfig::default();
-        config.bind_address = "127.0.0.1:0".to_string();
-        
-        let (tx, _) = mpsc::channel(10);
-        NetworkManager::new(config, tx).unwrap()
+    use romer_common::types::fix::utils::encode_message;
+    use romer_common::utils::clock::system_clock;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    fn create_test_manager(idle_timeout: Duration) -> NetworkManager {
+        let config = NetworkConfig {
+            bind_address: "127.0.0.1:0".to_string(),
+            idle_timeout,
+            ..NetworkConfig::default()
+        };
+        NetworkManager::new(config, system_clock())
     }
 
     #[tokio::test]
     async fn test_manager_lifecycle() {
-        let mut manager = create_test_manager().await;
-
-        // Start manager in background
-        let handle = tokio::spawn(async move {
-            manager.run().await.unwrap();
-        });
-
-        // Give it time to start
-        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-
-        // Create test connection
-        let socket = TcpSocket::new_v4().unwrap();
-        let addr = manager.config.bind_address.parse().unwrap();
-        let _stream = socket.connect(addr).await.unwrap();
-
-        // Check statistics
+        let manager = create_test_manager(Duration::from_secs(30));
+        let (addr, mut events) = manager.start().await.unwrap();
+
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        let message = encode_message("FIX.4.2", &[(35, "0".to_string())]);
+        client.write_all(&message).await.unwrap();
+        let Some(NetworkEvent::Message(incoming)) = events.recv().await else {
+            panic!("expected a message");
+        };
+        assert_eq!(incoming.data, message);
         assert_eq!(manager.get_stats().active_connections, 1);
 
-        handle.abort();
+        // Replies go to the connection the message came from
+        manager.send(incoming.connection_id, message.clone()).unwrap();
+        let mut reply = vec![0u8; message.len()];
+        client.read_exact(&mut reply).await.unwrap();
+        assert_eq!(reply, message);
+
+        manager.close(incoming.connection_id);
+        assert!(matches!(events.recv().await, Some(NetworkEvent::Closed(id)) if id == incoming.connection_id));
+        assert_eq!(manager.get_stats().active_connections, 0);
+        assert!(manager.send(incoming.connection_id, message).is_err());
     }
 
     #[tokio::test]
     async fn test_pause_resume() {
-        let manager = create_test_manager().await;
+        let manager = create_test_manager(Duration::from_secs(30));
+        let (addr, _events) = manager.start().await.unwrap();
 
-        // Pause and resume
         manager.pause().unwrap();
-        manager.resume().unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(TcpStream::connect(addr).await.is_err());
 
-        // Try connection while paused
-        manager.pause().unwrap();
-        let socket = TcpSocket::new_v4().unwrap();
-        let addr = manager.config.bind_address.parse().unwrap();
-        let result = socket.connect(addr).await;
-        assert!(result.is_err());
+        manager.resume().unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(TcpStream::connect(addr).await.is_ok());
     }
 
     #[tokio::test]
     async fn test_connection_health_check() {
-        let mut manager = create_test_manager().await;
-        
-        // Reduce health check interval for testing
-        manager.health_check_interval = 1;
-
-        // Start manager
-        let handle = tokio::spawn(async move {
-            manager.run().await.unwrap();
-        });
-
-        // Create connection that will timeout
-        let socket = TcpSocket::new_v4().unwrap();
-        let addr = manager.config.bind_address.parse().unwrap();
-        let _stream = socket.connect(addr).await.unwrap();
-
-        // Wait for health check to remove idle connection
-        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-
-        // Connection should be removed
+        let manager = create_test_manager(Duration::from_millis(200));
+        let (addr, mut events) = manager.start().await.unwrap();
+
+        // A connection that never sends anything is closed
+        let _stream = TcpStream::connect(addr).await.unwrap();
+        let closed = tokio::time::timeout(Duration::from_secs(2), events.recv()).await.unwrap();
+        assert!(matches!(closed, Some(NetworkEvent::Closed(_))));
+        tokio::time::sleep(Duration::from_millis(50)).await;
         assert_eq!(manager.get_stats().active_connections, 0);
-
-        handle.abort();
     }
-}
\ No newline at end of file
+}
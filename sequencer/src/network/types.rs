@@ -1,13 +1,12 @@
 // src/network/types.rs
 
-use crate::session::state::{Session, SessionState};
 use std::net::SocketAddr;
+use std::time::Instant;
 use tokio::net::TcpStream;
-use tokio::sync::mpsc;
 use uuid::Uuid;
 use thiserror::Error;
 
-/// Represents a FIX connection with its associated session
+/// A FIX connection accepted by the listener, before its handler starts
 pub struct Connection {
     /// Unique identifier for this connection
     pub connection_id: Uuid,
@@ -15,44 +14,16 @@ pub struct Connection {
     pub stream: TcpStream,
     /// Remote address of the connection
     pub remote_addr: SocketAddr,
-    /// Associated session ID if authenticated
-    pub session_id: Option<Uuid>,
-    /// Channel for sending messages to this connection
-    pub message_tx: mpsc::Sender<OutgoingMessage>,
-    /// Channel for receiving messages from this connection
-    pub message_rx: mpsc::Receiver<IncomingMessage>,
-    /// Last time activity was seen on this connection
-    pub last_activity: std::time::Instant,
 }
 
 impl Connection {
     /// Create a new connection from a TCP stream
-    pub fn new(stream: TcpStream, remote_addr: SocketAddr) -> (Self, mpsc::Sender<IncomingMessage>) {
-        let connection_id = Uuid::new_v4();
-        let (message_tx, rx) = mpsc::channel(100);
-        let (tx, message_rx) = mpsc::channel(100);
-        
-        let connection = Self {
-            connection_id,
+    pub fn new(stream: TcpStream, remote_addr: SocketAddr) -> Self {
+        Self {
+            connection_id: Uuid::new_v4(),
             stream,
             remote_addr,
-            session_id: None,
-            message_tx,
-            message_rx,
-            last_activity: std::time::Instant::now(),
-        };
-        
-        (connection, tx)
-    }
-
-    /// Update the last activity timestamp
-    pub fn record_activity(&mut self) {
-        self.last_activity = std::time::Instant::now();
-    }
-
-    /// Check if the connection has been idle too long
-    pub fn is_idle(&self, timeout: std::time::Duration) -> bool {
-        self.last_activity.elapsed() > timeout
+        }
     }
 }
 
@@ -63,8 +34,9 @@ pub struct IncomingMessage {
     pub connection_id: Uuid,
     /// Raw message bytes
     pub data: Vec<u8>,
-    /// When the message was received
-    pub received_at: std::time::Instant,
+    /// When the message was read off the connection, so the time it waits
+    /// for the pipeline counts as queueing
+    pub received_at: Instant,
 }
 
 /// Message to be sent on a connection
@@ -76,8 +48,18 @@ pub struct OutgoingMessage {
     pub data: Vec<u8>,
 }
 
+/// What connection handlers report to the consumer of the network manager
+#[derive(Debug)]
+pub enum NetworkEvent {
+    /// A complete message read from a connection
+    Message(IncomingMessage),
+    /// The connection was closed by the counterparty, failed, or was closed
+    /// by us
+    Closed(Uuid),
+}
+
 /// Statistics about network operations
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct NetworkStats {
     /// Number of active connections
     pub active_connections: usize,
@@ -93,19 +75,6 @@ pub struct NetworkStats {
     pub failed_connections: u64,
 }
 
-impl Default for NetworkStats {
-    fn default() -> Self {
-        Self {
-            active_connections: 0,
-            messages_received: 0,
-            messages_sent: 0,
-            bytes_received: 0,
-            bytes_sent: 0,
-            failed_connections: 0,
-        }
-    }
-}
-
 /// Configuration for network operations
 #[derive(Debug, Clone)]
 pub struct NetworkConfig {
@@ -117,7 +86,9 @@ pub struct NetworkConfig {
     pub message_buffer_size: usize,
     /// Maximum message size in bytes
     pub max_message_size: usize,
-    /// Connection idle timeout
+    /// Connections silent for this long are closed. Sessions detect a lost
+    /// counterparty sooner through their heartbeats; this catches
+    /// connections that never log on.
     pub idle_timeout: std::time::Duration,
 }
 
@@ -145,6 +116,9 @@ pub enum NetworkError {
     #[error("Message too large: {size} bytes")]
     MessageTooLarge { size: usize },
 
+    #[error("Invalid message format: {0}")]
+    InvalidFormat(String),
+
     #[error("Connection error: {0}")]
     ConnectionError(#[from] std::io::Error),
 
@@ -161,57 +135,22 @@ pub type NetworkResult<T> = Result<T, NetworkError>;
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tokio::net::TcpSocket;
+    use tokio::net::TcpListener;
 
     #[tokio::test]
     async fn test_connection_creation() {
-        // Create a mock TCP connection
-        let socket = TcpSocket::new_v4().unwrap();
-        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
-        let stream = socket.connect(addr).await.unwrap();
-        let remote_addr = stream.peer_addr().unwrap();
-
-        // Create a new connection
-        let (connection, _tx) = Connection::new(stream, remote_addr);
-
-        assert!(connection.session_id.is_none());
-        assert_eq!(connection.remote_addr, remote_addr);
-    }
-
-    #[test]
-    fn test_idle_detection() {
-        // Create a mock connection
-        let socket = TcpSocket::new_v4().unwrap();
-        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
-        let stream = socket.connect(addr).await.unwrap();
-        let remote_addr = stream.peer_addr().unwrap();
-        
-        let (mut connection, _tx) = Connection::new(stream, remote_addr);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let (stream, remote_addr) = listener.accept().await.unwrap();
 
-        // Should not be idle initially
-        assert!(!connection.is_idle(std::time::Duration::from_secs(1)));
+        let first = Connection::new(stream, remote_addr);
+        assert_eq!(first.remote_addr, client.local_addr().unwrap());
 
-        // Wait a bit
-        std::thread::sleep(std::time::Duration::from_millis(1500));
-
-        // Should now be idle
-        assert!(connection.is_idle(std::time::Duration::from_secs(1)));
-
-        // Record activity
-        connection.record_activity();
-
-        // Should no longer be idle
-        assert!(!connection.is_idle(std::time::Duration::from_secs(1)));
-    }
-
-    #[test]
-    fn test_network_config_defaults() {
-        let config = NetworkConfig::default();
-        
-        assert_eq!(config.bind_address, "0.0.0.0:8585");
-        assert_eq!(config.max_connections, 1000);
-        assert_eq!(config.message_buffer_size, 100);
-        assert_eq!(config.max_message_size, 4096);
-        assert_eq!(config.idle_timeout, std::time::Duration::from_secs(30));
+        let (stream, remote_addr) = {
+            let _other = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+            listener.accept().await.unwrap()
+        };
+        let second = Connection::new(stream, remote_addr);
+        assert_ne!(first.connection_id, second.connection_id);
     }
-}
\ No newline at end of file
+}
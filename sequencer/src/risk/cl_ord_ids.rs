@@ -0,0 +1,157 @@
+// src/risk/cl_ord_ids.rs
+
+use chrono::NaiveDate;
+use parking_lot::Mutex;
+use romer_common::storage::group_commit::GroupCommitter;
+use romer_common::storage::journal::RomerJournal;
+use romer_common::utils::clock::SharedClock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use thiserror::Error;
+use tracing::{info, warn};
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ClOrdIdError {
+    #[error("Duplicate ClOrdID {cl_ord_id} from {sender_comp_id}")]
+    Duplicate {
+        sender_comp_id: String,
+        cl_ord_id: String,
+    },
+
+    #[error("Storage error: {0}")]
+    Storage(String),
+}
+
+/// A ClOrdID seen on a trading day, as journaled
+#[derive(Serialize, Deserialize)]
+struct ClOrdIdRecord {
+    day: NaiveDate,
+    sender_comp_id: String,
+    cl_ord_id: String,
+}
+
+struct DayState {
+    day: NaiveDate,
+    seen: HashSet<(String, String)>,
+}
+
+/// ClOrdIDs used by each SenderCompID on the current trading day (the UTC
+/// date). A reused ID is rejected. Every accepted ID is journaled through a
+/// group commit before it is acknowledged, so a restart keeps rejecting the
+/// day's IDs.
+pub struct ClOrdIdRegistry {
+    state: Mutex<DayState>,
+    journal: Option<GroupCommitter>,
+    clock: SharedClock,
+}
+
+impl ClOrdIdRegistry {
+    /// Registry without persistence, for tests and tooling
+    pub fn in_memory(clock: SharedClock) -> Self {
+        Self {
+            state: Mutex::new(DayState {
+                day: clock.now().date_naive(),
+                seen: HashSet::new(),
+            }),
+            journal: None,
+            clock,
+        }
+    }
+
+    /// Opens the registry over `journal`, restoring the IDs of today.
+    /// Records of earlier days are ignored.
+    pub async fn open(mut journal: RomerJournal, clock: SharedClock) -> Result<Self, ClOrdIdError> {
+        let day = clock.now().date_naive();
+        let mut seen = HashSet::new();
+        for bytes in journal.replay_all().await.map_err(ClOrdIdError::Storage)? {
+            match serde_json::from_slice::<ClOrdIdRecord>(&bytes) {
+                Ok(record) if record.day == day => {
+                    seen.insert((record.sender_comp_id, record.cl_ord_id));
+                }
+                Ok(_) => {}
+                Err(e) => warn!(error = %e, "Skipping undecodable ClOrdID record"),
+            }
+        }
+        info!(%day, ids = seen.len(), "Restored ClOrdIDs of the trading day");
+
+        Ok(Self {
+            state: Mutex::new(DayState { day, seen }),
+            journal: Some(GroupCommitter::spawn(journal)),
+            clock,
+        })
+    }
+
+    /// Records `cl_ord_id` for `sender_comp_id`, failing if it was already
+    /// used today
+    pub async fn record(&self, sender_comp_id: &str, cl_ord_id: &str) -> Result<(), ClOrdIdError> {
+        let today = self.clock.now().date_naive();
+        let key = (sender_comp_id.to_string(), cl_ord_id.to_string());
+        {
+            let mut state = self.state.lock();
+            if state.day != today {
+                info!(day = %today, "New trading day, clearing ClOrdIDs");
+                state.day = today;
+                state.seen.clear();
+            }
+            // Claim the ID before journaling so concurrent reuse is rejected
+            if !state.seen.insert(key.clone()) {
+                return Err(ClOrdIdError::Duplicate {
+                    sender_comp_id: key.0,
+                    cl_ord_id: key.1,
+                });
+            }
+        }
+
+        if let Some(journal) = &self.journal {
+            let record = ClOrdIdRecord {
+                day: today,
+                sender_comp_id: key.0.clone(),
+                cl_ord_id: key.1.clone(),
+            };
+            let written = match serde_json::to_vec(&record) {
+                Ok(bytes) => journal.append(bytes).await,
+                Err(e) => Err(e.to_string()),
+            };
+            if let Err(e) = written {
+                self.state.lock().seen.remove(&key);
+                return Err(ClOrdIdError::Storage(e));
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of IDs recorded for the current trading day
+    pub fn ids_today(&self) -> usize {
+        self.state.lock().seen.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use romer_common::utils::clock::ManualClock;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_duplicates_rejected_per_sender_and_day() {
+        let clock = Arc::new(ManualClock::new(Utc.with_ymd_and_hms(2024, 3, 1, 23, 0, 0).unwrap()));
+        let registry = ClOrdIdRegistry::in_memory(clock.clone());
+
+        registry.record("MM1", "A1").await.unwrap();
+        registry.record("MM2", "A1").await.unwrap();
+        assert_eq!(
+            registry.record("MM1", "A1").await,
+            Err(ClOrdIdError::Duplicate {
+                sender_comp_id: "MM1".into(),
+                cl_ord_id: "A1".into(),
+            })
+        );
+
+        // IDs may be reused on the next trading day
+        clock.advance(Duration::from_secs(2 * 3600));
+        registry.record("MM1", "A1").await.unwrap();
+        assert_eq!(registry.ids_today(), 1);
+    }
+}
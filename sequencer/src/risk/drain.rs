@@ -0,0 +1,117 @@
+// src/risk/drain.rs
+
+use crate::events::bus::EventBus;
+use crate::events::types::SequencerEvent;
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use romer_common::utils::clock::{system_clock, SharedClock};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+/// Text of the reject sent for orders arriving while draining
+pub const MARKET_CLOSED: &str = "market closed";
+
+/// Why and when drain mode was entered
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DrainRecord {
+    pub reason: String,
+    /// Who started the drain, e.g. an admin user
+    pub triggered_by: String,
+    pub entered_at: DateTime<Utc>,
+}
+
+/// Market wide drain mode, entered ahead of maintenance or a listener
+/// pause. Connections and sessions stay up, cancels are accepted and blocks
+/// already being built are sealed as usual; only new orders are rejected
+/// as market closed.
+pub struct DrainMode {
+    state: RwLock<Option<DrainRecord>>,
+    events: EventBus,
+    clock: SharedClock,
+}
+
+impl DrainMode {
+    pub fn new(events: EventBus) -> Self {
+        Self::with_clock(events, system_clock())
+    }
+
+    pub fn with_clock(events: EventBus, clock: SharedClock) -> Self {
+        Self {
+            state: RwLock::new(None),
+            events,
+            clock,
+        }
+    }
+
+    /// Whether new orders must be rejected
+    pub fn is_draining(&self) -> bool {
+        self.state.read().is_some()
+    }
+
+    /// Starts draining. Entering while already draining keeps the original
+    /// record and returns it.
+    pub fn enter(&self, reason: &str, triggered_by: &str) -> DrainRecord {
+        let mut state = self.state.write();
+        if let Some(existing) = state.as_ref() {
+            return existing.clone();
+        }
+
+        let record = DrainRecord {
+            reason: reason.to_string(),
+            triggered_by: triggered_by.to_string(),
+            entered_at: self.clock.now(),
+        };
+        *state = Some(record.clone());
+        drop(state);
+
+        warn!(reason, triggered_by, "Drain mode entered, new orders rejected");
+        self.events.publish(SequencerEvent::DrainModeEntered {
+            reason: reason.to_string(),
+            triggered_by: triggered_by.to_string(),
+            at: record.entered_at,
+        });
+        record
+    }
+
+    /// Reopens order entry. Returns false if not draining.
+    pub fn exit(&self, released_by: &str) -> bool {
+        if self.state.write().take().is_none() {
+            return false;
+        }
+        info!(released_by, "Drain mode exited, order entry reopened");
+        self.events.publish(SequencerEvent::DrainModeExited {
+            released_by: released_by.to_string(),
+            at: self.clock.now(),
+        });
+        true
+    }
+
+    /// The current drain, if any
+    pub fn status(&self) -> Option<DrainRecord> {
+        self.state.read().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_enter_and_exit() {
+        let events = EventBus::default();
+        let mut rx = events.subscribe();
+        let drain = DrainMode::new(events);
+        assert!(!drain.is_draining());
+
+        let record = drain.enter("maintenance", "admin");
+        assert!(drain.is_draining());
+        // Entering again keeps the original drain
+        assert_eq!(drain.enter("other", "ops"), record);
+        assert!(matches!(*rx.recv().await.unwrap(), SequencerEvent::DrainModeEntered { .. }));
+
+        assert!(drain.exit("admin"));
+        assert!(!drain.exit("admin"));
+        assert!(!drain.is_draining());
+        assert!(matches!(*rx.recv().await.unwrap(), SequencerEvent::DrainModeExited { .. }));
+    }
+}
@@ -0,0 +1,205 @@
+// src/risk/kill_switch.rs
+
+use crate::events::bus::EventBus;
+use crate::events::types::SequencerEvent;
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use romer_common::utils::clock::{system_clock, SharedClock};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Cancels a firm's resting orders. Implemented by the order book.
+pub trait OrderCanceller: Send + Sync {
+    /// Cancels every resting order of `firm`, returning how many were cancelled
+    fn cancel_all(&self, firm: &str) -> usize;
+}
+
+/// Why and when a firm's kill switch was engaged
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KillRecord {
+    pub firm: String,
+    pub reason: String,
+    /// Who pulled the switch, e.g. an admin user or a SenderCompID
+    pub triggered_by: String,
+    pub engaged_at: DateTime<Utc>,
+    pub orders_cancelled: usize,
+}
+
+/// Firm level kill switch. Engaging it blocks new orders from every session
+/// of the firm, cancels the firm's resting orders, and announces a
+/// `KillSwitchEngaged` event on which the session manager logs the firm's
+/// sessions out.
+pub struct KillSwitch {
+    engaged: DashMap<String, KillRecord>,
+    /// SenderCompID to firm. Unmapped comp IDs are treated as their own firm.
+    firms: DashMap<String, String>,
+    canceller: Option<Arc<dyn OrderCanceller>>,
+    events: EventBus,
+    clock: SharedClock,
+}
+
+impl KillSwitch {
+    pub fn new(events: EventBus) -> Self {
+        Self::with_clock(events, system_clock())
+    }
+
+    pub fn with_clock(events: EventBus, clock: SharedClock) -> Self {
+        Self {
+            engaged: DashMap::new(),
+            firms: DashMap::new(),
+            canceller: None,
+            events,
+            clock,
+        }
+    }
+
+    /// Cancel resting orders through `canceller` when the switch is engaged
+    pub fn with_canceller(mut self, canceller: Arc<dyn OrderCanceller>) -> Self {
+        self.canceller = Some(canceller);
+        self
+    }
+
+    /// Records that `sender_comp_id` trades on behalf of `firm`
+    pub fn assign(&self, sender_comp_id: impl Into<String>, firm: impl Into<String>) {
+        self.firms.insert(sender_comp_id.into(), firm.into());
+    }
+
+    pub fn firm_of(&self, sender_comp_id: &str) -> String {
+        self.firms
+            .get(sender_comp_id)
+            .map(|firm| firm.clone())
+            .unwrap_or_else(|| sender_comp_id.to_string())
+    }
+
+    /// SenderCompIDs mapped to `firm`, including the firm id itself
+    pub fn comp_ids_of(&self, firm: &str) -> Vec<String> {
+        let mut comp_ids: Vec<String> = self
+            .firms
+            .iter()
+            .filter(|entry| entry.value() == firm)
+            .map(|entry| entry.key().clone())
+            .collect();
+        if !comp_ids.iter().any(|id| id == firm) {
+            comp_ids.push(firm.to_string());
+        }
+        comp_ids
+    }
+
+    /// Whether orders from `sender_comp_id` must be rejected
+    pub fn is_blocked(&self, sender_comp_id: &str) -> bool {
+        self.engaged.contains_key(&self.firm_of(sender_comp_id))
+    }
+
+    /// Engages the switch for `firm`. Engaging an already engaged firm
+    /// keeps the original record and returns it.
+    pub fn engage(&self, firm: &str, reason: &str, triggered_by: &str) -> KillRecord {
+        if let Some(existing) = self.engaged.get(firm) {
+            return existing.clone();
+        }
+
+        let engaged_at = self.clock.now();
+        let mut record = KillRecord {
+            firm: firm.to_string(),
+            reason: reason.to_string(),
+            triggered_by: triggered_by.to_string(),
+            engaged_at,
+            orders_cancelled: 0,
+        };
+        // Block order flow before cancelling so nothing new rests meanwhile
+        self.engaged.insert(firm.to_string(), record.clone());
+
+        if let Some(canceller) = &self.canceller {
+            record.orders_cancelled = canceller.cancel_all(firm);
+            self.engaged.insert(firm.to_string(), record.clone());
+        }
+
+        warn!(
+            firm,
+            reason,
+            triggered_by,
+            cancelled = record.orders_cancelled,
+            "Kill switch engaged"
+        );
+        self.events.publish(SequencerEvent::KillSwitchEngaged {
+            firm: firm.to_string(),
+            sender_comp_ids: self.comp_ids_of(firm),
+            reason: reason.to_string(),
+            triggered_by: triggered_by.to_string(),
+            orders_cancelled: record.orders_cancelled,
+            at: engaged_at,
+        });
+        record
+    }
+
+    /// Restores order flow for `firm`. Returns false if it was not engaged.
+    pub fn release(&self, firm: &str, released_by: &str) -> bool {
+        if self.engaged.remove(firm).is_none() {
+            return false;
+        }
+        info!(firm, released_by, "Kill switch released");
+        self.events.publish(SequencerEvent::KillSwitchReleased {
+            firm: firm.to_string(),
+            released_by: released_by.to_string(),
+            at: self.clock.now(),
+        });
+        true
+    }
+
+    /// Every firm whose switch is engaged
+    pub fn status(&self) -> Vec<KillRecord> {
+        let mut records: Vec<KillRecord> = self.engaged.iter().map(|e| e.value().clone()).collect();
+        records.sort_by(|a, b| a.firm.cmp(&b.firm));
+        records
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Book(AtomicUsize);
+
+    impl OrderCanceller for Book {
+        fn cancel_all(&self, _firm: &str) -> usize {
+            self.0.swap(0, Ordering::SeqCst)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_engage_blocks_all_firm_sessions() {
+        let bus = EventBus::new(16);
+        let mut events = bus.subscribe();
+        let switch = KillSwitch::new(bus).with_canceller(Arc::new(Book(AtomicUsize::new(3))));
+        switch.assign("MM1-ALGO", "MM1");
+        switch.assign("MM1-MANUAL", "MM1");
+
+        let record = switch.engage("MM1", "runaway algo", "risk-desk");
+        assert_eq!(record.orders_cancelled, 3);
+        assert!(switch.is_blocked("MM1-ALGO"));
+        assert!(switch.is_blocked("MM1-MANUAL"));
+        assert!(!switch.is_blocked("MM2"));
+
+        match &*events.recv().await.unwrap() {
+            SequencerEvent::KillSwitchEngaged { sender_comp_ids, .. } => {
+                assert_eq!(sender_comp_ids.len(), 3);
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+
+        // Re-engaging keeps the original record
+        assert_eq!(switch.engage("MM1", "again", "someone").reason, "runaway algo");
+    }
+
+    #[test]
+    fn test_release() {
+        let switch = KillSwitch::new(EventBus::new(16));
+        switch.engage("MM1", "test", "admin");
+        assert_eq!(switch.status().len(), 1);
+
+        assert!(switch.release("MM1", "admin"));
+        assert!(!switch.release("MM1", "admin"));
+        assert!(!switch.is_blocked("MM1"));
+    }
+}
@@ -0,0 +1,174 @@
+// src/risk/load_shed.rs
+
+use crate::config::LoadSheddingConfig;
+use crate::events::bus::EventBus;
+use crate::events::types::SequencerEvent;
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use romer_common::utils::clock::SharedClock;
+use serde::Serialize;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Text of the reject sent for orders arriving while shedding load
+pub const THROTTLED: &str = "sequencer overloaded";
+
+/// Weight of the newest measurement in the smoothed latencies
+const SMOOTHING: f64 = 0.2;
+
+/// Load shedding state, as reported by `admin_load_shedding_status`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LoadShedStatus {
+    pub shedding: bool,
+    pub since: Option<DateTime<Utc>>,
+    /// Smoothed latencies, in microseconds
+    pub queue_latency_us: u64,
+    pub batch_latency_us: u64,
+    /// Orders rejected in the current episode, or the last one
+    pub shed: u64,
+}
+
+#[derive(Default)]
+struct ShedState {
+    queue_us: f64,
+    batch_us: f64,
+    since: Option<DateTime<Utc>>,
+    shed: u64,
+}
+
+/// Sheds new order flow when the pipeline runs over its latency budget, so
+/// orders already in flight and cancels are not starved behind a backlog.
+/// Latencies are smoothed so a single slow message does not trip it, and
+/// order entry reopens only once both are well back under budget.
+pub struct LoadShedder {
+    config: LoadSheddingConfig,
+    state: Mutex<ShedState>,
+    events: EventBus,
+    clock: SharedClock,
+}
+
+impl LoadShedder {
+    pub fn new(config: LoadSheddingConfig, events: EventBus, clock: SharedClock) -> Self {
+        Self {
+            config,
+            state: Mutex::new(ShedState::default()),
+            events,
+            clock,
+        }
+    }
+
+    /// Records how long a message waited between arriving and being handled
+    pub fn record_queue(&self, latency: Duration) {
+        let mut state = self.state.lock();
+        state.queue_us = smooth(state.queue_us, latency);
+        self.evaluate(&mut state);
+    }
+
+    /// Records how long a batch of messages took to handle
+    pub fn record_batch(&self, latency: Duration) {
+        let mut state = self.state.lock();
+        state.batch_us = smooth(state.batch_us, latency);
+        self.evaluate(&mut state);
+    }
+
+    fn evaluate(&self, state: &mut ShedState) {
+        if !self.config.enabled {
+            return;
+        }
+        let queue_budget = self.config.max_queue_latency_ms as f64 * 1_000.0;
+        let batch_budget = self.config.max_batch_latency_ms as f64 * 1_000.0;
+        let now = self.clock.now();
+        match state.since {
+            None if state.queue_us > queue_budget || state.batch_us > batch_budget => {
+                state.since = Some(now);
+                state.shed = 0;
+                warn!(
+                    queue_latency_us = state.queue_us as u64,
+                    batch_latency_us = state.batch_us as u64,
+                    "Latency budget exceeded, shedding new orders"
+                );
+                self.events.publish(SequencerEvent::LoadSheddingEntered {
+                    queue_latency_us: state.queue_us as u64,
+                    batch_latency_us: state.batch_us as u64,
+                    at: now,
+                });
+            }
+            Some(since) => {
+                let recover = self.config.recover_pct as f64 / 100.0;
+                let held = (now - since).num_milliseconds() >= self.config.min_shed_ms as i64;
+                if held && state.queue_us < queue_budget * recover && state.batch_us < batch_budget * recover {
+                    state.since = None;
+                    info!(shed = state.shed, "Latency recovered, order entry reopened");
+                    self.events.publish(SequencerEvent::LoadSheddingExited { shed: state.shed, at: now });
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// Whether a new order may be accepted. Orders refused here are
+    /// counted; cancels must not be checked.
+    pub fn admit_order(&self) -> bool {
+        let mut state = self.state.lock();
+        if state.since.is_none() {
+            return true;
+        }
+        state.shed += 1;
+        false
+    }
+
+    pub fn status(&self) -> LoadShedStatus {
+        let state = self.state.lock();
+        LoadShedStatus {
+            shedding: state.since.is_some(),
+            since: state.since,
+            queue_latency_us: state.queue_us as u64,
+            batch_latency_us: state.batch_us as u64,
+            shed: state.shed,
+        }
+    }
+}
+
+fn smooth(current: f64, latency: Duration) -> f64 {
+    current + SMOOTHING * (latency.as_micros() as f64 - current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use romer_common::utils::clock::ManualClock;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_sheds_until_latency_recovers() {
+        let events = EventBus::default();
+        let mut rx = events.subscribe();
+        let clock = Arc::new(ManualClock::new(Utc::now()));
+        let config = LoadSheddingConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        let shedder = LoadShedder::new(config, events, clock.clone());
+
+        // One slow message is smoothed away
+        shedder.record_queue(Duration::from_millis(100));
+        assert!(shedder.admit_order());
+
+        for _ in 0..10 {
+            shedder.record_queue(Duration::from_millis(100));
+        }
+        assert!(!shedder.admit_order());
+        assert!(!shedder.admit_order());
+        assert!(matches!(*rx.recv().await.unwrap(), SequencerEvent::LoadSheddingEntered { .. }));
+
+        // Recovered latency still sheds until the minimum has passed
+        for _ in 0..30 {
+            shedder.record_queue(Duration::from_millis(1));
+        }
+        assert!(shedder.status().shedding);
+        clock.advance(Duration::from_secs(1));
+        shedder.record_queue(Duration::from_millis(1));
+        assert!(shedder.admit_order());
+        assert!(matches!(*rx.recv().await.unwrap(), SequencerEvent::LoadSheddingExited { shed: 2, .. }));
+    }
+}
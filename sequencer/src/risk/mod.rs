@@ -0,0 +1,8 @@
+pub mod cl_ord_ids;
+pub mod drain;
+pub mod kill_switch;
+pub mod load_shed;
+pub mod order_checks;
+pub mod permissions;
+pub mod plugins;
+pub mod sub_accounts;
@@ -0,0 +1,240 @@
+// src/risk/order_checks.rs
+
+use crate::fix::types::ValidatedMessage;
+use crate::governance::service::GovernanceService;
+use crate::market::calendar::{CalendarError, TradingCalendar};
+use crate::market::instruments::InstrumentRegistry;
+use crate::market::reference_price::{ReferencePriceError, ReferencePriceService};
+use crate::risk::load_shed::{LoadShedder, THROTTLED};
+use crate::risk::permissions::PermissionRegistry;
+use crate::risk::plugins::{OrderContext, PluginHost};
+use crate::risk::sub_accounts::{SubAccountError, SubAccountTracker};
+use crate::rpc::handler::RpcState;
+use romer_common::types::rejection::RejectReason;
+use romer_common::utils::clock::SharedClock;
+use std::sync::Arc;
+
+/// Why an order failed a pre-trade check, as reported in its
+/// ExecutionReport
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderRejection {
+    pub reason: RejectReason,
+    pub text: String,
+}
+
+impl OrderRejection {
+    fn new(reason: RejectReason, text: impl ToString) -> Self {
+        Self {
+            reason,
+            text: text.to_string(),
+        }
+    }
+}
+
+/// Pre-trade checks of a NewOrderSingle beyond the session's entitlements:
+/// load shedding, the market's trading hours, sub-account limits, plugins,
+/// tick and lot sizes, and the price collar. Run by the session manager
+/// after entitlements and before the ClOrdID is recorded.
+pub struct OrderChecks {
+    load_shedding: Arc<LoadShedder>,
+    calendar: TradingCalendar,
+    instruments: Arc<InstrumentRegistry>,
+    /// Organizations of senders, whose sub-accounts' limits are checked
+    sub_accounts: Option<(Arc<PermissionRegistry>, Arc<SubAccountTracker>)>,
+    reference_prices: Option<Arc<ReferencePriceService>>,
+    /// Height instrument overrides and plugin versions are resolved at
+    heights: Arc<RpcState>,
+    plugins: Option<Arc<PluginHost>>,
+    governance: Option<Arc<GovernanceService>>,
+    /// Collar of instruments without their own band, unless governance
+    /// sets one
+    price_collar_bps: Option<u32>,
+    clock: SharedClock,
+}
+
+impl OrderChecks {
+    pub fn new(
+        load_shedding: Arc<LoadShedder>,
+        calendar: TradingCalendar,
+        instruments: Arc<InstrumentRegistry>,
+        heights: Arc<RpcState>,
+        clock: SharedClock,
+    ) -> Self {
+        Self {
+            load_shedding,
+            calendar,
+            instruments,
+            sub_accounts: None,
+            reference_prices: None,
+            heights,
+            plugins: None,
+            governance: None,
+            price_collar_bps: None,
+            clock,
+        }
+    }
+
+    /// Hold the orders of organizations with sub-accounts to each desk's
+    /// limits
+    pub fn with_sub_accounts(mut self, permissions: Arc<PermissionRegistry>, sub_accounts: Arc<SubAccountTracker>) -> Self {
+        self.sub_accounts = Some((permissions, sub_accounts));
+        self
+    }
+
+    /// Collar prices around `reference_prices`
+    pub fn with_reference_prices(mut self, reference_prices: Arc<ReferencePriceService>) -> Self {
+        self.reference_prices = Some(reference_prices);
+        self
+    }
+
+    /// Run the exchange's WASM plugins on every order
+    pub fn with_plugins(mut self, plugins: Arc<PluginHost>) -> Self {
+        self.plugins = Some(plugins);
+        self
+    }
+
+    /// Take the price collar from governance parameters
+    pub fn with_governance(mut self, governance: Arc<GovernanceService>) -> Self {
+        self.governance = Some(governance);
+        self
+    }
+
+    /// Collar orders this many basis points around the reference price
+    pub fn with_price_collar(mut self, price_collar_bps: Option<u32>) -> Self {
+        self.price_collar_bps = price_collar_bps;
+        self
+    }
+
+    /// Checks a NewOrderSingle, returning the first check it fails
+    pub fn check(&self, message: &ValidatedMessage) -> Result<(), OrderRejection> {
+        let sender_comp_id = message.sender_comp_id.as_str();
+        let symbol = message.field(55).unwrap_or_default();
+        let side = message.field(54).unwrap_or_default();
+        let quantity = message.field(38).unwrap_or_default();
+        let price = message.field(44);
+
+        if !self.load_shedding.admit_order() {
+            return Err(OrderRejection::new(RejectReason::Throttled, THROTTLED));
+        }
+
+        self.calendar
+            .check_order(&message.target_comp_id, self.clock.now(), message.field(59), message.field(432))
+            .map_err(|e| {
+                let reason = match e {
+                    CalendarError::Closed { .. } => RejectReason::MarketClosed,
+                    CalendarError::Expired(_) => RejectReason::Expired,
+                    _ => RejectReason::InvalidOrder,
+                };
+                OrderRejection::new(reason, e)
+            })?;
+
+        let organization = self
+            .sub_accounts
+            .as_ref()
+            .and_then(|(permissions, sub_accounts)| Some((permissions.organization_for_sender(sender_comp_id)?, sub_accounts)));
+        if let Some((organization, sub_accounts)) = organization {
+            sub_accounts
+                .check(&organization, message.field(1), symbol, side, quantity)
+                .map_err(|e| {
+                    let reason = match e {
+                        SubAccountError::OrderQuantity { .. } | SubAccountError::Position { .. } => RejectReason::ExceedsLimit,
+                        SubAccountError::InvalidField { .. } => RejectReason::InvalidOrder,
+                        _ => RejectReason::PermissionDenied,
+                    };
+                    OrderRejection::new(reason, e)
+                })?;
+        }
+
+        let height = self.heights.next_height();
+        if let Some(plugins) = &self.plugins {
+            let order = OrderContext {
+                sender_comp_id: sender_comp_id.to_string(),
+                account: message.field(1).map(str::to_string),
+                symbol: symbol.to_string(),
+                side: side.to_string(),
+                quantity: message.field(38).map(str::to_string),
+                price: price.map(str::to_string),
+            };
+            plugins
+                .check(height, &order)
+                .map_err(|e| OrderRejection::new(RejectReason::PluginRejected, e))?;
+        }
+
+        let instrument = self.instruments.parameters(symbol, height);
+        instrument
+            .check_order(price, quantity)
+            .map_err(|e| OrderRejection::new(RejectReason::InvalidOrder, e))?;
+
+        let band_bps = instrument.band_bps.or_else(|| match &self.governance {
+            Some(governance) => governance.parameters().price_collar_bps,
+            None => self.price_collar_bps,
+        });
+        let collar = band_bps.zip(price.and_then(|price| price.parse::<f64>().ok()));
+        if let (Some((band_bps, price)), Some(reference_prices)) = (collar, &self.reference_prices) {
+            match reference_prices.check_collar(symbol, price, band_bps) {
+                // Without any reference price there is nothing to collar against
+                Ok(()) | Err(ReferencePriceError::NoPrice(_)) => {}
+                Err(e) => {
+                    let reason = match e {
+                        ReferencePriceError::OutsideCollar { .. } => RejectReason::OutsidePriceCollar,
+                        _ => RejectReason::NoReferencePrice,
+                    };
+                    return Err(OrderRejection::new(reason, e));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LoadSheddingConfig;
+    use crate::events::bus::EventBus;
+    use crate::market::reference_price::ManualFeed;
+    use chrono::Utc;
+    use romer_common::types::fix::utils::encode_message;
+    use romer_common::types::instrument::InstrumentParameters;
+    use romer_common::utils::clock::system_clock;
+    use std::time::Duration;
+
+    fn order(quantity: &str, price: &str) -> ValidatedMessage {
+        let raw = encode_message(
+            "FIX.4.2",
+            &[
+                (35, "D".to_string()),
+                (49, "MM1".to_string()),
+                (56, "ROMER".to_string()),
+                (34, "2".to_string()),
+                (11, "A1".to_string()),
+                (55, "AAPL".to_string()),
+                (54, "1".to_string()),
+                (38, quantity.to_string()),
+                (44, price.to_string()),
+            ],
+        );
+        ValidatedMessage::parse(&raw).unwrap()
+    }
+
+    #[test]
+    fn test_order_checks() {
+        let events = EventBus::default();
+        let clock = system_clock();
+        let manual = Arc::new(ManualFeed::new(Duration::from_secs(60)));
+        manual.set("AAPL", 100.0, Utc::now());
+        let checks = OrderChecks::new(
+            Arc::new(LoadShedder::new(LoadSheddingConfig::default(), events.clone(), clock.clone())),
+            TradingCalendar::new(Default::default()).unwrap(),
+            Arc::new(InstrumentRegistry::new(InstrumentParameters::default(), events, clock.clone())),
+            Arc::new(RpcState::new()),
+            clock,
+        )
+        .with_reference_prices(Arc::new(ReferencePriceService::new(Duration::from_secs(5)).with_feed(manual)))
+        .with_price_collar(Some(500));
+
+        checks.check(&order("10", "104")).unwrap();
+        assert_eq!(checks.check(&order("ten", "104")).unwrap_err().reason, RejectReason::InvalidOrder);
+        assert_eq!(checks.check(&order("10", "106")).unwrap_err().reason, RejectReason::OutsidePriceCollar);
+    }
+}
@@ -0,0 +1,310 @@
+// src/risk/permissions.rs
+
+use dashmap::DashMap;
+use romer_common::types::org::{LogonCredential, Organization, SubAccount, SymbolPermission};
+use thiserror::Error;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum PermissionError {
+    #[error("No organization registered for sender {0}")]
+    UnknownSender(String),
+
+    #[error("Organization not found: {0}")]
+    OrganizationNotFound(String),
+
+    #[error("SenderCompID {0} is already registered")]
+    SenderTaken(String),
+
+    #[error("Organization {org} has no sub-account {account}")]
+    UnknownSubAccount { org: String, account: String },
+
+    #[error("Organization {org} lacks {required:?} permission on {symbol}")]
+    Denied {
+        org: String,
+        symbol: String,
+        required: SymbolPermission,
+    },
+}
+
+/// Per-organization instrument permissions, checked when orders are accepted
+/// and market data is subscribed. Backed by the organizations of the
+/// registry; changed organizations are sent to the `with_updates` channel so
+/// they can be journaled as `OrganizationUpdated`.
+#[derive(Default)]
+pub struct PermissionRegistry {
+    organizations: DashMap<String, Organization>,
+    /// SenderCompID to organization id
+    senders: DashMap<String, String>,
+    updates: Option<mpsc::UnboundedSender<Organization>>,
+}
+
+impl PermissionRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry over already registered organizations, e.g. replayed from the journal
+    pub fn from_organizations(organizations: impl IntoIterator<Item = Organization>) -> Self {
+        let registry = Self::new();
+        for organization in organizations {
+            registry.register(organization);
+        }
+        registry
+    }
+
+    /// Send every organization changed by `grant` or `revoke` to `updates`
+    pub fn with_updates(mut self, updates: mpsc::UnboundedSender<Organization>) -> Self {
+        self.updates = Some(updates);
+        self
+    }
+
+    /// Adds or replaces an organization
+    pub fn register(&self, organization: Organization) {
+        self.senders
+            .insert(organization.sender_comp_id.clone(), organization.id.clone());
+        self.organizations.insert(organization.id.clone(), organization);
+    }
+
+    /// Registers a new organization, refusing SenderCompIDs already in use.
+    /// The organization is sent to the updates channel to be journaled.
+    pub fn create(&self, organization: Organization) -> Result<Organization, PermissionError> {
+        match self.senders.entry(organization.sender_comp_id.clone()) {
+            dashmap::mapref::entry::Entry::Occupied(_) => {
+                return Err(PermissionError::SenderTaken(organization.sender_comp_id));
+            }
+            dashmap::mapref::entry::Entry::Vacant(entry) => {
+                entry.insert(organization.id.clone());
+            }
+        }
+        self.organizations.insert(organization.id.clone(), organization.clone());
+        info!(org_id = %organization.id, sender_comp_id = %organization.sender_comp_id, "Registered organization");
+        Ok(self.updated(organization))
+    }
+
+    /// Replaces a registered organization with `organization`, moving its
+    /// SenderCompID if it changed. The organization is sent to the updates
+    /// channel to be journaled.
+    pub fn update(&self, organization: Organization) -> Result<Organization, PermissionError> {
+        let previous = self
+            .organization(&organization.id)
+            .ok_or_else(|| PermissionError::OrganizationNotFound(organization.id.clone()))?;
+        if previous.sender_comp_id != organization.sender_comp_id {
+            match self.senders.entry(organization.sender_comp_id.clone()) {
+                dashmap::mapref::entry::Entry::Occupied(_) => {
+                    return Err(PermissionError::SenderTaken(organization.sender_comp_id));
+                }
+                dashmap::mapref::entry::Entry::Vacant(entry) => {
+                    entry.insert(organization.id.clone());
+                }
+            }
+            self.senders.remove(&previous.sender_comp_id);
+        }
+        self.organizations.insert(organization.id.clone(), organization.clone());
+        info!(org_id = %organization.id, sender_comp_id = %organization.sender_comp_id, "Updated organization");
+        Ok(self.updated(organization))
+    }
+
+    pub fn organization(&self, org_id: &str) -> Option<Organization> {
+        self.organizations.get(org_id).map(|org| org.clone())
+    }
+
+    /// Every registered organization, by id
+    pub fn organizations(&self) -> Vec<Organization> {
+        let mut organizations: Vec<_> = self.organizations.iter().map(|entry| entry.value().clone()).collect();
+        organizations.sort_by(|a, b| a.id.cmp(&b.id));
+        organizations
+    }
+
+    /// Organization registered under `sender_comp_id`
+    pub fn organization_for_sender(&self, sender_comp_id: &str) -> Option<Organization> {
+        let org_id = self.senders.get(sender_comp_id)?.clone();
+        self.organization(&org_id)
+    }
+
+    /// Grants `permission` on `symbol` to `org_id`
+    pub fn grant(
+        &self,
+        org_id: &str,
+        symbol: &str,
+        permission: SymbolPermission,
+    ) -> Result<Organization, PermissionError> {
+        let mut organization = self
+            .organizations
+            .get_mut(org_id)
+            .ok_or_else(|| PermissionError::OrganizationNotFound(org_id.to_string()))?;
+        organization.set_symbol_permission(symbol, permission);
+        info!(org_id, symbol, ?permission, "Granted symbol permission");
+        Ok(self.updated(organization.clone()))
+    }
+
+    /// Removes all access of `org_id` to `symbol`
+    pub fn revoke(&self, org_id: &str, symbol: &str) -> Result<Organization, PermissionError> {
+        let mut organization = self
+            .organizations
+            .get_mut(org_id)
+            .ok_or_else(|| PermissionError::OrganizationNotFound(org_id.to_string()))?;
+        if let Some(previous) = organization.revoke_symbol_permission(symbol) {
+            info!(org_id, symbol, ?previous, "Revoked symbol permission");
+        }
+        Ok(self.updated(organization.clone()))
+    }
+
+    /// Adds or replaces the sub-account `account` of `org_id`
+    pub fn set_sub_account(
+        &self,
+        org_id: &str,
+        account: &str,
+        sub_account: SubAccount,
+    ) -> Result<Organization, PermissionError> {
+        let mut organization = self
+            .organizations
+            .get_mut(org_id)
+            .ok_or_else(|| PermissionError::OrganizationNotFound(org_id.to_string()))?;
+        info!(org_id, account, limits = ?sub_account.limits, "Set sub-account");
+        organization.set_sub_account(account, sub_account);
+        Ok(self.updated(organization.clone()))
+    }
+
+    /// Removes the sub-account `account` of `org_id`
+    pub fn remove_sub_account(&self, org_id: &str, account: &str) -> Result<Organization, PermissionError> {
+        let mut organization = self
+            .organizations
+            .get_mut(org_id)
+            .ok_or_else(|| PermissionError::OrganizationNotFound(org_id.to_string()))?;
+        if organization.remove_sub_account(account).is_none() {
+            return Err(PermissionError::UnknownSubAccount {
+                org: org_id.to_string(),
+                account: account.to_string(),
+            });
+        }
+        info!(org_id, account, "Removed sub-account");
+        Ok(self.updated(organization.clone()))
+    }
+
+    /// Sets the Username and Password the SenderCompID of `org_id` logs on
+    /// with, or removes them with `None`
+    pub fn set_logon_credential(
+        &self,
+        org_id: &str,
+        credential: Option<LogonCredential>,
+    ) -> Result<Organization, PermissionError> {
+        let mut organization = self
+            .organizations
+            .get_mut(org_id)
+            .ok_or_else(|| PermissionError::OrganizationNotFound(org_id.to_string()))?;
+        match &credential {
+            Some(credential) => info!(org_id, username = %credential.username, allow_unsigned = credential.allow_unsigned, "Set logon credential"),
+            None => info!(org_id, "Removed logon credential"),
+        }
+        organization.logon_credential = credential;
+        Ok(self.updated(organization.clone()))
+    }
+
+    fn updated(&self, organization: Organization) -> Organization {
+        if let Some(updates) = &self.updates {
+            if updates.send(organization.clone()).is_err() {
+                warn!(org_id = %organization.id, "Organization update channel closed");
+            }
+        }
+        organization
+    }
+
+    /// Checks that the organization behind `sender_comp_id` holds at least
+    /// `required` on `symbol`
+    pub fn check(
+        &self,
+        sender_comp_id: &str,
+        symbol: &str,
+        required: SymbolPermission,
+    ) -> Result<(), PermissionError> {
+        let org_id = self
+            .senders
+            .get(sender_comp_id)
+            .map(|id| id.clone())
+            .ok_or_else(|| PermissionError::UnknownSender(sender_comp_id.to_string()))?;
+        let held = self
+            .organizations
+            .get(&org_id)
+            .and_then(|org| org.permission_for(symbol));
+
+        match held {
+            Some(permission) if permission.allows(required) => Ok(()),
+            _ => Err(PermissionError::Denied {
+                org: org_id,
+                symbol: symbol.to_string(),
+                required,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use romer_common::types::org::OrganizationType;
+
+    fn organization() -> Organization {
+        Organization::new(
+            "mm1".into(),
+            "Market Maker One".into(),
+            OrganizationType::MarketMaker,
+            "MM1".into(),
+            vec![0; 48],
+        )
+    }
+
+    #[test]
+    fn test_levels_are_cumulative() {
+        let registry = PermissionRegistry::from_organizations([organization()]);
+        registry.grant("mm1", "BTC-USD", SymbolPermission::Quote).unwrap();
+
+        assert!(registry.check("MM1", "BTC-USD", SymbolPermission::ViewOnly).is_ok());
+        assert!(registry.check("MM1", "BTC-USD", SymbolPermission::Quote).is_ok());
+        assert!(matches!(
+            registry.check("MM1", "BTC-USD", SymbolPermission::Trade),
+            Err(PermissionError::Denied { .. })
+        ));
+        assert!(registry.check("MM1", "ETH-USD", SymbolPermission::ViewOnly).is_err());
+    }
+
+    #[test]
+    fn test_revoke_and_unknown() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let registry = PermissionRegistry::from_organizations([organization()]).with_updates(tx);
+        let updated = registry.grant("mm1", "BTC-USD", SymbolPermission::Trade).unwrap();
+        assert_eq!(updated.permission_for("BTC-USD"), Some(SymbolPermission::Trade));
+
+        registry.revoke("mm1", "BTC-USD").unwrap();
+        assert_eq!(rx.try_recv().unwrap().permission_for("BTC-USD"), Some(SymbolPermission::Trade));
+        assert_eq!(rx.try_recv().unwrap().permission_for("BTC-USD"), None);
+        assert!(registry.check("MM1", "BTC-USD", SymbolPermission::ViewOnly).is_err());
+
+        assert_eq!(
+            registry.check("NOBODY", "BTC-USD", SymbolPermission::ViewOnly),
+            Err(PermissionError::UnknownSender("NOBODY".into()))
+        );
+        assert!(registry.grant("nobody", "BTC-USD", SymbolPermission::Trade).is_err());
+    }
+
+    #[test]
+    fn test_update_moves_sender() {
+        let mut other = organization();
+        other.id = "mm2".into();
+        other.sender_comp_id = "MM2".into();
+        let registry = PermissionRegistry::from_organizations([organization(), other]);
+
+        let mut renamed = organization();
+        renamed.sender_comp_id = "MM2".into();
+        assert_eq!(
+            registry.update(renamed.clone()).unwrap_err(),
+            PermissionError::SenderTaken("MM2".into())
+        );
+
+        renamed.sender_comp_id = "MM3".into();
+        registry.update(renamed).unwrap();
+        assert_eq!(registry.organization_for_sender("MM3").unwrap().id, "mm1");
+        assert!(registry.organization_for_sender("MM1").is_none());
+    }
+}
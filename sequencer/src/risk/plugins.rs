@@ -0,0 +1,413 @@
+// src/risk/plugins.rs
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tracing::info;
+use wasmi::{Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+/// Page size of WASM linear memory
+const WASM_PAGE: usize = 65_536;
+
+#[derive(Error, Debug)]
+pub enum PluginError {
+    #[error("Plugin {name} failed to compile: {reason}")]
+    Compile { name: String, reason: String },
+
+    #[error("Plugin {name} imports {import}; plugins may not import host functions")]
+    Import { name: String, import: String },
+
+    #[error("Plugin {name} does not export {export}")]
+    MissingExport { name: String, export: &'static str },
+
+    #[error("Plugin {name} version {version} must follow version {current} activating at or after height {activation_height}")]
+    OutOfOrder {
+        name: String,
+        version: u32,
+        current: u32,
+        activation_height: u64,
+    },
+}
+
+/// Why a plugin refused an order. A plugin that traps, runs out of fuel
+/// or exceeds its memory refuses it too, so every validator reaches the
+/// same verdict.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum PluginRejection {
+    #[error("Rejected by pre-trade plugin {plugin} v{version} with code {code}")]
+    Rejected { plugin: String, version: u32, code: i32 },
+
+    #[error("Pre-trade plugin {plugin} v{version} failed: {reason}")]
+    Failed {
+        plugin: String,
+        version: u32,
+        reason: String,
+    },
+}
+
+/// Resources one plugin call may use
+#[derive(Debug, Clone, Copy)]
+pub struct PluginLimits {
+    /// Instructions, as counted by wasmi's fuel metering
+    pub fuel: u64,
+    /// Linear memory, rounded down to whole pages
+    pub memory_bytes: usize,
+}
+
+/// The order a plugin is asked about, passed to it as JSON. Fields are
+/// the FIX values as received.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderContext {
+    pub sender_comp_id: String,
+    pub account: Option<String>,
+    pub symbol: String,
+    pub side: String,
+    pub quantity: Option<String>,
+    pub price: Option<String>,
+}
+
+/// A registered plugin version
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginInfo {
+    pub name: String,
+    pub version: u32,
+    /// First block height the version checks orders at
+    pub activation_height: u64,
+    /// SHA-256 of the module, so operators can confirm validators load
+    /// the same code
+    pub sha256: String,
+}
+
+struct Plugin {
+    info: PluginInfo,
+    module: Module,
+}
+
+/// Runs exchange supplied WASM modules as additional pre-trade checks.
+///
+/// A plugin exports `memory`, `alloc(len: i32) -> i32` returning where
+/// to write its input, and `check(ptr: i32, len: i32) -> i32` returning 0
+/// to accept the order or a rejection code. It imports nothing, may not
+/// use floating point, and runs in a fresh instance per order under fuel
+/// and memory limits, so its verdict depends on the order alone.
+///
+/// Versions of a plugin are scheduled by block height; an order is
+/// checked by the latest version of each plugin active at the height of
+/// the block it will go in, in plugin name order.
+pub struct PluginHost {
+    engine: Engine,
+    limits: PluginLimits,
+    plugins: RwLock<Vec<Plugin>>,
+}
+
+impl PluginHost {
+    pub fn new(limits: PluginLimits) -> Self {
+        let mut config = Config::default();
+        config.consume_fuel(true).floats(false);
+        Self {
+            engine: Engine::new(&config),
+            limits,
+            plugins: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Schedules `version` of plugin `name` from `activation_height`.
+    /// Versions of a plugin must be registered in increasing order, each
+    /// activating no earlier than the last.
+    pub fn register(
+        &self,
+        name: &str,
+        version: u32,
+        activation_height: u64,
+        wasm: &[u8],
+    ) -> Result<PluginInfo, PluginError> {
+        let module = Module::new(&self.engine, wasm).map_err(|e| PluginError::Compile {
+            name: name.to_string(),
+            reason: e.to_string(),
+        })?;
+        if let Some(import) = module.imports().next() {
+            return Err(PluginError::Import {
+                name: name.to_string(),
+                import: format!("{}::{}", import.module(), import.name()),
+            });
+        }
+        for export in ["memory", "alloc", "check"] {
+            if !module.exports().any(|candidate| candidate.name() == export) {
+                return Err(PluginError::MissingExport {
+                    name: name.to_string(),
+                    export,
+                });
+            }
+        }
+
+        let mut plugins = self.plugins.write();
+        if let Some(current) = plugins.iter().rev().find(|plugin| plugin.info.name == name) {
+            if version <= current.info.version || activation_height < current.info.activation_height {
+                return Err(PluginError::OutOfOrder {
+                    name: name.to_string(),
+                    version,
+                    current: current.info.version,
+                    activation_height: current.info.activation_height,
+                });
+            }
+        }
+        let info = PluginInfo {
+            name: name.to_string(),
+            version,
+            activation_height,
+            sha256: hex::encode(Sha256::digest(wasm)),
+        };
+        info!(name, version, activation_height, sha256 = %info.sha256, "Registered pre-trade plugin");
+        plugins.push(Plugin {
+            info: info.clone(),
+            module,
+        });
+        Ok(info)
+    }
+
+    /// Every registered plugin version
+    pub fn plugins(&self) -> Vec<PluginInfo> {
+        self.plugins.read().iter().map(|plugin| plugin.info.clone()).collect()
+    }
+
+    /// The plugin versions checking orders at `height`, by name
+    pub fn active(&self, height: u64) -> Vec<PluginInfo> {
+        latest(&self.plugins.read(), height)
+            .into_iter()
+            .map(|plugin| plugin.info.clone())
+            .collect()
+    }
+
+    /// Runs `order` past every plugin active at `height`, stopping at the
+    /// first to refuse it
+    pub fn check(&self, height: u64, order: &OrderContext) -> Result<(), PluginRejection> {
+        let plugins = self.plugins.read();
+        let active = latest(&plugins, height);
+        if active.is_empty() {
+            return Ok(());
+        }
+
+        let input = serde_json::to_vec(order).expect("order context serializes");
+        for plugin in active {
+            match self.run(&plugin.module, &input) {
+                Ok(0) => {}
+                Ok(code) => {
+                    return Err(PluginRejection::Rejected {
+                        plugin: plugin.info.name.clone(),
+                        version: plugin.info.version,
+                        code,
+                    })
+                }
+                Err(reason) => {
+                    return Err(PluginRejection::Failed {
+                        plugin: plugin.info.name.clone(),
+                        version: plugin.info.version,
+                        reason,
+                    })
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Instantiates `module` and calls its `check` on `input`
+    fn run(&self, module: &Module, input: &[u8]) -> Result<i32, String> {
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(self.limits.memory_bytes / WASM_PAGE * WASM_PAGE)
+            .instances(1)
+            .build();
+        let mut store = Store::new(&self.engine, limits);
+        store.limiter(|limits| limits);
+        store.add_fuel(self.limits.fuel).map_err(|e| e.to_string())?;
+
+        let instance = Linker::<StoreLimits>::new(&self.engine)
+            .instantiate(&mut store, module)
+            .and_then(|instance| instance.start(&mut store))
+            .map_err(|e| e.to_string())?;
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or("memory is not a memory export")?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&store, "alloc")
+            .map_err(|e| e.to_string())?;
+        let check = instance
+            .get_typed_func::<(i32, i32), i32>(&store, "check")
+            .map_err(|e| e.to_string())?;
+
+        let len = i32::try_from(input.len()).map_err(|_| "order context too large".to_string())?;
+        let ptr = alloc.call(&mut store, len).map_err(|e| e.to_string())?;
+        memory
+            .write(&mut store, ptr as u32 as usize, input)
+            .map_err(|e| e.to_string())?;
+        check.call(&mut store, (ptr, len)).map_err(|e| e.to_string())
+    }
+}
+
+/// The latest version of each plugin active at `height`, by name
+fn latest(plugins: &[Plugin], height: u64) -> Vec<&Plugin> {
+    let mut latest: Vec<&Plugin> = Vec::new();
+    for plugin in plugins.iter().filter(|plugin| plugin.info.activation_height <= height) {
+        match latest.iter_mut().find(|current| current.info.name == plugin.info.name) {
+            // Versions are registered in increasing order
+            Some(current) => *current = plugin,
+            None => latest.push(plugin),
+        }
+    }
+    latest.sort_by(|a, b| a.info.name.cmp(&b.info.name));
+    latest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMITS: PluginLimits = PluginLimits {
+        fuel: 100_000,
+        memory_bytes: 2 * WASM_PAGE,
+    };
+
+    /// A plugin answering `verdict` to every order
+    fn constant(verdict: i32) -> Vec<u8> {
+        wat::parse_str(format!(
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "alloc") (param i32) (result i32) i32.const 1024)
+                (func (export "check") (param i32 i32) (result i32) i32.const {verdict}))"#
+        ))
+        .unwrap()
+    }
+
+    /// Rejects with code 451 orders whose context contains `"account":"US`,
+    /// accepting any other
+    fn jurisdiction() -> Vec<u8> {
+        wat::parse_str(
+            r#"(module
+                (memory (export "memory") 1)
+                (data (i32.const 0) "\"account\":\"US")
+                (func (export "alloc") (param i32) (result i32) i32.const 1024)
+                (func (export "check") (param $ptr i32) (param $len i32) (result i32)
+                  (local $at i32) (local $i i32)
+                  (local.set $at (local.get $ptr))
+                  (block $done
+                    (loop $scan
+                      (br_if $done (i32.gt_s (i32.add (local.get $at) (i32.const 13))
+                                             (i32.add (local.get $ptr) (local.get $len))))
+                      (local.set $i (i32.const 0))
+                      (block $mismatch
+                        (loop $compare
+                          (br_if $mismatch (i32.ne (i32.load8_u (i32.add (local.get $at) (local.get $i)))
+                                                   (i32.load8_u (local.get $i))))
+                          (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                          (if (i32.eq (local.get $i) (i32.const 13)) (then (return (i32.const 451))))
+                          (br $compare)))
+                      (local.set $at (i32.add (local.get $at) (i32.const 1)))
+                      (br $scan)))
+                  i32.const 0))"#,
+        )
+        .unwrap()
+    }
+
+    fn order(account: &str) -> OrderContext {
+        OrderContext {
+            sender_comp_id: "MM1".into(),
+            account: Some(account.into()),
+            symbol: "AAPL".into(),
+            side: "1".into(),
+            quantity: Some("100".into()),
+            price: Some("150.25".into()),
+        }
+    }
+
+    #[test]
+    fn test_versions_activate_by_height() {
+        let host = PluginHost::new(LIMITS);
+        host.register("limits", 1, 0, &constant(0)).unwrap();
+        host.register("limits", 2, 10, &constant(7)).unwrap();
+        assert!(matches!(
+            host.register("limits", 2, 20, &constant(0)),
+            Err(PluginError::OutOfOrder { .. })
+        ));
+
+        host.check(9, &order("GB1")).unwrap();
+        assert_eq!(host.active(9)[0].version, 1);
+        assert_eq!(
+            host.check(10, &order("GB1")),
+            Err(PluginRejection::Rejected {
+                plugin: "limits".into(),
+                version: 2,
+                code: 7
+            })
+        );
+        assert_eq!(host.plugins().len(), 2);
+    }
+
+    #[test]
+    fn test_jurisdiction_check() {
+        let host = PluginHost::new(LIMITS);
+        host.register("jurisdiction", 1, 0, &jurisdiction()).unwrap();
+        host.check(0, &order("GB1")).unwrap();
+        assert!(matches!(
+            host.check(0, &order("US1")),
+            Err(PluginRejection::Rejected { code: 451, .. })
+        ));
+    }
+
+    #[test]
+    fn test_sandbox() {
+        let host = PluginHost::new(LIMITS);
+        let spin = wat::parse_str(
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "alloc") (param i32) (result i32) i32.const 0)
+                (func (export "check") (param i32 i32) (result i32) (loop $forever (br $forever)) i32.const 0))"#,
+        )
+        .unwrap();
+        host.register("spin", 1, 0, &spin).unwrap();
+        assert!(matches!(
+            host.check(0, &order("GB1")),
+            Err(PluginRejection::Failed { .. })
+        ));
+
+        // Memory beyond the limit
+        let greedy = wat::parse_str(
+            r#"(module
+                (memory (export "memory") 4)
+                (func (export "alloc") (param i32) (result i32) i32.const 0)
+                (func (export "check") (param i32 i32) (result i32) i32.const 0))"#,
+        )
+        .unwrap();
+        let host = PluginHost::new(LIMITS);
+        host.register("greedy", 1, 0, &greedy).unwrap();
+        assert!(matches!(
+            host.check(0, &order("GB1")),
+            Err(PluginRejection::Failed { .. })
+        ));
+
+        let clock = wat::parse_str(
+            r#"(module
+                (import "env" "now" (func $now (result i64)))
+                (memory (export "memory") 1)
+                (func (export "alloc") (param i32) (result i32) i32.const 0)
+                (func (export "check") (param i32 i32) (result i32) i32.const 0))"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            host.register("clock", 1, 0, &clock),
+            Err(PluginError::Import { .. })
+        ));
+
+        let float = wat::parse_str(
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "alloc") (param i32) (result i32) i32.const 0)
+                (func (export "check") (param i32 i32) (result i32)
+                  (i32.trunc_f64_s (f64.const 1.5))))"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            host.register("float", 1, 0, &float),
+            Err(PluginError::Compile { .. })
+        ));
+    }
+}
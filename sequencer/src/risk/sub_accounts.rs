@@ -0,0 +1,300 @@
+// src/risk/sub_accounts.rs
+
+use crate::events::bus::EventSink;
+use crate::events::types::SequencerEvent;
+use parking_lot::RwLock;
+use romer_common::types::org::Organization;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SubAccountError {
+    #[error("Organization {org} requires an Account (1) naming one of its sub-accounts")]
+    AccountRequired { org: String },
+
+    #[error("Organization {org} has no sub-account {account}")]
+    UnknownAccount { org: String, account: String },
+
+    #[error("Invalid {field} {value}")]
+    InvalidField { field: &'static str, value: String },
+
+    #[error("Order quantity {quantity} exceeds the limit of {limit} of sub-account {account}")]
+    OrderQuantity { account: String, quantity: u64, limit: u64 },
+
+    #[error("Position in {symbol} would reach {position}, beyond the limit of {limit} of sub-account {account}")]
+    Position {
+        account: String,
+        symbol: String,
+        position: i128,
+        limit: u64,
+    },
+}
+
+/// A sub-account's fills in one symbol
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubAccountPosition {
+    pub account: String,
+    pub symbol: String,
+    /// Bought less sold
+    pub net: i128,
+    pub bought: u128,
+    pub sold: u128,
+}
+
+/// Positions of each sub-account, built from fills whose orders carried an
+/// Account (1), and the limits checked against them when orders arrive.
+/// Fills without an account count toward no sub-account.
+#[derive(Default)]
+pub struct SubAccountTracker {
+    /// By SenderCompID, then account and symbol
+    positions: RwLock<BTreeMap<String, BTreeMap<(String, String), SubAccountPosition>>>,
+}
+
+impl SubAccountTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a fill of `quantity` of `symbol` between the sub-accounts, if
+    /// named, of `buyer` and `seller`
+    pub fn record_fill(&self, symbol: &str, quantity: u64, buyer: (&str, Option<&str>), seller: (&str, Option<&str>)) {
+        let mut positions = self.positions.write();
+        for ((sender_comp_id, account), bought) in [(buyer, true), (seller, false)] {
+            let Some(account) = account else { continue };
+            let position = positions
+                .entry(sender_comp_id.to_string())
+                .or_default()
+                .entry((account.to_string(), symbol.to_string()))
+                .or_insert_with(|| SubAccountPosition {
+                    account: account.to_string(),
+                    symbol: symbol.to_string(),
+                    ..SubAccountPosition::default()
+                });
+            if bought {
+                position.net += quantity as i128;
+                position.bought += quantity as u128;
+            } else {
+                position.net -= quantity as i128;
+                position.sold += quantity as u128;
+            }
+        }
+    }
+
+    /// Net position of `account` of `sender_comp_id` in `symbol`
+    pub fn net(&self, sender_comp_id: &str, account: &str, symbol: &str) -> i128 {
+        self.positions
+            .read()
+            .get(sender_comp_id)
+            .and_then(|accounts| accounts.get(&(account.to_string(), symbol.to_string())))
+            .map_or(0, |position| position.net)
+    }
+
+    /// Positions of every sub-account of `sender_comp_id`, of `account`
+    /// only if given
+    pub fn positions(&self, sender_comp_id: &str, account: Option<&str>) -> Vec<SubAccountPosition> {
+        self.positions
+            .read()
+            .get(sender_comp_id)
+            .into_iter()
+            .flat_map(|accounts| accounts.values())
+            .filter(|position| account.is_none() || account == Some(position.account.as_str()))
+            .cloned()
+            .collect()
+    }
+
+    /// Checks an order's Account (1), Side (54) and OrderQty (38) against
+    /// the sub-accounts of `organization`. Organizations without
+    /// sub-accounts are not checked.
+    pub fn check(
+        &self,
+        organization: &Organization,
+        account: Option<&str>,
+        symbol: &str,
+        side: &str,
+        quantity: &str,
+    ) -> Result<(), SubAccountError> {
+        if organization.sub_accounts.is_empty() {
+            return Ok(());
+        }
+        let account =
+            account
+                .filter(|account| !account.is_empty())
+                .ok_or_else(|| SubAccountError::AccountRequired {
+                    org: organization.id.clone(),
+                })?;
+        let limits = &organization
+            .sub_account(account)
+            .ok_or_else(|| SubAccountError::UnknownAccount {
+                org: organization.id.clone(),
+                account: account.to_string(),
+            })?
+            .limits;
+
+        let invalid = |field, value: &str| SubAccountError::InvalidField {
+            field,
+            value: value.to_string(),
+        };
+        let parsed: u64 = quantity.parse().map_err(|_| invalid("quantity", quantity))?;
+        if let Some(limit) = limits.max_order_quantity.filter(|limit| parsed > *limit) {
+            return Err(SubAccountError::OrderQuantity {
+                account: account.to_string(),
+                quantity: parsed,
+                limit,
+            });
+        }
+        if let Some(limit) = limits.max_position {
+            // Limits hold should the whole order fill
+            let change = match side {
+                "1" | "3" => parsed as i128,
+                "2" | "5" | "6" => -(parsed as i128),
+                _ => return Err(invalid("side", side)),
+            };
+            let position = self.net(&organization.sender_comp_id, account, symbol) + change;
+            if position.unsigned_abs() > limit as u128 {
+                return Err(SubAccountError::Position {
+                    account: account.to_string(),
+                    symbol: symbol.to_string(),
+                    position,
+                    limit,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl EventSink for Arc<SubAccountTracker> {
+    fn name(&self) -> &str {
+        "sub_accounts"
+    }
+
+    fn handle(&mut self, event: &SequencerEvent) {
+        if let SequencerEvent::Match {
+            symbol,
+            quantity,
+            buyer,
+            seller,
+            buyer_account,
+            seller_account,
+            ..
+        } = event
+        {
+            self.record_fill(
+                symbol,
+                *quantity,
+                (buyer, buyer_account.as_deref()),
+                (seller, seller_account.as_deref()),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use romer_common::types::org::{OrganizationType, SubAccount, SubAccountLimits};
+
+    fn prime_broker() -> Organization {
+        let mut organization = Organization::new(
+            "pb1".into(),
+            "Prime Broker One".into(),
+            OrganizationType::PrimeBroker,
+            "PB1".into(),
+            vec![0; 48],
+        );
+        organization.set_sub_account(
+            "DESK-A",
+            SubAccount {
+                name: "Desk A".into(),
+                limits: SubAccountLimits {
+                    max_order_quantity: Some(500),
+                    max_position: Some(1_000),
+                },
+            },
+        );
+        organization.set_sub_account(
+            "DESK-B",
+            SubAccount {
+                name: "Desk B".into(),
+                limits: SubAccountLimits::default(),
+            },
+        );
+        organization
+    }
+
+    #[test]
+    fn test_account_required_once_registered() {
+        let tracker = SubAccountTracker::new();
+        let organization = prime_broker();
+
+        assert_eq!(
+            tracker.check(&organization, None, "AAPL", "1", "100"),
+            Err(SubAccountError::AccountRequired { org: "pb1".into() })
+        );
+        assert!(matches!(
+            tracker.check(&organization, Some("DESK-C"), "AAPL", "1", "100"),
+            Err(SubAccountError::UnknownAccount { .. })
+        ));
+        tracker
+            .check(&organization, Some("DESK-B"), "AAPL", "1", "1000000")
+            .unwrap();
+
+        let mut single_desk = organization.clone();
+        single_desk.sub_accounts.clear();
+        tracker.check(&single_desk, None, "AAPL", "1", "100").unwrap();
+    }
+
+    #[test]
+    fn test_limits_follow_fills() {
+        let mut tracker = Arc::new(SubAccountTracker::new());
+        let organization = prime_broker();
+
+        assert!(matches!(
+            tracker.check(&organization, Some("DESK-A"), "AAPL", "1", "600"),
+            Err(SubAccountError::OrderQuantity { limit: 500, .. })
+        ));
+
+        for _ in 0..2 {
+            tracker.handle(&SequencerEvent::Match {
+                symbol: "AAPL".into(),
+                price: 150_000_000_000,
+                quantity: 400,
+                buyer: "PB1".into(),
+                seller: "MM1".into(),
+                maker: None,
+                buyer_account: Some("DESK-A".into()),
+                seller_account: None,
+                at: chrono::Utc::now(),
+            });
+        }
+        assert_eq!(tracker.net("PB1", "DESK-A", "AAPL"), 800);
+        assert!(tracker.positions("MM1", None).is_empty());
+
+        assert_eq!(
+            tracker.check(&organization, Some("DESK-A"), "AAPL", "1", "300"),
+            Err(SubAccountError::Position {
+                account: "DESK-A".into(),
+                symbol: "AAPL".into(),
+                position: 1_100,
+                limit: 1_000,
+            })
+        );
+        // Selling reduces the position, other symbols are unaffected
+        tracker
+            .check(&organization, Some("DESK-A"), "AAPL", "2", "500")
+            .unwrap();
+        tracker
+            .check(&organization, Some("DESK-A"), "MSFT", "1", "500")
+            .unwrap();
+        assert!(tracker.check(&organization, Some("DESK-A"), "AAPL", "7", "1").is_err());
+
+        tracker.record_fill("AAPL", 100, ("MM1", None), ("PB1", Some("DESK-A")));
+        let positions = tracker.positions("PB1", Some("DESK-A"));
+        assert_eq!(
+            (positions[0].net, positions[0].bought, positions[0].sold),
+            (700, 800, 100)
+        );
+    }
+}
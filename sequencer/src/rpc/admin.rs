@@ -0,0 +1,198 @@
+// src/rpc/admin.rs
+
+use crate::config::AdminConfig;
+use crate::rpc::types::Caller;
+use romer_common::types::admin::{AdminAuthError, AdminRole, AdminToken};
+use romer_common::utils::clock::SharedClock;
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum AdminAccessError {
+    #[error("Admin methods are disabled: no certificate or key has an admin role")]
+    Disabled,
+
+    #[error("Admin methods need a client certificate or an admin token")]
+    Unauthenticated,
+
+    #[error("Client certificate {0} has no admin role")]
+    UnknownCertificate(String),
+
+    #[error("Key {0} has no admin role")]
+    UnknownKey(String),
+
+    #[error(transparent)]
+    Token(#[from] AdminAuthError),
+
+    #[error("{identity} ({role}) may not call {method}, which needs {required}")]
+    Forbidden {
+        identity: String,
+        role: AdminRole,
+        method: String,
+        required: AdminRole,
+    },
+}
+
+/// Who is calling an admin method, and with what role
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdminPrincipal {
+    /// `cert:<fingerprint>` or `key:<public key>`
+    pub identity: String,
+    pub role: AdminRole,
+}
+
+/// Role an admin method needs. Methods not listed need a superuser, so new
+/// ones are closed until classified.
+pub fn required_role(method: &str) -> AdminRole {
+    match method {
+        "admin_stats"
+        | "admin_kill_switch_status"
+        | "admin_drain_status"
+        | "admin_load_shedding_status"
+        | "admin_mm_obligation_report"
+        | "admin_log_level"
+        | "admin_market_data_stats"
+        | "admin_reconciliation_report"
+        | "admin_settlement_receipts"
+        | "admin_symbol_permissions"
+        | "admin_api_keys" => AdminRole::Viewer,
+        "admin_engage_kill_switch"
+        | "admin_release_kill_switch"
+        | "admin_enter_drain_mode"
+        | "admin_exit_drain_mode"
+        | "admin_grant_symbol_permission"
+        | "admin_revoke_symbol_permission"
+        | "admin_set_sub_account"
+        | "admin_remove_sub_account"
+        | "admin_set_mm_obligation"
+        | "admin_remove_mm_obligation" => AdminRole::Risk,
+        "admin_broadcast_news"
+        | "admin_set_reference_price"
+        | "admin_set_log_level"
+        | "admin_set_log_directive"
+        | "admin_reset_log_level"
+        | "admin_run_reconciliation"
+        | "admin_schedule_instrument_override"
+        | "admin_create_api_key"
+        | "admin_revoke_api_key" => AdminRole::Operator,
+        _ => AdminRole::Superuser,
+    }
+}
+
+/// Role-based access to the admin methods. Callers are identified by the
+/// client certificate they presented over mutual TLS, mapped by its
+/// fingerprint, or by an `AdminToken` signed with a KeyManager key, mapped
+/// by its public key.
+pub struct AdminAccess {
+    /// By hex SHA-256 fingerprint of the DER certificate
+    certificates: BTreeMap<String, AdminRole>,
+    /// By hex Ed25519 public key
+    keys: BTreeMap<String, AdminRole>,
+    token_max_lifetime_secs: u64,
+    clock: SharedClock,
+}
+
+impl AdminAccess {
+    pub fn new(config: &AdminConfig, clock: SharedClock) -> Self {
+        let lowercase = |roles: &BTreeMap<String, AdminRole>| {
+            roles.iter().map(|(id, role)| (id.to_lowercase(), *role)).collect()
+        };
+        Self {
+            certificates: lowercase(&config.certificates),
+            keys: lowercase(&config.tokens),
+            token_max_lifetime_secs: config.token_max_lifetime_secs,
+            clock,
+        }
+    }
+
+    /// Identifies `caller`, preferring its client certificate to a token
+    pub fn authenticate(&self, caller: &Caller) -> Result<AdminPrincipal, AdminAccessError> {
+        if let Some(fingerprint) = &caller.certificate {
+            let role = self
+                .certificates
+                .get(fingerprint)
+                .ok_or_else(|| AdminAccessError::UnknownCertificate(fingerprint.clone()))?;
+            return Ok(AdminPrincipal {
+                identity: format!("cert:{}", fingerprint),
+                role: *role,
+            });
+        }
+        let token: AdminToken = caller
+            .admin_token
+            .as_deref()
+            .ok_or(AdminAccessError::Unauthenticated)?
+            .parse()?;
+        let public_key = hex::encode(&token.public_key);
+        let role = *self
+            .keys
+            .get(&public_key)
+            .ok_or_else(|| AdminAccessError::UnknownKey(public_key.clone()))?;
+        token.verify(self.clock.now().timestamp().max(0) as u64, self.token_max_lifetime_secs)?;
+        Ok(AdminPrincipal {
+            identity: format!("key:{}", public_key),
+            role,
+        })
+    }
+
+    /// Identifies `caller` and checks its role allows `method`
+    pub fn authorize(&self, method: &str, caller: &Caller) -> Result<AdminPrincipal, AdminAccessError> {
+        let principal = self.authenticate(caller)?;
+        let required = required_role(method);
+        if !principal.role.allows(required) {
+            return Err(AdminAccessError::Forbidden {
+                identity: principal.identity,
+                role: principal.role,
+                method: method.to_string(),
+                required,
+            });
+        }
+        Ok(principal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use commonware_cryptography::{Ed25519, Scheme};
+    use romer_common::utils::clock::ManualClock;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_roles_from_certificates_and_tokens() {
+        let now = Utc::now();
+        let mut risk_officer = Ed25519::from_seed(7);
+        let config = AdminConfig {
+            certificates: [("AB01".to_string(), AdminRole::Viewer)].into(),
+            tokens: [(hex::encode(risk_officer.public_key()), AdminRole::Risk)].into(),
+            ..Default::default()
+        };
+        let access = AdminAccess::new(&config, Arc::new(ManualClock::new(now)));
+
+        assert_eq!(access.authorize("admin_stats", &Caller::default()), Err(AdminAccessError::Unauthenticated));
+        let viewer = Caller {
+            certificate: Some("ab01".into()),
+            ..Default::default()
+        };
+        assert_eq!(access.authorize("admin_stats", &viewer).unwrap().role, AdminRole::Viewer);
+        assert!(matches!(
+            access.authorize("admin_engage_kill_switch", &viewer),
+            Err(AdminAccessError::Forbidden { required: AdminRole::Risk, .. })
+        ));
+
+        let token = AdminToken::sign(now.timestamp() as u64 + 60, &mut risk_officer);
+        let risk = Caller {
+            admin_token: Some(token.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(access.authorize("admin_engage_kill_switch", &risk).unwrap().role, AdminRole::Risk);
+        assert!(access.authorize("admin_broadcast_news", &risk).is_err());
+        assert!(access.authorize("admin_settle", &risk).is_err());
+
+        let stranger = Caller {
+            admin_token: Some(AdminToken::sign(now.timestamp() as u64 + 60, &mut Ed25519::from_seed(8)).to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(access.authorize("admin_stats", &stranger), Err(AdminAccessError::UnknownKey(_))));
+    }
+}
@@ -0,0 +1,389 @@
+// src/rpc/api_keys.rs
+
+use crate::config::ApiKeyConfig;
+use crate::rpc::types::Caller;
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use rand::RngCore;
+use romer_common::storage::group_commit::GroupCommitter;
+use romer_common::storage::journal::RomerJournal;
+use romer_common::utils::clock::SharedClock;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use thiserror::Error;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Prefix of every API key secret, so leaked keys are easy to recognize
+pub const SECRET_PREFIX: &str = "romer_";
+
+/// Buckets of callers without a key kept before refilled ones are pruned
+const ANONYMOUS_PRUNE_AT: usize = 1024;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ApiKeyError {
+    #[error("An API key is required")]
+    Missing,
+
+    #[error("Unknown or revoked API key")]
+    Invalid,
+
+    #[error("Rate limit of {rate_per_sec} requests a second exceeded, retry in {retry_after_ms}ms")]
+    RateLimited { rate_per_sec: u32, retry_after_ms: u64 },
+
+    #[error("No API key {0}")]
+    NotFound(String),
+
+    #[error("API key {0} is already revoked")]
+    AlreadyRevoked(String),
+
+    #[error("Storage error: {0}")]
+    Storage(String),
+}
+
+/// Whether `method` is a public read, subject to API keys and rate limits
+pub fn is_read_method(method: &str) -> bool {
+    method.starts_with("get_")
+}
+
+/// An API key, as journaled whenever it changes. The secret is only ever
+/// returned when the key is created; its hash identifies it afterwards.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiKey {
+    pub id: String,
+    pub label: String,
+    /// Hex SHA-256 of the secret
+    pub secret_hash: String,
+    pub rate_per_sec: u32,
+    pub burst: u32,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// An API key as listed by `admin_api_keys`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ApiKeyStatus {
+    pub id: String,
+    pub label: String,
+    pub rate_per_sec: u32,
+    pub burst: u32,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    /// Requests served since the sequencer started
+    pub requests: u64,
+    /// Requests refused for exceeding the rate limit since the sequencer started
+    pub rate_limited: u64,
+}
+
+/// Tokens of one caller, refilled at its rate up to its burst
+struct Bucket {
+    tokens: f64,
+    updated: DateTime<Utc>,
+}
+
+impl Bucket {
+    fn full(burst: u32, now: DateTime<Utc>) -> Self {
+        Self {
+            tokens: burst as f64,
+            updated: now,
+        }
+    }
+
+    /// Tokens at `now`, refilled since the last request
+    fn tokens(&self, rate_per_sec: u32, burst: u32, now: DateTime<Utc>) -> f64 {
+        let elapsed = (now - self.updated).num_milliseconds().max(0) as f64 / 1000.0;
+        (self.tokens + elapsed * rate_per_sec as f64).min(burst as f64)
+    }
+
+    /// Takes a token, or returns the milliseconds until one is available
+    fn take(&mut self, rate_per_sec: u32, burst: u32, now: DateTime<Utc>) -> Result<(), u64> {
+        self.tokens = self.tokens(rate_per_sec, burst, now);
+        self.updated = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(((1.0 - self.tokens) / rate_per_sec as f64 * 1000.0).ceil() as u64)
+        }
+    }
+}
+
+#[derive(Default)]
+struct Usage {
+    requests: u64,
+    rate_limited: u64,
+}
+
+#[derive(Default)]
+struct KeyState {
+    keys: HashMap<String, ApiKey>,
+    /// Key id by secret hash
+    by_secret: HashMap<String, String>,
+    /// By key id
+    buckets: HashMap<String, Bucket>,
+    /// By remote address, for callers without a key
+    anonymous: HashMap<IpAddr, Bucket>,
+    /// Number of anonymous buckets at which refilled ones are next pruned
+    prune_anonymous_at: usize,
+    usage: HashMap<String, Usage>,
+}
+
+impl KeyState {
+    fn insert(&mut self, key: ApiKey) {
+        self.by_secret.insert(key.secret_hash.clone(), key.id.clone());
+        self.keys.insert(key.id.clone(), key);
+    }
+
+    /// Takes a token from the bucket of `remote`, whose burst is its rate.
+    /// A bucket refilled to its burst is no different from a new one, so
+    /// those are dropped whenever the buckets have doubled since the last
+    /// pruning.
+    fn take_anonymous(&mut self, remote: IpAddr, rate_per_sec: u32, now: DateTime<Utc>) -> Result<(), u64> {
+        if !self.anonymous.contains_key(&remote) && self.anonymous.len() >= self.prune_anonymous_at {
+            self.anonymous
+                .retain(|_, bucket| bucket.tokens(rate_per_sec, rate_per_sec, now) < rate_per_sec as f64);
+            self.prune_anonymous_at = (self.anonymous.len() * 2).max(ANONYMOUS_PRUNE_AT);
+        }
+        self.anonymous
+            .entry(remote)
+            .or_insert_with(|| Bucket::full(rate_per_sec, now))
+            .take(rate_per_sec, rate_per_sec, now)
+    }
+}
+
+/// API keys of the public read methods and their per-key token bucket
+/// rate limits. Keys are journaled as they are created and revoked; usage
+/// and buckets start afresh on restart.
+pub struct ApiKeyRegistry {
+    config: ApiKeyConfig,
+    state: Mutex<KeyState>,
+    journal: Option<GroupCommitter>,
+    clock: SharedClock,
+}
+
+impl ApiKeyRegistry {
+    /// Registry without persistence, for tests and tooling
+    pub fn in_memory(config: ApiKeyConfig, clock: SharedClock) -> Self {
+        Self {
+            config,
+            state: Mutex::new(KeyState::default()),
+            journal: None,
+            clock,
+        }
+    }
+
+    /// Opens the registry over `journal`, restoring the keys in it
+    pub async fn open(mut journal: RomerJournal, config: ApiKeyConfig, clock: SharedClock) -> Result<Self, ApiKeyError> {
+        let mut state = KeyState::default();
+        for bytes in journal.replay_all().await.map_err(ApiKeyError::Storage)? {
+            match serde_json::from_slice::<ApiKey>(&bytes) {
+                Ok(key) => state.insert(key),
+                Err(e) => warn!(error = %e, "Skipping undecodable API key record"),
+            }
+        }
+        info!(keys = state.keys.len(), "Restored API keys");
+
+        Ok(Self {
+            config,
+            state: Mutex::new(state),
+            journal: Some(GroupCommitter::spawn(journal)),
+            clock,
+        })
+    }
+
+    async fn persist(&self, key: &ApiKey) -> Result<(), ApiKeyError> {
+        if let Some(journal) = &self.journal {
+            let bytes = serde_json::to_vec(key).map_err(|e| ApiKeyError::Storage(e.to_string()))?;
+            journal.append(bytes).await.map_err(ApiKeyError::Storage)?;
+        }
+        Ok(())
+    }
+
+    /// Creates a key with the configured limits unless given, returning it
+    /// with its secret
+    pub async fn create(
+        &self,
+        label: String,
+        rate_per_sec: Option<u32>,
+        burst: Option<u32>,
+        created_by: &str,
+    ) -> Result<(ApiKeyStatus, String), ApiKeyError> {
+        let mut secret = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut secret);
+        let secret = format!("{}{}", SECRET_PREFIX, hex::encode(secret));
+        let key = ApiKey {
+            id: Uuid::new_v4().simple().to_string(),
+            label,
+            secret_hash: hash(&secret),
+            rate_per_sec: rate_per_sec.filter(|rate| *rate > 0).unwrap_or(self.config.rate_per_sec),
+            burst: burst.filter(|burst| *burst > 0).unwrap_or(self.config.burst),
+            created_by: created_by.to_string(),
+            created_at: self.clock.now(),
+            revoked_at: None,
+        };
+        self.persist(&key).await?;
+        info!(id = %key.id, label = %key.label, rate_per_sec = key.rate_per_sec, "API key created");
+
+        let mut state = self.state.lock();
+        state.insert(key.clone());
+        Ok((status(&key, state.usage.get(&key.id)), secret))
+    }
+
+    /// Revokes a key; requests made with it are refused from now on
+    pub async fn revoke(&self, id: &str) -> Result<ApiKeyStatus, ApiKeyError> {
+        let mut key = self
+            .state
+            .lock()
+            .keys
+            .get(id)
+            .cloned()
+            .ok_or_else(|| ApiKeyError::NotFound(id.to_string()))?;
+        if key.revoked_at.is_some() {
+            return Err(ApiKeyError::AlreadyRevoked(id.to_string()));
+        }
+        key.revoked_at = Some(self.clock.now());
+        self.persist(&key).await?;
+        info!(id = %key.id, label = %key.label, "API key revoked");
+
+        let mut state = self.state.lock();
+        state.insert(key.clone());
+        Ok(status(&key, state.usage.get(&key.id)))
+    }
+
+    /// Every key, oldest first
+    pub fn keys(&self) -> Vec<ApiKeyStatus> {
+        let state = self.state.lock();
+        let mut keys: Vec<_> = state.keys.values().map(|key| status(key, state.usage.get(&key.id))).collect();
+        keys.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.id.cmp(&b.id)));
+        keys
+    }
+
+    /// Admits a read from `caller`, taking a token from the bucket of its
+    /// key, or of its address when it has none
+    pub fn admit(&self, caller: &Caller) -> Result<(), ApiKeyError> {
+        let now = self.clock.now();
+        let mut state = self.state.lock();
+        let (rate_per_sec, taken) = match &caller.api_key {
+            Some(secret) => {
+                let key = state
+                    .by_secret
+                    .get(&hash(secret))
+                    .and_then(|id| state.keys.get(id))
+                    .filter(|key| key.revoked_at.is_none())
+                    .ok_or(ApiKeyError::Invalid)?;
+                let (id, rate_per_sec, burst) = (key.id.clone(), key.rate_per_sec, key.burst);
+
+                let taken = state
+                    .buckets
+                    .entry(id.clone())
+                    .or_insert_with(|| Bucket::full(burst, now))
+                    .take(rate_per_sec, burst, now);
+                let usage = state.usage.entry(id).or_default();
+                match taken {
+                    Ok(()) => usage.requests += 1,
+                    Err(_) => usage.rate_limited += 1,
+                }
+                (rate_per_sec, taken)
+            }
+            None if self.config.required => return Err(ApiKeyError::Missing),
+            None => match caller.remote {
+                Some(remote) if self.config.anonymous_rate_per_sec > 0 => {
+                    let rate = self.config.anonymous_rate_per_sec;
+                    (rate, state.take_anonymous(remote, rate, now))
+                }
+                _ => return Ok(()),
+            },
+        };
+        taken.map_err(|retry_after_ms| ApiKeyError::RateLimited { rate_per_sec, retry_after_ms })
+    }
+}
+
+fn hash(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+fn status(key: &ApiKey, usage: Option<&Usage>) -> ApiKeyStatus {
+    ApiKeyStatus {
+        id: key.id.clone(),
+        label: key.label.clone(),
+        rate_per_sec: key.rate_per_sec,
+        burst: key.burst,
+        created_by: key.created_by.clone(),
+        created_at: key.created_at,
+        revoked_at: key.revoked_at,
+        requests: usage.map_or(0, |usage| usage.requests),
+        rate_limited: usage.map_or(0, |usage| usage.rate_limited),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use romer_common::utils::clock::ManualClock;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_keys_are_rate_limited_and_revocable() {
+        let clock = Arc::new(ManualClock::new(Utc::now()));
+        let config = ApiKeyConfig {
+            required: true,
+            ..Default::default()
+        };
+        let registry = ApiKeyRegistry::in_memory(config, clock.clone());
+        assert_eq!(registry.admit(&Caller::default()), Err(ApiKeyError::Missing));
+
+        let (key, secret) = registry.create("explorer".into(), Some(2), Some(2), "admin").await.unwrap();
+        assert!(secret.starts_with(SECRET_PREFIX));
+        let caller = Caller {
+            api_key: Some(secret),
+            ..Default::default()
+        };
+        registry.admit(&caller).unwrap();
+        registry.admit(&caller).unwrap();
+        assert_eq!(
+            registry.admit(&caller),
+            Err(ApiKeyError::RateLimited { rate_per_sec: 2, retry_after_ms: 500 })
+        );
+        clock.advance(Duration::from_millis(500));
+        registry.admit(&caller).unwrap();
+        assert_eq!(registry.keys()[0].requests, 3);
+        assert_eq!(registry.keys()[0].rate_limited, 1);
+
+        registry.revoke(&key.id).await.unwrap();
+        assert_eq!(registry.admit(&caller), Err(ApiKeyError::Invalid));
+        assert_eq!(registry.revoke(&key.id).await, Err(ApiKeyError::AlreadyRevoked(key.id)));
+    }
+
+    #[test]
+    fn test_refilled_anonymous_buckets_pruned() {
+        let clock = Arc::new(ManualClock::new(Utc::now()));
+        let config = ApiKeyConfig {
+            anonymous_rate_per_sec: 2,
+            ..Default::default()
+        };
+        let registry = ApiKeyRegistry::in_memory(config, clock.clone());
+        let caller = |i: usize| Caller {
+            remote: Some(IpAddr::from([10, 0, (i >> 8) as u8, i as u8])),
+            ..Default::default()
+        };
+        for i in 0..ANONYMOUS_PRUNE_AT {
+            registry.admit(&caller(i)).unwrap();
+        }
+        // The first caller drains its bucket, so it stays limited
+        registry.admit(&caller(0)).unwrap();
+        assert!(registry.admit(&caller(0)).is_err());
+
+        // Once refilled the others are forgotten when a new caller arrives
+        clock.advance(Duration::from_millis(500));
+        registry.admit(&caller(ANONYMOUS_PRUNE_AT)).unwrap();
+        assert_eq!(registry.state.lock().anonymous.len(), 2);
+        assert!(registry.admit(&caller(0)).is_ok());
+        assert!(registry.admit(&caller(0)).is_err());
+    }
+}
@@ -0,0 +1,2804 @@
+// src/rpc/handler.rs
+
+use crate::attestation::registry::{AttestationRegistry, AttestationRejection};
+use crate::rpc::admin::{AdminAccess, AdminAccessError};
+use crate::rpc::api_keys::{is_read_method, ApiKeyError, ApiKeyRegistry};
+use crate::audit::reconciliation::ReconciliationService;
+use crate::block::builder::Block;
+use crate::block::clock_quality::ClockMonitor;
+use crate::bridge::relay::{BridgeRelay, RelayError};
+use crate::governance::service::{GovernanceService, ProposalError};
+use crate::market::fees::FeeEngine;
+use crate::market::instruments::{InstrumentRegistry, OverrideError};
+use crate::risk::plugins::PluginHost;
+use crate::events::bus::EventBus;
+use crate::gateway::speed_bump::SpeedBumpState;
+use crate::events::stats::StatsCollector;
+use crate::events::types::SequencerEvent;
+use crate::market::candles::{CandleStore, DEFAULT_RETENTION};
+use crate::market::orders::OrderStore;
+use crate::faucet::service::Faucet;
+use crate::gateway::news::{NewsError, NewsService};
+use crate::market::data::MarketDataPublisher;
+use crate::market::obligations::ObligationMonitor;
+use crate::market::oracle::{OracleAggregator, OracleSubmitError};
+use crate::market::reference_price::{ManualFeed, ReferencePriceError, ReferencePriceService};
+use crate::mempool::nonce::NonceRegistry;
+use crate::risk::drain::DrainMode;
+use crate::risk::kill_switch::KillSwitch;
+use crate::risk::load_shed::LoadShedder;
+use crate::risk::permissions::{PermissionError, PermissionRegistry};
+use crate::risk::sub_accounts::SubAccountTracker;
+use crate::settlement::allocations::AllocationService;
+use crate::settlement::service::SettlementService;
+use crate::rpc::types::{
+    AllocationParams, ApiKeyIdParams, Caller, AttestationParams, AttestationStatusParams, BalanceParams, BlockParams, BridgeAttestationParams, FaucetParams,
+    BridgeDepositParams, BridgeWithdrawalParams, CandleParams, CreateApiKeyParams, DailyStatsParams, DepthParams, DrainParams,
+    InstrumentOverrideParams, InstrumentParams, KillSwitchParams, LogDirectiveParams, LogLevelParams, LogonCredentialParams, ObligationParams, NewsParams, OrderLookupParams, OrganizationLookupParams, OrganizationParams,
+    ReconciliationParams, ReferencePriceParams, RpcError, SettleParams,
+    RpcRequest, RpcResponse, SimulationResult, SubAccountLookupParams, SubAccountParams, SubmitParams, SymbolPermissionParams,
+    TransactionParams, TransactionRecord, TransactionStatus, JSONRPC_VERSION,
+};
+use chrono::Utc;
+use commonware_utils::hex;
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use romer_common::types::address::Address;
+use romer_common::types::envelope::{SignedTransaction, TransactionPayload, DEFAULT_CHAIN_ID};
+use romer_common::types::environment::ExecutionEnvironment;
+use romer_common::types::genesis::GenesisBundle;
+use romer_common::types::nonce::check_nonce;
+use romer_common::types::governance::{Proposal, Signed, Vote};
+use romer_common::types::oracle::SignedPriceSubmission;
+use romer_common::types::org::{LogonCredential, Organization, OrganizationRegistration, OrganizationUpdate};
+use romer_common::types::protocol::{ProtocolSchedule, SUPPORTED_PROTOCOL_VERSION};
+use romer_common::types::rejection::RejectReason;
+use romer_common::utils::clock::{system_clock, SharedClock};
+use romer_common::utils::logging::LogHandle;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+/// Blocks after which included transactions are no longer looked up
+pub const TRANSACTION_RETENTION_BLOCKS: u64 = 100_000;
+
+/// Blocks between prunings of the transaction records
+const TRANSACTION_PRUNE_INTERVAL: u64 = 1_000;
+
+/// Chain state readable over RPC. Populated by the block pipeline as
+/// blocks are built and balances change.
+#[derive(Default)]
+pub struct RpcState {
+    blocks: DashMap<u64, Block>,
+    transactions: DashMap<String, TransactionRecord>,
+    balances: DashMap<Address, u64>,
+    nonces: NonceRegistry,
+    /// Balance changes are announced here
+    events: EventBus,
+}
+
+impl RpcState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Journal committed nonces to `path`, restoring those already there
+    pub fn with_nonce_journal(mut self, path: impl AsRef<Path>) -> io::Result<Self> {
+        self.nonces = self.nonces.with_journal(path)?;
+        Ok(self)
+    }
+
+    /// Publish a `BalanceChanged` event on `events` for every balance set
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Records a built block and marks the given transactions as included
+    pub fn record_block(&self, block: Block, transaction_hashes: &[String]) {
+        let block_id = block.header.block_id;
+        for hash in transaction_hashes {
+            if let Some(mut record) = self.transactions.get_mut(hash) {
+                record.status = TransactionStatus::Included;
+                record.block_id = Some(block_id);
+                let tx = &record.transaction.transaction;
+                self.nonces.commit(tx.sender, tx.nonce);
+            }
+        }
+        let timestamp = block.header.timestamp.timestamp().max(0) as u64;
+        self.blocks.insert(block_id, block);
+        if block_id % TRANSACTION_PRUNE_INTERVAL == 0 {
+            self.prune_transactions(block_id, timestamp);
+        }
+    }
+
+    /// Forgets transactions included more than `TRANSACTION_RETENTION_BLOCKS`
+    /// blocks before `height`, and dropped ones expired by `now`, which can
+    /// no longer be resubmitted. Pending transactions are always kept.
+    fn prune_transactions(&self, height: u64, now: u64) {
+        let before = self.transactions.len();
+        self.transactions.retain(|_, record| match record.status {
+            TransactionStatus::Pending => true,
+            TransactionStatus::Included => record
+                .block_id
+                .is_some_and(|block_id| block_id + TRANSACTION_RETENTION_BLOCKS > height),
+            TransactionStatus::Dropped => record.transaction.transaction.expiry > now,
+        });
+        debug!(height, pruned = before - self.transactions.len(), "Pruned transaction records");
+    }
+
+    /// Marks a pending transaction as dropped
+    pub fn drop_transaction(&self, hash: &str) {
+        if let Some(mut record) = self.transactions.get_mut(hash) {
+            record.status = TransactionStatus::Dropped;
+            let tx = &record.transaction.transaction;
+            self.nonces.release(&tx.sender, tx.nonce);
+        }
+    }
+
+    pub fn set_balance(&self, address: Address, balance: u64) {
+        self.balances.insert(address, balance);
+        self.events.publish(SequencerEvent::BalanceChanged {
+            address,
+            balance,
+            at: Utc::now(),
+        });
+    }
+
+    /// Next executable nonce for `address`, used when selecting from the mempool
+    pub fn next_nonce(&self, address: &Address) -> u64 {
+        self.nonces.next(address)
+    }
+
+    pub fn balance(&self, address: &Address) -> u64 {
+        self.balances.get(address).map(|b| *b).unwrap_or(0)
+    }
+
+    /// Height of the next block, one past the highest recorded
+    pub fn next_height(&self) -> u64 {
+        self.blocks.iter().map(|entry| *entry.key() + 1).max().unwrap_or(0)
+    }
+
+    /// Balances and committed nonces as of the next height, for a genesis
+    /// bundle
+    pub fn export_genesis(&self, environment: ExecutionEnvironment) -> GenesisBundle {
+        let mut bundle = GenesisBundle::new(environment, self.next_height());
+        bundle.balances = self.balances.iter().map(|entry| (*entry.key(), *entry.value())).collect();
+        bundle.nonces = self.nonces.committed().into_iter().collect();
+        bundle
+    }
+
+    /// Starts from the balances and nonces of a genesis bundle
+    pub fn apply_genesis(&self, bundle: &GenesisBundle) {
+        for (address, balance) in &bundle.balances {
+            self.balances.insert(*address, *balance);
+        }
+        for (address, next) in &bundle.nonces {
+            if *next > 0 {
+                self.nonces.commit(*address, next - 1);
+            }
+        }
+    }
+}
+
+/// Dispatches JSON-RPC requests to the sequencer
+#[derive(Clone)]
+pub struct RpcHandler {
+    state: Arc<RpcState>,
+    /// Accepted transactions are forwarded here for inclusion in blocks
+    submissions: mpsc::Sender<SignedTransaction>,
+    /// Time source for expiry checks
+    clock: SharedClock,
+    /// Firm kill switch driven by the `admin_*_kill_switch` methods
+    kill_switch: Option<Arc<KillSwitch>>,
+    /// Organization symbol permissions managed by the `admin_*_symbol_permission` methods
+    permissions: Option<Arc<PermissionRegistry>>,
+    /// Market wide drain mode driven by the `admin_*_drain_mode` methods
+    drain: Option<Arc<DrainMode>>,
+    /// Latency budget enforcement reported by `admin_load_shedding_status`
+    load_shedding: Option<Arc<LoadShedder>>,
+    /// Sequencer statistics served by `admin_stats`
+    stats: Option<StatsCollector>,
+    /// Market maker obligations managed by the `admin_*_mm_obligation` methods
+    obligations: Option<Arc<ObligationMonitor>>,
+    /// Reference prices, and the manual feed `admin_set_reference_price` writes to
+    reference_prices: Option<(Arc<ReferencePriceService>, Arc<ManualFeed>)>,
+    /// Market data subscriptions, whose conflation is served by
+    /// `admin_market_data_stats`, and the books `get_depth` snapshots
+    market_data: Option<Arc<MarketDataPublisher>>,
+    /// Candles and daily statistics served by `get_candles` and `get_daily_stats`
+    candles: Option<Arc<CandleStore>>,
+    /// Order lifecycle states served by `get_order`
+    orders: Option<Arc<OrderStore>>,
+    /// Operational notices published by `admin_broadcast_news` and listed by `get_news`
+    news: Option<Arc<NewsService>>,
+    /// Validator attestations delivered by `submit_attestation`
+    attestations: Option<Arc<AttestationRegistry>>,
+    /// Log level driven by the `admin_*_log_level` methods
+    logging: Option<Arc<LogHandle>>,
+    /// Protocol upgrade schedule served by `get_protocol_status`
+    protocol: Option<Arc<ProtocolSchedule>>,
+    /// End-of-day reports served and rebuilt by the `admin_*_reconciliation*` methods
+    reconciliation: Option<Arc<ReconciliationService>>,
+    /// Pushes reconciled amounts to an external system for `admin_settle`
+    settlement: Option<Arc<SettlementService>>,
+    /// Deposit and withdrawal attestations served by the `*_bridge_*` methods
+    bridge: Option<Arc<BridgeRelay>>,
+    /// Feeder prices taken by `submit_oracle_price`
+    oracle: Option<Arc<OracleAggregator>>,
+    /// Parameter proposals and votes taken by the `*_governance_*` methods
+    governance: Option<Arc<GovernanceService>>,
+    /// WASM pre-trade plugins listed by `get_pretrade_plugins`
+    plugins: Option<Arc<PluginHost>>,
+    /// Per-instrument parameters changed by `admin_schedule_instrument_override`
+    instruments: Option<Arc<InstrumentRegistry>>,
+    /// Fee tiers and firms' epoch volume served by `get_fee_tiers`
+    fees: Option<Arc<FeeEngine>>,
+    /// Sub-account positions reported by `get_sub_accounts`
+    sub_accounts: Option<Arc<SubAccountTracker>>,
+    /// Give-ups listed by `get_allocations`
+    allocations: Option<Arc<AllocationService>>,
+    /// Clock offset measurements reported by `get_clock_quality`
+    clock_monitor: Option<Arc<ClockMonitor>>,
+    /// Ingress delay published by `get_speed_bump`
+    speed_bump: Option<Arc<SpeedBumpState>>,
+    /// Test RØMER minted by `request_faucet`, on development and testnet
+    /// environments only
+    faucet: Option<Arc<Faucet>>,
+    /// Network the sequencer runs for, recorded in `admin_export_genesis`
+    /// bundles
+    environment: ExecutionEnvironment,
+    /// Network transactions must be signed for
+    chain_id: String,
+    /// API keys and rate limits of the `get_*` methods, managed by the
+    /// `admin_*_api_key` methods
+    api_keys: Option<Arc<ApiKeyRegistry>>,
+    /// Roles of the callers allowed `admin_*` methods. Without it they are
+    /// refused to everyone.
+    admin: Option<Arc<AdminAccess>>,
+}
+
+impl RpcHandler {
+    pub fn new(state: Arc<RpcState>, submissions: mpsc::Sender<SignedTransaction>) -> Self {
+        Self::with_clock(state, submissions, system_clock())
+    }
+
+    pub fn with_clock(
+        state: Arc<RpcState>,
+        submissions: mpsc::Sender<SignedTransaction>,
+        clock: SharedClock,
+    ) -> Self {
+        Self {
+            state,
+            submissions,
+            clock,
+            kill_switch: None,
+            permissions: None,
+            drain: None,
+            load_shedding: None,
+            stats: None,
+            obligations: None,
+            reference_prices: None,
+            market_data: None,
+            candles: None,
+            orders: None,
+            news: None,
+            attestations: None,
+            logging: None,
+            protocol: None,
+            reconciliation: None,
+            settlement: None,
+            bridge: None,
+            oracle: None,
+            governance: None,
+            plugins: None,
+            instruments: None,
+            fees: None,
+            sub_accounts: None,
+            allocations: None,
+            clock_monitor: None,
+            speed_bump: None,
+            faucet: None,
+            environment: ExecutionEnvironment::default(),
+            chain_id: DEFAULT_CHAIN_ID.to_string(),
+            api_keys: None,
+            admin: None,
+        }
+    }
+
+    pub fn with_kill_switch(mut self, kill_switch: Arc<KillSwitch>) -> Self {
+        self.kill_switch = Some(kill_switch);
+        self
+    }
+
+    pub fn with_permissions(mut self, permissions: Arc<PermissionRegistry>) -> Self {
+        self.permissions = Some(permissions);
+        self
+    }
+
+    pub fn with_drain_mode(mut self, drain: Arc<DrainMode>) -> Self {
+        self.drain = Some(drain);
+        self
+    }
+
+    pub fn with_load_shedding(mut self, load_shedding: Arc<LoadShedder>) -> Self {
+        self.load_shedding = Some(load_shedding);
+        self
+    }
+
+    pub fn with_stats(mut self, stats: StatsCollector) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    pub fn with_obligations(mut self, obligations: Arc<ObligationMonitor>) -> Self {
+        self.obligations = Some(obligations);
+        self
+    }
+
+    /// Serve reference prices from `service`, taking manual prices into `manual`
+    pub fn with_reference_prices(mut self, service: Arc<ReferencePriceService>, manual: Arc<ManualFeed>) -> Self {
+        self.reference_prices = Some((service, manual));
+        self
+    }
+
+    pub fn with_market_data(mut self, market_data: Arc<MarketDataPublisher>) -> Self {
+        self.market_data = Some(market_data);
+        self
+    }
+
+    pub fn with_candles(mut self, candles: Arc<CandleStore>) -> Self {
+        self.candles = Some(candles);
+        self
+    }
+
+    pub fn with_orders(mut self, orders: Arc<OrderStore>) -> Self {
+        self.orders = Some(orders);
+        self
+    }
+
+    pub fn with_news(mut self, news: Arc<NewsService>) -> Self {
+        self.news = Some(news);
+        self
+    }
+
+    pub fn with_attestations(mut self, attestations: Arc<AttestationRegistry>) -> Self {
+        self.attestations = Some(attestations);
+        self
+    }
+
+    pub fn with_logging(mut self, logging: Arc<LogHandle>) -> Self {
+        self.logging = Some(logging);
+        self
+    }
+
+    pub fn with_protocol(mut self, protocol: Arc<ProtocolSchedule>) -> Self {
+        self.protocol = Some(protocol);
+        self
+    }
+
+    pub fn with_reconciliation(mut self, reconciliation: Arc<ReconciliationService>) -> Self {
+        self.reconciliation = Some(reconciliation);
+        self
+    }
+
+    pub fn with_settlement(mut self, settlement: Arc<SettlementService>) -> Self {
+        self.settlement = Some(settlement);
+        self
+    }
+
+    pub fn with_bridge(mut self, bridge: Arc<BridgeRelay>) -> Self {
+        self.bridge = Some(bridge);
+        self
+    }
+
+    pub fn with_oracle(mut self, oracle: Arc<OracleAggregator>) -> Self {
+        self.oracle = Some(oracle);
+        self
+    }
+
+    pub fn with_governance(mut self, governance: Arc<GovernanceService>) -> Self {
+        self.governance = Some(governance);
+        self
+    }
+
+    pub fn with_plugins(mut self, plugins: Arc<PluginHost>) -> Self {
+        self.plugins = Some(plugins);
+        self
+    }
+
+    pub fn with_instruments(mut self, instruments: Arc<InstrumentRegistry>) -> Self {
+        self.instruments = Some(instruments);
+        self
+    }
+
+    pub fn with_fees(mut self, fees: Arc<FeeEngine>) -> Self {
+        self.fees = Some(fees);
+        self
+    }
+
+    pub fn with_sub_accounts(mut self, sub_accounts: Arc<SubAccountTracker>) -> Self {
+        self.sub_accounts = Some(sub_accounts);
+        self
+    }
+
+    pub fn with_allocations(mut self, allocations: Arc<AllocationService>) -> Self {
+        self.allocations = Some(allocations);
+        self
+    }
+
+    pub fn with_clock_monitor(mut self, clock_monitor: Arc<ClockMonitor>) -> Self {
+        self.clock_monitor = Some(clock_monitor);
+        self
+    }
+
+    pub fn with_speed_bump(mut self, speed_bump: Arc<SpeedBumpState>) -> Self {
+        self.speed_bump = Some(speed_bump);
+        self
+    }
+
+    pub fn with_faucet(mut self, faucet: Arc<Faucet>) -> Self {
+        self.faucet = Some(faucet);
+        self
+    }
+
+    pub fn with_environment(mut self, environment: ExecutionEnvironment) -> Self {
+        self.environment = environment;
+        self
+    }
+
+    pub fn with_chain_id(mut self, chain_id: impl Into<String>) -> Self {
+        self.chain_id = chain_id.into();
+        self
+    }
+
+    pub fn with_api_keys(mut self, api_keys: Arc<ApiKeyRegistry>) -> Self {
+        self.api_keys = Some(api_keys);
+        self
+    }
+
+    pub fn with_admin_access(mut self, admin: Arc<AdminAccess>) -> Self {
+        self.admin = Some(admin);
+        self
+    }
+
+    /// Handles a single request from an unidentified caller. Returns `None`
+    /// for notifications.
+    pub async fn handle(&self, request: RpcRequest) -> Option<RpcResponse> {
+        self.handle_from(request, &Caller::default()).await
+    }
+
+    /// Handles a single request from `caller`. Returns `None` for
+    /// notifications.
+    pub async fn handle_from(&self, request: RpcRequest, caller: &Caller) -> Option<RpcResponse> {
+        let id = request.id.clone();
+        let result = if request.jsonrpc != JSONRPC_VERSION {
+            Err(RpcError::InvalidRequest(format!(
+                "unsupported jsonrpc version {}",
+                request.jsonrpc
+            )))
+        } else if request.method.starts_with("admin_") {
+            self.dispatch_admin(&request.method, request.params, caller).await
+        } else {
+            match self.admit(&request.method, caller) {
+                Ok(()) => self.dispatch(&request.method, request.params).await,
+                Err(e) => Err(e),
+            }
+        };
+
+        let id = id?;
+        Some(match result {
+            Ok(value) => RpcResponse::success(id, value),
+            Err(error) => RpcResponse::failure(id, error),
+        })
+    }
+
+    /// Parses and handles a raw request body from `caller`, which may be a
+    /// single request or a batch. Returns the serialized response, if any.
+    pub async fn handle_body(&self, body: &[u8], caller: &Caller) -> Option<String> {
+        let value: Value = match serde_json::from_slice(body) {
+            Ok(value) => value,
+            Err(e) => {
+                let response = RpcResponse::failure(Value::Null, RpcError::Parse(e.to_string()));
+                return serde_json::to_string(&response).ok();
+            }
+        };
+
+        match value {
+            Value::Array(requests) if !requests.is_empty() => {
+                let mut responses = Vec::with_capacity(requests.len());
+                for request in requests {
+                    if let Some(response) = self.handle_value(request, caller).await {
+                        responses.push(response);
+                    }
+                }
+                if responses.is_empty() {
+                    None
+                } else {
+                    serde_json::to_string(&responses).ok()
+                }
+            }
+            value => {
+                let response = self.handle_value(value, caller).await?;
+                serde_json::to_string(&response).ok()
+            }
+        }
+    }
+
+    async fn handle_value(&self, value: Value, caller: &Caller) -> Option<RpcResponse> {
+        match serde_json::from_value::<RpcRequest>(value) {
+            Ok(request) => self.handle_from(request, caller).await,
+            Err(e) => Some(RpcResponse::failure(
+                Value::Null,
+                RpcError::InvalidRequest(e.to_string()),
+            )),
+        }
+    }
+
+    /// Checks the API key and rate limit of a public read. Every read of a
+    /// batch counts against the limit.
+    fn admit(&self, method: &str, caller: &Caller) -> Result<(), RpcError> {
+        match &self.api_keys {
+            Some(api_keys) if is_read_method(method) => api_keys.admit(caller).map_err(|e| match e {
+                ApiKeyError::RateLimited { .. } => RpcError::RateLimited(e.to_string()),
+                _ => RpcError::Unauthorized(e.to_string()),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Dispatches an admin method once the caller's role allows it, writing
+    /// the call and its outcome to the audit log either way
+    async fn dispatch_admin(&self, method: &str, params: Value, caller: &Caller) -> Result<Value, RpcError> {
+        let principal = match &self.admin {
+            Some(admin) => admin.authorize(method, caller),
+            None => Err(AdminAccessError::Disabled),
+        };
+        let (identity, role, result) = match principal {
+            Ok(principal) => {
+                let result = self.dispatch(method, params).await;
+                (principal.identity, Some(principal.role), result)
+            }
+            Err(e) => {
+                let identity = caller.remote.map_or_else(|| "unknown".to_string(), |remote| remote.to_string());
+                warn!(method, %identity, error = %e, "Admin call denied");
+                (identity, None, Err(RpcError::Unauthorized(e.to_string())))
+            }
+        };
+        let outcome = match &result {
+            Ok(_) => "ok".to_string(),
+            Err(RpcError::Unauthorized(reason)) => format!("denied: {}", reason),
+            Err(e) => e.to_string(),
+        };
+        self.state.events.publish(SequencerEvent::AdminAction {
+            method: method.to_string(),
+            identity,
+            role: role.map(|role| role.to_string()),
+            outcome,
+            at: self.clock.now(),
+        });
+        result
+    }
+
+    async fn dispatch(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        debug!(method, "Handling RPC request");
+        match method {
+            "submit_transaction" => self.submit_transaction(parse(params)?).await,
+            "get_block" => self.get_block(parse(params)?),
+            "get_transaction" => self.get_transaction(parse(params)?),
+            "get_receipt" => self.get_receipt(parse(params)?),
+            "get_balance" => self.get_balance(parse(params)?),
+            "request_faucet" => self.request_faucet(parse(params)?).await,
+            "get_faucet" => to_value(&self.faucet()?.status()),
+            "simulate" => self.simulate(parse(params)?),
+            "register_organization" => self.register_organization(parse(params)?),
+            "get_organization" => self.get_organization(parse(params)?),
+            "update_organization" => self.update_organization(parse(params)?),
+            "submit_attestation" => self.submit_attestation(parse(params)?),
+            "get_attestation_status" => self.attestation_status(parse(params)?),
+            "get_protocol_status" => to_value(&self.protocol()?.status(self.state.next_height(), SUPPORTED_PROTOCOL_VERSION)),
+            "get_clock_quality" => self.clock_quality(),
+            "admin_engage_kill_switch" => self.engage_kill_switch(parse(params)?),
+            "admin_release_kill_switch" => self.release_kill_switch(parse(params)?),
+            "admin_kill_switch_status" => to_value(&self.kill_switch()?.status()),
+            "admin_enter_drain_mode" => self.enter_drain_mode(parse(params)?),
+            "admin_exit_drain_mode" => Ok(json!({ "released": self.drain()?.exit("admin") })),
+            "admin_drain_status" => to_value(&self.drain()?.status()),
+            "admin_load_shedding_status" => to_value(&self.load_shedding()?.status()),
+            "admin_stats" => to_value(&self.stats()?.get_stats()),
+            "admin_export_genesis" => self.export_genesis(),
+            "admin_create_api_key" => self.create_api_key(parse(params)?).await,
+            "admin_revoke_api_key" => self.revoke_api_key(parse(params)?).await,
+            "admin_api_keys" => to_value(&self.api_keys()?.keys()),
+            "admin_set_mm_obligation" => self.set_obligation(parse(params)?),
+            "admin_remove_mm_obligation" => self.remove_obligation(parse(params)?),
+            "admin_mm_obligation_report" => to_value(&self.obligations()?.last_report()),
+            "get_reference_price" => self.get_reference_price(parse(params)?),
+            "get_candles" => self.get_candles(parse(params)?),
+            "get_daily_stats" => self.get_daily_stats(parse(params)?),
+            "get_depth" => self.get_depth(parse(params)?),
+            "get_order" => self.get_order(parse(params)?),
+            "get_news" => self.get_news(parse(params)?),
+            "admin_broadcast_news" => self.broadcast_news(parse(params)?).await,
+            "admin_set_reference_price" => self.set_reference_price(parse(params)?),
+            "admin_log_level" => Ok(json!({ "level": self.logging()?.level() })),
+            "admin_set_log_level" => self.set_log_level(parse(params)?),
+            "admin_set_log_directive" => self.set_log_directive(parse(params)?),
+            "admin_reset_log_level" => self.reset_log_level(),
+            "admin_market_data_stats" => to_value(&self.market_data()?.stats()),
+            "admin_reconciliation_report" => self.reconciliation_report(parse(params)?),
+            "admin_run_reconciliation" => self.run_reconciliation(parse(params)?).await,
+            "admin_settle" => self.settle(parse(params)?).await,
+            "admin_settlement_receipts" => self.settlement_receipts(parse(params)?),
+            "get_allocations" => self.get_allocations(parse(params)?),
+            "submit_oracle_price" => self.submit_oracle_price(parse(params)?),
+            "get_oracle_prices" => to_value(&self.oracle()?.latest()),
+            "submit_governance_proposal" => self.submit_governance_proposal(parse(params)?),
+            "submit_governance_vote" => self.submit_governance_vote(parse(params)?),
+            "get_governance_proposals" => to_value(&self.governance()?.proposals()),
+            "get_governance_parameters" => to_value(&self.governance()?.parameters()),
+            "admin_schedule_instrument_override" => self.schedule_instrument_override(parse(params)?),
+            "get_instrument" => self.get_instrument(parse(params)?),
+            "get_fee_tiers" => self.fee_tiers(),
+            // Markets without a speed bump publish a zero delay
+            "get_speed_bump" => to_value(&self.speed_bump.as_deref().map(SpeedBumpState::status).unwrap_or_default()),
+            "get_pretrade_plugins" => self.pretrade_plugins(),
+            "get_bridge_deposits" => to_value(&self.bridge()?.deposits()),
+            "get_bridge_mint" => self.get_bridge_mint(parse(params)?),
+            "get_bridge_withdrawals" => to_value(&self.bridge()?.withdrawals()),
+            "submit_bridge_attestation" => self.submit_bridge_attestation(parse(params)?),
+            "submit_bridge_withdrawal" => self.submit_bridge_withdrawal(parse(params)?).await,
+            "admin_grant_symbol_permission" => self.grant_symbol_permission(parse(params)?),
+            "admin_revoke_symbol_permission" => self.revoke_symbol_permission(parse(params)?),
+            "admin_symbol_permissions" => self.symbol_permissions(parse(params)?),
+            "admin_set_sub_account" => self.set_sub_account(parse(params)?),
+            "admin_remove_sub_account" => self.remove_sub_account(parse(params)?),
+            "admin_set_logon_credential" => self.set_logon_credential(parse(params)?),
+            "admin_remove_logon_credential" => self.remove_logon_credential(parse(params)?),
+            "get_sub_accounts" => self.get_sub_accounts(parse(params)?),
+            other => Err(RpcError::MethodNotFound(other.to_string())),
+        }
+    }
+
+    async fn submit_transaction(&self, params: SubmitParams) -> Result<Value, RpcError> {
+        let transaction = params.transaction;
+        transaction
+            .verify(&self.chain_id, self.clock.unix_secs())
+            .map_err(|e| RpcError::Rejected(RejectReason::from(&e), e.to_string()))?;
+
+        let hash = self.record(&transaction)?;
+        let (sender, nonce) = (transaction.transaction.sender, transaction.transaction.nonce);
+        if let Err(e) = self.state.nonces.admit(sender, nonce) {
+            self.state.transactions.remove(&hash);
+            return Err(RpcError::Rejected(RejectReason::from(&e), e.to_string()));
+        }
+
+        self.forward(transaction, &hash).await?;
+        Ok(json!({ "hash": hash }))
+    }
+
+    /// Records a transaction as pending, returning its hash, unless it was
+    /// seen before. Recorded before it is forwarded, so a block including it
+    /// right away finds the record to commit its nonce.
+    fn record(&self, transaction: &SignedTransaction) -> Result<String, RpcError> {
+        let digest = transaction
+            .digest()
+            .map_err(|e| RpcError::Rejected(RejectReason::from(&e), e.to_string()))?;
+        let hash = hex(&digest);
+        match self.state.transactions.entry(hash.clone()) {
+            Entry::Occupied(_) => Err(RpcError::Rejected(RejectReason::DuplicateTransaction, hash)),
+            Entry::Vacant(entry) => {
+                entry.insert(TransactionRecord {
+                    hash: hash.clone(),
+                    transaction: transaction.clone(),
+                    status: TransactionStatus::Pending,
+                    block_id: None,
+                });
+                Ok(hash)
+            }
+        }
+    }
+
+    /// Hands a recorded transaction whose nonce was admitted to the block
+    /// pipeline. The record and nonce are released if the pipeline is gone.
+    async fn forward(&self, transaction: SignedTransaction, hash: &str) -> Result<(), RpcError> {
+        let (sender, nonce) = (transaction.transaction.sender, transaction.transaction.nonce);
+        if self.submissions.send(transaction).await.is_err() {
+            self.state.transactions.remove(hash);
+            self.state.nonces.release(&sender, nonce);
+            return Err(RpcError::Internal("transaction pipeline unavailable".into()));
+        }
+
+        info!(hash = %hash, sender = %sender, "Accepted transaction");
+        Ok(())
+    }
+
+    fn get_block(&self, params: BlockParams) -> Result<Value, RpcError> {
+        let block = self
+            .state
+            .blocks
+            .get(&params.block_id)
+            .ok_or_else(|| RpcError::NotFound(format!("block {}", params.block_id)))?;
+        to_value(&*block)
+    }
+
+    fn get_transaction(&self, params: TransactionParams) -> Result<Value, RpcError> {
+        let record = self
+            .state
+            .transactions
+            .get(&params.hash)
+            .ok_or_else(|| RpcError::NotFound(format!("transaction {}", params.hash)))?;
+        to_value(&*record)
+    }
+
+    /// Receipt of an included transaction with the proof it is under the
+    /// block header's transactions root
+    fn get_receipt(&self, params: TransactionParams) -> Result<Value, RpcError> {
+        let not_found = || RpcError::NotFound(format!("receipt of {}", params.hash));
+        let block_id = self
+            .state
+            .transactions
+            .get(&params.hash)
+            .and_then(|record| record.block_id)
+            .ok_or_else(not_found)?;
+        let block = self.state.blocks.get(&block_id).ok_or_else(not_found)?;
+        let index = block
+            .transactions
+            .iter()
+            .position(|tx| tx.digest().is_ok_and(|digest| hex(&digest) == params.hash))
+            .ok_or_else(not_found)?;
+        let proven = block.receipt_with_proof(index).ok_or_else(not_found)?;
+        Ok(json!({
+            "transaction_hash": params.hash,
+            "block_hash": block.block_hash,
+            "header": block.header,
+            "receipt": proven,
+        }))
+    }
+
+    fn get_balance(&self, params: BalanceParams) -> Result<Value, RpcError> {
+        Ok(json!({
+            "address": params.address,
+            "balance": self.state.balance(&params.address),
+            "nonce": self.state.nonces.next(&params.address),
+        }))
+    }
+
+    fn faucet(&self) -> Result<&Faucet, RpcError> {
+        self.faucet
+            .as_deref()
+            .ok_or_else(|| RpcError::Rejected(RejectReason::PermissionDenied, "no faucet on this network".into()))
+    }
+
+    /// Grants test RØMER by submitting a mint the faucet signs, which the
+    /// address receives once the mint executes
+    async fn request_faucet(&self, params: FaucetParams) -> Result<Value, RpcError> {
+        let faucet = self.faucet()?;
+        let amount = faucet
+            .dispense(params.address, params.amount)
+            .map_err(|e| RpcError::Rejected(e.reject_reason(), e.to_string()))?;
+        let sender = faucet.sender();
+        let submitted = match self.state.nonces.reserve(sender) {
+            Ok(nonce) => {
+                let recorded = faucet
+                    .mint(&self.chain_id, params.address, amount, nonce)
+                    .map_err(|e| RpcError::Internal(e.to_string()))
+                    .and_then(|mint| Ok((self.record(&mint)?, mint)));
+                match recorded {
+                    Ok((hash, mint)) => self.forward(mint, &hash).await.map(|_| hash),
+                    Err(e) => {
+                        self.state.nonces.release(&sender, nonce);
+                        Err(e)
+                    }
+                }
+            }
+            Err(e) => Err(RpcError::Rejected(RejectReason::Throttled, format!("faucet is busy: {}", e))),
+        };
+        match submitted {
+            Ok(hash) => Ok(json!({
+                "address": params.address,
+                "amount": amount,
+                "hash": hash,
+            })),
+            Err(e) => {
+                faucet.refund(params.address, amount);
+                Err(e)
+            }
+        }
+    }
+
+    /// Balances, nonces and organizations as of the next height, sealed into
+    /// a genesis bundle another network can boot from
+    fn export_genesis(&self) -> Result<Value, RpcError> {
+        let mut bundle = self.state.export_genesis(self.environment);
+        if let Some(permissions) = &self.permissions {
+            bundle.organizations = permissions.organizations();
+        }
+        let bundle = bundle.seal().map_err(|e| RpcError::Internal(e.to_string()))?;
+        info!(height = bundle.height, digest = %bundle.digest, "Exported genesis bundle");
+        to_value(&bundle)
+    }
+
+    fn simulate(&self, params: SubmitParams) -> Result<Value, RpcError> {
+        let transaction = params.transaction;
+        let expected = self.state.nonces.next(&transaction.transaction.sender);
+        let verified = transaction
+            .verify(&self.chain_id, self.clock.unix_secs())
+            .map_err(|e| e.to_string())
+            .and_then(|_| check_nonce(expected, transaction.transaction.nonce, 0).map_err(|e| e.to_string()));
+        let result = match verified {
+            Err(e) => SimulationResult {
+                accepted: false,
+                error: Some(e),
+                balance_after: None,
+            },
+            Ok(()) => match &transaction.transaction.payload {
+                TransactionPayload::Transfer { amount, .. } => {
+                    let balance = self.state.balance(&transaction.transaction.sender);
+                    match balance.checked_sub(*amount) {
+                        Some(after) => SimulationResult {
+                            accepted: true,
+                            error: None,
+                            balance_after: Some(after),
+                        },
+                        None => SimulationResult {
+                            accepted: false,
+                            error: Some(format!("insufficient balance: {} < {}", balance, amount)),
+                            balance_after: Some(balance),
+                        },
+                    }
+                }
+                _ => SimulationResult {
+                    accepted: true,
+                    error: None,
+                    balance_after: None,
+                },
+            },
+        };
+        to_value(&result)
+    }
+
+    fn attestations(&self) -> Result<&AttestationRegistry, RpcError> {
+        self.attestations
+            .as_deref()
+            .ok_or_else(|| RpcError::Internal("attestations not configured".into()))
+    }
+
+    fn submit_attestation(&self, params: AttestationParams) -> Result<Value, RpcError> {
+        let status = self
+            .attestations()?
+            .submit(params.attestation)
+            .map_err(|e| {
+                let reason = match e {
+                    AttestationRejection::Unregistered(_) => RejectReason::PermissionDenied,
+                    AttestationRejection::Invalid(_) => RejectReason::InvalidSignature,
+                    AttestationRejection::Outdated { .. } => RejectReason::Expired,
+                };
+                RpcError::Rejected(reason, e.to_string())
+            })?;
+        to_value(&status)
+    }
+
+    fn attestation_status(&self, params: Option<AttestationStatusParams>) -> Result<Value, RpcError> {
+        let attestations = self.attestations()?;
+        match params.unwrap_or_default().validator {
+            Some(validator) => to_value(&attestations.status(&validator)),
+            None => to_value(&attestations.statuses()),
+        }
+    }
+
+    fn kill_switch(&self) -> Result<&KillSwitch, RpcError> {
+        self.kill_switch
+            .as_deref()
+            .ok_or_else(|| RpcError::Internal("kill switch not configured".into()))
+    }
+
+    fn engage_kill_switch(&self, params: KillSwitchParams) -> Result<Value, RpcError> {
+        let reason = params.reason.as_deref().unwrap_or("engaged by administrator");
+        let record = self.kill_switch()?.engage(&params.firm, reason, "admin");
+        to_value(&record)
+    }
+
+    fn release_kill_switch(&self, params: KillSwitchParams) -> Result<Value, RpcError> {
+        let released = self.kill_switch()?.release(&params.firm, "admin");
+        Ok(json!({ "firm": params.firm, "released": released }))
+    }
+
+    fn obligations(&self) -> Result<&ObligationMonitor, RpcError> {
+        self.obligations
+            .as_deref()
+            .ok_or_else(|| RpcError::Internal("market maker obligations not configured".into()))
+    }
+
+    fn set_obligation(&self, params: ObligationParams) -> Result<Value, RpcError> {
+        let obligation = params
+            .obligation
+            .ok_or_else(|| RpcError::InvalidParams("missing field `obligation`".into()))?;
+        self.obligations()?
+            .set_obligation(&params.sender_comp_id, &params.symbol, obligation);
+        Ok(json!({ "sender_comp_id": params.sender_comp_id, "symbol": params.symbol, "obligation": obligation }))
+    }
+
+    fn remove_obligation(&self, params: ObligationParams) -> Result<Value, RpcError> {
+        let removed = self
+            .obligations()?
+            .remove_obligation(&params.sender_comp_id, &params.symbol);
+        Ok(json!({ "removed": removed }))
+    }
+
+    fn reference_prices(&self) -> Result<&(Arc<ReferencePriceService>, Arc<ManualFeed>), RpcError> {
+        self.reference_prices
+            .as_ref()
+            .ok_or_else(|| RpcError::Internal("reference prices not configured".into()))
+    }
+
+    fn get_reference_price(&self, params: ReferencePriceParams) -> Result<Value, RpcError> {
+        match self.reference_prices()?.0.price(&params.symbol) {
+            Ok(price) => to_value(&price),
+            Err(e @ ReferencePriceError::NoPrice(_)) => Err(RpcError::NotFound(e.to_string())),
+            Err(e) => Err(RpcError::Rejected(RejectReason::NoReferencePrice, e.to_string())),
+        }
+    }
+
+    fn set_reference_price(&self, params: ReferencePriceParams) -> Result<Value, RpcError> {
+        let price = params
+            .price
+            .filter(|price| price.is_finite() && *price > 0.0)
+            .ok_or_else(|| RpcError::InvalidParams("`price` must be a positive number".into()))?;
+        let (_, manual) = self.reference_prices()?;
+        manual.set(&params.symbol, price, self.clock.now());
+        info!(symbol = %params.symbol, price, "Manual reference price set");
+        Ok(json!({ "symbol": params.symbol, "price": price }))
+    }
+
+    fn candles(&self) -> Result<&CandleStore, RpcError> {
+        self.candles
+            .as_deref()
+            .ok_or_else(|| RpcError::Internal("candles not configured".into()))
+    }
+
+    fn get_candles(&self, params: CandleParams) -> Result<Value, RpcError> {
+        let limit = params.limit.unwrap_or(100).min(DEFAULT_RETENTION);
+        to_value(&self.candles()?.candles(&params.symbol, params.interval, params.since, limit))
+    }
+
+    fn get_daily_stats(&self, params: DailyStatsParams) -> Result<Value, RpcError> {
+        to_value(&self.candles()?.daily(&params.symbol, params.days.unwrap_or(30)))
+    }
+
+    fn orders(&self) -> Result<&OrderStore, RpcError> {
+        self.orders
+            .as_deref()
+            .ok_or_else(|| RpcError::Internal("orders not configured".into()))
+    }
+
+    fn get_order(&self, params: OrderLookupParams) -> Result<Value, RpcError> {
+        let orders = self.orders()?;
+        let order = match (params.order_id, params.sender_comp_id, params.cl_ord_id) {
+            (Some(order_id), _, _) => orders
+                .by_order_id(&order_id)
+                .ok_or_else(|| RpcError::NotFound(format!("order {}", order_id)))?,
+            (None, Some(sender_comp_id), Some(cl_ord_id)) => orders
+                .by_cl_ord_id(&sender_comp_id, &cl_ord_id)
+                .ok_or_else(|| RpcError::NotFound(format!("order {} of {}", cl_ord_id, sender_comp_id)))?,
+            _ => return Err(RpcError::InvalidParams("order_id or sender_comp_id and cl_ord_id required".into())),
+        };
+        to_value(&order)
+    }
+
+    fn news(&self) -> Result<&NewsService, RpcError> {
+        self.news
+            .as_deref()
+            .ok_or_else(|| RpcError::Internal("news not configured".into()))
+    }
+
+    fn get_news(&self, params: NewsParams) -> Result<Value, RpcError> {
+        to_value(&self.news()?.recent(params.limit.unwrap_or(20)))
+    }
+
+    async fn broadcast_news(&self, params: NewsParams) -> Result<Value, RpcError> {
+        let headline = params
+            .headline
+            .ok_or_else(|| RpcError::InvalidParams("`headline` is required".into()))?;
+        let notice = self
+            .news()?
+            .publish(headline, params.text, params.recipients, "admin")
+            .await
+            .map_err(|e| match e {
+                NewsError::EmptyHeadline => RpcError::InvalidParams(e.to_string()),
+                NewsError::Storage(_) => RpcError::Internal(e.to_string()),
+            })?;
+        to_value(&notice)
+    }
+
+    fn api_keys(&self) -> Result<&ApiKeyRegistry, RpcError> {
+        self.api_keys
+            .as_deref()
+            .ok_or_else(|| RpcError::Internal("API keys not configured".into()))
+    }
+
+    /// Creates a key, returning its secret; it is not shown again
+    async fn create_api_key(&self, params: CreateApiKeyParams) -> Result<Value, RpcError> {
+        if params.label.trim().is_empty() {
+            return Err(RpcError::InvalidParams("`label` must not be empty".into()));
+        }
+        let (key, secret) = self
+            .api_keys()?
+            .create(params.label, params.rate_per_sec, params.burst, "admin")
+            .await
+            .map_err(|e| RpcError::Internal(e.to_string()))?;
+        Ok(json!({ "key": key, "secret": secret }))
+    }
+
+    async fn revoke_api_key(&self, params: ApiKeyIdParams) -> Result<Value, RpcError> {
+        let key = self.api_keys()?.revoke(&params.id).await.map_err(|e| match e {
+            ApiKeyError::NotFound(_) => RpcError::NotFound(e.to_string()),
+            ApiKeyError::AlreadyRevoked(_) => RpcError::InvalidParams(e.to_string()),
+            _ => RpcError::Internal(e.to_string()),
+        })?;
+        to_value(&key)
+    }
+
+    fn protocol(&self) -> Result<&ProtocolSchedule, RpcError> {
+        self.protocol
+            .as_deref()
+            .ok_or_else(|| RpcError::Internal("protocol schedule not configured".into()))
+    }
+
+    fn logging(&self) -> Result<&LogHandle, RpcError> {
+        self.logging
+            .as_deref()
+            .ok_or_else(|| RpcError::Internal("logging not configured".into()))
+    }
+
+    fn set_log_level(&self, params: LogLevelParams) -> Result<Value, RpcError> {
+        self.logging()?
+            .set_level(&params.level)
+            .map_err(|e| RpcError::InvalidParams(e.to_string()))?;
+        let level = self.logging()?.level();
+        info!(%level, "Log level changed");
+        Ok(json!({ "level": level }))
+    }
+
+    /// Changes the level of one module, keeping every other directive
+    fn set_log_directive(&self, params: LogDirectiveParams) -> Result<Value, RpcError> {
+        let level = self
+            .logging()?
+            .set_directive(&params.directive)
+            .map_err(|e| RpcError::InvalidParams(e.to_string()))?;
+        info!(directive = %params.directive, %level, "Log level changed");
+        Ok(json!({ "level": level }))
+    }
+
+    fn reset_log_level(&self) -> Result<Value, RpcError> {
+        let level = self
+            .logging()?
+            .reset()
+            .map_err(|e| RpcError::Internal(e.to_string()))?;
+        info!(%level, "Log level reset");
+        Ok(json!({ "level": level }))
+    }
+
+    fn market_data(&self) -> Result<&MarketDataPublisher, RpcError> {
+        self.market_data
+            .as_deref()
+            .ok_or_else(|| RpcError::Internal("market data not configured".into()))
+    }
+
+    fn reconciliation(&self) -> Result<&Arc<ReconciliationService>, RpcError> {
+        self.reconciliation
+            .as_ref()
+            .ok_or_else(|| RpcError::Internal("reconciliation not configured".into()))
+    }
+
+    fn reconciliation_report(&self, params: ReconciliationParams) -> Result<Value, RpcError> {
+        match self.reconciliation()?.reports(params.day, params.firm.as_deref()) {
+            Some(reports) => to_value(&reports),
+            None => Err(RpcError::NotFound("no reconciliation reports built for that day".into())),
+        }
+    }
+
+    /// Rebuilds a day's reports from the event log, e.g. after a correction
+    async fn run_reconciliation(&self, params: ReconciliationParams) -> Result<Value, RpcError> {
+        let day = params
+            .day
+            .ok_or_else(|| RpcError::InvalidParams("`day` is required".into()))?;
+        let service = self.reconciliation()?.clone();
+        let reports = tokio::task::spawn_blocking(move || service.run_day(day))
+            .await
+            .map_err(|e| RpcError::Internal(e.to_string()))?
+            .map_err(|e| RpcError::Internal(e.to_string()))?;
+        info!(%day, firms = reports.len(), "Reconciliation rebuilt");
+        let reports: Vec<_> = reports
+            .into_iter()
+            .filter(|report| params.firm.as_deref().map_or(true, |firm| report.firm == firm))
+            .collect();
+        to_value(&reports)
+    }
+
+    fn get_allocations(&self, params: AllocationParams) -> Result<Value, RpcError> {
+        let allocations = self
+            .allocations
+            .as_deref()
+            .ok_or_else(|| RpcError::Internal("allocations not configured".into()))?;
+        to_value(&allocations.allocations(params.firm.as_deref()))
+    }
+
+    fn settlement(&self) -> Result<&SettlementService, RpcError> {
+        self.settlement
+            .as_deref()
+            .ok_or_else(|| RpcError::Internal("settlement not configured".into()))
+    }
+
+    /// Settles the reconciled amounts of a day, the latest reconciled
+    /// without. Instructions settled before are not submitted again.
+    async fn settle(&self, params: SettleParams) -> Result<Value, RpcError> {
+        let settlement = self.settlement()?;
+        let reports = self
+            .reconciliation()?
+            .reports(params.day, None)
+            .ok_or_else(|| RpcError::NotFound("no reconciliation reports built for that day".into()))?;
+        let outcome = settlement.settle(&reports).await;
+        info!(
+            adapter = settlement.adapter(),
+            submitted = outcome.submitted.len(),
+            failed = outcome.failed.len(),
+            "Settlement run"
+        );
+        to_value(&outcome)
+    }
+
+    fn settlement_receipts(&self, params: SettleParams) -> Result<Value, RpcError> {
+        to_value(&self.settlement()?.receipts(params.day))
+    }
+
+    fn oracle(&self) -> Result<&OracleAggregator, RpcError> {
+        self.oracle
+            .as_deref()
+            .ok_or_else(|| RpcError::Internal("oracle not configured".into()))
+    }
+
+    fn submit_oracle_price(&self, params: SignedPriceSubmission) -> Result<Value, RpcError> {
+        self.oracle()?.submit(params).map_err(|e| match e {
+            OracleSubmitError::UnknownFeeder(_) => RpcError::Rejected(RejectReason::PermissionDenied, e.to_string()),
+            other => RpcError::InvalidParams(other.to_string()),
+        })?;
+        Ok(json!({ "accepted": true }))
+    }
+
+    fn governance(&self) -> Result<&GovernanceService, RpcError> {
+        self.governance
+            .as_deref()
+            .ok_or_else(|| RpcError::Internal("governance not configured".into()))
+    }
+
+    fn submit_governance_proposal(&self, params: Signed<Proposal>) -> Result<Value, RpcError> {
+        let id = self
+            .governance()?
+            .propose(params, self.state.next_height())
+            .map_err(governance_error)?;
+        Ok(json!({ "proposal_id": id }))
+    }
+
+    fn submit_governance_vote(&self, params: Signed<Vote>) -> Result<Value, RpcError> {
+        let state = self
+            .governance()?
+            .vote(params, self.state.next_height())
+            .map_err(governance_error)?;
+        to_value(&state)
+    }
+
+    fn instruments(&self) -> Result<&InstrumentRegistry, RpcError> {
+        self.instruments
+            .as_deref()
+            .ok_or_else(|| RpcError::Internal("instrument registry not configured".into()))
+    }
+
+    fn schedule_instrument_override(&self, params: InstrumentOverrideParams) -> Result<Value, RpcError> {
+        let height = self.state.next_height();
+        let scheduled = self
+            .instruments()?
+            .schedule(
+                &params.symbol,
+                params.overrides,
+                params.activation_height.unwrap_or(height),
+                "admin",
+                height,
+            )
+            .map_err(|e| match e {
+                OverrideError::Io(_) => RpcError::Internal(e.to_string()),
+                other => RpcError::InvalidParams(other.to_string()),
+            })?;
+        to_value(&scheduled)
+    }
+
+    fn get_instrument(&self, params: InstrumentParams) -> Result<Value, RpcError> {
+        let instruments = self.instruments()?;
+        let height = params.height.unwrap_or_else(|| self.state.next_height());
+        Ok(json!({
+            "symbol": params.symbol,
+            "height": height,
+            "parameters": instruments.parameters(&params.symbol, height),
+            "overrides": instruments.overrides(Some(&params.symbol)),
+        }))
+    }
+
+    fn clock_quality(&self) -> Result<Value, RpcError> {
+        let monitor = self
+            .clock_monitor
+            .as_deref()
+            .ok_or_else(|| RpcError::Internal("clock monitoring not configured".into()))?;
+        let (confidence, error) = match monitor.check() {
+            Ok(confidence) => (Some(confidence), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        Ok(json!({
+            "producing_blocks": error.is_none(),
+            "confidence": confidence,
+            "error": error,
+            "samples": monitor.samples(),
+        }))
+    }
+
+    fn fee_tiers(&self) -> Result<Value, RpcError> {
+        let fees = self
+            .fees
+            .as_deref()
+            .ok_or_else(|| RpcError::Internal("fee tiers not configured".into()))?;
+        Ok(json!({
+            "tiers": fees.tiers(),
+            "firms": fees.firms(),
+        }))
+    }
+
+    fn pretrade_plugins(&self) -> Result<Value, RpcError> {
+        let plugins = self
+            .plugins
+            .as_deref()
+            .ok_or_else(|| RpcError::Internal("pre-trade plugins not configured".into()))?;
+        Ok(json!({
+            "active": plugins.active(self.state.next_height()),
+            "registered": plugins.plugins(),
+        }))
+    }
+
+    fn bridge(&self) -> Result<&BridgeRelay, RpcError> {
+        self.bridge
+            .as_deref()
+            .ok_or_else(|| RpcError::Internal("bridge not configured".into()))
+    }
+
+    fn get_bridge_mint(&self, params: BridgeDepositParams) -> Result<Value, RpcError> {
+        let call = self
+            .bridge()?
+            .mint_call(&params.source_chain, &params.deposit_id)
+            .map_err(bridge_error)?;
+        to_value(&call)
+    }
+
+    fn submit_bridge_attestation(&self, params: BridgeAttestationParams) -> Result<Value, RpcError> {
+        let status = self
+            .bridge()?
+            .attest_deposit(&params.source_chain, &params.deposit_id, params.signature)
+            .map_err(bridge_error)?;
+        to_value(&status)
+    }
+
+    async fn submit_bridge_withdrawal(&self, params: BridgeWithdrawalParams) -> Result<Value, RpcError> {
+        let status = self
+            .bridge()?
+            .attest_withdrawal(params.withdrawal, params.signature)
+            .await
+            .map_err(bridge_error)?;
+        to_value(&status)
+    }
+
+    fn get_depth(&self, params: DepthParams) -> Result<Value, RpcError> {
+        match self.market_data()?.depth(&params.symbol, params.depth) {
+            Some(snapshot) => to_value(&snapshot),
+            None => Err(RpcError::NotFound(format!("no book for {}", params.symbol))),
+        }
+    }
+
+    fn stats(&self) -> Result<&StatsCollector, RpcError> {
+        self.stats
+            .as_ref()
+            .ok_or_else(|| RpcError::Internal("stats not configured".into()))
+    }
+
+    fn drain(&self) -> Result<&DrainMode, RpcError> {
+        self.drain
+            .as_deref()
+            .ok_or_else(|| RpcError::Internal("drain mode not configured".into()))
+    }
+
+    fn load_shedding(&self) -> Result<&LoadShedder, RpcError> {
+        self.load_shedding
+            .as_deref()
+            .ok_or_else(|| RpcError::Internal("load shedding not configured".into()))
+    }
+
+    fn enter_drain_mode(&self, params: Option<DrainParams>) -> Result<Value, RpcError> {
+        let params = params.unwrap_or_default();
+        let reason = params.reason.as_deref().unwrap_or("maintenance");
+        to_value(&self.drain()?.enter(reason, "admin"))
+    }
+
+    fn permissions(&self) -> Result<&PermissionRegistry, RpcError> {
+        self.permissions
+            .as_deref()
+            .ok_or_else(|| RpcError::Internal("symbol permissions not configured".into()))
+    }
+
+    fn grant_symbol_permission(&self, params: SymbolPermissionParams) -> Result<Value, RpcError> {
+        let permission = params
+            .permission
+            .ok_or_else(|| RpcError::InvalidParams("missing field `permission`".into()))?;
+        let organization = self
+            .permissions()?
+            .grant(&params.org_id, &params.symbol, permission)
+            .map_err(permission_error)?;
+        to_value(&organization.symbol_permissions)
+    }
+
+    fn revoke_symbol_permission(&self, params: SymbolPermissionParams) -> Result<Value, RpcError> {
+        let organization = self
+            .permissions()?
+            .revoke(&params.org_id, &params.symbol)
+            .map_err(permission_error)?;
+        to_value(&organization.symbol_permissions)
+    }
+
+    /// Registers an organization signed with its own key, e.g. by the
+    /// client onboarding wizard
+    fn register_organization(&self, registration: OrganizationRegistration) -> Result<Value, RpcError> {
+        registration
+            .verify()
+            .map_err(|e| RpcError::InvalidParams(e.to_string()))?;
+        let organization = registration.into_organization(uuid::Uuid::new_v4().to_string());
+        organization
+            .validate()
+            .map_err(|e| RpcError::InvalidParams(e.to_string()))?;
+        let organization = self
+            .permissions()?
+            .create(organization)
+            .map_err(permission_error)?;
+        to_value(&organization)
+    }
+
+    fn get_organization(&self, params: OrganizationLookupParams) -> Result<Value, RpcError> {
+        let permissions = self.permissions()?;
+        let organization = match (params.org_id, params.sender_comp_id) {
+            (Some(org_id), _) => permissions
+                .organization(&org_id)
+                .ok_or_else(|| RpcError::NotFound(format!("organization {}", org_id)))?,
+            (None, Some(sender_comp_id)) => permissions
+                .organization_for_sender(&sender_comp_id)
+                .ok_or_else(|| RpcError::NotFound(format!("organization for {}", sender_comp_id)))?,
+            (None, None) => {
+                return Err(RpcError::InvalidParams("org_id or sender_comp_id required".into()))
+            }
+        };
+        to_value(&redacted(organization))
+    }
+
+    /// Applies an update signed by the organization's current key
+    fn update_organization(&self, update: OrganizationUpdate) -> Result<Value, RpcError> {
+        let permissions = self.permissions()?;
+        let current = permissions
+            .organization(&update.org_id)
+            .ok_or_else(|| RpcError::NotFound(format!("organization {}", update.org_id)))?;
+        let updated = update
+            .apply(&current)
+            .map_err(|e| RpcError::InvalidParams(e.to_string()))?;
+        let organization = permissions.update(updated).map_err(permission_error)?;
+        to_value(&redacted(organization))
+    }
+
+    /// Sets the Username and Password an organization logs on with. The
+    /// password is not returned.
+    fn set_logon_credential(&self, params: LogonCredentialParams) -> Result<Value, RpcError> {
+        if params.username.is_empty() || params.password.is_empty() {
+            return Err(RpcError::InvalidParams("`username` and `password` must not be empty".into()));
+        }
+        let credential = LogonCredential::new(params.username, &params.password, params.allow_unsigned);
+        let organization = self
+            .permissions()?
+            .set_logon_credential(&params.org_id, Some(credential))
+            .map_err(permission_error)?;
+        to_value(&organization.logon_credential.map(|credential| credential.redacted()))
+    }
+
+    fn remove_logon_credential(&self, params: OrganizationParams) -> Result<Value, RpcError> {
+        let permissions = self.permissions()?;
+        let organization = permissions
+            .organization(&params.org_id)
+            .ok_or_else(|| RpcError::NotFound(format!("organization {}", params.org_id)))?;
+        if organization.logon_credential.is_none() {
+            return Err(RpcError::NotFound(format!("logon credential of {}", params.org_id)));
+        }
+        permissions.set_logon_credential(&params.org_id, None).map_err(permission_error)?;
+        Ok(json!({ "removed": true }))
+    }
+
+    fn symbol_permissions(&self, params: OrganizationParams) -> Result<Value, RpcError> {
+        let organization = self
+            .permissions()?
+            .organization(&params.org_id)
+            .ok_or_else(|| RpcError::NotFound(format!("organization {}", params.org_id)))?;
+        to_value(&organization.symbol_permissions)
+    }
+
+    fn set_sub_account(&self, params: SubAccountParams) -> Result<Value, RpcError> {
+        let sub_account = params
+            .sub_account
+            .ok_or_else(|| RpcError::InvalidParams("missing field `sub_account`".into()))?;
+        if params.account.is_empty() {
+            return Err(RpcError::InvalidParams("empty account".into()));
+        }
+        let organization = self
+            .permissions()?
+            .set_sub_account(&params.org_id, &params.account, sub_account)
+            .map_err(permission_error)?;
+        to_value(&organization.sub_accounts)
+    }
+
+    fn remove_sub_account(&self, params: SubAccountParams) -> Result<Value, RpcError> {
+        let organization = self
+            .permissions()?
+            .remove_sub_account(&params.org_id, &params.account)
+            .map_err(permission_error)?;
+        to_value(&organization.sub_accounts)
+    }
+
+    /// Each sub-account of an organization with its limits and positions
+    fn get_sub_accounts(&self, params: SubAccountLookupParams) -> Result<Value, RpcError> {
+        let organization = self
+            .permissions()?
+            .organization(&params.org_id)
+            .ok_or_else(|| RpcError::NotFound(format!("organization {}", params.org_id)))?;
+        let sub_accounts = self
+            .sub_accounts
+            .as_deref()
+            .ok_or_else(|| RpcError::Internal("sub-accounts not configured".into()))?;
+        let mut report = serde_json::Map::new();
+        for (account, sub_account) in &organization.sub_accounts {
+            if params.account.as_ref().is_some_and(|only| only != account) {
+                continue;
+            }
+            report.insert(
+                account.clone(),
+                json!({
+                    "name": sub_account.name,
+                    "limits": sub_account.limits,
+                    "positions": sub_accounts.positions(&organization.sender_comp_id, Some(account)),
+                }),
+            );
+        }
+        if let Some(account) = params.account.filter(|_| report.is_empty()) {
+            return Err(RpcError::NotFound(format!("sub-account {} of {}", account, params.org_id)));
+        }
+        Ok(Value::Object(report))
+    }
+}
+
+fn parse<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|e| RpcError::InvalidParams(e.to_string()))
+}
+
+/// `organization` without the salt and hash of its logon credential
+fn redacted(mut organization: Organization) -> Organization {
+    organization.logon_credential = organization.logon_credential.map(|credential| credential.redacted());
+    organization
+}
+
+fn permission_error(error: PermissionError) -> RpcError {
+    match error {
+        PermissionError::OrganizationNotFound(org_id) => {
+            RpcError::NotFound(format!("organization {}", org_id))
+        }
+        PermissionError::SenderTaken(_) => RpcError::InvalidParams(error.to_string()),
+        PermissionError::UnknownSubAccount { .. } => RpcError::NotFound(error.to_string()),
+        other => RpcError::Internal(other.to_string()),
+    }
+}
+
+fn bridge_error(error: RelayError) -> RpcError {
+    match error {
+        RelayError::UnknownDeposit { .. } | RelayError::UnknownChain(_) => RpcError::NotFound(error.to_string()),
+        RelayError::Bridge(_) => RpcError::InvalidParams(error.to_string()),
+    }
+}
+
+fn governance_error(error: ProposalError) -> RpcError {
+    match error {
+        ProposalError::NotValidator(_) => RpcError::Rejected(RejectReason::PermissionDenied, error.to_string()),
+        ProposalError::VotingClosed(_) => RpcError::Rejected(RejectReason::Expired, error.to_string()),
+        ProposalError::UnknownProposal(_) => RpcError::NotFound(error.to_string()),
+        ProposalError::Io(_) => RpcError::Internal(error.to_string()),
+        ProposalError::Invalid(_) | ProposalError::TooSoon { .. } => RpcError::InvalidParams(error.to_string()),
+    }
+}
+
+fn to_value<T: serde::Serialize>(value: &T) -> Result<Value, RpcError> {
+    serde_json::to_value(value).map_err(|e| RpcError::Internal(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AdminConfig;
+    use crate::rpc::types::{codes, RpcErrorObject};
+    use romer_common::types::admin::AdminRole;
+    use romer_common::types::envelope::UnsignedTransaction;
+    use romer_common::types::keymanager::SignatureScheme;
+    use commonware_cryptography::{Ed25519, Scheme};
+
+    fn signed_transfer(amount: u64) -> SignedTransaction {
+        let mut signer = Ed25519::from_seed(1);
+        UnsignedTransaction {
+            chain_id: DEFAULT_CHAIN_ID.to_string(),
+            payload: TransactionPayload::Transfer {
+                to: Address::new([9u8; 32]),
+                amount,
+            },
+            sender: Address::from_public_key(SignatureScheme::Ed25519, &signer.public_key()),
+            nonce: 0,
+            fee: 0,
+            expiry: u64::MAX,
+        }
+        .sign(SignatureScheme::Ed25519, &mut signer)
+        .unwrap()
+    }
+
+    fn request(method: &str, params: Value) -> RpcRequest {
+        RpcRequest {
+            jsonrpc: JSONRPC_VERSION.into(),
+            method: method.into(),
+            params,
+            id: Some(json!(1)),
+        }
+    }
+
+    /// Admin access granting `superuser()` every method
+    fn superuser_access() -> Arc<AdminAccess> {
+        let config = AdminConfig {
+            certificates: [("5e5e".to_string(), AdminRole::Superuser)].into(),
+            client_ca: Some("admin-ca.pem".into()),
+            ..Default::default()
+        };
+        Arc::new(AdminAccess::new(&config, system_clock()))
+    }
+
+    /// A caller presenting the certificate `superuser_access` trusts
+    fn superuser() -> Caller {
+        Caller {
+            certificate: Some("5e5e".into()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transaction_records_pruned() {
+        let (tx, _rx) = mpsc::channel(8);
+        let state = Arc::new(RpcState::new());
+        let handler = RpcHandler::new(state.clone(), tx);
+        let transaction = signed_transfer(10);
+        handler
+            .handle(request("submit_transaction", json!({ "transaction": transaction })))
+            .await
+            .unwrap();
+        let hash = hex(&transaction.digest().unwrap());
+
+        // Pending transactions are kept however long they wait
+        state.prune_transactions(TRANSACTION_RETENTION_BLOCKS, u64::MAX);
+        assert!(state.transactions.contains_key(&hash));
+
+        if let Some(mut record) = state.transactions.get_mut(&hash) {
+            record.status = TransactionStatus::Included;
+            record.block_id = Some(0);
+        }
+        state.prune_transactions(TRANSACTION_RETENTION_BLOCKS - 1, 0);
+        assert!(state.transactions.contains_key(&hash));
+        state.prune_transactions(TRANSACTION_RETENTION_BLOCKS, 0);
+        assert!(!state.transactions.contains_key(&hash));
+    }
+
+    #[tokio::test]
+    async fn test_submit_and_lookup() {
+        let (tx, mut rx) = mpsc::channel(8);
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx);
+        let transaction = signed_transfer(10);
+
+        let response = handler
+            .handle(request("submit_transaction", json!({ "transaction": transaction })))
+            .await
+            .unwrap();
+        let hash = response.result.unwrap()["hash"].as_str().unwrap().to_string();
+        assert_eq!(rx.recv().await.unwrap(), transaction);
+
+        let response = handler
+            .handle(request("get_transaction", json!({ "hash": hash })))
+            .await
+            .unwrap();
+        assert_eq!(response.result.unwrap()["status"], "pending");
+
+        let response = handler
+            .handle(request("submit_transaction", json!({ "transaction": transaction })))
+            .await
+            .unwrap();
+        let error = response.error.unwrap();
+        assert_eq!(error.code, codes::TRANSACTION_REJECTED);
+        assert_eq!(error.data.unwrap().reason, RejectReason::DuplicateTransaction.code());
+        assert_eq!(
+            serde_json::to_value(RpcErrorObject::from(RpcError::Rejected(RejectReason::Expired, "at 5".into())))
+                .unwrap()["data"],
+            json!({ "reason": 2002, "name": "expired" })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_receipt_proves_inclusion() {
+        use crate::block::batch::MessageBatch;
+        use crate::block::builder::BlockBuilder;
+        use romer_common::types::receipt::ReceiptWithProof;
+
+        let (tx, _rx) = mpsc::channel(8);
+        let state = Arc::new(RpcState::new());
+        let handler = RpcHandler::new(state.clone(), tx);
+        let transaction = signed_transfer(10);
+        let response = handler
+            .handle(request("submit_transaction", json!({ "transaction": transaction })))
+            .await
+            .unwrap();
+        let hash = response.result.unwrap()["hash"].as_str().unwrap().to_string();
+
+        let response = handler.handle(request("get_receipt", json!({ "hash": hash }))).await.unwrap();
+        assert_eq!(response.error.unwrap().code, codes::NOT_FOUND);
+
+        let batch = MessageBatch {
+            messages: Vec::new(),
+            start_time: tokio::time::Instant::now(),
+            end_time: tokio::time::Instant::now(),
+            sequence: 0,
+        };
+        let block = BlockBuilder::new()
+            .build_block_with_transactions(batch, vec![transaction])
+            .unwrap();
+        state.record_block(block, &[hash.clone()]);
+
+        let result = handler
+            .handle(request("get_receipt", json!({ "hash": hash })))
+            .await
+            .unwrap()
+            .result
+            .unwrap();
+        let proven: ReceiptWithProof = serde_json::from_value(result["receipt"].clone()).unwrap();
+        let root: [u8; 32] = hex::decode(result["header"]["transactions_root"].as_str().unwrap())
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert!(proven.verify(&root));
+        assert_eq!(proven.receipt.block_height, 0);
+    }
+
+    #[tokio::test]
+    async fn test_replayed_nonce_rejected() {
+        let (tx, _rx) = mpsc::channel(8);
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx);
+
+        let first = signed_transfer(10);
+        handler
+            .handle(request("submit_transaction", json!({ "transaction": first })))
+            .await
+            .unwrap();
+
+        // Different payload, same sender and nonce
+        let replay = signed_transfer(11);
+        let response = handler
+            .handle(request("submit_transaction", json!({ "transaction": replay })))
+            .await
+            .unwrap();
+        let error = response.error.unwrap();
+        assert_eq!(error.code, codes::TRANSACTION_REJECTED);
+        assert!(error.message.contains("already pending"));
+    }
+
+    #[tokio::test]
+    async fn test_simulate_checks_balance() {
+        let (tx, _rx) = mpsc::channel(8);
+        let state = Arc::new(RpcState::new());
+        let handler = RpcHandler::new(state.clone(), tx);
+        let transaction = signed_transfer(10);
+        state.set_balance(transaction.transaction.sender, 25);
+
+        let response = handler
+            .handle(request("simulate", json!({ "transaction": transaction })))
+            .await
+            .unwrap();
+        let result: SimulationResult = serde_json::from_value(response.result.unwrap()).unwrap();
+        assert!(result.accepted);
+        assert_eq!(result.balance_after, Some(15));
+
+        let response = handler
+            .handle(request("simulate", json!({ "transaction": signed_transfer(30) })))
+            .await
+            .unwrap();
+        let result: SimulationResult = serde_json::from_value(response.result.unwrap()).unwrap();
+        assert!(!result.accepted);
+    }
+
+    #[tokio::test]
+    async fn test_admin_drain_mode() {
+        use crate::events::bus::EventBus;
+
+        let (tx, _rx) = mpsc::channel(8);
+        let drain = Arc::new(DrainMode::new(EventBus::default()));
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx)
+            .with_drain_mode(drain.clone())
+            .with_admin_access(superuser_access());
+
+        let response = handler.handle_from(request("admin_enter_drain_mode", Value::Null), &superuser()).await.unwrap();
+        assert_eq!(response.result.unwrap()["reason"], "maintenance");
+        assert!(drain.is_draining());
+
+        let response = handler.handle_from(request("admin_drain_status", Value::Null), &superuser()).await.unwrap();
+        assert_eq!(response.result.unwrap()["triggered_by"], "admin");
+
+        let response = handler.handle_from(request("admin_exit_drain_mode", Value::Null), &superuser()).await.unwrap();
+        assert_eq!(response.result.unwrap()["released"], true);
+        assert!(!drain.is_draining());
+    }
+
+    #[tokio::test]
+    async fn test_reference_price() {
+        use std::time::Duration;
+
+        let (tx, _rx) = mpsc::channel(8);
+        let manual = Arc::new(ManualFeed::new(Duration::from_secs(60)));
+        let service = Arc::new(ReferencePriceService::new(Duration::from_secs(5)).with_feed(manual.clone()));
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx)
+            .with_reference_prices(service, manual)
+            .with_admin_access(superuser_access());
+
+        let response = handler.handle(request("get_reference_price", json!({ "symbol": "AAPL" }))).await.unwrap();
+        assert_eq!(response.error.unwrap().code, codes::NOT_FOUND);
+
+        let response = handler
+            .handle_from(request("admin_set_reference_price", json!({ "symbol": "AAPL", "price": -1.0 })), &superuser())
+            .await
+            .unwrap();
+        assert_eq!(response.error.unwrap().code, codes::INVALID_PARAMS);
+        handler
+            .handle_from(request("admin_set_reference_price", json!({ "symbol": "AAPL", "price": 187.5 })), &superuser())
+            .await
+            .unwrap();
+
+        let response = handler.handle(request("get_reference_price", json!({ "symbol": "AAPL" }))).await.unwrap();
+        let result = response.result.unwrap();
+        assert_eq!(result["price"], 187.5);
+        assert_eq!(result["source"], "manual");
+    }
+
+    #[tokio::test]
+    async fn test_candles() {
+        use crate::market::candles::Fill;
+
+        let (tx, _rx) = mpsc::channel(8);
+        let candles = Arc::new(CandleStore::default());
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx).with_candles(candles.clone());
+        candles.record(&Fill {
+            symbol: "AAPL".into(),
+            price: 100,
+            quantity: 5,
+            at: Utc::now(),
+        });
+
+        let response = handler
+            .handle(request("get_candles", json!({ "symbol": "AAPL", "interval": "5m" })))
+            .await
+            .unwrap();
+        let result = response.result.unwrap();
+        assert_eq!(result.as_array().unwrap().len(), 1);
+        assert_eq!(result[0]["volume"], 5);
+
+        let response = handler
+            .handle(request("get_candles", json!({ "symbol": "AAPL", "interval": "2m" })))
+            .await
+            .unwrap();
+        assert_eq!(response.error.unwrap().code, codes::INVALID_PARAMS);
+
+        let response = handler.handle(request("get_daily_stats", json!({ "symbol": "AAPL" }))).await.unwrap();
+        assert_eq!(response.result.unwrap()[0]["vwap"], 100);
+    }
+
+    #[tokio::test]
+    async fn test_depth() {
+        use crate::market::data::{BookSide, BookUpdate};
+
+        let (tx, _rx) = mpsc::channel(8);
+        let market_data = Arc::new(MarketDataPublisher::default());
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx).with_market_data(market_data.clone());
+
+        let response = handler.handle(request("get_depth", json!({ "symbol": "AAPL" }))).await.unwrap();
+        assert_eq!(response.error.unwrap().code, codes::NOT_FOUND);
+
+        for (side, price) in [(BookSide::Bid, 99), (BookSide::Bid, 100), (BookSide::Ask, 101)] {
+            market_data.publish(&BookUpdate {
+                symbol: "AAPL".into(),
+                side,
+                price,
+                size: 5,
+            });
+        }
+        let response = handler
+            .handle(request("get_depth", json!({ "symbol": "AAPL", "depth": 1 })))
+            .await
+            .unwrap();
+        let result = response.result.unwrap();
+        assert_eq!(result["sequence"], 3);
+        assert_eq!(result["bids"].as_array().unwrap().len(), 1);
+        assert_eq!(result["bids"][0]["price"], 100);
+        assert_eq!(result["checksum"], crc32fast::hash(b"100:5:101:5:99:5"));
+    }
+
+    #[tokio::test]
+    async fn test_reconciliation() {
+        use crate::audit::reconciliation::Reconciler;
+
+        let (tx, _rx) = mpsc::channel(8);
+        let dir = std::env::temp_dir().join(format!("romer-rpc-recon-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("events.jsonl");
+        let at = Utc::now();
+        let fill = SequencerEvent::Match {
+            symbol: "AAPL".into(),
+            price: 100,
+            quantity: 2,
+            buyer: "MM1".into(),
+            seller: "MM2".into(),
+            maker: None,
+            buyer_account: None,
+            seller_account: None,
+            at,
+        };
+        std::fs::write(&log, serde_json::to_string(&fill).unwrap()).unwrap();
+        let service = ReconciliationService::new(Reconciler::new(Default::default(), 0), &log, dir.join("out"));
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx)
+            .with_reconciliation(Arc::new(service))
+            .with_admin_access(superuser_access());
+
+        let response = handler
+            .handle_from(request("admin_reconciliation_report", Value::Null), &superuser())
+            .await
+            .unwrap();
+        assert_eq!(response.error.unwrap().code, codes::NOT_FOUND);
+        let response = handler
+            .handle_from(request("admin_run_reconciliation", Value::Null), &superuser())
+            .await
+            .unwrap();
+        assert_eq!(response.error.unwrap().code, codes::INVALID_PARAMS);
+
+        let day = at.date_naive().to_string();
+        let response = handler
+            .handle_from(request("admin_run_reconciliation", json!({ "day": day, "firm": "MM1" })), &superuser())
+            .await
+            .unwrap();
+        assert_eq!(response.result.unwrap()[0]["settlement"], -200);
+        let response = handler
+            .handle_from(request("admin_reconciliation_report", Value::Null), &superuser())
+            .await
+            .unwrap();
+        assert_eq!(response.result.unwrap().as_array().unwrap().len(), 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_oracle_prices() {
+        use romer_common::types::oracle::PriceSubmission;
+        use romer_common::utils::clock::Clock;
+
+        let clock = system_clock();
+        let mut feeder = Ed25519::from_seed(1);
+        let feeders = [(hex::encode(feeder.public_key()), "feeder".to_string())].into_iter().collect();
+        let oracle = Arc::new(OracleAggregator::new(feeders, std::time::Duration::from_secs(30), 1, 100, clock.clone()));
+        let (tx, _rx) = mpsc::channel(8);
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx).with_oracle(oracle.clone());
+
+        let submission = PriceSubmission {
+            symbol: "BTC/USD".into(),
+            price: 64_000,
+            observed_at_ms: clock.unix_millis(),
+        };
+        let signed = SignedPriceSubmission::sign(submission, &mut feeder);
+        let response = handler.handle(request("submit_oracle_price", json!(signed))).await.unwrap();
+        assert_eq!(response.result.unwrap()["accepted"], true);
+        oracle.aggregate(clock.unix_millis());
+        let prices = handler
+            .handle(request("get_oracle_prices", Value::Null))
+            .await
+            .unwrap()
+            .result
+            .unwrap();
+        assert_eq!(prices[0]["price"], 64_000);
+
+        let mut outsider = Ed25519::from_seed(2);
+        let forged = SignedPriceSubmission::sign(signed.submission, &mut outsider);
+        let response = handler.handle(request("submit_oracle_price", json!(forged))).await.unwrap();
+        assert_eq!(response.error.unwrap().code, codes::TRANSACTION_REJECTED);
+    }
+
+    #[tokio::test]
+    async fn test_governance() {
+        use romer_common::types::governance::{ParameterChange, Parameters};
+
+        let mut validator = Ed25519::from_seed(1);
+        let validators = [(hex::encode(validator.public_key()), 10)].into_iter().collect();
+        let governance = Arc::new(GovernanceService::new(validators, 6_667, 5, Parameters::default()));
+        let (tx, _rx) = mpsc::channel(8);
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx).with_governance(governance.clone());
+
+        let proposal = Proposal {
+            changes: vec![ParameterChange::FeeBurnBps { value: 2_500 }],
+            activation_height: 10,
+            description: String::new(),
+        };
+        let signed = Signed::sign(proposal, &mut validator).unwrap();
+        let response = handler.handle(request("submit_governance_proposal", json!(signed))).await.unwrap();
+        let id = response.result.unwrap()["proposal_id"].as_u64().unwrap();
+
+        let vote = Signed::sign(Vote { proposal_id: id, approve: true }, &mut validator).unwrap();
+        let response = handler.handle(request("submit_governance_vote", json!(vote))).await.unwrap();
+        assert_eq!(response.result.unwrap()["approve_stake"], 10);
+        governance.advance(10);
+        let parameters = handler
+            .handle(request("get_governance_parameters", Value::Null))
+            .await
+            .unwrap()
+            .result
+            .unwrap();
+        assert_eq!(parameters["fee_burn_bps"], 2_500);
+
+        let vote = Signed::sign(Vote { proposal_id: 7, approve: true }, &mut validator).unwrap();
+        let response = handler.handle(request("submit_governance_vote", json!(vote))).await.unwrap();
+        assert_eq!(response.error.unwrap().code, codes::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_instrument_overrides() {
+        use romer_common::types::instrument::InstrumentParameters;
+
+        let instruments = InstrumentRegistry::new(InstrumentParameters::default(), EventBus::new(16), system_clock());
+        let (tx, _rx) = mpsc::channel(8);
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx)
+            .with_instruments(Arc::new(instruments))
+            .with_admin_access(superuser_access());
+
+        let params = json!({
+            "symbol": "AAPL",
+            "overrides": { "tick_size": 10_000_000, "matching": "batch" },
+            "activation_height": 5,
+        });
+        let response = handler
+            .handle_from(request("admin_schedule_instrument_override", params), &superuser())
+            .await
+            .unwrap();
+        assert_eq!(response.result.unwrap()["source"], "admin");
+
+        let current = handler
+            .handle(request("get_instrument", json!({ "symbol": "AAPL" })))
+            .await
+            .unwrap()
+            .result
+            .unwrap();
+        assert_eq!(current["parameters"]["tick_size"], 1);
+        assert_eq!(current["overrides"].as_array().unwrap().len(), 1);
+        let later = handler
+            .handle(request("get_instrument", json!({ "symbol": "AAPL", "height": 5 })))
+            .await
+            .unwrap()
+            .result
+            .unwrap();
+        assert_eq!(later["parameters"]["matching"], "batch");
+
+        let params = json!({ "symbol": "AAPL", "overrides": { "lot_size": 0 } });
+        let response = handler
+            .handle_from(request("admin_schedule_instrument_override", params), &superuser())
+            .await
+            .unwrap();
+        assert_eq!(response.error.unwrap().code, codes::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_allocations() {
+        use romer_common::fix::allocation::{AllocSide, AllocationEntry, AllocationInstruction};
+
+        let (tx, _rx) = mpsc::channel(8);
+        let allocations = Arc::new(AllocationService::new(EventBus::new(16), system_clock()));
+        allocations
+            .submit(
+                "EXEC1",
+                AllocationInstruction {
+                    alloc_id: "A1".into(),
+                    side: AllocSide::Sell,
+                    symbol: "AAPL".into(),
+                    quantity: 10,
+                    avg_price: 150_000_000_000,
+                    trade_date: None,
+                    allocations: vec![AllocationEntry {
+                        account: "FUND-1".into(),
+                        clearing_firm: "CLR1".into(),
+                        quantity: 10,
+                    }],
+                },
+            )
+            .unwrap();
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx).with_allocations(allocations);
+
+        let response = handler
+            .handle(request("get_allocations", json!({ "firm": "CLR1" })))
+            .await
+            .unwrap();
+        let listed = response.result.unwrap();
+        assert_eq!(listed[0]["instruction"]["alloc_id"], "A1");
+        assert_eq!(listed[0]["status"], "pending");
+        let response = handler
+            .handle(request("get_allocations", json!({ "firm": "CLR2" })))
+            .await
+            .unwrap();
+        assert_eq!(response.result.unwrap(), json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_clock_quality() {
+        use crate::block::clock_quality::ClockSample;
+
+        let (tx, _rx) = mpsc::channel(8);
+        let monitor = Arc::new(ClockMonitor::new(
+            Default::default(),
+            chrono::Duration::milliseconds(100),
+            chrono::Duration::seconds(60),
+            system_clock(),
+        ));
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx).with_clock_monitor(monitor.clone());
+
+        let result = handler.handle(request("get_clock_quality", Value::Null)).await.unwrap().result.unwrap();
+        assert_eq!(result["producing_blocks"], false);
+
+        monitor.record(vec![ClockSample {
+            source: "ntp".into(),
+            offset_us: -250,
+            round_trip_us: 900,
+            at: Utc::now(),
+        }]);
+        let result = handler.handle(request("get_clock_quality", Value::Null)).await.unwrap().result.unwrap();
+        assert_eq!(result["producing_blocks"], true);
+        assert_eq!(result["confidence"]["offset_us"], -250);
+        assert_eq!(result["samples"][0]["source"], "ntp");
+    }
+
+    #[tokio::test]
+    async fn test_order_lookup() {
+        use crate::market::orders::OrderEntry;
+
+        let orders = Arc::new(OrderStore::in_memory(system_clock()));
+        let order = orders
+            .accept(OrderEntry {
+                sender_comp_id: "MM1".into(),
+                cl_ord_id: "A1".into(),
+                symbol: "BTC-USD".into(),
+                side: "1".into(),
+                account: None,
+                order_qty: 10,
+                price: Some("100".into()),
+            })
+            .await
+            .unwrap();
+        let (tx, _rx) = mpsc::channel(8);
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx).with_orders(orders);
+
+        let result = handler
+            .handle(request("get_order", json!({ "sender_comp_id": "MM1", "cl_ord_id": "A1" })))
+            .await
+            .unwrap()
+            .result
+            .unwrap();
+        assert_eq!(result["order_id"], order.order_id);
+        assert_eq!(result["status"], "new");
+        let error = handler
+            .handle(request("get_order", json!({ "order_id": "404" })))
+            .await
+            .unwrap()
+            .error
+            .unwrap();
+        assert_eq!(error.code, codes::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_request_faucet() {
+        use crate::config::FaucetConfig;
+
+        let config = FaucetConfig {
+            drip: 1_000,
+            max_per_address: 1_000,
+            cooldown_secs: 0,
+            object: Some(Address::new([5u8; 32]).to_hex()),
+            key: Some(hex::encode(Ed25519::from_seed(3).private_key())),
+        };
+        let faucet = Arc::new(Faucet::new(config, ExecutionEnvironment::Testnet, system_clock()).unwrap());
+        let (tx, mut rx) = mpsc::channel(8);
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx).with_faucet(faucet.clone());
+        let address = Address::new([3u8; 32]);
+
+        // The grant is minted by a transaction the faucet signs
+        let result = handler
+            .handle(request("request_faucet", json!({ "address": address })))
+            .await
+            .unwrap()
+            .result
+            .unwrap();
+        assert_eq!(result["amount"], 1_000);
+        let mint = rx.recv().await.unwrap();
+        assert_eq!(result["hash"], hex(&mint.digest().unwrap()));
+        assert_eq!((mint.transaction.sender, mint.transaction.nonce), (faucet.sender(), 0));
+        let error = handler
+            .handle(request("request_faucet", json!({ "address": address })))
+            .await
+            .unwrap()
+            .error
+            .unwrap();
+        assert_eq!(error.data.unwrap().name, RejectReason::ExceedsLimit);
+    }
+
+    #[tokio::test]
+    async fn test_export_genesis() {
+        let address = Address::new([4u8; 32]);
+        let mut source = GenesisBundle::new(ExecutionEnvironment::Mainnet, 0);
+        source.balances.insert(address, 250);
+        source.nonces.insert(address, 3);
+        let state = Arc::new(RpcState::new());
+        state.apply_genesis(&source);
+        let (tx, _rx) = mpsc::channel(8);
+        let handler = RpcHandler::new(state, tx)
+            .with_environment(ExecutionEnvironment::Testnet)
+            .with_admin_access(superuser_access());
+
+        let result = handler
+            .handle_from(request("admin_export_genesis", Value::Null), &superuser())
+            .await
+            .unwrap()
+            .result
+            .unwrap();
+        let bundle: GenesisBundle = serde_json::from_value(result).unwrap();
+        bundle.verify().unwrap();
+        assert_eq!(bundle.environment, ExecutionEnvironment::Testnet);
+        assert_eq!(bundle.balances, source.balances);
+        assert_eq!(bundle.nonces, source.nonces);
+    }
+
+    #[tokio::test]
+    async fn test_reads_need_api_key() {
+        use crate::config::ApiKeyConfig;
+
+        let config = ApiKeyConfig {
+            required: true,
+            ..Default::default()
+        };
+        let api_keys = Arc::new(ApiKeyRegistry::in_memory(config, system_clock()));
+        let (tx, _rx) = mpsc::channel(8);
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx)
+            .with_api_keys(api_keys)
+            .with_admin_access(superuser_access());
+        let balance = || request("get_balance", json!({ "address": Address::new([5u8; 32]) }));
+
+        let error = handler.handle(balance()).await.unwrap().error.unwrap();
+        assert_eq!(error.code, codes::UNAUTHORIZED);
+
+        let created = handler
+            .handle_from(
+                request("admin_create_api_key", json!({ "label": "explorer", "rate_per_sec": 1, "burst": 1 })),
+                &superuser(),
+            )
+            .await
+            .unwrap()
+            .result
+            .unwrap();
+        let caller = Caller {
+            api_key: created["secret"].as_str().map(String::from),
+            ..Default::default()
+        };
+        let result = handler.handle_from(balance(), &caller).await.unwrap().result.unwrap();
+        assert_eq!(result["balance"], 0);
+        let error = handler.handle_from(balance(), &caller).await.unwrap().error.unwrap();
+        assert_eq!(error.code, codes::RATE_LIMITED);
+    }
+
+    #[tokio::test]
+    async fn test_admin_calls_authorized_and_audited() {
+        use crate::config::AdminConfig;
+        use crate::events::bus::EventBus;
+        use crate::rpc::admin::AdminAccess;
+
+        let events = EventBus::default();
+        let mut audit = events.subscribe();
+        let config = AdminConfig {
+            certificates: [("ab01".to_string(), AdminRole::Viewer)].into(),
+            client_ca: Some("admin-ca.pem".into()),
+            ..Default::default()
+        };
+        let (tx, _rx) = mpsc::channel(8);
+        let handler = RpcHandler::new(Arc::new(RpcState::new().with_events(events)), tx)
+            .with_kill_switch(Arc::new(KillSwitch::new(EventBus::default())))
+            .with_admin_access(Arc::new(AdminAccess::new(&config, system_clock())));
+        let viewer = Caller {
+            certificate: Some("ab01".into()),
+            ..Default::default()
+        };
+
+        let error = handler.handle(request("admin_kill_switch_status", json!({}))).await.unwrap().error.unwrap();
+        assert_eq!(error.code, codes::UNAUTHORIZED);
+        handler
+            .handle_from(request("admin_kill_switch_status", json!({})), &viewer)
+            .await
+            .unwrap()
+            .result
+            .unwrap();
+        let error = handler
+            .handle_from(request("admin_engage_kill_switch", json!({ "firm": "FIRM1", "reason": "test" })), &viewer)
+            .await
+            .unwrap()
+            .error
+            .unwrap();
+        assert_eq!(error.code, codes::UNAUTHORIZED);
+
+        let mut outcomes = Vec::new();
+        for _ in 0..3 {
+            match audit.recv().await.as_deref() {
+                Some(SequencerEvent::AdminAction { identity, outcome, .. }) => outcomes.push((identity.clone(), outcome.clone())),
+                other => panic!("unexpected event {:?}", other),
+            }
+        }
+        assert!(outcomes[0].1.starts_with("denied"));
+        assert_eq!(outcomes[1], ("cert:ab01".to_string(), "ok".to_string()));
+        assert!(outcomes[2].1.starts_with("denied"));
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_news() {
+        use crate::events::bus::EventBus;
+
+        let news = Arc::new(NewsService::in_memory(EventBus::default(), system_clock()));
+        let (tx, _rx) = mpsc::channel(8);
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx)
+            .with_news(news.clone())
+            .with_admin_access(superuser_access());
+
+        let result = handler
+            .handle_from(
+                request(
+                    "admin_broadcast_news",
+                    json!({ "headline": "Maintenance", "text": "Closed 17:00-18:00", "recipients": ["MM1"] }),
+                ),
+                &superuser(),
+            )
+            .await
+            .unwrap()
+            .result
+            .unwrap();
+        assert_eq!(result["id"], 1);
+        assert_eq!(news.take_pending("MM1")[0].text, "Closed 17:00-18:00");
+        assert!(news.take_pending("MM2").is_empty());
+
+        let result = handler.handle(request("get_news", json!({}))).await.unwrap().result.unwrap();
+        assert_eq!(result[0]["headline"], "Maintenance");
+        let error = handler
+            .handle_from(request("admin_broadcast_news", json!({ "headline": "" })), &superuser())
+            .await
+            .unwrap()
+            .error
+            .unwrap();
+        assert_eq!(error.code, codes::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_speed_bump_published() {
+        use crate::gateway::speed_bump::SpeedBump;
+
+        let (tx, _rx) = mpsc::channel(8);
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx);
+        let result = handler.handle(request("get_speed_bump", Value::Null)).await.unwrap().result.unwrap();
+        assert_eq!(result["delay_ms"], 0);
+
+        let bump = SpeedBump::<String>::new(std::time::Duration::from_millis(350));
+        let (tx, _rx) = mpsc::channel(8);
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx).with_speed_bump(bump.state());
+        let result = handler.handle(request("get_speed_bump", Value::Null)).await.unwrap().result.unwrap();
+        assert_eq!(result, json!({ "delay_ms": 350, "held": 0, "released": 0 }));
+    }
+
+    #[tokio::test]
+    async fn test_pretrade_plugins() {
+        use crate::risk::plugins::PluginLimits;
+
+        let wasm = wat::parse_str(
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "alloc") (param i32) (result i32) i32.const 0)
+                (func (export "check") (param i32 i32) (result i32) i32.const 0))"#,
+        )
+        .unwrap();
+        let plugins = PluginHost::new(PluginLimits {
+            fuel: 1_000,
+            memory_bytes: 65_536,
+        });
+        plugins.register("noop", 1, 0, &wasm).unwrap();
+        plugins.register("noop", 2, 50, &wasm).unwrap();
+        let (tx, _rx) = mpsc::channel(8);
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx).with_plugins(Arc::new(plugins));
+
+        let result = handler
+            .handle(request("get_pretrade_plugins", Value::Null))
+            .await
+            .unwrap()
+            .result
+            .unwrap();
+        assert_eq!(result["registered"].as_array().unwrap().len(), 2);
+        assert_eq!(result["active"][0]["version"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_bridge() {
+        use romer_common::types::bridge::{BridgeCommittee, BridgeSignature, Deposit};
+
+        let mut signers: Vec<Ed25519> = (0..2).map(Ed25519::from_seed).collect();
+        let committee = BridgeCommittee {
+            validators: signers.iter().map(|signer| hex::encode(signer.public_key())).collect(),
+            threshold: 2,
+        };
+        let relay = Arc::new(BridgeRelay::new(committee).unwrap());
+        let deposit = Deposit {
+            source_chain: "ethereum".into(),
+            deposit_id: "0xabc-1".into(),
+            asset: format!("{:0>64}::usdc::USDC", "20"),
+            recipient: Address::new([7u8; 32]),
+            amount: 1_000,
+        };
+        relay.observe(deposit.clone());
+        let (tx, _rx) = mpsc::channel(8);
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx).with_bridge(relay);
+
+        let mint = json!({ "source_chain": "ethereum", "deposit_id": "0xabc-1" });
+        let response = handler.handle(request("get_bridge_mint", mint.clone())).await.unwrap();
+        assert_eq!(response.error.unwrap().code, codes::INVALID_PARAMS);
+
+        for signer in signers.iter_mut() {
+            let signature = BridgeSignature::sign(signer, &deposit.mint_message());
+            let params = json!({ "source_chain": "ethereum", "deposit_id": "0xabc-1", "signature": signature });
+            handler.handle(request("submit_bridge_attestation", params)).await.unwrap().result.unwrap();
+        }
+        let deposits = handler
+            .handle(request("get_bridge_deposits", Value::Null))
+            .await
+            .unwrap()
+            .result
+            .unwrap();
+        assert_eq!(deposits[0]["ready"], true);
+        let call = handler.handle(request("get_bridge_mint", mint)).await.unwrap().result.unwrap();
+        assert_eq!(call["function"], "mint");
+        assert_eq!(call["arguments"]["signers"], json!([0, 1]));
+
+        let unknown = json!({ "source_chain": "ethereum", "deposit_id": "0xdef-1" });
+        let response = handler.handle(request("get_bridge_mint", unknown)).await.unwrap();
+        assert_eq!(response.error.unwrap().code, codes::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_protocol_status() {
+        use romer_common::types::protocol::Activation;
+
+        let (tx, _rx) = mpsc::channel(8);
+        let schedule = ProtocolSchedule {
+            activations: vec![Activation {
+                version: SUPPORTED_PROTOCOL_VERSION + 1,
+                height: 100,
+                description: "gas schedule v2".into(),
+            }],
+        };
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx).with_protocol(Arc::new(schedule));
+        let status = handler
+            .handle(request("get_protocol_status", Value::Null))
+            .await
+            .unwrap()
+            .result
+            .unwrap();
+        assert_eq!(status["height"], 0);
+        assert_eq!(status["active_version"], SUPPORTED_PROTOCOL_VERSION);
+        assert_eq!(status["blocks_remaining"], 100);
+        assert_eq!(status["next_supported"], false);
+        assert_eq!(status["next"]["description"], "gas schedule v2");
+    }
+
+    #[tokio::test]
+    async fn test_attestations() {
+        use romer_common::types::attestation::{Attestation, HardwareAttestation, LocationAttestation};
+
+        let mut signer = Ed25519::from_seed(1);
+        let validator = Address::from_public_key(SignatureScheme::Ed25519, &signer.public_key());
+        let (tx, _rx) = mpsc::channel(8);
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx)
+            .with_attestations(Arc::new(AttestationRegistry::new([validator])));
+
+        let response = handler
+            .handle(request("get_attestation_status", json!({ "validator": validator })))
+            .await
+            .unwrap();
+        assert_eq!(response.result.unwrap()["state"], "missing");
+
+        let now = system_clock().unix_secs();
+        let attestation = Attestation {
+            validator,
+            location: LocationAttestation {
+                latitude: 50.1109,
+                longitude: 8.6821,
+                confidence: 0.9,
+                passed: true,
+            },
+            hardware: HardwareAttestation {
+                virtualization: None,
+                cpu_cores: Some(16),
+                memory_bytes: Some(64 << 30),
+                requirements_met: true,
+            },
+            issued_at: now,
+            expires_at: now + 600,
+        }
+        .sign(SignatureScheme::Ed25519, &mut signer)
+        .unwrap();
+        let response = handler
+            .handle(request("submit_attestation", json!({ "attestation": attestation })))
+            .await
+            .unwrap();
+        assert_eq!(response.result.unwrap()["state"], "attested");
+
+        let response = handler
+            .handle(request("submit_attestation", json!({ "attestation": attestation })))
+            .await
+            .unwrap();
+        assert_eq!(response.error.unwrap().code, codes::TRANSACTION_REJECTED);
+
+        let response = handler.handle(request("get_attestation_status", Value::Null)).await.unwrap();
+        assert_eq!(response.result.unwrap()[0]["attestation"]["location"]["confidence"], 0.9);
+    }
+
+    #[tokio::test]
+    async fn test_admin_kill_switch() {
+        use crate::events::bus::EventBus;
+
+        let (tx, _rx) = mpsc::channel(8);
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx).with_admin_access(superuser_access());
+        let response = handler
+            .handle_from(request("admin_kill_switch_status", Value::Null), &superuser())
+            .await
+            .unwrap();
+        assert_eq!(response.error.unwrap().code, codes::INTERNAL_ERROR);
+
+        let kill_switch = Arc::new(KillSwitch::new(EventBus::default()));
+        kill_switch.assign("TRADER1", "FIRM_A");
+        let handler = handler.with_kill_switch(kill_switch.clone());
+
+        let response = handler
+            .handle_from(
+                request("admin_engage_kill_switch", json!({ "firm": "FIRM_A", "reason": "runaway algo" })),
+                &superuser(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.result.unwrap()["triggered_by"], "admin");
+        assert!(kill_switch.is_blocked("TRADER1"));
+
+        let response = handler
+            .handle_from(request("admin_release_kill_switch", json!({ "firm": "FIRM_A" })), &superuser())
+            .await
+            .unwrap();
+        assert_eq!(response.result.unwrap()["released"], true);
+        assert!(!kill_switch.is_blocked("TRADER1"));
+    }
+
+    #[tokio::test]
+    async fn test_register_organization() {
+        use commonware_cryptography::Bls12381;
+        use romer_common::types::org::{OrganizationType, REGISTRATION_NAMESPACE};
+
+        let (tx, _rx) = mpsc::channel(8);
+        let permissions = Arc::new(PermissionRegistry::new());
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx).with_permissions(permissions.clone());
+
+        let mut signer = Bls12381::from_seed(7);
+        let message = OrganizationRegistration::signing_message("Market Maker One", &OrganizationType::MarketMaker, "MM1");
+        let registration = OrganizationRegistration {
+            name: "Market Maker One".into(),
+            org_type: OrganizationType::MarketMaker,
+            sender_comp_id: "MM1".into(),
+            public_key: signer.public_key().to_vec(),
+            signature: signer.sign(Some(REGISTRATION_NAMESPACE), &message).to_vec(),
+        };
+
+        let response = handler
+            .handle(request("register_organization", serde_json::to_value(&registration).unwrap()))
+            .await
+            .unwrap();
+        assert_eq!(response.result.unwrap()["sender_comp_id"], "MM1");
+        assert!(permissions.organization_for_sender("MM1").is_some());
+
+        // The CompID is taken now
+        let response = handler
+            .handle(request("register_organization", serde_json::to_value(&registration).unwrap()))
+            .await
+            .unwrap();
+        assert_eq!(response.error.unwrap().code, codes::INVALID_PARAMS);
+
+        // Someone else's key without its signature
+        let forged = OrganizationRegistration {
+            sender_comp_id: "MM2".into(),
+            ..registration
+        };
+        let response = handler
+            .handle(request("register_organization", serde_json::to_value(&forged).unwrap()))
+            .await
+            .unwrap();
+        assert_eq!(response.error.unwrap().code, codes::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_update_organization() {
+        use commonware_cryptography::Bls12381;
+        use romer_common::types::org::{Organization, OrganizationType, REGISTRATION_NAMESPACE, UPDATE_NAMESPACE};
+
+        let (tx, _rx) = mpsc::channel(8);
+        let mut current = Bls12381::from_seed(7);
+        let organization = Organization::new(
+            "mm1".into(),
+            "Market Maker One".into(),
+            OrganizationType::MarketMaker,
+            "MM1".into(),
+            current.public_key().to_vec(),
+        );
+        let issued_at = organization.updated_at + 1;
+        let permissions = Arc::new(PermissionRegistry::from_organizations([organization]));
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx).with_permissions(permissions.clone());
+
+        // Rotate to a new key and CompID, signed by both keys
+        let mut next = Bls12381::from_seed(8);
+        let mut update = OrganizationUpdate {
+            org_id: "mm1".into(),
+            name: None,
+            org_type: None,
+            sender_comp_id: Some("MM1B".into()),
+            public_key: Some(next.public_key().to_vec()),
+            issued_at,
+            signature: Vec::new(),
+            new_key_signature: None,
+        };
+        let message = update.signing_message();
+        update.signature = current.sign(Some(UPDATE_NAMESPACE), &message).to_vec();
+
+        // Without proof of the new key
+        let response = handler
+            .handle(request("update_organization", serde_json::to_value(&update).unwrap()))
+            .await
+            .unwrap();
+        assert_eq!(response.error.unwrap().code, codes::INVALID_PARAMS);
+
+        update.new_key_signature = Some(next.sign(Some(REGISTRATION_NAMESPACE), &message).to_vec());
+        let response = handler
+            .handle(request("update_organization", serde_json::to_value(&update).unwrap()))
+            .await
+            .unwrap();
+        assert_eq!(response.result.unwrap()["sender_comp_id"], "MM1B");
+
+        let response = handler
+            .handle(request("get_organization", json!({ "sender_comp_id": "MM1B" })))
+            .await
+            .unwrap();
+        assert_eq!(response.result.unwrap()["public_key"], json!(next.public_key().to_vec()));
+
+        // Replaying the same update
+        let response = handler
+            .handle(request("update_organization", serde_json::to_value(&update).unwrap()))
+            .await
+            .unwrap();
+        assert_eq!(response.error.unwrap().code, codes::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_admin_symbol_permissions() {
+        use romer_common::types::org::{Organization, OrganizationType, SymbolPermission};
+
+        let (tx, _rx) = mpsc::channel(8);
+        let organization = Organization::new(
+            "mm1".into(),
+            "Market Maker One".into(),
+            OrganizationType::MarketMaker,
+            "MM1".into(),
+            vec![0; 48],
+        );
+        let permissions = Arc::new(PermissionRegistry::from_organizations([organization]));
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx)
+            .with_permissions(permissions.clone())
+            .with_admin_access(superuser_access());
+
+        let response = handler
+            .handle_from(
+                request(
+                    "admin_grant_symbol_permission",
+                    json!({ "org_id": "mm1", "symbol": "BTC-USD", "permission": "quote" }),
+                ),
+                &superuser(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.result.unwrap()["BTC-USD"], "quote");
+        assert!(permissions.check("MM1", "BTC-USD", SymbolPermission::Quote).is_ok());
+
+        let response = handler
+            .handle_from(
+                request(
+                    "admin_revoke_symbol_permission",
+                    json!({ "org_id": "mm1", "symbol": "BTC-USD" }),
+                ),
+                &superuser(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.result.unwrap(), json!({}));
+
+        let response = handler
+            .handle_from(request("admin_symbol_permissions", json!({ "org_id": "nobody" })), &superuser())
+            .await
+            .unwrap();
+        assert_eq!(response.error.unwrap().code, codes::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_sub_accounts() {
+        use romer_common::types::org::{Organization, OrganizationType};
+
+        let (tx, _rx) = mpsc::channel(8);
+        let organization = Organization::new(
+            "pb1".into(),
+            "Prime Broker One".into(),
+            OrganizationType::PrimeBroker,
+            "PB1".into(),
+            vec![0; 48],
+        );
+        let permissions = Arc::new(PermissionRegistry::from_organizations([organization]));
+        let sub_accounts = Arc::new(SubAccountTracker::new());
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx)
+            .with_permissions(permissions.clone())
+            .with_sub_accounts(sub_accounts.clone())
+            .with_admin_access(superuser_access());
+
+        let response = handler
+            .handle_from(
+                request(
+                    "admin_set_sub_account",
+                    json!({
+                        "org_id": "pb1",
+                        "account": "DESK-A",
+                        "sub_account": { "name": "Desk A", "limits": { "max_position": 1000 } },
+                    }),
+                ),
+                &superuser(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.result.unwrap()["DESK-A"]["limits"]["max_position"], 1000);
+        assert!(permissions.organization("pb1").unwrap().sub_account("DESK-A").is_some());
+
+        sub_accounts.record_fill("AAPL", 40, ("PB1", Some("DESK-A")), ("MM1", None));
+        let response = handler
+            .handle(request("get_sub_accounts", json!({ "org_id": "pb1" })))
+            .await
+            .unwrap();
+        let report = response.result.unwrap();
+        assert_eq!(report["DESK-A"]["positions"][0]["net"], 40);
+
+        let response = handler
+            .handle_from(
+                request(
+                    "admin_remove_sub_account",
+                    json!({ "org_id": "pb1", "account": "DESK-A" }),
+                ),
+                &superuser(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.result.unwrap(), json!({}));
+        let response = handler
+            .handle_from(
+                request(
+                    "admin_remove_sub_account",
+                    json!({ "org_id": "pb1", "account": "DESK-A" }),
+                ),
+                &superuser(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.error.unwrap().code, codes::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_logon_credential_not_disclosed() {
+        use romer_common::types::org::{Organization, OrganizationType};
+
+        let (tx, _rx) = mpsc::channel(8);
+        let organization = Organization::new(
+            "mm1".into(),
+            "Market Maker One".into(),
+            OrganizationType::MarketMaker,
+            "MM1".into(),
+            vec![0; 48],
+        );
+        let permissions = Arc::new(PermissionRegistry::from_organizations([organization]));
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx)
+            .with_permissions(permissions.clone())
+            .with_admin_access(superuser_access());
+
+        let response = handler
+            .handle_from(
+                request(
+                    "admin_set_logon_credential",
+                    json!({ "org_id": "mm1", "username": "mm1-engine", "password": "s3cret" }),
+                ),
+                &superuser(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.result.unwrap()["password_hash"], "");
+        assert!(permissions.organization("mm1").unwrap().logon_credential.unwrap().matches("mm1-engine", "s3cret"));
+
+        let response = handler
+            .handle(request("get_organization", json!({ "sender_comp_id": "MM1" })))
+            .await
+            .unwrap();
+        let credential = &response.result.unwrap()["logon_credential"];
+        assert_eq!(credential["username"], "mm1-engine");
+        assert_eq!(credential["salt"], "");
+        assert_eq!(credential["params"]["memory_kib"], 19_456);
+
+        let remove = || request("admin_remove_logon_credential", json!({ "org_id": "mm1" }));
+        handler.handle_from(remove(), &superuser()).await.unwrap().result.unwrap();
+        assert_eq!(handler.handle_from(remove(), &superuser()).await.unwrap().error.unwrap().code, codes::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_admin_disabled_without_roles() {
+        let (tx, _rx) = mpsc::channel(8);
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx);
+        let response = handler
+            .handle_from(request("admin_stats", Value::Null), &superuser())
+            .await
+            .unwrap();
+        assert_eq!(response.error.unwrap().code, codes::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_protocol_errors() {
+        let (tx, _rx) = mpsc::channel(8);
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx);
+
+        let response = handler.handle(request("no_such_method", Value::Null)).await.unwrap();
+        assert_eq!(response.error.unwrap().code, codes::METHOD_NOT_FOUND);
+
+        let response = handler.handle_body(b"{not json", &Caller::default()).await.unwrap();
+        assert!(response.contains(&codes::PARSE_ERROR.to_string()));
+
+        let notification = RpcRequest { id: None, ..request("get_balance", Value::Null) };
+        assert!(handler.handle(notification).await.is_none());
+    }
+}
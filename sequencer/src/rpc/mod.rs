@@ -0,0 +1,5 @@
+pub mod admin;
+pub mod api_keys;
+pub mod types;
+pub mod handler;
+pub mod server;
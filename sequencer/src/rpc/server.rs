@@ -0,0 +1,290 @@
+// src/rpc/server.rs
+
+use crate::rpc::handler::RpcHandler;
+use crate::rpc::types::Caller;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{self, BufReader as StdBufReader};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::{RootCertStore, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+use tracing::{debug, error, info, warn};
+
+/// Largest request body we accept
+pub const MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// Longest request line or header we read
+const MAX_LINE_LENGTH: u64 = 8 * 1024;
+
+/// Most headers one request may carry
+const MAX_HEADERS: usize = 64;
+
+/// Configuration for the JSON-RPC server
+#[derive(Debug, Clone)]
+pub struct RpcConfig {
+    /// Address to serve JSON-RPC on
+    pub bind_address: SocketAddr,
+    /// Maximum request body size in bytes
+    pub max_body_size: usize,
+}
+
+impl Default for RpcConfig {
+    fn default() -> Self {
+        Self {
+            bind_address: "127.0.0.1:9879".parse().unwrap(),
+            max_body_size: MAX_BODY_SIZE,
+        }
+    }
+}
+
+/// Serves JSON-RPC 2.0 over HTTP POST, or HTTPS when given a TLS acceptor
+pub struct RpcServer {
+    config: RpcConfig,
+    handler: RpcHandler,
+    tls: Option<TlsAcceptor>,
+}
+
+impl RpcServer {
+    pub fn new(config: RpcConfig, handler: RpcHandler) -> Self {
+        Self {
+            config,
+            handler,
+            tls: None,
+        }
+    }
+
+    /// Serve over TLS. Client certificates presented in the handshake
+    /// identify callers of the admin methods.
+    pub fn with_tls(mut self, tls: TlsAcceptor) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Accept connections until the listener fails
+    pub async fn run(self) -> std::io::Result<()> {
+        let listener = TcpListener::bind(self.config.bind_address).await?;
+        info!(address = %self.config.bind_address, "JSON-RPC server started");
+
+        loop {
+            let (stream, remote) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    error!(error = %e, "Failed to accept RPC connection");
+                    continue;
+                }
+            };
+
+            let handler = self.handler.clone();
+            let max_body_size = self.config.max_body_size;
+            let tls = self.tls.clone();
+            tokio::spawn(async move {
+                let caller = Caller {
+                    remote: Some(remote.ip()),
+                    ..Default::default()
+                };
+                let result = match tls {
+                    Some(tls) => match tls.accept(stream).await {
+                        Ok(stream) => {
+                            let certificate = stream
+                                .get_ref()
+                                .1
+                                .peer_certificates()
+                                .and_then(|chain| chain.first())
+                                .map(|cert| hex::encode(Sha256::digest(cert.as_ref())));
+                            let caller = Caller { certificate, ..caller };
+                            serve_connection(stream, caller, handler, max_body_size).await
+                        }
+                        Err(e) => Err(e),
+                    },
+                    None => serve_connection(stream, caller, handler, max_body_size).await,
+                };
+                if let Err(e) = result {
+                    debug!(remote = %remote, error = %e, "RPC connection closed");
+                }
+            });
+        }
+    }
+}
+
+/// Serves keep-alive HTTP requests on a single connection from `peer`
+async fn serve_connection<S: AsyncRead + AsyncWrite>(
+    stream: S,
+    peer: Caller,
+    handler: RpcHandler,
+    max_body_size: usize,
+) -> std::io::Result<()> {
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut reader = BufReader::new(reader);
+
+    loop {
+        // Request line
+        let line = match read_line(&mut reader).await {
+            Ok(Some(line)) => line,
+            Ok(None) => return Ok(()),
+            Err(e) => return refuse_oversized(&mut writer, e).await,
+        };
+        let is_post = line.starts_with("POST ");
+
+        // Headers
+        let mut content_length = 0usize;
+        let mut caller = peer.clone();
+        let mut headers = 0;
+        loop {
+            let header = match read_line(&mut reader).await {
+                Ok(Some(header)) => header,
+                Ok(None) => return Ok(()),
+                Err(e) => return refuse_oversized(&mut writer, e).await,
+            };
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            headers += 1;
+            if headers > MAX_HEADERS {
+                let e = io::Error::new(io::ErrorKind::InvalidData, "too many headers");
+                return refuse_oversized(&mut writer, e).await;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap_or(0);
+                } else if name.eq_ignore_ascii_case("x-api-key") {
+                    caller.api_key = Some(value.trim().to_string());
+                } else if name.eq_ignore_ascii_case("x-romer-admin-token") {
+                    caller.admin_token = Some(value.trim().to_string());
+                } else if name.eq_ignore_ascii_case("authorization") {
+                    if let Some(token) = value.trim().strip_prefix("Bearer ") {
+                        caller.api_key = Some(token.trim().to_string());
+                    }
+                }
+            }
+        }
+
+        // The body of anything but a POST is never read, so the connection
+        // can't be kept
+        if !is_post {
+            write_response(&mut writer, "405 Method Not Allowed", "").await?;
+            return Ok(());
+        }
+        if content_length > max_body_size {
+            warn!(content_length, "Rejecting oversized RPC request");
+            write_response(&mut writer, "413 Payload Too Large", "").await?;
+            return Ok(());
+        }
+
+        let mut body = vec![0u8; content_length];
+        reader.read_exact(&mut body).await?;
+
+        match handler.handle_body(&body, &caller).await {
+            Some(response) => write_response(&mut writer, "200 OK", &response).await?,
+            None => write_response(&mut writer, "204 No Content", "").await?,
+        }
+    }
+}
+
+/// Reads a line of at most `MAX_LINE_LENGTH` bytes, or `None` at the end
+/// of the stream
+async fn read_line<R: AsyncBufRead + Unpin>(reader: &mut R) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    if reader.take(MAX_LINE_LENGTH).read_line(&mut line).await? == 0 {
+        return Ok(None);
+    }
+    if !line.ends_with('\n') {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "request line or header too long"));
+    }
+    Ok(Some(line))
+}
+
+/// Answers a request whose head is too large, then closes the connection
+async fn refuse_oversized<W: AsyncWriteExt + Unpin>(writer: &mut W, e: io::Error) -> io::Result<()> {
+    warn!(error = %e, "Rejecting oversized RPC request head");
+    write_response(writer, "431 Request Header Fields Too Large", "").await?;
+    Err(e)
+}
+
+async fn write_response<W: AsyncWriteExt + Unpin>(
+    writer: &mut W,
+    status: &str,
+    body: &str,
+) -> std::io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    writer.write_all(response.as_bytes()).await
+}
+
+/// TLS acceptor serving `cert` with `key`, both PEM. With `client_ca`,
+/// clients may present a certificate issued by it; those that do not are
+/// still served, as only the admin methods ask for one.
+pub fn load_tls(cert: &Path, key: &Path, client_ca: Option<&Path>) -> io::Result<TlsAcceptor> {
+    let invalid = |e: tokio_rustls::rustls::Error| io::Error::new(io::ErrorKind::InvalidData, e);
+    let certs = rustls_pemfile::certs(&mut StdBufReader::new(File::open(cert)?)).collect::<Result<Vec<_>, _>>()?;
+    let key = rustls_pemfile::private_key(&mut StdBufReader::new(File::open(key)?))?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("no private key in {}", key.display())))?;
+
+    let builder = ServerConfig::builder();
+    let config = match client_ca {
+        Some(client_ca) => {
+            let mut roots = RootCertStore::empty();
+            for ca in rustls_pemfile::certs(&mut StdBufReader::new(File::open(client_ca)?)) {
+                roots.add(ca?).map_err(invalid)?;
+            }
+            let verifier = WebPkiClientVerifier::builder(Arc::new(roots))
+                .allow_unauthenticated()
+                .build()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            builder.with_client_cert_verifier(verifier).with_single_cert(certs, key)
+        }
+        None => builder.with_no_client_auth().with_single_cert(certs, key),
+    }
+    .map_err(invalid)?;
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::handler::RpcState;
+    use tokio::sync::mpsc;
+
+    /// Writes `request` to a fresh connection and reads until the server
+    /// closes it
+    async fn exchange(request: &[u8]) -> String {
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        let (tx, _rx) = mpsc::channel(1);
+        let handler = RpcHandler::new(Arc::new(RpcState::new()), tx);
+        let serving = tokio::spawn(serve_connection(server, Caller::default(), handler, MAX_BODY_SIZE));
+        let (mut reader, mut writer) = tokio::io::split(client);
+        writer.write_all(request).await.unwrap();
+        let mut response = String::new();
+        reader.read_to_string(&mut response).await.unwrap();
+        let _ = serving.await;
+        response
+    }
+
+    #[tokio::test]
+    async fn test_non_post_closes_connection() {
+        // The unread body must not be taken for the next request
+        let response = exchange(b"GET / HTTP/1.1\r\nContent-Length: 22\r\n\r\nPOST / HTTP/1.1\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 405"));
+        assert_eq!(response.matches("HTTP/1.1").count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_head_refused() {
+        let mut request = b"POST / HTTP/1.1\r\nX-Padding: ".to_vec();
+        request.extend(vec![b'a'; MAX_LINE_LENGTH as usize]);
+        assert!(exchange(&request).await.starts_with("HTTP/1.1 431"));
+
+        let headers = "X-Padding: a\r\n".repeat(MAX_HEADERS + 1);
+        let request = format!("POST / HTTP/1.1\r\n{}\r\n", headers);
+        assert!(exchange(request.as_bytes()).await.starts_with("HTTP/1.1 431"));
+    }
+}
@@ -0,0 +1,497 @@
+// src/rpc/types.rs
+
+use crate::market::candles::Interval;
+use crate::market::obligations::Obligation;
+use chrono::{DateTime, NaiveDate, Utc};
+use romer_common::types::address::Address;
+use romer_common::types::attestation::SignedAttestation;
+use romer_common::types::bridge::{BridgeSignature, Withdrawal};
+use romer_common::types::envelope::SignedTransaction;
+use romer_common::types::instrument::InstrumentOverride;
+use romer_common::types::org::{SubAccount, SymbolPermission};
+use romer_common::types::rejection::RejectReason;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::net::IpAddr;
+use thiserror::Error;
+
+/// JSON-RPC protocol version we speak
+pub const JSONRPC_VERSION: &str = "2.0";
+
+/// Standard JSON-RPC 2.0 error codes
+pub mod codes {
+    pub const PARSE_ERROR: i64 = -32700;
+    pub const INVALID_REQUEST: i64 = -32600;
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    pub const INVALID_PARAMS: i64 = -32602;
+    pub const INTERNAL_ERROR: i64 = -32603;
+    /// Application defined: the transaction was rejected
+    pub const TRANSACTION_REJECTED: i64 = -32000;
+    /// Application defined: the requested item does not exist
+    pub const NOT_FOUND: i64 = -32001;
+    /// Application defined: the API key is missing, unknown or revoked
+    pub const UNAUTHORIZED: i64 = -32002;
+    /// Application defined: the caller exceeded its rate limit
+    pub const RATE_LIMITED: i64 = -32003;
+}
+
+/// Who sent a request, as far as the server could tell
+#[derive(Debug, Clone, Default)]
+pub struct Caller {
+    /// Secret from the `X-API-Key` header, or an `Authorization: Bearer` one
+    pub api_key: Option<String>,
+    pub remote: Option<IpAddr>,
+    /// Hex SHA-256 fingerprint of the client certificate presented over TLS
+    pub certificate: Option<String>,
+    /// `AdminToken` from the `X-Romer-Admin-Token` header
+    pub admin_token: Option<String>,
+}
+
+/// An incoming JSON-RPC request
+#[derive(Debug, Clone, Deserialize)]
+pub struct RpcRequest {
+    pub jsonrpc: String,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+    /// Absent for notifications, which receive no response
+    #[serde(default)]
+    pub id: Option<Value>,
+}
+
+/// An outgoing JSON-RPC response
+#[derive(Debug, Clone, Serialize)]
+pub struct RpcResponse {
+    pub jsonrpc: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcErrorObject>,
+    pub id: Value,
+}
+
+impl RpcResponse {
+    pub fn success(id: Value, result: Value) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION,
+            result: Some(result),
+            error: None,
+            id,
+        }
+    }
+
+    pub fn failure(id: Value, error: RpcError) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION,
+            result: None,
+            error: Some(error.into()),
+            id,
+        }
+    }
+}
+
+/// Error member of a JSON-RPC response
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RpcErrorObject {
+    pub code: i64,
+    pub message: String,
+    /// Catalogue reason of a rejection, the same one FIX clients are sent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<RejectionData>,
+}
+
+/// `data` member of a rejection
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct RejectionData {
+    /// Numeric code of the reason
+    pub reason: u16,
+    pub name: RejectReason,
+}
+
+/// Errors returned by RPC methods
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum RpcError {
+    #[error("Parse error: {0}")]
+    Parse(String),
+
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+
+    #[error("Method not found: {0}")]
+    MethodNotFound(String),
+
+    #[error("Invalid params: {0}")]
+    InvalidParams(String),
+
+    #[error("Transaction rejected: {0}: {1}")]
+    Rejected(RejectReason, String),
+
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    #[error("Rate limited: {0}")]
+    RateLimited(String),
+
+    #[error("Internal error: {0}")]
+    Internal(String),
+}
+
+impl RpcError {
+    pub fn code(&self) -> i64 {
+        match self {
+            RpcError::Parse(_) => codes::PARSE_ERROR,
+            RpcError::InvalidRequest(_) => codes::INVALID_REQUEST,
+            RpcError::MethodNotFound(_) => codes::METHOD_NOT_FOUND,
+            RpcError::InvalidParams(_) => codes::INVALID_PARAMS,
+            RpcError::Rejected(..) => codes::TRANSACTION_REJECTED,
+            RpcError::NotFound(_) => codes::NOT_FOUND,
+            RpcError::Unauthorized(_) => codes::UNAUTHORIZED,
+            RpcError::RateLimited(_) => codes::RATE_LIMITED,
+            RpcError::Internal(_) => codes::INTERNAL_ERROR,
+        }
+    }
+}
+
+impl From<RpcError> for RpcErrorObject {
+    fn from(error: RpcError) -> Self {
+        let data = match &error {
+            RpcError::Rejected(reason, _) => Some(RejectionData {
+                reason: reason.code(),
+                name: *reason,
+            }),
+            _ => None,
+        };
+        Self {
+            code: error.code(),
+            message: error.to_string(),
+            data,
+        }
+    }
+}
+
+/// Lifecycle of a submitted transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionStatus {
+    /// Accepted and waiting to be included in a block
+    Pending,
+    /// Included in the given block
+    Included,
+    /// Rejected after acceptance, e.g. expired before inclusion
+    Dropped,
+}
+
+/// What `get_transaction` returns
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionRecord {
+    pub hash: String,
+    pub transaction: SignedTransaction,
+    pub status: TransactionStatus,
+    pub block_id: Option<u64>,
+}
+
+/// Params of `get_balance`
+#[derive(Debug, Clone, Deserialize)]
+pub struct BalanceParams {
+    pub address: Address,
+}
+
+/// Params of `request_faucet`
+#[derive(Debug, Clone, Deserialize)]
+pub struct FaucetParams {
+    pub address: Address,
+    /// Defaults to the faucet's drip
+    #[serde(default)]
+    pub amount: Option<u64>,
+}
+
+/// Params of `get_block`
+#[derive(Debug, Clone, Deserialize)]
+pub struct BlockParams {
+    pub block_id: u64,
+}
+
+/// Params of `get_transaction`
+#[derive(Debug, Clone, Deserialize)]
+pub struct TransactionParams {
+    pub hash: String,
+}
+
+/// Params of `submit_transaction` and `simulate`
+#[derive(Debug, Clone, Deserialize)]
+pub struct SubmitParams {
+    pub transaction: SignedTransaction,
+}
+
+/// Params of `submit_attestation`
+#[derive(Debug, Clone, Deserialize)]
+pub struct AttestationParams {
+    pub attestation: SignedAttestation,
+}
+
+/// Params of `get_attestation_status`; every registered validator without
+/// `validator`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AttestationStatusParams {
+    #[serde(default)]
+    pub validator: Option<Address>,
+}
+
+/// Params of `admin_engage_kill_switch` and `admin_release_kill_switch`
+#[derive(Debug, Clone, Deserialize)]
+pub struct KillSwitchParams {
+    pub firm: String,
+    /// Why the switch was engaged, recorded in the audit trail
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Params of `admin_set_mm_obligation` and, without `obligation`,
+/// `admin_remove_mm_obligation`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ObligationParams {
+    pub sender_comp_id: String,
+    pub symbol: String,
+    #[serde(default)]
+    pub obligation: Option<Obligation>,
+}
+
+/// Params of `get_reference_price` and, with `price`, `admin_set_reference_price`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReferencePriceParams {
+    pub symbol: String,
+    #[serde(default)]
+    pub price: Option<f64>,
+}
+
+/// Params of `get_candles`
+#[derive(Debug, Clone, Deserialize)]
+pub struct CandleParams {
+    pub symbol: String,
+    pub interval: Interval,
+    /// Only candles opening at or after this time
+    #[serde(default)]
+    pub since: Option<DateTime<Utc>>,
+    /// Most candles returned, the latest ones; 100 without
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// Params of `get_daily_stats`
+#[derive(Debug, Clone, Deserialize)]
+pub struct DailyStatsParams {
+    pub symbol: String,
+    /// Trading days returned, the latest ones; 30 without
+    #[serde(default)]
+    pub days: Option<usize>,
+}
+
+/// Params of `get_depth`
+#[derive(Debug, Clone, Deserialize)]
+pub struct DepthParams {
+    pub symbol: String,
+    /// Levels per side, the full book without
+    #[serde(default)]
+    pub depth: Option<usize>,
+}
+
+/// Params of `admin_reconciliation_report` and, with `day`,
+/// `admin_run_reconciliation`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ReconciliationParams {
+    /// Trading day, the latest built without
+    #[serde(default)]
+    pub day: Option<NaiveDate>,
+    /// Only this firm's report
+    #[serde(default)]
+    pub firm: Option<String>,
+}
+
+/// Params of `get_allocations`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AllocationParams {
+    /// Only allocations sent by or given up to this SenderCompID
+    #[serde(default)]
+    pub firm: Option<String>,
+}
+
+/// Params of `admin_settle` and, optionally, `admin_settlement_receipts`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SettleParams {
+    #[serde(default)]
+    pub day: Option<NaiveDate>,
+}
+
+/// Params of `get_bridge_mint`
+#[derive(Debug, Clone, Deserialize)]
+pub struct BridgeDepositParams {
+    pub source_chain: String,
+    pub deposit_id: String,
+}
+
+/// Params of `submit_bridge_attestation`
+#[derive(Debug, Clone, Deserialize)]
+pub struct BridgeAttestationParams {
+    pub source_chain: String,
+    pub deposit_id: String,
+    pub signature: BridgeSignature,
+}
+
+/// Params of `submit_bridge_withdrawal`
+#[derive(Debug, Clone, Deserialize)]
+pub struct BridgeWithdrawalParams {
+    pub withdrawal: Withdrawal,
+    pub signature: BridgeSignature,
+}
+
+/// Params of `admin_schedule_instrument_override`
+#[derive(Debug, Clone, Deserialize)]
+pub struct InstrumentOverrideParams {
+    pub symbol: String,
+    pub overrides: InstrumentOverride,
+    /// Block the override applies from, the next block without
+    #[serde(default)]
+    pub activation_height: Option<u64>,
+}
+
+/// Params of `get_instrument`
+#[derive(Debug, Clone, Deserialize)]
+pub struct InstrumentParams {
+    pub symbol: String,
+    /// Height to read the parameters at, the next block without
+    #[serde(default)]
+    pub height: Option<u64>,
+}
+
+/// Params of `admin_set_log_level`
+#[derive(Debug, Clone, Deserialize)]
+pub struct LogLevelParams {
+    /// Level directives, e.g. `debug` or `info,romer_sequencer=trace`
+    pub level: String,
+}
+
+/// Params of `admin_set_log_directive`
+#[derive(Debug, Clone, Deserialize)]
+pub struct LogDirectiveParams {
+    /// Level of one module, e.g. `sequencer::fix=debug`
+    pub directive: String,
+}
+
+/// Params of `admin_enter_drain_mode`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DrainParams {
+    /// Why order entry is closing, recorded in the audit trail
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Params of `admin_grant_symbol_permission` and, without `permission`,
+/// `admin_revoke_symbol_permission`
+#[derive(Debug, Clone, Deserialize)]
+pub struct SymbolPermissionParams {
+    pub org_id: String,
+    pub symbol: String,
+    #[serde(default)]
+    pub permission: Option<SymbolPermission>,
+}
+
+/// Params of `admin_set_sub_account` and, without `sub_account`,
+/// `admin_remove_sub_account`
+#[derive(Debug, Clone, Deserialize)]
+pub struct SubAccountParams {
+    pub org_id: String,
+    /// Account (1) the sub-account's orders carry
+    pub account: String,
+    #[serde(default)]
+    pub sub_account: Option<SubAccount>,
+}
+
+/// Params of `admin_set_logon_credential`
+#[derive(Debug, Clone, Deserialize)]
+pub struct LogonCredentialParams {
+    pub org_id: String,
+    /// Username (553) the organization's Logons carry
+    pub username: String,
+    /// Password (554); only its Argon2id hash is kept
+    pub password: String,
+    /// Accept Logons authenticated by the password alone, without a
+    /// session key signature
+    #[serde(default)]
+    pub allow_unsigned: bool,
+}
+
+/// Params of `get_sub_accounts`
+#[derive(Debug, Clone, Deserialize)]
+pub struct SubAccountLookupParams {
+    pub org_id: String,
+    /// Only this sub-account
+    #[serde(default)]
+    pub account: Option<String>,
+}
+
+/// Params of `admin_symbol_permissions` and `admin_remove_logon_credential`
+#[derive(Debug, Clone, Deserialize)]
+pub struct OrganizationParams {
+    pub org_id: String,
+}
+
+/// Params of `get_organization`, by id or by SenderCompID
+#[derive(Debug, Clone, Deserialize)]
+pub struct OrganizationLookupParams {
+    #[serde(default)]
+    pub org_id: Option<String>,
+    #[serde(default)]
+    pub sender_comp_id: Option<String>,
+}
+
+/// Params of `get_order`, by OrderID or by SenderCompID and ClOrdID
+#[derive(Debug, Clone, Deserialize)]
+pub struct OrderLookupParams {
+    #[serde(default)]
+    pub order_id: Option<String>,
+    #[serde(default)]
+    pub sender_comp_id: Option<String>,
+    #[serde(default)]
+    pub cl_ord_id: Option<String>,
+}
+
+/// Params of `admin_broadcast_news`, and of `get_news`, which reads `limit`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct NewsParams {
+    pub headline: Option<String>,
+    pub text: String,
+    /// SenderCompIDs the notice is for; every session when empty
+    pub recipients: Vec<String>,
+    pub limit: Option<usize>,
+}
+
+/// Params of `admin_create_api_key`; limits default to the configured ones
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateApiKeyParams {
+    pub label: String,
+    #[serde(default)]
+    pub rate_per_sec: Option<u32>,
+    #[serde(default)]
+    pub burst: Option<u32>,
+}
+
+/// Params of `admin_revoke_api_key`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiKeyIdParams {
+    pub id: String,
+}
+
+/// Result of `simulate`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SimulationResult {
+    /// Whether the transaction would be accepted for inclusion
+    pub accepted: bool,
+    /// Reason for rejection, if any
+    pub error: Option<String>,
+    /// Sender balance after the transaction, for transfers
+    pub balance_after: Option<u64>,
+}
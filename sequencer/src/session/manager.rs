@@ -1,8 +1,27 @@
-use super::state::{Session, SessionState, SessionError};
-use crate::fix::types::ValidatedMessage;
-use tokio::sync::mpsc;
+use super::state::{
+    LogoutTimers, ResumePoint, SequenceNegotiation, Session, SessionState, SessionError, TimerAction,
+};
+use crate::events::bus::EventBus;
+use crate::events::types::SequencerEvent;
+use crate::fix::heartbeat::HeartbeatBounds;
+use crate::fix::reports::{OrderReject, OrderStatusReport};
+use crate::fix::types::{MessageType, ValidatedMessage};
+use crate::market::orders::{OrderEntry, OrderStore};
+use crate::market::registry::MarketRegistry;
+use crate::risk::cl_ord_ids::{ClOrdIdError, ClOrdIdRegistry};
+use crate::risk::drain::DrainMode;
+use crate::risk::kill_switch::KillSwitch;
+use crate::risk::order_checks::OrderChecks;
+use crate::risk::permissions::PermissionRegistry;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex as AsyncMutex};
 use tokio::time::{self, Duration};
 use dashmap::DashMap;
+use romer_common::fix::admin::AdminMessage;
+use romer_common::storage::metrics::CapacityGate;
+use romer_common::types::org::SymbolPermission;
+use romer_common::utils::clock::{system_clock, SharedClock};
 use tracing::{info, warn, error};
 use uuid::Uuid;
 
@@ -10,54 +29,279 @@ use uuid::Uuid;
 pub struct SessionManager {
     /// Active sessions indexed by session ID - using DashMap for thread-safe concurrent access
     sessions: DashMap<Uuid, Session>,
-    /// Sessions indexed by sender comp ID for quick lookup during message processing
-    sender_index: DashMap<String, Uuid>,
+    /// Sessions indexed by (target, sender) comp IDs for quick lookup during
+    /// message processing. A sender holds one session per market.
+    sender_index: DashMap<(String, String), Uuid>,
     /// Channel for forwarding validated messages to the batch manager
     message_tx: mpsc::Sender<ValidatedMessage>,
+    /// Time source for session timing
+    clock: SharedClock,
+    /// Session and order events are published here
+    events: EventBus,
+    /// Blocks order flow of firms whose kill switch is engaged
+    kill_switch: Option<Arc<KillSwitch>>,
+    /// Symbol permissions required for orders and market data requests
+    permissions: Option<Arc<PermissionRegistry>>,
+    /// ClOrdIDs already used today, for rejecting duplicate orders
+    cl_ord_ids: Option<Arc<ClOrdIdRegistry>>,
+    /// Lifecycle state of the orders entered
+    orders: Option<Arc<OrderStore>>,
+    /// Markets sessions are routed to by TargetCompID
+    markets: Option<Arc<MarketRegistry>>,
+    /// Closed while storage is below its capacity floor
+    capacity: Option<CapacityGate>,
+    /// Rejects new orders as market closed while draining
+    drain: Option<Arc<DrainMode>>,
+    /// Pre-trade checks orders pass before they are accepted
+    order_checks: Option<Arc<OrderChecks>>,
+    /// HeartBtInt values accepted at Logon
+    heartbeat_bounds: HeartbeatBounds,
+    /// Session level messages we originate, for the connection to write
+    outbound: Option<mpsc::Sender<OutboundMessage>>,
+    /// Grace period and confirmation timeout of the logout handshake
+    logout_timers: LogoutTimers,
+    /// Where closed sessions left off, by (target, sender) comp IDs
+    resume_points: DashMap<(String, String), ResumePoint>,
+    /// How long after closing a session may be resumed
+    resume_window: Duration,
+    /// Each session's write turn. Messages are numbered under the session's
+    /// entry and delivered once it is released, so the turn keeps them in
+    /// sequence order without the entry being held while the outbound
+    /// channel is full.
+    writers: DashMap<Uuid, Arc<AsyncMutex<()>>>,
 }
 
+/// Closed sessions may be resumed this long by default
+const DEFAULT_RESUME_WINDOW: Duration = Duration::from_secs(300);
+
+/// An encoded message to write to a session's connection
+#[derive(Debug, Clone)]
+pub struct OutboundMessage {
+    pub session_id: Uuid,
+    /// Empty when only closing
+    pub raw: Vec<u8>,
+    /// The session is over: close its connection once `raw` is written
+    pub close: bool,
+}
+
+/// Messages a session queued while its entry was locked, delivered once
+/// the entry is released
+#[derive(Default)]
+struct Outbox(Vec<OutboundMessage>);
+
+impl Outbox {
+    fn push(&mut self, session_id: Uuid, raw: Vec<u8>, close: bool) {
+        self.0.push(OutboundMessage { session_id, raw, close });
+    }
+}
+
+/// MassCancelRequestType (530) value asking to cancel all orders
+const MASS_CANCEL_ALL_ORDERS: &str = "7";
+
 impl SessionManager {
     /// Create a new session manager
     pub fn new(message_tx: mpsc::Sender<ValidatedMessage>) -> Self {
+        Self::with_clock(message_tx, system_clock())
+    }
+
+    /// Create a session manager driven by `clock`
+    pub fn with_clock(message_tx: mpsc::Sender<ValidatedMessage>, clock: SharedClock) -> Self {
         Self {
             sessions: DashMap::new(),
             sender_index: DashMap::new(),
             message_tx,
+            clock,
+            events: EventBus::default(),
+            kill_switch: None,
+            permissions: None,
+            cl_ord_ids: None,
+            orders: None,
+            markets: None,
+            capacity: None,
+            drain: None,
+            order_checks: None,
+            heartbeat_bounds: HeartbeatBounds::default(),
+            outbound: None,
+            logout_timers: LogoutTimers::default(),
+            resume_points: DashMap::new(),
+            resume_window: DEFAULT_RESUME_WINDOW,
+            writers: DashMap::new(),
         }
     }
 
+    /// Enforce `kill_switch` on incoming orders, and let sessions trigger it
+    /// for their own firm with an OrderMassCancelRequest
+    pub fn with_kill_switch(mut self, kill_switch: Arc<KillSwitch>) -> Self {
+        self.kill_switch = Some(kill_switch);
+        self
+    }
+
+    /// Require `Trade` permission on the symbol of incoming orders and
+    /// `ViewOnly` on the symbol of market data requests
+    pub fn with_permissions(mut self, permissions: Arc<PermissionRegistry>) -> Self {
+        self.permissions = Some(permissions);
+        self
+    }
+
+    /// Reject orders whose ClOrdID the sender already used this trading day
+    pub fn with_cl_ord_ids(mut self, cl_ord_ids: Arc<ClOrdIdRegistry>) -> Self {
+        self.cl_ord_ids = Some(cl_ord_ids);
+        self
+    }
+
+    /// Record orders accepted or rejected in `orders`
+    pub fn with_orders(mut self, orders: Arc<OrderStore>) -> Self {
+        self.orders = Some(orders);
+        self
+    }
+
+    /// Route sessions to `markets` by TargetCompID. Each market's
+    /// instruments are enforced and its own ClOrdIDs used in place of
+    /// `with_cl_ord_ids`.
+    pub fn with_markets(mut self, markets: Arc<MarketRegistry>) -> Self {
+        self.markets = Some(markets);
+        self
+    }
+
+    /// Refuse new orders while `capacity` is paused
+    pub fn with_capacity_gate(mut self, capacity: CapacityGate) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    /// Reject new orders while `drain` is draining; cancels still pass
+    pub fn with_drain_mode(mut self, drain: Arc<DrainMode>) -> Self {
+        self.drain = Some(drain);
+        self
+    }
+
+    /// Run `checks` on orders that passed the session's entitlements
+    pub fn with_order_checks(mut self, checks: Arc<OrderChecks>) -> Self {
+        self.order_checks = Some(checks);
+        self
+    }
+
+    /// Accept HeartBtInt values within `bounds` at Logon
+    pub fn with_heartbeat_bounds(mut self, bounds: HeartbeatBounds) -> Self {
+        self.heartbeat_bounds = bounds;
+        self
+    }
+
+    /// Let counterparties resume a session's sequence numbers for `window`
+    /// after it closed
+    pub fn with_resume_window(mut self, window: Duration) -> Self {
+        self.resume_window = window;
+        self
+    }
+
+    /// Send the messages sessions originate to `outbound`: Logon and Logout
+    /// replies, Heartbeats and TestRequests, and reports
+    pub fn with_outbound(mut self, outbound: mpsc::Sender<OutboundMessage>) -> Self {
+        self.outbound = Some(outbound);
+        self
+    }
+
+    /// Run the logout handshake with `timers`
+    pub fn with_logout_timers(mut self, timers: LogoutTimers) -> Self {
+        self.logout_timers = timers;
+        self
+    }
+
+    /// Publish session and order events on `events`
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = events;
+        self
+    }
+
     /// Start the session management background tasks
     pub async fn run(&self) {
         let mut interval = time::interval(Duration::from_secs(1));
-        
+        let mut events = self.events.subscribe();
+
         loop {
-            interval.tick().await;
-            self.check_sessions().await;
+            tokio::select! {
+                _ = interval.tick() => self.check_sessions().await,
+                Some(event) = events.recv() => {
+                    if let SequencerEvent::KillSwitchEngaged { firm, sender_comp_ids, .. } = &*event {
+                        self.logout_firm(firm, sender_comp_ids).await;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Logs out every active session of a firm whose kill switch was engaged
+    async fn logout_firm(&self, firm: &str, sender_comp_ids: &[String]) {
+        // Every market the firm's comp IDs are logged on to
+        let session_ids: Vec<Uuid> = self
+            .sender_index
+            .iter()
+            .filter(|entry| sender_comp_ids.contains(&entry.key().1))
+            .map(|entry| *entry.value())
+            .collect();
+        for session_id in session_ids {
+            let logged_out = self
+                .write(session_id, |session, outbox| {
+                    if session.state != SessionState::Active {
+                        return Ok(());
+                    }
+                    warn!(session_id = ?session_id, firm, "Logging out session, kill switch engaged");
+                    self.logout_internal(session, "kill switch", outbox)
+                })
+                .await;
+            match logged_out {
+                Ok(()) | Err(SessionError::NotFound(_)) => {}
+                Err(e) => error!(session_id = ?session_id, error = %e, "Failed to log out session"),
+            }
         }
     }
 
-    /// Create a new session for a market maker
+    /// Create a new session for a market maker, running its heartbeat at
+    /// the HeartBtInt (108) requested in its Logon if within bounds
     /// Returns the session ID if successful
     pub fn create_session(
         &self,
         sender_comp_id: String,
         target_comp_id: String,
-        heartbeat_interval: u32,
+        requested_heartbeat: Option<&str>,
         public_key: Vec<u8>,
     ) -> Result<Uuid, SessionError> {
-        // Check for existing session for this sender
-        if let Some(existing_id) = self.sender_index.get(&sender_comp_id) {
+        let heartbeat_interval = self
+            .heartbeat_bounds
+            .negotiate(requested_heartbeat)
+            .map_err(|e| SessionError::InvalidHeartbeat(e.to_string()))?;
+
+        let market = match &self.markets {
+            Some(markets) => Some(
+                markets
+                    .route(&target_comp_id)
+                    .map_err(|e| SessionError::AuthenticationFailed(e.to_string()))?,
+            ),
+            None => None,
+        };
+
+        // Check for existing session for this sender on this market
+        let key = (target_comp_id.clone(), sender_comp_id.clone());
+        // Copied out, as the maps cannot be written while their entries are held
+        let existing_id = self.sender_index.get(&key).map(|id| *id.value());
+        if let Some(existing_id) = existing_id {
+            let existing_state = self.sessions.get(&existing_id).map(|existing| existing.state);
             // Allow new session if the existing one is terminated
-            if let Some(existing) = self.sessions.get(existing_id.value()) {
-                if existing.state != SessionState::Terminated {
-                    return Err(SessionError::AuthenticationFailed(
-                        format!("Sender {} already has an active session", sender_comp_id)
-                    ));
-                }
-                // Clean up terminated session
-                self.sessions.remove(existing_id.value());
-                self.sender_index.remove(&sender_comp_id);
+            if existing_state.is_some_and(|state| state != SessionState::Terminated) {
+                return Err(SessionError::AuthenticationFailed(
+                    format!("Sender {} already has an active session", sender_comp_id)
+                ));
             }
+            // Clean up terminated session
+            self.sessions.remove(&existing_id);
+            self.writers.remove(&existing_id);
+            self.sender_index.remove(&key);
+        }
+
+        if let Some(market) = &market {
+            market
+                .open_session(&sender_comp_id, self.clock.now())
+                .map_err(|e| SessionError::AuthenticationFailed(e.to_string()))?;
         }
 
         // Create and store new session
@@ -66,132 +310,634 @@ impl SessionManager {
             target_comp_id,
             heartbeat_interval,
             public_key,
+            self.clock.now(),
         );
         
         let session_id = session.session_id;
+        self.events.publish(SequencerEvent::SessionOpened {
+            session_id,
+            sender_comp_id: sender_comp_id.clone(),
+            at: session.created_at,
+        });
         
         // Store both primary and index references
         self.sessions.insert(session_id, session);
-        self.sender_index.insert(sender_comp_id, session_id);
+        self.writers.insert(session_id, Arc::default());
+        self.sender_index.insert(key, session_id);
         
-        info!(session_id = ?session_id, "Created new session");
+        info!(session_id = ?session_id, heartbeat_interval, "Created new session");
         Ok(session_id)
     }
 
+    /// Logs a counterparty on, resuming the sequence numbers its last
+    /// session on the market left off at if it closed within the resume
+    /// window and the Logon does not set ResetSeqNumFlag (141). The Logon is
+    /// answered with the NextExpectedMsgSeqNum (789) we expect, followed by
+    /// a gap fill for our messages the counterparty missed and a
+    /// ResendRequest for its messages we missed.
+    pub async fn logon(
+        &self,
+        sender_comp_id: String,
+        target_comp_id: String,
+        requested_heartbeat: Option<&str>,
+        public_key: Vec<u8>,
+        sequences: SequenceNegotiation,
+    ) -> Result<Uuid, SessionError> {
+        let key = (target_comp_id.clone(), sender_comp_id.clone());
+        let session_id = self.create_session(sender_comp_id, target_comp_id, requested_heartbeat, public_key)?;
+
+        let now = self.clock.now();
+        let resume = self
+            .resume_points
+            .get(&key)
+            .map(|resume| *resume)
+            .filter(|resume| (now - resume.closed_at).to_std().unwrap_or_default() <= self.resume_window);
+        self.write(session_id, |session, outbox| {
+            let recovery = match session.negotiate_sequences(resume, sequences) {
+                Ok(recovery) => recovery,
+                Err(e) => {
+                    warn!(session_id = ?session_id, error = %e, "Logon sequence negotiation failed");
+                    self.terminate_session_internal(session, &e.to_string(), outbox)?;
+                    return Err(e);
+                }
+            };
+            self.resume_points.remove(&key);
+            session.last_received = now;
+            session.transition_to(SessionState::Authenticating)?;
+            session.transition_to(SessionState::Active)?;
+            info!(
+                session_id = ?session_id,
+                resumed = resume.is_some() && !sequences.reset,
+                next_incoming_seq = session.next_incoming_seq,
+                next_outgoing_seq = session.next_outgoing_seq,
+                "Session logged on"
+            );
+
+            let ack_seq = session.next_outgoing_seq;
+            let logon = AdminMessage::Logon {
+                heart_bt_int: session.heartbeat_interval,
+                reset_seq_num: sequences.reset,
+                next_expected_msg_seq_num: session.next_incoming_seq,
+            };
+            self.send_admin(session, logon, outbox);
+            if let Some(from) = recovery.gap_fill_from {
+                // Sent as a resend of `from`, covering everything up to the Logon
+                let gap_fill = AdminMessage::SequenceReset { new_seq_no: ack_seq, gap_fill: true };
+                self.send_admin_at(session, gap_fill, from, outbox);
+            }
+            if let Some((begin, end)) = recovery.resend {
+                self.send_admin(session, AdminMessage::ResendRequest { begin, end }, outbox);
+            }
+            // Open orders are reported unsolicited so the counterparty can
+            // rebuild its order state after reconnecting
+            if let Some(orders) = &self.orders {
+                for order in orders.open_orders(&session.sender_comp_id) {
+                    let report = OrderStatusReport {
+                        sender_comp_id: session.target_comp_id.clone(),
+                        target_comp_id: session.sender_comp_id.clone(),
+                        cl_ord_id: order.entry.cl_ord_id.clone(),
+                        symbol: order.entry.symbol.clone(),
+                        side: order.entry.side.clone(),
+                        order: Some(order),
+                        ord_status_req_id: None,
+                        unsolicited: true,
+                    };
+                    self.send_report(session, &report, outbox);
+                }
+            }
+            Ok(session_id)
+        })
+        .await
+    }
+
     /// Handle an incoming message for a specific session
     pub async fn handle_message(
         &self,
         session_id: Uuid,
         message: ValidatedMessage,
     ) -> Result<(), SessionError> {
-        // Get and verify session exists
-        let mut session = self.sessions.get_mut(&session_id)
-            .ok_or_else(|| {
-                error!(session_id = ?session_id, "Session not found");
-                SessionError::NotFound(session_id)
-            })?;
-            
-        // Verify session is in a state to accept messages
-        match session.state {
-            SessionState::Active => {},
-            state => {
-                error!(session_id = ?session_id, state = ?state, "Session not active");
-                return Err(SessionError::InvalidState(state));
-            }
+        // A Logout either confirms ours or starts the handshake
+        if message.msg_type == MessageType::Logout {
+            return self
+                .write(session_id, |session, outbox| self.handle_logout(session, &message, outbox))
+                .await;
         }
 
         // Update session sequence numbers and timing
-        session.message_received(message.msg_seq_num)?;
+        let now = self.clock.now();
+        {
+            // Get and verify session exists
+            let mut session = self.sessions.get_mut(&session_id)
+                .ok_or_else(|| {
+                    error!(session_id = ?session_id, "Session not found");
+                    SessionError::NotFound(session_id)
+                })?;
+
+            // Verify session is in a state to accept messages
+            match session.state {
+                SessionState::Active => {},
+                state => {
+                    error!(session_id = ?session_id, state = ?state, "Session not active");
+                    return Err(SessionError::InvalidState(state));
+                }
+            }
+
+            // A SequenceReset moves the sequence number we expect forward,
+            // filling the gap it covers
+            if message.msg_type == MessageType::SequenceReset {
+                let new_seq_no = message.field(36).and_then(|seq| seq.parse().ok()).unwrap_or(0);
+                session.last_received = now;
+                session.pending_test_request = None;
+                session.next_incoming_seq = session.next_incoming_seq.max(new_seq_no);
+                return Ok(());
+            }
+            session.message_received(message.msg_seq_num, now)?;
+        }
+
+        let is_order = message.msg_type == MessageType::NewOrderSingle;
+        let mut result = self
+            .check_entitlements(&message)
+            .and_then(|_| self.check_order(&message));
+        if result.is_ok() && is_order {
+            result = self.record_cl_ord_id(&message).await;
+        }
+        if is_order {
+            self.record_order(&message, result.as_ref().err()).await;
+        }
+        if let Err(e) = result {
+            // Refused orders are answered with an ExecutionReport
+            if is_order {
+                self.events.publish(SequencerEvent::OrderRejected {
+                    sender_comp_id: message.sender_comp_id.clone(),
+                    msg_seq_num: message.msg_seq_num,
+                    reason: e.to_string(),
+                    at: now,
+                });
+                self.write(session_id, |session, outbox| {
+                    self.reject_order(session, &message, &e, outbox);
+                    Ok(())
+                })
+                .await?;
+            }
+            return Err(e);
+        }
+        // Session level messages stop here; a TestRequest is answered with
+        // a Heartbeat echoing its TestReqID
+        match message.msg_type {
+            MessageType::Heartbeat => return Ok(()),
+            MessageType::ResendRequest => {
+                // Session messages are not kept for resending, so the
+                // range asked for is filled with a gap fill
+                let begin = message.field(7).and_then(|seq| seq.parse().ok()).unwrap_or(1);
+                return self
+                    .write(session_id, |session, outbox| {
+                        let gap_fill = AdminMessage::SequenceReset {
+                            new_seq_no: session.next_outgoing_seq,
+                            gap_fill: true,
+                        };
+                        self.send_admin_at(session, gap_fill, begin, outbox);
+                        Ok(())
+                    })
+                    .await;
+            }
+            MessageType::TestRequest => {
+                let reply = AdminMessage::Heartbeat {
+                    test_req_id: Some(field(&message, 112)),
+                };
+                return self
+                    .write(session_id, |session, outbox| {
+                        self.send_admin(session, reply, outbox);
+                        Ok(())
+                    })
+                    .await;
+            }
+            MessageType::OrderStatusRequest if self.orders.is_some() => {
+                return self
+                    .write(session_id, |session, outbox| {
+                        let report = self.order_status(session, &message);
+                        self.send_report(session, &report, outbox);
+                        Ok(())
+                    })
+                    .await;
+            }
+            _ => {}
+        }
+
+        let accepted = is_order.then(|| SequencerEvent::OrderAccepted {
+            sender_comp_id: message.sender_comp_id.clone(),
+            msg_seq_num: message.msg_seq_num,
+            at: now,
+        });
+
+        // A mass cancel of all orders pulls the firm's kill switch
+        if self.pulls_kill_switch(&message) {
+            if let Some(kill_switch) = &self.kill_switch {
+                let firm = kill_switch.firm_of(&message.sender_comp_id);
+                kill_switch.engage(&firm, "FIX mass cancel of all orders", &message.sender_comp_id);
+                return Ok(());
+            }
+        }
 
         // Forward message for processing
         if let Err(e) = self.message_tx.send(message).await {
             error!(session_id = ?session_id, error = %e, "Failed to forward message");
-            session.transition_to(SessionState::ResyncRequired)?;
-            return Err(SessionError::ProcessingFailed(e.to_string()));
+            if let Some(mut session) = self.sessions.get_mut(&session_id) {
+                session.transition_to(SessionState::ResyncRequired)?;
+            }
+            return Err(SessionError::Transport(e.to_string()));
         }
 
+        if let Some(event) = accepted {
+            self.events.publish(event);
+        }
         Ok(())
     }
 
-    /// Periodic check of all active sessions
-    async fn check_sessions(&self) {
-        let mut heartbeat_needed = Vec::new();
-        let mut timeouts = Vec::new();
+    /// Answers a counterparty's Logout and closes after the grace period, or
+    /// closes at once when it confirms a Logout we sent
+    fn handle_logout(
+        &self,
+        session: &mut Session,
+        message: &ValidatedMessage,
+        outbox: &mut Outbox,
+    ) -> Result<(), SessionError> {
+        let now = self.clock.now();
+        let text = field(message, 58);
+        match session.state {
+            SessionState::Active | SessionState::ResyncRequired | SessionState::LogoutPending => {}
+            state => return Err(SessionError::InvalidState(state)),
+        }
+        session.next_incoming_seq = session.next_incoming_seq.max(message.msg_seq_num + 1);
+        session.last_received = now;
+
+        if session.logout_received(Some(&text), &self.logout_timers, now)? {
+            let reason = session
+                .pending_logout
+                .take()
+                .map(|logout| logout.reason)
+                .unwrap_or_default();
+            info!(session_id = ?session.session_id, "Logout confirmed by counterparty");
+            return self.terminate_session_internal(session, &reason, outbox);
+        }
+        info!(session_id = ?session.session_id, text = %text, "Logout received, confirming");
+        self.send_admin(session, AdminMessage::Logout { text: None }, outbox);
+        Ok(())
+    }
+
+    /// Sends Logout with `reason` and waits for the counterparty to confirm
+    fn logout_internal(&self, session: &mut Session, reason: &str, outbox: &mut Outbox) -> Result<(), SessionError> {
+        session.begin_logout(reason, &self.logout_timers, self.clock.now())?;
+        self.send_admin(session, AdminMessage::Logout { text: Some(reason.to_string()) }, outbox);
+        Ok(())
+    }
+
+    /// Starts the logout handshake for a session. It closes once the
+    /// counterparty confirms or the confirmation times out.
+    pub async fn logout_session(&self, session_id: Uuid, reason: &str) -> Result<(), SessionError> {
+        self.write(session_id, |session, outbox| self.logout_internal(session, reason, outbox))
+            .await
+    }
+
+    /// Rejects orders while storage is nearly full, orders of firms whose
+    /// kill switch is engaged, and orders or market data requests on symbols
+    /// the sender's organization is not permissioned for
+    fn check_entitlements(&self, message: &ValidatedMessage) -> Result<(), SessionError> {
+        let required = match message.msg_type {
+            MessageType::NewOrderSingle => SymbolPermission::Trade,
+            MessageType::MarketDataRequest => SymbolPermission::ViewOnly,
+            _ => return Ok(()),
+        };
+
+        if required == SymbolPermission::Trade && self.drain.as_ref().is_some_and(|drain| drain.is_draining()) {
+            return Err(SessionError::MarketClosed);
+        }
+
+        if required == SymbolPermission::Trade && self.capacity.as_ref().is_some_and(CapacityGate::is_paused) {
+            return Err(SessionError::CapacityPaused);
+        }
+
+        if let Some(kill_switch) = &self.kill_switch {
+            if required == SymbolPermission::Trade && kill_switch.is_blocked(&message.sender_comp_id) {
+                return Err(SessionError::FirmBlocked(kill_switch.firm_of(&message.sender_comp_id)));
+            }
+        }
+
+        if let Some(markets) = &self.markets {
+            markets
+                .route(&message.target_comp_id)
+                .and_then(|market| market.check_instrument(&field(message, 55)))
+                .map_err(|e| SessionError::UnknownSymbol(e.to_string()))?;
+        }
+
+        if let Some(permissions) = &self.permissions {
+            let symbol = field(message, 55);
+            permissions
+                .check(&message.sender_comp_id, &symbol, required)
+                .map_err(|e| SessionError::PermissionDenied(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Runs the pre-trade checks on orders
+    fn check_order(&self, message: &ValidatedMessage) -> Result<(), SessionError> {
+        match &self.order_checks {
+            Some(checks) if message.msg_type == MessageType::NewOrderSingle => {
+                checks.check(message).map_err(SessionError::OrderRejected)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Records the order's ClOrdID, rejecting reuse within the trading day
+    async fn record_cl_ord_id(&self, message: &ValidatedMessage) -> Result<(), SessionError> {
+        let market = self.markets.as_ref().and_then(|markets| markets.route(&message.target_comp_id).ok());
+        let Some(cl_ord_ids) = market
+            .as_ref()
+            .map(|market| &market.cl_ord_ids)
+            .or(self.cl_ord_ids.as_deref())
+        else {
+            return Ok(());
+        };
+        let cl_ord_id = field(message, 11);
+        cl_ord_ids
+            .record(&message.sender_comp_id, &cl_ord_id)
+            .await
+            .map_err(|e| match e {
+                ClOrdIdError::Duplicate { cl_ord_id, .. } => SessionError::DuplicateClOrdId(cl_ord_id),
+                ClOrdIdError::Storage(e) => SessionError::Storage(e),
+            })
+    }
 
-        // First pass: identify sessions needing attention
-        for session in self.sessions.iter() {
+    /// Records an order in the lifecycle store, as New or rejected with
+    /// `error`
+    async fn record_order(&self, message: &ValidatedMessage, error: Option<&SessionError>) {
+        let Some(orders) = &self.orders else {
+            return;
+        };
+        let account = field(message, 1);
+        let price = field(message, 44);
+        let entry = OrderEntry {
+            sender_comp_id: message.sender_comp_id.clone(),
+            cl_ord_id: field(message, 11),
+            symbol: field(message, 55),
+            side: field(message, 54),
+            account: (!account.is_empty()).then_some(account),
+            order_qty: field(message, 38).parse().unwrap_or(0),
+            price: (!price.is_empty()).then_some(price),
+        };
+        let recorded = match error {
+            None => orders.accept(entry).await,
+            Some(e) => orders.reject(entry, e.to_string()).await,
+        };
+        if let Err(e) = recorded {
+            error!(sender_comp_id = %message.sender_comp_id, error = %e, "Failed to record order");
+        }
+    }
+
+    /// Answers an OrderStatusRequest with the order of its OrderID (37), or
+    /// else its ClOrdID (11). Orders of other senders are unknown.
+    fn order_status(&self, session: &Session, message: &ValidatedMessage) -> OrderStatusReport {
+        let cl_ord_id = field(message, 11);
+        let order_id = field(message, 37);
+        let order = self.orders.as_ref().and_then(|orders| {
+            if order_id.is_empty() || order_id == "NONE" {
+                orders.by_cl_ord_id(&message.sender_comp_id, &cl_ord_id)
+            } else {
+                orders.by_order_id(&order_id)
+            }
+        });
+        let req_id = field(message, 790);
+        OrderStatusReport {
+            sender_comp_id: session.target_comp_id.clone(),
+            target_comp_id: session.sender_comp_id.clone(),
+            order: order.filter(|order| order.entry.sender_comp_id == message.sender_comp_id),
+            cl_ord_id,
+            symbol: field(message, 55),
+            side: field(message, 54),
+            ord_status_req_id: (!req_id.is_empty()).then_some(req_id),
+            unsolicited: false,
+        }
+    }
+
+    /// Sends an order status report as the session's next message
+    fn send_report(&self, session: &mut Session, report: &OrderStatusReport, outbox: &mut Outbox) {
+        let now = self.clock.now();
+        let raw = report.encode(session.next_outgoing_seq, now).into_bytes();
+        session.message_sent(now);
+        outbox.push(session.session_id, raw, false);
+    }
+
+    /// Sends an application message as the session's next message, encoded
+    /// by `encode` from its MsgSeqNum (34) and SendingTime (52)
+    pub async fn send(
+        &self,
+        session_id: Uuid,
+        encode: impl FnOnce(u64, DateTime<Utc>) -> Vec<u8>,
+    ) -> Result<(), SessionError> {
+        self.write(session_id, |session, outbox| {
             if session.state != SessionState::Active {
-                continue;
+                return Err(SessionError::InvalidState(session.state));
             }
+            let now = self.clock.now();
+            let raw = encode(session.next_outgoing_seq, now);
+            session.message_sent(now);
+            outbox.push(session_id, raw, false);
+            Ok(())
+        })
+        .await
+    }
+
+    /// Runs `update` on the session in its write turn, then delivers the
+    /// messages it queued once the session's entry is released, so a full
+    /// outbound channel never holds up the other sessions of its shard
+    async fn write<T>(
+        &self,
+        session_id: Uuid,
+        update: impl FnOnce(&mut Session, &mut Outbox) -> Result<T, SessionError>,
+    ) -> Result<T, SessionError> {
+        let writer = self
+            .writers
+            .get(&session_id)
+            .map(|writer| writer.value().clone())
+            .ok_or(SessionError::NotFound(session_id))?;
+        let _turn = writer.lock().await;
+
+        let mut outbox = Outbox::default();
+        let result = match self.sessions.get_mut(&session_id) {
+            Some(mut session) => update(&mut session, &mut outbox),
+            None => Err(SessionError::NotFound(session_id)),
+        };
+        let delivered = self.deliver(outbox).await;
+        let value = result?;
+        delivered.map(|_| value)
+    }
 
-            if session.is_heartbeat_overdue() {
-                timeouts.push(session.session_id);
-            } else if session.needs_heartbeat() {
-                heartbeat_needed.push(session.session_id);
+    /// Queues the messages of `outbox` for their sessions' connections.
+    /// Sessions closing with them are dropped, their connections going with
+    /// them.
+    async fn deliver(&self, outbox: Outbox) -> Result<(), SessionError> {
+        for message in outbox.0.iter().filter(|message| message.close) {
+            self.sessions.remove(&message.session_id);
+            self.writers.remove(&message.session_id);
+        }
+        if let Some(outbound) = &self.outbound {
+            for message in outbox.0 {
+                outbound
+                    .send(message)
+                    .await
+                    .map_err(|e| SessionError::Transport(e.to_string()))?;
             }
         }
+        Ok(())
+    }
 
-        // Handle heartbeats
-        for session_id in heartbeat_needed {
-            if let Some(mut session) = self.sessions.get_mut(&session_id) {
-                if let Err(e) = self.send_heartbeat(&mut session).await {
-                    error!(session_id = ?session_id, error = %e, "Failed to send heartbeat");
-                }
+    /// Sends the ExecutionReport rejecting an order refused with `error` as
+    /// the session's next message
+    fn reject_order(
+        &self,
+        session: &mut Session,
+        message: &ValidatedMessage,
+        error: &SessionError,
+        outbox: &mut Outbox,
+    ) {
+        // Errors without detail are fully described by the catalogue text
+        let text = match error {
+            SessionError::MarketClosed | SessionError::CapacityPaused => String::new(),
+            other => other.to_string(),
+        };
+        let now = self.clock.now();
+        let report = OrderReject {
+            sender_comp_id: session.target_comp_id.clone(),
+            target_comp_id: session.sender_comp_id.clone(),
+            cl_ord_id: field(message, 11),
+            symbol: field(message, 55),
+            side: field(message, 54),
+            reason: error.reject_reason(),
+            text,
+        }
+        .encode(session.next_outgoing_seq, now);
+        session.message_sent(now);
+        outbox.push(session.session_id, report.into_bytes(), false);
+    }
+
+    /// Periodic check of all active sessions, each against its own
+    /// negotiated heartbeat interval
+    async fn check_sessions(&self) {
+        let mut due = Vec::new();
+        let now = self.clock.now();
+
+        // First pass: advance the timers of every live session
+        for mut session in self.sessions.iter_mut() {
+            if !matches!(
+                session.state,
+                SessionState::Active | SessionState::LogoutPending | SessionState::Disconnecting
+            ) {
+                continue;
+            }
+            if let Some(action) = session.poll_timers(now) {
+                due.push((session.session_id, action));
             }
         }
 
-        // Handle timeouts
-        for session_id in timeouts {
-            if let Some(mut session) = self.sessions.get_mut(&session_id) {
-                warn!(session_id = ?session_id, "Session timed out, terminating");
-                if let Err(e) = self.terminate_session_internal(&mut session).await {
-                    error!(session_id = ?session_id, error = %e, "Failed to terminate session");
-                }
+        for (session_id, action) in due {
+            let result = self
+                .write(session_id, |session, outbox| match action {
+                    TimerAction::SendHeartbeat => {
+                        self.send_admin(session, AdminMessage::Heartbeat { test_req_id: None }, outbox);
+                        Ok(())
+                    }
+                    TimerAction::SendTestRequest(test_req_id) => {
+                        warn!(session_id = ?session_id, "No messages within heartbeat interval, sending TestRequest");
+                        self.send_admin(session, AdminMessage::TestRequest { test_req_id }, outbox);
+                        Ok(())
+                    }
+                    TimerAction::TimedOut => {
+                        warn!(session_id = ?session_id, "TestRequest unanswered, terminating");
+                        self.terminate_session_internal(session, "heartbeat timeout", outbox)
+                    }
+                    TimerAction::CloseLogout => {
+                        let logout = session.pending_logout.take();
+                        let reason = match (&session.state, logout) {
+                            (SessionState::LogoutPending, Some(logout)) => {
+                                warn!(session_id = ?session_id, "Logout not confirmed, closing");
+                                format!("{} (logout unconfirmed)", logout.reason)
+                            }
+                            (_, logout) => logout.map(|logout| logout.reason).unwrap_or_default(),
+                        };
+                        self.terminate_session_internal(session, &reason, outbox)
+                    }
+                })
+                .await;
+            match result {
+                // Closed since its timers were polled
+                Ok(()) | Err(SessionError::NotFound(_)) => {}
+                Err(e) => error!(session_id = ?session_id, error = %e, "Failed to service heartbeat timer"),
             }
         }
     }
 
-    /// Send a heartbeat message for a session
-    async fn send_heartbeat(&self, session: &mut Session) -> Result<(), SessionError> {
-        // Create heartbeat message
-        let heartbeat = self.create_heartbeat_message(session)?;
-        
-        // Update session state
-        session.message_sent();
-        
-        // Send through normal message path
-        self.message_tx.send(heartbeat).await
-            .map_err(|e| SessionError::ProcessingFailed(e.to_string()))?;
-            
-        Ok(())
+    /// Sends a session level message on the session's connection, taking
+    /// its next outgoing sequence number
+    fn send_admin(&self, session: &mut Session, message: AdminMessage, outbox: &mut Outbox) {
+        let msg_seq_num = session.next_outgoing_seq;
+        session.message_sent(self.clock.now());
+        self.send_admin_at(session, message, msg_seq_num, outbox);
     }
 
-    /// Create a FIX heartbeat message
-    fn create_heartbeat_message(&self, session: &Session) -> Result<ValidatedMessage, SessionError> {
-        // TODO: Implement actual FIX heartbeat message creation
-        // For now returning placeholder
-        unimplemented!("Heartbeat message creation not implemented")
+    /// Sends a session level message as `msg_seq_num`, without advancing
+    /// the session's outgoing sequence number
+    fn send_admin_at(&self, session: &Session, message: AdminMessage, msg_seq_num: u64, outbox: &mut Outbox) {
+        let raw = message.encode(
+            "FIX.4.2",
+            &session.target_comp_id,
+            &session.sender_comp_id,
+            msg_seq_num,
+            self.clock.now(),
+        );
+        outbox.push(session.session_id, raw, false);
     }
 
-    /// Internal method to terminate a session
-    async fn terminate_session_internal(&self, session: &mut Session) -> Result<(), SessionError> {
+    /// Internal method to terminate a session. It is dropped once the
+    /// closing of its connection is delivered.
+    fn terminate_session_internal(
+        &self,
+        session: &mut Session,
+        reason: &str,
+        outbox: &mut Outbox,
+    ) -> Result<(), SessionError> {
         // Transition through proper states
-        session.transition_to(SessionState::Disconnecting)?;
+        if session.state != SessionState::Disconnecting {
+            session.transition_to(SessionState::Disconnecting)?;
+        }
         session.transition_to(SessionState::Terminated)?;
         
-        // Remove from sender index
-        self.sender_index.remove(&session.sender_comp_id);
+        // Remove from sender index, remembering where the session left off
+        let key = (session.target_comp_id.clone(), session.sender_comp_id.clone());
+        if let Some(resume) = session.resume_point(self.clock.now()) {
+            self.resume_points.insert(key.clone(), resume);
+        }
+        self.sender_index.remove(&key);
+        if let Some(market) = self.markets.as_ref().and_then(|markets| markets.route(&session.target_comp_id).ok()) {
+            market.close_session(&session.sender_comp_id);
+        }
         
-        info!(session_id = ?session.session_id, "Session terminated");
+        self.events.publish(SequencerEvent::SessionClosed {
+            session_id: session.session_id,
+            sender_comp_id: session.sender_comp_id.clone(),
+            reason: reason.to_string(),
+            at: self.clock.now(),
+        });
+        info!(session_id = ?session.session_id, reason, "Session terminated");
+        outbox.push(session.session_id, Vec::new(), true);
         Ok(())
     }
 
     /// Gracefully terminate a session
     pub async fn terminate_session(&self, session_id: Uuid) -> Result<(), SessionError> {
-        let mut session = self.sessions.get_mut(&session_id)
-            .ok_or(SessionError::NotFound(session_id))?;
-            
-        self.terminate_session_internal(&mut session).await
+        self.write(session_id, |session, outbox| {
+            self.terminate_session_internal(session, "terminated", outbox)
+        })
+        .await
     }
 
     /// Get information about a specific session
@@ -201,6 +947,14 @@ impl SessionManager {
             .ok_or(SessionError::NotFound(session_id))
     }
 
+    /// Whether `message` is a mass cancel of all orders, handled by pulling
+    /// the firm's kill switch
+    pub fn pulls_kill_switch(&self, message: &ValidatedMessage) -> bool {
+        self.kill_switch.is_some()
+            && message.msg_type == MessageType::OrderMassCancelRequest
+            && message.field(530) == Some(MASS_CANCEL_ALL_ORDERS)
+    }
+
     /// Get current active session count
     pub fn active_session_count(&self) -> usize {
         self.sessions.iter()
@@ -209,11 +963,28 @@ impl SessionManager {
     }
 }
 
+/// Value of `tag` in `message`, empty if absent
+fn field(message: &ValidatedMessage, tag: u32) -> String {
+    message.field(tag).unwrap_or_default().to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use tokio::time::sleep;
 
+    /// A message of MM1 to ROMER with `fields` after the header
+    fn message(seq: u64, msg_type: &str, fields: &[(u32, &str)]) -> ValidatedMessage {
+        let mut header = vec![
+            (35, msg_type.to_string()),
+            (49, "MM1".to_string()),
+            (56, "ROMER".to_string()),
+            (34, seq.to_string()),
+        ];
+        header.extend(fields.iter().map(|(tag, value)| (*tag, value.to_string())));
+        ValidatedMessage::parse(&romer_common::types::fix::utils::encode_message("FIX.4.2", &header)).unwrap()
+    }
+
     #[tokio::test]
     async fn test_session_lifecycle() {
         let (tx, _rx) = mpsc::channel(100);
@@ -223,7 +994,7 @@ mod tests {
         let session_id = manager.create_session(
             "SENDER".to_string(),
             "TARGET".to_string(),
-            30,
+            Some("30"),
             vec![1, 2, 3, 4],
         ).unwrap();
 
@@ -234,9 +1005,9 @@ mod tests {
         // Terminate session
         manager.terminate_session(session_id).await.unwrap();
         
-        // Verify session is terminated
-        let session = manager.get_session(session_id).unwrap();
-        assert_eq!(session.state, SessionState::Terminated);
+        // Verify session is terminated and dropped
+        assert!(matches!(manager.get_session(session_id), Err(SessionError::NotFound(_))));
+        assert!(manager.sessions.is_empty() && manager.writers.is_empty());
     }
 
     #[tokio::test]
@@ -248,7 +1019,7 @@ mod tests {
         manager.create_session(
             "SENDER".to_string(),
             "TARGET".to_string(),
-            30,
+            Some("30"),
             vec![1, 2, 3, 4],
         ).unwrap();
 
@@ -256,17 +1027,267 @@ mod tests {
         let result = manager.create_session(
             "SENDER".to_string(),
             "TARGET".to_string(),
-            30,
+            Some("30"),
             vec![1, 2, 3, 4],
         );
 
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_logon_resumes_sequences() {
+        let (tx, _rx) = mpsc::channel(100);
+        let (outbound_tx, mut outbound) = mpsc::channel(100);
+        let manager = SessionManager::new(tx).with_outbound(outbound_tx);
+        let logon = |logon_seq, next_expected| SequenceNegotiation {
+            reset: false,
+            logon_seq,
+            next_expected,
+        };
+
+        let session_id = manager
+            .logon("MM1".into(), "ROMER".into(), Some("30"), vec![1], logon(1, None))
+            .await
+            .unwrap();
+        // The Logon is acknowledged with a Logon of our own
+        let ack = outbound.recv().await.unwrap();
+        let fields = romer_common::types::fix::utils::validate_message(&ack.raw)
+            .map(|_| romer_common::types::fix::utils::parse_message_fields(&ack.raw))
+            .unwrap();
+        assert_eq!((fields[&35].as_str(), fields[&108].as_str(), fields[&789].as_str()), ("A", "30", "2"));
+        {
+            // The session carries on for a while before the network blips
+            let mut session = manager.sessions.get_mut(&session_id).unwrap();
+            session.next_incoming_seq = 8;
+            session.next_outgoing_seq = 5;
+        }
+        manager.terminate_session(session_id).await.unwrap();
+        assert!(outbound.recv().await.unwrap().close);
+
+        // We sent 4 but the counterparty only saw up to 2
+        let resumed = manager
+            .logon("MM1".into(), "ROMER".into(), Some("30"), vec![1], logon(8, Some(3)))
+            .await
+            .unwrap();
+        let session = manager.get_session(resumed).unwrap();
+        assert_eq!((session.next_incoming_seq, session.next_outgoing_seq), (9, 6));
+        let ack = String::from_utf8_lossy(&outbound.recv().await.unwrap().raw).into_owned();
+        assert!(ack.contains("34=5") && ack.contains("789=9"));
+        let gap_fill = String::from_utf8_lossy(&outbound.recv().await.unwrap().raw).into_owned();
+        assert!(gap_fill.contains("35=4") && gap_fill.contains("34=3") && gap_fill.contains("36=5"));
+    }
+
+    #[tokio::test]
+    async fn test_logon_reports_open_orders_unsolicited() {
+        use crate::market::orders::OrderEntry;
+
+        let orders = Arc::new(OrderStore::in_memory(system_clock()));
+        orders
+            .accept(OrderEntry {
+                sender_comp_id: "MM1".into(),
+                cl_ord_id: "A1".into(),
+                symbol: "BTC-USD".into(),
+                side: "1".into(),
+                account: None,
+                order_qty: 10,
+                price: Some("100".into()),
+            })
+            .await
+            .unwrap();
+        let (tx, _rx) = mpsc::channel(100);
+        let (outbound_tx, mut outbound) = mpsc::channel(100);
+        let manager = SessionManager::new(tx).with_outbound(outbound_tx).with_orders(orders);
+
+        let sequences = SequenceNegotiation {
+            reset: false,
+            logon_seq: 1,
+            next_expected: None,
+        };
+        manager
+            .logon("MM1".into(), "ROMER".into(), Some("30"), vec![1], sequences)
+            .await
+            .unwrap();
+        let ack = String::from_utf8_lossy(&outbound.recv().await.unwrap().raw).into_owned();
+        assert!(ack.contains("35=A"));
+        let status = String::from_utf8_lossy(&outbound.recv().await.unwrap().raw).into_owned();
+        assert!(status.contains("\x0135=8\x01") && status.contains("\x0134=2\x01"));
+        assert!(status.contains("\x0111=A1\x01") && status.contains("\x01150=I\x0139=0\x01"));
+        assert!(status.contains("\x01325=Y\x01"));
+    }
+
+    #[tokio::test]
+    async fn test_order_status_request() {
+        let (tx, _rx) = mpsc::channel(100);
+        let (outbound_tx, mut outbound) = mpsc::channel(100);
+        let orders = Arc::new(OrderStore::in_memory(system_clock()));
+        let manager = SessionManager::new(tx).with_outbound(outbound_tx).with_orders(orders);
+        let sequences = SequenceNegotiation {
+            reset: true,
+            logon_seq: 1,
+            next_expected: None,
+        };
+        let session_id = manager
+            .logon("MM1".into(), "ROMER".into(), Some("30"), vec![1], sequences)
+            .await
+            .unwrap();
+        assert!(String::from_utf8_lossy(&outbound.recv().await.unwrap().raw).contains("35=A"));
+
+        // An order accepted on the session is reported by its ClOrdID
+        let order = [(11, "A1"), (55, "BTC-USD"), (54, "1"), (38, "10"), (44, "100")];
+        manager.handle_message(session_id, message(2, "D", &order)).await.unwrap();
+        let request = [(11, "A1"), (55, "BTC-USD"), (54, "1"), (790, "Q1")];
+        manager.handle_message(session_id, message(3, "H", &request)).await.unwrap();
+        let status = String::from_utf8_lossy(&outbound.recv().await.unwrap().raw).into_owned();
+        assert!(status.contains("\x0135=8\x01") && status.contains("\x0134=2\x01"));
+        assert!(status.contains("\x0111=A1\x01") && status.contains("\x01150=I\x0139=0\x01"));
+        assert!(status.contains("\x01790=Q1\x01") && status.contains("\x01325=N\x01"));
+
+        // Unknown orders are reported rejected
+        let request = [(11, "B2"), (55, "BTC-USD"), (54, "2")];
+        manager.handle_message(session_id, message(4, "H", &request)).await.unwrap();
+        let status = String::from_utf8_lossy(&outbound.recv().await.unwrap().raw).into_owned();
+        assert!(status.contains("\x0134=3\x01") && status.contains("\x0111=B2\x01"));
+        assert!(status.contains("\x0139=8\x01") && !status.contains("\x01790="));
+    }
+
+    #[tokio::test]
+    async fn test_refused_orders_rejected() {
+        let (tx, _rx) = mpsc::channel(100);
+        let (outbound_tx, mut outbound) = mpsc::channel(100);
+        let kill_switch = Arc::new(KillSwitch::new(EventBus::default()));
+        let drain = Arc::new(DrainMode::new(EventBus::default()));
+        let manager = SessionManager::new(tx)
+            .with_outbound(outbound_tx)
+            .with_kill_switch(kill_switch.clone())
+            .with_drain_mode(drain.clone());
+        let sequences = SequenceNegotiation {
+            reset: true,
+            logon_seq: 1,
+            next_expected: None,
+        };
+        let session_id = manager
+            .logon("MM1".into(), "ROMER".into(), Some("30"), vec![1], sequences)
+            .await
+            .unwrap();
+        assert!(String::from_utf8_lossy(&outbound.recv().await.unwrap().raw).contains("35=A"));
+
+        // Orders of a blocked firm are rejected in sequence on the session
+        kill_switch.engage("MM1", "test", "ops");
+        let order = [(11, "A1"), (55, "BTC-USD"), (54, "1"), (38, "10")];
+        let refused = manager.handle_message(session_id, message(2, "D", &order)).await;
+        assert!(matches!(refused, Err(SessionError::FirmBlocked(_))));
+        let reject = String::from_utf8_lossy(&outbound.recv().await.unwrap().raw).into_owned();
+        assert!(reject.contains("\x0134=2\x01") && reject.contains("\x0111=A1\x01"));
+        assert!(reject.contains("\x01150=8\x0139=8\x01") && reject.contains("\x0158=[1006] Firm blocked: "));
+
+        // Draining rejects orders as market closed, with the catalogue text only
+        kill_switch.release("MM1", "ops");
+        drain.enter("maintenance", "ops");
+        let order = [(11, "A2"), (55, "BTC-USD"), (54, "1"), (38, "10")];
+        assert!(manager.handle_message(session_id, message(3, "D", &order)).await.is_err());
+        let reject = String::from_utf8_lossy(&outbound.recv().await.unwrap().raw).into_owned();
+        assert!(reject.contains("\x0134=3\x01") && reject.contains("\x01103=2\x01"));
+        assert!(reject.contains("\x0158=[1002] Market closed\x01"));
+    }
+
+    #[tokio::test]
+    async fn test_cl_ord_id_recorded_on_acceptance() {
+        let (tx, _rx) = mpsc::channel(100);
+        let kill_switch = Arc::new(KillSwitch::new(EventBus::default()));
+        let cl_ord_ids = Arc::new(ClOrdIdRegistry::in_memory(system_clock()));
+        let manager = SessionManager::new(tx)
+            .with_kill_switch(kill_switch.clone())
+            .with_cl_ord_ids(cl_ord_ids.clone());
+        let sequences = SequenceNegotiation {
+            reset: true,
+            logon_seq: 1,
+            next_expected: None,
+        };
+        let session_id = manager
+            .logon("MM1".into(), "ROMER".into(), Some("30"), vec![1], sequences)
+            .await
+            .unwrap();
+        let order = [(11, "A1"), (55, "BTC-USD"), (54, "1"), (38, "10")];
+
+        // A refused order leaves its ClOrdID free to be sent again
+        kill_switch.engage("MM1", "test", "ops");
+        assert!(manager.handle_message(session_id, message(2, "D", &order)).await.is_err());
+        assert_eq!(cl_ord_ids.ids_today(), 0);
+
+        kill_switch.release("MM1", "ops");
+        manager.handle_message(session_id, message(3, "D", &order)).await.unwrap();
+        assert_eq!(cl_ord_ids.ids_today(), 1);
+        let reused = manager.handle_message(session_id, message(4, "D", &order)).await;
+        assert!(matches!(reused, Err(SessionError::DuplicateClOrdId(id)) if id == "A1"));
+    }
+
+    #[test]
+    fn test_only_mass_cancel_of_all_orders_pulls_kill_switch() {
+        let (tx, _rx) = mpsc::channel(100);
+        let all = message(2, "q", &[(530, "7")]);
+        let symbol = message(2, "q", &[(530, "1"), (55, "BTC-USD")]);
+        assert!(!SessionManager::new(tx.clone()).pulls_kill_switch(&all));
+
+        let manager = SessionManager::new(tx).with_kill_switch(Arc::new(KillSwitch::new(EventBus::default())));
+        assert!(manager.pulls_kill_switch(&all));
+        assert!(!manager.pulls_kill_switch(&symbol));
+    }
+
+    #[tokio::test]
+    async fn test_logout_handshake() {
+        use romer_common::types::fix::utils::encode_message;
+
+        let (tx, _rx) = mpsc::channel(100);
+        let (outbound_tx, mut outbound) = mpsc::channel(100);
+        let timers = LogoutTimers {
+            grace: Duration::from_millis(100),
+            confirm_timeout: Duration::from_secs(1),
+        };
+        let manager = Arc::new(SessionManager::new(tx).with_outbound(outbound_tx).with_logout_timers(timers));
+        let running = manager.clone();
+        tokio::spawn(async move { running.run().await });
+        let sequences = SequenceNegotiation {
+            reset: true,
+            logon_seq: 1,
+            next_expected: None,
+        };
+
+        // The counterparty's Logout is confirmed, and the connection closed
+        // after the grace period
+        let session_id = manager
+            .logon("MM1".into(), "ROMER".into(), Some("30"), vec![1], sequences)
+            .await
+            .unwrap();
+        outbound.recv().await.unwrap();
+        let fields = [(35, "5"), (49, "MM1"), (56, "ROMER"), (34, "2")].map(|(tag, value)| (tag, value.to_string()));
+        let logout = ValidatedMessage::parse(&encode_message("FIX.4.2", &fields)).unwrap();
+        manager.handle_message(session_id, logout).await.unwrap();
+        let reply = outbound.recv().await.unwrap();
+        assert!(String::from_utf8_lossy(&reply.raw).contains("\x0135=5\x01") && !reply.close);
+        let closed = time::timeout(Duration::from_secs(3), outbound.recv()).await.unwrap().unwrap();
+        assert!(closed.close);
+        assert!(matches!(manager.get_session(session_id), Err(SessionError::NotFound(_))));
+
+        // Our Logout goes unconfirmed, so the session closes at the timeout
+        let session_id = manager
+            .logon("MM1".into(), "ROMER".into(), Some("30"), vec![1], sequences)
+            .await
+            .unwrap();
+        outbound.recv().await.unwrap();
+        manager.logout_session(session_id, "end of day").await.unwrap();
+        assert_eq!(manager.get_session(session_id).unwrap().state, SessionState::LogoutPending);
+        let logout = String::from_utf8_lossy(&outbound.recv().await.unwrap().raw).into_owned();
+        assert!(logout.contains("\x0135=5\x01") && logout.contains("\x0158=end of day\x01"));
+        let closed = time::timeout(Duration::from_secs(3), outbound.recv()).await.unwrap().unwrap();
+        assert!(closed.close);
+        assert!(matches!(manager.get_session(session_id), Err(SessionError::NotFound(_))));
+    }
+
     #[tokio::test]
     async fn test_session_timeout() {
         let (tx, _rx) = mpsc::channel(100);
-        let manager = SessionManager::new(tx);
+        let (outbound_tx, mut outbound) = mpsc::channel(100);
+        let manager = Arc::new(SessionManager::new(tx).with_outbound(outbound_tx));
 
         // Create and start manager
         let manager_clone = manager.clone();
@@ -274,19 +1295,27 @@ mod tests {
             manager_clone.run().await;
         });
 
-        // Create session
-        let session_id = manager.create_session(
-            "SENDER".to_string(),
-            "TARGET".to_string(),
-            1, // 1 second heartbeat for faster testing
-            vec![1, 2, 3, 4],
-        ).unwrap();
+        // Log on with a 1 second heartbeat for faster testing
+        let sequences = SequenceNegotiation {
+            reset: false,
+            logon_seq: 1,
+            next_expected: None,
+        };
+        let session_id = manager
+            .logon("SENDER".into(), "TARGET".into(), Some("1"), vec![1, 2, 3, 4], sequences)
+            .await
+            .unwrap();
 
-        // Wait for timeout
-        sleep(Duration::from_secs(3)).await;
+        // Wait for the TestRequest and then the timeout
+        sleep(Duration::from_secs(7)).await;
 
-        // Verify session was terminated
-        let session = manager.get_session(session_id).unwrap();
-        assert_eq!(session.state, SessionState::Terminated);
+        // Verify session was terminated and its connection closed
+        assert!(matches!(manager.get_session(session_id), Err(SessionError::NotFound(_))));
+        let mut sent = Vec::new();
+        while let Ok(message) = outbound.try_recv() {
+            sent.push(message);
+        }
+        assert!(sent.iter().any(|message| String::from_utf8_lossy(&message.raw).contains("\x0135=1\x01")));
+        assert!(sent.last().unwrap().close);
     }
-}
\ No newline at end of file
+}
@@ -1,3 +1,2 @@
 pub mod state;
 pub mod manager;
-pub mod auth;
\ No newline at end of file
@@ -1,6 +1,8 @@
 // src/session/state.rs
 
+use crate::risk::order_checks::OrderRejection;
 use chrono::{DateTime, Utc};
+use romer_common::types::rejection::RejectReason;
 use serde::{Serialize, Deserialize};
 use std::time::Duration;
 use uuid::Uuid;
@@ -16,12 +18,102 @@ pub enum SessionState {
     Active,
     /// Session is active but waiting for sequence reset
     ResyncRequired,
+    /// We sent Logout and are waiting for the counterparty's confirmation
+    LogoutPending,
     /// Session is being gracefully closed
     Disconnecting,
     /// Session has been terminated
     Terminated,
 }
 
+/// A TestRequest (35=1) awaiting a response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingTestRequest {
+    /// TestReqID (112) the counterparty's Heartbeat should echo
+    pub test_req_id: String,
+    pub sent_at: DateTime<Utc>,
+}
+
+/// Which side started the logout handshake
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum LogoutInitiator {
+    Sequencer,
+    Counterparty,
+}
+
+/// A logout handshake in progress
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingLogout {
+    pub initiator: LogoutInitiator,
+    /// Why the session is ending, journaled when it closes
+    pub reason: String,
+    /// When the connection is closed regardless of the counterparty
+    pub deadline: DateTime<Utc>,
+}
+
+/// How long each side of the logout handshake is given
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogoutTimers {
+    /// After answering a counterparty's Logout, before closing
+    pub grace: Duration,
+    /// Waiting for the counterparty to confirm our Logout
+    pub confirm_timeout: Duration,
+}
+
+impl Default for LogoutTimers {
+    fn default() -> Self {
+        Self {
+            grace: Duration::from_secs(2),
+            confirm_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// What the heartbeat timers of a session require
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimerAction {
+    /// Nothing sent for most of the interval
+    SendHeartbeat,
+    /// Nothing received for the interval; probe with this TestReqID
+    SendTestRequest(String),
+    /// The TestRequest went unanswered for another interval
+    TimedOut,
+    /// The logout handshake is over, by grace period or timeout
+    CloseLogout,
+}
+
+/// Sequence numbers a closed session left off at, so a counterparty
+/// reconnecting shortly after can carry on without a manual reset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResumePoint {
+    pub next_incoming_seq: u64,
+    pub next_outgoing_seq: u64,
+    pub closed_at: DateTime<Utc>,
+}
+
+/// Sequence fields of a counterparty's Logon
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SequenceNegotiation {
+    /// ResetSeqNumFlag (141=Y): both sides restart at 1
+    pub reset: bool,
+    /// MsgSeqNum (34) of the Logon itself
+    pub logon_seq: u64,
+    /// NextExpectedMsgSeqNum (789): what the counterparty expects from us
+    pub next_expected: Option<u64>,
+}
+
+/// Messages owed to recover from a reconnect
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SequenceRecovery {
+    /// The counterparty missed our messages from this sequence number on.
+    /// Session messages are not kept for resending, so the gap is filled
+    /// with a SequenceReset-GapFill.
+    pub gap_fill_from: Option<u64>,
+    /// Range of the counterparty's messages we missed, to ask for with a
+    /// ResendRequest
+    pub resend: Option<(u64, u64)>,
+}
+
 /// Contains all the information about a FIX session
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Session {
@@ -43,21 +135,26 @@ pub struct Session {
     pub next_incoming_seq: u64,
     /// Next outgoing message sequence number
     pub next_outgoing_seq: u64,
-    /// Heartbeat interval in seconds
+    /// Heartbeat interval in seconds, as negotiated at Logon
     pub heartbeat_interval: u32,
+    /// TestRequest sent after the counterparty fell silent, awaiting any reply
+    pub pending_test_request: Option<PendingTestRequest>,
+    /// Logout handshake in progress, if any
+    #[serde(default)]
+    pub pending_logout: Option<PendingLogout>,
     /// Market maker's BLS public key
     pub public_key: Vec<u8>,
 }
 
 impl Session {
-    /// Create a new session
+    /// Create a new session, starting at `now`
     pub fn new(
         sender_comp_id: String,
         target_comp_id: String,
         heartbeat_interval: u32,
         public_key: Vec<u8>,
+        now: DateTime<Utc>,
     ) -> Self {
-        let now = Utc::now();
         Self {
             session_id: Uuid::new_v4(),
             sender_comp_id,
@@ -69,18 +166,79 @@ impl Session {
             next_incoming_seq: 1,
             next_outgoing_seq: 1,
             heartbeat_interval,
+            pending_test_request: None,
+            pending_logout: None,
             public_key,
         }
     }
 
+    /// Applies a Logon's sequence fields, continuing from `resume` where a
+    /// previous session of the counterparty left off unless it asks for a
+    /// reset. Sequence numbers are left unchanged on error.
+    pub fn negotiate_sequences(
+        &mut self,
+        resume: Option<ResumePoint>,
+        logon: SequenceNegotiation,
+    ) -> Result<SequenceRecovery, SessionError> {
+        if logon.reset {
+            self.next_incoming_seq = logon.logon_seq + 1;
+            self.next_outgoing_seq = 1;
+            return Ok(SequenceRecovery::default());
+        }
+
+        let (mut next_incoming, next_outgoing) = resume
+            .map(|resume| (resume.next_incoming_seq, resume.next_outgoing_seq))
+            .unwrap_or((1, 1));
+        let mut recovery = SequenceRecovery::default();
+
+        if logon.logon_seq < next_incoming {
+            return Err(SessionError::InvalidSequence {
+                expected: next_incoming,
+                received: logon.logon_seq,
+            });
+        }
+        if logon.logon_seq > next_incoming {
+            // Keep expecting the gap, which the counterparty resends
+            recovery.resend = Some((next_incoming, logon.logon_seq - 1));
+        } else {
+            next_incoming += 1;
+        }
+
+        if let Some(next_expected) = logon.next_expected {
+            if next_expected > next_outgoing {
+                return Err(SessionError::NextExpectedAhead {
+                    expected: next_expected,
+                    next: next_outgoing,
+                });
+            }
+            if next_expected < next_outgoing {
+                recovery.gap_fill_from = Some(next_expected);
+            }
+        }
+
+        self.next_incoming_seq = next_incoming;
+        self.next_outgoing_seq = next_outgoing;
+        Ok(recovery)
+    }
+
+    /// Where a reconnect would resume, `None` before any message was
+    /// exchanged
+    pub fn resume_point(&self, now: DateTime<Utc>) -> Option<ResumePoint> {
+        (self.next_incoming_seq > 1 || self.next_outgoing_seq > 1).then_some(ResumePoint {
+            next_incoming_seq: self.next_incoming_seq,
+            next_outgoing_seq: self.next_outgoing_seq,
+            closed_at: now,
+        })
+    }
+
     /// Check if heartbeat is overdue
-    pub fn is_heartbeat_overdue(&self) -> bool {
-        let elapsed = Utc::now() - self.last_received;
+    pub fn is_heartbeat_overdue(&self, now: DateTime<Utc>) -> bool {
+        let elapsed = (now - self.last_received).to_std().unwrap_or_default();
         elapsed > Duration::from_secs(self.heartbeat_interval as u64 + 1)
     }
 
     /// Update the last received time and sequence number
-    pub fn message_received(&mut self, seq_num: u64) -> Result<(), SessionError> {
+    pub fn message_received(&mut self, seq_num: u64, now: DateTime<Utc>) -> Result<(), SessionError> {
         // Verify sequence number
         if seq_num != self.next_incoming_seq {
             return Err(SessionError::InvalidSequence {
@@ -89,20 +247,76 @@ impl Session {
             });
         }
 
-        self.last_received = Utc::now();
+        self.last_received = now;
         self.next_incoming_seq += 1;
+        // Any message proves the counterparty is alive
+        self.pending_test_request = None;
+        Ok(())
+    }
+
+    /// Advances the heartbeat timers, which run at this session's
+    /// negotiated interval. Records a TestRequest when one is due.
+    pub fn poll_timers(&mut self, now: DateTime<Utc>) -> Option<TimerAction> {
+        if let Some(logout) = &self.pending_logout {
+            return (now >= logout.deadline).then_some(TimerAction::CloseLogout);
+        }
+        let grace = Duration::from_secs(self.heartbeat_interval as u64 + 1);
+        if let Some(pending) = &self.pending_test_request {
+            let waited = (now - pending.sent_at).to_std().unwrap_or_default();
+            return (waited > grace).then_some(TimerAction::TimedOut);
+        }
+        if self.is_heartbeat_overdue(now) {
+            let test_req_id = format!("TEST-{}", now.timestamp_millis());
+            self.pending_test_request = Some(PendingTestRequest {
+                test_req_id: test_req_id.clone(),
+                sent_at: now,
+            });
+            return Some(TimerAction::SendTestRequest(test_req_id));
+        }
+        self.needs_heartbeat(now).then_some(TimerAction::SendHeartbeat)
+    }
+
+    /// Records that we sent Logout; the session waits for the counterparty's
+    /// Logout until `timers.confirm_timeout` has passed
+    pub fn begin_logout(&mut self, reason: &str, timers: &LogoutTimers, now: DateTime<Utc>) -> Result<(), SessionError> {
+        self.transition_to(SessionState::LogoutPending)?;
+        self.pending_logout = Some(PendingLogout {
+            initiator: LogoutInitiator::Sequencer,
+            reason: reason.to_string(),
+            deadline: now + timers.confirm_timeout,
+        });
         Ok(())
     }
 
+    /// Records the counterparty's Logout. Returns whether it confirms one we
+    /// sent; otherwise it must be answered and the connection closed after
+    /// `timers.grace`.
+    pub fn logout_received(&mut self, text: Option<&str>, timers: &LogoutTimers, now: DateTime<Utc>) -> Result<bool, SessionError> {
+        if self.state == SessionState::LogoutPending {
+            self.transition_to(SessionState::Disconnecting)?;
+            return Ok(true);
+        }
+        self.transition_to(SessionState::Disconnecting)?;
+        self.pending_logout = Some(PendingLogout {
+            initiator: LogoutInitiator::Counterparty,
+            reason: match text.filter(|text| !text.is_empty()) {
+                Some(text) => format!("counterparty logout: {}", text),
+                None => "counterparty logout".to_string(),
+            },
+            deadline: now + timers.grace,
+        });
+        Ok(false)
+    }
+
     /// Update the last sent time and sequence number
-    pub fn message_sent(&mut self) {
-        self.last_sent = Utc::now();
+    pub fn message_sent(&mut self, now: DateTime<Utc>) {
+        self.last_sent = now;
         self.next_outgoing_seq += 1;
     }
 
     /// Check if this session needs a heartbeat sent
-    pub fn needs_heartbeat(&self) -> bool {
-        let elapsed = Utc::now() - self.last_sent;
+    pub fn needs_heartbeat(&self, now: DateTime<Utc>) -> bool {
+        let elapsed = (now - self.last_sent).to_std().unwrap_or_default();
         elapsed >= Duration::from_secs((self.heartbeat_interval as f64 * 0.7) as u64)
     }
 
@@ -117,7 +331,13 @@ impl Session {
             (Authenticating, Active) |
             (Active, ResyncRequired) |
             (ResyncRequired, Active) |
+            (Active, LogoutPending) |
+            (ResyncRequired, LogoutPending) |
+            (LogoutPending, Disconnecting) |
+            (Connecting, Disconnecting) |
+            (Authenticating, Disconnecting) |
             (Active, Disconnecting) |
+            (ResyncRequired, Disconnecting) |
             (Disconnecting, Terminated) => {
                 self.state = new_state;
                 Ok(())
@@ -140,17 +360,74 @@ pub enum SessionError {
         received: u64,
     },
 
+    #[error("NextExpectedMsgSeqNum {expected} is beyond our next sequence number {next}")]
+    NextExpectedAhead {
+        expected: u64,
+        next: u64,
+    },
+
     #[error("Invalid state transition from {from:?} to {to:?}")]
     InvalidTransition {
         from: SessionState,
         to: SessionState,
     },
 
+    #[error("Session not accepting messages in state {0:?}")]
+    InvalidState(SessionState),
+
     #[error("Session not found: {0}")]
     NotFound(Uuid),
 
     #[error("Authentication failed: {0}")]
     AuthenticationFailed(String),
+
+    #[error("Order flow for firm {0} is blocked by the kill switch")]
+    FirmBlocked(String),
+
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+
+    #[error("{0}")]
+    UnknownSymbol(String),
+
+    #[error("{}", .0.text)]
+    OrderRejected(OrderRejection),
+
+    #[error("Duplicate ClOrdID {0}")]
+    DuplicateClOrdId(String),
+
+    #[error("Storage error: {0}")]
+    Storage(String),
+
+    #[error("Order acceptance paused: storage capacity below floor")]
+    CapacityPaused,
+
+    #[error("Market closed")]
+    MarketClosed,
+
+    #[error("Heartbeat negotiation failed: {0}")]
+    InvalidHeartbeat(String),
+
+    #[error("Failed to send to connection: {0}")]
+    Transport(String),
+}
+
+impl SessionError {
+    /// Catalogue reason reported to the counterparty for an order refused
+    /// with this error
+    pub fn reject_reason(&self) -> RejectReason {
+        match self {
+            SessionError::DuplicateClOrdId(_) => RejectReason::DuplicateOrder,
+            SessionError::PermissionDenied(_) => RejectReason::PermissionDenied,
+            SessionError::UnknownSymbol(_) => RejectReason::UnknownSymbol,
+            SessionError::OrderRejected(rejection) => rejection.reason,
+            SessionError::MarketClosed => RejectReason::MarketClosed,
+            SessionError::CapacityPaused => RejectReason::CapacityPaused,
+            SessionError::FirmBlocked(_) => RejectReason::FirmBlocked,
+            SessionError::InvalidState(_) | SessionError::NotFound(_) => RejectReason::NoSession,
+            _ => RejectReason::Other,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -163,6 +440,7 @@ mod tests {
             "TARGET".to_string(),
             30,
             vec![1, 2, 3, 4], // Dummy public key
+            Utc::now(),
         )
     }
 
@@ -179,11 +457,121 @@ mod tests {
         let mut session = create_test_session();
         
         // Test valid sequence
-        assert!(session.message_received(1).is_ok());
+        assert!(session.message_received(1, Utc::now()).is_ok());
         assert_eq!(session.next_incoming_seq, 2);
 
         // Test invalid sequence
-        assert!(session.message_received(3).is_err());
+        assert!(session.message_received(3, Utc::now()).is_err());
+    }
+
+    #[test]
+    fn test_heartbeat_timing() {
+        let start = Utc::now();
+        let session = Session::new("SENDER".to_string(), "TARGET".to_string(), 30, vec![], start);
+
+        assert!(!session.needs_heartbeat(start + chrono::Duration::seconds(20)));
+        assert!(session.needs_heartbeat(start + chrono::Duration::seconds(21)));
+        assert!(!session.is_heartbeat_overdue(start + chrono::Duration::seconds(31)));
+        assert!(session.is_heartbeat_overdue(start + chrono::Duration::seconds(32)));
+    }
+
+    #[test]
+    fn test_timers_follow_negotiated_interval() {
+        let start = Utc::now();
+        let mut session = Session::new("SENDER".to_string(), "TARGET".to_string(), 10, vec![], start);
+        let at = |secs| start + chrono::Duration::seconds(secs);
+
+        assert_eq!(session.poll_timers(at(5)), None);
+        assert_eq!(session.poll_timers(at(7)), Some(TimerAction::SendHeartbeat));
+
+        // Silence past the interval sends one TestRequest, then times out
+        let Some(TimerAction::SendTestRequest(id)) = session.poll_timers(at(12)) else {
+            panic!("expected a TestRequest");
+        };
+        assert_eq!(session.pending_test_request.as_ref().unwrap().test_req_id, id);
+        assert_eq!(session.poll_timers(at(20)), None);
+        assert_eq!(session.poll_timers(at(24)), Some(TimerAction::TimedOut));
+
+        // A reply clears the TestRequest
+        session.message_received(1, at(21)).unwrap();
+        assert!(session.pending_test_request.is_none());
+    }
+
+    #[test]
+    fn test_logout_handshake() {
+        let start = Utc::now();
+        let timers = LogoutTimers::default();
+        let active = || {
+            let mut session = create_test_session();
+            session.transition_to(SessionState::Authenticating).unwrap();
+            session.transition_to(SessionState::Active).unwrap();
+            session
+        };
+
+        // Initiated: the counterparty's Logout confirms ours
+        let mut session = active();
+        session.begin_logout("end of day", &timers, start).unwrap();
+        assert_eq!(session.state, SessionState::LogoutPending);
+        assert_eq!(session.poll_timers(start + chrono::Duration::seconds(9)), None);
+        assert!(session.logout_received(None, &timers, start).unwrap());
+        assert_eq!(session.state, SessionState::Disconnecting);
+
+        // Initiated: no confirmation within the timeout
+        let mut session = active();
+        session.begin_logout("end of day", &timers, start).unwrap();
+        assert_eq!(
+            session.poll_timers(start + chrono::Duration::seconds(10)),
+            Some(TimerAction::CloseLogout)
+        );
+
+        // Received: answered, then closed after the grace period
+        let mut session = active();
+        assert!(!session.logout_received(Some("done"), &timers, start).unwrap());
+        assert_eq!(session.state, SessionState::Disconnecting);
+        assert_eq!(session.pending_logout.as_ref().unwrap().reason, "counterparty logout: done");
+        assert_eq!(session.poll_timers(start + chrono::Duration::seconds(1)), None);
+        assert_eq!(
+            session.poll_timers(start + chrono::Duration::seconds(2)),
+            Some(TimerAction::CloseLogout)
+        );
+    }
+
+    #[test]
+    fn test_sequence_negotiation() {
+        let resume = Some(ResumePoint {
+            next_incoming_seq: 10,
+            next_outgoing_seq: 20,
+            closed_at: Utc::now(),
+        });
+        let logon = |logon_seq, next_expected| SequenceNegotiation {
+            reset: false,
+            logon_seq,
+            next_expected,
+        };
+
+        // Clean resume
+        let mut session = create_test_session();
+        let recovery = session.negotiate_sequences(resume, logon(10, Some(20))).unwrap();
+        assert_eq!(recovery, SequenceRecovery::default());
+        assert_eq!((session.next_incoming_seq, session.next_outgoing_seq), (11, 20));
+
+        // Both sides missed messages
+        let mut session = create_test_session();
+        let recovery = session.negotiate_sequences(resume, logon(13, Some(17))).unwrap();
+        assert_eq!(recovery.resend, Some((10, 12)));
+        assert_eq!(recovery.gap_fill_from, Some(17));
+        assert_eq!(session.next_incoming_seq, 10);
+
+        // Too low, or expecting messages we never sent
+        let mut session = create_test_session();
+        assert!(session.negotiate_sequences(resume, logon(9, None)).is_err());
+        assert!(session.negotiate_sequences(resume, logon(10, Some(21))).is_err());
+        assert_eq!(session.next_incoming_seq, 1);
+
+        // ResetSeqNumFlag starts over
+        let reset = SequenceNegotiation { reset: true, logon_seq: 1, next_expected: None };
+        session.negotiate_sequences(resume, reset).unwrap();
+        assert_eq!((session.next_incoming_seq, session.next_outgoing_seq), (2, 1));
     }
 
     #[test]
@@ -0,0 +1,73 @@
+// src/settlement/adapter.rs
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum SettlementError {
+    #[error("No settlement account configured for firm {0}")]
+    NoAccount(String),
+
+    #[error("No reconciliation reports built for {0}")]
+    NoReports(NaiveDate),
+
+    #[error("Invalid settlement account {account}: {reason}")]
+    InvalidAccount { account: String, reason: String },
+
+    #[error("{adapter} cannot settle this instruction: {reason}")]
+    Unsupported { adapter: String, reason: String },
+
+    #[error("{adapter} rejected the instruction: {reason}")]
+    Rejected { adapter: String, reason: String },
+}
+
+/// A firm's net movement of value for one trading day
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SettlementInstruction {
+    /// `<day>-<firm>`, the same each time the day is settled so external
+    /// systems and the service can recognize a resubmission
+    pub id: String,
+    pub day: NaiveDate,
+    pub firm: String,
+    /// The firm's account on the external system
+    pub account: String,
+    pub asset: String,
+    /// Paid to the firm when positive, collected from it when negative
+    pub amount: i128,
+}
+
+impl SettlementInstruction {
+    pub fn new(day: NaiveDate, firm: &str, account: &str, asset: &str, amount: i128) -> Self {
+        Self {
+            id: format!("{}-{}", day, firm),
+            day,
+            firm: firm.to_string(),
+            account: account.to_string(),
+            asset: asset.to_string(),
+            amount,
+        }
+    }
+}
+
+/// Proof that an external system accepted an instruction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SettlementReceipt {
+    pub instruction: SettlementInstruction,
+    pub adapter: String,
+    /// The external system's reference, e.g. a transaction hash
+    pub reference: String,
+    pub submitted_at: DateTime<Utc>,
+}
+
+pub type SubmitFuture<'a> = Pin<Box<dyn Future<Output = Result<String, SettlementError>> + Send + 'a>>;
+
+/// An external system net settlement instructions are pushed to
+pub trait SettlementAdapter: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Submits `instruction`, resolving to the external reference
+    fn submit<'a>(&'a self, instruction: &'a SettlementInstruction) -> SubmitFuture<'a>;
+}
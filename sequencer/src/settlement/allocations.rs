@@ -0,0 +1,421 @@
+// src/settlement/allocations.rs
+
+use crate::events::bus::EventBus;
+use crate::events::types::SequencerEvent;
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use romer_common::fix::allocation::{AllocStatus, AllocationAck, AllocationInstruction};
+use romer_common::utils::clock::SharedClock;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::{info, warn};
+
+#[derive(Error, Debug)]
+pub enum AllocationError {
+    #[error("Allocation {0} already exists")]
+    Duplicate(String),
+
+    #[error("Unknown allocation {0}")]
+    Unknown(String),
+
+    #[error("{sender} is not a clearing firm of allocation {alloc_id}")]
+    NotClearingFirm { alloc_id: String, sender: String },
+
+    #[error("Allocation {0} is no longer awaiting clearing firms")]
+    Closed(String),
+
+    #[error("Failed to journal allocation: {0}")]
+    Io(#[from] io::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AllocationStatus {
+    /// Awaiting clearing firms' acks
+    Pending,
+    /// Every clearing firm accepted; the executions are theirs to settle
+    Accepted,
+    /// A clearing firm rejected; the executions stay with the executing firm
+    Rejected,
+}
+
+/// A give-up and the clearing firms' answers so far
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AllocationRecord {
+    /// SenderCompID of the firm that sent the instruction
+    pub executing_firm: String,
+    pub instruction: AllocationInstruction,
+    /// Each clearing firm that answered, by SenderCompID
+    pub responses: BTreeMap<String, AllocationAck>,
+    pub status: AllocationStatus,
+    pub received_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl AllocationRecord {
+    /// Clearing firms yet to answer
+    pub fn awaiting(&self) -> Vec<String> {
+        self.instruction
+            .allocations
+            .iter()
+            .map(|entry| entry.clearing_firm.clone())
+            .filter(|firm| !self.responses.contains_key(firm))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+}
+
+/// A message held for a firm until its next FIX message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outbound {
+    /// An instruction given up to the clearing firm
+    Instruction(AllocationInstruction),
+    /// The outcome of the executing firm's instruction
+    Ack(AllocationAck),
+}
+
+impl Outbound {
+    pub fn encode(&self, sender_comp_id: &str, target_comp_id: &str, msg_seq_num: u64, sending_time: DateTime<Utc>) -> Vec<u8> {
+        match self {
+            Self::Instruction(instruction) => instruction.encode(sender_comp_id, target_comp_id, msg_seq_num, sending_time),
+            Self::Ack(ack) => ack.encode(sender_comp_id, target_comp_id, msg_seq_num, sending_time),
+        }
+    }
+}
+
+/// Post-trade give-ups: AllocationInstructions (35=J) from executing firms
+/// are routed to each clearing firm named, whose AllocationACKs (35=P)
+/// decide them. Once every clearing firm accepts, an `AllocationAccepted`
+/// event moves the executions, and the settlement they carry, to the
+/// clearing firms in reconciliation. Records are appended to a journal
+/// replayed at startup.
+pub struct AllocationService {
+    /// By AllocID
+    allocations: Mutex<BTreeMap<String, AllocationRecord>>,
+    /// By the SenderCompID they are for
+    outbound: Mutex<HashMap<String, Vec<Outbound>>>,
+    journal: Option<PathBuf>,
+    events: EventBus,
+    clock: SharedClock,
+}
+
+impl AllocationService {
+    pub fn new(events: EventBus, clock: SharedClock) -> Self {
+        Self {
+            allocations: Mutex::new(BTreeMap::new()),
+            outbound: Mutex::new(HashMap::new()),
+            journal: None,
+            events,
+            clock,
+        }
+    }
+
+    /// Appends every change to the JSON lines file at `path`, replaying
+    /// those already there. Pending instructions are routed again to the
+    /// clearing firms that have not answered.
+    pub fn with_journal(mut self, path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        if path.exists() {
+            let mut allocations = self.allocations.lock();
+            for line in BufReader::new(File::open(path)?).lines() {
+                match serde_json::from_str::<AllocationRecord>(&line?) {
+                    Ok(record) => {
+                        allocations.insert(record.instruction.alloc_id.clone(), record);
+                    }
+                    Err(e) => warn!(error = %e, "Skipping undecodable allocation"),
+                }
+            }
+            let mut outbound = self.outbound.lock();
+            for record in allocations
+                .values()
+                .filter(|record| record.status == AllocationStatus::Pending)
+            {
+                for firm in record.awaiting() {
+                    outbound
+                        .entry(firm)
+                        .or_default()
+                        .push(Outbound::Instruction(record.instruction.clone()));
+                }
+            }
+        }
+        self.journal = Some(path.to_path_buf());
+        Ok(self)
+    }
+
+    /// Takes an instruction from `executing_firm` and routes it to its
+    /// clearing firms
+    pub fn submit(
+        &self,
+        executing_firm: &str,
+        instruction: AllocationInstruction,
+    ) -> Result<AllocationRecord, AllocationError> {
+        let mut allocations = self.allocations.lock();
+        if allocations.contains_key(&instruction.alloc_id) {
+            return Err(AllocationError::Duplicate(instruction.alloc_id));
+        }
+        let now = self.clock.now();
+        let record = AllocationRecord {
+            executing_firm: executing_firm.to_string(),
+            instruction,
+            responses: BTreeMap::new(),
+            status: AllocationStatus::Pending,
+            received_at: now,
+            updated_at: now,
+        };
+        self.record(&record)?;
+        allocations.insert(record.instruction.alloc_id.clone(), record.clone());
+        drop(allocations);
+
+        let mut outbound = self.outbound.lock();
+        for firm in record.awaiting() {
+            outbound
+                .entry(firm)
+                .or_default()
+                .push(Outbound::Instruction(record.instruction.clone()));
+        }
+        info!(alloc_id = %record.instruction.alloc_id, executing_firm, "Allocation received");
+        Ok(record)
+    }
+
+    /// Applies `clearing_firm`'s ack. The first rejection rejects the
+    /// allocation; the last acceptance accepts it. Either outcome is held
+    /// for the executing firm.
+    pub fn acknowledge(&self, clearing_firm: &str, ack: AllocationAck) -> Result<AllocationRecord, AllocationError> {
+        let mut allocations = self.allocations.lock();
+        let record = allocations
+            .get_mut(&ack.alloc_id)
+            .ok_or_else(|| AllocationError::Unknown(ack.alloc_id.clone()))?;
+        if !record
+            .instruction
+            .allocations
+            .iter()
+            .any(|entry| entry.clearing_firm == clearing_firm)
+        {
+            return Err(AllocationError::NotClearingFirm {
+                alloc_id: ack.alloc_id,
+                sender: clearing_firm.to_string(),
+            });
+        }
+        if record.status != AllocationStatus::Pending {
+            return Err(AllocationError::Closed(ack.alloc_id));
+        }
+        // Receipt alone decides nothing
+        if ack.status == AllocStatus::Received {
+            return Ok(record.clone());
+        }
+
+        let mut updated = record.clone();
+        updated.responses.insert(clearing_firm.to_string(), ack.clone());
+        updated.updated_at = self.clock.now();
+        let outcome = if ack.status != AllocStatus::Accepted {
+            updated.status = AllocationStatus::Rejected;
+            Some(AllocationAck {
+                text: Some(format!(
+                    "rejected by {}{}",
+                    clearing_firm,
+                    ack.text
+                        .as_deref()
+                        .map(|text| format!(": {}", text))
+                        .unwrap_or_default()
+                )),
+                ..ack
+            })
+        } else if updated.awaiting().is_empty() {
+            updated.status = AllocationStatus::Accepted;
+            Some(AllocationAck { text: None, ..ack })
+        } else {
+            None
+        };
+        self.record(&updated)?;
+        *record = updated.clone();
+        drop(allocations);
+
+        if let Some(outcome) = outcome {
+            info!(alloc_id = %outcome.alloc_id, status = ?updated.status, "Allocation decided");
+            self.outbound
+                .lock()
+                .entry(updated.executing_firm.clone())
+                .or_default()
+                .push(Outbound::Ack(outcome));
+        }
+        if updated.status == AllocationStatus::Accepted {
+            let instruction = &updated.instruction;
+            self.events.publish(SequencerEvent::AllocationAccepted {
+                alloc_id: instruction.alloc_id.clone(),
+                executing_firm: updated.executing_firm.clone(),
+                symbol: instruction.symbol.clone(),
+                side: instruction.side,
+                avg_price: instruction.avg_price,
+                allocations: instruction.allocations.clone(),
+                at: updated.updated_at,
+            });
+        }
+        Ok(updated)
+    }
+
+    /// Messages held for `sender_comp_id`, removed
+    pub fn take_outbound(&self, sender_comp_id: &str) -> Vec<Outbound> {
+        self.outbound.lock().remove(sender_comp_id).unwrap_or_default()
+    }
+
+    /// Allocations sent by or given up to `firm`, every allocation without
+    pub fn allocations(&self, firm: Option<&str>) -> Vec<AllocationRecord> {
+        self.allocations
+            .lock()
+            .values()
+            .filter(|record| {
+                firm.is_none()
+                    || firm == Some(record.executing_firm.as_str())
+                    || record
+                        .instruction
+                        .allocations
+                        .iter()
+                        .any(|entry| firm == Some(entry.clearing_firm.as_str()))
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn record(&self, record: &AllocationRecord) -> io::Result<()> {
+        if let Some(path) = &self.journal {
+            let mut line = serde_json::to_vec(record).map_err(io::Error::other)?;
+            line.push(b'\n');
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)?
+                .write_all(&line)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use romer_common::fix::allocation::{AllocSide, AllocationEntry};
+    use romer_common::utils::clock::system_clock;
+
+    fn instruction(alloc_id: &str) -> AllocationInstruction {
+        AllocationInstruction {
+            alloc_id: alloc_id.into(),
+            side: AllocSide::Buy,
+            symbol: "AAPL".into(),
+            quantity: 300,
+            avg_price: 150_000_000_000,
+            trade_date: None,
+            allocations: vec![
+                AllocationEntry {
+                    account: "FUND-1".into(),
+                    clearing_firm: "CLR1".into(),
+                    quantity: 200,
+                },
+                AllocationEntry {
+                    account: "FUND-2".into(),
+                    clearing_firm: "CLR2".into(),
+                    quantity: 100,
+                },
+            ],
+        }
+    }
+
+    fn ack(alloc_id: &str, status: AllocStatus) -> AllocationAck {
+        AllocationAck {
+            alloc_id: alloc_id.into(),
+            status,
+            text: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_accepted_by_every_clearing_firm() {
+        let events = EventBus::new(16);
+        let mut audit = events.subscribe();
+        let service = AllocationService::new(events, system_clock());
+
+        service.submit("EXEC1", instruction("A1")).unwrap();
+        assert!(matches!(
+            service.submit("EXEC1", instruction("A1")),
+            Err(AllocationError::Duplicate(_))
+        ));
+        assert!(matches!(
+            service.take_outbound("CLR1").as_slice(),
+            [Outbound::Instruction(instruction)] if instruction.alloc_id == "A1"
+        ));
+        assert_eq!(service.take_outbound("CLR2").len(), 1);
+
+        assert!(matches!(
+            service.acknowledge("CLR3", ack("A1", AllocStatus::Accepted)),
+            Err(AllocationError::NotClearingFirm { .. })
+        ));
+        let record = service.acknowledge("CLR1", ack("A1", AllocStatus::Accepted)).unwrap();
+        assert_eq!(
+            (record.status, record.awaiting()),
+            (AllocationStatus::Pending, vec!["CLR2".to_string()])
+        );
+        assert!(service.take_outbound("EXEC1").is_empty());
+
+        let record = service.acknowledge("CLR2", ack("A1", AllocStatus::Accepted)).unwrap();
+        assert_eq!(record.status, AllocationStatus::Accepted);
+        assert!(matches!(
+            service.take_outbound("EXEC1").as_slice(),
+            [Outbound::Ack(AllocationAck {
+                status: AllocStatus::Accepted,
+                ..
+            })]
+        ));
+        match &*audit.recv().await.unwrap() {
+            SequencerEvent::AllocationAccepted {
+                alloc_id, allocations, ..
+            } => {
+                assert_eq!((alloc_id.as_str(), allocations.len()), ("A1", 2));
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+        assert!(matches!(
+            service.acknowledge("CLR2", ack("A1", AllocStatus::BlockRejected)),
+            Err(AllocationError::Closed(_))
+        ));
+    }
+
+    #[test]
+    fn test_rejection_and_replay() {
+        let path = std::env::temp_dir().join(format!("allocations-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let open = || {
+            AllocationService::new(EventBus::new(16), system_clock())
+                .with_journal(&path)
+                .unwrap()
+        };
+
+        let service = open();
+        service.submit("EXEC1", instruction("A1")).unwrap();
+        service.submit("EXEC1", instruction("A2")).unwrap();
+        let rejection = AllocationAck {
+            text: Some("unknown account".into()),
+            ..ack("A1", AllocStatus::AccountRejected)
+        };
+        let record = service.acknowledge("CLR2", rejection).unwrap();
+        assert_eq!(record.status, AllocationStatus::Rejected);
+        match service.take_outbound("EXEC1").as_slice() {
+            [Outbound::Ack(ack)] => assert_eq!(ack.text.as_deref(), Some("rejected by CLR2: unknown account")),
+            other => panic!("unexpected outbound {:?}", other),
+        }
+        service.acknowledge("CLR1", ack("A2", AllocStatus::Accepted)).unwrap();
+
+        // Only A2, still awaiting CLR2, is routed again
+        let restarted = open();
+        assert_eq!(restarted.allocations(Some("EXEC1")).len(), 2);
+        assert!(restarted.take_outbound("CLR1").is_empty());
+        assert!(matches!(
+            restarted.take_outbound("CLR2").as_slice(),
+            [Outbound::Instruction(instruction)] if instruction.alloc_id == "A2"
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
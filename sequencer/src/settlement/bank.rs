@@ -0,0 +1,34 @@
+// src/settlement/bank.rs
+
+use super::adapter::{SettlementAdapter, SettlementInstruction, SubmitFuture};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::info;
+
+/// Stands in for a bank payments API: accepts every instruction, logs it
+/// and answers with a sequential payment reference. Lets the settlement
+/// flow run end to end before a real bank integration exists.
+#[derive(Default)]
+pub struct BankStub {
+    payments: AtomicU64,
+}
+
+impl SettlementAdapter for BankStub {
+    fn name(&self) -> &str {
+        "bank"
+    }
+
+    fn submit<'a>(&'a self, instruction: &'a SettlementInstruction) -> SubmitFuture<'a> {
+        Box::pin(async move {
+            let reference = format!("BANK-{:08}", self.payments.fetch_add(1, Ordering::Relaxed) + 1);
+            info!(
+                id = %instruction.id,
+                account = %instruction.account,
+                asset = %instruction.asset,
+                amount = %instruction.amount,
+                %reference,
+                "Bank payment instruction accepted"
+            );
+            Ok(reference)
+        })
+    }
+}
@@ -0,0 +1,167 @@
+// src/settlement/evm.rs
+
+use super::adapter::{SettlementAdapter, SettlementError, SettlementInstruction, SubmitFuture};
+use romer_common::utils::rpc;
+use serde_json::{json, Value};
+
+/// `transfer(address,uint256)`
+const TRANSFER: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+/// `transferFrom(address,address,uint256)`
+const TRANSFER_FROM: [u8; 4] = [0x23, 0xb8, 0x72, 0xdd];
+
+/// Settles on an EVM chain through `eth_sendTransaction`, signed by the
+/// node or a signing proxy in front of it for the `treasury` account.
+/// With a `token` contract, firms are paid with `transfer` and collected
+/// from with `transferFrom` against the allowance they granted the
+/// treasury. Without one, payments are native transfers and collections
+/// are refused, since native value cannot be pulled from an account.
+pub struct EvmAdapter {
+    /// JSON-RPC endpoint, `host:port`
+    endpoint: String,
+    treasury: String,
+    token: Option<String>,
+}
+
+impl EvmAdapter {
+    pub fn new(endpoint: impl Into<String>, treasury: impl Into<String>, token: Option<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            treasury: treasury.into(),
+            token,
+        }
+    }
+
+    /// The `eth_sendTransaction` parameters settling `instruction`
+    pub fn transaction(&self, instruction: &SettlementInstruction) -> Result<Value, SettlementError> {
+        let account = address_word(&instruction.account)?;
+        let amount = instruction.amount.unsigned_abs();
+        let transaction = match (&self.token, instruction.amount >= 0) {
+            (None, true) => json!({
+                "from": self.treasury,
+                "to": instruction.account,
+                "value": format!("0x{:x}", amount),
+            }),
+            (None, false) => {
+                return Err(SettlementError::Unsupported {
+                    adapter: self.name().to_string(),
+                    reason: "collecting native value needs a token contract".into(),
+                })
+            }
+            (Some(token), true) => json!({
+                "from": self.treasury,
+                "to": token,
+                "data": calldata(TRANSFER, &[account, amount_word(amount)]),
+            }),
+            (Some(token), false) => json!({
+                "from": self.treasury,
+                "to": token,
+                "data": calldata(TRANSFER_FROM, &[account, address_word(&self.treasury)?, amount_word(amount)]),
+            }),
+        };
+        Ok(transaction)
+    }
+}
+
+impl SettlementAdapter for EvmAdapter {
+    fn name(&self) -> &str {
+        "evm"
+    }
+
+    fn submit<'a>(&'a self, instruction: &'a SettlementInstruction) -> SubmitFuture<'a> {
+        Box::pin(async move {
+            let transaction = self.transaction(instruction)?;
+            let result = rpc::call(&self.endpoint, "eth_sendTransaction", json!([transaction]))
+                .await
+                .map_err(|e| SettlementError::Rejected {
+                    adapter: self.name().to_string(),
+                    reason: e.to_string(),
+                })?;
+            result
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| SettlementError::Rejected {
+                    adapter: self.name().to_string(),
+                    reason: format!("unexpected result {}", result),
+                })
+        })
+    }
+}
+
+/// A 20 byte `0x` address left padded to a 32 byte ABI word
+fn address_word(address: &str) -> Result<[u8; 32], SettlementError> {
+    let invalid = |reason: &str| SettlementError::InvalidAccount {
+        account: address.to_string(),
+        reason: reason.to_string(),
+    };
+    let bytes = hex::decode(address.strip_prefix("0x").unwrap_or(address)).map_err(|_| invalid("not hex"))?;
+    if bytes.len() != 20 {
+        return Err(invalid("not 20 bytes"));
+    }
+    let mut word = [0u8; 32];
+    word[12..].copy_from_slice(&bytes);
+    Ok(word)
+}
+
+fn amount_word(amount: u128) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[16..].copy_from_slice(&amount.to_be_bytes());
+    word
+}
+
+fn calldata(selector: [u8; 4], words: &[[u8; 32]]) -> String {
+    let mut data = selector.to_vec();
+    for word in words {
+        data.extend_from_slice(word);
+    }
+    format!("0x{}", hex::encode(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    const FIRM: &str = "0x1111111111111111111111111111111111111111";
+    const TREASURY: &str = "0x2222222222222222222222222222222222222222";
+    const TOKEN: &str = "0x3333333333333333333333333333333333333333";
+
+    fn instruction(amount: i128) -> SettlementInstruction {
+        SettlementInstruction::new(NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(), "ACME", FIRM, "USDC", amount)
+    }
+
+    #[test]
+    fn test_native_transfers() {
+        let adapter = EvmAdapter::new("127.0.0.1:8545", TREASURY, None);
+        let transaction = adapter.transaction(&instruction(255)).unwrap();
+        assert_eq!(transaction["to"], FIRM);
+        assert_eq!(transaction["value"], "0xff");
+        assert!(matches!(
+            adapter.transaction(&instruction(-1)),
+            Err(SettlementError::Unsupported { .. })
+        ));
+    }
+
+    #[test]
+    fn test_token_transfers() {
+        let adapter = EvmAdapter::new("127.0.0.1:8545", TREASURY, Some(TOKEN.to_string()));
+        let pay = adapter.transaction(&instruction(1000)).unwrap();
+        assert_eq!(pay["to"], TOKEN);
+        assert_eq!(
+            pay["data"],
+            format!("0xa9059cbb{:0>64}{:064x}", &FIRM[2..], 1000)
+        );
+
+        let collect = adapter.transaction(&instruction(-1000)).unwrap();
+        assert_eq!(
+            collect["data"],
+            format!("0x23b872dd{:0>64}{:0>64}{:064x}", &FIRM[2..], &TREASURY[2..], 1000)
+        );
+
+        let mut invalid = instruction(1);
+        invalid.account = "0x1234".into();
+        assert!(matches!(
+            adapter.transaction(&invalid),
+            Err(SettlementError::InvalidAccount { .. })
+        ));
+    }
+}
@@ -0,0 +1,5 @@
+pub mod adapter;
+pub mod allocations;
+pub mod bank;
+pub mod evm;
+pub mod service;
@@ -0,0 +1,227 @@
+// src/settlement/service.rs
+
+use super::adapter::{SettlementAdapter, SettlementError, SettlementInstruction, SettlementReceipt};
+use crate::audit::reconciliation::FirmReport;
+use chrono::NaiveDate;
+use parking_lot::Mutex;
+use romer_common::utils::clock::SharedClock;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// A firm whose instruction could not be settled
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SettlementFailure {
+    pub firm: String,
+    pub reason: String,
+}
+
+/// What settling a day did
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SettlementOutcome {
+    pub submitted: Vec<SettlementReceipt>,
+    /// Instructions settled before, which are not submitted again
+    pub already_settled: Vec<String>,
+    pub failed: Vec<SettlementFailure>,
+}
+
+/// Turns each firm's reconciled settlement amount into an instruction and
+/// pushes it through the adapter. Receipts are appended to a file so a day
+/// settled before a restart is not paid twice.
+pub struct SettlementService {
+    adapter: Box<dyn SettlementAdapter>,
+    /// Firm to its account on the external system
+    accounts: BTreeMap<String, String>,
+    asset: String,
+    receipts_path: Option<PathBuf>,
+    receipts: Mutex<BTreeMap<String, SettlementReceipt>>,
+    /// Held while a day settles, so concurrent requests cannot both submit
+    settling: tokio::sync::Mutex<()>,
+    clock: SharedClock,
+}
+
+impl SettlementService {
+    pub fn new(
+        adapter: Box<dyn SettlementAdapter>,
+        accounts: BTreeMap<String, String>,
+        asset: impl Into<String>,
+        clock: SharedClock,
+    ) -> Self {
+        Self {
+            adapter,
+            accounts,
+            asset: asset.into(),
+            receipts_path: None,
+            receipts: Mutex::new(BTreeMap::new()),
+            settling: tokio::sync::Mutex::new(()),
+            clock,
+        }
+    }
+
+    /// Keeps receipts in the JSON lines file at `path`, loading those
+    /// already there
+    pub fn with_receipts(mut self, path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        if path.exists() {
+            let mut receipts = self.receipts.lock();
+            for line in BufReader::new(File::open(path)?).lines() {
+                let line = line?;
+                match serde_json::from_str::<SettlementReceipt>(&line) {
+                    Ok(receipt) => {
+                        receipts.insert(receipt.instruction.id.clone(), receipt);
+                    }
+                    Err(e) => warn!(error = %e, "Skipping undecodable settlement receipt"),
+                }
+            }
+        }
+        self.receipts_path = Some(path.to_path_buf());
+        Ok(self)
+    }
+
+    pub fn adapter(&self) -> &str {
+        self.adapter.name()
+    }
+
+    /// The instruction settling `report`
+    pub fn instruction(&self, report: &FirmReport) -> Result<SettlementInstruction, SettlementError> {
+        let account = self
+            .accounts
+            .get(&report.firm)
+            .ok_or_else(|| SettlementError::NoAccount(report.firm.clone()))?;
+        Ok(SettlementInstruction::new(
+            report.day,
+            &report.firm,
+            account,
+            &self.asset,
+            report.settlement,
+        ))
+    }
+
+    /// Settles every report with a nonzero amount that has not been settled
+    pub async fn settle(&self, reports: &[FirmReport]) -> SettlementOutcome {
+        let _settling = self.settling.lock().await;
+        let mut outcome = SettlementOutcome::default();
+        for report in reports.iter().filter(|report| report.settlement != 0) {
+            let fail = |e: SettlementError| SettlementFailure {
+                firm: report.firm.clone(),
+                reason: e.to_string(),
+            };
+            let instruction = match self.instruction(report) {
+                Ok(instruction) => instruction,
+                Err(e) => {
+                    outcome.failed.push(fail(e));
+                    continue;
+                }
+            };
+            if self.receipts.lock().contains_key(&instruction.id) {
+                outcome.already_settled.push(instruction.id);
+                continue;
+            }
+            match self.adapter.submit(&instruction).await {
+                Ok(reference) => {
+                    info!(id = %instruction.id, amount = %instruction.amount, %reference, adapter = self.adapter.name(), "Settlement submitted");
+                    let receipt = SettlementReceipt {
+                        instruction,
+                        adapter: self.adapter.name().to_string(),
+                        reference,
+                        submitted_at: self.clock.now(),
+                    };
+                    if let Err(e) = self.record(&receipt) {
+                        warn!(id = %receipt.instruction.id, error = %e, "Failed to persist settlement receipt");
+                    }
+                    outcome.submitted.push(receipt);
+                }
+                Err(e) => {
+                    warn!(id = %instruction.id, error = %e, "Settlement failed");
+                    outcome.failed.push(fail(e));
+                }
+            }
+        }
+        outcome
+    }
+
+    fn record(&self, receipt: &SettlementReceipt) -> io::Result<()> {
+        self.receipts
+            .lock()
+            .insert(receipt.instruction.id.clone(), receipt.clone());
+        if let Some(path) = &self.receipts_path {
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}", serde_json::to_string(receipt)?)?;
+        }
+        Ok(())
+    }
+
+    /// Receipts of `day`, every day without
+    pub fn receipts(&self, day: Option<NaiveDate>) -> Vec<SettlementReceipt> {
+        self.receipts
+            .lock()
+            .values()
+            .filter(|receipt| day.map_or(true, |day| receipt.instruction.day == day))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::reconciliation::Reconciler;
+    use crate::events::types::SequencerEvent;
+    use crate::settlement::bank::BankStub;
+    use chrono::{TimeZone, Utc};
+    use romer_common::utils::clock::system_clock;
+
+    fn reports() -> Vec<FirmReport> {
+        let at = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        let events = [SequencerEvent::Match {
+            symbol: "AAPL".into(),
+            price: 100,
+            quantity: 5,
+            buyer: "MM1".into(),
+            seller: "HF1".into(),
+            maker: None,
+            buyer_account: None,
+            seller_account: None,
+            at,
+        }];
+        Reconciler::new(BTreeMap::new(), 0).reports(at.date_naive(), &events)
+    }
+
+    fn service() -> SettlementService {
+        SettlementService::new(
+            Box::new(BankStub::default()),
+            BTreeMap::from([("MM1".to_string(), "GB33BUKB20201555555555".to_string())]),
+            "USD",
+            system_clock(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_settles_once() {
+        let path = std::env::temp_dir().join(format!("romer-settlement-{}.jsonl", uuid::Uuid::new_v4()));
+        let service = service().with_receipts(&path).unwrap();
+
+        let outcome = service.settle(&reports()).await;
+        assert_eq!(outcome.submitted.len(), 1);
+        let receipt = &outcome.submitted[0];
+        assert_eq!(receipt.instruction.id, "2024-03-01-MM1");
+        assert_eq!(receipt.instruction.amount, -500);
+        assert_eq!(receipt.reference, "BANK-00000001");
+        // HF1 has no account
+        assert_eq!(outcome.failed.len(), 1);
+        assert_eq!(outcome.failed[0].firm, "HF1");
+
+        let outcome = service.settle(&reports()).await;
+        assert!(outcome.submitted.is_empty());
+        assert_eq!(outcome.already_settled, vec!["2024-03-01-MM1".to_string()]);
+
+        // Receipts survive a restart
+        let restarted = service().with_receipts(&path).unwrap();
+        assert_eq!(restarted.receipts(None).len(), 1);
+        assert!(restarted.settle(&reports()).await.submitted.is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
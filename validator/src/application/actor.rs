@@ -1,117 +1,510 @@
-/* use crate::validation::proof_generator::ProofGenerator;
-use commonware_runtime::Spawner; 
+use crate::validation::proof_generator::ProofGenerator;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use commonware_runtime::{Blob, Clock, Spawner, Storage, SystemTimeExt};
 
+use crate::metrics::ConsensusMetrics;
+use crate::node::divergence::{DivergenceDetector, WriteSet};
+use crate::latency::{LatencyQuery, LatencyReport};
+use crate::rewards;
+use crate::slashing::{EvidencePool, SlashingQuery};
+use crate::supply::SupplyTracker;
 use super::{
+    block::{
+        entities::{Block, CertificateKind, TransactionType},
+        producer::{BlockProducer, Executed, Execution},
+        state::ChainRules,
+        BlockMessage, BLOCKS_PER_SECTION,
+    },
     ingress::{Mailbox, Message},
     supervisor::Supervisor,
     Config,
 };
-use commonware_consensus::simplex::Prover;
-use commonware_cryptography::{Hasher, Scheme};
+use bytes::Bytes;
+use commonware_consensus::{
+    simplex::{Context, Prover, View},
+    Supervisor as Su,
+};
+use commonware_cryptography::{Digest, Hasher, PublicKey, Scheme};
+use commonware_p2p::{Receiver, Recipients, Sender};
+use commonware_storage::journal::Journal;
 use commonware_utils::hex;
-use futures::{channel::mpsc, StreamExt};
+use futures::{
+    channel::{mpsc, oneshot},
+    future::{select, Either},
+    pin_mut, StreamExt,
+};
 use rand::Rng;
-use tracing::info;
-use anyhow::Context;
+use tracing::{debug, error, info, warn};
+use anyhow::Context as _;
 
-/// Genesis message to use during initialization.
-const GENESIS: &[u8] = b"commonware is neat";
+/// Hash of the block a consensus payload refers to
+fn block_hash(payload: &Digest) -> Option<[u8; 32]> {
+    payload.as_ref().try_into().ok()
+}
+
+fn payload(hash: [u8; 32]) -> Digest {
+    hash.to_vec().into()
+}
 
 /// Application actor.
-pub struct Application<R: Rng + Spawner, C: Scheme, H: Hasher> {
+pub struct Application<R: Rng + Spawner + Clock, C: Scheme, H: Hasher, B: Blob, E: Storage<B>> {
     runtime: R,
     prover: Prover<C, H>,
-    hasher: H,
+    /// Signs the reward mints of the blocks we propose
+    signer: C,
     mailbox: mpsc::Receiver<Message>,
+    metrics: ConsensusMetrics,
+    divergence: DivergenceDetector,
+    /// Decides which participant may propose in a view
+    supervisor: Supervisor<C, H>,
+    rewards: rewards::RewardsQuery,
+    supply: Option<SupplyTracker>,
+    latency: LatencyQuery,
+    pending_evidence: EvidencePool,
+    slashing: SlashingQuery,
+    /// Certificates from consensus to commit in the blocks we propose, by
+    /// view and kind
+    certificates: BTreeMap<(View, CertificateKind), Vec<u8>>,
+
+    producer: BlockProducer,
+    /// Finalized blocks, and where each is journaled by hash
+    journal: Journal<B, E>,
+    stored: HashMap<[u8; 32], (u64, u32)>,
+    /// Verifications waiting for a block, or its parent, to arrive
+    verifying: HashMap<[u8; 32], Vec<(Context, oneshot::Sender<bool>)>>,
+    /// Latest finalized block not applied yet, as its view and hash
+    finalizing: Option<(u64, [u8; 32])>,
+    /// Blocks requested from peers since the last finalization
+    requested: HashSet<[u8; 32]>,
 }
 
-impl<R: Rng + Spawner, C: Scheme, H: Hasher> Application<R, C, H> {
+impl<R: Rng + Spawner + Clock, C: Scheme, H: Hasher, B: Blob, E: Storage<B>> Application<R, C, H, B, E> {
     /// Create a new application actor.
-    pub fn new(runtime: R, config: Config<C, H>) -> (Self, Supervisor, Mailbox) {
-        
+    pub fn new(runtime: R, config: Config<C, H, B, E>) -> (Self, Supervisor<C, H>, Mailbox) {
         let _proof_generator = ProofGenerator::builder()
+            .with_confidence(config.confidence)
+            .with_references(config.references)
             .validate_hardware()
             .context("Failed to validate hardware requirements")
             .expect("Hardware validation must pass");
 
-        // Spawn the async location validation
+        // Spawn the async location validation, keeping the report of every
+        // check so operators can see why a location failed
+        let confidence_report = config.confidence_report;
+        let validator_location = config.validator_location;
         runtime.spawn("location_validator", async move {
-            if let Some(location) = config.validator_location {
-                _proof_generator
-                    .validate_location(location.to_point())
-                    .await
-                    .expect("Location validation must pass");
+            if let Some(location) = validator_location {
+                let report = _proof_generator.assess_location(location.to_point()).await;
+                info!(confidence = report.confidence, passed = report.passed, "assessed location");
+                if let Some(path) = confidence_report {
+                    if let Err(e) = std::fs::write(&path, report.to_json()) {
+                        warn!(path = %path.display(), error = %e, "failed to write confidence report");
+                    }
+                }
+                assert!(report.passed, "Location validation must pass: {}", report.to_json());
             }
         });
 
+        let rules = ChainRules::new(config.epoch_reward, config.participants.clone());
+        let (producer, _) = BlockProducer::new(&config.genesis, rules).expect("Genesis block must apply");
+        let mut supply = config.supply;
+        if let Some(supply) = &mut supply {
+            supply
+                .apply_block(&config.genesis)
+                .expect("Genesis block must match the configured supply");
+        }
+        let supervisor = Supervisor::new(
+            config.participants,
+            config.me,
+            config.prover.clone(),
+            config.metrics.clone(),
+            config.evidence,
+        );
+
         let (sender, mailbox) = mpsc::channel(config.mailbox_size);
         (
             Self {
                 runtime,
                 prover: config.prover,
-                hasher: config.hasher,
+                signer: config.signer,
                 mailbox,
+                metrics: config.metrics,
+                divergence: config.divergence,
+                supervisor: supervisor.clone(),
+                rewards: config.rewards,
+                supply,
+                latency: config.latency,
+                pending_evidence: config.pending_evidence,
+                slashing: config.slashing,
+                certificates: BTreeMap::new(),
+                producer,
+                journal: config.blocks,
+                stored: HashMap::new(),
+                verifying: HashMap::new(),
+                finalizing: None,
+                requested: HashSet::new(),
             },
-            Supervisor::new(config.participants),
+            supervisor,
             Mailbox::new(sender),
         )
     }
 
-    /// Run the application actor.
-    pub async fn run(mut self) {
-        while let Some(message) = self.mailbox.next().await {
+    /// Executes the finalized blocks journaled before a restart
+    async fn restore(&mut self) {
+        let mut journaled = Vec::new();
+        match self.journal.replay(1).await {
+            Ok(stream) => {
+                pin_mut!(stream);
+                while let Some(Ok((section, offset, _, bytes))) = stream.next().await {
+                    journaled.push((section, offset, bytes));
+                }
+            }
+            Err(e) => warn!(error = %e, "Failed to replay blocks journal"),
+        }
+
+        for (section, offset, bytes) in journaled {
+            let Some(BlockMessage::Block(block)) = BlockMessage::decode(&bytes) else {
+                warn!(section, offset, "Skipping unreadable journaled block");
+                continue;
+            };
+            let Some(hash) = self.producer.insert(block) else {
+                continue;
+            };
+            let execution = self.producer.execute::<C>(hash);
+            match self.producer.finalize(hash) {
+                Ok(chain) => {
+                    self.stored.insert(hash, (section, offset));
+                    for executed in chain {
+                        self.finalized(&executed).await;
+                    }
+                }
+                Err(_) => {
+                    error!(section, offset, ?execution, "Journaled block does not extend the chain");
+                    break;
+                }
+            }
+        }
+        info!(
+            height = self.producer.finalized().get_height(),
+            "Restored finalized blocks"
+        );
+    }
+
+    /// Asks peers for a block we need, once per finalization
+    async fn request(&mut self, sender: &mut impl Sender, hash: [u8; 32]) {
+        if !self.requested.insert(hash) {
+            return;
+        }
+        debug!(block = hex(&hash), "Requesting block");
+        let message = BlockMessage::Request(hash).encode();
+        if let Err(e) = sender.send(Recipients::All, message.into(), false).await {
+            debug!(error = ?e, "Failed to request block");
+        }
+    }
+
+    /// Whether an executed block is valid in the consensus `context`: it was
+    /// proposed by the view's leader on the context's parent, and commits to
+    /// the state root it results in
+    fn check(&self, context: &Context, hash: &[u8; 32]) -> bool {
+        let Some(executed) = self.producer.get(hash) else {
+            return false;
+        };
+        let header = &executed.block.header;
+        let leader = Su::leader(&self.supervisor, context.view, ());
+        u64::from(header.view) == context.view
+            && block_hash(&context.parent.1) == Some(header.previous_hash)
+            && leader.as_deref() == Some(&header.validator_public_key[..])
+            && executed.matches_root()
+    }
+
+    /// Executes a block, and every held block that was waiting on it, and
+    /// answers the verifications waiting for them
+    async fn execute(&mut self, sender: &mut impl Sender, hash: [u8; 32]) {
+        let mut work = vec![hash];
+        while let Some(hash) = work.pop() {
+            let valid = match self.producer.execute::<C>(hash) {
+                Execution::Executed => {
+                    work.extend(self.producer.children(&hash));
+                    None
+                }
+                Execution::Rejected(reason) => {
+                    warn!(block = hex(&hash), %reason, "Rejected block");
+                    Some(false)
+                }
+                Execution::Missing => {
+                    self.request(sender, hash).await;
+                    continue;
+                }
+                Execution::MissingParent(parent) => {
+                    self.request(sender, parent).await;
+                    continue;
+                }
+            };
+            for (context, response) in self.verifying.remove(&hash).unwrap_or_default() {
+                let _ = response.send(valid.unwrap_or_else(|| self.check(&context, &hash)));
+            }
+        }
+        self.finalize(sender).await;
+    }
+
+    /// Applies the latest finalized block and its ancestors, once all of
+    /// them have been executed
+    async fn finalize(&mut self, sender: &mut impl Sender) {
+        let Some((view, hash)) = self.finalizing else {
+            return;
+        };
+        match self.producer.finalize(hash) {
+            Ok(chain) => {
+                self.finalizing = None;
+                for executed in chain {
+                    self.persist(&executed).await;
+                    if !executed.matches_root() {
+                        self.diverged(&executed.block, executed.state_root, &executed.transition.writes);
+                    }
+                    self.finalized(&executed).await;
+                }
+                // Drop verifications consensus has given up on
+                self.verifying.retain(|_, waiting| {
+                    waiting.retain(|(_, response)| !response.is_canceled());
+                    !waiting.is_empty()
+                });
+                info!(view, height = self.producer.finalized().get_height(), "finalized");
+            }
+            Err(missing) => match self.producer.rejection(&missing) {
+                // The network finalized a block we can't apply at all, so
+                // our state is left where it was
+                Some((block, reason)) => {
+                    error!(block = hex(&missing), %reason, "Finalized block can't be applied to our state");
+                    let unchanged = self.producer.finalized().state_root();
+                    self.diverged(block, unchanged, &WriteSet::new());
+                }
+                None => self.request(sender, missing).await,
+            },
+        }
+    }
+
+    /// Halts voting on a block the network notarized or finalized whose state
+    /// root differs from the one we computed, writing a forensic bundle
+    fn diverged(&self, block: &Block, computed_root: [u8; 32], local_writes: &WriteSet) {
+        if self.divergence.is_halted() {
+            return;
+        }
+        match self
+            .divergence
+            .check(block, block.header.view, computed_root, local_writes, None)
+        {
+            Ok(Some(bundle)) => error!(bundle = %bundle.display(), "Wrote forensic bundle"),
+            Ok(None) => {}
+            Err(e) => error!(error = %e, "Failed to write forensic bundle"),
+        }
+    }
+
+    /// Journals a finalized block, so it is applied again after a restart
+    /// and can be served to peers catching up
+    async fn persist(&mut self, executed: &Executed) {
+        let section = executed.block.header.height / BLOCKS_PER_SECTION;
+        let bytes = BlockMessage::Block(executed.block.clone()).encode();
+        let offset = match self.journal.append(section, bytes.into()).await {
+            Ok(offset) => offset,
+            Err(e) => {
+                warn!(section, error = %e, "Failed to persist finalized block");
+                return;
+            }
+        };
+        if let Err(e) = self.journal.sync(section).await {
+            warn!(section, error = %e, "Failed to sync blocks journal");
+        }
+        self.stored.insert(executed.hash, (section, offset));
+    }
+
+    /// Effects of a finalized block beyond our own state
+    async fn finalized(&mut self, executed: &Executed) {
+        for summary in &executed.transition.rewarded {
+            info!(epoch = summary.epoch, recipients = summary.rewards.len(), "Reward epoch paid");
+            self.rewards.paid(summary.clone());
+        }
+        let epoch = rewards::epoch_of(u64::from(executed.block.header.view));
+        self.rewards.update_unpaid(executed.state.unpaid(epoch, self.producer.rules()));
+        if let Some(supply) = &mut self.supply {
+            if let Err(e) = supply.apply_block(&executed.block) {
+                error!(height = executed.block.header.height, error = %e, "Supply invariant violated");
+            }
+        }
+        for tx in &executed.block.transactions {
+            if let TransactionType::LatencyReport { report } = &tx.transaction_type {
+                if let Ok(report) = LatencyReport::decode(report) {
+                    self.latency.commit::<C>(report, executed.block.header.timestamp);
+                }
+            }
+        }
+        self.slashing.update(executed.state.slash_records());
+        self.pending_evidence.prune(|evidence| executed.state.has_evidence(evidence));
+        self.certificates.retain(|(view, kind), _| executed.state.accepts_certificate(*kind, *view));
+    }
+
+    /// Answers a peer's request with the block, if we hold it
+    async fn serve(&mut self, sender: &mut impl Sender, peer: PublicKey, hash: [u8; 32]) {
+        let block = match self.producer.block(&hash) {
+            Some(block) => Some(block.clone()),
+            None => match self.stored.get(&hash) {
+                Some((section, offset)) => match self.journal.get(*section, *offset).await {
+                    Ok(Some(bytes)) => match BlockMessage::decode(&bytes) {
+                        Some(BlockMessage::Block(block)) => Some(block),
+                        _ => None,
+                    },
+                    Ok(None) => None,
+                    Err(e) => {
+                        warn!(section, offset, error = %e, "Failed to read journaled block");
+                        None
+                    }
+                },
+                None => None,
+            },
+        };
+        let Some(block) = block else {
+            return;
+        };
+        let message = BlockMessage::Block(block).encode();
+        if let Err(e) = sender.send(Recipients::One(peer), message.into(), false).await {
+            debug!(error = ?e, "Failed to send requested block");
+        }
+    }
+
+    async fn receive(&mut self, sender: &mut impl Sender, peer: PublicKey, bytes: Bytes) {
+        match BlockMessage::decode(&bytes) {
+            Some(BlockMessage::Block(block)) => {
+                // Blocks already finalized, or beyond what we hold, are dropped
+                let Some(hash) = self.producer.insert(block) else {
+                    return;
+                };
+                self.requested.remove(&hash);
+                self.execute(sender, hash).await;
+            }
+            Some(BlockMessage::Request(hash)) => self.serve(sender, peer, hash).await,
+            None => debug!(peer = hex(&peer), "Ignoring malformed block message"),
+        }
+    }
+
+    /// Run the application actor, relaying blocks over `sender` and `receiver`.
+    pub async fn run(mut self, mut sender: impl Sender, mut receiver: impl Receiver) {
+        self.restore().await;
+        loop {
+            let event = {
+                let message = self.mailbox.next();
+                let network = receiver.recv();
+                pin_mut!(message, network);
+                match select(message, network).await {
+                    Either::Left((message, _)) => Either::Left(message),
+                    Either::Right((network, _)) => Either::Right(network),
+                }
+            };
+            let message = match event {
+                Either::Left(Some(message)) => message,
+                Either::Left(None) => return,
+                Either::Right(Ok((peer, bytes))) => {
+                    self.receive(&mut sender, peer, bytes).await;
+                    continue;
+                }
+                Either::Right(Err(e)) => {
+                    warn!(error = ?e, "Block channel closed");
+                    return;
+                }
+            };
+
             match message {
                 Message::Genesis { response } => {
-                    // Use the hash of the genesis message as the initial
-                    // payload.
-                    //
-                    // Since we don't verify that proposed messages link
-                    // to some parent, this doesn't really do anything
-                    // in this example.
-                    self.hasher.update(GENESIS);
-                    let digest = self.hasher.finalize();
-                    let _ = response.send(digest);
-                }
-                Message::Propose { response } => {
-                    // Generate a random message (secret to us)
-                    let mut msg = vec![0; 16];
-                    self.runtime.fill(&mut msg[..]);
-
-                    // Hash the message
-                    self.hasher.update(&msg);
-                    let digest = self.hasher.finalize();
-                    info!(
-                        target: "commonware_log::application",
-                        msg = hex(&msg),
-                        payload = hex(&digest),
-                        "proposed"
-                    );
-
-                    // Send digest to consensus
-                    let _ = response.send(digest);
-                }
-                Message::Verify { payload, response } => {
-                    // If we linked payloads to their parent, we would verify
-                    // the parent included in the payload matches the provided context.
-                    let valid = H::validate(&payload);
-                    let _ = response.send(valid);
+                    // Every chain starts from the same genesis block
+                    let _ = response.send(payload(self.producer.genesis()));
+                }
+                Message::Propose { context, response } => {
+                    let (Some(parent), Ok(view)) = (block_hash(&context.parent.1), u32::try_from(context.view)) else {
+                        continue;
+                    };
+                    let timestamp = self.runtime.current().epoch_millis();
+                    let certificates: Vec<_> = self
+                        .certificates
+                        .iter()
+                        .map(|((view, kind), proof)| (*view, *kind, proof.clone()))
+                        .collect();
+                    let reports = self.latency.reports();
+                    let evidence = self.pending_evidence.pending();
+                    match self.producer.create_block(
+                        &mut self.signer,
+                        parent,
+                        view,
+                        timestamp,
+                        &certificates,
+                        &reports,
+                        &evidence,
+                    ) {
+                        Ok((hash, block)) => {
+                            info!(
+                                target: "commonware_log::application",
+                                view,
+                                height = block.header.height,
+                                transactions = block.transactions.len(),
+                                payload = hex(&hash),
+                                "proposed"
+                            );
+                            self.metrics.proposals_made.inc();
+                            let _ = response.send(payload(hash));
+                        }
+                        // Dropping the response makes consensus give up on
+                        // proposing in this view
+                        Err(e) => warn!(view, parent = hex(&parent), error = %e, "Failed to propose"),
+                    }
+                }
+                Message::Broadcast { payload } => {
+                    let Some(block) = block_hash(&payload).and_then(|hash| self.producer.block(&hash)) else {
+                        continue;
+                    };
+                    let message = BlockMessage::Block(block.clone()).encode();
+                    if let Err(e) = sender.send(Recipients::All, message.into(), true).await {
+                        debug!(error = ?e, "Failed to relay block");
+                    }
+                }
+                Message::Verify { context, payload, response } => {
+                    let Some(hash) = block_hash(&payload) else {
+                        let _ = response.send(false);
+                        continue;
+                    };
+                    // Answered once the block has been executed
+                    self.verifying.entry(hash).or_default().push((context, response));
+                    self.execute(&mut sender, hash).await;
                 }
                 Message::Prepared { proof, payload } => {
                     let (view, _, _, _) = self
                         .prover
-                        .deserialize_notarization(proof, u32::MAX, false)
+                        .deserialize_notarization(proof.clone(), u32::MAX, false)
                         .unwrap();
-                    info!(view, payload = hex(&payload), "prepared")
+                    info!(view, payload = hex(&payload), "prepared");
+                    self.certificates.insert((view, CertificateKind::Notarization), proof.to_vec());
+
+                    // A notarized block we executed to another root means we
+                    // have diverged from the network
+                    let executed = block_hash(&payload).and_then(|hash| self.producer.get(&hash));
+                    if let Some(executed) = executed.filter(|executed| !executed.matches_root()) {
+                        self.diverged(&executed.block, executed.state_root, &executed.transition.writes);
+                    }
                 }
                 Message::Finalized { proof, payload } => {
                     let (view, _, _, _) = self
                         .prover
-                        .deserialize_finalization(proof, u32::MAX, false)
+                        .deserialize_finalization(proof.clone(), u32::MAX, false)
                         .unwrap();
-                    info!(view, payload = hex(&payload), "finalized")
+                    self.certificates.insert((view, CertificateKind::Finalization), proof.to_vec());
+                    let Some(hash) = block_hash(&payload) else {
+                        continue;
+                    };
+                    // Consensus replays finalizations we've applied already
+                    if self.stored.contains_key(&hash) || self.finalizing.is_some_and(|(at, _)| at >= view) {
+                        continue;
+                    }
+                    self.finalizing = Some((view, hash));
+                    self.requested.clear();
+                    self.finalize(&mut sender).await;
                 }
             }
         }
     }
 }
-*/
\ No newline at end of file
@@ -23,7 +23,9 @@ pub struct Transaction {
     pub from: [u8; 32],
     pub nonce: u64,
     pub gas_amount: u64,
-    pub signature: [u8 ; 32],
+    /// Signature authorizing the transaction, see `rewards::verify_mint`
+    /// for reward mints
+    pub signature: Vec<u8>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,7 +34,31 @@ pub enum TransactionType {
         to: [u8; 32],
         amount: u64,
         transfer_type: TransferType,
-    }
+    },
+    /// A validator's signed latency report, encoded as `LatencyReport::encode`
+    /// does, committing it to the chain. `from` is the reporter's key.
+    LatencyReport {
+        report: Vec<u8>,
+    },
+    /// Proof that a validator signed conflicting consensus messages, encoded
+    /// as `Evidence::encode` does, slashing the offender. `from` is the key
+    /// of the proposer that included it.
+    Evidence {
+        evidence: Vec<u8>,
+    },
+    /// A consensus certificate, committing the participation of its signers,
+    /// and for a notarization of the view's leader, to the reward epoch of
+    /// its view. `from` is the key of the proposer that included it.
+    Certificate {
+        kind: CertificateKind,
+        proof: Vec<u8>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum CertificateKind {
+    Notarization,
+    Finalization,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1,3 +1,76 @@
-pub mod entities;  
+//! Blocks, the state they are executed against and the producer tracking
+//! the chain from genesis to the blocks consensus is voting on.
+
+pub mod entities;
 pub mod producer;
-pub mod state;
\ No newline at end of file
+pub mod state;
+
+use entities::{Block, BlockHeader, Transaction, TransactionType, TransferType};
+use romer_common::types::tokenomics::TokenomicsConfig;
+use serde::{Deserialize, Serialize};
+use state::BlockchainState;
+use crate::utils::utils::BlockHasher;
+
+/// Journal partition finalized blocks are persisted to
+pub const BLOCKS_PARTITION: &str = "blocks";
+
+/// p2p channel proposed blocks are relayed and fetched on
+pub const BLOCKS_CHANNEL: u32 = 5;
+
+/// Finalized blocks are journaled in sections of this many heights
+pub const BLOCKS_PER_SECTION: u64 = 10_000;
+
+/// Messages exchanged on [`BLOCKS_CHANNEL`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum BlockMessage {
+    /// A block, relayed by its proposer or sent in answer to a request
+    Block(Block),
+    /// Asks peers for the block with this hash
+    Request([u8; 32]),
+}
+
+impl BlockMessage {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("block messages serialize to JSON")
+    }
+
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        serde_json::from_slice(bytes).ok()
+    }
+}
+
+/// The block every chain starts from, minting the initial supply to the
+/// treasury if the network's `tokenomics` are set
+pub fn genesis_block(tokenomics: Option<&TokenomicsConfig>) -> Block {
+    let transactions: Vec<Transaction> = tokenomics
+        .map(|tokenomics| Transaction {
+            transaction_type: TransactionType::TokenTransfer {
+                to: tokenomics.treasury.account().into(),
+                amount: tokenomics.supply.initial_supply,
+                transfer_type: TransferType::Mint,
+            },
+            from: [0u8; 32],
+            nonce: 0,
+            gas_amount: 0,
+            signature: Vec::new(),
+        })
+        .into_iter()
+        .collect();
+    let mut block = Block {
+        header: BlockHeader {
+            view: 0,
+            height: 0,
+            timestamp: 0,
+            previous_hash: [0u8; 32],
+            transactions_root: BlockHasher::new().calculate_transactions_root(&transactions),
+            state_root: [0u8; 32],
+            validator_public_key: [0u8; 32],
+        },
+        transactions,
+    };
+    let mut state = BlockchainState::new();
+    if state.apply_genesis_block(&block).is_ok() {
+        block.header.state_root = state.state_root();
+    }
+    block
+}
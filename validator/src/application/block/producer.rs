@@ -1,202 +1,393 @@
-/* 
-use std::sync::Arc;
+use std::collections::HashMap;
+use commonware_consensus::simplex::View;
+use commonware_cryptography::Scheme;
 use thiserror::Error;
-use commonware_cryptography::Ed25519;
-use commonware_runtime::{Clock, SystemTimeExt};
-use tracing::{info, warn};
-
-use crate::config::shared::SharedConfig;
-use crate::block::{
-    entities::{Block, BlockHeader, Transaction, TransactionType, TransferType},
-    state::BlockchainState,
-    validator::BlockValidator,
-};
+
+use super::entities::{Block, BlockHeader, CertificateKind, Transaction, TransactionType};
+use super::state::{BlockchainState, ChainRules, StateError, Transition};
+use crate::latency::LatencyReport;
+use crate::rewards::epoch_of;
+use crate::slashing::Evidence;
 use crate::utils::utils::BlockHasher;
 
+/// Blocks received ahead of their parent that are held at most
+pub const MAX_PENDING_BLOCKS: usize = 1024;
+
 #[derive(Error, Debug)]
 pub enum BlockProductionError {
     #[error("Block creation failed: {0}")]
     Creation(String),
     #[error("State transition error: {0}")]
-    StateTransition(String),
-    #[error("Validation error: {0}")]
-    Validation(String),
-    #[error("Configuration error: {0}")]
-    Config(String),
+    StateTransition(#[from] StateError),
 }
 
-// Domain events emitted by the BlockProducer
+/// A block executed on top of its parent
 #[derive(Debug, Clone)]
-pub enum BlockEvent {
-    GenesisCreated(Block),
-    BlockCreated(Block),
-    BlockValidated(Block),
-    ValidationFailed { reason: String },
+pub struct Executed {
+    pub hash: [u8; 32],
+    pub block: Block,
+    /// State after the block
+    pub state: BlockchainState,
+    pub transition: Transition,
+    /// State root this validator computed for the block
+    pub state_root: [u8; 32],
+}
+
+impl Executed {
+    /// Whether the block commits to the state root computed for it
+    pub fn matches_root(&self) -> bool {
+        self.state_root == self.block.header.state_root
+    }
 }
 
+/// Outcome of executing a block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Execution {
+    /// The block has been executed
+    Executed,
+    /// The block hasn't been received
+    Missing,
+    /// The block's parent hasn't been executed, the block is held until it is
+    MissingParent([u8; 32]),
+    /// The block can't be applied to its parent's state
+    Rejected(StateError),
+}
+
+/// Builds blocks on top of any executed block, executes the blocks peers
+/// propose and tracks which of them have been finalized.
+///
+/// Every block above the last finalized one is kept with the state it
+/// results in, so a proposal can build on, and a verifier execute on, a
+/// notarized parent before it is finalized.
 pub struct BlockProducer {
-    signer: Ed25519,
-    config: Arc<SharedConfig>,
-    state: BlockchainState,
-    validator: BlockValidator,
+    /// Rules blocks are executed with
+    rules: ChainRules,
     block_hasher: BlockHasher,
+    genesis: [u8; 32],
+    /// State as of the last finalized block
+    finalized: BlockchainState,
+    /// Executed blocks above the last finalized one, by hash
+    executed: HashMap<[u8; 32], Executed>,
+    /// Blocks received but not executed yet, by hash
+    pending: HashMap<[u8; 32], Block>,
+    /// Blocks that failed to execute, by hash
+    rejected: HashMap<[u8; 32], (Block, StateError)>,
 }
 
 impl BlockProducer {
-    pub fn new(
-        signer: Ed25519,
-        config: Arc<SharedConfig>,
-        state: BlockchainState,
-    ) -> Self {
-        Self {
-            signer,
-            config,
-            state,
-            validator: BlockValidator::new(),
+    /// Creates a producer whose chain starts at `genesis`
+    pub fn new(genesis: &Block, rules: ChainRules) -> Result<(Self, Transition), StateError> {
+        let mut finalized = BlockchainState::new();
+        let transition = finalized.apply_genesis_block(genesis)?;
+        let producer = Self {
+            rules,
             block_hasher: BlockHasher::new(),
-        }
-    }
-
-    /// Creates the genesis block with initial token distribution
-    pub async fn create_genesis_block(&mut self) -> Result<BlockEvent, BlockProductionError> {
-        info!("Creating genesis block");
-        
-        // Convert treasury address and prepare initial transaction
-        let treasury_vec = self.block_hasher.address_to_bytes(
-            &self.config.tokenomics().addresses.treasury
-        );
-        
-        let mut treasury_bytes = [0u8; 32];
-        treasury_bytes[..treasury_vec.len().min(32)]
-            .copy_from_slice(&treasury_vec[..treasury_vec.len().min(32)]);
-
-        // Create the genesis mint transaction
-        let mint_transaction = Transaction {
-            transaction_type: TransactionType::TokenTransfer {
-                to: treasury_bytes,
-                amount: self.config.tokenomics().supply.initial_supply,
-                transfer_type: TransferType::Mint,
-            },
-            from: [0u8; 32],
-            nonce: 0,
-            gas_amount: 0,
-            signature: [0u8; 32],
+            genesis: finalized.latest_hash(),
+            finalized,
+            executed: HashMap::new(),
+            pending: HashMap::new(),
+            rejected: HashMap::new(),
         };
+        Ok((producer, transition))
+    }
 
-        // Calculate roots
-        let transactions_root = self.block_hasher
-            .calculate_transactions_root(&[mint_transaction.clone()]);
-
-        let initial_state = vec![(
-            treasury_vec,
-            self.config.tokenomics().supply.initial_supply,
-        )];
-        let state_root = self.block_hasher.calculate_state_root(&initial_state);
+    /// Hash of the genesis block, the payload consensus starts from
+    pub fn genesis(&self) -> [u8; 32] {
+        self.genesis
+    }
 
-        // Prepare validator key
-        let mut validator_key = [0u8; 32];
-        validator_key.copy_from_slice(&self.signer.public_key());
+    /// State as of the last finalized block
+    pub fn finalized(&self) -> &BlockchainState {
+        &self.finalized
+    }
 
-        // Create the genesis block
-        let block = Block {
-            header: BlockHeader {
-                view: 0,
-                height: 0,
-                timestamp: self.config.genesis().network.genesis_time,
-                previous_hash: [0u8; 32],
-                transactions_root,
-                state_root,
-                validator_public_key: validator_key,
-            },
-            transactions: vec![mint_transaction],
-        };
+    /// Rules every block is executed with
+    pub fn rules(&self) -> &ChainRules {
+        &self.rules
+    }
 
-        // Validate the genesis block
-        if let Err(e) = self.validator.validate_genesis_block(&block) {
-            return Err(BlockProductionError::Validation(e.to_string()));
+    /// State after the block with `hash`, if it's the last finalized block or
+    /// executed above it
+    fn state_of(&self, hash: &[u8; 32]) -> Option<&BlockchainState> {
+        if *hash == self.finalized.latest_hash() {
+            return Some(&self.finalized);
         }
+        self.executed.get(hash).map(|executed| &executed.state)
+    }
 
-        // Apply state changes
-        if let Err(e) = self.state.apply_genesis_block(&block) {
-            return Err(BlockProductionError::StateTransition(e.to_string()));
-        }
+    pub fn get(&self, hash: &[u8; 32]) -> Option<&Executed> {
+        self.executed.get(hash)
+    }
+
+    /// A block held above the last finalized one, executed or not
+    pub fn block(&self, hash: &[u8; 32]) -> Option<&Block> {
+        self.executed
+            .get(hash)
+            .map(|executed| &executed.block)
+            .or_else(|| self.pending.get(hash))
+    }
 
-        info!("Genesis block created successfully");
-        Ok(BlockEvent::GenesisCreated(block))
+    /// The block with `hash` and why it was rejected, if it was
+    pub fn rejection(&self, hash: &[u8; 32]) -> Option<(&Block, &StateError)> {
+        self.rejected.get(hash).map(|(block, reason)| (block, reason))
     }
 
-    /// Creates a new block with pending transactions
-    pub async fn create_block(
+    /// Creates a block for `view` extending `parent`, committing every
+    /// certificate in `certificates`, given in view order, that `parent`'s
+    /// chain can still commit, every report in `reports` newer than the one
+    /// `parent`'s chain holds for its reporter, and every piece of `evidence`
+    /// `parent`'s chain hasn't committed. It then mints the rewards of every
+    /// epoch that has ended and that the chain hasn't minted yet. The block
+    /// is executed and kept.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_block<C: Scheme>(
         &mut self,
+        signer: &mut C,
+        parent: [u8; 32],
         view: u32,
-        transactions: Vec<Transaction>,
-    ) -> Result<BlockEvent, BlockProductionError> {
-        let previous_block = self.state.get_latest_block()
+        timestamp: u64,
+        certificates: &[(View, CertificateKind, Vec<u8>)],
+        reports: &[LatencyReport],
+        evidence: &[Evidence],
+    ) -> Result<([u8; 32], Block), BlockProductionError> {
+        let parent_state = self
+            .state_of(&parent)
+            .ok_or_else(|| BlockProductionError::Creation("Parent block not executed".to_string()))?
+            .clone();
+        let previous = parent_state
+            .get_latest_block()
             .ok_or_else(|| BlockProductionError::Creation("No previous block found".to_string()))?;
 
-        let transactions_root = self.block_hasher.calculate_transactions_root(&transactions);
-        let state_root = self.calculate_new_state_root(&transactions)?;
-
+        let timestamp = timestamp.max(previous.header.timestamp);
         let mut validator_key = [0u8; 32];
-        validator_key.copy_from_slice(&self.signer.public_key());
+        validator_key.copy_from_slice(&signer.public_key());
+        let mut transactions: Vec<Transaction> = certificates
+            .iter()
+            .filter(|(at, kind, _)| *at < u64::from(view) && parent_state.accepts_certificate(*kind, *at))
+            .map(|(_, kind, proof)| Transaction {
+                transaction_type: TransactionType::Certificate { kind: *kind, proof: proof.clone() },
+                from: validator_key,
+                nonce: 0,
+                gas_amount: 0,
+                signature: Vec::new(),
+            })
+            .collect();
+        for report in reports {
+            let Ok(from) = <[u8; 32]>::try_from(report.reporter.as_ref()) else {
+                continue;
+            };
+            if report.timestamp > timestamp
+                || parent_state.latency_timestamp(&from).is_some_and(|held| held >= report.timestamp)
+            {
+                continue;
+            }
+            transactions.push(Transaction {
+                transaction_type: TransactionType::LatencyReport { report: report.encode() },
+                from,
+                nonce: 0,
+                gas_amount: 0,
+                signature: Vec::new(),
+            });
+        }
 
-        let block = Block {
+        for evidence in evidence.iter().filter(|evidence| !parent_state.has_evidence(evidence)) {
+            transactions.push(Transaction {
+                transaction_type: TransactionType::Evidence { evidence: evidence.encode() },
+                from: validator_key,
+                nonce: 0,
+                gas_amount: 0,
+                signature: Vec::new(),
+            });
+        }
+
+        let mut block = Block {
             header: BlockHeader {
                 view,
-                height: previous_block.header.height + 1,
-                timestamp: SystemTime::now().unix_timestamp() as u64,
-                previous_hash: self.block_hasher.calculate_block_hash(&previous_block),
-                transactions_root,
-                state_root,
+                height: previous.header.height + 1,
+                timestamp,
+                previous_hash: parent,
+                transactions_root: self.block_hasher.calculate_transactions_root(&transactions),
+                state_root: [0u8; 32],
                 validator_public_key: validator_key,
             },
             transactions,
         };
 
-        // Validate the block
-        if let Err(e) = self.validator.validate_block(&block, &previous_block) {
-            return Err(BlockProductionError::Validation(e.to_string()));
+        // Rewards are minted as the participation and penalties the rest of
+        // the block commits leave them
+        let mut scratch = parent_state.clone();
+        scratch.apply_block::<C>(&block, &self.rules)?;
+        for summary in scratch.unpaid(epoch_of(u64::from(view)), &self.rules) {
+            block.transactions.extend(summary.reward_transactions(signer));
+        }
+        block.header.transactions_root = self.block_hasher.calculate_transactions_root(&block.transactions);
+
+        // The state root is computed on a scratch copy, as the state records
+        // the hash of the block applied, which covers the root
+        let mut scratch = parent_state;
+        scratch.apply_block::<C>(&block, &self.rules)?;
+        block.header.state_root = scratch.state_root();
+
+        let hash = self
+            .insert(block.clone())
+            .ok_or_else(|| BlockProductionError::Creation("Too many blocks held".to_string()))?;
+        match self.execute::<C>(hash) {
+            Execution::Executed => Ok((hash, block)),
+            Execution::Rejected(e) => Err(e.into()),
+            other => Err(BlockProductionError::Creation(format!("{:?}", other))),
         }
+    }
 
-        Ok(BlockEvent::BlockCreated(block))
+    /// Holds a block received from a peer until it can be executed,
+    /// returning its hash. Blocks at or below the last finalized height,
+    /// and any beyond [`MAX_PENDING_BLOCKS`], are dropped.
+    pub fn insert(&mut self, block: Block) -> Option<[u8; 32]> {
+        let hash = self.block_hasher.hash_block(&block);
+        if self.executed.contains_key(&hash) || self.rejected.contains_key(&hash) || self.pending.contains_key(&hash) {
+            return Some(hash);
+        }
+        if block.header.height <= self.finalized.get_height() || self.pending.len() >= MAX_PENDING_BLOCKS {
+            return None;
+        }
+        self.pending.insert(hash, block);
+        Some(hash)
     }
 
-    /// Validates a block received from the network
-    pub async fn validate_block(&self, block: &Block) -> Result<BlockEvent, BlockProductionError> {
-        let previous_block = self.state.get_block_at_height(block.header.height - 1)
-            .ok_or_else(|| BlockProductionError::Validation("Previous block not found".to_string()))?;
+    /// Executes a received block on its parent's state. A block whose
+    /// transactions are applied is kept even if it commits to a different
+    /// state root, so a divergence from a notarized block can be reported.
+    pub fn execute<C: Scheme>(&mut self, hash: [u8; 32]) -> Execution {
+        if self.executed.contains_key(&hash) {
+            return Execution::Executed;
+        }
+        if let Some((_, reason)) = self.rejection(&hash) {
+            return Execution::Rejected(reason.clone());
+        }
+        let Some(block) = self.pending.get(&hash) else {
+            return Execution::Missing;
+        };
+        let parent = block.header.previous_hash;
+        let Some(parent_state) = self.state_of(&parent) else {
+            return Execution::MissingParent(parent);
+        };
 
-        if let Err(e) = self.validator.validate_block(block, &previous_block) {
-            warn!("Block validation failed: {}", e);
-            return Ok(BlockEvent::ValidationFailed { reason: e.to_string() });
+        let mut state = parent_state.clone();
+        let block = self.pending.remove(&hash).expect("pending block");
+        match state.apply_block::<C>(&block, &self.rules) {
+            Ok(transition) => {
+                let state_root = state.state_root();
+                self.executed.insert(hash, Executed { hash, block, state, transition, state_root });
+                Execution::Executed
+            }
+            Err(e) => {
+                self.rejected.insert(hash, (block, e.clone()));
+                Execution::Rejected(e)
+            }
         }
+    }
+
+    /// Blocks held whose parent is `hash`
+    pub fn children(&self, hash: &[u8; 32]) -> Vec<[u8; 32]> {
+        self.pending
+            .iter()
+            .filter(|(_, block)| block.header.previous_hash == *hash)
+            .map(|(child, _)| *child)
+            .collect()
+    }
 
-        Ok(BlockEvent::BlockValidated(block.clone()))
-    }
-
-    // Helper method to calculate new state root after applying transactions
-    fn calculate_new_state_root(
-        &self,
-        transactions: &[Transaction]
-    ) -> Result<[u8; 32], BlockProductionError> {
-        // This would normally involve applying transactions to current state
-        // and calculating new state root. Simplified for demonstration.
-        let mut state_updates = Vec::new();
-        
-        for tx in transactions {
-            if let TransactionType::TokenTransfer { to, amount, .. } = tx.transaction_type {
-                state_updates.push((to.to_vec(), amount));
+    /// Finalizes the block with `hash` and every ancestor above the last
+    /// finalized block, returning them in height order. Fails with the hash
+    /// of the first block on the way down that hasn't been executed.
+    pub fn finalize(&mut self, hash: [u8; 32]) -> Result<Vec<Executed>, [u8; 32]> {
+        let mut chain = Vec::new();
+        let mut cursor = hash;
+        while cursor != self.finalized.latest_hash() {
+            match self.executed.get(&cursor) {
+                Some(executed) if executed.block.header.height > self.finalized.get_height() => {
+                    chain.push(cursor);
+                    cursor = executed.block.header.previous_hash;
+                }
+                _ => return Err(cursor),
             }
         }
 
-        Ok(self.block_hasher.calculate_state_root(&state_updates))
+        let finalized: Vec<Executed> = chain
+            .iter()
+            .rev()
+            .filter_map(|hash| self.executed.remove(hash))
+            .collect();
+        if let Some(last) = finalized.last() {
+            self.finalized = last.state.clone();
+        }
+
+        // Anything at or below the finalized height is on a dead fork
+        let height = self.finalized.get_height();
+        self.executed.retain(|_, executed| executed.block.header.height > height);
+        self.pending.retain(|_, block| block.header.height > height);
+        self.rejected.retain(|_, (block, _)| block.header.height > height);
+        Ok(finalized)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    
-    // Add tests for block creation and validation
+    use crate::rewards::EPOCH_LENGTH;
+    use commonware_cryptography::Ed25519;
+
+    fn genesis() -> Block {
+        Block {
+            header: BlockHeader {
+                view: 0,
+                height: 0,
+                timestamp: 0,
+                previous_hash: [0u8; 32],
+                transactions_root: [0u8; 32],
+                state_root: [0u8; 32],
+                validator_public_key: [0u8; 32],
+            },
+            transactions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_builds_executes_and_finalizes() {
+        let rules = ChainRules::new(100, vec![Ed25519::from_seed(1).public_key()]);
+        let (mut proposer, _) = BlockProducer::new(&genesis(), rules.clone()).unwrap();
+        let (mut verifier, _) = BlockProducer::new(&genesis(), rules).unwrap();
+        let mut signer = Ed25519::from_seed(1);
+
+        // An epoch without committed participation mints nothing once it ends
+        let view = EPOCH_LENGTH as u32;
+        let genesis = proposer.genesis();
+        let (first, first_block) = proposer.create_block(&mut signer, genesis, view - 1, 5, &[], &[], &[]).unwrap();
+        assert!(first_block.transactions.is_empty());
+        let (second, second_block) = proposer.create_block(&mut signer, first, view, 6, &[], &[], &[]).unwrap();
+        assert!(second_block.transactions.is_empty());
+
+        // A verifier receiving them out of order executes the child once the
+        // parent arrives, computing the same roots
+        assert_eq!(verifier.insert(second_block.clone()), Some(second));
+        assert_eq!(verifier.execute::<Ed25519>(second), Execution::MissingParent(first));
+        verifier.insert(first_block);
+        assert_eq!(verifier.execute::<Ed25519>(first), Execution::Executed);
+        assert_eq!(verifier.children(&first), vec![second]);
+        assert_eq!(verifier.execute::<Ed25519>(second), Execution::Executed);
+        assert!(verifier.get(&second).unwrap().matches_root());
+
+        // A block claiming another root executes but doesn't match it
+        let mut forged = second_block;
+        forged.header.view += 1;
+        forged.header.state_root = [7u8; 32];
+        let forged_hash = verifier.insert(forged).unwrap();
+        assert_eq!(verifier.execute::<Ed25519>(forged_hash), Execution::Executed);
+        assert!(!verifier.get(&forged_hash).unwrap().matches_root());
+
+        // Finalizing a block finalizes its ancestors and drops the fork
+        assert_eq!(verifier.finalize([9u8; 32]).unwrap_err(), [9u8; 32]);
+        let finalized = verifier.finalize(second).unwrap();
+        assert_eq!(finalized.iter().map(|e| e.block.header.height).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(verifier.finalized().latest_hash(), second);
+        assert!(verifier.get(&forged_hash).is_none());
+    }
 }
-    */
\ No newline at end of file
@@ -1,194 +1,721 @@
-/* 
-use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::collections::{BTreeMap, BTreeSet};
+use commonware_consensus::simplex::{Prover, View};
+use commonware_cryptography::{PublicKey, Scheme, Sha256};
+use commonware_utils::hex;
+use romer_common::light::CONSENSUS_NAMESPACE;
 use thiserror::Error;
-use tracing::{info, warn};
 
-use crate::block::entities::{Block, Transaction, TransactionType};
+use super::entities::{Block, CertificateKind, Transaction, TransactionType, TransferType};
+use crate::latency::LatencyReport;
+use crate::node::divergence::WriteSet;
+use crate::rewards::{distribute, epoch_of, penalize, verify_mint, EpochSummary, ValidatorEpochStats, EPOCH_LENGTH};
+use crate::slashing::{Evidence, EvidenceKind, SlashRecord};
+use crate::utils::utils::BlockHasher;
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
 pub enum StateError {
     #[error("State transition failed: {0}")]
     TransitionFailed(String),
     #[error("Invalid state: {0}")]
     InvalidState(String),
-    #[error("Block not found at height: {0}")]
-    BlockNotFound(u64),
+    #[error("Unauthorized mint: {0}")]
+    UnauthorizedMint(String),
+    #[error("Invalid latency report: {0}")]
+    InvalidReport(String),
+    #[error("Invalid slashing evidence: {0}")]
+    InvalidEvidence(String),
+    #[error("Invalid certificate: {0}")]
+    InvalidCertificate(String),
 }
 
+/// Parameters every validator executes blocks with
+#[derive(Debug, Clone, Default)]
+pub struct ChainRules {
+    /// Reward pool minted per epoch
+    pub epoch_reward: u64,
+    /// Consensus participants, sorted, the only signers certificates may
+    /// carry. The leader of a view is elected from them as the supervisor
+    /// does.
+    pub validators: Vec<PublicKey>,
+}
+
+impl ChainRules {
+    pub fn new(epoch_reward: u64, mut validators: Vec<PublicKey>) -> Self {
+        validators.sort();
+        validators.dedup();
+        Self { epoch_reward, validators }
+    }
+
+    /// Distinct signers a certificate needs: all but the up to a third of
+    /// validators that may be faulty
+    fn quorum(&self) -> usize {
+        let count = self.validators.len();
+        count - count.saturating_sub(1) / 3
+    }
+
+    fn leader(&self, view: View) -> Option<&PublicKey> {
+        let count = self.validators.len() as u64;
+        (count > 0).then(|| &self.validators[(view % count) as usize])
+    }
+}
+
+/// Participation the certificates committed in one reward epoch
+#[derive(Debug, Clone, Default)]
+struct Participation {
+    /// Views whose notarization was committed
+    views: u64,
+    stats: BTreeMap<[u8; 32], ValidatorEpochStats>,
+}
+
+/// Effects of applying a block
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Transition {
+    /// Balance after the block of every account it wrote
+    pub writes: WriteSet,
+    /// Reward epochs the block minted
+    pub rewarded: Vec<EpochSummary>,
+}
+
+/// Balances as of the latest applied block. Cheap enough to clone that a
+/// copy is kept for every block executed but not yet finalized.
+#[derive(Debug, Clone, Default)]
 pub struct BlockchainState {
-    // Using RwLock for concurrent access to state
-    blocks: Arc<RwLock<HashMap<u64, Block>>>,
-    balances: Arc<RwLock<HashMap<Vec<u8>, u64>>>,
-    latest_height: Arc<RwLock<u64>>,
+    balances: BTreeMap<[u8; 32], u64>,
+    /// Reward epochs already minted, which no later block may mint again
+    rewarded: BTreeSet<u64>,
+    /// Timestamp of the newest latency report committed by each reporter
+    latency: BTreeMap<[u8; 32], u64>,
+    /// Penalties proven against each validator by committed evidence
+    slashed: BTreeMap<[u8; 32], SlashRecord>,
+    /// Evidence committed, by offender, view and kind, which no later block
+    /// may commit again
+    evidence: BTreeSet<([u8; 32], View, EvidenceKind)>,
+    /// View of the latest certificate of each kind committed. Certificates
+    /// are committed in view order, so none is counted twice.
+    certified: BTreeMap<CertificateKind, View>,
+    /// Participation committed in every epoch not minted yet
+    participation: BTreeMap<u64, Participation>,
+    latest: Option<Block>,
+    latest_hash: [u8; 32],
 }
 
 impl BlockchainState {
     pub fn new() -> Self {
-        Self {
-            blocks: Arc::new(RwLock::new(HashMap::new())),
-            balances: Arc::new(RwLock::new(HashMap::new())),
-            latest_height: Arc::new(RwLock::new(0)),
-        }
+        Self::default()
     }
 
-    /// Applies the genesis block to initialize the blockchain state
-    pub fn apply_genesis_block(&self, block: &Block) -> Result<(), StateError> {
-        if block.header.height != 0 {
+    /// Applies the genesis block to initialize the blockchain state. Genesis
+    /// may only mint, and its mints carry no signature.
+    pub fn apply_genesis_block(&mut self, block: &Block) -> Result<Transition, StateError> {
+        if block.header.height != 0 || self.latest.is_some() {
             return Err(StateError::InvalidState(
                 "Genesis block must have height 0".to_string(),
             ));
         }
 
-        // Initialize state with genesis block
-        let mut blocks = self.blocks.write().map_err(|_| {
-            StateError::TransitionFailed("Failed to acquire blocks lock".to_string())
-        })?;
-
-        let mut balances = self.balances.write().map_err(|_| {
-            StateError::TransitionFailed("Failed to acquire balances lock".to_string())
-        })?;
-
-        let mut latest_height = self.latest_height.write().map_err(|_| {
-            StateError::TransitionFailed("Failed to acquire height lock".to_string())
-        })?;
-
-        // Process genesis transactions
+        let mut transition = Transition::default();
         for tx in &block.transactions {
-            if let TransactionType::TokenTransfer { to, amount, .. } = &tx.transaction_type {
-                balances.insert(to.to_vec(), *amount);
+            let TransactionType::TokenTransfer { to, amount, transfer_type } = &tx.transaction_type else {
+                return Err(StateError::InvalidState(
+                    "Genesis block may only mint".to_string(),
+                ));
+            };
+            if !matches!(transfer_type, TransferType::Mint) {
+                return Err(StateError::InvalidState(
+                    "Genesis block may only mint".to_string(),
+                ));
             }
+            self.credit(to, *amount, &mut transition)?;
         }
 
-        // Store the genesis block
-        blocks.insert(0, block.clone());
-        *latest_height = 0;
-
-        info!("Genesis block applied successfully");
-        Ok(())
+        self.latest_hash = BlockHasher::new().hash_block(block);
+        self.latest = Some(block.clone());
+        Ok(transition)
     }
 
-    /// Applies a new block to the current state
-    pub fn apply_block(&self, block: &Block) -> Result<(), StateError> {
-        // Verify block height is sequential
-        let expected_height = {
-            let height = self.latest_height.read().map_err(|_| {
-                StateError::TransitionFailed("Failed to read height".to_string())
-            })?;
-            *height + 1
-        };
-
+    /// Applies a block extending the latest one. The state is unchanged if
+    /// the block is rejected.
+    ///
+    /// Certificates must be signed by a quorum of `rules.validators`, for a
+    /// view before the block's, later than the last certificate of their
+    /// kind and in an epoch not minted yet. Reward mints must be signed by
+    /// the block's proposer, for an epoch that ended before the block's view
+    /// and hasn't been minted before, and must pay exactly the shares of
+    /// `rules.epoch_reward` the epoch's committed participation earned, less
+    /// committed penalties, as of the end of the block. Latency reports must
+    /// be signed by their reporter, no later than the block and newer than
+    /// the reporter's last committed report. Slashing evidence must verify
+    /// against the consensus namespace and not have been committed before.
+    /// Transfers are rejected until they carry their sender's signature.
+    pub fn apply_block<C: Scheme>(&mut self, block: &Block, rules: &ChainRules) -> Result<Transition, StateError> {
+        let latest = self
+            .latest
+            .as_ref()
+            .ok_or_else(|| StateError::InvalidState("Genesis block not applied".to_string()))?;
+        let expected_height = latest.header.height + 1;
         if block.header.height != expected_height {
             return Err(StateError::InvalidState(format!(
                 "Block height {} is not sequential. Expected {}",
                 block.header.height, expected_height
             )));
         }
+        if block.header.previous_hash != self.latest_hash {
+            return Err(StateError::InvalidState(format!(
+                "Block {} does not extend block {}",
+                block.header.height,
+                hex(&self.latest_hash)
+            )));
+        }
 
-        // Process all transactions and update state
-        let mut balances = self.balances.write().map_err(|_| {
-            StateError::TransitionFailed("Failed to acquire balances lock".to_string())
-        })?;
-
+        let mut next = self.clone();
+        let mut transition = Transition::default();
+        let mut minted: BTreeMap<u64, Vec<([u8; 32], u64)>> = BTreeMap::new();
         for tx in &block.transactions {
-            self.process_transaction(tx, &mut balances)?;
+            next.process_transaction::<C>(block, tx, rules, &mut minted, &mut transition)?;
+        }
+        for (epoch, mut mints) in minted {
+            let summary = next.epoch_summary(epoch, rules);
+            let mut earned = summary.payouts();
+            earned.sort();
+            mints.sort();
+            if mints != earned {
+                return Err(StateError::UnauthorizedMint(format!(
+                    "mints for epoch {} differ from the rewards its participation earned",
+                    epoch
+                )));
+            }
+            next.rewarded.insert(epoch);
+            next.participation.remove(&epoch);
+            transition.rewarded.push(summary);
         }
 
-        // Store the new block
-        let mut blocks = self.blocks.write().map_err(|_| {
-            StateError::TransitionFailed("Failed to acquire blocks lock".to_string())
-        })?;
+        next.latest_hash = BlockHasher::new().hash_block(block);
+        next.latest = Some(block.clone());
+        *self = next;
+        Ok(transition)
+    }
 
-        blocks.insert(block.header.height, block.clone());
+    /// Process a single transaction and update balances
+    fn process_transaction<C: Scheme>(
+        &mut self,
+        block: &Block,
+        tx: &Transaction,
+        rules: &ChainRules,
+        minted: &mut BTreeMap<u64, Vec<([u8; 32], u64)>>,
+        transition: &mut Transition,
+    ) -> Result<(), StateError> {
+        let (to, amount, transfer_type) = match &tx.transaction_type {
+            TransactionType::TokenTransfer { to, amount, transfer_type } => (to, amount, transfer_type),
+            TransactionType::LatencyReport { report } => {
+                return self.commit_report::<C>(block, tx, report, transition)
+            }
+            TransactionType::Evidence { evidence } => return self.commit_evidence::<C>(evidence, transition),
+            TransactionType::Certificate { kind, proof } => {
+                return self.commit_certificate::<C>(block, *kind, proof, rules, transition)
+            }
+        };
+        if !matches!(transfer_type, TransferType::Mint) {
+            return Err(StateError::TransitionFailed(
+                "Only reward mints, latency reports, evidence and certificates are executed".to_string(),
+            ));
+        }
 
-        // Update latest height
-        let mut latest_height = self.latest_height.write().map_err(|_| {
-            StateError::TransitionFailed("Failed to acquire height lock".to_string())
-        })?;
-        *latest_height = block.header.height;
+        // The nonce of a reward mint is the epoch it pays
+        let epoch = tx.nonce;
+        if epoch >= epoch_of(u64::from(block.header.view)) {
+            return Err(StateError::UnauthorizedMint(format!(
+                "epoch {} has not ended by view {}",
+                epoch, block.header.view
+            )));
+        }
+        if self.rewarded.contains(&epoch) {
+            return Err(StateError::UnauthorizedMint(format!(
+                "epoch {} was already minted",
+                epoch
+            )));
+        }
+        if !verify_mint::<C>(tx, &block.header.validator_public_key) {
+            return Err(StateError::UnauthorizedMint(
+                "not signed by the block's proposer".to_string(),
+            ));
+        }
+        minted.entry(epoch).or_default().push((*to, *amount));
+        self.credit(to, *amount, transition)
+    }
 
-        info!("Block {} applied successfully", block.header.height);
+    /// Commits a consensus certificate, crediting its signers, and for a
+    /// notarization the view's leader, in the reward epoch of its view
+    fn commit_certificate<C: Scheme>(
+        &mut self,
+        block: &Block,
+        kind: CertificateKind,
+        proof: &[u8],
+        rules: &ChainRules,
+        transition: &mut Transition,
+    ) -> Result<(), StateError> {
+        let prover = Prover::<C, Sha256>::new(CONSENSUS_NAMESPACE);
+        let max = rules.validators.len() as u32;
+        let certificate = match kind {
+            CertificateKind::Notarization => prover.deserialize_notarization(proof.to_vec().into(), max, true),
+            CertificateKind::Finalization => prover.deserialize_finalization(proof.to_vec().into(), max, true),
+        };
+        let (view, _, _, signers) = certificate
+            .ok_or_else(|| StateError::InvalidCertificate("signatures don't verify".to_string()))?;
+        if view >= u64::from(block.header.view) {
+            return Err(StateError::InvalidCertificate(format!(
+                "view {} is not before the block's view {}",
+                view, block.header.view
+            )));
+        }
+        if let Some(latest) = self.certified.get(&kind).filter(|latest| **latest >= view) {
+            return Err(StateError::InvalidCertificate(format!(
+                "{:?} for view {} is not after the last committed, for view {}",
+                kind, view, latest
+            )));
+        }
+        let epoch = epoch_of(view);
+        if self.rewarded.contains(&epoch) {
+            return Err(StateError::InvalidCertificate(format!(
+                "epoch {} was already minted",
+                epoch
+            )));
+        }
+
+        let mut members = BTreeSet::new();
+        for signer in signers {
+            if rules.validators.binary_search(&signer).is_err() {
+                return Err(StateError::InvalidCertificate(format!("{} is not a validator", hex(&signer))));
+            }
+            let member: [u8; 32] = signer
+                .as_ref()
+                .try_into()
+                .map_err(|_| StateError::InvalidCertificate("signer is not a 32 byte key".to_string()))?;
+            members.insert(member);
+        }
+        if members.len() < rules.quorum() {
+            return Err(StateError::InvalidCertificate(format!(
+                "{} distinct signers, {} needed",
+                members.len(),
+                rules.quorum()
+            )));
+        }
+
+        self.certified.insert(kind, view);
+        let participation = self.participation.entry(epoch).or_default();
+        for member in members {
+            let stats = participation.stats.entry(member).or_default();
+            stats.attestations += 1;
+            if kind == CertificateKind::Notarization {
+                stats.views_attested += 1;
+            }
+        }
+        if kind == CertificateKind::Notarization {
+            participation.views += 1;
+            if let Some(leader) = rules.leader(view).and_then(|leader| <[u8; 32]>::try_from(leader.as_ref()).ok()) {
+                participation.stats.entry(leader).or_default().blocks_proposed += 1;
+            }
+        }
+        transition.writes.insert(format!("certified/{:?}", kind), view);
         Ok(())
     }
 
-    /// Process a single transaction and update balances
-    fn process_transaction(
-        &self,
+    /// Commits a latency report signed by the transaction's sender
+    fn commit_report<C: Scheme>(
+        &mut self,
+        block: &Block,
         tx: &Transaction,
-        balances: &mut HashMap<Vec<u8>, u64>,
+        bytes: &[u8],
+        transition: &mut Transition,
     ) -> Result<(), StateError> {
-        match &tx.transaction_type {
-            TransactionType::TokenTransfer { to, amount, transfer_type } => {
-                match transfer_type {
-                    TransferType::Mint => {
-                        // Add new tokens to recipient
-                        let current_balance = balances.get(&to.to_vec()).unwrap_or(&0);
-                        balances.insert(to.to_vec(), current_balance + amount);
-                    }
-                    TransferType::Burn => {
-                        // Remove tokens from sender
-                        let sender_balance = balances.get(&tx.from.to_vec()).unwrap_or(&0);
-                        if *sender_balance < *amount {
-                            return Err(StateError::TransitionFailed(
-                                "Insufficient balance for burn".to_string(),
-                            ));
-                        }
-                        balances.insert(tx.from.to_vec(), sender_balance - amount);
-                    }
-                    TransferType::Normal => {
-                        // Regular transfer between accounts
-                        let sender_balance = balances.get(&tx.from.to_vec()).unwrap_or(&0);
-                        if *sender_balance < *amount {
-                            return Err(StateError::TransitionFailed(
-                                "Insufficient balance for transfer".to_string(),
-                            ));
-                        }
-                        
-                        let recipient_balance = balances.get(&to.to_vec()).unwrap_or(&0);
-                        
-                        balances.insert(tx.from.to_vec(), sender_balance - amount);
-                        balances.insert(to.to_vec(), recipient_balance + amount);
-                    }
-                }
-            }
+        let report = LatencyReport::decode(bytes).map_err(|e| StateError::InvalidReport(e.to_string()))?;
+        if report.reporter.as_ref() != tx.from.as_slice() || !report.verify::<C>() {
+            return Err(StateError::InvalidReport(
+                "not signed by the transaction's sender".to_string(),
+            ));
+        }
+        if report.timestamp > block.header.timestamp {
+            return Err(StateError::InvalidReport(format!(
+                "timestamp {} is after the block's {}",
+                report.timestamp, block.header.timestamp
+            )));
         }
+        if self.latency_timestamp(&tx.from).is_some_and(|held| held >= report.timestamp) {
+            return Err(StateError::InvalidReport(format!(
+                "{} has a report as new as {}",
+                hex(&tx.from),
+                report.timestamp
+            )));
+        }
+        self.latency.insert(tx.from, report.timestamp);
+        transition.writes.insert(format!("latency/{}", hex(&tx.from)), report.timestamp);
+        Ok(())
+    }
+
+    /// Commits evidence of a double vote, adding a penalty to its offender
+    fn commit_evidence<C: Scheme>(&mut self, bytes: &[u8], transition: &mut Transition) -> Result<(), StateError> {
+        let evidence = Evidence::decode(bytes).map_err(|e| StateError::InvalidEvidence(e.to_string()))?;
+        let offender: [u8; 32] = evidence
+            .offender
+            .as_ref()
+            .try_into()
+            .map_err(|_| StateError::InvalidEvidence("offender is not a 32 byte key".to_string()))?;
+        if !self.evidence.insert((offender, evidence.view, evidence.kind)) {
+            return Err(StateError::InvalidEvidence(format!(
+                "{} was already slashed for {:?} in view {}",
+                hex(&offender),
+                evidence.kind,
+                evidence.view
+            )));
+        }
+        evidence
+            .verify(&Prover::<C, Sha256>::new(CONSENSUS_NAMESPACE))
+            .map_err(|e| StateError::InvalidEvidence(e.to_string()))?;
+
+        let record = self
+            .slashed
+            .entry(offender)
+            .and_modify(|record| record.add(&evidence))
+            .or_insert_with(|| SlashRecord::new(&evidence));
+        transition
+            .writes
+            .insert(format!("slashed/{}", hex(&offender)), u64::from(record.penalty_bps));
+        Ok(())
+    }
+
+    fn credit(&mut self, account: &[u8; 32], amount: u64, transition: &mut Transition) -> Result<(), StateError> {
+        let balance = self.balances.entry(*account).or_default();
+        *balance = balance.checked_add(amount).ok_or_else(|| {
+            StateError::TransitionFailed("Balance overflow".to_string())
+        })?;
+        transition.writes.insert(hex(account), *balance);
         Ok(())
     }
 
-    /// Gets a block at a specific height
-    pub fn get_block_at_height(&self, height: u64) -> Option<Block> {
-        self.blocks
-            .read()
-            .ok()
-            .and_then(|blocks| blocks.get(&height).cloned())
+    /// Commits to every balance, in address order, followed by the timestamp
+    /// of every reporter's latest latency report, the evidence committed, the
+    /// penalty of every slashed validator, the latest certificates and the
+    /// participation of every epoch not minted yet
+    pub fn state_root(&self) -> [u8; 32] {
+        let pairs: Vec<(Vec<u8>, u64)> = self
+            .balances
+            .iter()
+            .map(|(account, balance)| (account.to_vec(), *balance))
+            .chain(
+                self.latency
+                    .iter()
+                    .map(|(reporter, timestamp)| ([b"latency/".as_slice(), reporter].concat(), *timestamp)),
+            )
+            .chain(self.evidence.iter().map(|(offender, view, kind)| {
+                let key = [b"evidence/".as_slice(), offender, &view.to_be_bytes(), &[kind.to_byte()]].concat();
+                (key, *view)
+            }))
+            .chain(self.slashed.iter().map(|(offender, record)| {
+                ([b"slashed/".as_slice(), offender].concat(), u64::from(record.penalty_bps))
+            }))
+            .chain(self.certified.iter().map(|(kind, view)| {
+                let kind = match kind {
+                    CertificateKind::Notarization => 0,
+                    CertificateKind::Finalization => 1,
+                };
+                ([b"certified/".as_slice(), &[kind]].concat(), *view)
+            }))
+            .chain(self.participation.iter().flat_map(|(epoch, participation)| {
+                let prefix = [b"participation/".as_slice(), &epoch.to_be_bytes()].concat();
+                let validators = participation.stats.iter().flat_map(move |(validator, stats)| {
+                    let key = |field: u8| [prefix.as_slice(), validator, &[field]].concat();
+                    [
+                        (key(0), stats.views_attested),
+                        (key(1), stats.blocks_proposed),
+                        (key(2), stats.attestations),
+                    ]
+                });
+                let views = [b"participation/".as_slice(), &epoch.to_be_bytes()].concat();
+                std::iter::once((views, participation.views)).chain(validators)
+            }))
+            .collect();
+        BlockHasher::new().calculate_state_root(&pairs)
     }
 
     /// Gets the latest block
-    pub fn get_latest_block(&self) -> Option<Block> {
-        let height = *self.latest_height.read().ok()?;
-        self.get_block_at_height(height)
+    pub fn get_latest_block(&self) -> Option<&Block> {
+        self.latest.as_ref()
+    }
+
+    /// Hash of the latest block, which the next block must extend
+    pub fn latest_hash(&self) -> [u8; 32] {
+        self.latest_hash
     }
 
     /// Gets the balance for an account
-    pub fn get_balance(&self, account: &[u8]) -> Result<u64, StateError> {
-        let balances = self.balances.read().map_err(|_| {
-            StateError::TransitionFailed("Failed to read balances".to_string())
-        })?;
-        Ok(*balances.get(account).unwrap_or(&0))
+    pub fn get_balance(&self, account: &[u8; 32]) -> u64 {
+        self.balances.get(account).copied().unwrap_or(0)
     }
 
     /// Gets the current blockchain height
-    pub fn get_height(&self) -> Result<u64, StateError> {
-        Ok(*self.latest_height.read().map_err(|_| {
-            StateError::TransitionFailed("Failed to read height".to_string())
-        })?)
+    pub fn get_height(&self) -> u64 {
+        self.latest.as_ref().map_or(0, |block| block.header.height)
+    }
+
+    /// Timestamp of the latest latency report committed by `reporter`
+    pub fn latency_timestamp(&self, reporter: &[u8; 32]) -> Option<u64> {
+        self.latency.get(reporter).copied()
+    }
+
+    /// Every slashed validator and its record
+    pub fn slash_records(&self) -> Vec<(PublicKey, SlashRecord)> {
+        self.slashed
+            .iter()
+            .map(|(offender, record)| (offender.to_vec().into(), record.clone()))
+            .collect()
+    }
+
+    /// Whether a block has already committed `evidence`
+    pub fn has_evidence(&self, evidence: &Evidence) -> bool {
+        <[u8; 32]>::try_from(evidence.offender.as_ref())
+            .is_ok_and(|offender| self.evidence.contains(&(offender, evidence.view, evidence.kind)))
+    }
+
+    /// Penalty committed against `validator`, in basis points of stake
+    pub fn penalty_bps(&self, validator: &[u8; 32]) -> u32 {
+        self.slashed.get(validator).map_or(0, |record| record.penalty_bps)
+    }
+
+    /// Whether a certificate of `kind` for `view` can be committed on top
+    /// of this state, leaving its signatures to be verified
+    pub fn accepts_certificate(&self, kind: CertificateKind, view: View) -> bool {
+        !self.certified.get(&kind).is_some_and(|latest| *latest >= view) && !self.rewarded.contains(&epoch_of(view))
+    }
+
+    /// The rewards `epoch`'s committed participation earns: each validator's
+    /// share of `rules.epoch_reward`, less its committed penalty
+    pub fn epoch_summary(&self, epoch: u64, rules: &ChainRules) -> EpochSummary {
+        let participation = self.participation.get(&epoch).cloned().unwrap_or_default();
+        let views = participation.views.min(EPOCH_LENGTH);
+        let validators: Vec<(PublicKey, ValidatorEpochStats)> = participation
+            .stats
+            .into_iter()
+            .map(|(validator, stats)| (validator.to_vec().into(), stats))
+            .collect();
+        let rewards = penalize(distribute(rules.epoch_reward, views, &validators), |validator| {
+            <[u8; 32]>::try_from(validator.as_ref()).map_or(0, |validator| self.penalty_bps(&validator))
+        });
+        EpochSummary {
+            epoch,
+            views,
+            pool: rules.epoch_reward,
+            validators,
+            rewards,
+        }
+    }
+
+    /// Epochs ended before `epoch` whose participation earned rewards no
+    /// block has minted yet
+    pub fn unpaid(&self, epoch: u64, rules: &ChainRules) -> Vec<EpochSummary> {
+        self.participation
+            .range(..epoch)
+            .map(|(ended, _)| self.epoch_summary(*ended, rules))
+            .filter(|summary| !summary.rewards.is_empty())
+            .collect()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    
-    // Add state transition and balance tracking tests
+    use crate::application::block::entities::BlockHeader;
+    use crate::types::ValidatorLocation;
+    use commonware_cryptography::Ed25519;
+
+    fn block(state: &BlockchainState, view: u32, proposer: [u8; 32], transactions: Vec<Transaction>) -> Block {
+        Block {
+            header: BlockHeader {
+                view,
+                height: state.get_height() + 1,
+                timestamp: 0,
+                previous_hash: state.latest_hash(),
+                transactions_root: [0u8; 32],
+                state_root: [0u8; 32],
+                validator_public_key: proposer,
+            },
+            transactions,
+        }
+    }
+
+    #[test]
+    fn test_reward_mints_match_participation() {
+        let mut state = BlockchainState::new();
+        let mut genesis = block(&state, 0, [0u8; 32], Vec::new());
+        genesis.header.height = 0;
+        state.apply_genesis_block(&genesis).unwrap();
+
+        let mut proposer = Ed25519::from_seed(1);
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&proposer.public_key());
+        let (honest, slashed) = (Ed25519::from_seed(2).public_key(), Ed25519::from_seed(3).public_key());
+        let rules = ChainRules::new(100, vec![honest.clone(), slashed.clone()]);
+        let stats = |views_attested, blocks_proposed| ValidatorEpochStats {
+            views_attested,
+            blocks_proposed,
+            attestations: views_attested,
+        };
+        let account = |validator: &PublicKey| <[u8; 32]>::try_from(validator.as_ref()).unwrap();
+        state.participation.insert(
+            0,
+            Participation {
+                views: 2,
+                stats: [(account(&honest), stats(2, 1)), (account(&slashed), stats(1, 1))].into(),
+            },
+        );
+        let unpenalized = state.epoch_summary(0, &rules);
+        assert_eq!(unpenalized.rewards.len(), 2);
+
+        // A committed penalty withholds the offender's share
+        state.slashed.insert(
+            account(&slashed),
+            SlashRecord { offenses: 20, penalty_bps: 10_000, last_view: 1, kinds: Vec::new() },
+        );
+        let earned = state.epoch_summary(0, &rules);
+        assert_eq!(earned.rewards.len(), 1);
+        assert_eq!(earned.rewards[0].validator, honest);
+        assert_eq!(state.unpaid(1, &rules), vec![earned.clone()]);
+
+        // Before the epoch ended, paying more, less or to the slashed
+        // validator, or by someone other than the proposer, the mints are
+        // refused and nothing changes
+        let view = EPOCH_LENGTH as u32;
+        let mut inflated = earned.clone();
+        inflated.rewards[0].amount += 1;
+        let mut partial = earned.clone();
+        partial.rewards[0].amount -= 1;
+        for (at, proposer_key, summary) in [
+            (view - 1, key, &earned),
+            (view, key, &inflated),
+            (view, key, &partial),
+            (view, key, &unpenalized),
+            (view, [9u8; 32], &earned),
+        ] {
+            let refused = block(&state, at, proposer_key, summary.reward_transactions(&mut proposer));
+            assert!(matches!(state.apply_block::<Ed25519>(&refused, &rules), Err(StateError::UnauthorizedMint(_))));
+        }
+        assert_eq!(state.get_height(), 0);
+
+        let paid = block(&state, view, key, earned.reward_transactions(&mut proposer));
+        let transition = state.apply_block::<Ed25519>(&paid, &rules).unwrap();
+        assert_eq!(transition.rewarded, vec![earned.clone()]);
+        assert_eq!(transition.writes.values().copied().collect::<Vec<_>>(), vec![earned.rewards[0].amount]);
+        assert!(state.unpaid(1, &rules).is_empty());
+        assert!(!state.accepts_certificate(CertificateKind::Notarization, 5));
+
+        // An epoch is minted once
+        let again = block(&state, view + 1, key, earned.reward_transactions(&mut proposer));
+        assert!(matches!(state.apply_block::<Ed25519>(&again, &rules), Err(StateError::UnauthorizedMint(_))));
+        assert_eq!(state.get_height(), 1);
+    }
+
+    #[test]
+    fn test_unverified_certificates_are_refused() {
+        let mut state = BlockchainState::new();
+        let mut genesis = block(&state, 0, [0u8; 32], Vec::new());
+        genesis.header.height = 0;
+        state.apply_genesis_block(&genesis).unwrap();
+
+        let rules = ChainRules::new(100, vec![Ed25519::from_seed(1).public_key()]);
+        let certificate = Transaction {
+            transaction_type: TransactionType::Certificate {
+                kind: CertificateKind::Notarization,
+                proof: vec![1, 2, 3],
+            },
+            from: [0u8; 32],
+            nonce: 0,
+            gas_amount: 0,
+            signature: Vec::new(),
+        };
+        let root = state.state_root();
+        let rejected = block(&state, 1, [0u8; 32], vec![certificate]);
+        assert!(matches!(state.apply_block::<Ed25519>(&rejected, &rules), Err(StateError::InvalidCertificate(_))));
+        assert!(state.accepts_certificate(CertificateKind::Notarization, 0));
+        assert_eq!(state.state_root(), root);
+    }
+
+    #[test]
+    fn test_unverified_evidence_is_refused() {
+        let mut state = BlockchainState::new();
+        let mut genesis = block(&state, 0, [0u8; 32], Vec::new());
+        genesis.header.height = 0;
+        state.apply_genesis_block(&genesis).unwrap();
+        let rules = ChainRules::default();
+
+        let evidence = |evidence: Vec<u8>| Transaction {
+            transaction_type: TransactionType::Evidence { evidence },
+            from: [0u8; 32],
+            nonce: 0,
+            gas_amount: 0,
+            signature: Vec::new(),
+        };
+        let forged = Evidence {
+            kind: EvidenceKind::ConflictingNotarize,
+            offender: Ed25519::from_seed(1).public_key(),
+            view: 3,
+            proof: vec![1, 2, 3].into(),
+        };
+
+        // Neither unreadable evidence nor a proof that doesn't verify slashes
+        let root = state.state_root();
+        for transaction in [evidence(vec![9]), evidence(forged.encode())] {
+            let rejected = block(&state, 1, [0u8; 32], vec![transaction]);
+            assert!(matches!(state.apply_block::<Ed25519>(&rejected, &rules), Err(StateError::InvalidEvidence(_))));
+        }
+        assert!(!state.has_evidence(&forged));
+        assert!(state.slash_records().is_empty());
+        assert_eq!(state.state_root(), root);
+    }
+
+    #[test]
+    fn test_latency_reports_are_committed() {
+        let mut state = BlockchainState::new();
+        let mut genesis = block(&state, 0, [0u8; 32], Vec::new());
+        genesis.header.height = 0;
+        state.apply_genesis_block(&genesis).unwrap();
+        let rules = ChainRules::default();
+
+        let mut reporter = Ed25519::from_seed(1);
+        let mut from = [0u8; 32];
+        from.copy_from_slice(&reporter.public_key());
+        let location = ValidatorLocation::new(50.1, 8.7).unwrap();
+        let report = |reporter: &mut Ed25519, timestamp| Transaction {
+            transaction_type: TransactionType::LatencyReport {
+                report: LatencyReport::sign(reporter, location.clone(), timestamp, Vec::new()).encode(),
+            },
+            from,
+            nonce: 0,
+            gas_amount: 0,
+            signature: Vec::new(),
+        };
+        let at = |state: &BlockchainState, timestamp, transactions| {
+            let mut block = block(state, 1, [0u8; 32], transactions);
+            block.header.timestamp = timestamp;
+            block
+        };
+
+        // Reports from the future or sent on another's behalf are refused
+        let early = at(&state, 5, vec![report(&mut reporter, 10)]);
+        assert!(matches!(state.apply_block::<Ed25519>(&early, &rules), Err(StateError::InvalidReport(_))));
+        let mut forged = report(&mut Ed25519::from_seed(2), 10);
+        forged.from = from;
+        assert!(matches!(
+            state.apply_block::<Ed25519>(&at(&state, 10, vec![forged]), &rules),
+            Err(StateError::InvalidReport(_))
+        ));
+
+        let root = state.state_root();
+        let reported = at(&state, 10, vec![report(&mut reporter, 10)]);
+        let transition = state.apply_block::<Ed25519>(&reported, &rules).unwrap();
+        assert_eq!(state.latency_timestamp(&from), Some(10));
+        assert_eq!(transition.writes.values().copied().collect::<Vec<_>>(), vec![10]);
+        assert_ne!(state.state_root(), root);
+
+        // Only a newer report replaces it
+        let stale = at(&state, 20, vec![report(&mut reporter, 10)]);
+        assert!(matches!(state.apply_block::<Ed25519>(&stale, &rules), Err(StateError::InvalidReport(_))));
+    }
 }
-    */
\ No newline at end of file
@@ -12,12 +12,17 @@ pub enum Message {
         response: oneshot::Sender<Digest>,
     },
     Propose {
+        context: Context,
         response: oneshot::Sender<Digest>,
     },
     Verify {
+        context: Context,
         payload: Digest,
         response: oneshot::Sender<bool>,
     },
+    Broadcast {
+        payload: Digest,
+    },
     Prepared {
         proof: Proof,
         payload: Digest,
@@ -52,23 +57,22 @@ impl Au for Mailbox {
         receiver.await.expect("Failed to receive genesis")
     }
 
-    async fn propose(&mut self, _: Context) -> oneshot::Receiver<Digest> {
-        // If we linked payloads to their parent, we would include
-        // the parent in the `Context` in the payload.
+    async fn propose(&mut self, context: Context) -> oneshot::Receiver<Digest> {
+        // The block built links to the parent in the `Context`
         let (response, receiver) = oneshot::channel();
         self.sender
-            .send(Message::Propose { response })
+            .send(Message::Propose { context, response })
             .await
             .expect("Failed to send propose");
         receiver
     }
 
-    async fn verify(&mut self, _: Context, payload: Digest) -> oneshot::Receiver<bool> {
-        // If we linked payloads to their parent, we would verify
-        // the parent included in the payload matches the provided `Context`.
+    async fn verify(&mut self, context: Context, payload: Digest) -> oneshot::Receiver<bool> {
+        // The block must extend the parent in the `Context`, which is
+        // checked once the block has been received and executed
         let (response, receiver) = oneshot::channel();
         self.sender
-            .send(Message::Verify { payload, response })
+            .send(Message::Verify { context, payload, response })
             .await
             .expect("Failed to send verify");
         receiver
@@ -76,11 +80,12 @@ impl Au for Mailbox {
 }
 
 impl Re for Mailbox {
-    async fn broadcast(&mut self, _: Digest) {
-        // We don't broadcast our raw messages to other peers.
-        //
-        // If we were building an EVM blockchain, for example, we'd
-        // send the block to other peers here.
+    async fn broadcast(&mut self, payload: Digest) {
+        // Relay the block we proposed to other peers
+        self.sender
+            .send(Message::Broadcast { payload })
+            .await
+            .expect("Failed to send broadcast");
     }
 }
 
@@ -1,28 +1,74 @@
 //! This crate contains all logic typically implemented by an application developer.
 //! This includes things like how to produce/verify blocks and how to identify which
 //! participants are active at a given view.
-/* 
 use commonware_consensus::simplex::Prover;
 use commonware_cryptography::{Hasher, PublicKey, Scheme};
+use commonware_runtime::{Blob, Storage};
+use commonware_storage::journal::Journal;
+use crate::metrics::ConsensusMetrics;
+use crate::{rewards, slashing};
+use crate::node::divergence::DivergenceDetector;
+use crate::supply::SupplyTracker;
 use crate::types::ValidatorLocation;
+use crate::latency::LatencyQuery;
+use crate::location::{ConfidenceConfig, ReferenceHealth};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 
 mod actor;
 pub use actor::Application;
 
+pub mod block;
+
 mod ingress;
 mod supervisor;
 
 /// Configuration for the application.
-pub struct Config<C: Scheme, H: Hasher> {
-    /// Hashing scheme to use.
-    pub hasher: H,
-
+pub struct Config<C: Scheme, H: Hasher, B: Blob, E: Storage<B>> {
     /// Prover used to decode opaque proofs from consensus.
     pub prover: Prover<C, H>,
 
+    /// Signer of the reward mints in the blocks we propose.
+    pub signer: C,
+
     /// Participants active in consensus.
     pub participants: Vec<PublicKey>,
 
+    /// Our own public key.
+    pub me: PublicKey,
+
+    /// Consensus participation metrics.
+    pub metrics: ConsensusMetrics,
+
+    /// Mailbox for submitting evidence of misbehavior.
+    pub evidence: slashing::Mailbox,
+
+    /// Verified evidence to commit in the blocks we propose.
+    pub pending_evidence: slashing::EvidencePool,
+
+    /// Penalties committed by the last finalized block.
+    pub slashing: slashing::SlashingQuery,
+
+    /// Reward epochs as finalized blocks leave them.
+    pub rewards: rewards::RewardsQuery,
+
+    /// Tokens minted per reward epoch.
+    pub epoch_reward: u64,
+
+    /// Journal finalized blocks are persisted to.
+    pub blocks: Journal<B, E>,
+
+    /// Block the chain starts from.
+    pub genesis: block::entities::Block,
+
+    /// Supply statistics updated with every finalized block, if the
+    /// network's tokenomics are known.
+    pub supply: Option<SupplyTracker>,
+
+    /// Latency reports to commit in the blocks we propose, and the matrix
+    /// of those finalized blocks commit.
+    pub latency: LatencyQuery,
+
     /// Number of messages from consensus to hold in our backlog
     /// before blocking.
     pub mailbox_size: usize,
@@ -30,5 +76,18 @@ pub struct Config<C: Scheme, H: Hasher> {
     /// Validator location information - can be loaded from config file
     /// or environment variables
     pub validator_location: Option<ValidatorLocation>,
+
+    /// Reference points the location is validated against, weighted by the
+    /// health checker running in the background.
+    pub references: Arc<Mutex<ReferenceHealth>>,
+
+    /// Parameters of the location confidence model.
+    pub confidence: ConfidenceConfig,
+
+    /// File the location confidence report is written to, if any.
+    pub confidence_report: Option<PathBuf>,
+
+    /// Checks the state root of every executed block against the notarized
+    /// one, halting voting on a mismatch.
+    pub divergence: DivergenceDetector,
 }
-    */
\ No newline at end of file
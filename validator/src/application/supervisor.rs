@@ -1,16 +1,33 @@
-use commonware_consensus::{simplex::View, Activity, Proof, Supervisor as Su};
-use commonware_cryptography::PublicKey;
+use crate::metrics::ConsensusMetrics;
+use crate::slashing;
+use commonware_consensus::{
+    simplex::{Prover, View, FINALIZE, NOTARIZE, NULLIFY},
+    Activity, Proof, Supervisor as Su,
+};
+use commonware_cryptography::{Hasher, PublicKey, Scheme};
 use std::collections::HashMap;
 
 /// Implementation of `commonware-consensus::Supervisor`.
 #[derive(Clone)]
-pub struct Supervisor {
+pub struct Supervisor<C: Scheme, H: Hasher> {
     participants: Vec<PublicKey>,
     participants_map: HashMap<PublicKey, u32>,
+
+    /// Our own public key, used to filter reported activity
+    me: PublicKey,
+    prover: Prover<C, H>,
+    metrics: ConsensusMetrics,
+    evidence: slashing::Mailbox,
 }
 
-impl Supervisor {
-    pub fn new(mut participants: Vec<PublicKey>) -> Self {
+impl<C: Scheme, H: Hasher> Supervisor<C, H> {
+    pub fn new(
+        mut participants: Vec<PublicKey>,
+        me: PublicKey,
+        prover: Prover<C, H>,
+        metrics: ConsensusMetrics,
+        evidence: slashing::Mailbox,
+    ) -> Self {
         // Setup participants
         participants.sort();
         let mut participants_map = HashMap::new();
@@ -22,11 +39,15 @@ impl Supervisor {
         Self {
             participants,
             participants_map,
+            me,
+            prover,
+            metrics,
+            evidence,
         }
     }
 }
 
-impl Su for Supervisor {
+impl<C: Scheme, H: Hasher> Su for Supervisor<C, H> {
     type Index = View;
     type Seed = ();
 
@@ -42,8 +63,39 @@ impl Su for Supervisor {
         self.participants_map.get(candidate).cloned()
     }
 
-    async fn report(&self, _: Activity, _: Proof) {
-        // We don't report activity in this example but you would otherwise use
-        // this to collect uptime and fraud proofs.
+    async fn report(&self, activity: Activity, proof: Proof) {
+        // Consensus reports activity for every participant, but our metrics
+        // only count the votes that we signed ourselves. Rewards are credited
+        // from the certificates blocks commit instead.
+        match activity {
+            NOTARIZE => {
+                if let Some((view, _, _, signer)) = self.prover.deserialize_notarize(proof, false) {
+                    if signer == self.me {
+                        self.metrics.record_view(view);
+                        self.metrics.notarizations_signed.inc();
+                    }
+                }
+            }
+            NULLIFY => {
+                if let Some((view, signer)) = self.prover.deserialize_nullify(proof, false) {
+                    if signer == self.me {
+                        self.metrics.record_view(view);
+                        self.metrics.nullifications.inc();
+                    }
+                }
+            }
+            FINALIZE => {
+                if let Some((view, _, _, signer)) = self.prover.deserialize_finalize(proof, false) {
+                    if signer == self.me {
+                        self.metrics.record_view(view);
+                        self.metrics.finalizations_signed.inc();
+                    }
+                }
+            }
+            _ => {
+                // Faults (double votes) are forwarded as slashing evidence
+                self.evidence.clone().report(activity, proof).await;
+            }
+        }
     }
 }
\ No newline at end of file
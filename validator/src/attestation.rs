@@ -0,0 +1,121 @@
+//! Delivery of signed location and hardware attestations to the sequencer.
+//!
+//! At every interval the validator assesses its claimed location against the
+//! reference points again, detects the hardware it runs on, signs both with
+//! its consensus key and submits them to the sequencer's
+//! `submit_attestation` RPC method. Counterparties query the latest one with
+//! `get_attestation_status`. Attestations expire after a few intervals, so a
+//! validator that stops attesting shows as expired rather than attested.
+
+use commonware_cryptography::Scheme;
+use commonware_runtime::{Clock, SystemTimeExt};
+use romer_common::types::address::Address;
+use romer_common::types::attestation::{Attestation, HardwareAttestation, LocationAttestation};
+use romer_common::types::keymanager::SignatureScheme;
+use romer_common::utils::hardware_validator::{HardwareDetector, HardwareRequirements, VirtualizationType};
+use romer_common::utils::rpc;
+use serde_json::json;
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::location::ValidatorLocation;
+use crate::validation::proof_generator::ProofGeneratorBuilder;
+
+/// Intervals an attestation stays valid for, so one missed delivery does
+/// not expire it
+const VALIDITY_INTERVALS: u32 = 3;
+
+/// Periodically attests to this validator's location and hardware
+pub struct Attester<R: Clock, C: Scheme> {
+    runtime: R,
+    signer: C,
+    /// JSON-RPC address of the sequencer, `host:port`
+    sequencer: String,
+    location: ValidatorLocation,
+    proof_generator: ProofGeneratorBuilder,
+    /// Directory whose filesystem free disk is measured on
+    storage_dir: PathBuf,
+    interval: Duration,
+}
+
+impl<R: Clock, C: Scheme> Attester<R, C> {
+    pub fn new(
+        runtime: R,
+        signer: C,
+        sequencer: String,
+        location: ValidatorLocation,
+        proof_generator: ProofGeneratorBuilder,
+        storage_dir: PathBuf,
+        interval: Duration,
+    ) -> Self {
+        Self {
+            runtime,
+            signer,
+            sequencer,
+            location,
+            proof_generator,
+            storage_dir,
+            interval,
+        }
+    }
+
+    /// Runs the checks and builds an unsigned attestation issued at `now`
+    /// (unix seconds)
+    pub async fn attest(&self, now: u64) -> Attestation {
+        let report = self.proof_generator.assess_location(self.location.to_point()).await;
+
+        let profile = HardwareDetector::profile(&self.storage_dir);
+        let (virtualization, requirements_met) = match HardwareDetector::detect_virtualization() {
+            Ok(virtualization) => {
+                let checks = HardwareRequirements::default().check(&virtualization, &profile);
+                let platform = match virtualization {
+                    VirtualizationType::Physical => None,
+                    VirtualizationType::Virtual(platform) => Some(platform),
+                };
+                (platform, checks.iter().all(|check| check.passed))
+            }
+            Err(e) => {
+                warn!(error = %e, "Failed to detect virtualization");
+                (Some("unknown".to_string()), false)
+            }
+        };
+
+        Attestation {
+            validator: Address::from_public_key(SignatureScheme::Ed25519, &self.signer.public_key()),
+            location: LocationAttestation {
+                latitude: self.location.latitude(),
+                longitude: self.location.longitude(),
+                confidence: report.confidence,
+                passed: report.passed,
+            },
+            hardware: HardwareAttestation {
+                virtualization,
+                cpu_cores: profile.cpu_cores,
+                memory_bytes: profile.memory_bytes,
+                requirements_met,
+            },
+            issued_at: now,
+            expires_at: now + (self.interval * VALIDITY_INTERVALS).as_secs(),
+        }
+    }
+
+    /// Attests at every interval until the process exits
+    pub async fn run(mut self) {
+        loop {
+            let now = self.runtime.current().epoch_millis() / 1000;
+            let attestation = self.attest(now).await;
+            match attestation.sign(SignatureScheme::Ed25519, &mut self.signer) {
+                Ok(signed) => {
+                    let params = json!({ "attestation": signed });
+                    match rpc::call(&self.sequencer, "submit_attestation", params).await {
+                        Ok(status) => info!(state = %status["state"], "Delivered attestation"),
+                        Err(e) => warn!(sequencer = %self.sequencer, error = %e, "Failed to deliver attestation"),
+                    }
+                }
+                Err(e) => warn!(error = %e, "Failed to sign attestation"),
+            }
+            self.runtime.sleep(self.interval).await;
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use super::record::{decode_batch, encode_batch, PeerRecord, RecordError};
+use commonware_cryptography::{PublicKey, Scheme};
+use commonware_p2p::{Receiver, Recipients, Sender};
+use commonware_runtime::{Blob, Clock, Storage, SystemTimeExt};
+use commonware_storage::journal::Journal;
+use commonware_utils::hex;
+use futures::{
+    future::{select, Either},
+    pin_mut, StreamExt,
+};
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Records timestamped further than this ahead of our clock are rejected
+const MAX_CLOCK_SKEW_MS: u64 = 60_000;
+
+/// Records are journaled in one section per day
+const SECTION_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// Days of journaled records kept
+const RETAINED_SECTIONS: u64 = 7;
+
+/// The newest verified record of every other participant
+pub struct PeerBook {
+    me: PublicKey,
+    participants: HashSet<PublicKey>,
+    records: HashMap<PublicKey, PeerRecord>,
+}
+
+impl PeerBook {
+    pub fn new(me: PublicKey, participants: &[PublicKey]) -> Self {
+        Self {
+            me,
+            participants: participants.iter().cloned().collect(),
+            records: HashMap::new(),
+        }
+    }
+
+    /// Accepts `record` if it is correctly signed by another participant,
+    /// not from the future and newer than the one held. Returns whether the
+    /// record was accepted.
+    pub fn insert<C: Scheme>(&mut self, record: PeerRecord, now_ms: u64) -> bool {
+        if record.public_key == self.me || !self.participants.contains(&record.public_key) {
+            return false;
+        }
+        if record.timestamp > now_ms.saturating_add(MAX_CLOCK_SKEW_MS) {
+            return false;
+        }
+        if let Some(held) = self.records.get(&record.public_key) {
+            if held.timestamp >= record.timestamp {
+                return false;
+            }
+        }
+        if !record.verify::<C>() {
+            return false;
+        }
+        self.records.insert(record.public_key.clone(), record);
+        true
+    }
+
+    pub fn records(&self) -> impl Iterator<Item = &PeerRecord> {
+        self.records.values()
+    }
+
+    /// Known peers in the form the p2p network dials on startup
+    pub fn bootstrappers(&self) -> Vec<(PublicKey, SocketAddr)> {
+        self.records
+            .values()
+            .map(|record| (record.public_key.clone(), record.address))
+            .collect()
+    }
+}
+
+/// Gossips this validator's signed address and every record it has learned
+/// every `interval`, and accepts the records other participants gossip
+pub struct Exchange<R: Clock, B: Blob, E: Storage<B>, C: Scheme> {
+    runtime: R,
+    journal: Journal<B, E>,
+    signer: C,
+    address: SocketAddr,
+    interval: Duration,
+    book: PeerBook,
+}
+
+impl<R: Clock, B: Blob, E: Storage<B>, C: Scheme> Exchange<R, B, E, C> {
+    /// Creates an exchange announcing `address`, learning records of
+    /// `participants` and persisting them to `journal`
+    pub fn new(
+        runtime: R,
+        journal: Journal<B, E>,
+        signer: C,
+        address: SocketAddr,
+        participants: &[PublicKey],
+        interval: Duration,
+    ) -> Self {
+        let book = PeerBook::new(signer.public_key(), participants);
+        Self {
+            runtime,
+            journal,
+            signer,
+            address,
+            interval,
+            book,
+        }
+    }
+
+    pub fn book(&self) -> &PeerBook {
+        &self.book
+    }
+
+    /// Replays persisted records, so peers learned before a restart can be
+    /// dialed before gossip resumes
+    pub async fn restore(&mut self) -> Result<(), RecordError> {
+        let now = self.runtime.current().epoch_millis();
+        let mut restored = Vec::new();
+        {
+            let stream = self
+                .journal
+                .replay(1)
+                .await
+                .map_err(|e| RecordError::Storage(e.to_string()))?;
+            pin_mut!(stream);
+            while let Some(item) = stream.next().await {
+                let (_, _, _, bytes) = item.map_err(|e| RecordError::Storage(e.to_string()))?;
+                match PeerRecord::decode(&bytes) {
+                    Ok(record) => restored.push(record),
+                    Err(e) => warn!(error = %e, "Skipping unreadable peer record"),
+                }
+            }
+        }
+        for record in restored {
+            self.book.insert::<C>(record, now);
+        }
+        info!(count = self.book.records.len(), "Restored peer records");
+        Ok(())
+    }
+
+    async fn persist(&mut self, record: &PeerRecord) -> Result<(), RecordError> {
+        let section = record.timestamp / SECTION_MS;
+        self.journal
+            .append(section, record.encode().into())
+            .await
+            .map_err(|e| RecordError::Storage(e.to_string()))?;
+        self.journal
+            .sync(section)
+            .await
+            .map_err(|e| RecordError::Storage(e.to_string()))?;
+        self.journal
+            .prune(section.saturating_sub(RETAINED_SECTIONS))
+            .await
+            .map_err(|e| RecordError::Storage(e.to_string()))
+    }
+
+    /// Sends a freshly signed record of our own address along with every
+    /// record we know to all peers
+    async fn gossip(&mut self, sender: &mut impl Sender) {
+        let now = self.runtime.current().epoch_millis();
+        let own = PeerRecord::sign(&mut self.signer, self.address, now);
+        let batch = encode_batch(std::iter::once(&own).chain(self.book.records()));
+        if let Err(e) = sender.send(Recipients::All, batch.into(), false).await {
+            debug!(error = ?e, "Failed to gossip peer records");
+        }
+    }
+
+    async fn receive(&mut self, peer: PublicKey, bytes: &[u8]) {
+        let records = match decode_batch(bytes) {
+            Ok(records) => records,
+            Err(e) => {
+                debug!(peer = hex(&peer), error = %e, "Ignoring malformed peer records");
+                return;
+            }
+        };
+        let now = self.runtime.current().epoch_millis();
+        for record in records {
+            if !self.book.insert::<C>(record.clone(), now) {
+                continue;
+            }
+            debug!(peer = hex(&record.public_key), address = %record.address, "Learned peer address");
+            if let Err(e) = self.persist(&record).await {
+                warn!(error = %e, "Failed to persist peer record");
+            }
+        }
+    }
+
+    /// Run the exchange until the channel is closed
+    pub async fn run(mut self, mut sender: impl Sender, mut receiver: impl Receiver) {
+        self.gossip(&mut sender).await;
+        loop {
+            let message = {
+                let recv = receiver.recv();
+                let tick = self.runtime.sleep(self.interval);
+                pin_mut!(recv, tick);
+                match select(recv, tick).await {
+                    Either::Left((message, _)) => Some(message),
+                    Either::Right(_) => None,
+                }
+            };
+            match message {
+                Some(Ok((peer, bytes))) => self.receive(peer, &bytes).await,
+                Some(Err(e)) => {
+                    warn!(error = ?e, "Peer exchange channel closed");
+                    return;
+                }
+                None => self.gossip(&mut sender).await,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use commonware_cryptography::Ed25519;
+
+    fn record(seed: u64, port: u16, timestamp: u64) -> PeerRecord {
+        let address = SocketAddr::from(([127, 0, 0, 1], port));
+        PeerRecord::sign(&mut Ed25519::from_seed(seed), address, timestamp)
+    }
+
+    fn book() -> PeerBook {
+        let participants: Vec<PublicKey> = (0..3).map(|seed| Ed25519::from_seed(seed).public_key()).collect();
+        PeerBook::new(participants[0].clone(), &participants)
+    }
+
+    #[test]
+    fn test_keeps_newest_record() {
+        let mut book = book();
+        assert!(book.insert::<Ed25519>(record(1, 3001, 10), 100));
+        assert!(!book.insert::<Ed25519>(record(1, 4001, 5), 100));
+        assert!(book.insert::<Ed25519>(record(1, 4001, 20), 100));
+        assert_eq!(book.bootstrappers(), vec![(Ed25519::from_seed(1).public_key(), SocketAddr::from(([127, 0, 0, 1], 4001)))]);
+    }
+
+    #[test]
+    fn test_rejects_untrusted_records() {
+        let mut book = book();
+        // Ourselves, and validators outside the participant set
+        assert!(!book.insert::<Ed25519>(record(0, 3000, 10), 100));
+        assert!(!book.insert::<Ed25519>(record(9, 3009, 10), 100));
+        // Too far in the future
+        assert!(!book.insert::<Ed25519>(record(1, 3001, 100 + MAX_CLOCK_SKEW_MS + 1), 100));
+        // Signed by someone else
+        let mut forged = record(1, 3001, 10);
+        forged.public_key = Ed25519::from_seed(2).public_key();
+        assert!(!book.insert::<Ed25519>(forged, 100));
+        assert_eq!(book.records().count(), 0);
+    }
+}
@@ -0,0 +1,23 @@
+//! Peer exchange between validators.
+//!
+//! Each validator signs a [`PeerRecord`] binding its public key to the
+//! address it listens on and gossips it, together with every record it has
+//! learned, over a dedicated p2p channel. The [`Exchange`] verifies records
+//! before accepting them, keeps the newest one per participant in its
+//! [`PeerBook`] and persists them, so a restarted node can dial the peers
+//! it learned instead of relying on statically configured bootstrappers.
+
+mod record;
+pub use record::{decode_batch, encode_batch, PeerRecord, RecordError};
+
+mod exchange;
+pub use exchange::{Exchange, PeerBook};
+
+/// Journal partition used to persist learned peer records
+pub const PEERS_PARTITION: &str = "peers";
+
+/// p2p channel peer records are gossiped on
+pub const PEER_EXCHANGE_CHANNEL: u32 = 2;
+
+/// Namespace peer records are signed under
+pub const PEER_RECORD_NAMESPACE: &[u8] = b"ROMER_PEER_RECORD";
@@ -0,0 +1,192 @@
+use super::PEER_RECORD_NAMESPACE;
+use commonware_cryptography::{PublicKey, Scheme, Signature};
+use std::net::SocketAddr;
+use thiserror::Error;
+
+/// Most records accepted in one gossiped batch
+const MAX_BATCH: usize = 1024;
+
+/// A validator's signed claim that it can be reached at `address`.
+///
+/// `timestamp` (milliseconds since the Unix epoch) orders the records of a
+/// validator, so a newer record replaces an older one when it moves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerRecord {
+    pub public_key: PublicKey,
+    pub address: SocketAddr,
+    pub timestamp: u64,
+    pub signature: Signature,
+}
+
+impl PeerRecord {
+    /// Signs a record for `signer` listening on `address`
+    pub fn sign<C: Scheme>(signer: &mut C, address: SocketAddr, timestamp: u64) -> Self {
+        let signature = signer.sign(Some(PEER_RECORD_NAMESPACE), &Self::payload(address, timestamp));
+        Self {
+            public_key: signer.public_key(),
+            address,
+            timestamp,
+            signature,
+        }
+    }
+
+    /// Checks the record was signed by the key it names
+    pub fn verify<C: Scheme>(&self) -> bool {
+        C::verify(
+            Some(PEER_RECORD_NAMESPACE),
+            &Self::payload(self.address, self.timestamp),
+            &self.public_key,
+            &self.signature,
+        )
+    }
+
+    fn payload(address: SocketAddr, timestamp: u64) -> Vec<u8> {
+        let address = address.to_string();
+        let mut bytes = Vec::with_capacity(address.len() + 8);
+        bytes.extend_from_slice(address.as_bytes());
+        bytes.extend_from_slice(&timestamp.to_be_bytes());
+        bytes
+    }
+
+    /// Serializes the record:
+    /// `key_len (4) | key | address_len (4) | address | timestamp (8) | signature_len (4) | signature`
+    pub fn encode(&self) -> Vec<u8> {
+        let address = self.address.to_string();
+        let mut bytes =
+            Vec::with_capacity(20 + self.public_key.len() + address.len() + self.signature.len());
+        bytes.extend_from_slice(&(self.public_key.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&self.public_key);
+        bytes.extend_from_slice(&(address.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(address.as_bytes());
+        bytes.extend_from_slice(&self.timestamp.to_be_bytes());
+        bytes.extend_from_slice(&(self.signature.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&self.signature);
+        bytes
+    }
+
+    /// Deserializes a record previously produced by [`PeerRecord::encode`]
+    pub fn decode(bytes: &[u8]) -> Result<Self, RecordError> {
+        let mut cursor = 0;
+        let record = Self::read(bytes, &mut cursor)?;
+        if cursor != bytes.len() {
+            return Err(RecordError::Malformed("trailing bytes"));
+        }
+        Ok(record)
+    }
+
+    fn read(bytes: &[u8], cursor: &mut usize) -> Result<Self, RecordError> {
+        let key_len = read_len(bytes, cursor)?;
+        let public_key = read(bytes, cursor, key_len)?.to_vec();
+
+        let address_len = read_len(bytes, cursor)?;
+        let address = std::str::from_utf8(read(bytes, cursor, address_len)?)
+            .ok()
+            .and_then(|address| address.parse().ok())
+            .ok_or(RecordError::Malformed("address"))?;
+
+        let timestamp = u64::from_be_bytes(read(bytes, cursor, 8)?.try_into().unwrap());
+
+        let signature_len = read_len(bytes, cursor)?;
+        let signature = read(bytes, cursor, signature_len)?.to_vec();
+
+        Ok(Self {
+            public_key: public_key.into(),
+            address,
+            timestamp,
+            signature: signature.into(),
+        })
+    }
+}
+
+/// Serializes a gossiped batch: `count (4) | record*`
+pub fn encode_batch<'a>(records: impl IntoIterator<Item = &'a PeerRecord>) -> Vec<u8> {
+    let mut count = 0u32;
+    let mut bytes = vec![0; 4];
+    for record in records {
+        bytes.extend_from_slice(&record.encode());
+        count += 1;
+    }
+    bytes[..4].copy_from_slice(&count.to_be_bytes());
+    bytes
+}
+
+/// Deserializes a batch produced by [`encode_batch`]
+pub fn decode_batch(bytes: &[u8]) -> Result<Vec<PeerRecord>, RecordError> {
+    let mut cursor = 0;
+    let count = read_len(bytes, &mut cursor)?;
+    if count > MAX_BATCH {
+        return Err(RecordError::Malformed("too many records"));
+    }
+    let mut records = Vec::with_capacity(count);
+    for _ in 0..count {
+        records.push(PeerRecord::read(bytes, &mut cursor)?);
+    }
+    if cursor != bytes.len() {
+        return Err(RecordError::Malformed("trailing bytes"));
+    }
+    Ok(records)
+}
+
+fn read<'a>(bytes: &'a [u8], cursor: &mut usize, len: usize) -> Result<&'a [u8], RecordError> {
+    let end = cursor
+        .checked_add(len)
+        .filter(|end| *end <= bytes.len())
+        .ok_or(RecordError::Malformed("truncated"))?;
+    let slice = &bytes[*cursor..end];
+    *cursor = end;
+    Ok(slice)
+}
+
+fn read_len(bytes: &[u8], cursor: &mut usize) -> Result<usize, RecordError> {
+    let raw = read(bytes, cursor, 4)?;
+    Ok(u32::from_be_bytes(raw.try_into().unwrap()) as usize)
+}
+
+/// Errors that can occur while handling peer records
+#[derive(Debug, Error)]
+pub enum RecordError {
+    #[error("Malformed peer record: {0}")]
+    Malformed(&'static str),
+
+    #[error("Storage error: {0}")]
+    Storage(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use commonware_cryptography::Ed25519;
+
+    fn sample() -> PeerRecord {
+        PeerRecord::sign(&mut Ed25519::from_seed(1), "127.0.0.1:3001".parse().unwrap(), 1_000)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let record = sample();
+        let decoded = PeerRecord::decode(&record.encode()).unwrap();
+        assert_eq!(decoded, record);
+        assert!(decoded.verify::<Ed25519>());
+    }
+
+    #[test]
+    fn test_tampered_rejected() {
+        let mut record = sample();
+        record.address = "10.0.0.1:3001".parse().unwrap();
+        assert!(!record.verify::<Ed25519>());
+
+        let mut record = sample();
+        record.timestamp += 1;
+        assert!(!record.verify::<Ed25519>());
+    }
+
+    #[test]
+    fn test_batch_round_trip() {
+        let other = PeerRecord::sign(&mut Ed25519::from_seed(2), "127.0.0.1:3002".parse().unwrap(), 5);
+        let records = vec![sample(), other];
+        assert_eq!(decode_batch(&encode_batch(&records)).unwrap(), records);
+
+        let bytes = encode_batch(&records);
+        assert!(decode_batch(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,242 @@
+//! Read-only JSON endpoint for block explorers and auditors, serving the
+//! latency matrix, reward epochs, supply statistics and slashing penalties.
+//!
+//! Like the metrics endpoint this is deliberately minimal: the request line
+//! is parsed for its path and everything else is ignored.
+
+use crate::latency::{LatencyQuery, LatencyReport, Region, RegionLatency};
+use crate::rewards::{EpochSummary, RewardsQuery};
+use crate::slashing::{SlashRecord, SlashingQuery};
+use crate::supply::SupplyQuery;
+use commonware_cryptography::PublicKey;
+use commonware_utils::{from_hex, hex};
+use serde::Serialize;
+use std::net::SocketAddr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tracing::{info, warn};
+
+/// A signed latency report with keys and signature hex encoded
+#[derive(Serialize)]
+struct ReportView {
+    reporter: String,
+    latitude: f64,
+    longitude: f64,
+    region: Region,
+    timestamp: u64,
+    samples: Vec<SampleView>,
+    signature: String,
+}
+
+#[derive(Serialize)]
+struct SampleView {
+    peer: String,
+    rtt_us: u64,
+}
+
+impl From<&LatencyReport> for ReportView {
+    fn from(report: &LatencyReport) -> Self {
+        Self {
+            reporter: hex(&report.reporter),
+            latitude: report.location.latitude(),
+            longitude: report.location.longitude(),
+            region: Region::of(&report.location),
+            timestamp: report.timestamp,
+            samples: report
+                .samples
+                .iter()
+                .map(|sample| SampleView {
+                    peer: hex(&sample.peer),
+                    rtt_us: sample.rtt_us,
+                })
+                .collect(),
+            signature: hex(&report.signature),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct LatencyView {
+    regions: Vec<RegionLatency>,
+    reports: Vec<ReportView>,
+}
+
+/// A validator's participation and reward in an epoch, key hex encoded
+#[derive(Serialize)]
+struct ParticipationView {
+    validator: String,
+    views_attested: u64,
+    blocks_proposed: u64,
+    attestations: u64,
+    reward: u64,
+}
+
+/// A completed reward epoch, and whether a finalized block has minted it
+#[derive(Serialize)]
+struct EpochView {
+    epoch: u64,
+    views: u64,
+    pool: u64,
+    paid: bool,
+    validators: Vec<ParticipationView>,
+}
+
+impl EpochView {
+    fn new(summary: EpochSummary, rewards: &RewardsQuery) -> Self {
+        let reward = |validator| {
+            summary
+                .rewards
+                .iter()
+                .find(|share| &share.validator == validator)
+                .map_or(0, |share| share.amount)
+        };
+        Self {
+            epoch: summary.epoch,
+            views: summary.views,
+            pool: summary.pool,
+            paid: summary.rewards.is_empty() || !rewards.is_unpaid(summary.epoch),
+            validators: summary
+                .validators
+                .iter()
+                .map(|(validator, stats)| ParticipationView {
+                    validator: hex(validator),
+                    views_attested: stats.views_attested,
+                    blocks_proposed: stats.blocks_proposed,
+                    attestations: stats.attestations,
+                    reward: reward(validator),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct RewardHistoryView {
+    epoch: u64,
+    amount: u64,
+}
+
+/// A slashed validator, key hex encoded, and the penalties against it
+#[derive(Serialize)]
+struct SlashView {
+    validator: String,
+    #[serde(flatten)]
+    record: SlashRecord,
+}
+
+/// Sources the explorer answers from
+#[derive(Clone)]
+pub struct Queries {
+    pub latency: LatencyQuery,
+    pub rewards: RewardsQuery,
+    pub supply: SupplyQuery,
+    pub slashing: SlashingQuery,
+}
+
+/// Routes a request path to its JSON body, or `None` if unknown
+fn route(path: &str, queries: &Queries) -> Option<String> {
+    let Queries {
+        latency,
+        rewards,
+        supply,
+        slashing,
+    } = queries;
+    let body = match path {
+        // The region matrix along with the signed reports behind it
+        "/latency" => serde_json::to_string(&LatencyView {
+            regions: latency.regions(),
+            reports: latency.reports().iter().map(ReportView::from).collect(),
+        }),
+        "/latency/regions" => serde_json::to_string(&latency.regions()),
+        // The same, from the reports committed by finalized blocks
+        "/latency/chain" => serde_json::to_string(&LatencyView {
+            regions: latency.chain_regions(),
+            reports: latency.chain_reports().iter().map(ReportView::from).collect(),
+        }),
+        "/latency/chain/regions" => serde_json::to_string(&latency.chain_regions()),
+        // The latest completed epoch, and the epochs waiting to be minted
+        "/rewards" => serde_json::to_string(&rewards.latest().map(|summary| EpochView::new(summary, rewards))),
+        "/rewards/unpaid" => serde_json::to_string(
+            &rewards
+                .unpaid()
+                .into_iter()
+                .map(|summary| EpochView::new(summary, rewards))
+                .collect::<Vec<_>>(),
+        ),
+        // Total, circulating, burned and treasury supply after the latest
+        // finalized block
+        "/supply" => serde_json::to_string(&supply.latest()),
+        // Every validator penalized for proven misbehavior
+        "/slashing" => serde_json::to_string(
+            &slashing
+                .records()
+                .into_iter()
+                .map(|(validator, record)| SlashView {
+                    validator: hex(&validator),
+                    record,
+                })
+                .collect::<Vec<_>>(),
+        ),
+        _ => {
+            if let Some(height) = path.strip_prefix("/supply/") {
+                serde_json::to_string(&supply.at(height.parse().ok()?)?)
+            } else if let Some(validator) = path.strip_prefix("/slashing/") {
+                let validator: PublicKey = from_hex(validator)?.into();
+                serde_json::to_string(&slashing.record(&validator))
+            } else if let Some(epoch) = path.strip_prefix("/rewards/epoch/") {
+                let summary = rewards.epoch(epoch.parse().ok()?)?;
+                serde_json::to_string(&EpochView::new(summary, rewards))
+            } else if let Some(validator) = path.strip_prefix("/rewards/validator/") {
+                let validator: PublicKey = from_hex(validator)?.into();
+                let history: Vec<RewardHistoryView> = rewards
+                    .validator_history(&validator)
+                    .into_iter()
+                    .map(|(epoch, amount)| RewardHistoryView { epoch, amount })
+                    .collect();
+                serde_json::to_string(&history)
+            } else {
+                return None;
+            }
+        }
+    };
+    body.ok()
+}
+
+/// Serves the explorer API on `addr`
+pub async fn serve(addr: SocketAddr, queries: Queries) {
+    let listener = match TcpListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            warn!(%addr, error = %e, "Failed to bind explorer endpoint");
+            return;
+        }
+    };
+    info!(%addr, "Serving explorer API");
+
+    loop {
+        let (mut socket, _) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                warn!(error = %e, "Failed to accept explorer connection");
+                continue;
+            }
+        };
+
+        let mut request = [0u8; 1024];
+        let read = socket.read(&mut request).await.unwrap_or(0);
+        let path = std::str::from_utf8(&request[..read])
+            .ok()
+            .and_then(|request| request.split_whitespace().nth(1))
+            .unwrap_or("/");
+
+        let response = match route(path, &queries) {
+            Some(body) => format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            ),
+            None => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n".to_string(),
+        };
+        let _ = socket.write_all(response.as_bytes()).await;
+    }
+}
@@ -23,7 +23,8 @@ use std::{
     sync::{Arc, Mutex},
     time::Duration,
 };
-use tracing_subscriber::fmt::MakeWriter;
+use romer_common::utils::logging::{self, LogFormat, LoggingConfig};
+use tracing_subscriber::fmt::{writer::BoxMakeWriter, MakeWriter};
 
 const HEIGHT_OFFSET: u16 = 2;
 
@@ -144,18 +145,16 @@ pub struct Gui {
 }
 
 impl Gui {
-    pub fn new() -> Self {
+    pub fn new(logging: &LoggingConfig) -> Self {
         // Create writer
         let progress = Arc::new(Mutex::new(Vec::new()));
         let logs = Arc::new(Mutex::new(Vec::new()));
         let writer = Writer::new(progress.clone(), logs.clone());
 
-        // Register writer
-        tracing_subscriber::fmt()
-            .json()
-            .with_max_level(tracing::Level::DEBUG)
-            .with_writer(writer)
-            .init();
+        // Register writer, which parses JSON whatever format the log file uses
+        if let Err(e) = logging::init_with_console(logging, BoxMakeWriter::new(writer), LogFormat::Json) {
+            eprintln!("Failed to initialize logging: {}", e);
+        }
         Self { progress, logs }
     }
 
@@ -0,0 +1,281 @@
+use super::hello::{Hello, HelloError};
+use commonware_cryptography::PublicKey;
+use commonware_p2p::{Receiver, Recipients, Sender};
+use commonware_runtime::Clock;
+use commonware_utils::hex;
+use futures::{
+    channel::mpsc,
+    future::{select, Either},
+    pin_mut, SinkExt,
+};
+use prometheus_client::metrics::{counter::Counter, family::Family, gauge::Gauge};
+use prometheus_client::registry::Registry;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+type VersionLabels = Vec<(&'static str, String)>;
+
+/// Versions of the peers handshaken with and peers refused
+#[derive(Clone)]
+pub struct HandshakeMetrics {
+    /// Accepted peers by the software version they announced
+    pub peers_by_version: Family<VersionLabels, Gauge>,
+    /// Peers refused for being on another chain or genesis
+    pub peers_refused: Counter,
+}
+
+impl HandshakeMetrics {
+    /// Creates the metrics and registers them under the `romer_handshake` prefix
+    pub fn new(registry: &Arc<Mutex<Registry>>) -> Self {
+        let metrics = Self {
+            peers_by_version: Family::default(),
+            peers_refused: Counter::default(),
+        };
+
+        let mut registry = registry.lock().unwrap();
+        let registry = registry.sub_registry_with_prefix("romer_handshake");
+        registry.register(
+            "peers_by_version",
+            "Handshaken peers by announced software version",
+            metrics.peers_by_version.clone(),
+        );
+        registry.register(
+            "peers_refused",
+            "Peers refused for being on another chain or genesis",
+            metrics.peers_refused.clone(),
+        );
+
+        metrics
+    }
+}
+
+/// Outcome of every handshake with a participant
+pub struct PeerTable {
+    participants: Vec<PublicKey>,
+    versions: HashMap<PublicKey, String>,
+    refused: HashSet<PublicKey>,
+}
+
+impl PeerTable {
+    pub fn new(participants: &[PublicKey]) -> Self {
+        Self {
+            participants: participants.to_vec(),
+            versions: HashMap::new(),
+            refused: HashSet::new(),
+        }
+    }
+
+    /// Checks `theirs` against `ours`, recording the peer's version if it
+    /// is compatible and refusing the peer for good if not. Hellos of
+    /// refused peers and of non-participants are ignored.
+    pub fn accept(&mut self, ours: &Hello, peer: PublicKey, theirs: &Hello) -> Result<bool, HelloError> {
+        if self.refused.contains(&peer) || !self.participants.contains(&peer) {
+            return Ok(false);
+        }
+        if let Err(e) = ours.check(theirs) {
+            self.versions.remove(&peer);
+            self.refused.insert(peer);
+            return Err(e);
+        }
+        self.versions.insert(peer, theirs.version.clone());
+        Ok(true)
+    }
+
+    pub fn version(&self, peer: &PublicKey) -> Option<&str> {
+        self.versions.get(peer).map(String::as_str)
+    }
+
+    pub fn is_refused(&self, peer: &PublicKey) -> bool {
+        self.refused.contains(peer)
+    }
+
+    /// Participants not refused, the peer set the network should allow
+    pub fn allowed(&self) -> Vec<PublicKey> {
+        self.participants
+            .iter()
+            .filter(|participant| !self.refused.contains(*participant))
+            .cloned()
+            .collect()
+    }
+
+    /// Allowed participants, other than `me`, that have not answered yet
+    pub fn pending(&self, me: &PublicKey) -> Vec<PublicKey> {
+        self.allowed()
+            .into_iter()
+            .filter(|participant| participant != me && !self.versions.contains_key(participant))
+            .collect()
+    }
+
+    /// Accepted peers per announced version
+    pub fn distribution(&self) -> HashMap<String, usize> {
+        let mut distribution = HashMap::new();
+        for version in self.versions.values() {
+            *distribution.entry(version.clone()).or_default() += 1;
+        }
+        distribution
+    }
+}
+
+/// Exchanges hellos with every participant, refusing those on another
+/// chain or genesis
+pub struct Handshake<R: Clock> {
+    runtime: R,
+    me: PublicKey,
+    hello: Hello,
+    interval: Duration,
+    table: PeerTable,
+    metrics: HandshakeMetrics,
+    /// Versions the distribution metric has been reported for
+    reported: HashSet<String>,
+    /// Receives the allowed peer set whenever a peer is refused
+    peer_sets: mpsc::Sender<Vec<PublicKey>>,
+}
+
+impl<R: Clock> Handshake<R> {
+    /// Creates a handshake announcing `hello` to `participants` every
+    /// `interval` until they answer
+    pub fn new(
+        runtime: R,
+        me: PublicKey,
+        hello: Hello,
+        participants: &[PublicKey],
+        interval: Duration,
+        metrics: HandshakeMetrics,
+        peer_sets: mpsc::Sender<Vec<PublicKey>>,
+    ) -> Self {
+        Self {
+            runtime,
+            me,
+            hello,
+            interval,
+            table: PeerTable::new(participants),
+            metrics,
+            reported: HashSet::new(),
+            peer_sets,
+        }
+    }
+
+    async fn greet(&mut self, sender: &mut impl Sender) {
+        let pending = self.table.pending(&self.me);
+        if pending.is_empty() {
+            return;
+        }
+        if let Err(e) = sender.send(Recipients::Some(pending), self.hello.encode().into(), false).await {
+            debug!(error = ?e, "Failed to send hello");
+        }
+    }
+
+    async fn receive(&mut self, sender: &mut impl Sender, peer: PublicKey, bytes: &[u8]) {
+        let theirs = match Hello::decode(bytes) {
+            Ok(hello) => hello,
+            Err(e) => {
+                debug!(peer = hex(&peer), error = %e, "Ignoring malformed hello");
+                return;
+            }
+        };
+        let previous = self.table.version(&peer).map(str::to_string);
+        match self.table.accept(&self.hello, peer.clone(), &theirs) {
+            Ok(false) => return,
+            Ok(true) => {
+                if previous.as_deref() != Some(theirs.version.as_str()) {
+                    info!(peer = hex(&peer), version = %theirs.version, "Handshake completed");
+                    if theirs.version != self.hello.version {
+                        warn!(peer = hex(&peer), ours = %self.hello.version, theirs = %theirs.version, "Peer runs another version");
+                    }
+                }
+                if !theirs.ack {
+                    let ack = self.hello.ack().encode();
+                    if let Err(e) = sender.send(Recipients::One(peer), ack.into(), true).await {
+                        debug!(error = ?e, "Failed to answer hello");
+                    }
+                }
+            }
+            Err(e) => {
+                warn!(peer = hex(&peer), error = %e, "Refusing peer");
+                self.metrics.peers_refused.inc();
+                if let Err(e) = self.peer_sets.send(self.table.allowed()).await {
+                    warn!(error = ?e, "Failed to update peer set");
+                }
+            }
+        }
+        self.report();
+    }
+
+    fn report(&mut self) {
+        let distribution = self.table.distribution();
+        for version in self.reported.iter() {
+            if !distribution.contains_key(version) {
+                self.metrics.peers_by_version.get_or_create(&vec![("version", version.clone())]).set(0);
+            }
+        }
+        for (version, count) in distribution {
+            self.metrics
+                .peers_by_version
+                .get_or_create(&vec![("version", version.clone())])
+                .set(count as i64);
+            self.reported.insert(version);
+        }
+    }
+
+    /// Run the handshake until the channel is closed
+    pub async fn run(mut self, mut sender: impl Sender, mut receiver: impl Receiver) {
+        self.greet(&mut sender).await;
+        loop {
+            let message = {
+                let recv = receiver.recv();
+                let tick = self.runtime.sleep(self.interval);
+                pin_mut!(recv, tick);
+                match select(recv, tick).await {
+                    Either::Left((message, _)) => Some(message),
+                    Either::Right(_) => None,
+                }
+            };
+            match message {
+                Some(Ok((peer, bytes))) => self.receive(&mut sender, peer, &bytes).await,
+                Some(Err(e)) => {
+                    warn!(error = ?e, "Handshake channel closed");
+                    return;
+                }
+                None => self.greet(&mut sender).await,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::genesis_hash;
+    use commonware_cryptography::{Ed25519, Scheme};
+
+    fn participants() -> Vec<PublicKey> {
+        (0..4).map(|seed| Ed25519::from_seed(seed).public_key()).collect()
+    }
+
+    #[test]
+    fn test_table() {
+        let participants = participants();
+        let ours = Hello::new("0.2.0", "romer-devnet", genesis_hash(&participants));
+        let mut table = PeerTable::new(&participants);
+        assert_eq!(table.pending(&participants[0]).len(), 3);
+
+        assert_eq!(table.accept(&ours, participants[1].clone(), &ours), Ok(true));
+        let older = Hello::new("0.1.0", "romer-devnet", genesis_hash(&participants));
+        assert_eq!(table.accept(&ours, participants[2].clone(), &older), Ok(true));
+        let other_chain = Hello::new("0.2.0", "romer-testnet", genesis_hash(&participants));
+        assert!(table.accept(&ours, participants[3].clone(), &other_chain).is_err());
+
+        // Refused peers stay refused, and outsiders are ignored
+        assert_eq!(table.accept(&ours, participants[3].clone(), &ours), Ok(false));
+        assert_eq!(table.accept(&ours, Ed25519::from_seed(9).public_key(), &ours), Ok(false));
+
+        assert!(table.is_refused(&participants[3]));
+        assert_eq!(table.allowed(), participants[..3].to_vec());
+        assert!(table.pending(&participants[0]).is_empty());
+        let distribution = table.distribution();
+        assert_eq!(distribution["0.2.0"], 1);
+        assert_eq!(distribution["0.1.0"], 1);
+    }
+}
@@ -0,0 +1,177 @@
+use commonware_cryptography::{Digest, Hasher, PublicKey, Sha256};
+use commonware_utils::hex;
+use thiserror::Error;
+
+/// Longest version or chain id accepted
+const MAX_FIELD_LEN: usize = 256;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum HelloError {
+    #[error("Malformed hello: {0}")]
+    Malformed(&'static str),
+
+    #[error("Peer is on chain {theirs}, not {ours}")]
+    ChainMismatch { ours: String, theirs: String },
+
+    #[error("Peer has genesis {theirs}, not {ours}")]
+    GenesisMismatch { ours: String, theirs: String },
+}
+
+/// What a validator announces about itself when it connects.
+///
+/// The channel is authenticated, so the sender is known and the hello needs
+/// no signature. `ack` marks an answer, which is not answered again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hello {
+    pub version: String,
+    pub chain_id: String,
+    pub genesis_hash: Digest,
+    pub ack: bool,
+}
+
+impl Hello {
+    pub fn new(version: &str, chain_id: &str, genesis_hash: Digest) -> Self {
+        Self {
+            version: version.to_string(),
+            chain_id: chain_id.to_string(),
+            genesis_hash,
+            ack: false,
+        }
+    }
+
+    /// The answer to a peer's hello
+    pub fn ack(&self) -> Self {
+        Self {
+            ack: true,
+            ..self.clone()
+        }
+    }
+
+    /// Checks a peer's hello is for the same chain and genesis as ours
+    pub fn check(&self, theirs: &Hello) -> Result<(), HelloError> {
+        if self.chain_id != theirs.chain_id {
+            return Err(HelloError::ChainMismatch {
+                ours: self.chain_id.clone(),
+                theirs: theirs.chain_id.clone(),
+            });
+        }
+        if self.genesis_hash != theirs.genesis_hash {
+            return Err(HelloError::GenesisMismatch {
+                ours: hex(&self.genesis_hash),
+                theirs: hex(&theirs.genesis_hash),
+            });
+        }
+        Ok(())
+    }
+
+    /// Serializes the hello:
+    /// `version_len (4) | version | chain_id_len (4) | chain_id | genesis_len (4) | genesis_hash | ack (1)`
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes =
+            Vec::with_capacity(13 + self.version.len() + self.chain_id.len() + self.genesis_hash.len());
+        for field in [self.version.as_bytes(), self.chain_id.as_bytes(), &self.genesis_hash] {
+            bytes.extend_from_slice(&(field.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(field);
+        }
+        bytes.push(self.ack as u8);
+        bytes
+    }
+
+    /// Deserializes a hello previously produced by [`Hello::encode`]
+    pub fn decode(bytes: &[u8]) -> Result<Self, HelloError> {
+        let mut cursor = 0;
+        let version = read_string(bytes, &mut cursor, "version")?;
+        let chain_id = read_string(bytes, &mut cursor, "chain id")?;
+        let genesis_len = read_len(bytes, &mut cursor)?;
+        let genesis_hash = read(bytes, &mut cursor, genesis_len)?.to_vec();
+        let ack = match read(bytes, &mut cursor, 1)?[0] {
+            0 => false,
+            1 => true,
+            _ => return Err(HelloError::Malformed("ack")),
+        };
+        if cursor != bytes.len() {
+            return Err(HelloError::Malformed("trailing bytes"));
+        }
+        Ok(Self {
+            version,
+            chain_id,
+            genesis_hash: genesis_hash.into(),
+            ack,
+        })
+    }
+}
+
+/// Hash identifying the genesis participant set, independent of the order
+/// the participants are listed in
+pub fn genesis_hash(participants: &[PublicKey]) -> Digest {
+    let mut participants = participants.to_vec();
+    participants.sort();
+    let mut hasher = Sha256::new();
+    for participant in &participants {
+        hasher.update(participant);
+    }
+    hasher.finalize()
+}
+
+fn read_string(bytes: &[u8], cursor: &mut usize, field: &'static str) -> Result<String, HelloError> {
+    let len = read_len(bytes, cursor)?;
+    if len > MAX_FIELD_LEN {
+        return Err(HelloError::Malformed(field));
+    }
+    String::from_utf8(read(bytes, cursor, len)?.to_vec()).map_err(|_| HelloError::Malformed(field))
+}
+
+fn read<'a>(bytes: &'a [u8], cursor: &mut usize, len: usize) -> Result<&'a [u8], HelloError> {
+    let end = cursor
+        .checked_add(len)
+        .filter(|end| *end <= bytes.len())
+        .ok_or(HelloError::Malformed("truncated"))?;
+    let slice = &bytes[*cursor..end];
+    *cursor = end;
+    Ok(slice)
+}
+
+fn read_len(bytes: &[u8], cursor: &mut usize) -> Result<usize, HelloError> {
+    Ok(u32::from_be_bytes(read(bytes, cursor, 4)?.try_into().unwrap()) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use commonware_cryptography::{Ed25519, Scheme};
+
+    fn participants() -> Vec<PublicKey> {
+        (0..3).map(|seed| Ed25519::from_seed(seed).public_key()).collect()
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let hello = Hello::new("0.1.0", "romer-devnet", genesis_hash(&participants()));
+        assert_eq!(Hello::decode(&hello.encode()).unwrap(), hello);
+        assert_eq!(Hello::decode(&hello.ack().encode()).unwrap(), hello.ack());
+
+        let encoded = hello.encode();
+        assert!(Hello::decode(&encoded[..encoded.len() - 1]).is_err());
+        let mut trailing = encoded.clone();
+        trailing.push(0);
+        assert_eq!(Hello::decode(&trailing), Err(HelloError::Malformed("trailing bytes")));
+    }
+
+    #[test]
+    fn test_check() {
+        let mut reversed = participants();
+        reversed.reverse();
+        assert_eq!(genesis_hash(&participants()), genesis_hash(&reversed));
+
+        let ours = Hello::new("0.1.0", "romer-devnet", genesis_hash(&participants()));
+        assert!(ours.check(&Hello::new("0.2.0", "romer-devnet", genesis_hash(&reversed))).is_ok());
+        assert!(matches!(
+            ours.check(&Hello::new("0.1.0", "romer-testnet", genesis_hash(&participants()))),
+            Err(HelloError::ChainMismatch { .. })
+        ));
+        assert!(matches!(
+            ours.check(&Hello::new("0.1.0", "romer-devnet", genesis_hash(&participants()[..2]))),
+            Err(HelloError::GenesisMismatch { .. })
+        ));
+    }
+}
@@ -0,0 +1,25 @@
+//! Version and chain handshake between validators.
+//!
+//! Whenever a validator starts, and until every participant has answered,
+//! it sends a [`Hello`] carrying its software version, the chain id and the
+//! hash of the genesis participant set over a dedicated p2p channel. Peers
+//! answer with their own. A peer on another chain or genesis is refused: it
+//! is dropped from the peer set registered with the network, so the
+//! connection is closed and not dialed again. The versions of accepted peers
+//! are exported as metrics, so an upgrade's progress across the network can
+//! be followed.
+
+mod hello;
+pub use hello::{genesis_hash, Hello, HelloError};
+
+mod gate;
+pub use gate::{Handshake, HandshakeMetrics, PeerTable};
+
+/// p2p channel hellos are exchanged on
+pub const HANDSHAKE_CHANNEL: u32 = 4;
+
+/// Chain id of a network that does not configure one
+pub use romer_common::types::envelope::DEFAULT_CHAIN_ID;
+
+/// Software version announced to peers
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -0,0 +1,249 @@
+use super::report::LatencyReport;
+use crate::types::ValidatorLocation;
+use commonware_cryptography::{PublicKey, Scheme};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+/// Reports timestamped further than this ahead of our clock are rejected
+const MAX_CLOCK_SKEW_MS: u64 = 60_000;
+
+/// Coarse continental region a validator declares itself in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub enum Region {
+    NorthAmerica,
+    SouthAmerica,
+    Europe,
+    Africa,
+    Asia,
+    Oceania,
+}
+
+impl Region {
+    /// Places a location in a region by rough continental bounds
+    pub fn of(location: &ValidatorLocation) -> Self {
+        let (latitude, longitude) = (location.latitude(), location.longitude());
+        if longitude < -30.0 {
+            if latitude >= 12.0 {
+                Self::NorthAmerica
+            } else {
+                Self::SouthAmerica
+            }
+        } else if longitude < 60.0 {
+            if latitude >= 36.0 {
+                Self::Europe
+            } else if longitude >= 35.0 && latitude >= 12.0 {
+                // Middle East
+                Self::Asia
+            } else {
+                Self::Africa
+            }
+        } else if latitude < -10.0 && longitude >= 110.0 {
+            Self::Oceania
+        } else {
+            Self::Asia
+        }
+    }
+}
+
+/// Median round trip time measured from validators in one region to
+/// validators in another
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RegionLatency {
+    pub from: Region,
+    pub to: Region,
+    pub median_ms: f64,
+    pub samples: usize,
+}
+
+/// The newest verified report of every participant
+pub struct LatencyMatrix {
+    participants: HashSet<PublicKey>,
+    reports: HashMap<PublicKey, LatencyReport>,
+}
+
+impl LatencyMatrix {
+    pub fn new(participants: &[PublicKey]) -> Self {
+        Self {
+            participants: participants.iter().cloned().collect(),
+            reports: HashMap::new(),
+        }
+    }
+
+    /// Accepts `report` if it is correctly signed by a participant, not from
+    /// the future and newer than the one held. Returns whether the report was
+    /// accepted.
+    pub fn insert<C: Scheme>(&mut self, report: LatencyReport, now_ms: u64) -> bool {
+        if !self.participants.contains(&report.reporter) {
+            return false;
+        }
+        if report.timestamp > now_ms.saturating_add(MAX_CLOCK_SKEW_MS) {
+            return false;
+        }
+        if let Some(held) = self.reports.get(&report.reporter) {
+            if held.timestamp >= report.timestamp {
+                return false;
+            }
+        }
+        if !report.verify::<C>() {
+            return false;
+        }
+        self.reports.insert(report.reporter.clone(), report);
+        true
+    }
+
+    pub fn reports(&self) -> impl Iterator<Item = &LatencyReport> {
+        self.reports.values()
+    }
+
+    /// Aggregates every sample whose peer has declared a location into the
+    /// median latency of each pair of regions
+    pub fn regions(&self) -> Vec<RegionLatency> {
+        let mut cells: BTreeMap<(Region, Region), Vec<u64>> = BTreeMap::new();
+        for report in self.reports.values() {
+            let from = Region::of(&report.location);
+            for sample in &report.samples {
+                let Some(peer) = self.reports.get(&sample.peer) else {
+                    continue;
+                };
+                cells
+                    .entry((from, Region::of(&peer.location)))
+                    .or_default()
+                    .push(sample.rtt_us);
+            }
+        }
+        cells
+            .into_iter()
+            .map(|((from, to), mut rtts)| {
+                rtts.sort_unstable();
+                let mid = rtts.len() / 2;
+                let median_us = if rtts.len() % 2 == 0 {
+                    (rtts[mid - 1] + rtts[mid]) as f64 / 2.0
+                } else {
+                    rtts[mid] as f64
+                };
+                RegionLatency {
+                    from,
+                    to,
+                    median_ms: median_us / 1000.0,
+                    samples: rtts.len(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Read handle onto the latency matrix built from gossiped reports, shared
+/// with the explorer and the block producer, and onto the matrix built from
+/// the reports committed by finalized blocks
+#[derive(Clone)]
+pub struct LatencyQuery {
+    matrix: Arc<Mutex<LatencyMatrix>>,
+    chain: Arc<Mutex<LatencyMatrix>>,
+}
+
+/// Newest first
+fn sorted(matrix: &Mutex<LatencyMatrix>) -> Vec<LatencyReport> {
+    let mut reports: Vec<LatencyReport> = matrix.lock().unwrap().reports().cloned().collect();
+    reports.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    reports
+}
+
+impl LatencyQuery {
+    pub(super) fn new(matrix: Arc<Mutex<LatencyMatrix>>, participants: &[PublicKey]) -> Self {
+        Self {
+            matrix,
+            chain: Arc::new(Mutex::new(LatencyMatrix::new(participants))),
+        }
+    }
+
+    pub fn regions(&self) -> Vec<RegionLatency> {
+        self.matrix.lock().unwrap().regions()
+    }
+
+    /// Signed reports the matrix was built from, newest first
+    pub fn reports(&self) -> Vec<LatencyReport> {
+        sorted(&self.matrix)
+    }
+
+    /// Adds a report committed by a block finalized at `finalized_ms`
+    pub fn commit<C: Scheme>(&self, report: LatencyReport, finalized_ms: u64) -> bool {
+        self.chain.lock().unwrap().insert::<C>(report, finalized_ms)
+    }
+
+    /// The region matrix of the reports committed on chain
+    pub fn chain_regions(&self) -> Vec<RegionLatency> {
+        self.chain.lock().unwrap().regions()
+    }
+
+    /// Reports committed on chain, newest first
+    pub fn chain_reports(&self) -> Vec<LatencyReport> {
+        sorted(&self.chain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::latency::report::LatencySample;
+    use commonware_cryptography::Ed25519;
+
+    fn report(seed: u64, latitude: f64, longitude: f64, samples: &[(u64, u64)], timestamp: u64) -> LatencyReport {
+        let samples = samples
+            .iter()
+            .map(|(peer, rtt_us)| LatencySample {
+                peer: Ed25519::from_seed(*peer).public_key(),
+                rtt_us: *rtt_us,
+            })
+            .collect();
+        let location = ValidatorLocation::new(latitude, longitude).unwrap();
+        LatencyReport::sign(&mut Ed25519::from_seed(seed), location, timestamp, samples)
+    }
+
+    fn matrix() -> LatencyMatrix {
+        let participants: Vec<PublicKey> = (0..3).map(|seed| Ed25519::from_seed(seed).public_key()).collect();
+        LatencyMatrix::new(&participants)
+    }
+
+    #[test]
+    fn test_regions() {
+        let at = |latitude, longitude| Region::of(&ValidatorLocation::new(latitude, longitude).unwrap());
+        assert_eq!(at(40.7, -74.0), Region::NorthAmerica);
+        assert_eq!(at(-23.5, -46.6), Region::SouthAmerica);
+        assert_eq!(at(51.5, -0.1), Region::Europe);
+        assert_eq!(at(-1.3, 36.8), Region::Africa);
+        assert_eq!(at(25.2, 55.3), Region::Asia);
+        assert_eq!(at(35.7, 139.7), Region::Asia);
+        assert_eq!(at(-28.0, 153.4), Region::Oceania);
+    }
+
+    #[test]
+    fn test_aggregates_medians() {
+        let mut matrix = matrix();
+        // Two validators in Europe, one in Oceania
+        assert!(matrix.insert::<Ed25519>(report(0, 51.5, -0.1, &[(1, 10_000), (2, 280_000)], 10), 100));
+        assert!(matrix.insert::<Ed25519>(report(1, 52.5, 13.4, &[(0, 12_000), (2, 300_000)], 10), 100));
+        assert!(matrix.insert::<Ed25519>(report(2, -28.0, 153.4, &[(0, 290_000)], 10), 100));
+
+        let regions = matrix.regions();
+        let cell = |from, to| regions.iter().find(|c| c.from == from && c.to == to).unwrap();
+        assert_eq!(cell(Region::Europe, Region::Europe).median_ms, 11.0);
+        assert_eq!(cell(Region::Europe, Region::Oceania).median_ms, 290.0);
+        assert_eq!(cell(Region::Europe, Region::Oceania).samples, 2);
+        assert_eq!(cell(Region::Oceania, Region::Europe).median_ms, 290.0);
+    }
+
+    #[test]
+    fn test_rejects_untrusted_reports() {
+        let mut matrix = matrix();
+        assert!(!matrix.insert::<Ed25519>(report(9, 51.5, -0.1, &[], 10), 100));
+        assert!(!matrix.insert::<Ed25519>(report(1, 51.5, -0.1, &[], 100 + MAX_CLOCK_SKEW_MS + 1), 100));
+        assert!(matrix.insert::<Ed25519>(report(1, 51.5, -0.1, &[], 10), 100));
+        assert!(!matrix.insert::<Ed25519>(report(1, 51.5, -0.1, &[], 5), 100));
+
+        let mut forged = report(2, 51.5, -0.1, &[], 20);
+        forged.reporter = Ed25519::from_seed(0).public_key();
+        assert!(!matrix.insert::<Ed25519>(forged, 100));
+        assert_eq!(matrix.reports().count(), 1);
+    }
+}
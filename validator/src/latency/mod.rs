@@ -0,0 +1,29 @@
+//! Cross-region latency reporting.
+//!
+//! Validators ping each other over a dedicated p2p channel and periodically
+//! gossip a signed [`LatencyReport`] of the round trip times they measured,
+//! tagged with their declared location. Every verified report is journaled,
+//! and the newest report of each participant is aggregated into a
+//! region-to-region [`LatencyMatrix`]. Block proposers commit the reports
+//! they hold to the chain, and a second matrix is built from the reports of
+//! finalized blocks. Both matrices and the signed reports they were built
+//! from are served by the explorer endpoint, so anyone can check a
+//! validator's claimed location against what its peers measured.
+
+mod report;
+pub use report::{LatencyReport, LatencySample, Message, ReportError};
+
+mod matrix;
+pub use matrix::{LatencyMatrix, LatencyQuery, Region, RegionLatency};
+
+mod probe;
+pub use probe::Prober;
+
+/// Journal partition used to persist verified latency reports
+pub const LATENCY_PARTITION: &str = "latency";
+
+/// p2p channel pings and latency reports are exchanged on
+pub const LATENCY_CHANNEL: u32 = 3;
+
+/// Namespace latency reports are signed under
+pub const LATENCY_REPORT_NAMESPACE: &[u8] = b"ROMER_LATENCY_REPORT";
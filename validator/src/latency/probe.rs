@@ -0,0 +1,239 @@
+use super::matrix::{LatencyMatrix, LatencyQuery};
+use super::report::{LatencyReport, LatencySample, Message, ReportError};
+use crate::types::ValidatorLocation;
+use commonware_cryptography::{PublicKey, Scheme};
+use commonware_p2p::{Receiver, Recipients, Sender};
+use commonware_runtime::{Blob, Clock, Storage, SystemTimeExt};
+use commonware_storage::journal::Journal;
+use commonware_utils::hex;
+use futures::{
+    future::{select, Either},
+    pin_mut, StreamExt,
+};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tracing::{debug, info, warn};
+
+/// Reports are journaled in one section per hour
+const SECTION_MS: u64 = 60 * 60 * 1000;
+
+/// Hours of journaled reports kept
+const RETAINED_SECTIONS: u64 = 24 * 7;
+
+/// Round trip times kept per peer, whose median is reported
+const WINDOW: usize = 8;
+
+/// Pings every participant each `interval` and, once enough rounds have
+/// been measured, gossips a signed report of the median round trip times
+pub struct Prober<R: Clock, B: Blob, E: Storage<B>, C: Scheme> {
+    runtime: R,
+    journal: Journal<B, E>,
+    signer: C,
+    location: ValidatorLocation,
+    interval: Duration,
+    /// Rounds between reports
+    report_every: u64,
+
+    round: u64,
+    sent: Option<SystemTime>,
+    answered: HashSet<PublicKey>,
+    rtts: HashMap<PublicKey, VecDeque<u64>>,
+    matrix: Arc<Mutex<LatencyMatrix>>,
+}
+
+impl<R: Clock, B: Blob, E: Storage<B>, C: Scheme> Prober<R, B, E, C> {
+    /// Creates a prober reporting from `location`, accepting reports of
+    /// `participants` and persisting them to `journal`
+    pub fn new(
+        runtime: R,
+        journal: Journal<B, E>,
+        signer: C,
+        location: ValidatorLocation,
+        participants: &[PublicKey],
+        interval: Duration,
+        report_every: u64,
+    ) -> (Self, LatencyQuery) {
+        let matrix = Arc::new(Mutex::new(LatencyMatrix::new(participants)));
+        let query = LatencyQuery::new(matrix.clone(), participants);
+        (
+            Self {
+                runtime,
+                journal,
+                signer,
+                location,
+                interval,
+                report_every: report_every.max(1),
+                round: 0,
+                sent: None,
+                answered: HashSet::new(),
+                rtts: HashMap::new(),
+                matrix,
+            },
+            query,
+        )
+    }
+
+    /// Replays persisted reports, so the matrix survives a restart
+    pub async fn restore(&mut self) -> Result<(), ReportError> {
+        let now = self.runtime.current().epoch_millis();
+        let mut restored = Vec::new();
+        {
+            let stream = self
+                .journal
+                .replay(1)
+                .await
+                .map_err(|e| ReportError::Storage(e.to_string()))?;
+            pin_mut!(stream);
+            while let Some(item) = stream.next().await {
+                let (_, _, _, bytes) = item.map_err(|e| ReportError::Storage(e.to_string()))?;
+                match LatencyReport::decode(&bytes) {
+                    Ok(report) => restored.push(report),
+                    Err(e) => warn!(error = %e, "Skipping unreadable latency report"),
+                }
+            }
+        }
+        let mut matrix = self.matrix.lock().unwrap();
+        for report in restored {
+            matrix.insert::<C>(report, now);
+        }
+        info!(count = matrix.reports().count(), "Restored latency reports");
+        Ok(())
+    }
+
+    async fn persist(&mut self, report: &LatencyReport) -> Result<(), ReportError> {
+        let section = report.timestamp / SECTION_MS;
+        self.journal
+            .append(section, report.encode().into())
+            .await
+            .map_err(|e| ReportError::Storage(e.to_string()))?;
+        self.journal
+            .sync(section)
+            .await
+            .map_err(|e| ReportError::Storage(e.to_string()))?;
+        self.journal
+            .prune(section.saturating_sub(RETAINED_SECTIONS))
+            .await
+            .map_err(|e| ReportError::Storage(e.to_string()))
+    }
+
+    /// Accepts a report into the matrix and journals it
+    async fn accept(&mut self, report: LatencyReport) {
+        let now = self.runtime.current().epoch_millis();
+        if !self.matrix.lock().unwrap().insert::<C>(report.clone(), now) {
+            return;
+        }
+        if let Err(e) = self.persist(&report).await {
+            warn!(error = %e, "Failed to persist latency report");
+        }
+    }
+
+    /// Starts a new round of pings, first reporting the last rounds if due
+    async fn tick(&mut self, sender: &mut impl Sender) {
+        if self.round > 0 && self.round % self.report_every == 0 {
+            self.report(sender).await;
+        }
+        self.round += 1;
+        self.sent = Some(self.runtime.current());
+        self.answered.clear();
+        let ping = Message::Ping(self.round).encode();
+        if let Err(e) = sender.send(Recipients::All, ping.into(), false).await {
+            debug!(error = ?e, "Failed to send latency ping");
+        }
+    }
+
+    /// Signs and gossips the median round trip time to every peer answering
+    /// recent pings
+    async fn report(&mut self, sender: &mut impl Sender) {
+        let mut samples: Vec<LatencySample> = self
+            .rtts
+            .iter()
+            .map(|(peer, rtts)| {
+                let mut sorted: Vec<u64> = rtts.iter().copied().collect();
+                sorted.sort_unstable();
+                LatencySample {
+                    peer: peer.clone(),
+                    rtt_us: sorted[sorted.len() / 2],
+                }
+            })
+            .collect();
+        if samples.is_empty() {
+            return;
+        }
+        samples.sort_by(|a, b| a.peer.cmp(&b.peer));
+        let now = self.runtime.current().epoch_millis();
+        let report = LatencyReport::sign(&mut self.signer, self.location.clone(), now, samples);
+        let message = Message::Report(report.clone()).encode();
+        if let Err(e) = sender.send(Recipients::All, message.into(), false).await {
+            debug!(error = ?e, "Failed to gossip latency report");
+        }
+        self.accept(report).await;
+    }
+
+    async fn receive(&mut self, sender: &mut impl Sender, peer: PublicKey, bytes: &[u8]) {
+        let message = match Message::decode(bytes) {
+            Ok(message) => message,
+            Err(e) => {
+                debug!(peer = hex(&peer), error = %e, "Ignoring malformed latency message");
+                return;
+            }
+        };
+        match message {
+            Message::Ping(nonce) => {
+                let pong = Message::Pong(nonce).encode();
+                if let Err(e) = sender.send(Recipients::One(peer), pong.into(), true).await {
+                    debug!(error = ?e, "Failed to answer latency ping");
+                }
+            }
+            Message::Pong(nonce) => {
+                // Only the first answer of each peer to the current round is
+                // timed
+                let Some(sent) = self.sent.filter(|_| nonce == self.round) else {
+                    return;
+                };
+                if !self.answered.insert(peer.clone()) {
+                    return;
+                }
+                let Ok(rtt) = self.runtime.current().duration_since(sent) else {
+                    return;
+                };
+                let rtts = self.rtts.entry(peer).or_default();
+                if rtts.len() == WINDOW {
+                    rtts.pop_front();
+                }
+                rtts.push_back(rtt.as_micros() as u64);
+            }
+            Message::Report(report) => self.accept(report).await,
+        }
+    }
+
+    /// Run the prober until the channel is closed
+    pub async fn run(mut self, mut sender: impl Sender, mut receiver: impl Receiver) {
+        self.tick(&mut sender).await;
+        let mut next_tick = self.runtime.current() + self.interval;
+        loop {
+            let message = {
+                let recv = receiver.recv();
+                // Pongs arrive constantly, so rounds run to a deadline rather
+                // than restarting the timer on every message
+                let tick = self.runtime.sleep_until(next_tick);
+                pin_mut!(recv, tick);
+                match select(recv, tick).await {
+                    Either::Left((message, _)) => Some(message),
+                    Either::Right(_) => None,
+                }
+            };
+            match message {
+                Some(Ok((peer, bytes))) => self.receive(&mut sender, peer, &bytes).await,
+                Some(Err(e)) => {
+                    warn!(error = ?e, "Latency channel closed");
+                    return;
+                }
+                None => {
+                    self.tick(&mut sender).await;
+                    next_tick = self.runtime.current() + self.interval;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use super::LATENCY_REPORT_NAMESPACE;
+use crate::types::ValidatorLocation;
+use commonware_cryptography::{PublicKey, Scheme, Signature};
+use thiserror::Error;
+
+/// Most samples accepted in one report
+const MAX_SAMPLES: usize = 1024;
+
+const PING: u8 = 0;
+const PONG: u8 = 1;
+const REPORT: u8 = 2;
+
+/// Median round trip time to one peer, in microseconds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LatencySample {
+    pub peer: PublicKey,
+    pub rtt_us: u64,
+}
+
+/// A validator's signed round trip times to its peers, measured from
+/// `location`.
+///
+/// `timestamp` (milliseconds since the Unix epoch) orders the reports of a
+/// validator, so a newer report replaces an older one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatencyReport {
+    pub reporter: PublicKey,
+    pub location: ValidatorLocation,
+    pub timestamp: u64,
+    pub samples: Vec<LatencySample>,
+    pub signature: Signature,
+}
+
+impl LatencyReport {
+    /// Signs a report of `samples` for `signer` located at `location`
+    pub fn sign<C: Scheme>(
+        signer: &mut C,
+        location: ValidatorLocation,
+        timestamp: u64,
+        samples: Vec<LatencySample>,
+    ) -> Self {
+        let mut report = Self {
+            reporter: signer.public_key(),
+            location,
+            timestamp,
+            samples,
+            signature: Signature::default(),
+        };
+        report.signature = signer.sign(Some(LATENCY_REPORT_NAMESPACE), &report.payload());
+        report
+    }
+
+    /// Checks the report was signed by the key it names
+    pub fn verify<C: Scheme>(&self) -> bool {
+        C::verify(
+            Some(LATENCY_REPORT_NAMESPACE),
+            &self.payload(),
+            &self.reporter,
+            &self.signature,
+        )
+    }
+
+    /// Everything but the signature
+    fn payload(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write_bytes(&mut bytes, &self.reporter);
+        bytes.extend_from_slice(&self.location.latitude().to_bits().to_be_bytes());
+        bytes.extend_from_slice(&self.location.longitude().to_bits().to_be_bytes());
+        bytes.extend_from_slice(&self.timestamp.to_be_bytes());
+        bytes.extend_from_slice(&(self.samples.len() as u32).to_be_bytes());
+        for sample in &self.samples {
+            write_bytes(&mut bytes, &sample.peer);
+            bytes.extend_from_slice(&sample.rtt_us.to_be_bytes());
+        }
+        bytes
+    }
+
+    /// Serializes the report:
+    /// `key_len (4) | key | latitude (8) | longitude (8) | timestamp (8) |
+    /// count (4) | (peer_len (4) | peer | rtt_us (8))* | signature_len (4) | signature`
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = self.payload();
+        write_bytes(&mut bytes, &self.signature);
+        bytes
+    }
+
+    /// Deserializes a report previously produced by [`LatencyReport::encode`]
+    pub fn decode(bytes: &[u8]) -> Result<Self, ReportError> {
+        let mut cursor = 0;
+        let report = Self::read(bytes, &mut cursor)?;
+        if cursor != bytes.len() {
+            return Err(ReportError::Malformed("trailing bytes"));
+        }
+        Ok(report)
+    }
+
+    fn read(bytes: &[u8], cursor: &mut usize) -> Result<Self, ReportError> {
+        let reporter = read_bytes(bytes, cursor)?.to_vec().into();
+        let latitude = f64::from_bits(read_u64(bytes, cursor)?);
+        let longitude = f64::from_bits(read_u64(bytes, cursor)?);
+        let location =
+            ValidatorLocation::new(latitude, longitude).map_err(|_| ReportError::Malformed("location"))?;
+        let timestamp = read_u64(bytes, cursor)?;
+
+        let count = read_len(bytes, cursor)?;
+        if count > MAX_SAMPLES {
+            return Err(ReportError::Malformed("too many samples"));
+        }
+        let mut samples = Vec::with_capacity(count);
+        for _ in 0..count {
+            let peer = read_bytes(bytes, cursor)?.to_vec().into();
+            let rtt_us = read_u64(bytes, cursor)?;
+            samples.push(LatencySample { peer, rtt_us });
+        }
+        let signature = read_bytes(bytes, cursor)?.to_vec().into();
+
+        Ok(Self {
+            reporter,
+            location,
+            timestamp,
+            samples,
+            signature,
+        })
+    }
+}
+
+/// Messages exchanged on the latency channel
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    /// Asks every peer to echo `nonce`
+    Ping(u64),
+    /// Echo of a ping
+    Pong(u64),
+    Report(LatencyReport),
+}
+
+impl Message {
+    /// Serializes the message: `tag (1) | nonce (8)` or `tag (1) | report`
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            Self::Ping(nonce) => [&[PING][..], &nonce.to_be_bytes()].concat(),
+            Self::Pong(nonce) => [&[PONG][..], &nonce.to_be_bytes()].concat(),
+            Self::Report(report) => [&[REPORT][..], &report.encode()].concat(),
+        }
+    }
+
+    /// Deserializes a message produced by [`Message::encode`]
+    pub fn decode(bytes: &[u8]) -> Result<Self, ReportError> {
+        let (tag, rest) = bytes.split_first().ok_or(ReportError::Malformed("empty message"))?;
+        let nonce = || -> Result<u64, ReportError> {
+            let raw: [u8; 8] = rest.try_into().map_err(|_| ReportError::Malformed("nonce"))?;
+            Ok(u64::from_be_bytes(raw))
+        };
+        match *tag {
+            PING => Ok(Self::Ping(nonce()?)),
+            PONG => Ok(Self::Pong(nonce()?)),
+            REPORT => LatencyReport::decode(rest).map(Self::Report),
+            _ => Err(ReportError::Malformed("unknown message")),
+        }
+    }
+}
+
+fn write_bytes(bytes: &mut Vec<u8>, value: &[u8]) {
+    bytes.extend_from_slice(&(value.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(value);
+}
+
+fn read<'a>(bytes: &'a [u8], cursor: &mut usize, len: usize) -> Result<&'a [u8], ReportError> {
+    let end = cursor
+        .checked_add(len)
+        .filter(|end| *end <= bytes.len())
+        .ok_or(ReportError::Malformed("truncated"))?;
+    let slice = &bytes[*cursor..end];
+    *cursor = end;
+    Ok(slice)
+}
+
+fn read_len(bytes: &[u8], cursor: &mut usize) -> Result<usize, ReportError> {
+    let raw = read(bytes, cursor, 4)?;
+    Ok(u32::from_be_bytes(raw.try_into().unwrap()) as usize)
+}
+
+fn read_u64(bytes: &[u8], cursor: &mut usize) -> Result<u64, ReportError> {
+    let raw = read(bytes, cursor, 8)?;
+    Ok(u64::from_be_bytes(raw.try_into().unwrap()))
+}
+
+fn read_bytes<'a>(bytes: &'a [u8], cursor: &mut usize) -> Result<&'a [u8], ReportError> {
+    let len = read_len(bytes, cursor)?;
+    read(bytes, cursor, len)
+}
+
+/// Errors that can occur while handling latency reports
+#[derive(Debug, Error)]
+pub enum ReportError {
+    #[error("Malformed latency message: {0}")]
+    Malformed(&'static str),
+
+    #[error("Storage error: {0}")]
+    Storage(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use commonware_cryptography::Ed25519;
+
+    fn sample() -> LatencyReport {
+        let samples = vec![LatencySample {
+            peer: Ed25519::from_seed(2).public_key(),
+            rtt_us: 12_500,
+        }];
+        let location = ValidatorLocation::new(-28.0167, 153.4).unwrap();
+        LatencyReport::sign(&mut Ed25519::from_seed(1), location, 1_000, samples)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let report = sample();
+        let decoded = LatencyReport::decode(&report.encode()).unwrap();
+        assert_eq!(decoded, report);
+        assert!(decoded.verify::<Ed25519>());
+
+        let message = Message::Report(report);
+        assert_eq!(Message::decode(&message.encode()).unwrap(), message);
+        assert_eq!(Message::decode(&Message::Pong(7).encode()).unwrap(), Message::Pong(7));
+        assert!(Message::decode(&[PING, 1]).is_err());
+    }
+
+    #[test]
+    fn test_tampered_rejected() {
+        let mut report = sample();
+        report.samples[0].rtt_us = 1;
+        assert!(!report.verify::<Ed25519>());
+
+        let mut report = sample();
+        report.location = ValidatorLocation::new(51.5, 0.0).unwrap();
+        assert!(!report.verify::<Ed25519>());
+    }
+}
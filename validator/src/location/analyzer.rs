@@ -0,0 +1,50 @@
+use geo::{HaversineDistance, Point};
+
+use super::confidence::{ConfidenceReport, Observation};
+use super::reference::ReferencePoint;
+
+// Physics constants
+const SPEED_OF_LIGHT_KMS: f64 = 299_792.458; // Speed of light in km/s
+const FIBER_OVERHEAD: f64 = 1.4; // Typical fiber route overhead factor
+const PROCESSING_OVERHEAD_MS: f64 = 0.1; // Minimal processing overhead
+
+/// Great circle distance between two points in kilometres
+pub fn distance_km(a: Point<f64>, b: Point<f64>) -> f64 {
+    a.haversine_distance(&b) / 1000.0
+}
+
+/// Theoretical minimum round trip time between two points based on the
+/// speed of light through fiber optic cables
+pub fn theoretical_minimum_ms(a: Point<f64>, b: Point<f64>) -> f64 {
+    // 1. Account for fiber path being longer than great circle (FIBER_OVERHEAD)
+    // 2. Convert to round trip (multiply by 2)
+    // 3. Add minimal processing overhead
+    (distance_km(a, b) * FIBER_OVERHEAD * 2.0 / SPEED_OF_LIGHT_KMS) * 1000.0 + PROCESSING_OVERHEAD_MS
+}
+
+/// Turns what was measured against each reference point, with the point's
+/// health weight, into a verdict on a claimed location
+pub trait Analyzer: Send + Sync {
+    fn analyze(
+        &self,
+        location: Point<f64>,
+        observations: Vec<(ReferencePoint, f64, Observation)>,
+    ) -> ConfidenceReport;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_theoretical_minimum() {
+        // Test points 1000km apart
+        let point_a = Point::new(0.0, 0.0);
+        let point_b = Point::new(8.993216, 0.0); // Approximately 1000km at equator
+
+        // Expected: 1000km * 1.4 * 2 / 299792.458 * 1000 + 0.1
+        // Should be approximately 9.44ms
+        let min_latency = theoretical_minimum_ms(point_a, point_b);
+        assert!((min_latency - 9.44).abs() < 0.1);
+    }
+}
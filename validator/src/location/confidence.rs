@@ -0,0 +1,250 @@
+//! Location confidence scoring.
+//!
+//! A claimed location is checked against every usable reference point. Each
+//! check `i` produces a score `s_i` in `[0, 1]` from the ratio `r` of the
+//! measured round trip time to the theoretical minimum for the distance:
+//!
+//! - `s_i = 1` when `r <= max_latency_ratio`,
+//! - `s_i = 0` when `r >= 2 * max_latency_ratio` or the point is unreachable,
+//! - falling linearly in between.
+//!
+//! Each check is weighted by `w_i = c_i * h_i`, where `c_i` is the weight
+//! configured for the reference point (1 by default) and `h_i` its current
+//! health weight. The confidence is the weighted mean `sum(w_i * s_i) /
+//! sum(w_i)`, and the location passes when it reaches `threshold`. Each
+//! check's contribution to the confidence is `w_i * s_i / sum(w_i)`, so the
+//! contributions of a [`ConfidenceReport`] add up to its confidence.
+
+use geo::Point;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::analyzer::{distance_km, Analyzer};
+use super::reference::ReferencePoint;
+
+/// Parameters of the scoring model
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfidenceConfig {
+    /// Confidence a location needs to pass
+    pub threshold: f64,
+    /// Ratio of measured to theoretical latency up to which a check scores
+    /// in full
+    pub max_latency_ratio: f64,
+    /// Weight of each reference point's check by name, 1 if not listed
+    pub weights: BTreeMap<String, f64>,
+}
+
+impl Default for ConfidenceConfig {
+    fn default() -> Self {
+        Self {
+            threshold: 0.5,
+            max_latency_ratio: 2.0,
+            weights: BTreeMap::new(),
+        }
+    }
+}
+
+/// What was measured against one reference point
+#[derive(Debug, Clone)]
+pub enum Observation {
+    Measured { theoretical_min_ms: f64, measured_ms: f64 },
+    Unreachable { error: String },
+}
+
+/// Inputs of one check, as reported
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckInputs {
+    pub reference_latitude: f64,
+    pub reference_longitude: f64,
+    pub reference_ip: String,
+    pub distance_km: f64,
+    pub theoretical_min_ms: Option<f64>,
+    pub measured_ms: Option<f64>,
+    pub ratio: Option<f64>,
+    pub error: Option<String>,
+}
+
+/// Outcome of one check and how it counted
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    pub check: String,
+    pub inputs: CheckInputs,
+    pub configured_weight: f64,
+    pub health_weight: f64,
+    pub weight: f64,
+    pub score: f64,
+    pub contribution: f64,
+}
+
+/// Every check of a location validation and the resulting confidence
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfidenceReport {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub confidence: f64,
+    pub threshold: f64,
+    pub passed: bool,
+    pub checks: Vec<CheckResult>,
+}
+
+impl ConfidenceReport {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+impl ConfidenceConfig {
+    /// Scores a latency ratio per the model above
+    fn latency_score(&self, ratio: f64) -> f64 {
+        if ratio <= self.max_latency_ratio {
+            1.0
+        } else {
+            (2.0 - ratio / self.max_latency_ratio).clamp(0.0, 1.0)
+        }
+    }
+
+    /// Scores `location` from the observation of each reference point,
+    /// given with its health weight
+    pub fn score(
+        &self,
+        location: Point<f64>,
+        observations: Vec<(ReferencePoint, f64, Observation)>,
+    ) -> ConfidenceReport {
+        let mut checks: Vec<CheckResult> = observations
+            .into_iter()
+            .map(|(reference, health_weight, observation)| {
+                let configured_weight = self.weights.get(&reference.name).copied().unwrap_or(1.0).max(0.0);
+                let mut inputs = CheckInputs {
+                    reference_latitude: reference.location.y(),
+                    reference_longitude: reference.location.x(),
+                    reference_ip: reference.ip.to_string(),
+                    distance_km: distance_km(location, reference.location),
+                    theoretical_min_ms: None,
+                    measured_ms: None,
+                    ratio: None,
+                    error: None,
+                };
+                let score = match observation {
+                    Observation::Measured {
+                        theoretical_min_ms,
+                        measured_ms,
+                    } => {
+                        let ratio = measured_ms / theoretical_min_ms;
+                        inputs.theoretical_min_ms = Some(theoretical_min_ms);
+                        inputs.measured_ms = Some(measured_ms);
+                        inputs.ratio = Some(ratio);
+                        self.latency_score(ratio)
+                    }
+                    Observation::Unreachable { error } => {
+                        inputs.error = Some(error);
+                        0.0
+                    }
+                };
+                CheckResult {
+                    check: format!("latency:{}", reference.name),
+                    inputs,
+                    configured_weight,
+                    health_weight,
+                    weight: configured_weight * health_weight,
+                    score,
+                    contribution: 0.0,
+                }
+            })
+            .collect();
+
+        let total: f64 = checks.iter().map(|check| check.weight).sum();
+        if total > 0.0 {
+            for check in &mut checks {
+                check.contribution = check.weight * check.score / total;
+            }
+        }
+        let confidence = checks.iter().map(|check| check.contribution).sum();
+        ConfidenceReport {
+            latitude: location.y(),
+            longitude: location.x(),
+            confidence,
+            threshold: self.threshold,
+            passed: total > 0.0 && confidence >= self.threshold,
+            checks,
+        }
+    }
+}
+
+impl Analyzer for ConfidenceConfig {
+    fn analyze(
+        &self,
+        location: Point<f64>,
+        observations: Vec<(ReferencePoint, f64, Observation)>,
+    ) -> ConfidenceReport {
+        self.score(location, observations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reference(name: &str) -> ReferencePoint {
+        ReferencePoint {
+            name: name.to_string(),
+            location: Point::new(8.6821, 50.1109),
+            ip: "80.81.192.3".parse().unwrap(),
+        }
+    }
+
+    fn measured(ratio: f64) -> Observation {
+        Observation::Measured {
+            theoretical_min_ms: 10.0,
+            measured_ms: 10.0 * ratio,
+        }
+    }
+
+    #[test]
+    fn test_latency_score() {
+        let config = ConfidenceConfig::default();
+        assert_eq!(config.latency_score(1.5), 1.0);
+        assert_eq!(config.latency_score(3.0), 0.5);
+        assert_eq!(config.latency_score(5.0), 0.0);
+    }
+
+    #[test]
+    fn test_contributions_sum_to_confidence() {
+        let mut config = ConfidenceConfig::default();
+        config.weights.insert("b".into(), 3.0);
+        let report = config.score(
+            Point::new(4.9, 52.4),
+            vec![
+                (reference("a"), 1.0, measured(1.0)),
+                (reference("b"), 1.0, Observation::Unreachable { error: "timeout".into() }),
+            ],
+        );
+        assert!((report.confidence - 0.25).abs() < 1e-9);
+        assert!(!report.passed);
+        assert_eq!(report.checks[1].weight, 3.0);
+        assert_eq!(report.checks[1].inputs.error.as_deref(), Some("timeout"));
+        let sum: f64 = report.checks.iter().map(|c| c.contribution).sum();
+        assert!((sum - report.confidence).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_health_weight_scales_check() {
+        let config = ConfidenceConfig::default();
+        let report = config.score(
+            Point::new(4.9, 52.4),
+            vec![
+                (reference("a"), 1.0, measured(1.0)),
+                (reference("b"), 0.5, measured(10.0)),
+            ],
+        );
+        assert!((report.confidence - 2.0 / 3.0).abs() < 1e-9);
+        assert!(report.passed);
+        assert!(report.to_json().contains("\"latency:b\""));
+    }
+
+    #[test]
+    fn test_no_weight_fails() {
+        let report = ConfidenceConfig::default().score(Point::new(4.9, 52.4), vec![]);
+        assert!(!report.passed);
+    }
+}
@@ -0,0 +1,63 @@
+use super::probe::{LatencyStats, Probe, ProbeConfig, ProbeError};
+use futures::future::BoxFuture;
+use rand::random;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use surge_ping::{Client, Config as PingConfig, PingIdentifier, PingSequence, ICMP};
+use tracing::debug;
+
+/// Measures ICMP echo round trips, which needs permission to open ICMP
+/// sockets
+pub struct IcmpProbe {
+    config: ProbeConfig,
+    v4: Client,
+    v6: Client,
+}
+
+impl IcmpProbe {
+    pub fn new(config: ProbeConfig) -> Result<Self, ProbeError> {
+        let client = |kind| {
+            Client::new(&PingConfig::builder().kind(kind).build())
+                .map_err(|e| ProbeError::Unavailable(e.to_string()))
+        };
+        Ok(Self {
+            v4: client(ICMP::V4)?,
+            v6: client(ICMP::V6)?,
+            config,
+        })
+    }
+}
+
+impl Probe for IcmpProbe {
+    fn name(&self) -> &'static str {
+        "icmp"
+    }
+
+    fn measure(&self, target: IpAddr) -> BoxFuture<'_, Result<LatencyStats, ProbeError>> {
+        Box::pin(async move {
+            let client = if target.is_ipv4() { &self.v4 } else { &self.v6 };
+            let mut pinger = client.pinger(target, PingIdentifier(random::<u16>())).await;
+            let payload = [0; 32];
+            let timeout = Duration::from_millis(self.config.timeout_ms);
+
+            let mut samples = Vec::with_capacity(self.config.sample_count);
+            let mut failures = 0;
+            for sequence in 0..self.config.sample_count {
+                let start = Instant::now();
+                match tokio::time::timeout(timeout, pinger.ping(PingSequence(sequence as u16), &payload)).await {
+                    Ok(Ok(_)) => samples.push(start.elapsed().as_secs_f64() * 1000.0),
+                    Ok(Err(e)) => {
+                        debug!(%target, error = %e, "Ping failed");
+                        failures += 1;
+                    }
+                    Err(_) => {
+                        debug!(%target, "Ping timed out");
+                        failures += 1;
+                    }
+                }
+                tokio::time::sleep(Duration::from_millis(self.config.spacing_ms)).await;
+            }
+            LatencyStats::collect(samples, failures)
+        })
+    }
+}
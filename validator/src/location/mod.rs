@@ -0,0 +1,30 @@
+//! Location validation.
+//!
+//! A validator's claimed location is checked by measuring round trip times
+//! to well-known [`ReferencePoint`]s and comparing them with the minimum
+//! physics allows for the distance. How latency is measured is a [`Probe`]
+//! and how the measurements are judged is an [`Analyzer`], so both can be
+//! swapped without touching the [`ProofGenerator`](crate::validation::proof_generator::ProofGenerator)
+//! or the `diagnose location` command, which share everything here.
+//!
+//! ICMP probing needs the `icmp` feature, on by default. Without it, or
+//! where raw sockets are not permitted, latency is measured over TCP.
+
+mod analyzer;
+pub use analyzer::{distance_km, theoretical_minimum_ms, Analyzer};
+
+mod confidence;
+pub use confidence::{CheckInputs, CheckResult, ConfidenceConfig, ConfidenceReport, Observation};
+
+#[cfg(feature = "icmp")]
+mod icmp;
+#[cfg(feature = "icmp")]
+pub use icmp::IcmpProbe;
+
+mod probe;
+pub use probe::{default_probe, LatencyStats, Probe, ProbeConfig, ProbeError, TcpProbe};
+
+mod reference;
+pub use reference::{HealthChecker, HealthConfig, ReferenceHealth, ReferencePoint, ReferenceStatus};
+
+pub use crate::types::{LocationError, ValidatorLocation};
@@ -0,0 +1,180 @@
+use futures::future::BoxFuture;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::net::TcpStream;
+use tracing::debug;
+
+#[derive(Error, Debug)]
+pub enum ProbeError {
+    #[error("Probe unavailable: {0}")]
+    Unavailable(String),
+
+    #[error("Too many failed measurements: {failures} out of {samples}")]
+    TooManyFailures { failures: usize, samples: usize },
+}
+
+/// Configuration for latency measurements
+#[derive(Debug, Clone)]
+pub struct ProbeConfig {
+    pub sample_count: usize,
+    pub timeout_ms: u64,
+    /// Pause between samples
+    pub spacing_ms: u64,
+    /// Port TCP probes connect to
+    pub tcp_port: u16,
+}
+
+impl Default for ProbeConfig {
+    fn default() -> Self {
+        Self {
+            sample_count: 10,
+            timeout_ms: 2000,
+            spacing_ms: 100,
+            tcp_port: 443,
+        }
+    }
+}
+
+/// Round trip times collected by one measurement
+#[derive(Debug, Clone)]
+pub struct LatencyStats {
+    /// Successful samples in milliseconds, sorted ascending
+    pub samples: Vec<f64>,
+    pub failures: usize,
+}
+
+impl LatencyStats {
+    /// Builds stats from raw samples, failing if fewer than half succeeded
+    pub fn collect(mut samples: Vec<f64>, failures: usize) -> Result<Self, ProbeError> {
+        let total = samples.len() + failures;
+        if samples.is_empty() || failures > total / 2 {
+            return Err(ProbeError::TooManyFailures {
+                failures,
+                samples: total,
+            });
+        }
+        samples.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        Ok(Self { samples, failures })
+    }
+
+    pub fn min(&self) -> f64 {
+        self.samples.first().copied().unwrap_or_default()
+    }
+
+    pub fn max(&self) -> f64 {
+        self.samples.last().copied().unwrap_or_default()
+    }
+
+    /// Median latency (more robust than mean)
+    pub fn median(&self) -> f64 {
+        self.samples.get(self.samples.len() / 2).copied().unwrap_or_default()
+    }
+
+    /// Standard deviation of the samples
+    pub fn jitter(&self) -> f64 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        let mean = self.samples.iter().sum::<f64>() / self.samples.len() as f64;
+        let variance = self.samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / self.samples.len() as f64;
+        variance.sqrt()
+    }
+}
+
+/// A way of measuring round trip time to a host
+pub trait Probe: Send + Sync {
+    /// Short name shown in diagnostics
+    fn name(&self) -> &'static str;
+
+    fn measure(&self, target: IpAddr) -> BoxFuture<'_, Result<LatencyStats, ProbeError>>;
+}
+
+/// Measures the time to set up a TCP connection, one round trip. A refused
+/// connection is answered by the host's kernel just as fast, so it counts as
+/// a sample too. Needs no privileges.
+pub struct TcpProbe {
+    config: ProbeConfig,
+}
+
+impl TcpProbe {
+    pub fn new(config: ProbeConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Probe for TcpProbe {
+    fn name(&self) -> &'static str {
+        "tcp"
+    }
+
+    fn measure(&self, target: IpAddr) -> BoxFuture<'_, Result<LatencyStats, ProbeError>> {
+        Box::pin(async move {
+            let address = SocketAddr::new(target, self.config.tcp_port);
+            let timeout = Duration::from_millis(self.config.timeout_ms);
+            let mut samples = Vec::with_capacity(self.config.sample_count);
+            let mut failures = 0;
+            for _ in 0..self.config.sample_count {
+                let start = Instant::now();
+                match tokio::time::timeout(timeout, TcpStream::connect(address)).await {
+                    Ok(Ok(_)) => samples.push(start.elapsed().as_secs_f64() * 1000.0),
+                    Ok(Err(e)) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
+                        samples.push(start.elapsed().as_secs_f64() * 1000.0)
+                    }
+                    Ok(Err(e)) => {
+                        debug!(%address, error = %e, "TCP probe failed");
+                        failures += 1;
+                    }
+                    Err(_) => {
+                        debug!(%address, "TCP probe timed out");
+                        failures += 1;
+                    }
+                }
+                tokio::time::sleep(Duration::from_millis(self.config.spacing_ms)).await;
+            }
+            LatencyStats::collect(samples, failures)
+        })
+    }
+}
+
+/// The most accurate probe this build supports: ICMP echo with the `icmp`
+/// feature, falling back to TCP where raw sockets are not permitted
+pub fn default_probe(config: ProbeConfig) -> Arc<dyn Probe> {
+    #[cfg(feature = "icmp")]
+    {
+        match super::icmp::IcmpProbe::new(config.clone()) {
+            Ok(probe) => return Arc::new(probe),
+            Err(e) => tracing::warn!(error = %e, "ICMP unavailable, measuring latency over TCP"),
+        }
+    }
+    Arc::new(TcpProbe::new(config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let stats = LatencyStats::collect(vec![30.0, 10.0, 20.0], 1).unwrap();
+        assert_eq!(stats.min(), 10.0);
+        assert_eq!(stats.median(), 20.0);
+        assert_eq!(stats.max(), 30.0);
+        assert!(LatencyStats::collect(vec![10.0], 2).is_err());
+        assert!(LatencyStats::collect(vec![], 0).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tcp_probe_counts_refused_connections() {
+        // Nothing listens on the port, so every connection is refused
+        let probe = TcpProbe::new(ProbeConfig {
+            sample_count: 3,
+            spacing_ms: 0,
+            tcp_port: 1,
+            ..ProbeConfig::default()
+        });
+        let stats = probe.measure("127.0.0.1".parse().unwrap()).await.unwrap();
+        assert_eq!(stats.samples.len(), 3);
+    }
+}
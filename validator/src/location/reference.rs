@@ -0,0 +1,285 @@
+use geo::Point;
+use std::collections::VecDeque;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use super::probe::Probe;
+
+/// A well-known location, typically an internet exchange, whose latency a
+/// validator's claimed location is checked against
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReferencePoint {
+    pub name: String,
+    pub location: Point<f64>,
+    pub ip: IpAddr,
+}
+
+/// Thresholds reference point health is judged by
+#[derive(Debug, Clone)]
+pub struct HealthConfig {
+    /// Most recent probes health is computed over
+    pub window: usize,
+    /// Probes needed before a reference point is judged at all
+    pub min_observations: usize,
+    /// Share of probes that must succeed for a point to be used
+    pub min_reachability: f64,
+    /// Coefficient of variation of latency at which a point's weight is halved
+    pub max_jitter: f64,
+    /// Changes between reachable and unreachable within the window after
+    /// which a point is excluded as flapping
+    pub max_flaps: usize,
+    /// Time between health probes
+    pub interval: Duration,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            window: 20,
+            min_observations: 5,
+            min_reachability: 0.8,
+            max_jitter: 0.25,
+            max_flaps: 4,
+            interval: Duration::from_secs(60),
+        }
+    }
+}
+
+/// How much a reference point is trusted
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReferenceStatus {
+    /// Not yet probed often enough to judge, used at full weight
+    Unknown,
+    Healthy,
+    /// Reachable but with unstable latency, used at reduced weight
+    Degraded(f64),
+    /// Unreachable or flapping, not used
+    Excluded,
+}
+
+impl ReferenceStatus {
+    pub fn weight(&self) -> f64 {
+        match self {
+            Self::Unknown | Self::Healthy => 1.0,
+            Self::Degraded(weight) => *weight,
+            Self::Excluded => 0.0,
+        }
+    }
+}
+
+/// Outcomes of the most recent probes of one reference point, `None` for an
+/// unreachable probe
+#[derive(Debug, Clone, Default)]
+struct Observations {
+    probes: VecDeque<Option<f64>>,
+}
+
+impl Observations {
+    fn record(&mut self, window: usize, latency_ms: Option<f64>) {
+        if self.probes.len() == window {
+            self.probes.pop_front();
+        }
+        self.probes.push_back(latency_ms);
+    }
+
+    fn reachability(&self) -> f64 {
+        let reached = self.probes.iter().filter(|probe| probe.is_some()).count();
+        reached as f64 / self.probes.len() as f64
+    }
+
+    /// Standard deviation of successful probes relative to their mean
+    fn jitter(&self) -> f64 {
+        let latencies: Vec<f64> = self.probes.iter().flatten().copied().collect();
+        if latencies.len() < 2 {
+            return 0.0;
+        }
+        let mean = latencies.iter().sum::<f64>() / latencies.len() as f64;
+        if mean <= 0.0 {
+            return 0.0;
+        }
+        let variance = latencies.iter().map(|l| (l - mean).powi(2)).sum::<f64>() / latencies.len() as f64;
+        variance.sqrt() / mean
+    }
+
+    fn flaps(&self) -> usize {
+        self.probes
+            .iter()
+            .zip(self.probes.iter().skip(1))
+            .filter(|(a, b)| a.is_some() != b.is_some())
+            .count()
+    }
+
+    fn status(&self, config: &HealthConfig) -> ReferenceStatus {
+        if self.probes.len() < config.min_observations {
+            return ReferenceStatus::Unknown;
+        }
+        if self.flaps() >= config.max_flaps || self.reachability() < config.min_reachability {
+            return ReferenceStatus::Excluded;
+        }
+        let jitter = self.jitter();
+        if jitter == 0.0 {
+            return ReferenceStatus::Healthy;
+        }
+        // Down-weight linearly to half at the jitter limit and no further,
+        // so a noisy but reachable point still counts for something
+        let weight = 1.0 - 0.5 * (jitter / config.max_jitter).min(1.0);
+        ReferenceStatus::Degraded(weight * self.reachability())
+    }
+}
+
+/// Health of every configured reference point, shared between the
+/// [`HealthChecker`] that probes them and location validation
+#[derive(Debug)]
+pub struct ReferenceHealth {
+    config: HealthConfig,
+    points: Vec<(ReferencePoint, Observations)>,
+}
+
+impl ReferenceHealth {
+    pub fn new(config: HealthConfig, points: Vec<ReferencePoint>) -> Self {
+        Self {
+            config,
+            points: points.into_iter().map(|point| (point, Observations::default())).collect(),
+        }
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = &ReferencePoint> {
+        self.points.iter().map(|(point, _)| point)
+    }
+
+    /// Records a probe of the point named `name`
+    pub fn record(&mut self, name: &str, latency_ms: Option<f64>) {
+        let window = self.config.window;
+        if let Some((_, observations)) = self.points.iter_mut().find(|(point, _)| point.name == name) {
+            observations.record(window, latency_ms);
+        }
+    }
+
+    /// Every point with its current status
+    pub fn statuses(&self) -> Vec<(ReferencePoint, ReferenceStatus)> {
+        self.points
+            .iter()
+            .map(|(point, observations)| (point.clone(), observations.status(&self.config)))
+            .collect()
+    }
+
+    /// Points usable for validation, with their weights
+    pub fn usable(&self) -> Vec<(ReferencePoint, f64)> {
+        self.statuses()
+            .into_iter()
+            .map(|(point, status)| (point, status.weight()))
+            .filter(|(_, weight)| *weight > 0.0)
+            .collect()
+    }
+}
+
+/// Probes every reference point at the configured interval
+pub struct HealthChecker {
+    health: Arc<Mutex<ReferenceHealth>>,
+    probe: Arc<dyn Probe>,
+    interval: Duration,
+}
+
+impl HealthChecker {
+    pub fn new(health: Arc<Mutex<ReferenceHealth>>, probe: Arc<dyn Probe>) -> Self {
+        let interval = health.lock().unwrap().config.interval;
+        Self {
+            health,
+            probe,
+            interval,
+        }
+    }
+
+    /// Probes every point once and records the outcome
+    pub async fn check(&self) {
+        let points: Vec<ReferencePoint> = self.health.lock().unwrap().points().cloned().collect();
+        for point in points {
+            let latency = match self.probe.measure(point.ip).await.map(|stats| stats.median()) {
+                Ok(latency) => {
+                    debug!(reference = %point.name, latency_ms = latency, "Probed reference point");
+                    Some(latency)
+                }
+                Err(e) => {
+                    warn!(reference = %point.name, error = %e, "Reference point unreachable");
+                    None
+                }
+            };
+            let mut health = self.health.lock().unwrap();
+            let before = health.statuses().into_iter().find(|(p, _)| p.name == point.name).map(|(_, s)| s);
+            health.record(&point.name, latency);
+            let after = health.statuses().into_iter().find(|(p, _)| p.name == point.name).map(|(_, s)| s);
+            if before != Some(ReferenceStatus::Excluded) && after == Some(ReferenceStatus::Excluded) {
+                warn!(reference = %point.name, "Excluding unhealthy reference point");
+            }
+        }
+    }
+
+    /// Probes every point until the process exits
+    pub async fn run(self) {
+        loop {
+            self.check().await;
+            tokio::time::sleep(self.interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn health() -> ReferenceHealth {
+        let point = |name: &str| ReferencePoint {
+            name: name.to_string(),
+            location: Point::new(8.6821, 50.1109),
+            ip: "80.81.192.3".parse().unwrap(),
+        };
+        ReferenceHealth::new(HealthConfig::default(), vec![point("steady"), point("other")])
+    }
+
+    fn status(health: &ReferenceHealth, name: &str) -> ReferenceStatus {
+        health.statuses().into_iter().find(|(p, _)| p.name == name).unwrap().1
+    }
+
+    #[test]
+    fn test_unknown_until_enough_probes() {
+        let mut health = health();
+        health.record("steady", None);
+        assert_eq!(status(&health, "steady"), ReferenceStatus::Unknown);
+        assert_eq!(health.usable().len(), 2);
+    }
+
+    #[test]
+    fn test_steady_point_healthy() {
+        let mut health = health();
+        for _ in 0..10 {
+            health.record("steady", Some(12.0));
+        }
+        assert_eq!(status(&health, "steady"), ReferenceStatus::Healthy);
+    }
+
+    #[test]
+    fn test_flapping_point_excluded() {
+        let mut health = health();
+        for i in 0..10 {
+            health.record("other", if i % 2 == 0 { Some(12.0) } else { None });
+        }
+        assert_eq!(status(&health, "other"), ReferenceStatus::Excluded);
+        let usable = health.usable();
+        assert_eq!(usable.len(), 1);
+        assert_eq!(usable[0].0.name, "steady");
+    }
+
+    #[test]
+    fn test_jittery_point_down_weighted() {
+        let mut health = health();
+        for i in 0..10 {
+            health.record("steady", Some(if i % 2 == 0 { 10.0 } else { 30.0 }));
+        }
+        match status(&health, "steady") {
+            ReferenceStatus::Degraded(weight) => assert!((weight - 0.5).abs() < 1e-9),
+            other => panic!("unexpected status {:?}", other),
+        }
+    }
+}
@@ -1,12 +1,24 @@
-/* mod application;
+mod application;
+mod attestation;
+mod discovery;
+mod explorer;
 mod gui;
+mod handshake;
+mod latency;
+mod location;
+mod metrics;
 mod node;
+mod rewards;
+mod slashing;
+mod snapshot;
+mod supply;
+mod utils;
 mod validation;
 mod types;
 
 
 use commonware_consensus::simplex::{self, Engine, Prover};
-use commonware_cryptography::{Ed25519, Scheme, Sha256};
+use commonware_cryptography::{Scheme, Sha256};
 use commonware_p2p::authenticated::{self, Network};
 use commonware_runtime::{
     tokio::{self, Executor},
@@ -16,75 +28,186 @@ use commonware_storage::journal::{self, Journal};
 use commonware_utils::{hex, union};
 use governor::Quota;
 use node::cmd::cli;
+use node::divergence::{DivergenceDetector, HaltSwitch};
+use node::signer::ConsensusKey;
+use node::watermark::{GuardedSigner, WatermarkStore};
 use prometheus_client::registry::Registry;
-use common::utils::hardware_validator::HardwareDetector;
+use romer_common::light::CONSENSUS_NAMESPACE;
+use romer_common::types::address::Address;
+use romer_common::types::keymanager::SignatureScheme;
+use romer_common::types::protocol::SUPPORTED_PROTOCOL_VERSION;
 use std::sync::{Arc, Mutex};
 use std::{
     net::{IpAddr, Ipv4Addr, SocketAddr},
     num::NonZeroU32,
 };
-use std::{str::FromStr, time::Duration};
+use std::time::{Duration, Instant};
 
 /// Unique namespace to avoid message replay attacks.
 const APPLICATION_NAMESPACE: &[u8] = b"ROMER";
 
+/// Tokens minted to validators at the end of each reward epoch.
+const EPOCH_REWARD: u64 = 1_000_000;
+
+/// How often validators gossip their peer records.
+const PEER_EXCHANGE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often validators resend their hello to peers that have not answered.
+const HANDSHAKE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Port offset (from the p2p port) used to serve the explorer API.
+const EXPLORER_PORT_OFFSET: u16 = 2000;
+
+/// How often validators ping each other, and how many rounds of pings each
+/// signed latency report covers.
+const LATENCY_PROBE_INTERVAL: Duration = Duration::from_secs(15);
+const LATENCY_REPORT_ROUNDS: u64 = 20;
+
+/// How long consensus waits for a peer to answer a fetch before asking another.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(1);
+
 fn main() {
-    let app_config = cli::setup_clap_command();
+    let app_config = match cli::setup_clap_command() {
+        Ok(Some(app_config)) => app_config,
+        Ok(None) => return,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
 
     // Create GUI
-    let gui = gui::Gui::new();
-    
-    // TODO: Replace this with getting the Signer from NodeKeyManager
-    let signer = Ed25519::from_seed(app_config.me.0.parse::<u64>().expect("Invalid node ID"));
+    let gui = gui::Gui::new(&app_config.logging);
+
+    // Identity keys come from the validator's keystore. Consensus votes are
+    // checked against the last ones signed so a restart from restored state
+    // can't double-sign.
+    let namespace = CONSENSUS_NAMESPACE.to_vec();
+    let consensus_key = app_config.identity.consensus;
+    let watermarks = WatermarkStore::open(&app_config.watermarks, &consensus_key.public_key())
+        .expect("Failed to open signing watermarks");
+    // Voting stops for good once this validator's state diverges from the
+    // notarized one, until an operator removes the halt file
+    let halt = HaltSwitch::open(&app_config.watermarks).expect("Failed to open halt file");
+    if let Some(divergence) = halt.reason() {
+        tracing::error!(
+            height = divergence.height,
+            path = %app_config.watermarks.join(node::divergence::HALT_FILE).display(),
+            "Halted after a state divergence, not voting"
+        );
+    }
+    let signer = GuardedSigner::new(consensus_key, watermarks, &namespace).with_halt(halt.clone());
     tracing::info!(key = hex(&signer.public_key()), "loaded signer");
+    tracing::info!(key = hex(&app_config.identity.bls.public_key()), "loaded BLS identity");
 
-    // Configure my port
-    let port = app_config.me.1.port();
-    tracing::info!(port, "loaded port");
+    // Configure my address
+    let listen = app_config.listen;
+    tracing::info!(%listen, "loaded address");
 
     // Configure allowed peers
-    let mut validators = Vec::new();
-    for peer in app_config.participants {
-        let verifier = Ed25519::from_seed(peer).public_key();
-        tracing::info!(key = hex(&verifier), "registered authorized key",);
-        validators.push(verifier);
+    let validators = app_config.participants;
+    for verifier in &validators {
+        tracing::info!(key = hex(verifier), "registered authorized key",);
     }
 
-    // Configure bootstrappers (if provided)
-    let mut bootstrapper_identities = Vec::new();
-    for bootstrapper in app_config.bootstrappers {
-        let parts = bootstrapper.split('@').collect::<Vec<&str>>();
-        let bootstrapper_key = parts[0]
-            .parse::<u64>()
-            .expect("Bootstrapper key not well-formed");
-        let verifier = Ed25519::from_seed(bootstrapper_key).public_key();
-        let bootstrapper_address =
-            SocketAddr::from_str(parts[1]).expect("Bootstrapper address not well-formed");
-        bootstrapper_identities.push((verifier, bootstrapper_address));
+    // Warn if the protocol schedule holds a version this binary cannot run,
+    // so it is upgraded before that version activates
+    if let Some(activation) = app_config
+        .protocol
+        .activations
+        .iter()
+        .find(|activation| activation.version > SUPPORTED_PROTOCOL_VERSION)
+    {
+        tracing::warn!(
+            version = activation.version,
+            height = activation.height,
+            supported = SUPPORTED_PROTOCOL_VERSION,
+            "Protocol schedule activates a version this binary does not support"
+        );
     }
 
-    // Configure storage directory
+    // Configure bootstrappers (if provided)
+    let bootstrapper_identities = app_config.bootstrappers;
+
+    // Configure storage directory, restoring the consensus journal from a
+    // snapshot if asked to. A snapshot that fails verification stops startup.
     let storage_directory = app_config.storage_dir;
+    node::crash::install(&storage_directory);
+    if let Some(path) = &app_config.from_snapshot {
+        if let Err(e) = snapshot::restore(path, &storage_directory) {
+            eprintln!("Failed to restore snapshot: {}", e);
+            std::process::exit(1);
+        }
+    }
+    let divergence = DivergenceDetector::new(
+        storage_directory.clone(),
+        &[
+            snapshot::CONSENSUS_PARTITION,
+            application::block::BLOCKS_PARTITION,
+            slashing::EVIDENCE_PARTITION,
+        ],
+        halt,
+    );
+    let snapshotter = snapshot::Snapshotter::new(
+        storage_directory.clone(),
+        snapshot::CONSENSUS_PARTITION,
+        app_config.snapshots,
+    );
 
     // Initialize runtime
     let runtime_cfg = tokio::Config {
-        storage_directory: storage_directory.into(),
+        storage_directory,
         ..Default::default()
     };
     let (executor, runtime) = Executor::init(runtime_cfg.clone());
 
+    // Every component shares a single registry so metrics can be scraped together
+    let registry = Arc::new(Mutex::new(Registry::default()));
+    let consensus_metrics = metrics::ConsensusMetrics::new(&registry);
+
     // Configure network
-    let p2p_cfg = authenticated::Config::aggressive(
+    let mut p2p_cfg = authenticated::Config::aggressive(
         signer.clone(),
         &union(APPLICATION_NAMESPACE, b"_P2P"),
-        Arc::new(Mutex::new(Registry::default())),
-        SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port),
+        registry.clone(),
+        listen,
         bootstrapper_identities.clone(),
         1024 * 1024, // 1MB
     );
+    // Only track the latest peer set, so a peer refused by the handshake is
+    // disconnected as soon as the set without it is registered
+    p2p_cfg.tracked_peer_sets = 1;
 
     // Start runtime
     executor.start(async move {
+        // Dial the peers learned before the last restart as well as the
+        // configured bootstrappers
+        let peers_journal = Journal::init(
+            runtime.clone(),
+            journal::Config {
+                registry: registry.clone(),
+                partition: String::from(discovery::PEERS_PARTITION),
+            },
+        )
+        .await
+        .expect("Failed to initialize peers journal");
+        let mut exchange = discovery::Exchange::new(
+            runtime.clone(),
+            peers_journal,
+            signer.clone(),
+            listen,
+            &validators,
+            PEER_EXCHANGE_INTERVAL,
+        );
+        if let Err(e) = exchange.restore().await {
+            tracing::warn!(error = %e, "Failed to restore peer records");
+        }
+        for (key, address) in exchange.book().bootstrappers() {
+            if !p2p_cfg.bootstrappers.iter().any(|(known, _)| *known == key) {
+                p2p_cfg.bootstrappers.push((key, address));
+            }
+        }
+
         let (mut network, mut oracle) = Network::new(runtime.clone(), p2p_cfg);
 
         // Provide authorized peers
@@ -109,30 +232,192 @@ fn main() {
             256, // 256 messages in flight
             Some(3),
         );
+        // Count the resolver's fetches that time out
+        let (resolver_sender, resolver_receiver) =
+            metrics::FetchMonitor::new(&consensus_metrics, FETCH_TIMEOUT).wrap(resolver_sender, resolver_receiver);
+        let (peers_sender, peers_receiver) = network.register(
+            discovery::PEER_EXCHANGE_CHANNEL,
+            Quota::per_second(NonZeroU32::new(1).unwrap()),
+            16,
+            None,
+        );
+        let (latency_sender, latency_receiver) = network.register(
+            latency::LATENCY_CHANNEL,
+            Quota::per_second(NonZeroU32::new(10).unwrap()),
+            64,
+            None,
+        );
+        let (handshake_sender, handshake_receiver) = network.register(
+            handshake::HANDSHAKE_CHANNEL,
+            Quota::per_second(NonZeroU32::new(1).unwrap()),
+            16,
+            None,
+        );
+        let (blocks_sender, blocks_receiver) = network.register(
+            application::block::BLOCKS_CHANNEL,
+            Quota::per_second(NonZeroU32::new(50).unwrap()),
+            256,
+            Some(3),
+        );
+
+        // Exchange versions and refuse peers on another chain or genesis by
+        // registering a peer set without them
+        let (peer_sets, mut refused) = futures::channel::mpsc::channel(16);
+        let handshake = handshake::Handshake::new(
+            runtime.clone(),
+            signer.public_key(),
+            handshake::Hello::new(handshake::VERSION, &app_config.chain_id, app_config.genesis_hash.clone()),
+            &validators,
+            HANDSHAKE_INTERVAL,
+            handshake::HandshakeMetrics::new(&registry),
+            peer_sets,
+        );
+        runtime.spawn("peer_sets", async move {
+            let mut index = 0;
+            while let Some(peers) = futures::StreamExt::next(&mut refused).await {
+                index += 1;
+                oracle.register(index, peers).await;
+            }
+        });
+
+        // Measure and report latency to the other validators
+        let latency_journal = Journal::init(
+            runtime.clone(),
+            journal::Config {
+                registry: registry.clone(),
+                partition: String::from(latency::LATENCY_PARTITION),
+            },
+        )
+        .await
+        .expect("Failed to initialize latency journal");
+        let (mut prober, latency_query) = latency::Prober::new(
+            runtime.clone(),
+            latency_journal,
+            signer.clone(),
+            app_config.location.clone(),
+            &validators,
+            LATENCY_PROBE_INTERVAL,
+            LATENCY_REPORT_ROUNDS,
+        );
+        if let Err(e) = prober.restore().await {
+            tracing::warn!(error = %e, "Failed to restore latency reports");
+        }
 
         // Initialize storage
+        let replay_start = Instant::now();
         let journal = Journal::init(
             runtime.clone(),
             journal::Config {
-                registry: Arc::new(Mutex::new(Registry::default())),
-                partition: String::from("log"),
+                registry: registry.clone(),
+                partition: String::from(snapshot::CONSENSUS_PARTITION),
             },
         )
         .await
         .expect("Failed to initialize journal");
+        consensus_metrics.record_replay(replay_start.elapsed());
 
         // Initialize application
-        let namespace = union(APPLICATION_NAMESPACE, b"_CONSENSUS");
         let hasher = Sha256::default();
-        let prover: Prover<Ed25519, Sha256> = Prover::new(&namespace);
+        let prover: Prover<GuardedSigner<ConsensusKey>, Sha256> = Prover::new(&namespace);
+
+        // Initialize slashing evidence collection
+        let evidence_journal = Journal::init(
+            runtime.clone(),
+            journal::Config {
+                registry: registry.clone(),
+                partition: String::from(slashing::EVIDENCE_PARTITION),
+            },
+        )
+        .await
+        .expect("Failed to initialize evidence journal");
+        let (collector, evidence, pending_evidence) =
+            slashing::Collector::new(evidence_journal, prover.clone(), 1024);
+        // Penalties and reward epochs are served as finalized blocks leave them
+        let slashing_query = slashing::SlashingQuery::default();
+        let rewards_query = rewards::RewardsQuery::default();
+
+        // Track supply from genesis on, if the genesis file sets tokenomics
+        let genesis = application::block::genesis_block(app_config.tokenomics.as_ref());
+        let (supply, supply_query) = match &app_config.tokenomics {
+            Some(tokenomics) => {
+                let (tracker, query) =
+                    supply::SupplyTracker::new(tokenomics, &registry).expect("Invalid tokenomics");
+                (Some(tracker), query)
+            }
+            None => {
+                tracing::warn!("Genesis sets no tokenomics, supply is not tracked");
+                (None, supply::SupplyQuery::default())
+            }
+        };
+
+        // Finalized blocks are journaled so state is rebuilt on restart
+        let blocks_journal = Journal::init(
+            runtime.clone(),
+            journal::Config {
+                registry: registry.clone(),
+                partition: String::from(application::block::BLOCKS_PARTITION),
+            },
+        )
+        .await
+        .expect("Failed to initialize blocks journal");
+        // Probe the reference points for as long as the node runs, so dead
+        // or flapping anchors are down-weighted in every location validation
+        let mut proof_generator = validation::proof_generator::ProofGenerator::builder()
+            .with_confidence(app_config.confidence.clone());
+        if !app_config.reference_points.is_empty() {
+            proof_generator = proof_generator.with_references(Arc::new(Mutex::new(location::ReferenceHealth::new(
+                location::HealthConfig::default(),
+                app_config.reference_points,
+            ))));
+        }
+        let references = proof_generator.references();
+        runtime.spawn(
+            "reference_health",
+            location::HealthChecker::new(references.clone(), proof_generator.probe()).run(),
+        );
+
+        // Deliver signed location and hardware attestations to the sequencer
+        if let Some(sequencer) = app_config.sequencer_rpc {
+            tracing::info!(
+                address = %Address::from_public_key(SignatureScheme::Ed25519, &signer.public_key()),
+                %sequencer,
+                "attesting to sequencer"
+            );
+            let attester = attestation::Attester::new(
+                runtime.clone(),
+                signer.clone(),
+                sequencer,
+                app_config.location.clone(),
+                proof_generator,
+                runtime_cfg.storage_directory.clone(),
+                app_config.attestation_interval,
+            );
+            runtime.spawn("attestation", attester.run());
+        }
+
         let (application, supervisor, mailbox) = application::Application::new(
             runtime.clone(),
             application::Config {
                 prover,
-                hasher: hasher.clone(),
+                signer: signer.clone(),
                 mailbox_size: 1024,
                 participants: validators.clone(),
+                me: signer.public_key(),
+                metrics: consensus_metrics.clone(),
+                evidence,
+                pending_evidence,
+                slashing: slashing_query.clone(),
+                rewards: rewards_query.clone(),
+                epoch_reward: EPOCH_REWARD,
+                blocks: blocks_journal,
+                genesis,
+                supply,
+                latency: latency_query.clone(),
                 validator_location: Some(app_config.location),
+                references,
+                confidence: app_config.confidence,
+                confidence_report: app_config.confidence_report,
+                divergence,
             },
         );
 
@@ -147,14 +432,14 @@ fn main() {
                 relay: mailbox.clone(),
                 committer: mailbox,
                 supervisor,
-                registry: Arc::new(Mutex::new(Registry::default())),
+                registry: registry.clone(),
                 namespace,
                 mailbox_size: 1024,
                 replay_concurrency: 1,
                 leader_timeout: Duration::from_secs(1),
                 notarization_timeout: Duration::from_secs(2),
                 nullify_retry: Duration::from_secs(10),
-                fetch_timeout: Duration::from_secs(1),
+                fetch_timeout: FETCH_TIMEOUT,
                 activity_timeout: 10,
                 max_fetch_count: 32,
                 max_fetch_size: 1024 * 512,
@@ -164,8 +449,25 @@ fn main() {
         );
 
         // Start consensus
-        runtime.spawn("application", application.run());
+        let metrics_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), listen.port() + metrics::METRICS_PORT_OFFSET);
+        runtime.spawn("metrics", metrics::serve(metrics_addr, registry.clone()));
+        let explorer_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), listen.port() + EXPLORER_PORT_OFFSET);
+        runtime.spawn(
+            "explorer",
+            explorer::serve(explorer_addr, explorer::Queries {
+                    latency: latency_query,
+                    rewards: rewards_query,
+                    supply: supply_query,
+                    slashing: slashing_query,
+                }),
+        );
+        runtime.spawn("handshake", handshake.run(handshake_sender, handshake_receiver));
+        runtime.spawn("latency", prober.run(latency_sender, latency_receiver));
+        runtime.spawn("evidence", collector.run());
+        runtime.spawn("application", application.run(blocks_sender, blocks_receiver));
         runtime.spawn("network", network.run());
+        runtime.spawn("discovery", exchange.run(peers_sender, peers_receiver));
+        runtime.spawn("snapshots", snapshotter.run(runtime.clone()));
         runtime.spawn(
             "engine",
             engine.run(
@@ -178,9 +480,3 @@ fn main() {
         gui.run(runtime).await;
     });
 }
-
-*/
-
-fn main() {
-    println!("Hello World");
-}
\ No newline at end of file
@@ -0,0 +1,318 @@
+//! Prometheus metrics describing this validator's participation in consensus.
+//!
+//! The consensus engine, p2p network and journal each take a shared
+//! `Arc<Mutex<Registry>>`. We register our own counters into that same
+//! registry so everything is exported from a single scrape endpoint. The
+//! engine does not export fetch timeouts, so they are counted by a
+//! [`FetchMonitor`] wrapped around its resolver channel.
+
+use bytes::Bytes;
+use commonware_consensus::simplex::View;
+use commonware_cryptography::PublicKey;
+use commonware_p2p::{Message, Receiver, Recipients, Sender};
+use prometheus_client::metrics::{counter::Counter, gauge::Gauge, histogram::Histogram};
+use prometheus_client::registry::Registry;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Port offset (from the p2p port) used to serve Prometheus metrics.
+pub const METRICS_PORT_OFFSET: u16 = 1000;
+
+/// Buckets (in seconds) used for the journal replay histogram
+const REPLAY_BUCKETS: [f64; 8] = [0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0, 30.0];
+
+/// Views below the latest one voted in that are still remembered, so a view
+/// reported late is counted once. Consensus stops reporting activity for
+/// views far older than this.
+const RETAINED_VIEWS: View = 1024;
+
+/// Per-validator consensus participation metrics
+#[derive(Clone)]
+pub struct ConsensusMetrics {
+    /// Number of distinct views this validator voted in
+    pub views_participated: Counter,
+    /// Highest view this validator has voted in
+    pub latest_view: Gauge,
+    /// Number of blocks proposed while leader
+    pub proposals_made: Counter,
+    /// Number of notarize votes signed
+    pub notarizations_signed: Counter,
+    /// Number of nullify votes signed
+    pub nullifications: Counter,
+    /// Number of finalize votes signed
+    pub finalizations_signed: Counter,
+    /// Number of fetches of missing notarizations and nullifications that
+    /// went unanswered for the fetch timeout
+    pub fetch_timeouts: Counter,
+    /// Time spent replaying the consensus journal on startup
+    pub journal_replay_seconds: Histogram,
+    /// Views voted in, down to `RETAINED_VIEWS` below the latest
+    voted: Arc<Mutex<BTreeSet<View>>>,
+}
+
+impl ConsensusMetrics {
+    /// Creates the metrics and registers them under the `romer_consensus` prefix
+    pub fn new(registry: &Arc<Mutex<Registry>>) -> Self {
+        let metrics = Self {
+            views_participated: Counter::default(),
+            latest_view: Gauge::default(),
+            proposals_made: Counter::default(),
+            notarizations_signed: Counter::default(),
+            nullifications: Counter::default(),
+            finalizations_signed: Counter::default(),
+            fetch_timeouts: Counter::default(),
+            journal_replay_seconds: Histogram::new(REPLAY_BUCKETS.into_iter()),
+            voted: Arc::new(Mutex::new(BTreeSet::new())),
+        };
+
+        let mut registry = registry.lock().unwrap();
+        let registry = registry.sub_registry_with_prefix("romer_consensus");
+        registry.register(
+            "views_participated",
+            "Number of distinct views this validator voted in",
+            metrics.views_participated.clone(),
+        );
+        registry.register(
+            "latest_view",
+            "Highest view this validator has voted in",
+            metrics.latest_view.clone(),
+        );
+        registry.register(
+            "proposals_made",
+            "Number of blocks proposed while leader",
+            metrics.proposals_made.clone(),
+        );
+        registry.register(
+            "notarizations_signed",
+            "Number of notarize votes signed",
+            metrics.notarizations_signed.clone(),
+        );
+        registry.register(
+            "nullifications",
+            "Number of nullify votes signed",
+            metrics.nullifications.clone(),
+        );
+        registry.register(
+            "finalizations_signed",
+            "Number of finalize votes signed",
+            metrics.finalizations_signed.clone(),
+        );
+        registry.register(
+            "fetch_timeouts",
+            "Number of fetches of missing notarizations and nullifications that timed out",
+            metrics.fetch_timeouts.clone(),
+        );
+        registry.register(
+            "journal_replay_seconds",
+            "Time spent replaying the consensus journal on startup",
+            metrics.journal_replay_seconds.clone(),
+        );
+
+        metrics
+    }
+
+    /// Records that we voted in `view`, counting each view only once however
+    /// out of order votes are reported
+    pub fn record_view(&self, view: View) {
+        let mut voted = self.voted.lock().unwrap();
+        let latest = voted.last().copied().unwrap_or_default().max(view);
+        let floor = latest.saturating_sub(RETAINED_VIEWS);
+        if view < floor || !voted.insert(view) {
+            return;
+        }
+        self.views_participated.inc();
+        self.latest_view.set(latest as i64);
+        *voted = voted.split_off(&floor);
+    }
+
+    /// Records how long the journal took to replay
+    pub fn record_replay(&self, elapsed: Duration) {
+        self.journal_replay_seconds.observe(elapsed.as_secs_f64());
+    }
+}
+
+/// Requests outstanding on the resolver channel, each by when it was sent
+#[derive(Debug, Default)]
+struct Fetches {
+    /// Requests we sent to each peer and have no answer to yet
+    sent: HashMap<PublicKey, VecDeque<Instant>>,
+    /// Requests each peer sent us that we have not answered yet
+    received: HashMap<PublicKey, VecDeque<Instant>>,
+}
+
+impl Fetches {
+    /// Drops requests older than `timeout`, returning how many of ours
+    /// went unanswered
+    fn expire(&mut self, now: Instant, timeout: Duration) -> u64 {
+        let mut expired = 0;
+        let mut drain = |requests: &mut HashMap<PublicKey, VecDeque<Instant>>, count: &mut u64| {
+            requests.retain(|_, pending| {
+                while pending.front().is_some_and(|sent| now.duration_since(*sent) >= timeout) {
+                    pending.pop_front();
+                    *count += 1;
+                }
+                !pending.is_empty()
+            });
+        };
+        drain(&mut self.sent, &mut expired);
+        drain(&mut self.received, &mut 0);
+        expired
+    }
+}
+
+/// Counts fetch timeouts of the consensus resolver, which sends a request
+/// to a single peer whenever it misses a notarization or nullification and
+/// answers every request it receives. A message to a peer that is owed no
+/// answer is a request, and the next message from a peer we asked is its
+/// answer. A request unanswered for the fetch timeout is counted in
+/// `fetch_timeouts`.
+#[derive(Clone, Debug)]
+pub struct FetchMonitor {
+    timeout: Duration,
+    fetches: Arc<Mutex<Fetches>>,
+    timeouts: Counter,
+}
+
+impl FetchMonitor {
+    pub fn new(metrics: &ConsensusMetrics, timeout: Duration) -> Self {
+        Self {
+            timeout,
+            fetches: Arc::new(Mutex::new(Fetches::default())),
+            timeouts: metrics.fetch_timeouts.clone(),
+        }
+    }
+
+    /// Wraps the resolver channel handed to the engine
+    pub fn wrap<S: Sender, R: Receiver>(&self, sender: S, receiver: R) -> (MonitoredSender<S>, MonitoredReceiver<R>) {
+        (
+            MonitoredSender {
+                inner: sender,
+                monitor: self.clone(),
+            },
+            MonitoredReceiver {
+                inner: receiver,
+                monitor: self.clone(),
+            },
+        )
+    }
+
+    fn sent(&self, peer: &PublicKey, now: Instant) {
+        let mut fetches = self.fetches.lock().unwrap();
+        self.timeouts.inc_by(fetches.expire(now, self.timeout));
+        let answered = fetches.received.get_mut(peer).and_then(VecDeque::pop_front).is_some();
+        if !answered {
+            fetches.sent.entry(peer.clone()).or_default().push_back(now);
+        }
+    }
+
+    fn received(&self, peer: &PublicKey, now: Instant) {
+        let mut fetches = self.fetches.lock().unwrap();
+        self.timeouts.inc_by(fetches.expire(now, self.timeout));
+        let answer = fetches.sent.get_mut(peer).and_then(VecDeque::pop_front).is_some();
+        if !answer {
+            fetches.received.entry(peer.clone()).or_default().push_back(now);
+        }
+    }
+}
+
+/// Resolver sender that reports requests to its [`FetchMonitor`]
+#[derive(Clone, Debug)]
+pub struct MonitoredSender<S: Sender> {
+    inner: S,
+    monitor: FetchMonitor,
+}
+
+impl<S: Sender> Sender for MonitoredSender<S> {
+    type Error = S::Error;
+
+    async fn send(&mut self, recipients: Recipients, message: Bytes, priority: bool) -> Result<Vec<PublicKey>, S::Error> {
+        if let Recipients::One(peer) = &recipients {
+            self.monitor.sent(peer, Instant::now());
+        }
+        self.inner.send(recipients, message, priority).await
+    }
+}
+
+/// Resolver receiver that reports answers to its [`FetchMonitor`]
+#[derive(Debug)]
+pub struct MonitoredReceiver<R: Receiver> {
+    inner: R,
+    monitor: FetchMonitor,
+}
+
+impl<R: Receiver> Receiver for MonitoredReceiver<R> {
+    type Error = R::Error;
+
+    async fn recv(&mut self) -> Result<Message, R::Error> {
+        let message = self.inner.recv().await?;
+        self.monitor.received(&message.0, Instant::now());
+        Ok(message)
+    }
+}
+
+pub use romer_common::utils::metrics::serve;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prometheus_client::encoding::text::encode;
+
+    #[test]
+    fn test_views_counted_once() {
+        let registry = Arc::new(Mutex::new(Registry::default()));
+        let metrics = ConsensusMetrics::new(&registry);
+
+        metrics.record_view(1);
+        metrics.record_view(1);
+        metrics.record_view(3);
+        metrics.record_view(1);
+        // Reported out of order, still counted once
+        metrics.record_view(2);
+        metrics.record_view(2);
+
+        assert_eq!(metrics.views_participated.get(), 3);
+        assert_eq!(metrics.latest_view.get(), 3);
+
+        // Views too far behind the latest are no longer remembered
+        metrics.record_view(RETAINED_VIEWS + 10);
+        metrics.record_view(5);
+        assert_eq!(metrics.views_participated.get(), 4);
+    }
+
+    #[test]
+    fn test_fetch_timeouts() {
+        let registry = Arc::new(Mutex::new(Registry::default()));
+        let metrics = ConsensusMetrics::new(&registry);
+        let monitor = FetchMonitor::new(&metrics, Duration::from_secs(1));
+        let (peer, other): (PublicKey, PublicKey) = (vec![1u8; 32].into(), vec![2u8; 32].into());
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+
+        // Answered in time
+        monitor.sent(&peer, at(0));
+        monitor.received(&peer, at(500));
+        // A request from a peer, and our answer to it
+        monitor.received(&other, at(600));
+        monitor.sent(&other, at(700));
+        assert_eq!(metrics.fetch_timeouts.get(), 0);
+
+        // Never answered
+        monitor.sent(&peer, at(800));
+        monitor.sent(&other, at(900));
+        monitor.received(&other, at(1_000));
+        monitor.sent(&peer, at(2_000));
+        assert_eq!(metrics.fetch_timeouts.get(), 1);
+    }
+
+    #[test]
+    fn test_metrics_encoded() {
+        let registry = Arc::new(Mutex::new(Registry::default()));
+        let metrics = ConsensusMetrics::new(&registry);
+        metrics.proposals_made.inc();
+
+        let mut body = String::new();
+        encode(&mut body, &registry.lock().unwrap()).unwrap();
+        assert!(body.contains("romer_consensus_proposals_made_total 1"));
+    }
+}
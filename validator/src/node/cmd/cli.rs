@@ -1,109 +1,850 @@
-// cmd.rs
-use clap::{value_parser, Arg, Command};
-use std::net::{IpAddr, SocketAddr};
-use std::str::FromStr;
+// src/node/cmd/cli.rs
+use clap::{value_parser, Arg, ArgMatches, Command};
+use commonware_cryptography::{Digest, Ed25519, PublicKey, Scheme};
+use commonware_utils::{from_hex, hex};
+use romer_common::types::keymanager::KeyManagerError;
+use romer_common::types::protocol::ProtocolSchedule;
+use romer_common::types::tokenomics::TokenomicsConfig;
+use romer_common::utils::logging::LoggingConfig;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
 
-use crate::types::ValidatorLocation;
+use crate::handshake::{genesis_hash, DEFAULT_CHAIN_ID};
+use crate::node::keystore::{read_passphrase, Genesis, NodeIdentity, NodeKeyManager};
+use crate::node::signer::{ConsensusKey, RemoteSigner, RemoteSignerConfig, RemoteSignerError};
+use crate::snapshot::{SnapshotConfig, KEPT_SNAPSHOTS, RETAINED_SECTIONS};
+use crate::types::{LocationError, ValidatorLocation};
+use crate::location::{ConfidenceConfig, ReferencePoint};
 
-pub struct AppConfig {
+use super::{diagnose, support};
+
+#[derive(Error, Debug)]
+pub enum CliError {
+    #[error("Failed to read {path}: {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("Failed to parse configuration: {0}")]
+    Parse(String),
+
+    #[error("Missing {0}: set it in the configuration file or pass --{0}")]
+    Missing(&'static str),
+
+    #[error("Invalid {field} address {value}: {reason}")]
+    InvalidAddress {
+        field: &'static str,
+        value: String,
+        reason: String,
+    },
+
+    #[error("Invalid {field} key {value}")]
+    InvalidKey { field: &'static str, value: String },
+
+    #[error("Invalid configuration: {0}")]
+    Invalid(String),
+
+    #[error(transparent)]
+    Location(#[from] LocationError),
+
+    #[error("Failed to read passphrase: {0}")]
+    Passphrase(std::io::Error),
+
+    #[error("Keystore error: {0}")]
+    Keystore(#[from] KeyManagerError),
+
+    #[error("Remote signer error: {0}")]
+    RemoteSigner(#[from] RemoteSignerError),
+
+    #[error("Diagnostic failed: {0}")]
+    Diagnose(String),
+
+    #[error("Failed to write support bundle {0}")]
+    Bundle(String),
+}
+
+/// Settings as read from the configuration file. Every field may instead be
+/// given on the command line, which takes precedence.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    /// Keystore directory holding this validator's encrypted identity keys,
+    /// generated on first start
+    pub identity: Option<PathBuf>,
+    /// Directory the last signed consensus votes are kept in, `watermarks`
+    /// in the identity directory by default. Keep it out of backups of the
+    /// chain state.
+    pub watermarks: Option<PathBuf>,
+    /// JSON file with the registrations of the genesis validator set, whose
+    /// keys join `participants`
+    pub genesis: Option<PathBuf>,
+    /// Address the p2p network listens on, `ip:port`
+    pub listen: Option<String>,
+    /// Peers dialed on startup, `<public key>@<ip:port>`
     pub bootstrappers: Vec<String>,
-    pub me: (String, SocketAddr),
-    pub participants: Vec<u64>,
-    pub storage_dir: String,
-    pub location: ValidatorLocation,
+    /// Hex encoded public keys of every validator in the participant set
+    pub participants: Vec<String>,
+    /// Chain this validator belongs to. Peers announcing another chain are
+    /// refused. `romer-devnet` by default.
+    pub chain_id: Option<String>,
+    pub storage_dir: Option<PathBuf>,
+    /// Directory consensus journal snapshots are written to, `snapshots` in
+    /// the storage directory by default
+    pub snapshot_dir: Option<PathBuf>,
+    /// Seconds between snapshots, 600 by default
+    pub snapshot_interval_secs: Option<u64>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Sign consensus messages with a remote signer instead of a local key
+    pub remote_signer: Option<RemoteSignerConfig>,
+    /// Reference points the location is validated against, replacing the
+    /// built-in Frankfurt one. Only settable in the configuration file.
+    pub reference_points: Vec<ReferencePointConfig>,
+    /// Threshold, latency ratio and per reference point weights of the
+    /// location confidence model
+    pub confidence: ConfidenceConfig,
+    /// File the JSON report of every location check is written to
+    pub confidence_report: Option<PathBuf>,
+    /// JSON-RPC address of the sequencer location and hardware attestations
+    /// are delivered to, `host:port`. Nothing is attested unless set.
+    pub sequencer_rpc: Option<String>,
+    /// Seconds between attestations, 600 by default
+    pub attestation_interval_secs: Option<u64>,
+    /// Level, format and log file with its rotation. The terminal UI always
+    /// shows the log at this level.
+    pub logging: LoggingConfig,
+    /// Heights protocol upgrades activate at, the same on every validator.
+    /// Only settable in the configuration file.
+    pub protocol: ProtocolSchedule,
+}
+
+/// A `[[reference_points]]` entry of the configuration file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReferencePointConfig {
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Address probed for latency
+    pub ip: String,
 }
 
-fn parse_me(value: &str) -> Result<(String, SocketAddr), String> {
-    let mut parts = value.split('@');
-    let node_id = parts.next().ok_or("Invalid format for 'me' argument")?;
+impl ConfigFile {
+    pub fn load(path: &Path) -> Result<Self, CliError> {
+        let raw = std::fs::read_to_string(path).map_err(|source| CliError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        toml::from_str(&raw).map_err(|e| CliError::Parse(e.to_string()))
+    }
+
+    /// Replaces settings with those given on the command line
+    fn overlay(&mut self, matches: &ArgMatches) {
+        if let Some(identity) = matches.get_one::<PathBuf>("identity") {
+            self.identity = Some(identity.clone());
+        }
+        if let Some(watermarks) = matches.get_one::<PathBuf>("watermarks") {
+            self.watermarks = Some(watermarks.clone());
+        }
+        if let Some(genesis) = matches.get_one::<PathBuf>("genesis") {
+            self.genesis = Some(genesis.clone());
+        }
+        if let Some(listen) = matches.get_one::<String>("listen") {
+            self.listen = Some(listen.clone());
+        }
+        if let Some(bootstrappers) = matches.get_many::<String>("bootstrappers") {
+            self.bootstrappers = bootstrappers.cloned().collect();
+        }
+        if let Some(participants) = matches.get_many::<String>("participants") {
+            self.participants = participants.cloned().collect();
+        }
+        if let Some(storage_dir) = matches.get_one::<PathBuf>("storage-dir") {
+            self.storage_dir = Some(storage_dir.clone());
+        }
+        if let Some(snapshot_dir) = matches.get_one::<PathBuf>("snapshot-dir") {
+            self.snapshot_dir = Some(snapshot_dir.clone());
+        }
+        if let Some(interval) = matches.get_one::<u64>("snapshot-interval-secs") {
+            self.snapshot_interval_secs = Some(*interval);
+        }
+        if let Some(latitude) = matches.get_one::<f64>("latitude") {
+            self.latitude = Some(*latitude);
+        }
+        if let Some(longitude) = matches.get_one::<f64>("longitude") {
+            self.longitude = Some(*longitude);
+        }
+        if let Some(confidence_report) = matches.get_one::<PathBuf>("confidence-report") {
+            self.confidence_report = Some(confidence_report.clone());
+        }
+        if let Some(chain_id) = matches.get_one::<String>("chain-id") {
+            self.chain_id = Some(chain_id.clone());
+        }
+        if let Some(sequencer_rpc) = matches.get_one::<String>("sequencer-rpc") {
+            self.sequencer_rpc = Some(sequencer_rpc.clone());
+        }
+        if let Some(interval) = matches.get_one::<u64>("attestation-interval-secs") {
+            self.attestation_interval_secs = Some(*interval);
+        }
+        if let Some(level) = matches.get_one::<String>("log-level") {
+            self.logging.level = level.clone();
+        }
+        if let Some(log_file) = matches.get_one::<PathBuf>("log-file") {
+            self.logging.file = Some(log_file.clone());
+        }
+        if matches.get_flag("local-signer") {
+            self.remote_signer = None;
+        }
+    }
+
+    /// Opens the keystore, with the consensus key from the remote signer if
+    /// one is configured
+    fn identity(&self, passphrase: &str) -> Result<NodeIdentity, CliError> {
+        let keystore = self.identity.as_ref().ok_or(CliError::Missing("identity"))?;
+        let keys = NodeKeyManager::open(keystore)?;
+        Ok(match &self.remote_signer {
+            Some(config) => {
+                let signer = RemoteSigner::connect(config.clone())?;
+                keys.load_with(ConsensusKey::Remote(signer), passphrase)?
+            }
+            None => keys.load(passphrase)?,
+        })
+    }
+
+    /// Checks and converts the configured reference points
+    pub fn reference_points(&self) -> Result<Vec<ReferencePoint>, CliError> {
+        let mut reference_points = Vec::with_capacity(self.reference_points.len());
+        for reference in &self.reference_points {
+            let ip = reference.ip.parse().map_err(|_| CliError::InvalidAddress {
+                field: "reference point",
+                value: reference.ip.clone(),
+                reason: "expected an ip address".into(),
+            })?;
+            if reference_points.iter().any(|point: &ReferencePoint| point.name == reference.name) {
+                return Err(CliError::Invalid(format!("reference point {} listed twice", reference.name)));
+            }
+            reference_points.push(ReferencePoint {
+                name: reference.name.clone(),
+                location: ValidatorLocation::new(reference.latitude, reference.longitude)?.to_point(),
+                ip,
+            });
+        }
+        Ok(reference_points)
+    }
+
+    /// Unlocks the identity with `passphrase` and checks every address and
+    /// key, so a node with a bad configuration fails before it starts the
+    /// network
+    pub fn resolve(self, passphrase: &str) -> Result<AppConfig, CliError> {
+        let identity = self.identity(passphrase)?;
+        let signer = identity.consensus.clone();
+        let watermarks = match self.watermarks {
+            Some(watermarks) => watermarks,
+            None => self.identity.as_ref().ok_or(CliError::Missing("identity"))?.join("watermarks"),
+        };
 
-    let port_and_addr = parts.next().ok_or("Invalid format for 'me' argument")?;
-    let mut port_and_addr_parts = port_and_addr.split(':');
-    let ip_addr_str = port_and_addr_parts
-        .next()
-        .ok_or("Invalid format for 'me' argument")?;
-    let port_str = port_and_addr_parts
-        .next()
-        .ok_or("Invalid format for 'me' argument")?;
+        let listen = parse_address("listen", &self.listen.ok_or(CliError::Missing("listen"))?)?;
 
-    let ip_addr: IpAddr = ip_addr_str.parse().map_err(|_| "Invalid IP address")?;
-    let port: u16 = port_str.parse().map_err(|_| "Invalid port number")?;
+        let mut registered = Vec::new();
+        let mut tokenomics = None;
+        if let Some(path) = &self.genesis {
+            let genesis = load_genesis(path)?;
+            if let Some(config) = &genesis.tokenomics {
+                config
+                    .validate()
+                    .map_err(|e| CliError::Invalid(format!("genesis tokenomics: {}", e)))?;
+            }
+            tokenomics = genesis.tokenomics;
+            for registration in genesis.validators {
+                if !registration.verify() {
+                    return Err(CliError::InvalidKey {
+                        field: "genesis validator",
+                        value: registration.ed25519_public_key,
+                    });
+                }
+                registered.push(registration.ed25519_public_key);
+            }
+        }
 
-    let socket_addr = SocketAddr::new(ip_addr, port);
+        if self.participants.is_empty() && registered.is_empty() {
+            return Err(CliError::Missing("participants"));
+        }
+        let mut participants = Vec::with_capacity(self.participants.len() + registered.len());
+        for participant in registered.iter().chain(&self.participants) {
+            let key = parse_key("participant", participant)?;
+            if participants.contains(&key) {
+                return Err(CliError::Invalid(format!("participant {} listed twice", participant)));
+            }
+            participants.push(key);
+        }
+        if !participants.contains(&signer.public_key()) {
+            return Err(CliError::Invalid(format!(
+                "identity {} is not in the participant set",
+                hex(&signer.public_key())
+            )));
+        }
 
-    Ok((node_id.to_string(), socket_addr))
+        let mut bootstrappers = Vec::with_capacity(self.bootstrappers.len());
+        let mut addresses = HashSet::new();
+        for bootstrapper in &self.bootstrappers {
+            let (key, address) = bootstrapper.split_once('@').ok_or_else(|| CliError::InvalidAddress {
+                field: "bootstrapper",
+                value: bootstrapper.clone(),
+                reason: "expected <public key>@<ip:port>".into(),
+            })?;
+            let key = parse_key("bootstrapper", key)?;
+            let address = parse_address("bootstrapper", address)?;
+            if !participants.contains(&key) {
+                return Err(CliError::Invalid(format!("bootstrapper {} is not a participant", bootstrapper)));
+            }
+            if key == signer.public_key() || address == listen {
+                return Err(CliError::Invalid(format!("bootstrapper {} is this validator", bootstrapper)));
+            }
+            if !addresses.insert(address) {
+                return Err(CliError::Invalid(format!("bootstrapper address {} listed twice", address)));
+            }
+            bootstrappers.push((key, address));
+        }
+
+        let storage_dir = self.storage_dir.ok_or(CliError::Missing("storage-dir"))?;
+        let interval = self.snapshot_interval_secs.unwrap_or(DEFAULT_SNAPSHOT_INTERVAL_SECS);
+        if interval == 0 {
+            return Err(CliError::Invalid("snapshot interval must be positive".into()));
+        }
+        let snapshots = SnapshotConfig {
+            directory: self.snapshot_dir.unwrap_or_else(|| storage_dir.join("snapshots")),
+            interval: Duration::from_secs(interval),
+            retained_sections: RETAINED_SECTIONS,
+            keep: KEPT_SNAPSHOTS,
+        };
+        let latitude = self.latitude.ok_or(CliError::Missing("latitude"))?;
+        let longitude = self.longitude.ok_or(CliError::Missing("longitude"))?;
+        let location = ValidatorLocation::new(latitude, longitude)?;
+
+        if !(0.0..=1.0).contains(&self.confidence.threshold) {
+            return Err(CliError::Invalid("confidence threshold must be between 0 and 1".into()));
+        }
+        if self.confidence.max_latency_ratio < 1.0 {
+            return Err(CliError::Invalid("confidence max_latency_ratio must be at least 1".into()));
+        }
+        if let Some((name, weight)) = self.confidence.weights.iter().find(|(_, weight)| **weight < 0.0) {
+            return Err(CliError::Invalid(format!("confidence weight {} of {} is negative", weight, name)));
+        }
+
+        let reference_points = self.reference_points()?;
+
+        self.logging
+            .validate()
+            .map_err(|e| CliError::Invalid(format!("logging: {}", e)))?;
+        self.protocol
+            .validate()
+            .map_err(|e| CliError::Invalid(format!("protocol: {}", e)))?;
+
+        let chain_id = self.chain_id.unwrap_or_else(|| DEFAULT_CHAIN_ID.to_string());
+        if chain_id.is_empty() {
+            return Err(CliError::Invalid("chain id must not be empty".into()));
+        }
+        let genesis_hash = genesis_hash(&participants);
+
+        let interval = self.attestation_interval_secs.unwrap_or(DEFAULT_ATTESTATION_INTERVAL_SECS);
+        if interval == 0 {
+            return Err(CliError::Invalid("attestation interval must be positive".into()));
+        }
+
+        Ok(AppConfig {
+            identity,
+            watermarks,
+            listen,
+            bootstrappers,
+            participants,
+            storage_dir,
+            snapshots,
+            from_snapshot: None,
+            location,
+            reference_points,
+            confidence: self.confidence,
+            confidence_report: self.confidence_report,
+            genesis_hash,
+            chain_id,
+            tokenomics,
+            sequencer_rpc: self.sequencer_rpc,
+            attestation_interval: Duration::from_secs(interval),
+            logging: self.logging,
+            protocol: self.protocol,
+        })
+    }
 }
 
-// cmd.rs
-pub fn setup_clap_command() -> AppConfig {
-    let matches = Command::new("romer")
-        .about("generate secret logs and agree on their hash")
+/// Validated startup settings of a validator
+pub struct AppConfig {
+    pub identity: NodeIdentity,
+    pub watermarks: PathBuf,
+    pub listen: SocketAddr,
+    pub bootstrappers: Vec<(PublicKey, SocketAddr)>,
+    pub participants: Vec<PublicKey>,
+    pub storage_dir: PathBuf,
+    pub snapshots: SnapshotConfig,
+    /// Snapshot, or directory of snapshots, to restore the consensus journal
+    /// from before starting
+    pub from_snapshot: Option<PathBuf>,
+    pub location: ValidatorLocation,
+    pub reference_points: Vec<ReferencePoint>,
+    pub confidence: ConfidenceConfig,
+    pub confidence_report: Option<PathBuf>,
+    /// Chain peers must be on, and the hash of the participant set it
+    /// started with
+    pub chain_id: String,
+    pub genesis_hash: Digest,
+    /// Token economics from the genesis file, if it sets them
+    pub tokenomics: Option<TokenomicsConfig>,
+    /// Sequencer attestations are delivered to, if any
+    pub sequencer_rpc: Option<String>,
+    pub attestation_interval: Duration,
+    pub logging: LoggingConfig,
+    pub protocol: ProtocolSchedule,
+}
+
+const DEFAULT_SNAPSHOT_INTERVAL_SECS: u64 = 600;
+const DEFAULT_ATTESTATION_INTERVAL_SECS: u64 = 600;
+
+fn load_genesis(path: &Path) -> Result<Genesis, CliError> {
+    let raw = std::fs::read_to_string(path).map_err(|source| CliError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    serde_json::from_str(&raw).map_err(|e| CliError::Parse(e.to_string()))
+}
+
+fn parse_key(field: &'static str, value: &str) -> Result<PublicKey, CliError> {
+    let key: PublicKey = from_hex(value.trim_start_matches("0x"))
+        .ok_or_else(|| CliError::InvalidKey {
+            field,
+            value: value.to_string(),
+        })?
+        .into();
+    if !Ed25519::validate(&key) {
+        return Err(CliError::InvalidKey {
+            field,
+            value: value.to_string(),
+        });
+    }
+    Ok(key)
+}
+
+/// Parses `ip:port`, rejecting addresses peers could not dial
+fn parse_address(field: &'static str, value: &str) -> Result<SocketAddr, CliError> {
+    let invalid = |reason: &str| CliError::InvalidAddress {
+        field,
+        value: value.to_string(),
+        reason: reason.to_string(),
+    };
+    let address: SocketAddr = value.parse().map_err(|_| invalid("expected ip:port"))?;
+    if address.port() == 0 {
+        return Err(invalid("port must not be 0"));
+    }
+    if address.ip().is_multicast() {
+        return Err(invalid("multicast addresses cannot be dialed"));
+    }
+    // Peers cannot dial an unspecified address, though we may listen on one
+    if field != "listen" && address.ip().is_unspecified() {
+        return Err(invalid("unspecified addresses cannot be dialed"));
+    }
+    Ok(address)
+}
+
+fn command() -> Command {
+    Command::new("romer")
+        .about("Run a Rømer Chain validator")
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .global(true)
+                .value_parser(value_parser!(PathBuf))
+                .help("TOML file with the settings below; command line values override it"),
+        )
+        .arg(
+            Arg::new("identity")
+                .long("identity")
+                .value_parser(value_parser!(PathBuf))
+                .help("Keystore directory of this validator's identity keys, created on first start"),
+        )
+        .arg(
+            Arg::new("watermarks")
+                .long("watermarks")
+                .value_parser(value_parser!(PathBuf))
+                .help("Directory of the last signed consensus votes, kept apart from chain state backups"),
+        )
+        .arg(
+            Arg::new("local-signer")
+                .long("local-signer")
+                .action(clap::ArgAction::SetTrue)
+                .help("Sign with the keystore's consensus key even if a remote signer is configured"),
+        )
+        .arg(
+            Arg::new("genesis")
+                .long("genesis")
+                .value_parser(value_parser!(PathBuf))
+                .help("JSON file with the registrations of the genesis validators"),
+        )
+        .arg(
+            Arg::new("export-registration")
+                .long("export-registration")
+                .value_parser(value_parser!(PathBuf))
+                .help("Write this validator's signed registration for genesis to a file and exit"),
+        )
+        .arg(
+            Arg::new("listen")
+                .long("listen")
+                .help("Address the p2p network listens on (ip:port)"),
+        )
         .arg(
             Arg::new("bootstrappers")
                 .long("bootstrappers")
-                .required(false)
                 .value_delimiter(',')
-                .value_parser(value_parser!(String)),
-        )
-        .arg(
-            Arg::new("me")
-                .long("me")
-                .required(true)
-                .value_parser(parse_me),
+                .help("Peers dialed on startup (<public key>@<ip:port>)"),
         )
         .arg(
             Arg::new("participants")
                 .long("participants")
-                .required(true)
                 .value_delimiter(',')
+                .help("Hex encoded public keys of all participants"),
+        )
+        .arg(
+            Arg::new("storage-dir")
+                .long("storage-dir")
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("snapshot-dir")
+                .long("snapshot-dir")
+                .value_parser(value_parser!(PathBuf))
+                .help("Directory consensus journal snapshots are written to"),
+        )
+        .arg(
+            Arg::new("snapshot-interval-secs")
+                .long("snapshot-interval-secs")
                 .value_parser(value_parser!(u64))
-                .help("All participants (arbiter and contributors)"),
+                .help("Seconds between consensus journal snapshots"),
+        )
+        .arg(
+            Arg::new("from-snapshot")
+                .long("from-snapshot")
+                .value_parser(value_parser!(PathBuf))
+                .help("Restore the consensus journal from a verified snapshot, or the newest one in a directory, before starting"),
+        )
+        .arg(
+            Arg::new("confidence-report")
+                .long("confidence-report")
+                .value_parser(value_parser!(PathBuf))
+                .help("Write a JSON report of every location check and its contribution to this file"),
+        )
+        .arg(
+            Arg::new("chain-id")
+                .long("chain-id")
+                .help("Chain this validator belongs to; peers on another chain are refused"),
+        )
+        .arg(
+            Arg::new("sequencer-rpc")
+                .long("sequencer-rpc")
+                .help("JSON-RPC address of the sequencer to deliver location and hardware attestations to (host:port)"),
+        )
+        .arg(
+            Arg::new("attestation-interval-secs")
+                .long("attestation-interval-secs")
+                .value_parser(value_parser!(u64))
+                .help("Seconds between attestations delivered to the sequencer"),
+        )
+        .arg(
+            Arg::new("log-level")
+                .long("log-level")
+                .help("Log level directives, e.g. info or info,commonware_p2p=warn"),
+        )
+        .arg(
+            Arg::new("log-file")
+                .long("log-file")
+                .value_parser(value_parser!(PathBuf))
+                .help("File logs are also written to, rotated by size"),
         )
-        .arg(Arg::new("storage-dir").long("storage-dir").required(true))
         .arg(
             Arg::new("latitude")
                 .long("latitude")
-                .required(true)
                 .value_parser(value_parser!(f64))
-                .help("Validator's latitude coordinate (-90 to 90)")
+                .help("Validator's latitude coordinate (-90 to 90)"),
         )
         .arg(
             Arg::new("longitude")
                 .long("longitude")
-                .required(true)
                 .value_parser(value_parser!(f64))
-                .help("Validator's longitude coordinate (-180 to 180)")
-        )
-        .get_matches();
-
-    let me = matches
-        .get_one::<(String, SocketAddr)>("me")
-        .expect("Invalid 'me' argument format");
-    let bootstrappers = matches
-        .get_many::<String>("bootstrappers")
-        .map(|b| b.cloned().collect())
-        .unwrap_or_default();
-    let participants = matches
-        .get_many::<u64>("participants")
-        .map(|p| p.cloned().collect())
-        .expect("Please provide at least one participant");
-    let storage_dir = matches
-        .get_one::<String>("storage-dir")
-        .expect("Please provide storage directory")
-        .clone();
-    let latitude = *matches.get_one::<f64>("latitude")
-        .expect("Latitude is required");
-    let longitude = *matches.get_one::<f64>("longitude")
-        .expect("Longitude is required");
-    
-        let location = ValidatorLocation::new(latitude, longitude)
-        .expect("Invalid validator location coordinates");
-
-    AppConfig {
-        bootstrappers,
-        me: (me.0.clone(), me.1),
-        participants,
-        storage_dir,
-        location,
+                .help("Validator's longitude coordinate (-180 to 180)"),
+        )
+        .subcommand(diagnose::command())
+        .subcommand(support::command())
+}
+
+/// Reads the configuration file, if one is given, applies the command line
+/// on top of it and validates the result. With `--export-registration`, a
+/// `diagnose` or a `support-bundle` command the node is not started and
+/// `None` returned.
+pub fn setup_clap_command() -> Result<Option<AppConfig>, CliError> {
+    let matches = command().get_matches();
+    let mut config = match matches.get_one::<PathBuf>("config") {
+        Some(path) => ConfigFile::load(path)?,
+        None => ConfigFile::default(),
+    };
+    config.overlay(&matches);
+
+    if let Some(("diagnose", diagnose)) = matches.subcommand() {
+        diagnose::run(diagnose, &config)?;
+        return Ok(None);
+    }
+    if let Some(("support-bundle", bundle)) = matches.subcommand() {
+        support::run(bundle, &config, matches.get_one::<PathBuf>("config").map(PathBuf::as_path))?;
+        return Ok(None);
+    }
+
+    if let Some(path) = matches.get_one::<PathBuf>("export-registration") {
+        let listen = parse_address("listen", config.listen.as_deref().ok_or(CliError::Missing("listen"))?)?;
+        let passphrase = read_passphrase().map_err(CliError::Passphrase)?;
+        let registration = config.identity(&passphrase)?.registration(listen);
+        let json = serde_json::to_string_pretty(&registration).map_err(|e| CliError::Parse(e.to_string()))?;
+        std::fs::write(path, json).map_err(|source| CliError::Read {
+            path: path.clone(),
+            source,
+        })?;
+        println!("Wrote registration of {} to {}", registration.ed25519_public_key, path.display());
+        return Ok(None);
+    }
+
+    let passphrase = read_passphrase().map_err(CliError::Passphrase)?;
+    let mut app_config = config.resolve(&passphrase)?;
+    app_config.from_snapshot = matches.get_one::<PathBuf>("from-snapshot").cloned();
+    Ok(Some(app_config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSPHRASE: &str = "passphrase";
+
+    /// Keystore directory of a fresh identity and its consensus key
+    fn keystore(name: &str) -> (PathBuf, String) {
+        let dir = std::env::temp_dir().join(format!("romer-cli-{}-{}", std::process::id(), name));
+        let identity = NodeKeyManager::open(&dir).unwrap().load(PASSPHRASE).unwrap();
+        (dir, hex(&identity.consensus.public_key()))
+    }
+
+    fn key(seed: u64) -> String {
+        hex(&Ed25519::from_seed(seed).public_key())
+    }
+
+    fn config(name: &str) -> ConfigFile {
+        let (identity, me) = keystore(name);
+        toml::from_str(&format!(
+            r#"
+            identity = "{}"
+            listen = "127.0.0.1:3001"
+            bootstrappers = ["{}@127.0.0.1:3000"]
+            participants = ["{}", "{}"]
+            storage_dir = "data/1"
+            latitude = -28.0167
+            longitude = 153.4
+            "#,
+            identity.display(),
+            key(0),
+            key(0),
+            me
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_resolve() {
+        let (_, me) = keystore("resolve");
+        let app = config("resolve").resolve(PASSPHRASE).unwrap();
+        assert_eq!(hex(&app.identity.consensus.public_key()), me);
+        assert_eq!(app.participants.len(), 2);
+        assert_eq!(app.bootstrappers[0].1, "127.0.0.1:3000".parse().unwrap());
+
+        assert!(matches!(config("resolve").resolve("wrong"), Err(CliError::Keystore(_))));
+    }
+
+    #[test]
+    fn test_command_line_overrides_file() {
+        let mut file = config("overrides");
+        let matches = command()
+            .try_get_matches_from([
+                "romer",
+                "--listen",
+                "127.0.0.1:4001",
+                "--storage-dir",
+                "other",
+                "--sequencer-rpc",
+                "127.0.0.1:9879",
+            ])
+            .unwrap();
+        file.overlay(&matches);
+        let app = file.resolve(PASSPHRASE).unwrap();
+        assert_eq!(app.listen, "127.0.0.1:4001".parse().unwrap());
+        assert_eq!(app.sequencer_rpc.as_deref(), Some("127.0.0.1:9879"));
+        assert_eq!(app.attestation_interval, Duration::from_secs(600));
+        assert_eq!(app.chain_id, DEFAULT_CHAIN_ID);
+        assert_eq!(app.genesis_hash, genesis_hash(&app.participants));
+        assert_eq!(app.storage_dir, PathBuf::from("other"));
+        assert_eq!(app.snapshots.directory, PathBuf::from("other/snapshots"));
+        assert_eq!(app.participants.len(), 2);
+    }
+
+    #[test]
+    fn test_genesis_participants() {
+        let (identity, _) = keystore("genesis");
+        let mut me = NodeKeyManager::open(&identity).unwrap().load(PASSPHRASE).unwrap();
+        let genesis = Genesis {
+            validators: vec![me.registration("127.0.0.1:3001".parse().unwrap())],
+            tokenomics: None,
+        };
+        let path = std::env::temp_dir().join(format!("romer-genesis-{}.json", std::process::id()));
+        std::fs::write(&path, serde_json::to_string(&genesis).unwrap()).unwrap();
+
+        let file = ConfigFile {
+            genesis: Some(path.clone()),
+            participants: vec![key(0)],
+            ..config("genesis")
+        };
+        assert_eq!(file.resolve(PASSPHRASE).unwrap().participants.len(), 2);
+
+        // Registrations must be signed by the keys they name
+        let mut forged = genesis.validators[0].clone();
+        forged.ed25519_public_key = key(0);
+        std::fs::write(&path, serde_json::to_string(&Genesis { validators: vec![forged], tokenomics: None }).unwrap()).unwrap();
+        let file = ConfigFile {
+            genesis: Some(path),
+            ..config("genesis")
+        };
+        assert!(matches!(file.resolve(PASSPHRASE), Err(CliError::InvalidKey { .. })));
+    }
+
+    #[test]
+    fn test_rejects_bad_addresses() {
+        let file = ConfigFile {
+            listen: Some("127.0.0.1:0".into()),
+            ..config("addresses")
+        };
+        assert!(matches!(file.resolve(PASSPHRASE), Err(CliError::InvalidAddress { field: "listen", .. })));
+
+        let file = ConfigFile {
+            bootstrappers: vec![format!("{}@0.0.0.0:3000", key(0))],
+            ..config("addresses")
+        };
+        assert!(matches!(file.resolve(PASSPHRASE), Err(CliError::InvalidAddress { field: "bootstrapper", .. })));
+
+        let file = ConfigFile {
+            bootstrappers: vec!["127.0.0.1:3000".into()],
+            ..config("addresses")
+        };
+        assert!(matches!(file.resolve(PASSPHRASE), Err(CliError::InvalidAddress { .. })));
+    }
+
+    #[test]
+    fn test_reference_points() {
+        let mut file = config("references");
+        file.reference_points = vec![ReferencePointConfig {
+            name: "AMS-IX".into(),
+            latitude: 52.35,
+            longitude: 4.95,
+            ip: "80.249.208.1".into(),
+        }];
+        let app = file.resolve(PASSPHRASE).unwrap();
+        assert_eq!(app.reference_points[0].location, geo::Point::new(4.95, 52.35));
+
+        let mut file = config("references");
+        file.reference_points = vec![ReferencePointConfig {
+            name: "AMS-IX".into(),
+            latitude: 52.35,
+            longitude: 4.95,
+            ip: "amsix".into(),
+        }];
+        assert!(matches!(file.resolve(PASSPHRASE), Err(CliError::InvalidAddress { .. })));
+    }
+
+    #[test]
+    fn test_diagnose_command() {
+        let matches = command()
+            .try_get_matches_from(["romer", "diagnose", "location", "--lat", "50.1", "--lon", "8.7", "--config", "node.toml"])
+            .unwrap();
+        assert_eq!(matches.get_one::<PathBuf>("config"), Some(&PathBuf::from("node.toml")));
+        let Some(("diagnose", diagnose)) = matches.subcommand() else {
+            panic!("expected diagnose");
+        };
+        let Some(("location", location)) = diagnose.subcommand() else {
+            panic!("expected location");
+        };
+        assert_eq!(location.get_one::<f64>("lat"), Some(&50.1));
+        assert!(command().try_get_matches_from(["romer", "diagnose"]).is_err());
+    }
+
+    #[test]
+    fn test_support_bundle_command() {
+        let matches = command()
+            .try_get_matches_from(["romer", "support-bundle", "--output", "bundle.tar.gz", "--config", "node.toml"])
+            .unwrap();
+        let Some(("support-bundle", bundle)) = matches.subcommand() else {
+            panic!("expected support-bundle");
+        };
+        assert_eq!(bundle.get_one::<PathBuf>("output"), Some(&PathBuf::from("bundle.tar.gz")));
+        assert!(command()
+            .try_get_matches_from(["romer", "support-bundle", "--metrics", "localhost"])
+            .is_err());
+    }
+
+    #[test]
+    fn test_confidence_model() {
+        let mut file = config("confidence");
+        file.confidence = toml::from_str("threshold = 0.7\n[weights]\n\"DE-CIX Frankfurt\" = 2.0").unwrap();
+        let app = file.resolve(PASSPHRASE).unwrap();
+        assert_eq!(app.confidence.threshold, 0.7);
+        assert_eq!(app.confidence.weights["DE-CIX Frankfurt"], 2.0);
+
+        let mut file = config("confidence");
+        file.confidence.threshold = 1.5;
+        assert!(matches!(file.resolve(PASSPHRASE), Err(CliError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_logging() {
+        let mut file = config("logging");
+        file.logging = toml::from_str("level = \"debug\"\nformat = \"json\"\n[rotation]\nmax_bytes = 1048576").unwrap();
+        let app = file.resolve(PASSPHRASE).unwrap();
+        assert_eq!(app.logging.level, "debug");
+        assert_eq!(app.logging.rotation.max_bytes, 1 << 20);
+
+        let mut file = config("logging");
+        file.logging.level = "info,romer=verbose".into();
+        assert!(matches!(file.resolve(PASSPHRASE), Err(CliError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_rejects_unknown_peers() {
+        let file = ConfigFile {
+            participants: vec![key(0)],
+            ..config("peers")
+        };
+        assert!(matches!(file.resolve(PASSPHRASE), Err(CliError::Invalid(_))));
+
+        let file = ConfigFile {
+            bootstrappers: vec![format!("{}@127.0.0.1:3000", key(2))],
+            ..config("peers")
+        };
+        assert!(matches!(file.resolve(PASSPHRASE), Err(CliError::Invalid(_))));
+
+        let file = ConfigFile {
+            participants: vec![key(0), "zz".into()],
+            ..config("peers")
+        };
+        assert!(matches!(file.resolve(PASSPHRASE), Err(CliError::InvalidKey { .. })));
     }
 }
@@ -0,0 +1,245 @@
+// src/node/cmd/diagnose.rs
+use clap::{value_parser, Arg, ArgMatches, Command};
+use romer_common::utils::hardware_validator::{HardwareDetector, HardwareRequirements};
+use std::path::PathBuf;
+
+use crate::location::{
+    default_probe, distance_km, theoretical_minimum_ms, ConfidenceReport, LatencyStats, Observation, ProbeConfig,
+    ReferencePoint, ValidatorLocation,
+};
+use crate::validation::proof_generator::ProofGenerator;
+
+use super::cli::{CliError, ConfigFile};
+
+pub fn command() -> Command {
+    Command::new("diagnose")
+        .about("Check this machine against validator requirements without starting the node")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("location")
+                .about("Measure latency to every reference point and score a location")
+                .arg(
+                    Arg::new("lat")
+                        .long("lat")
+                        .value_parser(value_parser!(f64))
+                        .help("Latitude to check, the configured latitude by default"),
+                )
+                .arg(
+                    Arg::new("lon")
+                        .long("lon")
+                        .value_parser(value_parser!(f64))
+                        .help("Longitude to check, the configured longitude by default"),
+                )
+                .arg(
+                    Arg::new("samples")
+                        .long("samples")
+                        .value_parser(value_parser!(usize))
+                        .help("Pings sent to each reference point"),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .action(clap::ArgAction::SetTrue)
+                        .help("Print the confidence report as JSON instead of a table"),
+                ),
+        )
+        .subcommand(
+            Command::new("hardware")
+                .about("Detect this machine's resources and compare them with validator requirements")
+                .arg(
+                    Arg::new("storage-dir")
+                        .long("storage-dir")
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Directory whose filesystem free disk is measured on, the configured one by default"),
+                ),
+        )
+}
+
+/// Runs the `diagnose` subcommand given in `matches`
+pub fn run(matches: &ArgMatches, config: &ConfigFile) -> Result<(), CliError> {
+    match matches.subcommand() {
+        Some(("location", matches)) => location(matches, config),
+        Some(("hardware", matches)) => hardware(matches, config),
+        _ => unreachable!("subcommand is required"),
+    }
+}
+
+/// What was measured against one reference point
+struct Measurement {
+    reference: ReferencePoint,
+    distance_km: f64,
+    theoretical_min_ms: f64,
+    stats: Result<LatencyStats, String>,
+}
+
+fn location(matches: &ArgMatches, config: &ConfigFile) -> Result<(), CliError> {
+    let latitude = matches
+        .get_one::<f64>("lat")
+        .copied()
+        .or(config.latitude)
+        .ok_or(CliError::Missing("lat"))?;
+    let longitude = matches
+        .get_one::<f64>("lon")
+        .copied()
+        .or(config.longitude)
+        .ok_or(CliError::Missing("lon"))?;
+    let location = ValidatorLocation::new(latitude, longitude)?;
+
+    // Probes open their sockets on the runtime, including the default
+    // probe of the proof generator the references fall back to
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| CliError::Diagnose(e.to_string()))?;
+    let _guard = runtime.enter();
+
+    let mut references = config.reference_points()?;
+    if references.is_empty() {
+        references = ProofGenerator::builder().references().lock().unwrap().points().cloned().collect();
+    }
+    let mut probe_config = ProbeConfig::default();
+    if let Some(samples) = matches.get_one::<usize>("samples") {
+        probe_config.sample_count = *samples;
+    }
+    let probe = default_probe(probe_config);
+    let json = matches.get_flag("json");
+    if !json {
+        println!("Measuring latency over {}", probe.name());
+    }
+
+    let measurements = runtime.block_on(async {
+        let mut measurements = Vec::with_capacity(references.len());
+        for reference in references {
+            if !json {
+                println!("Measuring {} ({})...", reference.name, reference.ip);
+            }
+            let stats = probe.measure(reference.ip).await.map_err(|e| e.to_string());
+            measurements.push(Measurement {
+                distance_km: distance_km(location.to_point(), reference.location),
+                theoretical_min_ms: theoretical_minimum_ms(location.to_point(), reference.location),
+                reference,
+                stats,
+            });
+        }
+        measurements
+    });
+
+    // Without a health history every reference point counts in full
+    let observations = measurements
+        .iter()
+        .map(|measurement| {
+            let observation = match &measurement.stats {
+                Ok(stats) => Observation::Measured {
+                    theoretical_min_ms: measurement.theoretical_min_ms,
+                    measured_ms: stats.median(),
+                },
+                Err(error) => Observation::Unreachable { error: error.clone() },
+            };
+            (measurement.reference.clone(), 1.0, observation)
+        })
+        .collect();
+    let report = config.confidence.score(location.to_point(), observations);
+
+    if json {
+        println!("{}", report.to_json());
+    } else {
+        print_location(&location, &measurements, &report);
+    }
+    Ok(())
+}
+
+fn print_location(location: &ValidatorLocation, measurements: &[Measurement], report: &ConfidenceReport) {
+    println!();
+    println!("Location {}", location);
+    println!(
+        "{:<24} {:>10} {:>9} {:>7} {:>9} {:>9} {:>9} {:>9} {:>7} {:>6} {:>6}",
+        "Reference", "Distance", "Minimum", "Samples", "Min", "Median", "Max", "Jitter", "Ratio", "Weight", "Score"
+    );
+    for (measurement, check) in measurements.iter().zip(&report.checks) {
+        let name = &measurement.reference.name;
+        match &measurement.stats {
+            Ok(stats) => println!(
+                "{:<24} {:>8.0}km {:>7.2}ms {:>4}/{:<2} {:>7.2}ms {:>7.2}ms {:>7.2}ms {:>7.2}ms {:>7.2} {:>6.2} {:>6.2}",
+                name,
+                measurement.distance_km,
+                measurement.theoretical_min_ms,
+                stats.samples.len(),
+                stats.samples.len() + stats.failures,
+                stats.min(),
+                stats.median(),
+                stats.max(),
+                stats.jitter(),
+                check.inputs.ratio.unwrap_or_default(),
+                check.weight,
+                check.score,
+            ),
+            Err(error) => println!(
+                "{:<24} {:>8.0}km {:>7.2}ms unreachable: {}",
+                name, measurement.distance_km, measurement.theoretical_min_ms, error
+            ),
+        }
+    }
+    println!();
+    println!(
+        "Confidence {:.2} (threshold {:.2}): {}",
+        report.confidence,
+        report.threshold,
+        if report.passed { "PASS" } else { "FAIL" }
+    );
+}
+
+/// Prints virtualization evidence, detected resources and a pass/fail table
+/// against [`HardwareRequirements`]. Fails if any requirement is not met, so
+/// it can gate provisioning scripts.
+fn hardware(matches: &ArgMatches, config: &ConfigFile) -> Result<(), CliError> {
+    let storage_dir = matches
+        .get_one::<PathBuf>("storage-dir")
+        .or(config.storage_dir.as_ref())
+        .cloned()
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    println!("Operating system: {:?}", HardwareDetector::detect_os());
+    let virtualization =
+        HardwareDetector::detect_virtualization().map_err(|e| CliError::Diagnose(e.to_string()))?;
+    let evidence = HardwareDetector::virtualization_evidence();
+    if evidence.is_empty() {
+        println!("Virtualization evidence: none");
+    } else {
+        println!("Virtualization evidence:");
+        for item in &evidence {
+            println!("  - {}", item);
+        }
+    }
+
+    let profile = HardwareDetector::profile(&storage_dir);
+    println!();
+    println!("CPU: {}", profile.cpu_model.as_deref().unwrap_or("unknown"));
+    println!("Storage directory: {}", storage_dir.display());
+    if profile.nics.is_empty() {
+        println!("Network interfaces: none detected");
+    }
+    for nic in &profile.nics {
+        match nic.speed_mbps {
+            Some(speed) => println!("Network interface {}: {} Mbps", nic.name, speed),
+            None => println!("Network interface {}: link speed unknown", nic.name),
+        }
+    }
+
+    let checks = HardwareRequirements::default().check(&virtualization, &profile);
+    println!();
+    println!("{:<20} {:<16} {:<28} {}", "Requirement", "Required", "Detected", "Result");
+    for check in &checks {
+        println!(
+            "{:<20} {:<16} {:<28} {}",
+            check.requirement,
+            check.required,
+            check.detected,
+            if check.passed { "PASS" } else { "FAIL" }
+        );
+    }
+
+    let failed = checks.iter().filter(|check| !check.passed).count();
+    if failed > 0 {
+        return Err(CliError::Diagnose(format!("{} hardware requirement(s) not met", failed)));
+    }
+    println!();
+    println!("All hardware requirements met");
+    Ok(())
+}
@@ -0,0 +1,395 @@
+// src/node/cmd/support.rs
+use clap::{value_parser, Arg, ArgMatches, Command};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::metrics::METRICS_PORT_OFFSET;
+use crate::node::crash;
+use crate::snapshot::live_sections;
+
+use super::cli::{CliError, ConfigFile};
+
+/// Bytes from the end of each log file included
+const LOG_TAIL_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Newest crash dumps included
+const CRASH_DUMPS: usize = 10;
+
+/// Longest wait for the metrics endpoint to connect and answer
+const METRICS_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Configuration keys containing any of these have their values redacted
+const SECRET_KEYS: &[&str] = &["passphrase", "password", "secret", "token", "private", "api_key"];
+
+const REDACTED: &str = "<redacted>";
+
+pub fn command() -> Command {
+    Command::new("support-bundle")
+        .about("Collect logs, configuration, metrics and journal state into a tarball to attach to bug reports")
+        .arg(
+            Arg::new("output")
+                .long("output")
+                .short('o')
+                .value_parser(value_parser!(PathBuf))
+                .help("Tarball to write, romer-support-<time>.tar.gz by default"),
+        )
+        .arg(
+            Arg::new("metrics")
+                .long("metrics")
+                .value_parser(value_parser!(SocketAddr))
+                .help("Metrics endpoint to snapshot, the p2p port plus 1000 on localhost by default"),
+        )
+}
+
+/// What went into a bundle and what could not be collected
+#[derive(Debug, Default, Serialize)]
+pub struct BundleManifest {
+    pub version: &'static str,
+    /// Unix seconds the bundle was created at
+    pub created_at: u64,
+    pub files: Vec<String>,
+    pub skipped: Vec<Skipped>,
+}
+
+/// An item left out of a bundle
+#[derive(Debug, Serialize)]
+pub struct Skipped {
+    pub item: String,
+    pub reason: String,
+}
+
+/// State of one journal partition in the storage directory
+#[derive(Debug, Serialize)]
+struct JournalHead {
+    partition: String,
+    sections: usize,
+    /// Oldest and newest section present
+    first: Option<u64>,
+    last: Option<u64>,
+    bytes: u64,
+    /// Unix seconds the partition was last written
+    modified: Option<u64>,
+}
+
+/// Runs the `support-bundle` subcommand given in `matches`
+pub fn run(matches: &ArgMatches, config: &ConfigFile, config_path: Option<&Path>) -> Result<(), CliError> {
+    let created_at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let output = matches
+        .get_one::<PathBuf>("output")
+        .cloned()
+        .unwrap_or_else(|| PathBuf::from(format!("romer-support-{}.tar.gz", created_at)));
+    let metrics = matches.get_one::<SocketAddr>("metrics").copied().or_else(|| {
+        let listen: SocketAddr = config.listen.as_deref()?.parse().ok()?;
+        Some(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), listen.port().checked_add(METRICS_PORT_OFFSET)?))
+    });
+
+    let manifest = write_bundle(&output, config, config_path, metrics, created_at)
+        .map_err(|e| CliError::Bundle(format!("{}: {}", output.display(), e)))?;
+    println!("Wrote {} with {} files", output.display(), manifest.files.len());
+    for skipped in &manifest.skipped {
+        println!("  skipped {}: {}", skipped.item, skipped.reason);
+    }
+    Ok(())
+}
+
+/// Tarball under construction
+struct Bundle {
+    builder: tar::Builder<GzEncoder<File>>,
+    manifest: BundleManifest,
+}
+
+impl Bundle {
+    fn add(&mut self, path: &str, data: &[u8]) -> io::Result<()> {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(self.manifest.created_at);
+        header.set_cksum();
+        self.builder.append_data(&mut header, path, data)?;
+        self.manifest.files.push(path.to_string());
+        Ok(())
+    }
+
+    fn skip(&mut self, item: impl Into<String>, reason: impl ToString) {
+        self.manifest.skipped.push(Skipped {
+            item: item.into(),
+            reason: reason.to_string(),
+        });
+    }
+}
+
+/// Writes a bundle to `output`. Items that cannot be collected are recorded
+/// as skipped in the manifest rather than failing the bundle.
+pub fn write_bundle(
+    output: &Path,
+    config: &ConfigFile,
+    config_path: Option<&Path>,
+    metrics: Option<SocketAddr>,
+    created_at: u64,
+) -> io::Result<BundleManifest> {
+    let encoder = GzEncoder::new(File::create(output)?, Compression::default());
+    let mut bundle = Bundle {
+        builder: tar::Builder::new(encoder),
+        manifest: BundleManifest {
+            version: env!("CARGO_PKG_VERSION"),
+            created_at,
+            ..Default::default()
+        },
+    };
+
+    let version = format!(
+        "romer-validator {}\nos: {}\narch: {}\nicmp: {}\n",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        cfg!(feature = "icmp"),
+    );
+    bundle.add("version.txt", version.as_bytes())?;
+
+    match config_path {
+        Some(path) => match redacted_config(path) {
+            Ok(redacted) => bundle.add("config.toml", redacted.as_bytes())?,
+            Err(reason) => bundle.skip("config.toml", reason),
+        },
+        None => bundle.skip("config.toml", "no configuration file given"),
+    }
+
+    match &config.logging.file {
+        Some(file) => {
+            let mut rotated = file.as_os_str().to_owned();
+            rotated.push(".1");
+            for path in [file.clone(), PathBuf::from(rotated)] {
+                let name = format!("logs/{}", path.file_name().unwrap_or_default().to_string_lossy());
+                match tail(&path, LOG_TAIL_BYTES) {
+                    Ok(log) => bundle.add(&name, &log)?,
+                    Err(e) => bundle.skip(name, e),
+                }
+            }
+        }
+        None => bundle.skip("logs", "no log file configured"),
+    }
+
+    match metrics {
+        Some(address) => match fetch_metrics(address) {
+            Ok(snapshot) => bundle.add("metrics.txt", snapshot.as_bytes())?,
+            Err(e) => bundle.skip("metrics.txt", format!("{}: {}", address, e)),
+        },
+        None => bundle.skip("metrics.txt", "no listen address configured"),
+    }
+
+    match &config.storage_dir {
+        Some(storage_dir) => {
+            match journal_heads(storage_dir) {
+                Ok(heads) => bundle.add("journals.json", &serde_json::to_vec_pretty(&heads)?)?,
+                Err(e) => bundle.skip("journals.json", e),
+            }
+            for path in crash_dumps(&crash::crash_dir(storage_dir)) {
+                let name = format!("crashes/{}", path.file_name().unwrap_or_default().to_string_lossy());
+                match std::fs::read(&path) {
+                    Ok(dump) => bundle.add(&name, &dump)?,
+                    Err(e) => bundle.skip(name, e),
+                }
+            }
+        }
+        None => bundle.skip("journals.json", "no storage directory configured"),
+    }
+
+    let manifest = serde_json::to_vec_pretty(&bundle.manifest)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(created_at);
+    header.set_cksum();
+    bundle.builder.append_data(&mut header, "manifest.json", manifest.as_slice())?;
+    bundle.builder.into_inner()?.finish()?.flush()?;
+    Ok(bundle.manifest)
+}
+
+/// Reads the configuration file with the value of every secret key replaced
+fn redacted_config(path: &Path) -> Result<String, String> {
+    let raw = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let mut value: toml::Value = toml::from_str(&raw).map_err(|e| format!("not included unparsed: {}", e))?;
+    redact(&mut value);
+    toml::to_string_pretty(&value).map_err(|e| e.to_string())
+}
+
+fn redact(value: &mut toml::Value) {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table.iter_mut() {
+                let key = key.to_lowercase();
+                if SECRET_KEYS.iter().any(|secret| key.contains(secret)) {
+                    *value = toml::Value::String(REDACTED.to_string());
+                } else {
+                    redact(value);
+                }
+            }
+        }
+        toml::Value::Array(values) => values.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+/// Last `max` bytes of a file, starting at a line boundary if cut
+fn tail(path: &Path, max: u64) -> io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let start = len.saturating_sub(max);
+    file.seek(SeekFrom::Start(start))?;
+    let mut data = Vec::with_capacity((len - start) as usize);
+    file.read_to_end(&mut data)?;
+    if start > 0 {
+        if let Some(newline) = data.iter().position(|&b| b == b'\n') {
+            data.drain(..=newline);
+        }
+    }
+    Ok(data)
+}
+
+/// Scrapes the metrics endpoint of a running node
+fn fetch_metrics(address: SocketAddr) -> io::Result<String> {
+    let mut stream = TcpStream::connect_timeout(&address, METRICS_TIMEOUT)?;
+    stream.set_read_timeout(Some(METRICS_TIMEOUT))?;
+    stream.set_write_timeout(Some(METRICS_TIMEOUT))?;
+    write!(stream, "GET /metrics HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n", address)?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    match response.split_once("\r\n\r\n") {
+        Some((_, body)) => Ok(body.to_string()),
+        None => Err(io::Error::new(io::ErrorKind::InvalidData, "malformed response")),
+    }
+}
+
+/// Section range, size and last write of every journal partition
+fn journal_heads(storage_dir: &Path) -> io::Result<Vec<JournalHead>> {
+    let mut heads = Vec::new();
+    for entry in std::fs::read_dir(storage_dir)? {
+        let path = entry?.path();
+        if !path.is_dir() {
+            continue;
+        }
+        let sections = live_sections(&path).map_err(|e| io::Error::other(e.to_string()))?;
+        if sections.is_empty() {
+            continue;
+        }
+        let (mut bytes, mut modified) = (0, None);
+        for file in std::fs::read_dir(&path)? {
+            let metadata = file?.metadata()?;
+            bytes += metadata.len();
+            let written = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|time| time.as_secs());
+            modified = modified.max(written);
+        }
+        heads.push(JournalHead {
+            partition: path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+            sections: sections.len(),
+            first: sections.first().copied(),
+            last: sections.last().copied(),
+            bytes,
+            modified,
+        });
+    }
+    heads.sort_by(|a, b| a.partition.cmp(&b.partition));
+    Ok(heads)
+}
+
+/// Newest crash dumps in `dir`, if it exists
+fn crash_dumps(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut dumps: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .collect();
+    // Dumps are named after the time they were written
+    dumps.sort();
+    let skip = dumps.len().saturating_sub(CRASH_DUMPS);
+    dumps.split_off(skip)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_redact() {
+        let mut value: toml::Value = toml::from_str(
+            r#"
+            listen = "127.0.0.1:3001"
+            keystore_passphrase = "hunter2"
+
+            [remote_signer]
+            endpoint = "https://signer:9000"
+            client_key = "client.key"
+            api_key = "abc"
+            "#,
+        )
+        .unwrap();
+        redact(&mut value);
+        assert_eq!(value["listen"].as_str(), Some("127.0.0.1:3001"));
+        assert_eq!(value["keystore_passphrase"].as_str(), Some(REDACTED));
+        assert_eq!(value["remote_signer"]["endpoint"].as_str(), Some("https://signer:9000"));
+        assert_eq!(value["remote_signer"]["client_key"].as_str(), Some("client.key"));
+        assert_eq!(value["remote_signer"]["api_key"].as_str(), Some(REDACTED));
+    }
+
+    #[test]
+    fn test_write_bundle() {
+        let dir = std::env::temp_dir().join(format!("romer-support-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let storage = dir.join("storage");
+        std::fs::create_dir_all(storage.join("log")).unwrap();
+        std::fs::write(storage.join("log").join("0000000000000002"), [0u8; 10]).unwrap();
+        std::fs::write(storage.join("log").join("0000000000000005"), [0u8; 20]).unwrap();
+        std::fs::create_dir_all(crash::crash_dir(&storage)).unwrap();
+        std::fs::write(crash::crash_dir(&storage).join("crash-1.txt"), "panicked").unwrap();
+        let log = dir.join("node.log");
+        std::fs::write(&log, "first\nsecond\n").unwrap();
+        let config_path = dir.join("node.toml");
+        std::fs::write(&config_path, "secret_token = \"x\"\nstorage_dir = \"storage\"\n").unwrap();
+
+        let mut config = ConfigFile {
+            storage_dir: Some(storage),
+            ..ConfigFile::default()
+        };
+        config.logging.file = Some(log);
+        let output = dir.join("bundle.tar.gz");
+        let manifest = write_bundle(&output, &config, Some(&config_path), None, 1_000).unwrap();
+        assert!(manifest.skipped.iter().any(|skipped| skipped.item == "metrics.txt"));
+        assert!(manifest.skipped.iter().any(|skipped| skipped.item == "logs/node.log.1"));
+
+        let mut archive = tar::Archive::new(GzDecoder::new(File::open(&output).unwrap()));
+        let mut files = HashMap::new();
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let mut contents = String::new();
+            entry.read_to_string(&mut contents).unwrap();
+            files.insert(entry.path().unwrap().to_string_lossy().into_owned(), contents);
+        }
+        assert!(files["version.txt"].starts_with("romer-validator"));
+        assert!(files["config.toml"].contains(REDACTED));
+        assert!(!files["config.toml"].contains("\"x\""));
+        assert_eq!(files["logs/node.log"], "first\nsecond\n");
+        assert_eq!(files["crashes/crash-1.txt"], "panicked");
+        let journals: serde_json::Value = serde_json::from_str(&files["journals.json"]).unwrap();
+        assert_eq!(journals[0]["partition"], "log");
+        assert_eq!(journals[0]["first"], 2);
+        assert_eq!(journals[0]["last"], 5);
+        assert_eq!(journals[0]["bytes"], 30);
+        assert!(files.contains_key("manifest.json"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
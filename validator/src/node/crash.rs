@@ -0,0 +1,36 @@
+// src/node/crash.rs
+use std::backtrace::Backtrace;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Directory in the storage directory crash dumps are written to
+pub const CRASH_DIR: &str = "crashes";
+
+/// Directory crash dumps of a node with `storage_dir` are written to
+pub fn crash_dir(storage_dir: &Path) -> PathBuf {
+    storage_dir.join(CRASH_DIR)
+}
+
+/// Writes a crash dump with the panic message, location and a backtrace to
+/// the crash directory whenever a thread panics, then runs the previous hook
+pub fn install(storage_dir: &Path) {
+    let dir = crash_dir(storage_dir);
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let thread = std::thread::current();
+        let dump = format!(
+            "romer {}\ntime: {}\nthread: {}\n{}\n\nbacktrace:\n{}\n",
+            env!("CARGO_PKG_VERSION"),
+            now.as_secs(),
+            thread.name().unwrap_or("unnamed"),
+            info,
+            Backtrace::force_capture(),
+        );
+        let path = dir.join(format!("crash-{}.txt", now.as_millis()));
+        if std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&path, dump)).is_ok() {
+            eprintln!("Wrote crash dump to {}", path.display());
+        }
+        previous(info);
+    }));
+}
@@ -0,0 +1,290 @@
+// src/node/divergence.rs
+use crate::application::block::entities::Block;
+use crate::snapshot::live_sections;
+use commonware_utils::hex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use tracing::error;
+
+/// Directory in the storage directory forensic bundles are written to
+pub const FORENSICS_DIR: &str = "forensics";
+
+/// File whose presence keeps the validator from voting
+pub const HALT_FILE: &str = "halted.json";
+
+/// Newest sections of each journal partition copied into a bundle
+pub const FORENSIC_SECTIONS: usize = 4;
+
+#[derive(Error, Debug)]
+pub enum DivergenceError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Failed to encode forensic bundle: {0}")]
+    Encoding(String),
+}
+
+/// Balances written by a block, by hex encoded address
+pub type WriteSet = BTreeMap<String, u64>;
+
+/// A block whose state root this validator computed differently from the
+/// one notarized
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Divergence {
+    pub height: u64,
+    pub view: u32,
+    /// Hex encoded
+    pub notarized_root: String,
+    pub computed_root: String,
+    /// Unix seconds
+    pub detected_at: u64,
+}
+
+/// A key the two write sets disagree on, `None` where one of them did not
+/// write it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DivergentWrite {
+    pub key: String,
+    pub local: Option<u64>,
+    pub notarized: Option<u64>,
+}
+
+/// Written to `divergence.json` at the root of a forensic bundle, beside
+/// the copied journal sections
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForensicReport {
+    pub divergence: Divergence,
+    pub block: Block,
+    pub local_writes: WriteSet,
+    /// Writes the notarized root was computed from, if known
+    pub notarized_writes: Option<WriteSet>,
+    /// Keys whose values differ, every local write if the notarized writes
+    /// are not known
+    pub divergent_writes: Vec<DivergentWrite>,
+    /// `<partition>/<section>` of every journal section copied
+    pub journal_sections: Vec<String>,
+}
+
+/// Keys `local` and `notarized` disagree on
+pub fn divergent_writes(local: &WriteSet, notarized: Option<&WriteSet>) -> Vec<DivergentWrite> {
+    let empty = WriteSet::new();
+    let notarized = notarized.unwrap_or(&empty);
+    let keys: BTreeSet<&String> = local.keys().chain(notarized.keys()).collect();
+    keys.into_iter()
+        .filter_map(|key| {
+            let (local, notarized) = (local.get(key).copied(), notarized.get(key).copied());
+            (local != notarized).then(|| DivergentWrite {
+                key: key.clone(),
+                local,
+                notarized,
+            })
+        })
+        .collect()
+}
+
+/// Stops the validator from voting once its state has diverged. The halt
+/// is written to a file beside the signing watermarks, so a restart stays
+/// halted until an operator has investigated and removed it.
+#[derive(Clone)]
+pub struct HaltSwitch {
+    path: PathBuf,
+    halted: Arc<AtomicBool>,
+}
+
+impl HaltSwitch {
+    /// Opens the halt file in `dir`, halted if it exists
+    pub fn open(dir: &Path) -> std::io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(HALT_FILE);
+        let halted = Arc::new(AtomicBool::new(path.exists()));
+        Ok(Self { path, halted })
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted.load(Ordering::SeqCst)
+    }
+
+    /// Halts voting, recording why. The flag is set even if the file can't
+    /// be written.
+    pub fn halt(&self, divergence: &Divergence) -> Result<(), DivergenceError> {
+        self.halted.store(true, Ordering::SeqCst);
+        let raw = serde_json::to_vec_pretty(divergence).map_err(|e| DivergenceError::Encoding(e.to_string()))?;
+        fs::write(&self.path, raw)?;
+        Ok(())
+    }
+
+    /// Divergence that halted the validator, if any
+    pub fn reason(&self) -> Option<Divergence> {
+        let raw = fs::read(&self.path).ok()?;
+        serde_json::from_slice(&raw).ok()
+    }
+}
+
+/// Compares the state root of every executed block with the notarized one.
+/// On a mismatch it writes a forensic bundle and halts voting rather than
+/// keep signing votes for a state the rest of the network doesn't share.
+pub struct DivergenceDetector {
+    storage_dir: PathBuf,
+    forensics_dir: PathBuf,
+    /// Journal partitions whose newest sections go in a bundle
+    partitions: Vec<String>,
+    halt: HaltSwitch,
+}
+
+impl DivergenceDetector {
+    pub fn new(storage_dir: PathBuf, partitions: &[&str], halt: HaltSwitch) -> Self {
+        Self {
+            forensics_dir: storage_dir.join(FORENSICS_DIR),
+            storage_dir,
+            partitions: partitions.iter().map(|partition| partition.to_string()).collect(),
+            halt,
+        }
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halt.is_halted()
+    }
+
+    /// Checks `computed_root`, the state root of executing `block` notarized
+    /// in `view`, against the block's. Returns the bundle written if they
+    /// differ. Voting is halted even if the bundle can't be written.
+    pub fn check(
+        &self,
+        block: &Block,
+        view: u32,
+        computed_root: [u8; 32],
+        local_writes: &WriteSet,
+        notarized_writes: Option<&WriteSet>,
+    ) -> Result<Option<PathBuf>, DivergenceError> {
+        if computed_root == block.header.state_root {
+            return Ok(None);
+        }
+        let divergence = Divergence {
+            height: block.header.height,
+            view,
+            notarized_root: hex(&block.header.state_root),
+            computed_root: hex(&computed_root),
+            detected_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default(),
+        };
+        error!(
+            height = divergence.height,
+            view,
+            notarized = %divergence.notarized_root,
+            computed = %divergence.computed_root,
+            "State root diverged from the notarized block, halting"
+        );
+        let halted = self.halt.halt(&divergence);
+        let bundle = self.write_bundle(divergence, block, local_writes, notarized_writes)?;
+        halted?;
+        Ok(Some(bundle))
+    }
+
+    fn write_bundle(
+        &self,
+        divergence: Divergence,
+        block: &Block,
+        local_writes: &WriteSet,
+        notarized_writes: Option<&WriteSet>,
+    ) -> Result<PathBuf, DivergenceError> {
+        let bundle = self
+            .forensics_dir
+            .join(format!("{}-{}", divergence.height, divergence.detected_at));
+        let mut journal_sections = Vec::new();
+        for partition in &self.partitions {
+            let source = self.storage_dir.join(partition);
+            let mut sections = live_sections(&source).map_err(|e| std::io::Error::other(e.to_string()))?;
+            let skip = sections.len().saturating_sub(FORENSIC_SECTIONS);
+            sections.drain(..skip);
+            let target = bundle.join("journal").join(partition);
+            fs::create_dir_all(&target)?;
+            for section in sections {
+                let name = hex(&section.to_be_bytes());
+                fs::copy(source.join(&name), target.join(&name))?;
+                journal_sections.push(format!("{}/{}", partition, name));
+            }
+        }
+
+        let report = ForensicReport {
+            divergence,
+            block: block.clone(),
+            local_writes: local_writes.clone(),
+            notarized_writes: notarized_writes.cloned(),
+            divergent_writes: divergent_writes(local_writes, notarized_writes),
+            journal_sections,
+        };
+        fs::create_dir_all(&bundle)?;
+        let raw = serde_json::to_vec_pretty(&report).map_err(|e| DivergenceError::Encoding(e.to_string()))?;
+        fs::write(bundle.join("divergence.json"), raw)?;
+        Ok(bundle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::block::entities::BlockHeader;
+
+    fn block(state_root: [u8; 32]) -> Block {
+        Block {
+            header: BlockHeader {
+                view: 7,
+                height: 42,
+                timestamp: 0,
+                previous_hash: [0u8; 32],
+                transactions_root: [0u8; 32],
+                state_root,
+                validator_public_key: [0u8; 32],
+            },
+            transactions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_divergence_halts_and_writes_bundle() {
+        let dir = std::env::temp_dir().join(format!("romer-divergence-{}", std::process::id()));
+        let storage = dir.join("storage");
+        fs::create_dir_all(storage.join("log")).unwrap();
+        for section in 0..6u64 {
+            fs::write(storage.join("log").join(hex(&section.to_be_bytes())), [section as u8]).unwrap();
+        }
+        let halt = HaltSwitch::open(&dir.join("watermarks")).unwrap();
+        let detector = DivergenceDetector::new(storage.clone(), &["log"], halt.clone());
+
+        let local: WriteSet = [("aa".to_string(), 10), ("bb".to_string(), 5)].into();
+        let notarized: WriteSet = [("aa".to_string(), 10), ("cc".to_string(), 5)].into();
+        assert_eq!(detector.check(&block([1u8; 32]), 7, [1u8; 32], &local, None).unwrap(), None);
+        assert!(!halt.is_halted());
+
+        let bundle = detector
+            .check(&block([1u8; 32]), 7, [2u8; 32], &local, Some(&notarized))
+            .unwrap()
+            .unwrap();
+        assert!(halt.is_halted());
+        let report: ForensicReport = serde_json::from_slice(&fs::read(bundle.join("divergence.json")).unwrap()).unwrap();
+        assert_eq!(report.divergence.height, 42);
+        assert_eq!(
+            report.divergent_writes,
+            vec![
+                DivergentWrite { key: "bb".into(), local: Some(5), notarized: None },
+                DivergentWrite { key: "cc".into(), local: None, notarized: Some(5) },
+            ]
+        );
+        assert_eq!(report.journal_sections.len(), FORENSIC_SECTIONS);
+        assert!(bundle.join("journal/log").join(hex(&5u64.to_be_bytes())).exists());
+
+        // A restart stays halted
+        let reopened = HaltSwitch::open(&dir.join("watermarks")).unwrap();
+        assert!(reopened.is_halted());
+        assert_eq!(reopened.reason().unwrap().computed_root, hex(&[2u8; 32]));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,184 @@
+// src/node/keystore.rs
+use commonware_cryptography::{Bls12381, Ed25519, PrivateKey, PublicKey, Scheme};
+use commonware_utils::{from_hex, hex};
+use romer_common::keystore::keymanager::KeyManager;
+use romer_common::keystore::unlock::SecretBytes;
+use romer_common::types::keymanager::{KeyManagerError, KeyManagerResult, SignatureScheme};
+use romer_common::types::tokenomics::TokenomicsConfig;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::path::Path;
+use tracing::info;
+
+use super::signer::ConsensusKey;
+
+/// Environment variable holding the keystore passphrase, for unattended
+/// starts. Without it the passphrase is prompted for.
+pub const PASSPHRASE_ENV: &str = "ROMER_VALIDATOR_PASSPHRASE";
+
+/// Namespace both identity keys sign a registration under
+const REGISTRATION_NAMESPACE: &[u8] = b"ROMER_VALIDATOR_REGISTRATION";
+
+/// Reads the keystore passphrase from `ROMER_VALIDATOR_PASSPHRASE`, or
+/// prompts for it
+pub fn read_passphrase() -> std::io::Result<String> {
+    match std::env::var(PASSPHRASE_ENV) {
+        Ok(passphrase) => Ok(passphrase),
+        Err(_) => rpassword::prompt_password("Validator keystore passphrase: "),
+    }
+}
+
+/// The validator's permanent keys, kept encrypted in a keystore directory
+/// by the common [`KeyManager`]. The Ed25519 key signs consensus and p2p
+/// traffic unless a remote signer holds it; the BLS key is the validator's
+/// on-chain identity.
+pub struct NodeKeyManager {
+    keys: KeyManager,
+}
+
+impl NodeKeyManager {
+    pub fn open(dir: &Path) -> KeyManagerResult<Self> {
+        Ok(Self {
+            keys: KeyManager::with_base_dir(dir.to_path_buf())?,
+        })
+    }
+
+    /// Decrypts the identity keys with `passphrase`, generating any that do
+    /// not exist yet on first start
+    pub fn load(&self, passphrase: &str) -> KeyManagerResult<NodeIdentity> {
+        let ed25519 = self.load_key(SignatureScheme::Ed25519, passphrase)?;
+        let ed25519 = <Ed25519 as Scheme>::from(PrivateKey::from(ed25519.to_vec()))
+            .ok_or_else(|| KeyManagerError::InvalidKeyFormat("Invalid Ed25519 key".into()))?;
+        self.load_with(ConsensusKey::Local(ed25519), passphrase)
+    }
+
+    /// Decrypts the BLS identity key with `passphrase`, generating it on
+    /// first start, and pairs it with `consensus`. A validator whose
+    /// consensus key is held by a remote signer loads its identity this way,
+    /// so no Ed25519 key is generated or read on the host.
+    pub fn load_with(&self, consensus: ConsensusKey, passphrase: &str) -> KeyManagerResult<NodeIdentity> {
+        let bls = self.load_key(SignatureScheme::Bls12381, passphrase)?;
+        let bls = <Bls12381 as Scheme>::from(PrivateKey::from(bls.to_vec()))
+            .ok_or_else(|| KeyManagerError::InvalidKeyFormat("Invalid BLS key".into()))?;
+        Ok(NodeIdentity { consensus, bls })
+    }
+
+    fn load_key(&self, scheme: SignatureScheme, passphrase: &str) -> KeyManagerResult<SecretBytes> {
+        if !self.keys.has_permanent_key(scheme) {
+            let public_key = self.keys.initialize(scheme, passphrase)?;
+            info!(?scheme, key = hex(&public_key), "Generated validator identity key");
+        }
+        self.keys.load_permanent_key(scheme, passphrase)
+    }
+}
+
+/// Decrypted identity keys of a running validator
+#[derive(Clone)]
+pub struct NodeIdentity {
+    pub consensus: ConsensusKey,
+    pub bls: Bls12381,
+}
+
+impl NodeIdentity {
+    /// Registers this validator at `address`, signed by both keys to prove
+    /// it holds them
+    pub fn registration(&mut self, address: SocketAddr) -> ValidatorRegistration {
+        let mut registration = ValidatorRegistration {
+            ed25519_public_key: hex(&self.consensus.public_key()),
+            bls_public_key: hex(&self.bls.public_key()),
+            address,
+            ed25519_signature: String::new(),
+            bls_signature: String::new(),
+        };
+        let payload = registration.payload();
+        registration.ed25519_signature = hex(&self.consensus.sign(Some(REGISTRATION_NAMESPACE), &payload));
+        registration.bls_signature = hex(&self.bls.sign(Some(REGISTRATION_NAMESPACE), &payload));
+        registration
+    }
+}
+
+/// A validator's entry in the genesis validator set
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidatorRegistration {
+    pub ed25519_public_key: String,
+    pub bls_public_key: String,
+    pub address: SocketAddr,
+    pub ed25519_signature: String,
+    pub bls_signature: String,
+}
+
+impl ValidatorRegistration {
+    fn payload(&self) -> Vec<u8> {
+        format!("{}:{}:{}", self.ed25519_public_key, self.bls_public_key, self.address).into_bytes()
+    }
+
+    /// Consensus key of the registered validator
+    pub fn public_key(&self) -> Option<PublicKey> {
+        from_hex(&self.ed25519_public_key).map(Into::into)
+    }
+
+    /// Checks both keys signed the registration
+    pub fn verify(&self) -> bool {
+        let payload = self.payload();
+        let decode = |value: &str| from_hex(value).map(Into::into);
+        let (Some(ed25519), Some(ed25519_signature), Some(bls), Some(bls_signature)) = (
+            decode(&self.ed25519_public_key),
+            decode(&self.ed25519_signature),
+            decode(&self.bls_public_key),
+            decode(&self.bls_signature),
+        ) else {
+            return false;
+        };
+        Ed25519::verify(Some(REGISTRATION_NAMESPACE), &payload, &ed25519, &ed25519_signature)
+            && Bls12381::verify(Some(REGISTRATION_NAMESPACE), &payload, &bls, &bls_signature)
+    }
+}
+
+/// Validator set the network starts with
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Genesis {
+    pub validators: Vec<ValidatorRegistration>,
+    /// Token economics of the network. The genesis block mints the initial
+    /// supply to the treasury, and supply is only tracked if set.
+    #[serde(default)]
+    pub tokenomics: Option<TokenomicsConfig>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_generates_once() {
+        let dir = std::env::temp_dir().join(format!("romer-node-keys-{}", std::process::id()));
+        let keys = NodeKeyManager::open(&dir).unwrap();
+        let first = keys.load("passphrase").unwrap();
+        let second = keys.load("passphrase").unwrap();
+        assert_eq!(first.consensus.public_key(), second.consensus.public_key());
+        assert_eq!(first.bls.public_key(), second.bls.public_key());
+        assert!(keys.load("wrong").is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_registration_verifies() {
+        let mut identity = NodeIdentity {
+            consensus: ConsensusKey::Local(Ed25519::from_seed(1)),
+            bls: Bls12381::from_seed(1),
+        };
+        let registration = identity.registration("127.0.0.1:3000".parse().unwrap());
+        assert!(registration.verify());
+        assert_eq!(registration.public_key(), Some(identity.consensus.public_key()));
+
+        let moved = ValidatorRegistration {
+            address: "127.0.0.1:3001".parse().unwrap(),
+            ..registration.clone()
+        };
+        assert!(!moved.verify());
+        let swapped = ValidatorRegistration {
+            bls_public_key: hex(&Bls12381::from_seed(2).public_key()),
+            ..registration
+        };
+        assert!(!swapped.verify());
+    }
+}
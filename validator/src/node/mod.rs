@@ -1,2 +1,6 @@
 pub mod cmd;
-pub mod keystore;
\ No newline at end of file
+pub mod crash;
+pub mod divergence;
+pub mod keystore;
+pub mod signer;
+pub mod watermark;
@@ -0,0 +1,332 @@
+// src/node/signer.rs
+use commonware_cryptography::{Ed25519, PrivateKey, PublicKey, Scheme, Signature};
+use commonware_utils::hex;
+use rand::{CryptoRng, Rng};
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::sync::mpsc as std_mpsc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::mpsc;
+use tonic::codec::ProstCodec;
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Identity};
+use tracing::{error, info};
+
+const PUBLIC_KEY_PATH: &str = "/romer.signer.v1.RemoteSigner/PublicKey";
+const SIGN_PATH: &str = "/romer.signer.v1.RemoteSigner/Sign";
+
+#[derive(Error, Debug)]
+pub enum RemoteSignerError {
+    #[error("Failed to read {path}: {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("Transport error: {0}")]
+    Transport(String),
+
+    #[error("Signer returned {0}")]
+    Status(String),
+
+    #[error("Signer key {0} is not a valid Ed25519 key")]
+    InvalidKey(String),
+
+    #[error("Signer holds key {actual}, expected {expected}")]
+    UnexpectedKey { expected: String, actual: String },
+
+    #[error("Signer did not answer within {0:?}")]
+    Timeout(Duration),
+}
+
+/// Where the remote signer is and the certificates for mutual TLS with it
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RemoteSignerConfig {
+    /// gRPC endpoint, `https://host:port`
+    pub endpoint: String,
+    /// Name the signer's certificate is issued to, the endpoint host if unset
+    #[serde(default)]
+    pub domain: Option<String>,
+    /// CA the signer's certificate must chain to
+    pub ca_cert: PathBuf,
+    /// Certificate and key this validator authenticates with
+    pub client_cert: PathBuf,
+    pub client_key: PathBuf,
+    /// Hex encoded consensus key the signer is expected to hold
+    #[serde(default)]
+    pub public_key: Option<String>,
+    /// Longest wait for a signature
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+fn default_timeout_ms() -> u64 {
+    2_000
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct PublicKeyRequest {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct PublicKeyResponse {
+    #[prost(bytes = "vec", tag = "1")]
+    public_key: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct SignRequest {
+    #[prost(bytes = "vec", optional, tag = "1")]
+    namespace: Option<Vec<u8>>,
+    #[prost(bytes = "vec", tag = "2")]
+    message: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct SignResponse {
+    #[prost(bytes = "vec", tag = "1")]
+    signature: Vec<u8>,
+}
+
+/// gRPC client of the `romer.signer.v1.RemoteSigner` service described in
+/// `proto/signer.proto`
+struct Client {
+    grpc: tonic::client::Grpc<Channel>,
+}
+
+impl Client {
+    async fn connect(config: &RemoteSignerConfig) -> Result<Self, RemoteSignerError> {
+        let read = |path: &PathBuf| {
+            std::fs::read(path).map_err(|source| RemoteSignerError::Read {
+                path: path.clone(),
+                source,
+            })
+        };
+        let mut tls = ClientTlsConfig::new()
+            .ca_certificate(Certificate::from_pem(read(&config.ca_cert)?))
+            .identity(Identity::from_pem(read(&config.client_cert)?, read(&config.client_key)?));
+        if let Some(domain) = &config.domain {
+            tls = tls.domain_name(domain.clone());
+        }
+        let channel = Endpoint::from_shared(config.endpoint.clone())
+            .and_then(|endpoint| endpoint.tls_config(tls))
+            .map_err(|e| RemoteSignerError::Transport(e.to_string()))?
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .connect()
+            .await
+            .map_err(|e| RemoteSignerError::Transport(e.to_string()))?;
+        Ok(Self {
+            grpc: tonic::client::Grpc::new(channel),
+        })
+    }
+
+    async fn unary<Req, Resp>(&mut self, path: &'static str, request: Req) -> Result<Resp, RemoteSignerError>
+    where
+        Req: prost::Message + Send + Sync + 'static,
+        Resp: prost::Message + Default + Send + Sync + 'static,
+    {
+        self.grpc
+            .ready()
+            .await
+            .map_err(|e| RemoteSignerError::Transport(e.to_string()))?;
+        self.grpc
+            .unary(
+                tonic::Request::new(request),
+                PathAndQuery::from_static(path),
+                ProstCodec::<Req, Resp>::default(),
+            )
+            .await
+            .map(tonic::Response::into_inner)
+            .map_err(|status| RemoteSignerError::Status(status.to_string()))
+    }
+}
+
+struct Job {
+    request: SignRequest,
+    reply: std_mpsc::Sender<Result<Vec<u8>, RemoteSignerError>>,
+}
+
+/// Consensus key held by a remote signer service. Consensus signs
+/// synchronously, so requests are handed to a dedicated thread running the
+/// gRPC client and the caller waits up to the configured timeout for the
+/// answer. Every signature is verified before it is used.
+#[derive(Clone)]
+pub struct RemoteSigner {
+    public_key: PublicKey,
+    jobs: mpsc::UnboundedSender<Job>,
+    timeout: Duration,
+}
+
+impl RemoteSigner {
+    /// Connects to the signer and fetches the key it holds, failing if it
+    /// is not the key the configuration expects
+    pub fn connect(config: RemoteSignerConfig) -> Result<Self, RemoteSignerError> {
+        let timeout = Duration::from_millis(config.timeout_ms);
+        let (jobs, mut queue) = mpsc::unbounded_channel::<Job>();
+        let (ready, connected) = std_mpsc::channel();
+        let endpoint = config.endpoint.clone();
+        let expected = config.public_key.clone();
+        std::thread::Builder::new()
+            .name("remote-signer".into())
+            .spawn(move || {
+                let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                    Ok(runtime) => runtime,
+                    Err(e) => {
+                        let _ = ready.send(Err(RemoteSignerError::Transport(e.to_string())));
+                        return;
+                    }
+                };
+                runtime.block_on(async move {
+                    let mut client = match Client::connect(&config).await {
+                        Ok(client) => client,
+                        Err(e) => {
+                            let _ = ready.send(Err(e));
+                            return;
+                        }
+                    };
+                    let public_key = client
+                        .unary::<_, PublicKeyResponse>(PUBLIC_KEY_PATH, PublicKeyRequest {})
+                        .await
+                        .map(|response| response.public_key);
+                    let _ = ready.send(public_key);
+                    while let Some(job) = queue.recv().await {
+                        let signature = client
+                            .unary::<_, SignResponse>(SIGN_PATH, job.request)
+                            .await
+                            .map(|response| response.signature);
+                        let _ = job.reply.send(signature);
+                    }
+                });
+            })
+            .map_err(|e| RemoteSignerError::Transport(e.to_string()))?;
+
+        let public_key: PublicKey = connected
+            .recv()
+            .map_err(|_| RemoteSignerError::Transport("signer thread exited".into()))??
+            .into();
+        if !Ed25519::validate(&public_key) {
+            return Err(RemoteSignerError::InvalidKey(hex(&public_key)));
+        }
+        if let Some(expected) = expected {
+            if expected.trim_start_matches("0x").to_lowercase() != hex(&public_key) {
+                return Err(RemoteSignerError::UnexpectedKey {
+                    expected,
+                    actual: hex(&public_key),
+                });
+            }
+        }
+        info!(%endpoint, key = hex(&public_key), "Connected to remote signer");
+        Ok(Self {
+            public_key,
+            jobs,
+            timeout,
+        })
+    }
+
+    fn request(&self, namespace: Option<&[u8]>, message: &[u8]) -> Result<Signature, RemoteSignerError> {
+        let (reply, answer) = std_mpsc::channel();
+        let job = Job {
+            request: SignRequest {
+                namespace: namespace.map(<[u8]>::to_vec),
+                message: message.to_vec(),
+            },
+            reply,
+        };
+        self.jobs
+            .send(job)
+            .map_err(|_| RemoteSignerError::Transport("signer thread exited".into()))?;
+        let signature: Signature = match answer.recv_timeout(self.timeout) {
+            Ok(signature) => signature?.into(),
+            Err(_) => return Err(RemoteSignerError::Timeout(self.timeout)),
+        };
+        if !Ed25519::verify(namespace, message, &self.public_key, &signature) {
+            return Err(RemoteSignerError::Status("a signature that does not verify".into()));
+        }
+        Ok(signature)
+    }
+}
+
+/// The key consensus and p2p sign with: a local Ed25519 key from the
+/// keystore, or one held by a remote signer
+#[derive(Clone)]
+pub enum ConsensusKey {
+    Local(Ed25519),
+    Remote(RemoteSigner),
+}
+
+impl Scheme for ConsensusKey {
+    fn new<R: Rng + CryptoRng>(rng: &mut R) -> Self {
+        Self::Local(Ed25519::new(rng))
+    }
+
+    fn from(private_key: PrivateKey) -> Option<Self> {
+        <Ed25519 as Scheme>::from(private_key).map(Self::Local)
+    }
+
+    fn from_seed(seed: u64) -> Self {
+        Self::Local(Ed25519::from_seed(seed))
+    }
+
+    /// Empty for a remote key, which never leaves the signer
+    fn private_key(&self) -> PrivateKey {
+        match self {
+            Self::Local(signer) => signer.private_key(),
+            Self::Remote(_) => PrivateKey::default(),
+        }
+    }
+
+    fn public_key(&self) -> PublicKey {
+        match self {
+            Self::Local(signer) => signer.public_key(),
+            Self::Remote(signer) => signer.public_key.clone(),
+        }
+    }
+
+    /// A remote signature that cannot be obtained is left empty, which
+    /// peers reject like a missing vote, rather than halting the node
+    fn sign(&mut self, namespace: Option<&[u8]>, message: &[u8]) -> Signature {
+        match self {
+            Self::Local(signer) => signer.sign(namespace, message),
+            Self::Remote(signer) => signer.request(namespace, message).unwrap_or_else(|e| {
+                error!(error = %e, "Remote signing failed");
+                Signature::default()
+            }),
+        }
+    }
+
+    fn validate(public_key: &PublicKey) -> bool {
+        Ed25519::validate(public_key)
+    }
+
+    fn verify(namespace: Option<&[u8]>, message: &[u8], public_key: &PublicKey, signature: &Signature) -> bool {
+        Ed25519::verify(namespace, message, public_key, signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_key_signs_as_ed25519() {
+        let mut key = ConsensusKey::from_seed(1);
+        let signature = key.sign(Some(b"ns"), b"message");
+        assert!(Ed25519::verify(Some(b"ns"), b"message", &Ed25519::from_seed(1).public_key(), &signature));
+        assert!(ConsensusKey::verify(Some(b"ns"), b"message", &key.public_key(), &signature));
+    }
+
+    #[test]
+    fn test_connect_fails_without_certificates() {
+        let config = RemoteSignerConfig {
+            endpoint: "https://127.0.0.1:1".into(),
+            domain: None,
+            ca_cert: "missing-ca.pem".into(),
+            client_cert: "missing-cert.pem".into(),
+            client_key: "missing-key.pem".into(),
+            public_key: None,
+            timeout_ms: 100,
+        };
+        assert!(matches!(RemoteSigner::connect(config), Err(RemoteSignerError::Read { .. })));
+    }
+}
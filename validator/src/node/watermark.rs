@@ -0,0 +1,350 @@
+// src/node/watermark.rs
+use crate::node::divergence::HaltSwitch;
+use commonware_consensus::simplex::View;
+use commonware_cryptography::{Hasher, PrivateKey, PublicKey, Scheme, Sha256, Signature};
+use commonware_utils::{hex, union};
+use rand::{CryptoRng, Rng};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+use tracing::error;
+
+/// Suffixes simplex appends to the consensus namespace for each vote
+const NOTARIZE_SUFFIX: &[u8] = b"_NOTARIZE";
+const NULLIFY_SUFFIX: &[u8] = b"_NULLIFY";
+const FINALIZE_SUFFIX: &[u8] = b"_FINALIZE";
+
+#[derive(Error, Debug)]
+pub enum WatermarkError {
+    #[error("Watermark store error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Corrupt watermark file {path}: {reason}")]
+    Corrupt { path: PathBuf, reason: String },
+
+    #[error("Refusing to sign {kind:?} for view {view}: already signed view {signed}")]
+    Regression { kind: VoteKind, view: View, signed: View },
+
+    #[error("Refusing to sign conflicting {kind:?} for view {view}")]
+    Conflict { kind: VoteKind, view: View },
+
+    #[error("Malformed {0:?} message")]
+    Malformed(VoteKind),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VoteKind {
+    Notarize,
+    Nullify,
+    Finalize,
+}
+
+/// Highest vote of one kind signed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Watermark {
+    pub view: View,
+    /// SHA-256 of the signed message, hex encoded
+    pub digest: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct Watermarks {
+    notarize: Option<Watermark>,
+    nullify: Option<Watermark>,
+    finalize: Option<Watermark>,
+}
+
+impl Watermarks {
+    fn get(&self, kind: VoteKind) -> Option<&Watermark> {
+        match kind {
+            VoteKind::Notarize => self.notarize.as_ref(),
+            VoteKind::Nullify => self.nullify.as_ref(),
+            VoteKind::Finalize => self.finalize.as_ref(),
+        }
+    }
+
+    fn set(&mut self, kind: VoteKind, watermark: Watermark) {
+        match kind {
+            VoteKind::Notarize => self.notarize = Some(watermark),
+            VoteKind::Nullify => self.nullify = Some(watermark),
+            VoteKind::Finalize => self.finalize = Some(watermark),
+        }
+    }
+}
+
+/// The last consensus vote of each kind signed by a key, persisted before
+/// the signature is released. Votes below a watermark, a different vote at
+/// the watermark, and a nullify and finalize of the same view are refused,
+/// so a validator restarted from restored state cannot sign twice. The store
+/// must live apart from the chain state, which may be restored from backup.
+pub struct WatermarkStore {
+    path: PathBuf,
+    watermarks: Watermarks,
+}
+
+impl WatermarkStore {
+    /// Opens the watermarks of `public_key` in `dir`
+    pub fn open(dir: &Path, public_key: &PublicKey) -> Result<Self, WatermarkError> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!("{}.json", hex(public_key)));
+        let watermarks = match fs::read_to_string(&path) {
+            Ok(raw) => serde_json::from_str(&raw).map_err(|e| WatermarkError::Corrupt {
+                path: path.clone(),
+                reason: e.to_string(),
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Watermarks::default(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self { path, watermarks })
+    }
+
+    pub fn get(&self, kind: VoteKind) -> Option<&Watermark> {
+        self.watermarks.get(kind)
+    }
+
+    /// Records `message` as signed at `view`, or refuses it
+    pub fn check_and_record(&mut self, kind: VoteKind, view: View, message: &[u8]) -> Result<(), WatermarkError> {
+        let mut hasher = Sha256::new();
+        hasher.update(message);
+        let digest = hex(&hasher.finalize());
+
+        if let Some(signed) = self.watermarks.get(kind) {
+            if view < signed.view {
+                return Err(WatermarkError::Regression {
+                    kind,
+                    view,
+                    signed: signed.view,
+                });
+            }
+            if view == signed.view {
+                // Signing the same vote again is harmless
+                if signed.digest == digest {
+                    return Ok(());
+                }
+                return Err(WatermarkError::Conflict { kind, view });
+            }
+        }
+        // A nullify and a finalize of the same view are a fault
+        let opposite = match kind {
+            VoteKind::Nullify => Some(VoteKind::Finalize),
+            VoteKind::Finalize => Some(VoteKind::Nullify),
+            VoteKind::Notarize => None,
+        };
+        if let Some(opposite) = opposite.and_then(|opposite| self.watermarks.get(opposite)) {
+            if opposite.view == view {
+                return Err(WatermarkError::Conflict { kind, view });
+            }
+        }
+
+        let mut updated = self.watermarks.clone();
+        updated.set(kind, Watermark { view, digest });
+        self.persist(&updated)?;
+        self.watermarks = updated;
+        Ok(())
+    }
+
+    /// Writes and syncs a temporary file, then renames it over the store
+    fn persist(&self, watermarks: &Watermarks) -> Result<(), WatermarkError> {
+        let raw = serde_json::to_vec(watermarks).map_err(|e| WatermarkError::Corrupt {
+            path: self.path.clone(),
+            reason: e.to_string(),
+        })?;
+        let temp = self.path.with_extension("json.tmp");
+        let mut file = fs::File::create(&temp)?;
+        file.write_all(&raw)?;
+        file.sync_all()?;
+        fs::rename(&temp, &self.path)?;
+        if let Some(dir) = self.path.parent() {
+            fs::File::open(dir)?.sync_all()?;
+        }
+        Ok(())
+    }
+}
+
+/// Consensus signer that checks every vote against its [`WatermarkStore`]
+/// before signing, and refuses every vote once its [`HaltSwitch`] is
+/// thrown. Messages outside the consensus namespace, such as p2p
+/// handshakes, are signed as they are.
+#[derive(Clone)]
+pub struct GuardedSigner<C: Scheme> {
+    inner: C,
+    store: Arc<Mutex<WatermarkStore>>,
+    kinds: [(Vec<u8>, VoteKind); 3],
+    halt: Option<HaltSwitch>,
+}
+
+impl<C: Scheme> GuardedSigner<C> {
+    /// Guards `inner`'s votes under the consensus `namespace`
+    pub fn new(inner: C, store: WatermarkStore, namespace: &[u8]) -> Self {
+        Self {
+            inner,
+            store: Arc::new(Mutex::new(store)),
+            kinds: [
+                (union(namespace, NOTARIZE_SUFFIX), VoteKind::Notarize),
+                (union(namespace, NULLIFY_SUFFIX), VoteKind::Nullify),
+                (union(namespace, FINALIZE_SUFFIX), VoteKind::Finalize),
+            ],
+            halt: None,
+        }
+    }
+
+    /// Stops voting once `halt` is thrown
+    pub fn with_halt(mut self, halt: HaltSwitch) -> Self {
+        self.halt = Some(halt);
+        self
+    }
+
+    fn kind(&self, namespace: Option<&[u8]>) -> Option<VoteKind> {
+        let namespace = namespace?;
+        self.kinds
+            .iter()
+            .find(|(prefix, _)| prefix.as_slice() == namespace)
+            .map(|(_, kind)| *kind)
+    }
+
+    /// Checks and records a vote. Every simplex vote message starts with
+    /// its view.
+    fn admit(&self, kind: VoteKind, message: &[u8]) -> Result<(), WatermarkError> {
+        let view = message
+            .get(..8)
+            .and_then(|view| view.try_into().ok())
+            .map(View::from_be_bytes)
+            .ok_or(WatermarkError::Malformed(kind))?;
+        self.store.lock().unwrap().check_and_record(kind, view, message)
+    }
+}
+
+impl<C: Scheme> Scheme for GuardedSigner<C> {
+    /// Guarded signers only wrap an existing key with its history
+    fn new<R: Rng + CryptoRng>(_rng: &mut R) -> Self {
+        unreachable!("guarded signers wrap an existing key")
+    }
+
+    fn from(_private_key: PrivateKey) -> Option<Self> {
+        None
+    }
+
+    fn from_seed(_seed: u64) -> Self {
+        unreachable!("guarded signers wrap an existing key")
+    }
+
+    fn private_key(&self) -> PrivateKey {
+        self.inner.private_key()
+    }
+
+    fn public_key(&self) -> PublicKey {
+        self.inner.public_key()
+    }
+
+    /// A refused vote is left unsigned, which peers reject like a missing
+    /// vote
+    fn sign(&mut self, namespace: Option<&[u8]>, message: &[u8]) -> Signature {
+        if let Some(kind) = self.kind(namespace) {
+            if self.halt.as_ref().is_some_and(HaltSwitch::is_halted) {
+                error!(?kind, "Refused to sign consensus message: halted after a state divergence");
+                return Signature::default();
+            }
+            if let Err(e) = self.admit(kind, message) {
+                error!(error = %e, "Refused to sign consensus message");
+                return Signature::default();
+            }
+        }
+        self.inner.sign(namespace, message)
+    }
+
+    fn validate(public_key: &PublicKey) -> bool {
+        C::validate(public_key)
+    }
+
+    fn verify(namespace: Option<&[u8]>, message: &[u8], public_key: &PublicKey, signature: &Signature) -> bool {
+        C::verify(namespace, message, public_key, signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::divergence::Divergence;
+    use commonware_cryptography::Ed25519;
+
+    fn dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("romer-watermarks-{}-{}", std::process::id(), name))
+    }
+
+    fn vote(view: View, payload: u8) -> Vec<u8> {
+        let mut message = view.to_be_bytes().to_vec();
+        message.push(payload);
+        message
+    }
+
+    #[test]
+    fn test_refuses_regressing_and_conflicting_votes() {
+        let key = Ed25519::from_seed(1).public_key();
+        let mut store = WatermarkStore::open(&dir("rules"), &key).unwrap();
+        store.check_and_record(VoteKind::Notarize, 5, &vote(5, 1)).unwrap();
+        // Re-signing the same vote is allowed
+        store.check_and_record(VoteKind::Notarize, 5, &vote(5, 1)).unwrap();
+        assert!(matches!(
+            store.check_and_record(VoteKind::Notarize, 5, &vote(5, 2)),
+            Err(WatermarkError::Conflict { .. })
+        ));
+        assert!(matches!(
+            store.check_and_record(VoteKind::Notarize, 4, &vote(4, 1)),
+            Err(WatermarkError::Regression { .. })
+        ));
+
+        store.check_and_record(VoteKind::Nullify, 6, &vote(6, 0)).unwrap();
+        assert!(matches!(
+            store.check_and_record(VoteKind::Finalize, 6, &vote(6, 1)),
+            Err(WatermarkError::Conflict { .. })
+        ));
+        store.check_and_record(VoteKind::Finalize, 5, &vote(5, 1)).unwrap();
+    }
+
+    #[test]
+    fn test_survives_restart() {
+        let key = Ed25519::from_seed(1).public_key();
+        let path = dir("restart");
+        WatermarkStore::open(&path, &key)
+            .unwrap()
+            .check_and_record(VoteKind::Finalize, 10, &vote(10, 1))
+            .unwrap();
+
+        let mut reopened = WatermarkStore::open(&path, &key).unwrap();
+        assert_eq!(reopened.get(VoteKind::Finalize).unwrap().view, 10);
+        assert!(reopened.check_and_record(VoteKind::Finalize, 9, &vote(9, 1)).is_err());
+        assert!(reopened.check_and_record(VoteKind::Finalize, 10, &vote(10, 2)).is_err());
+    }
+
+    #[test]
+    fn test_guarded_signer() {
+        let inner = Ed25519::from_seed(1);
+        let store = WatermarkStore::open(&dir("signer"), &inner.public_key()).unwrap();
+        let namespace = b"ROMER_CONSENSUS";
+        let mut signer = GuardedSigner::new(inner, store, namespace);
+        let notarize = union(namespace, NOTARIZE_SUFFIX);
+
+        let first = signer.sign(Some(&notarize), &vote(3, 1));
+        assert!(Ed25519::verify(Some(&notarize), &vote(3, 1), &signer.public_key(), &first));
+        assert!(signer.sign(Some(&notarize), &vote(3, 2)).is_empty());
+        // Other namespaces are not guarded
+        assert!(!signer.sign(Some(b"ROMER_P2P"), &vote(3, 2)).is_empty());
+
+        // Nothing is voted once halted
+        let halt = HaltSwitch::open(&dir("halt")).unwrap();
+        let mut signer = signer.with_halt(halt.clone());
+        halt.halt(&Divergence {
+            height: 1,
+            view: 4,
+            notarized_root: String::new(),
+            computed_root: String::new(),
+            detected_at: 0,
+        })
+        .unwrap();
+        assert!(signer.sign(Some(&notarize), &vote(4, 1)).is_empty());
+        assert!(!signer.sign(Some(b"ROMER_P2P"), &vote(4, 1)).is_empty());
+    }
+}
@@ -0,0 +1,172 @@
+use super::summary::ValidatorEpochStats;
+use commonware_cryptography::PublicKey;
+
+/// How many attestations a single block proposal is worth when weighting rewards
+pub const PROPOSAL_WEIGHT: u64 = 10;
+
+/// Basis points used to express uptime
+const BPS: u64 = 10_000;
+
+/// A single validator's share of an epoch's reward pool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RewardShare {
+    pub validator: PublicKey,
+    pub amount: u64,
+}
+
+/// Splits `pool` between validators in proportion to their participation.
+///
+/// Each validator's weight is its attestations plus `PROPOSAL_WEIGHT` per block
+/// proposed, scaled by its uptime. Rounding dust goes to the validator with the
+/// highest weight so that the full pool is always distributed. Validators with
+/// zero weight receive nothing.
+pub fn distribute(
+    pool: u64,
+    views_in_epoch: u64,
+    stats: &[(PublicKey, ValidatorEpochStats)],
+) -> Vec<RewardShare> {
+    let weights: Vec<(PublicKey, u128)> = stats
+        .iter()
+        .map(|(validator, s)| {
+            let work = s.attestations + s.blocks_proposed * PROPOSAL_WEIGHT;
+            let uptime = s.uptime_bps(views_in_epoch);
+            (validator.clone(), work as u128 * uptime as u128)
+        })
+        .filter(|(_, weight)| *weight > 0)
+        .collect();
+
+    let total: u128 = weights.iter().map(|(_, w)| w).sum();
+    if total == 0 || pool == 0 {
+        return Vec::new();
+    }
+
+    let mut shares: Vec<RewardShare> = weights
+        .iter()
+        .map(|(validator, weight)| RewardShare {
+            validator: validator.clone(),
+            amount: (pool as u128 * weight / total) as u64,
+        })
+        .collect();
+
+    // Hand rounding dust to the heaviest validator (ties broken by key order)
+    let distributed: u64 = shares.iter().map(|s| s.amount).sum();
+    let dust = pool - distributed;
+    if dust > 0 {
+        let heaviest = weights
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))
+            .map(|(i, _)| i)
+            .unwrap();
+        shares[heaviest].amount += dust;
+    }
+
+    shares.sort_by(|a, b| a.validator.cmp(&b.validator));
+    shares
+}
+
+/// Reduces each share by the validator's slashing penalty, in basis points.
+///
+/// What is withheld is not redistributed: it is never minted. Shares
+/// reduced to nothing are dropped.
+pub fn penalize(shares: Vec<RewardShare>, penalty_bps: impl Fn(&PublicKey) -> u32) -> Vec<RewardShare> {
+    shares
+        .into_iter()
+        .filter_map(|share| {
+            let kept = BPS.saturating_sub(penalty_bps(&share.validator) as u64);
+            let amount = (share.amount as u128 * kept as u128 / BPS as u128) as u64;
+            (amount > 0).then_some(RewardShare {
+                validator: share.validator,
+                amount,
+            })
+        })
+        .collect()
+}
+
+impl ValidatorEpochStats {
+    /// Fraction of the epoch's views this validator voted in, in basis points
+    pub fn uptime_bps(&self, views_in_epoch: u64) -> u64 {
+        if views_in_epoch == 0 {
+            return 0;
+        }
+        (self.views_attested.min(views_in_epoch) * BPS) / views_in_epoch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(b: u8) -> PublicKey {
+        vec![b; 32].into()
+    }
+
+    fn stats(views: u64, proposed: u64, attestations: u64) -> ValidatorEpochStats {
+        ValidatorEpochStats {
+            views_attested: views,
+            blocks_proposed: proposed,
+            attestations,
+        }
+    }
+
+    #[test]
+    fn test_full_pool_distributed() {
+        let shares = distribute(
+            1_000,
+            100,
+            &[
+                (key(1), stats(100, 3, 200)),
+                (key(2), stats(100, 3, 200)),
+                (key(3), stats(100, 3, 200)),
+            ],
+        );
+        assert_eq!(shares.len(), 3);
+        assert_eq!(shares.iter().map(|s| s.amount).sum::<u64>(), 1_000);
+    }
+
+    #[test]
+    fn test_downtime_reduces_share() {
+        let shares = distribute(
+            1_000,
+            100,
+            &[(key(1), stats(100, 0, 100)), (key(2), stats(50, 0, 100))],
+        );
+        assert!(shares[0].amount > shares[1].amount);
+    }
+
+    #[test]
+    fn test_inactive_validator_excluded() {
+        let shares = distribute(
+            1_000,
+            100,
+            &[(key(1), stats(100, 1, 100)), (key(2), stats(0, 0, 0))],
+        );
+        assert_eq!(shares, vec![RewardShare { validator: key(1), amount: 1_000 }]);
+    }
+
+    #[test]
+    fn test_penalty_withholds_reward() {
+        let shares = vec![
+            RewardShare { validator: key(1), amount: 1_000 },
+            RewardShare { validator: key(2), amount: 1_000 },
+            RewardShare { validator: key(3), amount: 1_000 },
+        ];
+        let penalized = penalize(shares, |validator| match validator[0] {
+            2 => 500,
+            3 => 10_000,
+            _ => 0,
+        });
+        assert_eq!(
+            penalized,
+            vec![
+                RewardShare { validator: key(1), amount: 1_000 },
+                RewardShare { validator: key(2), amount: 950 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_empty_pool() {
+        assert!(distribute(0, 100, &[(key(1), stats(100, 1, 100))]).is_empty());
+    }
+}
@@ -0,0 +1,31 @@
+//! Per-epoch accounting of validator participation and rewards.
+//!
+//! Proposers include the notarization and finalization certificates
+//! consensus produces in their blocks, and every validator verifies them and
+//! credits their signers, and the leader of each notarized view, with
+//! participation in the chain state. Once an epoch has ended, the next
+//! proposer mints each validator's share of the epoch reward pool, less any
+//! slashing penalty committed on chain, and every validator recomputes those
+//! shares from its own state and rejects a block whose mints differ.
+//!
+//! Reward mints are signed by the proposer of the block that carries them,
+//! see [`verify_mint`]. The epochs minted and waiting to be minted as of the
+//! last finalized block are served by the explorer through a
+//! [`RewardsQuery`].
+
+mod distribution;
+pub use distribution::{distribute, penalize, RewardShare, PROPOSAL_WEIGHT};
+
+mod summary;
+pub use summary::{verify_mint, EpochSummary, RewardsQuery, ValidatorEpochStats};
+
+/// Namespace reward mints are signed under by the proposer including them
+pub const REWARD_MINT_NAMESPACE: &[u8] = b"ROMER_REWARD_MINT";
+
+/// Number of views in a reward epoch
+pub const EPOCH_LENGTH: u64 = 10_000;
+
+/// Returns the epoch a view belongs to
+pub fn epoch_of(view: u64) -> u64 {
+    view / EPOCH_LENGTH
+}
@@ -0,0 +1,206 @@
+use super::{RewardShare, REWARD_MINT_NAMESPACE};
+use crate::application::block::entities::{Transaction, TransactionType, TransferType};
+use commonware_cryptography::{PublicKey, Scheme};
+use romer_common::types::address::Address;
+use romer_common::types::keymanager::SignatureScheme;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// Participation counters for one validator within one epoch, as committed
+/// by the certificates in finalized blocks
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidatorEpochStats {
+    /// Number of committed notarizations the validator signed
+    pub views_attested: u64,
+    /// Number of views in which the validator's proposal was notarized
+    pub blocks_proposed: u64,
+    /// Total number of votes (notarize and finalize) signed in committed
+    /// certificates
+    pub attestations: u64,
+}
+
+/// Final accounting for a completed epoch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpochSummary {
+    pub epoch: u64,
+    /// Number of views in the epoch whose notarization was committed
+    pub views: u64,
+    /// Reward pool distributed for this epoch
+    pub pool: u64,
+    pub validators: Vec<(PublicKey, ValidatorEpochStats)>,
+    pub rewards: Vec<RewardShare>,
+}
+
+impl EpochSummary {
+    /// Account each reward is minted to, and the amount
+    pub fn payouts(&self) -> Vec<([u8; 32], u64)> {
+        self.rewards
+            .iter()
+            .map(|share| {
+                let to = Address::from_public_key(SignatureScheme::Ed25519, &share.validator).into();
+                (to, share.amount)
+            })
+            .collect()
+    }
+
+    /// Builds the mint transactions paying out this epoch's rewards, signed
+    /// by `proposer`, the validator proposing the block that includes them
+    pub fn reward_transactions<C: Scheme>(&self, proposer: &mut C) -> Vec<Transaction> {
+        let mut from = [0u8; 32];
+        from.copy_from_slice(&proposer.public_key());
+        self.payouts()
+            .into_iter()
+            .map(|(to, amount)| {
+                let signature = proposer.sign(Some(REWARD_MINT_NAMESPACE), &mint_payload(self.epoch, &to, amount));
+                Transaction {
+                    transaction_type: TransactionType::TokenTransfer {
+                        to,
+                        amount,
+                        transfer_type: TransferType::Mint,
+                    },
+                    from,
+                    nonce: self.epoch,
+                    gas_amount: 0,
+                    signature: signature.to_vec(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Bytes a reward mint's signature covers: the epoch it pays, the recipient
+/// and the amount
+fn mint_payload(epoch: u64, to: &[u8; 32], amount: u64) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(48);
+    payload.extend_from_slice(&epoch.to_be_bytes());
+    payload.extend_from_slice(to);
+    payload.extend_from_slice(&amount.to_be_bytes());
+    payload
+}
+
+/// Whether `tx` is a reward mint signed by `proposer`, the validator whose
+/// block includes it. The epoch paid is the transaction's nonce.
+///
+/// Reward mints have no sender account: they are authorized by the
+/// proposer's signature, and only accepted in a block whose proposer was
+/// the view's leader. When the block is executed, the mints of an epoch
+/// must pay exactly the rewards its committed participation earned, for an
+/// epoch that has ended and not been paid before.
+pub fn verify_mint<C: Scheme>(tx: &Transaction, proposer: &[u8; 32]) -> bool {
+    let TransactionType::TokenTransfer {
+        to,
+        amount,
+        transfer_type: TransferType::Mint,
+    } = &tx.transaction_type
+    else {
+        return false;
+    };
+    tx.from == *proposer
+        && C::verify(
+            Some(REWARD_MINT_NAMESPACE),
+            &mint_payload(tx.nonce, to, *amount),
+            &proposer.to_vec().into(),
+            &tx.signature.clone().into(),
+        )
+}
+
+/// Read handle onto reward epochs as the last finalized block left them,
+/// shared with the explorer
+#[derive(Clone, Default)]
+pub struct RewardsQuery {
+    /// Epochs finalized blocks have minted
+    completed: Arc<Mutex<BTreeMap<u64, EpochSummary>>>,
+    /// Epochs that have ended and are waiting to be minted
+    unpaid: Arc<Mutex<BTreeMap<u64, EpochSummary>>>,
+}
+
+impl RewardsQuery {
+    /// Records that a finalized block minted an epoch's rewards
+    pub fn paid(&self, summary: EpochSummary) {
+        self.unpaid.lock().unwrap().remove(&summary.epoch);
+        self.completed.lock().unwrap().insert(summary.epoch, summary);
+    }
+
+    /// Replaces the epochs waiting to be minted with those of a newly
+    /// finalized state
+    pub fn update_unpaid(&self, summaries: Vec<EpochSummary>) {
+        *self.unpaid.lock().unwrap() = summaries.into_iter().map(|summary| (summary.epoch, summary)).collect();
+    }
+
+    /// Returns the summary for an ended epoch, minted or not
+    pub fn epoch(&self, epoch: u64) -> Option<EpochSummary> {
+        let completed = self.completed.lock().unwrap().get(&epoch).cloned();
+        completed.or_else(|| self.unpaid.lock().unwrap().get(&epoch).cloned())
+    }
+
+    /// Returns the most recently ended epoch
+    pub fn latest(&self) -> Option<EpochSummary> {
+        let completed = self.completed.lock().unwrap().values().next_back().cloned();
+        let unpaid = self.unpaid.lock().unwrap().values().next_back().cloned();
+        completed.into_iter().chain(unpaid).max_by_key(|summary| summary.epoch)
+    }
+
+    /// Returns a validator's reward for every minted epoch
+    pub fn validator_history(&self, validator: &PublicKey) -> Vec<(u64, u64)> {
+        self.completed
+            .lock()
+            .unwrap()
+            .values()
+            .map(|summary| {
+                let amount = summary
+                    .rewards
+                    .iter()
+                    .find(|r| &r.validator == validator)
+                    .map(|r| r.amount)
+                    .unwrap_or(0);
+                (summary.epoch, amount)
+            })
+            .collect()
+    }
+
+    /// Whether an ended epoch's rewards are still waiting to be minted
+    pub fn is_unpaid(&self, epoch: u64) -> bool {
+        self.unpaid.lock().unwrap().contains_key(&epoch)
+    }
+
+    /// Ended epochs whose rewards no finalized block has minted yet
+    pub fn unpaid(&self) -> Vec<EpochSummary> {
+        self.unpaid.lock().unwrap().values().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use commonware_cryptography::Ed25519;
+
+    #[test]
+    fn test_mints_are_signed_by_the_proposer() {
+        let validator = Ed25519::from_seed(1).public_key();
+        let summary = EpochSummary {
+            epoch: 3,
+            views: 10,
+            pool: 100,
+            validators: Vec::new(),
+            rewards: vec![RewardShare { validator, amount: 100 }],
+        };
+        let mut proposer = Ed25519::from_seed(2);
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&proposer.public_key());
+        let mints = summary.reward_transactions(&mut proposer);
+        assert_eq!(mints.len(), 1);
+        assert!(verify_mint::<Ed25519>(&mints[0], &key));
+
+        // Another proposer, or a changed amount, doesn't verify
+        let mut other = [0u8; 32];
+        other.copy_from_slice(&Ed25519::from_seed(3).public_key());
+        assert!(!verify_mint::<Ed25519>(&mints[0], &other));
+        let mut inflated = mints[0].clone();
+        inflated.transaction_type = TransactionType::TokenTransfer {
+            to: [1u8; 32],
+            amount: 1_000,
+            transfer_type: TransferType::Mint,
+        };
+        assert!(!verify_mint::<Ed25519>(&inflated, &key));
+    }
+}
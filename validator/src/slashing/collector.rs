@@ -0,0 +1,189 @@
+use super::{
+    evidence::{Evidence, EvidenceError},
+    VIEWS_PER_SECTION,
+};
+use commonware_consensus::{simplex::Prover, Activity, Proof};
+use commonware_cryptography::{Hasher, PublicKey, Scheme};
+use commonware_runtime::{Blob, Storage};
+use commonware_storage::journal::Journal;
+use futures::{channel::mpsc, pin_mut, SinkExt, StreamExt};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+
+use super::EvidenceKind;
+
+/// Mailbox used by the supervisor to hand fault activity to the collector
+#[derive(Clone)]
+pub struct Mailbox {
+    sender: mpsc::Sender<(Activity, Proof)>,
+}
+
+impl Mailbox {
+    /// Forwards a reported activity to the collector. Activity that isn't a
+    /// fault is dropped before it reaches the channel.
+    pub async fn report(&mut self, activity: Activity, proof: Proof) {
+        if EvidenceKind::from_activity(activity).is_none() {
+            return;
+        }
+        let _ = self.sender.send((activity, proof)).await;
+    }
+}
+
+/// Verified evidence waiting to be included in a block, shared with the
+/// application that proposes them
+#[derive(Clone, Default)]
+pub struct EvidencePool {
+    pending: Arc<Mutex<Vec<Evidence>>>,
+}
+
+impl EvidencePool {
+    /// Evidence collected and not yet known to be committed
+    pub fn pending(&self) -> Vec<Evidence> {
+        self.pending.lock().unwrap().clone()
+    }
+
+    /// Drops the evidence `committed` reports a finalized block has included
+    pub fn prune(&self, committed: impl Fn(&Evidence) -> bool) {
+        self.pending.lock().unwrap().retain(|evidence| !committed(evidence));
+    }
+
+    fn push(&self, evidence: Evidence) {
+        self.pending.lock().unwrap().push(evidence);
+    }
+}
+
+/// Collects evidence of double votes and persists it until a block commits
+/// it, which is when the offender is slashed
+pub struct Collector<B: Blob, E: Storage<B>, C: Scheme, H: Hasher> {
+    journal: Journal<B, E>,
+    prover: Prover<C, H>,
+    pool: EvidencePool,
+    mailbox: mpsc::Receiver<(Activity, Proof)>,
+
+    /// Evidence we've already persisted, keyed by offender, view and kind
+    seen: HashSet<(PublicKey, u64, EvidenceKind)>,
+}
+
+impl<B: Blob, E: Storage<B>, C: Scheme, H: Hasher> Collector<B, E, C, H> {
+    /// Creates a new collector backed by `journal`
+    pub fn new(journal: Journal<B, E>, prover: Prover<C, H>, mailbox_size: usize) -> (Self, Mailbox, EvidencePool) {
+        let (sender, mailbox) = mpsc::channel(mailbox_size);
+        let pool = EvidencePool::default();
+        (
+            Self {
+                journal,
+                prover,
+                pool: pool.clone(),
+                mailbox,
+                seen: HashSet::new(),
+            },
+            Mailbox { sender },
+            pool,
+        )
+    }
+
+    /// Replays persisted evidence so that evidence not yet committed when
+    /// we stopped is still proposed
+    async fn replay(&mut self) -> Result<(), EvidenceError> {
+        let mut restored = Vec::new();
+        {
+            let stream = self
+                .journal
+                .replay(1)
+                .await
+                .map_err(|e| EvidenceError::Storage(e.to_string()))?;
+            pin_mut!(stream);
+            while let Some(item) = stream.next().await {
+                let (_, _, _, bytes) = item.map_err(|e| EvidenceError::Storage(e.to_string()))?;
+                match Evidence::decode(&bytes) {
+                    Ok(evidence) => restored.push(evidence),
+                    Err(e) => warn!(error = %e, "Skipping unreadable evidence"),
+                }
+            }
+        }
+
+        for evidence in restored {
+            self.seen
+                .insert((evidence.offender.clone(), evidence.view, evidence.kind));
+            match evidence.verify(&self.prover) {
+                Ok(()) => self.pool.push(evidence),
+                Err(e) => warn!(error = %e, "Persisted evidence failed verification"),
+            }
+        }
+        info!(count = self.seen.len(), "Replayed slashing evidence");
+        Ok(())
+    }
+
+    /// Packages a fault reported by consensus into evidence
+    fn package(&self, activity: Activity, proof: Proof) -> Option<Evidence> {
+        let kind = EvidenceKind::from_activity(activity)?;
+        let (offender, view) = match kind {
+            EvidenceKind::ConflictingNotarize => {
+                self.prover.deserialize_conflicting_notarize(proof.clone(), false)?
+            }
+            EvidenceKind::ConflictingFinalize => {
+                self.prover.deserialize_conflicting_finalize(proof.clone(), false)?
+            }
+            EvidenceKind::NullifyFinalize => {
+                self.prover.deserialize_nullify_finalize(proof.clone(), false)?
+            }
+        };
+        Some(Evidence {
+            kind,
+            offender,
+            view,
+            proof,
+        })
+    }
+
+    /// Persists evidence before it is acted on so nothing is lost on crash
+    async fn persist(&mut self, evidence: &Evidence) -> Result<(), EvidenceError> {
+        let section = evidence.view / VIEWS_PER_SECTION;
+        self.journal
+            .append(section, evidence.encode().into())
+            .await
+            .map_err(|e| EvidenceError::Storage(e.to_string()))?;
+        self.journal
+            .sync(section)
+            .await
+            .map_err(|e| EvidenceError::Storage(e.to_string()))
+    }
+
+    /// Run the collector until the mailbox is closed
+    pub async fn run(mut self) {
+        if let Err(e) = self.replay().await {
+            warn!(error = %e, "Failed to replay slashing evidence");
+        }
+
+        while let Some((activity, proof)) = self.mailbox.next().await {
+            let Some(evidence) = self.package(activity, proof) else {
+                warn!(activity, "Received malformed fault proof");
+                continue;
+            };
+
+            let key = (evidence.offender.clone(), evidence.view, evidence.kind);
+            if !self.seen.insert(key) {
+                continue;
+            }
+
+            // Blocks carrying evidence that fails verification are rejected
+            if let Err(e) = evidence.verify(&self.prover) {
+                warn!(error = %e, "Evidence failed verification");
+                continue;
+            }
+            if let Err(e) = self.persist(&evidence).await {
+                warn!(error = %e, "Failed to persist evidence");
+                continue;
+            }
+
+            warn!(
+                offender = commonware_utils::hex(&evidence.offender),
+                view = evidence.view,
+                kind = ?evidence.kind,
+                "Collected slashing evidence"
+            );
+            self.pool.push(evidence);
+        }
+    }
+}
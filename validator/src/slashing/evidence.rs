@@ -0,0 +1,184 @@
+use commonware_consensus::{
+    simplex::{Prover, View, CONFLICTING_FINALIZE, CONFLICTING_NOTARIZE, NULLIFY_AND_FINALIZE},
+    Activity, Proof,
+};
+use commonware_cryptography::{Hasher, PublicKey, Scheme};
+use serde::Serialize;
+use thiserror::Error;
+
+/// The kind of misbehavior captured by a piece of evidence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub enum EvidenceKind {
+    /// Notarize votes for two different payloads in the same view
+    ConflictingNotarize,
+    /// Finalize votes for two different payloads in the same view
+    ConflictingFinalize,
+    /// A nullify and a finalize vote in the same view
+    NullifyFinalize,
+}
+
+impl EvidenceKind {
+    /// Maps a consensus activity to the kind of evidence it represents,
+    /// returning `None` for activity that isn't a fault
+    pub fn from_activity(activity: Activity) -> Option<Self> {
+        match activity {
+            CONFLICTING_NOTARIZE => Some(Self::ConflictingNotarize),
+            CONFLICTING_FINALIZE => Some(Self::ConflictingFinalize),
+            NULLIFY_AND_FINALIZE => Some(Self::NullifyFinalize),
+            _ => None,
+        }
+    }
+
+    /// Byte identifying the kind in encoded evidence
+    pub fn to_byte(self) -> u8 {
+        match self {
+            Self::ConflictingNotarize => 0,
+            Self::ConflictingFinalize => 1,
+            Self::NullifyFinalize => 2,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Self::ConflictingNotarize),
+            1 => Some(Self::ConflictingFinalize),
+            2 => Some(Self::NullifyFinalize),
+            _ => None,
+        }
+    }
+}
+
+/// A self-contained proof that `offender` signed conflicting messages in `view`.
+///
+/// The `proof` is the opaque blob produced by consensus and contains both
+/// signatures, so anyone holding the evidence can verify it independently.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Evidence {
+    pub kind: EvidenceKind,
+    pub offender: PublicKey,
+    pub view: View,
+    pub proof: Proof,
+}
+
+impl Evidence {
+    /// Verifies both signatures in the proof and that they implicate the
+    /// claimed offender in the claimed view
+    pub fn verify<C: Scheme, H: Hasher>(&self, prover: &Prover<C, H>) -> Result<(), EvidenceError> {
+        let verified = match self.kind {
+            EvidenceKind::ConflictingNotarize => prover.deserialize_conflicting_notarize(self.proof.clone(), true),
+            EvidenceKind::ConflictingFinalize => prover.deserialize_conflicting_finalize(self.proof.clone(), true),
+            EvidenceKind::NullifyFinalize => prover.deserialize_nullify_finalize(self.proof.clone(), true),
+        };
+        match verified {
+            Some((offender, view)) if offender == self.offender && view == self.view => Ok(()),
+            _ => Err(EvidenceError::InvalidProof),
+        }
+    }
+
+    /// Serializes the evidence for persistence:
+    /// `kind (1) | view (8) | offender_len (4) | offender | proof_len (4) | proof`
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(17 + self.offender.len() + self.proof.len());
+        bytes.push(self.kind.to_byte());
+        bytes.extend_from_slice(&self.view.to_be_bytes());
+        bytes.extend_from_slice(&(self.offender.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&self.offender);
+        bytes.extend_from_slice(&(self.proof.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&self.proof);
+        bytes
+    }
+
+    /// Deserializes evidence previously produced by [`Evidence::encode`]
+    pub fn decode(bytes: &[u8]) -> Result<Self, EvidenceError> {
+        let kind = bytes
+            .first()
+            .and_then(|b| EvidenceKind::from_byte(*b))
+            .ok_or(EvidenceError::Malformed("unknown evidence kind"))?;
+        let mut cursor = 1;
+
+        let view = View::from_be_bytes(
+            read(bytes, &mut cursor, 8)?
+                .try_into()
+                .map_err(|_| EvidenceError::Malformed("view"))?,
+        );
+
+        let offender_len = read_len(bytes, &mut cursor)?;
+        let offender = read(bytes, &mut cursor, offender_len)?.to_vec();
+
+        let proof_len = read_len(bytes, &mut cursor)?;
+        let proof = read(bytes, &mut cursor, proof_len)?.to_vec();
+
+        if cursor != bytes.len() {
+            return Err(EvidenceError::Malformed("trailing bytes"));
+        }
+
+        Ok(Self {
+            kind,
+            offender: offender.into(),
+            view,
+            proof: proof.into(),
+        })
+    }
+}
+
+fn read<'a>(bytes: &'a [u8], cursor: &mut usize, len: usize) -> Result<&'a [u8], EvidenceError> {
+    let end = cursor
+        .checked_add(len)
+        .filter(|end| *end <= bytes.len())
+        .ok_or(EvidenceError::Malformed("truncated"))?;
+    let slice = &bytes[*cursor..end];
+    *cursor = end;
+    Ok(slice)
+}
+
+fn read_len(bytes: &[u8], cursor: &mut usize) -> Result<usize, EvidenceError> {
+    let raw = read(bytes, cursor, 4)?;
+    Ok(u32::from_be_bytes(raw.try_into().unwrap()) as usize)
+}
+
+/// Errors that can occur while handling evidence
+#[derive(Debug, Error)]
+pub enum EvidenceError {
+    #[error("Malformed evidence: {0}")]
+    Malformed(&'static str),
+
+    #[error("Evidence failed verification")]
+    InvalidProof,
+
+    #[error("Storage error: {0}")]
+    Storage(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Evidence {
+        Evidence {
+            kind: EvidenceKind::ConflictingNotarize,
+            offender: vec![7u8; 32].into(),
+            view: 42,
+            proof: vec![1, 2, 3, 4, 5].into(),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let evidence = sample();
+        let decoded = Evidence::decode(&evidence.encode()).unwrap();
+        assert_eq!(decoded, evidence);
+    }
+
+    #[test]
+    fn test_truncated_rejected() {
+        let bytes = sample().encode();
+        assert!(Evidence::decode(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_unknown_kind_rejected() {
+        let mut bytes = sample().encode();
+        bytes[0] = 9;
+        assert!(Evidence::decode(&bytes).is_err());
+    }
+}
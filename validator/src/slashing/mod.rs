@@ -0,0 +1,25 @@
+//! Detection, persistence and punishment of consensus misbehavior.
+//!
+//! The supervisor forwards any conflicting votes reported by consensus to the
+//! evidence [`Collector`], which verifies, deduplicates and persists them and
+//! adds them to the [`EvidencePool`]. Proposers include pooled evidence in
+//! their blocks, and every validator verifies it again when applying the
+//! block, adding a penalty to the offender's [`SlashRecord`] in the chain
+//! state. Penalties reduce the offender's share of every epoch reward closed
+//! after them, and the records of the last finalized block are served by the
+//! explorer through a [`SlashingQuery`].
+
+mod evidence;
+pub use evidence::{Evidence, EvidenceError, EvidenceKind};
+
+mod collector;
+pub use collector::{Collector, EvidencePool, Mailbox};
+
+mod record;
+pub use record::{SlashRecord, SlashingQuery};
+
+/// Journal partition used to persist collected evidence
+pub const EVIDENCE_PARTITION: &str = "evidence";
+
+/// Number of views grouped into a single journal section
+pub const VIEWS_PER_SECTION: u64 = 1024;
@@ -0,0 +1,81 @@
+use super::evidence::{Evidence, EvidenceKind};
+use commonware_consensus::simplex::View;
+use commonware_cryptography::PublicKey;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Penalty per offense, in basis points of stake
+const PENALTY_BPS: u32 = 500;
+
+/// Maximum cumulative penalty, in basis points of stake
+const MAX_PENALTY_BPS: u32 = 10_000;
+
+/// Accumulated penalties for a single validator
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SlashRecord {
+    /// Number of distinct offenses proven against the validator
+    pub offenses: u32,
+    /// Total penalty applied so far, in basis points of stake
+    pub penalty_bps: u32,
+    /// View of the most recent offense
+    pub last_view: View,
+    /// Kinds of misbehavior observed
+    pub kinds: Vec<EvidenceKind>,
+}
+
+impl SlashRecord {
+    /// Record of a validator's first offense
+    pub fn new(evidence: &Evidence) -> Self {
+        let mut record = Self {
+            offenses: 0,
+            penalty_bps: 0,
+            last_view: evidence.view,
+            kinds: Vec::new(),
+        };
+        record.add(evidence);
+        record
+    }
+
+    /// Applies the penalty for another offense
+    pub fn add(&mut self, evidence: &Evidence) {
+        self.offenses += 1;
+        self.penalty_bps = (self.penalty_bps + PENALTY_BPS).min(MAX_PENALTY_BPS);
+        self.last_view = self.last_view.max(evidence.view);
+        if !self.kinds.contains(&evidence.kind) {
+            self.kinds.push(evidence.kind);
+        }
+    }
+}
+
+/// Read-only handle to the penalties committed by the last finalized block,
+/// shared with the explorer
+#[derive(Clone, Default)]
+pub struct SlashingQuery {
+    records: Arc<Mutex<HashMap<PublicKey, SlashRecord>>>,
+}
+
+impl SlashingQuery {
+    /// Replaces the records with those of a newly finalized state
+    pub fn update(&self, records: impl IntoIterator<Item = (PublicKey, SlashRecord)>) {
+        *self.records.lock().unwrap() = records.into_iter().collect();
+    }
+
+    /// Returns the slashing record for a validator, if any
+    pub fn record(&self, validator: &PublicKey) -> Option<SlashRecord> {
+        self.records.lock().unwrap().get(validator).cloned()
+    }
+
+    /// Returns every slashed validator and its record, ordered by key
+    pub fn records(&self) -> Vec<(PublicKey, SlashRecord)> {
+        let mut records: Vec<_> = self
+            .records
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(validator, record)| (validator.clone(), record.clone()))
+            .collect();
+        records.sort_by(|a, b| a.0.cmp(&b.0));
+        records
+    }
+}
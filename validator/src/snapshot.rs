@@ -0,0 +1,378 @@
+//! Checkpoints of the consensus journal for fast restarts.
+//!
+//! The consensus engine replays its whole journal partition on startup. A
+//! [`Snapshotter`] periodically copies the newest sections of that partition
+//! into a snapshot directory together with a manifest of their lengths and
+//! SHA-256 digests. Starting with `--from-snapshot` verifies a snapshot
+//! against its manifest and swaps it in for the live partition, so replay
+//! only reads the retained sections. The partition it replaces is moved
+//! aside rather than deleted.
+
+use commonware_cryptography::{Hasher, Sha256};
+use commonware_runtime::Clock;
+use commonware_utils::hex;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use tracing::{info, warn};
+
+/// Journal partition the consensus engine writes its votes to
+pub const CONSENSUS_PARTITION: &str = "log";
+
+/// Newest journal sections each snapshot holds, well beyond the views
+/// consensus keeps unpruned
+pub const RETAINED_SECTIONS: usize = 64;
+
+/// Snapshots kept on disk
+pub const KEPT_SNAPSHOTS: usize = 3;
+
+const MANIFEST: &str = "manifest.json";
+
+#[derive(Error, Debug)]
+pub enum SnapshotError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Invalid manifest in {path}: {reason}")]
+    Manifest { path: PathBuf, reason: String },
+
+    #[error("Section {section} of snapshot {path} does not match its manifest")]
+    Corrupt { path: PathBuf, section: u64 },
+
+    #[error("No snapshot found in {0}")]
+    NotFound(PathBuf),
+}
+
+/// When and how much of the consensus journal is checkpointed
+#[derive(Debug, Clone)]
+pub struct SnapshotConfig {
+    /// Where snapshots are written, one subdirectory each
+    pub directory: PathBuf,
+    pub interval: Duration,
+    /// Newest journal sections copied into each snapshot
+    pub retained_sections: usize,
+    /// Snapshots kept before the oldest are removed
+    pub keep: usize,
+}
+
+/// One checkpointed journal section
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SectionEntry {
+    pub section: u64,
+    pub len: u64,
+    /// SHA-256 of the section blob, hex encoded
+    pub sha256: String,
+}
+
+/// Contents of a snapshot, written last so a snapshot without one is
+/// incomplete
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    pub partition: String,
+    /// Unix seconds the snapshot was taken at
+    pub created_at: u64,
+    pub sections: Vec<SectionEntry>,
+    /// SHA-256 over the fields above, so the manifest itself can't be
+    /// altered unnoticed
+    pub digest: String,
+}
+
+impl SnapshotManifest {
+    fn compute_digest(partition: &str, created_at: u64, sections: &[SectionEntry]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(partition.as_bytes());
+        hasher.update(&created_at.to_be_bytes());
+        for entry in sections {
+            hasher.update(&entry.section.to_be_bytes());
+            hasher.update(&entry.len.to_be_bytes());
+            hasher.update(entry.sha256.as_bytes());
+        }
+        hex(&hasher.finalize())
+    }
+}
+
+/// Takes snapshots of one journal partition
+pub struct Snapshotter {
+    storage_directory: PathBuf,
+    partition: String,
+    config: SnapshotConfig,
+}
+
+impl Snapshotter {
+    pub fn new(storage_directory: PathBuf, partition: &str, config: SnapshotConfig) -> Self {
+        Self {
+            storage_directory,
+            partition: partition.to_string(),
+            config,
+        }
+    }
+
+    /// Copies the newest sections of the partition into a new snapshot.
+    /// Sections are append-only, so a copy taken while the engine writes
+    /// holds at worst a torn final entry, which replay discards.
+    pub fn checkpoint(&self, created_at: u64) -> Result<SnapshotManifest, SnapshotError> {
+        let source = self.storage_directory.join(&self.partition);
+        let mut sections = live_sections(&source)?;
+        let skip = sections.len().saturating_sub(self.config.retained_sections);
+        sections.drain(..skip);
+
+        let target = self.config.directory.join(created_at.to_string());
+        fs::create_dir_all(&target)?;
+        let mut entries = Vec::with_capacity(sections.len());
+        for section in sections {
+            let name = section_name(section);
+            let copied = target.join(&name);
+            fs::copy(source.join(&name), &copied)?;
+            let (len, sha256) = digest_file(&copied)?;
+            entries.push(SectionEntry { section, len, sha256 });
+        }
+
+        let manifest = SnapshotManifest {
+            digest: SnapshotManifest::compute_digest(&self.partition, created_at, &entries),
+            partition: self.partition.clone(),
+            created_at,
+            sections: entries,
+        };
+        let raw = serde_json::to_vec_pretty(&manifest).map_err(|e| SnapshotError::Manifest {
+            path: target.clone(),
+            reason: e.to_string(),
+        })?;
+        let temp = target.join(format!("{}.tmp", MANIFEST));
+        let mut file = File::create(&temp)?;
+        file.write_all(&raw)?;
+        file.sync_all()?;
+        fs::rename(&temp, target.join(MANIFEST))?;
+
+        self.prune()?;
+        Ok(manifest)
+    }
+
+    /// Removes all but the newest `keep` complete snapshots
+    fn prune(&self) -> Result<(), SnapshotError> {
+        let snapshots = complete_snapshots(&self.config.directory)?;
+        let excess = snapshots.len().saturating_sub(self.config.keep.max(1));
+        for (_, path) in snapshots.into_iter().take(excess) {
+            fs::remove_dir_all(path)?;
+        }
+        Ok(())
+    }
+
+    /// Takes a snapshot every interval until the process exits
+    pub async fn run(self, runtime: impl Clock) {
+        loop {
+            runtime.sleep(self.config.interval).await;
+            let created_at = runtime
+                .current()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default();
+            match self.checkpoint(created_at) {
+                Ok(manifest) => info!(
+                    sections = manifest.sections.len(),
+                    created_at, "Checkpointed consensus journal"
+                ),
+                Err(e) => warn!(error = %e, "Failed to checkpoint consensus journal"),
+            }
+        }
+    }
+}
+
+/// Checks every section of the snapshot at `path` against its manifest
+pub fn verify(path: &Path) -> Result<SnapshotManifest, SnapshotError> {
+    let invalid = |reason: String| SnapshotError::Manifest {
+        path: path.to_path_buf(),
+        reason,
+    };
+    let raw = fs::read(path.join(MANIFEST))?;
+    let manifest: SnapshotManifest = serde_json::from_slice(&raw).map_err(|e| invalid(e.to_string()))?;
+    let digest = SnapshotManifest::compute_digest(&manifest.partition, manifest.created_at, &manifest.sections);
+    if digest != manifest.digest {
+        return Err(invalid("digest mismatch".into()));
+    }
+    for entry in &manifest.sections {
+        let (len, sha256) = digest_file(&path.join(section_name(entry.section)))?;
+        if len != entry.len || sha256 != entry.sha256 {
+            return Err(SnapshotError::Corrupt {
+                path: path.to_path_buf(),
+                section: entry.section,
+            });
+        }
+    }
+    Ok(manifest)
+}
+
+/// Resolves `path` to a snapshot: `path` itself if it holds a manifest,
+/// otherwise the newest complete snapshot inside it
+pub fn locate(path: &Path) -> Result<PathBuf, SnapshotError> {
+    if path.join(MANIFEST).exists() {
+        return Ok(path.to_path_buf());
+    }
+    complete_snapshots(path)?
+        .pop()
+        .map(|(_, snapshot)| snapshot)
+        .ok_or_else(|| SnapshotError::NotFound(path.to_path_buf()))
+}
+
+/// Verifies the snapshot at `path` and makes it the partition's contents.
+/// The partition it replaces is kept beside it with a `.pre-snapshot-<time>`
+/// suffix.
+pub fn restore(path: &Path, storage_directory: &Path) -> Result<SnapshotManifest, SnapshotError> {
+    let snapshot = locate(path)?;
+    let manifest = verify(&snapshot)?;
+
+    let live = storage_directory.join(&manifest.partition);
+    if live.exists() {
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        let aside = storage_directory.join(format!("{}.pre-snapshot-{}", manifest.partition, stamp));
+        fs::rename(&live, &aside)?;
+        info!(path = %aside.display(), "Moved consensus journal aside");
+    }
+    fs::create_dir_all(&live)?;
+    for entry in &manifest.sections {
+        let name = section_name(entry.section);
+        fs::copy(snapshot.join(&name), live.join(&name))?;
+    }
+    info!(
+        snapshot = %snapshot.display(),
+        sections = manifest.sections.len(),
+        "Restored consensus journal from snapshot"
+    );
+    Ok(manifest)
+}
+
+/// Snapshots in `directory` that have a manifest, oldest first
+fn complete_snapshots(directory: &Path) -> Result<Vec<(u64, PathBuf)>, SnapshotError> {
+    let mut snapshots = Vec::new();
+    if !directory.exists() {
+        return Ok(snapshots);
+    }
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        let Some(created_at) = path.file_name().and_then(|n| n.to_str()).and_then(|n| n.parse().ok()) else {
+            continue;
+        };
+        if path.join(MANIFEST).exists() {
+            snapshots.push((created_at, path));
+        }
+    }
+    snapshots.sort();
+    Ok(snapshots)
+}
+
+/// Sections of a partition directory in ascending order
+pub(crate) fn live_sections(partition: &Path) -> Result<Vec<u64>, SnapshotError> {
+    let mut sections = Vec::new();
+    if !partition.exists() {
+        return Ok(sections);
+    }
+    for entry in fs::read_dir(partition)? {
+        let name = entry?.file_name();
+        if let Some(section) = name.to_str().and_then(parse_section) {
+            sections.push(section);
+        }
+    }
+    sections.sort_unstable();
+    Ok(sections)
+}
+
+fn digest_file(path: &Path) -> Result<(u64, String), SnapshotError> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut len = 0u64;
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        len += read as u64;
+    }
+    Ok((len, hex(&hasher.finalize())))
+}
+
+/// Blob file name of a section, hex of its big-endian bytes
+fn section_name(section: u64) -> String {
+    hex(&section.to_be_bytes())
+}
+
+fn parse_section(name: &str) -> Option<u64> {
+    if name.len() != 16 {
+        return None;
+    }
+    u64::from_str_radix(name, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup(name: &str, sections: &[u64]) -> (PathBuf, Snapshotter) {
+        let root = std::env::temp_dir().join(format!("romer-snapshot-{}-{}", std::process::id(), name));
+        let partition = root.join("storage").join(CONSENSUS_PARTITION);
+        fs::create_dir_all(&partition).unwrap();
+        for section in sections {
+            fs::write(partition.join(section_name(*section)), vec![*section as u8; 100]).unwrap();
+        }
+        let snapshotter = Snapshotter::new(
+            root.join("storage"),
+            CONSENSUS_PARTITION,
+            SnapshotConfig {
+                directory: root.join("snapshots"),
+                interval: Duration::from_secs(60),
+                retained_sections: 2,
+                keep: 2,
+            },
+        );
+        (root, snapshotter)
+    }
+
+    #[test]
+    fn test_checkpoint_and_restore() {
+        let (root, snapshotter) = setup("restore", &[1, 2, 3]);
+        let manifest = snapshotter.checkpoint(100).unwrap();
+        assert_eq!(manifest.sections.iter().map(|s| s.section).collect::<Vec<_>>(), vec![2, 3]);
+
+        let restored = restore(&root.join("snapshots"), &root.join("storage")).unwrap();
+        assert_eq!(restored, manifest);
+        let live = root.join("storage").join(CONSENSUS_PARTITION);
+        assert_eq!(live_sections(&live).unwrap(), vec![2, 3]);
+        assert_eq!(fs::read(live.join(section_name(3))).unwrap(), vec![3u8; 100]);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_tampering_detected() {
+        let (root, snapshotter) = setup("tamper", &[1, 2]);
+        snapshotter.checkpoint(100).unwrap();
+        let snapshot = root.join("snapshots").join("100");
+        fs::write(snapshot.join(section_name(2)), vec![9u8; 100]).unwrap();
+        assert!(matches!(verify(&snapshot), Err(SnapshotError::Corrupt { section: 2, .. })));
+        assert!(restore(&snapshot, &root.join("storage")).is_err());
+        // The live journal is untouched by a failed restore
+        assert_eq!(live_sections(&root.join("storage").join(CONSENSUS_PARTITION)).unwrap(), vec![1, 2]);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_keeps_newest_snapshots() {
+        let (root, snapshotter) = setup("prune", &[1]);
+        for created_at in [100, 200, 300] {
+            snapshotter.checkpoint(created_at).unwrap();
+        }
+        let kept: Vec<u64> = complete_snapshots(&root.join("snapshots"))
+            .unwrap()
+            .into_iter()
+            .map(|(created_at, _)| created_at)
+            .collect();
+        assert_eq!(kept, vec![200, 300]);
+        assert_eq!(locate(&root.join("snapshots")).unwrap(), root.join("snapshots").join("300"));
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,9 @@
+//! Tracking of token supply and monetary statistics.
+//!
+//! The [`SupplyTracker`] is updated with every applied block and keeps a
+//! per-block history of total, circulating, burned and treasury-held supply.
+//! Snapshots are readable through a cloneable [`SupplyQuery`] and exported as
+//! gauges on the validator's metrics endpoint.
+
+mod tracker;
+pub use tracker::{SupplyError, SupplyQuery, SupplySnapshot, SupplyTracker};
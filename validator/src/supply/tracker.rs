@@ -0,0 +1,339 @@
+use crate::application::block::entities::{Block, TransactionType, TransferType};
+use prometheus_client::metrics::gauge::Gauge;
+use prometheus_client::registry::Registry;
+use romer_common::types::address::Address;
+use romer_common::types::tokenomics::{
+    SupplyConfig, TokenomicsConfig, TokenomicsError, BURN_ADDRESS,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum SupplyError {
+    #[error("Block {got} does not follow block {latest}")]
+    NonSequential { latest: u64, got: u64 },
+
+    #[error("Total supply {total} exceeds max supply {max} at block {height}")]
+    MaxSupplyExceeded { height: u64, total: u64, max: u64 },
+
+    #[error("Genesis supply {got} does not match configured initial supply {expected}")]
+    GenesisMismatch { expected: u64, got: u64 },
+
+    #[error("Supply accounting is inconsistent at block {0}")]
+    Inconsistent(u64),
+
+    #[error("Invalid supply configuration: {0}")]
+    Config(#[from] TokenomicsError),
+}
+
+/// Supply figures as of the end of a block
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SupplySnapshot {
+    pub height: u64,
+    /// Tokens in existence (minted minus burned)
+    pub total: u64,
+    /// Tokens held outside the treasury
+    pub circulating: u64,
+    /// Tokens burned since genesis
+    pub burned: u64,
+    /// Tokens held by the treasury account
+    pub treasury: u64,
+}
+
+impl SupplySnapshot {
+    /// Total is always split between the treasury and circulation
+    fn is_consistent(&self) -> bool {
+        self.circulating.checked_add(self.treasury) == Some(self.total)
+    }
+}
+
+/// Read-only view of the supply history, shared with API handlers
+#[derive(Clone, Default)]
+pub struct SupplyQuery {
+    history: Arc<Mutex<BTreeMap<u64, SupplySnapshot>>>,
+}
+
+impl SupplyQuery {
+    /// Most recent snapshot
+    pub fn latest(&self) -> Option<SupplySnapshot> {
+        self.history.lock().unwrap().values().next_back().copied()
+    }
+
+    /// Snapshot as of `height`, i.e. the last snapshot at or below it
+    pub fn at(&self, height: u64) -> Option<SupplySnapshot> {
+        self.history
+            .lock()
+            .unwrap()
+            .range(..=height)
+            .next_back()
+            .map(|(_, s)| *s)
+    }
+
+    /// Snapshots for blocks in `[from, to]`
+    pub fn range(&self, from: u64, to: u64) -> Vec<SupplySnapshot> {
+        self.history
+            .lock()
+            .unwrap()
+            .range(from..=to)
+            .map(|(_, s)| *s)
+            .collect()
+    }
+}
+
+/// Prometheus gauges mirroring the latest snapshot
+#[derive(Clone, Default)]
+struct SupplyGauges {
+    total: Gauge,
+    circulating: Gauge,
+    burned: Gauge,
+    treasury: Gauge,
+}
+
+impl SupplyGauges {
+    fn register(&self, registry: &Arc<Mutex<Registry>>) {
+        let mut registry = registry.lock().unwrap();
+        let registry = registry.sub_registry_with_prefix("romer_supply");
+        registry.register("total", "Tokens in existence", self.total.clone());
+        registry.register("circulating", "Tokens held outside the treasury", self.circulating.clone());
+        registry.register("burned", "Tokens burned since genesis", self.burned.clone());
+        registry.register("treasury", "Tokens held by the treasury", self.treasury.clone());
+    }
+
+    fn set(&self, snapshot: &SupplySnapshot) {
+        self.total.set(snapshot.total as i64);
+        self.circulating.set(snapshot.circulating as i64);
+        self.burned.set(snapshot.burned as i64);
+        self.treasury.set(snapshot.treasury as i64);
+    }
+}
+
+/// Maintains supply statistics as blocks are applied
+pub struct SupplyTracker {
+    config: SupplyConfig,
+    treasury: Address,
+    current: Option<SupplySnapshot>,
+    query: SupplyQuery,
+    gauges: SupplyGauges,
+}
+
+impl SupplyTracker {
+    /// Creates a tracker after validating the supply configuration
+    pub fn new(
+        tokenomics: &TokenomicsConfig,
+        registry: &Arc<Mutex<Registry>>,
+    ) -> Result<(Self, SupplyQuery), SupplyError> {
+        tokenomics.validate()?;
+
+        let gauges = SupplyGauges::default();
+        gauges.register(registry);
+
+        let query = SupplyQuery::default();
+        let tracker = Self {
+            config: tokenomics.supply.clone(),
+            treasury: tokenomics.treasury.account(),
+            current: None,
+            query: query.clone(),
+            gauges,
+        };
+        Ok((tracker, query))
+    }
+
+    /// Applies a block's mints, burns and treasury transfers.
+    ///
+    /// Blocks must be applied in height order starting from genesis.
+    pub fn apply_block(&mut self, block: &Block) -> Result<SupplySnapshot, SupplyError> {
+        let height = block.header.height;
+        let mut next = match self.current {
+            Some(current) if height != current.height + 1 => {
+                return Err(SupplyError::NonSequential { latest: current.height, got: height })
+            }
+            Some(current) => SupplySnapshot { height, ..current },
+            None if height != 0 => {
+                return Err(SupplyError::NonSequential { latest: 0, got: height })
+            }
+            None => SupplySnapshot::default(),
+        };
+
+        for tx in &block.transactions {
+            let TransactionType::TokenTransfer { to, amount, transfer_type } = &tx.transaction_type else {
+                continue;
+            };
+            let amount = *amount;
+            match transfer_type {
+                TransferType::Mint => {
+                    next.total = next.total.saturating_add(amount);
+                    self.credit(&mut next, to, amount);
+                }
+                TransferType::Burn => {
+                    next.total = next.total.saturating_sub(amount);
+                    next.burned = next.burned.saturating_add(amount);
+                    self.debit(&mut next, &tx.from, amount);
+                }
+                TransferType::Normal if Address::new(*to) == BURN_ADDRESS => {
+                    // Sending to the burn address removes tokens from supply
+                    next.total = next.total.saturating_sub(amount);
+                    next.burned = next.burned.saturating_add(amount);
+                    self.debit(&mut next, &tx.from, amount);
+                }
+                TransferType::Normal => {
+                    self.debit(&mut next, &tx.from, amount);
+                    self.credit(&mut next, to, amount);
+                }
+            }
+        }
+
+        if height == 0 && next.total != self.config.initial_supply {
+            return Err(SupplyError::GenesisMismatch {
+                expected: self.config.initial_supply,
+                got: next.total,
+            });
+        }
+        self.check_invariants(&next)?;
+
+        self.current = Some(next);
+        self.query.history.lock().unwrap().insert(height, next);
+        self.gauges.set(&next);
+        Ok(next)
+    }
+
+    /// Verifies a snapshot against the configured supply limits
+    pub fn check_invariants(&self, snapshot: &SupplySnapshot) -> Result<(), SupplyError> {
+        if let Some(max) = self.config.max_supply {
+            if snapshot.total > max {
+                return Err(SupplyError::MaxSupplyExceeded {
+                    height: snapshot.height,
+                    total: snapshot.total,
+                    max,
+                });
+            }
+        }
+        if !snapshot.is_consistent() {
+            return Err(SupplyError::Inconsistent(snapshot.height));
+        }
+        Ok(())
+    }
+
+    fn credit(&self, snapshot: &mut SupplySnapshot, account: &[u8; 32], amount: u64) {
+        if Address::new(*account) == self.treasury {
+            snapshot.treasury = snapshot.treasury.saturating_add(amount);
+        } else {
+            snapshot.circulating = snapshot.circulating.saturating_add(amount);
+        }
+    }
+
+    fn debit(&self, snapshot: &mut SupplySnapshot, account: &[u8; 32], amount: u64) {
+        if Address::new(*account) == self.treasury {
+            snapshot.treasury = snapshot.treasury.saturating_sub(amount);
+        } else {
+            snapshot.circulating = snapshot.circulating.saturating_sub(amount);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::block::entities::{BlockHeader, Transaction};
+    use romer_common::types::keymanager::SignatureScheme;
+    use romer_common::types::address::Address;
+use romer_common::types::tokenomics::{AddressConfig, FeeConfig, TreasuryConfig};
+
+    fn tokenomics(max_supply: Option<u64>) -> TokenomicsConfig {
+        TokenomicsConfig {
+            supply: SupplyConfig { initial_supply: 1_000, max_supply },
+            addresses: AddressConfig::default(),
+            treasury: TreasuryConfig {
+                scheme: SignatureScheme::Ed25519,
+                signers: vec!["aa".into()],
+                threshold: 1,
+                timelock_secs: 0,
+            },
+            fees: FeeConfig::default(),
+        }
+    }
+
+    fn transfer(from: [u8; 32], to: [u8; 32], amount: u64, transfer_type: TransferType) -> Transaction {
+        Transaction {
+            transaction_type: TransactionType::TokenTransfer { to, amount, transfer_type },
+            from,
+            nonce: 0,
+            gas_amount: 0,
+            signature: Vec::new(),
+        }
+    }
+
+    fn block(height: u64, transactions: Vec<Transaction>) -> Block {
+        Block {
+            header: BlockHeader {
+                view: height as u32,
+                height,
+                timestamp: 0,
+                previous_hash: [0u8; 32],
+                transactions_root: [0u8; 32],
+                state_root: [0u8; 32],
+                validator_public_key: [0u8; 32],
+            },
+            transactions,
+        }
+    }
+
+    #[test]
+    fn test_tracks_supply_per_block() {
+        let config = tokenomics(Some(2_000));
+        let treasury = config.treasury.account().into();
+        let registry = Arc::new(Mutex::new(Registry::default()));
+        let (mut tracker, query) = SupplyTracker::new(&config, &registry).unwrap();
+
+        tracker
+            .apply_block(&block(0, vec![transfer([0u8; 32], treasury, 1_000, TransferType::Mint)]))
+            .unwrap();
+        tracker
+            .apply_block(&block(1, vec![
+                transfer(treasury, [1u8; 32], 300, TransferType::Normal),
+                transfer([1u8; 32], BURN_ADDRESS.into(), 50, TransferType::Normal),
+            ]))
+            .unwrap();
+
+        assert_eq!(
+            query.latest(),
+            Some(SupplySnapshot { height: 1, total: 950, circulating: 250, burned: 50, treasury: 700 })
+        );
+        assert_eq!(query.at(0).unwrap().treasury, 1_000);
+        assert_eq!(query.range(0, 1).len(), 2);
+    }
+
+    #[test]
+    fn test_startup_rejects_invalid_config() {
+        let registry = Arc::new(Mutex::new(Registry::default()));
+        assert!(matches!(
+            SupplyTracker::new(&tokenomics(Some(999)), &registry),
+            Err(SupplyError::Config(TokenomicsError::InitialExceedsMax { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_rejects_invariant_violations() {
+        let config = tokenomics(Some(1_500));
+        let treasury = config.treasury.account().into();
+        let registry = Arc::new(Mutex::new(Registry::default()));
+        let (mut tracker, _) = SupplyTracker::new(&config, &registry).unwrap();
+
+        assert_eq!(
+            tracker.apply_block(&block(0, vec![transfer([0u8; 32], treasury, 10, TransferType::Mint)])),
+            Err(SupplyError::GenesisMismatch { expected: 1_000, got: 10 })
+        );
+        tracker
+            .apply_block(&block(0, vec![transfer([0u8; 32], treasury, 1_000, TransferType::Mint)]))
+            .unwrap();
+        assert_eq!(
+            tracker.apply_block(&block(2, vec![])),
+            Err(SupplyError::NonSequential { latest: 0, got: 2 })
+        );
+        assert!(matches!(
+            tracker.apply_block(&block(1, vec![transfer([0u8; 32], [2u8; 32], 600, TransferType::Mint)])),
+            Err(SupplyError::MaxSupplyExceeded { .. })
+        ));
+    }
+}
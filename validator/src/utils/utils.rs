@@ -1,10 +1,10 @@
 use bytes::{BufMut, BytesMut};
-use commonware_cryptography::{ Hasher, Sha256};
+use commonware_cryptography::{Hasher, Sha256};
 
-use crate::block::entities::{Block, Transaction, TransactionType, TransferType};
+use crate::application::block::entities::{Block, CertificateKind, Transaction, TransactionType, TransferType};
 
-/// Provides core hashing functionality for the blockchain using Ed25519
-#[derive(Clone)]
+/// Provides core hashing functionality for the blockchain using SHA-256
+#[derive(Clone, Default)]
 pub struct BlockHasher {
     hasher: Sha256,
 }
@@ -58,7 +58,7 @@ impl BlockHasher {
 
         // Build the Merkle tree level by level
         while hashes.len() > 1 {
-            let mut next_level = Vec::with_capacity((hashes.len() + 1) / 2);
+            let mut next_level = Vec::with_capacity(hashes.len().div_ceil(2));
 
             // Process pairs of hashes
             for chunk in hashes.chunks(2) {
@@ -105,6 +105,25 @@ impl BlockHasher {
                 };
                 buffer.put_u8(transfer_type_value);
             }
+            TransactionType::LatencyReport { report } => {
+                buffer.put_u8(1);
+                buffer.put_u32_le(report.len() as u32);
+                buffer.put_slice(report);
+            }
+            TransactionType::Evidence { evidence } => {
+                buffer.put_u8(2);
+                buffer.put_u32_le(evidence.len() as u32);
+                buffer.put_slice(evidence);
+            }
+            TransactionType::Certificate { kind, proof } => {
+                buffer.put_u8(3);
+                buffer.put_u8(match kind {
+                    CertificateKind::Notarization => 0,
+                    CertificateKind::Finalization => 1,
+                });
+                buffer.put_u32_le(proof.len() as u32);
+                buffer.put_slice(proof);
+            }
         }
 
         // Add remaining transaction fields
@@ -120,19 +139,7 @@ impl BlockHasher {
         result
     }
 
-    pub fn address_to_bytes(&self, address: &str) -> Vec<u8> {
-        // If the address starts with "0x", remove it
-        let clean_address = address.trim_start_matches("0x");
 
-        // Try to decode from hex first
-        if let Ok(bytes) = hex::decode(clean_address) {
-            return bytes;
-        }
-
-        // If not hex, fall back to raw bytes
-        // In production, you might want to handle this case differently
-        address.as_bytes().to_vec()
-    }
     /// Calculate state root from a set of address/balance pairs
     /// Uses a simple concatenation for now - could be upgraded to a Merkle Patricia Trie
     pub fn calculate_state_root(&mut self, state_pairs: &[(Vec<u8>, u64)]) -> [u8; 32] {
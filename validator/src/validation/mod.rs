@@ -1,3 +1 @@
-pub mod hardware_validator;
-pub mod latency_validator;
-pub mod proof_generator;
\ No newline at end of file
+pub mod proof_generator;
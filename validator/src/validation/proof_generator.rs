@@ -1,10 +1,13 @@
 use anyhow::{Context, Result};
 use geo::Point;
-use crate::common::utils::{
-    hardware_validator::{HardwareDetector, VirtualizationType},
-    latency_validator::{LatencyValidator, LatencyConfig},
+use romer_common::utils::hardware_validator::{HardwareDetector, VirtualizationType};
+use crate::location::{
+    default_probe, theoretical_minimum_ms, Analyzer, ConfidenceConfig, ConfidenceReport, HealthConfig,
+    Observation, Probe, ProbeConfig, ReferenceHealth, ReferencePoint,
 };
 use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use tracing::warn;
 
 // Default reference point constants for Frankfurt IX
 const DEFAULT_REF_LAT: f64 = 50.1109;
@@ -15,24 +18,31 @@ pub struct ProofGeneratorBuilder {
     // Validation state
     hardware_validation: Option<VirtualizationType>,
     location_validation: Option<Point<f64>>,
+    location_report: Option<ConfidenceReport>,
     
-    // Reference point for validation
-    reference_point: Point<f64>,
-    reference_ip: IpAddr,
+    // Reference points for validation, weighted by their health
+    references: Arc<Mutex<ReferenceHealth>>,
+    analyzer: Arc<dyn Analyzer>,
     
-    // Latency validator instance
-    latency_validator: LatencyValidator,
+    // How latency to reference points is measured
+    probe: Arc<dyn Probe>,
 }
 
 impl ProofGeneratorBuilder {
     pub fn new() -> Self {
         // Initialize with default Frankfurt reference point
+        let frankfurt = ReferencePoint {
+            name: "DE-CIX Frankfurt".to_string(),
+            location: Point::new(DEFAULT_REF_LON, DEFAULT_REF_LAT),
+            ip: DEFAULT_REF_IP.parse().unwrap(),
+        };
         Self {
             hardware_validation: None,
             location_validation: None,
-            reference_point: Point::new(DEFAULT_REF_LON, DEFAULT_REF_LAT),
-            reference_ip: DEFAULT_REF_IP.parse().unwrap(),
-            latency_validator: LatencyValidator::new(LatencyConfig::default()),
+            location_report: None,
+            references: Arc::new(Mutex::new(ReferenceHealth::new(HealthConfig::default(), vec![frankfurt]))),
+            analyzer: Arc::new(ConfidenceConfig::default()),
+            probe: default_probe(ProbeConfig::default()),
         }
     }
 
@@ -55,36 +65,90 @@ impl ProofGeneratorBuilder {
         }
     }
 
-    /// Validates the claimed location using latency measurements
+    /// Measures the claimed location against every usable reference point
+    /// and scores it with the analyzer. Each point counts by its
+    /// configured and health weights, so an unreachable or flapping anchor
+    /// cannot fail the validation alone.
+    pub async fn assess_location(&self, location: Point<f64>) -> ConfidenceReport {
+        let references = self.references.lock().unwrap().usable();
+        let mut observations = Vec::with_capacity(references.len());
+        for (reference, health_weight) in references {
+            let observation = match self.probe.measure(reference.ip).await {
+                Ok(stats) => Observation::Measured {
+                    theoretical_min_ms: theoretical_minimum_ms(location, reference.location),
+                    measured_ms: stats.median(),
+                },
+                Err(e) => {
+                    warn!(reference = %reference.name, error = %e, "Failed to measure reference point");
+                    Observation::Unreachable { error: e.to_string() }
+                }
+            };
+            observations.push((reference, health_weight, observation));
+        }
+        self.analyzer.analyze(location, observations)
+    }
+
+    /// Validates the claimed location, failing with the confidence report
+    /// if it does not pass
     pub async fn validate_location(mut self, location: Point<f64>) -> Result<Self> {
-        // Perform latency validation against reference point
-        let validation_result = self.latency_validator
-            .validate_latency(
-                location,
-                self.reference_point,
-                self.reference_ip
-            )
-            .await
-            .context("Failed to validate location using latency measurements")?;
-
-        if validation_result.is_valid {
-            self.location_validation = Some(location);
-            Ok(self)
-        } else {
-            Err(anyhow::anyhow!(
-                "Location validation failed: {}", 
-                validation_result.details
-            ))
+        let report = self.assess_location(location).await;
+        if !report.passed {
+            return Err(anyhow::anyhow!(
+                "Location validation failed with confidence {:.2}: {}",
+                report.confidence,
+                report.to_json()
+            ));
         }
+        self.location_validation = Some(location);
+        self.location_report = Some(report);
+        Ok(self)
     }
 
     /// Optionally override the default reference point
     pub fn with_reference(mut self, point: Point<f64>, ip: IpAddr) -> Self {
-        self.reference_point = point;
-        self.reference_ip = ip;
+        let reference = ReferencePoint {
+            name: ip.to_string(),
+            location: point,
+            ip,
+        };
+        self.references = Arc::new(Mutex::new(ReferenceHealth::new(HealthConfig::default(), vec![reference])));
+        self
+    }
+
+    /// Validates against a set of reference points whose health is tracked
+    /// by a running [`HealthChecker`](crate::location::HealthChecker)
+    pub fn with_references(mut self, references: Arc<Mutex<ReferenceHealth>>) -> Self {
+        self.references = references;
+        self
+    }
+
+    /// Replaces the parameters of the confidence model
+    pub fn with_confidence(self, confidence: ConfidenceConfig) -> Self {
+        self.with_analyzer(Arc::new(confidence))
+    }
+
+    /// Judges measurements with `analyzer` instead of the confidence model
+    pub fn with_analyzer(mut self, analyzer: Arc<dyn Analyzer>) -> Self {
+        self.analyzer = analyzer;
+        self
+    }
+
+    /// Measures latency with `probe` instead of the best one available
+    pub fn with_probe(mut self, probe: Arc<dyn Probe>) -> Self {
+        self.probe = probe;
         self
     }
 
+    /// Probe latency is measured with, shared with the health checker
+    pub fn probe(&self) -> Arc<dyn Probe> {
+        self.probe.clone()
+    }
+
+    /// Reference points in use, shared with their health checker
+    pub fn references(&self) -> Arc<Mutex<ReferenceHealth>> {
+        self.references.clone()
+    }
+
     /// Checks if all required validations are complete
     fn validations_complete(&self) -> bool {
         self.hardware_validation.is_some() && self.location_validation.is_some()
@@ -101,6 +165,7 @@ impl ProofGeneratorBuilder {
         Ok(ProofGenerator {
             hardware_validation: self.hardware_validation.unwrap(),
             location_validation: self.location_validation.unwrap(),
+            location_report: self.location_report.unwrap(),
         })
     }
 }
@@ -109,6 +174,7 @@ impl ProofGeneratorBuilder {
 pub struct ProofGenerator {
     hardware_validation: VirtualizationType,
     location_validation: Point<f64>,
+    location_report: ConfidenceReport,
 }
 
 impl ProofGenerator {
@@ -120,4 +186,9 @@ impl ProofGenerator {
     pub fn location(&self) -> &Point<f64> {
         &self.location_validation
     }
+
+    /// Returns the checks the location passed with
+    pub fn location_report(&self) -> &ConfidenceReport {
+        &self.location_report
+    }
 }
\ No newline at end of file
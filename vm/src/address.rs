@@ -0,0 +1,27 @@
+// src/address.rs
+use move_core_types::account_address::AccountAddress;
+use romer_common::types::address::Address;
+
+/// Converts a Romer address into the Move VM's account address.
+/// Both are 32 bytes, so the conversion is lossless.
+pub fn to_account_address(address: &Address) -> AccountAddress {
+    AccountAddress::new(*address.as_bytes())
+}
+
+/// Converts a Move account address back into a Romer address
+pub fn from_account_address(address: &AccountAddress) -> Address {
+    Address::new(address.into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let address = Address::new([3u8; 32]);
+        let account = to_account_address(&address);
+        assert_eq!(account.to_vec(), address.to_vec());
+        assert_eq!(from_account_address(&account), address);
+    }
+}
@@ -0,0 +1,93 @@
+// src/compat/executor.rs
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use move_cli::base::test::{run_move_unit_tests, UnitTestResult};
+use move_package::BuildConfig;
+use move_unit_test::UnitTestingConfig;
+
+use super::{parse_test_output, TestExecutor, TestOutcome};
+use crate::error::VMError;
+use crate::natives::table::build_natives;
+use crate::runtime::gas::GasCostTable;
+
+/// Runs package unit tests with the Move CLI test runner, linked against
+/// Romer's natives instead of Sui's
+pub struct MoveCliExecutor {
+    /// Instruction limit per test, so runaway tests time out
+    pub gas_limit: u64,
+}
+
+impl Default for MoveCliExecutor {
+    fn default() -> Self {
+        Self { gas_limit: 1_000_000 }
+    }
+}
+
+impl TestExecutor for MoveCliExecutor {
+    fn run(&self, package: &Path) -> Result<BTreeMap<String, TestOutcome>, VMError> {
+        let build_config = BuildConfig {
+            test_mode: true,
+            ..BuildConfig::default()
+        };
+        let unit_test_config = UnitTestingConfig {
+            gas_limit: Some(self.gas_limit),
+            ..UnitTestingConfig::default_with_bound(None)
+        };
+
+        let mut output = Vec::new();
+        let (result, _) = run_move_unit_tests(
+            package,
+            build_config,
+            unit_test_config,
+            build_natives(&GasCostTable::default()),
+            None,
+            false,
+            &mut output,
+        )
+        .map_err(|e| VMError::Execution(format!("{}: {}", package.display(), e)))?;
+
+        let outcomes = parse_test_output(&String::from_utf8_lossy(&output));
+        if outcomes.is_empty() && matches!(result, UnitTestResult::Failure) {
+            return Err(VMError::Execution(format!(
+                "{}: test run failed before any test executed",
+                package.display()
+            )));
+        }
+        Ok(outcomes)
+    }
+}
+
+/// Runs package unit tests with the `sui` CLI, on Sui's own natives. Used
+/// to record the baselines Romer is compared against.
+pub struct SuiCliExecutor {
+    /// The `sui` binary
+    pub sui: PathBuf,
+}
+
+impl Default for SuiCliExecutor {
+    fn default() -> Self {
+        Self { sui: PathBuf::from("sui") }
+    }
+}
+
+impl TestExecutor for SuiCliExecutor {
+    fn run(&self, package: &Path) -> Result<BTreeMap<String, TestOutcome>, VMError> {
+        let output = Command::new(&self.sui)
+            .args(["move", "test", "--path"])
+            .arg(package)
+            .output()
+            .map_err(|e| VMError::Execution(format!("failed to run {}: {}", self.sui.display(), e)))?;
+
+        let outcomes = parse_test_output(&String::from_utf8_lossy(&output.stdout));
+        if outcomes.is_empty() {
+            return Err(VMError::Execution(format!(
+                "{}: no test ran on Sui: {}",
+                package.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(outcomes)
+    }
+}
@@ -0,0 +1,310 @@
+// src/compat/mod.rs
+//! Sui Move compatibility suite.
+//!
+//! Runs the unit tests of the Sui framework and a corpus of public Sui
+//! packages against Romer's natives and compares each test's outcome to a
+//! baseline recorded on Sui. Any test whose outcome differs is reported as a
+//! divergence.
+
+pub mod executor;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::VMError;
+
+/// Outcome of a single Move unit test
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestOutcome {
+    Pass,
+    Fail,
+    Timeout,
+}
+
+/// A package in the compatibility corpus
+#[derive(Debug, Clone, Deserialize)]
+pub struct CorpusEntry {
+    pub name: String,
+    /// Package directory, relative to the corpus root
+    pub path: PathBuf,
+    /// Baseline outcomes recorded on Sui, relative to the corpus file
+    pub baseline: Option<PathBuf>,
+}
+
+/// The set of packages the suite runs
+#[derive(Debug, Clone, Deserialize)]
+pub struct Corpus {
+    pub packages: Vec<CorpusEntry>,
+    /// Directory the corpus file was loaded from
+    #[serde(skip)]
+    pub dir: PathBuf,
+}
+
+impl Corpus {
+    pub fn load(path: &Path) -> Result<Self, VMError> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| VMError::Storage(format!("failed to read corpus {}: {}", path.display(), e)))?;
+        let mut corpus: Corpus = serde_json::from_str(&content)
+            .map_err(|e| VMError::Storage(format!("invalid corpus {}: {}", path.display(), e)))?;
+        corpus.dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Ok(corpus)
+    }
+
+    /// Expected outcomes for `entry`. Tests missing from the baseline are expected to pass.
+    pub fn baseline(&self, entry: &CorpusEntry) -> Result<BTreeMap<String, TestOutcome>, VMError> {
+        let Some(baseline) = &entry.baseline else {
+            return Ok(BTreeMap::new());
+        };
+        let path = self.dir.join(baseline);
+        let content = fs::read_to_string(&path)
+            .map_err(|e| VMError::Storage(format!("failed to read baseline {}: {}", path.display(), e)))?;
+        serde_json::from_str(&content)
+            .map_err(|e| VMError::Storage(format!("invalid baseline {}: {}", path.display(), e)))
+    }
+
+    /// Entries whose baseline is missing or records no test, so the suite
+    /// has nothing to hold their outcomes against
+    pub fn unrecorded(&self) -> Vec<&str> {
+        self.packages
+            .iter()
+            .filter(|entry| self.baseline(entry).map_or(true, |baseline| baseline.is_empty()))
+            .map(|entry| entry.name.as_str())
+            .collect()
+    }
+
+    /// Writes the outcomes `entry` had on Sui as its baseline
+    pub fn record(&self, entry: &CorpusEntry, outcomes: &BTreeMap<String, TestOutcome>) -> Result<(), VMError> {
+        let Some(baseline) = &entry.baseline else {
+            return Err(VMError::Storage(format!("{} has no baseline path", entry.name)));
+        };
+        let path = self.dir.join(baseline);
+        let content = serde_json::to_string_pretty(outcomes)
+            .map_err(|e| VMError::Storage(format!("failed to encode baseline {}: {}", path.display(), e)))?;
+        fs::write(&path, content + "\n")
+            .map_err(|e| VMError::Storage(format!("failed to write baseline {}: {}", path.display(), e)))
+    }
+}
+
+/// Runs every package in `corpus` on Sui with `executor` and records its
+/// outcomes as the package's baseline. Returns the packages that failed.
+pub fn record_corpus<E: TestExecutor>(corpus: &Corpus, root: &Path, executor: &E) -> BTreeMap<String, String> {
+    let mut errors = BTreeMap::new();
+    for entry in &corpus.packages {
+        let recorded = executor
+            .run(&root.join(&entry.path))
+            .and_then(|outcomes| corpus.record(entry, &outcomes));
+        if let Err(e) = recorded {
+            errors.insert(entry.name.clone(), e.to_string());
+        }
+    }
+    errors
+}
+
+/// Runs a package's unit tests and reports each test's outcome
+pub trait TestExecutor {
+    fn run(&self, package: &Path) -> Result<BTreeMap<String, TestOutcome>, VMError>;
+}
+
+/// A test whose outcome on Romer differs from Sui
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Divergence {
+    pub package: String,
+    pub test: String,
+    pub expected: TestOutcome,
+    /// `None` if the test did not run at all
+    pub actual: Option<TestOutcome>,
+}
+
+/// Summary of one suite run
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CompatReport {
+    pub packages: usize,
+    pub tests: usize,
+    pub matching: usize,
+    pub divergences: Vec<Divergence>,
+    /// Packages that failed to build or run
+    pub errors: BTreeMap<String, String>,
+}
+
+impl CompatReport {
+    /// Share of tests whose outcome matches Sui, in percent
+    pub fn compatibility(&self) -> f64 {
+        if self.tests == 0 {
+            return 0.0;
+        }
+        self.matching as f64 * 100.0 / self.tests as f64
+    }
+
+    pub fn is_compatible(&self) -> bool {
+        self.divergences.is_empty() && self.errors.is_empty()
+    }
+}
+
+/// Compares observed outcomes against a baseline
+pub fn compare(
+    package: &str,
+    baseline: &BTreeMap<String, TestOutcome>,
+    actual: &BTreeMap<String, TestOutcome>,
+) -> (usize, Vec<Divergence>) {
+    let mut matching = 0;
+    let mut divergences = Vec::new();
+
+    for (test, outcome) in actual {
+        let expected = baseline.get(test).copied().unwrap_or(TestOutcome::Pass);
+        if expected == *outcome {
+            matching += 1;
+        } else {
+            divergences.push(Divergence {
+                package: package.to_string(),
+                test: test.clone(),
+                expected,
+                actual: Some(*outcome),
+            });
+        }
+    }
+
+    // Tests Sui ran that we never saw
+    for (test, expected) in baseline {
+        if !actual.contains_key(test) {
+            divergences.push(Divergence {
+                package: package.to_string(),
+                test: test.clone(),
+                expected: *expected,
+                actual: None,
+            });
+        }
+    }
+
+    (matching, divergences)
+}
+
+/// Runs every package in `corpus`, with package paths resolved against `root`
+pub fn run_corpus<E: TestExecutor>(corpus: &Corpus, root: &Path, executor: &E) -> CompatReport {
+    let mut report = CompatReport::default();
+
+    for entry in &corpus.packages {
+        report.packages += 1;
+        let result = corpus
+            .baseline(entry)
+            .and_then(|baseline| executor.run(&root.join(&entry.path)).map(|actual| (baseline, actual)));
+
+        match result {
+            Ok((baseline, actual)) => {
+                let (matching, divergences) = compare(&entry.name, &baseline, &actual);
+                report.tests += actual.len() + divergences.iter().filter(|d| d.actual.is_none()).count();
+                report.matching += matching;
+                report.divergences.extend(divergences);
+            }
+            Err(e) => {
+                report.errors.insert(entry.name.clone(), e.to_string());
+            }
+        }
+    }
+
+    report
+}
+
+/// Parses the per-test lines printed by the Move unit test runner, e.g.
+/// `[ PASS    ] 0x2::coin::test_split`
+pub fn parse_test_output(output: &str) -> BTreeMap<String, TestOutcome> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let rest = line.strip_prefix('[')?;
+            let (status, name) = rest.split_once(']')?;
+            let outcome = match status.trim() {
+                "PASS" => TestOutcome::Pass,
+                "FAIL" => TestOutcome::Fail,
+                "TIMEOUT" => TestOutcome::Timeout,
+                _ => return None,
+            };
+            Some((name.trim().to_string(), outcome))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_test_output() {
+        let output = "\
+INCLUDING DEPENDENCY Sui
+Running Move unit tests
+[ PASS    ] 0x2::coin::test_split
+[ FAIL    ] 0x2::coin::test_join
+[ TIMEOUT ] 0x2::table::test_big
+Test result: FAILED. Total tests: 3; passed: 1; failed: 2";
+
+        let outcomes = parse_test_output(output);
+        assert_eq!(outcomes.len(), 3);
+        assert_eq!(outcomes["0x2::coin::test_split"], TestOutcome::Pass);
+        assert_eq!(outcomes["0x2::coin::test_join"], TestOutcome::Fail);
+        assert_eq!(outcomes["0x2::table::test_big"], TestOutcome::Timeout);
+    }
+
+    struct FixedExecutor(BTreeMap<String, TestOutcome>);
+
+    impl TestExecutor for FixedExecutor {
+        fn run(&self, _package: &Path) -> Result<BTreeMap<String, TestOutcome>, VMError> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[test]
+    fn test_divergences_reported() {
+        let corpus = Corpus {
+            packages: vec![CorpusEntry {
+                name: "pkg".into(),
+                path: "pkg".into(),
+                baseline: None,
+            }],
+            dir: PathBuf::new(),
+        };
+        let executor = FixedExecutor(BTreeMap::from([
+            ("a".to_string(), TestOutcome::Pass),
+            ("b".to_string(), TestOutcome::Fail),
+        ]));
+
+        let report = run_corpus(&corpus, Path::new("."), &executor);
+        assert_eq!(report.tests, 2);
+        assert_eq!(report.matching, 1);
+        assert_eq!(report.divergences[0].test, "b");
+        assert_eq!(report.compatibility(), 50.0);
+        assert!(!report.is_compatible());
+    }
+
+    #[test]
+    fn test_recorded_baselines() {
+        let dir = tempfile::tempdir().unwrap();
+        let corpus = Corpus {
+            packages: vec![CorpusEntry {
+                name: "pkg".into(),
+                path: "pkg".into(),
+                baseline: Some("pkg.json".into()),
+            }],
+            dir: dir.path().to_path_buf(),
+        };
+        fs::write(dir.path().join("pkg.json"), "{}").unwrap();
+        assert_eq!(corpus.unrecorded(), vec!["pkg"]);
+
+        let executor = FixedExecutor(BTreeMap::from([("a".to_string(), TestOutcome::Fail)]));
+        assert!(record_corpus(&corpus, Path::new("."), &executor).is_empty());
+        assert!(corpus.unrecorded().is_empty());
+        assert_eq!(corpus.baseline(&corpus.packages[0]).unwrap()["a"], TestOutcome::Fail);
+    }
+
+    #[test]
+    fn test_missing_tests_diverge() {
+        let baseline = BTreeMap::from([("gone".to_string(), TestOutcome::Pass)]);
+        let (matching, divergences) = compare("pkg", &baseline, &BTreeMap::new());
+        assert_eq!(matching, 0);
+        assert_eq!(divergences[0].actual, None);
+    }
+}
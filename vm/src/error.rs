@@ -1,6 +1,7 @@
 // src/error.rs
 use thiserror::Error;
 use std::error;
+use romer_common::types::rejection::RejectReason;
 
 #[derive(Error, Debug)]
 pub enum VMError {
@@ -16,6 +17,43 @@ pub enum VMError {
     #[error("Verification failed: {0}")]
     Verification(String),
 
+    #[error("Module {module} failed verification: {reason}")]
+    ModuleVerification { module: String, reason: String },
+
+    #[error("Struct {module}::{name} rejected: {reason}")]
+    StructLayout { module: String, name: String, reason: String },
+
+    #[error("Function {module}::{function} rejected: {reason}")]
+    FunctionVerification { module: String, function: String, reason: String },
+
+    #[error("Function {module}::{function} uses denied native {native}")]
+    DeniedNative { module: String, function: String, native: String },
+
+    #[error("Package import failed: {0}")]
+    Import(String),
+
+    #[error("Transaction rejected: {0}: {1}")]
+    Rejected(RejectReason, String),
+
+    #[error("Aborted in {module} with code {code}")]
+    Abort { module: String, code: u64 },
+
+    #[error("Out of gas, limit {limit}")]
+    OutOfGas { limit: u64 },
+
     #[error(transparent)]
     Common(#[from] Box<dyn error::Error + Send + Sync>),
-}
\ No newline at end of file
+}
+
+impl VMError {
+    /// Catalogue reason reported to whoever submitted the failing
+    /// transaction
+    pub fn reject_reason(&self) -> RejectReason {
+        match self {
+            VMError::Abort { module, code } => RejectReason::from_abort(module, *code),
+            VMError::Rejected(reason, _) => *reason,
+            VMError::OutOfGas { .. } => RejectReason::OutOfGas,
+            _ => RejectReason::Other,
+        }
+    }
+}
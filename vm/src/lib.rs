@@ -12,9 +12,20 @@ mod storage;
 mod verifier;
 mod package;
 mod error;
+mod address;
+
+#[cfg(feature = "compat")]
+pub mod compat;
 
 pub use vm::RomerVM;
-pub use package::deployer::SuiPackageDeployer;
+pub use package::deployer::{DeployerConfig, ProverCli, SpecVerifier, SuiPackageDeployer};
+pub use package::importer::{ImportReport, PackageImporter, PackageSource, SuiRpcSource};
+pub use package::verification::{SourceBundle, VerificationStatus};
+pub use address::{from_account_address, to_account_address};
+pub use runtime::fees::{BurnEvent, FeeSettlement};
+pub use runtime::gas::{GasCostTable, GasMeter, GasUsage};
+pub use runtime::execution::{ExecutionResult, ExecutionStatus};
+pub use verifier::{RomerVerifier, VerifierConfig};
 
 // Re-export common types that users of the VM will need
 pub use crate::error::VMError;
\ No newline at end of file
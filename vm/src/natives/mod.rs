@@ -1,2 +1,3 @@
 // src/natives/mod.rs
+pub mod orderbook;
 pub mod table;
\ No newline at end of file
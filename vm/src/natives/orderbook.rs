@@ -0,0 +1,276 @@
+// src/natives/orderbook.rs
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use move_binary_format::errors::PartialVMResult;
+use move_core_types::gas_algebra::InternalGas;
+use move_core_types::vm_status::sub_status::NFE_OUT_OF_GAS;
+use move_vm_runtime::native_functions::{NativeContext, NativeFunction};
+use move_vm_types::loaded_data::runtime_types::Type;
+use move_vm_types::natives::function::NativeResult;
+use move_vm_types::pop_arg;
+use move_vm_types::values::{Reference, Value, VectorRef};
+
+use crate::runtime::gas::GasCostTable;
+
+/// Module of the framework the natives are declared in, `romer::orderbook`
+pub const MODULE: &str = "orderbook";
+
+/// Abort codes, matching the constants of `romer::orderbook`
+pub const E_LENGTH_MISMATCH: u64 = 1;
+pub const E_ZERO_PRICE: u64 = 2;
+pub const E_ZERO_QUANTITY: u64 = 3;
+pub const E_UNSORTED: u64 = 4;
+pub const E_QUANTITY_OVERFLOW: u64 = 5;
+
+/// One side of a book as Move holds it: prices best first, bids descending
+/// and asks ascending, with the quantity resting at each
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BookSide {
+    pub is_bid: bool,
+    pub prices: Vec<u64>,
+    pub quantities: Vec<u64>,
+}
+
+/// Quantity taken from a side and what it cost
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Fill {
+    pub filled: u64,
+    /// Sum of price times quantity of every level taken from
+    pub cost: u128,
+}
+
+/// Whether price `a` is strictly better than `b` on a bid or ask side
+fn better(is_bid: bool, a: u64, b: u64) -> bool {
+    if is_bid {
+        a > b
+    } else {
+        a < b
+    }
+}
+
+impl BookSide {
+    /// Checks the side is well formed, returning the abort code if not
+    pub fn new(is_bid: bool, prices: Vec<u64>, quantities: Vec<u64>) -> Result<Self, u64> {
+        if prices.len() != quantities.len() {
+            return Err(E_LENGTH_MISMATCH);
+        }
+        let side = Self {
+            is_bid,
+            prices,
+            quantities,
+        };
+        let ordered = side.prices.windows(2).all(|pair| better(is_bid, pair[0], pair[1]));
+        if !ordered || side.quantities.contains(&0) {
+            return Err(E_UNSORTED);
+        }
+        Ok(side)
+    }
+
+    /// Adds `quantity` at `price`, to its level or as a new level in order
+    pub fn insert(&mut self, price: u64, quantity: u64) -> Result<(), u64> {
+        if price == 0 {
+            return Err(E_ZERO_PRICE);
+        }
+        if quantity == 0 {
+            return Err(E_ZERO_QUANTITY);
+        }
+        let index = self.prices.partition_point(|level| better(self.is_bid, *level, price));
+        if self.prices.get(index) == Some(&price) {
+            self.quantities[index] = self.quantities[index]
+                .checked_add(quantity)
+                .ok_or(E_QUANTITY_OVERFLOW)?;
+        } else {
+            self.prices.insert(index, price);
+            self.quantities.insert(index, quantity);
+        }
+        Ok(())
+    }
+
+    pub fn best(&self) -> Option<(u64, u64)> {
+        Some((*self.prices.first()?, *self.quantities.first()?))
+    }
+
+    /// Takes up to `quantity` best level first, from levels no worse than
+    /// `limit_price` for the taker, removing the levels emptied
+    pub fn take(&mut self, limit_price: u64, quantity: u64) -> Fill {
+        let mut fill = Fill::default();
+        let mut emptied = 0;
+        for (price, resting) in self.prices.iter().zip(self.quantities.iter_mut()) {
+            if fill.filled == quantity || better(self.is_bid, limit_price, *price) {
+                break;
+            }
+            let taken = (*resting).min(quantity - fill.filled);
+            *resting -= taken;
+            fill.filled += taken;
+            fill.cost += u128::from(*price) * u128::from(taken);
+            if *resting == 0 {
+                emptied += 1;
+            }
+        }
+        self.prices.drain(..emptied);
+        self.quantities.drain(..emptied);
+        fill
+    }
+}
+
+/// Bytes of a side's levels, which natives are charged for
+fn levels_bytes(prices: &[u64], quantities: &[u64]) -> usize {
+    8 * (prices.len() + quantities.len())
+}
+
+/// Charges `function`'s cost for `bytes` of arguments against the budget
+/// the VM gave the native, before any work is done. Past the budget the
+/// native fails with the whole budget used.
+fn charge(context: &mut NativeContext, costs: &GasCostTable, function: &str, bytes: usize) -> Result<(), NativeResult> {
+    if context.charge_gas(InternalGas::new(costs.native_cost(MODULE, function, bytes))) {
+        Ok(())
+    } else {
+        Err(NativeResult::err(context.gas_budget(), NFE_OUT_OF_GAS))
+    }
+}
+
+/// Reports what the native charged, which the VM takes from the meter
+fn result(context: &NativeContext, outcome: Result<Vec<Value>, u64>) -> PartialVMResult<NativeResult> {
+    let cost = context.gas_used();
+    Ok(match outcome {
+        Ok(values) => NativeResult::ok(cost, values.into()),
+        Err(code) => NativeResult::err(cost, code),
+    })
+}
+
+/// `insert_level(prices, quantities, price, quantity, is_bid): (vector<u64>, vector<u64>)`
+fn native_insert_level(
+    costs: &GasCostTable,
+    context: &mut NativeContext,
+    ty_args: Vec<Type>,
+    mut args: VecDeque<Value>,
+) -> PartialVMResult<NativeResult> {
+    debug_assert!(ty_args.is_empty() && args.len() == 5);
+    let is_bid = pop_arg!(args, bool);
+    let quantity = pop_arg!(args, u64);
+    let price = pop_arg!(args, u64);
+    let quantities = pop_arg!(args, Vec<u64>);
+    let prices = pop_arg!(args, Vec<u64>);
+    if let Err(out_of_gas) = charge(context, costs, "insert_level", levels_bytes(&prices, &quantities)) {
+        return Ok(out_of_gas);
+    }
+    result(context, BookSide::new(is_bid, prices, quantities).and_then(|mut side| {
+        side.insert(price, quantity)?;
+        Ok(vec![Value::vector_u64(side.prices), Value::vector_u64(side.quantities)])
+    }))
+}
+
+/// `best_level(&prices, &quantities): (bool, u64, u64)`. Reads only the
+/// first level, so the side is not checked for order.
+fn native_best_level(
+    costs: &GasCostTable,
+    context: &mut NativeContext,
+    ty_args: Vec<Type>,
+    mut args: VecDeque<Value>,
+) -> PartialVMResult<NativeResult> {
+    debug_assert!(ty_args.is_empty() && args.len() == 2);
+    let quantities = pop_arg!(args, VectorRef);
+    let prices = pop_arg!(args, VectorRef);
+    if let Err(out_of_gas) = charge(context, costs, "best_level", 0) {
+        return Ok(out_of_gas);
+    }
+    let len = prices.len(&Type::U64)?.value_as::<u64>()?;
+    if len != quantities.len(&Type::U64)?.value_as::<u64>()? {
+        return result(context, Err(E_LENGTH_MISMATCH));
+    }
+    if len == 0 {
+        return result(context, Ok(vec![Value::bool(false), Value::u64(0), Value::u64(0)]));
+    }
+    let first = |levels: &VectorRef| -> PartialVMResult<u64> {
+        levels
+            .borrow_elem(0, &Type::U64)?
+            .value_as::<Reference>()?
+            .read_ref()?
+            .value_as::<u64>()
+    };
+    let best = vec![Value::bool(true), Value::u64(first(&prices)?), Value::u64(first(&quantities)?)];
+    result(context, Ok(best))
+}
+
+/// `match_quantity(prices, quantities, limit_price, quantity, is_bid): (vector<u64>, vector<u64>, u64, u128)`
+fn native_match_quantity(
+    costs: &GasCostTable,
+    context: &mut NativeContext,
+    ty_args: Vec<Type>,
+    mut args: VecDeque<Value>,
+) -> PartialVMResult<NativeResult> {
+    debug_assert!(ty_args.is_empty() && args.len() == 5);
+    let is_bid = pop_arg!(args, bool);
+    let quantity = pop_arg!(args, u64);
+    let limit_price = pop_arg!(args, u64);
+    let quantities = pop_arg!(args, Vec<u64>);
+    let prices = pop_arg!(args, Vec<u64>);
+    if let Err(out_of_gas) = charge(context, costs, "match_quantity", levels_bytes(&prices, &quantities)) {
+        return Ok(out_of_gas);
+    }
+    result(context, BookSide::new(is_bid, prices, quantities).map(|mut side| {
+        let fill = side.take(limit_price, quantity);
+        vec![
+            Value::vector_u64(side.prices),
+            Value::vector_u64(side.quantities),
+            Value::u64(fill.filled),
+            Value::u128(fill.cost),
+        ]
+    }))
+}
+
+type Native = fn(&GasCostTable, &mut NativeContext, Vec<Type>, VecDeque<Value>) -> PartialVMResult<NativeResult>;
+
+/// `function` charged under `costs`
+fn native(costs: &GasCostTable, function: Native) -> NativeFunction {
+    let costs = costs.clone();
+    Arc::new(move |context, ty_args, args| function(&costs, context, ty_args, args))
+}
+
+/// Every native of `romer::orderbook`, by function name, charged under
+/// `native:orderbook::<function>` of `costs`
+pub fn natives(costs: &GasCostTable) -> Vec<(&'static str, NativeFunction)> {
+    vec![
+        ("insert_level", native(costs, native_insert_level)),
+        ("best_level", native(costs, native_best_level)),
+        ("match_quantity", native(costs, native_match_quantity)),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_and_match() {
+        let mut asks = BookSide::new(false, vec![], vec![]).unwrap();
+        asks.insert(102, 5).unwrap();
+        asks.insert(100, 3).unwrap();
+        asks.insert(101, 4).unwrap();
+        asks.insert(100, 2).unwrap();
+        assert_eq!((asks.prices.clone(), asks.quantities.clone()), (vec![100, 101, 102], vec![5, 4, 5]));
+        assert_eq!(asks.best(), Some((100, 5)));
+        assert_eq!(asks.insert(0, 1), Err(E_ZERO_PRICE));
+        assert_eq!(asks.insert(100, 0), Err(E_ZERO_QUANTITY));
+        assert_eq!(asks.insert(100, u64::MAX), Err(E_QUANTITY_OVERFLOW));
+
+        // Stops at the limit price, leaving the partly filled level
+        let fill = asks.take(101, 7);
+        assert_eq!(fill, Fill { filled: 7, cost: 5 * 100 + 2 * 101 });
+        assert_eq!((asks.prices.clone(), asks.quantities.clone()), (vec![101, 102], vec![2, 5]));
+        assert_eq!(asks.take(101, 10), Fill { filled: 2, cost: 202 });
+        assert_eq!(asks.best(), Some((102, 5)));
+
+        let mut bids = BookSide::new(true, vec![99, 98], vec![1, 1]).unwrap();
+        bids.insert(100, 1).unwrap();
+        assert_eq!(bids.prices, vec![100, 99, 98]);
+        assert_eq!(bids.take(0, 3).filled, 3);
+        assert_eq!(bids.best(), None);
+
+        assert_eq!(BookSide::new(true, vec![98, 99], vec![1, 1]), Err(E_UNSORTED));
+        assert_eq!(BookSide::new(false, vec![98, 98], vec![1, 1]), Err(E_UNSORTED));
+        assert_eq!(BookSide::new(false, vec![98], vec![0]), Err(E_UNSORTED));
+        assert_eq!(BookSide::new(false, vec![98], vec![]), Err(E_LENGTH_MISMATCH));
+    }
+}
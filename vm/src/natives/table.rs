@@ -1,7 +1,24 @@
 // src/natives/table.rs
+use move_core_types::identifier::Identifier;
 use move_vm_runtime::native_functions::NativeFunctionTable;
+use romer_framework::ROMER_FRAMEWORK_ADDRESS;
 
-pub fn build_natives() -> NativeFunctionTable {
-    // Start with an empty native function table
-    NativeFunctionTable::new()
-}
\ No newline at end of file
+use super::orderbook;
+use crate::runtime::gas::GasCostTable;
+
+/// Romer's natives, each charging its cost under `costs`
+pub fn build_natives(costs: &GasCostTable) -> NativeFunctionTable {
+    let mut natives = NativeFunctionTable::new();
+
+    // Order book primitives, declared in `romer::orderbook`
+    let module = Identifier::new(orderbook::MODULE).expect("valid module name");
+    for (name, function) in orderbook::natives(costs) {
+        natives.push((
+            ROMER_FRAMEWORK_ADDRESS,
+            module.clone(),
+            Identifier::new(name).expect("valid function name"),
+            function,
+        ));
+    }
+    natives
+}
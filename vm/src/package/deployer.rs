@@ -1,8 +1,249 @@
 // src/package/deployer.rs
-pub struct SuiPackageDeployer;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+use move_core_types::language_storage::ModuleId;
+use tracing::info;
+
+use super::verification::{MovePackageCompiler, SourceCompiler};
+use crate::error::VMError;
+use crate::storage::modules::ModuleStore;
+use crate::verifier::{RomerVerifier, VerifierConfig};
+
+/// Deployment policy
+#[derive(Debug, Clone)]
+pub struct DeployerConfig {
+    /// Run the spec verifier on packages that contain specs, and reject
+    /// publication if verification fails
+    pub verify_specs: bool,
+    /// Reject packages without any specs when `verify_specs` is set.
+    /// Intended for critical settlement modules.
+    pub require_specs: bool,
+    /// Limits and denied natives every compiled module is checked against
+    pub verifier: VerifierConfig,
+}
+
+impl Default for DeployerConfig {
+    fn default() -> Self {
+        Self {
+            verify_specs: false,
+            require_specs: false,
+            verifier: VerifierConfig::default(),
+        }
+    }
+}
+
+/// Formal verification hook run before a package is accepted
+pub trait SpecVerifier: Send + Sync {
+    fn verify(&self, package: &Path) -> Result<(), String>;
+}
+
+/// Runs the Move Prover as an external process
+pub struct ProverCli {
+    /// Prover command, e.g. `sui` (invoked as `sui move prove`)
+    pub program: String,
+    pub args: Vec<String>,
+    pub timeout: Duration,
+}
+
+impl Default for ProverCli {
+    fn default() -> Self {
+        Self {
+            program: "sui".to_string(),
+            args: vec!["move".to_string(), "prove".to_string()],
+            timeout: Duration::from_secs(600),
+        }
+    }
+}
+
+impl SpecVerifier for ProverCli {
+    fn verify(&self, package: &Path) -> Result<(), String> {
+        let output = Command::new(&self.program)
+            .args(&self.args)
+            .arg("--path")
+            .arg(package)
+            .arg("--")
+            .arg(format!("--timeout={}", self.timeout.as_secs()))
+            .output()
+            .map_err(|e| format!("failed to run prover: {}", e))?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(String::from_utf8_lossy(&output.stderr).into_owned())
+        }
+    }
+}
+
+/// Returns whether any source file of the package declares a spec block
+pub fn has_specs(package: &Path) -> Result<bool, VMError> {
+    fn visit(dir: &Path) -> std::io::Result<bool> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                if visit(&path)? {
+                    return Ok(true);
+                }
+            } else if path.extension().is_some_and(|e| e == "move") {
+                let content = fs::read_to_string(&path)?;
+                if content.lines().any(|l| l.trim_start().starts_with("spec ")) {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    let sources = package.join("sources");
+    if !sources.exists() {
+        return Ok(false);
+    }
+    visit(&sources).map_err(|e| VMError::Storage(e.to_string()))
+}
+
+/// Compiles Sui Move packages and publishes them into the module store
+pub struct SuiPackageDeployer {
+    config: DeployerConfig,
+    verifier: RomerVerifier,
+    compiler: Box<dyn SourceCompiler + Send + Sync>,
+    spec_verifier: Box<dyn SpecVerifier>,
+}
 
 impl SuiPackageDeployer {
     pub fn new() -> Self {
-        Self {}
+        Self::with_config(DeployerConfig::default())
+    }
+
+    pub fn with_config(config: DeployerConfig) -> Self {
+        Self {
+            verifier: RomerVerifier::new(config.verifier.clone()),
+            config,
+            compiler: Box::new(MovePackageCompiler),
+            spec_verifier: Box::new(ProverCli::default()),
+        }
+    }
+
+    /// Replaces the spec verification hook
+    pub fn with_spec_verifier(mut self, verifier: impl SpecVerifier + 'static) -> Self {
+        self.spec_verifier = Box::new(verifier);
+        self
+    }
+
+    /// Replaces the compiler
+    pub fn with_compiler(mut self, compiler: impl SourceCompiler + Send + Sync + 'static) -> Self {
+        self.compiler = Box::new(compiler);
+        self
+    }
+
+    /// Checks specs if configured, then compiles the package and publishes
+    /// it if every module passes verification
+    pub fn deploy_package(
+        &self,
+        package: &Path,
+        store: &mut ModuleStore,
+    ) -> Result<Vec<ModuleId>, VMError> {
+        if self.config.verify_specs {
+            if has_specs(package)? {
+                self.spec_verifier.verify(package).map_err(|e| {
+                    VMError::Verification(format!("spec verification failed: {}", e))
+                })?;
+                info!(package = %package.display(), "Package specs verified");
+            } else if self.config.require_specs {
+                return Err(VMError::Verification(format!(
+                    "package {} has no specs",
+                    package.display()
+                )));
+            }
+        }
+
+        let modules = self
+            .compiler
+            .compile(package)
+            .map_err(VMError::ModuleDeployment)?;
+
+        for bytes in modules.values() {
+            self.verifier.verify_bytes(bytes)?;
+        }
+        modules
+            .into_values()
+            .map(|bytes| store.store_module(bytes))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    struct NoModules;
+
+    impl SourceCompiler for NoModules {
+        fn compile(&self, _dir: &Path) -> Result<BTreeMap<String, Vec<u8>>, String> {
+            Ok(BTreeMap::new())
+        }
+    }
+
+    struct CountingVerifier {
+        calls: Arc<AtomicUsize>,
+        result: Result<(), String>,
+    }
+
+    impl SpecVerifier for CountingVerifier {
+        fn verify(&self, _package: &Path) -> Result<(), String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.result.clone()
+        }
+    }
+
+    fn package(source: &str) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("sources")).unwrap();
+        fs::write(dir.path().join("sources/m.move"), source).unwrap();
+        dir
+    }
+
+    fn deployer(config: DeployerConfig, result: Result<(), String>) -> (SuiPackageDeployer, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let deployer = SuiPackageDeployer::with_config(config)
+            .with_compiler(NoModules)
+            .with_spec_verifier(CountingVerifier { calls: calls.clone(), result });
+        (deployer, calls)
+    }
+
+    const WITH_SPEC: &str = "module a::m {\n    fun f() {}\n    spec f { aborts_if false; }\n}";
+
+    #[test]
+    fn test_specs_checked_only_when_enabled() {
+        let dir = package(WITH_SPEC);
+        let mut store = ModuleStore::new();
+
+        let (disabled, calls) = deployer(DeployerConfig::default(), Err("fail".into()));
+        assert!(disabled.deploy_package(dir.path(), &mut store).is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        let config = DeployerConfig { verify_specs: true, require_specs: false, ..Default::default() };
+        let (enabled, calls) = deployer(config, Err("fail".into()));
+        assert!(enabled.deploy_package(dir.path(), &mut store).is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_require_specs() {
+        let dir = package("module a::m { fun f() {} }");
+        let mut store = ModuleStore::new();
+
+        let config = DeployerConfig { verify_specs: true, require_specs: false, ..Default::default() };
+        let (lenient, calls) = deployer(config, Ok(()));
+        assert!(lenient.deploy_package(dir.path(), &mut store).is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        let config = DeployerConfig { verify_specs: true, require_specs: true, ..Default::default() };
+        let (strict, _) = deployer(config, Ok(()));
+        assert!(strict.deploy_package(dir.path(), &mut store).is_err());
     }
 }
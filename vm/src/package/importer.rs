@@ -0,0 +1,357 @@
+// src/package/importer.rs
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use move_binary_format::CompiledModule;
+use move_core_types::account_address::AccountAddress;
+use romer_common::types::address::Address;
+use romer_framework::ROMER_FRAMEWORK_ADDRESS;
+use serde::Deserialize;
+use serde_json::json;
+use sha3::{Digest, Sha3_256};
+use tracing::info;
+
+use crate::error::VMError;
+use crate::storage::modules::ModuleStore;
+use crate::verifier::RomerVerifier;
+
+/// Packages at these addresses are provided by the framework and never imported
+pub const FRAMEWORK_ADDRESSES: [AccountAddress; 4] = [
+    AccountAddress::ONE,
+    AccountAddress::TWO,
+    AccountAddress::THREE,
+    ROMER_FRAMEWORK_ADDRESS,
+];
+
+/// A package as published on Sui
+#[derive(Debug, Clone)]
+pub struct FetchedPackage {
+    /// Address the package was fetched from
+    pub id: AccountAddress,
+    /// Module name to bytecode
+    pub modules: BTreeMap<String, Vec<u8>>,
+    /// Original package id to the id of the version this package links against
+    pub linkage: BTreeMap<AccountAddress, AccountAddress>,
+}
+
+/// Somewhere packages can be fetched from
+#[allow(async_fn_in_trait)]
+pub trait PackageSource {
+    async fn fetch(&self, id: AccountAddress) -> Result<FetchedPackage, VMError>;
+}
+
+/// Fetches packages from a Sui fullnode over JSON-RPC
+pub struct SuiRpcSource {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl SuiRpcSource {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RpcResponse {
+    result: Option<ObjectResponse>,
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct ObjectResponse {
+    data: Option<ObjectData>,
+}
+
+#[derive(Deserialize)]
+struct ObjectData {
+    bcs: Option<PackageBcs>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PackageBcs {
+    data_type: String,
+    module_map: BTreeMap<String, String>,
+    #[serde(default)]
+    linkage_table: BTreeMap<String, UpgradeInfo>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpgradeInfo {
+    upgraded_id: String,
+}
+
+fn parse_address(s: &str) -> Result<AccountAddress, VMError> {
+    AccountAddress::from_hex_literal(s)
+        .map_err(|e| VMError::Import(format!("invalid address {}: {}", s, e)))
+}
+
+impl PackageSource for SuiRpcSource {
+    async fn fetch(&self, id: AccountAddress) -> Result<FetchedPackage, VMError> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "sui_getObject",
+            "params": [id.to_hex_literal(), { "showBcs": true }],
+        });
+
+        let response: RpcResponse = self
+            .client
+            .post(&self.url)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| VMError::Import(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| VMError::Import(e.to_string()))?;
+
+        if let Some(error) = response.error {
+            return Err(VMError::Import(format!("RPC error fetching {}: {}", id, error)));
+        }
+        let bcs = response
+            .result
+            .and_then(|r| r.data)
+            .and_then(|d| d.bcs)
+            .ok_or_else(|| VMError::Import(format!("object {} not found", id)))?;
+        if bcs.data_type != "package" {
+            return Err(VMError::Import(format!("object {} is not a package", id)));
+        }
+
+        let mut modules = BTreeMap::new();
+        for (name, encoded) in bcs.module_map {
+            let bytes = STANDARD
+                .decode(encoded)
+                .map_err(|e| VMError::Import(format!("module {}: {}", name, e)))?;
+            modules.insert(name, bytes);
+        }
+
+        let mut linkage = BTreeMap::new();
+        for (original, info) in bcs.linkage_table {
+            linkage.insert(parse_address(&original)?, parse_address(&info.upgraded_id)?);
+        }
+
+        Ok(FetchedPackage { id, modules, linkage })
+    }
+}
+
+/// Result of importing a package and its dependencies
+#[derive(Debug, Clone, Default)]
+pub struct ImportReport {
+    /// Sui package id to the Romer address it was republished at
+    pub remapped: BTreeMap<AccountAddress, AccountAddress>,
+    /// Number of modules published
+    pub modules: usize,
+}
+
+/// Imports published Sui packages, with their dependency closure, into the
+/// Romer module store. Every non-framework package is republished at a new
+/// address derived from its Sui id and all references are rewritten.
+pub struct PackageImporter<S: PackageSource> {
+    source: S,
+    verifier: RomerVerifier,
+}
+
+impl<S: PackageSource> PackageImporter<S> {
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            verifier: RomerVerifier::default(),
+        }
+    }
+
+    /// Checks every rewritten module against `verifier` instead of the
+    /// default limits
+    pub fn with_verifier(mut self, verifier: RomerVerifier) -> Self {
+        self.verifier = verifier;
+        self
+    }
+
+    /// Derives the Romer address a Sui package is republished at
+    pub fn remapped_address(id: &AccountAddress) -> AccountAddress {
+        let mut hasher = Sha3_256::new();
+        hasher.update(b"romer-import");
+        hasher.update(id.as_ref());
+        let address = Address::from_digest(&hasher.finalize().into());
+        AccountAddress::new(*address.as_bytes())
+    }
+
+    /// Fetches `root` and every package it links against, then publishes
+    /// them into `store` in dependency order
+    pub async fn import(
+        &self,
+        root: AccountAddress,
+        store: &mut ModuleStore,
+    ) -> Result<ImportReport, VMError> {
+        // Fetch the dependency closure
+        let mut packages: HashMap<AccountAddress, FetchedPackage> = HashMap::new();
+        let mut queue = vec![root];
+        while let Some(id) = queue.pop() {
+            if packages.contains_key(&id) || FRAMEWORK_ADDRESSES.contains(&id) {
+                continue;
+            }
+            let package = self.source.fetch(id).await?;
+            queue.extend(package.linkage.values().copied());
+            packages.insert(id, package);
+        }
+
+        // Every original id resolves to the linked version, which is
+        // republished at an address derived from that version's id
+        let mut remapped: BTreeMap<AccountAddress, AccountAddress> = BTreeMap::new();
+        for package in packages.values() {
+            remapped.insert(package.id, Self::remapped_address(&package.id));
+            for (original, upgraded) in &package.linkage {
+                if !FRAMEWORK_ADDRESSES.contains(upgraded) {
+                    remapped.insert(*original, Self::remapped_address(upgraded));
+                }
+            }
+        }
+
+        let mut report = ImportReport {
+            remapped: remapped.clone(),
+            modules: 0,
+        };
+        for id in Self::dependency_order(&packages, root)? {
+            let package = &packages[&id];
+            for (name, bytes) in &package.modules {
+                let rewritten = remap_module(bytes, &remapped)
+                    .map_err(|e| VMError::Import(format!("{}::{}: {}", id, name, e)))?;
+                self.verifier.verify_bytes(&rewritten)?;
+                store.store_module(rewritten)?;
+                report.modules += 1;
+            }
+            info!(package = %id, romer = %remapped[&id], "Imported package");
+        }
+
+        Ok(report)
+    }
+
+    /// Orders packages so that dependencies come before dependents
+    fn dependency_order(
+        packages: &HashMap<AccountAddress, FetchedPackage>,
+        root: AccountAddress,
+    ) -> Result<Vec<AccountAddress>, VMError> {
+        fn visit(
+            id: AccountAddress,
+            packages: &HashMap<AccountAddress, FetchedPackage>,
+            visiting: &mut HashSet<AccountAddress>,
+            done: &mut HashSet<AccountAddress>,
+            order: &mut Vec<AccountAddress>,
+        ) -> Result<(), VMError> {
+            if done.contains(&id) || !packages.contains_key(&id) {
+                return Ok(());
+            }
+            if !visiting.insert(id) {
+                return Err(VMError::Import(format!("dependency cycle at {}", id)));
+            }
+            for dep in packages[&id].linkage.values() {
+                visit(*dep, packages, visiting, done, order)?;
+            }
+            visiting.remove(&id);
+            done.insert(id);
+            order.push(id);
+            Ok(())
+        }
+
+        let mut order = Vec::new();
+        visit(root, packages, &mut HashSet::new(), &mut HashSet::new(), &mut order)?;
+        Ok(order)
+    }
+}
+
+/// Rewrites every address a module refers to according to `remapped`
+pub fn remap_module(
+    bytes: &[u8],
+    remapped: &BTreeMap<AccountAddress, AccountAddress>,
+) -> Result<Vec<u8>, String> {
+    let mut module = CompiledModule::deserialize_with_defaults(bytes).map_err(|e| e.to_string())?;
+    for address in module.address_identifiers.iter_mut() {
+        if let Some(new) = remapped.get(address) {
+            *address = *new;
+        }
+    }
+
+    let mut out = Vec::new();
+    module
+        .serialize_with_version(module.version, &mut out)
+        .map_err(|e| e.to_string())?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_binary_format::file_format::empty_module;
+
+    fn module_at(address: AccountAddress) -> Vec<u8> {
+        let mut module = empty_module();
+        module.address_identifiers[0] = address;
+        let mut bytes = Vec::new();
+        module.serialize_with_version(module.version, &mut bytes).unwrap();
+        bytes
+    }
+
+    struct MockSource(HashMap<AccountAddress, FetchedPackage>);
+
+    impl PackageSource for MockSource {
+        async fn fetch(&self, id: AccountAddress) -> Result<FetchedPackage, VMError> {
+            self.0
+                .get(&id)
+                .cloned()
+                .ok_or_else(|| VMError::Import(format!("object {} not found", id)))
+        }
+    }
+
+    #[test]
+    fn test_remap_rewrites_self_address() {
+        let original = AccountAddress::from_hex_literal("0xabc").unwrap();
+        let target = AccountAddress::from_hex_literal("0xdef").unwrap();
+        let remapped = BTreeMap::from([(original, target)]);
+
+        let bytes = remap_module(&module_at(original), &remapped).unwrap();
+        let module = CompiledModule::deserialize_with_defaults(&bytes).unwrap();
+        assert_eq!(*module.self_id().address(), target);
+    }
+
+    #[tokio::test]
+    async fn test_import_dependency_closure() {
+        let dep = AccountAddress::from_hex_literal("0xd1").unwrap();
+        let root = AccountAddress::from_hex_literal("0xa1").unwrap();
+        let source = MockSource(HashMap::from([
+            (
+                root,
+                FetchedPackage {
+                    id: root,
+                    modules: BTreeMap::from([("root".to_string(), module_at(root))]),
+                    linkage: BTreeMap::from([
+                        (dep, dep),
+                        (AccountAddress::TWO, AccountAddress::TWO),
+                    ]),
+                },
+            ),
+            (
+                dep,
+                FetchedPackage {
+                    id: dep,
+                    modules: BTreeMap::from([("dep".to_string(), module_at(dep))]),
+                    linkage: BTreeMap::new(),
+                },
+            ),
+        ]));
+
+        let mut store = ModuleStore::new();
+        let report = PackageImporter::new(source).import(root, &mut store).await.unwrap();
+
+        assert_eq!(report.modules, 2);
+        assert!(!report.remapped.contains_key(&AccountAddress::TWO));
+        assert_eq!(
+            report.remapped[&dep],
+            PackageImporter::<MockSource>::remapped_address(&dep)
+        );
+    }
+}
@@ -1,2 +1,4 @@
 // src/package/mod.rs
-pub mod deployer;
\ No newline at end of file
+pub mod deployer;
+pub mod importer;
+pub mod verification;
\ No newline at end of file
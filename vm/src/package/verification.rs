@@ -0,0 +1,261 @@
+// src/package/verification.rs
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use move_core_types::account_address::AccountAddress;
+use move_package::BuildConfig;
+use serde::{Deserialize, Serialize};
+
+use super::importer::remap_module;
+use crate::error::VMError;
+use crate::storage::modules::ModuleStore;
+
+/// Source code submitted for a published package: every file of the Move
+/// package, keyed by its path relative to the package root
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SourceBundle {
+    pub files: BTreeMap<PathBuf, String>,
+}
+
+impl SourceBundle {
+    /// Writes the bundle to `dir`, refusing paths that escape it
+    fn write_to(&self, dir: &Path) -> Result<(), VMError> {
+        for (path, content) in &self.files {
+            if path.is_absolute() || path.components().any(|c| matches!(c, Component::ParentDir)) {
+                return Err(VMError::Verification(format!("invalid source path {}", path.display())));
+            }
+            let target = dir.join(path);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).map_err(|e| VMError::Storage(e.to_string()))?;
+            }
+            fs::write(&target, content).map_err(|e| VMError::Storage(e.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Verification state of a published package, as shown by the explorer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum VerificationStatus {
+    /// No source has been submitted
+    Unverified,
+    /// Compiling the source reproduces the published bytecode exactly
+    Verified { modules: Vec<String> },
+    /// The source compiled but some modules differ from the published ones
+    Mismatch {
+        mismatched: Vec<String>,
+        missing: Vec<String>,
+        unexpected: Vec<String>,
+    },
+    /// The source failed to compile
+    CompilationFailed { error: String },
+}
+
+/// Compiles a Move package to module bytecode
+pub trait SourceCompiler {
+    /// Returns module name to bytecode for the package rooted at `dir`
+    fn compile(&self, dir: &Path) -> Result<BTreeMap<String, Vec<u8>>, String>;
+}
+
+/// Compiles with the Move package system
+#[derive(Default)]
+pub struct MovePackageCompiler;
+
+impl SourceCompiler for MovePackageCompiler {
+    fn compile(&self, dir: &Path) -> Result<BTreeMap<String, Vec<u8>>, String> {
+        let mut log = Vec::new();
+        let package = BuildConfig::default()
+            .compile_package(dir, &mut log)
+            .map_err(|e| e.to_string())?;
+
+        let mut modules = BTreeMap::new();
+        for unit in package.root_modules() {
+            let module = &unit.unit.module;
+            let mut bytes = Vec::new();
+            module
+                .serialize_with_version(module.version, &mut bytes)
+                .map_err(|e| e.to_string())?;
+            modules.insert(module.self_id().name().to_string(), bytes);
+        }
+        Ok(modules)
+    }
+}
+
+/// Associates source code with published packages and checks that the
+/// source reproduces the on-chain bytecode
+pub struct SourceVerifier<C: SourceCompiler = MovePackageCompiler> {
+    compiler: C,
+    sources: HashMap<AccountAddress, SourceBundle>,
+    statuses: HashMap<AccountAddress, VerificationStatus>,
+}
+
+impl SourceVerifier {
+    pub fn new() -> Self {
+        Self::with_compiler(MovePackageCompiler)
+    }
+}
+
+impl<C: SourceCompiler> SourceVerifier<C> {
+    pub fn with_compiler(compiler: C) -> Self {
+        Self {
+            compiler,
+            sources: HashMap::new(),
+            statuses: HashMap::new(),
+        }
+    }
+
+    /// Compiles `bundle` and compares it with the package published at
+    /// `package`. The bundle is kept so it can be served alongside the status.
+    pub fn submit(
+        &mut self,
+        package: AccountAddress,
+        bundle: SourceBundle,
+        store: &ModuleStore,
+    ) -> Result<VerificationStatus, VMError> {
+        let published: BTreeMap<String, Vec<u8>> = store
+            .modules_at(&package)
+            .into_iter()
+            .map(|(id, bytes)| (id.name().to_string(), bytes.clone()))
+            .collect();
+        if published.is_empty() {
+            return Err(VMError::Verification(format!("no package published at {}", package)));
+        }
+
+        let dir = tempfile::tempdir().map_err(|e| VMError::Storage(e.to_string()))?;
+        bundle.write_to(dir.path())?;
+
+        let status = match self.compiler.compile(dir.path()) {
+            Err(error) => VerificationStatus::CompilationFailed { error },
+            Ok(compiled) => compare(package, &compiled, &published),
+        };
+
+        self.sources.insert(package, bundle);
+        self.statuses.insert(package, status.clone());
+        Ok(status)
+    }
+
+    /// Current verification status of `package`
+    pub fn status(&self, package: &AccountAddress) -> VerificationStatus {
+        self.statuses
+            .get(package)
+            .cloned()
+            .unwrap_or(VerificationStatus::Unverified)
+    }
+
+    /// Source submitted for `package`, if any
+    pub fn source(&self, package: &AccountAddress) -> Option<&SourceBundle> {
+        self.sources.get(package)
+    }
+}
+
+/// Compares freshly compiled modules with published ones. Source compiles
+/// its own package at `0x0`, so that address is remapped before comparing.
+fn compare(
+    package: AccountAddress,
+    compiled: &BTreeMap<String, Vec<u8>>,
+    published: &BTreeMap<String, Vec<u8>>,
+) -> VerificationStatus {
+    let remapping = BTreeMap::from([(AccountAddress::ZERO, package)]);
+    let mut mismatched = Vec::new();
+    let mut unexpected = Vec::new();
+
+    for (name, bytes) in compiled {
+        let Some(expected) = published.get(name) else {
+            unexpected.push(name.clone());
+            continue;
+        };
+        let same = remap_module(bytes, &remapping)
+            .map(|remapped| &remapped == expected)
+            .unwrap_or(false);
+        if !same {
+            mismatched.push(name.clone());
+        }
+    }
+    let missing: Vec<String> = published
+        .keys()
+        .filter(|name| !compiled.contains_key(*name))
+        .cloned()
+        .collect();
+
+    if mismatched.is_empty() && missing.is_empty() && unexpected.is_empty() {
+        VerificationStatus::Verified {
+            modules: compiled.keys().cloned().collect(),
+        }
+    } else {
+        VerificationStatus::Mismatch {
+            mismatched,
+            missing,
+            unexpected,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_binary_format::file_format::empty_module;
+
+    fn module_at(address: AccountAddress) -> Vec<u8> {
+        let mut module = empty_module();
+        module.address_identifiers[0] = address;
+        let mut bytes = Vec::new();
+        module.serialize_with_version(module.version, &mut bytes).unwrap();
+        bytes
+    }
+
+    struct FixedCompiler(Result<BTreeMap<String, Vec<u8>>, String>);
+
+    impl SourceCompiler for FixedCompiler {
+        fn compile(&self, _dir: &Path) -> Result<BTreeMap<String, Vec<u8>>, String> {
+            self.0.clone()
+        }
+    }
+
+    fn setup() -> (AccountAddress, ModuleStore, String) {
+        let package = AccountAddress::from_hex_literal("0x42").unwrap();
+        let mut store = ModuleStore::new();
+        let id = store.store_module(module_at(package)).unwrap();
+        (package, store, id.name().to_string())
+    }
+
+    #[test]
+    fn test_matching_source_verifies() {
+        let (package, store, name) = setup();
+        let compiled = BTreeMap::from([(name.clone(), module_at(AccountAddress::ZERO))]);
+        let mut verifier = SourceVerifier::with_compiler(FixedCompiler(Ok(compiled)));
+
+        assert_eq!(verifier.status(&package), VerificationStatus::Unverified);
+        let status = verifier.submit(package, SourceBundle::default(), &store).unwrap();
+        assert_eq!(status, VerificationStatus::Verified { modules: vec![name] });
+        assert!(verifier.source(&package).is_some());
+    }
+
+    #[test]
+    fn test_mismatch_and_compile_failure() {
+        let (package, store, name) = setup();
+        let other = AccountAddress::from_hex_literal("0x99").unwrap();
+        let compiled = BTreeMap::from([(name.clone(), module_at(other))]);
+        let mut verifier = SourceVerifier::with_compiler(FixedCompiler(Ok(compiled)));
+        assert!(matches!(
+            verifier.submit(package, SourceBundle::default(), &store).unwrap(),
+            VerificationStatus::Mismatch { .. }
+        ));
+
+        let mut verifier = SourceVerifier::with_compiler(FixedCompiler(Err("boom".into())));
+        assert_eq!(
+            verifier.submit(package, SourceBundle::default(), &store).unwrap(),
+            VerificationStatus::CompilationFailed { error: "boom".into() }
+        );
+    }
+
+    #[test]
+    fn test_rejects_escaping_paths() {
+        let bundle = SourceBundle {
+            files: BTreeMap::from([(PathBuf::from("../evil.move"), String::new())]),
+        };
+        let dir = tempfile::tempdir().unwrap();
+        assert!(bundle.write_to(dir.path()).is_err());
+    }
+}
@@ -0,0 +1,83 @@
+// src/runtime/execution.rs
+use crate::error::VMError;
+use crate::runtime::gas::{GasMeter, GasUsage};
+use romer_common::types::rejection::RejectReason;
+
+/// How a transaction's execution ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecutionStatus {
+    Success,
+    OutOfGas,
+    Aborted { module: String, code: u64 },
+    Failed(String),
+}
+
+/// Outcome of executing one transaction, with the gas it used so the
+/// sequencer can charge its fee. Gas is used whether or not execution
+/// succeeded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionResult {
+    pub status: ExecutionStatus,
+    pub gas_limit: u64,
+    pub gas_used: u64,
+    pub gas_remaining: u64,
+    pub gas_usage: GasUsage,
+}
+
+impl ExecutionResult {
+    /// Result of an execution metered by `meter` that ended with `outcome`
+    pub fn new(outcome: Result<(), VMError>, meter: &GasMeter) -> Self {
+        let status = match outcome {
+            Ok(()) => ExecutionStatus::Success,
+            Err(VMError::OutOfGas { .. }) => ExecutionStatus::OutOfGas,
+            Err(VMError::Abort { module, code }) => ExecutionStatus::Aborted { module, code },
+            Err(e) => ExecutionStatus::Failed(e.to_string()),
+        };
+        Self {
+            status,
+            gas_limit: meter.limit(),
+            gas_used: meter.used(),
+            gas_remaining: meter.remaining(),
+            gas_usage: meter.usage(),
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.status == ExecutionStatus::Success
+    }
+
+    /// Fee owed for the gas used at `gas_price` per unit
+    pub fn fee(&self, gas_price: u64) -> u64 {
+        self.gas_used.saturating_mul(gas_price)
+    }
+
+    /// Catalogue reason for a failed execution, `None` if it succeeded
+    pub fn reject_reason(&self) -> Option<RejectReason> {
+        match &self.status {
+            ExecutionStatus::Success => None,
+            ExecutionStatus::OutOfGas => Some(RejectReason::OutOfGas),
+            ExecutionStatus::Aborted { module, code } => Some(RejectReason::from_abort(module, *code)),
+            ExecutionStatus::Failed(_) => Some(RejectReason::Other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::gas::GasCostTable;
+
+    #[test]
+    fn test_out_of_gas_still_pays() {
+        let mut meter = GasMeter::new(GasCostTable::default(), 300);
+        meter.charge_storage_read(50).unwrap();
+        let outcome = meter.charge_storage_write(0);
+        let result = ExecutionResult::new(outcome, &meter);
+
+        assert_eq!(result.status, ExecutionStatus::OutOfGas);
+        assert_eq!(result.gas_used, 300);
+        assert_eq!(result.gas_remaining, 0);
+        assert_eq!(result.fee(2), 600);
+        assert_eq!(result.reject_reason(), Some(RejectReason::OutOfGas));
+    }
+}
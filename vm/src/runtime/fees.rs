@@ -0,0 +1,118 @@
+// src/runtime/fees.rs
+use crate::storage::state::{StateKey, StateStore};
+use romer_common::types::address::Address;
+use romer_common::types::tokenomics::{FeeConfig, BURN_ADDRESS};
+
+/// Emitted whenever collected fees are burned
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BurnEvent {
+    /// Block in which the fees were collected
+    pub height: u64,
+    /// Account the burned tokens were sent to
+    pub address: Address,
+    /// Amount removed from circulation
+    pub amount: u64,
+    /// Amount burned since genesis, including this event
+    pub total_burned: u64,
+}
+
+/// Result of settling a block's fees
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeSettlement {
+    pub burned: u64,
+    pub to_treasury: u64,
+}
+
+/// Applies the fee burn policy and records burn events for consumers
+/// such as supply tracking and the explorer. The running total lives in
+/// state, so it is persisted and rolled back with the block that burned.
+pub struct FeeBurner {
+    config: FeeConfig,
+    events: Vec<BurnEvent>,
+}
+
+impl FeeBurner {
+    pub fn new(config: FeeConfig) -> Self {
+        Self {
+            config,
+            events: Vec::new(),
+        }
+    }
+
+    /// Splits the fees collected at `height`, adding the burned share to
+    /// the total in `state` and emitting a burn event if anything was burned
+    pub fn settle(&mut self, state: &mut StateStore, height: u64, fees: u64) -> FeeSettlement {
+        let (burned, to_treasury) = self.config.split(fees);
+        if burned > 0 {
+            let total_burned = Self::total_burned(state).saturating_add(burned);
+            state.put(StateKey::TotalBurned, total_burned.to_le_bytes().to_vec());
+            self.events.push(BurnEvent {
+                height,
+                address: BURN_ADDRESS,
+                amount: burned,
+                total_burned,
+            });
+        }
+
+        FeeSettlement { burned, to_treasury }
+    }
+
+    /// Total burned since genesis
+    pub fn total_burned(state: &StateStore) -> u64 {
+        state
+            .get(&StateKey::TotalBurned)
+            .and_then(|bytes| bytes.as_slice().try_into().ok())
+            .map(u64::from_le_bytes)
+            .unwrap_or(0)
+    }
+
+    /// Removes and returns burn events emitted since the last call
+    pub fn drain_events(&mut self) -> Vec<BurnEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Drops the events of the block at `height`, whose commit was rolled
+    /// back
+    pub fn discard(&mut self, height: u64) {
+        self.events.retain(|event| event.height != height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settle_emits_burn_events() {
+        let mut state = StateStore::in_memory();
+        let mut burner = FeeBurner::new(FeeConfig { burn_bps: 5_000 });
+
+        assert_eq!(burner.settle(&mut state, 1, 100), FeeSettlement { burned: 50, to_treasury: 50 });
+        assert_eq!(burner.settle(&mut state, 2, 1), FeeSettlement { burned: 0, to_treasury: 1 });
+        assert_eq!(burner.settle(&mut state, 3, 10), FeeSettlement { burned: 5, to_treasury: 5 });
+
+        let events = burner.drain_events();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].height, 3);
+        assert_eq!(events[1].total_burned, 55);
+        assert_eq!(FeeBurner::total_burned(&state), 55);
+        assert!(burner.drain_events().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_total_follows_commits() {
+        let mut state = StateStore::in_memory();
+        let mut burner = FeeBurner::new(FeeConfig { burn_bps: 5_000 });
+        burner.settle(&mut state, 1, 100);
+        state.prepare(1).await.unwrap();
+        state.commit();
+
+        // A block that never commits burns nothing
+        burner.settle(&mut state, 2, 100);
+        state.prepare(2).await.unwrap();
+        state.rollback();
+        burner.discard(2);
+        assert_eq!(FeeBurner::total_burned(&state), 50);
+        assert_eq!(burner.drain_events().len(), 1);
+    }
+}
@@ -0,0 +1,438 @@
+// src/runtime/gas.rs
+use crate::error::VMError;
+use move_binary_format::errors::{PartialVMError, PartialVMResult};
+use move_core_types::gas_algebra::{InternalGas, NumArgs, NumBytes};
+use move_core_types::language_storage::ModuleId;
+use move_core_types::vm_status::StatusCode;
+use move_vm_profiler::GasProfiler;
+use move_vm_types::gas::{GasMeter as MoveGasMeter, SimpleInstruction};
+use move_vm_types::views::{TypeView, ValueView};
+use std::collections::BTreeMap;
+
+/// Loads, stores, branches, comparisons and other constant-time instructions
+pub const INSTRUCTION: &str = "instruction";
+/// Multiplication, division, modulo and shifts
+pub const ARITHMETIC: &str = "arithmetic";
+/// A call into another Move function
+pub const MOVE_CALL: &str = "move_call";
+/// Packing or unpacking a struct
+pub const PACK: &str = "pack";
+/// A vector operation
+pub const VECTOR: &str = "vector";
+/// A call into a native function without a cost of its own
+pub const NATIVE_CALL: &str = "native_call";
+/// Per byte of native function arguments
+pub const NATIVE_PER_BYTE: &str = "native_per_byte";
+pub const STORAGE_READ: &str = "storage_read";
+pub const STORAGE_READ_PER_BYTE: &str = "storage_read_per_byte";
+pub const STORAGE_WRITE: &str = "storage_write";
+pub const STORAGE_WRITE_PER_BYTE: &str = "storage_write_per_byte";
+
+/// Default costs. Storage dominates, and writes cost far more than reads
+/// since every validator keeps what is written.
+const DEFAULT_COSTS: [(&str, u64); 11] = [
+    (INSTRUCTION, 1),
+    (ARITHMETIC, 2),
+    (MOVE_CALL, 20),
+    (PACK, 4),
+    (VECTOR, 4),
+    (NATIVE_CALL, 10),
+    (NATIVE_PER_BYTE, 1),
+    (STORAGE_READ, 100),
+    (STORAGE_READ_PER_BYTE, 1),
+    (STORAGE_WRITE, 500),
+    (STORAGE_WRITE_PER_BYTE, 10),
+];
+
+/// Operation key of a native function's own cost
+pub fn native_operation(module: &str, function: &str) -> String {
+    format!("native:{}::{}", module, function)
+}
+
+/// Gas charged per operation, keyed by the operation names governance
+/// `GasCost` changes use. Natives can be priced individually under
+/// `native:<module>::<function>`, falling back to `native_call`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GasCostTable {
+    costs: BTreeMap<String, u64>,
+}
+
+impl Default for GasCostTable {
+    fn default() -> Self {
+        Self {
+            costs: DEFAULT_COSTS
+                .iter()
+                .map(|(operation, cost)| (operation.to_string(), *cost))
+                .collect(),
+        }
+    }
+}
+
+impl GasCostTable {
+    /// The defaults with the `gas_costs` approved by governance in force
+    pub fn with_overrides(mut self, overrides: &BTreeMap<String, u64>) -> Self {
+        self.costs.extend(overrides.iter().map(|(operation, cost)| (operation.clone(), *cost)));
+        self
+    }
+
+    /// Cost of `operation`, zero if it has none
+    pub fn cost(&self, operation: &str) -> u64 {
+        self.costs.get(operation).copied().unwrap_or(0)
+    }
+
+    pub fn instruction_cost(&self, instruction: SimpleInstruction) -> u64 {
+        self.cost(match instruction {
+            SimpleInstruction::Mul
+            | SimpleInstruction::Div
+            | SimpleInstruction::Mod
+            | SimpleInstruction::Shl
+            | SimpleInstruction::Shr => ARITHMETIC,
+            _ => INSTRUCTION,
+        })
+    }
+
+    /// Cost of calling a native with `bytes` of arguments
+    pub fn native_cost(&self, module: &str, function: &str, bytes: usize) -> u64 {
+        let base = self
+            .costs
+            .get(&native_operation(module, function))
+            .copied()
+            .unwrap_or_else(|| self.cost(NATIVE_CALL));
+        base.saturating_add(self.cost(NATIVE_PER_BYTE).saturating_mul(bytes as u64))
+    }
+
+    pub fn storage_read_cost(&self, bytes: usize) -> u64 {
+        self.cost(STORAGE_READ)
+            .saturating_add(self.cost(STORAGE_READ_PER_BYTE).saturating_mul(bytes as u64))
+    }
+
+    pub fn storage_write_cost(&self, bytes: usize) -> u64 {
+        self.cost(STORAGE_WRITE)
+            .saturating_add(self.cost(STORAGE_WRITE_PER_BYTE).saturating_mul(bytes as u64))
+    }
+}
+
+/// Gas used so far, by what it was spent on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GasUsage {
+    pub instructions: u64,
+    pub natives: u64,
+    pub storage: u64,
+}
+
+impl GasUsage {
+    pub fn total(&self) -> u64 {
+        self.instructions
+            .saturating_add(self.natives)
+            .saturating_add(self.storage)
+    }
+}
+
+/// Meters one transaction's execution against its gas limit. Once a charge
+/// would exceed the limit the whole limit counts as used and execution
+/// must stop, so a transaction that runs out pays for what it consumed.
+#[derive(Debug, Clone)]
+pub struct GasMeter {
+    table: GasCostTable,
+    limit: u64,
+    usage: GasUsage,
+}
+
+impl GasMeter {
+    pub fn new(table: GasCostTable, limit: u64) -> Self {
+        Self {
+            table,
+            limit,
+            usage: GasUsage::default(),
+        }
+    }
+
+    /// Meter that never runs out, for genesis and tooling
+    pub fn unmetered() -> Self {
+        Self::new(GasCostTable::default(), u64::MAX)
+    }
+
+    pub fn charge_storage_read(&mut self, bytes: usize) -> Result<(), VMError> {
+        let cost = self.table.storage_read_cost(bytes);
+        self.charge(cost, |usage| &mut usage.storage)
+    }
+
+    pub fn charge_storage_write(&mut self, bytes: usize) -> Result<(), VMError> {
+        let cost = self.table.storage_write_cost(bytes);
+        self.charge(cost, |usage| &mut usage.storage)
+    }
+
+    fn charge(&mut self, cost: u64, category: impl FnOnce(&mut GasUsage) -> &mut u64) -> Result<(), VMError> {
+        let remaining = self.remaining();
+        let spent = category(&mut self.usage);
+        if cost > remaining {
+            *spent = spent.saturating_add(remaining);
+            return Err(VMError::OutOfGas { limit: self.limit });
+        }
+        *spent = spent.saturating_add(cost);
+        Ok(())
+    }
+
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    pub fn used(&self) -> u64 {
+        self.usage.total()
+    }
+
+    pub fn remaining(&self) -> u64 {
+        self.limit.saturating_sub(self.used())
+    }
+
+    pub fn usage(&self) -> GasUsage {
+        self.usage
+    }
+
+    fn charge_operation(&mut self, operation: &str) -> PartialVMResult<()> {
+        let cost = self.table.cost(operation);
+        self.charge(cost, |usage| &mut usage.instructions).map_err(out_of_gas)
+    }
+}
+
+/// Running out is the only way a charge fails
+fn out_of_gas(_: VMError) -> PartialVMError {
+    PartialVMError::new(StatusCode::OUT_OF_GAS)
+}
+
+/// Meters Move execution: instructions under the table's operation costs,
+/// natives by the cost each reports from `GasCostTable::native_cost`, and
+/// resources by the bytes loaded
+impl MoveGasMeter for GasMeter {
+    fn charge_simple_instr(&mut self, instr: SimpleInstruction) -> PartialVMResult<()> {
+        let cost = self.table.instruction_cost(instr);
+        self.charge(cost, |usage| &mut usage.instructions).map_err(out_of_gas)
+    }
+
+    fn charge_pop(&mut self, _popped_val: impl ValueView) -> PartialVMResult<()> {
+        self.charge_operation(INSTRUCTION)
+    }
+
+    fn charge_call(
+        &mut self,
+        _module_id: &ModuleId,
+        _func_name: &str,
+        _args: impl ExactSizeIterator<Item = impl ValueView>,
+        _num_locals: NumArgs,
+    ) -> PartialVMResult<()> {
+        self.charge_operation(MOVE_CALL)
+    }
+
+    fn charge_call_generic(
+        &mut self,
+        _module_id: &ModuleId,
+        _func_name: &str,
+        _ty_args: impl ExactSizeIterator<Item = impl TypeView>,
+        _args: impl ExactSizeIterator<Item = impl ValueView>,
+        _num_locals: NumArgs,
+    ) -> PartialVMResult<()> {
+        self.charge_operation(MOVE_CALL)
+    }
+
+    fn charge_ld_const(&mut self, _size: NumBytes) -> PartialVMResult<()> {
+        self.charge_operation(INSTRUCTION)
+    }
+
+    fn charge_ld_const_after_deserialization(&mut self, _val: impl ValueView) -> PartialVMResult<()> {
+        Ok(())
+    }
+
+    fn charge_copy_loc(&mut self, _val: impl ValueView) -> PartialVMResult<()> {
+        self.charge_operation(INSTRUCTION)
+    }
+
+    fn charge_move_loc(&mut self, _val: impl ValueView) -> PartialVMResult<()> {
+        self.charge_operation(INSTRUCTION)
+    }
+
+    fn charge_store_loc(&mut self, _val: impl ValueView) -> PartialVMResult<()> {
+        self.charge_operation(INSTRUCTION)
+    }
+
+    fn charge_pack(
+        &mut self,
+        _is_generic: bool,
+        _args: impl ExactSizeIterator<Item = impl ValueView>,
+    ) -> PartialVMResult<()> {
+        self.charge_operation(PACK)
+    }
+
+    fn charge_unpack(
+        &mut self,
+        _is_generic: bool,
+        _args: impl ExactSizeIterator<Item = impl ValueView>,
+    ) -> PartialVMResult<()> {
+        self.charge_operation(PACK)
+    }
+
+    fn charge_variant_switch(&mut self, _val: impl ValueView) -> PartialVMResult<()> {
+        self.charge_operation(INSTRUCTION)
+    }
+
+    fn charge_read_ref(&mut self, _val: impl ValueView) -> PartialVMResult<()> {
+        self.charge_operation(INSTRUCTION)
+    }
+
+    fn charge_write_ref(&mut self, _new_val: impl ValueView, _old_val: impl ValueView) -> PartialVMResult<()> {
+        self.charge_operation(INSTRUCTION)
+    }
+
+    fn charge_eq(&mut self, _lhs: impl ValueView, _rhs: impl ValueView) -> PartialVMResult<()> {
+        self.charge_operation(INSTRUCTION)
+    }
+
+    fn charge_neq(&mut self, _lhs: impl ValueView, _rhs: impl ValueView) -> PartialVMResult<()> {
+        self.charge_operation(INSTRUCTION)
+    }
+
+    fn charge_borrow_global(
+        &mut self,
+        _is_mut: bool,
+        _is_generic: bool,
+        _ty: impl TypeView,
+        _is_success: bool,
+    ) -> PartialVMResult<()> {
+        self.charge_operation(INSTRUCTION)
+    }
+
+    fn charge_exists(&mut self, _is_generic: bool, _ty: impl TypeView, _exists: bool) -> PartialVMResult<()> {
+        self.charge_operation(INSTRUCTION)
+    }
+
+    fn charge_move_from(
+        &mut self,
+        _is_generic: bool,
+        _ty: impl TypeView,
+        _val: Option<impl ValueView>,
+    ) -> PartialVMResult<()> {
+        self.charge_operation(INSTRUCTION)
+    }
+
+    /// Written bytes are charged once the session's changes are known
+    fn charge_move_to(
+        &mut self,
+        _is_generic: bool,
+        _ty: impl TypeView,
+        _val: impl ValueView,
+        _is_success: bool,
+    ) -> PartialVMResult<()> {
+        self.charge_operation(INSTRUCTION)
+    }
+
+    fn charge_vec_pack<'a>(
+        &mut self,
+        _ty: impl TypeView + 'a,
+        _args: impl ExactSizeIterator<Item = impl ValueView>,
+    ) -> PartialVMResult<()> {
+        self.charge_operation(VECTOR)
+    }
+
+    fn charge_vec_len(&mut self, _ty: impl TypeView) -> PartialVMResult<()> {
+        self.charge_operation(VECTOR)
+    }
+
+    fn charge_vec_borrow(&mut self, _is_mut: bool, _ty: impl TypeView, _is_success: bool) -> PartialVMResult<()> {
+        self.charge_operation(VECTOR)
+    }
+
+    fn charge_vec_push_back(&mut self, _ty: impl TypeView, _val: impl ValueView) -> PartialVMResult<()> {
+        self.charge_operation(VECTOR)
+    }
+
+    fn charge_vec_pop_back(&mut self, _ty: impl TypeView, _val: Option<impl ValueView>) -> PartialVMResult<()> {
+        self.charge_operation(VECTOR)
+    }
+
+    fn charge_vec_unpack(
+        &mut self,
+        _ty: impl TypeView,
+        _expect_num_elements: NumArgs,
+        _elems: impl ExactSizeIterator<Item = impl ValueView>,
+    ) -> PartialVMResult<()> {
+        self.charge_operation(VECTOR)
+    }
+
+    fn charge_vec_swap(&mut self, _ty: impl TypeView) -> PartialVMResult<()> {
+        self.charge_operation(VECTOR)
+    }
+
+    fn charge_load_resource(&mut self, loaded: Option<(NumBytes, impl ValueView)>) -> PartialVMResult<()> {
+        let bytes = loaded.map_or(0, |(bytes, _)| u64::from(bytes));
+        self.charge_storage_read(bytes as usize).map_err(out_of_gas)
+    }
+
+    /// `amount` is what the native charged against its budget
+    fn charge_native_function(
+        &mut self,
+        amount: InternalGas,
+        _ret_vals: Option<impl ExactSizeIterator<Item = impl ValueView>>,
+    ) -> PartialVMResult<()> {
+        self.charge(u64::from(amount), |usage| &mut usage.natives).map_err(out_of_gas)
+    }
+
+    fn charge_native_function_before_execution(
+        &mut self,
+        _ty_args: impl ExactSizeIterator<Item = impl TypeView>,
+        _args: impl ExactSizeIterator<Item = impl ValueView>,
+    ) -> PartialVMResult<()> {
+        Ok(())
+    }
+
+    fn charge_drop_frame(&mut self, _locals: impl Iterator<Item = impl ValueView>) -> PartialVMResult<()> {
+        Ok(())
+    }
+
+    fn remaining_gas(&self) -> InternalGas {
+        InternalGas::new(self.remaining())
+    }
+
+    fn get_profiler_mut(&mut self) -> Option<&mut GasProfiler> {
+        None
+    }
+
+    fn set_profiler(&mut self, _profiler: GasProfiler) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_vm_types::values::Value;
+
+    #[test]
+    fn test_meter_charges_until_out_of_gas() {
+        let overrides = [(native_operation("orderbook", "match"), 50)].into();
+        let table = GasCostTable::default().with_overrides(&overrides);
+        assert_eq!(table.native_cost("orderbook", "match", 8), 58);
+        assert_eq!(table.native_cost("table", "add", 0), 10);
+        let mut meter = GasMeter::new(table, 1_000);
+
+        meter.charge_simple_instr(SimpleInstruction::LdTrue).unwrap();
+        meter.charge_simple_instr(SimpleInstruction::Mul).unwrap();
+        meter.charge_pack(false, std::iter::empty::<Value>()).unwrap();
+        meter.charge_native_function(InternalGas::new(68), None::<std::iter::Empty<Value>>).unwrap();
+        meter.charge_storage_read(10).unwrap();
+        assert_eq!(
+            meter.usage(),
+            GasUsage {
+                instructions: 7,
+                natives: 68,
+                storage: 110,
+            }
+        );
+        assert_eq!(meter.remaining(), 815);
+        assert_eq!(meter.remaining_gas(), InternalGas::new(815));
+
+        assert!(matches!(meter.charge_storage_write(100), Err(VMError::OutOfGas { limit: 1_000 })));
+        assert_eq!(meter.used(), 1_000);
+        assert_eq!(meter.remaining(), 0);
+        assert_eq!(
+            meter.charge_simple_instr(SimpleInstruction::LdTrue).unwrap_err().major_status(),
+            StatusCode::OUT_OF_GAS
+        );
+    }
+}
@@ -1 +1,5 @@
-pub mod session;
\ No newline at end of file
+pub mod session;
+pub mod gas;
+pub mod execution;
+pub mod fees;
+pub mod prologue;
\ No newline at end of file
@@ -0,0 +1,100 @@
+// src/runtime/prologue.rs
+use crate::error::VMError;
+use crate::storage::state::{StateKey, StateStore};
+use romer_common::types::address::Address;
+use romer_common::types::envelope::SignedTransaction;
+use romer_common::types::nonce::check_nonce;
+use romer_common::types::rejection::RejectReason;
+
+/// Checks run before a direct transaction executes. Failing transactions
+/// are rejected without touching state, so every validator reaches the
+/// same verdict for the same block.
+///
+/// Validates chain, signature, expiry and nonce against the block
+/// timestamp, consuming the nonce on success. Nonces live in `state`, so
+/// they are persisted, rolled back and exported with the rest of the
+/// block's writes.
+pub fn run(
+    state: &mut StateStore,
+    transaction: &SignedTransaction,
+    chain_id: &str,
+    block_time: u64,
+) -> Result<(), VMError> {
+    transaction
+        .verify(chain_id, block_time)
+        .map_err(|e| VMError::Rejected(RejectReason::from(&e), e.to_string()))?;
+
+    let (sender, nonce) = (transaction.transaction.sender, transaction.transaction.nonce);
+    // Execution allows no gap: every transaction carries the next nonce
+    check_nonce(next_nonce(state, &sender), nonce, 0)
+        .map_err(|e| VMError::Rejected(RejectReason::from(&e), e.to_string()))?;
+    state.put(StateKey::Nonce(sender), (nonce + 1).to_le_bytes().to_vec());
+    Ok(())
+}
+
+/// Next nonce expected from `address`
+pub fn next_nonce(state: &StateStore, address: &Address) -> u64 {
+    state
+        .get(&StateKey::Nonce(*address))
+        .and_then(|bytes| bytes.as_slice().try_into().ok())
+        .map(u64::from_le_bytes)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use commonware_cryptography::{Ed25519, Scheme};
+    use romer_common::types::envelope::{TransactionPayload, UnsignedTransaction, DEFAULT_CHAIN_ID};
+    use romer_common::types::keymanager::SignatureScheme;
+
+    fn signed(nonce: u64) -> SignedTransaction {
+        let mut signer = Ed25519::from_seed(7);
+        UnsignedTransaction {
+            chain_id: DEFAULT_CHAIN_ID.to_string(),
+            payload: TransactionPayload::Transfer {
+                to: Address::new([1u8; 32]),
+                amount: 1,
+            },
+            sender: Address::from_public_key(SignatureScheme::Ed25519, &signer.public_key()),
+            nonce,
+            fee: 0,
+            expiry: 100,
+        }
+        .sign(SignatureScheme::Ed25519, &mut signer)
+        .unwrap()
+    }
+
+    #[test]
+    fn test_replay_rejected_deterministically() {
+        let mut state = StateStore::in_memory();
+        let tx = signed(0);
+
+        assert!(run(&mut state, &tx, DEFAULT_CHAIN_ID, 50).is_ok());
+        assert!(run(&mut state, &tx, DEFAULT_CHAIN_ID, 50).is_err());
+        assert!(run(&mut state, &signed(2), DEFAULT_CHAIN_ID, 50).is_err());
+        assert_eq!(
+            run(&mut state, &signed(1), DEFAULT_CHAIN_ID, 101).unwrap_err().reject_reason(),
+            RejectReason::Expired
+        );
+        assert_eq!(
+            run(&mut state, &signed(1), "romer-mainnet", 50).unwrap_err().reject_reason(),
+            RejectReason::InvalidSignature
+        );
+        assert!(run(&mut state, &signed(1), DEFAULT_CHAIN_ID, 50).is_ok());
+        assert_eq!(next_nonce(&state, &tx.transaction.sender), 2);
+    }
+
+    #[tokio::test]
+    async fn test_consumed_nonces_follow_commits() {
+        let mut state = StateStore::in_memory();
+        run(&mut state, &signed(0), DEFAULT_CHAIN_ID, 50).unwrap();
+        state.flush().await.unwrap();
+
+        // A block that never commits gives its nonces back
+        run(&mut state, &signed(1), DEFAULT_CHAIN_ID, 50).unwrap();
+        state.prepare(1).await.unwrap();
+        state.rollback();
+        assert_eq!(next_nonce(&state, &signed(0).transaction.sender), 1);
+    }
+}
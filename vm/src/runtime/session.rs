@@ -1,19 +1,105 @@
 // src/runtime/session.rs
+use move_binary_format::errors::{Location, VMError as MoveError};
+use move_core_types::effects::ChangeSet;
+use move_core_types::identifier::IdentStr;
+use move_core_types::language_storage::{ModuleId, TypeTag};
+use move_core_types::vm_status::sub_status::NFE_OUT_OF_GAS;
+use move_core_types::vm_status::StatusCode;
 use move_vm_runtime::move_vm::MoveVM;
+use romer_framework::ROMER_FRAMEWORK_ADDRESS;
 use crate::storage::modules::ModuleStore;
+use crate::runtime::execution::ExecutionResult;
+use crate::runtime::gas::GasMeter;
 use crate::error::VMError;
 
-pub struct SessionManager {
-    // Session state will go here
-}
+/// Runs Move functions in sessions over the module store. A session's
+/// writes reach the store only once it has finished and paid for them.
+pub struct SessionManager {}
 
 impl SessionManager {
     pub fn new() -> Self {
         Self {}
     }
 
-    pub fn new_session(&self, _vm: &MoveVM, _store: &ModuleStore) -> Result<SessionManager, VMError> {
-        // Session creation logic will go here
-        Ok(Self {})
+    /// Executes `module::function` with BCS-encoded `args` under `meter`,
+    /// applying its writes if it succeeds. Gas is used either way.
+    #[allow(clippy::too_many_arguments)]
+    pub fn execute(
+        &self,
+        vm: &MoveVM,
+        store: &mut ModuleStore,
+        module: &ModuleId,
+        function: &IdentStr,
+        ty_args: &[TypeTag],
+        args: Vec<Vec<u8>>,
+        meter: &mut GasMeter,
+    ) -> ExecutionResult {
+        let outcome = Self::run(vm, store, module, function, ty_args, args, meter)
+            .and_then(|changes| Self::apply(store, changes, meter));
+        ExecutionResult::new(outcome, meter)
+    }
+
+    fn run(
+        vm: &MoveVM,
+        store: &ModuleStore,
+        module: &ModuleId,
+        function: &IdentStr,
+        ty_args: &[TypeTag],
+        args: Vec<Vec<u8>>,
+        meter: &mut GasMeter,
+    ) -> Result<ChangeSet, VMError> {
+        let limit = meter.limit();
+        let mut session = vm.new_session(store);
+        let ty_args = ty_args
+            .iter()
+            .map(|tag| session.load_type(tag))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| error(e, limit))?;
+        session
+            .execute_function_bypass_visibility(module, function, ty_args, args, meter, None)
+            .map_err(|e| error(e, limit))?;
+        let (changes, _) = session.finish();
+        changes.map_err(|e| error(e, limit))
+    }
+
+    /// Charges for every resource the session wrote, then applies them
+    fn apply(store: &mut ModuleStore, changes: ChangeSet, meter: &mut GasMeter) -> Result<(), VMError> {
+        let mut writes = Vec::new();
+        for (address, account) in changes.into_inner() {
+            let (modules, resources) = account.into_inner();
+            // Packages are published by the deployer, never by a session
+            if !modules.is_empty() {
+                return Err(VMError::Execution(format!("session published modules at {}", address)));
+            }
+            writes.extend(resources.into_iter().map(|(tag, op)| (address, tag, op.ok())));
+        }
+
+        for (_, _, value) in &writes {
+            meter.charge_storage_write(value.as_ref().map_or(0, Vec::len))?;
+        }
+        for (address, tag, value) in writes {
+            store.apply_resource(address, tag, value);
+        }
+        Ok(())
+    }
+}
+
+/// Our error for a failed session. Natives that exceed their budget abort
+/// with `NFE_OUT_OF_GAS`, which is running out of gas like any other.
+/// Aborts in the Romer framework keep the bare module name the rejection
+/// catalogue knows them by.
+fn error(e: MoveError, limit: u64) -> VMError {
+    match e.major_status() {
+        StatusCode::OUT_OF_GAS => VMError::OutOfGas { limit },
+        StatusCode::ABORTED if e.sub_status() == Some(NFE_OUT_OF_GAS) => VMError::OutOfGas { limit },
+        StatusCode::ABORTED => VMError::Abort {
+            module: match e.location() {
+                Location::Module(id) if *id.address() == ROMER_FRAMEWORK_ADDRESS => id.name().to_string(),
+                Location::Module(id) => id.to_string(),
+                _ => String::new(),
+            },
+            code: e.sub_status().unwrap_or_default(),
+        },
+        _ => VMError::Execution(e.to_string()),
     }
 }
@@ -1 +1,2 @@
-pub mod modules;
\ No newline at end of file
+pub mod modules;
+pub mod state;
@@ -1,21 +1,26 @@
 // src/storage/modules.rs
 use move_binary_format::CompiledModule;
-use move_core_types::language_storage::ModuleId;
-use std::collections::HashMap;
+use move_core_types::account_address::AccountAddress;
+use move_core_types::language_storage::{ModuleId, StructTag};
+use move_core_types::resolver::{LinkageResolver, ModuleResolver, ResourceResolver};
 use crate::error::VMError;
+use super::state::{StateKey, StateStore};
 
-/// Stores and manages deployed Move modules
+/// Stores and manages deployed Move modules and resources. Backed by the
+/// journaled `StateStore`; call `flush` to persist.
 pub struct ModuleStore {
-    /// Maps module IDs to their compiled bytecode
-    modules: HashMap<ModuleId, Vec<u8>>,
+    state: StateStore,
 }
 
 impl ModuleStore {
-    /// Create a new empty module store
+    /// Create a new empty, unpersisted module store
     pub fn new() -> Self {
-        Self {
-            modules: HashMap::new(),
-        }
+        Self::with_state(StateStore::in_memory())
+    }
+
+    /// Module store over an opened state journal
+    pub fn with_state(state: StateStore) -> Self {
+        Self { state }
     }
 
     /// Store a new module, deserializing it first to verify its correctness
@@ -25,30 +30,100 @@ impl ModuleStore {
         // This will validate that the bytecode is well-formed
         let module = CompiledModule::deserialize_with_defaults(&module_bytes)
             .map_err(|e| VMError::ModuleDeployment(format!("Failed to deserialize module: {}", e)))?;
-            
+
         // Extract the module's ID - this uniquely identifies the module
         let module_id = module.self_id();
-        
-        // Store the original bytecode - we keep the original bytes rather than 
+
+        // Store the original bytecode - we keep the original bytes rather than
         // re-serializing the deserialized module to preserve exact byte-for-byte compatibility
-        self.modules.insert(module_id.clone(), module_bytes);
-        
+        self.state.put(StateKey::Module(module_id.clone()), module_bytes);
+
         Ok(module_id)
     }
 
     /// Retrieve a module's bytecode by its ID
     pub fn get_module(&self, id: &ModuleId) -> Option<&Vec<u8>> {
-        self.modules.get(id)
+        self.state.get(&StateKey::Module(id.clone()))
+    }
+
+    /// All modules published at `address`, i.e. one package
+    pub fn modules_at(&self, address: &AccountAddress) -> Vec<(&ModuleId, &Vec<u8>)> {
+        self.state
+            .iter()
+            .filter_map(|(key, bytes)| match key {
+                StateKey::Module(id) if id.address() == address => Some((id, bytes)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Writes or deletes a resource produced by a committed session
+    pub fn apply_resource(&mut self, address: AccountAddress, tag: StructTag, value: Option<Vec<u8>>) {
+        let key = StateKey::Resource(address, tag);
+        match value {
+            Some(value) => self.state.put(key, value),
+            None => self.state.delete(&key),
+        }
+    }
+
+    /// Persists pending writes to the journal
+    pub async fn flush(&mut self) -> Result<usize, VMError> {
+        self.state.flush().await
+    }
+
+    /// Underlying state, for exporting it
+    pub fn state(&self) -> &StateStore {
+        &self.state
+    }
+
+    /// Underlying state, for taking part in block commits
+    pub fn state_mut(&mut self) -> &mut StateStore {
+        &mut self.state
+    }
+}
+
+impl LinkageResolver for ModuleStore {
+    type Error = VMError;
+}
+
+impl ModuleResolver for ModuleStore {
+    type Error = VMError;
+
+    fn get_module(&self, id: &ModuleId) -> Result<Option<Vec<u8>>, Self::Error> {
+        Ok(ModuleStore::get_module(self, id).cloned())
+    }
+}
+
+impl ResourceResolver for ModuleStore {
+    type Error = VMError;
+
+    fn get_resource(
+        &self,
+        address: &AccountAddress,
+        typ: &StructTag,
+    ) -> Result<Option<Vec<u8>>, Self::Error> {
+        Ok(self
+            .state
+            .get(&StateKey::Resource(*address, typ.clone()))
+            .cloned())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    
+    use move_binary_format::file_format::empty_module;
+
     #[test]
     fn test_module_storage() {
         let mut store = ModuleStore::new();
-        // Add test implementation here once we have sample Move modules
+        let module = empty_module();
+        let mut bytes = Vec::new();
+        module.serialize_with_version(module.version, &mut bytes).unwrap();
+
+        let id = store.store_module(bytes.clone()).unwrap();
+        assert_eq!(store.get_module(&id), Some(&bytes));
+        assert_eq!(store.modules_at(id.address()).len(), 1);
+        assert_eq!(ModuleResolver::get_module(&store, &id).unwrap(), Some(bytes));
     }
-}
\ No newline at end of file
+}
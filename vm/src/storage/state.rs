@@ -0,0 +1,269 @@
+// src/storage/state.rs
+use std::collections::BTreeMap;
+
+use move_core_types::account_address::AccountAddress;
+use move_core_types::language_storage::{ModuleId, StructTag};
+use romer_common::storage::commit::Recovery;
+use romer_common::storage::journal::RomerJournal;
+use romer_common::types::address::Address;
+use romer_common::types::genesis::GenesisObject;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::error::VMError;
+
+/// Key of a piece of Move state
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum StateKey {
+    Module(ModuleId),
+    Resource(AccountAddress, StructTag),
+    /// Next nonce expected from an account, as little-endian bytes
+    Nonce(Address),
+    /// Fees burned since genesis, as little-endian bytes
+    TotalBurned,
+}
+
+/// A single journaled write. `value` of `None` deletes the key.
+#[derive(Serialize, Deserialize)]
+struct StateRecord {
+    key: StateKey,
+    value: Option<Vec<u8>>,
+    /// Block commit the write belongs to. Untagged writes are always applied.
+    #[serde(default)]
+    txn: Option<u64>,
+}
+
+/// Move state persisted in a `RomerJournal` partition, so the VM shares one
+/// storage engine (and one backup story) with blocks and FIX data.
+///
+/// Reads are served from a cache rebuilt by replaying the journal on open.
+/// Writes land in the cache immediately and are only appended to the journal
+/// on `flush`, or on `prepare` when taking part in a block commit.
+pub struct StateStore {
+    journal: Option<RomerJournal>,
+    cache: BTreeMap<StateKey, Vec<u8>>,
+    dirty: BTreeMap<StateKey, Option<Vec<u8>>>,
+    /// Values keys held before the uncommitted writes, for rollback
+    undo: BTreeMap<StateKey, Option<Vec<u8>>>,
+}
+
+impl StateStore {
+    /// Store without persistence, for tests and tooling
+    pub fn in_memory() -> Self {
+        Self {
+            journal: None,
+            cache: BTreeMap::new(),
+            dirty: BTreeMap::new(),
+            undo: BTreeMap::new(),
+        }
+    }
+
+    /// Opens the store over `journal`, replaying every record into the cache.
+    /// Records of block commits that `recovery` does not report as committed
+    /// are skipped.
+    pub async fn open(mut journal: RomerJournal, recovery: &Recovery) -> Result<Self, VMError> {
+        let mut cache = BTreeMap::new();
+        let mut records = 0usize;
+        for bytes in journal.replay_all().await.map_err(VMError::Storage)? {
+            let record: StateRecord = match serde_json::from_slice(&bytes) {
+                Ok(record) => record,
+                Err(e) => {
+                    warn!(error = %e, "Skipping undecodable state record");
+                    continue;
+                }
+            };
+            if record.txn.is_some_and(|txn| !recovery.is_committed(txn)) {
+                continue;
+            }
+            match record.value {
+                Some(value) => cache.insert(record.key, value),
+                None => cache.remove(&record.key),
+            };
+            records += 1;
+        }
+        info!(records, keys = cache.len(), "Replayed VM state journal");
+
+        Ok(Self {
+            journal: Some(journal),
+            cache,
+            dirty: BTreeMap::new(),
+            undo: BTreeMap::new(),
+        })
+    }
+
+    pub fn get(&self, key: &StateKey) -> Option<&Vec<u8>> {
+        self.cache.get(key)
+    }
+
+    pub fn put(&mut self, key: StateKey, value: Vec<u8>) {
+        let previous = self.cache.insert(key.clone(), value.clone());
+        self.undo.entry(key.clone()).or_insert(previous);
+        self.dirty.insert(key, Some(value));
+    }
+
+    pub fn delete(&mut self, key: &StateKey) {
+        let previous = self.cache.remove(key);
+        self.undo.entry(key.clone()).or_insert(previous);
+        self.dirty.insert(key.clone(), None);
+    }
+
+    /// Iterates over cached entries in key order
+    pub fn iter(&self) -> impl Iterator<Item = (&StateKey, &Vec<u8>)> {
+        self.cache.iter()
+    }
+
+    /// Every entry as genesis objects, keyed by the JSON encoding of its
+    /// `StateKey`
+    pub fn export(&self) -> Result<Vec<GenesisObject>, VMError> {
+        self.cache
+            .iter()
+            .map(|(key, value)| {
+                let key = serde_json::to_vec(key).map_err(|e| VMError::Storage(e.to_string()))?;
+                Ok(GenesisObject::new(&key, value))
+            })
+            .collect()
+    }
+
+    /// Writes objects exported by `export`, replacing what is stored under
+    /// the same keys. Returns the number of objects written.
+    pub fn import(&mut self, objects: &[GenesisObject]) -> Result<usize, VMError> {
+        for object in objects {
+            let (key, value) = object.decode().map_err(|e| VMError::Storage(e.to_string()))?;
+            let key: StateKey = serde_json::from_slice(&key).map_err(|e| VMError::Storage(e.to_string()))?;
+            self.put(key, value);
+        }
+        Ok(objects.len())
+    }
+
+    /// Number of writes not yet persisted
+    pub fn pending(&self) -> usize {
+        self.dirty.len()
+    }
+
+    /// Appends pending writes to the journal and syncs it. Returns the number
+    /// of records written.
+    pub async fn flush(&mut self) -> Result<usize, VMError> {
+        let written = self.write(None).await?;
+        self.undo.clear();
+        Ok(written)
+    }
+
+    /// Durably writes pending changes tagged with block commit `txn`. They
+    /// stay revertible until `commit` or `rollback`.
+    pub async fn prepare(&mut self, txn: u64) -> Result<usize, VMError> {
+        self.write(Some(txn)).await
+    }
+
+    /// Accepts every change prepared so far as committed state
+    pub fn commit(&mut self) {
+        self.undo.clear();
+    }
+
+    /// Reverts the cache to the last committed state. Prepared records left
+    /// in the journal are ignored on replay as their commit never completed.
+    pub fn rollback(&mut self) {
+        for (key, previous) in std::mem::take(&mut self.undo) {
+            match previous {
+                Some(value) => self.cache.insert(key, value),
+                None => self.cache.remove(&key),
+            };
+        }
+        self.dirty.clear();
+    }
+
+    async fn write(&mut self, txn: Option<u64>) -> Result<usize, VMError> {
+        let Some(journal) = self.journal.as_mut() else {
+            self.dirty.clear();
+            return Ok(0);
+        };
+        if self.dirty.is_empty() {
+            return Ok(0);
+        }
+
+        let records = std::mem::take(&mut self.dirty)
+            .into_iter()
+            .map(|(key, value)| serde_json::to_vec(&StateRecord { key, value, txn }))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| VMError::Storage(e.to_string()))?;
+        // One group commit for the whole write set
+        let written = journal.append_batch(records).await.map_err(VMError::Storage)?;
+        journal.sync().await.map_err(VMError::Storage)?;
+
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_core_types::identifier::Identifier;
+
+    fn module_key(name: &str) -> StateKey {
+        StateKey::Module(ModuleId::new(AccountAddress::ONE, Identifier::new(name).unwrap()))
+    }
+
+    #[tokio::test]
+    async fn test_write_back() {
+        let mut store = StateStore::in_memory();
+        store.put(module_key("a"), vec![1]);
+        store.put(module_key("b"), vec![2]);
+        store.delete(&module_key("b"));
+
+        assert_eq!(store.get(&module_key("a")), Some(&vec![1]));
+        assert_eq!(store.get(&module_key("b")), None);
+        assert_eq!(store.pending(), 2);
+
+        assert_eq!(store.flush().await.unwrap(), 0);
+        assert_eq!(store.pending(), 0);
+        assert_eq!(store.get(&module_key("a")), Some(&vec![1]));
+    }
+
+    #[tokio::test]
+    async fn test_rollback_restores_committed_state() {
+        let mut store = StateStore::in_memory();
+        store.put(module_key("a"), vec![1]);
+        store.flush().await.unwrap();
+
+        store.put(module_key("a"), vec![2]);
+        store.put(module_key("b"), vec![3]);
+        store.prepare(7).await.unwrap();
+        store.rollback();
+
+        assert_eq!(store.get(&module_key("a")), Some(&vec![1]));
+        assert_eq!(store.get(&module_key("b")), None);
+
+        store.put(module_key("b"), vec![4]);
+        store.prepare(8).await.unwrap();
+        store.commit();
+        store.rollback();
+        assert_eq!(store.get(&module_key("b")), Some(&vec![4]));
+    }
+
+    #[test]
+    fn test_export_import() {
+        let mut store = StateStore::in_memory();
+        store.put(module_key("b"), vec![2]);
+        store.put(module_key("a"), vec![1]);
+        let objects = store.export().unwrap();
+
+        let mut imported = StateStore::in_memory();
+        assert_eq!(imported.import(&objects).unwrap(), 2);
+        assert_eq!(imported.get(&module_key("a")), Some(&vec![1]));
+        assert_eq!(imported.get(&module_key("b")), Some(&vec![2]));
+        assert_eq!(imported.export().unwrap(), objects);
+    }
+
+    #[test]
+    fn test_record_round_trip() {
+        let record = StateRecord {
+            key: module_key("a"),
+            value: Some(vec![1, 2, 3]),
+            txn: Some(1),
+        };
+        let bytes = serde_json::to_vec(&record).unwrap();
+        let decoded: StateRecord = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(decoded.key, record.key);
+        assert_eq!(decoded.value, record.value);
+        assert_eq!(decoded.txn, Some(1));
+    }
+}
@@ -1,12 +1,441 @@
 // src/verifier/mod.rs
+use std::collections::{BTreeSet, HashMap};
+
+use move_binary_format::file_format::{
+    Bytecode, FunctionDefinition, FunctionHandleIndex, ModuleHandleIndex, SignatureToken, StructDefinition,
+};
 use move_binary_format::CompiledModule;
+
 use crate::error::VMError;
 
-pub struct RomerVerifier;
+/// Limits and policy applied to every published module on top of the Move
+/// bytecode verifier. They keep what a single trading transaction can do
+/// predictable: the defaults are well above what the framework and the
+/// DeepBook contracts need.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifierConfig {
+    pub max_struct_fields: usize,
+    /// Structs of the same module nested inside each other
+    pub max_struct_depth: usize,
+    /// Nesting of vectors, references and generic instantiations in a type
+    pub max_type_depth: usize,
+    pub max_function_parameters: usize,
+    /// Longest chain of calls between functions of the same module.
+    /// Recursion is always rejected.
+    pub max_call_depth: usize,
+    pub max_loops_per_function: usize,
+    pub max_loop_nesting: usize,
+    /// Functions that may not be called or declared, as
+    /// `<address>::<module>::<function>`, or `<address>::<module>` for every
+    /// function of a module
+    pub denied_natives: BTreeSet<String>,
+}
+
+impl Default for VerifierConfig {
+    fn default() -> Self {
+        Self {
+            max_struct_fields: 64,
+            max_struct_depth: 8,
+            max_type_depth: 16,
+            max_function_parameters: 32,
+            max_call_depth: 32,
+            max_loops_per_function: 16,
+            max_loop_nesting: 4,
+            denied_natives: ["0x1::debug", "0x1::unit_test", "0x2::test_scenario", "0x2::test_utils"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+        }
+    }
+}
+
+/// Verifies modules before they are published: the Move bytecode verifier
+/// (including its ability and signature checks), then Romer's own limits
+#[derive(Debug, Clone, Default)]
+pub struct RomerVerifier {
+    config: VerifierConfig,
+}
 
 impl RomerVerifier {
-    pub fn verify_module(module: &CompiledModule) -> Result<(), VMError> {
-        // Basic verification will go here
+    pub fn new(config: VerifierConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &VerifierConfig {
+        &self.config
+    }
+
+    pub fn verify_module(&self, module: &CompiledModule) -> Result<(), VMError> {
+        move_bytecode_verifier::verify_module_unmetered(module).map_err(|e| VMError::ModuleVerification {
+            module: module_name(module, module.self_handle_idx()),
+            reason: e.to_string(),
+        })?;
+        self.check_limits(module)
+    }
+
+    /// Deserializes and verifies published bytecode
+    pub fn verify_bytes(&self, bytes: &[u8]) -> Result<CompiledModule, VMError> {
+        let module = CompiledModule::deserialize_with_defaults(bytes)
+            .map_err(|e| VMError::ModuleDeployment(format!("Failed to deserialize module: {}", e)))?;
+        self.verify_module(&module)?;
+        Ok(module)
+    }
+
+    fn check_limits(&self, module: &CompiledModule) -> Result<(), VMError> {
+        for def in module.struct_defs() {
+            self.check_struct(module, def)?;
+        }
+        let mut calls = CallGraph::new(module);
+        for def in module.function_defs() {
+            self.check_signature(module, def)?;
+            self.check_natives(module, def)?;
+            self.check_loops(module, def)?;
+            if calls.depth(def.function)? > self.config.max_call_depth {
+                return Err(function_error(
+                    module,
+                    def.function,
+                    format!("call depth exceeds {}", self.config.max_call_depth),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_struct(&self, module: &CompiledModule, def: &StructDefinition) -> Result<(), VMError> {
+        let handle = module.datatype_handle_at(def.struct_handle);
+        let error = |reason: String| VMError::StructLayout {
+            module: module_name(module, module.self_handle_idx()),
+            name: module.identifier_at(handle.name).to_string(),
+            reason,
+        };
+        // Objects hold assets and positions, which must never be duplicated
+        if handle.abilities.has_key() && handle.abilities.has_copy() {
+            return Err(error("has both key and copy".into()));
+        }
+        let fields = def.fields().unwrap_or_default();
+        if fields.len() > self.config.max_struct_fields {
+            return Err(error(format!(
+                "{} fields exceed the limit of {}",
+                fields.len(),
+                self.config.max_struct_fields
+            )));
+        }
+        for field in fields {
+            if type_depth(&field.signature.0) > self.config.max_type_depth {
+                return Err(error(format!(
+                    "field {} is nested deeper than {}",
+                    module.identifier_at(field.name),
+                    self.config.max_type_depth
+                )));
+            }
+        }
+        if struct_depth(module, def, 0) > self.config.max_struct_depth {
+            return Err(error(format!("structs nested deeper than {}", self.config.max_struct_depth)));
+        }
+        Ok(())
+    }
+
+    fn check_signature(&self, module: &CompiledModule, def: &FunctionDefinition) -> Result<(), VMError> {
+        let handle = module.function_handle_at(def.function);
+        let parameters = &module.signature_at(handle.parameters).0;
+        if parameters.len() > self.config.max_function_parameters {
+            return Err(function_error(
+                module,
+                def.function,
+                format!(
+                    "{} parameters exceed the limit of {}",
+                    parameters.len(),
+                    self.config.max_function_parameters
+                ),
+            ));
+        }
+        let returns = &module.signature_at(handle.return_).0;
+        if parameters.iter().chain(returns).any(|token| type_depth(token) > self.config.max_type_depth) {
+            return Err(function_error(
+                module,
+                def.function,
+                format!("signature nests types deeper than {}", self.config.max_type_depth),
+            ));
+        }
         Ok(())
     }
+
+    /// Denied functions may neither be called nor declared native
+    fn check_natives(&self, module: &CompiledModule, def: &FunctionDefinition) -> Result<(), VMError> {
+        let mut used: Vec<FunctionHandleIndex> = called(module, def).collect();
+        if def.is_native() {
+            used.push(def.function);
+        }
+        for callee in used {
+            let handle = module.function_handle_at(callee);
+            let owner = module_name(module, handle.module);
+            let native = format!("{}::{}", owner, module.identifier_at(handle.name));
+            if self.config.denied_natives.contains(&owner) || self.config.denied_natives.contains(&native) {
+                return Err(VMError::DeniedNative {
+                    module: module_name(module, module.self_handle_idx()),
+                    function: function_name(module, def.function),
+                    native,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Loops are found from their back edges. Gas bounds every loop, but a
+    /// loop with no way out can only ever run out of it, and deeply nested
+    /// loops make the cost of a call hard to predict.
+    fn check_loops(&self, module: &CompiledModule, def: &FunctionDefinition) -> Result<(), VMError> {
+        let Some(code) = &def.code else {
+            return Ok(());
+        };
+        let code = &code.code;
+        let loops: Vec<(usize, usize)> = code
+            .iter()
+            .enumerate()
+            .filter_map(|(offset, instruction)| match branch_target(instruction) {
+                Some(target) if target <= offset => Some((target, offset)),
+                _ => None,
+            })
+            .collect();
+        if loops.len() > self.config.max_loops_per_function {
+            return Err(function_error(
+                module,
+                def.function,
+                format!("{} loops exceed the limit of {}", loops.len(), self.config.max_loops_per_function),
+            ));
+        }
+        for &(head, tail) in &loops {
+            let exits = code[head..=tail].iter().any(|instruction| match instruction {
+                Bytecode::Ret | Bytecode::Abort => true,
+                _ => branch_target(instruction).is_some_and(|target| target < head || target > tail),
+            });
+            if !exits {
+                return Err(function_error(module, def.function, format!("loop at offset {} never exits", head)));
+            }
+        }
+        let nesting = (0..code.len())
+            .map(|offset| loops.iter().filter(|(head, tail)| (*head..=*tail).contains(&offset)).count())
+            .max()
+            .unwrap_or(0);
+        if nesting > self.config.max_loop_nesting {
+            return Err(function_error(
+                module,
+                def.function,
+                format!("loops nested {} deep exceed the limit of {}", nesting, self.config.max_loop_nesting),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Calls between the functions of one module, with the longest chain from
+/// each memoized
+struct CallGraph<'a> {
+    module: &'a CompiledModule,
+    defs: HashMap<FunctionHandleIndex, &'a FunctionDefinition>,
+    depths: HashMap<FunctionHandleIndex, usize>,
+}
+
+impl<'a> CallGraph<'a> {
+    fn new(module: &'a CompiledModule) -> Self {
+        Self {
+            module,
+            defs: module.function_defs().iter().map(|def| (def.function, def)).collect(),
+            depths: HashMap::new(),
+        }
+    }
+
+    /// Longest chain of calls starting at `function`, counting itself
+    fn depth(&mut self, function: FunctionHandleIndex) -> Result<usize, VMError> {
+        self.visit(function, &mut Vec::new())
+    }
+
+    fn visit(&mut self, function: FunctionHandleIndex, path: &mut Vec<FunctionHandleIndex>) -> Result<usize, VMError> {
+        if let Some(depth) = self.depths.get(&function) {
+            return Ok(*depth);
+        }
+        if path.contains(&function) {
+            return Err(function_error(
+                self.module,
+                function,
+                format!("recursive call through {}", function_name(self.module, *path.last().unwrap_or(&function))),
+            ));
+        }
+        let Some(def) = self.defs.get(&function).copied() else {
+            // Functions of other modules are checked when they are published
+            return Ok(0);
+        };
+        path.push(function);
+        let mut deepest = 0;
+        for callee in called(self.module, def) {
+            deepest = deepest.max(self.visit(callee, path)?);
+        }
+        path.pop();
+        self.depths.insert(function, deepest + 1);
+        Ok(deepest + 1)
+    }
+}
+
+/// Functions called by `def`
+fn called<'a>(module: &'a CompiledModule, def: &'a FunctionDefinition) -> impl Iterator<Item = FunctionHandleIndex> + 'a {
+    def.code.iter().flat_map(|code| &code.code).filter_map(|instruction| match instruction {
+        Bytecode::Call(handle) => Some(*handle),
+        Bytecode::CallGeneric(instantiation) => Some(module.function_instantiation_at(*instantiation).handle),
+        _ => None,
+    })
+}
+
+fn branch_target(instruction: &Bytecode) -> Option<usize> {
+    match instruction {
+        Bytecode::Branch(target) | Bytecode::BrTrue(target) | Bytecode::BrFalse(target) => Some(*target as usize),
+        _ => None,
+    }
+}
+
+fn type_depth(token: &SignatureToken) -> usize {
+    token.preorder_traversal_with_depth().map(|(_, depth)| depth).max().unwrap_or(0)
+}
+
+/// Depth of structs of this module nested in `def`, counting itself
+fn struct_depth(module: &CompiledModule, def: &StructDefinition, seen: usize) -> usize {
+    // Recursive structs are rejected by the bytecode verifier; stop anyway
+    if seen > module.struct_defs().len() {
+        return seen;
+    }
+    let nested = def
+        .fields()
+        .unwrap_or_default()
+        .iter()
+        .flat_map(|field| field.signature.0.preorder_traversal())
+        .filter_map(|token| match token {
+            SignatureToken::Datatype(handle) => Some(*handle),
+            SignatureToken::DatatypeInstantiation(instantiation) => Some(instantiation.0),
+            _ => None,
+        })
+        .filter_map(|handle| module.struct_defs().iter().find(|def| def.struct_handle == handle))
+        .map(|inner| struct_depth(module, inner, seen + 1))
+        .max()
+        .unwrap_or(0);
+    nested + 1
+}
+
+/// `<address>::<module>`, with the address in its short form
+fn module_name(module: &CompiledModule, handle: ModuleHandleIndex) -> String {
+    let handle = module.module_handle_at(handle);
+    format!(
+        "0x{}::{}",
+        module.address_identifier_at(handle.address).short_str_lossless(),
+        module.identifier_at(handle.name)
+    )
+}
+
+fn function_name(module: &CompiledModule, function: FunctionHandleIndex) -> String {
+    module.identifier_at(module.function_handle_at(function).name).to_string()
+}
+
+fn function_error(module: &CompiledModule, function: FunctionHandleIndex, reason: String) -> VMError {
+    VMError::FunctionVerification {
+        module: module_name(module, module.self_handle_idx()),
+        function: function_name(module, function),
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_binary_format::file_format::{
+        empty_module, CodeUnit, FunctionHandle, IdentifierIndex, ModuleHandle, SignatureIndex, Visibility,
+    };
+    use move_core_types::account_address::AccountAddress;
+    use move_core_types::identifier::Identifier;
+
+    fn identifier(module: &mut CompiledModule, name: &str) -> IdentifierIndex {
+        module.identifiers.push(Identifier::new(name).unwrap());
+        IdentifierIndex((module.identifiers.len() - 1) as u16)
+    }
+
+    fn handle(module: &mut CompiledModule, owner: ModuleHandleIndex, name: &str) -> FunctionHandleIndex {
+        let name = identifier(module, name);
+        module.function_handles.push(FunctionHandle {
+            module: owner,
+            name,
+            parameters: SignatureIndex(0),
+            return_: SignatureIndex(0),
+            type_parameters: vec![],
+        });
+        FunctionHandleIndex((module.function_handles.len() - 1) as u16)
+    }
+
+    /// A module with one function per body, named `f0`, `f1`, ...
+    fn module(bodies: Vec<Vec<Bytecode>>) -> CompiledModule {
+        let mut module = empty_module();
+        for (i, code) in bodies.into_iter().enumerate() {
+            let function = handle(&mut module, ModuleHandleIndex(0), &format!("f{}", i));
+            module.function_defs.push(FunctionDefinition {
+                function,
+                visibility: Visibility::Public,
+                is_entry: false,
+                acquires_global_resources: vec![],
+                code: Some(CodeUnit {
+                    locals: SignatureIndex(0),
+                    code,
+                    ..Default::default()
+                }),
+            });
+        }
+        module
+    }
+
+    #[test]
+    fn test_trading_limits() {
+        let verifier = RomerVerifier::new(VerifierConfig {
+            max_call_depth: 2,
+            max_loop_nesting: 1,
+            ..Default::default()
+        });
+        let call = |f: u16| Bytecode::Call(FunctionHandleIndex(f));
+        let function = |error: VMError| match error {
+            VMError::FunctionVerification { function, reason, .. } => (function, reason),
+            other => panic!("unexpected {}", other),
+        };
+
+        let chain = module(vec![vec![call(1), Bytecode::Ret], vec![Bytecode::Ret]]);
+        verifier.check_limits(&chain).unwrap();
+        let deeper = module(vec![vec![call(1), Bytecode::Ret], vec![call(2), Bytecode::Ret], vec![Bytecode::Ret]]);
+        assert_eq!(function(verifier.check_limits(&deeper).unwrap_err()).0, "f0");
+        let recursive = module(vec![vec![call(1), Bytecode::Ret], vec![call(0), Bytecode::Ret]]);
+        assert!(function(verifier.check_limits(&recursive).unwrap_err()).1.starts_with("recursive"));
+
+        let spin = module(vec![vec![Bytecode::Branch(0)]]);
+        assert_eq!(
+            function(verifier.check_limits(&spin).unwrap_err()).1,
+            "loop at offset 0 never exits"
+        );
+        let nested = module(vec![vec![
+            Bytecode::LdTrue,
+            Bytecode::BrFalse(6),
+            Bytecode::LdTrue,
+            Bytecode::BrFalse(5),
+            Bytecode::Branch(2),
+            Bytecode::Branch(0),
+            Bytecode::Ret,
+        ]]);
+        assert!(function(verifier.check_limits(&nested).unwrap_err()).1.contains("nested 2 deep"));
+
+        let mut debug = module(vec![vec![call(1), Bytecode::Ret]]);
+        let name = identifier(&mut debug, "debug");
+        debug.address_identifiers.push(AccountAddress::ONE);
+        debug.module_handles.push(ModuleHandle {
+            address: move_binary_format::file_format::AddressIdentifierIndex(1),
+            name,
+        });
+        handle(&mut debug, ModuleHandleIndex(1), "print");
+        match verifier.check_limits(&debug).unwrap_err() {
+            VMError::DeniedNative { function, native, .. } => {
+                assert_eq!((function.as_str(), native.as_str()), ("f0", "0x1::debug::print"))
+            }
+            other => panic!("unexpected {}", other),
+        }
+    }
 }
@@ -1,34 +1,275 @@
 // Updated src/vm.rs
 use anyhow::Result;
+use move_core_types::account_address::AccountAddress;
+use move_core_types::identifier::IdentStr;
+use move_core_types::language_storage::{ModuleId, TypeTag};
+use std::path::Path;
+use tracing::info;
 use move_vm_runtime::move_vm::MoveVM;
 use crate::{
     natives::table::build_natives,
     storage::modules::ModuleStore,
+    storage::state::StateStore,
+    package::deployer::SuiPackageDeployer,
+    package::importer::{ImportReport, PackageImporter, PackageSource},
+    package::verification::{SourceBundle, SourceVerifier, VerificationStatus},
+    runtime::fees::{BurnEvent, FeeBurner, FeeSettlement},
+    runtime::execution::ExecutionResult,
+    runtime::gas::{GasCostTable, GasMeter},
+    runtime::prologue,
     runtime::session::SessionManager,
     error::VMError,
 };
+use futures::future::BoxFuture;
+use romer_common::storage::commit::{Participant, Recovery};
+use romer_common::storage::journal::RomerJournal;
+use romer_common::types::address::Address;
+use romer_common::types::envelope::{SignedTransaction, DEFAULT_CHAIN_ID};
+use romer_common::types::genesis::GenesisObject;
+use romer_common::types::tokenomics::FeeConfig;
+use std::collections::BTreeMap;
 
 pub struct RomerVM {
     vm: MoveVM,
     module_store: ModuleStore,
     session_manager: SessionManager,
+    fee_burner: FeeBurner,
+    source_verifier: SourceVerifier,
+    gas_costs: GasCostTable,
+    /// Network whose transactions the prologue accepts
+    chain_id: String,
+    /// Block being executed, whose fees are settled when it commits
+    block_height: u64,
+    /// Fees of the transactions admitted to the block so far
+    block_fees: u64,
 }
 
 impl RomerVM {
     pub fn new() -> Result<Self, VMError> {
-        let natives = build_natives();
-        let vm = MoveVM::new(natives)
-            .map_err(|e| VMError::Execution(e.to_string()))?;
-            
+        Self::with_fee_config(FeeConfig::default())
+    }
+
+    pub fn with_fee_config(fees: FeeConfig) -> Result<Self, VMError> {
+        Self::with_store(ModuleStore::new(), fees)
+    }
+
+    /// Opens the VM over Move state persisted in `journal`, keeping only
+    /// writes of block commits that `recovery` reports as committed. The
+    /// framework is only published if there is no state yet, i.e. at genesis.
+    pub async fn open(
+        journal: RomerJournal,
+        recovery: &Recovery,
+        fees: FeeConfig,
+    ) -> Result<Self, VMError> {
+        let state = StateStore::open(journal, recovery).await?;
+        Self::with_store(ModuleStore::with_state(state), fees)
+    }
+
+    fn with_store(mut module_store: ModuleStore, fees: FeeConfig) -> Result<Self, VMError> {
+        let gas_costs = GasCostTable::default();
+        let vm = Self::move_vm(&gas_costs)?;
+
+        if module_store.modules_at(&romer_framework::ROMER_FRAMEWORK_ADDRESS).is_empty() {
+            Self::load_framework(&mut module_store)?;
+        }
+
         Ok(Self {
             vm,
-            module_store: ModuleStore::new(),
+            module_store,
             session_manager: SessionManager::new(),
+            fee_burner: FeeBurner::new(fees),
+            source_verifier: SourceVerifier::new(),
+            gas_costs,
+            chain_id: DEFAULT_CHAIN_ID.to_string(),
+            block_height: 0,
+            block_fees: 0,
         })
     }
 
-    pub fn new_session(&self) -> Result<SessionManager, VMError> {
-        self.session_manager.new_session(&self.vm, &self.module_store)
+    /// Move VM whose natives charge under `gas_costs`
+    fn move_vm(gas_costs: &GasCostTable) -> Result<MoveVM, VMError> {
+        MoveVM::new(build_natives(gas_costs)).map_err(|e| VMError::Execution(e.to_string()))
+    }
+
+    /// Publishes the embedded Romer framework and its dependencies at genesis
+    fn load_framework(store: &mut ModuleStore) -> Result<(), VMError> {
+        for module in romer_framework::modules() {
+            store.store_module(module.bytes.to_vec())?;
+        }
+        info!(modules = romer_framework::modules().len(), "Loaded Romer framework");
+        Ok(())
+    }
+
+    /// Persists state written since the last commit to the journal, outside
+    /// of the block commit protocol (e.g. for tooling)
+    pub async fn commit(&mut self) -> Result<usize, VMError> {
+        self.module_store.flush().await
+    }
+
+    /// Every module and resource, for a genesis bundle
+    pub fn export_genesis(&self) -> Result<Vec<GenesisObject>, VMError> {
+        self.module_store.state().export()
+    }
+
+    /// Loads the objects of a genesis bundle over the current state, the
+    /// framework included, and persists them. Returns the number loaded.
+    pub async fn import_genesis(&mut self, objects: &[GenesisObject]) -> Result<usize, VMError> {
+        let imported = self.module_store.state_mut().import(objects)?;
+        self.module_store.flush().await?;
+        info!(objects = imported, "Imported genesis state");
+        Ok(imported)
+    }
+
+    /// Executes `module::function` with BCS-encoded `args` for a
+    /// transaction of `gas_limit`, under the gas costs in force. Its writes
+    /// are applied only if it succeeds, and its gas is used either way.
+    pub fn execute(
+        &mut self,
+        module: &ModuleId,
+        function: &IdentStr,
+        ty_args: &[TypeTag],
+        args: Vec<Vec<u8>>,
+        gas_limit: u64,
+    ) -> ExecutionResult {
+        let mut meter = self.gas_meter(gas_limit);
+        self.session_manager
+            .execute(&self.vm, &mut self.module_store, module, function, ty_args, args, &mut meter)
+    }
+
+    /// Imports a published Sui package and its dependencies from `source`,
+    /// republishing them at remapped Romer addresses
+    pub async fn import_package<S: PackageSource>(
+        &mut self,
+        source: S,
+        package: AccountAddress,
+    ) -> Result<ImportReport, VMError> {
+        PackageImporter::new(source).import(package, &mut self.module_store).await
+    }
+
+    /// Compiles and publishes a package, running spec verification first
+    /// if the deployer is configured to
+    pub fn deploy_package(
+        &mut self,
+        deployer: &SuiPackageDeployer,
+        package: &Path,
+    ) -> Result<Vec<ModuleId>, VMError> {
+        deployer.deploy_package(package, &mut self.module_store)
+    }
+
+    /// Associates source code with a published package and checks that it
+    /// reproduces the on-chain bytecode
+    pub fn verify_source(
+        &mut self,
+        package: AccountAddress,
+        bundle: SourceBundle,
+    ) -> Result<VerificationStatus, VMError> {
+        self.source_verifier.submit(package, bundle, &self.module_store)
+    }
+
+    /// Source verification status of a package, for the explorer
+    pub fn verification_status(&self, package: &AccountAddress) -> VerificationStatus {
+        self.source_verifier.status(package)
+    }
+
+    /// Accept transactions signed for `chain_id` only
+    pub fn with_chain_id(mut self, chain_id: impl Into<String>) -> Self {
+        self.chain_id = chain_id.into();
+        self
+    }
+
+    /// Starts executing the block at `height`. The fees of transactions
+    /// admitted from here on are settled when the block commits.
+    pub fn begin_block(&mut self, height: u64) {
+        self.block_height = height;
+        self.block_fees = 0;
+    }
+
+    /// Runs the transaction prologue (chain, signature, expiry and nonce
+    /// checks) for a direct transaction included in a block at
+    /// `block_time`, collecting its fee for the block
+    pub fn prologue(&mut self, transaction: &SignedTransaction, block_time: u64) -> Result<(), VMError> {
+        prologue::run(self.module_store.state_mut(), transaction, &self.chain_id, block_time)?;
+        self.block_fees = self.block_fees.saturating_add(transaction.transaction.fee);
+        Ok(())
+    }
+
+    /// Next nonce expected from `address`, as of the last prologue run
+    pub fn next_nonce(&self, address: &Address) -> u64 {
+        prologue::next_nonce(self.module_store.state(), address)
+    }
+
+    /// Applies the gas cost overrides approved by governance, replacing
+    /// any applied before. Natives take their costs when the Move VM is
+    /// built, so it is rebuilt under the new ones.
+    pub fn set_gas_costs(&mut self, overrides: &BTreeMap<String, u64>) -> Result<(), VMError> {
+        let gas_costs = GasCostTable::default().with_overrides(overrides);
+        self.vm = Self::move_vm(&gas_costs)?;
+        self.gas_costs = gas_costs;
+        Ok(())
+    }
+
+    pub fn gas_costs(&self) -> &GasCostTable {
+        &self.gas_costs
+    }
+
+    /// Meter for one transaction of `gas_limit` under the costs in force
+    pub fn gas_meter(&self, gas_limit: u64) -> GasMeter {
+        GasMeter::new(self.gas_costs.clone(), gas_limit)
+    }
+
+    /// Settles the fees collected in the current block, burning the
+    /// configured share. Part of the block's writes, so it commits or rolls
+    /// back with them.
+    fn settle_fees(&mut self) -> FeeSettlement {
+        let fees = std::mem::take(&mut self.block_fees);
+        self.fee_burner.settle(self.module_store.state_mut(), self.block_height, fees)
+    }
+
+    /// Total fees burned since genesis, as of the last block settled
+    pub fn total_burned(&self) -> u64 {
+        FeeBurner::total_burned(self.module_store.state())
+    }
+
+    /// Burn events emitted since the last call, for the explorer and supply tracking
+    pub fn drain_burn_events(&mut self) -> Vec<BurnEvent> {
+        self.fee_burner.drain_events()
+    }
+}
+
+/// Move state changes of a block commit atomically with its fills and
+/// journal appends
+impl Participant for RomerVM {
+    fn name(&self) -> &str {
+        "vm"
+    }
+
+    fn prepare(&mut self, txn: u64) -> BoxFuture<'_, Result<(), String>> {
+        Box::pin(async move {
+            let settlement = self.settle_fees();
+            if settlement.burned > 0 {
+                info!(height = self.block_height, burned = settlement.burned, "Burned fees");
+            }
+            self.module_store
+                .state_mut()
+                .prepare(txn)
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn commit(&mut self, _txn: u64) -> BoxFuture<'_, Result<(), String>> {
+        Box::pin(async move {
+            self.module_store.state_mut().commit();
+            Ok(())
+        })
+    }
+
+    fn rollback(&mut self, _txn: u64) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            self.module_store.state_mut().rollback();
+            self.fee_burner.discard(self.block_height);
+        })
     }
 }
 
@@ -41,4 +282,125 @@ mod tests {
         let vm = RomerVM::new();
         assert!(vm.is_ok());
     }
+
+    #[test]
+    fn test_framework_loaded_at_genesis() {
+        let vm = RomerVM::new().unwrap();
+        let modules = vm.module_store.modules_at(&romer_framework::ROMER_FRAMEWORK_ADDRESS);
+        assert_eq!(modules.len(), romer_framework::FRAMEWORK_MODULES.len());
+    }
+
+    #[test]
+    fn test_execute_meters_gas() {
+        use crate::runtime::execution::ExecutionStatus;
+        use move_core_types::identifier::Identifier;
+
+        let mut vm = RomerVM::new().unwrap();
+        let module = ModuleId::new(
+            romer_framework::ROMER_FRAMEWORK_ADDRESS,
+            Identifier::new("orderbook").unwrap(),
+        );
+        let function = Identifier::new("insert_level").unwrap();
+        // Empty bid side, then the price, quantity and side, BCS encoded
+        let args = |price: u64| {
+            vec![vec![0], vec![0], price.to_le_bytes().to_vec(), 5u64.to_le_bytes().to_vec(), vec![1]]
+        };
+
+        let result = vm.execute(&module, &function, &[], args(100), 100_000);
+        assert!(result.is_success());
+        assert!(result.gas_used > 0);
+
+        let result = vm.execute(&module, &function, &[], args(0), 100_000);
+        assert_eq!(
+            result.status,
+            ExecutionStatus::Aborted {
+                module: "orderbook".to_string(),
+                code: crate::natives::orderbook::E_ZERO_PRICE,
+            }
+        );
+
+        let result = vm.execute(&module, &function, &[], args(100), 1);
+        assert_eq!(result.status, ExecutionStatus::OutOfGas);
+        assert_eq!(result.gas_used, 1);
+    }
+
+    #[test]
+    fn test_natives_charge_governed_costs() {
+        use crate::runtime::execution::ExecutionStatus;
+        use crate::runtime::gas::native_operation;
+        use move_core_types::identifier::Identifier;
+
+        let mut vm = RomerVM::new().unwrap();
+        let module = ModuleId::new(
+            romer_framework::ROMER_FRAMEWORK_ADDRESS,
+            Identifier::new("orderbook").unwrap(),
+        );
+        let function = Identifier::new("best_level").unwrap();
+        let args = || vec![vec![0], vec![0]];
+        let before = vm.execute(&module, &function, &[], args(), 100_000);
+        assert!(before.is_success());
+
+        let overrides = [(native_operation("orderbook", "best_level"), 1_000)].into();
+        vm.set_gas_costs(&overrides).unwrap();
+        let after = vm.execute(&module, &function, &[], args(), 100_000);
+        assert_eq!(after.gas_usage.natives, 1_000);
+        assert_eq!(after.gas_used - before.gas_used, 1_000 - before.gas_usage.natives);
+
+        // A native past its budget runs the transaction out of gas
+        let result = vm.execute(&module, &function, &[], args(), 500);
+        assert_eq!(result.status, ExecutionStatus::OutOfGas);
+        assert_eq!(result.gas_used, 500);
+    }
+
+    #[tokio::test]
+    async fn test_block_commit_with_fills() {
+        use romer_common::storage::commit::{CommitCoordinator, StagedJournal};
+
+        let mut vm = RomerVM::new().unwrap();
+        let mut fills = StagedJournal::in_memory("fills");
+        let mut coordinator = CommitCoordinator::in_memory();
+
+        fills.stage(b"fill".to_vec());
+        coordinator.commit_block(1, &mut [&mut vm, &mut fills]).await.unwrap();
+        assert_eq!(fills.committed().len(), 1);
+        assert_eq!(vm.module_store.state_mut().pending(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_block_commit_settles_fees() {
+        use commonware_cryptography::{Ed25519, Scheme};
+        use romer_common::storage::commit::CommitCoordinator;
+        use romer_common::types::envelope::{TransactionPayload, UnsignedTransaction};
+        use romer_common::types::keymanager::SignatureScheme;
+
+        let mut vm = RomerVM::with_fee_config(FeeConfig { burn_bps: 5_000 }).unwrap();
+        let mut coordinator = CommitCoordinator::in_memory();
+        let mut signer = Ed25519::from_seed(3);
+        let sender = Address::from_public_key(SignatureScheme::Ed25519, &signer.public_key());
+        let transaction = UnsignedTransaction {
+            chain_id: DEFAULT_CHAIN_ID.to_string(),
+            payload: TransactionPayload::Transfer {
+                to: Address::new([1u8; 32]),
+                amount: 1,
+            },
+            sender,
+            nonce: 0,
+            fee: 40,
+            expiry: u64::MAX,
+        }
+        .sign(SignatureScheme::Ed25519, &mut signer)
+        .unwrap();
+
+        vm.begin_block(7);
+        vm.prologue(&transaction, 0).unwrap();
+        coordinator.commit_block(7, &mut [&mut vm]).await.unwrap();
+        assert_eq!(vm.total_burned(), 20);
+        let events = vm.drain_burn_events();
+        assert_eq!((events[0].height, events[0].amount), (7, 20));
+
+        // The next block collected nothing, so burns nothing
+        vm.begin_block(8);
+        coordinator.commit_block(8, &mut [&mut vm]).await.unwrap();
+        assert_eq!(vm.total_burned(), 20);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,57 @@
+#![cfg(feature = "compat")]
+
+use std::path::PathBuf;
+
+use romer_vm::compat::{
+    executor::{MoveCliExecutor, SuiCliExecutor},
+    record_corpus, run_corpus, Corpus,
+};
+
+fn load() -> (PathBuf, Corpus) {
+    let root = PathBuf::from(
+        std::env::var("ROMER_COMPAT_ROOT").expect("ROMER_COMPAT_ROOT must be set"),
+    );
+    let corpus_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("compat/corpus.json");
+    let corpus = Corpus::load(&corpus_path).expect("failed to load corpus");
+    (root, corpus)
+}
+
+#[test]
+#[ignore = "requires ROMER_COMPAT_ROOT with Sui and Romer checkouts"]
+fn sui_compatibility_suite() {
+    let (root, corpus) = load();
+    // An empty baseline would expect every test to pass and hide divergences
+    let unrecorded = corpus.unrecorded();
+    assert!(unrecorded.is_empty(), "no Sui baseline recorded for {:?}", unrecorded);
+
+    let report = run_corpus(&corpus, &root, &MoveCliExecutor::default());
+
+    println!(
+        "{} packages, {} tests, {:.2}% compatible",
+        report.packages,
+        report.tests,
+        report.compatibility()
+    );
+    for divergence in &report.divergences {
+        println!(
+            "DIVERGENCE {}::{} expected {:?}, got {:?}",
+            divergence.package, divergence.test, divergence.expected, divergence.actual
+        );
+    }
+    for (package, error) in &report.errors {
+        println!("ERROR {}: {}", package, error);
+    }
+
+    assert!(report.is_compatible(), "Sui compatibility suite diverged");
+}
+
+#[test]
+#[ignore = "requires ROMER_COMPAT_ROOT with Sui and Romer checkouts and the sui CLI"]
+fn record_sui_baselines() {
+    let (root, corpus) = load();
+    let errors = record_corpus(&corpus, &root, &SuiCliExecutor::default());
+    for (package, error) in &errors {
+        println!("ERROR {}: {}", package, error);
+    }
+    assert!(errors.is_empty(), "failed to record Sui baselines");
+}